@@ -1,6 +1,7 @@
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
 import { getApiUrl } from './backendStore';
+import { useAuthStore } from './authStore';
 import type {
   AppState,
   BoardState,
@@ -225,7 +226,10 @@ export const useChatStore = create<ExtendedChatStore>()((set) => ({
 
   fetchMessages: async (sessionId) => {
     try {
-      const res = await fetch(getApiUrl(`/api/chat/${sessionId}/messages`));
+      const token = useAuthStore.getState().token;
+      const res = await fetch(getApiUrl(`/api/chat/${sessionId}/messages`), {
+        headers: token ? { 'Authorization': `Bearer ${token}` } : {},
+      });
       const data = await res.json();
       if (data.success) {
         set((state) => ({
@@ -3,13 +3,63 @@
 //! 能够自主接收消息、做出决策并执行
 
 use anyhow::Result;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{broadcast, RwLock};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
-use crate::core::agent::{AgentRuntime, Context, Decision};
-use crate::core::messaging::{MessageBus, MessageReceiver};
-use crate::domain::{Agent, Message, MessageTarget};
+use crate::core::agent::{load_context, load_pinned_messages, AgentRuntime, Context, Decision, SnapshotConfig};
+use crate::core::budget::DepartmentBudgets;
+use crate::core::circuit_breaker::{BreakerGate, BreakerStatus, BreakerTransition, BreakerTrip, CircuitBreakers};
+use crate::core::citations::CitationTracker;
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::escalation::NO_ESCALATION_KEY;
+use crate::core::events::{CompanyEvent, EventBus};
+use crate::core::goals::{GoalBoard, GoalSource, GOAL_NOTICE_SENDER};
+use crate::core::loop_guard::{LoopGuard, LoopVerdict, LOOP_NOTICE_SENDER};
+use crate::core::messaging::{MessageBus, MessageReceiver, OutboxPolicy, PriorityInbox};
+use crate::core::moderation::{is_internal_traffic, ModerationService};
+use crate::core::nudge::{Nudge, NudgeSlots};
+use crate::core::scheduler::{TurnPriority, TurnScheduler};
+use crate::core::scratchpad::{Scratchpad, SCRATCHPAD_WRITE_TOOL};
+use crate::core::send_dedup::{SendDedup, ALLOW_DUPLICATE_KEY};
+use crate::core::tool_view::AgentToolView;
+use crate::core::translation::TranslationService;
+use crate::core::turn_taking::{PeerTurn, TurnCoordinator};
+use crate::domain::scratchpad::ScratchpadSource;
+use crate::domain::tool::ToolCallContext;
+use crate::infrastructure::llm::LlmError;
+use crate::infrastructure::logger::with_trace_id;
+use crate::infrastructure::tool::FrameworkToolExecutor;
+use crate::domain::user::{is_user_principal, Position};
+use crate::domain::{
+    new_trace_id, Agent, Availability, Message, MessageId, MessagePriority, MessageTarget, ReactionCount, TurnOutbox,
+};
+
+/// 两轮之间的空闲休眠时长
+const IDLE_BACKOFF: Duration = Duration::from_millis(100);
+
+/// 部门预算用尽时的检查间隔
+const BUDGET_BACKOFF: Duration = Duration::from_secs(5);
+
+/// 工作时间外的检查间隔
+const AWAY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 熔断期间的检查间隔
+const FAULTED_BACKOFF: Duration = Duration::from_secs(1);
+
+/// 从存储重新读取工作时间的间隔，修改后最迟在这个时间后生效
+const AVAILABILITY_REFRESH: Duration = Duration::from_secs(30);
+
+/// 群聊连发消息最多等待几个安静窗口，避免持续刷屏时一直不开始轮次
+const MAX_DEBOUNCE_WINDOWS: u32 = 4;
+
+/// 标记工作时间外自动回复的元数据键，收到此类消息不再回复
+pub const AUTO_REPLY_KEY: &str = "auto_reply";
+
+/// 熔断告警的发送者
+pub const BREAKER_ALERT_SENDER: &str = "system";
 
 /// 自主Agent
 ///
@@ -21,6 +71,75 @@ pub struct AutonomousAgent {
     message_rx: Arc<RwLock<MessageReceiver>>,
     message_tx: broadcast::Sender<Message>,
     pending_task: Arc<RwLock<Option<String>>>,
+    events: Option<Arc<EventBus>>,
+    reactions_in_context: bool,
+    snapshot_config: SnapshotConfig,
+    scheduler: Option<Arc<TurnScheduler>>,
+    outbox_policy: OutboxPolicy,
+    loop_guard: Option<Arc<LoopGuard>>,
+    send_dedup: Option<Arc<SendDedup>>,
+    turn_coordinator: Option<Arc<TurnCoordinator>>,
+    translator: Option<Arc<TranslationService>>,
+    moderation: Option<Arc<ModerationService>>,
+    scratchpad: Option<Arc<Scratchpad>>,
+    goals: Option<Arc<GoalBoard>>,
+    nudges: Option<Arc<NudgeSlots>>,
+    tool_view: Option<Arc<AgentToolView>>,
+    tool_executor: Option<Arc<FrameworkToolExecutor>>,
+    budgets: Option<Arc<DepartmentBudgets>>,
+    breakers: Option<Arc<CircuitBreakers>>,
+    /// 上次发出回答以来的工具结果，供回答引用
+    citations: Arc<std::sync::Mutex<CitationTracker>>,
+    clock: Arc<dyn Clock>,
+    presence: Arc<std::sync::Mutex<PresenceState>>,
+}
+
+/// 工作时间与在线状态
+#[derive(Debug, Default)]
+struct PresenceState {
+    /// 最近一次读取的工作时间
+    availability: Option<Availability>,
+    refreshed_at: Option<tokio::time::Instant>,
+    /// 当前是否在工作时间外
+    away: bool,
+    /// 本次离开期间已自动回复过的发送者
+    acknowledged: HashSet<String>,
+}
+
+/// 轮次开始时的上下文快照：本轮只看到截止时间前已取出的消息
+#[derive(Debug, Default)]
+struct ContextSnapshot {
+    /// 截止时间（秒）
+    cutoff: i64,
+    /// 截止那一秒内已取出的消息；消息时间戳只精确到秒，同一秒内靠 ID 区分
+    delivered: HashSet<MessageId>,
+}
+
+impl ContextSnapshot {
+    /// 取出本轮消息时推进截止点
+    fn advance(&mut self, messages: &[Message]) {
+        let cutoff = chrono::Utc::now().timestamp();
+        if cutoff != self.cutoff {
+            self.delivered.clear();
+            self.cutoff = cutoff;
+        }
+        self.delivered
+            .extend(messages.iter().filter(|m| m.timestamp >= cutoff).map(|m| m.id.clone()));
+    }
+
+    /// 消息是否在快照内
+    fn includes(&self, message: &Message) -> bool {
+        message.timestamp < self.cutoff || self.delivered.contains(&message.id)
+    }
+}
+
+/// 触发本轮的消息来源，用于循环抑制
+#[derive(Debug, Default)]
+struct TurnOrigin {
+    /// 本轮发出消息的回复链深度
+    chain_depth: u32,
+    /// 触发本轮的其他 Agent
+    peers: Vec<String>,
 }
 
 impl AutonomousAgent {
@@ -29,25 +148,160 @@ impl AutonomousAgent {
         agent: Agent,
         message_bus: Arc<MessageBus>,
     ) -> Result<Self> {
-        let runtime = Arc::new(AgentRuntime::new(agent).await?);
+        Ok(Self::from_runtime(AgentRuntime::new(agent).await?, message_bus))
+    }
+
+    /// 使用已配置好的运行时创建自主Agent
+    pub fn from_runtime(runtime: AgentRuntime, message_bus: Arc<MessageBus>) -> Self {
+        let runtime = Arc::new(runtime);
 
         // 注册到消息总线
         let private_rx = message_bus.register(runtime.id());
 
-        let message_rx = Arc::new(RwLock::new(MessageReceiver::new(
-            runtime.id().to_string(),
-            private_rx,
-        )));
+        let mut receiver = MessageReceiver::new(runtime.id().to_string(), private_rx);
+        if let Some(store) = message_bus.store() {
+            receiver = receiver.with_store(store);
+        }
+        let message_rx = Arc::new(RwLock::new(receiver));
 
         let (message_tx, _) = broadcast::channel(100);
 
-        Ok(Self {
-            runtime,
+        Self {
             message_bus,
             message_rx,
             message_tx,
             pending_task: Arc::new(RwLock::new(None)),
-        })
+            events: None,
+            reactions_in_context: false,
+            snapshot_config: SnapshotConfig::default(),
+            scheduler: None,
+            outbox_policy: OutboxPolicy::default(),
+            loop_guard: None,
+            send_dedup: None,
+            turn_coordinator: None,
+            translator: None,
+            moderation: None,
+            scratchpad: None,
+            goals: None,
+            nudges: None,
+            tool_view: None,
+            tool_executor: None,
+            budgets: None,
+            breakers: None,
+            citations: Arc::new(std::sync::Mutex::new(
+                CitationTracker::new().with_tokenizer(runtime.token_counter().tokenizer()),
+            )),
+            clock: Arc::new(SystemClock),
+            presence: Arc::new(std::sync::Mutex::new(PresenceState {
+                availability: runtime.agent().availability.clone(),
+                ..Default::default()
+            })),
+            runtime,
+        }
+    }
+
+    /// 使用指定时钟判断工作时间（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 发出启动和每轮完成事件
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 在上下文中附带消息回应汇总
+    pub fn with_reactions_in_context(mut self, enabled: bool) -> Self {
+        self.reactions_in_context = enabled;
+        self
+    }
+
+    /// 上下文快照配置：群聊连发消息的等待窗口、思考期间到达消息是否显示内容
+    pub fn with_snapshot_config(mut self, config: SnapshotConfig) -> Self {
+        self.snapshot_config = config;
+        self
+    }
+
+    /// 每轮 LLM 调用前向调度器申请许可
+    pub fn with_scheduler(mut self, scheduler: Arc<TurnScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// 轮次失败时本轮排队消息的处理策略
+    pub fn with_outbox_policy(mut self, policy: OutboxPolicy) -> Self {
+        self.outbox_policy = policy;
+        self
+    }
+
+    /// 发出的消息先经过循环抑制检查
+    pub fn with_loop_guard(mut self, loop_guard: Arc<LoopGuard>) -> Self {
+        self.loop_guard = Some(loop_guard);
+        self
+    }
+
+    /// 丢弃窗口期内重复发送的相同消息
+    pub fn with_send_dedup(mut self, send_dedup: Arc<SendDedup>) -> Self {
+        self.send_dedup = Some(send_dedup);
+        self
+    }
+
+    /// 开启轮流发言的群聊中，由协调器决定是否轮到自己回答
+    pub fn with_turn_coordinator(mut self, coordinator: Arc<TurnCoordinator>) -> Self {
+        self.turn_coordinator = Some(coordinator);
+        self
+    }
+
+    /// 其他语言的消息在上下文中附上译为自己回复语言的译文
+    pub fn with_translator(mut self, translator: Arc<TranslationService>) -> Self {
+        self.translator = Some(translator);
+        self
+    }
+
+    /// 发出的消息先经过内容审核，被拦截的以提示代替
+    pub fn with_moderation(mut self, moderation: Arc<ModerationService>) -> Self {
+        self.moderation = Some(moderation);
+        self
+    }
+
+    /// 运行草稿：按配置把以工具调用结束的 LLM 回复原文记为本轮草稿
+    pub fn with_scratchpad(mut self, scratchpad: Arc<Scratchpad>) -> Self {
+        self.scratchpad = Some(scratchpad);
+        self
+    }
+
+    /// 记录当前目标，每轮提示词都带上（见 [`crate::core::goals`]）
+    pub fn with_goals(mut self, goals: Arc<GoalBoard>) -> Self {
+        self.goals = Some(goals);
+        self
+    }
+
+    /// 每轮把工具视图中可用的工具交给 LLM，工具调用由执行器执行
+    /// 工具循环期间接受插话（见 [`crate::core::nudge`]）
+    pub fn with_nudges(mut self, nudges: Arc<NudgeSlots>) -> Self {
+        self.nudges = Some(nudges);
+        self
+    }
+
+    pub fn with_tools(mut self, tool_view: Arc<AgentToolView>, executor: Arc<FrameworkToolExecutor>) -> Self {
+        tool_view.set_agent_skills(self.id(), self.runtime.agent().skills.clone());
+        self.tool_view = Some(tool_view);
+        self.tool_executor = Some(executor);
+        self
+    }
+
+    /// 每轮 LLM 调用前检查部门预算，调用后记录 token 用量
+    pub fn with_budgets(mut self, budgets: Arc<DepartmentBudgets>) -> Self {
+        self.budgets = Some(budgets);
+        self
+    }
+
+    /// LLM 调用或工具调用持续失败时熔断，熔断期间消息留在信箱里
+    pub fn with_circuit_breakers(mut self, breakers: Arc<CircuitBreakers>) -> Self {
+        self.breakers = Some(breakers);
+        self
     }
 
     /// 获取Agent ID
@@ -70,63 +324,822 @@ impl AutonomousAgent {
     /// 自主运行循环
     pub async fn run_loop(&self) -> Result<()> {
         info!("Agent {} started autonomous loop", self.id());
+        self.emit(|agent_id| CompanyEvent::AgentStarted { agent_id });
 
+        // 休眠期间到达的消息暂存在这里，下一轮按优先级处理
+        let mut inbox = PriorityInbox::new();
+        let mut snapshot = ContextSnapshot::default();
+        // 同伴正在回答的群聊问题，每轮重新裁决
+        let mut parked: Vec<Message> = Vec::new();
+        let mut over_budget = false;
+        let mut in_tool_loop = false;
         loop {
-            // 1. 收集未读消息
-            let mut messages = vec![];
+            // 0. 部门预算用尽时暂停，消息留在信箱里等预算恢复
+            if let Some(budgets) = &self.budgets {
+                if let Err(e) = budgets.check(self.id()) {
+                    if !over_budget {
+                        warn!("Agent {} paused: {}", self.id(), e);
+                        over_budget = true;
+                    }
+                    self.paused_wait(&mut inbox, BUDGET_BACKOFF).await;
+                    continue;
+                }
+                over_budget = false;
+            }
+
+            // 熔断期间不进行轮次，消息留在信箱里；退避结束后试探一轮，试探不取出信箱中的消息
+            let probing = match self.breakers.as_ref().map(|breakers| breakers.gate(self.id())) {
+                Some(BreakerGate::Open { .. }) => {
+                    self.paused_wait(&mut inbox, FAULTED_BACKOFF).await;
+                    continue;
+                }
+                Some(BreakerGate::Probe) => true,
+                Some(BreakerGate::Closed) | None => false,
+            };
+
+            // 1. 收集未读消息，Urgent/High 排在前面；群聊序号有缺口时先从存储补齐
             {
                 let mut rx = self.message_rx.write().await;
-                while let Some(msg) = rx.try_recv() {
-                    messages.push(msg);
+                inbox.fill(&mut rx);
+                if rx.has_gaps() {
+                    rx.fill_gaps().await;
+                    inbox.fill(&mut rx);
                 }
             }
 
+            // 工作时间外不进行轮次，私聊自动回复，消息留在信箱里等上班后处理
+            if self.is_away(&inbox).await {
+                self.acknowledge_away(&inbox).await;
+                // 信箱里可能留着高优先级消息，不能用 idle_wait（会立即返回）
+                tokio::time::sleep(AWAY_BACKOFF).await;
+                continue;
+            }
+
+            // 群聊连发时等这一串发完，整串进入同一个快照
+            if !probing && inbox.iter().any(|m| matches!(m.to, MessageTarget::Group(_))) && !inbox.has_elevated() {
+                self.debounce_burst(&mut inbox).await;
+            }
+
+            let messages = if probing { Vec::new() } else { inbox.drain() };
+            snapshot.advance(&messages);
+            let (messages, peer_turns) = self.coordinate_turns(messages, &mut parked).await;
+            let (messages, mut nudges) = self.urgent_nudges(messages);
+
             // 2. 检查是否有待处理任务
             let task = {
                 let mut pending = self.pending_task.write().await;
                 pending.take()
             };
 
-            // 3. 构建上下文
-            let mut context = Context::default().with_messages(messages);
+            // 用户私聊、手动任务或高优先级消息优先于自主轮次
+            let priority = if task.is_some()
+                || messages.iter().any(|m| self.is_user_message(m) || m.priority.is_elevated())
+            {
+                TurnPriority::User
+            } else {
+                TurnPriority::Background
+            };
+
+            let origin = self.turn_origin(&messages);
+            let goal_notice = self.track_goal(&messages, task.as_deref(), in_tool_loop);
+
+            // 3. 构建上下文（历史消息与偏好来自存储，未读消息不重复出现在历史中）
+            let mut context = self.load_context().await;
+            context.history.retain(|h| {
+                !messages.iter().any(|m| m.id == h.id)
+                    && !nudges.iter().any(|n| n.message_id.as_ref() == Some(&h.id))
+                    && (h.from == self.id() || snapshot.includes(h))
+            });
+            context = context.with_messages(messages).with_peer_turns(peer_turns);
             if let Some(task) = task {
                 context = context.with_task(task);
             }
+            if let Some(notice) = goal_notice {
+                context.unread_messages.push(notice);
+            }
+            context = self.attach_goal(context);
+            if self.reactions_in_context {
+                context = self.attach_reactions(context).await;
+            }
+            if self.snapshot_config.pinned_token_budget > 0 {
+                context = self.attach_pins(context).await;
+            }
+            if self.translator.is_some() {
+                context = self.attach_translations(context).await;
+            }
+            if let Some(section) = self.citations.lock().unwrap().prompt_section() {
+                context = context.with_tool_results(section);
+            }
 
-            // 4. 做出决策
-            match self.runtime.think(context).await {
-                Ok(decision) => {
-                    debug!("Agent {} decision: {:?}", self.id(), decision);
+            // 4. 做出决策并执行，每轮一个追踪ID，LLM 调用与产生的消息都挂在这一轮下
+            let trace_id = new_trace_id();
+            let role_revision = context.role_revision.as_ref().map(|r| r.revision);
+            let span = info_span!("agent_turn", agent_id = %self.id(), trace_id = %trace_id, role_revision = ?role_revision);
+            let permit = match &self.scheduler {
+                Some(scheduler) => Some(scheduler.acquire(self.id(), priority).await),
+                None => None,
+            };
+            // 快照之后（组装上下文、等待许可期间）到达的消息只提示数量，下一轮再处理
+            if inbox.fill(&mut *self.message_rx.write().await) > 0 {
+                let late: Vec<Message> = inbox.iter().cloned().collect();
+                context.history.retain(|h| !late.iter().any(|m| m.id == h.id));
+                context = context.with_late_arrivals(late, self.snapshot_config.late_arrival_content);
+            }
+            // 工具循环中排队的插话在本轮 LLM 请求前注入，cancel 插话结束剩余的工具调用
+            if let Some(slots) = &self.nudges {
+                nudges.extend(slots.take(self.id()));
+            }
+            let finishing = nudges.iter().any(|n| n.cancel);
+            for nudge in &nudges {
+                info!("Agent {} nudged by {} [{}]: {}", self.id(), nudge.from, trace_id, nudge.content);
+                self.emit(|agent_id| CompanyEvent::NudgeInjected {
+                    agent_id,
+                    trace_id: trace_id.as_str().into(),
+                    nudge: Arc::new(nudge.clone()),
+                });
+            }
+            context = context.with_nudges(nudges);
+            let called_tool = with_trace_id(
+                trace_id.clone(),
+                self.run_turn(context, &trace_id, &origin, finishing).instrument(span),
+            )
+            .await;
+            drop(permit);
+            in_tool_loop = called_tool && !finishing;
+            self.update_tool_loop(in_tool_loop).await;
 
-                    // 5. 执行决策
-                    if let Err(e) = self.execute_decision(decision).await {
-                        error!("Agent {} failed to execute decision: {}", self.id(), e);
-                        // 在这里我们可以考虑实现重试逻辑或其他恢复机制
-                    }
+            // 6. 短暂休眠避免CPU占用过高，收到 Urgent/High 消息时提前结束
+            self.idle_wait(&mut inbox, IDLE_BACKOFF).await;
+        }
+    }
+
+    /// 经轮流发言协调器过滤：轮到自己的本轮处理，同伴正在回答的暂存，其余丢弃
+    async fn coordinate_turns(&self, messages: Vec<Message>, parked: &mut Vec<Message>) -> (Vec<Message>, Vec<PeerTurn>) {
+        let Some(coordinator) = &self.turn_coordinator else {
+            return (messages, Vec::new());
+        };
+        let mut pending = std::mem::take(parked);
+        pending.extend(messages);
+        let selection = coordinator.select(self.id(), pending).await;
+        parked.extend(selection.deferred.iter().map(|turn| turn.message.clone()));
+        (selection.respond, selection.deferred)
+    }
+
+    /// 工具循环中收到的 Urgent 私聊消息从未读消息中取出，作为插话注入
+    fn urgent_nudges(&self, messages: Vec<Message>) -> (Vec<Message>, Vec<Nudge>) {
+        if !self.nudges.as_ref().is_some_and(|slots| slots.in_tool_loop(self.id())) {
+            return (messages, Vec::new());
+        }
+        let (urgent, messages): (Vec<Message>, Vec<Message>) = messages
+            .into_iter()
+            .partition(|m| m.priority == MessagePriority::Urgent && matches!(m.to, MessageTarget::Direct(_)));
+        (messages, urgent.iter().map(Nudge::from_message).collect())
+    }
+
+    /// 轮次结束后更新工具循环状态；工具循环结束时还没注入的插话改为 Urgent 消息
+    async fn update_tool_loop(&self, in_tool_loop: bool) {
+        let Some(slots) = &self.nudges else {
+            return;
+        };
+        for nudge in slots.set_tool_loop(self.id(), in_tool_loop) {
+            if let Err(e) = self.message_bus.send(nudge.into_message(self.id())).await {
+                warn!("Failed to deliver nudge to {} as a message: {}", self.id(), e);
+            }
+        }
+    }
+
+    /// 执行 LLM 请求的工具调用，只允许工具视图中的工具
+    async fn call_tool(&self, tool_id: &str, arguments: serde_json::Value, trace_id: &str, outbox: &TurnOutbox) -> Result<()> {
+        let (Some(view), Some(executor)) = (&self.tool_view, &self.tool_executor) else {
+            return Err(anyhow::anyhow!("Agent {} has no tools configured", self.id()));
+        };
+        if !view.can_use(self.id(), tool_id) {
+            return Err(anyhow::anyhow!("Tool {} is not available to agent {}", tool_id, self.id()));
+        }
+
+        let context = ToolCallContext::new(self.id())
+            .with_trace_id(trace_id)
+            .with_outbox(outbox.clone());
+        let result = executor.execute(tool_id, arguments.clone(), &context).await;
+        let error = match &result {
+            Ok(result) if result.success => None,
+            Ok(result) => Some(result.error.clone().unwrap_or_default()),
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(breakers) = &self.breakers {
+            if let Some(transition) = breakers.record_tool(self.id(), error.as_deref()) {
+                self.breaker_changed(breakers, transition).await;
+            }
+        }
+        let result = result?;
+        if tool_id == SCRATCHPAD_WRITE_TOOL {
+            // 草稿不进入之后的上下文
+            return Ok(());
+        }
+        if result.success {
+            // 带进度的长任务用执行摘要代替完整结果进入上下文
+            let data = match result.metadata.get("progress_summary") {
+                Some(summary) => summary,
+                None => &result.data,
+            };
+            let ref_id = self.citations.lock().unwrap().record(tool_id, &arguments, data);
+            info!("Agent {} called tool {} [{}]: {}", self.id(), tool_id, ref_id, result.data);
+        } else {
+            warn!("Agent {} tool {} failed: {}", self.id(), tool_id, result.error.unwrap_or_default());
+        }
+        Ok(())
+    }
+
+    /// 是否在工作时间外；允许紧急消息打断时，信箱里有 Urgent 消息视为在岗。状态变化时发出在线状态事件
+    async fn is_away(&self, inbox: &PriorityInbox) -> bool {
+        self.refresh_availability().await;
+        let now = self.clock.now();
+        let mut presence = self.presence.lock().unwrap();
+        let Some(availability) = presence.availability.clone() else {
+            presence.away = false;
+            return false;
+        };
+        let away = !(availability.is_available_at(now) || (availability.urgent_override && inbox.has_urgent()));
+        if away != presence.away {
+            presence.away = away;
+            presence.acknowledged.clear();
+            let next_available_at = if away { availability.next_available_at(now) } else { None };
+            info!("Agent {} is now {}", self.id(), if away { "away" } else { "available" });
+            self.emit(|agent_id| CompanyEvent::AgentPresenceChanged {
+                agent_id,
+                available: !away,
+                next_available_at,
+            });
+        }
+        away
+    }
+
+    /// 定期从存储读取工作时间，没有存储时沿用配置
+    async fn refresh_availability(&self) {
+        let due = self
+            .presence
+            .lock()
+            .unwrap()
+            .refreshed_at
+            .is_none_or(|at| at.elapsed() >= AVAILABILITY_REFRESH);
+        let Some(store) = self.message_bus.store().filter(|_| due) else {
+            return;
+        };
+        let loaded = store.load_agent(self.id()).await;
+        let mut presence = self.presence.lock().unwrap();
+        presence.refreshed_at = Some(tokio::time::Instant::now());
+        match loaded {
+            Ok(Some(agent)) => presence.availability = agent.availability,
+            Ok(None) => {}
+            Err(e) => warn!("Agent {} failed to load availability: {}", self.id(), e),
+        }
+    }
+
+    /// 离开期间给每个私聊发送者自动回复一次（自动回复和系统提示除外）
+    async fn acknowledge_away(&self, inbox: &PriorityInbox) {
+        let now = self.clock.now();
+        let replies: Vec<Message> = {
+            let mut presence = self.presence.lock().unwrap();
+            let Some(availability) = presence.availability.clone() else {
+                return;
+            };
+            let notice = match availability.next_available_at(now) {
+                Some(next) => format!(
+                    "I'm outside my working hours right now. Your message is queued and I'll respond at {}.",
+                    availability.format_local(next)
+                ),
+                None => "I'm away right now. Your message is queued and I'll respond when I'm back.".to_string(),
+            };
+            inbox
+                .iter()
+                .filter(|m| matches!(&m.to, MessageTarget::Direct(to) if to == self.id()))
+                .filter(|m| m.from != LOOP_NOTICE_SENDER && m.metadata(AUTO_REPLY_KEY).is_none())
+                .filter(|m| presence.acknowledged.insert(m.from.clone()))
+                .map(|m| {
+                    let mut reply = Message::private(self.id(), &m.from, &notice);
+                    reply.reply_to = Some(m.id.clone());
+                    reply.metadata.insert(AUTO_REPLY_KEY.to_string(), "true".to_string());
+                    reply
+                })
+                .collect()
+        };
+        for reply in replies {
+            let to = reply.to.clone();
+            if let Err(e) = self.message_bus.send(reply.clone()).await {
+                warn!("Agent {} failed to send away notice to {:?}: {}", self.id(), to, e);
+                continue;
+            }
+            let _ = self.message_tx.send(reply);
+        }
+    }
+
+    /// 群聊消息到达后等待一个安静窗口再开始轮次，窗口内有新消息就重新计时，
+    /// 最多等待 [`MAX_DEBOUNCE_WINDOWS`] 个窗口；收到高优先级消息立即返回
+    async fn debounce_burst(&self, inbox: &mut PriorityInbox) {
+        let window = Duration::from_millis(self.snapshot_config.group_debounce_ms);
+        if window.is_zero() {
+            return;
+        }
+        let deadline = tokio::time::Instant::now() + window * MAX_DEBOUNCE_WINDOWS;
+        let mut rx = self.message_rx.write().await;
+        while !inbox.has_elevated() {
+            let quiet_until = (tokio::time::Instant::now() + window).min(deadline);
+            match tokio::time::timeout_at(quiet_until, rx.recv()).await {
+                Ok(Some(message)) => inbox.push(message),
+                Ok(None) | Err(_) => return,
+            }
+        }
+    }
+
+    /// 轮次间休眠，期间到达的消息进入信箱；收到高优先级消息立即返回
+    async fn idle_wait(&self, inbox: &mut PriorityInbox, backoff: Duration) {
+        let deadline = tokio::time::Instant::now() + backoff;
+        let mut rx = self.message_rx.write().await;
+        while !inbox.has_elevated() {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(message)) => inbox.push(message),
+                Ok(None) => {
+                    // 信箱已关闭，睡完剩余时间
+                    tokio::time::sleep_until(deadline).await;
+                    return;
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// 暂停（预算用尽、熔断）期间持续把到达的消息收进信箱，不提前结束；
+    /// 有界的私聊通道不被写满，发送方不会因为本 Agent 暂停而阻塞
+    async fn paused_wait(&self, inbox: &mut PriorityInbox, backoff: Duration) {
+        let deadline = tokio::time::Instant::now() + backoff;
+        let mut rx = self.message_rx.write().await;
+        loop {
+            match tokio::time::timeout_at(deadline, rx.recv()).await {
+                Ok(Some(message)) => inbox.push(message),
+                Ok(None) => {
+                    tokio::time::sleep_until(deadline).await;
+                    return;
+                }
+                Err(_) => return,
+            }
+        }
+    }
+
+    /// 进行一轮思考与执行，`finishing` 时不提供工具；返回本轮是否以成功的工具调用结束
+    async fn run_turn(&self, context: Context, trace_id: &str, origin: &TurnOrigin, finishing: bool) -> bool {
+        // 本轮要发的消息先进发件箱，轮次结束后统一发送
+        let outbox = TurnOutbox::new();
+        let role_revision = context.role_revision.as_ref().map(|r| r.revision);
+        let goal = self.goals.as_ref().and_then(|goals| goals.get(self.id())).map(|goal| Arc::from(goal.text));
+        let tokens_before = self.runtime.tokens_used();
+        let thought = match self.tool_view.as_ref().filter(|_| !finishing) {
+            Some(view) => self.runtime.think_with_tools(context, &view.tools_for(self.id())).await,
+            None => self.runtime.think(context).await,
+        };
+        if let Some(correction) = self.runtime.take_language_correction() {
+            self.emit(|agent_id| CompanyEvent::ResponseLanguageCorrected {
+                agent_id,
+                trace_id: trace_id.into(),
+                correction: Arc::new(correction),
+            });
+        }
+        if let Some(content) = self.runtime.take_tool_turn_content() {
+            self.capture_tool_turn(trace_id, &content).await;
+        }
+        if let Some(budgets) = &self.budgets {
+            budgets.record(self.id(), self.runtime.tokens_used() - tokens_before);
+        }
+        if let Some(breakers) = &self.breakers {
+            let error = thought.as_ref().err();
+            let message = error.map(|e| format!("{:#}", e));
+            let transition = match error.and_then(LlmError::of) {
+                Some(LlmError::AuthFailed { .. }) => {
+                    breakers.record_llm_auth_failure(self.id(), message.as_deref().unwrap_or_default())
                 }
-                Err(e) => {
+                _ => breakers.record_llm(self.id(), message.as_deref()),
+            };
+            if let Some(transition) = transition {
+                self.breaker_changed(breakers, transition).await;
+            }
+        }
+        let (decision, error) = match thought {
+            Ok(decision) => {
+                debug!("Agent {} decision: {:?}", self.id(), decision);
+                let decision = Arc::new(decision);
+
+                // 5. 执行决策
+                let result = self
+                    .execute_decision((*decision).clone(), trace_id, origin.chain_depth, &outbox)
+                    .await;
+                if let Err(e) = &result {
+                    error!("Agent {} failed to execute decision: {}", self.id(), e);
+                    // 在这里我们可以考虑实现重试逻辑或其他恢复机制
+                }
+                (Some(decision), result.err().map(|e| e.to_string().into()))
+            }
+            Err(e) => match LlmError::of(&e) {
+                // 被内容过滤拦截不是故障，记下拦截原因而不是笼统的调用失败
+                Some(llm_error @ LlmError::ContentFiltered { .. }) => {
+                    warn!("Agent {} turn blocked by the LLM content filter: {}", self.id(), llm_error);
+                    (None, Some(llm_error.to_string().into()))
+                }
+                Some(llm_error) => {
+                    error!("Agent {} think error: {}", self.id(), llm_error);
+                    (None, Some(llm_error.to_string().into()))
+                }
+                None => {
                     error!("Agent {} think error: {}", self.id(), e);
+                    (None, Some(e.to_string().into()))
+                }
+            },
+        };
+
+        self.flush_outbox(&outbox, trace_id, error.is_some(), origin).await;
+        let called_tool = error.is_none() && matches!(decision.as_deref(), Some(Decision::CallTool { .. }));
+        self.emit(|agent_id| CompanyEvent::AgentTurnCompleted {
+            agent_id,
+            trace_id: trace_id.into(),
+            decision,
+            error,
+            role_revision,
+            goal,
+        });
+        called_tool
+    }
+
+    /// 启用自动记录时，把以工具调用结束的 LLM 回复原文记为本轮草稿
+    async fn capture_tool_turn(&self, trace_id: &str, content: &str) {
+        let Some(scratchpad) = self.scratchpad.as_ref().filter(|s| s.config().capture_tool_turns) else {
+            return;
+        };
+        if let Err(e) = scratchpad.write(trace_id, self.id(), ScratchpadSource::ToolTurn, content).await {
+            warn!("Agent {} failed to capture tool turn in scratchpad: {}", self.id(), e);
+        }
+    }
+
+    /// 通过重复发送和循环抑制检查后经消息总线发送本轮排队的消息，轮次失败时按策略处理
+    async fn flush_outbox(&self, outbox: &TurnOutbox, trace_id: &str, turn_failed: bool, origin: &TurnOrigin) {
+        let mut messages = outbox.take();
+        let discarding = turn_failed && self.outbox_policy == OutboxPolicy::Discard;
+        if !discarding {
+            messages = self.drop_duplicates(messages, trace_id);
+        }
+        if let (Some(guard), false) = (&self.loop_guard, discarding) {
+            let mut allowed = Vec::with_capacity(messages.len());
+            for message in messages {
+                match guard.check(&message, &self.peers_for(&message, origin)) {
+                    LoopVerdict::Allow => allowed.push(message),
+                    LoopVerdict::Block { trip, notify } => {
+                        warn!("Agent {} message {} blocked by loop guard: {:?}", self.id(), message.id, trip);
+                        if notify {
+                            if let Err(e) = self.message_bus.send(guard.notice(&message, &trip)).await {
+                                warn!("Failed to deliver loop guard notice to {}: {}", self.id(), e);
+                            }
+                        }
+                        self.emit(|agent_id| CompanyEvent::LoopGuardTripped {
+                            agent_id,
+                            trace_id: trace_id.into(),
+                            message_id: message.id.clone(),
+                            trip: Arc::new(trip),
+                        });
+                    }
+                }
+            }
+            messages = allowed;
+        }
+        if let (Some(moderation), false) = (&self.moderation, discarding) {
+            messages = self.moderate(moderation, messages, trace_id).await;
+        }
+
+        for message in &mut messages {
+            if let (Some(coordinator), false) = (&self.turn_coordinator, discarding) {
+                coordinator.annotate(self.id(), message);
+            }
+            outbox.enqueue(message.clone());
+        }
+        let report = self
+            .message_bus
+            .flush_outbox(self.id(), trace_id, outbox, turn_failed, self.outbox_policy)
+            .await;
+        for message in messages.into_iter().filter(|m| report.sent.contains(&m.id)) {
+            let _ = self.message_tx.send(message);
+        }
+        for (message_id, reason) in &report.failed {
+            error!("Agent {} could not send message {}: {}", self.id(), message_id, reason);
+        }
+    }
+
+    /// 丢弃窗口期内已发过的相同消息；发送工具标记了 `allow_duplicate` 的消息跳过检查
+    fn drop_duplicates(&self, messages: Vec<Message>, trace_id: &str) -> Vec<Message> {
+        let mut kept = Vec::with_capacity(messages.len());
+        for mut message in messages {
+            if message.metadata.remove(ALLOW_DUPLICATE_KEY).is_some() {
+                kept.push(message);
+                continue;
+            }
+            match &self.send_dedup {
+                Some(dedup) if dedup.suppress(&message) => {
+                    warn!("Agent {} message {} suppressed as a duplicate of a recent send to {:?}", self.id(), message.id, message.to);
+                    self.emit(|agent_id| CompanyEvent::DuplicateSendSuppressed {
+                        agent_id,
+                        trace_id: trace_id.into(),
+                        message_id: message.id.clone(),
+                        target: message.to.clone(),
+                    });
+                }
+                _ => kept.push(message),
+            }
+        }
+        kept
+    }
+
+    /// 内容审核：被拦截的消息以提示代替，原文进入隔离表，多次拦截时熔断
+    async fn moderate(&self, moderation: &ModerationService, messages: Vec<Message>, trace_id: &str) -> Vec<Message> {
+        let mut moderated = Vec::with_capacity(messages.len());
+        for message in messages {
+            let internal = is_internal_traffic(&message, &self.message_bus).await;
+            let outcome = moderation.review(self.name(), message, internal).await;
+            if let Some(quarantined) = &outcome.quarantined {
+                warn!(target: "audit", agent_id = %self.id(), trace_id, message_id = %quarantined.message.id, quarantine_id = %quarantined.id, reason = %quarantined.reason, "Agent output blocked by content moderation");
+                if let Some(breakers) = &self.breakers {
+                    if let Some(transition) = breakers.record_moderation_block(self.id(), &quarantined.reason) {
+                        self.breaker_changed(breakers, transition).await;
+                    }
+                }
+            }
+            moderated.push(outcome.message);
+        }
+        moderated
+    }
+
+    /// 人类消息重置回复链；否则取触发本轮的 Agent 消息深度加一，系统提示沿用其深度
+    fn turn_origin(&self, messages: &[Message]) -> TurnOrigin {
+        let mut origin = TurnOrigin::default();
+        if messages.iter().any(|m| self.is_human_message(m)) {
+            if let Some(guard) = &self.loop_guard {
+                guard.note_human_input(self.id());
+            }
+            return origin;
+        }
+
+        for message in messages {
+            let depth = if message.from == LOOP_NOTICE_SENDER {
+                message.chain_depth()
+            } else {
+                if !origin.peers.contains(&message.from) {
+                    origin.peers.push(message.from.clone());
                 }
+                message.chain_depth() + 1
+            };
+            origin.chain_depth = origin.chain_depth.max(depth);
+        }
+        origin
+    }
+
+    /// 私聊对象是 Agent 时只与其往来；群聊消息回应触发本轮的 Agent
+    fn peers_for(&self, message: &Message, origin: &TurnOrigin) -> Vec<String> {
+        match &message.to {
+            MessageTarget::Direct(to) if self.message_bus.is_registered(to) => vec![to.clone()],
+            MessageTarget::Direct(_) => Vec::new(),
+            MessageTarget::Group(_) => origin.peers.clone(),
+        }
+    }
+
+    /// 来自用户或组织外发送者的消息，系统消息除外
+    fn is_human_message(&self, message: &Message) -> bool {
+        message.from != LOOP_NOTICE_SENDER
+            && message.from != self.id()
+            && (is_user_principal(&message.from) || !self.message_bus.is_registered(&message.from))
+    }
+
+    /// 发给自己的、来自非 Agent 发送者（用户）的私聊
+    fn is_user_message(&self, message: &Message) -> bool {
+        matches!(&message.to, MessageTarget::Direct(to) if to == self.id())
+            && (is_user_principal(&message.from) || !self.message_bus.is_registered(&message.from))
+    }
+
+    /// 熔断状态变化：记录审计日志并发出事件，熔断时向管理层发送系统告警
+    async fn breaker_changed(&self, breakers: &CircuitBreakers, transition: BreakerTransition) {
+        let status = breakers.status(self.id());
+        match transition {
+            BreakerTransition::Tripped => {
+                warn!(target: "audit", agent_id = %self.id(), trip = ?status.trip, retry_at = ?status.retry_at, "Agent circuit breaker tripped");
+                self.alert_management(&status).await;
+            }
+            BreakerTransition::ProbeFailed => {
+                warn!(target: "audit", agent_id = %self.id(), failed_probes = status.failed_probes, retry_at = ?status.retry_at, "Agent circuit breaker probe failed");
+            }
+            BreakerTransition::Recovered => {
+                info!(target: "audit", agent_id = %self.id(), "Agent circuit breaker recovered");
+            }
+        }
+        self.emit(|agent_id| CompanyEvent::AgentBreakerChanged {
+            agent_id,
+            status: Arc::new(status),
+        });
+    }
+
+    /// 向管理层用户发送熔断告警
+    async fn alert_management(&self, status: &BreakerStatus) {
+        let Some(store) = self.message_bus.store() else {
+            return;
+        };
+        let users = match store.load_users().await {
+            Ok(users) => users,
+            Err(e) => {
+                warn!("Agent {} failed to load users for breaker alert: {}", self.id(), e);
+                return;
             }
+        };
 
-            // 6. 短暂休眠避免CPU占用过高
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        let catalog = self.message_bus.catalog();
+        let reason = match &status.trip {
+            Some(BreakerTrip::LlmFailures { consecutive }) => {
+                catalog.format("breaker.llm_failures", &[("count", &consecutive.to_string())])
+            }
+            Some(BreakerTrip::LlmAuthFailed) => catalog.get("breaker.llm_auth_failed"),
+            Some(BreakerTrip::ToolErrorRate { errors, calls, window_secs }) => catalog.format(
+                "breaker.tool_errors",
+                &[("errors", &errors.to_string()), ("calls", &calls.to_string()), ("window", &window_secs.to_string())],
+            ),
+            Some(BreakerTrip::ModerationBlocks { blocks, window_secs }) => catalog.format(
+                "breaker.moderation_blocks",
+                &[("blocks", &blocks.to_string()), ("window", &window_secs.to_string())],
+            ),
+            None => String::new(),
+        };
+        let retry_at = status
+            .retry_at
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_default();
+        let content = catalog.format(
+            "breaker.tripped",
+            &[
+                ("agent", self.name()),
+                ("agent_id", self.id()),
+                ("reason", &reason),
+                ("error", status.last_error.as_deref().unwrap_or_default()),
+                ("retry_at", &retry_at),
+            ],
+        );
+
+        for user in users.iter().filter(|u| matches!(u.position, Position::Management)) {
+            let alert = Message::private(BREAKER_ALERT_SENDER, user.principal_id(), &content)
+                .with_metadata(NO_ESCALATION_KEY, "true");
+            if let Err(e) = self.message_bus.send(alert).await {
+                warn!("Failed to deliver breaker alert for {} to {}: {}", self.id(), user.username, e);
+            }
         }
     }
 
+    fn emit(&self, event: impl FnOnce(Arc<str>) -> CompanyEvent) {
+        if let Some(events) = &self.events {
+            events.emit(event(self.id().into()));
+        }
+    }
+
+    /// 从存储加载当前上下文，每轮重新读取以便偏好更新在下一轮生效
+    async fn load_context(&self) -> Context {
+        let Some(store) = self.message_bus.store() else {
+            return Context::default();
+        };
+        match load_context(store.as_ref(), self.id(), None).await {
+            Ok(context) => context,
+            Err(e) => {
+                error!("Agent {} failed to load context: {}", self.id(), e);
+                Context::default()
+            }
+        }
+    }
+
+    /// 加载上下文中消息的回应汇总
+    async fn attach_reactions(&self, context: Context) -> Context {
+        let Some(store) = self.message_bus.store() else {
+            return context;
+        };
+        match store.load_reactions(&context.message_ids()).await {
+            Ok(reactions) => context.with_reactions(ReactionCount::aggregate(&reactions)),
+            Err(e) => {
+                error!("Agent {} failed to load reactions: {}", self.id(), e);
+                context
+            }
+        }
+    }
+
+    /// 更新当前目标：先移除闲置过期的目标；手动分配的任务替换目标，没有目标时本轮最早的用户请求
+    /// 或任务通知成为目标（之后的追问不替换，由 Agent 用 `self.set_goal` 更新），其余有输入的轮次
+    /// 和工具循环推迟过期；返回过期提示（本轮又设置了新目标时不提示）
+    fn track_goal(&self, messages: &[Message], task: Option<&str>, in_tool_loop: bool) -> Option<Message> {
+        let goals = self.goals.as_ref()?;
+        let expired = goals.take_expired(self.id());
+        let request = messages
+            .iter()
+            .find_map(|m| self.goal_source(m).map(|source| (m, source)));
+        if let Some(task) = task {
+            goals.set(self.id(), task, GoalSource::Task, None);
+        } else if let Some((message, source)) = request.filter(|_| goals.get(self.id()).is_none()) {
+            goals.set(self.id(), &message.content, source, Some(message.id.clone()));
+        } else if !messages.is_empty() || in_tool_loop {
+            goals.touch(self.id());
+        }
+
+        let expired = expired.filter(|_| goals.get(self.id()).is_none())?;
+        info!("Agent {} goal expired after inactivity: {}", self.id(), expired.text);
+        Some(expired.expiry_notice(self.id()))
+    }
+
+    /// 能设为目标的消息：用户私聊，或分配给自己的任务通知
+    fn goal_source(&self, message: &Message) -> Option<GoalSource> {
+        if message.content.trim().is_empty() {
+            return None;
+        }
+        if message.from == GOAL_NOTICE_SENDER {
+            let assigned = matches!(&message.to, MessageTarget::Direct(to) if to == self.id());
+            return (assigned && message.metadata("kind") == Some("task")).then_some(GoalSource::Task);
+        }
+        self.is_user_message(message).then_some(GoalSource::User)
+    }
+
+    /// 附上当前目标，截断到目标段落的 token 上限
+    fn attach_goal(&self, context: Context) -> Context {
+        let Some(goals) = &self.goals else {
+            return context;
+        };
+        match goals.get(self.id()) {
+            Some(goal) => {
+                let text = self.runtime.token_counter().truncate(&goal.text, goals.config().token_reserve);
+                context.with_goal(text)
+            }
+            None => context,
+        }
+    }
+
+    /// 附上所在会话的置顶消息，超出预算时保留最新置顶的
+    async fn attach_pins(&self, context: Context) -> Context {
+        let Some(store) = self.message_bus.store() else {
+            return context;
+        };
+        let seen: Vec<Message> = context.history.iter().chain(&context.unread_messages).cloned().collect();
+        match load_pinned_messages(store.as_ref(), self.id(), &seen).await {
+            Ok(pinned) => {
+                let budget = self.snapshot_config.pinned_token_budget;
+                let kept = self.runtime.token_counter().fit_newest(&pinned, budget).to_vec();
+                context.with_pinned(kept)
+            }
+            Err(e) => {
+                error!("Agent {} failed to load pinned messages: {}", self.id(), e);
+                context
+            }
+        }
+    }
+
+    /// 为他人发来的其他语言消息附上译文，阅读语言取回复语言
+    async fn attach_translations(&self, context: Context) -> Context {
+        let (Some(translator), Some(language)) = (&self.translator, self.runtime.response_style(&context).language)
+        else {
+            return context;
+        };
+        let incoming: Vec<Message> = context
+            .history
+            .iter()
+            .chain(&context.unread_messages)
+            .filter(|m| m.from != self.id())
+            .cloned()
+            .collect();
+        let translations = translator.translate_all(&incoming, &language).await;
+        context.with_translations(translations)
+    }
+
     /// 执行决策
-    async fn execute_decision(&self, decision: Decision) -> Result<()> {
+    async fn execute_decision(
+        &self,
+        decision: Decision,
+        trace_id: &str,
+        chain_depth: u32,
+        outbox: &TurnOutbox,
+    ) -> Result<()> {
         match decision {
             Decision::SendMessage { target, content } => {
+                // 只保留对应实际工具结果的引用，回答发出后重新计数
+                let (content, citations) = {
+                    let mut tracker = self.citations.lock().unwrap();
+                    let resolved = tracker.resolve(&content);
+                    tracker.clear();
+                    resolved
+                };
                 let msg = match target {
                     MessageTarget::Direct(to) => Message::private(self.id(), to, content),
                     MessageTarget::Group(group_id) => {
                         Message::group(self.id(), group_id, content)
                     }
-                };
+                }
+                .with_trace(trace_id, None)
+                .with_chain_depth(chain_depth)
+                .with_citations(&citations);
 
-                let _ = self.message_tx.send(msg.clone());
-                info!("Agent {} sent message: {:?}", self.id(), msg);
+                info!("Agent {} queued message: {:?}", self.id(), msg);
+                outbox.enqueue(msg);
             }
             Decision::CreateGroup { name, members } => {
                 info!("Agent {} wants to create group: {} with members: {:?}", self.id(), name, members);
@@ -164,6 +1177,9 @@ impl AutonomousAgent {
                     }
                 }
             }
+            Decision::CallTool { tool_id, arguments } => {
+                self.call_tool(&tool_id, arguments, trace_id, outbox).await?;
+            }
             Decision::Wait => {
                 // 什么都不做，等待下一次循环
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
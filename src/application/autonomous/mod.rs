@@ -4,4 +4,4 @@
 
 pub mod agent;
 
-pub use agent::AutonomousAgent;
+pub use agent::{AutonomousAgent, AUTO_REPLY_KEY, BREAKER_ALERT_SENDER};
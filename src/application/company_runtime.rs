@@ -5,16 +5,41 @@
 use std::sync::Arc;
 
 use anyhow::Result;
+use async_trait::async_trait;
 use dashmap::DashMap;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tracing::{error, info};
 
+use crate::core::agent::{AgentRuntime, SnapshotConfig};
+use crate::core::budget::DepartmentBudgets;
+use crate::core::circuit_breaker::CircuitBreakers;
+#[cfg(feature = "chaos")]
+use crate::core::chaos::FaultInjector;
 use crate::core::config::CompanyConfig;
-use crate::core::messaging::MessageBus;
+use crate::core::events::EventBus;
+use crate::core::loop_guard::LoopGuard;
+use crate::core::moderation::ModerationService;
+use crate::core::messaging::{MessageBus, OutboxPolicy};
+use crate::core::response_language::ResponseStyle;
+use crate::core::retry::{Retries, RetrySubsystem};
+use crate::core::scheduler::TurnScheduler;
+use crate::core::goals::GoalBoard;
+use crate::core::nudge::NudgeSlots;
+use crate::core::scratchpad::Scratchpad;
+use crate::core::send_dedup::SendDedup;
 use crate::core::store::Store;
+use crate::core::temp_agents::TempAgentRunner;
 use crate::core::tool::ToolRegistry;
+use crate::core::tool_concurrency::ToolConcurrency;
+use crate::core::skill::SkillManager;
+use crate::core::tool_stats::ToolStats;
+use crate::core::tool_view::AgentToolView;
+use crate::core::turn_taking::TurnCoordinator;
+use crate::core::translation::TranslationService;
 use crate::core::capability::CapabilityRegistry;
-use crate::domain::Organization;
+use crate::domain::{Agent, Organization};
+use crate::infrastructure::logger::PromptLog;
 use crate::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
 use crate::infrastructure::capability::{McpServer, McpProtocolHandler};
 use super::autonomous::AutonomousAgent;
@@ -50,24 +75,191 @@ impl OrganizationManager {
     }
 }
 
+/// `attach_tools` 提供给 Agent 的工具视图和执行器
+type AttachedTools = Option<(Arc<AgentToolView>, Arc<FrameworkToolExecutor>)>;
+
 /// Agent 管理器
+///
+/// 克隆共享同一组 Agent，可作为 [`TempAgentRunner`] 交给临时 Agent 管理。
+#[derive(Clone)]
 pub struct AgentManager {
-    agents: DashMap<String, AutonomousAgent>,
+    agents: Arc<DashMap<String, AutonomousAgent>>,
+    /// `attach_tools` 提供的工具，之后创建的临时 Agent 同样使用
+    tools: Arc<std::sync::RwLock<AttachedTools>>,
+    /// 临时 Agent 的自主循环，过期时中止
+    temp_loops: Arc<DashMap<String, JoinHandle<()>>>,
     message_bus: Arc<MessageBus>,
+    events: Option<Arc<EventBus>>,
+    reactions_in_context: bool,
+    snapshot: SnapshotConfig,
+    response_style: ResponseStyle,
+    scheduler: Option<Arc<TurnScheduler>>,
+    outbox_policy: OutboxPolicy,
+    loop_guard: Option<Arc<LoopGuard>>,
+    send_dedup: Option<Arc<SendDedup>>,
+    turn_coordinator: Option<Arc<TurnCoordinator>>,
+    translator: Option<Arc<TranslationService>>,
+    moderation: Option<Arc<ModerationService>>,
+    scratchpad: Option<Arc<Scratchpad>>,
+    goals: Option<Arc<GoalBoard>>,
+    nudges: Option<Arc<NudgeSlots>>,
+    budgets: Option<Arc<DepartmentBudgets>>,
+    breakers: Option<Arc<CircuitBreakers>>,
+    prompt_log: Option<Arc<PromptLog>>,
+    retries: Option<Arc<Retries>>,
+    #[cfg(feature = "chaos")]
+    fault_injector: Option<Arc<FaultInjector>>,
 }
 
 impl AgentManager {
     pub fn new(message_bus: Arc<MessageBus>) -> Self {
         Self {
-            agents: DashMap::new(),
+            agents: Arc::new(DashMap::new()),
+            tools: Arc::new(std::sync::RwLock::new(None)),
+            temp_loops: Arc::new(DashMap::new()),
             message_bus,
+            events: None,
+            reactions_in_context: false,
+            snapshot: SnapshotConfig::default(),
+            response_style: ResponseStyle::default(),
+            scheduler: None,
+            outbox_policy: OutboxPolicy::default(),
+            loop_guard: None,
+            send_dedup: None,
+            turn_coordinator: None,
+            translator: None,
+            moderation: None,
+            scratchpad: None,
+            goals: None,
+            nudges: None,
+            budgets: None,
+            breakers: None,
+            prompt_log: None,
+            retries: None,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
         }
     }
 
+    /// 创建的 Agent 向该总线发出生命周期事件
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 创建的 Agent 在上下文中显示消息回应汇总
+    pub fn with_reactions_in_context(mut self, enabled: bool) -> Self {
+        self.reactions_in_context = enabled;
+        self
+    }
+
+    /// 创建的 Agent 按该配置获取上下文快照
+    pub fn with_snapshot_config(mut self, snapshot: SnapshotConfig) -> Self {
+        self.snapshot = snapshot;
+        self
+    }
+
+    /// 创建的 Agent 默认使用的回复语言和语气
+    pub fn with_response_style(mut self, style: ResponseStyle) -> Self {
+        self.response_style = style;
+        self
+    }
+
+    /// 创建的 Agent 通过该调度器获取轮次许可
+    pub fn with_scheduler(mut self, scheduler: Arc<TurnScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// 创建的 Agent 在轮次失败时按该策略处理发件箱
+    pub fn with_outbox_policy(mut self, policy: OutboxPolicy) -> Self {
+        self.outbox_policy = policy;
+        self
+    }
+
+    /// 创建的 Agent 发出的消息经过该循环抑制器检查
+    pub fn with_loop_guard(mut self, loop_guard: Arc<LoopGuard>) -> Self {
+        self.loop_guard = Some(loop_guard);
+        self
+    }
+
+    /// 创建的 Agent 共享该重复发送缓存
+    pub fn with_send_dedup(mut self, send_dedup: Arc<SendDedup>) -> Self {
+        self.send_dedup = Some(send_dedup);
+        self
+    }
+
+    /// 创建的 Agent 在开启轮流发言的群聊中听从该协调器
+    pub fn with_turn_coordinator(mut self, coordinator: Arc<TurnCoordinator>) -> Self {
+        self.turn_coordinator = Some(coordinator);
+        self
+    }
+
+    /// 创建的 Agent 在上下文中看到其他语言消息的译文
+    pub fn with_translator(mut self, translator: Arc<TranslationService>) -> Self {
+        self.translator = Some(translator);
+        self
+    }
+
+    /// 创建的 Agent 发出的消息先经过内容审核
+    pub fn with_moderation(mut self, moderation: Arc<ModerationService>) -> Self {
+        self.moderation = Some(moderation);
+        self
+    }
+
+    /// 创建的 Agent 的运行草稿（按配置自动记录以工具调用结束的 LLM 回复）
+    pub fn with_scratchpad(mut self, scratchpad: Arc<Scratchpad>) -> Self {
+        self.scratchpad = Some(scratchpad);
+        self
+    }
+
+    /// 创建的 Agent 记录当前目标
+    pub fn with_goals(mut self, goals: Arc<GoalBoard>) -> Self {
+        self.goals = Some(goals);
+        self
+    }
+
+    /// 创建的 Agent 在工具循环期间接受插话
+    pub fn with_nudges(mut self, nudges: Arc<NudgeSlots>) -> Self {
+        self.nudges = Some(nudges);
+        self
+    }
+
+    /// 创建的 Agent 受部门 LLM 预算约束
+    pub fn with_budgets(mut self, budgets: Arc<DepartmentBudgets>) -> Self {
+        self.budgets = Some(budgets);
+        self
+    }
+
+    /// 创建的 Agent 持续失败时由该熔断器暂停
+    pub fn with_circuit_breakers(mut self, breakers: Arc<CircuitBreakers>) -> Self {
+        self.breakers = Some(breakers);
+        self
+    }
+
+    /// 创建的 Agent 把每次 LLM 请求和响应写入提示词日志
+    pub fn with_prompt_log(mut self, prompt_log: Arc<PromptLog>) -> Self {
+        self.prompt_log = Some(prompt_log);
+        self
+    }
+
+    /// 创建的 Agent 按注册表中 LLM 子系统的策略和预算重试
+    pub fn with_retries(mut self, retries: Arc<Retries>) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// 创建的 Agent 在每次 LLM 请求前询问故障注入器
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injector(mut self, injector: Arc<FaultInjector>) -> Self {
+        self.fault_injector = Some(injector);
+        self
+    }
+
     /// 初始化所有 Agent
     pub async fn initialize_agents(&self, organization: &Organization) -> Result<()> {
         for agent_data in &organization.agents {
-            let agent = AutonomousAgent::new(agent_data.clone(), self.message_bus.clone()).await?;
+            let agent = self.build_agent(agent_data).await?;
             let agent_id = agent.id().to_string();
             self.agents.insert(agent_id.clone(), agent);
             info!("Created agent: {}", agent_id);
@@ -75,6 +267,76 @@ impl AgentManager {
         Ok(())
     }
 
+    /// 按管理器的配置创建 Agent 并注册到消息总线
+    async fn build_agent(&self, agent_data: &Agent) -> Result<AutonomousAgent> {
+        let runtime = AgentRuntime::new(agent_data.clone())
+            .await?
+            .with_response_style(self.response_style.clone());
+        let runtime = match &self.prompt_log {
+            Some(prompt_log) => runtime.with_prompt_log(prompt_log.clone()),
+            None => runtime,
+        };
+        let runtime = match &self.retries {
+            Some(retries) => runtime.with_retrier(retries.retrier(RetrySubsystem::Llm, "llm.agent")),
+            None => runtime,
+        };
+        #[cfg(feature = "chaos")]
+        let runtime = match &self.fault_injector {
+            Some(injector) => runtime.with_fault_injector(injector.clone()),
+            None => runtime,
+        };
+        let mut agent = AutonomousAgent::from_runtime(runtime, self.message_bus.clone())
+            .with_reactions_in_context(self.reactions_in_context)
+            .with_snapshot_config(self.snapshot)
+            .with_outbox_policy(self.outbox_policy);
+        if let Some(events) = &self.events {
+            agent = agent.with_events(events.clone());
+        }
+        if let Some(scheduler) = &self.scheduler {
+            agent = agent.with_scheduler(scheduler.clone());
+        }
+        if let Some(loop_guard) = &self.loop_guard {
+            agent = agent.with_loop_guard(loop_guard.clone());
+        }
+        if let Some(send_dedup) = &self.send_dedup {
+            agent = agent.with_send_dedup(send_dedup.clone());
+        }
+        if let Some(coordinator) = &self.turn_coordinator {
+            agent = agent.with_turn_coordinator(coordinator.clone());
+        }
+        if let Some(translator) = &self.translator {
+            agent = agent.with_translator(translator.clone());
+        }
+        if let Some(moderation) = &self.moderation {
+            agent = agent.with_moderation(moderation.clone());
+        }
+        if let Some(scratchpad) = &self.scratchpad {
+            agent = agent.with_scratchpad(scratchpad.clone());
+        }
+        if let Some(goals) = &self.goals {
+            agent = agent.with_goals(goals.clone());
+        }
+        if let Some(nudges) = &self.nudges {
+            agent = agent.with_nudges(nudges.clone());
+        }
+        if let Some(budgets) = &self.budgets {
+            agent = agent.with_budgets(budgets.clone());
+        }
+        if let Some(breakers) = &self.breakers {
+            agent = agent.with_circuit_breakers(breakers.clone());
+        }
+        Ok(agent)
+    }
+
+    /// 为已创建的 Agent 提供工具：每轮按视图过滤后交给 LLM
+    pub fn attach_tools(&self, tool_view: Arc<AgentToolView>, executor: Arc<FrameworkToolExecutor>) {
+        for mut agent in self.agents.iter_mut() {
+            let updated = agent.clone().with_tools(tool_view.clone(), executor.clone());
+            *agent = updated;
+        }
+        *self.tools.write().unwrap_or_else(|e| e.into_inner()) = Some((tool_view, executor));
+    }
+
     /// 启动所有 Agent 的自主循环
     pub async fn start_agent_loops(&self) -> Result<Vec<tokio::task::JoinHandle<()>>> {
         let mut handles = vec![];
@@ -110,25 +372,83 @@ impl AgentManager {
     }
 }
 
+#[async_trait]
+impl TempAgentRunner for AgentManager {
+    async fn start(&self, agent: &Agent) -> Result<()> {
+        let mut agent = self.build_agent(agent).await?;
+        let tools = self.tools.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some((tool_view, executor)) = tools {
+            agent = agent.with_tools(tool_view, executor);
+        }
+        let agent_id = agent.id().to_string();
+        self.agents.insert(agent_id.clone(), agent.clone());
+        let handle = tokio::spawn(async move {
+            if let Err(e) = agent.run_loop().await {
+                error!("Agent {} error: {}", agent.id(), e);
+            }
+        });
+        self.temp_loops.insert(agent_id.clone(), handle);
+        info!("Started temporary agent: {}", agent_id);
+        Ok(())
+    }
+
+    async fn stop(&self, agent_id: &str) {
+        if let Some((_, handle)) = self.temp_loops.remove(agent_id) {
+            handle.abort();
+        }
+        self.agents.remove(agent_id);
+        self.message_bus.unregister(agent_id);
+    }
+}
+
 /// 工具和功能管理器
 pub struct ToolCapabilityManager {
     tool_registry: Arc<ToolRegistry>,
     capability_registry: Arc<CapabilityRegistry>,
+    tool_stats: Arc<ToolStats>,
+    tool_concurrency: Arc<ToolConcurrency>,
+    skill_manager: Arc<SkillManager>,
 }
 
 impl ToolCapabilityManager {
     pub fn new() -> Self {
+        let tool_registry = Arc::new(ToolRegistry::new());
+        let capability_registry = Arc::new(CapabilityRegistry::new());
         Self {
-            tool_registry: Arc::new(ToolRegistry::new()),
-            capability_registry: Arc::new(CapabilityRegistry::new()),
+            skill_manager: Arc::new(SkillManager::new(tool_registry.clone(), capability_registry.clone())),
+            tool_registry,
+            capability_registry,
+            tool_stats: Arc::new(ToolStats::new()),
+            tool_concurrency: Arc::new(ToolConcurrency::default()),
         }
     }
 
+    /// 获取技能管理器（与工具/功能注册表共享）
+    pub fn skill_manager(&self) -> Arc<SkillManager> {
+        self.skill_manager.clone()
+    }
+
+    /// 使用指定的工具并发控制器
+    pub fn with_tool_concurrency(mut self, tool_concurrency: Arc<ToolConcurrency>) -> Self {
+        self.tool_concurrency = tool_concurrency;
+        self
+    }
+
+    /// 获取工具并发控制器
+    pub fn tool_concurrency(&self) -> Arc<ToolConcurrency> {
+        self.tool_concurrency.clone()
+    }
+
     /// 获取 ToolRegistry 引用
     pub fn tool_registry(&self) -> Arc<ToolRegistry> {
         self.tool_registry.clone()
     }
 
+    /// 获取工具统计收集器
+    pub fn tool_stats(&self) -> Arc<ToolStats> {
+        self.tool_stats.clone()
+    }
+
     /// 注册应用自定义工具
     pub async fn register_app_tool(&self, tool: crate::domain::tool::Tool) -> Result<()> {
         let tool_id = tool.id.clone();
@@ -150,6 +470,7 @@ impl ToolCapabilityManager {
             self.tool_registry.clone(),
             store,
         )
+        .with_tool_stats(self.tool_stats.clone())
     }
 
     /// 获取框架工具执行器
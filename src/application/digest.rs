@@ -0,0 +1,639 @@
+//! 公司活动摘要
+//!
+//! 不常看系统的相关人员可以定期收到一份摘要：消息量、各群聊的重要决定（由 LLM 总结）、
+//! 完成的任务、Watchdog 触发和部门预算用量。[`DigestGenerator`] 按 `digest.cadence`
+//! 定期检查：每日摘要在每天 `hour_utc` 点生成，覆盖之前 24 小时；每周摘要在周一的这个时刻生成，
+//! 覆盖之前 7 天。已生成的摘要保存在存储中（`GET /api/digests` 可浏览），重启后不会重复生成。
+//!
+//! 生成尽量不失败：某一部分出错（如总结群聊时 LLM 报错）时只省略这一部分，并在摘要中注明原因。
+//! 摘要生成后发给配置的接收方：按用户名发邮件（需要 SMTP）、发到指定群聊、POST 到 webhook，
+//! 某个接收方失败只记录日志。
+//!
+//! Watchdog 触发不落库，生成器通过事件总线在内存中记录，进程重启前的触发不计入。
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::DateTime;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::core::budget::DepartmentBudgets;
+use crate::core::chat_sessions::SessionSummarizer;
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::config::DigestConfig;
+use crate::core::escalation::NO_ESCALATION_KEY;
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::group_stats::GroupStatsTracker;
+use crate::core::i18n::MessageCatalog;
+use crate::core::join_context::GroupSummarizer;
+use crate::core::messaging::MessageBus;
+use crate::core::retry::{Retrier, RetryPolicy};
+use crate::core::store::{MessageFilter, Store, TaskFilter};
+use crate::core::tokenizer::{HeuristicTokenizer, Tokenizer};
+use crate::domain::digest::{
+    BudgetUsage, CompletedTask, GroupActivity, GroupDecisions, IncidentCount, OmittedSection, SenderCount,
+};
+use crate::domain::{Digest, DigestCadence, DigestSection, LLMConfig, Message, MessageTarget, TaskStatus};
+use crate::infrastructure::email::{EmailMessage, EmailNotifier};
+use crate::infrastructure::llm::OpenAIClient;
+
+/// 群聊摘要消息的发送者
+pub const DIGEST_SENDER: &str = "system";
+
+/// 统计消息量时读取的消息上限
+const MAX_DIGEST_MESSAGES: usize = 100_000;
+
+/// 消息量中列出的最活跃发送者数
+const TOP_SENDERS: usize = 5;
+
+/// 记住的 Watchdog 触发数，超出后忘记最早的
+const INCIDENT_CAPACITY: usize = 10_000;
+
+const SECS_PER_DAY: i64 = 24 * 3600;
+
+/// 总结一段群聊记录
+#[async_trait]
+pub trait Summarizer: Send + Sync {
+    /// 总结 `group_name` 中的对话（每行 `发送者: 内容`，按时间先后）里做出的决定
+    async fn summarize(&self, group_name: &str, transcript: &str) -> Result<String>;
+}
+
+/// 使用 LLM 总结
+pub struct LlmSummarizer {
+    client: OpenAIClient,
+}
+
+impl LlmSummarizer {
+    pub fn new(config: &LLMConfig) -> Self {
+        Self {
+            client: OpenAIClient::for_config(config),
+        }
+    }
+}
+
+#[async_trait]
+impl Summarizer for LlmSummarizer {
+    async fn summarize(&self, group_name: &str, transcript: &str) -> Result<String> {
+        let prompt = format!(
+            "Below is a conversation from the group \"{}\". List the notable decisions that were made, \
+             one short sentence each. If no decision was made, reply with \"No decisions.\"\n\n{}",
+            group_name, transcript
+        );
+        self.client.complete(&prompt).await
+    }
+}
+
+#[async_trait]
+impl SessionSummarizer for LlmSummarizer {
+    async fn summarize_session(&self, agent_name: &str, transcript: &str) -> Result<String> {
+        let prompt = format!(
+            "Below is a support conversation with {}. Summarize in a few short sentences what was asked, \
+             what was done and whether anything is still open.\n\n{}",
+            agent_name, transcript
+        );
+        self.client.complete(&prompt).await
+    }
+}
+
+#[async_trait]
+impl GroupSummarizer for LlmSummarizer {
+    async fn summarize_group(&self, group_name: &str, transcript: &str) -> Result<String> {
+        let prompt = format!(
+            "Below is a conversation from the group \"{}\". Someone new is joining it. Summarize in a few short \
+             sentences what was discussed, what was decided and what is still open.\n\n{}",
+            group_name, transcript
+        );
+        self.client.complete(&prompt).await
+    }
+}
+
+/// 活动摘要生成器
+pub struct DigestGenerator {
+    config: DigestConfig,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    catalog: MessageCatalog,
+    summarizer: Option<Arc<dyn Summarizer>>,
+    tokenizer: Arc<dyn Tokenizer>,
+    message_bus: Option<Arc<MessageBus>>,
+    email: Option<Arc<EmailNotifier>>,
+    budgets: Option<Arc<DepartmentBudgets>>,
+    group_stats: Option<Arc<GroupStatsTracker>>,
+    http: reqwest::Client,
+    /// webhook 投递的重试
+    retrier: Retrier,
+    /// Watchdog 触发：(时间, 规则ID, 工具ID)
+    incidents: Mutex<VecDeque<(i64, String, String)>>,
+}
+
+impl DigestGenerator {
+    pub fn new(config: DigestConfig, store: Arc<dyn Store>) -> Self {
+        Self {
+            config,
+            store,
+            clock: Arc::new(SystemClock),
+            catalog: MessageCatalog::default(),
+            summarizer: None,
+            tokenizer: Arc::new(HeuristicTokenizer),
+            message_bus: None,
+            email: None,
+            budgets: None,
+            group_stats: None,
+            http: reqwest::Client::new(),
+            retrier: Retrier::new("digest.webhook", RetryPolicy::default()).with_retryable(is_retryable_webhook_error),
+            incidents: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置摘要文本使用的消息目录
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// 设置总结群聊决定的总结器，未设置时摘要省略这一部分
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// 设置计算 token 上限使用的分词器
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// 设置消息总线，用于发到群聊
+    pub fn with_message_bus(mut self, bus: Arc<MessageBus>) -> Self {
+        self.message_bus = Some(bus);
+        self
+    }
+
+    /// 设置邮件通知器，用于发给用户
+    pub fn with_email(mut self, email: Arc<EmailNotifier>) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// 设置 webhook 投递的重试执行器（子系统策略、预算和指标）
+    pub fn with_retrier(mut self, retrier: Retrier) -> Self {
+        self.retrier = retrier.with_retryable(is_retryable_webhook_error);
+        self
+    }
+
+    /// 设置部门预算，未设置时摘要省略预算用量
+    pub fn with_budgets(mut self, budgets: Arc<DepartmentBudgets>) -> Self {
+        self.budgets = Some(budgets);
+        self
+    }
+
+    /// 设置群聊活动统计，未设置时摘要省略群聊发言分布
+    pub fn with_group_stats(mut self, group_stats: Arc<GroupStatsTracker>) -> Self {
+        self.group_stats = Some(group_stats);
+        self
+    }
+
+    pub fn config(&self) -> &DigestConfig {
+        &self.config
+    }
+
+    /// 记录事件中的 Watchdog 触发
+    pub fn record(&self, event: &CompanyEvent) {
+        let CompanyEvent::WatchdogTriggered { rule_id, tool_id, .. } = event else {
+            return;
+        };
+        let mut incidents = self.incidents.lock().unwrap();
+        if incidents.len() >= INCIDENT_CAPACITY {
+            incidents.pop_front();
+        }
+        incidents.push_back((self.clock.now(), rule_id.to_string(), tool_id.to_string()));
+    }
+
+    /// `now` 时最近一个已结束的周期（开始，结束）
+    pub fn latest_period(&self, now: i64) -> (i64, i64) {
+        let offset = i64::from(self.config.hour_utc.min(23)) * 3600;
+        let day = (now - offset).div_euclid(SECS_PER_DAY);
+        let end_day = match self.config.cadence {
+            DigestCadence::Daily => day,
+            // 1970-01-01 是周四，对齐到周一
+            DigestCadence::Weekly => day - (day + 3).rem_euclid(7),
+        };
+        let end = end_day * SECS_PER_DAY + offset;
+        (end - self.config.cadence.period_secs(), end)
+    }
+
+    /// 到期且尚未生成的周期
+    pub async fn due_period(&self) -> Result<Option<(i64, i64)>> {
+        let (start, end) = self.latest_period(self.clock.now());
+        let latest = self.store.load_digests(1).await?;
+        if latest.first().is_some_and(|digest| digest.period_end >= end) {
+            return Ok(None);
+        }
+        Ok(Some((start, end)))
+    }
+
+    /// 到期时生成、保存并发送摘要
+    pub async fn run_due(&self) -> Result<Option<Digest>> {
+        let Some((start, end)) = self.due_period().await? else {
+            return Ok(None);
+        };
+        let digest = self.generate(start, end).await;
+        self.store.save_digest(&digest).await?;
+        info!(
+            digest_id = %digest.id,
+            sections = digest.sections.len(),
+            omitted = digest.omitted.len(),
+            "Generated {} digest",
+            digest.cadence.as_str()
+        );
+        self.deliver(&digest).await;
+        Ok(Some(digest))
+    }
+
+    /// 生成 `[start, end)` 的摘要；出错的部分省略并注明原因
+    pub async fn generate(&self, start: i64, end: i64) -> Digest {
+        let mut digest = Digest::new(self.config.cadence, start, end, self.clock.now());
+        let sections = self.config.sections;
+        if sections.messages {
+            add_section(&mut digest, "messages", self.messages_section(start, end).await);
+        }
+        if sections.decisions {
+            add_section(&mut digest, "decisions", self.decisions_section(start, end).await);
+        }
+        if sections.tasks {
+            add_section(&mut digest, "tasks", self.tasks_section(start, end).await);
+        }
+        if sections.incidents {
+            add_section(&mut digest, "incidents", Ok(self.incidents_section(start, end)));
+        }
+        if sections.budgets {
+            add_section(&mut digest, "budgets", self.budgets_section().await);
+        }
+        if sections.group_activity {
+            add_section(&mut digest, "group_activity", self.group_activity_section(start, end).await);
+        }
+        digest
+    }
+
+    async fn period_messages(&self, start: i64, end: i64) -> Result<Vec<Message>> {
+        let mut filter = MessageFilter::new().since(start).until(end - 1).oldest_first();
+        filter.limit = MAX_DIGEST_MESSAGES;
+        self.store.load_messages(filter).await
+    }
+
+    async fn messages_section(&self, start: i64, end: i64) -> Result<DigestSection> {
+        let messages = self.period_messages(start, end).await?;
+        let group = messages.iter().filter(|m| matches!(m.to, MessageTarget::Group(_))).count();
+        let mut senders: HashMap<&str, usize> = HashMap::new();
+        for message in &messages {
+            *senders.entry(message.from.as_str()).or_default() += 1;
+        }
+        let mut top_senders: Vec<SenderCount> = senders
+            .into_iter()
+            .map(|(sender, count)| SenderCount {
+                sender: sender.to_string(),
+                count,
+            })
+            .collect();
+        top_senders.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.sender.cmp(&b.sender)));
+        top_senders.truncate(TOP_SENDERS);
+        Ok(DigestSection::Messages {
+            total: messages.len(),
+            direct: messages.len() - group,
+            group,
+            top_senders,
+        })
+    }
+
+    /// 每个有消息的群聊总结一次；token 上限在这些群聊之间平分，超出时保留最新的消息
+    async fn decisions_section(&self, start: i64, end: i64) -> Result<DigestSection> {
+        let summarizer = self.summarizer.as_ref().ok_or_else(|| anyhow!("no summarizer configured"))?;
+        let messages = self.period_messages(start, end).await?;
+        let mut by_group: BTreeMap<&str, Vec<&Message>> = BTreeMap::new();
+        for message in &messages {
+            if let MessageTarget::Group(group_id) = &message.to {
+                by_group.entry(group_id.as_str()).or_default().push(message);
+            }
+        }
+        if by_group.is_empty() {
+            return Ok(DigestSection::Decisions { groups: Vec::new() });
+        }
+
+        let names: HashMap<String, String> =
+            self.store.load_groups().await?.into_iter().map(|g| (g.id, g.name)).collect();
+        let share = self.config.token_budget / by_group.len();
+        let mut groups = Vec::new();
+        for (group_id, messages) in by_group {
+            let mut lines = Vec::new();
+            let mut remaining = share;
+            for message in messages.iter().rev() {
+                let line = format!("{}: {}", message.from, message.content);
+                let tokens = self.tokenizer.count(&line) + 1;
+                if tokens > remaining {
+                    break;
+                }
+                remaining -= tokens;
+                lines.push(line);
+            }
+            if lines.is_empty() {
+                continue;
+            }
+            lines.reverse();
+
+            let group_name = names.get(group_id).cloned().unwrap_or_else(|| group_id.to_string());
+            let summary = summarizer
+                .summarize(&group_name, &lines.join("\n"))
+                .await
+                .map_err(|e| anyhow!("failed to summarize {}: {}", group_id, e))?;
+            groups.push(GroupDecisions {
+                group_id: group_id.to_string(),
+                group_name,
+                summary: summary.trim().to_string(),
+            });
+        }
+        Ok(DigestSection::Decisions { groups })
+    }
+
+    async fn tasks_section(&self, start: i64, end: i64) -> Result<DigestSection> {
+        let mut filter = TaskFilter::new().status(TaskStatus::Done);
+        filter.limit = MAX_DIGEST_MESSAGES;
+        let mut tasks: Vec<_> = self
+            .store
+            .load_tasks(filter)
+            .await?
+            .into_iter()
+            .filter(|t| t.updated_at >= start && t.updated_at < end)
+            .collect();
+        tasks.sort_by(|a, b| a.updated_at.cmp(&b.updated_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(DigestSection::Tasks {
+            completed: tasks
+                .into_iter()
+                .map(|t| CompletedTask {
+                    id: t.id,
+                    title: t.title,
+                    assignee: t.assignee,
+                })
+                .collect(),
+        })
+    }
+
+    fn incidents_section(&self, start: i64, end: i64) -> DigestSection {
+        let mut counts: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for (at, rule_id, tool_id) in self.incidents.lock().unwrap().iter() {
+            if *at >= start && *at < end {
+                *counts.entry((rule_id.clone(), tool_id.clone())).or_default() += 1;
+            }
+        }
+        let mut incidents: Vec<IncidentCount> = counts
+            .into_iter()
+            .map(|((rule_id, tool_id), count)| IncidentCount { rule_id, tool_id, count })
+            .collect();
+        incidents.sort_by_key(|i| std::cmp::Reverse(i.count));
+        DigestSection::Incidents { incidents }
+    }
+
+    async fn budgets_section(&self) -> Result<DigestSection> {
+        let budgets = self.budgets.as_ref().ok_or_else(|| anyhow!("department budgets not attached"))?;
+        let org = self.store.load_organization().await?;
+        let departments = org
+            .departments
+            .iter()
+            .filter_map(|d| {
+                d.llm_budget.map(|budget| BudgetUsage {
+                    department_id: d.id.clone(),
+                    department_name: d.name.clone(),
+                    used: budgets.department_usage(&d.id),
+                    budget,
+                })
+            })
+            .collect();
+        Ok(DigestSection::Budgets { departments })
+    }
+
+    /// 从群聊活动统计读取各群聊的发言分布，不扫描消息
+    async fn group_activity_section(&self, start: i64, end: i64) -> Result<DigestSection> {
+        let tracker = self.group_stats.as_ref().ok_or_else(|| anyhow!("group stats not attached"))?;
+        let mut activity = tracker.activity_between(start, end).await?;
+        let mut groups = Vec::new();
+        for group in self.store.load_groups().await? {
+            let Some(counts) = activity.remove(&group.id) else {
+                continue;
+            };
+            let mut senders: Vec<SenderCount> = counts
+                .iter()
+                .map(|(sender, count)| SenderCount {
+                    sender: sender.clone(),
+                    count: *count as usize,
+                })
+                .collect();
+            senders.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.sender.cmp(&b.sender)));
+            let quiet = group.members.iter().filter(|m| !counts.contains_key(*m)).cloned().collect();
+            groups.push(GroupActivity {
+                group_id: group.id,
+                group_name: group.name,
+                senders,
+                quiet,
+            });
+        }
+        Ok(DigestSection::GroupActivity { groups })
+    }
+
+    /// 摘要的标题和正文
+    pub fn render(&self, digest: &Digest) -> (String, String) {
+        let date = |ts: i64| {
+            DateTime::from_timestamp(ts, 0)
+                .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+                .unwrap_or_default()
+        };
+        let subject = self.catalog.format(
+            "digest.subject",
+            &[("start", &date(digest.period_start)), ("end", &date(digest.period_end))],
+        );
+
+        let mut lines = vec![subject.clone()];
+        for section in &digest.sections {
+            lines.push(String::new());
+            match section {
+                DigestSection::Messages { total, direct, group, top_senders } => {
+                    lines.push(self.catalog.format(
+                        "digest.messages",
+                        &[("total", &total.to_string()), ("direct", &direct.to_string()), ("group", &group.to_string())],
+                    ));
+                    if !top_senders.is_empty() {
+                        let senders: Vec<String> =
+                            top_senders.iter().map(|s| format!("{} ({})", s.sender, s.count)).collect();
+                        lines.push(self.catalog.format("digest.top_senders", &[("senders", &senders.join(", "))]));
+                    }
+                }
+                DigestSection::Decisions { groups } => {
+                    lines.push(self.catalog.get("digest.decisions"));
+                    for group in groups {
+                        lines.push(format!("- {}: {}", group.group_name, group.summary));
+                    }
+                }
+                DigestSection::Tasks { completed } => {
+                    lines.push(self.catalog.format("digest.tasks", &[("count", &completed.len().to_string())]));
+                    for task in completed {
+                        match &task.assignee {
+                            Some(assignee) => lines.push(format!("- {} ({})", task.title, assignee)),
+                            None => lines.push(format!("- {}", task.title)),
+                        }
+                    }
+                }
+                DigestSection::Incidents { incidents } => {
+                    let total: usize = incidents.iter().map(|i| i.count).sum();
+                    lines.push(self.catalog.format("digest.incidents", &[("count", &total.to_string())]));
+                    for incident in incidents {
+                        lines.push(format!("- {} / {}: {}", incident.rule_id, incident.tool_id, incident.count));
+                    }
+                }
+                DigestSection::Budgets { departments } => {
+                    lines.push(self.catalog.get("digest.budgets"));
+                    for usage in departments {
+                        lines.push(format!("- {}: {}/{}", usage.department_name, usage.used, usage.budget));
+                    }
+                }
+                DigestSection::GroupActivity { groups } => {
+                    lines.push(self.catalog.get("digest.group_activity"));
+                    for group in groups {
+                        let senders: Vec<String> =
+                            group.senders.iter().map(|s| format!("{} ({})", s.sender, s.count)).collect();
+                        lines.push(format!("- {}: {}", group.group_name, senders.join(", ")));
+                        if !group.quiet.is_empty() {
+                            lines.push(format!(
+                                "  {}",
+                                self.catalog.format("digest.group_quiet", &[("members", &group.quiet.join(", "))])
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+        if !digest.omitted.is_empty() {
+            lines.push(String::new());
+            for omitted in &digest.omitted {
+                lines.push(self.catalog.format(
+                    "digest.omitted",
+                    &[("section", &omitted.section), ("reason", &omitted.reason)],
+                ));
+            }
+        }
+        (subject, lines.join("\n"))
+    }
+
+    /// 发给所有接收方，单个接收方失败只记录日志
+    pub async fn deliver(&self, digest: &Digest) {
+        let recipients = &self.config.recipients;
+        let (subject, body) = self.render(digest);
+
+        if let Some(group_id) = &recipients.group {
+            match &self.message_bus {
+                Some(bus) => {
+                    let message = Message::group(DIGEST_SENDER, group_id.clone(), body.clone())
+                        .with_metadata("kind", "digest")
+                        .with_metadata(NO_ESCALATION_KEY, "true");
+                    if let Err(e) = bus.send(message).await {
+                        warn!("Failed to post digest {} to group {}: {}", digest.id, group_id, e);
+                    }
+                }
+                None => warn!("Digest group {} configured but no message bus attached", group_id),
+            }
+        }
+
+        if !recipients.users.is_empty() {
+            match &self.email {
+                Some(email) => {
+                    for username in &recipients.users {
+                        if let Err(e) = self.email_user(email, username, &subject, &body).await {
+                            warn!("Failed to email digest {} to {}: {}", digest.id, username, e);
+                        }
+                    }
+                }
+                None => warn!("Digest recipients configured but email is not enabled"),
+            }
+        }
+
+        if let Some(url) = &recipients.webhook {
+            let result = self
+                .retrier
+                .run(|| async {
+                    let response = self.http.post(url).json(digest).send().await?;
+                    Ok(response.error_for_status()?)
+                })
+                .await;
+            if let Err(e) = result {
+                warn!("Failed to post digest {} to webhook: {}", digest.id, e);
+            }
+        }
+    }
+
+    async fn email_user(&self, email: &EmailNotifier, username: &str, subject: &str, body: &str) -> Result<()> {
+        let user = self
+            .store
+            .load_user_by_username(username)
+            .await?
+            .ok_or_else(|| anyhow!("user not found"))?;
+        let address = user.email.ok_or_else(|| anyhow!("user has no email address"))?;
+        email.deliver("digest", &EmailMessage::new(address, subject, body)).await?;
+        Ok(())
+    }
+
+    /// 监听 Watchdog 触发，并按 `check_secs` 检查是否到期
+    pub fn spawn(self: Arc<Self>, events: &EventBus) -> JoinHandle<()> {
+        events.register_listener(Box::new(DigestListener(self.clone())));
+        let interval = Duration::from_secs(self.config.check_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_due().await {
+                    warn!("Digest generation failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// webhook 只重试连接失败、超时、429 和 5xx，其他 4xx 重试也不会成功
+fn is_retryable_webhook_error(error: &anyhow::Error) -> bool {
+    match error.downcast_ref::<reqwest::Error>() {
+        Some(e) => match e.status() {
+            Some(status) => status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+            None => true,
+        },
+        None => true,
+    }
+}
+
+/// 加入生成成功的部分，失败的记入省略列表
+fn add_section(digest: &mut Digest, name: &str, section: Result<DigestSection>) {
+    match section {
+        Ok(section) => digest.sections.push(section),
+        Err(e) => {
+            warn!("Digest section {} omitted: {}", name, e);
+            digest.omitted.push(OmittedSection {
+                section: name.to_string(),
+                reason: e.to_string(),
+            });
+        }
+    }
+}
+
+struct DigestListener(Arc<DigestGenerator>);
+
+#[async_trait]
+impl CompanyEventListener for DigestListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        self.0.record(event);
+    }
+}
@@ -2,24 +2,88 @@
 //!
 //! 框架主入口：VirtualCompany
 
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
 use anyhow::Result;
+use axum::Router;
 use tokio::sync::{broadcast, RwLock};
-use tracing::info;
+use tracing::{info, warn};
 
+use crate::core::clock::{Clock, SystemClock};
 use crate::core::config::CompanyConfig;
-use crate::core::messaging::MessageBus;
+use crate::core::escalation::EscalationChecker;
+use crate::core::tasks::TaskManager;
+use crate::core::archive::MessageArchiver;
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::features::{Feature, FeatureFlags, FeatureSource};
+use crate::core::group_sync::GroupSyncReconciler;
+use crate::core::i18n::MessageCatalog;
+use crate::core::message_limits::MessageLimits;
+use crate::core::integrity::{IntegrityReport, Repair, StoreIntegrityChecker};
+use crate::core::budget::DepartmentBudgets;
+use crate::core::loop_guard::LoopGuard;
+use crate::core::secrets::{print_extracted_secrets, secret_for_redaction};
+use crate::core::send_dedup::SendDedup;
+use crate::core::circuit_breaker::CircuitBreakers;
+use crate::core::turn_taking::TurnCoordinator;
+use crate::core::translation::{LlmTranslator, TranslationService};
+use crate::core::agent_draft::AgentInterviewer;
+use crate::core::messaging::{MessageBus, ReactionEvent};
+use crate::core::moderation::ModerationService;
+use crate::core::org_changes::{save_organization_tracked, SYSTEM_ACTOR};
+use crate::core::proactive::ProactiveDispatcher;
+use crate::core::retry::{Retries, RetrySubsystem};
+use crate::core::runtime_info::{ConfigSource, RuntimeInfo};
+use crate::core::scheduler::TurnScheduler;
+use crate::core::skill::SkillManager;
 use crate::core::store::Store;
-use crate::domain::{Message, Organization};
+use crate::core::scratchpad::Scratchpad;
+use crate::core::temp_agents::{TempAgentRunner, TemporaryAgents};
+use crate::core::mentions::MentionIndex;
+use crate::core::org_tree::OrgTreeProjection;
+use crate::core::agent_batch::{AgentBatch, AgentBatchReport};
+use crate::core::goals::GoalBoard;
+use crate::core::chat_sessions::ChatSessions;
+use crate::core::group_stats::GroupStatsTracker;
+use crate::core::join_context::JoinContext;
+use crate::core::nudge::NudgeSlots;
+use crate::core::leadership::{LeaderElection, LeadershipError};
+use crate::core::tokenizer::tokenizer_for;
+use crate::core::tool_concurrency::ToolConcurrency;
+use crate::core::tool_stats::ToolStats;
+use crate::core::tool_view::AgentToolView;
+use crate::domain::{LLMConfig, Message, Organization, ToolProgressEvent};
+use crate::infrastructure::auth::PermissionConfig;
+use crate::infrastructure::blob::BlobStore;
+use crate::infrastructure::email::{EmailNotifier, EmailSender};
+use crate::infrastructure::logger::PromptLog;
+use crate::infrastructure::sandbox::CodeSandbox;
 use crate::infrastructure::store::SqliteStore;
+use crate::infrastructure::web::{create_api_router, jwt_service_from_env, AppState, RouterOptions};
 
 use super::company_runtime::{OrganizationManager, AgentManager, ToolCapabilityManager};
+use super::digest::{DigestGenerator, LlmSummarizer};
 
 // 导入缺失的类型
 use crate::{ToolRegistry, ToolEnvironment, FrameworkToolExecutor, CapabilityRegistry, McpServer, McpProtocolHandler};
 
+/// 未回复消息升级的检查间隔
+const ESCALATION_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 过期临时群聊的清理间隔
+const GROUP_SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// 临时 Agent 过期的检查间隔
+const TEMP_AGENT_SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 任务截止时间的检查间隔
+const TASK_DUE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// 工具调用统计写回存储的间隔
+const TOOL_STATS_PERSIST_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
 // 默认数据库路径现在由 AppConfig 管理
 // pub const DEFAULT_DB_PATH: &str = "imitatort.db"; // 已移除硬编码
 
@@ -32,7 +96,39 @@ pub struct VirtualCompany {
     tool_capability_manager: ToolCapabilityManager,
     message_bus: Arc<MessageBus>,
     message_tx: broadcast::Sender<Message>,
+    tool_progress: broadcast::Sender<ToolProgressEvent>,
     store: Arc<dyn Store>,
+    templates: Arc<RwLock<HashMap<String, String>>>,
+    events: Arc<EventBus>,
+    scheduler: Arc<TurnScheduler>,
+    loop_guard: Arc<LoopGuard>,
+    send_dedup: Arc<SendDedup>,
+    breakers: Arc<CircuitBreakers>,
+    turn_coordinator: Arc<TurnCoordinator>,
+    proactive: Arc<ProactiveDispatcher>,
+    budgets: Arc<DepartmentBudgets>,
+    email: Option<Arc<EmailNotifier>>,
+    code_sandbox: Option<Arc<CodeSandbox>>,
+    translator: Option<Arc<TranslationService>>,
+    moderation: Option<Arc<ModerationService>>,
+    agent_interviewer: Option<Arc<AgentInterviewer>>,
+    temp_agents: Arc<TemporaryAgents>,
+    scratchpad: Option<Arc<Scratchpad>>,
+    goals: Option<Arc<GoalBoard>>,
+    chat_sessions: Option<Arc<ChatSessions>>,
+    group_stats: Option<Arc<GroupStatsTracker>>,
+    org_tree: Arc<OrgTreeProjection>,
+    mentions: Arc<MentionIndex>,
+    nudges: Arc<NudgeSlots>,
+    features: Arc<FeatureFlags>,
+    leadership: Option<Arc<LeaderElection>>,
+    config_source: Option<ConfigSource>,
+    data_dir: Option<PathBuf>,
+    blob_store: Option<Arc<dyn BlobStore>>,
+    prompt_log: Option<Arc<PromptLog>>,
+    retries: Arc<Retries>,
+    message_limits: MessageLimits,
+    tool_view: OnceLock<Arc<AgentToolView>>,
 }
 
 impl VirtualCompany {
@@ -49,12 +145,176 @@ impl VirtualCompany {
 
     /// 从配置创建虚拟公司，使用指定的存储
     pub fn with_store(config: CompanyConfig, store: Arc<dyn Store>) -> Self {
-        let message_bus = Arc::new(MessageBus::with_store(store.clone()));
+        Self::with_store_and_clock(config, store, Arc::new(SystemClock))
+    }
+
+    /// 从配置创建虚拟公司，使用指定的存储和时钟（测试中传入 ManualClock）
+    pub fn with_store_and_clock(config: CompanyConfig, store: Arc<dyn Store>, clock: Arc<dyn Clock>) -> Self {
+        let events = Arc::new(EventBus::new());
+        let mut message_bus = MessageBus::with_store(store.clone())
+            .with_clock(clock)
+            .with_events(events.clone())
+            .with_urgent_rate_limit(config.urgent_rate_limit)
+            .with_catalog(config.catalog());
+        // 前情提要的总结使用组织中第一个 Agent 的模型
+        if config.join_context.enabled {
+            let join_context = JoinContext::new(config.join_context.clone(), store.clone());
+            let join_context = match config.organization.agents.first() {
+                Some(agent) => join_context
+                    .with_tokenizer(tokenizer_for(&agent.llm_config))
+                    .with_summarizer(Arc::new(LlmSummarizer::new(&agent.llm_config))),
+                None => join_context,
+            };
+            message_bus = message_bus.with_join_context(Arc::new(join_context));
+        }
+        let message_bus = Arc::new(message_bus);
         let (message_tx, _) = broadcast::channel(1000);
+        let templates = Arc::new(RwLock::new(config.templates.clone()));
+        let reactions_in_context = config.reactions_in_context;
+        let snapshot = config.snapshot;
+        let response_style = config.response_style();
+        let scheduler = Arc::new(TurnScheduler::new(config.scheduler.clone()));
+        let outbox_policy = config.outbox_policy;
+        let loop_guard = Arc::new(
+            LoopGuard::new(config.loop_guard.clone())
+                .with_languages(config.language, config.agent_languages.clone()),
+        );
+        let send_dedup = Arc::new(SendDedup::new(config.send_dedup.clone()).with_clock(message_bus.clock()));
+        let mentions = Arc::new(MentionIndex::new(config.mentions.clone()));
+
+        let breakers = Arc::new(CircuitBreakers::new(config.circuit_breaker.clone()));
+        let tool_concurrency = Arc::new(ToolConcurrency::new(config.tool_concurrency.clone()));
+        let budgets = Arc::new(DepartmentBudgets::new());
+        let code_sandbox = if config.code_sandbox.enabled {
+            match CodeSandbox::new(config.code_sandbox.clone()) {
+                Ok(sandbox) => Some(Arc::new(sandbox)),
+                Err(e) => {
+                    warn!("Code sandbox disabled: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
 
+        // 运行时状态：沙箱可能创建失败，邮件、附件和提示词日志由嵌入方稍后设置
+        let features = Arc::new(FeatureFlags::from_config(&config).with_events(events.clone()));
+        features.register(
+            Feature::new("code_sandbox", code_sandbox.is_some(), FeatureSource::Runtime).with_toggleable(code_sandbox.is_some()),
+        );
+        for name in ["email", "attachments", "prompt_log"] {
+            features.register(Feature::new(name, false, FeatureSource::Runtime));
+        }
+
+        let tool_capability_manager = ToolCapabilityManager::new().with_tool_concurrency(tool_concurrency);
+        if let Err(e) = tool_capability_manager
+            .tool_registry()
+            .apply_deprecation_config(&config.tool_deprecation)
+        {
+            warn!("Invalid tool alias configuration: {}", e);
+        }
+        let translator = if config.translation.enabled {
+            let llm = config
+                .translation
+                .llm
+                .clone()
+                .or_else(|| config.organization.agents.first().map(|a| a.llm_config.clone()));
+            match llm {
+                Some(llm) => Some(Arc::new(TranslationService::new(Arc::new(LlmTranslator::new(&llm)), store.clone()))),
+                None => {
+                    warn!("Message translation disabled: no LLM configured");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let moderation = config.moderation.is_active().then(|| {
+            Arc::new(
+                ModerationService::new(config.moderation.clone(), store.clone())
+                    .with_clock(message_bus.clock())
+                    .with_catalog(config.catalog()),
+            )
+        });
+        // 面试式创建 Agent 使用组织中第一个 Agent 的模型
+        let agent_interviewer = config
+            .organization
+            .agents
+            .first()
+            .map(|a| Arc::new(AgentInterviewer::new(store.clone(), message_bus.clone(), &a.llm_config)));
+        let temp_agents = Arc::new(
+            TemporaryAgents::new(store.clone(), message_bus.clone(), config.temp_agents.clone())
+                .with_clock(message_bus.clock()),
+        );
+        // 草稿写入前替换各 Agent 的 API Key
+        let scratchpad = config.scratchpad.enabled.then(|| {
+            Arc::new(
+                Scratchpad::new(config.scratchpad.clone(), store.clone())
+                    .with_clock(message_bus.clock())
+                    .with_secrets(config.organization.agents.iter().map(|a| secret_for_redaction(&a.llm_config.api_key))),
+            )
+        });
+        let goals = config
+            .goals
+            .enabled
+            .then(|| Arc::new(GoalBoard::new(config.goals.clone()).with_clock(message_bus.clock())));
+        // 会话总结使用组织中第一个 Agent 的模型
+        let chat_sessions = config.chat_sessions.enabled.then(|| {
+            let sessions = ChatSessions::new(config.chat_sessions.clone(), store.clone()).with_clock(message_bus.clock());
+            let sessions = match config.organization.agents.first() {
+                Some(agent) => sessions
+                    .with_tokenizer(tokenizer_for(&agent.llm_config))
+                    .with_summarizer(Arc::new(LlmSummarizer::new(&agent.llm_config))),
+                None => sessions,
+            };
+            Arc::new(sessions)
+        });
+        let group_stats = config.group_stats.enabled.then(|| {
+            Arc::new(GroupStatsTracker::new(config.group_stats.clone(), store.clone()).with_clock(message_bus.clock()))
+        });
+        // 启用主备时只有持有租约的节点运行 Agent
+        let leadership = config.leadership.enabled.then(|| {
+            Arc::new(LeaderElection::new(store.clone(), &config.leadership).with_clock(message_bus.clock()))
+        });
+        let proactive = Arc::new(
+            ProactiveDispatcher::new(config.proactive.clone(), store.clone())
+                .with_message_bus(message_bus.clone())
+                .with_clock(message_bus.clock()),
+        );
         let organization_manager = OrganizationManager::new(config);
-        let tool_capability_manager = ToolCapabilityManager::new();
-        let agent_manager = AgentManager::new(message_bus.clone());
+        let turn_coordinator = Arc::new(TurnCoordinator::new(message_bus.clone(), organization_manager.organization_arc()));
+        let nudges = Arc::new(NudgeSlots::new());
+        let retries = Arc::new(Retries::default());
+        let agent_manager = AgentManager::new(message_bus.clone())
+            .with_events(events.clone())
+            .with_reactions_in_context(reactions_in_context)
+            .with_snapshot_config(snapshot)
+            .with_response_style(response_style)
+            .with_scheduler(scheduler.clone())
+            .with_outbox_policy(outbox_policy)
+            .with_loop_guard(loop_guard.clone())
+            .with_send_dedup(send_dedup.clone())
+            .with_circuit_breakers(breakers.clone())
+            .with_turn_coordinator(turn_coordinator.clone())
+            .with_nudges(nudges.clone())
+            .with_budgets(budgets.clone())
+            .with_retries(retries.clone());
+        let agent_manager = match &translator {
+            Some(translator) => agent_manager.with_translator(translator.clone()),
+            None => agent_manager,
+        };
+        let agent_manager = match &moderation {
+            Some(moderation) => agent_manager.with_moderation(moderation.clone()),
+            None => agent_manager,
+        };
+        let agent_manager = match &scratchpad {
+            Some(scratchpad) => agent_manager.with_scratchpad(scratchpad.clone()),
+            None => agent_manager,
+        };
+        let agent_manager = match &goals {
+            Some(goals) => agent_manager.with_goals(goals.clone()),
+            None => agent_manager,
+        };
 
         Self {
             organization_manager,
@@ -62,10 +322,214 @@ impl VirtualCompany {
             tool_capability_manager,
             message_bus,
             message_tx,
+            tool_progress: broadcast::channel(256).0,
             store,
+            templates,
+            events,
+            scheduler,
+            loop_guard,
+            send_dedup,
+            breakers,
+            turn_coordinator,
+            proactive,
+            budgets,
+            email: None,
+            code_sandbox,
+            translator,
+            moderation,
+            agent_interviewer,
+            temp_agents,
+            scratchpad,
+            goals,
+            chat_sessions,
+            group_stats,
+            org_tree: Arc::new(OrgTreeProjection::new()),
+            mentions,
+            nudges,
+            features,
+            leadership,
+            config_source: None,
+            data_dir: None,
+            blob_store: None,
+            prompt_log: None,
+            retries,
+            message_limits: MessageLimits::default(),
+            tool_view: OnceLock::new(),
         }
     }
 
+    /// 启用邮件通知：Agent 可以使用 `notify.email`，升级策略可以同时发邮件
+    pub fn with_email_sender(mut self, sender: Arc<dyn EmailSender>) -> Self {
+        let policy = self.organization_manager.config().email.clone();
+        let retrier = self.retries.retrier(RetrySubsystem::Email, "email.deliver");
+        self.email = Some(Arc::new(EmailNotifier::new(sender, policy).with_retrier(retrier)));
+        self.features
+            .register(Feature::new("email", true, FeatureSource::Runtime).with_toggleable(true));
+        self
+    }
+
+    /// 邮件通知器，未配置时为 None
+    pub fn email_notifier(&self) -> Option<Arc<EmailNotifier>> {
+        self.email.clone()
+    }
+
+    /// 消息翻译服务，未启用时为 None
+    pub fn translator(&self) -> Option<Arc<TranslationService>> {
+        self.translator.clone()
+    }
+
+    /// 输出内容审核，未启用时为 None
+    pub fn moderation(&self) -> Option<Arc<ModerationService>> {
+        self.moderation.clone()
+    }
+
+    /// HR 面试官，组织中没有 Agent（没有可用的模型）时为 None
+    pub fn agent_interviewer(&self) -> Option<Arc<AgentInterviewer>> {
+        self.agent_interviewer.clone()
+    }
+
+    /// 新建 Agent 的默认 LLM 配置：与 HR 面试官一样使用组织中第一个 Agent 的模型，组织为空时为 None
+    pub fn default_llm(&self) -> Option<LLMConfig> {
+        self.organization_manager
+            .config()
+            .organization
+            .agents
+            .first()
+            .map(|a| a.llm_config.clone())
+    }
+
+    /// 启动和停止 Agent 自主循环的运行器，与临时 Agent 共用
+    pub fn agent_runner(&self) -> Arc<dyn TempAgentRunner> {
+        Arc::new(self.agent_manager.clone())
+    }
+
+    /// 会话内临时 Agent 管理
+    pub fn temp_agents(&self) -> Arc<TemporaryAgents> {
+        self.temp_agents.clone()
+    }
+
+    /// Agent 运行草稿，未启用时为 None
+    pub fn scratchpad(&self) -> Option<Arc<Scratchpad>> {
+        self.scratchpad.clone()
+    }
+
+    /// 各 Agent 的当前目标，未启用时为 None
+    pub fn goals(&self) -> Option<Arc<GoalBoard>> {
+        self.goals.clone()
+    }
+
+    /// 会话生命周期管理，未启用时为 None
+    pub fn chat_sessions(&self) -> Option<Arc<ChatSessions>> {
+        self.chat_sessions.clone()
+    }
+
+    /// 群聊活动统计，未启用时为 None
+    pub fn group_stats(&self) -> Option<Arc<GroupStatsTracker>> {
+        self.group_stats.clone()
+    }
+
+    /// Web 界面使用的部门树缓存，组织架构变更时失效
+    pub fn org_tree(&self) -> Arc<OrgTreeProjection> {
+        self.org_tree.clone()
+    }
+
+    /// Web 界面 @提及补全的候选索引，组织架构或用户变更时失效
+    pub fn mentions(&self) -> Arc<MentionIndex> {
+        self.mentions.clone()
+    }
+
+    /// 各 Agent 的插话槽，`/agents/{id}/nudge` 写入
+    pub fn nudges(&self) -> Arc<NudgeSlots> {
+        self.nudges.clone()
+    }
+
+    /// 功能开关：编译特性、配置开关和运行时状态，关闭的功能所属的工具不会提供给 Agent
+    pub fn features(&self) -> Arc<FeatureFlags> {
+        self.features.clone()
+    }
+
+    /// 主备部署的领导权，未启用主备时为 None
+    pub fn leadership(&self) -> Option<Arc<LeaderElection>> {
+        self.leadership.clone()
+    }
+
+    /// 记录加载的配置文件，`runtime_info` 报告其路径和哈希
+    pub fn with_config_source(mut self, source: ConfigSource) -> Self {
+        self.config_source = Some(source);
+        self
+    }
+
+    /// 记录数据目录，`runtime_info` 报告该目录
+    pub fn with_data_dir(mut self, data_dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(data_dir.into());
+        self
+    }
+
+    /// 使用大对象存储保存附件，Web 层的 `/attachments` 接口随之可用
+    pub fn with_blob_store(mut self, blob_store: Arc<dyn BlobStore>) -> Self {
+        self.blob_store = Some(blob_store);
+        self.features.register(Feature::new("attachments", true, FeatureSource::Runtime));
+        self
+    }
+
+    /// 把 Agent 的每次 LLM 请求和响应写入提示词日志，Web 层可在运行中调整脱敏级别
+    ///
+    /// 需在 Agent 初始化（[`Self::run`]）之前设置。
+    pub fn with_prompt_log(mut self, prompt_log: Arc<PromptLog>) -> Self {
+        self.agent_manager = self.agent_manager.with_prompt_log(prompt_log.clone());
+        self.prompt_log = Some(prompt_log);
+        self.features.register(Feature::new("prompt_log", true, FeatureSource::Runtime));
+        self
+    }
+
+    /// 设置各子系统的重试策略；Agent 的 LLM 请求、邮件和 webhook 共用注册表中的预算和指标
+    ///
+    /// 需在 [`Self::with_email_sender`] 和 Agent 初始化（[`Self::run`]）之前设置。
+    pub fn with_retries(mut self, retries: Arc<Retries>) -> Self {
+        self.agent_manager = self.agent_manager.with_retries(retries.clone());
+        self.retries = retries;
+        self
+    }
+
+    /// 重试注册表（各调用点的重试指标）
+    pub fn retries(&self) -> Arc<Retries> {
+        self.retries.clone()
+    }
+
+    /// 设置发送消息的大小限制，Web 接口和 `message.send_*` 工具共用
+    pub fn with_message_limits(mut self, message_limits: MessageLimits) -> Self {
+        self.message_limits = message_limits;
+        self
+    }
+
+    /// 发送消息的大小限制
+    pub fn message_limits(&self) -> &MessageLimits {
+        &self.message_limits
+    }
+
+    /// 提示词日志，未启用时为 None
+    pub fn prompt_log(&self) -> Option<Arc<PromptLog>> {
+        self.prompt_log.clone()
+    }
+
+    /// 大对象存储，未配置时为 None
+    pub fn blob_store(&self) -> Option<Arc<dyn BlobStore>> {
+        self.blob_store.clone()
+    }
+
+    /// 版本、构建、存储和配置信息，与 `GET /api/info` 相同
+    pub async fn runtime_info(&self) -> RuntimeInfo {
+        let agent_count = self.organization_manager.organization().await.agents.len();
+        self.runtime_info_with(agent_count)
+    }
+
+    fn runtime_info_with(&self, agent_count: usize) -> RuntimeInfo {
+        RuntimeInfo::new(self.name(), agent_count, self.store.backend_info())
+            .with_data_dir(self.data_dir.clone())
+            .with_config(self.config_source.clone())
+            .with_flags(&self.features)
+    }
+
     /// 从SQLite存储加载虚拟公司
     pub async fn from_sqlite<P: AsRef<Path>>(db_path: P) -> Result<Self> {
         let store = Arc::new(SqliteStore::new(db_path)?);
@@ -83,10 +547,7 @@ impl VirtualCompany {
             ));
         }
 
-        let config = CompanyConfig {
-            name: "Loaded Company".to_string(),
-            organization: org,
-        };
+        let config = CompanyConfig::new("Loaded Company", org);
 
         Ok(Self::with_store(config, store))
     }
@@ -95,7 +556,22 @@ impl VirtualCompany {
     pub async fn save(&self) -> Result<()> {
         info!("Saving company state to storage...");
         let org = self.organization_manager.organization().await;
-        self.store.save_organization(&*org).await?;
+        // 已经失去租约的旧领导者不能覆盖新领导者的组织架构
+        if let Some(leadership) = &self.leadership {
+            leadership.check_fence().await?;
+        }
+        if let Some(entry) = save_organization_tracked(self.store.as_ref(), &org, SYSTEM_ACTOR).await? {
+            // 直接失效，不依赖事件监听是否已启动
+            self.org_tree.invalidate();
+            self.mentions.invalidate();
+            self.events.emit(CompanyEvent::OrgChanged { entry: Arc::new(entry) });
+        }
+        // 存储换成引用的明文 API Key 只在这里展示一次
+        let extracted = self.store.take_extracted_api_keys();
+        if !extracted.is_empty() {
+            warn!("{} plaintext API key(s) were stored as env references, printed to console", extracted.len());
+            print_extracted_secrets(&self.catalog(), &extracted);
+        }
         info!("Company state saved successfully");
         Ok(())
     }
@@ -104,24 +580,196 @@ impl VirtualCompany {
     pub async fn run(&self) -> Result<()> {
         info!("Starting virtual company: {}", self.organization_manager.config().name);
 
+        // 启用主备时作为备用节点等待，取得租约后才启动
+        if let Some(leadership) = &self.leadership {
+            info!("Node {} waiting for leadership", leadership.node_id());
+            leadership.wait_for_leadership().await;
+            leadership.clone().spawn();
+        }
+
+        // 0. 检查持久化数据，严格模式下有严重问题时拒绝启动
+        let integrity = self.organization_manager.config().integrity;
+        let report = StoreIntegrityChecker::new(self.store.clone())
+            .run_startup(integrity, SYSTEM_ACTOR)
+            .await?;
+        if report.is_some_and(|r| r.findings.iter().any(|f| f.repaired && f.repair == Some(Repair::ClearReference))) {
+            // 修复改写了存储中的组织架构，运行时使用修复后的版本
+            let repaired = self.store.load_organization().await?;
+            *self.organization_manager.organization_arc().write().await = repaired;
+        }
+
         // 1. 初始化所有Agent
         let org = self.organization_manager.organization().await;
+        self.budgets.sync(&org);
         self.agent_manager.initialize_agents(&*org).await?;
         drop(org); // 释放读锁
+        self.agent_manager
+            .attach_tools(self.tool_view(), Arc::new(self.get_framework_tool_executor()));
 
         info!("All {} agents initialized", self.agent_manager.get_agents().await?.len());
 
+        // 恢复当日工具调用统计，之后定期写回
+        let tool_stats = self.tool_stats();
+        if let Err(e) = tool_stats.load_daily(self.store.as_ref()).await {
+            warn!("Failed to load daily tool stats: {}", e);
+        }
+        tool_stats.spawn(self.store.clone(), TOOL_STATS_PERSIST_INTERVAL);
+
         // 2. 启动所有Agent的自主循环
         let handles = self.agent_manager.start_agent_loops().await?;
 
+        // 启动后才能创建临时 Agent，重启前仍有效的临时 Agent 随之恢复
+        if self.temp_agents.config().enabled {
+            if let Err(e) = self.temp_agents.attach_runner(self.agent_runner()).await {
+                warn!("Failed to restore temporary agents: {}", e);
+            }
+            self.temp_agents.clone().spawn_sweeper(TEMP_AGENT_SWEEP_INTERVAL);
+        }
+
+        // 配置了升级策略时启动周期检查
+        if !self.organization_manager.config().escalation.is_empty() {
+            self.escalation_checker().spawn(ESCALATION_CHECK_INTERVAL);
+        }
+        self.message_bus.clone().spawn_group_sweeper(GROUP_SWEEP_INTERVAL);
+        self.task_manager().spawn(TASK_DUE_CHECK_INTERVAL);
+        self.proactive.clone().spawn(&self.events);
+        // 同步群聊跟随组织架构调整成员
+        let group_sync = Arc::new(self.group_sync());
+        if let Err(e) = group_sync.reconcile_all().await {
+            warn!("Initial group membership sync failed: {}", e);
+        }
+        group_sync.spawn(&self.events);
+        self.org_tree.clone().spawn(&self.events);
+        self.mentions.clone().spawn(&self.events);
+        self.tool_view().spawn(&self.events);
+        if let Some(interviewer) = &self.agent_interviewer {
+            interviewer.clone().spawn();
+        }
+        // 配置了活动摘要时定期生成
+        if self.organization_manager.config().digest.enabled {
+            Arc::new(self.digest_generator()).spawn(&self.events);
+        }
+        // 启用会话生命周期时记录会话并关闭闲置会话
+        if let Some(chat_sessions) = &self.chat_sessions {
+            chat_sessions.clone().spawn(&self.events);
+        }
+        // 启用群聊活动统计时随消息落库增量更新
+        if let Some(group_stats) = &self.group_stats {
+            group_stats.clone().spawn(&self.events);
+        }
+        // 配置了保留期时把旧消息移入归档
+        if self.organization_manager.config().archive.is_enabled() {
+            self.message_archiver().spawn();
+        }
+
         info!("All agents started, company is running...");
 
-        // 3. 等待所有Agent（实际上不会结束）
-        for handle in handles {
-            let _ = handle.await;
+        // 3. 等待所有Agent（实际上不会结束）；失去领导权时立即停止 Agent 循环
+        let Some(leadership) = &self.leadership else {
+            for handle in handles {
+                let _ = handle.await;
+            }
+            return Ok(());
+        };
+        let aborts: Vec<_> = handles.iter().map(|handle| handle.abort_handle()).collect();
+        let mut leading = leadership.subscribe();
+        tokio::select! {
+            _ = futures_util::future::join_all(handles) => Ok(()),
+            _ = leading.wait_for(|leading| !*leading) => {
+                warn!("Node {} lost leadership, stopping agents", leadership.node_id());
+                for abort in aborts {
+                    abort.abort();
+                }
+                Err(LeadershipError::Lost { node_id: leadership.node_id().to_string() }.into())
+            }
         }
+    }
 
-        Ok(())
+    /// 检查持久化数据完整性；`repair` 为 true 时执行安全修复并记入审计日志
+    pub async fn check_integrity(&self, repair: bool, actor: &str) -> Result<IntegrityReport> {
+        let checker = StoreIntegrityChecker::new(self.store.clone());
+        if repair {
+            checker.repair(actor).await
+        } else {
+            checker.check().await
+        }
+    }
+
+    /// 创建群成员与部门的同步器
+    pub fn group_sync(&self) -> GroupSyncReconciler {
+        GroupSyncReconciler::new(self.store.clone(), self.message_bus.clone())
+    }
+
+    /// 创建未回复消息升级检查器
+    pub fn escalation_checker(&self) -> EscalationChecker {
+        let checker = EscalationChecker::new(
+            self.organization_manager.config().clone(),
+            self.organization_arc(),
+            self.message_bus.clone(),
+            self.store.clone(),
+        );
+        match &self.email {
+            Some(email) => checker.with_email(email.clone()),
+            None => checker,
+        }
+    }
+
+    /// 创建任务管理器，通知经消息总线投递
+    pub fn task_manager(&self) -> TaskManager {
+        TaskManager::new(self.store.clone()).with_message_bus(self.message_bus.clone())
+    }
+
+    /// 创建活动摘要生成器；未配置总结模型时使用组织中第一个 Agent 的模型
+    pub fn digest_generator(&self) -> DigestGenerator {
+        let config = self.organization_manager.config();
+        let mut generator = DigestGenerator::new(config.digest.clone(), self.store.clone())
+            .with_catalog(config.catalog())
+            .with_message_bus(self.message_bus.clone())
+            .with_budgets(self.budgets.clone())
+            .with_retrier(self.retries.retrier(RetrySubsystem::Webhook, "digest.webhook"));
+        let llm = config
+            .digest
+            .llm
+            .clone()
+            .or_else(|| config.organization.agents.first().map(|a| a.llm_config.clone()));
+        if let Some(llm) = llm {
+            generator = generator
+                .with_tokenizer(tokenizer_for(&llm))
+                .with_summarizer(Arc::new(LlmSummarizer::new(&llm)));
+        }
+        if let Some(group_stats) = &self.group_stats {
+            generator = generator.with_group_stats(group_stats.clone());
+        }
+        match &self.email {
+            Some(email) => generator.with_email(email.clone()),
+            None => generator,
+        }
+    }
+
+    /// 主动问候分发器，可用于持久化 Agent 的策略
+    pub fn proactive(&self) -> Arc<ProactiveDispatcher> {
+        self.proactive.clone()
+    }
+
+    /// 创建消息归档任务，使用公司配置的保留期
+    pub fn message_archiver(&self) -> MessageArchiver {
+        MessageArchiver::new(self.store.clone(), self.organization_manager.config().archive.clone())
+            .with_clock(self.message_bus.clock())
+    }
+
+    /// 订阅生命周期事件
+    pub fn events(&self) -> broadcast::Receiver<CompanyEvent> {
+        self.events.subscribe()
+    }
+
+    /// 注册生命周期事件监听器，监听器在独立任务中按顺序处理事件
+    pub fn register_event_listener(&self, listener: Box<dyn CompanyEventListener>) {
+        self.events.register_listener(listener);
+    }
+
+    /// 共享的事件总线，用于接入自行创建的 ToolExecutorRegistry / WatchdogFramework
+    pub fn event_bus(&self) -> Arc<EventBus> {
+        self.events.clone()
     }
 
     /// 获取消息流（用于外部监听）
@@ -165,6 +813,21 @@ impl VirtualCompany {
         self.tool_capability_manager.tool_registry()
     }
 
+    /// 获取工具统计收集器
+    pub fn tool_stats(&self) -> Arc<ToolStats> {
+        self.tool_capability_manager.tool_stats()
+    }
+
+    /// 把当日工具调用统计写回存储，停机前调用
+    pub async fn persist_tool_stats(&self) -> Result<()> {
+        self.tool_stats().persist_daily(self.store.as_ref()).await
+    }
+
+    /// 获取工具并发控制器，用于接入自行创建的 ToolExecutorRegistry
+    pub fn tool_concurrency(&self) -> Arc<ToolConcurrency> {
+        self.tool_capability_manager.tool_concurrency()
+    }
+
     /// 注册应用自定义工具
     pub async fn register_app_tool(&self, tool: crate::domain::tool::Tool) -> Result<()> {
         self.tool_capability_manager.register_app_tool(tool).await
@@ -172,20 +835,147 @@ impl VirtualCompany {
 
     /// 创建工具执行环境
     pub fn create_tool_environment(&self) -> ToolEnvironment {
-        self.tool_capability_manager.create_tool_environment(
+        self.base_tool_environment().with_tool_view(self.tool_view())
+    }
+
+    /// 技能管理器，决定私有工具对哪些 Agent 可见
+    pub fn skill_manager(&self) -> Arc<SkillManager> {
+        self.tool_capability_manager.skill_manager()
+    }
+
+    /// 按 Agent 过滤的工具视图（首次调用时创建，之后共享）
+    pub fn tool_view(&self) -> Arc<AgentToolView> {
+        self.tool_view
+            .get_or_init(|| {
+                let view = AgentToolView::new(self.base_tool_environment().tool_provider, self.skill_manager())
+                    .with_disabled_tools(self.organization_manager.config().disabled_tools.clone())
+                    .with_features(self.features());
+                Arc::new(view)
+            })
+            .clone()
+    }
+
+    fn base_tool_environment(&self) -> ToolEnvironment {
+        let env = self.tool_capability_manager.create_tool_environment(
             self.message_bus.clone(),
             self.organization_manager.organization_arc(),
             self.store.clone(),
         )
+        .with_catalog(self.organization_manager.config().catalog())
+        .with_templates(self.templates.clone())
+        .with_message_limits(self.message_limits.clone());
+        let env = match &self.blob_store {
+            Some(blob_store) => env.with_blob_store(blob_store.clone()),
+            None => env,
+        };
+        let handoff = &self.organization_manager.config().handoff;
+        let env = if handoff.enabled { env.with_handoff(handoff.clone()) } else { env };
+        let env = if self.temp_agents.config().enabled {
+            env.with_temp_agents(self.temp_agents.clone())
+        } else {
+            env
+        };
+        let env = match &self.code_sandbox {
+            Some(sandbox) => env.with_code_sandbox(sandbox.clone()),
+            None => env,
+        };
+        let env = match &self.scratchpad {
+            Some(scratchpad) => env.with_scratchpad(scratchpad.clone()),
+            None => env,
+        };
+        let env = match &self.goals {
+            Some(goals) => env.with_goals(goals.clone()),
+            None => env,
+        };
+        let env = match &self.chat_sessions {
+            Some(chat_sessions) => env.with_chat_sessions(chat_sessions.clone()),
+            None => env,
+        };
+        let env = match &self.group_stats {
+            Some(group_stats) => env.with_group_stats(group_stats.clone()),
+            None => env,
+        };
+        let env = env.with_send_dedup(self.send_dedup.clone());
+        match &self.email {
+            Some(email) => env.with_email(email.clone()),
+            None => env,
+        }
+    }
+
+    /// 当前的公司级消息模板
+    pub async fn templates(&self) -> HashMap<String, String> {
+        self.templates.read().await.clone()
+    }
+
+    /// 消息回应广播发送端，传给 Web 层以推送 Agent 的回应
+    pub fn reaction_sender(&self) -> broadcast::Sender<ReactionEvent> {
+        self.message_bus.reaction_sender()
+    }
+
+    /// 工具进度广播发送端，传给自行创建的 ToolExecutorRegistry 和 Web 层以推送长任务的进度
+    pub fn tool_progress_sender(&self) -> broadcast::Sender<ToolProgressEvent> {
+        self.tool_progress.clone()
+    }
+
+    /// 消息总线
+    pub fn message_bus(&self) -> Arc<MessageBus> {
+        self.message_bus.clone()
+    }
+
+    /// Agent 轮次调度器
+    pub fn scheduler(&self) -> Arc<TurnScheduler> {
+        self.scheduler.clone()
+    }
+
+    /// 共享的循环抑制器
+    pub fn loop_guard(&self) -> Arc<LoopGuard> {
+        self.loop_guard.clone()
+    }
+
+    /// 共享的重复发送缓存
+    pub fn send_dedup(&self) -> Arc<SendDedup> {
+        self.send_dedup.clone()
+    }
+
+    /// Agent 熔断器
+    pub fn circuit_breakers(&self) -> Arc<CircuitBreakers> {
+        self.breakers.clone()
+    }
+
+    /// 群聊轮流发言协调器
+    pub fn turn_coordinator(&self) -> Arc<TurnCoordinator> {
+        self.turn_coordinator.clone()
+    }
+
+    /// 部门 LLM 预算及当天用量
+    pub fn department_budgets(&self) -> Arc<DepartmentBudgets> {
+        self.budgets.clone()
+    }
+
+    /// 共享的公司级消息模板
+    pub fn templates_arc(&self) -> Arc<RwLock<HashMap<String, String>>> {
+        self.templates.clone()
+    }
+
+    /// 替换公司级消息模板，已创建的工具环境立即生效
+    pub async fn reload_templates(&self, templates: HashMap<String, String>) {
+        *self.templates.write().await = templates;
+        info!("Reloaded company message templates");
     }
 
     /// 获取框架工具执行器
     pub fn get_framework_tool_executor(&self) -> FrameworkToolExecutor {
-        self.tool_capability_manager.get_framework_tool_executor(
-            self.message_bus.clone(),
-            self.organization_manager.organization_arc(),
-            self.store.clone(),
-        )
+        FrameworkToolExecutor::new(self.create_tool_environment())
+    }
+
+    /// 获取公司级消息目录
+    pub fn catalog(&self) -> MessageCatalog {
+        self.organization_manager.config().catalog()
+    }
+
+    /// 管理接口的权限配置
+    pub fn permissions(&self) -> PermissionConfig {
+        self.organization_manager.config().permissions.clone()
     }
 
     /// 获取 CapabilityRegistry 引用
@@ -207,6 +997,90 @@ impl VirtualCompany {
     pub fn get_mcp_protocol_handler(&self) -> McpProtocolHandler {
         self.tool_capability_manager.get_mcp_protocol_handler()
     }
+
+    /// 创建 Web 层状态，与公司共享存储、消息总线、模板、回应广播和调度器
+    pub fn web_state(&self, options: &RouterOptions) -> AppState {
+        // 组织架构正被写入时退回到创建时的快照
+        let agents = match self.organization_arc().try_read() {
+            Ok(org) => org.agents.clone(),
+            Err(_) => self.organization_manager.config().organization.agents.clone(),
+        };
+        let jwt_service = options.jwt_service.clone().unwrap_or_else(jwt_service_from_env);
+        let runtime_info = self.runtime_info_with(agents.len());
+
+        let state = AppState::new(agents, self.message_tx.clone(), self.store.clone(), jwt_service)
+            .with_clock(self.message_bus.clock())
+            .with_catalog(options.catalog.unwrap_or_else(|| self.catalog()))
+            .with_legacy_api_routes(options.legacy_api_routes)
+            .with_password_policy(options.password_policy.clone())
+            .with_tool_stats(self.tool_stats())
+            .with_templates(self.templates_arc())
+            .with_reactions(self.reaction_sender())
+            .with_tool_progress(self.tool_progress_sender())
+            .with_scheduler(self.scheduler())
+            .with_circuit_breakers(self.circuit_breakers())
+            .with_message_bus(self.message_bus())
+            .with_health_config(options.health.clone())
+            .with_permissions(self.permissions())
+            .with_message_limits(self.message_limits.clone())
+            .with_temp_agents(self.temp_agents())
+            .with_agent_runner(self.agent_runner())
+            .with_org_tree(self.org_tree())
+            .with_mentions(self.mentions())
+            .with_features(self.features())
+            .with_nudges(self.nudges())
+            .with_runtime_info(runtime_info);
+        let state = match &self.translator {
+            Some(translator) => state.with_translator(translator.clone()),
+            None => state,
+        };
+        let state = match &self.agent_interviewer {
+            Some(interviewer) => state.with_agent_interviewer(interviewer.clone()),
+            None => state,
+        };
+        let state = match self.default_llm() {
+            Some(llm) => state.with_default_llm(llm),
+            None => state,
+        };
+        let state = match &self.prompt_log {
+            Some(prompt_log) => state.with_prompt_log(prompt_log.clone()),
+            None => state,
+        };
+        let state = match &self.leadership {
+            Some(leadership) => state.with_leadership(leadership.clone()),
+            None => state,
+        };
+        let state = match &self.scratchpad {
+            Some(scratchpad) => state.with_scratchpad(scratchpad.clone()),
+            None => state,
+        };
+        let state = match &self.goals {
+            Some(goals) => state.with_goals(goals.clone()),
+            None => state,
+        };
+        let state = match &self.chat_sessions {
+            Some(chat_sessions) => state.with_chat_sessions(chat_sessions.clone()),
+            None => state,
+        };
+        let state = match &self.group_stats {
+            Some(group_stats) => state.with_group_stats(group_stats.clone()),
+            None => state,
+        };
+        let state = state.with_retries(self.retries.clone());
+        match &self.blob_store {
+            Some(blob_store) => state.with_blob_store(blob_store.clone()),
+            None => state,
+        }
+    }
+}
+
+/// 为嵌入到已有 axum 应用创建公司路由
+///
+/// 返回的路由不绑定监听端口，也不套 CORS、请求体上限等部署层，
+/// 宿主应用可以 `.nest("/imitator", router)` 并使用自己的中间件栈。
+pub fn build_company_router(company: &VirtualCompany, options: RouterOptions) -> Router {
+    let state = Arc::new(company.web_state(&options));
+    create_api_router(state, &options.routes)
 }
 
 /// 公司构建器
@@ -252,15 +1126,21 @@ impl CompanyBuilder {
         self
     }
 
+    /// 对配置中的组织架构应用一批 Agent 操作，返回逐项结果（见 [`crate::core::agent_batch`]）
+    pub fn apply_batch(&mut self, batch: &AgentBatch) -> Result<AgentBatchReport> {
+        let config = self
+            .config
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Config not set. Use .config() or .load() first."))?;
+        Ok(batch.apply(&mut config.organization, chrono::Utc::now().timestamp()))
+    }
+
     /// 从存储加载配置
     pub async fn load(mut self) -> Result<Self> {
         if let Some(ref store) = self.store {
             let org = store.load_organization().await?;
             if !org.agents.is_empty() {
-                self.config = Some(CompanyConfig {
-                    name: "Loaded Company".to_string(),
-                    organization: org,
-                });
+                self.config = Some(CompanyConfig::new("Loaded Company", org));
             }
         }
         Ok(self)
@@ -271,6 +1151,8 @@ impl CompanyBuilder {
         let config = self
             .config
             .ok_or_else(|| anyhow::anyhow!("Config not set. Use .config() or .load() first."))?;
+        config.organization.validate_ids()?;
+        config.organization.validate_headcount()?;
 
         let store = self
             .store
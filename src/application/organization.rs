@@ -9,6 +9,8 @@ use crate::domain::{Group, Organization};
 use crate::core::store::Store;
 use crate::domain::user::{User, Position};
 
+pub mod guilty_line;
+
 /// 组织架构管理器
 pub struct OrganizationManager {
     store: Arc<dyn Store>,
@@ -35,6 +37,13 @@ impl OrganizationManager {
             creator_id: String::new(), // Will be set below
             members: Vec::new(),
             created_at: chrono::Utc::now().timestamp(),
+            ephemeral: false,
+            expires_at: None,
+            admins: Vec::new(),
+            muted: Default::default(),
+            turn_taking: None,
+            kicked: Vec::new(),
+            sync: None,
         };
 
         // Find corporate chairman
@@ -44,6 +53,7 @@ impl OrganizationManager {
             // Add corporate chairman as group chat creator and member
             let mut updated_group = guilty_cliff_group;
             updated_group.creator_id = chairman_user.id.clone();
+            updated_group.ensure_creator_admin();
             updated_group.members.push(chairman_user.id.clone());
 
             // 添加所有管理层成员
@@ -82,6 +92,13 @@ impl OrganizationManager {
                 creator_id: user_id.to_string(),
                 members: vec![user_id.to_string()],
                 created_at: chrono::Utc::now().timestamp(),
+                ephemeral: false,
+                expires_at: None,
+                admins: vec![user_id.to_string()],
+                muted: Default::default(),
+                turn_taking: None,
+                kicked: Vec::new(),
+                sync: None,
             };
             self.store.save_group(&new_group).await?;
         }
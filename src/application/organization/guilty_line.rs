@@ -1,20 +1,37 @@
 //! Cliff of Contemplation Line Organization Management
 
 use std::sync::Arc;
-use tokio::sync::broadcast;
 
-use crate::domain::{Agent, Group, Message, Organization, Role, LLMConfig, Department, MessageTarget};
+use crate::domain::{Group, Organization, Department};
+use crate::core::org_changes::save_organization_tracked;
+use crate::core::org_tree::OrgTreeProjection;
 use crate::core::store::Store;
 
 /// Cliff of Contemplation Line Manager
 pub struct GuiltyLineManager {
     store: Arc<dyn Store>,
-    message_tx: broadcast::Sender<Message>,
+    org_tree: Option<Arc<OrgTreeProjection>>,
 }
 
 impl GuiltyLineManager {
-    pub fn new(store: Arc<dyn Store>, message_tx: broadcast::Sender<Message>) -> Self {
-        Self { store, message_tx }
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self { store, org_tree: None }
+    }
+
+    /// 组织架构变更后使该部门树缓存失效
+    pub fn with_org_tree(mut self, org_tree: Arc<OrgTreeProjection>) -> Self {
+        self.org_tree = Some(org_tree);
+        self
+    }
+
+    /// 保存并记录组织架构变更，有变化时使部门树缓存失效
+    async fn save_organization(&self, org: &Organization, actor: &str) -> Result<(), Box<dyn std::error::Error>> {
+        if save_organization_tracked(self.store.as_ref(), org, actor).await?.is_some() {
+            if let Some(org_tree) = &self.org_tree {
+                org_tree.invalidate();
+            }
+        }
+        Ok(())
     }
 
     /// Initialize Cliff of Contemplation Line architecture
@@ -26,9 +43,11 @@ impl GuiltyLineManager {
             name: "Cliff of Contemplation Line".to_string(),
             parent_id: None,
             leader_id: Some("guilty_chairman".to_string()), // Corporate chairman will become the leader
+            max_agents: None,
+            llm_budget: None,
         };
 
-        org.add_department(guilty_dept);
+        org.try_add_department(guilty_dept)?;
 
         // 注意：实际的Agent添加会在用户注册后动态进行
         Ok(())
@@ -66,7 +85,7 @@ impl GuiltyLineManager {
             agent.department_id = Some("guilty_line".to_string());
         }
 
-        self.store.save_organization(&org).await?;
+        self.save_organization(&org, user_id).await?;
 
         Ok(())
     }
@@ -80,7 +99,7 @@ impl GuiltyLineManager {
             agent.department_id = Some("guilty_line".to_string());
         }
 
-        self.store.save_organization(&org).await?;
+        self.save_organization(&org, user_id).await?;
 
         Ok(())
     }
@@ -113,13 +132,8 @@ impl GuiltyLineManager {
         members.sort();
         members.dedup();
 
-        let group = Group {
-            id: "guilty_line_group".to_string(),
-            name: "Cliff of Contemplation Line".to_string(),
-            creator_id: "system".to_string(), // 系统创建
-            members,
-            created_at: chrono::Utc::now().timestamp(),
-        };
+        // 系统创建
+        let group = Group::new("guilty_line_group", "Cliff of Contemplation Line", "system", members);
 
         self.store.save_group(&group).await?;
 
@@ -4,30 +4,143 @@
 //! This module encapsulates the framework's auto-configuration logic, allowing developers to start a complete multi-Agent system and Web service with minimal configuration
 
 use anyhow::Result;
+use rand::Rng;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::broadcast;
 use tracing::{info, warn};
 
+use crate::core::leadership::LeadershipError;
+use crate::core::retry::Retries;
+use crate::core::runtime_info::ConfigSource;
+use crate::core::store::{BufferedStore, MessageFilter, Store};
+use crate::domain::user::User;
+use crate::infrastructure::auth::PasswordService;
+use crate::infrastructure::email::SmtpEmailSender;
+use crate::infrastructure::logger::PromptLog;
+use crate::infrastructure::store::SqliteStore;
 use crate::{
-    Agent, AppConfig, CompanyBuilder, CompanyConfig, VirtualCompany, start_web_server,
+    Agent, AppConfig, CompanyBuilder, CompanyConfig, Group, Message, MessageCatalog, VirtualCompany, start_web_server_with_options, WebServerOptions,
 };
 
+/// Company config file picked up from the working directory
+const COMPANY_CONFIG_FILE: &str = "company_config.yaml";
+
+/// Metadata key marking the example messages injected on first run
+pub const DEMO_MESSAGE_KEY: &str = "demo";
+
+/// Group that holds the demo conversation
+pub const DEMO_GROUP_ID: &str = "general";
+
+/// Starter organization used when the store is empty
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum StarterOrganization {
+    /// Single-agent default setup
+    #[default]
+    Default,
+    /// Built-in template, see `CompanyConfig::STARTER_TEMPLATES`
+    Template(String),
+    /// YAML company config file
+    ConfigFile(PathBuf),
+}
+
+/// First-run seeding options for `quick_start_with` / `FrameworkLauncher`
+///
+/// Seeding only happens on an uninitialized store: the organization when none is
+/// stored, the admin when there are no users, the demo conversation when there are
+/// no messages. Restarting never overwrites existing data.
+#[derive(Debug, Clone, Default)]
+pub struct QuickStartOptions {
+    seed_admin: bool,
+    admin_username: Option<String>,
+    admin_password: Option<String>,
+    organization: StarterOrganization,
+    demo_conversation: bool,
+}
+
+impl QuickStartOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Seed an initial admin user; credentials come from `ADMIN_USERNAME` /
+    /// `ADMIN_PASSWORD`, otherwise `admin` with a generated password printed once
+    pub fn with_admin(mut self) -> Self {
+        self.seed_admin = true;
+        self
+    }
+
+    /// Seed an initial admin user with explicit credentials
+    pub fn with_admin_credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.seed_admin = true;
+        self.admin_username = Some(username.into());
+        self.admin_password = Some(password.into());
+        self
+    }
+
+    /// Start from a built-in organization template such as `startup`
+    pub fn with_template(mut self, template: impl Into<String>) -> Self {
+        self.organization = StarterOrganization::Template(template.into());
+        self
+    }
+
+    /// Start from a YAML company config file
+    pub fn with_config_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.organization = StarterOrganization::ConfigFile(path.into());
+        self
+    }
+
+    /// Inject a few example messages so the UI is not blank
+    pub fn with_demo_conversation(mut self, enabled: bool) -> Self {
+        self.demo_conversation = enabled;
+        self
+    }
+}
+
+/// What first-run seeding did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeedReport {
+    /// The starter organization was saved
+    pub organization: bool,
+    /// Username of the seeded admin
+    pub admin: Option<String>,
+    /// The admin password was generated (and printed to the console)
+    pub admin_password_generated: bool,
+    /// Number of demo messages injected
+    pub demo_messages: usize,
+}
+
+impl SeedReport {
+    /// Nothing was seeded, the store was already initialized
+    pub fn is_empty(&self) -> bool {
+        !self.organization && self.admin.is_none() && self.demo_messages == 0
+    }
+}
+
 /// Framework Launcher - Provides auto-configured startup functionality
 pub struct FrameworkLauncher {
     config: AppConfig,
+    options: QuickStartOptions,
 }
 
 impl FrameworkLauncher {
     /// Create a new framework launcher
     pub fn new() -> Self {
-        Self {
-            config: AppConfig::from_env(),
-        }
+        Self::with_config(AppConfig::from_env())
     }
 
     /// Create framework launcher with custom configuration
     pub fn with_config(config: AppConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            options: QuickStartOptions::default(),
+        }
+    }
+
+    /// Set first-run seeding options
+    pub fn with_options(mut self, options: QuickStartOptions) -> Self {
+        self.options = options;
+        self
     }
 
     /// Auto-configure and start the complete framework services
@@ -35,7 +148,7 @@ impl FrameworkLauncher {
         info!("🚀 Launching ImitatorT Framework...");
 
         // Initialize multi-Agent system
-        let company = self.initialize_multi_agent_system().await?;
+        let (company, _) = self.initialize().await?;
 
         // Start services
         self.start_services(company).await?;
@@ -43,43 +156,230 @@ impl FrameworkLauncher {
         Ok(())
     }
 
-    /// Initialize multi-Agent system
-    async fn initialize_multi_agent_system(&self) -> Result<VirtualCompany> {
+    /// Load or create the company and apply first-run seeding, without starting services
+    pub async fn initialize(&self) -> Result<(VirtualCompany, SeedReport)> {
         info!("🔧 Initializing multi-agent system...");
+        let mut report = SeedReport::default();
 
         // Try to load new configuration from config file
         if let Ok(config) = self.load_company_config() {
             info!("📋 Loaded company configuration");
+            let source = ConfigSource::new(Some(Path::new(COMPANY_CONFIG_FILE)), &config);
             let company = CompanyBuilder::from_config(config)?
                 .build_and_save()
-                .await?;
+                .await?
+                .with_config_source(source);
             info!("✅ Multi-agent system initialized with custom configuration");
-            return Ok(company);
+            self.seed(company.store().as_ref(), &mut report).await?;
+            return Ok((self.attach_services(company)?, report));
         }
 
         // Try to load from database
         info!("🔍 Attempting to load from database...");
-        match VirtualCompany::from_sqlite(&self.config.db_path).await {
+        if self.config.data_dir.is_some() {
+            self.config.prepare_data_dir()?;
+        }
+        let db_location = self.config.database_location();
+        let sqlite = SqliteStore::new(&db_location)?;
+        let store: Arc<dyn Store> = match &self.config.store_buffer {
+            Some(buffer) => {
+                info!("🛟 Store buffering enabled ({} writes)", buffer.max_buffered_writes);
+                Arc::new(BufferedStore::new(sqlite, buffer.clone())?)
+            }
+            None => Arc::new(sqlite),
+        };
+        let company = match VirtualCompany::from_store(store.clone()).await {
             Ok(company) => {
                 info!("✅ Loaded existing company from database");
-                Ok(company)
+                company
             }
             Err(_) => {
-                warn!("⚠️  No existing configuration found, using default setup");
-                let config = CompanyConfig::test_config();
-                let company = CompanyBuilder::from_config(config)?
+                warn!("⚠️  No existing configuration found, using starter setup");
+                let config = self.starter_config()?;
+                let source = match &self.options.organization {
+                    StarterOrganization::ConfigFile(path) => Some(ConfigSource::new(Some(path), &config)),
+                    _ => None,
+                };
+                let company = CompanyBuilder::with_store(store.clone())
+                    .config(config)
                     .build_and_save()
                     .await?;
-                info!("✅ Initialized default multi-agent system");
-                Ok(company)
+                let company = match source {
+                    Some(source) => company.with_config_source(source),
+                    None => company,
+                };
+                report.organization = true;
+                info!("✅ Initialized starter multi-agent system");
+                company
             }
+        };
+
+        self.seed(store.as_ref(), &mut report).await?;
+        Ok((self.attach_services(company)?, report))
+    }
+
+    /// Attach the services configured in the app config
+    fn attach_services(&self, company: VirtualCompany) -> Result<VirtualCompany> {
+        let company = self.attach_retries(company);
+        let company = self.attach_prompt_log(self.attach_email(self.attach_blob_store(self.attach_data_dir(company))?)?)?;
+        Ok(company.with_message_limits(self.config.message_limits.clone()))
+    }
+
+    /// Apply the configured retry policies; must come before email so notifications share the registry
+    fn attach_retries(&self, company: VirtualCompany) -> VirtualCompany {
+        company.with_retries(Arc::new(Retries::new(self.config.retry.clone())))
+    }
+
+    /// Report the resolved data directory in the runtime info
+    fn attach_data_dir(&self, company: VirtualCompany) -> VirtualCompany {
+        company.with_data_dir(self.config.data_dir())
+    }
+
+    /// Store attachments in the configured blob store
+    fn attach_blob_store(&self, company: VirtualCompany) -> Result<VirtualCompany> {
+        let blob_store = self.config.open_blob_store()?;
+        info!("📦 Attachments stored in the {} blob store", blob_store.backend());
+        Ok(company.with_blob_store(blob_store))
+    }
+
+    /// Enable email notifications when SMTP is configured
+    fn attach_email(&self, company: VirtualCompany) -> Result<VirtualCompany> {
+        let Some(smtp) = &self.config.email else {
+            return Ok(company);
+        };
+        info!("📧 Email notifications enabled via {}:{}", smtp.host, smtp.port);
+        Ok(company.with_email_sender(Arc::new(SmtpEmailSender::new(smtp)?)))
+    }
+
+    /// Log LLM prompts and responses when a prompt log directory is configured
+    fn attach_prompt_log(&self, company: VirtualCompany) -> Result<VirtualCompany> {
+        let Some(config) = &self.config.prompt_log else {
+            return Ok(company);
+        };
+        info!("📝 Prompt log enabled in {} ({})", config.directory.display(), config.level);
+        Ok(company.with_prompt_log(Arc::new(PromptLog::open(config)?)))
+    }
+
+    /// Company config for an empty store
+    fn starter_config(&self) -> Result<CompanyConfig> {
+        match &self.options.organization {
+            StarterOrganization::Default => Ok(CompanyConfig::test_config()),
+            StarterOrganization::Template(name) => CompanyConfig::starter(name).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown organization template: {} (available: {})",
+                    name,
+                    CompanyConfig::STARTER_TEMPLATES.join(", ")
+                )
+            }),
+            StarterOrganization::ConfigFile(path) => {
+                let content = std::fs::read_to_string(path)?;
+                Ok(serde_yaml::from_str(&content)?)
+            }
+        }
+    }
+
+    /// Seed the admin user and demo conversation, skipping what already exists
+    async fn seed(&self, store: &dyn Store, report: &mut SeedReport) -> Result<()> {
+        if self.options.seed_admin {
+            if store.load_users().await?.is_empty() {
+                self.seed_admin(store, report).await?;
+            } else {
+                info!("👤 Users already exist, skipping admin seeding");
+            }
+        }
+
+        if self.options.demo_conversation {
+            let existing = store.load_messages(MessageFilter::new().limit(1)).await?;
+            if existing.is_empty() {
+                report.demo_messages = self.seed_demo_conversation(store).await?;
+                info!("💬 Seeded {} demo messages", report.demo_messages);
+            } else {
+                info!("💬 Messages already exist, skipping demo conversation");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn seed_admin(&self, store: &dyn Store, report: &mut SeedReport) -> Result<()> {
+        let username = self
+            .options
+            .admin_username
+            .clone()
+            .or_else(|| std::env::var("ADMIN_USERNAME").ok())
+            .unwrap_or_else(|| "admin".to_string());
+        let (password, generated) = match self
+            .options
+            .admin_password
+            .clone()
+            .or_else(|| std::env::var("ADMIN_PASSWORD").ok())
+        {
+            Some(password) => (password, false),
+            None => (generate_password(), true),
+        };
+
+        let password_hash = PasswordService::hash_password(&password)?;
+        let admin = User::new_chairman(username.clone(), "Administrator".to_string(), password_hash, None);
+        store.save_user(&admin).await?;
+
+        if generated {
+            // 只打印到控制台一次，日志里不出现密码
+            let catalog = MessageCatalog::new(self.config.language);
+            println!(
+                "{}",
+                catalog.format("startup.admin_credentials", &[("username", &username), ("password", &password)])
+            );
+            info!("👤 Seeded admin user {} (password: [REDACTED], printed to console)", username);
+        } else {
+            info!("👤 Seeded admin user {}", username);
+        }
+
+        report.admin = Some(username);
+        report.admin_password_generated = generated;
+        Ok(())
+    }
+
+    /// Example group chat between the first agents
+    async fn seed_demo_conversation(&self, store: &dyn Store) -> Result<usize> {
+        let org = store.load_organization().await?;
+        if org.agents.is_empty() {
+            return Ok(0);
+        }
+
+        let members: Vec<String> = org.agents.iter().map(|a| a.id.clone()).collect();
+        let group = Group::new(DEMO_GROUP_ID, "General", members[0].clone(), members.clone());
+        store.save_group(&group).await?;
+
+        let catalog = MessageCatalog::new(self.config.language);
+        let speaker = |i: usize| members[i % members.len()].clone();
+        let lines = [
+            catalog.get("startup.demo_welcome"),
+            catalog.get("startup.demo_mentions"),
+            catalog.get("startup.demo_prompt"),
+        ];
+
+        // 时间戳依次递增，保证展示顺序
+        let start = chrono::Utc::now().timestamp() - lines.len() as i64;
+        let mut messages = Vec::new();
+        for (i, line) in lines.into_iter().enumerate() {
+            let mut message = Message::group(speaker(i), DEMO_GROUP_ID, line).with_metadata(DEMO_MESSAGE_KEY, "true");
+            message.timestamp = start + i as i64;
+            if i == 1 && members.len() > 1 {
+                message = message.with_mention(speaker(2));
+            }
+            messages.push(message);
         }
+        store.save_messages(&messages).await?;
+        Ok(messages.len())
     }
 
     /// Start all services
     async fn start_services(&self, company: VirtualCompany) -> Result<()> {
         info!("⚡ Starting framework services...");
 
+        let runtime_info = company.runtime_info().await;
+        runtime_info.log();
+
         // Get Agent list
         let agents: Vec<Agent> = company.get_agents().await?;
         info!("👥 Loaded {} agents", agents.len());
@@ -99,6 +399,10 @@ impl FrameworkLauncher {
             tokio::spawn(async move {
                 if let Err(e) = Self::start_agent_loops(company_for_agents, message_tx_for_agents).await {
                     tracing::error!("Agent loops error: {}", e);
+                    // 失去领导权后退出，由进程管理器以备用节点身份重启，后台轮询随进程一起停止
+                    if e.downcast_ref::<LeadershipError>().is_some() {
+                        std::process::exit(1);
+                    }
                 }
             });
         } else {
@@ -109,18 +413,53 @@ impl FrameworkLauncher {
         if self.config.output_mode == "web" {
             info!("🌐 Starting embedded web server on {}", self.config.web_bind);
 
-            start_web_server(
+            let server = start_web_server_with_options(
                 &self.config.web_bind,
                 agents,
                 message_tx,
-                company_arc.store().clone()
-            ).await?;
+                company_arc.store().clone(),
+                WebServerOptions {
+                    catalog: MessageCatalog::new(self.config.language),
+                    legacy_api_routes: self.config.legacy_api_routes,
+                    password_policy: self.config.password_policy.clone(),
+                    server: self.config.web_server.clone(),
+                    templates: company_arc.templates_arc(),
+                    reactions: Some(company_arc.reaction_sender()),
+                    tool_progress: Some(company_arc.tool_progress_sender()),
+                    scheduler: Some(company_arc.scheduler()),
+                    circuit_breakers: Some(company_arc.circuit_breakers()),
+                    message_bus: Some(company_arc.message_bus()),
+                    jwt_service: None,
+                    runtime_info: Some(runtime_info),
+                    blob_store: company_arc.blob_store(),
+                    permissions: company_arc.permissions(),
+                    prompt_log: company_arc.prompt_log(),
+                    agent_interviewer: company_arc.agent_interviewer(),
+                    default_llm: company_arc.default_llm(),
+                    agent_runner: Some(company_arc.agent_runner()),
+                    temp_agents: Some(company_arc.temp_agents()),
+                    scratchpad: company_arc.scratchpad(),
+                    goals: company_arc.goals(),
+                    chat_sessions: company_arc.chat_sessions(),
+                    group_stats: company_arc.group_stats(),
+                    retries: Some(company_arc.retries()),
+                    org_tree: Some(company_arc.org_tree()),
+                    mentions: Some(company_arc.mentions()),
+                    nudges: Some(company_arc.nudges()),
+                    features: Some(company_arc.features()),
+                    leadership: company_arc.leadership(),
+                    tool_executor: None,
+                    message_limits: company_arc.message_limits().clone(),
+                    #[cfg(feature = "chaos")]
+                    fault_injector: None,
+                },
+            );
 
-            info!("✅ Web server started successfully");
-
-            // Wait until terminated by interrupt signal
-            tokio::signal::ctrl_c().await.expect("Failed to listen for ctrl+c");
-            info!("🛑 Received shutdown signal");
+            // Serve until terminated by interrupt signal
+            tokio::select! {
+                result = server => result?,
+                _ = tokio::signal::ctrl_c() => info!("🛑 Received shutdown signal"),
+            }
         } else {
             info!("ℹ️  Running in console mode");
             // Wait for interrupt signal in console mode
@@ -128,13 +467,18 @@ impl FrameworkLauncher {
             info!("🛑 Received shutdown signal");
         }
 
+        // Flush today's tool stats before exiting
+        if let Err(e) = company_arc.persist_tool_stats().await {
+            warn!("Failed to persist tool stats: {}", e);
+        }
+
         Ok(())
     }
 
     /// Load company configuration
     fn load_company_config(&self) -> Result<CompanyConfig> {
         // Try to load configuration from YAML file
-        if let Ok(content) = std::fs::read_to_string("company_config.yaml") {
+        if let Ok(content) = std::fs::read_to_string(COMPANY_CONFIG_FILE) {
             let config: CompanyConfig = serde_yaml::from_str(&content)?;
             return Ok(config);
         }
@@ -160,8 +504,33 @@ pub async fn quick_start() -> Result<()> {
     launcher.launch().await
 }
 
+/// Quick start with first-run seeding options
+pub async fn quick_start_with(options: QuickStartOptions) -> Result<()> {
+    let launcher = FrameworkLauncher::new().with_options(options);
+    launcher.launch().await
+}
+
 /// Quick start with custom configuration
 pub async fn start_with_config(config: AppConfig) -> Result<()> {
     let launcher = FrameworkLauncher::with_config(config);
     launcher.launch().await
-}
\ No newline at end of file
+}
+/// Random admin password with upper/lower case letters and digits
+fn generate_password() -> String {
+    const UPPER: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+    const LOWER: &[u8] = b"abcdefghijkmnopqrstuvwxyz";
+    const DIGITS: &[u8] = b"23456789";
+    let mut rng = rand::thread_rng();
+    let mut pick = |set: &[u8]| set[rng.gen_range(0..set.len())] as char;
+
+    let mut password: Vec<char> = vec![pick(UPPER), pick(LOWER), pick(DIGITS)];
+    let all = [UPPER, LOWER, DIGITS].concat();
+    while password.len() < 16 {
+        password.push(pick(&all));
+    }
+    let mut rng = rand::thread_rng();
+    for i in (1..password.len()).rev() {
+        password.swap(i, rng.gen_range(0..=i));
+    }
+    password.into_iter().collect()
+}
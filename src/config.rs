@@ -4,13 +4,29 @@
 
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::core::i18n::Language;
+use crate::core::message_limits::MessageLimits;
+use crate::core::retry::RetryConfig;
+use crate::core::store::BufferConfig;
+use crate::infrastructure::auth::PasswordPolicy;
+use crate::infrastructure::blob::{BlobStore, BlobStoreConfig, S3Config};
+use crate::infrastructure::email::{SmtpConfig, SmtpTls};
+use crate::infrastructure::logger::PromptLogConfig;
+use crate::infrastructure::web::{HealthConfig, TlsConfig, WebServerConfig};
 
 /// Application Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
-    /// Database path
+    /// Database path or SQLite URI (`file:name?mode=memory&cache=shared`)
     pub db_path: String,
 
+    /// Data directory; when set, a relative `db_path` resolves under `<data_dir>/db`
+    #[serde(default)]
+    pub data_dir: Option<String>,
+
     /// Web server binding address
     pub web_bind: String,
 
@@ -31,12 +47,53 @@ pub struct AppConfig {
 
     /// Whether to run Agent autonomous loops (in web mode)
     pub run_agent_loops: bool,
+
+    /// Language for framework-generated messages (en or zh)
+    #[serde(default)]
+    pub language: Language,
+
+    /// Keep the unversioned `/api/...` routes as aliases of `/api/v1`
+    #[serde(default = "default_true")]
+    pub legacy_api_routes: bool,
+
+    /// Password policy for registration and password change
+    #[serde(default)]
+    pub password_policy: PasswordPolicy,
+
+    /// Web server deployment settings (CORS, body limit, timeout, TLS, base path)
+    #[serde(default)]
+    pub web_server: WebServerConfig,
+
+    /// SMTP server for email notifications; `notify.email` is disabled when unset
+    #[serde(default)]
+    pub email: Option<SmtpConfig>,
+
+    /// Read cache and write buffering while the store is unavailable; disabled when unset
+    #[serde(default)]
+    pub store_buffer: Option<BufferConfig>,
+
+    /// Backend for attachments and other large objects (local directory or S3)
+    #[serde(default)]
+    pub blob_store: BlobStoreConfig,
+
+    /// Dedicated JSON-lines log of LLM prompts and responses; disabled when unset
+    #[serde(default)]
+    pub prompt_log: Option<PromptLogConfig>,
+
+    /// Size limits applied to every message sent through the web API, WebSocket and agent tools
+    #[serde(default)]
+    pub message_limits: MessageLimits,
+
+    /// Retry policies for LLM requests, webhooks and MCP calls, plus the per-subsystem retry budget
+    #[serde(default)]
+    pub retry: RetryConfig,
 }
 
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             db_path: get_env_or_default("DB_PATH", "imitatort.db".to_string()),
+            data_dir: env::var("DATA_DIR").ok().filter(|dir| !dir.is_empty()),
             web_bind: get_env_or_default("WEB_BIND", "0.0.0.0:8080".to_string()),
             output_mode: get_env_or_default("OUTPUT_MODE", "cli".to_string()),
             message_channel_capacity: get_env_or_default("MESSAGE_CHANNEL_CAPACITY", 1000usize),
@@ -44,6 +101,19 @@ impl Default for AppConfig {
             default_model: get_env_or_default("DEFAULT_MODEL", "gpt-4o-mini".to_string()),
             log_level: get_env_or_default("LOG_LEVEL", "info".to_string()),
             run_agent_loops: get_env_or_default("RUN_AGENT_LOOPS", true), // Default to run agent loops, maintaining backward compatibility
+            language: get_env_or_default("LANGUAGE", Language::default()),
+            legacy_api_routes: get_env_or_default("LEGACY_API_ROUTES", true),
+            password_policy: PasswordPolicy {
+                min_length: get_env_or_default("PASSWORD_MIN_LENGTH", PasswordPolicy::default().min_length),
+                ..PasswordPolicy::default()
+            },
+            web_server: web_server_config_from_env(),
+            email: smtp_config_from_env(),
+            store_buffer: store_buffer_config_from_env(),
+            blob_store: blob_store_config_from_env(),
+            prompt_log: prompt_log_config_from_env(),
+            message_limits: message_limits_from_env(),
+            retry: RetryConfig::default(),
         }
     }
 }
@@ -68,6 +138,240 @@ impl AppConfig {
             None => Self::from_env(),
         }
     }
+
+    /// Data directory, falling back to the platform data directory
+    pub fn data_dir(&self) -> PathBuf {
+        match &self.data_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => default_data_dir(),
+        }
+    }
+
+    /// Create the data directory and its subdirectories, owner-only on unix
+    pub fn prepare_data_dir(&self) -> std::io::Result<DataDirs> {
+        let dirs = DataDirs::new(self.data_dir());
+        for dir in [&dirs.root, &dirs.db, &dirs.workspaces, &dirs.attachments] {
+            create_private_dir(dir)?;
+        }
+        Ok(dirs)
+    }
+
+    /// Open the configured blob store; the local backend defaults to the data directory,
+    /// so attachments land in `<data_dir>/attachments`
+    pub fn open_blob_store(&self) -> anyhow::Result<Arc<dyn BlobStore>> {
+        self.blob_store.open(self.data_dir())
+    }
+
+    /// Database file or URI actually opened
+    ///
+    /// URIs, `:memory:` and absolute paths are used as is; relative paths only
+    /// move under the data directory when `data_dir` is configured, so existing
+    /// deployments keep their `imitatort.db` in the working directory.
+    pub fn database_location(&self) -> String {
+        let is_plain_relative = !self.db_path.starts_with("file:")
+            && self.db_path != ":memory:"
+            && Path::new(&self.db_path).is_relative();
+        match &self.data_dir {
+            Some(_) if is_plain_relative => DataDirs::new(self.data_dir())
+                .db
+                .join(&self.db_path)
+                .to_string_lossy()
+                .into_owned(),
+            _ => self.db_path.clone(),
+        }
+    }
+}
+
+/// Layout of the data directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDirs {
+    pub root: PathBuf,
+    /// SQLite database files
+    pub db: PathBuf,
+    /// Agent working directories
+    pub workspaces: PathBuf,
+    /// Uploaded files
+    pub attachments: PathBuf,
+}
+
+impl DataDirs {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            db: root.join("db"),
+            workspaces: root.join("workspaces"),
+            attachments: root.join("attachments"),
+            root,
+        }
+    }
+}
+
+/// Platform data directory: `$XDG_DATA_HOME/imitatort`, `~/.local/share/imitatort`,
+/// `~/Library/Application Support/imitatort` or `%APPDATA%\imitatort`
+fn default_data_dir() -> PathBuf {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join("imitatort")
+}
+
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Web server settings from CORS_ALLOWED_ORIGINS (comma separated), MAX_BODY_BYTES,
+/// REQUEST_TIMEOUT_SECS, TLS_CERT_PATH / TLS_KEY_PATH, BASE_PATH,
+/// HEALTH_CRITICAL_CHECKS and DISABLED_ROUTE_GROUPS (comma separated)
+fn web_server_config_from_env() -> WebServerConfig {
+    let defaults = WebServerConfig::default();
+    let mut routes = defaults.routes.clone();
+    for group in env_list("DISABLED_ROUTE_GROUPS").unwrap_or_default() {
+        if !routes.disable(&group) {
+            tracing::warn!("Ignoring unknown route group in DISABLED_ROUTE_GROUPS: {}", group);
+        }
+    }
+    let allowed_origins = env_list("CORS_ALLOWED_ORIGINS").unwrap_or_default();
+    let tls = match (env::var("TLS_CERT_PATH"), env::var("TLS_KEY_PATH")) {
+        (Ok(cert_path), Ok(key_path)) => Some(TlsConfig {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+        }),
+        _ => None,
+    };
+
+    WebServerConfig {
+        allowed_origins,
+        max_body_bytes: get_env_or_default("MAX_BODY_BYTES", defaults.max_body_bytes),
+        request_timeout_secs: get_env_or_default("REQUEST_TIMEOUT_SECS", defaults.request_timeout_secs),
+        tls,
+        base_path: get_env_or_default("BASE_PATH", defaults.base_path),
+        health: HealthConfig {
+            critical: env_list("HEALTH_CRITICAL_CHECKS").unwrap_or(defaults.health.critical),
+            ..defaults.health
+        },
+        routes,
+    }
+}
+
+/// SMTP settings from SMTP_HOST, SMTP_PORT, SMTP_TLS (none, starttls or tls), SMTP_FROM,
+/// SMTP_USERNAME and SMTP_PASSWORD; only enabled when both SMTP_HOST and SMTP_FROM are set
+fn smtp_config_from_env() -> Option<SmtpConfig> {
+    let host = env::var("SMTP_HOST").ok().filter(|host| !host.is_empty())?;
+    let from = env::var("SMTP_FROM").ok().filter(|from| !from.is_empty())?;
+    let tls = get_env_or_default("SMTP_TLS", SmtpTls::default());
+    let default_port = match tls {
+        SmtpTls::None => 25,
+        SmtpTls::StartTls => 587,
+        SmtpTls::Tls => 465,
+    };
+
+    Some(SmtpConfig {
+        host,
+        port: get_env_or_default("SMTP_PORT", default_port),
+        tls,
+        from,
+        username: env::var("SMTP_USERNAME").ok(),
+        password: env::var("SMTP_PASSWORD").ok(),
+    })
+}
+
+/// Store degradation settings from STORE_BUFFER_WRITES, STORE_CACHE_ENTRIES and
+/// STORE_JOURNAL_PATH; only enabled when STORE_BUFFER_WRITES is set
+fn store_buffer_config_from_env() -> Option<BufferConfig> {
+    let defaults = BufferConfig::default();
+    let max_buffered_writes = env::var("STORE_BUFFER_WRITES").ok()?.parse().ok()?;
+
+    Some(BufferConfig {
+        max_buffered_writes,
+        cache_entries: get_env_or_default("STORE_CACHE_ENTRIES", defaults.cache_entries),
+        journal_path: env::var("STORE_JOURNAL_PATH")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+    })
+}
+
+/// Prompt log settings from PROMPT_LOG_DIR, PROMPT_LOG_LEVEL (full, redact_user_content or
+/// metadata_only), PROMPT_LOG_MAX_BYTES, PROMPT_LOG_MAX_AGE_SECS and PROMPT_LOG_MAX_FILES;
+/// only enabled when PROMPT_LOG_DIR is set
+fn prompt_log_config_from_env() -> Option<PromptLogConfig> {
+    let directory = env::var("PROMPT_LOG_DIR").ok().filter(|dir| !dir.is_empty())?;
+    let defaults = PromptLogConfig::new(directory);
+
+    Some(PromptLogConfig {
+        level: get_env_or_default("PROMPT_LOG_LEVEL", defaults.level),
+        max_file_bytes: get_env_or_default("PROMPT_LOG_MAX_BYTES", defaults.max_file_bytes),
+        max_age_secs: get_env_or_default("PROMPT_LOG_MAX_AGE_SECS", defaults.max_age_secs),
+        max_files: get_env_or_default("PROMPT_LOG_MAX_FILES", defaults.max_files),
+        ..defaults
+    })
+}
+
+/// Message limits from MESSAGE_MAX_BYTES, MESSAGE_MAX_MENTIONS, MESSAGE_MAX_METADATA_BYTES,
+/// MESSAGE_OVERSIZE (reject or attach) and MESSAGE_PREVIEW_CHARS
+fn message_limits_from_env() -> MessageLimits {
+    let defaults = MessageLimits::default();
+    MessageLimits {
+        max_content_bytes: get_env_or_default("MESSAGE_MAX_BYTES", defaults.max_content_bytes),
+        max_mentions: get_env_or_default("MESSAGE_MAX_MENTIONS", defaults.max_mentions),
+        max_metadata_bytes: get_env_or_default("MESSAGE_MAX_METADATA_BYTES", defaults.max_metadata_bytes),
+        oversize: get_env_or_default("MESSAGE_OVERSIZE", defaults.oversize),
+        preview_chars: get_env_or_default("MESSAGE_PREVIEW_CHARS", defaults.preview_chars),
+    }
+}
+
+/// Blob store from BLOB_STORE (local or s3) and BLOB_DIR; S3 uses S3_ENDPOINT, S3_BUCKET,
+/// S3_REGION, S3_PRESIGN_DOWNLOADS and S3_ACCESS_KEY_ID / S3_SECRET_ACCESS_KEY / S3_SESSION_TOKEN
+/// (falling back to the AWS_* variables)
+fn blob_store_config_from_env() -> BlobStoreConfig {
+    let backend = env::var("BLOB_STORE").unwrap_or_default();
+    if !backend.eq_ignore_ascii_case("s3") {
+        if !backend.is_empty() && !backend.eq_ignore_ascii_case("local") {
+            tracing::warn!("Unknown BLOB_STORE {}, using the local blob store", backend);
+        }
+        return BlobStoreConfig::Local {
+            root: env::var("BLOB_DIR").ok().filter(|dir| !dir.is_empty()).map(PathBuf::from),
+        };
+    }
+
+    let secret = |name: &str, aws_name: &str| env::var(name).or_else(|_| env::var(aws_name)).ok();
+    let defaults = S3Config::new("", "");
+    BlobStoreConfig::S3(S3Config {
+        endpoint: get_env_or_default("S3_ENDPOINT", "https://s3.amazonaws.com".to_string()),
+        bucket: env::var("S3_BUCKET").unwrap_or_default(),
+        region: secret("S3_REGION", "AWS_REGION").unwrap_or(defaults.region),
+        access_key_id: secret("S3_ACCESS_KEY_ID", "AWS_ACCESS_KEY_ID"),
+        secret_access_key: secret("S3_SECRET_ACCESS_KEY", "AWS_SECRET_ACCESS_KEY"),
+        session_token: secret("S3_SESSION_TOKEN", "AWS_SESSION_TOKEN"),
+        presign_downloads: get_env_or_default("S3_PRESIGN_DOWNLOADS", defaults.presign_downloads),
+        part_size: defaults.part_size,
+    })
+}
+
+/// Comma separated list from an environment variable
+fn env_list(key: &str) -> Option<Vec<String>> {
+    env::var(key).ok().map(|val| {
+        val.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Helper function: get value from environment variable, return default if not exists
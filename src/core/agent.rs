@@ -2,27 +2,114 @@
 //!
 //! Responsible for interacting with LLM and executing decisions
 
+use std::collections::HashMap;
+use std::sync::Mutex;
+
 use anyhow::Result;
-use crate::domain::{Agent, Message, MessageTarget};
-use crate::infrastructure::llm::OpenAIClient;
+use crate::core::messaging::order_by_group_seq;
+use crate::core::nudge::Nudge;
+use crate::core::preferences::render_preferences_section;
+use crate::core::response_language::{
+    check_language, corrective_instruction, language_name, LanguageCheck, LanguageCorrection, ResponseStyle,
+};
+use crate::core::role_history::role_in_effect;
+use crate::core::store::{MessageFilter, Store, TaskFilter};
+use crate::core::tasks::render_open_tasks;
+use crate::core::tokenizer::TokenCounter;
+use crate::core::turn_taking::PeerTurn;
+use crate::domain::{
+    Agent, Message, MessageId, MessagePriority, MessageTarget, MessageTranslation, ReactionCount, RoleRevision, Task,
+};
+use crate::domain::tool::Tool;
+use crate::infrastructure::llm::{self, LlmError, OpenAIClient, ToolResponse};
+use serde::{Deserialize, Serialize};
 use serde_json;
 
 /// Agent Runtime - Responsible for thinking and executing
 pub struct AgentRuntime {
     agent: Agent,
     llm: OpenAIClient,
+    /// Company default language and tone, overridden by the role
+    response_style: ResponseStyle,
+    /// Language retry made by the last decision, taken by the runner
+    language_correction: Mutex<Option<LanguageCorrection>>,
+    /// Text the model wrote alongside the tool call of the last decision, taken by the runner
+    tool_turn_content: Mutex<Option<String>>,
+    /// Token counts of history messages, cached across turns
+    tokens: TokenCounter,
+    /// Token budget for the conversation history in the prompt
+    history_token_budget: usize,
 }
 
 impl AgentRuntime {
     /// Create a new Agent Runtime
     pub async fn new(agent: Agent) -> Result<Self> {
-        let llm = OpenAIClient::new_with_base_url(
-            agent.llm_config.api_key.clone(),
-            agent.llm_config.model.clone(),
-            agent.llm_config.base_url.clone(),
-        );
+        let tokens = TokenCounter::for_config(&agent.llm_config);
+        let llm = OpenAIClient::from_config(&agent.llm_config)
+            .map_err(|e| anyhow::anyhow!("Agent {}: {}", agent.id, e))?
+            .with_tokenizer(tokens.tokenizer());
+
+        Ok(Self {
+            agent,
+            llm,
+            response_style: ResponseStyle::default(),
+            language_correction: Mutex::new(None),
+            tool_turn_content: Mutex::new(None),
+            tokens,
+            history_token_budget: HISTORY_TOKEN_BUDGET,
+        })
+    }
+
+    /// Set the token budget for the conversation history; older messages are dropped first
+    pub fn with_history_token_budget(mut self, budget: usize) -> Self {
+        self.history_token_budget = budget;
+        self
+    }
 
-        Ok(Self { agent, llm })
+    /// Token counter for this agent's model
+    pub fn token_counter(&self) -> &TokenCounter {
+        &self.tokens
+    }
+
+    /// Set the company default response language and tone
+    pub fn with_response_style(mut self, style: ResponseStyle) -> Self {
+        self.response_style = style;
+        self
+    }
+
+    /// Response style in effect for this context (role revision, then configured role, then company default)
+    pub fn response_style(&self, context: &Context) -> ResponseStyle {
+        let role = context.role_revision.as_ref().map(|r| &r.role).unwrap_or(&self.agent.role);
+        ResponseStyle::for_role(role, &self.response_style)
+    }
+
+    /// Take the language correction recorded by the last decision, if any
+    pub fn take_language_correction(&self) -> Option<LanguageCorrection> {
+        self.language_correction.lock().unwrap().take()
+    }
+
+    /// Take the text the model wrote alongside the tool call of the last decision, if any
+    pub fn take_tool_turn_content(&self) -> Option<String> {
+        self.tool_turn_content.lock().unwrap().take()
+    }
+
+    /// Consult the fault injector before every LLM request (`chaos` feature)
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injector(mut self, injector: std::sync::Arc<crate::core::chaos::FaultInjector>) -> Self {
+        self.llm = self.llm.with_fault_injector(injector);
+        self
+    }
+
+    /// Write every LLM request and response of this agent to the prompt log
+    pub fn with_prompt_log(mut self, prompt_log: std::sync::Arc<crate::infrastructure::logger::PromptLog>) -> Self {
+        self.llm = self.llm.with_prompt_log(prompt_log);
+        self
+    }
+
+    /// Retry transient LLM failures with a shared retrier (subsystem policy, budget and metrics)
+    pub fn with_retrier(mut self, retrier: crate::core::retry::Retrier) -> Self {
+        self.llm = self.llm.with_retrier(retrier);
+        self
     }
 
     /// Get Agent ID
@@ -40,32 +127,195 @@ impl AgentRuntime {
         &self.agent
     }
 
+    /// Total LLM tokens consumed by this runtime
+    pub fn tokens_used(&self) -> u64 {
+        self.llm.tokens_used()
+    }
+
     /// Think and make decisions
     pub async fn think(&self, context: Context) -> Result<Decision> {
-        // Build prompt
-        let prompt = self.build_thinking_prompt(&context);
+        self.decide(&context, Vec::new()).await
+    }
+
+    /// Think with tool calling: the given tools (already filtered for this agent)
+    /// are sent with the request, and a tool call becomes [`Decision::CallTool`]
+    pub async fn think_with_tools(&self, context: Context, tools: &[Tool]) -> Result<Decision> {
+        let tools = tools.iter().map(llm::Tool::from_domain_tool).collect();
+        self.decide(&context, tools).await
+    }
 
-        // Call LLM
-        let response = self.llm.complete(&prompt).await?;
+    /// Ask for a decision; a message in the wrong language is retried once with a corrective instruction
+    async fn decide(&self, context: &Context, tools: Vec<llm::Tool>) -> Result<Decision> {
+        let style = self.response_style(context);
+        let decision = self.request_fitted(context, &style, None, tools.clone()).await?;
+
+        let Some(expected) = style.language.as_deref() else {
+            return Ok(decision);
+        };
+        let Decision::SendMessage { content, .. } = &decision else {
+            return Ok(decision);
+        };
+        let LanguageCheck::Mismatch { detected } = check_language(expected, content) else {
+            return Ok(decision);
+        };
+
+        tracing::info!("Agent {} answered in {} instead of {}, retrying", self.agent.id, detected, expected);
+        let correction = corrective_instruction(expected);
+        let retried = self.request_fitted(context, &style, Some(&correction), tools).await?;
+        let corrected = match &retried {
+            Decision::SendMessage { content, .. } => {
+                !matches!(check_language(expected, content), LanguageCheck::Mismatch { .. })
+            }
+            _ => true,
+        };
+        *self.language_correction.lock().unwrap() = Some(LanguageCorrection {
+            expected: expected.to_string(),
+            detected,
+            corrected,
+        });
+        Ok(retried)
+    }
 
-        // Parse decision
-        let decision = self.parse_decision(&response)?;
+    /// Ask for a decision; when the prompt exceeds the model's context length the history is
+    /// shrunk to fit and the request is retried once
+    async fn request_fitted(
+        &self,
+        context: &Context,
+        style: &ResponseStyle,
+        correction: Option<&str>,
+        tools: Vec<llm::Tool>,
+    ) -> Result<Decision> {
+        let budget = self.history_token_budget;
+        let prompt = self.render_prompt(context, style, correction, budget);
+        let error = match self.request_decision(prompt, &context.nudges, tools.clone()).await {
+            Ok(decision) => return Ok(decision),
+            Err(e) => e,
+        };
+        let Some(LlmError::ContextLengthExceeded { limit, requested }) = LlmError::of(&error) else {
+            return Err(error);
+        };
+
+        let used = self.tokens.count_messages(self.tokens.fit_newest(&context.history, budget));
+        let shrunk = shrink_history_budget(used, *limit, *requested);
+        tracing::info!(
+            "Agent {} prompt exceeded the context length (limit {:?}, requested {:?}), retrying with {} history tokens instead of {}",
+            self.agent.id,
+            limit,
+            requested,
+            shrunk,
+            used
+        );
+        let prompt = self.render_prompt(context, style, correction, shrunk);
+        self.request_decision(prompt, &context.nudges, tools).await
+    }
 
-        Ok(decision)
+    async fn request_decision(&self, prompt: String, nudges: &[Nudge], tools: Vec<llm::Tool>) -> Result<Decision> {
+        let mut messages = vec![llm::Message::user(prompt)];
+        messages.extend(nudges.iter().map(|nudge| llm::Message::user(nudge.prompt())));
+        if tools.is_empty() {
+            let response = self.llm.chat(messages).await?;
+            return self.parse_decision(&response);
+        }
+
+        match self.llm.chat_with_tools(messages, tools).await? {
+            ToolResponse::ToolCalls { content, tool_calls } => match tool_calls.into_iter().next() {
+                Some(call) => {
+                    let content = content.trim();
+                    *self.tool_turn_content.lock().unwrap() = (!content.is_empty()).then(|| content.to_string());
+                    Ok(Decision::CallTool {
+                        tool_id: call.name,
+                        arguments: call.arguments,
+                    })
+                }
+                None => self.parse_decision(&content),
+            },
+            ToolResponse::Message(content) => self.parse_decision(&content),
+        }
     }
 
     /// Build thinking prompt
-    fn build_thinking_prompt(&self, context: &Context) -> String {
-        let mut prompt = format!(
-            "{}\n\nCurrent situation:\n",
-            self.agent.system_prompt()
-        );
+    pub fn build_thinking_prompt(&self, context: &Context) -> String {
+        self.render_prompt(context, &self.response_style(context), None, self.history_token_budget)
+    }
+
+    fn render_prompt(
+        &self,
+        context: &Context,
+        style: &ResponseStyle,
+        correction: Option<&str>,
+        history_budget: usize,
+    ) -> String {
+        let mut prompt = match &context.role_revision {
+            Some(revision) => revision.role.system_prompt.clone(),
+            None => self.agent.system_prompt(),
+        };
+
+        // Add the required response language and tone
+        if let Some(instruction) = style.instruction() {
+            prompt.push_str(&instruction);
+        }
+        if let Some(correction) = correction {
+            prompt.push_str(correction);
+        }
+
+        // Add agent's saved preferences as a delimited data section
+        if let Some(section) = context.preferences.as_ref().and_then(render_preferences_section) {
+            prompt.push_str(&section);
+        }
+
+        prompt.push_str("\n\nCurrent situation:\n");
+
+        // Add the active goal; it is kept even when the conversation that set it is trimmed
+        if let Some(goal) = &context.goal {
+            prompt.push_str(&format!(
+                "\nActive goal (the request you are working on; call self.clear_goal once it is done):\n{}\n",
+                goal
+            ));
+        }
+
+        // Add messages pinned in the agent's conversations
+        if !context.pinned.is_empty() {
+            prompt.push_str("\nPinned messages:\n");
+            for msg in &context.pinned {
+                prompt.push_str(&context.render_message(msg));
+            }
+        }
+
+        // Add recent conversation history, newest messages first within the token budget
+        let history = self.tokens.fit_newest(&context.history, history_budget);
+        if history.len() < context.history.len() {
+            tracing::debug!(
+                "Agent {} history trimmed to {} of {} messages to fit {} tokens",
+                self.agent.id,
+                history.len(),
+                context.history.len(),
+                history_budget
+            );
+        }
+        if !history.is_empty() {
+            prompt.push_str("\nRecent conversation:\n");
+            for msg in history {
+                prompt.push_str(&context.render_message(msg));
+            }
+        }
 
         // Add unread messages
         if !context.unread_messages.is_empty() {
             prompt.push_str("\nUnread messages:\n");
             for msg in &context.unread_messages {
-                prompt.push_str(&format!("- [{}]: {}\n", msg.from, msg.content));
+                prompt.push_str(&context.render_message(msg));
+            }
+        }
+
+        // Add group questions a teammate is answering
+        if !context.peer_turns.is_empty() {
+            prompt.push_str("\nA teammate is answering these; do not reply unless you are mentioned:\n");
+            for turn in &context.peer_turns {
+                let (MessageTarget::Group(target) | MessageTarget::Direct(target)) = &turn.message.to;
+                prompt.push_str(&format!(
+                    "- {} is answering [{}] in {}\n",
+                    turn.responder, turn.message.from, target
+                ));
             }
         }
 
@@ -74,6 +324,31 @@ impl AgentRuntime {
             prompt.push_str(&format!("\nCurrent task: {}\n", task));
         }
 
+        // Add open tasks assigned to the agent
+        if let Some(section) = render_open_tasks(&context.open_tasks) {
+            prompt.push_str(&section);
+        }
+
+        // Add tool results available for citation
+        if let Some(section) = &context.tool_results {
+            prompt.push_str(section);
+        }
+
+        // Add messages that arrived after the context snapshot, shown in full next turn
+        if !context.late_arrivals.is_empty() {
+            let count = context.late_arrivals.len();
+            prompt.push_str(&format!(
+                "\nArrived while you were thinking: {} new message{}. You will see them next turn; do not reply to them yet.\n",
+                count,
+                if count == 1 { "" } else { "s" }
+            ));
+            if context.late_arrival_content {
+                for msg in &context.late_arrivals {
+                    prompt.push_str(&context.render_message(msg));
+                }
+            }
+        }
+
         // Add available decision instructions with JSON format
         prompt.push_str(
             "\nDecide your next action. Respond with ONLY a valid JSON object with one of these formats:\n\
@@ -190,9 +465,11 @@ impl AgentRuntime {
 
     /// Execute task
     pub async fn execute_task(&self, task: &str) -> Result<String> {
+        let style = ResponseStyle::for_role(&self.agent.role, &self.response_style);
         let prompt = format!(
-            "{}\n\nComplete the following task:\n{}\n",
+            "{}{}\n\nComplete the following task:\n{}\n",
             self.agent.system_prompt(),
+            style.instruction().unwrap_or_default(),
             task
         );
 
@@ -217,6 +494,11 @@ pub enum Decision {
     ExecuteTask {
         task: String,
     },
+    /// Call a tool from the agent's tool view
+    CallTool {
+        tool_id: String,
+        arguments: serde_json::Value,
+    },
     /// Wait
     Wait,
 }
@@ -230,6 +512,32 @@ pub struct Context {
     pub current_task: Option<String>,
     /// Organization information
     pub organization_info: Option<String>,
+    /// Agent's saved preferences
+    pub preferences: Option<serde_json::Value>,
+    /// Recent conversation history (oldest first)
+    pub history: Vec<Message>,
+    /// Aggregated reactions by message ID, rendered next to the messages
+    pub reactions: HashMap<MessageId, Vec<ReactionCount>>,
+    /// Role revision in effect, overrides the configured role
+    pub role_revision: Option<RoleRevision>,
+    /// Rendered tool results the agent can cite (see [`crate::core::citations`])
+    pub tool_results: Option<String>,
+    /// Messages that arrived after the snapshot was taken; they are handled next turn
+    pub late_arrivals: Vec<Message>,
+    /// Render late arrivals with their content instead of only counting them
+    pub late_arrival_content: bool,
+    /// Open tasks assigned to the agent, soonest due first
+    pub open_tasks: Vec<Task>,
+    /// Group questions a teammate is answering (see [`crate::core::turn_taking`])
+    pub peer_turns: Vec<PeerTurn>,
+    /// Translations into the agent's language by message ID (see [`crate::core::translation`])
+    pub translations: HashMap<MessageId, MessageTranslation>,
+    /// Messages pinned in the agent's conversations, oldest pin first
+    pub pinned: Vec<Message>,
+    /// Nudges sent as user messages after the prompt, oldest first (see [`crate::core::nudge`])
+    pub nudges: Vec<Nudge>,
+    /// Active goal, rendered in its own section that history trimming never drops (see [`crate::core::goals`])
+    pub goal: Option<String>,
 }
 
 impl Context {
@@ -244,4 +552,255 @@ impl Context {
         self.current_task = Some(task.into());
         self
     }
+
+    /// Add preferences
+    pub fn with_preferences(mut self, preferences: serde_json::Value) -> Self {
+        self.preferences = Some(preferences);
+        self
+    }
+
+    /// Add conversation history
+    pub fn with_history(mut self, history: Vec<Message>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Use a stored role revision instead of the configured role
+    pub fn with_role_revision(mut self, revision: RoleRevision) -> Self {
+        self.role_revision = Some(revision);
+        self
+    }
+
+    /// Add the rendered tool results section
+    pub fn with_tool_results(mut self, section: impl Into<String>) -> Self {
+        self.tool_results = Some(section.into());
+        self
+    }
+
+    /// Add messages that arrived after the snapshot
+    pub fn with_late_arrivals(mut self, messages: Vec<Message>, include_content: bool) -> Self {
+        self.late_arrivals = messages;
+        self.late_arrival_content = include_content;
+        self
+    }
+
+    /// Add open tasks assigned to the agent
+    pub fn with_open_tasks(mut self, tasks: Vec<Task>) -> Self {
+        self.open_tasks = tasks;
+        self
+    }
+
+    /// Add group questions a teammate is answering
+    pub fn with_peer_turns(mut self, turns: Vec<PeerTurn>) -> Self {
+        self.peer_turns = turns;
+        self
+    }
+
+    /// Set the active goal
+    pub fn with_goal(mut self, goal: impl Into<String>) -> Self {
+        self.goal = Some(goal.into());
+        self
+    }
+
+    /// Add pinned messages
+    pub fn with_pinned(mut self, messages: Vec<Message>) -> Self {
+        self.pinned = messages;
+        self
+    }
+
+    /// Add nudges, sent after the prompt in arrival order
+    pub fn with_nudges(mut self, nudges: Vec<Nudge>) -> Self {
+        self.nudges = nudges;
+        self
+    }
+
+    /// Add reactions
+    pub fn with_reactions(mut self, reactions: HashMap<MessageId, Vec<ReactionCount>>) -> Self {
+        self.reactions = reactions;
+        self
+    }
+
+    /// Show these translations in place of the original text, which stays visible as the source
+    pub fn with_translations(mut self, translations: HashMap<MessageId, MessageTranslation>) -> Self {
+        self.translations = translations;
+        self
+    }
+
+    /// IDs of all messages in the context
+    pub fn message_ids(&self) -> Vec<MessageId> {
+        self.history
+            .iter()
+            .chain(&self.unread_messages)
+            .map(|m| m.id.clone())
+            .collect()
+    }
+
+    /// Render a message line, with its translation and a compact reaction summary if any
+    fn render_message(&self, msg: &Message) -> String {
+        let content = match self.translations.get(&msg.id) {
+            Some(translation) => format!(
+                "{} (translated from {}; original: {})",
+                translation.text,
+                language_name(&translation.source_language).unwrap_or(&translation.source_language),
+                msg.content
+            ),
+            None => msg.content.clone(),
+        };
+        let mut line = match msg.priority {
+            MessagePriority::Normal => format!("- [{}]: {}", msg.from, content),
+            priority => format!(
+                "- [{}] ({} priority): {}",
+                msg.from,
+                priority.as_str().to_uppercase(),
+                content
+            ),
+        };
+        if let Some(counts) = self.reactions.get(&msg.id).filter(|c| !c.is_empty()) {
+            let summary: Vec<String> = counts
+                .iter()
+                .map(|c| format!("{} reacted {}", c.count, c.emoji))
+                .collect();
+            line.push_str(&format!(" [{}]", summary.join(", ")));
+        }
+        line.push('\n');
+        line
+    }
+}
+
+/// Number of history messages included in the context
+pub const HISTORY_WINDOW: usize = 20;
+
+/// Number of open tasks included in the context
+pub const OPEN_TASKS_WINDOW: usize = 10;
+
+/// Default token budget for the history rendered into the prompt
+pub const HISTORY_TOKEN_BUDGET: usize = 4000;
+
+/// Default token budget for pinned messages rendered into the prompt
+pub const PINNED_TOKEN_BUDGET: usize = 1000;
+
+/// History budget for the retry after a context length error: the reported overflow is cut
+/// from the history actually rendered, with a quarter more taken off because the provider counts
+/// tokens differently from our tokenizer; without numbers the history is halved
+fn shrink_history_budget(used: usize, limit: Option<u32>, requested: Option<u32>) -> usize {
+    match (limit, requested) {
+        (Some(limit), Some(requested)) if requested > limit => {
+            let overflow = (requested - limit) as usize;
+            used.saturating_sub(overflow + overflow / 4)
+        }
+        _ => used / 2,
+    }
+}
+
+/// How the context snapshot at the start of a turn is taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    /// Quiet period (ms) to wait for after a group message before the turn starts,
+    /// so a burst lands in one snapshot; 0 disables
+    #[serde(default = "default_group_debounce_ms")]
+    pub group_debounce_ms: u64,
+    /// Show the content of messages that arrived after the snapshot, not just their count
+    #[serde(default)]
+    pub late_arrival_content: bool,
+    /// Token budget for the "Pinned messages" section, newest pins kept first; 0 leaves it out
+    #[serde(default = "default_pinned_token_budget")]
+    pub pinned_token_budget: usize,
+}
+
+fn default_group_debounce_ms() -> u64 {
+    250
+}
+
+fn default_pinned_token_budget() -> usize {
+    PINNED_TOKEN_BUDGET
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            group_debounce_ms: default_group_debounce_ms(),
+            late_arrival_content: false,
+            pinned_token_budget: default_pinned_token_budget(),
+        }
+    }
+}
+
+/// Messages pinned in the agent's groups and in the direct conversations of `history`, oldest pin first
+pub async fn load_pinned_messages(store: &dyn Store, agent_id: &str, history: &[Message]) -> Result<Vec<Message>> {
+    let mut sessions: Vec<String> = store
+        .load_groups()
+        .await?
+        .into_iter()
+        .filter(|g| g.has_member(agent_id))
+        .map(|g| g.id)
+        .collect();
+    for msg in history.iter().filter(|m| m.is_direct_participant(agent_id)) {
+        let session = msg.session_id();
+        if !sessions.contains(&session) {
+            sessions.push(session);
+        }
+    }
+
+    let mut pins = Vec::new();
+    for session in &sessions {
+        pins.extend(store.load_pins(session).await?);
+    }
+    pins.sort_by_key(|p| p.timestamp);
+
+    let mut pinned = Vec::with_capacity(pins.len());
+    for pin in pins {
+        if let Some(message) = store.load_message(&pin.message_id).await? {
+            pinned.push(message);
+        }
+    }
+    Ok(pinned)
+}
+
+/// Build an agent's context from the store
+///
+/// With `as_of` set, only messages at or before that timestamp are used, which
+/// reconstructs the context the agent would have seen at that moment.
+/// The role revision in effect at that moment is used; preferences are not
+/// versioned, so the current document is always used. Open tasks are only
+/// included for the live context, since task history is not kept.
+pub async fn load_context(store: &dyn Store, agent_id: &str, as_of: Option<i64>) -> Result<Context> {
+    let mut sent_filter = MessageFilter::new().from(agent_id).limit(HISTORY_WINDOW);
+    let mut received_filter = MessageFilter::new()
+        .to(agent_id)
+        .target_type("direct")
+        .limit(HISTORY_WINDOW);
+    if let Some(as_of) = as_of {
+        sent_filter = sent_filter.until(as_of);
+        received_filter = received_filter.until(as_of);
+    }
+
+    let mut history = store.load_messages(sent_filter).await?;
+    for msg in store.load_messages(received_filter).await? {
+        if !history.iter().any(|m| m.id == msg.id) {
+            history.push(msg);
+        }
+    }
+
+    // Keep the newest messages, then present them oldest first; messages of the
+    // same group follow their sequence numbers, which timestamps can tie or skew on
+    history.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| b.id.cmp(&a.id)));
+    history.truncate(HISTORY_WINDOW);
+    history.reverse();
+    order_by_group_seq(&mut history);
+
+    let mut context = Context::default().with_history(history);
+    if let Some(preferences) = store.load_agent_preferences(agent_id).await? {
+        context = context.with_preferences(preferences);
+    }
+    if let Some(revision) = role_in_effect(store, agent_id, as_of).await? {
+        context = context.with_role_revision(revision);
+    }
+    if as_of.is_none() {
+        let tasks = store
+            .load_tasks(TaskFilter::new().assignee(agent_id).open().limit(OPEN_TASKS_WINDOW))
+            .await?;
+        context = context.with_open_tasks(tasks);
+    }
+
+    Ok(context)
 }
\ No newline at end of file
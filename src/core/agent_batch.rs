@@ -0,0 +1,322 @@
+//! Agent 批量操作
+//!
+//! 一次提交多个 Agent 操作（以已有 Agent 为模板创建、修改 LLM 配置、调整部门、设置技能），
+//! 按顺序逐项应用到组织架构上，后面的操作能看到前面操作的结果。每项单独检查 Agent 与部门是否存在、
+//! ID 是否重复和部门人数上限，返回逐项结果。
+//!
+//! 非原子模式下失败的项被跳过，其余照常生效；原子模式（`atomic: true`）在组织架构的副本上应用，
+//! 任一项失败时丢弃副本，已成功的项标记为回滚，原组织架构不变。调用方只需在
+//! [`AgentBatchReport::committed`] 时整体保存一次组织架构。
+//!
+//! 逐项结果不回显 LLM 配置，轮换 API Key 时密钥不会出现在响应中。
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::org::{DepartmentFull, DuplicateId};
+use crate::domain::{Agent, LLMConfig, Organization};
+
+/// 单次批量操作的最大项数
+pub const MAX_BATCH_OPERATIONS: usize = 500;
+
+/// 一批 Agent 操作
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AgentBatch {
+    /// 任一项失败时全部回滚
+    #[serde(default)]
+    pub atomic: bool,
+    pub operations: Vec<AgentOperation>,
+}
+
+impl AgentBatch {
+    pub fn new(operations: Vec<AgentOperation>) -> Self {
+        Self { atomic: false, operations }
+    }
+
+    /// 任一项失败时全部回滚
+    pub fn with_atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// 应用到组织架构，不检查部门范围的权限
+    pub fn apply(&self, org: &mut Organization, now: i64) -> AgentBatchReport {
+        self.apply_authorized(org, now, |_| true)
+    }
+
+    /// 应用到组织架构，`authorize` 对每项涉及的部门（Agent 原部门和目标部门）检查权限
+    pub fn apply_authorized(
+        &self,
+        org: &mut Organization,
+        now: i64,
+        authorize: impl Fn(Option<&str>) -> bool,
+    ) -> AgentBatchReport {
+        let mut working = if self.atomic { Some(org.clone()) } else { None };
+        let target = working.as_mut().unwrap_or(&mut *org);
+
+        let mut results: Vec<AgentOperationResult> = self
+            .operations
+            .iter()
+            .enumerate()
+            .map(|(index, operation)| {
+                let outcome = operation.apply(target, now, &authorize);
+                AgentOperationResult {
+                    index,
+                    op: operation.name(),
+                    agent_id: operation.agent_id().to_string(),
+                    status: if outcome.is_ok() { AgentOperationStatus::Applied } else { AgentOperationStatus::Failed },
+                    error: outcome.err().map(|e| e.to_string()),
+                }
+            })
+            .collect();
+
+        let failed = results.iter().filter(|r| r.status == AgentOperationStatus::Failed).count();
+        let mut applied = results.len() - failed;
+        let committed = match working {
+            Some(working) if failed == 0 => {
+                *org = working;
+                applied > 0
+            }
+            Some(_) => {
+                for result in results.iter_mut().filter(|r| r.status == AgentOperationStatus::Applied) {
+                    result.status = AgentOperationStatus::RolledBack;
+                }
+                applied = 0;
+                false
+            }
+            None => applied > 0,
+        };
+
+        AgentBatchReport {
+            atomic: self.atomic,
+            committed,
+            applied,
+            failed,
+            results,
+        }
+    }
+}
+
+/// 批量中的一项操作
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AgentOperation {
+    /// 以已有 Agent 为模板创建：复制角色、LLM 配置、模式和技能，再按本项覆盖
+    Create {
+        id: String,
+        /// 模板 Agent 的 ID
+        template: String,
+        /// 默认与 ID 相同
+        #[serde(default)]
+        name: Option<String>,
+        /// 默认与模板相同
+        #[serde(default)]
+        department_id: Option<String>,
+        #[serde(default)]
+        llm_config: LlmConfigPatch,
+        #[serde(default)]
+        skills: Option<Vec<String>>,
+    },
+    /// 修改 LLM 配置中给出的字段
+    UpdateLlmConfig { agent_id: String, llm_config: LlmConfigPatch },
+    /// 调整部门
+    ReassignDepartment { agent_id: String, department_id: String },
+    /// 替换技能
+    SetSkills { agent_id: String, skills: Vec<String> },
+}
+
+impl AgentOperation {
+    /// 操作名，与请求中的 `op` 一致
+    pub fn name(&self) -> &'static str {
+        match self {
+            AgentOperation::Create { .. } => "create",
+            AgentOperation::UpdateLlmConfig { .. } => "update_llm_config",
+            AgentOperation::ReassignDepartment { .. } => "reassign_department",
+            AgentOperation::SetSkills { .. } => "set_skills",
+        }
+    }
+
+    /// 操作的 Agent（创建时为新 Agent 的 ID）
+    pub fn agent_id(&self) -> &str {
+        match self {
+            AgentOperation::Create { id, .. } => id,
+            AgentOperation::UpdateLlmConfig { agent_id, .. }
+            | AgentOperation::ReassignDepartment { agent_id, .. }
+            | AgentOperation::SetSkills { agent_id, .. } => agent_id,
+        }
+    }
+
+    fn apply(
+        &self,
+        org: &mut Organization,
+        now: i64,
+        authorize: &impl Fn(Option<&str>) -> bool,
+    ) -> Result<(), AgentOperationError> {
+        match self {
+            AgentOperation::Create { id, template, name, department_id, llm_config, skills } => {
+                let template = org
+                    .find_agent(template)
+                    .ok_or_else(|| AgentOperationError::TemplateNotFound(template.clone()))?;
+                let department_id = department_id.clone().or_else(|| template.department_id.clone());
+                let mut agent = Agent::new(id, name.as_deref().unwrap_or(id), template.role.clone(), template.llm_config.clone())
+                    .with_mode(template.mode.clone())
+                    .with_skills(skills.clone().unwrap_or_else(|| template.skills.clone()))
+                    .with_created_at(now);
+                llm_config.apply_to(&mut agent.llm_config);
+                if let Some(department_id) = &department_id {
+                    check_department(org, department_id, authorize)?;
+                    org.check_headcount(department_id, id)?;
+                    agent = agent.with_department(department_id);
+                } else if !authorize(None) {
+                    return Err(AgentOperationError::Forbidden(None));
+                }
+                org.try_add_agent(agent)?;
+            }
+            AgentOperation::UpdateLlmConfig { agent_id, llm_config } => {
+                if llm_config.is_empty() {
+                    return Err(AgentOperationError::EmptyUpdate);
+                }
+                llm_config.apply_to(&mut find_authorized(org, agent_id, authorize)?.llm_config);
+            }
+            AgentOperation::ReassignDepartment { agent_id, department_id } => {
+                find_authorized(org, agent_id, authorize)?;
+                check_department(org, department_id, authorize)?;
+                org.move_agent(agent_id, department_id)?;
+            }
+            AgentOperation::SetSkills { agent_id, skills } => {
+                find_authorized(org, agent_id, authorize)?.skills = skills.clone();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 找到 Agent 并检查其所在部门的权限
+fn find_authorized<'a>(
+    org: &'a mut Organization,
+    agent_id: &str,
+    authorize: &impl Fn(Option<&str>) -> bool,
+) -> Result<&'a mut Agent, AgentOperationError> {
+    let agent = org
+        .agents
+        .iter_mut()
+        .find(|a| a.id == agent_id)
+        .ok_or_else(|| AgentOperationError::AgentNotFound(agent_id.to_string()))?;
+    if !authorize(agent.department_id.as_deref()) {
+        return Err(AgentOperationError::Forbidden(agent.department_id.clone()));
+    }
+    Ok(agent)
+}
+
+/// 检查目标部门存在且有权限
+fn check_department(
+    org: &Organization,
+    department_id: &str,
+    authorize: &impl Fn(Option<&str>) -> bool,
+) -> Result<(), AgentOperationError> {
+    if org.find_department(department_id).is_none() {
+        return Err(AgentOperationError::DepartmentNotFound(department_id.to_string()));
+    }
+    if !authorize(Some(department_id)) {
+        return Err(AgentOperationError::Forbidden(Some(department_id.to_string())));
+    }
+    Ok(())
+}
+
+/// LLM 配置的部分修改，只改给出的字段
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LlmConfigPatch {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub tokenizer: Option<String>,
+}
+
+impl LlmConfigPatch {
+    /// 没有给出任何字段
+    pub fn is_empty(&self) -> bool {
+        self.model.is_none() && self.api_key.is_none() && self.base_url.is_none() && self.tokenizer.is_none()
+    }
+
+    pub fn apply_to(&self, config: &mut LLMConfig) {
+        if let Some(model) = &self.model {
+            config.model = model.clone();
+        }
+        if let Some(api_key) = &self.api_key {
+            config.api_key = api_key.clone();
+        }
+        if let Some(base_url) = &self.base_url {
+            config.base_url = base_url.clone();
+        }
+        if let Some(tokenizer) = &self.tokenizer {
+            config.tokenizer = Some(tokenizer.clone());
+        }
+    }
+}
+
+// API Key 不出现在日志中
+impl std::fmt::Debug for LlmConfigPatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LlmConfigPatch")
+            .field("model", &self.model)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("base_url", &self.base_url)
+            .field("tokenizer", &self.tokenizer)
+            .finish()
+    }
+}
+
+/// 单项操作失败的原因
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AgentOperationError {
+    #[error("Agent {0} not found")]
+    AgentNotFound(String),
+    #[error("Template agent {0} not found")]
+    TemplateNotFound(String),
+    #[error("Department {0} not found")]
+    DepartmentNotFound(String),
+    #[error(transparent)]
+    Duplicate(#[from] DuplicateId),
+    #[error(transparent)]
+    DepartmentFull(#[from] DepartmentFull),
+    #[error("Not allowed to manage agents in department {}", .0.as_deref().unwrap_or("(none)"))]
+    Forbidden(Option<String>),
+    #[error("No LLM config fields to update")]
+    EmptyUpdate,
+}
+
+/// 单项结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentOperationStatus {
+    Applied,
+    Failed,
+    /// 原子模式下本项成功，但因其他项失败被回滚
+    RolledBack,
+}
+
+/// 单项操作的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentOperationResult {
+    /// 在请求中的位置（从 0 开始）
+    pub index: usize,
+    pub op: &'static str,
+    pub agent_id: String,
+    pub status: AgentOperationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 批量操作的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentBatchReport {
+    pub atomic: bool,
+    /// 组织架构是否有变化，需要保存
+    pub committed: bool,
+    pub applied: usize,
+    pub failed: usize,
+    pub results: Vec<AgentOperationResult>,
+}
@@ -0,0 +1,398 @@
+//! 面试式创建 Agent
+//!
+//! 不熟悉提示词的用户可以通过对话创建 Agent：[`AgentInterviewer`] 以 [`INTERVIEWER_ID`] 的身份
+//! 在普通私聊中依次询问职责、语气、需要的工具和所属部门，全部回答后用内置的 HR 提示词让 LLM
+//! 起草 [`AgentDraft`]（角色定义、建议的技能和关注的工具）。用户审阅、修改草稿后提交，
+//! [`AgentInterviewer::commit`] 按正常的创建流程校验并写入组织架构（含部门人数上限和变更记录）。
+//!
+//! 面试会话保存在存储的应用状态中，断线重连或重启后可以继续回答。
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::messaging::MessageBus;
+use crate::core::org_changes::save_organization_tracked;
+use crate::core::store::Store;
+use crate::domain::org_change::OrgChangeEntry;
+use crate::domain::user::is_user_principal;
+use crate::domain::{Agent, AgentMode, LLMConfig, Message, MessageTarget, Role};
+use crate::infrastructure::llm::{Message as LlmMessage, OpenAIClient};
+
+/// 面试官在消息总线上的 ID，用户回复发给它
+pub const INTERVIEWER_ID: &str = "hr-interviewer";
+
+/// 面试问题（消息目录的键），按顺序提问
+const QUESTIONS: [&str; 4] = [
+    "agent_draft.question_responsibilities",
+    "agent_draft.question_tone",
+    "agent_draft.question_tools",
+    "agent_draft.question_department",
+];
+
+/// 起草角色时的系统提示词
+const HR_PROMPT: &str = "You are the HR partner of a virtual company and write job profiles for new AI employees. \
+Based on the interview below, draft the new employee. Reply with a single JSON object and nothing else:\n\
+{\"agent_id\": \"short lowercase id\", \"name\": \"display name\", \"department_id\": \"one of the listed department ids or null\", \
+\"role\": {\"title\": \"...\", \"responsibilities\": [\"...\"], \"expertise\": [\"...\"], \"system_prompt\": \"second-person instructions for the employee\", \"tone\": \"optional\"}, \
+\"skills\": [\"suggested skill ids\"], \"watched_tools\": [\"tool ids the employee should monitor\"]}";
+
+/// 面试会话状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DraftStatus {
+    /// 正在回答问题
+    Interviewing,
+    /// 草稿已生成，等待审阅和提交
+    Drafted,
+    /// 已创建 Agent
+    Committed,
+}
+
+/// 一问一答
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftAnswer {
+    pub question: String,
+    pub answer: String,
+}
+
+/// Agent 草稿，用户可以在提交前修改任意字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentDraft {
+    pub agent_id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub department_id: Option<String>,
+    pub role: Role,
+    /// 建议的技能
+    #[serde(default)]
+    pub skills: Vec<String>,
+    /// 建议关注的工具，非空时 Agent 以主动模式运行
+    #[serde(default)]
+    pub watched_tools: Vec<String>,
+}
+
+impl AgentDraft {
+    /// 按草稿创建 Agent
+    pub fn to_agent(&self, llm_config: LLMConfig, now: i64) -> Agent {
+        let mode = if self.watched_tools.is_empty() {
+            AgentMode::Passive
+        } else {
+            AgentMode::Active {
+                watched_tools: self.watched_tools.clone(),
+                trigger_conditions: Vec::new(),
+            }
+        };
+        let mut agent = Agent::new(&self.agent_id, &self.name, self.role.clone(), llm_config).with_mode(mode);
+        agent.department_id = self.department_id.clone();
+        agent.skills = self.skills.clone();
+        agent.created_at = Some(now);
+        agent
+    }
+
+    /// 检查必填字段
+    fn validate(&self) -> Result<(), AgentDraftError> {
+        let invalid = |reason: &str| Err(AgentDraftError::Invalid { reason: reason.to_string() });
+        if self.agent_id.trim().is_empty() || self.agent_id.chars().any(char::is_whitespace) {
+            return invalid("agent_id must be non-empty and contain no whitespace");
+        }
+        if is_user_principal(&self.agent_id) || self.agent_id == INTERVIEWER_ID {
+            return invalid("agent_id is reserved");
+        }
+        if self.name.trim().is_empty() {
+            return invalid("name must not be empty");
+        }
+        if self.role.title.trim().is_empty() || self.role.system_prompt.trim().is_empty() {
+            return invalid("role title and system_prompt must not be empty");
+        }
+        Ok(())
+    }
+}
+
+/// 面试会话
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftSession {
+    pub id: String,
+    /// 发起面试的用户（`user:{id}`）
+    pub owner: String,
+    pub status: DraftStatus,
+    pub answers: Vec<DraftAnswer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub draft: Option<AgentDraft>,
+    /// 提交后创建的 Agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl DraftSession {
+    fn key(id: &str) -> String {
+        format!("agent_draft:{}", id)
+    }
+
+    /// 用户正在进行的面试
+    fn owner_key(owner: &str) -> String {
+        format!("agent_draft_owner:{}", owner)
+    }
+
+    /// 下一个要问的问题
+    fn next_question(&self) -> Option<&'static str> {
+        QUESTIONS.get(self.answers.len()).copied()
+    }
+}
+
+/// 草稿无法查看、修改或提交
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AgentDraftError {
+    #[error("Draft session {session_id} not found")]
+    SessionNotFound { session_id: String },
+    #[error("Draft session {session_id} has no draft yet")]
+    NotDrafted { session_id: String },
+    #[error("Draft session {session_id} was already committed as agent {agent_id}")]
+    AlreadyCommitted { session_id: String, agent_id: String },
+    #[error("Invalid agent draft: {reason}")]
+    Invalid { reason: String },
+    #[error("Agent {agent_id} already exists")]
+    AgentExists { agent_id: String },
+    #[error("Department {department_id} does not exist")]
+    DepartmentNotFound { department_id: String },
+}
+
+/// HR 面试官：在私聊中提问，起草并创建 Agent
+pub struct AgentInterviewer {
+    store: Arc<dyn Store>,
+    bus: Arc<MessageBus>,
+    client: OpenAIClient,
+    /// 起草用的模型，也是新 Agent 的默认模型
+    llm: LLMConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl AgentInterviewer {
+    pub fn new(store: Arc<dyn Store>, bus: Arc<MessageBus>, llm: &LLMConfig) -> Self {
+        Self {
+            store,
+            bus,
+            client: OpenAIClient::for_config(llm),
+            llm: llm.clone(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 开始新的面试并发出第一个问题，替代该用户未完成的面试
+    pub async fn start(&self, owner: &str) -> Result<DraftSession> {
+        let now = self.clock.now();
+        let session = DraftSession {
+            id: uuid::Uuid::new_v4().to_string(),
+            owner: owner.to_string(),
+            status: DraftStatus::Interviewing,
+            answers: Vec::new(),
+            draft: None,
+            agent_id: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.save(&session).await?;
+        self.store
+            .save_app_state(&DraftSession::owner_key(owner), &serde_json::json!(session.id))
+            .await?;
+        info!("Started agent draft interview {} for {}", session.id, owner);
+
+        let catalog = self.bus.catalog();
+        let intro = catalog.get("agent_draft.intro");
+        self.say(owner, format!("{}\n\n{}", intro, catalog.get(QUESTIONS[0]))).await;
+        Ok(session)
+    }
+
+    /// 加载面试会话
+    pub async fn session(&self, session_id: &str) -> Result<Option<DraftSession>> {
+        match self.store.load_app_state(&DraftSession::key(session_id)).await? {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 记录用户的回答并提出下一个问题，答完后起草；用户没有进行中的面试时返回 None
+    pub async fn answer(&self, owner: &str, text: &str) -> Result<Option<DraftSession>> {
+        let Some(session_id) = self.store.load_app_state(&DraftSession::owner_key(owner)).await? else {
+            return Ok(None);
+        };
+        let Some(mut session) = self.session(session_id.as_str().unwrap_or_default()).await? else {
+            return Ok(None);
+        };
+        if session.status != DraftStatus::Interviewing {
+            return Ok(None);
+        }
+
+        // 答完后再收到消息时重新起草（上次起草失败）
+        if let Some(question) = session.next_question() {
+            session.answers.push(DraftAnswer {
+                question: self.bus.catalog().get(question),
+                answer: text.to_string(),
+            });
+            session.updated_at = self.clock.now();
+            self.save(&session).await?;
+            if let Some(next) = session.next_question() {
+                self.say(owner, self.bus.catalog().get(next)).await;
+                return Ok(Some(session));
+            }
+        }
+
+        self.draft(&mut session).await?;
+        Ok(Some(session))
+    }
+
+    /// 让 LLM 起草，失败时告知用户可以回复任意内容重试
+    async fn draft(&self, session: &mut DraftSession) -> Result<()> {
+        let catalog = self.bus.catalog();
+        self.say(&session.owner, catalog.get("agent_draft.drafting")).await;
+        let draft = match self.request_draft(session).await {
+            Ok(draft) => draft,
+            Err(e) => {
+                warn!("Failed to draft agent for interview {}: {}", session.id, e);
+                self.say(&session.owner, catalog.get("agent_draft.failed")).await;
+                return Ok(());
+            }
+        };
+
+        let ready = catalog.format(
+            "agent_draft.ready",
+            &[("title", &draft.role.title), ("session_id", &session.id)],
+        );
+        session.draft = Some(draft);
+        session.status = DraftStatus::Drafted;
+        session.updated_at = self.clock.now();
+        self.save(session).await?;
+        self.say(&session.owner, ready).await;
+        Ok(())
+    }
+
+    async fn request_draft(&self, session: &DraftSession) -> Result<AgentDraft> {
+        let org = self.store.load_organization().await?;
+        let departments: Vec<String> = org.departments.iter().map(|d| format!("- {}: {}", d.id, d.name)).collect();
+        let interview: Vec<String> = session
+            .answers
+            .iter()
+            .map(|a| format!("Q: {}\nA: {}", a.question, a.answer))
+            .collect();
+        let prompt = format!(
+            "Departments:\n{}\n\nInterview:\n{}",
+            if departments.is_empty() { "(none)".to_string() } else { departments.join("\n") },
+            interview.join("\n\n")
+        );
+
+        let response = self
+            .client
+            .chat(vec![LlmMessage::system(HR_PROMPT), LlmMessage::user(prompt)])
+            .await?;
+        let json = extract_json(&response).ok_or_else(|| anyhow::anyhow!("no JSON object in draft: {}", response))?;
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// 替换草稿，只能在起草后、提交前修改
+    pub async fn update_draft(&self, session_id: &str, draft: AgentDraft) -> Result<DraftSession> {
+        let mut session = self.drafted(session_id).await?;
+        session.draft = Some(draft);
+        session.updated_at = self.clock.now();
+        self.save(&session).await?;
+        Ok(session)
+    }
+
+    /// 按草稿创建 Agent：校验草稿、ID 不重复、部门存在且未满员，然后保存组织架构并记录变更
+    pub async fn commit(&self, session_id: &str, actor: &str) -> Result<(Agent, Option<OrgChangeEntry>)> {
+        let mut session = self.drafted(session_id).await?;
+        let draft = session.draft.clone().ok_or_else(|| AgentDraftError::NotDrafted {
+            session_id: session_id.to_string(),
+        })?;
+        draft.validate()?;
+
+        let mut org = self.store.load_organization().await?;
+        if org.agents.iter().any(|a| a.id == draft.agent_id) {
+            return Err(AgentDraftError::AgentExists { agent_id: draft.agent_id }.into());
+        }
+        if let Some(department_id) = &draft.department_id {
+            if org.find_department(department_id).is_none() {
+                return Err(AgentDraftError::DepartmentNotFound { department_id: department_id.clone() }.into());
+            }
+        }
+        let agent = draft.to_agent(self.llm.clone(), self.clock.now());
+        org.add_agent_checked(agent.clone())?;
+        let entry = save_organization_tracked(self.store.as_ref(), &org, actor).await?;
+
+        session.status = DraftStatus::Committed;
+        session.agent_id = Some(agent.id.clone());
+        session.updated_at = self.clock.now();
+        self.save(&session).await?;
+        info!("Created agent {} from draft interview {}", agent.id, session.id);
+        Ok((agent, entry))
+    }
+
+    /// 加载已起草、未提交的会话
+    async fn drafted(&self, session_id: &str) -> Result<DraftSession> {
+        let session = self.session(session_id).await?.ok_or_else(|| AgentDraftError::SessionNotFound {
+            session_id: session_id.to_string(),
+        })?;
+        match session.status {
+            DraftStatus::Drafted => Ok(session),
+            DraftStatus::Interviewing => Err(AgentDraftError::NotDrafted { session_id: session.id }.into()),
+            DraftStatus::Committed => Err(AgentDraftError::AlreadyCommitted {
+                session_id: session.id,
+                agent_id: session.agent_id.unwrap_or_default(),
+            }
+            .into()),
+        }
+    }
+
+    async fn save(&self, session: &DraftSession) -> Result<()> {
+        self.store
+            .save_app_state(&DraftSession::key(&session.id), &serde_json::to_value(session)?)
+            .await
+    }
+
+    /// 以面试官身份私聊用户
+    async fn say(&self, owner: &str, text: String) {
+        if let Err(e) = self.bus.send(Message::private(INTERVIEWER_ID, owner, text)).await {
+            warn!("Failed to send interview message to {}: {}", owner, e);
+        }
+    }
+
+    /// 注册到消息总线，把用户发给面试官的私聊当作回答（需在 Tokio 运行时中调用）
+    pub fn spawn(self: Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let mut rx = self.bus.register(INTERVIEWER_ID);
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                if !matches!(message.to, MessageTarget::Direct(_)) {
+                    continue;
+                }
+                if let Err(e) = self.answer(&message.from, &message.content).await {
+                    warn!("Failed to handle interview answer from {}: {}", message.from, e);
+                }
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for AgentInterviewer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentInterviewer")
+            .field("model", &self.llm.model)
+            .finish()
+    }
+}
+
+/// 取出回复中的第一个 JSON 对象（LLM 可能在前后加说明或代码块）
+fn extract_json(response: &str) -> Option<&str> {
+    let start = response.find('{')?;
+    let end = response.rfind('}')?;
+    (start < end).then(|| &response[start..=end])
+}
@@ -0,0 +1,101 @@
+//! 消息归档
+//!
+//! 长期运行的公司会积累大量消息，全部留在热表中会拖慢每次查询。归档任务周期性地把
+//! 超过保留期的消息移入归档（SQLite 中为 `messages_archive` 表）；查询的时间范围
+//! 延伸到归档边界之前时存储会透明地同时查询归档，按ID查找消息也会回退到归档。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::store::Store;
+
+/// 消息归档配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MessageArchiveConfig {
+    /// 热表保留的天数，更早的消息移入归档；为 0 时不归档
+    pub hot_days: u64,
+    /// 归档任务的运行间隔（秒）
+    pub interval_secs: u64,
+}
+
+impl Default for MessageArchiveConfig {
+    fn default() -> Self {
+        Self {
+            hot_days: 0,
+            interval_secs: 3600,
+        }
+    }
+}
+
+impl MessageArchiveConfig {
+    /// 保留最近 `hot_days` 天的消息在热表中
+    pub fn keep_days(hot_days: u64) -> Self {
+        Self {
+            hot_days,
+            ..Self::default()
+        }
+    }
+
+    /// 是否启用归档
+    pub fn is_enabled(&self) -> bool {
+        self.hot_days > 0
+    }
+}
+
+/// 消息归档任务
+pub struct MessageArchiver {
+    store: Arc<dyn Store>,
+    config: MessageArchiveConfig,
+    clock: Arc<dyn Clock>,
+}
+
+impl MessageArchiver {
+    /// 创建归档任务
+    pub fn new(store: Arc<dyn Store>, config: MessageArchiveConfig) -> Self {
+        Self {
+            store,
+            config,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 归档一轮，返回移动的消息数
+    pub async fn run_once(&self) -> Result<usize> {
+        if !self.config.is_enabled() {
+            return Ok(0);
+        }
+        let cutoff = self.clock.now() - (self.config.hot_days * 86_400) as i64;
+        let moved = self.store.archive_messages_before(cutoff).await?;
+        if moved > 0 {
+            info!("Archived {} messages older than {}", moved, cutoff);
+        }
+        Ok(moved)
+    }
+
+    /// 按配置的间隔启动周期归档
+    pub fn spawn(self) -> JoinHandle<()> {
+        let interval = Duration::from_secs(self.config.interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    warn!("Message archiving failed: {}", e);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,78 @@
+//! 附件存储约定
+//!
+//! 附件内容写入 [`BlobStore`]，键为 `attachments/{id}`，自定义元数据记录原始文件名和上传者。
+//! 上传接口（`infrastructure::web::attachments`）和超长消息转附件
+//! （[`enforce_message_limits`](crate::core::message_limits::enforce_message_limits)）共用这里的约定。
+
+use tracing::info;
+
+use crate::infrastructure::blob::{BlobBody, BlobInfo, BlobMetadata, BlobStore};
+
+/// 附件在大对象存储中的键前缀
+pub const ATTACHMENT_PREFIX: &str = "attachments/";
+
+/// 自定义元数据：原始文件名（百分号编码，S3 元数据只允许 ASCII）
+pub const META_FILENAME: &str = "filename";
+
+/// 自定义元数据：上传者
+pub const META_UPLOADED_BY: &str = "uploaded-by";
+
+/// 附件的存储键
+pub fn attachment_key(id: &str) -> String {
+    format!("{}{}", ATTACHMENT_PREFIX, id)
+}
+
+/// 附件的下载路径
+pub fn attachment_url(id: &str) -> String {
+    format!("/api/v1/attachments/{}", id)
+}
+
+/// 以 `id` 保存附件，记录文件名和上传者
+pub async fn save_attachment(
+    blobs: &dyn BlobStore,
+    id: &str,
+    name: &str,
+    content_type: &str,
+    uploader: &str,
+    body: BlobBody,
+) -> anyhow::Result<BlobInfo> {
+    let metadata = BlobMetadata::new()
+        .with_content_type(content_type)
+        .with(META_FILENAME, percent_encode(name))
+        .with(META_UPLOADED_BY, uploader);
+    let blob = blobs.put(&attachment_key(id), body, metadata).await?;
+    info!(attachment_id = %id, size = blob.size, backend = blobs.backend(), uploader = %uploader, "Attachment uploaded");
+    Ok(blob)
+}
+
+/// 百分号编码，保留 RFC 5987 的 attr-char
+pub fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// [`percent_encode`] 的逆操作，无效的编码原样保留
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
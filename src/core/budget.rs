@@ -0,0 +1,117 @@
+//! 部门 LLM 预算
+//!
+//! 部门可以设置每日 token 预算（`Department::llm_budget`），由部门内的 Agent 共享。
+//! 每次 LLM 调用前汇总部门成员当天（UTC）的用量，达到预算后该部门的 Agent 暂停思考，
+//! 次日自动恢复。
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::domain::Organization;
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// 部门当天的 token 用量已达到预算
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Department {department_id} reached its daily LLM budget ({used}/{budget} tokens)")]
+pub struct BudgetExceeded {
+    pub department_id: String,
+    pub used: u64,
+    pub budget: u64,
+}
+
+/// 按部门汇总 Agent 的 token 用量并检查预算
+pub struct DepartmentBudgets {
+    clock: Arc<dyn Clock>,
+    /// 部门 ID -> 每日 token 预算
+    budgets: DashMap<String, u64>,
+    /// Agent ID -> 所属部门
+    members: DashMap<String, String>,
+    /// Agent ID -> (日期序号, 当天用量)
+    usage: DashMap<String, (i64, u64)>,
+}
+
+impl DepartmentBudgets {
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            budgets: DashMap::new(),
+            members: DashMap::new(),
+            usage: DashMap::new(),
+        }
+    }
+
+    /// 按组织架构更新部门预算和成员关系，已记录的用量保留
+    pub fn sync(&self, org: &Organization) {
+        self.budgets.clear();
+        for dept in &org.departments {
+            if let Some(budget) = dept.llm_budget {
+                self.budgets.insert(dept.id.clone(), budget);
+            }
+        }
+        self.members.clear();
+        for agent in &org.agents {
+            if let Some(dept_id) = &agent.department_id {
+                self.members.insert(agent.id.clone(), dept_id.clone());
+            }
+        }
+    }
+
+    /// 记录 Agent 一次 LLM 调用消耗的 token
+    pub fn record(&self, agent_id: &str, tokens: u64) {
+        if tokens == 0 {
+            return;
+        }
+        let today = self.today();
+        let mut entry = self.usage.entry(agent_id.to_string()).or_insert((today, 0));
+        if entry.0 != today {
+            *entry = (today, 0);
+        }
+        entry.1 += tokens;
+    }
+
+    /// 部门成员当天的 token 用量之和
+    pub fn department_usage(&self, dept_id: &str) -> u64 {
+        let today = self.today();
+        self.members
+            .iter()
+            .filter(|m| m.value() == dept_id)
+            .filter_map(|m| self.usage.get(m.key()).filter(|u| u.0 == today).map(|u| u.1))
+            .sum()
+    }
+
+    /// Agent 所在部门是否还有预算；没有部门或部门未设预算时总是通过
+    pub fn check(&self, agent_id: &str) -> Result<(), BudgetExceeded> {
+        let Some(dept_id) = self.members.get(agent_id).map(|d| d.value().clone()) else {
+            return Ok(());
+        };
+        let Some(budget) = self.budgets.get(&dept_id).map(|b| *b.value()) else {
+            return Ok(());
+        };
+        let used = self.department_usage(&dept_id);
+        if used >= budget {
+            return Err(BudgetExceeded {
+                department_id: dept_id,
+                used,
+                budget,
+            });
+        }
+        Ok(())
+    }
+
+    fn today(&self) -> i64 {
+        self.clock.now().div_euclid(SECS_PER_DAY)
+    }
+}
+
+impl Default for DepartmentBudgets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -230,7 +230,7 @@ impl FrameworkCapabilityProvider {
     }
 
     fn create_capability_discovery() -> Capability {
-        use crate::domain::capability::{CapabilityPath, InputSchema, OutputSchema};
+        use crate::domain::capability::{CapabilityPath, JsonSchema};
         use serde_json::json;
 
         Capability::new(
@@ -238,18 +238,18 @@ impl FrameworkCapabilityProvider {
             "MCP Capability Discovery",
             "Discover available MCP capabilities",
             CapabilityPath::from_str("mcp/discovery"),
-            InputSchema::object()
+            JsonSchema::object()
                 .property(
                     "requested",
-                    InputSchema::string_array()
+                    JsonSchema::string_array()
                         .description("List of requested capability names")
                         .optional(),
                 )
                 .build(),
-            OutputSchema::object()
+            JsonSchema::object()
                 .property(
                     "capabilities",
-                    OutputSchema::array(json!({
+                    JsonSchema::array(json!({
                         "type": "object",
                         "properties": {
                             "name": {"type": "string"},
@@ -266,7 +266,7 @@ impl FrameworkCapabilityProvider {
     }
 
     fn create_capability_list() -> Capability {
-        use crate::domain::capability::{CapabilityPath, InputSchema, OutputSchema};
+        use crate::domain::capability::{CapabilityPath, JsonSchema};
         use serde_json::json;
 
         Capability::new(
@@ -274,11 +274,11 @@ impl FrameworkCapabilityProvider {
             "List Capabilities",
             "List all available capabilities",
             CapabilityPath::from_str("mcp/discovery"),
-            InputSchema::object().build(),
-            OutputSchema::object()
+            JsonSchema::object().build(),
+            JsonSchema::object()
                 .property(
                     "capabilities",
-                    OutputSchema::array(json!({
+                    JsonSchema::array(json!({
                         "type": "object",
                         "properties": {
                             "id": {"type": "string"},
@@ -297,21 +297,21 @@ impl FrameworkCapabilityProvider {
     }
 
     fn create_capability_info() -> Capability {
-        use crate::domain::capability::{CapabilityPath, InputSchema, OutputSchema};
+        use crate::domain::capability::{CapabilityPath, JsonSchema};
 
         Capability::new(
             "mcp.info",
             "Get Capability Info",
             "Get detailed information about a specific capability",
             CapabilityPath::from_str("mcp/discovery"),
-            InputSchema::object()
+            JsonSchema::object()
                 .property(
                     "capability_id",
-                    InputSchema::string().description("ID of the capability to get info for"),
+                    JsonSchema::string().description("ID of the capability to get info for"),
                 )
                 .build(),
-            OutputSchema::object()
-                .raw_property("capability", OutputSchema::object().build(), false)
+            JsonSchema::object()
+                .raw_property("capability", JsonSchema::object().build(), false)
                 .build(),
             "http".to_string(),
             Some("/mcp/info".to_string()),
@@ -319,16 +319,16 @@ impl FrameworkCapabilityProvider {
     }
 
     fn create_mcp_ping() -> Capability {
-        use crate::domain::capability::{CapabilityPath, InputSchema, OutputSchema};
+        use crate::domain::capability::{CapabilityPath, JsonSchema};
 
         Capability::new(
             "mcp.ping",
             "MCP Ping",
             "Ping the MCP server to check connectivity",
             CapabilityPath::from_str("mcp/protocol"),
-            InputSchema::object().build(),
-            OutputSchema::object()
-                .property("result", OutputSchema::string().description("Ping result"))
+            JsonSchema::object().build(),
+            JsonSchema::object()
+                .property("result", JsonSchema::string().description("Ping result"))
                 .build(),
             "http".to_string(),
             Some("/mcp/ping".to_string()),
@@ -336,7 +336,7 @@ impl FrameworkCapabilityProvider {
     }
 
     fn create_mcp_initialize() -> Capability {
-        use crate::domain::capability::{CapabilityPath, InputSchema, OutputSchema};
+        use crate::domain::capability::{CapabilityPath, JsonSchema};
         use serde_json::json;
 
         Capability::new(
@@ -344,21 +344,21 @@ impl FrameworkCapabilityProvider {
             "MCP Initialize",
             "Initialize the MCP connection",
             CapabilityPath::from_str("mcp/protocol"),
-            InputSchema::object()
+            JsonSchema::object()
                 .raw_property(
                     "client_info",
-                    InputSchema::object()
-                        .property("name", InputSchema::string().description("Client name"))
+                    JsonSchema::object()
+                        .property("name", JsonSchema::string().description("Client name"))
                         .property(
                             "version",
-                            InputSchema::string().description("Client version").optional(),
+                            JsonSchema::string().description("Client version").optional(),
                         )
                         .build(),
                     true
                 )
                 .property(
                     "capabilities",
-                    InputSchema::array(json!({
+                    JsonSchema::array(json!({
                         "type": "object",
                         "properties": {
                             "name": {"type": "string"},
@@ -369,14 +369,14 @@ impl FrameworkCapabilityProvider {
                     .optional(),
                 )
                 .build(),
-            OutputSchema::object()
+            JsonSchema::object()
                 .raw_property(
                     "server_info",
-                    OutputSchema::object()
-                        .property("name", OutputSchema::string().description("Server name"))
+                    JsonSchema::object()
+                        .property("name", JsonSchema::string().description("Server name"))
                         .property(
                             "version",
-                            OutputSchema::string().description("Server version"),
+                            JsonSchema::string().description("Server version"),
                         )
                         .build(),
                     true
@@ -388,26 +388,26 @@ impl FrameworkCapabilityProvider {
     }
 
     fn create_mcp_server_notification() -> Capability {
-        use crate::domain::capability::{CapabilityPath, InputSchema, OutputSchema};
+        use crate::domain::capability::{CapabilityPath, JsonSchema};
 
         Capability::new(
             "mcp.server.notification",
             "MCP Server Notification",
             "Handle server-initiated notifications",
             CapabilityPath::from_str("mcp/protocol"),
-            InputSchema::object()
-                .property("method", InputSchema::string().description("Notification method"))
+            JsonSchema::object()
+                .property("method", JsonSchema::string().description("Notification method"))
                 .raw_property(
                     "params",
-                    InputSchema::object()
-                        .property("type", InputSchema::string().description("Parameter type"))
-                        .property("value", InputSchema::string().description("Parameter value"))
+                    JsonSchema::object()
+                        .property("type", JsonSchema::string().description("Parameter type"))
+                        .property("value", JsonSchema::string().description("Parameter value"))
                         .build(),
                     true
                 )
                 .build(),
-            OutputSchema::object()
-                .property("success", OutputSchema::boolean().description("Success status"))
+            JsonSchema::object()
+                .property("success", JsonSchema::boolean().description("Success status"))
                 .build(),
             "websocket".to_string(),
             None,
@@ -415,26 +415,26 @@ impl FrameworkCapabilityProvider {
     }
 
     fn create_mcp_server_request() -> Capability {
-        use crate::domain::capability::{CapabilityPath, InputSchema, OutputSchema};
+        use crate::domain::capability::{CapabilityPath, JsonSchema};
 
         Capability::new(
             "mcp.server.request",
             "MCP Server Request",
             "Handle server-initiated requests",
             CapabilityPath::from_str("mcp/protocol"),
-            InputSchema::object()
-                .property("method", InputSchema::string().description("Request method"))
+            JsonSchema::object()
+                .property("method", JsonSchema::string().description("Request method"))
                 .raw_property(
                     "params",
-                    InputSchema::object()
-                        .property("type", InputSchema::string().description("Parameter type"))
-                        .property("value", InputSchema::string().description("Parameter value"))
+                    JsonSchema::object()
+                        .property("type", JsonSchema::string().description("Parameter type"))
+                        .property("value", JsonSchema::string().description("Parameter value"))
                         .build(),
                     true
                 )
                 .build(),
-            OutputSchema::object()
-                .raw_property("result", OutputSchema::object().build(), false)
+            JsonSchema::object()
+                .raw_property("result", JsonSchema::object().build(), false)
                 .build(),
             "websocket".to_string(),
             None,
@@ -0,0 +1,320 @@
+//! 故障注入（`chaos` 特性）
+//!
+//! 用于多节点部署前的韧性测试：LLM 客户端、存储包装（[`ChaosStore`](crate::core::store::ChaosStore)）、
+//! 消息总线和工具执行器注册表在关键调用前询问 [`FaultInjector`]，按规则注入延迟、错误或丢弃投递。
+//!
+//! - 规则按子系统（可选再按操作名）匹配，按概率触发，可限定生效时间窗口
+//! - 注入器默认关闭，必须显式启用；关闭时不注入任何故障
+//! - 每次注入都会写一条 `chaos` 目标的日志并记入历史，测试可据此把观察到的行为与注入的故障对应起来
+//!
+//! 运行时可通过管理接口 `POST /api/admin/chaos/rules` 调整规则（仅董事长）。
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::core::clock::{Clock, SystemClock};
+
+/// 注入日志使用的 tracing 目标
+pub const CHAOS_LOG_TARGET: &str = "chaos";
+
+/// 保留的注入历史条数
+const HISTORY_LIMIT: usize = 1000;
+
+/// 可注入故障的子系统
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Subsystem {
+    Llm,
+    Store,
+    Messaging,
+    Tools,
+}
+
+impl std::fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Llm => "llm",
+            Self::Store => "store",
+            Self::Messaging => "messaging",
+            Self::Tools => "tools",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 故障类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FaultKind {
+    /// 调用前等待指定毫秒，之后正常执行
+    Latency { ms: u64 },
+    /// 调用失败
+    Error,
+    /// 丢弃（消息不投递；其他子系统按失败处理）
+    Drop,
+}
+
+/// 故障规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FaultRule {
+    #[serde(default = "new_rule_id")]
+    pub id: String,
+    pub subsystem: Subsystem,
+    pub fault: FaultKind,
+    /// 触发概率（0.0-1.0），默认每次都触发
+    #[serde(default = "always")]
+    pub probability: f64,
+    /// 只对该操作生效（如 `chat`、`save_message`、`send`、工具 ID），为空时对子系统所有操作生效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation: Option<String>,
+    /// 生效窗口起点（毫秒时间戳，包含）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from_ms: Option<i64>,
+    /// 生效窗口终点（毫秒时间戳，不含）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until_ms: Option<i64>,
+}
+
+fn new_rule_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn always() -> f64 {
+    1.0
+}
+
+impl FaultRule {
+    /// 创建每次都触发的规则
+    pub fn new(subsystem: Subsystem, fault: FaultKind) -> Self {
+        Self {
+            id: new_rule_id(),
+            subsystem,
+            fault,
+            probability: 1.0,
+            operation: None,
+            active_from_ms: None,
+            active_until_ms: None,
+        }
+    }
+
+    /// 设置触发概率
+    pub fn with_probability(mut self, probability: f64) -> Self {
+        self.probability = probability;
+        self
+    }
+
+    /// 只对指定操作生效
+    pub fn with_operation(mut self, operation: impl Into<String>) -> Self {
+        self.operation = Some(operation.into());
+        self
+    }
+
+    /// 只在时间窗口内生效（毫秒时间戳，左闭右开）
+    pub fn with_window(mut self, from_ms: i64, until_ms: i64) -> Self {
+        self.active_from_ms = Some(from_ms);
+        self.active_until_ms = Some(until_ms);
+        self
+    }
+
+    /// 概率需在 0.0-1.0 之间，窗口不能为空
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.probability) {
+            return Err(format!("probability must be between 0 and 1, got {}", self.probability));
+        }
+        if let (Some(from), Some(until)) = (self.active_from_ms, self.active_until_ms) {
+            if from >= until {
+                return Err(format!("active window is empty ({} >= {})", from, until));
+            }
+        }
+        Ok(())
+    }
+
+    fn applies(&self, subsystem: Subsystem, operation: &str, now_ms: i64) -> bool {
+        self.subsystem == subsystem
+            && self.operation.as_deref().is_none_or(|op| op == operation)
+            && self.active_from_ms.is_none_or(|from| now_ms >= from)
+            && self.active_until_ms.is_none_or(|until| now_ms < until)
+    }
+}
+
+/// 一次注入的故障
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, thiserror::Error)]
+#[error("chaos: injected {fault:?} into {subsystem}/{operation} (rule {rule_id})")]
+pub struct InjectedFault {
+    pub rule_id: String,
+    pub subsystem: Subsystem,
+    pub operation: String,
+    pub fault: FaultKind,
+    /// 注入时间（毫秒时间戳）
+    pub at_ms: i64,
+}
+
+impl InjectedFault {
+    /// 是否为丢弃
+    pub fn is_drop(&self) -> bool {
+        self.fault == FaultKind::Drop
+    }
+}
+
+/// 错误链中是否有注入的故障
+pub fn is_injected(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<InjectedFault>())
+}
+
+/// 故障注入器
+pub struct FaultInjector {
+    enabled: AtomicBool,
+    rules: RwLock<Vec<FaultRule>>,
+    rng: Mutex<StdRng>,
+    clock: Arc<dyn Clock>,
+    history: Mutex<VecDeque<InjectedFault>>,
+}
+
+impl std::fmt::Debug for FaultInjector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FaultInjector")
+            .field("enabled", &self.is_enabled())
+            .field("rules", &self.rules())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for FaultInjector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FaultInjector {
+    /// 创建注入器（默认关闭，没有规则）
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            rules: RwLock::new(Vec::new()),
+            rng: Mutex::new(StdRng::from_entropy()),
+            clock: Arc::new(SystemClock),
+            history: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// 设置是否启用
+    pub fn with_enabled(self, enabled: bool) -> Self {
+        self.set_enabled(enabled);
+        self
+    }
+
+    /// 使用固定随机种子，便于复现
+    pub fn with_seed(self, seed: u64) -> Self {
+        *self.rng.lock().unwrap() = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// 使用指定时钟判断时间窗口（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 启用或关闭注入
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 是否启用
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// 添加规则，返回规则 ID
+    pub fn add_rule(&self, rule: FaultRule) -> String {
+        let id = rule.id.clone();
+        self.rules.write().unwrap().push(rule);
+        id
+    }
+
+    /// 替换全部规则
+    pub fn set_rules(&self, rules: Vec<FaultRule>) {
+        *self.rules.write().unwrap() = rules;
+    }
+
+    /// 删除规则
+    pub fn remove_rule(&self, rule_id: &str) -> bool {
+        let mut rules = self.rules.write().unwrap();
+        let before = rules.len();
+        rules.retain(|r| r.id != rule_id);
+        rules.len() != before
+    }
+
+    /// 当前规则
+    pub fn rules(&self) -> Vec<FaultRule> {
+        self.rules.read().unwrap().clone()
+    }
+
+    /// 最近注入的故障（最早的在前）
+    pub fn history(&self) -> Vec<InjectedFault> {
+        self.history.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// 某个子系统被注入的故障数（在保留的历史内）
+    pub fn injected_count(&self, subsystem: Subsystem) -> usize {
+        self.history.lock().unwrap().iter().filter(|f| f.subsystem == subsystem).count()
+    }
+
+    /// 清空注入历史
+    pub fn clear_history(&self) {
+        self.history.lock().unwrap().clear();
+    }
+
+    /// 调用前询问注入器
+    ///
+    /// 匹配的延迟规则会先等待；命中错误或丢弃规则时返回对应的故障，调用方应放弃本次操作。
+    pub async fn inject(&self, subsystem: Subsystem, operation: &str) -> Result<(), InjectedFault> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let now_ms = self.clock.now_millis();
+        let triggered: Vec<FaultRule> = {
+            let rules = self.rules.read().unwrap();
+            let mut rng = self.rng.lock().unwrap();
+            rules
+                .iter()
+                .filter(|rule| rule.applies(subsystem, operation, now_ms))
+                .filter(|rule| rng.gen_bool(rule.probability.clamp(0.0, 1.0)))
+                .cloned()
+                .collect()
+        };
+
+        for rule in triggered {
+            let fault = InjectedFault {
+                rule_id: rule.id,
+                subsystem,
+                operation: operation.to_string(),
+                fault: rule.fault,
+                at_ms: now_ms,
+            };
+            self.record(&fault);
+            match fault.fault {
+                FaultKind::Latency { ms } => tokio::time::sleep(Duration::from_millis(ms)).await,
+                FaultKind::Error | FaultKind::Drop => return Err(fault),
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&self, fault: &InjectedFault) {
+        warn!(target: CHAOS_LOG_TARGET, "{}", fault);
+        let mut history = self.history.lock().unwrap();
+        if history.len() == HISTORY_LIMIT {
+            history.pop_front();
+        }
+        history.push_back(fault.clone());
+    }
+}
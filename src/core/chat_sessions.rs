@@ -0,0 +1,366 @@
+//! 会话生命周期：按时结束的会话与总结
+//!
+//! 客服式的使用需要会话正式结束。每个 Agent 同一时间有一个当前会话，由用户与它之间的私聊开启；
+//! 会话被显式关闭（`POST /chat/{id}/close` 或 Agent 调用 `session.close`），或闲置超过
+//! [`ChatSessionConfig::inactivity_secs`] 后自动关闭。关闭时用总结器总结会话中的消息，
+//! 连同解决状态作为会话的产物保存；总结失败时产物仍然保存，并注明原因。
+//!
+//! 用户向已关闭的会话发消息时，按 [`ClosedSessionPolicy`] 重新打开它或开启新会话。
+//! Agent 自己发出的消息不会重新打开会话。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::store::{MessageFilter, Store};
+use crate::core::tokenizer::{HeuristicTokenizer, Tokenizer};
+use crate::domain::{ChatSession, Message, MessageTarget, SessionArtifact, SessionResolution};
+
+/// Agent 关闭会话使用的工具
+pub const SESSION_CLOSE_TOOL: &str = "session.close";
+
+/// 闲置自动关闭时记录的关闭者
+pub const INACTIVITY_CLOSER: &str = "system";
+
+/// 总结时读取的消息上限
+const MAX_SESSION_MESSAGES: usize = 10_000;
+
+/// 用户向已关闭的会话发消息时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClosedSessionPolicy {
+    /// 重新打开原会话
+    #[default]
+    Reopen,
+    /// 原会话保持关闭，开启新会话
+    NewSession,
+}
+
+/// 会话生命周期配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ChatSessionConfig {
+    /// 是否记录会话（同时决定是否提供 `session.close` 工具）
+    pub enabled: bool,
+    /// 会话闲置多久后自动关闭（秒），0 表示不自动关闭
+    pub inactivity_secs: u64,
+    /// 检查闲置会话的间隔（秒）
+    pub check_secs: u64,
+    /// 用户向已关闭的会话发消息时的处理方式
+    pub on_message_after_close: ClosedSessionPolicy,
+    /// 总结时提供给 LLM 的消息 token 上限，超出时保留最新的消息
+    pub token_budget: usize,
+}
+
+impl Default for ChatSessionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            inactivity_secs: 30 * 60,
+            check_secs: 60,
+            on_message_after_close: ClosedSessionPolicy::Reopen,
+            token_budget: 2000,
+        }
+    }
+}
+
+/// 会话操作错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChatSessionError {
+    #[error("Chat session {session_id} not found")]
+    NotFound { session_id: String },
+    #[error("Chat session {session_id} is already closed")]
+    AlreadyClosed { session_id: String },
+    #[error("Chat session {session_id} is not closed")]
+    NotClosed { session_id: String },
+}
+
+/// 总结一段会话
+#[async_trait]
+pub trait SessionSummarizer: Send + Sync {
+    /// 总结与 `agent_name` 的会话（每行 `发送者: 内容`，按时间先后）
+    async fn summarize_session(&self, agent_name: &str, transcript: &str) -> Result<String>;
+}
+
+/// 会话管理器
+pub struct ChatSessions {
+    config: ChatSessionConfig,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    summarizer: Option<Arc<dyn SessionSummarizer>>,
+    tokenizer: Arc<dyn Tokenizer>,
+    /// 串行化会话的读改写，避免消息与关闭同时修改同一会话
+    lock: Mutex<()>,
+}
+
+impl ChatSessions {
+    pub fn new(config: ChatSessionConfig, store: Arc<dyn Store>) -> Self {
+        Self {
+            config,
+            store,
+            clock: Arc::new(SystemClock),
+            summarizer: None,
+            tokenizer: Arc::new(HeuristicTokenizer),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置总结器，未设置时产物只有解决状态
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn SessionSummarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// 设置计算 token 上限使用的分词器
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    pub fn config(&self) -> &ChatSessionConfig {
+        &self.config
+    }
+
+    /// 会话列表，按创建时间最早的在前
+    pub async fn list(&self, include_closed: bool) -> Result<Vec<ChatSession>> {
+        self.store.load_chat_sessions(include_closed).await
+    }
+
+    /// Agent 的当前会话（最近开启的一个）
+    pub async fn current(&self, agent_id: &str) -> Result<Option<ChatSession>> {
+        let sessions = self.store.load_chat_sessions(true).await?;
+        Ok(sessions.into_iter().rev().find(|s| s.agent_id == agent_id))
+    }
+
+    /// 按会话 ID 查找；不是会话 ID 时视为 Agent ID，返回它的当前会话
+    pub async fn resolve(&self, id: &str) -> Result<Option<ChatSession>> {
+        match self.store.load_chat_session(id).await? {
+            Some(session) => Ok(Some(session)),
+            None => self.current(id).await,
+        }
+    }
+
+    /// 记录一条私聊：开启或更新对应 Agent 的当前会话，返回受影响的会话
+    ///
+    /// 接收方是 Agent 时视为用户发给它的消息，否则发送方是 Agent 时视为它的回复；
+    /// 群聊和 Agent 之间的私聊之外的消息忽略。
+    pub async fn record_message(&self, message: &Message) -> Result<Option<ChatSession>> {
+        let MessageTarget::Direct(to) = &message.to else {
+            return Ok(None);
+        };
+        let (agent_id, inbound) = if self.store.load_agent(to).await?.is_some() {
+            (to.as_str(), true)
+        } else if self.store.load_agent(&message.from).await?.is_some() {
+            (message.from.as_str(), false)
+        } else {
+            return Ok(None);
+        };
+
+        let _guard = self.lock.lock().await;
+        let now = self.clock.now();
+        let session = match self.current(agent_id).await? {
+            None => ChatSession::open(agent_id, agent_id, now),
+            Some(mut session) if !session.is_closed() => {
+                session.last_activity_at = now;
+                session
+            }
+            // Agent 在会话关闭后的回复不改变会话
+            Some(_) if !inbound => return Ok(None),
+            Some(mut session) => match self.config.on_message_after_close {
+                ClosedSessionPolicy::Reopen => {
+                    session.reopen(now);
+                    info!(session_id = %session.id, "Chat session reopened by new message");
+                    session
+                }
+                ClosedSessionPolicy::NewSession => {
+                    let id = format!("{}-{}", agent_id, &uuid::Uuid::new_v4().simple().to_string()[..8]);
+                    info!(session_id = %id, previous = %session.id, "New chat session after close");
+                    ChatSession::open(id, agent_id, now)
+                }
+            },
+        };
+        self.store.save_chat_session(&session).await?;
+        Ok(Some(session))
+    }
+
+    /// 关闭会话并生成产物；`id` 为会话 ID 或 Agent ID
+    pub async fn close(
+        &self,
+        id: &str,
+        resolution: SessionResolution,
+        closed_by: &str,
+        note: Option<String>,
+    ) -> Result<ChatSession> {
+        let _guard = self.lock.lock().await;
+        let mut session = self.resolve(id).await?.ok_or_else(|| ChatSessionError::NotFound {
+            session_id: id.to_string(),
+        })?;
+        if session.is_closed() {
+            return Err(ChatSessionError::AlreadyClosed { session_id: session.id }.into());
+        }
+        self.close_session(&mut session, resolution, closed_by, note).await?;
+        Ok(session)
+    }
+
+    /// 重新打开已关闭的会话；`id` 为会话 ID 或 Agent ID
+    pub async fn reopen(&self, id: &str) -> Result<ChatSession> {
+        let _guard = self.lock.lock().await;
+        let mut session = self.resolve(id).await?.ok_or_else(|| ChatSessionError::NotFound {
+            session_id: id.to_string(),
+        })?;
+        if !session.is_closed() {
+            return Err(ChatSessionError::NotClosed { session_id: session.id }.into());
+        }
+        session.reopen(self.clock.now());
+        self.store.save_chat_session(&session).await?;
+        info!(target: "audit", session_id = %session.id, "Chat session reopened");
+        Ok(session)
+    }
+
+    /// 关闭闲置超时的会话，返回关闭的会话
+    pub async fn close_inactive(&self) -> Result<Vec<ChatSession>> {
+        if self.config.inactivity_secs == 0 {
+            return Ok(Vec::new());
+        }
+        let _guard = self.lock.lock().await;
+        let deadline = self.clock.now() - self.config.inactivity_secs as i64;
+        let mut closed = Vec::new();
+        for mut session in self.store.load_chat_sessions(false).await? {
+            if session.last_activity_at > deadline {
+                continue;
+            }
+            self.close_session(&mut session, SessionResolution::Inactive, INACTIVITY_CLOSER, None)
+                .await?;
+            closed.push(session);
+        }
+        Ok(closed)
+    }
+
+    async fn close_session(
+        &self,
+        session: &mut ChatSession,
+        resolution: SessionResolution,
+        closed_by: &str,
+        note: Option<String>,
+    ) -> Result<()> {
+        let artifact = self.artifact(session, resolution, note).await?;
+        session.close(closed_by, artifact);
+        self.store.save_chat_session(session).await?;
+        info!(
+            target: "audit",
+            session_id = %session.id,
+            closed_by = %closed_by,
+            "Chat session closed as {}",
+            resolution.as_str()
+        );
+        Ok(())
+    }
+
+    /// 总结会话中的消息；token 上限内保留最新的消息，总结失败时注明原因
+    async fn artifact(
+        &self,
+        session: &ChatSession,
+        resolution: SessionResolution,
+        note: Option<String>,
+    ) -> Result<SessionArtifact> {
+        let mut filter = MessageFilter::new()
+            .participant(session.agent_id.clone())
+            .target_type("direct")
+            .since(session.created_at)
+            .oldest_first();
+        filter.limit = MAX_SESSION_MESSAGES;
+        let messages = self.store.load_messages(filter).await?;
+
+        let mut artifact = SessionArtifact {
+            resolution,
+            summary: None,
+            summary_error: None,
+            note: note.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+            message_count: messages.len(),
+            generated_at: self.clock.now(),
+        };
+        let Some(summarizer) = &self.summarizer else {
+            return Ok(artifact);
+        };
+        if messages.is_empty() {
+            return Ok(artifact);
+        }
+
+        let mut lines = Vec::new();
+        let mut remaining = self.config.token_budget;
+        for message in messages.iter().rev() {
+            let line = format!("{}: {}", message.from, message.content);
+            let tokens = self.tokenizer.count(&line) + 1;
+            if tokens > remaining {
+                break;
+            }
+            remaining -= tokens;
+            lines.push(line);
+        }
+        lines.reverse();
+
+        let agent_name = self
+            .store
+            .load_agent(&session.agent_id)
+            .await?
+            .map(|agent| agent.name)
+            .unwrap_or_else(|| session.agent_id.clone());
+        match summarizer.summarize_session(&agent_name, &lines.join("\n")).await {
+            Ok(summary) => artifact.summary = Some(summary.trim().to_string()),
+            Err(e) => {
+                warn!("Failed to summarize chat session {}: {}", session.id, e);
+                artifact.summary_error = Some(e.to_string());
+            }
+        }
+        Ok(artifact)
+    }
+
+    /// 监听落库的消息，并按 `check_secs` 关闭闲置会话
+    pub fn spawn(self: Arc<Self>, events: &EventBus) -> JoinHandle<()> {
+        events.register_listener(Box::new(ChatSessionListener(self.clone())));
+        let interval = Duration::from_secs(self.config.check_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.close_inactive().await {
+                    warn!("Failed to close inactive chat sessions: {}", e);
+                }
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for ChatSessions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChatSessions").field("config", &self.config).finish()
+    }
+}
+
+struct ChatSessionListener(Arc<ChatSessions>);
+
+#[async_trait]
+impl CompanyEventListener for ChatSessionListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        let CompanyEvent::MessagePersisted { message } = event else {
+            return;
+        };
+        if let Err(e) = self.0.record_message(message).await {
+            warn!("Failed to record message {} in chat session: {}", message.id, e);
+        }
+    }
+}
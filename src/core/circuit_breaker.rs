@@ -0,0 +1,458 @@
+//! Agent 熔断
+//!
+//! 配置有误的 Agent（base_url 写错、API key 被吊销）每轮都会失败，一直重试只会刷日志、占用轮次调度。
+//! 每个 Agent 有一个熔断器：
+//!
+//! - 连续 `llm_failure_threshold` 次 LLM 调用失败（鉴权失败不等阈值，立即熔断），或 `tool_window_secs` 内工具调用不少于
+//!   `tool_min_calls` 次且失败率达到 `tool_error_rate` 时熔断，Agent 进入故障状态（faulted）
+//! - 故障期间 Agent 不进行轮次，收到的消息留在信箱里
+//! - 退避结束后进入半开状态，试探一轮：LLM 调用成功则恢复，失败则再次熔断，
+//!   退避时间翻倍，最长 `max_backoff_secs`
+//! - `moderation_window_secs` 内有 `moderation_block_threshold` 条输出被内容审核拦截时熔断
+//!   （见 [`crate::core::moderation`]）
+//! - 管理员可以随时手动复位
+//!
+//! 阈值可以全局配置，也可以按 Agent ID 覆盖。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::clock::{Clock, SystemClock};
+
+/// 熔断阈值，`llm_failure_threshold` 或 `tool_error_rate` 为 0 时关闭对应检查
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CircuitBreakerPolicy {
+    /// 连续失败多少次 LLM 调用后熔断
+    pub llm_failure_threshold: u32,
+    /// 窗口内工具调用失败率达到多少时熔断（0-1）
+    pub tool_error_rate: f64,
+    /// 窗口内至少有多少次工具调用才计算失败率
+    pub tool_min_calls: usize,
+    /// 工具调用统计窗口（秒）
+    pub tool_window_secs: u64,
+    /// 第一次熔断后多久试探（秒），之后每次试探失败翻倍
+    pub initial_backoff_secs: u64,
+    /// 试探间隔上限（秒）
+    pub max_backoff_secs: u64,
+    /// 窗口内被内容审核拦截多少条输出后熔断
+    pub moderation_block_threshold: u32,
+    /// 内容审核拦截的统计窗口（秒）
+    pub moderation_window_secs: u64,
+}
+
+impl Default for CircuitBreakerPolicy {
+    fn default() -> Self {
+        Self {
+            llm_failure_threshold: 5,
+            tool_error_rate: 0.5,
+            tool_min_calls: 10,
+            tool_window_secs: 300,
+            initial_backoff_secs: 30,
+            max_backoff_secs: 1800,
+            moderation_block_threshold: 3,
+            moderation_window_secs: 3600,
+        }
+    }
+}
+
+impl CircuitBreakerPolicy {
+    /// 设置连续 LLM 失败阈值
+    pub fn with_llm_failure_threshold(mut self, threshold: u32) -> Self {
+        self.llm_failure_threshold = threshold;
+        self
+    }
+
+    /// 设置工具失败率阈值、最少调用数和统计窗口
+    pub fn with_tool_error_rate(mut self, rate: f64, min_calls: usize, window_secs: u64) -> Self {
+        self.tool_error_rate = rate;
+        self.tool_min_calls = min_calls;
+        self.tool_window_secs = window_secs;
+        self
+    }
+
+    /// 设置内容审核拦截阈值和统计窗口
+    pub fn with_moderation_blocks(mut self, threshold: u32, window_secs: u64) -> Self {
+        self.moderation_block_threshold = threshold;
+        self.moderation_window_secs = window_secs;
+        self
+    }
+
+    /// 设置初始退避和上限
+    pub fn with_backoff(mut self, initial_secs: u64, max_secs: u64) -> Self {
+        self.initial_backoff_secs = initial_secs;
+        self.max_backoff_secs = max_secs;
+        self
+    }
+
+    /// 第 `failed_probes` 次试探失败后的退避（秒）
+    fn backoff_secs(&self, failed_probes: u32) -> u64 {
+        let factor = 1u64.checked_shl(failed_probes.min(32)).unwrap_or(u64::MAX);
+        self.initial_backoff_secs
+            .saturating_mul(factor)
+            .min(self.max_backoff_secs.max(self.initial_backoff_secs))
+    }
+}
+
+/// 熔断配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// 未单独配置的 Agent 使用的阈值
+    #[serde(flatten)]
+    pub defaults: CircuitBreakerPolicy,
+    /// 按 Agent ID 覆盖阈值，未写的项取内置默认值
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub agents: HashMap<String, CircuitBreakerPolicy>,
+}
+
+impl CircuitBreakerConfig {
+    /// 所有 Agent 使用同一组阈值
+    pub fn new(defaults: CircuitBreakerPolicy) -> Self {
+        Self {
+            defaults,
+            agents: HashMap::new(),
+        }
+    }
+
+    /// 为某个 Agent 单独设置阈值
+    pub fn with_agent(mut self, agent_id: impl Into<String>, policy: CircuitBreakerPolicy) -> Self {
+        self.agents.insert(agent_id.into(), policy);
+        self
+    }
+
+    /// Agent 生效的阈值
+    pub fn policy_for(&self, agent_id: &str) -> &CircuitBreakerPolicy {
+        self.agents.get(agent_id).unwrap_or(&self.defaults)
+    }
+}
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// 正常
+    #[default]
+    Closed,
+    /// 已熔断，等待退避结束
+    Open,
+    /// 正在试探
+    HalfOpen,
+}
+
+/// 熔断原因
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BreakerTrip {
+    /// LLM 调用连续失败
+    LlmFailures { consecutive: u32 },
+    /// LLM 鉴权失败（API key 无效或被吊销）
+    LlmAuthFailed,
+    /// 工具调用失败率过高
+    ToolErrorRate { errors: usize, calls: usize, window_secs: u64 },
+    /// 输出多次被内容审核拦截
+    ModerationBlocks { blocks: usize, window_secs: u64 },
+}
+
+/// Agent 的熔断状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    /// 最近一次熔断的原因
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trip: Option<BreakerTrip>,
+    /// 最近一次失败的错误信息
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// 熔断时间（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opened_at: Option<i64>,
+    /// 下一次试探的时间（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<i64>,
+    /// 当前连续失败的 LLM 调用数
+    pub consecutive_llm_failures: u32,
+    /// 本次故障以来失败的试探次数
+    pub failed_probes: u32,
+}
+
+impl BreakerStatus {
+    /// 是否处于故障状态（已熔断或正在试探）
+    pub fn is_faulted(&self) -> bool {
+        self.state != BreakerState::Closed
+    }
+}
+
+/// 轮次开始前的检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerGate {
+    /// 正常进行轮次
+    Closed,
+    /// 退避已结束，本轮是试探
+    Probe,
+    /// 仍在退避，`retry_at`（秒）后试探
+    Open { retry_at: i64 },
+}
+
+/// 状态变化，调用方据此告警和记录审计日志
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerTransition {
+    /// 正常状态下熔断
+    Tripped,
+    /// 试探失败，再次熔断
+    ProbeFailed,
+    /// 试探成功，恢复正常
+    Recovered,
+}
+
+#[derive(Default)]
+struct AgentBreaker {
+    status: BreakerStatus,
+    /// 工具调用：(时间毫秒, 是否失败)
+    tool_calls: VecDeque<(i64, bool)>,
+    /// 输出被内容审核拦截的时间（秒）
+    moderation_blocks: VecDeque<i64>,
+}
+
+/// 公司级熔断器，所有 Agent 共享
+pub struct CircuitBreakers {
+    config: CircuitBreakerConfig,
+    clock: Arc<dyn Clock>,
+    agents: Mutex<HashMap<String, AgentBreaker>>,
+}
+
+impl CircuitBreakers {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            agents: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    /// 轮次开始前检查；退避结束时转为半开，本轮作为试探
+    pub fn gate(&self, agent_id: &str) -> BreakerGate {
+        let now = self.clock.now();
+        let mut agents = self.agents.lock().unwrap();
+        let Some(breaker) = agents.get_mut(agent_id) else {
+            return BreakerGate::Closed;
+        };
+        match breaker.status.state {
+            BreakerState::Closed => BreakerGate::Closed,
+            BreakerState::HalfOpen => BreakerGate::Probe,
+            BreakerState::Open => {
+                let retry_at = breaker.status.retry_at.unwrap_or(now);
+                if now < retry_at {
+                    return BreakerGate::Open { retry_at };
+                }
+                breaker.status.state = BreakerState::HalfOpen;
+                BreakerGate::Probe
+            }
+        }
+    }
+
+    /// 记录一次 LLM 调用的结果（`error` 为空表示成功）
+    pub fn record_llm(&self, agent_id: &str, error: Option<&str>) -> Option<BreakerTransition> {
+        let policy = self.config.policy_for(agent_id);
+        let now = self.clock.now();
+        let mut agents = self.agents.lock().unwrap();
+        let breaker = agents.entry(agent_id.to_string()).or_default();
+        let status = &mut breaker.status;
+
+        let Some(error) = error else {
+            status.consecutive_llm_failures = 0;
+            if status.state != BreakerState::HalfOpen {
+                return None;
+            }
+            *status = BreakerStatus {
+                trip: status.trip.take(),
+                last_error: status.last_error.take(),
+                ..BreakerStatus::default()
+            };
+            breaker.tool_calls.clear();
+            breaker.moderation_blocks.clear();
+            return Some(BreakerTransition::Recovered);
+        };
+
+        Self::llm_failed(breaker, policy, now, error, false)
+    }
+
+    /// 记录一次 LLM 鉴权失败：重试不会好转，不等连续失败阈值立即熔断（阈值为 0 时同样不检查）
+    pub fn record_llm_auth_failure(&self, agent_id: &str, error: &str) -> Option<BreakerTransition> {
+        let policy = self.config.policy_for(agent_id);
+        let now = self.clock.now();
+        let mut agents = self.agents.lock().unwrap();
+        let breaker = agents.entry(agent_id.to_string()).or_default();
+        Self::llm_failed(breaker, policy, now, error, true)
+    }
+
+    fn llm_failed(
+        breaker: &mut AgentBreaker,
+        policy: &CircuitBreakerPolicy,
+        now: i64,
+        error: &str,
+        auth: bool,
+    ) -> Option<BreakerTransition> {
+        let status = &mut breaker.status;
+        status.consecutive_llm_failures += 1;
+        status.last_error = Some(error.to_string());
+        match status.state {
+            BreakerState::HalfOpen => {
+                status.failed_probes += 1;
+                Self::open(status, policy, now);
+                Some(BreakerTransition::ProbeFailed)
+            }
+            BreakerState::Closed
+                if policy.llm_failure_threshold > 0
+                    && (auth || status.consecutive_llm_failures >= policy.llm_failure_threshold) =>
+            {
+                status.trip = Some(if auth {
+                    BreakerTrip::LlmAuthFailed
+                } else {
+                    BreakerTrip::LlmFailures {
+                        consecutive: status.consecutive_llm_failures,
+                    }
+                });
+                status.opened_at = Some(now);
+                Self::open(status, policy, now);
+                breaker.tool_calls.clear();
+                Some(BreakerTransition::Tripped)
+            }
+            _ => None,
+        }
+    }
+
+    /// 记录一次工具调用的结果（`error` 为空表示成功）；只在正常状态下计入失败率
+    pub fn record_tool(&self, agent_id: &str, error: Option<&str>) -> Option<BreakerTransition> {
+        let policy = self.config.policy_for(agent_id);
+        let now_millis = self.clock.now_millis();
+        let mut agents = self.agents.lock().unwrap();
+        let breaker = agents.entry(agent_id.to_string()).or_default();
+        if breaker.status.state != BreakerState::Closed || policy.tool_error_rate <= 0.0 {
+            return None;
+        }
+
+        let window_start = now_millis - policy.tool_window_secs as i64 * 1000;
+        breaker.tool_calls.push_back((now_millis, error.is_some()));
+        while breaker.tool_calls.front().is_some_and(|(at, _)| *at < window_start) {
+            breaker.tool_calls.pop_front();
+        }
+        if let Some(error) = error {
+            breaker.status.last_error = Some(error.to_string());
+        }
+
+        let calls = breaker.tool_calls.len();
+        let errors = breaker.tool_calls.iter().filter(|(_, failed)| *failed).count();
+        if calls < policy.tool_min_calls.max(1) || (errors as f64) < policy.tool_error_rate * calls as f64 {
+            return None;
+        }
+        let now = now_millis / 1000;
+        breaker.status.trip = Some(BreakerTrip::ToolErrorRate {
+            errors,
+            calls,
+            window_secs: policy.tool_window_secs,
+        });
+        breaker.status.opened_at = Some(now);
+        Self::open(&mut breaker.status, policy, now);
+        breaker.tool_calls.clear();
+        Some(BreakerTransition::Tripped)
+    }
+
+    /// 记录一次输出被内容审核拦截；只在正常状态下计数
+    pub fn record_moderation_block(&self, agent_id: &str, reason: &str) -> Option<BreakerTransition> {
+        let policy = self.config.policy_for(agent_id);
+        let now = self.clock.now();
+        let mut agents = self.agents.lock().unwrap();
+        let breaker = agents.entry(agent_id.to_string()).or_default();
+        if breaker.status.state != BreakerState::Closed || policy.moderation_block_threshold == 0 {
+            return None;
+        }
+
+        let window_start = now - policy.moderation_window_secs as i64;
+        breaker.moderation_blocks.push_back(now);
+        while breaker.moderation_blocks.front().is_some_and(|at| *at <= window_start) {
+            breaker.moderation_blocks.pop_front();
+        }
+        breaker.status.last_error = Some(reason.to_string());
+
+        let blocks = breaker.moderation_blocks.len();
+        if blocks < policy.moderation_block_threshold as usize {
+            return None;
+        }
+        breaker.status.trip = Some(BreakerTrip::ModerationBlocks {
+            blocks,
+            window_secs: policy.moderation_window_secs,
+        });
+        breaker.status.opened_at = Some(now);
+        Self::open(&mut breaker.status, policy, now);
+        breaker.moderation_blocks.clear();
+        Some(BreakerTransition::Tripped)
+    }
+
+    fn open(status: &mut BreakerStatus, policy: &CircuitBreakerPolicy, now: i64) {
+        status.state = BreakerState::Open;
+        status.retry_at = Some(now + policy.backoff_secs(status.failed_probes) as i64);
+    }
+
+    /// 手动复位，返回复位前是否处于故障状态
+    pub fn reset(&self, agent_id: &str) -> bool {
+        let mut agents = self.agents.lock().unwrap();
+        agents
+            .remove(agent_id)
+            .is_some_and(|breaker| breaker.status.is_faulted())
+    }
+
+    /// Agent 当前的熔断状态
+    pub fn status(&self, agent_id: &str) -> BreakerStatus {
+        self.agents
+            .lock()
+            .unwrap()
+            .get(agent_id)
+            .map(|breaker| breaker.status.clone())
+            .unwrap_or_default()
+    }
+
+    /// Agent 是否处于故障状态
+    pub fn is_faulted(&self, agent_id: &str) -> bool {
+        self.agents
+            .lock()
+            .unwrap()
+            .get(agent_id)
+            .is_some_and(|breaker| breaker.status.is_faulted())
+    }
+}
+
+impl std::fmt::Debug for CircuitBreakers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let faulted: Vec<String> = self
+            .agents
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, breaker)| breaker.status.is_faulted())
+            .map(|(agent_id, _)| agent_id.clone())
+            .collect();
+        f.debug_struct("CircuitBreakers")
+            .field("config", &self.config)
+            .field("faulted", &faulted)
+            .finish()
+    }
+}
+
+impl Default for CircuitBreakers {
+    fn default() -> Self {
+        Self::new(CircuitBreakerConfig::default())
+    }
+}
@@ -0,0 +1,183 @@
+//! 工具结果引用
+//!
+//! Agent 调用工具后，结果按调用顺序编号为 `[T1]`、`[T2]`……并写入下一轮提示词，要求模型在回答中
+//! 标注用到的引用。Agent 发出回答时解析其中的引用标记，只保留确实对应本轮工具结果的引用
+//! （模型编造的标记会从正文中删除），并把引用列表写入消息元数据（[`CITATIONS_KEY`](crate::domain::CITATIONS_KEY)）。
+//! 模型没有标注任何引用时回答原样发出。
+
+use std::sync::Arc;
+
+use sha2::{Digest, Sha256};
+
+use crate::core::tokenizer::{truncate_with, HeuristicTokenizer, Tokenizer};
+use crate::domain::Citation;
+
+/// 一轮回答最多保留的工具结果数，超出时丢弃最早的
+pub const MAX_REFERENCES: usize = 10;
+
+/// 引用摘录的最大字符数
+pub const EXCERPT_CHARS: usize = 200;
+
+/// 提示词中每条工具结果的最大 token 数
+pub const PROMPT_RESULT_TOKENS: usize = 250;
+
+/// 参数摘要的字节数（十六进制后长度翻倍）
+const DIGEST_BYTES: usize = 6;
+
+/// 记录一次回答前调用过的工具结果
+#[derive(Debug)]
+pub struct CitationTracker {
+    references: Vec<(Citation, String)>,
+    next_id: usize,
+    /// 截断写入提示词的工具结果
+    tokenizer: Arc<dyn Tokenizer>,
+}
+
+impl Default for CitationTracker {
+    fn default() -> Self {
+        Self {
+            references: Vec::new(),
+            next_id: 0,
+            tokenizer: Arc::new(HeuristicTokenizer),
+        }
+    }
+}
+
+impl CitationTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按 Agent 模型的分词器截断工具结果
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// 记录一次工具结果，返回引用 ID（如 `T1`）
+    pub fn record(&mut self, tool_id: &str, params: &serde_json::Value, result: &serde_json::Value) -> String {
+        self.next_id += 1;
+        let ref_id = format!("T{}", self.next_id);
+        let text = result_text(result);
+        let citation = Citation {
+            ref_id: ref_id.clone(),
+            tool_id: tool_id.to_string(),
+            params_digest: params_digest(params),
+            excerpt: truncate(&text, EXCERPT_CHARS),
+        };
+        if self.references.len() == MAX_REFERENCES {
+            self.references.remove(0);
+        }
+        self.references.push((citation, truncate_with(self.tokenizer.as_ref(), &text, PROMPT_RESULT_TOKENS)));
+        ref_id
+    }
+
+    /// 当前可引用的工具结果
+    pub fn references(&self) -> Vec<Citation> {
+        self.references.iter().map(|(citation, _)| citation.clone()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.references.is_empty()
+    }
+
+    /// 写入提示词的工具结果段落，没有结果时为空
+    pub fn prompt_section(&self) -> Option<String> {
+        if self.references.is_empty() {
+            return None;
+        }
+        let mut section = String::from(
+            "\nTool results (when your message relies on one, cite it inline with its reference, e.g. [T1]):\n",
+        );
+        for (citation, text) in &self.references {
+            section.push_str(&format!("- [{}] {}: {}\n", citation.ref_id, citation.tool_id, text));
+        }
+        Some(section)
+    }
+
+    /// 解析回答中的引用：删除不对应任何工具结果的标记，返回清理后的正文和按首次出现排序的引用
+    pub fn resolve(&self, content: &str) -> (String, Vec<Citation>) {
+        let known = |ref_id: &str| self.references.iter().any(|(c, _)| c.ref_id == ref_id);
+        let mut citations: Vec<Citation> = Vec::new();
+        for ref_id in citation_markers(content) {
+            if let Some((citation, _)) = self.references.iter().find(|(c, _)| c.ref_id == ref_id) {
+                citations.push(citation.clone());
+            }
+        }
+        (strip_markers(content, known), citations)
+    }
+
+    /// 回答发出后清空，编号从 `T1` 重新开始
+    pub fn clear(&mut self) {
+        self.references.clear();
+        self.next_id = 0;
+    }
+}
+
+/// 正文中的引用标记（`[T<数字>]`），去重并按首次出现排序
+pub fn citation_markers(text: &str) -> Vec<String> {
+    let mut markers: Vec<String> = Vec::new();
+    for (_, ref_id) in marker_spans(text) {
+        if !markers.iter().any(|m| m == ref_id) {
+            markers.push(ref_id.to_string());
+        }
+    }
+    markers
+}
+
+/// 删除 `keep` 不接受的引用标记，连同标记前的一个空格
+fn strip_markers(text: &str, keep: impl Fn(&str) -> bool) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut last = 0;
+    for (range, ref_id) in marker_spans(text) {
+        if keep(ref_id) {
+            continue;
+        }
+        output.push_str(&text[last..range.start]);
+        if output.ends_with(' ') {
+            output.pop();
+        }
+        last = range.end;
+    }
+    output.push_str(&text[last..]);
+    output
+}
+
+/// `[T<数字>]` 标记的位置和引用 ID
+fn marker_spans(text: &str) -> Vec<(std::ops::Range<usize>, &str)> {
+    let mut spans = Vec::new();
+    let mut rest = 0;
+    while let Some(offset) = text[rest..].find("[T") {
+        let start = rest + offset;
+        let digits = text[start + 2..].bytes().take_while(u8::is_ascii_digit).count();
+        let end = start + 2 + digits;
+        if digits > 0 && text[end..].starts_with(']') {
+            spans.push((start..end + 1, &text[start + 1..end]));
+            rest = end + 1;
+        } else {
+            rest = start + 2;
+        }
+    }
+    spans
+}
+
+/// 参数的短摘要（规范化 JSON 的 SHA-256 前缀）
+pub fn params_digest(params: &serde_json::Value) -> String {
+    let digest = Sha256::digest(params.to_string().as_bytes());
+    digest.iter().take(DIGEST_BYTES).map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 字符串结果直接使用，其他结果用紧凑 JSON
+fn result_text(result: &serde_json::Value) -> String {
+    match result {
+        serde_json::Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,58 @@
+//! 时钟抽象
+//!
+//! 需要按时间判断的组件（升级检查等）通过 Clock 取当前时间，测试中可替换为 ManualClock。
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+/// 时钟
+pub trait Clock: Send + Sync {
+    /// 当前时间（毫秒时间戳）
+    fn now_millis(&self) -> i64;
+
+    /// 当前时间（秒时间戳，与 Message::timestamp 一致）
+    fn now(&self) -> i64 {
+        self.now_millis() / 1000
+    }
+}
+
+/// 系统时钟
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> i64 {
+        chrono::Utc::now().timestamp_millis()
+    }
+}
+
+/// 手动推进的时钟（用于测试）
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    millis: AtomicI64,
+}
+
+impl ManualClock {
+    /// 从指定秒时间戳开始
+    pub fn new(start_secs: i64) -> Self {
+        Self {
+            millis: AtomicI64::new(start_secs * 1000),
+        }
+    }
+
+    /// 向前推进
+    pub fn advance(&self, duration: Duration) {
+        self.millis.fetch_add(duration.as_millis() as i64, Ordering::SeqCst);
+    }
+
+    /// 设置为指定秒时间戳
+    pub fn set(&self, secs: i64) {
+        self.millis.store(secs * 1000, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> i64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
@@ -1,17 +1,692 @@
 //! 配置管理
 
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-use crate::domain::{Agent, Department, LLMConfig, Organization, Role};
+use crate::core::i18n::{Language, MessageCatalog};
+use crate::core::agent::SnapshotConfig;
+use crate::core::archive::MessageArchiveConfig;
+use crate::core::chat_sessions::ChatSessionConfig;
+use crate::core::circuit_breaker::CircuitBreakerConfig;
+use crate::core::goals::GoalConfig;
+use crate::core::handoff::HandoffConfig;
+use crate::core::integrity::IntegrityConfig;
+use crate::core::group_stats::GroupStatsConfig;
+use crate::core::join_context::JoinContextConfig;
+use crate::core::loop_guard::LoopGuardConfig;
+use crate::core::messaging::{OutboxPolicy, UrgentRateLimit};
+use crate::core::moderation::ModerationConfig;
+use crate::core::proactive::ProactiveConfig;
+use crate::core::response_language::ResponseStyle;
+use crate::core::retry::RetryPolicy;
+use crate::core::scheduler::SchedulerConfig;
+use crate::core::scratchpad::ScratchpadConfig;
+use crate::core::mentions::MentionConfig;
+use crate::core::secrets::ENV_PREFIX;
+use crate::core::send_dedup::SendDedupConfig;
+use crate::core::temp_agents::TempAgentConfig;
+use crate::core::leadership::LeadershipConfig;
+use crate::core::tool::{ToolAliasConfig, ToolDeprecationConfig};
+use crate::core::tool_concurrency::ToolConcurrencyConfig;
+use crate::core::translation::TranslationConfig;
+use crate::domain::{Agent, Department, DigestCadence, LLMConfig, Organization, Role};
+use crate::infrastructure::auth::PermissionConfig;
+use crate::infrastructure::sandbox::CodeSandboxConfig;
 
 /// 公司配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompanyConfig {
     pub name: String,
     pub organization: Organization,
+    /// 框架生成文本使用的语言
+    #[serde(default)]
+    pub language: Language,
+    /// 按 Agent 覆盖的语言设置
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub agent_languages: HashMap<String, Language>,
+    /// Agent 默认回复语言代码（如 `en`、`zh`），角色可覆盖
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_language: Option<String>,
+    /// Agent 默认语气，角色可覆盖
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
+    /// 按部门配置的未回复消息升级策略
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub escalation: HashMap<String, EscalationPolicy>,
+    /// 公司级消息模板，角色模板同名时优先使用角色模板
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, String>,
+    /// 是否在 Agent 上下文中显示消息回应汇总（如 `[3 reacted 👍]`）
+    #[serde(default)]
+    pub reactions_in_context: bool,
+    /// 轮次开始时的上下文快照：群聊连发消息的等待窗口、思考期间到达消息的显示方式
+    #[serde(default)]
+    pub snapshot: SnapshotConfig,
+    /// Agent 轮次并发调度
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    /// 轮次失败时本轮排队消息的处理策略
+    #[serde(default)]
+    pub outbox_policy: OutboxPolicy,
+    /// 每个发送者的 Urgent 消息限流
+    #[serde(default)]
+    pub urgent_rate_limit: UrgentRateLimit,
+    /// Agent 之间回声循环的抑制阈值
+    #[serde(default)]
+    pub loop_guard: LoopGuardConfig,
+    /// 窗口期内重复发送相同消息的抑制
+    #[serde(default)]
+    pub send_dedup: SendDedupConfig,
+    /// @提及补全的可见范围
+    #[serde(default)]
+    pub mentions: MentionConfig,
+    /// Agent 连续失败时的熔断阈值和试探退避
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
+    /// 工具并发限制和繁忙策略
+    #[serde(default)]
+    pub tool_concurrency: ToolConcurrencyConfig,
+    /// 停用的工具 ID，不会提供给任何 Agent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled_tools: Vec<String>,
+    /// 重命名工具的别名及停用日期
+    #[serde(default)]
+    pub tool_deprecation: ToolDeprecationConfig,
+    /// 启动时的存储完整性检查（默认记录警告后继续）
+    #[serde(default)]
+    pub integrity: IntegrityConfig,
+    /// `notify.email` 工具的收件域名白名单、每日上限和重试策略
+    #[serde(default)]
+    pub email: EmailPolicy,
+    /// `handoff` 工具：是否提供、目标选择策略和最多转交次数
+    #[serde(default)]
+    pub handoff: HandoffConfig,
+    /// 旧消息移入归档的保留期和运行间隔（默认不归档）
+    #[serde(default)]
+    pub archive: MessageArchiveConfig,
+    /// `code.run` 工具的 WASM 沙箱：各语言的运行时和资源上限（默认不启用）
+    #[serde(default)]
+    pub code_sandbox: CodeSandboxConfig,
+    /// 按读者的语言为消息附上译文（默认不翻译）
+    #[serde(default)]
+    pub translation: TranslationConfig,
+    /// 组织事件触发的主动问候（默认没有 Agent 配置策略）
+    #[serde(default)]
+    pub proactive: ProactiveConfig,
+    /// 管理接口的权限（默认按职位授权，部门负责人可管理本部门 Agent）
+    #[serde(default)]
+    pub permissions: PermissionConfig,
+    /// 定期生成的公司活动摘要（默认不生成）
+    #[serde(default)]
+    pub digest: DigestConfig,
+    /// 会话内临时 Agent 的数量上限和过期时间（默认启用）
+    #[serde(default)]
+    pub temp_agents: TempAgentConfig,
+    /// 主备部署：多个节点共享存储时只有领导者运行 Agent（默认关闭）
+    #[serde(default)]
+    pub leadership: LeadershipConfig,
+    /// 面向用户的输出在发送前的内容审核（默认关闭）
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    /// Agent 运行草稿（默认关闭）
+    #[serde(default)]
+    pub scratchpad: ScratchpadConfig,
+    /// Agent 当前目标的记录与过期（默认启用）
+    #[serde(default)]
+    pub goals: GoalConfig,
+    /// 会话的闲置自动关闭与总结（默认关闭）
+    #[serde(default)]
+    pub chat_sessions: ChatSessionConfig,
+    /// 成员被邀请加入群聊时发送的前情提要（默认启用）
+    #[serde(default)]
+    pub join_context: JoinContextConfig,
+    /// 群聊活动统计（默认启用）
+    #[serde(default)]
+    pub group_stats: GroupStatsConfig,
+}
+
+/// 未回复消息升级策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EscalationPolicy {
+    /// 是否启用
+    #[serde(default = "default_escalation_enabled")]
+    pub enabled: bool,
+    /// 多久未回复后升级（秒）
+    pub timeout_secs: u64,
+    /// 同时发邮件通知的负责人邮箱（需要配置 SMTP）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify_email: Option<String>,
+}
+
+fn default_escalation_enabled() -> bool {
+    true
+}
+
+impl EscalationPolicy {
+    /// 创建启用的策略
+    pub fn after_secs(timeout_secs: u64) -> Self {
+        Self {
+            enabled: true,
+            timeout_secs,
+            notify_email: None,
+        }
+    }
+
+    /// 升级时同时发邮件给负责人
+    pub fn with_notify_email(mut self, address: impl Into<String>) -> Self {
+        self.notify_email = Some(address.into());
+        self
+    }
+}
+
+/// 邮件通知策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct EmailPolicy {
+    /// 允许的收件域名，未单独配置的 Agent 使用；为空时不能发给任何人
+    pub allowed_domains: Vec<String>,
+    /// 按 Agent ID 覆盖允许的收件域名
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub agent_domains: HashMap<String, Vec<String>>,
+    /// 每个 Agent 每天（UTC）最多发送的邮件数
+    pub daily_cap: u32,
+    /// 发送失败后的重试次数
+    pub max_retries: u32,
+    /// 第一次重试前的等待（毫秒），之后每次翻倍
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for EmailPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_domains: Vec::new(),
+            agent_domains: HashMap::new(),
+            daily_cap: 20,
+            max_retries: 3,
+            retry_backoff_ms: 1000,
+        }
+    }
+}
+
+impl EmailPolicy {
+    /// 允许所有 Agent 发往这些域名
+    pub fn allow_domains(domains: Vec<impl Into<String>>) -> Self {
+        Self {
+            allowed_domains: domains.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// 为某个 Agent 单独设置允许的域名
+    pub fn with_agent_domains(mut self, agent_id: impl Into<String>, domains: Vec<impl Into<String>>) -> Self {
+        self.agent_domains
+            .insert(agent_id.into(), domains.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// 设置每日上限
+    pub fn with_daily_cap(mut self, daily_cap: u32) -> Self {
+        self.daily_cap = daily_cap;
+        self
+    }
+
+    /// 设置重试次数和初始退避
+    pub fn with_retry(mut self, max_retries: u32, retry_backoff_ms: u64) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff_ms = retry_backoff_ms;
+        self
+    }
+
+    /// 发送失败时使用的重试策略
+    pub fn retry_policy(&self) -> RetryPolicy {
+        let base = Duration::from_millis(self.retry_backoff_ms);
+        let max = Duration::from_millis(RetryPolicy::default().max_delay_ms).max(base);
+        RetryPolicy::default()
+            .with_max_attempts(self.max_retries + 1)
+            .with_delays(base, max)
+    }
+
+    /// Agent 能否发往该地址：域名相同或是允许域名的子域名
+    pub fn allows(&self, agent_id: &str, address: &str) -> bool {
+        let Some((_, domain)) = address.rsplit_once('@') else {
+            return false;
+        };
+        let domain = domain.trim().to_ascii_lowercase();
+        let allowed = self.agent_domains.get(agent_id).unwrap_or(&self.allowed_domains);
+        allowed.iter().any(|allowed| {
+            let allowed = allowed.trim().trim_start_matches('@').to_ascii_lowercase();
+            domain == allowed || domain.ends_with(&format!(".{}", allowed))
+        })
+    }
+}
+
+/// 活动摘要配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    pub enabled: bool,
+    pub cadence: DigestCadence,
+    /// 生成时刻（UTC 小时）；每周摘要在周一的这个时刻生成
+    pub hour_utc: u32,
+    /// 检查是否到期的间隔（秒）
+    pub check_secs: u64,
+    pub recipients: DigestRecipients,
+    pub sections: DigestSections,
+    /// 总结群聊决定时提供给 LLM 的消息 token 上限（所有群聊合计）
+    pub token_budget: usize,
+    /// 总结使用的模型，未设置时使用组织中第一个 Agent 的模型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm: Option<LLMConfig>,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cadence: DigestCadence::Daily,
+            hour_utc: 8,
+            check_secs: 15 * 60,
+            recipients: DigestRecipients::default(),
+            sections: DigestSections::default(),
+            token_budget: 4000,
+            llm: None,
+        }
+    }
+}
+
+impl DigestConfig {
+    /// 按指定周期生成摘要
+    pub fn enabled(cadence: DigestCadence) -> Self {
+        Self {
+            enabled: true,
+            cadence,
+            ..Self::default()
+        }
+    }
+
+    /// 设置接收方
+    pub fn with_recipients(mut self, recipients: DigestRecipients) -> Self {
+        self.recipients = recipients;
+        self
+    }
+
+    /// 设置包含的内容
+    pub fn with_sections(mut self, sections: DigestSections) -> Self {
+        self.sections = sections;
+        self
+    }
+
+    /// 设置总结群聊决定的 token 上限
+    pub fn with_token_budget(mut self, token_budget: usize) -> Self {
+        self.token_budget = token_budget;
+        self
+    }
+}
+
+/// 摘要接收方
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct DigestRecipients {
+    /// 按用户名发送邮件（需要配置 SMTP，用户需填写邮箱）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+    /// 发到指定群聊
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// 以 JSON POST 到 webhook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+}
+
+/// 摘要包含的内容，除群聊发言分布外默认全部包含
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct DigestSections {
+    /// 消息量
+    pub messages: bool,
+    /// 由 LLM 总结的群聊决定
+    pub decisions: bool,
+    /// 完成的任务
+    pub tasks: bool,
+    /// Watchdog 触发
+    pub incidents: bool,
+    /// 部门 LLM 预算用量
+    pub budgets: bool,
+    /// 各群聊的发言分布（需启用群聊活动统计，默认不包含）
+    pub group_activity: bool,
+}
+
+impl Default for DigestSections {
+    fn default() -> Self {
+        Self {
+            messages: true,
+            decisions: true,
+            tasks: true,
+            incidents: true,
+            budgets: true,
+            group_activity: false,
+        }
+    }
 }
 
 impl CompanyConfig {
+    /// 使用默认语言创建配置
+    pub fn new(name: impl Into<String>, organization: Organization) -> Self {
+        Self {
+            name: name.into(),
+            organization,
+            language: Language::default(),
+            agent_languages: HashMap::new(),
+            response_language: None,
+            tone: None,
+            escalation: HashMap::new(),
+            templates: HashMap::new(),
+            reactions_in_context: false,
+            snapshot: SnapshotConfig::default(),
+            scheduler: SchedulerConfig::default(),
+            outbox_policy: OutboxPolicy::default(),
+            urgent_rate_limit: UrgentRateLimit::default(),
+            loop_guard: LoopGuardConfig::default(),
+            send_dedup: SendDedupConfig::default(),
+            mentions: MentionConfig::default(),
+            circuit_breaker: CircuitBreakerConfig::default(),
+            tool_concurrency: ToolConcurrencyConfig::default(),
+            disabled_tools: Vec::new(),
+            tool_deprecation: ToolDeprecationConfig::default(),
+            integrity: IntegrityConfig::default(),
+            email: EmailPolicy::default(),
+            handoff: HandoffConfig::default(),
+            archive: MessageArchiveConfig::default(),
+            code_sandbox: CodeSandboxConfig::default(),
+            translation: TranslationConfig::default(),
+            proactive: ProactiveConfig::default(),
+            permissions: PermissionConfig::default(),
+            digest: DigestConfig::default(),
+            temp_agents: TempAgentConfig::default(),
+            leadership: LeadershipConfig::default(),
+            moderation: ModerationConfig::default(),
+            scratchpad: ScratchpadConfig::default(),
+            goals: GoalConfig::default(),
+            chat_sessions: ChatSessionConfig::default(),
+            join_context: JoinContextConfig::default(),
+            group_stats: GroupStatsConfig::default(),
+        }
+    }
+
+    /// 设置公司语言
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// 获取指定 Agent 的语言，未单独配置时使用公司语言
+    pub fn language_for(&self, agent_id: &str) -> Language {
+        self.agent_languages
+            .get(agent_id)
+            .copied()
+            .unwrap_or(self.language)
+    }
+
+    /// 设置 Agent 默认回复语言
+    pub fn with_response_language(mut self, language: impl Into<String>) -> Self {
+        self.response_language = Some(language.into());
+        self
+    }
+
+    /// 设置 Agent 默认语气
+    pub fn with_tone(mut self, tone: impl Into<String>) -> Self {
+        self.tone = Some(tone.into());
+        self
+    }
+
+    /// 公司默认的回复语言和语气
+    pub fn response_style(&self) -> ResponseStyle {
+        ResponseStyle::new(self.response_language.clone(), self.tone.clone())
+    }
+
+    /// 为部门设置升级策略
+    pub fn with_escalation(mut self, department_id: impl Into<String>, policy: EscalationPolicy) -> Self {
+        self.escalation.insert(department_id.into(), policy);
+        self
+    }
+
+    /// 添加公司级消息模板
+    pub fn with_template(mut self, name: impl Into<String>, text: impl Into<String>) -> Self {
+        self.templates.insert(name.into(), text.into());
+        self
+    }
+
+    /// 在 Agent 上下文中显示消息回应汇总
+    pub fn with_reactions_in_context(mut self, enabled: bool) -> Self {
+        self.reactions_in_context = enabled;
+        self
+    }
+
+    /// 设置上下文快照配置
+    pub fn with_snapshot(mut self, snapshot: SnapshotConfig) -> Self {
+        self.snapshot = snapshot;
+        self
+    }
+
+    /// 设置轮次调度配置
+    pub fn with_scheduler(mut self, scheduler: SchedulerConfig) -> Self {
+        self.scheduler = scheduler;
+        self
+    }
+
+    /// 设置轮次失败时的发件箱策略
+    pub fn with_outbox_policy(mut self, policy: OutboxPolicy) -> Self {
+        self.outbox_policy = policy;
+        self
+    }
+
+    /// 设置 Urgent 消息限流
+    pub fn with_urgent_rate_limit(mut self, limit: UrgentRateLimit) -> Self {
+        self.urgent_rate_limit = limit;
+        self
+    }
+
+    /// 设置循环抑制阈值
+    pub fn with_loop_guard(mut self, loop_guard: LoopGuardConfig) -> Self {
+        self.loop_guard = loop_guard;
+        self
+    }
+
+    /// 设置重复发送的抑制窗口
+    pub fn with_send_dedup(mut self, send_dedup: SendDedupConfig) -> Self {
+        self.send_dedup = send_dedup;
+        self
+    }
+
+    /// 设置 @提及补全的可见范围
+    pub fn with_mentions(mut self, mentions: MentionConfig) -> Self {
+        self.mentions = mentions;
+        self
+    }
+
+    /// 设置 Agent 熔断阈值
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = circuit_breaker;
+        self
+    }
+
+    /// 设置工具并发控制
+    pub fn with_tool_concurrency(mut self, tool_concurrency: ToolConcurrencyConfig) -> Self {
+        self.tool_concurrency = tool_concurrency;
+        self
+    }
+
+    /// 停用指定工具
+    pub fn with_disabled_tool(mut self, tool_id: impl Into<String>) -> Self {
+        self.disabled_tools.push(tool_id.into());
+        self
+    }
+
+    /// 把旧工具ID路由到新ID，`sunset` 之后按配置决定是否拒绝调用
+    pub fn with_tool_alias(mut self, from: impl Into<String>, to: impl Into<String>, sunset: Option<NaiveDate>) -> Self {
+        self.tool_deprecation.aliases.push(ToolAliasConfig {
+            from: from.into(),
+            to: to.into(),
+            sunset,
+        });
+        self
+    }
+
+    /// 设置启动时的存储完整性检查
+    pub fn with_integrity(mut self, integrity: IntegrityConfig) -> Self {
+        self.integrity = integrity;
+        self
+    }
+
+    /// 设置邮件通知策略
+    pub fn with_email_policy(mut self, email: EmailPolicy) -> Self {
+        self.email = email;
+        self
+    }
+
+    /// 设置 Agent 转交配置
+    pub fn with_handoff(mut self, handoff: HandoffConfig) -> Self {
+        self.handoff = handoff;
+        self
+    }
+
+    /// 设置消息归档
+    pub fn with_archive(mut self, archive: MessageArchiveConfig) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// 设置代码沙箱
+    pub fn with_code_sandbox(mut self, code_sandbox: CodeSandboxConfig) -> Self {
+        self.code_sandbox = code_sandbox;
+        self
+    }
+
+    /// 设置消息翻译
+    pub fn with_translation(mut self, translation: TranslationConfig) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    /// 设置主动问候
+    pub fn with_proactive(mut self, proactive: ProactiveConfig) -> Self {
+        self.proactive = proactive;
+        self
+    }
+
+    /// 设置管理接口的权限
+    pub fn with_permissions(mut self, permissions: PermissionConfig) -> Self {
+        self.permissions = permissions;
+        self
+    }
+
+    /// 设置活动摘要
+    pub fn with_digest(mut self, digest: DigestConfig) -> Self {
+        self.digest = digest;
+        self
+    }
+
+    /// 设置临时 Agent
+    pub fn with_temp_agents(mut self, temp_agents: TempAgentConfig) -> Self {
+        self.temp_agents = temp_agents;
+        self
+    }
+
+    /// 设置主备部署
+    pub fn with_leadership(mut self, leadership: LeadershipConfig) -> Self {
+        self.leadership = leadership;
+        self
+    }
+
+    /// 设置内容审核
+    pub fn with_moderation(mut self, moderation: ModerationConfig) -> Self {
+        self.moderation = moderation;
+        self
+    }
+
+    /// 设置 Agent 运行草稿
+    pub fn with_scratchpad(mut self, scratchpad: ScratchpadConfig) -> Self {
+        self.scratchpad = scratchpad;
+        self
+    }
+
+    /// 设置 Agent 当前目标
+    pub fn with_goals(mut self, goals: GoalConfig) -> Self {
+        self.goals = goals;
+        self
+    }
+
+    /// 设置会话生命周期
+    pub fn with_chat_sessions(mut self, chat_sessions: ChatSessionConfig) -> Self {
+        self.chat_sessions = chat_sessions;
+        self
+    }
+
+    /// 设置加入群聊时的前情提要
+    pub fn with_join_context(mut self, join_context: JoinContextConfig) -> Self {
+        self.join_context = join_context;
+        self
+    }
+
+    /// 设置群聊活动统计
+    pub fn with_group_stats(mut self, group_stats: GroupStatsConfig) -> Self {
+        self.group_stats = group_stats;
+        self
+    }
+
+    /// 公司级消息目录
+    pub fn catalog(&self) -> MessageCatalog {
+        MessageCatalog::new(self.language)
+    }
+
+    /// 内置的初始组织模板名称
+    pub const STARTER_TEMPLATES: &'static [&'static str] = &["startup"];
+
+    /// 按内置模板创建初始组织，未知模板返回 None
+    ///
+    /// - `startup`：CEO 加研发、产品两个部门，各有一名负责人
+    pub fn starter(template: &str) -> Option<Self> {
+        match template {
+            "startup" => {
+                let llm_config = env_llm_config();
+                let mut org = Organization::new();
+                org.add_department(Department::top_level("engineering", "Engineering").with_leader("cto"));
+                org.add_department(Department::top_level("product", "Product").with_leader("head-of-product"));
+
+                org.add_agent(Agent::new(
+                    "ceo",
+                    "CEO",
+                    Role::simple("CEO", "You are the CEO of a small startup. You set priorities, make decisions and keep the team aligned."),
+                    llm_config.clone(),
+                ));
+                org.add_agent(
+                    Agent::new(
+                        "cto",
+                        "CTO",
+                        Role::simple("CTO", "You lead the engineering department. You plan technical work and review engineering decisions."),
+                        llm_config.clone(),
+                    )
+                    .with_department("engineering"),
+                );
+                org.add_agent(
+                    Agent::new(
+                        "head-of-product",
+                        "Head of Product",
+                        Role::simple("Head of Product", "You lead the product department. You turn customer needs into clear priorities."),
+                        llm_config,
+                    )
+                    .with_department("product"),
+                );
+
+                Some(Self::new("Startup", org))
+            }
+            _ => None,
+        }
+    }
+
     /// 创建简单的测试配置
     pub fn test_config() -> Self {
         let mut org = Organization::new();
@@ -19,32 +694,38 @@ impl CompanyConfig {
         // Add department
         org.add_department(Department::top_level("tech", "Technology Department"));
 
-        // 从环境变量获取配置，提供更合理的默认值
-        let api_key = std::env::var("OPENAI_API_KEY").unwrap_or_else(|_| {
-            eprintln!("警告: OPENAI_API_KEY 环境变量未设置，使用测试密钥");
-            "sk-your-api-key-here".to_string()
-        });
-        let model = std::env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
-        let base_url = std::env::var("OPENAI_BASE_URL").unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
-
         // 添加Agent
         let agent1 = Agent::new(
             "ceo",
             "CEO",
             Role::simple("CEO", "You are the CEO of the company, responsible for decision-making and management."),
-            LLMConfig {
-                api_key,
-                model,
-                base_url,
-            },
+            env_llm_config(),
         );
 
         org.add_agent(agent1);
 
-        Self {
-            name: "Test Company".to_string(),
-            organization: org,
+        Self::new("Test Company", org)
+    }
+}
+
+/// 从环境变量获取 LLM 配置，提供更合理的默认值
+fn env_llm_config() -> LLMConfig {
+    // 只保存引用，存储和接口中不会出现 Key 本身
+    let api_key = match std::env::var("OPENAI_API_KEY") {
+        Ok(_) => format!("{}OPENAI_API_KEY", ENV_PREFIX),
+        Err(_) => {
+            eprintln!("{}", MessageCatalog::default().get("startup.missing_api_key"));
+            "sk-your-api-key-here".to_string()
         }
+    };
+    let model = std::env::var("OPENAI_MODEL").unwrap_or_else(|_| "gpt-4o-mini".to_string());
+    let base_url = std::env::var("OPENAI_BASE_URL").unwrap_or_else(|_| "https://api.openai.com/v1".to_string());
+
+    LLMConfig {
+        api_key,
+        model,
+        base_url,
+        tokenizer: std::env::var("OPENAI_TOKENIZER").ok(),
     }
 }
 
@@ -0,0 +1,262 @@
+//! 未回复消息升级
+//!
+//! 发给 Agent 的私聊消息在部门策略规定的时间内没有得到回复时，
+//! 向其部门负责人发送一条系统消息（负责人就是该 Agent 时沿上级部门查找），
+//! 每条消息只升级一次，记录保存在 Store 中。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::config::{CompanyConfig, EscalationPolicy};
+use crate::core::messaging::MessageBus;
+use crate::core::store::{MessageFilter, Store};
+use crate::domain::{Escalation, Message, MessageTarget, Organization};
+use crate::infrastructure::email::{EmailMessage, EmailNotifier};
+
+/// 发送方在消息元数据中设置此键（值为 "true"）可关闭该消息的升级
+pub const NO_ESCALATION_KEY: &str = "no_escalation";
+
+/// 升级系统消息的发送者
+pub const ESCALATION_SENDER: &str = "system";
+
+/// 每轮检查回看的时间范围（秒）
+const LOOKBACK_SECS: i64 = 24 * 60 * 60;
+
+/// 每轮检查最多扫描的消息数
+const SCAN_LIMIT: usize = 1000;
+
+/// 升级检查器
+pub struct EscalationChecker {
+    config: CompanyConfig,
+    organization: Arc<RwLock<Organization>>,
+    message_bus: Arc<MessageBus>,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    email: Option<Arc<EmailNotifier>>,
+}
+
+impl EscalationChecker {
+    /// 创建检查器，使用系统时钟
+    pub fn new(
+        config: CompanyConfig,
+        organization: Arc<RwLock<Organization>>,
+        message_bus: Arc<MessageBus>,
+        store: Arc<dyn Store>,
+    ) -> Self {
+        Self {
+            config,
+            organization,
+            message_bus,
+            store,
+            clock: Arc::new(SystemClock),
+            email: None,
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 策略配置了 `notify_email` 时同时发邮件给负责人
+    pub fn with_email(mut self, email: Arc<EmailNotifier>) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// 检查一轮，返回本轮新产生的升级
+    pub async fn check(&self) -> Result<Vec<Escalation>> {
+        if self.config.escalation.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let now = self.clock.now();
+        let candidates = self
+            .store
+            .load_messages(
+                MessageFilter::new()
+                    .target_type("direct")
+                    .since(now - LOOKBACK_SECS)
+                    .limit(SCAN_LIMIT),
+            )
+            .await?;
+
+        let mut escalations = Vec::new();
+        for message in candidates {
+            if let Some(escalation) = self.check_message(&message, now).await? {
+                escalations.push(escalation);
+            }
+        }
+
+        Ok(escalations)
+    }
+
+    async fn check_message(&self, message: &Message, now: i64) -> Result<Option<Escalation>> {
+        let Some(agent_id) = message.target_agent() else {
+            return Ok(None);
+        };
+        if message.metadata(NO_ESCALATION_KEY) == Some("true") || message.from == agent_id {
+            return Ok(None);
+        }
+
+        let (policy, leader_id) = {
+            let org = self.organization.read().await;
+            let Some(department_id) = org.find_agent(agent_id).and_then(|a| a.department_id.clone()) else {
+                return Ok(None);
+            };
+            let Some(policy) = policy_for(&self.config.escalation, &org, &department_id) else {
+                return Ok(None);
+            };
+            let Some(leader_id) = find_escalation_leader(&org, &department_id, agent_id, &message.from) else {
+                return Ok(None);
+            };
+            (policy.clone(), leader_id)
+        };
+
+        if now - message.timestamp < policy.timeout_secs as i64 {
+            return Ok(None);
+        }
+        if self.store.load_escalation(&message.id).await?.is_some() {
+            return Ok(None);
+        }
+        if self.has_reply(message, agent_id).await? {
+            return Ok(None);
+        }
+
+        let escalation = Escalation {
+            message_id: message.id.clone(),
+            agent_id: agent_id.to_string(),
+            escalated_to: leader_id.clone(),
+            escalated_at: now,
+        };
+
+        let catalog = crate::core::i18n::MessageCatalog::new(self.config.language_for(&leader_id));
+        let minutes = ((now - message.timestamp) / 60).to_string();
+        let content = catalog.format(
+            "escalation.notice",
+            &[
+                ("agent_id", agent_id),
+                ("from", &message.from),
+                ("minutes", &minutes),
+                ("content", &message.content),
+            ],
+        );
+        let email = match (&self.email, &policy.notify_email) {
+            (Some(_), Some(address)) => Some(EmailMessage::new(
+                address,
+                catalog.format("escalation.email_subject", &[("agent_id", agent_id)]),
+                content.clone(),
+            )),
+            _ => None,
+        };
+        let mut notice = Message::private(ESCALATION_SENDER, &leader_id, content)
+            .with_metadata("kind", "escalation")
+            .with_metadata("escalated_message_id", &message.id)
+            .with_metadata(NO_ESCALATION_KEY, "true");
+        notice.timestamp = now;
+        if let Some(trace_id) = message.trace_id() {
+            notice = notice.with_trace(trace_id, None);
+        }
+
+        // 先记录再发送，避免发送失败时反复升级
+        self.store.save_escalation(&escalation).await?;
+        if let Err(e) = self.message_bus.send(notice).await {
+            warn!("Failed to deliver escalation of message {} to {}: {}", message.id, leader_id, e);
+        }
+        info!("Escalated message {} from {} to leader {}", message.id, agent_id, leader_id);
+
+        // 邮件失败已在审计日志中记录，不影响升级本身
+        if let (Some(notifier), Some(email)) = (&self.email, email) {
+            let _ = notifier.deliver(ESCALATION_SENDER, &email).await;
+        }
+
+        Ok(Some(escalation))
+    }
+
+    /// Agent 是否已回复：引用了原消息，或在此后私聊过原发送方
+    async fn has_reply(&self, message: &Message, agent_id: &str) -> Result<bool> {
+        let sent = self
+            .store
+            .load_messages(
+                MessageFilter::new()
+                    .from(agent_id)
+                    .since(message.timestamp)
+                    .limit(SCAN_LIMIT),
+            )
+            .await?;
+
+        Ok(sent.iter().any(|m| {
+            m.reply_to.as_deref() == Some(message.id.as_str())
+                || m.to == MessageTarget::Direct(message.from.clone())
+        }))
+    }
+
+    /// 启动周期检查任务
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.check().await {
+                    warn!("Escalation check failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// 获取部门的升级策略，未配置时沿上级部门继承
+pub fn policy_for<'a>(
+    policies: &'a HashMap<String, EscalationPolicy>,
+    org: &Organization,
+    department_id: &str,
+) -> Option<&'a EscalationPolicy> {
+    let mut current = Some(department_id.to_string());
+    let mut visited = HashSet::new();
+
+    while let Some(dept_id) = current {
+        if !visited.insert(dept_id.clone()) {
+            break;
+        }
+        if let Some(policy) = policies.get(&dept_id) {
+            return Some(policy).filter(|p| p.enabled);
+        }
+        current = org.find_department(&dept_id).and_then(|d| d.parent_id.clone());
+    }
+
+    None
+}
+
+/// 查找升级对象：部门负责人，负责人是未回复的 Agent 或原发送方时沿上级部门查找
+pub fn find_escalation_leader(
+    org: &Organization,
+    department_id: &str,
+    agent_id: &str,
+    sender_id: &str,
+) -> Option<String> {
+    let mut current = Some(department_id.to_string());
+    let mut visited = HashSet::new();
+
+    while let Some(dept_id) = current {
+        if !visited.insert(dept_id.clone()) {
+            break;
+        }
+        let dept = org.find_department(&dept_id)?;
+        if let Some(leader_id) = dept.leader_id.as_deref() {
+            if leader_id != agent_id && leader_id != sender_id {
+                return Some(leader_id.to_string());
+            }
+        }
+        current = dept.parent_id.clone();
+    }
+
+    None
+}
@@ -0,0 +1,234 @@
+//! 公司生命周期事件
+//!
+//! 嵌入 `VirtualCompany` 的应用可以订阅运行时的关键时刻（Agent 启动、一轮思考完成、
+//! 消息落库、工具执行、Watchdog 触发、发件箱处理、循环抑制、重复发送抑制、熔断、插话、组织变更、用户变更、功能开关切换、任务指派和逾期），
+//! 不必修改框架代码。
+//!
+//! 两种接入方式：
+//! - [`EventBus::subscribe`]：broadcast 接收端，慢订阅者会丢失最旧的事件（`Lagged`）
+//! - [`EventBus::register_listener`]：每个监听器有独立的队列和任务，按入队顺序逐个处理，
+//!   监听器 panic 只会记录日志，不影响其他监听器和后续事件
+//!
+//! 顺序保证：同一来源（同一 Agent、同一 MessageBus 等）发出的事件按发出顺序送达；
+//! 不同来源之间没有全局顺序。
+
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use futures_util::FutureExt;
+use tokio::sync::{broadcast, mpsc};
+use tracing::error;
+
+use crate::core::agent::Decision;
+use crate::core::circuit_breaker::BreakerStatus;
+use crate::core::features::Feature;
+use crate::core::loop_guard::LoopTrip;
+use crate::core::messaging::OutboxReport;
+use crate::core::nudge::Nudge;
+use crate::core::response_language::LanguageCorrection;
+use crate::domain::{Message, MessageId, MessageTarget, OrgChangeEntry, Task};
+
+/// broadcast 订阅的缓冲大小
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// 生命周期事件，负载均为 Arc，克隆开销很小
+#[derive(Debug, Clone)]
+pub enum CompanyEvent {
+    /// Agent 自主循环启动
+    AgentStarted { agent_id: Arc<str> },
+    /// Agent 完成一轮思考与执行；思考失败时 `decision` 为空
+    AgentTurnCompleted {
+        agent_id: Arc<str>,
+        trace_id: Arc<str>,
+        decision: Option<Arc<Decision>>,
+        error: Option<Arc<str>>,
+        /// 本轮生效的角色修订，未修改过角色时为空
+        role_revision: Option<u32>,
+        /// 本轮开始时的当前目标（见 [`crate::core::goals`]）
+        goal: Option<Arc<str>>,
+    },
+    /// 消息已写入存储
+    MessagePersisted { message: Arc<Message> },
+    /// 工具执行完成
+    ToolExecuted {
+        tool_id: Arc<str>,
+        caller_id: Arc<str>,
+        trace_id: Arc<str>,
+        duration_ms: u64,
+        error: Option<Arc<str>>,
+    },
+    /// Watchdog 规则被触发
+    WatchdogTriggered {
+        rule_id: Arc<str>,
+        tool_id: Arc<str>,
+        target_agent_id: Arc<str>,
+        trace_id: Arc<str>,
+    },
+    /// 轮次结束时发件箱已处理
+    OutboxFlushed {
+        agent_id: Arc<str>,
+        trace_id: Arc<str>,
+        turn_failed: bool,
+        report: Arc<OutboxReport>,
+    },
+    /// 循环抑制拦截了 Agent 的一条消息
+    LoopGuardTripped {
+        agent_id: Arc<str>,
+        trace_id: Arc<str>,
+        message_id: MessageId,
+        trip: Arc<LoopTrip>,
+    },
+    /// 窗口期内重复发送的相同消息被丢弃
+    DuplicateSendSuppressed {
+        agent_id: Arc<str>,
+        trace_id: Arc<str>,
+        message_id: MessageId,
+        target: MessageTarget,
+    },
+    /// Agent 进入或离开工作时间
+    AgentPresenceChanged {
+        agent_id: Arc<str>,
+        available: bool,
+        /// 离开时下一次工作时间的开始（秒级时间戳）
+        next_available_at: Option<i64>,
+    },
+    /// Agent 熔断、试探失败或恢复
+    AgentBreakerChanged {
+        agent_id: Arc<str>,
+        status: Arc<BreakerStatus>,
+    },
+    /// Agent 的回复语言不符合配置，已带纠正说明重试一次
+    ResponseLanguageCorrected {
+        agent_id: Arc<str>,
+        trace_id: Arc<str>,
+        correction: Arc<LanguageCorrection>,
+    },
+    /// 工具循环中的插话已在本轮 LLM 请求前注入
+    NudgeInjected {
+        agent_id: Arc<str>,
+        trace_id: Arc<str>,
+        nudge: Arc<Nudge>,
+    },
+    /// 组织架构已保存并产生了变更记录
+    OrgChanged { entry: Arc<OrgChangeEntry> },
+    /// 用户已注册或资料已变更
+    UserChanged { user_id: Arc<str> },
+    /// 运行中切换了功能开关，依赖功能状态的缓存应失效
+    FeatureToggled { feature: Arc<Feature> },
+    /// 任务被指派给他人
+    TaskAssigned {
+        task: Arc<Task>,
+        assignee: Arc<str>,
+        actor: Arc<str>,
+    },
+    /// 截止提醒时任务已过截止时间
+    TaskOverdue { task: Arc<Task> },
+}
+
+impl CompanyEvent {
+    /// 事件名称（用于日志和断言）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            CompanyEvent::AgentStarted { .. } => "agent_started",
+            CompanyEvent::AgentTurnCompleted { .. } => "agent_turn_completed",
+            CompanyEvent::MessagePersisted { .. } => "message_persisted",
+            CompanyEvent::ToolExecuted { .. } => "tool_executed",
+            CompanyEvent::WatchdogTriggered { .. } => "watchdog_triggered",
+            CompanyEvent::OutboxFlushed { .. } => "outbox_flushed",
+            CompanyEvent::LoopGuardTripped { .. } => "loop_guard_tripped",
+            CompanyEvent::DuplicateSendSuppressed { .. } => "duplicate_send_suppressed",
+            CompanyEvent::AgentPresenceChanged { .. } => "agent_presence_changed",
+            CompanyEvent::AgentBreakerChanged { .. } => "agent_breaker_changed",
+            CompanyEvent::ResponseLanguageCorrected { .. } => "response_language_corrected",
+            CompanyEvent::NudgeInjected { .. } => "nudge_injected",
+            CompanyEvent::OrgChanged { .. } => "org_changed",
+            CompanyEvent::UserChanged { .. } => "user_changed",
+            CompanyEvent::FeatureToggled { .. } => "feature_toggled",
+            CompanyEvent::TaskAssigned { .. } => "task_assigned",
+            CompanyEvent::TaskOverdue { .. } => "task_overdue",
+        }
+    }
+
+    /// 事件所属的追踪ID（Agent 启动与消息落库事件取自消息元数据）
+    pub fn trace_id(&self) -> Option<&str> {
+        match self {
+            CompanyEvent::AgentStarted { .. }
+            | CompanyEvent::AgentPresenceChanged { .. }
+            | CompanyEvent::AgentBreakerChanged { .. }
+            | CompanyEvent::OrgChanged { .. }
+            | CompanyEvent::UserChanged { .. }
+            | CompanyEvent::FeatureToggled { .. }
+            | CompanyEvent::TaskAssigned { .. }
+            | CompanyEvent::TaskOverdue { .. } => None,
+            CompanyEvent::AgentTurnCompleted { trace_id, .. }
+            | CompanyEvent::ToolExecuted { trace_id, .. }
+            | CompanyEvent::WatchdogTriggered { trace_id, .. }
+            | CompanyEvent::OutboxFlushed { trace_id, .. }
+            | CompanyEvent::LoopGuardTripped { trace_id, .. }
+            | CompanyEvent::DuplicateSendSuppressed { trace_id, .. }
+            | CompanyEvent::ResponseLanguageCorrected { trace_id, .. }
+            | CompanyEvent::NudgeInjected { trace_id, .. } => Some(trace_id),
+            CompanyEvent::MessagePersisted { message } => message.trace_id(),
+        }
+    }
+}
+
+/// 事件监听器
+#[async_trait]
+pub trait CompanyEventListener: Send + Sync {
+    async fn on_event(&self, event: &CompanyEvent);
+}
+
+/// 事件总线
+pub struct EventBus {
+    tx: broadcast::Sender<CompanyEvent>,
+    listeners: RwLock<Vec<mpsc::UnboundedSender<CompanyEvent>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            tx,
+            listeners: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// 订阅事件流
+    pub fn subscribe(&self) -> broadcast::Receiver<CompanyEvent> {
+        self.tx.subscribe()
+    }
+
+    /// 注册监听器（需在 Tokio 运行时中调用）
+    pub fn register_listener(&self, listener: Box<dyn CompanyEventListener>) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<CompanyEvent>();
+
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let handled = AssertUnwindSafe(listener.on_event(&event)).catch_unwind().await;
+                if handled.is_err() {
+                    error!("Company event listener panicked on {}", event.kind());
+                }
+            }
+        });
+
+        self.listeners.write().unwrap_or_else(|e| e.into_inner()).push(tx);
+    }
+
+    /// 发出事件，不等待监听器处理
+    pub fn emit(&self, event: CompanyEvent) {
+        let mut listeners = self.listeners.write().unwrap_or_else(|e| e.into_inner());
+        listeners.retain(|listener| listener.send(event.clone()).is_ok());
+        drop(listeners);
+
+        // 没有订阅者时发送失败，忽略即可
+        let _ = self.tx.send(event);
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
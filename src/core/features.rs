@@ -0,0 +1,188 @@
+//! 功能开关
+//!
+//! 框架的可选子系统（邮件、代码沙箱、转交、临时 Agent、翻译等）是否可用，由三类来源汇总：
+//! 编译进来的特性、配置中的开关和运行时状态（如是否设置了邮件发送器）。前端通过
+//! `GET /api/features` 隐藏不可用的界面；工具定义用 [`Tool::feature`](crate::domain::tool::Tool::feature)
+//! 声明所属功能，功能关闭时 [`AgentToolView`](crate::core::tool_view::AgentToolView) 不再把它交给 Agent。
+//!
+//! 启动时已可用的工具类功能可以在运行中关闭和重新打开（管理接口 `/admin/features/{name}`），
+//! 切换后发出 [`CompanyEvent::FeatureToggled`]，工具视图等缓存收到后失效。
+//! 未登记的功能视为开启，应用工具声明的自定义功能不会因此被隐藏。
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use serde::Serialize;
+use thiserror::Error;
+use tracing::info;
+
+use crate::core::config::CompanyConfig;
+use crate::core::events::{CompanyEvent, EventBus};
+use crate::core::runtime_info::FEATURES;
+
+/// 功能状态的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureSource {
+    /// 编译时的 cargo 特性
+    Compiled,
+    /// 公司配置
+    Config,
+    /// 运行时状态，如嵌入方设置的邮件发送器
+    Runtime,
+}
+
+/// 一项功能
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Feature {
+    pub name: String,
+    pub enabled: bool,
+    pub source: FeatureSource,
+    /// 能否在运行中切换
+    pub toggleable: bool,
+}
+
+impl Feature {
+    pub fn new(name: impl Into<String>, enabled: bool, source: FeatureSource) -> Self {
+        Self {
+            name: name.into(),
+            enabled,
+            source,
+            toggleable: false,
+        }
+    }
+
+    /// 允许在运行中切换
+    pub fn with_toggleable(mut self, toggleable: bool) -> Self {
+        self.toggleable = toggleable;
+        self
+    }
+}
+
+/// 切换功能失败
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FeatureError {
+    #[error("Unknown feature: {0}")]
+    NotFound(String),
+    #[error("Feature {0} cannot be toggled at runtime")]
+    NotToggleable(String),
+}
+
+/// 功能开关登记表
+#[derive(Default)]
+pub struct FeatureFlags {
+    features: RwLock<BTreeMap<String, Feature>>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl FeatureFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记编译进来的特性
+    pub fn compiled() -> Self {
+        let flags = Self::new();
+        for (name, enabled) in FEATURES {
+            flags.register(Feature::new(*name, *enabled, FeatureSource::Compiled));
+        }
+        flags
+    }
+
+    /// 登记编译进来的特性和配置中的开关；提供工具的功能启用时可以在运行中切换
+    pub fn from_config(config: &CompanyConfig) -> Self {
+        let flags = Self::compiled();
+        for (name, enabled) in [
+            ("handoff", config.handoff.enabled),
+            ("temp_agents", config.temp_agents.enabled),
+            ("scratchpad", config.scratchpad.enabled),
+        ] {
+            flags.register(Feature::new(name, enabled, FeatureSource::Config).with_toggleable(enabled));
+        }
+        for (name, enabled) in [
+            ("translation", config.translation.enabled),
+            ("moderation", config.moderation.is_active()),
+            ("digest", config.digest.enabled),
+            ("archive", config.archive.is_enabled()),
+            ("escalation", config.escalation.values().any(|p| p.enabled)),
+            ("leadership", config.leadership.enabled),
+        ] {
+            flags.register(Feature::new(name, enabled, FeatureSource::Config));
+        }
+        flags
+    }
+
+    /// 切换时发出 `FeatureToggled` 事件
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 登记或覆盖一项功能，不发出事件（用于启动阶段）
+    pub fn register(&self, feature: Feature) {
+        self.write().insert(feature.name.clone(), feature);
+    }
+
+    /// 功能是否开启，未登记的功能视为开启
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.read().get(name).is_none_or(|f| f.enabled)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Feature> {
+        self.read().get(name).cloned()
+    }
+
+    /// 所有功能，按名称排序
+    pub fn list(&self) -> Vec<Feature> {
+        self.read().values().cloned().collect()
+    }
+
+    /// 功能名称到是否开启
+    pub fn states(&self) -> BTreeMap<String, bool> {
+        self.read().iter().map(|(name, f)| (name.clone(), f.enabled)).collect()
+    }
+
+    /// 开启的功能名称
+    pub fn enabled(&self) -> Vec<String> {
+        self.read().values().filter(|f| f.enabled).map(|f| f.name.clone()).collect()
+    }
+
+    /// 运行中切换功能；状态变化时发出 `FeatureToggled`
+    pub fn toggle(&self, name: &str, enabled: bool) -> Result<Feature, FeatureError> {
+        let (feature, changed) = {
+            let mut features = self.write();
+            let feature = features.get_mut(name).ok_or_else(|| FeatureError::NotFound(name.to_string()))?;
+            if !feature.toggleable {
+                return Err(FeatureError::NotToggleable(name.to_string()));
+            }
+            let changed = feature.enabled != enabled;
+            feature.enabled = enabled;
+            (feature.clone(), changed)
+        };
+        if changed {
+            info!("Feature {} {}", name, if enabled { "enabled" } else { "disabled" });
+            if let Some(events) = &self.events {
+                events.emit(CompanyEvent::FeatureToggled {
+                    feature: Arc::new(feature.clone()),
+                });
+            }
+        }
+        Ok(feature)
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Feature>> {
+        self.features.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<String, Feature>> {
+        self.features.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl std::fmt::Debug for FeatureFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeatureFlags")
+            .field("features", &self.states())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,165 @@
+//! 当前目标：长任务中始终保留用户最初的请求
+//!
+//! 多轮对话中历史按 token 预算从最早的开始丢弃，用户最初的请求往往最先被截掉。由用户私聊或任务
+//! 分配（工作流步骤）发起的轮次把该请求记为 Agent 的当前目标（已有目标时追问不替换它），之后每轮
+//! 提示词都在专门的段落中带上它，不受历史预算影响，只截断到 [`GoalConfig::token_reserve`]。
+//! 任务变化或完成时 Agent 用 `self.set_goal` / `self.clear_goal` 更新或清除目标。
+//!
+//! 目标闲置（没有新的输入或工具循环）超过 [`GoalConfig::idle_expiry_secs`] 后过期，
+//! 过期后的下一轮附上一条系统提示告知 Agent。当前目标记入每轮的
+//! [`CompanyEvent::AgentTurnCompleted`](crate::core::events::CompanyEvent::AgentTurnCompleted)，
+//! 也可以通过 `GET /agents/{id}` 查看。
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::domain::Message;
+
+/// Agent 设置目标使用的工具
+pub const SET_GOAL_TOOL: &str = "self.set_goal";
+
+/// Agent 清除目标使用的工具
+pub const CLEAR_GOAL_TOOL: &str = "self.clear_goal";
+
+/// 目标过期提示的发送者，不计为人类输入
+pub const GOAL_NOTICE_SENDER: &str = "system";
+
+/// 目标配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct GoalConfig {
+    /// 是否记录目标（同时决定是否提供 `self.set_goal` / `self.clear_goal` 工具）
+    pub enabled: bool,
+    /// 目标闲置多久后过期（秒），0 表示不过期
+    pub idle_expiry_secs: u64,
+    /// 提示词中目标段落的 token 上限，超出时截断
+    pub token_reserve: usize,
+}
+
+impl Default for GoalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_expiry_secs: 3600,
+            token_reserve: 200,
+        }
+    }
+}
+
+/// 目标来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalSource {
+    /// 用户私聊
+    User,
+    /// 任务分配
+    Task,
+    /// Agent 通过 `self.set_goal` 设置
+    Agent,
+}
+
+/// Agent 的当前目标
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Goal {
+    pub text: String,
+    pub source: GoalSource,
+    /// 来自消息时为消息 ID
+    pub message_id: Option<String>,
+    /// 设置时间（秒时间戳）
+    pub set_at: i64,
+    /// 最近一次有输入或工具循环的时间（秒时间戳），闲置从这里起算
+    pub active_at: i64,
+}
+
+impl Goal {
+    /// 过期后附给 Agent 的系统提示
+    pub fn expiry_notice(&self, agent_id: &str) -> Message {
+        let content = format!(
+            "Your goal \"{}\" expired after a period of inactivity and is no longer shown. \
+             Call {} if you are still working on it.",
+            self.text, SET_GOAL_TOOL
+        );
+        Message::private(GOAL_NOTICE_SENDER, agent_id, content).with_metadata("kind", "goal_expired")
+    }
+}
+
+/// 各 Agent 的当前目标
+pub struct GoalBoard {
+    config: GoalConfig,
+    clock: Arc<dyn Clock>,
+    goals: DashMap<String, Goal>,
+}
+
+impl GoalBoard {
+    pub fn new(config: GoalConfig) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            goals: DashMap::new(),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn config(&self) -> &GoalConfig {
+        &self.config
+    }
+
+    /// 设置目标，替换原有目标
+    pub fn set(&self, agent_id: &str, text: impl Into<String>, source: GoalSource, message_id: Option<String>) -> Goal {
+        let now = self.clock.now();
+        let goal = Goal {
+            text: text.into(),
+            source,
+            message_id,
+            set_at: now,
+            active_at: now,
+        };
+        self.goals.insert(agent_id.to_string(), goal.clone());
+        goal
+    }
+
+    /// 当前目标
+    pub fn get(&self, agent_id: &str) -> Option<Goal> {
+        self.goals.get(agent_id).map(|goal| goal.clone())
+    }
+
+    /// 清除目标，返回被清除的目标
+    pub fn clear(&self, agent_id: &str) -> Option<Goal> {
+        self.goals.remove(agent_id).map(|(_, goal)| goal)
+    }
+
+    /// 记录一次活动，推迟过期
+    pub fn touch(&self, agent_id: &str) {
+        if let Some(mut goal) = self.goals.get_mut(agent_id) {
+            goal.active_at = self.clock.now();
+        }
+    }
+
+    /// 闲置超时时移除并返回目标
+    pub fn take_expired(&self, agent_id: &str) -> Option<Goal> {
+        if self.config.idle_expiry_secs == 0 {
+            return None;
+        }
+        let deadline = self.clock.now() - self.config.idle_expiry_secs as i64;
+        self.goals
+            .remove_if(agent_id, |_, goal| goal.active_at <= deadline)
+            .map(|(_, goal)| goal)
+    }
+}
+
+impl std::fmt::Debug for GoalBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GoalBoard")
+            .field("config", &self.config)
+            .field("goals", &self.goals.len())
+            .finish()
+    }
+}
@@ -0,0 +1,298 @@
+//! 群聊活动统计
+//!
+//! 管理者想知道哪些 Agent 在群里说得最多、哪些从不发言，Agent 也可以据此拉上沉默的成员。
+//! [`GroupStatsTracker`] 监听落库的群聊消息，增量更新每个群聊的 [`GroupStats`]：各成员按小时
+//! 分桶的消息数、最后发言时间和被 @ 后回复的平均延迟。统计保存在存储中，查询时只读这一行，
+//! 不扫描消息；滚动窗口（默认最近 24 小时和 7 天）按查询时的时钟计算，过期的分桶在更新时丢弃。
+//!
+//! 增量状态与从原始消息重新计算的结果一致；两者出现偏差（例如监听器漏掉了事件）时，
+//! 管理员可以调用 [`GroupStatsTracker::recompute`] 修复。
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::messaging::order_by_group_seq;
+use crate::core::store::{MessageFilter, Store};
+use crate::domain::{GroupStats, Message, MessageTarget};
+
+/// 重新计算时读取的群聊消息上限
+const MAX_GROUP_MESSAGES: usize = 100_000;
+
+/// 群聊活动统计配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct GroupStatsConfig {
+    /// 是否统计群聊活动（同时决定是否提供 `group.stats` 工具）
+    pub enabled: bool,
+    /// 统计消息数的滚动窗口（秒），从短到长
+    pub windows_secs: Vec<u64>,
+    /// 分桶粒度（秒），窗口边界按分桶对齐
+    pub bucket_secs: u64,
+}
+
+impl Default for GroupStatsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            windows_secs: vec![24 * 3600, 7 * 24 * 3600],
+            bucket_secs: 3600,
+        }
+    }
+}
+
+impl GroupStatsConfig {
+    /// 最长的窗口（秒）
+    pub fn longest_window_secs(&self) -> u64 {
+        self.windows_secs.iter().copied().max().unwrap_or(0)
+    }
+
+    /// 分桶保留的时长：最长窗口再多一个分桶，窗口起点落在分桶中间时不会少算
+    fn retention_secs(&self) -> i64 {
+        (self.longest_window_secs() + self.bucket_secs.max(1)) as i64
+    }
+}
+
+/// 群聊活动统计报告
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupStatsReport {
+    pub group_id: String,
+    /// 计算窗口使用的当前时间
+    pub generated_at: i64,
+    /// 成员，按最长窗口内的消息数降序
+    pub members: Vec<MemberStats>,
+    /// 最长窗口内没有发言的群成员
+    pub quiet: Vec<String>,
+}
+
+/// 成员在群聊中的活动
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MemberStats {
+    pub member_id: String,
+    /// 是否仍是群成员
+    pub is_member: bool,
+    pub total_messages: u64,
+    /// 各窗口内的消息数与占全群的比例
+    pub windows: Vec<WindowActivity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active_at: Option<i64>,
+    pub mention_replies: u64,
+    /// 被 @ 后回复的平均延迟（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_response_secs: Option<u64>,
+    /// 尚未回复的最早一次 @ 的时间
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub awaiting_reply_since: Option<i64>,
+}
+
+/// 一个滚动窗口内的消息数
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowActivity {
+    pub window_secs: u64,
+    pub messages: u64,
+    /// 占窗口内全群消息的比例，窗口内没有消息时为 0
+    pub share: f64,
+}
+
+/// 群聊活动统计
+pub struct GroupStatsTracker {
+    config: GroupStatsConfig,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    /// 串行化统计的读改写，避免增量更新与重新计算互相覆盖
+    lock: Mutex<()>,
+}
+
+impl GroupStatsTracker {
+    pub fn new(config: GroupStatsConfig, store: Arc<dyn Store>) -> Self {
+        Self {
+            config,
+            store,
+            clock: Arc::new(SystemClock),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn config(&self) -> &GroupStatsConfig {
+        &self.config
+    }
+
+    /// 保存的统计，群聊还没有消息时为空
+    pub async fn stats(&self, group_id: &str) -> Result<Option<GroupStats>> {
+        self.store.load_group_stats(group_id).await
+    }
+
+    /// 把一条落库的群聊消息计入统计，返回更新后的统计；非群聊消息和已计入的消息忽略
+    pub async fn record_message(&self, message: &Message) -> Result<Option<GroupStats>> {
+        let MessageTarget::Group(group_id) = &message.to else {
+            return Ok(None);
+        };
+        let _guard = self.lock.lock().await;
+        let mut stats = self
+            .store
+            .load_group_stats(group_id)
+            .await?
+            .unwrap_or_else(|| GroupStats::new(group_id.clone()));
+        if stats.has_applied(message) {
+            return Ok(None);
+        }
+        stats.apply(message, self.config.bucket_secs as i64, self.config.retention_secs());
+        self.store.save_group_stats(&stats).await?;
+        Ok(Some(stats))
+    }
+
+    /// 从原始消息重新计算群聊的统计并覆盖保存的状态
+    pub async fn recompute(&self, group_id: &str) -> Result<GroupStats> {
+        let _guard = self.lock.lock().await;
+        let mut filter = MessageFilter::new().to(group_id.to_string()).target_type("group").oldest_first();
+        filter.limit = MAX_GROUP_MESSAGES;
+        let mut messages: Vec<Message> = self
+            .store
+            .load_messages(filter)
+            .await?
+            .into_iter()
+            .filter(|m| m.target_group() == Some(group_id))
+            .collect();
+        order_by_group_seq(&mut messages);
+
+        let mut stats = GroupStats::new(group_id);
+        for message in &messages {
+            stats.apply(message, self.config.bucket_secs as i64, self.config.retention_secs());
+        }
+        self.store.save_group_stats(&stats).await?;
+        info!(target: "audit", group_id = %group_id, messages = messages.len(), "Group stats recomputed");
+        Ok(stats)
+    }
+
+    /// 当前时刻的统计报告；群成员按存储中的群聊计算，没有发言的成员也会列出
+    pub async fn report(&self, group_id: &str) -> Result<GroupStatsReport> {
+        let stats = self
+            .store
+            .load_group_stats(group_id)
+            .await?
+            .unwrap_or_else(|| GroupStats::new(group_id));
+        let group_members: Vec<String> = self
+            .store
+            .load_groups()
+            .await?
+            .into_iter()
+            .find(|g| g.id == group_id)
+            .map(|g| g.members)
+            .unwrap_or_default();
+        let now = self.clock.now();
+
+        let since: Vec<(u64, i64)> = self
+            .config
+            .windows_secs
+            .iter()
+            .map(|&window| (window, now - window as i64))
+            .collect();
+        let totals: Vec<u64> = since
+            .iter()
+            .map(|&(_, start)| stats.members.values().map(|m| m.messages_since(start)).sum())
+            .collect();
+
+        let mut ids: Vec<&String> = group_members.iter().collect();
+        ids.extend(stats.members.keys().filter(|id| !group_members.contains(*id)));
+        let mut members: Vec<MemberStats> = ids
+            .into_iter()
+            .map(|id| {
+                let activity = stats.members.get(id).cloned().unwrap_or_default();
+                let windows = since
+                    .iter()
+                    .zip(&totals)
+                    .map(|(&(window_secs, start), &total)| {
+                        let messages = activity.messages_since(start);
+                        WindowActivity {
+                            window_secs,
+                            messages,
+                            share: if total == 0 { 0.0 } else { messages as f64 / total as f64 },
+                        }
+                    })
+                    .collect();
+                MemberStats {
+                    member_id: id.clone(),
+                    is_member: group_members.contains(id),
+                    total_messages: activity.total_messages,
+                    windows,
+                    last_active_at: activity.last_active_at,
+                    mention_replies: activity.mention_replies,
+                    average_response_secs: activity.average_response_secs(),
+                    awaiting_reply_since: stats.pending_mentions.get(id).copied(),
+                }
+            })
+            .collect();
+
+        let longest = |m: &MemberStats| m.windows.iter().max_by_key(|w| w.window_secs).map_or(0, |w| w.messages);
+        members.sort_by(|a, b| longest(b).cmp(&longest(a)).then_with(|| a.member_id.cmp(&b.member_id)));
+        let quiet = members
+            .iter()
+            .filter(|m| m.is_member && longest(m) == 0)
+            .map(|m| m.member_id.clone())
+            .collect();
+        Ok(GroupStatsReport {
+            group_id: group_id.to_string(),
+            generated_at: now,
+            members,
+            quiet,
+        })
+    }
+
+    /// 每个群聊在 `[start, end)` 内各成员的消息数（按分桶计算，早于保留时长的部分不计入）
+    pub async fn activity_between(&self, start: i64, end: i64) -> Result<BTreeMap<String, BTreeMap<String, u64>>> {
+        let mut activity = BTreeMap::new();
+        for group in self.store.load_groups().await? {
+            let Some(stats) = self.store.load_group_stats(&group.id).await? else {
+                continue;
+            };
+            let counts: BTreeMap<String, u64> = stats
+                .members
+                .iter()
+                .map(|(id, member)| (id.clone(), member.messages_between(start, end)))
+                .filter(|(_, count)| *count > 0)
+                .collect();
+            if !counts.is_empty() {
+                activity.insert(group.id, counts);
+            }
+        }
+        Ok(activity)
+    }
+
+    /// 注册为事件监听器，消息落库后更新统计（需在 Tokio 运行时中调用）
+    pub fn spawn(self: Arc<Self>, events: &EventBus) {
+        events.register_listener(Box::new(GroupStatsListener(self)));
+    }
+}
+
+impl std::fmt::Debug for GroupStatsTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GroupStatsTracker").field("config", &self.config).finish()
+    }
+}
+
+struct GroupStatsListener(Arc<GroupStatsTracker>);
+
+#[async_trait]
+impl CompanyEventListener for GroupStatsListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        let CompanyEvent::MessagePersisted { message } = event else {
+            return;
+        };
+        if let Err(e) = self.0.record_message(message).await {
+            warn!("Failed to update stats of group for message {}: {}", message.id, e);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! 群成员与部门同步
+//!
+//! 设置了 [`GroupSync`] 的常设群聊跟随部门（可含子部门）的当前成员：[`GroupSyncReconciler`]
+//! 监听 `OrgChanged` 事件，把新加入部门的 Agent 拉进群，把离开部门的移出群，并在群里发系统通知。
+//!
+//! - 默认只移出由同步加入（或同步时已在部门中）的成员，手动加入的额外成员保留；严格模式下一并移出
+//! - 被管理员移出的 Agent 不会被悄悄加回，而是在通知和同步结果中列出
+//! - 被移出的成员保留禁言记录，重新加入后禁言仍然有效
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tracing::{info, warn};
+
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::messaging::{GroupModerationError, MessageBus, GROUP_EXPIRY_SENDER};
+use crate::core::store::Store;
+use crate::domain::{Group, GroupSync, GroupSyncReport, Message, Organization, SyncSource};
+
+/// 群聊无法同步
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GroupSyncError {
+    #[error("Group {group_id} has no membership sync source")]
+    NotSynced { group_id: String },
+    #[error("Department {department_id} synced by group {group_id} does not exist")]
+    DepartmentNotFound { group_id: String, department_id: String },
+}
+
+/// 按组织架构调整同步群聊的成员
+pub struct GroupSyncReconciler {
+    store: Arc<dyn Store>,
+    bus: Arc<MessageBus>,
+}
+
+impl GroupSyncReconciler {
+    pub fn new(store: Arc<dyn Store>, bus: Arc<MessageBus>) -> Self {
+        Self { store, bus }
+    }
+
+    /// 同步单个群聊
+    pub async fn reconcile(&self, group_id: &str) -> Result<GroupSyncReport> {
+        let group = self.bus.get_group(group_id).await.ok_or_else(|| GroupModerationError::GroupNotFound {
+            group_id: group_id.to_string(),
+        })?;
+        let org = self.store.load_organization().await?;
+        self.reconcile_group(&org, &group).await
+    }
+
+    /// 同步所有设置了同步来源的群聊，返回成员有变化或有冲突的结果
+    pub async fn reconcile_all(&self) -> Result<Vec<GroupSyncReport>> {
+        let groups = self.bus.list_synced_groups().await;
+        if groups.is_empty() {
+            return Ok(Vec::new());
+        }
+        let org = self.store.load_organization().await?;
+        let mut reports = Vec::new();
+        for group in groups {
+            match self.reconcile_group(&org, &group).await {
+                Ok(report) if report.changed() || !report.kicked.is_empty() => reports.push(report),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to sync members of group {}: {}", group.id, e),
+            }
+        }
+        Ok(reports)
+    }
+
+    async fn reconcile_group(&self, org: &Organization, group: &Group) -> Result<GroupSyncReport> {
+        let sync = group.sync.as_ref().ok_or_else(|| GroupSyncError::NotSynced {
+            group_id: group.id.clone(),
+        })?;
+        let SyncSource::Department(department_id) = &sync.source;
+        let Some(department) = org.find_department(department_id) else {
+            return Err(GroupSyncError::DepartmentNotFound {
+                group_id: group.id.clone(),
+                department_id: department_id.clone(),
+            }
+            .into());
+        };
+
+        let members = source_members(org, sync);
+        let report = self.bus.sync_group_members(&group.id, &members).await?;
+        if report.changed() {
+            info!(
+                "Synced group {} with department {}: {} joined, {} left",
+                group.id,
+                department_id,
+                report.joined.len(),
+                report.left.len()
+            );
+        }
+        self.notify(group, &department.name, &report).await;
+        Ok(report)
+    }
+
+    /// 在群里发出加入、离开和冲突通知
+    async fn notify(&self, group: &Group, department: &str, report: &GroupSyncReport) {
+        let catalog = self.bus.catalog();
+        let notices = [
+            ("group.sync_joined_notice", &report.joined),
+            ("group.sync_left_notice", &report.left),
+            ("group.sync_kicked_notice", &report.kicked),
+        ];
+        for (key, members) in notices {
+            if members.is_empty() {
+                continue;
+            }
+            let text = catalog.format(
+                key,
+                &[("members", &members.join(", ")), ("name", &group.name), ("department", department)],
+            );
+            if let Err(e) = self.bus.send(Message::group(GROUP_EXPIRY_SENDER, &group.id, text)).await {
+                warn!("Failed to post sync notice to group {}: {}", group.id, e);
+            }
+        }
+    }
+
+    /// 注册为事件监听器，组织架构变更后同步所有群聊（需在 Tokio 运行时中调用）
+    pub fn spawn(self: Arc<Self>, events: &EventBus) {
+        events.register_listener(Box::new(GroupSyncListener(self)));
+    }
+}
+
+struct GroupSyncListener(Arc<GroupSyncReconciler>);
+
+#[async_trait]
+impl CompanyEventListener for GroupSyncListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        if let CompanyEvent::OrgChanged { .. } = event {
+            if let Err(e) = self.0.reconcile_all().await {
+                warn!("Group membership sync failed: {}", e);
+            }
+        }
+    }
+}
+
+/// 同步来源的当前成员（按组织架构中的顺序）
+fn source_members(org: &Organization, sync: &GroupSync) -> Vec<String> {
+    let SyncSource::Department(department_id) = &sync.source;
+    let mut departments = HashSet::from([department_id.as_str()]);
+    if sync.include_sub_departments {
+        let mut pending = vec![department_id.as_str()];
+        while let Some(parent) = pending.pop() {
+            for child in org.get_sub_departments(parent) {
+                if departments.insert(child.id.as_str()) {
+                    pending.push(child.id.as_str());
+                }
+            }
+        }
+    }
+    org.agents
+        .iter()
+        .filter(|a| a.department_id.as_deref().is_some_and(|d| departments.contains(d)))
+        .map(|a| a.id.clone())
+        .collect()
+}
@@ -0,0 +1,289 @@
+//! Agent 转交
+//!
+//! 收到的私聊超出自己技能范围时，Agent 调用 `handoff` 工具把消息转交给组织中
+//! 合适的 Agent：按技能或部门挑选目标，把原消息连同转交原因私聊转发给目标，
+//! 并告知原发送者消息转给了谁。转交链记录在转发消息的元数据
+//! [`HANDOFF_CHAIN_KEY`] 中，超过 `max_hops` 后不再转交，避免消息在 Agent 之间打转。
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::i18n::MessageCatalog;
+use crate::core::store::Store;
+use crate::domain::{Agent, Message, MessageTarget, Organization};
+
+/// 转发消息元数据中转交链的键（JSON 数组，按转交顺序）
+pub const HANDOFF_CHAIN_KEY: &str = "handoff_chain";
+
+/// 转发消息元数据中原消息ID的键
+pub const HANDOFF_ORIGIN_ID_KEY: &str = "handoff_origin_id";
+
+/// 转发消息元数据中原发送者的键
+pub const HANDOFF_ORIGIN_FROM_KEY: &str = "handoff_origin_from";
+
+/// 查找原消息时最多扫描的近期消息数
+const SCAN_LIMIT: usize = 200;
+
+/// 转交目标的选择策略
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HandoffPolicy {
+    /// 第一个具备所需技能（或属于所需部门）的 Agent
+    #[default]
+    FirstMatch,
+    /// 匹配部门的负责人
+    DepartmentLeader,
+    /// 不自动转交，把候选人列给原发送者选择
+    AskUser,
+}
+
+/// 转交配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HandoffConfig {
+    /// 是否向 Agent 提供 `handoff` 工具
+    pub enabled: bool,
+    /// 目标选择策略
+    pub policy: HandoffPolicy,
+    /// 一条消息最多被转交几次
+    pub max_hops: usize,
+}
+
+impl Default for HandoffConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            policy: HandoffPolicy::default(),
+            max_hops: 3,
+        }
+    }
+}
+
+/// 转交链中的一次转交
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HandoffHop {
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+    pub at: i64,
+}
+
+/// 转交请求
+#[derive(Debug, Clone, Default)]
+pub struct HandoffRequest {
+    /// 要转交的消息，未指定时取最近一条发给自己的私聊
+    pub message_id: Option<String>,
+    /// 转交原因，会转告目标和原发送者
+    pub reason: String,
+    /// 需要的技能
+    pub skill: Option<String>,
+    /// 建议的部门（ID 或名称）
+    pub department: Option<String>,
+}
+
+/// 转交错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HandoffError {
+    #[error("Message {message_id} not found")]
+    MessageNotFound { message_id: String },
+    #[error("No direct message to hand off")]
+    NothingToHandOff,
+    #[error("Handoff reason must not be empty")]
+    EmptyReason,
+    #[error("A skill or department is required to pick a handoff target")]
+    NoCriteria,
+    #[error("Message was already handed off {hops} times (limit {max_hops})")]
+    HopLimit { hops: usize, max_hops: usize },
+    #[error("No agent matches the handoff criteria")]
+    NoCandidate,
+}
+
+/// 转交结果：要发出的消息由调用方投递
+#[derive(Debug, Clone)]
+pub enum HandoffOutcome {
+    /// 转发给目标，并通知原发送者
+    Forwarded {
+        target: String,
+        chain: Vec<HandoffHop>,
+        forward: Box<Message>,
+        notice: Message,
+    },
+    /// 按策略询问原发送者，附候选 Agent
+    AskedUser { candidates: Vec<String>, notice: Message },
+}
+
+/// 读取消息上的转交链，没有或无法解析时为空
+pub fn handoff_chain(message: &Message) -> Vec<HandoffHop> {
+    message
+        .metadata(HANDOFF_CHAIN_KEY)
+        .and_then(|chain| serde_json::from_str(chain).ok())
+        .unwrap_or_default()
+}
+
+/// 转交服务：挑选目标并构造转发消息和通知
+pub struct Handoff {
+    config: HandoffConfig,
+    organization: Arc<RwLock<Organization>>,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    catalog: MessageCatalog,
+}
+
+impl Handoff {
+    /// 创建转交服务
+    pub fn new(config: HandoffConfig, organization: Arc<RwLock<Organization>>, store: Arc<dyn Store>) -> Self {
+        Self {
+            config,
+            organization,
+            store,
+            clock: Arc::new(SystemClock),
+            catalog: MessageCatalog::default(),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置消息目录
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// 转交 `caller` 收到的一条私聊
+    pub async fn hand_off(&self, caller: &str, request: HandoffRequest) -> Result<HandoffOutcome> {
+        let reason = request.reason.trim().to_string();
+        if reason.is_empty() {
+            return Err(HandoffError::EmptyReason.into());
+        }
+        if request.skill.is_none() && request.department.is_none() {
+            return Err(HandoffError::NoCriteria.into());
+        }
+
+        let original = self.find_message(caller, request.message_id.as_deref()).await?;
+        let mut chain = handoff_chain(&original);
+        if chain.len() >= self.config.max_hops {
+            return Err(HandoffError::HopLimit { hops: chain.len(), max_hops: self.config.max_hops }.into());
+        }
+        let origin_from = original.metadata(HANDOFF_ORIGIN_FROM_KEY).unwrap_or(&original.from).to_string();
+        let origin_id = original.metadata(HANDOFF_ORIGIN_ID_KEY).unwrap_or(&original.id).to_string();
+
+        // 已经经手的 Agent 和原发送者都不再作为目标
+        let mut excluded: Vec<&str> = vec![caller, &origin_from];
+        excluded.extend(chain.iter().flat_map(|hop| [hop.from.as_str(), hop.to.as_str()]));
+
+        let org = self.organization.read().await;
+        let candidates = self.candidates(&org, &request, &excluded);
+        let Some(target) = candidates.first() else {
+            return Err(HandoffError::NoCandidate.into());
+        };
+        let now = self.clock.now();
+
+        if self.config.policy == HandoffPolicy::AskUser {
+            let names: Vec<String> = candidates.iter().map(|a| format!("{} ({})", a.name, a.id)).collect();
+            let content = self.catalog.format(
+                "handoff.ask_user",
+                &[("reason", &reason), ("candidates", &names.join(", "))],
+            );
+            let notice = self.notice(caller, &origin_from, &origin_id, content, now);
+            return Ok(HandoffOutcome::AskedUser {
+                candidates: candidates.iter().map(|a| a.id.clone()).collect(),
+                notice,
+            });
+        }
+
+        chain.push(HandoffHop { from: caller.to_string(), to: target.id.clone(), reason: reason.clone(), at: now });
+        let caller_name = org.find_agent(caller).map_or(caller, |a| a.name.as_str());
+        let content = self.catalog.format(
+            "handoff.forwarded",
+            &[("agent", caller_name), ("from", &origin_from), ("reason", &reason), ("content", &original.content)],
+        );
+        let mut forward = Message::private(caller, &target.id, content)
+            .with_priority(original.priority)
+            .with_metadata("kind", "handoff")
+            .with_metadata(HANDOFF_ORIGIN_ID_KEY, &origin_id)
+            .with_metadata(HANDOFF_ORIGIN_FROM_KEY, &origin_from)
+            .with_metadata(HANDOFF_CHAIN_KEY, serde_json::to_string(&chain)?);
+        forward.reply_to = Some(original.id.clone());
+        forward.timestamp = now;
+
+        let content = self.catalog.format(
+            "handoff.notice",
+            &[("agent", &target.name), ("agent_id", &target.id), ("reason", &reason)],
+        );
+        let notice = self.notice(caller, &origin_from, &origin_id, content, now);
+
+        Ok(HandoffOutcome::Forwarded { target: target.id.clone(), chain, forward: Box::new(forward), notice })
+    }
+
+    /// 按策略列出候选目标，排除已经手的 Agent
+    fn candidates<'a>(&self, org: &'a Organization, request: &HandoffRequest, excluded: &[&str]) -> Vec<&'a Agent> {
+        let department = request.department.as_deref().map(str::to_lowercase);
+        let in_department = |agent: &Agent| {
+            department.as_ref().is_none_or(|wanted| {
+                agent.department_id.as_ref().is_some_and(|id| {
+                    id.to_lowercase() == *wanted
+                        || org.find_department(id).is_some_and(|d| d.name.to_lowercase() == *wanted)
+                })
+            })
+        };
+        let has_skill = |agent: &Agent| {
+            request
+                .skill
+                .as_ref()
+                .is_none_or(|skill| agent.skills.iter().any(|s| s.eq_ignore_ascii_case(skill)))
+        };
+        let matching = org.agents.iter().filter(|a| in_department(a) && has_skill(a));
+
+        let mut candidates: Vec<&Agent> = match self.config.policy {
+            HandoffPolicy::DepartmentLeader => {
+                let mut leaders: Vec<&Agent> = Vec::new();
+                for agent in matching {
+                    let leader = agent
+                        .department_id
+                        .as_deref()
+                        .and_then(|id| org.get_department_leader(id));
+                    if let Some(leader) = leader.filter(|l| !leaders.iter().any(|a| a.id == l.id)) {
+                        leaders.push(leader);
+                    }
+                }
+                leaders
+            }
+            HandoffPolicy::FirstMatch | HandoffPolicy::AskUser => matching.collect(),
+        };
+        candidates.retain(|a| !excluded.contains(&a.id.as_str()));
+        candidates
+    }
+
+    /// 在调用者近期收到的私聊中查找要转交的消息
+    async fn find_message(&self, caller: &str, message_id: Option<&str>) -> Result<Message> {
+        let recent = self.store.load_messages_by_agent(caller, SCAN_LIMIT).await?;
+        let received = recent
+            .into_iter()
+            .filter(|m| m.from != caller && matches!(&m.to, MessageTarget::Direct(to) if to == caller));
+        let found = match message_id {
+            Some(id) => received.into_iter().find(|m| m.id == id),
+            None => received.into_iter().next(),
+        };
+        found.ok_or_else(|| match message_id {
+            Some(id) => HandoffError::MessageNotFound { message_id: id.to_string() }.into(),
+            None => HandoffError::NothingToHandOff.into(),
+        })
+    }
+
+    fn notice(&self, caller: &str, recipient: &str, origin_id: &str, content: String, now: i64) -> Message {
+        let mut notice = Message::private(caller, recipient, content)
+            .with_metadata("kind", "handoff_notice")
+            .with_metadata(HANDOFF_ORIGIN_ID_KEY, origin_id);
+        notice.reply_to = Some(origin_id.to_string());
+        notice.timestamp = now;
+        notice
+    }
+}
@@ -0,0 +1,701 @@
+//! 框架消息目录（i18n）
+//!
+//! 框架生成的面向用户文本（工具错误、Watchdog 通知、Web 错误）统一通过键查表，
+//! 内置 `en` 与 `zh` 两套目录，缺失的键回退到英文。
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// 支持的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Language {
+    /// 语言代码
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::En => "en",
+            Language::Zh => "zh",
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // 兼容 zh-CN / en_US 这类带地区的写法
+        let lower = s.trim().to_lowercase();
+        match lower.split(['-', '_']).next().unwrap_or("") {
+            "en" => Ok(Language::En),
+            "zh" => Ok(Language::Zh),
+            _ => Err(format!("Unsupported language: {}", s)),
+        }
+    }
+}
+
+/// 英文目录
+const EN_CATALOG: &[(&str, &str)] = &[
+    // 工具执行
+    ("tool.unknown", "Unknown tool: {tool_id}"),
+    ("tool.param_required", "{param} is required"),
+    ("tool.no_executor", "No executor found for tool: {tool_id}"),
+    ("tool.insufficient_skills", "Insufficient skills to execute tool: {tool_id}"),
+    ("tool.dry_run_unsupported", "Tool {tool_id} cannot be previewed; nothing was executed"),
+    ("tool.dry_run_message", "Would send a {priority} message to {target} ({count} recipients)"),
+    ("tool.dry_run_preferences", "Would update the preferences of {agent_id}"),
+    ("tool.unknown_error", "Unknown error"),
+    ("tool.original_message_not_found", "Original message not found: {message_id}"),
+    ("tool.department_not_found", "Department not found: {department_id}"),
+    ("tool.leader_not_found", "No leader found for department: {department_id}"),
+    ("tool.agent_not_found", "Agent not found: {agent_id}"),
+    ("tool.reply_prefix", "[Reply to message {message_id}] {content}"),
+    ("tool.preferences_forbidden", "You can only access your own preferences, not those of {agent_id}"),
+    ("tool.preferences_invalid", "Invalid preferences: {error}"),
+    ("tool.template_not_found", "Template not found: {name}"),
+    ("tool.template_missing_variables", "Missing template variables: {names}"),
+    ("tool.template_invalid", "Invalid template {name}: {error}"),
+    ("tool.template_escape_invalid", "Invalid escape: {escape} (expected none, html or json)"),
+    ("tool.invalid_reaction", "Invalid reaction: {emoji}"),
+    ("tool.busy", "Tool {tool_id} is busy, try again later"),
+    ("tool.busy_group", "Tool {tool_id} is busy: another tool in group {group} is running, try again later"),
+    ("tool.queued", "Tool {tool_id} was busy, queued for {seconds}s"),
+    ("tool.stalled", "Tool {tool_id} made no progress for {seconds}s and was stopped"),
+    ("tool.progress_summary", "Tool {tool_id} ran {duration}, {updates} progress updates, final result: {result}"),
+    ("tool.deprecated_alias", "Tool {alias} is deprecated and was routed to {tool_id}; call {tool_id} directly"),
+    ("tool.alias_retired", "Tool {alias} was retired on {sunset}; use {tool_id} instead"),
+    ("tool.transcript_forbidden", "You can only export your own direct messages or groups you belong to, not {session_id}"),
+    ("tool.transcript_format_invalid", "Unsupported transcript format: {format}"),
+    ("tool.email_disabled", "Email notifications are not configured"),
+    ("tool.priority_invalid", "Invalid priority: {priority} (expected low, normal, high or urgent)"),
+    ("tool.duplicate_suppressed", "Not sent: you sent the same message to the same recipient within the last {window_secs} seconds. Set allow_duplicate to true if the repeat is intended."),
+    ("tool.email_invalid_address", "Invalid email address: {address}"),
+    ("tool.email_domain_not_allowed", "You are not allowed to send email to {address}"),
+    ("tool.email_cap_reached", "Daily email limit of {cap} reached, try again tomorrow"),
+    ("tool.email_failed", "Failed to send email after {attempts} attempts: {error}"),
+    ("tool.group_ttl_invalid", "ttl_secs must be between 1 and {max}"),
+    ("tool.group_limit_reached", "You already have {count} active temporary groups (limit {limit})"),
+    ("tool.group_create_failed", "Failed to create group: {error}"),
+    ("tool.group_not_admin", "Only admins of group {group_id} can do this"),
+    ("tool.group_already_member", "{member} is already a member of group {group_id}"),
+    ("tool.group_not_member", "You are not a member of group {group_id}"),
+    ("tool.group_stats_disabled", "Group stats are not enabled for this company"),
+    ("tool.group_moderation_failed", "Group moderation failed: {error}"),
+    ("tool.pin_message_not_found", "Message not found: {message_id}"),
+    ("tool.pin_not_participant", "You are not a participant of {session_id}"),
+    ("tool.pin_not_admin", "Only {pinned_by} or a group admin can unpin this message"),
+    ("tool.pin_failed", "Failed to update pins: {error}"),
+    ("tool.group_mute_invalid", "duration_secs must be between 1 and {max}"),
+    ("tool.task_not_found", "Task not found: {task_id}"),
+    ("tool.task_invalid_transition", "Task {task_id} cannot move from {from} to {to}"),
+    ("tool.task_not_participant", "You are not the creator or assignee of task {task_id}"),
+    ("tool.task_invalid_status", "Invalid task status: {status} (expected open, in_progress, blocked, done or cancelled)"),
+    ("tool.handoff_disabled", "Handoff is not enabled for this company"),
+    ("tool.handoff_message_not_found", "Message {message_id} is not a direct message you received"),
+    ("tool.handoff_nothing", "You have no direct message to hand off"),
+    ("tool.handoff_hop_limit", "This message was already handed off {hops} times (limit {max_hops}); answer it yourself or ask the sender"),
+    ("tool.handoff_no_candidate", "No other agent matches the requested skill or department"),
+    ("tool.code_run_disabled", "Code execution is not enabled for this company"),
+    ("tool.code_run_unsupported_language", "No sandbox runtime for {language}; available: {languages}"),
+    ("tool.code_run_fuel", "The program ran out of fuel ({fuel} steps); simplify it or process less input"),
+    ("tool.code_run_timeout", "The program ran longer than {timeout_ms} ms and was stopped"),
+    ("tool.code_run_memory", "The program exceeded the memory limit of {limit_mb} MiB"),
+    ("tool.code_run_output", "The program printed more than {max_bytes} bytes; print a summary instead"),
+    ("tool.code_run_failed", "The program failed: {error}"),
+    ("tool.temp_agent_disabled", "Temporary agents are not enabled for this company"),
+    ("tool.scratchpad_disabled", "The scratchpad is not enabled for this company"),
+    ("tool.goal_disabled", "Goals are not enabled for this company"),
+    ("tool.session_disabled", "Chat sessions are not enabled for this company"),
+    ("tool.session_invalid_resolution", "Unknown resolution {resolution}; use resolved or unresolved"),
+    ("tool.session_not_open", "You have no open chat session"),
+    ("tool.temp_agent_nested", "Temporary agents cannot spawn other temporary agents"),
+    ("tool.temp_agent_limit", "The company already has {count} temporary agents (limit {limit}); try again after one expires"),
+    ("tool.temp_agent_spawner_limit", "You already have {count} temporary agents (limit {limit}); try again after one expires"),
+    ("tool.temp_agent_failed", "Cannot spawn a temporary agent: {error}"),
+    // 临时群聊
+    ("group.expired_notice", "[Temporary group] {name} has expired and is now closed."),
+    ("group.removed_notice", "[Group] {actor} removed you from {name}."),
+    // 加入群聊的前情提要
+    ("join_context.header", "[Catch-up] You were added to {name}. Here is what happened there before you joined; only you can see this."),
+    ("join_context.empty", "Nothing has been said there yet."),
+    ("join_context.pinned", "Pinned messages:"),
+    ("join_context.summary", "Summary of the earlier discussion ({count} messages):"),
+    ("join_context.recent", "Last {count} messages:"),
+    // 临时 Agent
+    ("temp_agent.expired_notice", "[Temporary agent] {name} in {session_id} has expired; the conversation is kept."),
+    ("message.content_too_large", "The message is {size} bytes, over the {limit}-byte limit. Shorten it or split it into several messages."),
+    ("message.too_many_mentions", "The message mentions {size} agents, over the limit of {limit}. Mention fewer agents."),
+    ("message.metadata_too_large", "The message metadata is {size} bytes, over the {limit}-byte limit."),
+    ("message.truncated_preview", "{preview}\n[Truncated: the full {size}-byte message is in attachment {attachment_id}]"),
+    ("group.sync_joined_notice", "[Group] {members} joined {name} with department {department}."),
+    ("group.sync_left_notice", "[Group] {members} left {name} as they are no longer in department {department}."),
+    ("group.sync_kicked_notice", "[Group] {members} are in department {department} but were removed from {name} by an admin, so they were not added back."),
+    ("agent_draft.intro", "Hi, I'm HR. I'll ask a few questions and then draft the new agent for you to review."),
+    ("agent_draft.question_responsibilities", "What should this agent be responsible for? Describe its main tasks."),
+    ("agent_draft.question_tone", "How should it talk to people, e.g. formal, friendly or concise?"),
+    ("agent_draft.question_tools", "Which tools or systems does it need, and should it keep an eye on any of them?"),
+    ("agent_draft.question_department", "Which department should it join?"),
+    ("agent_draft.drafting", "Thanks, drafting the role now..."),
+    ("agent_draft.failed", "Sorry, I couldn't draft the role. Reply with anything to try again."),
+    ("agent_draft.ready", "The draft \"{title}\" is ready. Review and edit it in draft session {session_id}, then commit it to create the agent."),
+    // 任务通知
+    ("task.assigned", "[Task] {actor} assigned you task {task_id}: {title} (due {due})"),
+    ("task.due_soon", "[Task] Task {task_id} \"{title}\" is due at {due}."),
+    ("task.overdue", "[Task] Task {task_id} \"{title}\" was due at {due} and is overdue."),
+    // Agent 转交
+    ("handoff.forwarded", "[Handoff] {agent} handed you a message from {from}. Reason: {reason}\nOriginal message: {content}"),
+    ("handoff.notice", "[Handoff] Your message was handed to {agent} ({agent_id}). Reason: {reason}"),
+    ("handoff.ask_user", "[Handoff] I'm not the right one for this ({reason}). Who should take it: {candidates}?"),
+    // Watchdog 通知
+    ("watchdog.triggered", "Watchdog rule {rule_id} triggered by tool {tool_id}: {result}"),
+    // 消息升级
+    ("escalation.notice", "[Escalation] {agent_id} has not replied to {from} for {minutes} minutes. Original message: {content}"),
+    ("escalation.email_subject", "[ImitatorT] {agent_id} needs attention"),
+    // 循环抑制
+    ("loop_guard.notice", "[Loop guard] Your message was not sent: {reason}. Outgoing messages are paused for about {minutes} minutes or until a person writes to you."),
+    ("loop_guard.chain_depth", "the reply chain between agents reached depth {depth} (max {max})"),
+    ("loop_guard.pair_rate", "you exchanged {exchanges} messages with {peer} within {minutes} minutes without human input"),
+    ("loop_guard.duplicate", "it repeats your recent messages"),
+    ("loop_guard.cooldown", "you are cooling down after a loop was detected"),
+    // Web 错误
+    ("web.unauthorized", "Unauthorized"),
+    ("web.insufficient_permissions", "Insufficient permissions"),
+    ("web.agent_not_found", "Agent not found: {agent_id}"),
+    ("web.missing_to_field", "Missing 'to' field"),
+    ("web.idempotency_conflict", "Idempotency key {key} was already used for a different request"),
+    ("web.idempotency_in_progress", "A request with idempotency key {key} is still being processed"),
+    ("web.idempotency_requires_auth", "Requests with an Idempotency-Key must be authenticated"),
+    ("web.database_error", "Database error"),
+    ("web.token_generation_failed", "Failed to generate token"),
+    ("web.invalid_credentials", "Invalid username or password"),
+    ("web.username_exists", "Username already exists"),
+    ("web.department_full", "Department {department} is full ({current}/{max} agents)"),
+    ("web.first_user_no_invite", "First user registration does not require an invitation code"),
+    ("web.password_processing_failed", "Failed to process password"),
+    ("web.invite_code_required", "Invitation code is required for registration"),
+    ("web.invite_code_invalid", "Invalid invitation code"),
+    ("web.invite_code_expired", "Invitation code has expired or reached maximum usage"),
+    ("web.invite_code_update_failed", "Failed to update invitation code"),
+    ("web.register_failed", "Failed to register user"),
+    ("web.invite_codes_load_failed", "Failed to load invitation codes"),
+    ("web.invite_code_save_failed", "Failed to save invitation code"),
+    ("web.invite_codes_update_failed", "Failed to update invitation codes"),
+    ("web.invite_code_not_found", "Invitation code not found"),
+    ("web.chat_sessions_load_failed", "Failed to load chat sessions"),
+    ("web.chat_session_unavailable", "Chat sessions are not enabled"),
+    ("web.chat_session_invalid", "Cannot update chat session: {error}"),
+    ("web.chat_session_failed", "Failed to update chat session"),
+    ("web.messages_load_failed", "Failed to load messages"),
+    ("web.unknown_agent", "Unknown Agent"),
+    ("web.org_tree_load_failed", "Failed to load organization tree"),
+    ("web.mentions_load_failed", "Failed to load mention suggestions"),
+    ("web.users_load_failed", "Failed to load users"),
+    ("web.tool_stats_load_failed", "Failed to load tool stats"),
+    ("web.preferences_load_failed", "Failed to load agent preferences"),
+    ("web.org_changes_load_failed", "Failed to load organization changes"),
+    ("web.chaos_not_configured", "Fault injection is not configured"),
+    ("web.chaos_disabled", "Fault injection is disabled"),
+    ("web.chaos_rule_invalid", "Invalid fault rule: {error}"),
+    ("web.agent_unavailable", "Agent {agent_id} is outside working hours until {next}"),
+    ("web.availability_invalid", "Invalid availability schedule: {error}"),
+    ("web.availability_update_failed", "Failed to update availability"),
+    ("web.agent_exists", "Agent {agent_id} already exists"),
+    ("web.agent_create_invalid", "Invalid agent: {error}"),
+    ("web.agent_llm_required", "No default LLM is configured; give llm_config.api_key and llm_config.model"),
+    ("web.agent_api_key_literal", "The API key of agent {agent_id} must be a reference such as env:NAME or keyring:NAME, plaintext keys are not stored"),
+    ("web.agent_create_failed", "Failed to create agent"),
+    ("web.department_not_found", "Department not found: {department_id}"),
+    ("web.agent_is_leader", "Agent {agent_id} leads departments {departments}; pass force=true to clear the leadership and delete it"),
+    ("web.agent_delete_failed", "Failed to delete agent"),
+    ("web.agent_batch_too_large", "A batch can hold at most {max} operations (got {count})"),
+    ("web.agent_batch_failed", "Failed to apply agent batch"),
+    ("web.integrity_check_failed", "Integrity check failed"),
+    ("web.message_tiers_failed", "Failed to count messages"),
+    ("web.preferences_reset_failed", "Failed to reset agent preferences"),
+    ("web.role_update_failed", "Failed to update agent role"),
+    ("web.response_language_invalid", "Unknown response language: {language}"),
+    ("web.preferred_language_invalid", "Unknown preferred language: {language}"),
+    ("web.group_not_found", "Group {group_id} not found"),
+    ("web.group_not_admin", "Only group admins can moderate {group_id}"),
+    ("web.group_moderation_invalid", "Invalid moderation request: {error}"),
+    ("web.task_not_found", "Task {task_id} not found"),
+    ("web.share_token_invalid", "Share link is invalid, expired or revoked"),
+    ("web.share_token_scope", "Share tokens can only be used to view the shared transcript"),
+    ("web.standby_read_only", "This node is a standby and is read-only; send changes to the leader {leader}"),
+    ("web.standby_no_leader", "This node is a standby and is read-only; no leader is currently available"),
+    ("web.quarantine_not_found", "Quarantined output {id} not found"),
+    ("web.quarantine_failed", "Failed to access moderation quarantine"),
+    ("web.share_forbidden", "Only admins and admins of group {group_id} can manage its share links"),
+    ("web.share_expiry_invalid", "expires_in_secs must be between 1 and {max}"),
+    ("web.share_not_found", "Share link {share_id} not found"),
+    ("web.attachments_unavailable", "Attachment storage is not configured"),
+    ("web.attachment_not_found", "Attachment {attachment_id} not found"),
+    ("web.attachment_range_invalid", "Requested range is outside attachment {attachment_id}"),
+    ("web.attachment_failed", "Attachment storage operation failed"),
+    ("web.permissions_changed", "Your permissions have changed, please log in again"),
+    ("web.digests_load_failed", "Failed to load digests"),
+    ("web.task_invalid", "Invalid task request: {error}"),
+    ("web.task_forbidden", "You are not the creator or assignee of task {task_id}"),
+    ("web.group_moderation_failed", "Failed to update group"),
+    ("web.group_sync_invalid", "Cannot sync group members: {error}"),
+    ("web.group_sync_failed", "Failed to sync group members"),
+    ("web.group_stats_unavailable", "Group stats are not enabled"),
+    ("web.group_stats_forbidden", "Only members and admins of {group_id} can view its stats"),
+    ("web.group_stats_failed", "Failed to load group stats"),
+    ("web.group_exists", "Group {group_id} already exists"),
+    ("web.group_create_invalid", "Invalid group: {error}"),
+    ("web.group_create_failed", "Failed to create group"),
+    ("web.group_member_unknown", "Unknown member: {member_id}"),
+    ("web.group_messages_forbidden", "Only members and admins of {group_id} can read its messages"),
+    ("web.agent_draft_unavailable", "Agent drafting is not available: no LLM configured"),
+    ("web.agent_draft_not_found", "Draft session not found: {session_id}"),
+    ("web.agent_draft_invalid", "Cannot use agent draft: {error}"),
+    ("web.agent_draft_failed", "Failed to process agent draft"),
+    ("web.temp_agent_unavailable", "Temporary agents are not available"),
+    ("web.temp_agent_invalid", "Cannot spawn temporary agent: {error}"),
+    ("web.temp_agent_failed", "Failed to spawn temporary agent"),
+    ("web.scratchpad_unavailable", "The scratchpad is not enabled"),
+    ("web.tool_executor_unavailable", "Tool execution is not available on this server"),
+    ("web.tool_dry_run_failed", "Failed to preview the tool call: {error}"),
+    ("web.ws_encoding_unsupported", "This frame encoding is not supported by the server"),
+    ("web.scratchpad_load_failed", "Failed to load scratchpad"),
+    ("web.nudge_empty", "A nudge needs content unless it cancels"),
+    ("web.nudge_no_tool_loop", "Agent {agent_id} is not calling tools; there is nothing to cancel"),
+    ("web.message_bus_unavailable", "Message bus is not attached"),
+    ("web.role_revision_not_found", "Role revision {revision} not found for agent {agent_id}"),
+    ("web.session_not_found", "Chat session {session_id} not found"),
+    ("web.export_format_invalid", "Unsupported export format: {format} (use markdown, html or json)"),
+    ("web.route_not_found", "API route not found"),
+    ("web.not_ready", "Not ready: {checks}"),
+    ("web.invalid_reaction", "Invalid reaction: {emoji}"),
+    ("web.reaction_failed", "Failed to update reaction"),
+    ("web.message_not_found", "Message {message_id} not found"),
+    ("web.offset_too_large", "offset must not exceed {max}, use the next_cursor to page further back"),
+    ("web.pin_not_participant", "You are not a participant of {session_id}"),
+    ("web.pin_not_admin", "Only {pinned_by} or a group admin can unpin this message"),
+    ("web.pin_failed", "Failed to update pinned messages"),
+    ("web.bookmark_failed", "Failed to update bookmarks"),
+    ("web.scheduler_unavailable", "Turn scheduler is not enabled"),
+    ("web.retries_unavailable", "Retry metrics are not available"),
+    ("web.breakers_unavailable", "Agent circuit breakers are not enabled"),
+    ("web.prompt_log_disabled", "Prompt logging is not enabled"),
+    ("web.feature_not_found", "Unknown feature: {feature}"),
+    ("web.feature_not_toggleable", "Feature {feature} cannot be toggled at runtime"),
+    ("web.context_load_failed", "Failed to reconstruct agent context"),
+    ("web.login_throttled", "Too many failed login attempts, please retry in {seconds} seconds"),
+    ("web.current_password_incorrect", "Current password is incorrect"),
+    ("web.password_change_failed", "Failed to change password"),
+    // 活动摘要
+    ("digest.subject", "Company digest for {start} – {end}"),
+    ("digest.messages", "Messages: {total} ({direct} direct, {group} in groups)"),
+    ("digest.top_senders", "Most active: {senders}"),
+    ("digest.decisions", "Decisions:"),
+    ("digest.tasks", "Completed tasks: {count}"),
+    ("digest.incidents", "Watchdog incidents: {count}"),
+    ("digest.budgets", "LLM budget used today:"),
+    ("digest.group_activity", "Who spoke in groups:"),
+    ("digest.group_quiet", "Quiet: {members}"),
+    ("digest.omitted", "Not included: {section} ({reason})"),
+    // Agent 熔断
+    ("breaker.tripped", "Agent {agent} ({agent_id}) has been paused after repeated failures: {reason}. Last error: {error}. Incoming messages are queued; it will retry at {retry_at}, or an admin can reset it with POST /api/agents/{agent_id}/reset-breaker."),
+    ("breaker.llm_failures", "{count} consecutive LLM failures"),
+    ("breaker.llm_auth_failed", "the LLM API rejected its API key"),
+    ("breaker.tool_errors", "{errors} of {calls} tool calls failed in the last {window} seconds"),
+    ("breaker.moderation_blocks", "{blocks} outputs blocked by content moderation in the last {window} seconds"),
+    // 内容审核
+    ("moderation.blocked_notice", "This reply from {agent} was withheld by content moderation. An administrator can review it."),
+    // 密码策略
+    ("password.too_short", "Password must be at least {min} characters long"),
+    ("password.missing_uppercase", "Password must contain an uppercase letter"),
+    ("password.missing_lowercase", "Password must contain a lowercase letter"),
+    ("password.missing_digit", "Password must contain a digit"),
+    ("password.missing_symbol", "Password must contain a special character"),
+    ("password.too_common", "Password is too common"),
+    // 启动提示
+    ("startup.banner", "🚀 Starting ImitatorT - Multi-Agent Company Framework..."),
+    ("startup.console_mode", "ℹ️  Running in console mode (no web interface)"),
+    ("startup.shutdown", "🛑 Received shutdown signal"),
+    ("startup.missing_api_key", "Warning: OPENAI_API_KEY is not set, using a placeholder key"),
+    ("startup.admin_credentials", "🔑 Created admin user '{username}' with password: {password}\n   This password is shown only once, change it after logging in."),
+    ("startup.secrets_migrated", "🔑 {agent_id}: {name}={value}"),
+    ("startup.secrets_migrate_notice", "   The API keys above are no longer stored in the database and are shown only once; set these environment variables before restarting."),
+    ("startup.secrets_migrate_none", "No plaintext API keys found in {database}"),
+    ("startup.demo_welcome", "Welcome to ImitatorT! This is a demo conversation seeded on first run."),
+    ("startup.demo_mentions", "Mention a colleague with @ to get their attention, or send them a direct message."),
+    ("startup.demo_prompt", "Try assigning a task to the team from the web console to see the agents collaborate."),
+];
+
+/// 中文目录
+const ZH_CATALOG: &[(&str, &str)] = &[
+    // 工具执行
+    ("tool.unknown", "未知工具: {tool_id}"),
+    ("tool.param_required", "缺少必填参数 {param}"),
+    ("tool.no_executor", "没有可执行该工具的执行器: {tool_id}"),
+    ("tool.insufficient_skills", "技能不足，无法执行工具: {tool_id}"),
+    ("tool.dry_run_unsupported", "工具 {tool_id} 不支持预演，未执行任何操作"),
+    ("tool.dry_run_message", "将向 {target} 发送一条 {priority} 优先级的消息（{count} 位收件人）"),
+    ("tool.dry_run_preferences", "将更新 {agent_id} 的偏好设置"),
+    ("tool.unknown_error", "未知错误"),
+    ("tool.original_message_not_found", "未找到原消息: {message_id}"),
+    ("tool.department_not_found", "未找到部门: {department_id}"),
+    ("tool.leader_not_found", "部门 {department_id} 没有负责人"),
+    ("tool.agent_not_found", "未找到 Agent: {agent_id}"),
+    ("tool.reply_prefix", "[回复消息 {message_id}] {content}"),
+    ("tool.preferences_forbidden", "只能访问自己的偏好设置，无权访问 {agent_id} 的偏好"),
+    ("tool.preferences_invalid", "偏好设置无效: {error}"),
+    ("tool.template_not_found", "模板不存在: {name}"),
+    ("tool.template_missing_variables", "缺少模板变量: {names}"),
+    ("tool.template_invalid", "模板 {name} 无效: {error}"),
+    ("tool.template_escape_invalid", "无效的转义方式: {escape}（可选 none、html、json）"),
+    ("tool.invalid_reaction", "无效的回应: {emoji}"),
+    ("tool.busy", "工具 {tool_id} 正忙，请稍后再试"),
+    ("tool.busy_group", "工具 {tool_id} 正忙：互斥组 {group} 中有工具正在执行，请稍后再试"),
+    ("tool.queued", "工具 {tool_id} 正忙，已排队 {seconds} 秒"),
+    ("tool.stalled", "工具 {tool_id} 已 {seconds} 秒没有进展，已停止"),
+    ("tool.progress_summary", "工具 {tool_id} 运行了 {duration}，{updates} 次进度更新，最终结果：{result}"),
+    ("tool.deprecated_alias", "工具 {alias} 已弃用，本次已转到 {tool_id}；请直接调用 {tool_id}"),
+    ("tool.alias_retired", "工具 {alias} 已于 {sunset} 停用，请改用 {tool_id}"),
+    ("tool.transcript_forbidden", "只能导出自己的私聊或自己所在的群聊，无权导出 {session_id}"),
+    ("tool.transcript_format_invalid", "不支持的导出格式: {format}"),
+    ("tool.email_disabled", "未配置邮件通知"),
+    ("tool.priority_invalid", "无效的优先级: {priority}（可选 low、normal、high、urgent）"),
+    ("tool.duplicate_suppressed", "未发送：{window_secs} 秒内你已向同一对象发送过相同的消息。确需重复发送时把 allow_duplicate 设为 true。"),
+    ("tool.email_invalid_address", "无效的邮箱地址: {address}"),
+    ("tool.email_domain_not_allowed", "不允许给 {address} 发邮件"),
+    ("tool.email_cap_reached", "已达到每日 {cap} 封的邮件上限，请明天再试"),
+    ("tool.email_failed", "邮件发送失败（已尝试 {attempts} 次）: {error}"),
+    ("tool.group_ttl_invalid", "ttl_secs 必须在 1 到 {max} 之间"),
+    ("tool.group_limit_reached", "你已有 {count} 个进行中的临时群聊（上限 {limit}）"),
+    ("tool.group_create_failed", "创建群聊失败: {error}"),
+    ("tool.group_not_admin", "只有群聊 {group_id} 的管理员可以执行此操作"),
+    ("tool.group_already_member", "{member} 已经是群聊 {group_id} 的成员"),
+    ("tool.group_not_member", "你不是群聊 {group_id} 的成员"),
+    ("tool.group_stats_disabled", "公司未启用群聊活动统计"),
+    ("tool.group_moderation_failed", "群聊管理操作失败: {error}"),
+    ("tool.pin_message_not_found", "消息不存在：{message_id}"),
+    ("tool.pin_not_participant", "你不是 {session_id} 的参与者"),
+    ("tool.pin_not_admin", "只有 {pinned_by} 或群管理员可以取消置顶"),
+    ("tool.pin_failed", "更新置顶失败：{error}"),
+    ("tool.group_mute_invalid", "duration_secs 必须在 1 到 {max} 之间"),
+    ("tool.task_not_found", "任务不存在：{task_id}"),
+    ("tool.task_invalid_transition", "任务 {task_id} 不能从 {from} 变为 {to}"),
+    ("tool.task_not_participant", "你不是任务 {task_id} 的创建者或负责人"),
+    ("tool.task_invalid_status", "无效的任务状态：{status}（应为 open、in_progress、blocked、done 或 cancelled）"),
+    ("tool.handoff_disabled", "公司未启用消息转交"),
+    ("tool.handoff_message_not_found", "消息 {message_id} 不是你收到的私聊"),
+    ("tool.handoff_nothing", "你没有可以转交的私聊"),
+    ("tool.handoff_hop_limit", "这条消息已被转交 {hops} 次（上限 {max_hops}），请自行回复或询问发送者"),
+    ("tool.handoff_no_candidate", "没有其他 Agent 具备所需技能或属于所需部门"),
+    ("tool.code_run_disabled", "公司未启用代码运行"),
+    ("tool.code_run_unsupported_language", "没有 {language} 的沙箱运行时，可用语言：{languages}"),
+    ("tool.code_run_fuel", "程序耗尽了燃料（{fuel} 步），请简化程序或减少输入"),
+    ("tool.code_run_timeout", "程序运行超过 {timeout_ms} 毫秒，已被终止"),
+    ("tool.code_run_memory", "程序超过了 {limit_mb} MiB 的内存上限"),
+    ("tool.code_run_output", "程序输出超过 {max_bytes} 字节，请改为输出摘要"),
+    ("tool.code_run_failed", "程序运行失败：{error}"),
+    ("tool.temp_agent_disabled", "公司未启用临时 Agent"),
+    ("tool.scratchpad_disabled", "公司未启用草稿"),
+    ("tool.goal_disabled", "公司未启用当前目标"),
+    ("tool.session_disabled", "公司未启用会话生命周期"),
+    ("tool.session_invalid_resolution", "未知的解决状态 {resolution}，请使用 resolved 或 unresolved"),
+    ("tool.session_not_open", "你当前没有进行中的会话"),
+    ("tool.temp_agent_nested", "临时 Agent 不能再创建临时 Agent"),
+    ("tool.temp_agent_limit", "公司已有 {count} 个临时 Agent（上限 {limit}），请等其中一个过期后再试"),
+    ("tool.temp_agent_spawner_limit", "你已有 {count} 个临时 Agent（上限 {limit}），请等其中一个过期后再试"),
+    ("tool.temp_agent_failed", "无法创建临时 Agent：{error}"),
+    // 临时群聊
+    ("group.expired_notice", "[临时群聊] {name} 已到期关闭。"),
+    ("group.removed_notice", "[群聊] {actor} 已将你移出 {name}。"),
+    // 加入群聊的前情提要
+    ("join_context.header", "[前情提要] 你已加入 {name}。以下是你加入前群里的情况，只有你能看到。"),
+    ("join_context.empty", "群里还没有人发言。"),
+    ("join_context.pinned", "置顶消息："),
+    ("join_context.summary", "之前讨论的总结（{count} 条消息）："),
+    ("join_context.recent", "最近 {count} 条消息："),
+    // 临时 Agent
+    ("temp_agent.expired_notice", "[临时 Agent] {session_id} 中的 {name} 已过期，会话记录已保留。"),
+    ("message.content_too_large", "消息有 {size} 字节，超过 {limit} 字节的上限。请缩短或拆成多条消息。"),
+    ("message.too_many_mentions", "消息 @ 了 {size} 个 Agent，超过 {limit} 个的上限。请减少 @ 的对象。"),
+    ("message.metadata_too_large", "消息元数据有 {size} 字节，超过 {limit} 字节的上限。"),
+    ("message.truncated_preview", "{preview}\n[已截断：完整的 {size} 字节消息见附件 {attachment_id}]"),
+    ("group.sync_joined_notice", "[群聊] {members} 随部门 {department} 加入了 {name}。"),
+    ("group.sync_left_notice", "[群聊] {members} 已不在部门 {department}，离开了 {name}。"),
+    ("group.sync_kicked_notice", "[群聊] {members} 在部门 {department} 中，但曾被管理员移出 {name}，未重新加入。"),
+    ("agent_draft.intro", "你好，我是 HR。我会问几个问题，然后起草新 Agent 供你审阅。"),
+    ("agent_draft.question_responsibilities", "这个 Agent 负责什么？请描述它的主要工作。"),
+    ("agent_draft.question_tone", "它和别人说话应该是什么语气，例如正式、友好还是简洁？"),
+    ("agent_draft.question_tools", "它需要哪些工具或系统？需要持续关注其中哪些？"),
+    ("agent_draft.question_department", "它应该加入哪个部门？"),
+    ("agent_draft.drafting", "谢谢，正在起草角色……"),
+    ("agent_draft.failed", "抱歉，未能起草角色。回复任意内容重试。"),
+    ("agent_draft.ready", "草稿「{title}」已生成。请在草稿会话 {session_id} 中审阅和修改，然后提交以创建 Agent。"),
+    // 任务通知
+    ("task.assigned", "[任务] {actor} 给你指派了任务 {task_id}：{title}（截止 {due}）"),
+    ("task.due_soon", "[任务] 任务 {task_id}「{title}」将于 {due} 到期。"),
+    ("task.overdue", "[任务] 任务 {task_id}「{title}」已于 {due} 到期，现已逾期。"),
+    // Agent 转交
+    ("handoff.forwarded", "[转交] {agent} 转交给你一条来自 {from} 的消息。原因：{reason}\n原消息：{content}"),
+    ("handoff.notice", "[转交] 你的消息已转交给 {agent}（{agent_id}）。原因：{reason}"),
+    ("handoff.ask_user", "[转交] 这件事不适合由我处理（{reason}）。请问交给谁：{candidates}？"),
+    // Watchdog 通知
+    ("watchdog.triggered", "监控规则 {rule_id} 被工具 {tool_id} 触发: {result}"),
+    // 消息升级
+    ("escalation.notice", "[升级] {agent_id} 已 {minutes} 分钟未回复 {from} 的消息。原消息: {content}"),
+    ("escalation.email_subject", "[ImitatorT] {agent_id} 需要处理"),
+    // 循环抑制
+    ("loop_guard.notice", "[循环抑制] 你的消息未发送: {reason}。约 {minutes} 分钟内或有人给你发消息前将暂停发送。"),
+    ("loop_guard.chain_depth", "Agent 之间的回复链达到 {depth} 层（上限 {max}）"),
+    ("loop_guard.pair_rate", "你与 {peer} 在 {minutes} 分钟内无人介入地往来了 {exchanges} 条消息"),
+    ("loop_guard.duplicate", "内容与你最近的消息重复"),
+    ("loop_guard.cooldown", "检测到循环后的冷却期"),
+    // Web 错误
+    ("web.unauthorized", "未授权"),
+    ("web.insufficient_permissions", "权限不足"),
+    ("web.agent_not_found", "未找到 Agent: {agent_id}"),
+    ("web.missing_to_field", "缺少 'to' 字段"),
+    ("web.idempotency_conflict", "幂等键 {key} 已用于其他请求"),
+    ("web.idempotency_in_progress", "幂等键 {key} 对应的请求仍在处理中"),
+    ("web.idempotency_requires_auth", "携带幂等键的请求需要登录"),
+    ("web.database_error", "数据库错误"),
+    ("web.token_generation_failed", "生成令牌失败"),
+    ("web.invalid_credentials", "用户名或密码错误"),
+    ("web.username_exists", "用户名已存在"),
+    ("web.department_full", "部门 {department} 已满员（{current}/{max} 个 Agent）"),
+    ("web.first_user_no_invite", "首位用户注册无需邀请码"),
+    ("web.password_processing_failed", "密码处理失败"),
+    ("web.invite_code_required", "注册需要邀请码"),
+    ("web.invite_code_invalid", "邀请码无效"),
+    ("web.invite_code_expired", "邀请码已过期或已达到使用上限"),
+    ("web.invite_code_update_failed", "更新邀请码失败"),
+    ("web.register_failed", "注册用户失败"),
+    ("web.invite_codes_load_failed", "加载邀请码失败"),
+    ("web.invite_code_save_failed", "保存邀请码失败"),
+    ("web.invite_codes_update_failed", "更新邀请码列表失败"),
+    ("web.invite_code_not_found", "邀请码不存在"),
+    ("web.chat_sessions_load_failed", "加载会话列表失败"),
+    ("web.chat_session_unavailable", "未启用会话生命周期"),
+    ("web.chat_session_invalid", "无法更新会话：{error}"),
+    ("web.chat_session_failed", "更新会话失败"),
+    ("web.messages_load_failed", "加载消息失败"),
+    ("web.unknown_agent", "未知 Agent"),
+    ("web.org_tree_load_failed", "加载组织架构失败"),
+    ("web.mentions_load_failed", "加载提及候选失败"),
+    ("web.users_load_failed", "加载用户列表失败"),
+    ("web.tool_stats_load_failed", "加载工具统计失败"),
+    ("web.preferences_load_failed", "加载 Agent 偏好失败"),
+    ("web.org_changes_load_failed", "加载组织架构变更失败"),
+    ("web.chaos_not_configured", "未配置故障注入"),
+    ("web.chaos_disabled", "故障注入未启用"),
+    ("web.chaos_rule_invalid", "故障规则无效：{error}"),
+    ("web.agent_unavailable", "Agent {agent_id} 当前不在工作时间，将于 {next} 上线"),
+    ("web.availability_invalid", "工作时间设置无效：{error}"),
+    ("web.availability_update_failed", "更新工作时间失败"),
+    ("web.agent_exists", "Agent {agent_id} 已存在"),
+    ("web.agent_create_invalid", "Agent 无效: {error}"),
+    ("web.agent_llm_required", "未配置默认 LLM，请给出 llm_config.api_key 和 llm_config.model"),
+    ("web.agent_api_key_literal", "Agent {agent_id} 的 API Key 须为 env:NAME 或 keyring:NAME 形式的引用，明文不会被保存"),
+    ("web.agent_create_failed", "创建 Agent 失败"),
+    ("web.department_not_found", "未找到部门: {department_id}"),
+    ("web.agent_is_leader", "Agent {agent_id} 是部门 {departments} 的负责人；传入 force=true 可清除负责人后删除"),
+    ("web.agent_delete_failed", "删除 Agent 失败"),
+    ("web.agent_batch_too_large", "一次最多提交 {max} 项操作（收到 {count} 项）"),
+    ("web.agent_batch_failed", "批量操作 Agent 失败"),
+    ("web.integrity_check_failed", "数据完整性检查失败"),
+    ("web.message_tiers_failed", "统计消息数失败"),
+    ("web.preferences_reset_failed", "重置 Agent 偏好失败"),
+    ("web.role_update_failed", "更新 Agent 角色失败"),
+    ("web.response_language_invalid", "未知的回复语言：{language}"),
+    ("web.preferred_language_invalid", "未知的首选语言：{language}"),
+    ("web.group_not_found", "群聊 {group_id} 不存在"),
+    ("web.group_not_admin", "只有群管理员可以管理 {group_id}"),
+    ("web.group_moderation_invalid", "无效的群聊管理请求：{error}"),
+    ("web.task_not_found", "任务 {task_id} 不存在"),
+    ("web.share_token_invalid", "分享链接无效、已过期或已撤销"),
+    ("web.share_token_scope", "分享令牌只能用于查看分享的群聊记录"),
+    ("web.standby_read_only", "本节点是备用节点，只能读取；修改请发往领导者 {leader}"),
+    ("web.standby_no_leader", "本节点是备用节点，只能读取；当前没有可用的领导者"),
+    ("web.quarantine_not_found", "隔离的输出 {id} 不存在"),
+    ("web.quarantine_failed", "访问内容审核隔离表失败"),
+    ("web.share_forbidden", "只有管理员和群 {group_id} 的管理员可以管理分享链接"),
+    ("web.share_expiry_invalid", "expires_in_secs 必须在 1 到 {max} 之间"),
+    ("web.share_not_found", "分享链接 {share_id} 不存在"),
+    ("web.attachments_unavailable", "未配置附件存储"),
+    ("web.attachment_not_found", "附件 {attachment_id} 不存在"),
+    ("web.attachment_range_invalid", "请求的范围超出附件 {attachment_id}"),
+    ("web.attachment_failed", "附件存储操作失败"),
+    ("web.permissions_changed", "权限已变更，请重新登录"),
+    ("web.digests_load_failed", "加载活动摘要失败"),
+    ("web.task_invalid", "无效的任务请求：{error}"),
+    ("web.task_forbidden", "你不是任务 {task_id} 的创建者或负责人"),
+    ("web.group_moderation_failed", "更新群聊失败"),
+    ("web.group_sync_invalid", "无法同步群成员：{error}"),
+    ("web.group_sync_failed", "同步群成员失败"),
+    ("web.group_stats_unavailable", "未启用群聊活动统计"),
+    ("web.group_stats_forbidden", "只有 {group_id} 的成员和管理员可以查看其活动统计"),
+    ("web.group_stats_failed", "加载群聊活动统计失败"),
+    ("web.group_exists", "群聊 {group_id} 已存在"),
+    ("web.group_create_invalid", "无效的群聊：{error}"),
+    ("web.group_create_failed", "创建群聊失败"),
+    ("web.group_member_unknown", "未知的成员：{member_id}"),
+    ("web.group_messages_forbidden", "只有 {group_id} 的成员和管理员可以查看其消息"),
+    ("web.agent_draft_unavailable", "无法起草 Agent：未配置 LLM"),
+    ("web.agent_draft_not_found", "未找到草稿会话: {session_id}"),
+    ("web.agent_draft_invalid", "无法使用 Agent 草稿：{error}"),
+    ("web.agent_draft_failed", "处理 Agent 草稿失败"),
+    ("web.temp_agent_unavailable", "临时 Agent 不可用"),
+    ("web.temp_agent_invalid", "无法创建临时 Agent：{error}"),
+    ("web.temp_agent_failed", "创建临时 Agent 失败"),
+    ("web.scratchpad_unavailable", "未启用草稿"),
+    ("web.tool_executor_unavailable", "此服务器未提供工具执行"),
+    ("web.tool_dry_run_failed", "预演工具调用失败：{error}"),
+    ("web.ws_encoding_unsupported", "服务端不支持该帧编码"),
+    ("web.scratchpad_load_failed", "加载草稿失败"),
+    ("web.nudge_empty", "插话内容不能为空（cancel 插话除外）"),
+    ("web.nudge_no_tool_loop", "Agent {agent_id} 没有在调用工具，没有可结束的工具调用"),
+    ("web.message_bus_unavailable", "未接入消息总线"),
+    ("web.role_revision_not_found", "Agent {agent_id} 没有角色修订 {revision}"),
+    ("web.session_not_found", "会话 {session_id} 不存在"),
+    ("web.export_format_invalid", "不支持的导出格式：{format}（可选 markdown、html、json）"),
+    ("web.route_not_found", "接口不存在"),
+    ("web.not_ready", "服务未就绪: {checks}"),
+    ("web.invalid_reaction", "无效的回应: {emoji}"),
+    ("web.reaction_failed", "更新回应失败"),
+    ("web.message_not_found", "消息 {message_id} 不存在"),
+    ("web.offset_too_large", "offset 不能超过 {max}，请使用 next_cursor 继续向前翻页"),
+    ("web.pin_not_participant", "你不是 {session_id} 的参与者"),
+    ("web.pin_not_admin", "只有 {pinned_by} 或群管理员可以取消置顶"),
+    ("web.pin_failed", "更新置顶消息失败"),
+    ("web.bookmark_failed", "更新收藏失败"),
+    ("web.scheduler_unavailable", "未启用轮次调度器"),
+    ("web.retries_unavailable", "未提供重试统计"),
+    ("web.breakers_unavailable", "未启用 Agent 熔断"),
+    ("web.prompt_log_disabled", "未启用提示词日志"),
+    ("web.feature_not_found", "未知的功能：{feature}"),
+    ("web.feature_not_toggleable", "功能 {feature} 不能在运行中切换"),
+    ("web.context_load_failed", "重现 Agent 上下文失败"),
+    ("web.login_throttled", "登录失败次数过多，请在 {seconds} 秒后重试"),
+    ("web.current_password_incorrect", "当前密码错误"),
+    ("web.password_change_failed", "修改密码失败"),
+    // 活动摘要
+    ("digest.subject", "公司活动摘要：{start} – {end}"),
+    ("digest.messages", "消息：{total} 条（私聊 {direct} 条，群聊 {group} 条）"),
+    ("digest.top_senders", "最活跃：{senders}"),
+    ("digest.decisions", "决定："),
+    ("digest.tasks", "完成的任务：{count} 个"),
+    ("digest.incidents", "Watchdog 触发：{count} 次"),
+    ("digest.budgets", "今日 LLM 预算用量："),
+    ("digest.group_activity", "群聊发言分布："),
+    ("digest.group_quiet", "未发言：{members}"),
+    ("digest.omitted", "未包含：{section}（{reason}）"),
+    // Agent 熔断
+    ("breaker.tripped", "Agent {agent}（{agent_id}）多次失败，已暂停：{reason}。最近的错误：{error}。收到的消息会排队等待，将于 {retry_at} 重试，管理员也可以通过 POST /api/agents/{agent_id}/reset-breaker 手动复位。"),
+    ("breaker.llm_failures", "LLM 调用连续失败 {count} 次"),
+    ("breaker.llm_auth_failed", "LLM API 拒绝了它的 API key"),
+    ("breaker.tool_errors", "最近 {window} 秒内 {calls} 次工具调用中有 {errors} 次失败"),
+    ("breaker.moderation_blocks", "最近 {window} 秒内有 {blocks} 条输出被内容审核拦截"),
+    // 内容审核
+    ("moderation.blocked_notice", "{agent} 的这条回复未通过内容审核，已暂不显示，管理员可以复核。"),
+    // 密码策略
+    ("password.too_short", "密码长度至少为 {min} 个字符"),
+    ("password.missing_uppercase", "密码必须包含大写字母"),
+    ("password.missing_lowercase", "密码必须包含小写字母"),
+    ("password.missing_digit", "密码必须包含数字"),
+    ("password.missing_symbol", "密码必须包含特殊字符"),
+    ("password.too_common", "密码过于常见"),
+    // 启动提示
+    ("startup.banner", "🚀 正在启动 ImitatorT 多 Agent 公司框架..."),
+    ("startup.console_mode", "ℹ️  以控制台模式运行（无 Web 界面）"),
+    ("startup.shutdown", "🛑 收到关闭信号"),
+    ("startup.missing_api_key", "警告: OPENAI_API_KEY 环境变量未设置，使用测试密钥"),
+    ("startup.admin_credentials", "🔑 已创建管理员 '{username}'，密码: {password}\n   密码只显示这一次，请登录后修改。"),
+    ("startup.secrets_migrated", "🔑 {agent_id}: {name}={value}"),
+    ("startup.secrets_migrate_notice", "   以上 API Key 已不再保存在数据库中，只显示这一次，请在重启前设置这些环境变量。"),
+    ("startup.secrets_migrate_none", "{database} 中没有明文 API Key"),
+    ("startup.demo_welcome", "欢迎使用 ImitatorT！这是首次启动时生成的示例对话。"),
+    ("startup.demo_mentions", "用 @ 提及同事可以引起对方注意，也可以直接发私信。"),
+    ("startup.demo_prompt", "试试在 Web 控制台给团队分配一个任务，看看 Agent 们如何协作。"),
+];
+
+/// 已告警过的缺失键，保证每个键只记录一次日志
+fn reported_missing_keys() -> &'static Mutex<HashSet<String>> {
+    static REPORTED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    REPORTED.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn lookup(catalog: &'static [(&'static str, &'static str)], key: &str) -> Option<&'static str> {
+    catalog.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// 消息目录
+///
+/// 按语言查找模板，模板中的 `{name}` 占位符通过 [`MessageCatalog::format`] 替换
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MessageCatalog {
+    language: Language,
+}
+
+impl MessageCatalog {
+    /// 创建指定语言的目录
+    pub fn new(language: Language) -> Self {
+        Self { language }
+    }
+
+    /// 当前语言
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// 某语言目录中的全部键
+    pub fn keys(language: Language) -> Vec<&'static str> {
+        Self::entries(language).iter().map(|(k, _)| *k).collect()
+    }
+
+    fn entries(language: Language) -> &'static [(&'static str, &'static str)] {
+        match language {
+            Language::En => EN_CATALOG,
+            Language::Zh => ZH_CATALOG,
+        }
+    }
+
+    /// 获取原始模板，缺失时回退到英文，英文也缺失则返回键本身
+    pub fn get(&self, key: &str) -> String {
+        if let Some(text) = lookup(Self::entries(self.language), key) {
+            return text.to_string();
+        }
+
+        if let Ok(mut reported) = reported_missing_keys().lock() {
+            if reported.insert(format!("{}:{}", self.language, key)) {
+                warn!("Message catalog '{}' has no entry for key '{}', falling back to English", self.language, key);
+            }
+        }
+
+        lookup(EN_CATALOG, key)
+            .map(|text| text.to_string())
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// 获取模板并替换占位符
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let mut text = self.get(key);
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
@@ -0,0 +1,433 @@
+//! 持久化数据完整性检查
+//!
+//! 手工改库或版本不一致后，存储里可能留下悬空引用（Agent 指向已删除的部门）、
+//! 群聊中已不存在的成员、重复的工号或无法解析的行，这些问题通常要到运行很久后
+//! 才以难以理解的错误出现。[`StoreIntegrityChecker`] 在公司启动时（或由管理接口按需）
+//! 扫描这些问题并按严重程度报告；修复模式只做安全的修复：
+//! - 清空悬空引用（部门负责人、上级部门、Agent 所属部门）
+//! - 从群聊中移除不存在的成员
+//! - 将无法解析的行移入隔离表
+//!
+//! 每次修复都记入审计日志。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::core::org_changes::save_organization_tracked;
+use crate::core::store::Store;
+use crate::domain::user::{is_user_principal, USER_PRINCIPAL_PREFIX};
+
+/// 问题的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Warning,
+    /// 严格模式下拒绝启动
+    Critical,
+}
+
+/// 问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// 引用的部门或 Agent 不存在
+    DanglingReference,
+    /// 群聊成员已不存在
+    OrphanedGroupMember,
+    /// 多个用户使用同一工号
+    DuplicateEmployeeId,
+    /// 列中的枚举值无法识别
+    InvalidEnumValue,
+    /// 行无法解析
+    UnparseableRow,
+}
+
+/// 修复模式下对该问题执行的安全修复
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Repair {
+    /// 将引用列置空
+    ClearReference,
+    /// 从群聊中移除该成员
+    DropMember,
+    /// 将整行移入隔离表
+    Quarantine,
+}
+
+/// 一条检查结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityFinding {
+    pub severity: Severity,
+    pub kind: IssueKind,
+    pub table: String,
+    pub row_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    /// 有问题的值（悬空的ID、不存在的成员、非法的枚举值等）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    pub detail: String,
+    /// 可执行的安全修复，None 表示需要人工处理
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repair: Option<Repair>,
+    #[serde(default)]
+    pub repaired: bool,
+}
+
+impl IntegrityFinding {
+    pub fn new(severity: Severity, kind: IssueKind, table: impl Into<String>, row_id: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            severity,
+            kind,
+            table: table.into(),
+            row_id: row_id.into(),
+            column: None,
+            value: None,
+            detail: detail.into(),
+            repair: None,
+            repaired: false,
+        }
+    }
+
+    /// 设置有问题的列和值
+    pub fn with_value(mut self, column: impl Into<String>, value: impl Into<String>) -> Self {
+        self.column = Some(column.into());
+        self.value = Some(value.into());
+        self
+    }
+
+    /// 设置安全修复
+    pub fn with_repair(mut self, repair: Repair) -> Self {
+        self.repair = Some(repair);
+        self
+    }
+}
+
+/// 检查报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub findings: Vec<IntegrityFinding>,
+    /// 是否以修复模式运行
+    pub repair_mode: bool,
+}
+
+impl IntegrityReport {
+    /// 是否有未修复的严重问题
+    pub fn has_critical(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|f| f.severity == Severity::Critical && !f.repaired)
+    }
+
+    /// 指定严重程度的问题数
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings.iter().filter(|f| f.severity == severity).count()
+    }
+
+    /// 已修复的问题数
+    pub fn repaired(&self) -> usize {
+        self.findings.iter().filter(|f| f.repaired).count()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+/// 隔离表中的一行
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantinedRow {
+    pub table: String,
+    pub row_id: String,
+    /// 原始行（列名 -> 值）
+    pub data: serde_json::Value,
+    pub reason: String,
+    pub quarantined_at: i64,
+}
+
+/// 启动时的检查方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityMode {
+    /// 不检查
+    Off,
+    /// 记录警告后继续启动
+    #[default]
+    Warn,
+    /// 有未修复的严重问题时拒绝启动
+    Strict,
+}
+
+/// 启动检查配置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntegrityConfig {
+    #[serde(default)]
+    pub mode: IntegrityMode,
+    /// 启动时执行安全修复（默认只报告）
+    #[serde(default)]
+    pub repair: bool,
+}
+
+/// 存储完整性检查器
+pub struct StoreIntegrityChecker {
+    store: Arc<dyn Store>,
+}
+
+impl StoreIntegrityChecker {
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self { store }
+    }
+
+    /// 只检查，不修改数据
+    pub async fn check(&self) -> Result<IntegrityReport> {
+        let mut findings = self.check_organization().await?;
+        findings.extend(self.check_groups().await?);
+        findings.extend(self.check_users().await?);
+        findings.extend(self.store.scan_integrity().await?);
+        Ok(IntegrityReport { findings, repair_mode: false })
+    }
+
+    /// 检查并执行安全修复，每次修复以 `actor` 的名义记入审计日志
+    pub async fn repair(&self, actor: &str) -> Result<IntegrityReport> {
+        let mut report = self.check().await?;
+        report.repair_mode = true;
+        self.repair_organization(&mut report.findings, actor).await?;
+        self.repair_groups(&mut report.findings, actor).await?;
+
+        for finding in report.findings.iter_mut().filter(|f| f.repair == Some(Repair::Quarantine)) {
+            finding.repaired = self
+                .store
+                .quarantine_row(&finding.table, &finding.row_id, &finding.detail)
+                .await?;
+            if finding.repaired {
+                info!(target: "audit", "User {} quarantined {} row {}: {}", actor, finding.table, finding.row_id, finding.detail);
+            }
+        }
+        Ok(report)
+    }
+
+    /// 按配置执行启动检查：记录问题，严格模式下有未修复的严重问题时返回错误
+    pub async fn run_startup(&self, config: IntegrityConfig, actor: &str) -> Result<Option<IntegrityReport>> {
+        if config.mode == IntegrityMode::Off {
+            return Ok(None);
+        }
+        let report = if config.repair {
+            self.repair(actor).await?
+        } else {
+            self.check().await?
+        };
+
+        for finding in &report.findings {
+            warn!(
+                "Integrity {:?}: {} {} {}{}",
+                finding.severity,
+                finding.table,
+                finding.row_id,
+                finding.detail,
+                if finding.repaired { " (repaired)" } else { "" }
+            );
+        }
+        if !report.is_clean() {
+            warn!(
+                "Store integrity check found {} critical, {} warning, {} info issue(s); {} repaired",
+                report.count(Severity::Critical),
+                report.count(Severity::Warning),
+                report.count(Severity::Info),
+                report.repaired()
+            );
+        }
+        if config.mode == IntegrityMode::Strict && report.has_critical() {
+            return Err(anyhow::anyhow!(
+                "Store integrity check found critical issues; repair the data or start with integrity mode \"warn\""
+            ));
+        }
+        Ok(Some(report))
+    }
+
+    async fn check_organization(&self) -> Result<Vec<IntegrityFinding>> {
+        let org = self.store.load_organization().await?;
+        let departments: HashSet<&str> = org.departments.iter().map(|d| d.id.as_str()).collect();
+        let agents: HashSet<&str> = org.agents.iter().map(|a| a.id.as_str()).collect();
+        let mut findings = Vec::new();
+
+        for dept in &org.departments {
+            if let Some(parent) = dept.parent_id.as_deref().filter(|p| !departments.contains(p)) {
+                findings.push(
+                    IntegrityFinding::new(
+                        Severity::Warning,
+                        IssueKind::DanglingReference,
+                        "departments",
+                        &dept.id,
+                        format!("parent department {} does not exist", parent),
+                    )
+                    .with_value("parent_id", parent)
+                    .with_repair(Repair::ClearReference),
+                );
+            }
+            if let Some(leader) = dept.leader_id.as_deref().filter(|l| !agents.contains(l)) {
+                findings.push(
+                    IntegrityFinding::new(
+                        Severity::Warning,
+                        IssueKind::DanglingReference,
+                        "departments",
+                        &dept.id,
+                        format!("leader {} does not exist", leader),
+                    )
+                    .with_value("leader_id", leader)
+                    .with_repair(Repair::ClearReference),
+                );
+            }
+        }
+        for agent in &org.agents {
+            if let Some(dept) = agent.department_id.as_deref().filter(|d| !departments.contains(d)) {
+                findings.push(
+                    IntegrityFinding::new(
+                        Severity::Critical,
+                        IssueKind::DanglingReference,
+                        "agents",
+                        &agent.id,
+                        format!("department {} does not exist", dept),
+                    )
+                    .with_value("department_id", dept)
+                    .with_repair(Repair::ClearReference),
+                );
+            }
+        }
+        Ok(findings)
+    }
+
+    /// 成员既不是 Agent 也不是已知用户时视为不存在；存储中没有用户数据时不检查用户成员
+    async fn check_groups(&self) -> Result<Vec<IntegrityFinding>> {
+        let org = self.store.load_organization().await?;
+        let users = self.store.load_users().await?;
+        let agents: HashSet<&str> = org.agents.iter().map(|a| a.id.as_str()).collect();
+        let user_ids: HashSet<&str> = users.iter().map(|u| u.id.as_str()).collect();
+        let mut findings = Vec::new();
+
+        for group in self.store.load_groups().await? {
+            for member in &group.members {
+                let exists = if is_user_principal(member) {
+                    users.is_empty() || user_ids.contains(&member[USER_PRINCIPAL_PREFIX.len()..])
+                } else {
+                    agents.contains(member.as_str())
+                };
+                if !exists {
+                    findings.push(
+                        IntegrityFinding::new(
+                            Severity::Warning,
+                            IssueKind::OrphanedGroupMember,
+                            "groups",
+                            &group.id,
+                            format!("member {} does not exist", member),
+                        )
+                        .with_value("members", member)
+                        .with_repair(Repair::DropMember),
+                    );
+                }
+            }
+        }
+        Ok(findings)
+    }
+
+    async fn check_users(&self) -> Result<Vec<IntegrityFinding>> {
+        let mut by_employee_id: HashMap<&str, Vec<&str>> = HashMap::new();
+        let users = self.store.load_users().await?;
+        for user in &users {
+            by_employee_id.entry(&user.employee_id).or_default().push(&user.username);
+        }
+        let mut duplicates: Vec<_> = by_employee_id.into_iter().filter(|(_, names)| names.len() > 1).collect();
+        duplicates.sort();
+        Ok(duplicates
+            .into_iter()
+            .flat_map(|(employee_id, names)| {
+                let detail = format!("employee_id {} is shared by {}", employee_id, names.join(", "));
+                names.into_iter().map(move |name| {
+                    IntegrityFinding::new(Severity::Critical, IssueKind::DuplicateEmployeeId, "users", name, detail.clone())
+                        .with_value("employee_id", employee_id)
+                })
+            })
+            .collect())
+    }
+
+    async fn repair_organization(&self, findings: &mut [IntegrityFinding], actor: &str) -> Result<()> {
+        let pending: Vec<&mut IntegrityFinding> = findings
+            .iter_mut()
+            .filter(|f| f.repair == Some(Repair::ClearReference))
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let mut org = self.store.load_organization().await?;
+        for finding in &pending {
+            match (finding.table.as_str(), finding.column.as_deref()) {
+                ("departments", Some(column)) => {
+                    if let Some(dept) = org.departments.iter_mut().find(|d| d.id == finding.row_id) {
+                        match column {
+                            "parent_id" => dept.parent_id = None,
+                            _ => dept.leader_id = None,
+                        }
+                    }
+                }
+                ("agents", _) => {
+                    if let Some(agent) = org.agents.iter_mut().find(|a| a.id == finding.row_id) {
+                        agent.department_id = None;
+                    }
+                }
+                _ => {}
+            }
+        }
+        save_organization_tracked(self.store.as_ref(), &org, actor).await?;
+
+        for finding in pending {
+            finding.repaired = true;
+            info!(
+                target: "audit",
+                "User {} cleared dangling {}.{} of {} ({})",
+                actor,
+                finding.table,
+                finding.column.as_deref().unwrap_or_default(),
+                finding.row_id,
+                finding.value.as_deref().unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+
+    async fn repair_groups(&self, findings: &mut [IntegrityFinding], actor: &str) -> Result<()> {
+        let mut orphans: HashMap<String, HashSet<String>> = HashMap::new();
+        for finding in findings.iter().filter(|f| f.repair == Some(Repair::DropMember)) {
+            if let Some(member) = &finding.value {
+                orphans.entry(finding.row_id.clone()).or_default().insert(member.clone());
+            }
+        }
+        if orphans.is_empty() {
+            return Ok(());
+        }
+
+        for mut group in self.store.load_groups().await? {
+            let Some(members) = orphans.get(&group.id) else {
+                continue;
+            };
+            group.members.retain(|m| !members.contains(m));
+            self.store.save_group(&group).await?;
+        }
+
+        for finding in findings.iter_mut().filter(|f| f.repair == Some(Repair::DropMember)) {
+            finding.repaired = true;
+            info!(
+                target: "audit",
+                "User {} removed missing member {} from group {}",
+                actor,
+                finding.value.as_deref().unwrap_or_default(),
+                finding.row_id
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+//! 加入群聊时的前情提要
+//!
+//! 被邀请进一个讨论已久的群聊时，新成员对之前的内容一无所知，常会问早已回答过的问题。
+//! 成员被邀请加入后（[`crate::core::messaging::MessageBus::invite_member`]），[`JoinContext`]
+//! 为新成员生成一份有上限的前情提要：群里的置顶消息、较早讨论的总结（优先使用缓存，
+//! 没有时在 token 上限内现场总结）和最近的若干条原始消息，以 `system` 私聊只发给新成员。
+//! 提要带 [`JOIN_CONTEXT_KEY`] 标记，作为未读消息进入新成员下一轮的上下文，但不会被转发到群聊。
+//!
+//! 提要只取自该群聊本身：置顶和消息都按群 ID 过滤，不会带出新成员看不到的其他会话的内容。
+//! 邀请时设置 [`InviteOptions::cold_start`] 可以跳过提要。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::core::i18n::MessageCatalog;
+use crate::core::messaging::order_by_group_seq;
+use crate::core::store::{MessageFilter, Store};
+use crate::core::tokenizer::{HeuristicTokenizer, Tokenizer};
+use crate::domain::{Group, Message, MessageId, MessageTarget};
+
+/// 前情提要消息的元数据键，值为群 ID
+pub const JOIN_CONTEXT_KEY: &str = "join_context";
+
+/// 前情提要的发送者
+pub const JOIN_CONTEXT_SENDER: &str = "system";
+
+/// 生成提要时读取的群聊消息上限
+const MAX_GROUP_MESSAGES: usize = 10_000;
+
+/// 前情提要配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct JoinContextConfig {
+    /// 成员被邀请加入时是否发送前情提要
+    pub enabled: bool,
+    /// 原样附上的最近消息数，更早的消息以总结代替
+    pub recent_messages: usize,
+    /// 总结时提供给 LLM 的消息 token 上限，超出时保留最新的消息
+    pub token_budget: usize,
+}
+
+impl Default for JoinContextConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            recent_messages: 20,
+            token_budget: 2000,
+        }
+    }
+}
+
+/// 邀请选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InviteOptions {
+    /// 不发送前情提要，新成员从零开始
+    pub cold_start: bool,
+}
+
+impl InviteOptions {
+    /// 不发送前情提要
+    pub fn cold_start() -> Self {
+        Self { cold_start: true }
+    }
+}
+
+/// 总结一段群聊记录，供新成员了解前情
+#[async_trait]
+pub trait GroupSummarizer: Send + Sync {
+    /// 总结 `group_name` 中的讨论（每行 `发送者: 内容`，按时间先后）
+    async fn summarize_group(&self, group_name: &str, transcript: &str) -> Result<String>;
+}
+
+/// 发给新成员的前情提要
+#[derive(Debug, Clone, Serialize)]
+pub struct JoinContextPackage {
+    pub group_id: String,
+    pub group_name: String,
+    /// 群里的置顶消息，按置顶时间升序
+    pub pinned: Vec<Message>,
+    /// 最近消息之前的讨论的总结；没有更早的消息、未设置总结器或总结失败时为空
+    pub summary: Option<String>,
+    /// 最近消息之前的消息数
+    pub earlier: usize,
+    /// 最近的消息，按群内序号升序
+    pub recent: Vec<Message>,
+}
+
+impl JoinContextPackage {
+    /// 群里还没有任何内容
+    pub fn is_empty(&self) -> bool {
+        self.pinned.is_empty() && self.summary.is_none() && self.recent.is_empty()
+    }
+
+    /// 渲染为发给新成员的文本
+    pub fn render(&self, catalog: &MessageCatalog) -> String {
+        let mut text = catalog.format("join_context.header", &[("name", &self.group_name)]);
+        if self.is_empty() {
+            text.push('\n');
+            text.push_str(&catalog.get("join_context.empty"));
+            return text;
+        }
+        if !self.pinned.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&catalog.get("join_context.pinned"));
+            push_lines(&mut text, &self.pinned);
+        }
+        if let Some(summary) = &self.summary {
+            text.push_str("\n\n");
+            text.push_str(&catalog.format("join_context.summary", &[("count", &self.earlier.to_string())]));
+            text.push('\n');
+            text.push_str(summary);
+        }
+        if !self.recent.is_empty() {
+            text.push_str("\n\n");
+            text.push_str(&catalog.format("join_context.recent", &[("count", &self.recent.len().to_string())]));
+            push_lines(&mut text, &self.recent);
+        }
+        text
+    }
+
+    /// 发给 `member` 的私聊，带 [`JOIN_CONTEXT_KEY`] 标记
+    pub fn to_message(&self, member: &str, catalog: &MessageCatalog) -> Message {
+        Message::private(JOIN_CONTEXT_SENDER, member, self.render(catalog))
+            .with_metadata(JOIN_CONTEXT_KEY, self.group_id.clone())
+    }
+}
+
+fn push_lines(text: &mut String, messages: &[Message]) {
+    for message in messages {
+        text.push_str(&format!("\n- {}: {}", message.from, message.content));
+    }
+}
+
+/// 前情提要生成器
+pub struct JoinContext {
+    config: JoinContextConfig,
+    store: Arc<dyn Store>,
+    summarizer: Option<Arc<dyn GroupSummarizer>>,
+    tokenizer: Arc<dyn Tokenizer>,
+    /// 群ID -> (总结涵盖的最后一条消息ID, 总结)
+    summaries: Mutex<HashMap<String, (MessageId, String)>>,
+}
+
+impl JoinContext {
+    pub fn new(config: JoinContextConfig, store: Arc<dyn Store>) -> Self {
+        Self {
+            config,
+            store,
+            summarizer: None,
+            tokenizer: Arc::new(HeuristicTokenizer),
+            summaries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 设置总结器，未设置时提要只有置顶和最近消息
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn GroupSummarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// 设置计算 token 上限使用的分词器
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    pub fn config(&self) -> &JoinContextConfig {
+        &self.config
+    }
+
+    /// 为群聊生成前情提要
+    pub async fn build(&self, group: &Group) -> Result<JoinContextPackage> {
+        let in_group = |message: &Message| matches!(&message.to, MessageTarget::Group(id) if *id == group.id);
+
+        let mut filter = MessageFilter::new().to(group.id.clone()).target_type("group").oldest_first();
+        filter.limit = MAX_GROUP_MESSAGES;
+        let mut messages: Vec<Message> = self.store.load_messages(filter).await?.into_iter().filter(in_group).collect();
+        order_by_group_seq(&mut messages);
+        let split = messages.len().saturating_sub(self.config.recent_messages);
+        let recent = messages.split_off(split);
+        let earlier = messages;
+
+        let mut pinned = Vec::new();
+        for pin in self.store.load_pins(&group.id).await? {
+            if let Some(message) = self.store.load_message(&pin.message_id).await?.filter(in_group) {
+                pinned.push(message);
+            }
+        }
+
+        let summary = self.summarize(group, &earlier).await;
+        Ok(JoinContextPackage {
+            group_id: group.id.clone(),
+            group_name: group.name.clone(),
+            pinned,
+            summary,
+            earlier: earlier.len(),
+            recent,
+        })
+    }
+
+    /// 群聊已缓存的总结
+    pub fn cached_summary(&self, group_id: &str) -> Option<String> {
+        self.summaries.lock().unwrap().get(group_id).map(|(_, summary)| summary.clone())
+    }
+
+    /// 总结较早的消息；涵盖的最后一条消息没变时使用缓存
+    async fn summarize(&self, group: &Group, earlier: &[Message]) -> Option<String> {
+        let last = earlier.last()?;
+        if let Some((covered, summary)) = self.summaries.lock().unwrap().get(&group.id) {
+            if *covered == last.id {
+                return Some(summary.clone());
+            }
+        }
+        let summarizer = self.summarizer.as_ref()?;
+
+        let mut lines = Vec::new();
+        let mut remaining = self.config.token_budget;
+        for message in earlier.iter().rev() {
+            let line = format!("{}: {}", message.from, message.content);
+            let tokens = self.tokenizer.count(&line) + 1;
+            if tokens > remaining {
+                break;
+            }
+            remaining -= tokens;
+            lines.push(line);
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+
+        match summarizer.summarize_group(&group.name, &lines.join("\n")).await {
+            Ok(summary) => {
+                let summary = summary.trim().to_string();
+                self.summaries
+                    .lock()
+                    .unwrap()
+                    .insert(group.id.clone(), (last.id.clone(), summary.clone()));
+                Some(summary)
+            }
+            Err(e) => {
+                warn!("Failed to summarize group {} for a new member: {}", group.id, e);
+                None
+            }
+        }
+    }
+}
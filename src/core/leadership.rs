@@ -0,0 +1,286 @@
+//! 主备部署的领导权
+//!
+//! 两个节点共享同一存储运行同一家公司时，只有持有租约的节点（领导者）驱动 Agent 循环、调度和各类
+//! 轮询；另一个节点（备用）只提供只读 API，写请求返回 409 并指明领导者，领导者失效后接管。
+//!
+//! - 租约保存在存储中（[`Store::acquire_lease`]），领导者每个续期间隔续期一次
+//! - 节点只在租约到期前认为自己是领导者，租约到期后其他节点才能取得租约，两个节点不会同时领导
+//! - 租约每次易主，令牌（fencing token）加一；重要的写入（组织架构保存、Web 写请求）前用
+//!   [`LeaderElection::check_fence`] 确认令牌仍是当前的，已经失去租约的旧领导者写入失败
+//!
+//! 判断租约是否到期使用各节点自己的时钟，部署时节点间的时钟偏差应远小于租约有效期。
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::store::Store;
+use crate::domain::lease::Lease;
+
+/// 公司运行权的租约名称
+pub const COMPANY_LEASE: &str = "company";
+
+/// 主备配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LeadershipConfig {
+    /// 是否启用主备（默认关闭，单节点直接运行）
+    pub enabled: bool,
+    /// 节点 ID，为空时使用主机名和进程号
+    pub node_id: Option<String>,
+    /// 租约有效期（秒）
+    pub lease_ttl_secs: u64,
+    /// 续期间隔（秒），应明显小于有效期
+    pub renew_interval_secs: u64,
+}
+
+impl Default for LeadershipConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            node_id: None,
+            lease_ttl_secs: 15,
+            renew_interval_secs: 5,
+        }
+    }
+}
+
+impl LeadershipConfig {
+    /// 配置的节点 ID，未配置时为主机名和进程号
+    pub fn resolved_node_id(&self) -> String {
+        self.node_id.clone().unwrap_or_else(|| {
+            let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "node".to_string());
+            format!("{}-{}", host, std::process::id())
+        })
+    }
+}
+
+/// 领导权错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LeadershipError {
+    #[error("Node {node_id} is a standby; the leader is {}", leader.as_deref().unwrap_or("unknown"))]
+    NotLeader { node_id: String, leader: Option<String> },
+    #[error("Fencing token {token} of node {node_id} is no longer current")]
+    Fenced { node_id: String, token: u64 },
+    #[error("Node {node_id} lost leadership")]
+    Lost { node_id: String },
+}
+
+/// 节点的领导权状态
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct LeadershipStatus {
+    pub node_id: String,
+    pub is_leader: bool,
+    /// 当前的领导者，租约已过期时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leader: Option<String>,
+    /// 本节点领导期间的令牌
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<u64>,
+    /// 租约到期时间（秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+/// 租约的获取、续期和令牌检查
+pub struct LeaderElection {
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    node_id: String,
+    lease_ttl_secs: i64,
+    renew_interval: Duration,
+    /// 最近一次从存储看到的租约
+    lease: RwLock<Option<Lease>>,
+    leading: watch::Sender<bool>,
+}
+
+impl LeaderElection {
+    pub fn new(store: Arc<dyn Store>, config: &LeadershipConfig) -> Self {
+        Self {
+            store,
+            clock: Arc::new(SystemClock),
+            node_id: config.resolved_node_id(),
+            lease_ttl_secs: config.lease_ttl_secs.max(1) as i64,
+            renew_interval: Duration::from_secs(config.renew_interval_secs.max(1)),
+            lease: RwLock::new(None),
+            leading: watch::channel(false).0,
+        }
+    }
+
+    /// 使用指定时钟（测试中使用 ManualClock）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    pub fn renew_interval(&self) -> Duration {
+        self.renew_interval
+    }
+
+    /// 获取或续期租约；存储不可用时保留上次的租约，到期后自然失去领导权
+    pub async fn tick(&self) -> Result<LeadershipStatus> {
+        let now = self.clock.now();
+        let result = self.store.acquire_lease(COMPANY_LEASE, &self.node_id, now, self.lease_ttl_secs).await;
+        if let Ok(lease) = &result {
+            let mut current = self.lease.write().unwrap_or_else(|e| e.into_inner());
+            let was_leader = current.as_ref().is_some_and(|l| l.is_held_by(&self.node_id, now));
+            let is_leader = lease.is_held_by(&self.node_id, now);
+            if is_leader && !was_leader {
+                info!("Node {} acquired leadership (token {})", self.node_id, lease.token);
+            } else if was_leader && !is_leader {
+                warn!("Node {} lost leadership to {}", self.node_id, lease.holder);
+            }
+            *current = Some(lease.clone());
+        }
+        self.publish();
+        result.map(|_| self.status())
+    }
+
+    /// 本节点当前是否是领导者（租约由本节点持有且未到期）
+    pub fn is_leader(&self) -> bool {
+        self.token().is_some()
+    }
+
+    /// 本节点领导期间的令牌
+    pub fn token(&self) -> Option<u64> {
+        let now = self.clock.now();
+        self.lease
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|lease| lease.is_held_by(&self.node_id, now))
+            .map(|lease| lease.token)
+    }
+
+    /// 当前的领导者（按最近一次看到的租约）
+    pub fn leader(&self) -> Option<String> {
+        let now = self.clock.now();
+        self.lease
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|lease| !lease.is_expired(now))
+            .map(|lease| lease.holder.clone())
+    }
+
+    pub fn status(&self) -> LeadershipStatus {
+        let now = self.clock.now();
+        let lease = self.lease.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let live = lease.filter(|lease| !lease.is_expired(now));
+        let is_leader = live.as_ref().is_some_and(|lease| lease.holder == self.node_id);
+        LeadershipStatus {
+            node_id: self.node_id.clone(),
+            is_leader,
+            leader: live.as_ref().map(|lease| lease.holder.clone()),
+            token: live.as_ref().filter(|_| is_leader).map(|lease| lease.token),
+            expires_at: live.map(|lease| lease.expires_at),
+        }
+    }
+
+    /// 领导权变化的订阅（true 表示本节点是领导者）
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.leading.subscribe()
+    }
+
+    /// 确认本节点是领导者且令牌仍是存储中的当前令牌，失败时返回 [`LeadershipError`]
+    pub async fn check_fence(&self) -> Result<()> {
+        let Some(token) = self.token() else {
+            return Err(LeadershipError::NotLeader {
+                node_id: self.node_id.clone(),
+                leader: self.leader(),
+            }
+            .into());
+        };
+        let current = self.store.load_lease(COMPANY_LEASE).await?;
+        let now = self.clock.now();
+        if current.is_some_and(|lease| lease.token == token && lease.is_held_by(&self.node_id, now)) {
+            return Ok(());
+        }
+        Err(LeadershipError::Fenced {
+            node_id: self.node_id.clone(),
+            token,
+        }
+        .into())
+    }
+
+    /// 每个续期间隔尝试一次，直到成为领导者
+    pub async fn wait_for_leadership(&self) {
+        loop {
+            if let Err(e) = self.tick().await {
+                warn!("Failed to acquire leadership lease: {}", e);
+            }
+            if self.is_leader() {
+                return;
+            }
+            tokio::time::sleep(self.renew_interval).await;
+        }
+    }
+
+    /// 主动释放租约（正常退出时），备用节点无需等待到期即可接管
+    pub async fn release(&self) -> Result<bool> {
+        let Some(token) = self.token() else {
+            return Ok(false);
+        };
+        let released = self.store.release_lease(COMPANY_LEASE, &self.node_id, token).await?;
+        if released {
+            *self.lease.write().unwrap_or_else(|e| e.into_inner()) = None;
+            self.publish();
+        }
+        Ok(released)
+    }
+
+    /// 后台定期续期（需在 Tokio 运行时中调用）；续期失败时最迟在租约到期时再检查一次，
+    /// 保证失去领导权在一个续期间隔内被发现
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.tick().await {
+                    warn!("Failed to renew leadership lease: {}", e);
+                }
+                tokio::time::sleep(self.next_check()).await;
+            }
+        })
+    }
+
+    /// 下次续期前的等待时间：续期间隔，但不晚于本节点租约的到期时间
+    fn next_check(&self) -> Duration {
+        let until_expiry = self
+            .lease
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|lease| lease.holder == self.node_id)
+            .map(|lease| lease.expires_at * 1000 - self.clock.now_millis())
+            .filter(|millis| *millis > 0)
+            .map(|millis| Duration::from_millis(millis as u64));
+        until_expiry.map_or(self.renew_interval, |until| until.min(self.renew_interval))
+    }
+
+    fn publish(&self) {
+        let leading = self.is_leader();
+        self.leading.send_if_modified(|current| {
+            let changed = *current != leading;
+            *current = leading;
+            changed
+        });
+    }
+}
+
+impl std::fmt::Debug for LeaderElection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LeaderElection")
+            .field("node_id", &self.node_id)
+            .field("is_leader", &self.is_leader())
+            .finish()
+    }
+}
@@ -0,0 +1,344 @@
+//! Agent 之间的回声循环抑制
+//!
+//! Agent 在群聊或私聊中互相回复时可能无限往复。每条 Agent 发出的消息在发送前检查：
+//!
+//! - 回复链深度：Agent 回复另一个 Agent 的消息时深度加一（存于消息元数据
+//!   [`CHAIN_DEPTH_KEY`]），人类消息重置为 0，超过 `max_chain_depth` 时拦截
+//! - 同一对 Agent 在窗口期内没有人类介入的往来次数上限
+//! - 同一 Agent 连续输出近似重复内容（归一化后的词集合相似度）
+//!
+//! 任一项触发后 Agent 进入冷却期，期间发出的消息都被拦截；只在进入冷却时向 Agent
+//! 发送一条系统提示，避免提示本身再引发循环。人类消息会清除冷却和往来计数。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::i18n::{Language, MessageCatalog};
+use crate::domain::Message;
+
+pub use crate::domain::message::CHAIN_DEPTH_KEY;
+
+/// 循环提示的发送者，不计为人类输入
+pub const LOOP_NOTICE_SENDER: &str = "system";
+
+/// 循环抑制配置，各项为 0 时关闭对应检查
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LoopGuardConfig {
+    /// Agent 回复 Agent 的最大链深度
+    pub max_chain_depth: u32,
+    /// 窗口期内同一对 Agent 的最大往来消息数
+    pub max_pair_exchanges: usize,
+    /// 往来计数窗口（秒）
+    pub pair_window_secs: u64,
+    /// 与最近几条输出比较相似度
+    pub duplicate_window: usize,
+    /// 视为重复的相似度阈值（0-1）
+    pub duplicate_similarity: f64,
+    /// 触发后的冷却时长（秒）
+    pub cooldown_secs: u64,
+}
+
+impl Default for LoopGuardConfig {
+    fn default() -> Self {
+        Self {
+            max_chain_depth: 10,
+            max_pair_exchanges: 20,
+            pair_window_secs: 600,
+            duplicate_window: 3,
+            duplicate_similarity: 0.9,
+            cooldown_secs: 300,
+        }
+    }
+}
+
+/// 拦截原因
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LoopTrip {
+    /// 回复链过深
+    ChainDepth { depth: u32, max: u32 },
+    /// 与同一 Agent 往来过多
+    PairRate { peer: String, exchanges: usize, window_secs: u64 },
+    /// 与最近的输出近似重复
+    Duplicate { similarity: f64 },
+    /// 冷却中
+    Cooldown { remaining_secs: i64 },
+}
+
+impl LoopTrip {
+    /// 名称（用于日志和指标）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LoopTrip::ChainDepth { .. } => "chain_depth",
+            LoopTrip::PairRate { .. } => "pair_rate",
+            LoopTrip::Duplicate { .. } => "duplicate",
+            LoopTrip::Cooldown { .. } => "cooldown",
+        }
+    }
+}
+
+/// 检查结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoopVerdict {
+    Allow,
+    /// 拦截；`notify` 为真时应向 Agent 发送系统提示（刚进入冷却）
+    Block { trip: LoopTrip, notify: bool },
+}
+
+/// 拦截计数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LoopGuardMetrics {
+    pub chain_depth: u64,
+    pub pair_rate: u64,
+    pub duplicate: u64,
+    pub cooldown: u64,
+}
+
+#[derive(Default)]
+struct GuardState {
+    /// 排序后的 Agent 对 -> 往来时间（毫秒）
+    exchanges: HashMap<(String, String), VecDeque<i64>>,
+    /// Agent -> 最近输出的归一化词集合
+    outputs: HashMap<String, VecDeque<HashSet<String>>>,
+    /// Agent -> 冷却结束时间（毫秒）
+    cooldowns: HashMap<String, i64>,
+}
+
+/// 公司级循环抑制器，所有 Agent 共享
+pub struct LoopGuard {
+    config: LoopGuardConfig,
+    clock: Arc<dyn Clock>,
+    language: Language,
+    agent_languages: HashMap<String, Language>,
+    state: Mutex<GuardState>,
+    trips: [AtomicU64; 4],
+}
+
+impl LoopGuard {
+    pub fn new(config: LoopGuardConfig) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            language: Language::default(),
+            agent_languages: HashMap::new(),
+            state: Mutex::new(GuardState::default()),
+            trips: Default::default(),
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 系统提示使用的语言
+    pub fn with_languages(mut self, language: Language, agent_languages: HashMap<String, Language>) -> Self {
+        self.language = language;
+        self.agent_languages = agent_languages;
+        self
+    }
+
+    pub fn config(&self) -> &LoopGuardConfig {
+        &self.config
+    }
+
+    fn lock(&self) -> MutexGuard<'_, GuardState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 人类向 Agent 发了消息：清除其冷却和相关的往来计数
+    pub fn note_human_input(&self, agent_id: &str) {
+        let mut state = self.lock();
+        state.cooldowns.remove(agent_id);
+        state.exchanges.retain(|(a, b), _| a != agent_id && b != agent_id);
+    }
+
+    /// 检查一条即将发出的消息，通过时记录往来与输出
+    ///
+    /// `peers` 是这条消息回应的其他 Agent（私聊对象或群聊中触发本轮的 Agent）。
+    pub fn check(&self, message: &Message, peers: &[String]) -> LoopVerdict {
+        let agent_id = message.from.as_str();
+        let now = self.clock.now_millis();
+        let mut state = self.lock();
+
+        if let Some(&until) = state.cooldowns.get(agent_id) {
+            if until > now {
+                let trip = LoopTrip::Cooldown {
+                    remaining_secs: (until - now + 999) / 1000,
+                };
+                return self.block(&mut state, agent_id, now, trip);
+            }
+            state.cooldowns.remove(agent_id);
+        }
+
+        let depth = message.chain_depth();
+        if self.config.max_chain_depth > 0 && depth > self.config.max_chain_depth {
+            let trip = LoopTrip::ChainDepth {
+                depth,
+                max: self.config.max_chain_depth,
+            };
+            return self.block(&mut state, agent_id, now, trip);
+        }
+
+        let window = self.config.pair_window_secs as i64 * 1000;
+        if self.config.max_pair_exchanges > 0 {
+            for peer in peers.iter().filter(|p| p.as_str() != agent_id) {
+                let times = state.exchanges.entry(pair(agent_id, peer)).or_default();
+                while times.front().is_some_and(|&t| now - t >= window) {
+                    times.pop_front();
+                }
+                if times.len() >= self.config.max_pair_exchanges {
+                    let trip = LoopTrip::PairRate {
+                        peer: peer.clone(),
+                        exchanges: times.len(),
+                        window_secs: self.config.pair_window_secs,
+                    };
+                    return self.block(&mut state, agent_id, now, trip);
+                }
+            }
+        }
+
+        let words = normalize(&message.content);
+        if self.config.duplicate_window > 0 {
+            let recent = state.outputs.get(agent_id);
+            let full = recent.is_some_and(|r| r.len() >= self.config.duplicate_window);
+            // 与最近 K 条输出都近似重复才视为循环
+            let similarity = recent
+                .filter(|_| full)
+                .and_then(|r| r.iter().map(|prev| similarity(prev, &words)).reduce(f64::min));
+            if let Some(similarity) = similarity.filter(|s| *s >= self.config.duplicate_similarity) {
+                return self.block(&mut state, agent_id, now, LoopTrip::Duplicate { similarity });
+            }
+        }
+
+        for peer in peers.iter().filter(|p| p.as_str() != agent_id) {
+            state.exchanges.entry(pair(agent_id, peer)).or_default().push_back(now);
+        }
+        if self.config.duplicate_window > 0 {
+            let recent = state.outputs.entry(agent_id.to_string()).or_default();
+            recent.push_back(words);
+            while recent.len() > self.config.duplicate_window {
+                recent.pop_front();
+            }
+        }
+        LoopVerdict::Allow
+    }
+
+    fn block(&self, state: &mut GuardState, agent_id: &str, now: i64, trip: LoopTrip) -> LoopVerdict {
+        let index = match trip {
+            LoopTrip::ChainDepth { .. } => 0,
+            LoopTrip::PairRate { .. } => 1,
+            LoopTrip::Duplicate { .. } => 2,
+            LoopTrip::Cooldown { .. } => 3,
+        };
+        self.trips[index].fetch_add(1, Ordering::Relaxed);
+
+        let notify = !matches!(trip, LoopTrip::Cooldown { .. });
+        if notify {
+            let until = now + self.config.cooldown_secs as i64 * 1000;
+            state.cooldowns.insert(agent_id.to_string(), until);
+        }
+        LoopVerdict::Block { trip, notify }
+    }
+
+    /// Agent 当前是否在冷却中
+    pub fn is_cooling_down(&self, agent_id: &str) -> bool {
+        let now = self.clock.now_millis();
+        self.lock().cooldowns.get(agent_id).is_some_and(|&until| until > now)
+    }
+
+    /// 发给被拦截 Agent 的系统提示，深度沿用被拦截的消息，不会重置回复链
+    pub fn notice(&self, blocked: &Message, trip: &LoopTrip) -> Message {
+        let agent_id = blocked.from.as_str();
+        let language = self.agent_languages.get(agent_id).copied().unwrap_or(self.language);
+        let catalog = MessageCatalog::new(language);
+        let reason = match trip {
+            LoopTrip::ChainDepth { depth, max } => catalog.format(
+                "loop_guard.chain_depth",
+                &[("depth", &depth.to_string()), ("max", &max.to_string())],
+            ),
+            LoopTrip::PairRate { peer, exchanges, window_secs } => catalog.format(
+                "loop_guard.pair_rate",
+                &[
+                    ("peer", peer),
+                    ("exchanges", &exchanges.to_string()),
+                    ("minutes", &(window_secs / 60).max(1).to_string()),
+                ],
+            ),
+            LoopTrip::Duplicate { .. } => catalog.get("loop_guard.duplicate"),
+            LoopTrip::Cooldown { .. } => catalog.get("loop_guard.cooldown"),
+        };
+        let content = catalog.format(
+            "loop_guard.notice",
+            &[("reason", &reason), ("minutes", &(self.config.cooldown_secs / 60).max(1).to_string())],
+        );
+
+        let mut notice = Message::private(LOOP_NOTICE_SENDER, agent_id, content)
+            .with_metadata("kind", "loop_guard")
+            .with_metadata("blocked_message_id", &blocked.id)
+            .with_chain_depth(blocked.chain_depth());
+        if let Some(trace_id) = blocked.trace_id() {
+            notice = notice.with_trace(trace_id, None);
+        }
+        notice
+    }
+
+    /// 拦截计数快照
+    pub fn metrics(&self) -> LoopGuardMetrics {
+        let load = |i: usize| self.trips[i].load(Ordering::Relaxed);
+        LoopGuardMetrics {
+            chain_depth: load(0),
+            pair_rate: load(1),
+            duplicate: load(2),
+            cooldown: load(3),
+        }
+    }
+}
+
+impl std::fmt::Debug for LoopGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoopGuard")
+            .field("config", &self.config)
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+impl Default for LoopGuard {
+    fn default() -> Self {
+        Self::new(LoopGuardConfig::default())
+    }
+}
+
+fn pair(a: &str, b: &str) -> (String, String) {
+    if a <= b {
+        (a.to_string(), b.to_string())
+    } else {
+        (b.to_string(), a.to_string())
+    }
+}
+
+/// 小写、去标点后的词集合
+fn normalize(content: &str) -> HashSet<String> {
+    content
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Jaccard 相似度，两者都为空时视为相同
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
@@ -0,0 +1,253 @@
+//! @提及自动补全
+//!
+//! Web 聊天输入 `@` 时由 `GET /api/mentions/suggest` 返回候选人（Agent 和用户），前端不必拉取
+//! 整个组织架构和用户列表。[`MentionIndex`] 与 [`OrgTreeProjection`](crate::core::org_tree::OrgTreeProjection)
+//! 一样缓存从存储构建的索引，`OrgChanged`、`UserChanged` 事件或 [`MentionIndex::invalidate`] 使其失效，
+//! 下一次请求重新构建，每个请求本身不读取存储。
+//!
+//! 排序：ID 或名称前缀匹配输入，当前会话（群聊）的成员优先，其次是与输入者同部门的，最后是其他人。
+//! 私有部门（[`MentionConfig::private_departments`]）的成员只对同部门的人和同一会话的成员可见。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::store::Store;
+use crate::domain::user::{user_principal, User};
+use crate::domain::Organization;
+
+/// 提及补全配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MentionConfig {
+    /// 私有部门ID，其成员只对同部门和同一会话的人出现在候选中
+    pub private_departments: Vec<String>,
+}
+
+/// 候选人类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MentionKind {
+    Agent,
+    User,
+}
+
+/// 返回给前端的候选人，只含补全需要的字段
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MentionCandidate {
+    /// 消息参与者ID：Agent 为其ID，用户为 `user:{id}`
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: MentionKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// 部门名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub department: Option<String>,
+}
+
+/// 一次补全请求
+#[derive(Debug, Clone, Default)]
+pub struct MentionQuery {
+    /// 输入者的参与者ID，自己不出现在候选中
+    pub viewer: String,
+    /// `@` 之后已输入的文字，为空时列出所有可见的人
+    pub prefix: String,
+    /// 当前会话中输入者可见的成员
+    pub session_members: HashSet<String>,
+    pub limit: usize,
+}
+
+/// 排序分组，值越小越靠前
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Rank {
+    SessionMember,
+    SameDepartment,
+    Other,
+}
+
+#[derive(Debug)]
+struct Entry {
+    candidate: MentionCandidate,
+    /// 参与匹配的小写字段（ID、名称、用户名）
+    keys: Vec<String>,
+    department_id: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Snapshot {
+    entries: Vec<Entry>,
+    by_id: HashMap<String, usize>,
+}
+
+impl Snapshot {
+    fn build(org: &Organization, users: &[User]) -> Self {
+        let departments: HashMap<&str, &str> = org.departments.iter().map(|d| (d.id.as_str(), d.name.as_str())).collect();
+        // 用户的部门字段可能是部门ID或名称
+        let department_of_user = |department: &str| {
+            org.departments
+                .iter()
+                .find(|d| d.id == department || d.name == department)
+                .map(|d| d.id.clone())
+        };
+        let user_ids: HashSet<&str> = users.iter().map(|user| user.id.as_str()).collect();
+
+        let mut entries = Vec::with_capacity(org.agents.len() + users.len());
+        // 董事长和管理层注册时会同时建一个同 ID 的 Agent，只保留用户本身
+        for agent in org.agents.iter().filter(|agent| !user_ids.contains(agent.id.as_str())) {
+            entries.push(Entry {
+                keys: vec![agent.id.to_lowercase(), agent.name.to_lowercase()],
+                department_id: agent.department_id.clone(),
+                candidate: MentionCandidate {
+                    id: agent.id.clone(),
+                    name: agent.name.clone(),
+                    kind: MentionKind::Agent,
+                    title: Some(agent.role.title.clone()).filter(|title| !title.is_empty()),
+                    department: agent
+                        .department_id
+                        .as_deref()
+                        .and_then(|id| departments.get(id))
+                        .map(|name| name.to_string()),
+                },
+            });
+        }
+        for user in users {
+            let department_id = department_of_user(&user.department);
+            entries.push(Entry {
+                keys: vec![user.id.to_lowercase(), user.name.to_lowercase(), user.username.to_lowercase()],
+                candidate: MentionCandidate {
+                    id: user_principal(&user.id),
+                    name: user.name.clone(),
+                    kind: MentionKind::User,
+                    title: Some(format!("{:?}", user.position)),
+                    department: Some(user.department.clone()).filter(|department| !department.is_empty()),
+                },
+                department_id,
+            });
+        }
+        let by_id = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.candidate.id.clone(), index))
+            .collect();
+        Self { entries, by_id }
+    }
+}
+
+/// 缓存的提及候选索引
+#[derive(Debug, Default)]
+pub struct MentionIndex {
+    config: MentionConfig,
+    cached: RwLock<Option<Arc<Snapshot>>>,
+    /// 每次失效加一；构建期间发生失效时不写入缓存，避免缓存旧版本
+    generation: AtomicU64,
+    builds: AtomicU64,
+}
+
+impl MentionIndex {
+    pub fn new(config: MentionConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    pub fn config(&self) -> &MentionConfig {
+        &self.config
+    }
+
+    /// 按输入者可见范围返回排好序的候选人，缓存失效时从存储重新构建索引
+    pub async fn suggest(&self, store: &dyn Store, query: &MentionQuery) -> Result<Vec<MentionCandidate>> {
+        let snapshot = self.snapshot(store).await?;
+        let prefix = query.prefix.trim().trim_start_matches('@').to_lowercase();
+        let viewer_department = snapshot
+            .by_id
+            .get(&query.viewer)
+            .and_then(|&index| snapshot.entries[index].department_id.as_deref());
+
+        let mut ranked: Vec<(Rank, &Entry)> = snapshot
+            .entries
+            .iter()
+            .filter(|entry| entry.candidate.id != query.viewer)
+            .filter(|entry| entry.keys.iter().any(|key| key.starts_with(&prefix)))
+            .filter_map(|entry| {
+                let same_department = viewer_department.is_some() && entry.department_id.as_deref() == viewer_department;
+                let rank = if query.session_members.contains(&entry.candidate.id) {
+                    Rank::SessionMember
+                } else if same_department {
+                    Rank::SameDepartment
+                } else if entry
+                    .department_id
+                    .as_ref()
+                    .is_some_and(|department| self.config.private_departments.contains(department))
+                {
+                    return None;
+                } else {
+                    Rank::Other
+                };
+                Some((rank, entry))
+            })
+            .collect();
+        ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+            a_rank
+                .cmp(b_rank)
+                .then_with(|| a.candidate.name.to_lowercase().cmp(&b.candidate.name.to_lowercase()))
+                .then_with(|| a.candidate.id.cmp(&b.candidate.id))
+        });
+        Ok(ranked
+            .into_iter()
+            .take(query.limit)
+            .map(|(_, entry)| entry.candidate.clone())
+            .collect())
+    }
+
+    async fn snapshot(&self, store: &dyn Store) -> Result<Arc<Snapshot>> {
+        if let Some(snapshot) = self.cached.read().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Ok(snapshot);
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let org = store.load_organization().await?;
+        let users = store.load_users().await?;
+        let snapshot = Arc::new(Snapshot::build(&org, &users));
+        self.builds.fetch_add(1, Ordering::Relaxed);
+        let mut cached = self.cached.write().unwrap_or_else(|e| e.into_inner());
+        if self.generation.load(Ordering::SeqCst) == generation {
+            *cached = Some(snapshot.clone());
+        }
+        Ok(snapshot)
+    }
+
+    /// 组织架构或用户已变更，下一次请求重新构建
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.cached.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// 累计构建次数
+    pub fn builds(&self) -> u64 {
+        self.builds.load(Ordering::Relaxed)
+    }
+
+    /// 收到 `OrgChanged` 或 `UserChanged` 事件时使缓存失效（需在 Tokio 运行时中调用）
+    pub fn spawn(self: Arc<Self>, events: &EventBus) {
+        events.register_listener(Box::new(MentionListener(self)));
+    }
+}
+
+struct MentionListener(Arc<MentionIndex>);
+
+#[async_trait]
+impl CompanyEventListener for MentionListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        if let CompanyEvent::OrgChanged { .. } | CompanyEvent::UserChanged { .. } = event {
+            self.0.invalidate();
+        }
+    }
+}
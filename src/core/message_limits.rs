@@ -0,0 +1,207 @@
+//! 消息大小限制
+//!
+//! 所有入口（REST 发送、WebSocket `send_message` 帧、`message.send_*` 框架工具）用同一个
+//! [`MessageLimits::check`] 校验正文字节数、@ 数量和元数据大小，超出时返回 [`MessageLimitError`]。
+//! 配置为 [`OversizeAction::Attach`] 且有附件存储时，超长正文改存为附件，消息只保留截断的预览
+//! （见 [`enforce_message_limits`]）；@ 和元数据超限总是拒绝。
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::core::attachments::{attachment_url, save_attachment};
+use crate::core::i18n::MessageCatalog;
+use crate::domain::Message;
+use crate::infrastructure::blob::{body_from_bytes, BlobStore};
+
+/// 默认的正文上限（字节）
+pub const DEFAULT_MAX_CONTENT_BYTES: usize = 64 * 1024;
+
+/// 默认的 @ 数量上限
+pub const DEFAULT_MAX_MENTIONS: usize = 50;
+
+/// 默认的元数据上限（所有键和值的字节数之和）
+pub const DEFAULT_MAX_METADATA_BYTES: usize = 16 * 1024;
+
+/// 默认的预览字符数
+pub const DEFAULT_PREVIEW_CHARS: usize = 500;
+
+/// 超长消息正文转成的附件的内容类型
+const MESSAGE_CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// 正文超长时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizeAction {
+    /// 拒绝消息
+    #[default]
+    Reject,
+    /// 正文存为附件，消息保留截断的预览；没有附件存储时仍然拒绝
+    Attach,
+}
+
+impl FromStr for OversizeAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "attach" => Ok(Self::Attach),
+            other => Err(format!("unknown oversize action: {}", other)),
+        }
+    }
+}
+
+/// 消息大小限制
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MessageLimits {
+    /// 正文的最大字节数
+    pub max_content_bytes: usize,
+    /// 最多 @ 的数量
+    pub max_mentions: usize,
+    /// 元数据所有键和值的最大字节数
+    pub max_metadata_bytes: usize,
+    /// 正文超长时拒绝还是转为附件
+    pub oversize: OversizeAction,
+    /// 转为附件时消息中保留的预览字符数
+    pub preview_chars: usize,
+}
+
+impl Default for MessageLimits {
+    fn default() -> Self {
+        Self {
+            max_content_bytes: DEFAULT_MAX_CONTENT_BYTES,
+            max_mentions: DEFAULT_MAX_MENTIONS,
+            max_metadata_bytes: DEFAULT_MAX_METADATA_BYTES,
+            oversize: OversizeAction::Reject,
+            preview_chars: DEFAULT_PREVIEW_CHARS,
+        }
+    }
+}
+
+/// 消息超出限制
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MessageLimitError {
+    #[error("Message content is {size} bytes, over the {limit}-byte limit")]
+    ContentTooLarge { size: usize, limit: usize },
+    #[error("Message mentions {count} agents, over the limit of {limit}")]
+    TooManyMentions { count: usize, limit: usize },
+    #[error("Message metadata is {size} bytes, over the {limit}-byte limit")]
+    MetadataTooLarge { size: usize, limit: usize },
+}
+
+impl MessageLimitError {
+    /// 按消息目录的语言描述错误，告诉发送者如何缩短
+    pub fn describe(&self, catalog: &MessageCatalog) -> String {
+        let (key, size, limit) = match self {
+            Self::ContentTooLarge { size, limit } => ("message.content_too_large", size, limit),
+            Self::TooManyMentions { count, limit } => ("message.too_many_mentions", count, limit),
+            Self::MetadataTooLarge { size, limit } => ("message.metadata_too_large", size, limit),
+        };
+        catalog.format(key, &[("size", &size.to_string()), ("limit", &limit.to_string())])
+    }
+}
+
+impl MessageLimits {
+    /// 正文上限
+    pub fn with_max_content_bytes(mut self, max_content_bytes: usize) -> Self {
+        self.max_content_bytes = max_content_bytes;
+        self
+    }
+
+    /// @ 数量上限
+    pub fn with_max_mentions(mut self, max_mentions: usize) -> Self {
+        self.max_mentions = max_mentions;
+        self
+    }
+
+    /// 元数据上限
+    pub fn with_max_metadata_bytes(mut self, max_metadata_bytes: usize) -> Self {
+        self.max_metadata_bytes = max_metadata_bytes;
+        self
+    }
+
+    /// 正文超长时转为附件，保留 `preview_chars` 个字符的预览
+    pub fn attach_oversized(mut self, preview_chars: usize) -> Self {
+        self.oversize = OversizeAction::Attach;
+        self.preview_chars = preview_chars;
+        self
+    }
+
+    /// 校验消息，依次检查 @ 数量、元数据和正文
+    pub fn check(&self, message: &Message) -> Result<(), MessageLimitError> {
+        self.check_envelope(message)?;
+        let size = message.content.len();
+        if size > self.max_content_bytes {
+            return Err(MessageLimitError::ContentTooLarge { size, limit: self.max_content_bytes });
+        }
+        Ok(())
+    }
+
+    /// 只校验正文以外的部分（@ 数量和元数据），这两项超限时不能转为附件
+    pub fn check_envelope(&self, message: &Message) -> Result<(), MessageLimitError> {
+        let count = message.mentions.len();
+        if count > self.max_mentions {
+            return Err(MessageLimitError::TooManyMentions { count, limit: self.max_mentions });
+        }
+        let size: usize = message.metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
+        if size > self.max_metadata_bytes {
+            return Err(MessageLimitError::MetadataTooLarge { size, limit: self.max_metadata_bytes });
+        }
+        Ok(())
+    }
+
+    /// 正文的预览：最多 `preview_chars` 个字符，且不超过正文上限的一半
+    pub fn preview<'a>(&self, content: &'a str) -> &'a str {
+        let max_bytes = self.max_content_bytes / 2;
+        let end = content
+            .char_indices()
+            .take(self.preview_chars)
+            .map(|(i, c)| i + c.len_utf8())
+            .take_while(|end| *end <= max_bytes)
+            .last()
+            .unwrap_or(0);
+        &content[..end]
+    }
+}
+
+/// 按限制校验消息，所有发送入口共用
+///
+/// 正文超长且配置为 [`OversizeAction::Attach`]、有附件存储时，正文存为附件（上传者为发送者），
+/// 消息改为截断的预览并附上附件链接；其余超限情况返回错误。附件保存失败时同样按超长拒绝。
+pub async fn enforce_message_limits(
+    limits: &MessageLimits,
+    message: Message,
+    blobs: Option<&dyn BlobStore>,
+    catalog: &MessageCatalog,
+) -> Result<Message, MessageLimitError> {
+    let error = match limits.check(&message) {
+        Ok(()) => return Ok(message),
+        Err(error) => error,
+    };
+    let (MessageLimitError::ContentTooLarge { size, .. }, OversizeAction::Attach, Some(blobs)) =
+        (&error, limits.oversize, blobs)
+    else {
+        return Err(error);
+    };
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let name = format!("message-{}.txt", message.id);
+    let body = body_from_bytes(message.content.clone().into_bytes());
+    if let Err(e) = save_attachment(blobs, &id, &name, MESSAGE_CONTENT_TYPE, &message.from, body).await {
+        error!("Failed to store oversized message {} as an attachment: {}", message.id, e);
+        return Err(error);
+    }
+    let content = catalog.format(
+        "message.truncated_preview",
+        &[
+            ("preview", limits.preview(&message.content)),
+            ("size", &size.to_string()),
+            ("attachment_id", &id),
+        ],
+    );
+    info!(message_id = %message.id, attachment_id = %id, size, "Oversized message content moved to an attachment");
+    Ok(Message { content, ..message }.with_attachment(attachment_url(&id)))
+}
@@ -3,11 +3,184 @@
 //! 提供 Agent 间的消息传递能力：私聊、群聊、广播
 
 use anyhow::{Context, Result};
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc, RwLock};
-use tracing::{debug, info, warn};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
 
-use crate::domain::{Group, Message, MessageTarget};
+#[cfg(feature = "chaos")]
+use crate::core::chaos::{FaultInjector, Subsystem};
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::events::{CompanyEvent, EventBus};
+use crate::core::i18n::MessageCatalog;
+use crate::core::join_context::{InviteOptions, JoinContext, JOIN_CONTEXT_KEY};
+use crate::core::store::MessageFilter;
+use crate::domain::user::is_user_principal;
+use crate::domain::{
+    direct_session_id, Group, GroupSync, GroupSyncReport, Message, MessageId, MessagePin, MessagePriority, MessageReaction,
+    MessageTarget, TurnOutbox, TurnTakingSettings,
+};
+
+/// 回应事件通道容量
+const REACTION_CHANNEL_CAPACITY: usize = 256;
+
+/// 用户信箱通道容量（同一用户的所有在线会话共享）
+const USER_MAILBOX_CAPACITY: usize = 100;
+
+/// 临时群聊关闭通知的发送者
+pub const GROUP_EXPIRY_SENDER: &str = "system";
+
+/// 每个 Agent 默认最多同时拥有的临时群聊数
+pub const DEFAULT_EPHEMERAL_GROUP_LIMIT: usize = 3;
+
+/// 关闭临时群聊时快照保存的最多消息数
+const GROUP_SNAPSHOT_MAX_MESSAGES: usize = 10_000;
+
+/// 群成员移除通知通道容量
+const REMOVAL_CHANNEL_CAPACITY: usize = 64;
+
+/// 群聊管理操作或发言被拒绝
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GroupModerationError {
+    #[error("Group not found: {group_id}")]
+    GroupNotFound { group_id: String },
+    #[error("{actor} is not an admin of group {group_id}")]
+    NotAdmin { group_id: String, actor: String },
+    #[error("{member} is not a member of group {group_id}")]
+    NotMember { group_id: String, member: String },
+    #[error("{member} is already a member of group {group_id}")]
+    AlreadyMember { group_id: String, member: String },
+    #[error("{member} created group {group_id} and cannot be removed or muted")]
+    Creator { group_id: String, member: String },
+    #[error("{member} is muted in group {group_id} until {} and cannot post there; the message was not sent", format_until(*until))]
+    Muted { group_id: String, member: String, until: i64 },
+}
+
+/// 置顶操作被拒绝
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PinError {
+    #[error("Message not found: {message_id}")]
+    MessageNotFound { message_id: String },
+    #[error("{actor} is not a participant of session {session_id}")]
+    NotParticipant { session_id: String, actor: String },
+    #[error("{actor} cannot unpin a message pinned by {pinned_by} in {session_id}; only group admins can")]
+    NotAdmin { session_id: String, actor: String, pinned_by: String },
+}
+
+/// 置顶记录及被置顶的消息
+#[derive(Debug, Clone, Serialize)]
+pub struct PinnedMessage {
+    #[serde(flatten)]
+    pub pin: MessagePin,
+    pub message: Message,
+}
+
+fn format_until(until: i64) -> String {
+    chrono::DateTime::from_timestamp(until, 0)
+        .map(|at| at.to_rfc3339())
+        .unwrap_or_else(|| until.to_string())
+}
+
+/// 成员被管理员移出群聊，已订阅的接收器据此退订
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupRemoval {
+    pub group_id: String,
+    pub member: String,
+}
+
+/// 临时群聊关闭时保存的会话快照，以 `group_snapshot:{id}` 存在应用状态中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupSnapshot {
+    pub group: Group,
+    pub closed_at: i64,
+    /// 群聊内的全部消息，按时间升序
+    pub messages: Vec<Message>,
+}
+
+impl GroupSnapshot {
+    /// 快照在应用状态中的键
+    pub fn key(group_id: &str) -> String {
+        format!("group_snapshot:{}", group_id)
+    }
+}
+
+/// 消息回应变化，推送给 WebSocket 等订阅者
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReactionEvent {
+    Added(MessageReaction),
+    Removed(MessageReaction),
+}
+
+/// 轮次失败时发件箱的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxPolicy {
+    /// 丢弃本轮排队的消息
+    #[default]
+    Discard,
+    /// 仍然发送本轮排队的消息
+    Flush,
+}
+
+/// 发件箱处理结果
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OutboxReport {
+    /// 已发送的消息
+    pub sent: Vec<MessageId>,
+    /// 被丢弃的消息
+    pub discarded: Vec<MessageId>,
+    /// 无法投递的消息及原因
+    pub failed: Vec<(MessageId, String)>,
+}
+
+/// 每个发送者的 Urgent 消息限流
+///
+/// 窗口内超出上限的 Urgent 消息降级为 High 继续投递，避免一个发送者用 Urgent 刷屏
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrgentRateLimit {
+    /// 窗口内允许的 Urgent 消息数
+    pub max_messages: u32,
+    /// 窗口长度（秒）
+    pub window_secs: u64,
+}
+
+impl Default for UrgentRateLimit {
+    fn default() -> Self {
+        Self {
+            max_messages: 5,
+            window_secs: 60,
+        }
+    }
+}
+
+/// 按发送者记录窗口内的 Urgent 消息时间
+#[derive(Debug, Default)]
+struct UrgentLimiter {
+    limit: UrgentRateLimit,
+    sent: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl UrgentLimiter {
+    /// 占用一个 Urgent 名额，已满时返回 false
+    fn admit(&self, sender: &str) -> bool {
+        let now = Instant::now();
+        let window = Duration::from_secs(self.limit.window_secs);
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let times = sent.entry(sender.to_string()).or_default();
+        while times.front().is_some_and(|t| now.duration_since(*t) >= window) {
+            times.pop_front();
+        }
+        if times.len() >= self.limit.max_messages as usize {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
 
 /// 消息总线
 ///
@@ -15,12 +188,50 @@ use crate::domain::{Group, Message, MessageTarget};
 pub struct MessageBus {
     /// 私聊通道映射
     private_txs: dashmap::DashMap<String, mpsc::Sender<Message>>,
+    /// 用户信箱（`user:{id}`），桥接到该用户的 WebSocket 会话
+    user_txs: dashmap::DashMap<String, broadcast::Sender<Message>>,
     /// 群聊信息映射
     groups: Arc<RwLock<std::collections::HashMap<String, Group>>>,
     /// 群聊通道映射
     group_txs: dashmap::DashMap<String, broadcast::Sender<Message>>,
     /// 消息存储（可选）
     store: Option<Arc<dyn crate::core::store::Store>>,
+    /// 生命周期事件（可选）
+    events: Option<Arc<EventBus>>,
+    /// 回应变化广播
+    reaction_tx: broadcast::Sender<ReactionEvent>,
+    /// Urgent 消息限流
+    urgent: UrgentLimiter,
+    /// 判断临时群聊过期和禁言到期的时钟
+    clock: Arc<dyn Clock>,
+    /// 系统通知使用的消息目录
+    catalog: MessageCatalog,
+    /// 已关闭的临时群聊 ID -> 关闭时间，不能再次创建
+    closed_groups: dashmap::DashMap<String, i64>,
+    /// 每个 Agent 最多同时拥有的临时群聊数
+    ephemeral_group_limit: usize,
+    /// 成员被移出群聊的广播
+    removal_tx: broadcast::Sender<GroupRemoval>,
+    /// 成员信箱已满、没能投递的群聊消息数
+    dropped_group_deliveries: AtomicU64,
+    /// 每个群聊最后分配的序号，None 表示尚未从存储读取
+    group_sequences: dashmap::DashMap<String, Arc<tokio::sync::Mutex<Option<u64>>>>,
+    /// 邀请成员时生成前情提要（可选）
+    join_context: Option<Arc<JoinContext>>,
+    /// 故障注入器（`chaos` 特性）
+    #[cfg(feature = "chaos")]
+    fault_injector: Option<Arc<FaultInjector>>,
+}
+
+impl std::fmt::Debug for MessageBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MessageBus")
+            .field("agents", &self.private_txs.len())
+            .field("users", &self.user_txs.len())
+            .field("groups", &self.group_txs.len())
+            .field("has_store", &self.store.is_some())
+            .finish()
+    }
 }
 
 impl MessageBus {
@@ -28,22 +239,105 @@ impl MessageBus {
     pub fn new() -> Self {
         Self {
             private_txs: dashmap::DashMap::new(),
+            user_txs: dashmap::DashMap::new(),
             groups: Arc::new(RwLock::new(std::collections::HashMap::new())),
             group_txs: dashmap::DashMap::new(),
             store: None,
+            events: None,
+            reaction_tx: broadcast::channel(REACTION_CHANNEL_CAPACITY).0,
+            urgent: UrgentLimiter::default(),
+            clock: Arc::new(SystemClock),
+            catalog: MessageCatalog::default(),
+            closed_groups: dashmap::DashMap::new(),
+            ephemeral_group_limit: DEFAULT_EPHEMERAL_GROUP_LIMIT,
+            removal_tx: broadcast::channel(REMOVAL_CHANNEL_CAPACITY).0,
+            dropped_group_deliveries: AtomicU64::new(0),
+            group_sequences: dashmap::DashMap::new(),
+            join_context: None,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
         }
     }
 
     /// 创建带有存储的新消息总线
     pub fn with_store(store: Arc<dyn crate::core::store::Store>) -> Self {
         Self {
-            private_txs: dashmap::DashMap::new(),
-            groups: Arc::new(RwLock::new(std::collections::HashMap::new())),
-            group_txs: dashmap::DashMap::new(),
             store: Some(store),
+            ..Self::new()
         }
     }
 
+    /// 消息落库后发出 `MessagePersisted` 事件
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 消息总线使用的事件总线，任务管理器等组件借此发出自己的事件
+    pub fn events(&self) -> Option<Arc<EventBus>> {
+        self.events.clone()
+    }
+
+    /// 设置每个发送者的 Urgent 消息限流
+    pub fn with_urgent_rate_limit(mut self, limit: UrgentRateLimit) -> Self {
+        self.urgent = UrgentLimiter {
+            limit,
+            sent: Mutex::new(HashMap::new()),
+        };
+        self
+    }
+
+    /// 设置时钟（测试中用 ManualClock 驱动临时群聊过期）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 因成员信箱已满而没能投递到信箱的群聊消息数（实时广播不受影响）
+    pub fn dropped_group_deliveries(&self) -> u64 {
+        self.dropped_group_deliveries.load(Ordering::Relaxed)
+    }
+
+    /// 判断临时群聊过期和禁言到期所用的时钟
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// 设置系统通知使用的消息目录
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// 系统通知使用的消息目录
+    pub fn catalog(&self) -> MessageCatalog {
+        self.catalog
+    }
+
+    /// 设置每个 Agent 最多同时拥有的临时群聊数
+    pub fn with_ephemeral_group_limit(mut self, limit: usize) -> Self {
+        self.ephemeral_group_limit = limit;
+        self
+    }
+
+    /// 邀请成员加入群聊时给新成员发送前情提要
+    pub fn with_join_context(mut self, join_context: Arc<JoinContext>) -> Self {
+        self.join_context = Some(join_context);
+        self
+    }
+
+    /// 投递前询问故障注入器（子系统 `messaging`，操作 `send`）：丢弃时消息已落库但不投递
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injector(mut self, injector: Arc<FaultInjector>) -> Self {
+        self.fault_injector = Some(injector);
+        self
+    }
+
+    /// 获取消息存储（如果配置了）
+    pub fn store(&self) -> Option<Arc<dyn crate::core::store::Store>> {
+        self.store.clone()
+    }
+
     /// 注册 Agent 到消息总线
     pub fn register(&self, agent_id: &str) -> mpsc::Receiver<Message> {
         let (tx, rx) = mpsc::channel(100);
@@ -53,13 +347,41 @@ impl MessageBus {
         rx
     }
 
+    /// Agent 是否已注册到消息总线
+    pub fn is_registered(&self, agent_id: &str) -> bool {
+        self.private_txs.contains_key(agent_id)
+    }
+
+    /// 注册用户为消息参与者（`user:{id}`）
+    ///
+    /// 用户不在线时消息只落库，登录后通过存储拉取；在线会话通过 [`Self::subscribe_user`] 实时接收。
+    pub fn register_user(&self, principal: &str) -> broadcast::Sender<Message> {
+        self.user_txs
+            .entry(principal.to_string())
+            .or_insert_with(|| {
+                info!("Registered user to message bus: {}", principal);
+                broadcast::channel(USER_MAILBOX_CAPACITY).0
+            })
+            .clone()
+    }
+
+    /// 订阅用户信箱，每个在线会话一个接收端
+    pub fn subscribe_user(&self, principal: &str) -> broadcast::Receiver<Message> {
+        self.register_user(principal).subscribe()
+    }
+
+    /// 用户是否已注册
+    pub fn is_user_registered(&self, principal: &str) -> bool {
+        self.user_txs.contains_key(principal)
+    }
+
     /// 注销 Agent
     pub fn unregister(&self, agent_id: &str) {
         self.private_txs.remove(agent_id);
         info!("Unregistered agent from message bus: {}", agent_id);
     }
 
-    /// 创建群聊
+    /// 创建群聊，连接了存储时同时写入存储
     pub async fn create_group(
         &self,
         id: &str,
@@ -67,31 +389,235 @@ impl MessageBus {
         creator_id: &str,
         members: Vec<String>,
     ) -> Result<()> {
-        // 验证创建者
-        if !self.private_txs.contains_key(creator_id) {
+        self.insert_group(Group::new(id, name, creator_id, members)).await
+    }
+
+    /// 创建临时群聊，`ttl` 后由 [`Self::close_expired_groups`] 自动关闭
+    ///
+    /// 临时群聊会写入存储，但不出现在默认的群聊列表中；每个创建者同时拥有的数量受
+    /// [`Self::with_ephemeral_group_limit`] 限制。
+    pub async fn create_ephemeral_group(
+        &self,
+        id: &str,
+        name: &str,
+        creator_id: &str,
+        members: Vec<String>,
+        ttl: Duration,
+    ) -> Result<Group> {
+        let active = self.active_ephemeral_groups(creator_id).await;
+        if active >= self.ephemeral_group_limit {
+            return Err(anyhow::anyhow!(
+                "{} already has {} active ephemeral groups (limit {})",
+                creator_id,
+                active,
+                self.ephemeral_group_limit
+            ));
+        }
+
+        let mut group = Group::new(id, name, creator_id, members).with_expiry(self.clock.now() + ttl.as_secs() as i64);
+        group.created_at = self.clock.now();
+        self.insert_group(group.clone()).await?;
+        Ok(group)
+    }
+
+    /// 创建者当前拥有的临时群聊数
+    pub async fn active_ephemeral_groups(&self, creator_id: &str) -> usize {
+        let groups = self.groups.read().await;
+        groups.values().filter(|g| g.ephemeral && g.creator_id == creator_id).count()
+    }
+
+    /// 每个 Agent 最多同时拥有的临时群聊数
+    pub fn ephemeral_group_limit(&self) -> usize {
+        self.ephemeral_group_limit
+    }
+
+    /// 注册群聊并写入存储，已关闭的临时群聊 ID 不能再次使用
+    async fn insert_group(&self, group: Group) -> Result<()> {
+        let (id, creator_id) = (group.id.clone(), group.creator_id.clone());
+        // 验证创建者：已注册的 Agent 或用户
+        if !self.private_txs.contains_key(&creator_id) && !self.user_txs.contains_key(&creator_id) {
             return Err(anyhow::anyhow!("Creator not registered: {}", creator_id));
         }
+        if let Some(closed_at) = self.closed_at(&id).await {
+            return Err(anyhow::anyhow!(
+                "Group {} expired at {} and cannot be re-activated",
+                id,
+                closed_at
+            ));
+        }
+
+        // 先写存储，失败时总线上也不出现这个群聊
+        if let Some(store) = &self.store {
+            store.save_group(&group).await?;
+        }
 
-        let group = Group::new(id, name, creator_id, members);
         let (tx, _) = broadcast::channel(100);
 
         {
             let mut groups = self.groups.write().await;
-            groups.insert(id.to_string(), group);
+            groups.insert(id.clone(), group);
         }
 
-        self.group_txs.insert(id.to_string(), tx);
+        self.group_txs.insert(id.clone(), tx);
 
         info!("Created group: {} by {}", id, creator_id);
         Ok(())
     }
 
+    /// 临时群聊的关闭时间；重启后从存储中的快照判断
+    async fn closed_at(&self, group_id: &str) -> Option<i64> {
+        if let Some(closed_at) = self.closed_groups.get(group_id) {
+            return Some(*closed_at);
+        }
+        let store = self.store.as_ref()?;
+        let snapshot = store.load_app_state(&GroupSnapshot::key(group_id)).await.ok()??;
+        snapshot["closed_at"].as_i64()
+    }
+
+    /// 关闭所有已过期的临时群聊，返回关闭的群聊 ID
+    ///
+    /// 关闭前向成员发送系统通知，并把包含通知在内的完整会话快照写入存储，
+    /// 随后从总线和存储中移除群聊。存储中残留的过期群聊（如重启前创建的）也会一并关闭。
+    pub async fn close_expired_groups(&self) -> Vec<String> {
+        let now = self.clock.now();
+        if let Some(store) = &self.store {
+            match store.load_groups().await {
+                Ok(stored) => {
+                    let mut groups = self.groups.write().await;
+                    for group in stored.into_iter().filter(|g| g.is_expired(now)) {
+                        self.group_txs
+                            .entry(group.id.clone())
+                            .or_insert_with(|| broadcast::channel(100).0);
+                        groups.entry(group.id.clone()).or_insert(group);
+                    }
+                }
+                Err(e) => warn!("Failed to load groups for expiry sweep: {}", e),
+            }
+        }
+
+        let expired: Vec<Group> = {
+            let groups = self.groups.read().await;
+            groups.values().filter(|g| g.is_expired(now)).cloned().collect()
+        };
+
+        let mut closed = Vec::new();
+        for group in expired {
+            match self.close_group(group, now).await {
+                Ok(id) => closed.push(id),
+                Err(e) => error!("Failed to close expired group: {}", e),
+            }
+        }
+        closed
+    }
+
+    async fn close_group(&self, group: Group, now: i64) -> Result<String> {
+        let notice = Message::group(
+            GROUP_EXPIRY_SENDER,
+            &group.id,
+            self.catalog.format("group.expired_notice", &[("name", &group.name)]),
+        );
+        if let Err(e) = self.send(notice).await {
+            warn!("Failed to notify members of expired group {}: {}", group.id, e);
+        }
+
+        if let Some(store) = &self.store {
+            let filter = MessageFilter::new()
+                .to(&group.id)
+                .target_type("group")
+                .oldest_first()
+                .limit(GROUP_SNAPSHOT_MAX_MESSAGES);
+            let snapshot = GroupSnapshot {
+                group: group.clone(),
+                closed_at: now,
+                messages: store.load_messages(filter).await?,
+            };
+            store
+                .save_app_state(&GroupSnapshot::key(&group.id), &serde_json::to_value(&snapshot)?)
+                .await?;
+            store.delete_group(&group.id).await?;
+        }
+
+        self.groups.write().await.remove(&group.id);
+        self.group_txs.remove(&group.id);
+        self.closed_groups.insert(group.id.clone(), now);
+        info!("Closed expired group: {} ({} members)", group.id, group.members.len());
+        Ok(group.id)
+    }
+
+    /// 读取已关闭临时群聊的会话快照
+    pub async fn group_snapshot(&self, group_id: &str) -> Result<Option<GroupSnapshot>> {
+        let Some(store) = &self.store else {
+            return Ok(None);
+        };
+        match store.load_app_state(&GroupSnapshot::key(group_id)).await? {
+            Some(value) => Ok(Some(serde_json::from_value(value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 在后台定期关闭过期的临时群聊
+    pub fn spawn_group_sweeper(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.close_expired_groups().await;
+            }
+        })
+    }
+
     /// 发送消息（自动路由）
-    pub async fn send(&self, message: Message) -> Result<()> {
+    pub async fn send(&self, mut message: Message) -> Result<()> {
+        if message.priority == MessagePriority::Urgent && !self.urgent.admit(&message.from) {
+            warn!(
+                "Urgent message rate limit reached for {}, delivering {} as high priority",
+                message.from, message.id
+            );
+            message.priority = MessagePriority::High;
+        }
+
+        // 被禁言的成员发的群聊消息不落库也不投递；前情提要只发给新成员本人，不能转发到群聊
+        if let MessageTarget::Group(group_id) = &message.to {
+            if message.metadata(JOIN_CONTEXT_KEY).is_some() {
+                anyhow::bail!("Join context {} cannot be sent to group {}", message.id, group_id);
+            }
+            if let Some(error) = self.muted_error(group_id, &message.from).await {
+                warn!("Rejected group message from muted member: {}", error);
+                return Err(error.into());
+            }
+        }
+
+        // 群聊消息在该群的序号锁内分配序号、落库并投递，同一群聊的消息按序号顺序进入各成员的信箱
+        let _sequence = match message.target_group() {
+            Some(group_id) if self.get_group(group_id).await.is_some() => {
+                let (seq, guard) = self.next_group_seq(group_id).await?;
+                message = message.with_group_seq(seq);
+                Some(guard)
+            }
+            _ => None,
+        };
+
         // 先保存消息到存储
         if let Some(ref store) = self.store {
-            if let Err(e) = store.save_message(&message).await {
-                warn!("Failed to save message to store: {}", e);
+            match store.save_message(&message).await {
+                Ok(()) => {
+                    if let Some(events) = &self.events {
+                        events.emit(CompanyEvent::MessagePersisted {
+                            message: Arc::new(message.clone()),
+                        });
+                    }
+                }
+                Err(e) => warn!("Failed to save message to store: {}", e),
+            }
+        }
+
+        #[cfg(feature = "chaos")]
+        if let Some(injector) = &self.fault_injector {
+            if let Err(fault) = injector.inject(Subsystem::Messaging, "send").await {
+                if fault.is_drop() {
+                    return Ok(());
+                }
+                return Err(fault.into());
             }
         }
 
@@ -102,8 +628,124 @@ impl MessageBus {
         }
     }
 
+    /// 分配群聊的下一个序号，返回的锁在消息投递完成前不能释放
+    ///
+    /// 每个群聊第一次发送时从存储中已有的最大序号继续，重启后序号保持连续
+    async fn next_group_seq(&self, group_id: &str) -> Result<(u64, tokio::sync::OwnedMutexGuard<Option<u64>>)> {
+        let sequence = self.group_sequences.entry(group_id.to_string()).or_default().clone();
+        let mut last = sequence.lock_owned().await;
+        let current = match (*last, &self.store) {
+            (Some(seq), _) => seq,
+            (None, Some(store)) => store
+                .max_group_seq(group_id)
+                .await
+                .with_context(|| format!("Failed to load sequence of group {}", group_id))?,
+            (None, None) => 0,
+        };
+        *last = Some(current + 1);
+        Ok((current + 1, last))
+    }
+
+    /// 轮次结束时处理发件箱
+    ///
+    /// 轮次成功（或失败但策略为 [`OutboxPolicy::Flush`]）时按入队顺序发送。发送前先检查
+    /// 所有收件人，只要有一条无法投递就整批不发，避免只送出一部分。
+    pub async fn flush_outbox(
+        &self,
+        agent_id: &str,
+        trace_id: &str,
+        outbox: &TurnOutbox,
+        turn_failed: bool,
+        policy: OutboxPolicy,
+    ) -> OutboxReport {
+        let messages = outbox.take();
+        if messages.is_empty() {
+            return OutboxReport::default();
+        }
+
+        let mut report = OutboxReport::default();
+        if turn_failed && policy == OutboxPolicy::Discard {
+            report.discarded = messages.into_iter().map(|m| m.id).collect();
+        } else {
+            let mut undeliverable = Vec::new();
+            for message in &messages {
+                if let Some(reason) = self.undeliverable_reason(message).await {
+                    undeliverable.push((message.id.clone(), reason));
+                }
+            }
+
+            if undeliverable.is_empty() {
+                for message in messages {
+                    let id = message.id.clone();
+                    match self.send(message).await {
+                        Ok(()) => report.sent.push(id),
+                        Err(e) => report.failed.push((id, e.to_string())),
+                    }
+                }
+            } else {
+                report.discarded = messages
+                    .into_iter()
+                    .map(|m| m.id)
+                    .filter(|id| !undeliverable.iter().any(|(failed, _)| failed == id))
+                    .collect();
+                report.failed = undeliverable;
+            }
+        }
+
+        info!(
+            "Agent {} outbox flushed (turn_failed={}): {} sent, {} discarded, {} failed",
+            agent_id,
+            turn_failed,
+            report.sent.len(),
+            report.discarded.len(),
+            report.failed.len()
+        );
+        if let Some(events) = &self.events {
+            events.emit(CompanyEvent::OutboxFlushed {
+                agent_id: agent_id.into(),
+                trace_id: trace_id.into(),
+                turn_failed,
+                report: Arc::new(report.clone()),
+            });
+        }
+        report
+    }
+
+    /// 消息无法投递的原因
+    async fn undeliverable_reason(&self, message: &Message) -> Option<String> {
+        match &message.to {
+            MessageTarget::Direct(to) if is_user_principal(to) => {
+                (self.store.is_none() && !self.user_txs.contains_key(to))
+                    .then(|| format!("Recipient not found: {}", to))
+            }
+            MessageTarget::Direct(to) => {
+                (!self.private_txs.contains_key(to)).then(|| format!("Recipient not found: {}", to))
+            }
+            MessageTarget::Group(group_id) => {
+                if self.get_group(group_id).await.is_none() {
+                    return Some(format!("Group not found: {}", group_id));
+                }
+                self.muted_error(group_id, &message.from).await.map(|e| e.to_string())
+            }
+        }
+    }
+
+    /// 发送者在群聊中被禁言时的错误，禁言到期后自动失效
+    async fn muted_error(&self, group_id: &str, member: &str) -> Option<GroupModerationError> {
+        let groups = self.groups.read().await;
+        let until = groups.get(group_id)?.muted_until(member, self.clock.now())?;
+        Some(GroupModerationError::Muted {
+            group_id: group_id.to_string(),
+            member: member.to_string(),
+            until,
+        })
+    }
+
     /// 发送私聊消息
     async fn send_private(&self, message: Message, to: &str) -> Result<()> {
+        if is_user_principal(to) {
+            return self.deliver_to_user(message, to);
+        }
         if let Some(tx) = self.private_txs.get(to) {
             tx.send(message)
                 .await
@@ -116,23 +758,66 @@ impl MessageBus {
         }
     }
 
+    /// 投递到用户信箱：已落库的消息即使用户离线也视为送达
+    fn deliver_to_user(&self, message: Message, principal: &str) -> Result<()> {
+        if self.store.is_none() && !self.user_txs.contains_key(principal) {
+            warn!("Recipient not found: {}", principal);
+            return Err(anyhow::anyhow!("Recipient not found: {}", principal));
+        }
+        if let Some(tx) = self.user_txs.get(principal) {
+            // 没有在线会话不算失败，消息已在存储中等待拉取
+            let _ = tx.send(message);
+        }
+        debug!("Sent private message to user {}", principal);
+        Ok(())
+    }
+
     /// 发送群聊消息
+    ///
+    /// 除实时广播外，还会投递到每个成员（发送者除外）的私聊信箱，只要是群成员就能收到，
+    /// 不依赖是否已订阅群聊。已订阅的成员会从两条路径各收到一份，由 MessageReceiver
+    /// 按消息ID去重，保证只处理一次；两条路径之间的先后顺序不做保证，MessageReceiver
+    /// 按消息的群内序号（[`Message::group_seq`]）发现缺口并从存储补齐。
+    /// 成员信箱已满时不等待，跳过该成员的信箱投递并计入 `dropped_group_deliveries`。
     async fn send_group(&self, mut message: Message, group_id: &str) -> Result<()> {
-        // 如果是群聊消息，自动检测内容中的@提及
-        if let MessageTarget::Group(_) = &message.to {
-            if let Some(group) = self.get_group(group_id).await {
-                message = self.extract_mentions_from_content(message, &group);
-            }
-        }
+        let Some(group) = self.get_group(group_id).await else {
+            warn!("Group not found: {}", group_id);
+            return Err(anyhow::anyhow!("Group not found: {}", group_id));
+        };
 
+        // 自动检测内容中的@提及
+        message = self.extract_mentions_from_content(message, &group);
+
+        // 实时广播，没有订阅者不算失败
         if let Some(tx) = self.group_txs.get(group_id) {
-            tx.send(message).context("Failed to send group message")?;
-            debug!("Sent group message to {}", group_id);
-            Ok(())
-        } else {
-            warn!("Group not found: {}", group_id);
-            Err(anyhow::anyhow!("Group not found: {}", group_id))
+            let _ = tx.send(message.clone());
+        }
+
+        // 投递到成员信箱
+        for member_id in group.members.iter().filter(|id| **id != message.from) {
+            if is_user_principal(member_id) {
+                let _ = self.deliver_to_user(message.clone(), member_id);
+                continue;
+            }
+            // 不等待信箱腾出空间，一个处理慢的成员不能拖住其他成员
+            let Some(tx) = self.private_txs.get(member_id).map(|tx| tx.clone()) else {
+                debug!("Group member {} is not registered, skipping mailbox delivery", member_id);
+                continue;
+            };
+            match tx.try_send(message.clone()) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    self.dropped_group_deliveries.fetch_add(1, Ordering::Relaxed);
+                    warn!("Mailbox of {} is full, group message {} not delivered", member_id, message.id);
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    warn!("Mailbox of {} is closed, group message {} not delivered", member_id, message.id);
+                }
+            }
         }
+
+        debug!("Sent group message to {}", group_id);
+        Ok(())
     }
 
     /// 从消息内容中提取@提及
@@ -185,23 +870,380 @@ impl MessageBus {
         mentioned_name.contains(agent_id)
     }
 
+    /// 添加消息回应，重复回应不会再次广播，返回是否新增
+    pub async fn add_reaction(&self, reaction: MessageReaction) -> Result<bool> {
+        let added = match &self.store {
+            Some(store) => store.add_reaction(&reaction).await?,
+            None => true,
+        };
+        if added {
+            let _ = self.reaction_tx.send(ReactionEvent::Added(reaction));
+        }
+        Ok(added)
+    }
+
+    /// 移除消息回应，返回是否存在
+    pub async fn remove_reaction(&self, reaction: MessageReaction) -> Result<bool> {
+        let removed = match &self.store {
+            Some(store) => {
+                store
+                    .remove_reaction(&reaction.message_id, &reaction.reactor_id, &reaction.emoji)
+                    .await?
+            }
+            None => true,
+        };
+        if removed {
+            let _ = self.reaction_tx.send(ReactionEvent::Removed(reaction));
+        }
+        Ok(removed)
+    }
+
+    /// 置顶消息，会话内所有参与者可见；已置顶时保留原记录
+    ///
+    /// 置顶者须是群成员或私聊的一方
+    pub async fn pin_message(&self, message_id: &str, actor: &str) -> Result<MessagePin> {
+        let store = self.store.as_ref().context("Pinning messages requires a store")?;
+        let message = store
+            .load_message(message_id)
+            .await?
+            .ok_or_else(|| PinError::MessageNotFound { message_id: message_id.to_string() })?;
+        let session_id = message.session_id();
+        self.check_participant(&session_id, actor).await?;
+
+        let pin = MessagePin::new(message_id, &session_id, actor);
+        if !store.add_pin(&pin).await? {
+            if let Some(existing) = store.load_pins(&session_id).await?.into_iter().find(|p| p.message_id == message_id) {
+                return Ok(existing);
+            }
+        }
+        debug!("{} pinned message {} in {}", actor, message_id, session_id);
+        Ok(pin)
+    }
+
+    /// 取消置顶，返回是否存在；他人置顶的消息只有群管理员能取消
+    pub async fn unpin_message(&self, message_id: &str, actor: &str) -> Result<bool> {
+        let store = self.store.as_ref().context("Pinning messages requires a store")?;
+        let message = store
+            .load_message(message_id)
+            .await?
+            .ok_or_else(|| PinError::MessageNotFound { message_id: message_id.to_string() })?;
+        let session_id = message.session_id();
+        let Some(pin) = store.load_pins(&session_id).await?.into_iter().find(|p| p.message_id == message_id) else {
+            return Ok(false);
+        };
+        if pin.pinned_by != actor {
+            let is_admin = match message.target_group() {
+                Some(group_id) => self.get_group(group_id).await.is_some_and(|g| g.is_admin(actor)),
+                None => false,
+            };
+            if !is_admin {
+                return Err(PinError::NotAdmin { session_id, actor: actor.to_string(), pinned_by: pin.pinned_by }.into());
+            }
+            info!(target: "audit", "{} unpinned message {} pinned by {} in {}", actor, message_id, pin.pinned_by, session_id);
+        }
+        store.remove_pin(&session_id, message_id).await
+    }
+
+    /// 会话的置顶消息（按置顶时间升序），`viewer` 须是会话参与者
+    ///
+    /// `session` 为群ID，或私聊对方的ID（即与 `viewer` 的私聊）
+    pub async fn pinned_messages(&self, session: &str, viewer: &str) -> Result<Vec<PinnedMessage>> {
+        let session_id = self.resolve_session(session, viewer).await;
+        self.check_participant(&session_id, viewer).await?;
+        let Some(store) = &self.store else {
+            return Ok(Vec::new());
+        };
+        let mut pinned = Vec::new();
+        for pin in store.load_pins(&session_id).await? {
+            // 消息被清理后置顶记录随之失效
+            if let Some(message) = store.load_message(&pin.message_id).await? {
+                pinned.push(PinnedMessage { pin, message });
+            }
+        }
+        Ok(pinned)
+    }
+
+    /// 群ID原样返回，其他视为私聊对方
+    async fn resolve_session(&self, session: &str, viewer: &str) -> String {
+        if self.groups.read().await.contains_key(session) {
+            session.to_string()
+        } else {
+            direct_session_id(viewer, session)
+        }
+    }
+
+    async fn check_participant(&self, session_id: &str, principal: &str) -> Result<()> {
+        let allowed = match self.get_group(session_id).await {
+            Some(group) => group.has_member(principal),
+            None => session_id
+                .split_once('|')
+                .is_some_and(|(a, b)| a == principal || b == principal),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(PinError::NotParticipant { session_id: session_id.to_string(), actor: principal.to_string() }.into())
+        }
+    }
+
+    /// 订阅回应变化
+    pub fn subscribe_reactions(&self) -> broadcast::Receiver<ReactionEvent> {
+        self.reaction_tx.subscribe()
+    }
+
+    /// 回应广播发送端（供 Web 层共享）
+    pub fn reaction_sender(&self) -> broadcast::Sender<ReactionEvent> {
+        self.reaction_tx.clone()
+    }
+
     /// 订阅群聊消息
     pub fn subscribe_group(&self, group_id: &str) -> Option<broadcast::Receiver<Message>> {
         self.group_txs.get(group_id).map(|tx| tx.subscribe())
     }
 
+    /// 设置群管理员（仅管理员可操作）
+    pub async fn add_admin(&self, group_id: &str, actor: &str, member: &str) -> Result<Group> {
+        self.moderate(group_id, actor, member, false, |group| group.add_admin(member)).await
+    }
+
+    /// 把成员移出群聊（仅管理员可操作，不同于成员自行退出）
+    ///
+    /// 被移出的成员不再收到群聊消息，其接收器的实时订阅随之取消，并会收到一条系统通知。
+    /// 成员同步不会把被移出的成员重新加回。
+    pub async fn remove_member(&self, group_id: &str, actor: &str, member: &str) -> Result<Group> {
+        let group = self.moderate(group_id, actor, member, true, |group| group.kick(member)).await?;
+        let _ = self.removal_tx.send(GroupRemoval {
+            group_id: group_id.to_string(),
+            member: member.to_string(),
+        });
+        let notice = Message::private(
+            GROUP_EXPIRY_SENDER,
+            member,
+            self.catalog.format("group.removed_notice", &[("name", &group.name), ("actor", actor)]),
+        );
+        if let Err(e) = self.send(notice).await {
+            debug!("Failed to notify {} of removal from {}: {}", member, group_id, e);
+        }
+        info!(target: "audit", "{} removed {} from group {}", actor, member, group_id);
+        Ok(group)
+    }
+
+    /// 由系统把成员加入群聊（不检查管理员，如会话内的临时 Agent）并写入存储
+    pub async fn join_group(&self, group_id: &str, member: &str) -> Result<Group> {
+        let group = {
+            let mut groups = self.groups.write().await;
+            let group = groups.get_mut(group_id).ok_or_else(|| GroupModerationError::GroupNotFound {
+                group_id: group_id.to_string(),
+            })?;
+            group.add_member(member);
+            group.clone()
+        };
+        if let Some(store) = &self.store {
+            store.save_group(&group).await?;
+        }
+        Ok(group)
+    }
+
+    /// 邀请成员加入群聊（仅管理员可操作，可以邀请曾被移出的成员）并写入存储
+    ///
+    /// 设置了前情提要（[`MessageBus::with_join_context`]）且未选择冷启动时，新成员随即收到一条
+    /// 只有自己可见的前情提要；提要生成或发送失败只记录日志，不影响加入。
+    pub async fn invite_member(&self, group_id: &str, actor: &str, member: &str, options: InviteOptions) -> Result<Group> {
+        let group = {
+            let mut groups = self.groups.write().await;
+            let group = groups.get_mut(group_id).ok_or_else(|| GroupModerationError::GroupNotFound {
+                group_id: group_id.to_string(),
+            })?;
+            if !group.is_admin(actor) {
+                return Err(GroupModerationError::NotAdmin {
+                    group_id: group_id.to_string(),
+                    actor: actor.to_string(),
+                }
+                .into());
+            }
+            if group.has_member(member) {
+                return Err(GroupModerationError::AlreadyMember {
+                    group_id: group_id.to_string(),
+                    member: member.to_string(),
+                }
+                .into());
+            }
+            group.add_member(member);
+            group.clone()
+        };
+        if let Some(store) = &self.store {
+            store.save_group(&group).await?;
+        }
+        info!(target: "audit", "{} invited {} to group {}", actor, member, group_id);
+
+        let join_context = self.join_context.as_ref().filter(|c| c.config().enabled && !options.cold_start);
+        if let Some(join_context) = join_context {
+            match join_context.build(&group).await {
+                Ok(package) => {
+                    if let Err(e) = self.send(package.to_message(member, &self.catalog)).await {
+                        warn!("Failed to send join context of {} to {}: {}", group_id, member, e);
+                    }
+                }
+                Err(e) => warn!("Failed to build join context of {} for {}: {}", group_id, member, e),
+            }
+        }
+        Ok(group)
+    }
+
+    /// 由系统把成员移出群聊（不同于管理员移出，之后可以重新加入），接收器的实时订阅随之取消
+    pub async fn leave_group(&self, group_id: &str, member: &str) -> Result<Group> {
+        let group = {
+            let mut groups = self.groups.write().await;
+            let group = groups.get_mut(group_id).ok_or_else(|| GroupModerationError::GroupNotFound {
+                group_id: group_id.to_string(),
+            })?;
+            group.remove_member(member);
+            group.clone()
+        };
+        if let Some(store) = &self.store {
+            store.save_group(&group).await?;
+        }
+        let _ = self.removal_tx.send(GroupRemoval {
+            group_id: group_id.to_string(),
+            member: member.to_string(),
+        });
+        Ok(group)
+    }
+
+    /// 禁言成员到 `until`（秒级时间戳，仅管理员可操作），到期后自动解除
+    pub async fn mute_member(&self, group_id: &str, actor: &str, member: &str, until: i64) -> Result<Group> {
+        let now = self.clock.now();
+        let group = self.moderate(group_id, actor, member, true, |group| group.mute(member, until, now)).await?;
+        info!(target: "audit", "{} muted {} in group {} until {}", actor, member, group_id, until);
+        Ok(group)
+    }
+
+    /// 开启或关闭群聊的轮流发言（仅管理员可操作），`None` 表示关闭
+    pub async fn set_turn_taking(&self, group_id: &str, actor: &str, settings: Option<TurnTakingSettings>) -> Result<Group> {
+        let enabled = settings.is_some();
+        let group = self.moderate(group_id, actor, actor, false, |group| group.turn_taking = settings).await?;
+        info!(target: "audit", "{} set turn-taking of group {} to {}", actor, group_id, enabled);
+        Ok(group)
+    }
+
+    /// 设置或取消群聊的成员同步（仅管理员可操作），`None` 表示取消
+    pub async fn set_group_sync(&self, group_id: &str, actor: &str, sync: Option<GroupSync>) -> Result<Group> {
+        let enabled = sync.is_some();
+        let group = self.moderate(group_id, actor, actor, false, |group| group.sync = sync).await?;
+        info!(target: "audit", "{} set membership sync of group {} to {}", actor, group_id, enabled);
+        Ok(group)
+    }
+
+    /// 设置了成员同步的群聊
+    pub async fn list_synced_groups(&self) -> Vec<Group> {
+        let groups = self.groups.read().await;
+        groups.values().filter(|g| g.sync.is_some()).cloned().collect()
+    }
+
+    /// 按同步来源的当前成员调整群成员并写入存储，离开的成员的接收器随之退订
+    pub async fn sync_group_members(&self, group_id: &str, source_members: &[String]) -> Result<GroupSyncReport> {
+        let (group, report, changed) = {
+            let mut groups = self.groups.write().await;
+            let group = groups.get_mut(group_id).ok_or_else(|| GroupModerationError::GroupNotFound {
+                group_id: group_id.to_string(),
+            })?;
+            let before = group.sync.clone();
+            let report = group.apply_sync(source_members);
+            let changed = report.changed() || group.sync != before;
+            (group.clone(), report, changed)
+        };
+        if changed {
+            if let Some(store) = &self.store {
+                store.save_group(&group).await?;
+            }
+        }
+        for member in &report.left {
+            let _ = self.removal_tx.send(GroupRemoval {
+                group_id: group_id.to_string(),
+                member: member.clone(),
+            });
+        }
+        Ok(report)
+    }
+
+    /// 订阅成员被移出群聊的通知
+    pub fn subscribe_removals(&self) -> broadcast::Receiver<GroupRemoval> {
+        self.removal_tx.subscribe()
+    }
+
+    /// 校验操作者是管理员、对象是成员后修改群聊并写入存储；`against_member` 时不能针对创建者
+    async fn moderate(
+        &self,
+        group_id: &str,
+        actor: &str,
+        member: &str,
+        against_member: bool,
+        apply: impl FnOnce(&mut Group),
+    ) -> Result<Group> {
+        let group = {
+            let mut groups = self.groups.write().await;
+            let group = groups.get_mut(group_id).ok_or_else(|| GroupModerationError::GroupNotFound {
+                group_id: group_id.to_string(),
+            })?;
+            if !group.is_admin(actor) {
+                return Err(GroupModerationError::NotAdmin {
+                    group_id: group_id.to_string(),
+                    actor: actor.to_string(),
+                }
+                .into());
+            }
+            if !group.has_member(member) {
+                return Err(GroupModerationError::NotMember {
+                    group_id: group_id.to_string(),
+                    member: member.to_string(),
+                }
+                .into());
+            }
+            if against_member && group.creator_id == member {
+                return Err(GroupModerationError::Creator {
+                    group_id: group_id.to_string(),
+                    member: member.to_string(),
+                }
+                .into());
+            }
+            apply(group);
+            group.clone()
+        };
+        if let Some(store) = &self.store {
+            store.save_group(&group).await?;
+        }
+        Ok(group)
+    }
+
     /// 获取群组信息
     pub async fn get_group(&self, group_id: &str) -> Option<Group> {
         let groups = self.groups.read().await;
         groups.get(group_id).cloned()
     }
 
-    /// 列出Agent所在的所有群组
+    /// 列出所有群组（按创建时间），`include_ephemeral` 为 true 时包含临时群聊
+    pub async fn list_groups(&self, include_ephemeral: bool) -> Vec<Group> {
+        let groups = self.groups.read().await;
+        let mut listed: Vec<Group> = groups
+            .values()
+            .filter(|g| include_ephemeral || !g.ephemeral)
+            .cloned()
+            .collect();
+        listed.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        listed
+    }
+
+    /// 列出Agent所在的所有群组（不含临时群聊）
     pub async fn list_agent_groups(&self, agent_id: &str) -> Vec<Group> {
+        self.list_agent_groups_with(agent_id, false).await
+    }
+
+    /// 列出Agent所在的群组，`include_ephemeral` 为 true 时包含临时群聊
+    pub async fn list_agent_groups_with(&self, agent_id: &str, include_ephemeral: bool) -> Vec<Group> {
         let groups = self.groups.read().await;
         groups
             .values()
-            .filter(|g| g.has_member(agent_id))
+            .filter(|g| g.has_member(agent_id) && (include_ephemeral || !g.ephemeral))
             .cloned()
             .collect()
     }
@@ -243,13 +1285,202 @@ impl Default for MessageBus {
     }
 }
 
-/// 消息接收器
+/// 接收端去重窗口大小
+const DEDUP_WINDOW: usize = 1024;
+
+/// 最近处理过的群聊消息ID
+#[derive(Default)]
+struct SeenMessages {
+    order: std::collections::VecDeque<String>,
+    ids: std::collections::HashSet<String>,
+}
+
+impl SeenMessages {
+    /// 记录消息ID，已存在时返回 false
+    fn insert(&mut self, id: &str) -> bool {
+        if !self.ids.insert(id.to_string()) {
+            return false;
+        }
+        self.order.push_back(id.to_string());
+        while self.order.len() > DEDUP_WINDOW {
+            if let Some(old) = self.order.pop_front() {
+                self.ids.remove(&old);
+            }
+        }
+        true
+    }
+}
+
+/// Agent 的待处理消息队列
 ///
-/// 聚合私聊和群聊消息源
+/// 按优先级从高到低出队，同一优先级保持到达顺序
+#[derive(Debug, Default)]
+pub struct PriorityInbox {
+    heap: BinaryHeap<InboxEntry>,
+    next_seq: u64,
+}
+
+#[derive(Debug)]
+struct InboxEntry {
+    priority: MessagePriority,
+    seq: u64,
+    message: Message,
+}
+
+impl PartialEq for InboxEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl Eq for InboxEntry {}
+
+impl PartialOrd for InboxEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InboxEntry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        // 大顶堆：优先级高的在前，同优先级先到的在前
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PriorityInbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, message: Message) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.heap.push(InboxEntry {
+            priority: message.priority,
+            seq,
+            message,
+        });
+    }
+
+    /// 把接收器中已到达的消息全部收入队列，返回收入条数
+    pub fn fill(&mut self, rx: &mut MessageReceiver) -> usize {
+        let mut count = 0;
+        while let Some(message) = rx.try_recv() {
+            self.push(message);
+            count += 1;
+        }
+        count
+    }
+
+    /// 队列中是否有 High 及以上的消息
+    pub fn has_elevated(&self) -> bool {
+        self.heap.peek().is_some_and(|e| e.priority.is_elevated())
+    }
+
+    /// 队列中是否有 Urgent 消息
+    pub fn has_urgent(&self) -> bool {
+        self.heap.peek().is_some_and(|e| e.priority == MessagePriority::Urgent)
+    }
+
+    /// 遍历队列中的消息（不按处理顺序，不出队）
+    pub fn iter(&self) -> impl Iterator<Item = &Message> {
+        self.heap.iter().map(|e| &e.message)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// 按处理顺序取出全部消息
+    pub fn drain(&mut self) -> Vec<Message> {
+        let mut messages = Vec::with_capacity(self.heap.len());
+        while let Some(entry) = self.heap.pop() {
+            messages.push(entry.message);
+        }
+        messages
+    }
+}
+
+/// 群聊序号检查结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceCheck {
+    /// 该群聊的第一条，或紧接已见过的最大序号
+    InOrder,
+    /// 不大于已见过的最大序号：迟到或重复
+    Stale,
+    /// 与上一条之间缺了 `from..=to`
+    Gap { from: u64, to: u64 },
+}
+
+/// 接收端记录每个群聊已见过的最大序号，用于发现缺口
+#[derive(Debug, Default)]
+pub struct GroupSequenceTracker {
+    last: HashMap<String, u64>,
+}
+
+impl GroupSequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录收到的序号；第一次见到的群聊不算缺口（可能是中途加入的）
+    pub fn observe(&mut self, group_id: &str, seq: u64) -> SequenceCheck {
+        let Some(last) = self.last.get_mut(group_id) else {
+            self.last.insert(group_id.to_string(), seq);
+            return SequenceCheck::InOrder;
+        };
+        if seq <= *last {
+            return SequenceCheck::Stale;
+        }
+        let check = if seq == *last + 1 {
+            SequenceCheck::InOrder
+        } else {
+            SequenceCheck::Gap { from: *last + 1, to: seq - 1 }
+        };
+        *last = seq;
+        check
+    }
+
+    /// 已见过的最大序号
+    pub fn last_seen(&self, group_id: &str) -> Option<u64> {
+        self.last.get(group_id).copied()
+    }
+}
+
+/// 等待从存储补齐的缺口，期间该群聊后到的消息先暂存
+struct PendingGap {
+    group_id: String,
+    from: u64,
+    to: u64,
+    held: Vec<Message>,
+}
+
+/// Agent 消息接收器
+///
+/// 合并私聊信箱与已订阅的群聊广播，跳过自己发出的群聊消息，
+/// 同一条群聊消息经信箱和广播各到达一次时只返回一次。
+/// 设置了存储时，群聊序号出现缺口（收到 41 之后收到 43）会暂存后到的消息，
+/// 由 [`MessageReceiver::fill_gaps`]（`recv` 会自动调用）从存储取回缺失的消息后按序号返回
 pub struct MessageReceiver {
     agent_id: String,
     private_rx: mpsc::Receiver<Message>,
     group_rxs: Vec<(String, broadcast::Receiver<Message>)>,
+    /// 被管理员移出群聊的通知，加入第一个群聊时订阅
+    removals: Option<broadcast::Receiver<GroupRemoval>>,
+    seen: SeenMessages,
+    sequences: GroupSequenceTracker,
+    /// 补齐缺口用的存储
+    store: Option<Arc<dyn crate::core::store::Store>>,
+    gaps: Vec<PendingGap>,
+    /// 已补齐、等待返回的消息
+    ready: VecDeque<Message>,
 }
 
 impl MessageReceiver {
@@ -259,13 +1490,26 @@ impl MessageReceiver {
             agent_id,
             private_rx,
             group_rxs: Vec::new(),
+            removals: None,
+            seen: SeenMessages::default(),
+            sequences: GroupSequenceTracker::new(),
+            store: None,
+            gaps: Vec::new(),
+            ready: VecDeque::new(),
         }
     }
 
+    /// 群聊序号出现缺口时从该存储补齐
+    pub fn with_store(mut self, store: Arc<dyn crate::core::store::Store>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
     /// 加入群聊（订阅群聊消息）
     pub fn join_group(&mut self, group_id: &str, bus: &MessageBus) -> Result<()> {
         if let Some(rx) = bus.subscribe_group(group_id) {
             self.group_rxs.push((group_id.to_string(), rx));
+            self.removals.get_or_insert_with(|| bus.subscribe_removals());
             Ok(())
         } else {
             Err(anyhow::anyhow!("Group not found: {}", group_id))
@@ -277,40 +1521,177 @@ impl MessageReceiver {
         self.group_rxs.retain(|(id, _)| id != group_id);
     }
 
+    /// 已订阅的群聊 ID
+    pub fn joined_groups(&mut self) -> Vec<String> {
+        self.apply_removals();
+        self.group_rxs.iter().map(|(id, _)| id.clone()).collect()
+    }
+
+    /// 退订已被管理员移出的群聊
+    fn apply_removals(&mut self) {
+        let Some(removals) = &mut self.removals else {
+            return;
+        };
+        let mut removed = Vec::new();
+        loop {
+            match removals.try_recv() {
+                Ok(removal) if removal.member == self.agent_id => removed.push(removal.group_id),
+                Ok(_) | Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+        for group_id in removed {
+            debug!("{} was removed from group {}, unsubscribing", self.agent_id, group_id);
+            self.leave_group(&group_id);
+        }
+    }
+
     /// 接收下一条消息（阻塞）
     pub async fn recv(&mut self) -> Option<Message> {
-        // 优先检查私聊
-        if let Ok(msg) = self.private_rx.try_recv() {
-            return Some(msg);
-        }
+        loop {
+            self.fill_gaps().await;
+            if let Some(msg) = self.try_recv() {
+                return Some(msg);
+            }
+            if !self.gaps.is_empty() {
+                continue;
+            }
 
-        // 检查群聊
-        for (_group_id, rx) in &mut self.group_rxs {
-            if let Ok(msg) = rx.try_recv() {
-                if msg.from != self.agent_id {
-                    return Some(msg);
-                }
+            // 等待私聊信箱（群聊消息也会投递到这里）
+            let msg = self.private_rx.recv().await?;
+            if let Some(msg) = self.admit(msg) {
+                return Some(msg);
             }
         }
-
-        // 等待私聊
-        self.private_rx.recv().await
     }
 
-    /// 尝试接收消息（非阻塞）
+    /// 尝试接收消息（非阻塞），不补齐缺口
     pub fn try_recv(&mut self) -> Option<Message> {
-        if let Ok(msg) = self.private_rx.try_recv() {
+        if let Some(msg) = self.ready.pop_front() {
             return Some(msg);
         }
+        self.apply_removals();
+        while let Ok(msg) = self.private_rx.try_recv() {
+            if let Some(msg) = self.admit(msg) {
+                return Some(msg);
+            }
+        }
 
-        for (_group_id, rx) in &mut self.group_rxs {
-            if let Ok(msg) = rx.try_recv() {
-                if msg.from != self.agent_id {
-                    return Some(msg);
+        for index in 0..self.group_rxs.len() {
+            loop {
+                match self.group_rxs[index].1.try_recv() {
+                    Ok(msg) => {
+                        if let Some(msg) = self.admit(msg) {
+                            return Some(msg);
+                        }
+                    }
+                    Err(broadcast::error::TryRecvError::Lagged(_)) => continue,
+                    Err(_) => break,
                 }
             }
         }
 
         None
     }
+
+    /// 是否有等待补齐的群聊缺口
+    pub fn has_gaps(&self) -> bool {
+        !self.gaps.is_empty()
+    }
+
+    /// 从存储取回缺口中的消息，连同暂存的后到消息按序号排好等待返回；返回取回的条数
+    ///
+    /// 可以安全地被取消：每个缺口在存储读取完成后才移除
+    pub async fn fill_gaps(&mut self) -> usize {
+        let mut filled = 0;
+        while let Some(gap) = self.gaps.first() {
+            let (group_id, from, to) = (gap.group_id.clone(), gap.from, gap.to);
+            let fetched = match &self.store {
+                Some(store) => store.load_group_messages_by_seq(&group_id, from, to).await.unwrap_or_else(|e| {
+                    warn!("Failed to load messages {}..={} of group {}: {}", from, to, group_id, e);
+                    Vec::new()
+                }),
+                None => Vec::new(),
+            };
+            let gap = self.gaps.remove(0);
+            let mut messages: Vec<Message> = fetched
+                .into_iter()
+                .filter(|m| accept(&self.agent_id, &mut self.seen, m))
+                .collect();
+            debug!(
+                "{} filled {} of messages {}..={} in group {}",
+                self.agent_id,
+                messages.len(),
+                from,
+                to,
+                group_id
+            );
+            filled += messages.len();
+            messages.extend(gap.held);
+            messages.sort_by_key(|m| m.group_seq());
+            self.ready.extend(messages);
+        }
+        filled
+    }
+
+    /// 去重后检查群聊序号：有缺口且能补齐时暂存，返回 None
+    fn admit(&mut self, msg: Message) -> Option<Message> {
+        if !accept(&self.agent_id, &mut self.seen, &msg) {
+            return None;
+        }
+        let (Some(group_id), Some(seq)) = (msg.target_group(), msg.group_seq()) else {
+            return Some(msg);
+        };
+        if let Some(gap) = self.gaps.iter_mut().find(|gap| gap.group_id == group_id) {
+            gap.held.push(msg);
+            return None;
+        }
+        match self.sequences.observe(group_id, seq) {
+            SequenceCheck::Gap { from, to } if self.store.is_some() => {
+                debug!("{} missed messages {}..={} in group {}", self.agent_id, from, to, group_id);
+                self.gaps.push(PendingGap {
+                    group_id: group_id.to_string(),
+                    from,
+                    to,
+                    held: vec![msg],
+                });
+                None
+            }
+            SequenceCheck::Gap { from, to } => {
+                warn!(
+                    "{} missed messages {}..={} in group {} and has no store to fill them",
+                    self.agent_id, from, to, group_id
+                );
+                Some(msg)
+            }
+            SequenceCheck::InOrder | SequenceCheck::Stale => Some(msg),
+        }
+    }
+}
+
+/// 按群内序号整理按时间排好的消息：每个群聊带序号的消息保持所占的位置，彼此之间按序号排列
+///
+/// 时间戳只精确到秒，同一秒内的消息或不同节点时钟有偏差时，时间顺序与发送顺序不一定一致
+pub fn order_by_group_seq(messages: &mut [Message]) {
+    let mut slots: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, message) in messages.iter().enumerate() {
+        if let (Some(group_id), Some(_)) = (message.target_group(), message.group_seq()) {
+            slots.entry(group_id.to_string()).or_default().push(index);
+        }
+    }
+    for indices in slots.values().filter(|indices| indices.len() > 1) {
+        let mut ordered: Vec<Message> = indices.iter().map(|&index| messages[index].clone()).collect();
+        ordered.sort_by_key(|m| m.group_seq());
+        for (&index, message) in indices.iter().zip(ordered) {
+            messages[index] = message;
+        }
+    }
+}
+
+/// 是否应交给 Agent 处理：跳过自己发的群聊消息和重复到达的群聊消息
+fn accept(agent_id: &str, seen: &mut SeenMessages, msg: &Message) -> bool {
+    match msg.to {
+        MessageTarget::Group(_) => msg.from != agent_id && seen.insert(&msg.id),
+        MessageTarget::Direct(_) => true,
+    }
 }
@@ -0,0 +1,473 @@
+//! 输出内容审核
+//!
+//! 面向外部用户的公司需要在 Agent 的输出送达前拦住有害或违反政策的内容。Agent 每轮发出的消息
+//! 在发送前交给 [`Moderator`] 判定：
+//!
+//! - 放行（allow）：原样发送
+//! - 改写（rewrite）：发送去掉敏感内容后的版本
+//! - 拦截（block）：发送一条模板提示代替原文，原文保存到隔离表（[`QuarantinedOutput`]），
+//!   管理员通过 `/admin/moderation/quarantine` 复核；同一 Agent 短时间内多次被拦截时熔断并告警
+//!
+//! 配置了评审模型（`judge`）时由 LLM 按策略说明判定，未配置或评审失败时使用关键词规则。
+//! 审核会增加发送延迟，因此可以按 Agent 关闭，Agent 之间的内部消息默认不审核。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::i18n::MessageCatalog;
+use crate::core::messaging::MessageBus;
+use crate::core::store::Store;
+use crate::core::template::TemplateSandbox;
+use crate::domain::moderation::QuarantinedOutput;
+use crate::domain::user::is_user_principal;
+use crate::domain::{LLMConfig, Message, MessageTarget};
+use crate::infrastructure::llm::OpenAIClient;
+
+/// 标记审核结果的消息元数据键（`blocked` 或 `rewritten`）
+pub const MODERATION_KEY: &str = "moderation";
+
+/// 改写时替换敏感词的文本
+pub const REDACTION: &str = "[redacted]";
+
+/// 内容审核配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModerationConfig {
+    /// 是否审核 Agent 的输出（默认关闭）
+    pub enabled: bool,
+    /// 交给评审模型的策略说明
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub policy: String,
+    /// 评审模型，应使用便宜的小模型；未设置时只使用关键词规则
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge: Option<LLMConfig>,
+    /// 出现即拦截的关键词（不区分大小写）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub blocked_keywords: Vec<String>,
+    /// 替换为 [`REDACTION`] 后放行的关键词（不区分大小写）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub redacted_keywords: Vec<String>,
+    /// 拦截提示的模板，`{{agent}}` 替换为 Agent 名称（旧写法 `{agent}` 仍然可用）；未设置或渲染失败时使用内置提示
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notice: Option<String>,
+    /// 是否也审核 Agent 之间的内部消息
+    pub moderate_internal: bool,
+    /// 按 Agent ID 开关审核，未列出的 Agent 跟随 `enabled`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub agents: HashMap<String, bool>,
+}
+
+impl ModerationConfig {
+    /// 启用审核
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// 设置策略说明
+    pub fn with_policy(mut self, policy: impl Into<String>) -> Self {
+        self.policy = policy.into();
+        self
+    }
+
+    /// 设置评审模型
+    pub fn with_judge(mut self, judge: LLMConfig) -> Self {
+        self.judge = Some(judge);
+        self
+    }
+
+    /// 设置拦截和改写的关键词
+    pub fn with_keywords(mut self, blocked: Vec<String>, redacted: Vec<String>) -> Self {
+        self.blocked_keywords = blocked;
+        self.redacted_keywords = redacted;
+        self
+    }
+
+    /// 设置拦截提示模板
+    pub fn with_notice(mut self, notice: impl Into<String>) -> Self {
+        self.notice = Some(notice.into());
+        self
+    }
+
+    /// 设置是否审核内部消息
+    pub fn with_internal(mut self, moderate_internal: bool) -> Self {
+        self.moderate_internal = moderate_internal;
+        self
+    }
+
+    /// 为某个 Agent 单独开关审核
+    pub fn with_agent(mut self, agent_id: impl Into<String>, enabled: bool) -> Self {
+        self.agents.insert(agent_id.into(), enabled);
+        self
+    }
+
+    /// Agent 的输出是否需要审核
+    pub fn applies_to(&self, agent_id: &str) -> bool {
+        self.agents.get(agent_id).copied().unwrap_or(self.enabled)
+    }
+
+    /// 是否有 Agent 需要审核
+    pub fn is_active(&self) -> bool {
+        self.enabled || self.agents.values().any(|enabled| *enabled)
+    }
+}
+
+/// 审核结论
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum ModerationDecision {
+    /// 原样发送
+    Allow,
+    /// 拦截，以提示代替
+    Block { reason: String },
+    /// 发送改写后的内容
+    Rewrite { content: String, reason: String },
+}
+
+impl ModerationDecision {
+    /// 名称（用于日志和元数据）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ModerationDecision::Allow => "allow",
+            ModerationDecision::Block { .. } => "block",
+            ModerationDecision::Rewrite { .. } => "rewrite",
+        }
+    }
+
+    /// 解析评审模型的回复：回复中第一个 JSON 对象，形如
+    /// `{"decision": "rewrite", "reason": "...", "content": "..."}`
+    pub fn parse_judgement(reply: &str) -> Result<Self> {
+        let start = reply.find('{').context("judge reply contains no JSON object")?;
+        let end = reply.rfind('}').filter(|end| *end > start).context("judge reply contains no JSON object")?;
+        let judgement: Judgement = serde_json::from_str(&reply[start..=end]).context("invalid judge reply")?;
+        match judgement.decision.to_ascii_lowercase().as_str() {
+            "allow" => Ok(ModerationDecision::Allow),
+            "block" => Ok(ModerationDecision::Block {
+                reason: judgement.reason,
+            }),
+            "rewrite" => {
+                let content = judgement.content.filter(|c| !c.trim().is_empty()).context("rewrite without content")?;
+                Ok(ModerationDecision::Rewrite {
+                    content,
+                    reason: judgement.reason,
+                })
+            }
+            other => anyhow::bail!("unknown judge decision {:?}", other),
+        }
+    }
+}
+
+/// 评审模型的回复格式
+#[derive(Deserialize)]
+struct Judgement {
+    decision: String,
+    #[serde(default)]
+    reason: String,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+/// 内容审核器
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    /// 名称，记录在隔离表中
+    fn name(&self) -> &str;
+
+    /// 按策略说明判定一条输出
+    async fn moderate(&self, content: &str, policy: &str) -> Result<ModerationDecision>;
+}
+
+/// 关键词规则：出现拦截词时拦截，否则把改写词替换为 [`REDACTION`]
+#[derive(Debug, Clone, Default)]
+pub struct KeywordModerator {
+    blocked: Vec<String>,
+    redacted: Vec<String>,
+}
+
+impl KeywordModerator {
+    pub fn new(blocked: Vec<String>, redacted: Vec<String>) -> Self {
+        let clean = |words: Vec<String>| words.into_iter().filter(|w| !w.trim().is_empty()).collect();
+        Self {
+            blocked: clean(blocked),
+            redacted: clean(redacted),
+        }
+    }
+
+    /// 按配置中的关键词创建
+    pub fn from_config(config: &ModerationConfig) -> Self {
+        Self::new(config.blocked_keywords.clone(), config.redacted_keywords.clone())
+    }
+
+    /// 同步判定
+    pub fn check(&self, content: &str) -> ModerationDecision {
+        if let Some(word) = self.blocked.iter().find(|w| find_ignore_case(content, w, 0).is_some()) {
+            return ModerationDecision::Block {
+                reason: format!("blocked keyword {:?}", word),
+            };
+        }
+
+        let mut redacted = content.to_string();
+        let mut hits = Vec::new();
+        for word in &self.redacted {
+            let mut from = 0;
+            while let Some(at) = find_ignore_case(&redacted, word, from) {
+                redacted.replace_range(at..at + word.len(), REDACTION);
+                from = at + REDACTION.len();
+                if !hits.contains(word) {
+                    hits.push(word.clone());
+                }
+            }
+        }
+        if hits.is_empty() {
+            return ModerationDecision::Allow;
+        }
+        ModerationDecision::Rewrite {
+            content: redacted,
+            reason: format!("redacted {}", hits.iter().map(|w| format!("{:?}", w)).collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+#[async_trait]
+impl Moderator for KeywordModerator {
+    fn name(&self) -> &str {
+        "keyword"
+    }
+
+    async fn moderate(&self, content: &str, _policy: &str) -> Result<ModerationDecision> {
+        Ok(self.check(content))
+    }
+}
+
+/// 从 `from` 开始查找 `needle`（ASCII 不区分大小写），返回字节位置
+fn find_ignore_case(haystack: &str, needle: &str, from: usize) -> Option<usize> {
+    let (haystack, needle) = (haystack.as_bytes(), needle.as_bytes());
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+    (from..=haystack.len() - needle.len()).find(|&at| haystack[at..at + needle.len()].eq_ignore_ascii_case(needle))
+}
+
+/// 使用评审模型判定
+pub struct LlmJudgeModerator {
+    client: OpenAIClient,
+}
+
+impl LlmJudgeModerator {
+    pub fn new(config: &LLMConfig) -> Self {
+        Self {
+            client: OpenAIClient::for_config(config),
+        }
+    }
+}
+
+#[async_trait]
+impl Moderator for LlmJudgeModerator {
+    fn name(&self) -> &str {
+        "judge"
+    }
+
+    async fn moderate(&self, content: &str, policy: &str) -> Result<ModerationDecision> {
+        let prompt = format!(
+            "You review messages an AI assistant is about to send to external users.\n\
+             Policy:\n{}\n\n\
+             Reply with a single JSON object and nothing else:\n\
+             {{\"decision\": \"allow\"}} if the message complies,\n\
+             {{\"decision\": \"rewrite\", \"reason\": \"...\", \"content\": \"...\"}} if removing or redacting parts makes it comply \
+             (content is the full corrected message),\n\
+             {{\"decision\": \"block\", \"reason\": \"...\"}} otherwise.\n\n\
+             Message:\n{}",
+            if policy.trim().is_empty() { "Block harmful, abusive or illegal content and leaked secrets." } else { policy },
+            content
+        );
+        let reply = self.client.complete(&prompt).await?;
+        ModerationDecision::parse_judgement(&reply)
+    }
+}
+
+/// 一条输出的审核结果
+#[derive(Debug, Clone)]
+pub struct ModerationOutcome {
+    /// 实际发送的消息：原文、改写后的消息或拦截提示
+    pub message: Message,
+    /// 审核结论，未审核（关闭或内部消息）时为 None
+    pub decision: Option<ModerationDecision>,
+    /// 被拦截时保存的原文
+    pub quarantined: Option<QuarantinedOutput>,
+}
+
+impl ModerationOutcome {
+    fn skipped(message: Message) -> Self {
+        Self {
+            message,
+            decision: None,
+            quarantined: None,
+        }
+    }
+
+    /// 是否被拦截
+    pub fn is_blocked(&self) -> bool {
+        matches!(self.decision, Some(ModerationDecision::Block { .. }))
+    }
+}
+
+/// 公司级内容审核服务，所有 Agent 共享
+pub struct ModerationService {
+    config: ModerationConfig,
+    judge: Option<Arc<dyn Moderator>>,
+    fallback: KeywordModerator,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    catalog: MessageCatalog,
+}
+
+impl ModerationService {
+    /// 按配置创建；配置了评审模型时使用 [`LlmJudgeModerator`]
+    pub fn new(config: ModerationConfig, store: Arc<dyn Store>) -> Self {
+        let judge = config
+            .judge
+            .as_ref()
+            .map(|llm| Arc::new(LlmJudgeModerator::new(llm)) as Arc<dyn Moderator>);
+        Self {
+            fallback: KeywordModerator::from_config(&config),
+            config,
+            judge,
+            store,
+            clock: Arc::new(SystemClock),
+            catalog: MessageCatalog::default(),
+        }
+    }
+
+    /// 替换评审者（测试中使用脚本化的评审者）
+    pub fn with_judge(mut self, judge: Arc<dyn Moderator>) -> Self {
+        self.judge = Some(judge);
+        self
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 拦截提示使用的消息目录
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    pub fn config(&self) -> &ModerationConfig {
+        &self.config
+    }
+
+    /// 是否需要审核 `agent_id` 发出的这条消息；`internal` 表示收件方都是 Agent
+    pub fn applies(&self, agent_id: &str, internal: bool) -> bool {
+        self.config.applies_to(agent_id) && (!internal || self.config.moderate_internal)
+    }
+
+    /// 审核一条输出；评审失败时退回关键词规则，隔离表写入失败不影响拦截
+    pub async fn review(&self, agent_name: &str, message: Message, internal: bool) -> ModerationOutcome {
+        if !self.applies(&message.from, internal) {
+            return ModerationOutcome::skipped(message);
+        }
+
+        let (decision, moderator) = match &self.judge {
+            Some(judge) => match judge.moderate(&message.content, &self.config.policy).await {
+                Ok(decision) => (decision, judge.name().to_string()),
+                Err(e) => {
+                    warn!("Moderation judge failed for message {}, using keyword rules: {:#}", message.id, e);
+                    (self.fallback.check(&message.content), self.fallback.name().to_string())
+                }
+            },
+            None => (self.fallback.check(&message.content), self.fallback.name().to_string()),
+        };
+
+        match &decision {
+            ModerationDecision::Allow => ModerationOutcome {
+                message,
+                decision: Some(decision),
+                quarantined: None,
+            },
+            ModerationDecision::Rewrite { content, .. } => {
+                let mut rewritten = message;
+                rewritten.content = content.clone();
+                rewritten.metadata.insert(MODERATION_KEY.to_string(), "rewritten".to_string());
+                ModerationOutcome {
+                    message: rewritten,
+                    decision: Some(decision),
+                    quarantined: None,
+                }
+            }
+            ModerationDecision::Block { reason } => {
+                let mut notice = message.clone();
+                notice.content = self
+                    .config
+                    .notice
+                    .as_deref()
+                    .and_then(|template| render_notice(template, agent_name))
+                    .unwrap_or_else(|| self.catalog.format("moderation.blocked_notice", &[("agent", agent_name)]));
+                notice.mentions.clear();
+                notice.metadata.insert(MODERATION_KEY.to_string(), "blocked".to_string());
+
+                let quarantined = QuarantinedOutput::new(message, reason.clone(), moderator, self.clock.now());
+                if let Err(e) = self.store.save_quarantined_output(&quarantined).await {
+                    warn!("Failed to quarantine blocked output {}: {}", quarantined.message.id, e);
+                }
+                ModerationOutcome {
+                    message: notice,
+                    decision: Some(decision),
+                    quarantined: Some(quarantined),
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ModerationService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModerationService")
+            .field("config", &self.config)
+            .field("judge", &self.judge.as_ref().map(|judge| judge.name().to_string()))
+            .finish()
+    }
+}
+
+/// 消息是否只在 Agent 之间流转：私聊对象是 Agent，或群聊成员都是 Agent
+pub async fn is_internal_traffic(message: &Message, bus: &MessageBus) -> bool {
+    let is_agent = |id: &str| !is_user_principal(id) && bus.is_registered(id);
+    match &message.to {
+        MessageTarget::Direct(to) => is_agent(to),
+        MessageTarget::Group(group_id) => match bus.get_group(group_id).await {
+            Some(group) => group.members.iter().all(|member| is_agent(member)),
+            None => false,
+        },
+    }
+}
+
+/// 渲染配置的拦截提示，只能引用 `agent`；模板不合法时记录警告并返回 None
+fn render_notice(template: &str, agent_name: &str) -> Option<String> {
+    // 兼容旧写法 `{agent}`
+    let template = if template.contains("{{") {
+        template.to_string()
+    } else {
+        template.replace("{agent}", "{{agent}}")
+    };
+    match TemplateSandbox::new()
+        .with_variables(&["agent"])
+        .render(&template, &serde_json::json!({ "agent": agent_name }))
+    {
+        Ok(notice) => Some(notice),
+        Err(e) => {
+            warn!("Moderation notice template failed: {}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! 插话：Agent 连续调用工具时中途追加指令
+//!
+//! Agent 的一轮以工具调用结束时进入工具循环，下一轮带着工具结果继续。工具循环期间发给它的插话
+//! 放进该 Agent 的插话槽，下一轮 LLM 请求前按到达顺序作为 user 消息附在提示词之后，并以
+//! [`CompanyEvent::NudgeInjected`](crate::core::events::CompanyEvent::NudgeInjected) 记入本轮的追踪。
+//! 工具循环期间收到的 Urgent 消息走同一条路径。
+//!
+//! `cancel` 插话结束剩余的工具调用：下一轮不再提供工具，Agent 只能用已有的工具结果收尾。
+//! 不在工具循环中时插话无处可放，调用方改为发送一条 Urgent 私聊消息；工具循环结束时还没取出的
+//! 插话同样转为 Urgent 消息，不会丢失。
+
+use std::collections::VecDeque;
+
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::domain::{Message, MessagePriority};
+
+/// 插话转成的消息上带的元数据键，值为插话 ID
+pub const NUDGE_KEY: &str = "nudge";
+
+/// 一条插话
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Nudge {
+    pub id: String,
+    /// 发出者（用户主体或 Agent ID）
+    pub from: String,
+    pub content: String,
+    /// 结束剩余的工具调用
+    pub cancel: bool,
+    /// 来自工具循环期间收到的 Urgent 消息时为消息 ID
+    pub message_id: Option<String>,
+}
+
+impl Nudge {
+    pub fn new(from: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            from: from.into(),
+            content: content.into(),
+            cancel: false,
+            message_id: None,
+        }
+    }
+
+    /// 结束剩余的工具调用
+    pub fn with_cancel(mut self, cancel: bool) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// 工具循环期间收到的 Urgent 消息
+    pub fn from_message(message: &Message) -> Self {
+        Self {
+            id: message.metadata(NUDGE_KEY).map(str::to_string).unwrap_or_else(|| message.id.clone()),
+            from: message.from.clone(),
+            content: message.content.clone(),
+            cancel: false,
+            message_id: Some(message.id.clone()),
+        }
+    }
+
+    /// 没有进行中的工具循环时改发的 Urgent 私聊消息
+    pub fn into_message(self, agent_id: &str) -> Message {
+        Message::private(self.from, agent_id, self.content)
+            .with_priority(MessagePriority::Urgent)
+            .with_metadata(NUDGE_KEY, self.id)
+    }
+
+    /// 附在提示词之后的 user 消息
+    pub fn prompt(&self) -> String {
+        let mut prompt = format!("[Nudge from {}]", self.from);
+        if !self.content.is_empty() {
+            prompt.push(' ');
+            prompt.push_str(&self.content);
+        }
+        if self.cancel {
+            prompt.push_str(" Stop calling tools now and finish with what you have so far.");
+        }
+        prompt
+    }
+}
+
+#[derive(Debug, Default)]
+struct NudgeSlot {
+    in_tool_loop: bool,
+    queued: VecDeque<Nudge>,
+}
+
+/// 各 Agent 的插话槽
+#[derive(Debug, Default)]
+pub struct NudgeSlots {
+    slots: DashMap<String, NudgeSlot>,
+}
+
+impl NudgeSlots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 放入插话，返回排队位置（从 1 开始）；Agent 不在工具循环中时原样退回
+    pub fn offer(&self, agent_id: &str, nudge: Nudge) -> Result<usize, Nudge> {
+        let Some(mut slot) = self.slots.get_mut(agent_id).filter(|slot| slot.in_tool_loop) else {
+            return Err(nudge);
+        };
+        slot.queued.push_back(nudge);
+        Ok(slot.queued.len())
+    }
+
+    /// Agent 是否在工具循环中
+    pub fn in_tool_loop(&self, agent_id: &str) -> bool {
+        self.slots.get(agent_id).is_some_and(|slot| slot.in_tool_loop)
+    }
+
+    /// 取出排队的插话，最早的在前
+    pub fn take(&self, agent_id: &str) -> Vec<Nudge> {
+        self.slots
+            .get_mut(agent_id)
+            .map(|mut slot| slot.queued.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    /// 轮次结束时更新工具循环状态；离开工具循环时返回还没取出的插话
+    pub fn set_tool_loop(&self, agent_id: &str, in_tool_loop: bool) -> Vec<Nudge> {
+        if in_tool_loop {
+            self.slots.entry(agent_id.to_string()).or_default().in_tool_loop = true;
+            return Vec::new();
+        }
+        self.slots
+            .remove(agent_id)
+            .map(|(_, slot)| slot.queued.into())
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,39 @@
+//! 组织架构变更记录
+//!
+//! 保存组织架构时与存储中的上一版本比较，把结构化差异追加到变更记录，
+//! 供管理员查询（`GET /api/org/changes`）和 Agent 通过 `org.get_recent_changes` 了解新同事。
+
+use anyhow::Result;
+use tracing::warn;
+
+use crate::core::store::Store;
+use crate::domain::{OrgChangeEntry, OrgDiff, Organization};
+
+/// 非用户发起的变更（如启动时保存配置）的操作者
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// 保存组织架构并记录与上一版本的差异；没有变化时不记录，返回 None
+pub async fn save_organization_tracked(store: &dyn Store, org: &Organization, actor: &str) -> Result<Option<OrgChangeEntry>> {
+    let previous = store.load_organization().await;
+    store.save_organization(org).await?;
+
+    let previous = match previous {
+        Ok(previous) => previous,
+        Err(e) => {
+            warn!("Failed to load previous organization, change not recorded: {}", e);
+            return Ok(None);
+        }
+    };
+    let diff = OrgDiff::between(&previous, org);
+    if diff.is_empty() {
+        return Ok(None);
+    }
+
+    let entry = OrgChangeEntry::new(actor, diff);
+    // 变更记录失败不影响已经成功的保存
+    if let Err(e) = store.save_org_change(&entry).await {
+        warn!("Failed to record organization change: {}", e);
+        return Ok(None);
+    }
+    Ok(Some(entry))
+}
@@ -0,0 +1,163 @@
+//! 组织架构树投影
+//!
+//! `GET /api/org/tree` 返回的部门树（含各部门成员）由 [`build_org_tree`] 按组织架构一次性构建，
+//! [`OrgTreeProjection`] 缓存构建结果和所依据的组织架构，前端轮询和每个请求的权限解析直接使用缓存
+//! 而不读取存储。组织架构变更（`OrgChanged` 事件或 [`OrgTreeProjection::invalidate`]）使缓存失效，
+//! 下一次请求重新构建。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::store::Store;
+use crate::domain::{Agent, Department, Organization};
+
+/// 职位中包含该词的 Agent 作为部门负责人排在成员最前
+const LEADER_TITLE_MARKER: &str = "主管";
+
+/// 构建部门树：根部门（没有上级）按组织架构中的顺序排列，每个部门的 `children` 含全部下级部门
+pub fn build_org_tree(org: &Organization) -> Value {
+    let mut children: HashMap<&str, Vec<&Department>> = HashMap::new();
+    for dept in &org.departments {
+        if let Some(parent_id) = &dept.parent_id {
+            children.entry(parent_id.as_str()).or_default().push(dept);
+        }
+    }
+    let mut members: HashMap<&str, Vec<&Agent>> = HashMap::new();
+    for agent in &org.agents {
+        if let Some(department_id) = &agent.department_id {
+            members.entry(department_id.as_str()).or_default().push(agent);
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let roots: Vec<Value> = org
+        .departments
+        .iter()
+        .filter(|dept| dept.parent_id.is_none())
+        .map(|dept| department_node(dept, &children, &members, &mut visited))
+        .collect();
+    Value::Array(roots)
+}
+
+/// 部门节点及其全部下级；`visited` 防止错误数据中的循环引用导致无限递归
+fn department_node<'a>(
+    dept: &'a Department,
+    children: &HashMap<&str, Vec<&'a Department>>,
+    members: &HashMap<&str, Vec<&Agent>>,
+    visited: &mut HashSet<&'a str>,
+) -> Value {
+    visited.insert(dept.id.as_str());
+    let agents = members.get(dept.id.as_str()).map(Vec::as_slice).unwrap_or_default();
+    let (leaders, staff): (Vec<&Agent>, Vec<&Agent>) =
+        agents.iter().partition(|agent| agent.role.title.contains(LEADER_TITLE_MARKER));
+    let users: Vec<Value> = leaders.iter().chain(staff.iter()).map(|agent| member_node(agent)).collect();
+
+    let mut sub_departments = Vec::new();
+    for child in children.get(dept.id.as_str()).map(Vec::as_slice).unwrap_or_default() {
+        if !visited.contains(child.id.as_str()) {
+            sub_departments.push(department_node(child, children, members, visited));
+        }
+    }
+
+    json!({
+        "id": dept.id,
+        "name": dept.name,
+        "parentId": dept.parent_id,
+        "leader": leaders.first().map(|agent| member_node(agent)),
+        "memberCount": users.len(),
+        "users": users,
+        "children": sub_departments,
+    })
+}
+
+fn member_node(agent: &Agent) -> Value {
+    json!({
+        "id": agent.id,
+        "name": agent.name,
+        "title": agent.role.title,
+        "status": "online"
+    })
+}
+
+/// 缓存的组织架构和部门树
+#[derive(Debug, Default)]
+pub struct OrgTreeProjection {
+    cached: RwLock<Option<Arc<Projection>>>,
+    /// 每次失效加一；构建期间发生失效时不写入缓存，避免缓存旧版本
+    generation: AtomicU64,
+    builds: AtomicU64,
+}
+
+impl OrgTreeProjection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前的部门树，缓存失效时从存储加载组织架构重新构建
+    pub async fn tree(&self, store: &dyn Store) -> Result<Arc<Value>> {
+        Ok(self.projection(store).await?.tree.clone())
+    }
+
+    /// 当前的组织架构，与 [`tree`](Self::tree) 共用同一份缓存
+    pub async fn organization(&self, store: &dyn Store) -> Result<Arc<Organization>> {
+        Ok(self.projection(store).await?.org.clone())
+    }
+
+    async fn projection(&self, store: &dyn Store) -> Result<Arc<Projection>> {
+        if let Some(projection) = self.cached.read().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Ok(projection);
+        }
+
+        let generation = self.generation.load(Ordering::SeqCst);
+        let org = store.load_organization().await?;
+        let projection = Arc::new(Projection {
+            tree: Arc::new(build_org_tree(&org)),
+            org: Arc::new(org),
+        });
+        self.builds.fetch_add(1, Ordering::Relaxed);
+        let mut cached = self.cached.write().unwrap_or_else(|e| e.into_inner());
+        if self.generation.load(Ordering::SeqCst) == generation {
+            *cached = Some(projection.clone());
+        }
+        Ok(projection)
+    }
+
+    /// 组织架构已变更，下一次请求重新构建
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *self.cached.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// 累计构建次数
+    pub fn builds(&self) -> u64 {
+        self.builds.load(Ordering::Relaxed)
+    }
+
+    /// 收到 `OrgChanged` 事件时使缓存失效（需在 Tokio 运行时中调用）
+    pub fn spawn(self: Arc<Self>, events: &EventBus) {
+        events.register_listener(Box::new(OrgTreeListener(self)));
+    }
+}
+
+#[derive(Debug)]
+struct Projection {
+    org: Arc<Organization>,
+    tree: Arc<Value>,
+}
+
+struct OrgTreeListener(Arc<OrgTreeProjection>);
+
+#[async_trait]
+impl CompanyEventListener for OrgTreeListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        if let CompanyEvent::OrgChanged { .. } = event {
+            self.0.invalidate();
+        }
+    }
+}
@@ -0,0 +1,577 @@
+//! 主动问候
+//!
+//! 组织中发生的事情（新同事加入、任务被指派或逾期、同部门同事被 Watchdog 唤醒、同事入职周年）
+//! 可以让 Agent 主动开口，而不必等别人先发消息。每个 Agent 的 [`ProactivePolicy`] 把触发类型
+//! 映射到提示模板；[`ProactiveDispatcher`] 监听事件总线，按接收者的策略渲染模板，
+//! 以 `system` 私聊消息投递给 Agent，Agent 在下一轮据此决定要不要问候或跟进。
+//!
+//! - 策略来自公司配置（`proactive.agents`），也可以在运行时持久化（`proactive_policy:{agent_id}`），
+//!   持久化的策略优先
+//! - 同一事件对同一 Agent 最多产生一条消息
+//! - 每个 Agent 在时间窗口内收到的主动消息有上限（`max_checkins` / `window_secs`），超出的丢弃
+//!
+//! 模板变量：所有模板都有 `agent_id`、`agent_name`（接收者）；新同事、Watchdog 和周年另有
+//! `teammate_id`、`teammate_name`、`teammate_role`、`department`；任务有 `task_id`、`task_title`、
+//! `due`，指派另有 `actor`；Watchdog 有 `rule_id`、`tool_id`；周年有 `years`。模板只能引用
+//! 所属触发类型的变量（[`ProactiveTrigger::variables`]），保存策略时检查。
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::escalation::NO_ESCALATION_KEY;
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::messaging::MessageBus;
+use crate::core::store::Store;
+use crate::core::template::{TemplateError, TemplateSandbox};
+use crate::domain::{Agent, ChangeKind, Message, Organization};
+
+/// 主动问候的发送者
+pub const PROACTIVE_SENDER: &str = "system";
+
+/// 消息元数据中触发类型的键
+pub const PROACTIVE_TRIGGER_KEY: &str = "proactive_trigger";
+
+/// 记住的已投递（事件，Agent）数，超出后忘记最早的
+const DELIVERED_CAPACITY: usize = 10_000;
+
+/// 触发主动问候的事件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProactiveTrigger {
+    /// 新同事加入本部门
+    TeammateJoined,
+    /// 任务被指派给自己
+    TaskAssigned,
+    /// 自己的任务已过截止时间
+    TaskOverdue,
+    /// 同部门的同事被 Watchdog 规则唤醒
+    WatchdogAlert,
+    /// 同部门的同事入职周年
+    WorkAnniversary,
+}
+
+impl ProactiveTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProactiveTrigger::TeammateJoined => "teammate_joined",
+            ProactiveTrigger::TaskAssigned => "task_assigned",
+            ProactiveTrigger::TaskOverdue => "task_overdue",
+            ProactiveTrigger::WatchdogAlert => "watchdog_alert",
+            ProactiveTrigger::WorkAnniversary => "work_anniversary",
+        }
+    }
+
+    /// 这类模板可以引用的变量
+    pub fn variables(&self) -> &'static [&'static str] {
+        match self {
+            ProactiveTrigger::TeammateJoined => {
+                &["agent_id", "agent_name", "teammate_id", "teammate_name", "teammate_role", "department"]
+            }
+            ProactiveTrigger::TaskAssigned => &["agent_id", "agent_name", "actor", "task_id", "task_title", "due"],
+            ProactiveTrigger::TaskOverdue => &["agent_id", "agent_name", "task_id", "task_title", "due"],
+            ProactiveTrigger::WatchdogAlert => &[
+                "agent_id",
+                "agent_name",
+                "teammate_id",
+                "teammate_name",
+                "teammate_role",
+                "department",
+                "rule_id",
+                "tool_id",
+            ],
+            ProactiveTrigger::WorkAnniversary => {
+                &["agent_id", "agent_name", "teammate_id", "teammate_name", "teammate_role", "department", "years"]
+            }
+        }
+    }
+
+    /// 渲染这类模板的沙箱
+    pub fn sandbox(&self) -> TemplateSandbox {
+        TemplateSandbox::new().with_variables(self.variables())
+    }
+}
+
+/// 单个 Agent 的主动问候策略
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProactivePolicy {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 触发类型到提示模板，没有模板的触发类型不产生消息
+    #[serde(default)]
+    pub templates: BTreeMap<ProactiveTrigger, String>,
+}
+
+impl ProactivePolicy {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            templates: BTreeMap::new(),
+        }
+    }
+
+    /// 为触发类型设置提示模板
+    pub fn with_template(mut self, trigger: ProactiveTrigger, template: impl Into<String>) -> Self {
+        self.templates.insert(trigger, template.into());
+        self
+    }
+
+    /// 持久化时使用的键
+    pub fn key(agent_id: &str) -> String {
+        format!("proactive_policy:{}", agent_id)
+    }
+
+    /// 触发类型对应的模板，策略停用时为 None
+    pub fn template(&self, trigger: ProactiveTrigger) -> Option<&str> {
+        self.enabled.then(|| self.templates.get(&trigger).map(String::as_str)).flatten()
+    }
+
+    /// 检查各模板只引用了对应触发类型的变量
+    pub fn validate(&self) -> Result<(), TemplateError> {
+        for (trigger, template) in &self.templates {
+            trigger.sandbox().check(template)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ProactivePolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// 主动问候配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProactiveConfig {
+    /// 每个 Agent 在时间窗口内最多收到的主动消息数，0 表示不限制
+    pub max_checkins: usize,
+    /// 限流时间窗口（秒）
+    pub window_secs: i64,
+    /// 检查入职周年的间隔（秒）
+    pub anniversary_check_secs: u64,
+    /// Agent ID 到策略
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub agents: HashMap<String, ProactivePolicy>,
+}
+
+impl Default for ProactiveConfig {
+    fn default() -> Self {
+        Self {
+            max_checkins: 3,
+            window_secs: 24 * 60 * 60,
+            anniversary_check_secs: 60 * 60,
+            agents: HashMap::new(),
+        }
+    }
+}
+
+impl ProactiveConfig {
+    /// 设置 Agent 的策略
+    pub fn with_policy(mut self, agent_id: impl Into<String>, policy: ProactivePolicy) -> Self {
+        self.agents.insert(agent_id.into(), policy);
+        self
+    }
+
+    /// 设置限流：每 `window_secs` 秒最多 `max_checkins` 条
+    pub fn with_rate_cap(mut self, max_checkins: usize, window_secs: i64) -> Self {
+        self.max_checkins = max_checkins;
+        self.window_secs = window_secs;
+        self
+    }
+}
+
+/// 一个事件对应的主动问候：触发类型、去重键，以及每个接收者的模板变量
+#[derive(Debug, Clone)]
+pub struct ProactiveOccasion {
+    pub trigger: ProactiveTrigger,
+    /// 同一事件重复出现时键相同
+    pub key: String,
+    /// 接收者 Agent ID 到该接收者的模板变量
+    pub recipients: Vec<(String, Value)>,
+}
+
+#[derive(Default)]
+struct DispatchState {
+    delivered: HashSet<String>,
+    delivered_order: VecDeque<String>,
+    /// Agent ID 到窗口内的投递时间
+    sent_at: HashMap<String, VecDeque<i64>>,
+}
+
+impl DispatchState {
+    /// 记录（事件，Agent），已记录过时返回 false
+    fn mark_delivered(&mut self, key: String) -> bool {
+        if !self.delivered.insert(key.clone()) {
+            return false;
+        }
+        self.delivered_order.push_back(key);
+        if self.delivered_order.len() > DELIVERED_CAPACITY {
+            if let Some(oldest) = self.delivered_order.pop_front() {
+                self.delivered.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    /// 占用一次限流额度，超出时返回 false
+    fn take_quota(&mut self, agent_id: &str, now: i64, max: usize, window_secs: i64) -> bool {
+        if max == 0 {
+            return true;
+        }
+        let sent = self.sent_at.entry(agent_id.to_string()).or_default();
+        while sent.front().is_some_and(|at| *at <= now - window_secs) {
+            sent.pop_front();
+        }
+        if sent.len() >= max {
+            return false;
+        }
+        sent.push_back(now);
+        true
+    }
+}
+
+/// 主动问候分发器
+pub struct ProactiveDispatcher {
+    config: ProactiveConfig,
+    store: Arc<dyn Store>,
+    message_bus: Option<Arc<MessageBus>>,
+    clock: Arc<dyn Clock>,
+    state: Mutex<DispatchState>,
+}
+
+impl ProactiveDispatcher {
+    /// 创建分发器；未接入消息总线时消息只写入存储
+    pub fn new(config: ProactiveConfig, store: Arc<dyn Store>) -> Self {
+        Self {
+            config,
+            store,
+            message_bus: None,
+            clock: Arc::new(SystemClock),
+            state: Mutex::new(DispatchState::default()),
+        }
+    }
+
+    /// 通过消息总线投递，并使用总线的时钟
+    pub fn with_message_bus(mut self, message_bus: Arc<MessageBus>) -> Self {
+        self.clock = message_bus.clock();
+        self.message_bus = Some(message_bus);
+        self
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Agent 的策略：持久化的优先，其次是配置，都没有时为 None
+    pub async fn policy(&self, agent_id: &str) -> Result<Option<ProactivePolicy>> {
+        if let Some(value) = self.store.load_app_state(&ProactivePolicy::key(agent_id)).await? {
+            return Ok(Some(serde_json::from_value(value)?));
+        }
+        Ok(self.config.agents.get(agent_id).cloned())
+    }
+
+    /// 持久化 Agent 的策略，覆盖配置中的策略；模板引用了不可用的变量时拒绝
+    pub async fn save_policy(&self, agent_id: &str, policy: &ProactivePolicy) -> Result<()> {
+        policy.validate()?;
+        self.store
+            .save_app_state(&ProactivePolicy::key(agent_id), &serde_json::to_value(policy)?)
+            .await
+    }
+
+    /// 删除持久化的策略，恢复使用配置中的策略
+    pub async fn delete_policy(&self, agent_id: &str) -> Result<()> {
+        self.store.delete_app_state(&ProactivePolicy::key(agent_id)).await
+    }
+
+    /// 事件对应的主动问候，与主动问候无关的事件返回 None
+    pub async fn resolve(&self, event: &CompanyEvent) -> Result<Option<ProactiveOccasion>> {
+        let occasion = match event {
+            CompanyEvent::OrgChanged { entry } => {
+                let joined: Vec<&str> = entry
+                    .diff
+                    .agents
+                    .iter()
+                    .filter(|change| change.kind == ChangeKind::Added)
+                    .map(|change| change.id.as_str())
+                    .collect();
+                if joined.is_empty() {
+                    return Ok(None);
+                }
+                let org = self.store.load_organization().await?;
+                let recipients = joined
+                    .into_iter()
+                    .filter_map(|id| org.find_agent(id))
+                    .flat_map(|newcomer| teammate_recipients(&org, newcomer))
+                    .collect();
+                ProactiveOccasion {
+                    trigger: ProactiveTrigger::TeammateJoined,
+                    key: format!("org_changed:{}", entry.id),
+                    recipients,
+                }
+            }
+            CompanyEvent::TaskAssigned { task, assignee, actor } => {
+                let recipients = self
+                    .store
+                    .load_agent(assignee)
+                    .await?
+                    .map(|agent| {
+                        let mut vars = agent_vars(&agent);
+                        vars["actor"] = json!(actor.as_ref());
+                        vars["task_id"] = json!(task.id);
+                        vars["task_title"] = json!(task.title);
+                        vars["due"] = json!(task.due_at.map(format_due).unwrap_or_else(|| "-".to_string()));
+                        (agent.id, vars)
+                    })
+                    .into_iter()
+                    .collect();
+                ProactiveOccasion {
+                    trigger: ProactiveTrigger::TaskAssigned,
+                    key: format!("task_assigned:{}:{}:{}", task.id, assignee, task.updated_at),
+                    recipients,
+                }
+            }
+            CompanyEvent::TaskOverdue { task } => {
+                let assignee = match task.assignee.as_deref() {
+                    Some(assignee) => self.store.load_agent(assignee).await?,
+                    None => None,
+                };
+                let recipients = assignee
+                    .map(|agent| {
+                        let mut vars = agent_vars(&agent);
+                        vars["task_id"] = json!(task.id);
+                        vars["task_title"] = json!(task.title);
+                        vars["due"] = json!(task.due_at.map(format_due).unwrap_or_else(|| "-".to_string()));
+                        (agent.id, vars)
+                    })
+                    .into_iter()
+                    .collect();
+                ProactiveOccasion {
+                    trigger: ProactiveTrigger::TaskOverdue,
+                    key: format!("task_overdue:{}:{}", task.id, task.due_at.unwrap_or_default()),
+                    recipients,
+                }
+            }
+            CompanyEvent::WatchdogTriggered {
+                rule_id,
+                tool_id,
+                target_agent_id,
+                trace_id,
+            } => {
+                let org = self.store.load_organization().await?;
+                let Some(target) = org.find_agent(target_agent_id) else {
+                    return Ok(None);
+                };
+                let recipients = teammate_recipients(&org, target)
+                    .into_iter()
+                    .map(|(id, mut vars)| {
+                        vars["rule_id"] = json!(rule_id.as_ref());
+                        vars["tool_id"] = json!(tool_id.as_ref());
+                        (id, vars)
+                    })
+                    .collect();
+                ProactiveOccasion {
+                    trigger: ProactiveTrigger::WatchdogAlert,
+                    key: format!("watchdog:{}:{}", rule_id, trace_id),
+                    recipients,
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(occasion))
+    }
+
+    /// 按接收者的策略渲染并投递，返回实际投递的消息
+    pub async fn dispatch(&self, occasion: &ProactiveOccasion) -> Vec<Message> {
+        let mut sent = Vec::new();
+        for (agent_id, vars) in &occasion.recipients {
+            let policy = match self.policy(agent_id).await {
+                Ok(policy) => policy,
+                Err(e) => {
+                    warn!("Failed to load proactive policy of {}: {}", agent_id, e);
+                    continue;
+                }
+            };
+            let Some(template) = policy.as_ref().and_then(|p| p.template(occasion.trigger)) else {
+                continue;
+            };
+            let content = match occasion.trigger.sandbox().render(template, vars) {
+                Ok(content) => content,
+                Err(e) => {
+                    warn!("Proactive {} template of {} failed: {}", occasion.trigger.as_str(), agent_id, e);
+                    continue;
+                }
+            };
+
+            let now = self.clock.now();
+            {
+                let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+                if !state.mark_delivered(format!("{}|{}", occasion.key, agent_id)) {
+                    debug!("Proactive {} for {} already delivered", occasion.key, agent_id);
+                    continue;
+                }
+                if !state.take_quota(agent_id, now, self.config.max_checkins, self.config.window_secs) {
+                    info!("Proactive {} for {} dropped by rate cap", occasion.trigger.as_str(), agent_id);
+                    continue;
+                }
+            }
+
+            let mut message = Message::private(PROACTIVE_SENDER, agent_id, content)
+                .with_metadata("kind", "proactive")
+                .with_metadata(PROACTIVE_TRIGGER_KEY, occasion.trigger.as_str())
+                .with_metadata(NO_ESCALATION_KEY, "true");
+            message.timestamp = now;
+            let result = match &self.message_bus {
+                Some(bus) => bus.send(message.clone()).await,
+                None => self.store.save_message(&message).await,
+            };
+            match result {
+                Ok(()) => sent.push(message),
+                Err(e) => warn!("Failed to deliver proactive {} to {}: {}", occasion.trigger.as_str(), agent_id, e),
+            }
+        }
+        sent
+    }
+
+    /// 处理一个事件，返回投递的消息
+    pub async fn handle(&self, event: &CompanyEvent) -> Vec<Message> {
+        match self.resolve(event).await {
+            Ok(Some(occasion)) => self.dispatch(&occasion).await,
+            Ok(None) => Vec::new(),
+            Err(e) => {
+                warn!("Failed to resolve proactive check-ins for {}: {}", event.kind(), e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// 检查今天入职周年的 Agent，通知同部门的同事；每人每年只通知一次（记录在存储中，重启后不重复）
+    pub async fn check_anniversaries(&self) -> Result<Vec<Message>> {
+        let now = self.clock.now();
+        let Some(today) = DateTime::from_timestamp(now, 0).map(|t| t.date_naive()) else {
+            return Ok(Vec::new());
+        };
+        let org = self.store.load_organization().await?;
+
+        let mut sent = Vec::new();
+        for agent in &org.agents {
+            let Some(joined) = agent.created_at.and_then(|at| DateTime::from_timestamp(at, 0)).map(|t| t.date_naive())
+            else {
+                continue;
+            };
+            let years = today.year() - joined.year();
+            if years < 1 || anniversary_in(joined, today.year()) != Some(today) {
+                continue;
+            }
+
+            let marker = format!("proactive_anniversary:{}", agent.id);
+            let celebrated = self.store.load_app_state(&marker).await?.and_then(|v| v.as_i64());
+            if celebrated == Some(today.year() as i64) {
+                continue;
+            }
+            self.store.save_app_state(&marker, &json!(today.year())).await?;
+
+            let recipients = teammate_recipients(&org, agent)
+                .into_iter()
+                .map(|(id, mut vars)| {
+                    vars["years"] = json!(years);
+                    (id, vars)
+                })
+                .collect();
+            let occasion = ProactiveOccasion {
+                trigger: ProactiveTrigger::WorkAnniversary,
+                key: format!("anniversary:{}:{}", agent.id, today.year()),
+                recipients,
+            };
+            sent.extend(self.dispatch(&occasion).await);
+        }
+        Ok(sent)
+    }
+
+    /// 注册为事件监听器，并启动周期的入职周年检查（需在 Tokio 运行时中调用）
+    pub fn spawn(self: Arc<Self>, events: &EventBus) -> JoinHandle<()> {
+        events.register_listener(Box::new(ProactiveListener(self.clone())));
+        let interval = Duration::from_secs(self.config.anniversary_check_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.check_anniversaries().await {
+                    warn!("Work anniversary check failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+struct ProactiveListener(Arc<ProactiveDispatcher>);
+
+#[async_trait]
+impl CompanyEventListener for ProactiveListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        self.0.handle(event).await;
+    }
+}
+
+/// 接收者自己的模板变量
+fn agent_vars(agent: &Agent) -> Value {
+    json!({
+        "agent_id": agent.id,
+        "agent_name": agent.name,
+    })
+}
+
+/// 与 `teammate` 同部门的其他 Agent 及其模板变量；没有部门时没有同事
+fn teammate_recipients(org: &Organization, teammate: &Agent) -> Vec<(String, Value)> {
+    let Some(department_id) = teammate.department_id.as_deref() else {
+        return Vec::new();
+    };
+    let department = org
+        .departments
+        .iter()
+        .find(|d| d.id == department_id)
+        .map_or(department_id, |d| d.name.as_str());
+
+    org.agents
+        .iter()
+        .filter(|agent| agent.id != teammate.id && agent.department_id.as_deref() == Some(department_id))
+        .map(|agent| {
+            let mut vars = agent_vars(agent);
+            vars["teammate_id"] = json!(teammate.id);
+            vars["teammate_name"] = json!(teammate.name);
+            vars["teammate_role"] = json!(teammate.role.title);
+            vars["department"] = json!(department);
+            (agent.id.clone(), vars)
+        })
+        .collect()
+}
+
+/// 某年的周年日；2 月 29 日入职的在平年按 2 月 28 日算
+fn anniversary_in(joined: NaiveDate, year: i32) -> Option<NaiveDate> {
+    joined
+        .with_year(year)
+        .or_else(|| NaiveDate::from_ymd_opt(year, joined.month(), joined.day() - 1))
+}
+
+/// 截止时间的显示格式
+fn format_due(due_at: i64) -> String {
+    DateTime::from_timestamp(due_at, 0)
+        .map(|due| due.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| due_at.to_string())
+}
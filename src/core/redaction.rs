@@ -0,0 +1,37 @@
+//! 文本脱敏
+//!
+//! 对外展示 Agent 的提示词、上下文或草稿前替换其中的密钥
+
+/// 替换文本中的密钥：已知密钥、`sk-` 开头的 API Key 与 Bearer 令牌
+pub fn redact_secrets(text: &str, known_secrets: &[&str]) -> String {
+    const REDACTED: &str = "[REDACTED]";
+
+    let mut redacted = text.to_string();
+    for secret in known_secrets.iter().filter(|s| s.len() >= 4) {
+        redacted = redacted.replace(secret, REDACTED);
+    }
+
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+    for prefix in ["sk-", "Bearer "] {
+        let mut result = String::with_capacity(redacted.len());
+        let mut rest = redacted.as_str();
+        while let Some(pos) = rest.find(prefix) {
+            let token_start = pos + prefix.len();
+            let token_len = rest[token_start..]
+                .find(|c: char| !is_token_char(c))
+                .unwrap_or(rest.len() - token_start);
+            result.push_str(&rest[..pos]);
+            if token_len >= 8 {
+                result.push_str(prefix);
+                result.push_str(REDACTED);
+            } else {
+                result.push_str(&rest[pos..token_start + token_len]);
+            }
+            rest = &rest[token_start + token_len..];
+        }
+        result.push_str(rest);
+        redacted = result;
+    }
+
+    redacted
+}
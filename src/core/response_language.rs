@@ -0,0 +1,189 @@
+//! Response language and tone
+//!
+//! Agents in mixed-language companies drift towards the language of the incoming
+//! message. The configured language and tone are rendered into the system prompt,
+//! and outgoing messages are checked with a cheap language detector so the runtime
+//! can retry once with a corrective instruction.
+
+use serde::{Deserialize, Serialize};
+use whatlang::Lang;
+
+use crate::domain::Role;
+
+/// Minimum number of letters (outside code) needed before the language is checked
+pub const MIN_DETECTION_CHARS: usize = 24;
+
+/// Messages where code makes up more than this share of the text are not checked
+pub const MAX_CODE_RATIO: f64 = 0.5;
+
+/// Language codes accepted in configuration, with the name used in prompts
+const LANGUAGES: &[(&[&str], Lang, &str)] = &[
+    (&["en", "eng", "english"], Lang::Eng, "English"),
+    (&["zh", "zh-cn", "zh-hans", "cmn", "chinese"], Lang::Cmn, "Chinese"),
+    (&["ja", "jpn", "japanese"], Lang::Jpn, "Japanese"),
+    (&["ko", "kor", "korean"], Lang::Kor, "Korean"),
+    (&["de", "deu", "german"], Lang::Deu, "German"),
+    (&["fr", "fra", "french"], Lang::Fra, "French"),
+    (&["es", "spa", "spanish"], Lang::Spa, "Spanish"),
+    (&["pt", "por", "portuguese"], Lang::Por, "Portuguese"),
+    (&["it", "ita", "italian"], Lang::Ita, "Italian"),
+    (&["ru", "rus", "russian"], Lang::Rus, "Russian"),
+];
+
+/// Response language and tone an agent must use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseStyle {
+    /// Language code, e.g. `en` or `zh`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Free-form tone, e.g. `formal` or `friendly`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
+}
+
+impl ResponseStyle {
+    pub fn new(language: Option<String>, tone: Option<String>) -> Self {
+        Self { language, tone }
+    }
+
+    /// Role settings override the company default field by field
+    pub fn for_role(role: &Role, default: &ResponseStyle) -> Self {
+        Self {
+            language: role.response_language.clone().or_else(|| default.language.clone()),
+            tone: role.tone.clone().or_else(|| default.tone.clone()),
+        }
+    }
+
+    /// Instruction appended to the system prompt, None when nothing is configured
+    pub fn instruction(&self) -> Option<String> {
+        let mut lines = Vec::new();
+        if let Some(name) = self.language.as_deref().and_then(language_name) {
+            lines.push(format!(
+                "Always write your messages in {}, whatever language the incoming messages use.",
+                name
+            ));
+        }
+        if let Some(tone) = self.tone.as_deref().filter(|t| !t.trim().is_empty()) {
+            lines.push(format!("Use a {} tone.", tone.trim()));
+        }
+        if lines.is_empty() {
+            None
+        } else {
+            Some(format!("\n\nResponse style:\n{}", lines.join("\n")))
+        }
+    }
+}
+
+/// Whether the code names a supported language
+pub fn is_known_language(code: &str) -> bool {
+    parse_language(code).is_some()
+}
+
+/// Name of the language used in prompts
+pub fn language_name(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(codes, _, _)| codes.contains(&code.as_str()))
+        .map(|(_, _, name)| *name)
+        .or_else(|| Lang::from_code(code).map(|lang| lang.eng_name()))
+}
+
+fn parse_language(code: &str) -> Option<Lang> {
+    let code = code.trim().to_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(codes, _, _)| codes.contains(&code.as_str()))
+        .map(|(_, lang, _)| *lang)
+        .or_else(|| Lang::from_code(code))
+}
+
+/// Retry made because a message was not in the expected language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageCorrection {
+    /// Configured language code
+    pub expected: String,
+    /// Language detected in the first answer (ISO 639-3)
+    pub detected: String,
+    /// Whether the retried answer is in the expected language
+    pub corrected: bool,
+}
+
+/// Result of checking a message against the expected language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LanguageCheck {
+    Matches,
+    /// Detected language code (ISO 639-3)
+    Mismatch { detected: String },
+    /// Too short, mostly code, or detection unreliable
+    Skipped,
+}
+
+/// Check the language of a message, ignoring fenced and inline code
+pub fn check_language(expected: &str, text: &str) -> LanguageCheck {
+    let Some(expected) = parse_language(expected) else {
+        return LanguageCheck::Skipped;
+    };
+    match detect(text) {
+        Some(lang) if lang == expected => LanguageCheck::Matches,
+        Some(lang) => LanguageCheck::Mismatch {
+            detected: lang.code().to_string(),
+        },
+        None => LanguageCheck::Skipped,
+    }
+}
+
+/// Language of a message (ISO 639-3), None when too short, mostly code, or unreliable
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    detect(text).map(|lang| lang.code())
+}
+
+/// Whether a configured language code and a detected one name the same language
+pub fn same_language(code: &str, detected: &str) -> bool {
+    matches!((parse_language(code), parse_language(detected)), (Some(a), Some(b)) if a == b)
+}
+
+fn detect(text: &str) -> Option<Lang> {
+    let (prose, code_chars) = strip_code(text);
+    let letters = prose.chars().filter(|c| c.is_alphabetic()).count();
+    let total = prose.chars().filter(|c| !c.is_whitespace()).count() + code_chars;
+    if letters < MIN_DETECTION_CHARS || total == 0 || code_chars as f64 / total as f64 > MAX_CODE_RATIO {
+        return None;
+    }
+    whatlang::detect(&prose).filter(|info| info.is_reliable()).map(|info| info.lang())
+}
+
+/// Instruction added to the prompt when retrying after a language mismatch
+pub fn corrective_instruction(expected: &str) -> String {
+    let name = language_name(expected).unwrap_or(expected);
+    format!(
+        "\n\nYour previous answer was not written in {name}. Answer again and write the message content in {name}.",
+    )
+}
+
+/// Text outside code fences and inline code, and the number of non-whitespace code characters
+fn strip_code(text: &str) -> (String, usize) {
+    let mut prose = String::with_capacity(text.len());
+    let mut code_chars = 0;
+    let mut in_fence = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            code_chars += line.chars().filter(|c| !c.is_whitespace()).count();
+            continue;
+        }
+        for (i, part) in line.split('`').enumerate() {
+            if i % 2 == 1 {
+                code_chars += part.chars().filter(|c| !c.is_whitespace()).count();
+            } else {
+                prose.push_str(part);
+                prose.push(' ');
+            }
+        }
+        prose.push('\n');
+    }
+    (prose, code_chars)
+}
@@ -0,0 +1,589 @@
+//! 统一的重试与退避
+//!
+//! LLM 请求、webhook 投递、MCP 调用和邮件发送共用同一套重试语义：
+//!
+//! - [`RetryPolicy`]：最多尝试次数、初始/最大间隔、指数因子、full jitter 和整体截止时间。
+//!   第 n 次重试前等待 `[0, min(max_delay, base_delay * factor^(n-1))]` 内的随机时长，
+//!   同一上游故障时各调用方不会同时重试
+//! - 重试判断由调用方提供（如 LLM 只重试速率限制、服务端错误和连接失败），不可重试的错误立即返回；
+//!   错误带有服务端要求的等待时间（`Retry-After`）时按它等待
+//! - [`RetryBudget`]：子系统共享的令牌桶，每次重试消耗一个令牌，按时间补充。
+//!   整个子系统出故障时重试总量受限，不会把负载放大成 `max_attempts` 倍
+//! - [`RetryMetrics`]：按调用点统计调用、重试和放弃原因，通过 `GET /retries/stats` 查看
+//!
+//! 各子系统的策略在 [`AppConfig::retry`](crate::config::AppConfig::retry) 中配置，
+//! 由 [`Retries`] 为每个调用点创建 [`Retrier`]。
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::core::clock::{Clock, ManualClock, SystemClock};
+
+/// 重试策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 最多尝试次数（包括第一次），1 表示不重试
+    pub max_attempts: u32,
+    /// 第一次重试前的等待上限（毫秒）
+    pub base_delay_ms: u64,
+    /// 单次等待上限（毫秒）
+    pub max_delay_ms: u64,
+    /// 每次重试等待上限的增长因子
+    pub factor: f64,
+    /// 是否在 `[0, 上限]` 内随机等待（full jitter），关闭时固定等待上限
+    pub jitter: bool,
+    /// 从第一次尝试开始的整体截止时间（毫秒），0 表示不限；等待会越过截止时间时不再重试
+    pub deadline_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 200,
+            max_delay_ms: 10_000,
+            factor: 2.0,
+            jitter: true,
+            deadline_ms: 0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 不重试
+    pub fn no_retry() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// 设置最多尝试次数（包括第一次）
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// 设置初始和最大等待
+    pub fn with_delays(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay_ms = base.as_millis() as u64;
+        self.max_delay_ms = max.as_millis() as u64;
+        self
+    }
+
+    /// 设置增长因子
+    pub fn with_factor(mut self, factor: f64) -> Self {
+        self.factor = factor;
+        self
+    }
+
+    /// 开启或关闭 full jitter
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// 设置整体截止时间
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline_ms = deadline.as_millis() as u64;
+        self
+    }
+
+    /// 第 `retry` 次重试（从 1 开始）前的等待上限
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(63) as i32;
+        let millis = self.base_delay_ms as f64 * self.factor.max(1.0).powi(exponent);
+        Duration::from_millis(millis.min(self.max_delay_ms as f64) as u64)
+    }
+
+    /// 第 `retry` 次重试前的实际等待，`sample` 为 `[0, 1)` 内的随机数
+    pub fn delay(&self, retry: u32, sample: f64) -> Duration {
+        let cap = self.backoff(retry);
+        if self.jitter {
+            cap.mul_f64(sample.clamp(0.0, 1.0))
+        } else {
+            cap
+        }
+    }
+}
+
+/// 重试预算配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetryBudgetConfig {
+    /// 令牌桶容量，0 表示不限制重试
+    pub capacity: u32,
+    /// 每秒补充的令牌数
+    pub refill_per_sec: f64,
+}
+
+impl Default for RetryBudgetConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 20,
+            refill_per_sec: 1.0,
+        }
+    }
+}
+
+/// 子系统共享的重试令牌桶
+pub struct RetryBudget {
+    config: RetryBudgetConfig,
+    clock: Arc<dyn Clock>,
+    /// (剩余令牌, 上次补充时间毫秒)
+    state: Mutex<(f64, Option<i64>)>,
+}
+
+impl RetryBudget {
+    pub fn new(config: RetryBudgetConfig) -> Self {
+        let tokens = config.capacity as f64;
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            state: Mutex::new((tokens, None)),
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 取一个令牌，用完时返回 false
+    pub fn try_acquire(&self) -> bool {
+        if self.config.capacity == 0 {
+            return true;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        if state.0 < 1.0 {
+            return false;
+        }
+        state.0 -= 1.0;
+        true
+    }
+
+    /// 剩余令牌数
+    pub fn available(&self) -> f64 {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut state);
+        state.0
+    }
+
+    fn refill(&self, state: &mut (f64, Option<i64>)) {
+        let now = self.clock.now_millis();
+        if let Some(last) = state.1 {
+            let elapsed = (now - last).max(0) as f64 / 1000.0;
+            state.0 = (state.0 + elapsed * self.config.refill_per_sec).min(self.config.capacity as f64);
+        }
+        state.1 = Some(now);
+    }
+}
+
+impl std::fmt::Debug for RetryBudget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryBudget")
+            .field("config", &self.config)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// 放弃重试的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GiveUpReason {
+    /// 错误不可重试
+    NonRetryable,
+    /// 用完了尝试次数
+    Exhausted,
+    /// 下一次等待会越过截止时间
+    Deadline,
+    /// 子系统的重试预算用完
+    Budget,
+}
+
+/// 单个调用点的重试统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetrySiteStats {
+    pub site: String,
+    /// 调用次数
+    pub calls: u64,
+    /// 实际尝试次数（包括重试）
+    pub attempts: u64,
+    /// 重试次数
+    pub retries: u64,
+    /// 最终成功的调用
+    pub successes: u64,
+    /// 最终失败的调用
+    pub failures: u64,
+    /// 按原因统计的失败
+    pub non_retryable: u64,
+    pub exhausted: u64,
+    pub deadline_exceeded: u64,
+    pub budget_denied: u64,
+}
+
+/// 按调用点统计的重试指标
+#[derive(Debug, Default)]
+pub struct RetryMetrics {
+    sites: DashMap<String, RetrySiteStats>,
+}
+
+impl RetryMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 某个调用点的统计
+    pub fn site(&self, site: &str) -> Option<RetrySiteStats> {
+        self.sites.get(site).map(|stats| stats.clone())
+    }
+
+    /// 所有调用点的统计，按调用点排序
+    pub fn snapshot(&self) -> Vec<RetrySiteStats> {
+        let sites: BTreeMap<String, RetrySiteStats> =
+            self.sites.iter().map(|entry| (entry.key().clone(), entry.value().clone())).collect();
+        sites.into_values().collect()
+    }
+
+    fn record(&self, site: &str, attempts: u32, outcome: Result<(), GiveUpReason>) {
+        let mut stats = self.sites.entry(site.to_string()).or_insert_with(|| RetrySiteStats {
+            site: site.to_string(),
+            ..RetrySiteStats::default()
+        });
+        stats.calls += 1;
+        stats.attempts += attempts as u64;
+        stats.retries += attempts.saturating_sub(1) as u64;
+        match outcome {
+            Ok(()) => stats.successes += 1,
+            Err(reason) => {
+                stats.failures += 1;
+                match reason {
+                    GiveUpReason::NonRetryable => stats.non_retryable += 1,
+                    GiveUpReason::Exhausted => stats.exhausted += 1,
+                    GiveUpReason::Deadline => stats.deadline_exceeded += 1,
+                    GiveUpReason::Budget => stats.budget_denied += 1,
+                }
+            }
+        }
+    }
+}
+
+/// 重试之间的等待
+#[async_trait]
+pub trait Sleeper: Send + Sync {
+    async fn sleep(&self, duration: Duration);
+}
+
+/// 使用 tokio 定时器等待
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TokioSleeper;
+
+#[async_trait]
+impl Sleeper for TokioSleeper {
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// 测试中直接推进手动时钟，不真正等待
+#[async_trait]
+impl Sleeper for ManualClock {
+    async fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+        tokio::task::yield_now().await;
+    }
+}
+
+type Retryable = Arc<dyn Fn(&anyhow::Error) -> bool + Send + Sync>;
+type RetryAfter = Arc<dyn Fn(&anyhow::Error) -> Option<Duration> + Send + Sync>;
+
+/// 某个调用点的重试执行器
+#[derive(Clone)]
+pub struct Retrier {
+    site: String,
+    policy: RetryPolicy,
+    retryable: Retryable,
+    retry_after: RetryAfter,
+    budget: Option<Arc<RetryBudget>>,
+    metrics: Option<Arc<RetryMetrics>>,
+    clock: Arc<dyn Clock>,
+    sleeper: Arc<dyn Sleeper>,
+}
+
+impl Retrier {
+    /// 创建执行器，默认所有错误都可重试，不使用预算和指标
+    pub fn new(site: impl Into<String>, policy: RetryPolicy) -> Self {
+        Self {
+            site: site.into(),
+            policy,
+            retryable: Arc::new(|_| true),
+            retry_after: Arc::new(|_| None),
+            budget: None,
+            metrics: None,
+            clock: Arc::new(SystemClock),
+            sleeper: Arc::new(TokioSleeper),
+        }
+    }
+
+    /// 设置哪些错误值得重试
+    pub fn with_retryable(mut self, retryable: impl Fn(&anyhow::Error) -> bool + Send + Sync + 'static) -> Self {
+        self.retryable = Arc::new(retryable);
+        self
+    }
+
+    /// 设置如何从错误中取服务端要求的等待时间（如 `Retry-After`），取到时代替退避间隔
+    pub fn with_retry_after(
+        mut self,
+        retry_after: impl Fn(&anyhow::Error) -> Option<Duration> + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_after = Arc::new(retry_after);
+        self
+    }
+
+    /// 替换策略
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// 重试时从共享预算中取令牌
+    pub fn with_budget(mut self, budget: Arc<RetryBudget>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// 把结果记入指标
+    pub fn with_metrics(mut self, metrics: Arc<RetryMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// 替换时钟和等待方式（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>, sleeper: Arc<dyn Sleeper>) -> Self {
+        self.clock = clock;
+        self.sleeper = sleeper;
+        self
+    }
+
+    pub fn site(&self) -> &str {
+        &self.site
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// 执行操作，失败时按策略重试，返回最后一次的错误
+    pub async fn run<T, F, Fut>(&self, op: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.run_counted(op).await.0
+    }
+
+    /// 同 [`run`](Self::run)，同时返回尝试次数
+    pub async fn run_counted<T, F, Fut>(&self, mut op: F) -> (anyhow::Result<T>, u32)
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let started = self.clock.now_millis();
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let error = match op().await {
+                Ok(value) => {
+                    self.record(attempts, Ok(()));
+                    return (Ok(value), attempts);
+                }
+                Err(e) => e,
+            };
+            let delay = (self.retry_after)(&error).unwrap_or_else(|| self.policy.delay(attempts, rand::random()));
+            if let Err(reason) = self.should_retry(&error, attempts, started, delay) {
+                self.record(attempts, Err(reason));
+                return (Err(error), attempts);
+            }
+            warn!("{} failed (attempt {}), retrying in {:?}: {}", self.site, attempts, delay, error);
+            self.sleeper.sleep(delay).await;
+        }
+    }
+
+    fn should_retry(&self, error: &anyhow::Error, attempts: u32, started: i64, delay: Duration) -> Result<(), GiveUpReason> {
+        if !(self.retryable)(error) {
+            return Err(GiveUpReason::NonRetryable);
+        }
+        if attempts >= self.policy.max_attempts {
+            return Err(GiveUpReason::Exhausted);
+        }
+        if self.policy.deadline_ms > 0 {
+            let resume_at = self.clock.now_millis() + delay.as_millis() as i64;
+            if resume_at >= started + self.policy.deadline_ms as i64 {
+                return Err(GiveUpReason::Deadline);
+            }
+        }
+        if let Some(budget) = &self.budget {
+            if !budget.try_acquire() {
+                return Err(GiveUpReason::Budget);
+            }
+        }
+        Ok(())
+    }
+
+    fn record(&self, attempts: u32, outcome: Result<(), GiveUpReason>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record(&self.site, attempts, outcome);
+        }
+    }
+}
+
+impl std::fmt::Debug for Retrier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Retrier")
+            .field("site", &self.site)
+            .field("policy", &self.policy)
+            .field("budget", &self.budget.is_some())
+            .finish()
+    }
+}
+
+/// 按策略重试所有错误（不使用预算和指标）
+pub async fn retry<T, F, Fut>(policy: &RetryPolicy, op: F) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    Retrier::new("retry", policy.clone()).run(op).await
+}
+
+/// 使用重试的子系统
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetrySubsystem {
+    Llm,
+    Webhook,
+    Mcp,
+    Email,
+}
+
+impl RetrySubsystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RetrySubsystem::Llm => "llm",
+            RetrySubsystem::Webhook => "webhook",
+            RetrySubsystem::Mcp => "mcp",
+            RetrySubsystem::Email => "email",
+        }
+    }
+}
+
+/// 各子系统的重试策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// LLM 请求（只重试速率限制、服务端错误、连接失败等临时错误）
+    pub llm: RetryPolicy,
+    /// webhook 投递（如活动摘要）
+    pub webhook: RetryPolicy,
+    /// MCP HTTP 调用
+    pub mcp: RetryPolicy,
+    /// 每个子系统各自一个令牌桶
+    pub budget: RetryBudgetConfig,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            llm: RetryPolicy::default(),
+            webhook: RetryPolicy::default().with_max_attempts(4).with_deadline(Duration::from_secs(60)),
+            mcp: RetryPolicy::default().with_max_attempts(2),
+            budget: RetryBudgetConfig::default(),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// 子系统的策略；邮件的次数和间隔来自公司配置的 `EmailPolicy`，这里返回默认值
+    pub fn policy(&self, subsystem: RetrySubsystem) -> RetryPolicy {
+        match subsystem {
+            RetrySubsystem::Llm => self.llm.clone(),
+            RetrySubsystem::Webhook => self.webhook.clone(),
+            RetrySubsystem::Mcp => self.mcp.clone(),
+            RetrySubsystem::Email => RetryPolicy::default(),
+        }
+    }
+}
+
+/// 重试注册表：按子系统共享预算，按调用点汇总指标
+pub struct Retries {
+    config: RetryConfig,
+    clock: Arc<dyn Clock>,
+    budgets: DashMap<RetrySubsystem, Arc<RetryBudget>>,
+    metrics: Arc<RetryMetrics>,
+}
+
+impl Retries {
+    pub fn new(config: RetryConfig) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            budgets: DashMap::new(),
+            metrics: Arc::new(RetryMetrics::new()),
+        }
+    }
+
+    /// 替换预算使用的时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn config(&self) -> &RetryConfig {
+        &self.config
+    }
+
+    pub fn metrics(&self) -> Arc<RetryMetrics> {
+        self.metrics.clone()
+    }
+
+    /// 子系统共享的预算
+    pub fn budget(&self, subsystem: RetrySubsystem) -> Arc<RetryBudget> {
+        self.budgets
+            .entry(subsystem)
+            .or_insert_with(|| Arc::new(RetryBudget::new(self.config.budget.clone()).with_clock(self.clock.clone())))
+            .clone()
+    }
+
+    /// 为调用点创建执行器：使用子系统的策略和预算，结果记入指标
+    pub fn retrier(&self, subsystem: RetrySubsystem, site: impl Into<String>) -> Retrier {
+        Retrier::new(site, self.config.policy(subsystem))
+            .with_budget(self.budget(subsystem))
+            .with_metrics(self.metrics.clone())
+    }
+}
+
+impl Default for Retries {
+    fn default() -> Self {
+        Self::new(RetryConfig::default())
+    }
+}
+
+impl std::fmt::Debug for Retries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Retries")
+            .field("config", &self.config)
+            .field("sites", &self.metrics.sites.len())
+            .finish()
+    }
+}
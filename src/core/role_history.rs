@@ -0,0 +1,88 @@
+//! Agent 角色修订历史
+//!
+//! 通过 API 修改 Agent 的角色（主要是系统提示词）时不覆盖原值，而是追加一条修订；
+//! 运行时每轮读取最新修订，无需重启即可生效。第一次修改时先把配置中的原始角色
+//! 记为修订 1，回滚到它也是一条新修订。
+
+use anyhow::Result;
+
+use crate::core::store::Store;
+use crate::domain::{Agent, Role, RoleRevision};
+
+/// 配置中原始角色的编辑者
+pub const CONFIG_EDITOR: &str = "config";
+
+/// 修订号冲突（并发编辑）时的重试次数
+const APPEND_ATTEMPTS: usize = 3;
+
+/// 追加一条新修订并返回
+pub async fn append_role_revision(
+    store: &dyn Store,
+    agent: &Agent,
+    role: Role,
+    editor: &str,
+    note: Option<String>,
+) -> Result<RoleRevision> {
+    let mut last_error = None;
+    for _ in 0..APPEND_ATTEMPTS {
+        let revisions = store.load_role_revisions(&agent.id).await?;
+        let now = chrono::Utc::now().timestamp();
+        if revisions.is_empty() {
+            let initial = RoleRevision {
+                agent_id: agent.id.clone(),
+                revision: 1,
+                role: agent.role.clone(),
+                editor: CONFIG_EDITOR.to_string(),
+                note: None,
+                created_at: now,
+            };
+            if let Err(e) = store.save_role_revision(&initial).await {
+                last_error = Some(e);
+                continue;
+            }
+        }
+
+        let revision = RoleRevision {
+            agent_id: agent.id.clone(),
+            revision: revisions.last().map_or(2, |r| r.revision + 1),
+            role: role.clone(),
+            editor: editor.to_string(),
+            note: note.clone(),
+            created_at: now,
+        };
+        match store.save_role_revision(&revision).await {
+            Ok(()) => return Ok(revision),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| anyhow::anyhow!("Failed to save role revision")))
+}
+
+/// 以旧修订的角色追加一条新修订；修订不存在时返回 None
+pub async fn rollback_role(
+    store: &dyn Store,
+    agent: &Agent,
+    revision: u32,
+    editor: &str,
+    note: Option<String>,
+) -> Result<Option<RoleRevision>> {
+    let revisions = store.load_role_revisions(&agent.id).await?;
+    let Some(target) = revisions.into_iter().find(|r| r.revision == revision) else {
+        return Ok(None);
+    };
+
+    let note = match note {
+        Some(note) => format!("Rollback to revision {}: {}", revision, note),
+        None => format!("Rollback to revision {}", revision),
+    };
+    append_role_revision(store, agent, target.role, editor, Some(note)).await.map(Some)
+}
+
+/// 某一时刻生效的修订（`as_of` 为空时取最新）；从未修改过时返回 None
+pub async fn role_in_effect(store: &dyn Store, agent_id: &str, as_of: Option<i64>) -> Result<Option<RoleRevision>> {
+    let revisions = store.load_role_revisions(agent_id).await?;
+    Ok(revisions
+        .into_iter()
+        .filter(|r| as_of.is_none_or(|as_of| r.created_at <= as_of))
+        .max_by_key(|r| r.revision))
+}
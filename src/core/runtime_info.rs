@@ -0,0 +1,195 @@
+//! 运行时信息
+//!
+//! 运维同时运行多个实例时，用 `/api/info` 和启动日志确认正在访问的构建、
+//! 加载的配置文件和编译进来的特性。配置文件只报告路径和内容哈希，哈希的输入
+//! 先去掉 API 密钥等敏感值，任何字段都不会输出密钥本身。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+use crate::core::config::CompanyConfig;
+use crate::core::features::FeatureFlags;
+use crate::core::store::StoreBackendInfo;
+
+/// 敏感值的占位符
+const REDACTED: &str = "***";
+
+/// 值不参与配置哈希的字段名（也匹配以 `_` 加这些名字结尾的字段，如 `smtp_password`）
+const SECRET_FIELDS: &[&str] = &["api_key", "apikey", "password", "secret", "token"];
+
+/// 编译进来的可选特性
+pub(crate) const FEATURES: &[(&str, bool)] = &[
+    ("chaos", cfg!(feature = "chaos")),
+    ("tiktoken", cfg!(feature = "tiktoken")),
+    ("sandbox", cfg!(feature = "sandbox")),
+    ("s3", cfg!(feature = "s3")),
+    ("msgpack", cfg!(feature = "msgpack")),
+];
+
+/// 构建信息，由构建脚本在编译时记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BuildInfo {
+    /// crate 版本
+    pub version: String,
+    /// git 提交（短哈希），不在 git 仓库中构建时为 `unknown`
+    pub git_commit: String,
+    /// 构建时间（RFC 3339）
+    pub build_timestamp: String,
+    /// 启用的 cargo 特性
+    pub features: Vec<String>,
+}
+
+impl BuildInfo {
+    /// 当前二进制的构建信息
+    pub fn current() -> Self {
+        let build_timestamp = env!("IMITATORT_BUILD_TIMESTAMP")
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_else(|| "unknown".to_string());
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: env!("IMITATORT_GIT_COMMIT").to_string(),
+            build_timestamp,
+            features: FEATURES
+                .iter()
+                .filter(|(_, enabled)| *enabled)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// 加载的配置文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigSource {
+    /// 配置文件路径，嵌入方直接传入配置时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 去掉敏感值后的配置内容的 SHA-256，节点间不一致即配置漂移
+    pub sha256: String,
+}
+
+impl ConfigSource {
+    pub fn new(path: Option<&Path>, config: &CompanyConfig) -> Self {
+        // 优先报告绝对路径，便于区分不同工作目录启动的实例
+        let path = path.map(|p| {
+            std::fs::canonicalize(p)
+                .unwrap_or_else(|_| p.to_path_buf())
+                .to_string_lossy()
+                .into_owned()
+        });
+        let digest = Sha256::digest(redacted_config(config).as_bytes());
+        Self {
+            path,
+            sha256: digest.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+}
+
+/// 配置哈希的输入：键有序的 JSON，敏感字段的值替换为占位符
+pub fn redacted_config(config: &CompanyConfig) -> String {
+    let mut value = serde_json::to_value(config).unwrap_or(Value::Null);
+    redact(&mut value);
+    // serde_json 的 Map 按键有序，结果与 YAML 中的字段顺序无关
+    serde_json::to_string(&value).unwrap_or_default()
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_field(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn is_secret_field(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_FIELDS
+        .iter()
+        .any(|field| key == *field || key.ends_with(&format!("_{}", field)))
+}
+
+/// 运行时信息，见 `GET /api/info` 和 `VirtualCompany::runtime_info`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuntimeInfo {
+    #[serde(flatten)]
+    pub build: BuildInfo,
+    /// 公司名称
+    pub company: String,
+    pub agent_count: usize,
+    /// 存储后端
+    pub store: StoreBackendInfo,
+    /// 数据目录，嵌入方未设置时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
+    /// 加载的配置，从存储加载公司时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub config: Option<ConfigSource>,
+    /// 功能开关的当前状态，见 [`FeatureFlags`]
+    pub flags: BTreeMap<String, bool>,
+}
+
+impl RuntimeInfo {
+    pub fn new(company: impl Into<String>, agent_count: usize, store: StoreBackendInfo) -> Self {
+        Self {
+            build: BuildInfo::current(),
+            company: company.into(),
+            agent_count,
+            store,
+            data_dir: None,
+            config: None,
+            flags: BTreeMap::new(),
+        }
+    }
+
+    /// 设置数据目录
+    pub fn with_data_dir(mut self, data_dir: Option<PathBuf>) -> Self {
+        self.data_dir = data_dir.map(|dir| dir.to_string_lossy().into_owned());
+        self
+    }
+
+    /// 设置加载的配置
+    pub fn with_config(mut self, config: Option<ConfigSource>) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// 设置功能开关的当前状态
+    pub fn with_flags(mut self, flags: &FeatureFlags) -> Self {
+        self.flags = flags.states();
+        self
+    }
+
+    /// 输出一行结构化启动日志
+    pub fn log(&self) {
+        info!(
+            version = %self.build.version,
+            git_commit = %self.build.git_commit,
+            build_timestamp = %self.build.build_timestamp,
+            features = %self.build.features.join(","),
+            company = %self.company,
+            agent_count = self.agent_count,
+            store = %self.store.label(),
+            data_dir = self.data_dir.as_deref().unwrap_or("-"),
+            config_path = self.config.as_ref().and_then(|c| c.path.as_deref()).unwrap_or("-"),
+            config_sha256 = self.config.as_ref().map(|c| c.sha256.as_str()).unwrap_or("-"),
+            flags = %self.flags.iter().filter(|(_, enabled)| **enabled).map(|(name, _)| name.as_str()).collect::<Vec<_>>().join(","),
+            "ImitatorT runtime info"
+        );
+    }
+}
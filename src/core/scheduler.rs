@@ -0,0 +1,274 @@
+//! Agent 轮次调度
+//!
+//! 所有 Agent 的 LLM 轮次共享一个全局并发上限，避免广播给大量 Agent 时同时发起
+//! 过多请求触发服务商限流。超出上限的轮次进入等待队列：
+//!
+//! - 由用户私聊触发的轮次（[`TurnPriority::User`]）优先于自主/后台轮次
+//! - 同一优先级内按入队顺序（FIFO）放行
+//! - 后台轮次等待超过 `starvation_ms` 后提升到最前，避免被持续的用户轮次饿死
+//!
+//! 许可在 [`TurnPermit`] 释放时直接移交给下一个等待者。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+/// 调度配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// 同时进行的 LLM 轮次上限
+    pub max_concurrent_turns: usize,
+    /// 后台轮次等待多久后提升优先级（毫秒）
+    pub starvation_ms: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_turns: 8,
+            starvation_ms: 5000,
+        }
+    }
+}
+
+/// 轮次优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnPriority {
+    /// 由用户私聊或手动分配的任务触发
+    User,
+    /// 自主循环/后台轮次
+    Background,
+}
+
+/// Agent 在调度器中的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnState {
+    /// 不在调度器中
+    Idle,
+    /// 排队等待许可
+    Queued,
+    /// 正在执行轮次
+    Running,
+}
+
+/// 调度指标快照
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SchedulerMetrics {
+    pub max_concurrent_turns: usize,
+    pub running: usize,
+    /// 当前队列深度
+    pub queued: usize,
+    pub queued_user: usize,
+    pub queued_background: usize,
+    /// 已放行的轮次数
+    pub total_turns: u64,
+    /// 平均等待时间（毫秒）
+    pub avg_wait_ms: f64,
+    /// 最长等待时间（毫秒）
+    pub max_wait_ms: u64,
+}
+
+struct Waiter {
+    agent_id: String,
+    enqueued: Instant,
+    tx: oneshot::Sender<TurnPermit>,
+}
+
+#[derive(Default)]
+struct SchedulerState {
+    running: usize,
+    user: VecDeque<Waiter>,
+    background: VecDeque<Waiter>,
+    agents: HashMap<String, TurnState>,
+}
+
+impl SchedulerState {
+    /// 取下一个等待者：饥饿的后台轮次 > 用户轮次 > 后台轮次
+    fn next_waiter(&mut self, starvation: Duration) -> Option<Waiter> {
+        let starving = self
+            .background
+            .front()
+            .is_some_and(|w| w.enqueued.elapsed() >= starvation);
+        if starving {
+            return self.background.pop_front();
+        }
+        self.user.pop_front().or_else(|| self.background.pop_front())
+    }
+}
+
+/// 全局轮次调度器
+pub struct TurnScheduler {
+    config: SchedulerConfig,
+    state: Mutex<SchedulerState>,
+    total_turns: AtomicU64,
+    total_wait_us: AtomicU64,
+    max_wait_us: AtomicU64,
+}
+
+impl TurnScheduler {
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            config: SchedulerConfig {
+                max_concurrent_turns: config.max_concurrent_turns.max(1),
+                ..config
+            },
+            state: Mutex::new(SchedulerState::default()),
+            total_turns: AtomicU64::new(0),
+            total_wait_us: AtomicU64::new(0),
+            max_wait_us: AtomicU64::new(0),
+        }
+    }
+
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SchedulerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 等待一个轮次许可，许可释放前计入并发数
+    pub async fn acquire(self: &Arc<Self>, agent_id: &str, priority: TurnPriority) -> TurnPermit {
+        let enqueued = Instant::now();
+        let rx = {
+            let mut state = self.lock();
+            if state.running < self.config.max_concurrent_turns {
+                state.running += 1;
+                state.agents.insert(agent_id.to_string(), TurnState::Running);
+                drop(state);
+                self.record_wait(Duration::ZERO);
+                return self.permit(agent_id);
+            }
+
+            let (tx, rx) = oneshot::channel();
+            let waiter = Waiter {
+                agent_id: agent_id.to_string(),
+                enqueued,
+                tx,
+            };
+            match priority {
+                TurnPriority::User => state.user.push_back(waiter),
+                TurnPriority::Background => state.background.push_back(waiter),
+            }
+            state.agents.insert(agent_id.to_string(), TurnState::Queued);
+            rx
+        };
+
+        // 发送端只会在移交许可时使用，调度器存活期间不会被丢弃
+        rx.await.expect("turn scheduler dropped a queued waiter")
+    }
+
+    fn permit(self: &Arc<Self>, agent_id: &str) -> TurnPermit {
+        TurnPermit {
+            scheduler: Some(self.clone()),
+            agent_id: agent_id.to_string(),
+        }
+    }
+
+    fn record_wait(&self, wait: Duration) {
+        let wait_us = wait.as_micros() as u64;
+        self.total_turns.fetch_add(1, Ordering::Relaxed);
+        self.total_wait_us.fetch_add(wait_us, Ordering::Relaxed);
+        self.max_wait_us.fetch_max(wait_us, Ordering::Relaxed);
+    }
+
+    /// 释放许可：移交给下一个仍在等待的轮次，没有则减少并发数
+    fn release(self: &Arc<Self>, agent_id: &str) {
+        let starvation = Duration::from_millis(self.config.starvation_ms);
+        let mut state = self.lock();
+        if state.agents.get(agent_id) == Some(&TurnState::Running) {
+            state.agents.remove(agent_id);
+        }
+
+        while let Some(waiter) = state.next_waiter(starvation) {
+            state.agents.insert(waiter.agent_id.clone(), TurnState::Running);
+            let wait = waiter.enqueued.elapsed();
+            let permit = self.permit(&waiter.agent_id);
+            match waiter.tx.send(permit) {
+                Ok(()) => {
+                    drop(state);
+                    self.record_wait(wait);
+                    return;
+                }
+                Err(mut permit) => {
+                    // 等待者已取消；解除许可后丢弃，避免在持锁时递归释放
+                    state.agents.remove(&waiter.agent_id);
+                    permit.scheduler = None;
+                }
+            }
+        }
+        state.running -= 1;
+    }
+
+    /// Agent 当前的调度状态
+    pub fn state_of(&self, agent_id: &str) -> TurnState {
+        self.lock().agents.get(agent_id).copied().unwrap_or(TurnState::Idle)
+    }
+
+    /// 指标快照
+    pub fn metrics(&self) -> SchedulerMetrics {
+        let (running, queued_user, queued_background) = {
+            let state = self.lock();
+            (state.running, state.user.len(), state.background.len())
+        };
+        let total_turns = self.total_turns.load(Ordering::Relaxed);
+        let total_wait_us = self.total_wait_us.load(Ordering::Relaxed);
+
+        SchedulerMetrics {
+            max_concurrent_turns: self.config.max_concurrent_turns,
+            running,
+            queued: queued_user + queued_background,
+            queued_user,
+            queued_background,
+            total_turns,
+            avg_wait_ms: if total_turns == 0 {
+                0.0
+            } else {
+                total_wait_us as f64 / total_turns as f64 / 1000.0
+            },
+            max_wait_ms: self.max_wait_us.load(Ordering::Relaxed) / 1000,
+        }
+    }
+}
+
+impl std::fmt::Debug for TurnScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TurnScheduler")
+            .field("config", &self.config)
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+impl Default for TurnScheduler {
+    fn default() -> Self {
+        Self::new(SchedulerConfig::default())
+    }
+}
+
+/// 轮次许可，drop 时释放
+pub struct TurnPermit {
+    scheduler: Option<Arc<TurnScheduler>>,
+    agent_id: String,
+}
+
+impl TurnPermit {
+    pub fn agent_id(&self) -> &str {
+        &self.agent_id
+    }
+}
+
+impl Drop for TurnPermit {
+    fn drop(&mut self) {
+        if let Some(scheduler) = self.scheduler.take() {
+            scheduler.release(&self.agent_id);
+        }
+    }
+}
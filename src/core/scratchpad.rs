@@ -0,0 +1,132 @@
+//! Agent 运行草稿
+//!
+//! Agent 在一次运行（一个轮次，以轮次的 trace ID 标识）中记下的中间思考：工具计划、放弃的草稿等。
+//! 草稿只供管理员调试时查看，不作为消息发送，因此不会进入会话记录、其他 Agent 的上下文、
+//! 导出和群聊记录。写入前按 [`redact_secrets`] 替换密钥；每次运行的草稿总大小有上限，
+//! 超出时从最早的开始删除。新草稿同时广播给订阅的管理界面。
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::redaction::redact_secrets;
+use crate::core::store::Store;
+use crate::domain::scratchpad::{ScratchpadEntry, ScratchpadSource};
+
+/// Agent 写草稿使用的工具
+pub const SCRATCHPAD_WRITE_TOOL: &str = "scratchpad.write";
+
+/// 草稿配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ScratchpadConfig {
+    /// 是否启用草稿（同时决定是否提供 `scratchpad.write` 工具）
+    pub enabled: bool,
+    /// 每次运行的草稿总大小上限（字节），超出时删除最早的
+    pub max_bytes_per_run: usize,
+    /// 是否把以工具调用结束的 LLM 回复原文自动记为草稿
+    pub capture_tool_turns: bool,
+}
+
+impl Default for ScratchpadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes_per_run: 32 * 1024,
+            capture_tool_turns: false,
+        }
+    }
+}
+
+/// 草稿服务
+pub struct Scratchpad {
+    config: ScratchpadConfig,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    /// 写入草稿前替换的已知密钥（如 Agent 的 API Key）
+    secrets: Vec<String>,
+    entries: broadcast::Sender<ScratchpadEntry>,
+}
+
+impl Scratchpad {
+    pub fn new(config: ScratchpadConfig, store: Arc<dyn Store>) -> Self {
+        Self {
+            config,
+            store,
+            clock: Arc::new(SystemClock),
+            secrets: Vec::new(),
+            entries: broadcast::channel(256).0,
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 写入前替换的已知密钥
+    pub fn with_secrets(mut self, secrets: impl IntoIterator<Item = String>) -> Self {
+        self.secrets = secrets.into_iter().filter(|s| !s.is_empty()).collect();
+        self
+    }
+
+    pub fn config(&self) -> &ScratchpadConfig {
+        &self.config
+    }
+
+    /// 订阅新写入的草稿
+    pub fn subscribe(&self) -> broadcast::Receiver<ScratchpadEntry> {
+        self.entries.subscribe()
+    }
+
+    /// 脱敏后写入一条草稿并广播；单条超过上限时只保留开头
+    pub async fn write(
+        &self,
+        run_id: &str,
+        agent_id: &str,
+        source: ScratchpadSource,
+        content: &str,
+    ) -> Result<ScratchpadEntry> {
+        let secrets: Vec<&str> = self.secrets.iter().map(String::as_str).collect();
+        let mut content = redact_secrets(content, &secrets);
+        truncate_bytes(&mut content, self.config.max_bytes_per_run);
+
+        let entry = ScratchpadEntry::new(run_id, agent_id, source, content, self.clock.now());
+        let evicted = self.store.append_scratchpad_entry(&entry, self.config.max_bytes_per_run).await?;
+        if evicted > 0 {
+            tracing::debug!("Scratchpad of run {} dropped {} oldest entries", run_id, evicted);
+        }
+        let _ = self.entries.send(entry.clone());
+        Ok(entry)
+    }
+
+    /// 某次运行的草稿，最早的在前
+    pub async fn entries(&self, run_id: &str) -> Result<Vec<ScratchpadEntry>> {
+        self.store.load_scratchpad(run_id).await
+    }
+}
+
+impl std::fmt::Debug for Scratchpad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scratchpad")
+            .field("config", &self.config)
+            .field("secrets", &self.secrets.len())
+            .finish()
+    }
+}
+
+/// 截断到不超过 `max` 字节（在字符边界上）
+fn truncate_bytes(text: &mut String, max: usize) {
+    if text.len() <= max {
+        return;
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    text.truncate(end);
+}
@@ -0,0 +1,219 @@
+//! 密钥引用
+//!
+//! `LLMConfig.api_key` 可以是明文（旧配置），也可以是引用：`env:OPENAI_KEY` 读取环境变量，
+//! `keyring:company-openai` 读取系统钥匙串（需要 `keyring` 特性）。引用在创建 LLM 客户端时经
+//! [`SecretResolver`] 解析，解析出的值只留在客户端里；存储、接口响应和变更记录中只出现引用本身，
+//! 明文显示为 `***`。
+//!
+//! SQLite 存储只保存引用：保存时明文换成 [`env_reference_for`] 生成的 `env:` 引用，取出的值经
+//! `Store::take_extracted_api_keys` 交给调用方（`VirtualCompany::save` 用 [`print_extracted_secrets`] 打印）；
+//! 库中已有的明文用 `imitatort migrate-secrets` 迁移（见 `SqliteStore::migrate_plaintext_api_keys`）。
+//! 两种情况都把取出的值打印一次，由运维人员转移到对应的环境变量。
+
+use std::fmt;
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::core::i18n::MessageCatalog;
+
+/// 环境变量引用的前缀
+pub const ENV_PREFIX: &str = "env:";
+/// 系统钥匙串引用的前缀
+pub const KEYRING_PREFIX: &str = "keyring:";
+/// 钥匙串条目的服务名，条目的用户名为引用中的名称
+pub const KEYRING_SERVICE: &str = "imitatort";
+/// 明文迁移时生成的环境变量名前缀
+const MIGRATED_ENV_PREFIX: &str = "IMITATORT_API_KEY_";
+
+/// 解析后的密钥配置
+#[derive(Clone, PartialEq, Eq)]
+pub enum SecretRef {
+    /// 明文（旧配置）
+    Literal(String),
+    /// 环境变量名
+    Env(String),
+    /// 钥匙串条目名
+    Keyring(String),
+}
+
+impl SecretRef {
+    pub fn parse(value: &str) -> Self {
+        if let Some(name) = value.strip_prefix(ENV_PREFIX) {
+            SecretRef::Env(name.trim().to_string())
+        } else if let Some(name) = value.strip_prefix(KEYRING_PREFIX) {
+            SecretRef::Keyring(name.trim().to_string())
+        } else {
+            SecretRef::Literal(value.to_string())
+        }
+    }
+
+    /// 是否为引用（而非明文）
+    pub fn is_reference(&self) -> bool {
+        !matches!(self, SecretRef::Literal(_))
+    }
+}
+
+/// 引用原样显示，明文显示为 `***`，错误信息和日志可以直接使用
+impl fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecretRef::Literal(_) => f.write_str("***"),
+            SecretRef::Env(name) => write!(f, "{}{}", ENV_PREFIX, name),
+            SecretRef::Keyring(name) => write!(f, "{}{}", KEYRING_PREFIX, name),
+        }
+    }
+}
+
+impl fmt::Debug for SecretRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretRef({})", self)
+    }
+}
+
+/// 接口响应和导出中的显示形式，见 [`SecretRef`] 的 `Display`
+pub fn render_secret(value: &str) -> String {
+    SecretRef::parse(value).to_string()
+}
+
+/// 密钥解析失败，信息中带引用
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SecretError {
+    #[error("Secret {reference} could not be resolved: environment variable {name} is not set")]
+    EnvNotSet { reference: String, name: String },
+    #[error("Secret {reference} could not be resolved: built without the `keyring` feature")]
+    KeyringUnavailable { reference: String },
+    #[error("Secret {reference} could not be resolved: {message}")]
+    Backend { reference: String, message: String },
+}
+
+/// 把密钥配置解析为实际的值
+pub trait SecretResolver: Send + Sync {
+    fn resolve(&self, secret: &SecretRef) -> Result<String, SecretError>;
+}
+
+/// 明文原样返回，`env:` 读取环境变量；不支持钥匙串引用
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvSecretResolver;
+
+impl SecretResolver for EnvSecretResolver {
+    fn resolve(&self, secret: &SecretRef) -> Result<String, SecretError> {
+        match secret {
+            SecretRef::Literal(value) => Ok(value.clone()),
+            SecretRef::Env(name) => std::env::var(name).map_err(|_| SecretError::EnvNotSet {
+                reference: secret.to_string(),
+                name: name.clone(),
+            }),
+            SecretRef::Keyring(_) => Err(SecretError::KeyringUnavailable {
+                reference: secret.to_string(),
+            }),
+        }
+    }
+}
+
+/// 从系统钥匙串（macOS 钥匙串、Windows 凭据管理器、Secret Service）读取 `keyring:` 引用，
+/// 其他形式交给 [`EnvSecretResolver`]
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone)]
+pub struct KeyringSecretResolver {
+    service: String,
+}
+
+#[cfg(feature = "keyring")]
+impl KeyringSecretResolver {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self { service: service.into() }
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl Default for KeyringSecretResolver {
+    fn default() -> Self {
+        Self::new(KEYRING_SERVICE)
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl SecretResolver for KeyringSecretResolver {
+    fn resolve(&self, secret: &SecretRef) -> Result<String, SecretError> {
+        let SecretRef::Keyring(name) = secret else {
+            return EnvSecretResolver.resolve(secret);
+        };
+        let backend = |e: keyring::Error| SecretError::Backend {
+            reference: secret.to_string(),
+            message: e.to_string(),
+        };
+        keyring::Entry::new(&self.service, name)
+            .map_err(backend)?
+            .get_password()
+            .map_err(backend)
+    }
+}
+
+/// 默认解析器：启用 `keyring` 特性时支持钥匙串引用，否则只支持环境变量
+pub fn default_resolver() -> Arc<dyn SecretResolver> {
+    #[cfg(feature = "keyring")]
+    {
+        Arc::new(KeyringSecretResolver::default())
+    }
+    #[cfg(not(feature = "keyring"))]
+    {
+        Arc::new(EnvSecretResolver)
+    }
+}
+
+/// 用默认解析器解析密钥配置
+pub fn resolve_secret(value: &str) -> Result<String, SecretError> {
+    default_resolver().resolve(&SecretRef::parse(value))
+}
+
+/// 脱敏时要替换掉的值：引用能解析时为解析出的值，否则为配置原文
+pub fn secret_for_redaction(value: &str) -> String {
+    resolve_secret(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// 明文迁移成的环境变量引用：`env:IMITATORT_API_KEY_` 加大写的 Agent ID，非字母数字换成 `_`
+pub fn env_reference_for(agent_id: &str) -> String {
+    let name: String = agent_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("{}{}{}", ENV_PREFIX, MIGRATED_ENV_PREFIX, name)
+}
+
+/// 保存或迁移时取出的明文，只用于打印一次
+#[derive(Clone)]
+pub struct ExtractedSecret {
+    pub agent_id: String,
+    /// 替换后的引用
+    pub reference: String,
+    pub value: String,
+}
+
+impl fmt::Debug for ExtractedSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtractedSecret")
+            .field("agent_id", &self.agent_id)
+            .field("reference", &self.reference)
+            .field("value", &"***")
+            .finish()
+    }
+}
+
+/// 把取出的明文打印到控制台（只这一次，日志里不出现），并提示运维人员设置对应的环境变量
+pub fn print_extracted_secrets(catalog: &MessageCatalog, extracted: &[ExtractedSecret]) {
+    if extracted.is_empty() {
+        return;
+    }
+    for secret in extracted {
+        let name = secret.reference.trim_start_matches(ENV_PREFIX);
+        println!(
+            "{}",
+            catalog.format(
+                "startup.secrets_migrated",
+                &[("agent_id", &secret.agent_id), ("name", name), ("value", &secret.value)],
+            )
+        );
+    }
+    println!("{}", catalog.get("startup.secrets_migrate_notice"));
+}
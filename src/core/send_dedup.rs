@@ -0,0 +1,172 @@
+//! 重复发送抑制
+//!
+//! LLM 请求在实际成功后因临时错误重试、或提示词循环时，Agent 会把完全相同的消息发两次。
+//! 每条 Agent 发出的消息在发送前按 (发送者, 归一化内容的哈希, 目标) 检查，
+//! 窗口期内已发过的直接丢弃，计入指标并在本轮的追踪中记一条
+//! [`CompanyEvent::DuplicateSendSuppressed`](crate::core::events::CompanyEvent::DuplicateSendSuppressed)。
+//! 发送工具的 `allow_duplicate: true` 参数可以跳过检查（如定时提醒）。
+//!
+//! 与 [`loop_guard`](crate::core::loop_guard) 不同，这里只拦截内容完全相同的消息，
+//! 不判断对话是否在来回往复。缓存按公司共享，条目数有上限，超出时丢弃最早的。
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::domain::{Message, MessageTarget};
+
+/// 发送工具在消息元数据中标记跳过检查，检查后移除，不会存入消息
+pub const ALLOW_DUPLICATE_KEY: &str = "allow_duplicate";
+
+/// 重复发送抑制配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SendDedupConfig {
+    /// 多久内的相同消息视为重复（秒），0 表示关闭
+    pub window_secs: u64,
+    /// 缓存的最多条目数
+    pub max_entries: usize,
+}
+
+impl Default for SendDedupConfig {
+    fn default() -> Self {
+        Self {
+            window_secs: 30,
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SendKey {
+    sender: String,
+    target: String,
+    content: u64,
+}
+
+impl SendKey {
+    fn of(message: &Message) -> Self {
+        let target = match &message.to {
+            MessageTarget::Direct(to) => format!("direct:{}", to),
+            MessageTarget::Group(group_id) => format!("group:{}", group_id),
+        };
+        let mut hasher = DefaultHasher::new();
+        normalize(&message.content).hash(&mut hasher);
+        Self {
+            sender: message.from.clone(),
+            target,
+            content: hasher.finish(),
+        }
+    }
+}
+
+/// 去掉首尾空白并合并连续空白，只差空格换行的消息也算重复
+fn normalize(content: &str) -> String {
+    content.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Default)]
+struct DedupState {
+    /// 消息 -> 首次发送时间（毫秒）
+    sent: HashMap<SendKey, i64>,
+    /// 按发送时间排列，用于过期和容量淘汰
+    order: VecDeque<(SendKey, i64)>,
+}
+
+/// 公司级重复发送缓存，所有 Agent 和发送工具共享
+pub struct SendDedup {
+    config: SendDedupConfig,
+    clock: Arc<dyn Clock>,
+    state: Mutex<DedupState>,
+    suppressed: AtomicU64,
+}
+
+impl SendDedup {
+    pub fn new(config: SendDedupConfig) -> Self {
+        Self {
+            config,
+            clock: Arc::new(SystemClock),
+            state: Mutex::new(DedupState::default()),
+            suppressed: AtomicU64::new(0),
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn config(&self) -> &SendDedupConfig {
+        &self.config
+    }
+
+    /// 是否应拦截：窗口期内发过相同消息时返回 true 并计数，否则记下这条消息
+    pub fn suppress(&self, message: &Message) -> bool {
+        if self.config.window_secs == 0 {
+            return false;
+        }
+        let now = self.clock.now_millis();
+        let window = self.config.window_secs as i64 * 1000;
+        let key = SendKey::of(message);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        evict(&mut state, |&(_, at)| now - at >= window);
+
+        if state.sent.contains_key(&key) {
+            self.suppressed.fetch_add(1, Ordering::Relaxed);
+            return true;
+        }
+        state.sent.insert(key.clone(), now);
+        state.order.push_back((key, now));
+        let max_entries = self.config.max_entries.max(1);
+        let mut excess = state.sent.len().saturating_sub(max_entries);
+        evict(&mut state, |_| {
+            let evicting = excess > 0;
+            excess = excess.saturating_sub(1);
+            evicting
+        });
+        false
+    }
+
+    /// 累计拦截的消息数
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed.load(Ordering::Relaxed)
+    }
+
+    /// 缓存中的条目数
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).sent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 从最早的开始移除满足条件的条目
+fn evict(state: &mut DedupState, mut expired: impl FnMut(&(SendKey, i64)) -> bool) {
+    while let Some(entry) = state.order.front() {
+        if !expired(entry) {
+            break;
+        }
+        let (key, at) = state.order.pop_front().expect("front exists");
+        if state.sent.get(&key) == Some(&at) {
+            state.sent.remove(&key);
+        }
+    }
+}
+
+impl std::fmt::Debug for SendDedup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SendDedup")
+            .field("config", &self.config)
+            .field("entries", &self.len())
+            .field("suppressed", &self.suppressed())
+            .finish()
+    }
+}
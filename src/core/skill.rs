@@ -8,6 +8,7 @@ use crate::domain::capability::{Capability, CapabilityAccessType, SkillCapabilit
 use crate::core::capability::CapabilityRegistry;
 use anyhow::Result;
 use dashmap::DashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 pub struct SkillManager {
@@ -29,6 +30,8 @@ pub struct SkillManager {
     tool_registry: Arc<ToolRegistry>,
     /// 功能注册表引用
     capability_registry: Arc<CapabilityRegistry>,
+    /// 技能、绑定或访问控制变化时递增
+    generation: AtomicU64,
 }
 
 impl SkillManager {
@@ -43,9 +46,24 @@ impl SkillManager {
             capability_access_control: DashMap::new(),
             tool_registry,
             capability_registry,
+            generation: AtomicU64::new(0),
         }
     }
 
+    /// 版本号，技能、工具绑定或访问控制变化后改变
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    fn bump(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 获取工具注册表引用
+    pub fn tool_registry(&self) -> Arc<ToolRegistry> {
+        self.tool_registry.clone()
+    }
+
     /// 创建仅支持工具的 SkillManager（用于向后兼容）
     pub fn new_with_tool_registry(tool_registry: Arc<ToolRegistry>) -> Self {
         let capability_registry = Arc::new(CapabilityRegistry::new());
@@ -70,6 +88,7 @@ impl SkillManager {
         }
 
         self.skills.insert(skill.id.clone(), skill);
+        self.bump();
         Ok(())
     }
 
@@ -80,6 +99,7 @@ impl SkillManager {
         }
 
         self.tool_access_control.insert(tool_id.to_string(), access_type);
+        self.bump();
         Ok(())
     }
 
@@ -120,6 +140,7 @@ impl SkillManager {
             self.tool_access_control
                 .insert(binding.tool_id.clone(), ToolAccessType::Private);
         }
+        self.bump();
 
         Ok(())
     }
@@ -185,8 +206,12 @@ impl SkillManager {
         capabilities
     }
 
-    /// 检查工具是否可以被调用（基于技能绑定）
+    /// 检查工具是否可以被调用（基于技能绑定，别名按规范ID检查）
     pub fn can_call_tool(&self, tool_id: &str, caller_skills: &[String]) -> bool {
+        // 别名按规范ID检查
+        let tool_id = self.tool_registry.canonical_id(tool_id);
+        let tool_id = tool_id.as_str();
+
         // 检查工具是否存在
         if !self.tool_registry.contains(tool_id) {
             return false;
@@ -0,0 +1,788 @@
+//! 存储降级包装
+//!
+//! 存储短暂不可用时（网络挂载的 SQLite 文件消失、数据库重启）让系统继续工作：
+//! 读取失败时返回最近读到的缓存结果，写入失败时按顺序缓冲（可同时写入磁盘日志），
+//! 存储恢复后按原顺序重放。缓冲满后拒绝新的写入。
+//!
+//! 缓冲中的消息、组织架构、群聊、用户、偏好和应用状态对后续读取可见（read-your-writes）。
+
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use super::{MessageFilter, MessageTierCounts, Store, StoreBackendInfo, TaskFilter};
+use crate::core::integrity::{IntegrityFinding, QuarantinedRow};
+use crate::core::secrets::ExtractedSecret;
+use crate::domain::idempotency::IdempotencyRecord;
+use crate::domain::lease::Lease;
+use crate::domain::moderation::QuarantinedOutput;
+use crate::domain::chat_session::ChatSession;
+use crate::domain::group_stats::GroupStats;
+use crate::domain::scratchpad::ScratchpadEntry;
+use crate::domain::share_token::ShareToken;
+use crate::domain::digest::Digest;
+use crate::domain::org_change::OrgChangeEntry;
+use crate::domain::invitation_code::InvitationCode;
+use crate::domain::tool::ToolUsage;
+use crate::domain::user::{LoginFailures, User};
+use crate::domain::{
+    Escalation, Group, Message, MessageBookmark, MessagePin, MessageReaction, MessageTranslation, Organization, RoleRevision,
+    Task,
+};
+
+/// 降级配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BufferConfig {
+    /// 最多缓冲的写入数，达到后拒绝新的写入
+    pub max_buffered_writes: usize,
+    /// 读缓存的最大条目数
+    pub cache_entries: usize,
+    /// 缓冲写入的磁盘日志（JSON Lines），重启后继续重放；为 None 时只在内存中缓冲
+    pub journal_path: Option<PathBuf>,
+}
+
+impl Default for BufferConfig {
+    fn default() -> Self {
+        Self {
+            max_buffered_writes: 1000,
+            cache_entries: 256,
+            journal_path: None,
+        }
+    }
+}
+
+/// 被缓冲的一次写入
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BufferedWrite {
+    SaveOrganization { organization: Organization },
+    SaveGroup { group: Group },
+    DeleteGroup { group_id: String },
+    SaveMessage { message: Message },
+    SaveUser { user: User },
+    SaveAgentPreferences { agent_id: String, preferences: Value },
+    DeleteAgentPreferences { agent_id: String },
+    SaveAppState { key: String, value: Value },
+    DeleteAppState { key: String },
+    SaveRoleRevision { revision: RoleRevision },
+    SaveEscalation { escalation: Escalation },
+}
+
+impl BufferedWrite {
+    async fn apply(&self, store: &dyn Store) -> Result<()> {
+        match self {
+            Self::SaveOrganization { organization } => store.save_organization(organization).await,
+            Self::SaveGroup { group } => store.save_group(group).await,
+            Self::DeleteGroup { group_id } => store.delete_group(group_id).await,
+            Self::SaveMessage { message } => store.save_message(message).await,
+            Self::SaveUser { user } => store.save_user(user).await,
+            Self::SaveAgentPreferences { agent_id, preferences } => {
+                store.save_agent_preferences(agent_id, preferences).await
+            }
+            Self::DeleteAgentPreferences { agent_id } => store.delete_agent_preferences(agent_id).await,
+            Self::SaveAppState { key, value } => store.save_app_state(key, value).await,
+            Self::DeleteAppState { key } => store.delete_app_state(key).await,
+            Self::SaveRoleRevision { revision } => store.save_role_revision(revision).await,
+            Self::SaveEscalation { escalation } => store.save_escalation(escalation).await,
+        }
+    }
+}
+
+/// 最近读取结果的 LRU 缓存，值以 JSON 保存
+struct ReadCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (u64, Value)>,
+}
+
+impl ReadCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<Value> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|entry| {
+            entry.0 = tick;
+            entry.1.clone()
+        })
+    }
+
+    fn put(&mut self, key: String, value: Value) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        self.entries.insert(key, (self.tick, value));
+        if self.entries.len() > self.capacity {
+            if let Some(oldest) = self.entries.iter().min_by_key(|(_, (t, _))| *t).map(|(k, _)| k.clone()) {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+/// 带读缓存和写缓冲的存储包装
+pub struct BufferedStore<S: Store> {
+    inner: S,
+    config: BufferConfig,
+    pending: tokio::sync::Mutex<VecDeque<BufferedWrite>>,
+    pending_len: AtomicUsize,
+    cache: Mutex<ReadCache>,
+    degraded: AtomicBool,
+}
+
+impl<S: Store> BufferedStore<S> {
+    /// 包装存储；配置了日志文件时先载入上次未重放的写入
+    pub fn new(inner: S, config: BufferConfig) -> Result<Self> {
+        let mut pending = VecDeque::new();
+        if let Some(path) = &config.journal_path {
+            if path.exists() {
+                for line in fs::read_to_string(path)?.lines().filter(|l| !l.trim().is_empty()) {
+                    pending.push_back(serde_json::from_str(line)?);
+                }
+                if !pending.is_empty() {
+                    info!("Loaded {} buffered writes from {}", pending.len(), path.display());
+                }
+            }
+        }
+
+        Ok(Self {
+            inner,
+            cache: Mutex::new(ReadCache::new(config.cache_entries)),
+            degraded: AtomicBool::new(!pending.is_empty()),
+            pending_len: AtomicUsize::new(pending.len()),
+            pending: tokio::sync::Mutex::new(pending),
+            config,
+        })
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn config(&self) -> &BufferConfig {
+        &self.config
+    }
+
+    /// 等待重放的写入数
+    pub fn buffered_writes(&self) -> usize {
+        self.pending_len.load(Ordering::Relaxed)
+    }
+
+    /// 是否处于降级状态（最近一次访问失败或仍有未重放的写入）
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed) || self.buffered_writes() > 0
+    }
+
+    /// 按顺序重放缓冲的写入，遇到失败即停止，返回已重放的数量
+    pub async fn flush(&self) -> usize {
+        let mut pending = self.pending.lock().await;
+        self.replay(&mut pending).await
+    }
+
+    async fn replay(&self, pending: &mut VecDeque<BufferedWrite>) -> usize {
+        let mut replayed = 0;
+        while let Some(write) = pending.front() {
+            if let Err(e) = write.apply(&self.inner).await {
+                warn!("Store still unavailable, {} writes buffered: {}", pending.len(), e);
+                self.degraded.store(true, Ordering::Relaxed);
+                break;
+            }
+            pending.pop_front();
+            replayed += 1;
+        }
+        if replayed > 0 {
+            self.pending_len.store(pending.len(), Ordering::Relaxed);
+            self.rewrite_journal(pending);
+            info!("Replayed {} buffered writes, {} remaining", replayed, pending.len());
+        }
+        if pending.is_empty() {
+            self.degraded.store(false, Ordering::Relaxed);
+        }
+        replayed
+    }
+
+    /// 写入：没有积压时直接写，失败或有积压时进入缓冲
+    async fn write(&self, write: BufferedWrite) -> Result<()> {
+        if self.buffered_writes() == 0 {
+            match write.apply(&self.inner).await {
+                Ok(()) => {
+                    self.degraded.store(false, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err(e) => warn!("Store write failed, buffering: {}", e),
+            }
+        }
+
+        let mut pending = self.pending.lock().await;
+        if !pending.is_empty() {
+            self.replay(&mut pending).await;
+            if pending.is_empty() && write.apply(&self.inner).await.is_ok() {
+                return Ok(());
+            }
+        }
+
+        self.degraded.store(true, Ordering::Relaxed);
+        if pending.len() >= self.config.max_buffered_writes {
+            warn!("Store write buffer full ({} writes), rejecting write", pending.len());
+            return Err(anyhow::anyhow!(
+                "Store unavailable and write buffer is full ({} writes)",
+                self.config.max_buffered_writes
+            ));
+        }
+        self.append_journal(&write)?;
+        pending.push_back(write);
+        self.pending_len.store(pending.len(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 读取：成功时更新缓存，失败时回退到缓存
+    async fn read<T: Serialize + DeserializeOwned>(
+        &self,
+        key: String,
+        result: Result<T>,
+    ) -> Result<T> {
+        match result {
+            Ok(value) => {
+                if let Ok(json) = serde_json::to_value(&value) {
+                    self.cache.lock().unwrap_or_else(|e| e.into_inner()).put(key, json);
+                }
+                Ok(value)
+            }
+            Err(e) => {
+                self.degraded.store(true, Ordering::Relaxed);
+                let cached = self.cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key);
+                match cached.and_then(|v| serde_json::from_value(v).ok()) {
+                    Some(value) => {
+                        warn!("Store read failed, serving {} from cache: {}", key, e);
+                        Ok(value)
+                    }
+                    None => Err(e),
+                }
+            }
+        }
+    }
+
+    /// 有积压时先尝试重放，再读取
+    async fn catch_up(&self) {
+        if self.buffered_writes() > 0 {
+            self.flush().await;
+        }
+    }
+
+    /// 当前缓冲中的写入快照
+    async fn pending_writes(&self) -> Vec<BufferedWrite> {
+        if self.buffered_writes() == 0 {
+            return Vec::new();
+        }
+        self.pending.lock().await.iter().cloned().collect()
+    }
+
+    fn append_journal(&self, write: &BufferedWrite) -> Result<()> {
+        let Some(path) = &self.config.journal_path else {
+            return Ok(());
+        };
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(write)?)?;
+        Ok(())
+    }
+
+    fn rewrite_journal(&self, pending: &VecDeque<BufferedWrite>) {
+        let Some(path) = &self.config.journal_path else {
+            return;
+        };
+        let mut contents = String::new();
+        for write in pending {
+            if let Ok(line) = serde_json::to_string(write) {
+                contents.push_str(&line);
+                contents.push('\n');
+            }
+        }
+        if let Err(e) = fs::write(path, contents) {
+            warn!("Failed to rewrite store journal {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Store> Store for BufferedStore<S> {
+    async fn save_organization(&self, org: &Organization) -> Result<()> {
+        self.write(BufferedWrite::SaveOrganization { organization: org.clone() }).await
+    }
+
+    async fn load_organization(&self) -> Result<Organization> {
+        self.catch_up().await;
+        let buffered = self.pending_writes().await.into_iter().rev().find_map(|w| match w {
+            BufferedWrite::SaveOrganization { organization } => Some(organization),
+            _ => None,
+        });
+        if let Some(organization) = buffered {
+            return Ok(organization);
+        }
+        self.read("organization".to_string(), self.inner.load_organization().await).await
+    }
+
+    async fn save_group(&self, group: &Group) -> Result<()> {
+        self.write(BufferedWrite::SaveGroup { group: group.clone() }).await
+    }
+
+    async fn load_groups(&self) -> Result<Vec<Group>> {
+        self.catch_up().await;
+        let mut groups = self.read("groups".to_string(), self.inner.load_groups().await).await?;
+        for write in self.pending_writes().await {
+            match write {
+                BufferedWrite::SaveGroup { group } => {
+                    groups.retain(|g| g.id != group.id);
+                    groups.push(group);
+                }
+                BufferedWrite::DeleteGroup { group_id } => groups.retain(|g| g.id != group_id),
+                _ => {}
+            }
+        }
+        Ok(groups)
+    }
+
+    async fn delete_group(&self, group_id: &str) -> Result<()> {
+        self.write(BufferedWrite::DeleteGroup { group_id: group_id.to_string() }).await
+    }
+
+    async fn save_message(&self, message: &Message) -> Result<()> {
+        self.write(BufferedWrite::SaveMessage { message: message.clone() }).await
+    }
+
+    async fn load_messages(&self, filter: MessageFilter) -> Result<Vec<Message>> {
+        self.catch_up().await;
+        // 有偏移时从头取到这一页的末尾，与缓冲中的消息合并后再跳过偏移
+        let mut inner_filter = filter.clone();
+        if let Some(offset) = inner_filter.offset.take() {
+            inner_filter.limit = filter.effective_limit().saturating_add(offset);
+        }
+        let key = format!("messages:{:?}", inner_filter);
+        let mut messages = self.read(key, self.inner.load_messages(inner_filter).await).await?;
+        let buffered: Vec<Message> = self
+            .pending_writes()
+            .await
+            .into_iter()
+            .filter_map(|w| match w {
+                BufferedWrite::SaveMessage { message } if filter.matches(&message) => Some(message),
+                _ => None,
+            })
+            .collect();
+        if buffered.is_empty() && filter.offset.is_none() {
+            return Ok(messages);
+        }
+        messages.retain(|m| !buffered.iter().any(|b| b.id == m.id));
+        messages.extend(buffered);
+        Ok(filter.apply(messages))
+    }
+
+    async fn load_message(&self, message_id: &str) -> Result<Option<Message>> {
+        self.catch_up().await;
+        let buffered = self.pending_writes().await.into_iter().find_map(|w| match w {
+            BufferedWrite::SaveMessage { message } if message.id == message_id => Some(message),
+            _ => None,
+        });
+        if buffered.is_some() {
+            return Ok(buffered);
+        }
+        let key = format!("message:{}", message_id);
+        self.read(key, self.inner.load_message(message_id).await).await
+    }
+
+    async fn archive_messages_before(&self, timestamp: i64) -> Result<usize> {
+        // 先写入缓冲中的消息，避免它们在归档后才落到热表
+        self.flush().await;
+        self.inner.archive_messages_before(timestamp).await
+    }
+
+    async fn delete_message(&self, message_id: &str) -> Result<bool> {
+        // 先写入缓冲中的消息，避免删除后又被写回
+        self.flush().await;
+        self.inner.delete_message(message_id).await
+    }
+
+    async fn purge_messages_before(&self, timestamp: i64) -> Result<usize> {
+        self.flush().await;
+        self.inner.purge_messages_before(timestamp).await
+    }
+
+    async fn message_tier_counts(&self) -> Result<MessageTierCounts> {
+        self.inner.message_tier_counts().await
+    }
+
+    async fn save_user(&self, user: &User) -> Result<()> {
+        self.write(BufferedWrite::SaveUser { user: user.clone() }).await
+    }
+
+    async fn load_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        self.catch_up().await;
+        let buffered = self.pending_writes().await.into_iter().rev().find_map(|w| match w {
+            BufferedWrite::SaveUser { user } if user.username == username => Some(user),
+            _ => None,
+        });
+        if buffered.is_some() {
+            return Ok(buffered);
+        }
+        let key = format!("user:{}", username);
+        self.read(key, self.inner.load_user_by_username(username).await).await
+    }
+
+    async fn load_users(&self) -> Result<Vec<User>> {
+        self.catch_up().await;
+        let mut users = self.read("users".to_string(), self.inner.load_users().await).await?;
+        for write in self.pending_writes().await {
+            if let BufferedWrite::SaveUser { user } = write {
+                users.retain(|u| u.id != user.id);
+                users.push(user);
+            }
+        }
+        Ok(users)
+    }
+
+    async fn save_invitation_code(&self, code: &InvitationCode) -> Result<()> {
+        self.inner.save_invitation_code(code).await
+    }
+
+    async fn load_invitation_code_by_code(&self, code: &str) -> Result<Option<InvitationCode>> {
+        self.inner.load_invitation_code_by_code(code).await
+    }
+
+    async fn load_invitation_codes(&self) -> Result<Vec<InvitationCode>> {
+        self.inner.load_invitation_codes().await
+    }
+
+    async fn update_invitation_code(&self, code: &InvitationCode) -> Result<()> {
+        self.inner.update_invitation_code(code).await
+    }
+
+    async fn load_invitation_codes_by_creator(&self, creator_id: &str) -> Result<Vec<InvitationCode>> {
+        self.inner.load_invitation_codes_by_creator(creator_id).await
+    }
+
+    async fn save_tool_stats(&self, date: &str, stats: &[ToolUsage]) -> Result<()> {
+        self.inner.save_tool_stats(date, stats).await
+    }
+
+    async fn load_tool_stats(&self, date: &str) -> Result<Vec<ToolUsage>> {
+        self.inner.load_tool_stats(date).await
+    }
+
+    async fn save_agent_preferences(&self, agent_id: &str, preferences: &Value) -> Result<()> {
+        self.write(BufferedWrite::SaveAgentPreferences {
+            agent_id: agent_id.to_string(),
+            preferences: preferences.clone(),
+        })
+        .await
+    }
+
+    async fn load_agent_preferences(&self, agent_id: &str) -> Result<Option<Value>> {
+        self.catch_up().await;
+        let buffered = self.pending_writes().await.into_iter().rev().find_map(|w| match w {
+            BufferedWrite::SaveAgentPreferences { agent_id: id, preferences } if id == agent_id => Some(Some(preferences)),
+            BufferedWrite::DeleteAgentPreferences { agent_id: id } if id == agent_id => Some(None),
+            _ => None,
+        });
+        if let Some(preferences) = buffered {
+            return Ok(preferences);
+        }
+        let key = format!("preferences:{}", agent_id);
+        self.read(key, self.inner.load_agent_preferences(agent_id).await).await
+    }
+
+    async fn delete_agent_preferences(&self, agent_id: &str) -> Result<()> {
+        self.write(BufferedWrite::DeleteAgentPreferences { agent_id: agent_id.to_string() }).await
+    }
+
+    async fn save_role_revision(&self, revision: &RoleRevision) -> Result<()> {
+        self.write(BufferedWrite::SaveRoleRevision { revision: revision.clone() }).await
+    }
+
+    async fn load_role_revisions(&self, agent_id: &str) -> Result<Vec<RoleRevision>> {
+        self.catch_up().await;
+        let key = format!("role_revisions:{}", agent_id);
+        let mut revisions = self.read(key, self.inner.load_role_revisions(agent_id).await).await?;
+        for write in self.pending_writes().await {
+            if let BufferedWrite::SaveRoleRevision { revision } = write {
+                if revision.agent_id == agent_id {
+                    revisions.push(revision);
+                }
+            }
+        }
+        Ok(revisions)
+    }
+
+    async fn save_escalation(&self, escalation: &Escalation) -> Result<()> {
+        self.write(BufferedWrite::SaveEscalation { escalation: escalation.clone() }).await
+    }
+
+    async fn load_escalation(&self, message_id: &str) -> Result<Option<Escalation>> {
+        self.catch_up().await;
+        let buffered = self.pending_writes().await.into_iter().rev().find_map(|w| match w {
+            BufferedWrite::SaveEscalation { escalation } if escalation.message_id == message_id => Some(escalation),
+            _ => None,
+        });
+        if buffered.is_some() {
+            return Ok(buffered);
+        }
+        let key = format!("escalation:{}", message_id);
+        self.read(key, self.inner.load_escalation(message_id).await).await
+    }
+
+    async fn save_login_failures(&self, failures: &LoginFailures) -> Result<()> {
+        self.inner.save_login_failures(failures).await
+    }
+
+    async fn load_login_failures(&self, key: &str) -> Result<Option<LoginFailures>> {
+        self.inner.load_login_failures(key).await
+    }
+
+    async fn delete_login_failures(&self, key: &str) -> Result<()> {
+        self.inner.delete_login_failures(key).await
+    }
+
+    async fn save_idempotency_record(&self, record: &IdempotencyRecord) -> Result<()> {
+        self.inner.save_idempotency_record(record).await
+    }
+
+    async fn load_idempotency_record(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+        self.inner.load_idempotency_record(scope, key).await
+    }
+
+    async fn delete_expired_idempotency_records(&self, now: i64) -> Result<usize> {
+        self.inner.delete_expired_idempotency_records(now).await
+    }
+
+    async fn save_share_token(&self, token: &ShareToken) -> Result<()> {
+        self.inner.save_share_token(token).await
+    }
+
+    async fn load_share_token(&self, id: &str) -> Result<Option<ShareToken>> {
+        self.inner.load_share_token(id).await
+    }
+
+    async fn load_share_tokens(&self, group_id: &str) -> Result<Vec<ShareToken>> {
+        self.inner.load_share_tokens(group_id).await
+    }
+
+    async fn save_translation(&self, translation: &MessageTranslation) -> Result<()> {
+        self.inner.save_translation(translation).await
+    }
+
+    async fn load_translation(&self, message_id: &str, language: &str) -> Result<Option<MessageTranslation>> {
+        self.inner.load_translation(message_id, language).await
+    }
+
+    async fn save_org_change(&self, entry: &OrgChangeEntry) -> Result<()> {
+        self.inner.save_org_change(entry).await
+    }
+
+    async fn load_org_changes(&self, since: i64, limit: usize) -> Result<Vec<OrgChangeEntry>> {
+        self.inner.load_org_changes(since, limit).await
+    }
+
+    async fn save_digest(&self, digest: &Digest) -> Result<()> {
+        self.inner.save_digest(digest).await
+    }
+
+    async fn load_digests(&self, limit: usize) -> Result<Vec<Digest>> {
+        self.inner.load_digests(limit).await
+    }
+
+    async fn save_app_state(&self, key: &str, value: &Value) -> Result<()> {
+        self.write(BufferedWrite::SaveAppState {
+            key: key.to_string(),
+            value: value.clone(),
+        })
+        .await
+    }
+
+    async fn load_app_state(&self, key: &str) -> Result<Option<Value>> {
+        self.catch_up().await;
+        let buffered = self.pending_writes().await.into_iter().rev().find_map(|w| match w {
+            BufferedWrite::SaveAppState { key: k, value } if k == key => Some(Some(value)),
+            BufferedWrite::DeleteAppState { key: k } if k == key => Some(None),
+            _ => None,
+        });
+        if let Some(value) = buffered {
+            return Ok(value);
+        }
+        let cache_key = format!("app_state:{}", key);
+        self.read(cache_key, self.inner.load_app_state(key).await).await
+    }
+
+    async fn delete_app_state(&self, key: &str) -> Result<()> {
+        self.write(BufferedWrite::DeleteAppState { key: key.to_string() }).await
+    }
+
+    async fn add_reaction(&self, reaction: &MessageReaction) -> Result<bool> {
+        self.inner.add_reaction(reaction).await
+    }
+
+    async fn remove_reaction(&self, message_id: &str, reactor_id: &str, emoji: &str) -> Result<bool> {
+        self.inner.remove_reaction(message_id, reactor_id, emoji).await
+    }
+
+    async fn load_reactions(&self, message_ids: &[String]) -> Result<Vec<MessageReaction>> {
+        self.inner.load_reactions(message_ids).await
+    }
+
+    async fn add_pin(&self, pin: &MessagePin) -> Result<bool> {
+        self.inner.add_pin(pin).await
+    }
+
+    async fn remove_pin(&self, session_id: &str, message_id: &str) -> Result<bool> {
+        self.inner.remove_pin(session_id, message_id).await
+    }
+
+    async fn load_pins(&self, session_id: &str) -> Result<Vec<MessagePin>> {
+        self.inner.load_pins(session_id).await
+    }
+
+    async fn add_bookmark(&self, bookmark: &MessageBookmark) -> Result<bool> {
+        self.inner.add_bookmark(bookmark).await
+    }
+
+    async fn remove_bookmark(&self, owner_id: &str, message_id: &str) -> Result<bool> {
+        self.inner.remove_bookmark(owner_id, message_id).await
+    }
+
+    async fn load_bookmarks(&self, owner_id: &str) -> Result<Vec<MessageBookmark>> {
+        self.inner.load_bookmarks(owner_id).await
+    }
+
+    async fn save_task(&self, task: &Task) -> Result<()> {
+        self.inner.save_task(task).await
+    }
+
+    async fn load_task(&self, task_id: &str) -> Result<Option<Task>> {
+        self.inner.load_task(task_id).await
+    }
+
+    async fn load_tasks(&self, filter: TaskFilter) -> Result<Vec<Task>> {
+        self.inner.load_tasks(filter).await
+    }
+
+    async fn delete_task(&self, task_id: &str) -> Result<bool> {
+        self.inner.delete_task(task_id).await
+    }
+
+    async fn acquire_lease(&self, name: &str, holder: &str, now: i64, ttl_secs: i64) -> Result<Lease> {
+        self.inner.acquire_lease(name, holder, now, ttl_secs).await
+    }
+
+    async fn load_lease(&self, name: &str) -> Result<Option<Lease>> {
+        self.inner.load_lease(name).await
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str, token: u64) -> Result<bool> {
+        self.inner.release_lease(name, holder, token).await
+    }
+
+    async fn save_quarantined_output(&self, output: &QuarantinedOutput) -> Result<()> {
+        self.inner.save_quarantined_output(output).await
+    }
+
+    async fn load_quarantined_output(&self, id: &str) -> Result<Option<QuarantinedOutput>> {
+        self.inner.load_quarantined_output(id).await
+    }
+
+    async fn load_quarantined_outputs(&self, pending_only: bool, limit: usize) -> Result<Vec<QuarantinedOutput>> {
+        self.inner.load_quarantined_outputs(pending_only, limit).await
+    }
+
+    async fn append_scratchpad_entry(&self, entry: &ScratchpadEntry, max_bytes: usize) -> Result<usize> {
+        self.inner.append_scratchpad_entry(entry, max_bytes).await
+    }
+
+    async fn load_scratchpad(&self, run_id: &str) -> Result<Vec<ScratchpadEntry>> {
+        self.inner.load_scratchpad(run_id).await
+    }
+
+    async fn save_chat_session(&self, session: &ChatSession) -> Result<()> {
+        self.inner.save_chat_session(session).await
+    }
+
+    async fn load_chat_session(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        self.inner.load_chat_session(session_id).await
+    }
+
+    async fn load_chat_sessions(&self, include_closed: bool) -> Result<Vec<ChatSession>> {
+        self.inner.load_chat_sessions(include_closed).await
+    }
+
+    async fn save_group_stats(&self, stats: &GroupStats) -> Result<()> {
+        self.inner.save_group_stats(stats).await
+    }
+
+    async fn load_group_stats(&self, group_id: &str) -> Result<Option<GroupStats>> {
+        self.inner.load_group_stats(group_id).await
+    }
+
+    async fn scan_integrity(&self) -> Result<Vec<IntegrityFinding>> {
+        self.inner.scan_integrity().await
+    }
+
+    async fn quarantine_row(&self, table: &str, row_id: &str, reason: &str) -> Result<bool> {
+        self.inner.quarantine_row(table, row_id, reason).await
+    }
+
+    async fn load_quarantined_rows(&self) -> Result<Vec<QuarantinedRow>> {
+        self.inner.load_quarantined_rows().await
+    }
+
+    /// 存储恢复时顺带重放缓冲；缓冲未满时仍视为可用（降级），满了才算失败
+    async fn health_check(&self) -> Result<()> {
+        self.catch_up().await;
+        match self.inner.health_check().await {
+            Ok(()) => {
+                if self.buffered_writes() == 0 {
+                    self.degraded.store(false, Ordering::Relaxed);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.degraded.store(true, Ordering::Relaxed);
+                if self.buffered_writes() >= self.config.max_buffered_writes {
+                    Err(e.context("write buffer is full"))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    fn degraded(&self) -> Option<String> {
+        self.is_degraded().then(|| {
+            format!(
+                "store unavailable, serving cached reads; {} of {} writes buffered",
+                self.buffered_writes(),
+                self.config.max_buffered_writes
+            )
+        })
+    }
+
+    fn take_extracted_api_keys(&self) -> Vec<ExtractedSecret> {
+        self.inner.take_extracted_api_keys()
+    }
+
+    fn backend_info(&self) -> StoreBackendInfo {
+        self.inner.backend_info()
+    }
+}
@@ -0,0 +1,492 @@
+//! 故障注入存储包装（`chaos` 特性）
+//!
+//! 每次调用前询问 [`FaultInjector`]（子系统 `store`，操作名为方法名，如 `save_message`），
+//! 命中错误或丢弃规则时返回错误，不访问内层存储。
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::{MessageFilter, MessageTierCounts, Store, StoreBackendInfo, TaskFilter};
+use crate::core::chaos::{FaultInjector, Subsystem};
+use crate::core::integrity::{IntegrityFinding, QuarantinedRow};
+use crate::core::secrets::ExtractedSecret;
+use crate::domain::idempotency::IdempotencyRecord;
+use crate::domain::lease::Lease;
+use crate::domain::moderation::QuarantinedOutput;
+use crate::domain::chat_session::ChatSession;
+use crate::domain::group_stats::GroupStats;
+use crate::domain::scratchpad::ScratchpadEntry;
+use crate::domain::share_token::ShareToken;
+use crate::domain::invitation_code::InvitationCode;
+use crate::domain::digest::Digest;
+use crate::domain::org_change::OrgChangeEntry;
+use crate::domain::tool::ToolUsage;
+use crate::domain::user::LoginFailures;
+use crate::domain::{
+    Agent, Department, Escalation, Group, Message, MessageBookmark, MessagePin, MessageReaction, MessageTranslation,
+    Organization, RoleRevision, Task,
+};
+
+/// 故障注入存储包装
+pub struct ChaosStore {
+    inner: Arc<dyn Store>,
+    injector: Arc<FaultInjector>,
+}
+
+impl ChaosStore {
+    /// 包装存储
+    pub fn new(inner: Arc<dyn Store>, injector: Arc<FaultInjector>) -> Self {
+        Self { inner, injector }
+    }
+
+    async fn fault(&self, operation: &str) -> Result<()> {
+        self.injector.inject(Subsystem::Store, operation).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Store for ChaosStore {
+    async fn save_organization(&self, org: &Organization) -> Result<()> {
+        self.fault("save_organization").await?;
+        self.inner.save_organization(org).await
+    }
+
+    async fn load_organization(&self) -> Result<Organization> {
+        self.fault("load_organization").await?;
+        self.inner.load_organization().await
+    }
+
+    async fn load_department(&self, department_id: &str) -> Result<Option<Department>> {
+        self.fault("load_department").await?;
+        self.inner.load_department(department_id).await
+    }
+
+    async fn load_department_members(&self, department_id: &str) -> Result<Vec<Agent>> {
+        self.fault("load_department_members").await?;
+        self.inner.load_department_members(department_id).await
+    }
+
+    async fn load_agent(&self, agent_id: &str) -> Result<Option<Agent>> {
+        self.fault("load_agent").await?;
+        self.inner.load_agent(agent_id).await
+    }
+
+    async fn save_group(&self, group: &Group) -> Result<()> {
+        self.fault("save_group").await?;
+        self.inner.save_group(group).await
+    }
+
+    async fn load_groups(&self) -> Result<Vec<Group>> {
+        self.fault("load_groups").await?;
+        self.inner.load_groups().await
+    }
+
+    async fn delete_group(&self, group_id: &str) -> Result<()> {
+        self.fault("delete_group").await?;
+        self.inner.delete_group(group_id).await
+    }
+
+    async fn save_message(&self, message: &Message) -> Result<()> {
+        self.fault("save_message").await?;
+        self.inner.save_message(message).await
+    }
+
+    async fn save_messages(&self, messages: &[Message]) -> Result<()> {
+        self.fault("save_messages").await?;
+        self.inner.save_messages(messages).await
+    }
+
+    async fn load_messages(&self, filter: MessageFilter) -> Result<Vec<Message>> {
+        self.fault("load_messages").await?;
+        self.inner.load_messages(filter).await
+    }
+
+    async fn load_message(&self, message_id: &str) -> Result<Option<Message>> {
+        self.fault("load_message").await?;
+        self.inner.load_message(message_id).await
+    }
+
+    async fn archive_messages_before(&self, timestamp: i64) -> Result<usize> {
+        self.fault("archive_messages_before").await?;
+        self.inner.archive_messages_before(timestamp).await
+    }
+
+    async fn delete_message(&self, message_id: &str) -> Result<bool> {
+        self.fault("delete_message").await?;
+        self.inner.delete_message(message_id).await
+    }
+
+    async fn purge_messages_before(&self, timestamp: i64) -> Result<usize> {
+        self.fault("purge_messages_before").await?;
+        self.inner.purge_messages_before(timestamp).await
+    }
+
+    async fn message_tier_counts(&self) -> Result<MessageTierCounts> {
+        self.fault("message_tier_counts").await?;
+        self.inner.message_tier_counts().await
+    }
+
+    async fn load_messages_by_agent(&self, agent_id: &str, limit: usize) -> Result<Vec<Message>> {
+        self.fault("load_messages_by_agent").await?;
+        self.inner.load_messages_by_agent(agent_id, limit).await
+    }
+
+    async fn load_messages_by_group(&self, group_id: &str, limit: usize) -> Result<Vec<Message>> {
+        self.fault("load_messages_by_group").await?;
+        self.inner.load_messages_by_group(group_id, limit).await
+    }
+
+    async fn max_group_seq(&self, group_id: &str) -> Result<u64> {
+        self.fault("max_group_seq").await?;
+        self.inner.max_group_seq(group_id).await
+    }
+
+    async fn load_group_messages_by_seq(&self, group_id: &str, from_seq: u64, to_seq: u64) -> Result<Vec<Message>> {
+        self.fault("load_group_messages_by_seq").await?;
+        self.inner.load_group_messages_by_seq(group_id, from_seq, to_seq).await
+    }
+
+    async fn save_user(&self, user: &crate::domain::user::User) -> Result<()> {
+        self.fault("save_user").await?;
+        self.inner.save_user(user).await
+    }
+
+    async fn load_user_by_username(&self, username: &str) -> Result<Option<crate::domain::user::User>> {
+        self.fault("load_user_by_username").await?;
+        self.inner.load_user_by_username(username).await
+    }
+
+    async fn load_users(&self) -> Result<Vec<crate::domain::user::User>> {
+        self.fault("load_users").await?;
+        self.inner.load_users().await
+    }
+
+    async fn save_invitation_code(&self, code: &InvitationCode) -> Result<()> {
+        self.fault("save_invitation_code").await?;
+        self.inner.save_invitation_code(code).await
+    }
+
+    async fn load_invitation_code_by_code(&self, code: &str) -> Result<Option<InvitationCode>> {
+        self.fault("load_invitation_code_by_code").await?;
+        self.inner.load_invitation_code_by_code(code).await
+    }
+
+    async fn load_invitation_codes(&self) -> Result<Vec<InvitationCode>> {
+        self.fault("load_invitation_codes").await?;
+        self.inner.load_invitation_codes().await
+    }
+
+    async fn update_invitation_code(&self, code: &InvitationCode) -> Result<()> {
+        self.fault("update_invitation_code").await?;
+        self.inner.update_invitation_code(code).await
+    }
+
+    async fn load_invitation_codes_by_creator(&self, creator_id: &str) -> Result<Vec<InvitationCode>> {
+        self.fault("load_invitation_codes_by_creator").await?;
+        self.inner.load_invitation_codes_by_creator(creator_id).await
+    }
+
+    async fn save_tool_stats(&self, date: &str, stats: &[ToolUsage]) -> Result<()> {
+        self.fault("save_tool_stats").await?;
+        self.inner.save_tool_stats(date, stats).await
+    }
+
+    async fn load_tool_stats(&self, date: &str) -> Result<Vec<ToolUsage>> {
+        self.fault("load_tool_stats").await?;
+        self.inner.load_tool_stats(date).await
+    }
+
+    async fn save_agent_preferences(&self, agent_id: &str, preferences: &serde_json::Value) -> Result<()> {
+        self.fault("save_agent_preferences").await?;
+        self.inner.save_agent_preferences(agent_id, preferences).await
+    }
+
+    async fn load_agent_preferences(&self, agent_id: &str) -> Result<Option<serde_json::Value>> {
+        self.fault("load_agent_preferences").await?;
+        self.inner.load_agent_preferences(agent_id).await
+    }
+
+    async fn delete_agent_preferences(&self, agent_id: &str) -> Result<()> {
+        self.fault("delete_agent_preferences").await?;
+        self.inner.delete_agent_preferences(agent_id).await
+    }
+
+    async fn save_role_revision(&self, revision: &RoleRevision) -> Result<()> {
+        self.fault("save_role_revision").await?;
+        self.inner.save_role_revision(revision).await
+    }
+
+    async fn load_role_revisions(&self, agent_id: &str) -> Result<Vec<RoleRevision>> {
+        self.fault("load_role_revisions").await?;
+        self.inner.load_role_revisions(agent_id).await
+    }
+
+    async fn save_escalation(&self, escalation: &Escalation) -> Result<()> {
+        self.fault("save_escalation").await?;
+        self.inner.save_escalation(escalation).await
+    }
+
+    async fn load_escalation(&self, message_id: &str) -> Result<Option<Escalation>> {
+        self.fault("load_escalation").await?;
+        self.inner.load_escalation(message_id).await
+    }
+
+    async fn save_login_failures(&self, failures: &LoginFailures) -> Result<()> {
+        self.fault("save_login_failures").await?;
+        self.inner.save_login_failures(failures).await
+    }
+
+    async fn load_login_failures(&self, key: &str) -> Result<Option<LoginFailures>> {
+        self.fault("load_login_failures").await?;
+        self.inner.load_login_failures(key).await
+    }
+
+    async fn delete_login_failures(&self, key: &str) -> Result<()> {
+        self.fault("delete_login_failures").await?;
+        self.inner.delete_login_failures(key).await
+    }
+
+    async fn save_idempotency_record(&self, record: &IdempotencyRecord) -> Result<()> {
+        self.fault("save_idempotency_record").await?;
+        self.inner.save_idempotency_record(record).await
+    }
+
+    async fn load_idempotency_record(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+        self.fault("load_idempotency_record").await?;
+        self.inner.load_idempotency_record(scope, key).await
+    }
+
+    async fn delete_expired_idempotency_records(&self, now: i64) -> Result<usize> {
+        self.fault("delete_expired_idempotency_records").await?;
+        self.inner.delete_expired_idempotency_records(now).await
+    }
+
+    async fn save_share_token(&self, token: &ShareToken) -> Result<()> {
+        self.fault("save_share_token").await?;
+        self.inner.save_share_token(token).await
+    }
+
+    async fn load_share_token(&self, id: &str) -> Result<Option<ShareToken>> {
+        self.fault("load_share_token").await?;
+        self.inner.load_share_token(id).await
+    }
+
+    async fn load_share_tokens(&self, group_id: &str) -> Result<Vec<ShareToken>> {
+        self.fault("load_share_tokens").await?;
+        self.inner.load_share_tokens(group_id).await
+    }
+
+    async fn save_translation(&self, translation: &MessageTranslation) -> Result<()> {
+        self.fault("save_translation").await?;
+        self.inner.save_translation(translation).await
+    }
+
+    async fn load_translation(&self, message_id: &str, language: &str) -> Result<Option<MessageTranslation>> {
+        self.fault("load_translation").await?;
+        self.inner.load_translation(message_id, language).await
+    }
+
+    async fn save_org_change(&self, entry: &OrgChangeEntry) -> Result<()> {
+        self.fault("save_org_change").await?;
+        self.inner.save_org_change(entry).await
+    }
+
+    async fn load_org_changes(&self, since: i64, limit: usize) -> Result<Vec<OrgChangeEntry>> {
+        self.fault("load_org_changes").await?;
+        self.inner.load_org_changes(since, limit).await
+    }
+
+    async fn save_digest(&self, digest: &Digest) -> Result<()> {
+        self.fault("save_digest").await?;
+        self.inner.save_digest(digest).await
+    }
+
+    async fn load_digests(&self, limit: usize) -> Result<Vec<Digest>> {
+        self.fault("load_digests").await?;
+        self.inner.load_digests(limit).await
+    }
+
+    async fn save_app_state(&self, key: &str, value: &serde_json::Value) -> Result<()> {
+        self.fault("save_app_state").await?;
+        self.inner.save_app_state(key, value).await
+    }
+
+    async fn load_app_state(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        self.fault("load_app_state").await?;
+        self.inner.load_app_state(key).await
+    }
+
+    async fn delete_app_state(&self, key: &str) -> Result<()> {
+        self.fault("delete_app_state").await?;
+        self.inner.delete_app_state(key).await
+    }
+
+    async fn add_reaction(&self, reaction: &MessageReaction) -> Result<bool> {
+        self.fault("add_reaction").await?;
+        self.inner.add_reaction(reaction).await
+    }
+
+    async fn remove_reaction(&self, message_id: &str, reactor_id: &str, emoji: &str) -> Result<bool> {
+        self.fault("remove_reaction").await?;
+        self.inner.remove_reaction(message_id, reactor_id, emoji).await
+    }
+
+    async fn load_reactions(&self, message_ids: &[String]) -> Result<Vec<MessageReaction>> {
+        self.fault("load_reactions").await?;
+        self.inner.load_reactions(message_ids).await
+    }
+
+    async fn add_pin(&self, pin: &MessagePin) -> Result<bool> {
+        self.fault("add_pin").await?;
+        self.inner.add_pin(pin).await
+    }
+
+    async fn remove_pin(&self, session_id: &str, message_id: &str) -> Result<bool> {
+        self.fault("remove_pin").await?;
+        self.inner.remove_pin(session_id, message_id).await
+    }
+
+    async fn load_pins(&self, session_id: &str) -> Result<Vec<MessagePin>> {
+        self.fault("load_pins").await?;
+        self.inner.load_pins(session_id).await
+    }
+
+    async fn add_bookmark(&self, bookmark: &MessageBookmark) -> Result<bool> {
+        self.fault("add_bookmark").await?;
+        self.inner.add_bookmark(bookmark).await
+    }
+
+    async fn remove_bookmark(&self, owner_id: &str, message_id: &str) -> Result<bool> {
+        self.fault("remove_bookmark").await?;
+        self.inner.remove_bookmark(owner_id, message_id).await
+    }
+
+    async fn load_bookmarks(&self, owner_id: &str) -> Result<Vec<MessageBookmark>> {
+        self.fault("load_bookmarks").await?;
+        self.inner.load_bookmarks(owner_id).await
+    }
+
+    async fn save_task(&self, task: &Task) -> Result<()> {
+        self.fault("save_task").await?;
+        self.inner.save_task(task).await
+    }
+
+    async fn load_task(&self, task_id: &str) -> Result<Option<Task>> {
+        self.fault("load_task").await?;
+        self.inner.load_task(task_id).await
+    }
+
+    async fn load_tasks(&self, filter: TaskFilter) -> Result<Vec<Task>> {
+        self.fault("load_tasks").await?;
+        self.inner.load_tasks(filter).await
+    }
+
+    async fn delete_task(&self, task_id: &str) -> Result<bool> {
+        self.fault("delete_task").await?;
+        self.inner.delete_task(task_id).await
+    }
+
+    async fn acquire_lease(&self, name: &str, holder: &str, now: i64, ttl_secs: i64) -> Result<Lease> {
+        self.fault("acquire_lease").await?;
+        self.inner.acquire_lease(name, holder, now, ttl_secs).await
+    }
+
+    async fn load_lease(&self, name: &str) -> Result<Option<Lease>> {
+        self.fault("load_lease").await?;
+        self.inner.load_lease(name).await
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str, token: u64) -> Result<bool> {
+        self.fault("release_lease").await?;
+        self.inner.release_lease(name, holder, token).await
+    }
+
+    async fn save_quarantined_output(&self, output: &QuarantinedOutput) -> Result<()> {
+        self.fault("save_quarantined_output").await?;
+        self.inner.save_quarantined_output(output).await
+    }
+
+    async fn load_quarantined_output(&self, id: &str) -> Result<Option<QuarantinedOutput>> {
+        self.fault("load_quarantined_output").await?;
+        self.inner.load_quarantined_output(id).await
+    }
+
+    async fn load_quarantined_outputs(&self, pending_only: bool, limit: usize) -> Result<Vec<QuarantinedOutput>> {
+        self.fault("load_quarantined_outputs").await?;
+        self.inner.load_quarantined_outputs(pending_only, limit).await
+    }
+
+    async fn append_scratchpad_entry(&self, entry: &ScratchpadEntry, max_bytes: usize) -> Result<usize> {
+        self.fault("append_scratchpad_entry").await?;
+        self.inner.append_scratchpad_entry(entry, max_bytes).await
+    }
+
+    async fn load_scratchpad(&self, run_id: &str) -> Result<Vec<ScratchpadEntry>> {
+        self.fault("load_scratchpad").await?;
+        self.inner.load_scratchpad(run_id).await
+    }
+
+    async fn save_chat_session(&self, session: &ChatSession) -> Result<()> {
+        self.fault("save_chat_session").await?;
+        self.inner.save_chat_session(session).await
+    }
+
+    async fn load_chat_session(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        self.fault("load_chat_session").await?;
+        self.inner.load_chat_session(session_id).await
+    }
+
+    async fn load_chat_sessions(&self, include_closed: bool) -> Result<Vec<ChatSession>> {
+        self.fault("load_chat_sessions").await?;
+        self.inner.load_chat_sessions(include_closed).await
+    }
+
+    async fn save_group_stats(&self, stats: &GroupStats) -> Result<()> {
+        self.fault("save_group_stats").await?;
+        self.inner.save_group_stats(stats).await
+    }
+
+    async fn load_group_stats(&self, group_id: &str) -> Result<Option<GroupStats>> {
+        self.fault("load_group_stats").await?;
+        self.inner.load_group_stats(group_id).await
+    }
+
+    async fn scan_integrity(&self) -> Result<Vec<IntegrityFinding>> {
+        self.fault("scan_integrity").await?;
+        self.inner.scan_integrity().await
+    }
+
+    async fn quarantine_row(&self, table: &str, row_id: &str, reason: &str) -> Result<bool> {
+        self.fault("quarantine_row").await?;
+        self.inner.quarantine_row(table, row_id, reason).await
+    }
+
+    async fn load_quarantined_rows(&self) -> Result<Vec<QuarantinedRow>> {
+        self.fault("load_quarantined_rows").await?;
+        self.inner.load_quarantined_rows().await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.fault("health_check").await?;
+        self.inner.health_check().await
+    }
+
+    fn degraded(&self) -> Option<String> {
+        self.inner.degraded()
+    }
+
+    fn take_extracted_api_keys(&self) -> Vec<ExtractedSecret> {
+        self.inner.take_extracted_api_keys()
+    }
+
+    fn backend_info(&self) -> StoreBackendInfo {
+        self.inner.backend_info()
+    }
+
+    fn path(&self) -> Option<String> {
+        self.inner.path()
+    }
+}
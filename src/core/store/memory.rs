@@ -2,15 +2,29 @@
 //!
 //! 默认的存储实现，数据仅在内存中，重启后丢失
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
 use async_trait::async_trait;
 use tokio::sync::RwLock;
 
-use crate::domain::{Group, Message, MessageTarget, Organization};
+use crate::domain::{
+    Agent, Department, Escalation, Group, Message, MessageBookmark, MessagePin, MessageReaction, MessageTranslation,
+    Organization, RoleRevision, Task,
+};
+use crate::domain::idempotency::IdempotencyRecord;
+use crate::domain::lease::Lease;
+use crate::domain::moderation::QuarantinedOutput;
+use crate::domain::chat_session::ChatSession;
+use crate::domain::group_stats::GroupStats;
+use crate::domain::scratchpad::ScratchpadEntry;
+use crate::domain::share_token::ShareToken;
+use crate::domain::digest::Digest;
+use crate::domain::org_change::OrgChangeEntry;
+use crate::domain::tool::ToolUsage;
+use crate::domain::user::LoginFailures;
 
-use super::{MessageFilter, Store};
+use super::{MessageFilter, MessageTierCounts, Store, StoreBackendInfo, TaskFilter};
 
 /// 内存存储
 ///
@@ -19,6 +33,29 @@ pub struct MemoryStore {
     organization: RwLock<Option<Organization>>,
     groups: RwLock<HashMap<String, Group>>,
     messages: RwLock<Vec<Message>>,
+    archived_messages: RwLock<Vec<Message>>,
+    archived_before: RwLock<Option<i64>>,
+    tool_stats: RwLock<HashMap<String, HashMap<String, ToolUsage>>>,
+    agent_preferences: RwLock<HashMap<String, serde_json::Value>>,
+    role_revisions: RwLock<HashMap<String, Vec<RoleRevision>>>,
+    escalations: RwLock<HashMap<String, Escalation>>,
+    login_failures: RwLock<HashMap<String, LoginFailures>>,
+    app_state: RwLock<HashMap<String, serde_json::Value>>,
+    reactions: RwLock<HashMap<String, Vec<MessageReaction>>>,
+    pins: RwLock<HashMap<String, Vec<MessagePin>>>,
+    bookmarks: RwLock<HashMap<String, Vec<MessageBookmark>>>,
+    idempotency: RwLock<HashMap<(String, String), IdempotencyRecord>>,
+    share_tokens: RwLock<HashMap<String, ShareToken>>,
+    translations: RwLock<HashMap<(String, String), MessageTranslation>>,
+    org_changes: RwLock<Vec<OrgChangeEntry>>,
+    digests: RwLock<Vec<Digest>>,
+    tasks: RwLock<HashMap<String, Task>>,
+    leases: RwLock<HashMap<String, Lease>>,
+    quarantined_outputs: RwLock<HashMap<String, QuarantinedOutput>>,
+    /// 按运行ID分组的草稿，最早的在前
+    scratchpads: RwLock<HashMap<String, Vec<ScratchpadEntry>>>,
+    chat_sessions: RwLock<HashMap<String, ChatSession>>,
+    group_stats: RwLock<HashMap<String, GroupStats>>,
 }
 
 impl MemoryStore {
@@ -28,8 +65,45 @@ impl MemoryStore {
             organization: RwLock::new(None),
             groups: RwLock::new(HashMap::new()),
             messages: RwLock::new(Vec::new()),
+            archived_messages: RwLock::new(Vec::new()),
+            archived_before: RwLock::new(None),
+            tool_stats: RwLock::new(HashMap::new()),
+            agent_preferences: RwLock::new(HashMap::new()),
+            role_revisions: RwLock::new(HashMap::new()),
+            escalations: RwLock::new(HashMap::new()),
+            login_failures: RwLock::new(HashMap::new()),
+            app_state: RwLock::new(HashMap::new()),
+            reactions: RwLock::new(HashMap::new()),
+            pins: RwLock::new(HashMap::new()),
+            bookmarks: RwLock::new(HashMap::new()),
+            idempotency: RwLock::new(HashMap::new()),
+            share_tokens: RwLock::new(HashMap::new()),
+            translations: RwLock::new(HashMap::new()),
+            org_changes: RwLock::new(Vec::new()),
+            digests: RwLock::new(Vec::new()),
+            tasks: RwLock::new(HashMap::new()),
+            leases: RwLock::new(HashMap::new()),
+            quarantined_outputs: RwLock::new(HashMap::new()),
+            scratchpads: RwLock::new(HashMap::new()),
+            chat_sessions: RwLock::new(HashMap::new()),
+            group_stats: RwLock::new(HashMap::new()),
         }
     }
+
+    /// 删除这些消息的表情回应、置顶、书签和译文
+    async fn remove_message_dependents(&self, message_ids: &HashSet<String>) {
+        if message_ids.is_empty() {
+            return;
+        }
+        self.reactions.write().await.retain(|message_id, _| !message_ids.contains(message_id));
+        for pins in self.pins.write().await.values_mut() {
+            pins.retain(|p| !message_ids.contains(&p.message_id));
+        }
+        for bookmarks in self.bookmarks.write().await.values_mut() {
+            bookmarks.retain(|b| !message_ids.contains(&b.message_id));
+        }
+        self.translations.write().await.retain(|(message_id, _), _| !message_ids.contains(message_id));
+    }
 }
 
 impl Default for MemoryStore {
@@ -51,6 +125,24 @@ impl Store for MemoryStore {
         Ok(stored.clone().unwrap_or_default())
     }
 
+    async fn load_department(&self, department_id: &str) -> Result<Option<Department>> {
+        let stored = self.organization.read().await;
+        Ok(stored.as_ref().and_then(|org| org.find_department(department_id)).cloned())
+    }
+
+    async fn load_department_members(&self, department_id: &str) -> Result<Vec<Agent>> {
+        let stored = self.organization.read().await;
+        Ok(stored
+            .as_ref()
+            .map(|org| org.get_department_members(department_id).into_iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn load_agent(&self, agent_id: &str) -> Result<Option<Agent>> {
+        let stored = self.organization.read().await;
+        Ok(stored.as_ref().and_then(|org| org.find_agent(agent_id)).cloned())
+    }
+
     async fn save_group(&self, group: &Group) -> Result<()> {
         let mut groups = self.groups.write().await;
         groups.insert(group.id.clone(), group.clone());
@@ -83,62 +175,471 @@ impl Store for MemoryStore {
     async fn load_messages(&self, filter: MessageFilter) -> Result<Vec<Message>> {
         let messages = self.messages.read().await;
 
-        let mut result: Vec<Message> = messages
+        let mut matched: Vec<Message> = messages.iter().filter(|m| filter.matches(m)).cloned().collect();
+        if MessageTierCounts::reaches_archive(*self.archived_before.read().await, &filter) {
+            let archived = self.archived_messages.read().await;
+            matched.extend(archived.iter().filter(|m| filter.matches(m)).cloned());
+        }
+        Ok(filter.apply(matched))
+    }
+
+    async fn load_message(&self, message_id: &str) -> Result<Option<Message>> {
+        if let Some(message) = self.messages.read().await.iter().find(|m| m.id == message_id) {
+            return Ok(Some(message.clone()));
+        }
+        Ok(self.archived_messages.read().await.iter().find(|m| m.id == message_id).cloned())
+    }
+
+    async fn max_group_seq(&self, group_id: &str) -> Result<u64> {
+        let messages = self.messages.read().await;
+        let archived = self.archived_messages.read().await;
+        Ok(messages
+            .iter()
+            .chain(archived.iter())
+            .filter(|m| m.target_group() == Some(group_id))
+            .filter_map(Message::group_seq)
+            .max()
+            .unwrap_or(0))
+    }
+
+    async fn load_group_messages_by_seq(&self, group_id: &str, from_seq: u64, to_seq: u64) -> Result<Vec<Message>> {
+        let messages = self.messages.read().await;
+        let archived = self.archived_messages.read().await;
+        let mut matched: Vec<Message> = messages
+            .iter()
+            .chain(archived.iter())
+            .filter(|m| m.target_group() == Some(group_id))
+            .filter(|m| m.group_seq().is_some_and(|seq| (from_seq..=to_seq).contains(&seq)))
+            .cloned()
+            .collect();
+        matched.sort_by_key(|m| m.group_seq());
+        Ok(matched)
+    }
+
+    async fn archive_messages_before(&self, timestamp: i64) -> Result<usize> {
+        let mut messages = self.messages.write().await;
+        let (old, hot): (Vec<Message>, Vec<Message>) = messages.drain(..).partition(|m| m.timestamp < timestamp);
+        *messages = hot;
+        let moved = old.len();
+        self.archived_messages.write().await.extend(old);
+        let mut boundary = self.archived_before.write().await;
+        *boundary = Some(boundary.map_or(timestamp, |b| b.max(timestamp)));
+        Ok(moved)
+    }
+
+    async fn delete_message(&self, message_id: &str) -> Result<bool> {
+        let mut deleted = false;
+        for tier in [&self.messages, &self.archived_messages] {
+            let mut messages = tier.write().await;
+            let before = messages.len();
+            messages.retain(|m| m.id != message_id);
+            deleted |= messages.len() < before;
+        }
+        self.remove_message_dependents(&HashSet::from([message_id.to_string()])).await;
+        Ok(deleted)
+    }
+
+    async fn purge_messages_before(&self, timestamp: i64) -> Result<usize> {
+        let mut purged = 0;
+        let mut purged_ids = HashSet::new();
+        for tier in [&self.messages, &self.archived_messages] {
+            let mut messages = tier.write().await;
+            let (old, kept): (Vec<Message>, Vec<Message>) = messages.drain(..).partition(|m| m.timestamp < timestamp);
+            *messages = kept;
+            purged += old.len();
+            purged_ids.extend(old.into_iter().map(|m| m.id));
+        }
+        self.remove_message_dependents(&purged_ids).await;
+        Ok(purged)
+    }
+
+    async fn message_tier_counts(&self) -> Result<MessageTierCounts> {
+        Ok(MessageTierCounts {
+            hot: self.messages.read().await.len(),
+            archived: self.archived_messages.read().await.len(),
+            archived_before: *self.archived_before.read().await,
+        })
+    }
+    async fn save_tool_stats(&self, date: &str, stats: &[ToolUsage]) -> Result<()> {
+        let mut tool_stats = self.tool_stats.write().await;
+        let day = tool_stats.entry(date.to_string()).or_default();
+        for usage in stats {
+            day.insert(usage.tool_id.clone(), usage.clone());
+        }
+        Ok(())
+    }
+
+    async fn load_tool_stats(&self, date: &str) -> Result<Vec<ToolUsage>> {
+        let tool_stats = self.tool_stats.read().await;
+        Ok(tool_stats
+            .get(date)
+            .map(|day| day.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn save_agent_preferences(&self, agent_id: &str, preferences: &serde_json::Value) -> Result<()> {
+        let mut stored = self.agent_preferences.write().await;
+        stored.insert(agent_id.to_string(), preferences.clone());
+        Ok(())
+    }
+
+    async fn load_agent_preferences(&self, agent_id: &str) -> Result<Option<serde_json::Value>> {
+        let stored = self.agent_preferences.read().await;
+        Ok(stored.get(agent_id).cloned())
+    }
+
+    async fn delete_agent_preferences(&self, agent_id: &str) -> Result<()> {
+        let mut stored = self.agent_preferences.write().await;
+        stored.remove(agent_id);
+        Ok(())
+    }
+
+    async fn save_role_revision(&self, revision: &RoleRevision) -> Result<()> {
+        let mut stored = self.role_revisions.write().await;
+        let revisions = stored.entry(revision.agent_id.clone()).or_default();
+        if revisions.iter().any(|r| r.revision == revision.revision) {
+            return Err(anyhow::anyhow!(
+                "Role revision {} already exists for agent {}",
+                revision.revision,
+                revision.agent_id
+            ));
+        }
+        revisions.push(revision.clone());
+        revisions.sort_by_key(|r| r.revision);
+        Ok(())
+    }
+
+    async fn load_role_revisions(&self, agent_id: &str) -> Result<Vec<RoleRevision>> {
+        let stored = self.role_revisions.read().await;
+        Ok(stored.get(agent_id).cloned().unwrap_or_default())
+    }
+
+    async fn save_escalation(&self, escalation: &Escalation) -> Result<()> {
+        let mut escalations = self.escalations.write().await;
+        escalations.insert(escalation.message_id.clone(), escalation.clone());
+        Ok(())
+    }
+
+    async fn load_escalation(&self, message_id: &str) -> Result<Option<Escalation>> {
+        let escalations = self.escalations.read().await;
+        Ok(escalations.get(message_id).cloned())
+    }
+
+    async fn save_login_failures(&self, failures: &LoginFailures) -> Result<()> {
+        let mut stored = self.login_failures.write().await;
+        stored.insert(failures.key.clone(), failures.clone());
+        Ok(())
+    }
+
+    async fn load_login_failures(&self, key: &str) -> Result<Option<LoginFailures>> {
+        let stored = self.login_failures.read().await;
+        Ok(stored.get(key).cloned())
+    }
+
+    async fn delete_login_failures(&self, key: &str) -> Result<()> {
+        let mut stored = self.login_failures.write().await;
+        stored.remove(key);
+        Ok(())
+    }
+
+    async fn save_idempotency_record(&self, record: &IdempotencyRecord) -> Result<()> {
+        let mut stored = self.idempotency.write().await;
+        stored.insert((record.scope.clone(), record.key.clone()), record.clone());
+        Ok(())
+    }
+
+    async fn load_idempotency_record(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let stored = self.idempotency.read().await;
+        Ok(stored.get(&(scope.to_string(), key.to_string())).cloned())
+    }
+
+    async fn delete_expired_idempotency_records(&self, now: i64) -> Result<usize> {
+        let mut stored = self.idempotency.write().await;
+        let before = stored.len();
+        stored.retain(|_, record| !record.is_expired(now));
+        Ok(before - stored.len())
+    }
+
+    async fn save_share_token(&self, token: &ShareToken) -> Result<()> {
+        self.share_tokens.write().await.insert(token.id.clone(), token.clone());
+        Ok(())
+    }
+
+    async fn load_share_token(&self, id: &str) -> Result<Option<ShareToken>> {
+        Ok(self.share_tokens.read().await.get(id).cloned())
+    }
+
+    async fn load_share_tokens(&self, group_id: &str) -> Result<Vec<ShareToken>> {
+        let mut tokens: Vec<ShareToken> = self
+            .share_tokens
+            .read()
+            .await
+            .values()
+            .filter(|t| t.group_id == group_id)
+            .cloned()
+            .collect();
+        tokens.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(tokens)
+    }
+
+    async fn save_translation(&self, translation: &MessageTranslation) -> Result<()> {
+        let key = (translation.message_id.clone(), translation.language.clone());
+        self.translations.write().await.insert(key, translation.clone());
+        Ok(())
+    }
+
+    async fn load_translation(&self, message_id: &str, language: &str) -> Result<Option<MessageTranslation>> {
+        let key = (message_id.to_string(), language.to_string());
+        Ok(self.translations.read().await.get(&key).cloned())
+    }
+
+    async fn save_org_change(&self, entry: &OrgChangeEntry) -> Result<()> {
+        self.org_changes.write().await.push(entry.clone());
+        Ok(())
+    }
+
+    async fn load_org_changes(&self, since: i64, limit: usize) -> Result<Vec<OrgChangeEntry>> {
+        let stored = self.org_changes.read().await;
+        Ok(stored.iter().rev().filter(|e| e.timestamp > since).take(limit).cloned().collect())
+    }
+
+    async fn save_digest(&self, digest: &Digest) -> Result<()> {
+        let mut stored = self.digests.write().await;
+        stored.retain(|d| d.id != digest.id);
+        stored.push(digest.clone());
+        Ok(())
+    }
+
+    async fn load_digests(&self, limit: usize) -> Result<Vec<Digest>> {
+        let mut stored = self.digests.read().await.clone();
+        stored.sort_by(|a, b| b.period_end.cmp(&a.period_end).then(b.generated_at.cmp(&a.generated_at)));
+        stored.truncate(limit);
+        Ok(stored)
+    }
+
+    async fn save_app_state(&self, key: &str, value: &serde_json::Value) -> Result<()> {
+        let mut stored = self.app_state.write().await;
+        stored.insert(key.to_string(), value.clone());
+        Ok(())
+    }
+
+    async fn load_app_state(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        let stored = self.app_state.read().await;
+        Ok(stored.get(key).cloned())
+    }
+
+    async fn delete_app_state(&self, key: &str) -> Result<()> {
+        let mut stored = self.app_state.write().await;
+        stored.remove(key);
+        Ok(())
+    }
+
+    async fn add_reaction(&self, reaction: &MessageReaction) -> Result<bool> {
+        let mut reactions = self.reactions.write().await;
+        let existing = reactions.entry(reaction.message_id.clone()).or_default();
+        if existing.iter().any(|r| r.reactor_id == reaction.reactor_id && r.emoji == reaction.emoji) {
+            return Ok(false);
+        }
+        existing.push(reaction.clone());
+        Ok(true)
+    }
+
+    async fn remove_reaction(&self, message_id: &str, reactor_id: &str, emoji: &str) -> Result<bool> {
+        let mut reactions = self.reactions.write().await;
+        let Some(existing) = reactions.get_mut(message_id) else {
+            return Ok(false);
+        };
+        let before = existing.len();
+        existing.retain(|r| !(r.reactor_id == reactor_id && r.emoji == emoji));
+        Ok(existing.len() != before)
+    }
+
+    async fn load_reactions(&self, message_ids: &[String]) -> Result<Vec<MessageReaction>> {
+        let reactions = self.reactions.read().await;
+        let mut result: Vec<MessageReaction> = message_ids
             .iter()
-            .filter(|m| {
-                // 发送者过滤
-                if let Some(ref from) = filter.from {
-                    if m.from != *from {
-                        return false;
-                    }
-                }
-
-                // 时间戳过滤
-                if let Some(since) = filter.since {
-                    if m.timestamp < since {
-                        return false;
-                    }
-                }
-
-                // 目标类型和接收者过滤
-                match &m.to {
-                    MessageTarget::Direct(agent_id) => {
-                        if let Some(ref target_type) = filter.target_type {
-                            if target_type != "direct" {
-                                return false;
-                            }
-                        }
-                        if let Some(ref to) = filter.to {
-                            if agent_id != to {
-                                return false;
-                            }
-                        }
-                    }
-                    MessageTarget::Group(group_id) => {
-                        if let Some(ref target_type) = filter.target_type {
-                            if target_type != "group" {
-                                return false;
-                            }
-                        }
-                        if let Some(ref to) = filter.to {
-                            if group_id != to {
-                                return false;
-                            }
-                        }
-                    }
-                }
-
-                true
-            })
+            .filter_map(|id| reactions.get(id))
+            .flatten()
             .cloned()
             .collect();
+        result.sort_by_key(|r| r.timestamp);
+        Ok(result)
+    }
+
+    async fn add_pin(&self, pin: &MessagePin) -> Result<bool> {
+        let mut pins = self.pins.write().await;
+        let existing = pins.entry(pin.session_id.clone()).or_default();
+        if existing.iter().any(|p| p.message_id == pin.message_id) {
+            return Ok(false);
+        }
+        existing.push(pin.clone());
+        Ok(true)
+    }
+
+    async fn remove_pin(&self, session_id: &str, message_id: &str) -> Result<bool> {
+        let mut pins = self.pins.write().await;
+        let Some(existing) = pins.get_mut(session_id) else {
+            return Ok(false);
+        };
+        let before = existing.len();
+        existing.retain(|p| p.message_id != message_id);
+        Ok(existing.len() != before)
+    }
 
-        // 按时间戳降序排序（最新的在前）
-        result.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    async fn load_pins(&self, session_id: &str) -> Result<Vec<MessagePin>> {
+        let mut result = self.pins.read().await.get(session_id).cloned().unwrap_or_default();
+        result.sort_by_key(|p| p.timestamp);
+        Ok(result)
+    }
 
-        // 应用数量限制
-        result.truncate(filter.limit);
+    async fn add_bookmark(&self, bookmark: &MessageBookmark) -> Result<bool> {
+        let mut bookmarks = self.bookmarks.write().await;
+        let existing = bookmarks.entry(bookmark.owner_id.clone()).or_default();
+        if existing.iter().any(|b| b.message_id == bookmark.message_id) {
+            return Ok(false);
+        }
+        existing.push(bookmark.clone());
+        Ok(true)
+    }
 
+    async fn remove_bookmark(&self, owner_id: &str, message_id: &str) -> Result<bool> {
+        let mut bookmarks = self.bookmarks.write().await;
+        let Some(existing) = bookmarks.get_mut(owner_id) else {
+            return Ok(false);
+        };
+        let before = existing.len();
+        existing.retain(|b| b.message_id != message_id);
+        Ok(existing.len() != before)
+    }
+
+    async fn load_bookmarks(&self, owner_id: &str) -> Result<Vec<MessageBookmark>> {
+        let mut result = self.bookmarks.read().await.get(owner_id).cloned().unwrap_or_default();
+        result.sort_by_key(|b| std::cmp::Reverse(b.timestamp));
         Ok(result)
     }
+
+    async fn save_task(&self, task: &Task) -> Result<()> {
+        let mut tasks = self.tasks.write().await;
+        tasks.insert(task.id.clone(), task.clone());
+        Ok(())
+    }
+
+    async fn load_task(&self, task_id: &str) -> Result<Option<Task>> {
+        let tasks = self.tasks.read().await;
+        Ok(tasks.get(task_id).cloned())
+    }
+
+    async fn load_tasks(&self, filter: TaskFilter) -> Result<Vec<Task>> {
+        let tasks = self.tasks.read().await;
+        let matched = tasks.values().filter(|t| filter.matches(t)).cloned().collect();
+        Ok(filter.apply(matched))
+    }
+
+    async fn delete_task(&self, task_id: &str) -> Result<bool> {
+        let mut tasks = self.tasks.write().await;
+        Ok(tasks.remove(task_id).is_some())
+    }
+
+    async fn acquire_lease(&self, name: &str, holder: &str, now: i64, ttl_secs: i64) -> Result<Lease> {
+        let mut leases = self.leases.write().await;
+        let lease = Lease::claim(leases.remove(name), name, holder, now, ttl_secs);
+        leases.insert(name.to_string(), lease.clone());
+        Ok(lease)
+    }
+
+    async fn load_lease(&self, name: &str) -> Result<Option<Lease>> {
+        Ok(self.leases.read().await.get(name).cloned())
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str, token: u64) -> Result<bool> {
+        let mut leases = self.leases.write().await;
+        match leases.get_mut(name) {
+            Some(lease) if lease.holder == holder && lease.token == token => {
+                // 标记为已过期而不是删除，下一任持有者的令牌继续递增
+                lease.expires_at = lease.acquired_at;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn save_quarantined_output(&self, output: &QuarantinedOutput) -> Result<()> {
+        self.quarantined_outputs.write().await.insert(output.id.clone(), output.clone());
+        Ok(())
+    }
+
+    async fn load_quarantined_output(&self, id: &str) -> Result<Option<QuarantinedOutput>> {
+        Ok(self.quarantined_outputs.read().await.get(id).cloned())
+    }
+
+    async fn load_quarantined_outputs(&self, pending_only: bool, limit: usize) -> Result<Vec<QuarantinedOutput>> {
+        let mut outputs: Vec<QuarantinedOutput> = self
+            .quarantined_outputs
+            .read()
+            .await
+            .values()
+            .filter(|output| !pending_only || output.is_pending())
+            .cloned()
+            .collect();
+        outputs.sort_by(|a, b| b.quarantined_at.cmp(&a.quarantined_at).then_with(|| b.id.cmp(&a.id)));
+        outputs.truncate(limit);
+        Ok(outputs)
+    }
+
+    async fn append_scratchpad_entry(&self, entry: &ScratchpadEntry, max_bytes: usize) -> Result<usize> {
+        let mut scratchpads = self.scratchpads.write().await;
+        let entries = scratchpads.entry(entry.run_id.clone()).or_default();
+        entries.push(entry.clone());
+
+        let mut total: usize = entries.iter().map(ScratchpadEntry::size).sum();
+        let mut evicted = 0;
+        while total > max_bytes && entries.len() > 1 {
+            total -= entries.remove(0).size();
+            evicted += 1;
+        }
+        Ok(evicted)
+    }
+
+    async fn load_scratchpad(&self, run_id: &str) -> Result<Vec<ScratchpadEntry>> {
+        Ok(self.scratchpads.read().await.get(run_id).cloned().unwrap_or_default())
+    }
+
+    async fn save_chat_session(&self, session: &ChatSession) -> Result<()> {
+        self.chat_sessions.write().await.insert(session.id.clone(), session.clone());
+        Ok(())
+    }
+
+    async fn load_chat_session(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        Ok(self.chat_sessions.read().await.get(session_id).cloned())
+    }
+
+    async fn load_chat_sessions(&self, include_closed: bool) -> Result<Vec<ChatSession>> {
+        let mut sessions: Vec<ChatSession> = self
+            .chat_sessions
+            .read()
+            .await
+            .values()
+            .filter(|session| include_closed || !session.is_closed())
+            .cloned()
+            .collect();
+        sessions.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(sessions)
+    }
+
+    async fn save_group_stats(&self, stats: &GroupStats) -> Result<()> {
+        self.group_stats.write().await.insert(stats.group_id.clone(), stats.clone());
+        Ok(())
+    }
+
+    async fn load_group_stats(&self, group_id: &str) -> Result<Option<GroupStats>> {
+        Ok(self.group_stats.read().await.get(group_id).cloned())
+    }
+
+    fn backend_info(&self) -> StoreBackendInfo {
+        StoreBackendInfo {
+            backend: "memory".to_string(),
+            path: None,
+            in_memory: true,
+        }
+    }
 }
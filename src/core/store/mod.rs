@@ -4,40 +4,117 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
-
-use crate::domain::{Group, Message, Organization};
+use serde::Serialize;
+
+use crate::core::integrity::{IntegrityFinding, QuarantinedRow};
+use crate::core::secrets::ExtractedSecret;
+use crate::domain::{
+    Agent, Department, Escalation, Group, Message, MessageBookmark, MessageId, MessagePin, MessageReaction, MessageTarget,
+    MessageTranslation, Organization, RoleRevision, Task, TaskStatus,
+};
+use crate::domain::idempotency::IdempotencyRecord;
+use crate::domain::lease::Lease;
+use crate::domain::moderation::QuarantinedOutput;
+use crate::domain::scratchpad::ScratchpadEntry;
+use crate::domain::share_token::ShareToken;
+use crate::domain::chat_session::ChatSession;
+use crate::domain::group_stats::GroupStats;
+use crate::domain::digest::Digest;
+use crate::domain::org_change::OrgChangeEntry;
 use crate::domain::invitation_code::InvitationCode;
+use crate::domain::user::LoginFailures;
+use crate::domain::tool::ToolUsage;
+
+/// 消息查询的默认返回数量，`limit` 为 0 时使用，避免意外的无上限查询
+pub const DEFAULT_MESSAGE_LIMIT: usize = 100;
 
 /// 消息查询过滤器
 #[derive(Debug, Clone, Default)]
 pub struct MessageFilter {
     /// 发送者ID
     pub from: Option<String>,
+    /// 发送者或接收者（私聊对象、群ID）之一，用于加载双方向的会话
+    pub participant: Option<String>,
+    /// 只返回这两方之间的私聊（任一方向），不含他们与第三方的私聊
+    pub between: Option<(String, String)>,
     /// 接收者ID（私聊）或群ID（群聊）
     pub to: Option<String>,
     /// 目标类型: "direct", "group", "broadcast"
     pub target_type: Option<String>,
     /// 起始时间戳（包含）
     pub since: Option<i64>,
-    /// 最大返回数量
+    /// 截止时间戳（包含）
+    pub until: Option<i64>,
+    /// 最大返回数量，0 表示使用 [`DEFAULT_MESSAGE_LIMIT`]
     pub limit: usize,
+    /// 按时间升序返回（默认最新的在前），用于向前分页
+    pub oldest_first: bool,
+    /// 只返回排在游标之前（更早）的消息
+    pub before: Option<MessageCursor>,
+    /// 跳过排序后的前若干条（偏移分页）
+    pub offset: Option<usize>,
+    /// 同一时间戳内按消息 ID 排序，游标分页需要稳定的顺序
+    pub stable_order: bool,
+}
+
+/// 消息分页游标：按消息的原始时间戳和 ID 定位，消息内容变化不影响位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageCursor {
+    pub timestamp: i64,
+    pub id: MessageId,
+}
+
+impl MessageCursor {
+    /// 以指定消息为游标
+    pub fn at(message: &Message) -> Self {
+        Self {
+            timestamp: message.timestamp,
+            id: message.id.clone(),
+        }
+    }
+
+    /// 消息是否排在游标之前（属于下一页）
+    pub fn includes(&self, message: &Message) -> bool {
+        (message.timestamp, message.id.as_str()) < (self.timestamp, self.id.as_str())
+    }
 }
 
 impl MessageFilter {
     /// 创建新的过滤器
     pub fn new() -> Self {
         Self {
-            limit: 100,
+            limit: DEFAULT_MESSAGE_LIMIT,
             ..Default::default()
         }
     }
 
+    /// 实际生效的数量限制，未设置（0）时取默认值
+    pub fn effective_limit(&self) -> usize {
+        if self.limit == 0 {
+            DEFAULT_MESSAGE_LIMIT
+        } else {
+            self.limit
+        }
+    }
+
     /// 设置发送者
     pub fn from(mut self, agent_id: impl Into<String>) -> Self {
         self.from = Some(agent_id.into());
         self
     }
 
+    /// 设置会话参与者：该参与者发送或接收的消息
+    pub fn participant(mut self, id: impl Into<String>) -> Self {
+        self.participant = Some(id.into());
+        self
+    }
+
+    /// 只返回 `a` 与 `b` 之间的私聊（任一方向）
+    pub fn between(mut self, a: impl Into<String>, b: impl Into<String>) -> Self {
+        self.between = Some((a.into(), b.into()));
+        self
+    }
+
     /// 设置接收者
     pub fn to(mut self, target_id: impl Into<String>) -> Self {
         self.to = Some(target_id.into());
@@ -56,11 +133,213 @@ impl MessageFilter {
         self
     }
 
+    /// 设置截止时间
+    pub fn until(mut self, timestamp: i64) -> Self {
+        self.until = Some(timestamp);
+        self
+    }
+
     /// 设置返回数量限制
     pub fn limit(mut self, n: usize) -> Self {
         self.limit = n;
         self
     }
+
+    /// 按时间升序返回，数量限制取最早的消息
+    pub fn oldest_first(mut self) -> Self {
+        self.oldest_first = true;
+        self
+    }
+
+    /// 只返回游标之前的消息（隐含稳定顺序）
+    pub fn before(mut self, cursor: MessageCursor) -> Self {
+        self.before = Some(cursor);
+        self.stable_order = true;
+        self
+    }
+
+    /// 跳过排序后的前 `n` 条（隐含稳定顺序，翻页时同一秒内的消息不会重复或遗漏）
+    pub fn offset(mut self, n: usize) -> Self {
+        self.offset = Some(n);
+        self.stable_order = true;
+        self
+    }
+
+    /// 同一时间戳内按消息 ID 排序
+    pub fn stable_order(mut self) -> Self {
+        self.stable_order = true;
+        self
+    }
+
+    /// 消息是否满足发送者、时间和目标条件（不考虑数量限制）
+    pub fn matches(&self, message: &Message) -> bool {
+        if self.from.as_ref().is_some_and(|from| message.from != *from) {
+            return false;
+        }
+        if self.since.is_some_and(|since| message.timestamp < since) {
+            return false;
+        }
+        if self.until.is_some_and(|until| message.timestamp > until) {
+            return false;
+        }
+        if self.before.as_ref().is_some_and(|cursor| !cursor.includes(message)) {
+            return false;
+        }
+        if let Some((a, b)) = &self.between {
+            let MessageTarget::Direct(to) = &message.to else {
+                return false;
+            };
+            if !((message.from == *a && to == b) || (message.from == *b && to == a)) {
+                return false;
+            }
+        }
+
+        let (target_type, target_id) = match &message.to {
+            MessageTarget::Direct(agent_id) => ("direct", agent_id),
+            MessageTarget::Group(group_id) => ("group", group_id),
+        };
+        self.target_type.as_ref().is_none_or(|t| t == target_type)
+            && self.to.as_ref().is_none_or(|to| to == target_id)
+            && self.participant.as_ref().is_none_or(|p| *p == message.from || p == target_id)
+    }
+
+    /// 按时间排序（默认最新的在前）并应用偏移和数量限制
+    pub fn apply(&self, mut messages: Vec<Message>) -> Vec<Message> {
+        if self.stable_order {
+            messages.sort_by(|a, b| (a.timestamp, &a.id).cmp(&(b.timestamp, &b.id)));
+            if !self.oldest_first {
+                messages.reverse();
+            }
+        } else if self.oldest_first {
+            messages.sort_by_key(|m| m.timestamp);
+        } else {
+            messages.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+        }
+        if let Some(offset) = self.offset {
+            messages.drain(..offset.min(messages.len()));
+        }
+        messages.truncate(self.effective_limit());
+        messages
+    }
+}
+
+/// 任务查询过滤器
+#[derive(Debug, Clone, Default)]
+pub struct TaskFilter {
+    /// 负责人（Agent ID 或用户 principal）
+    pub assignee: Option<String>,
+    /// 创建者
+    pub creator: Option<String>,
+    /// 状态，为空时不限
+    pub statuses: Vec<TaskStatus>,
+    /// 只返回未关闭的任务
+    pub open_only: bool,
+    /// 截止时间不晚于此时间戳（秒）
+    pub due_before: Option<i64>,
+    /// 最大返回数量
+    pub limit: usize,
+}
+
+impl TaskFilter {
+    /// 创建新的过滤器
+    pub fn new() -> Self {
+        Self {
+            limit: 100,
+            ..Default::default()
+        }
+    }
+
+    /// 设置负责人
+    pub fn assignee(mut self, assignee: impl Into<String>) -> Self {
+        self.assignee = Some(assignee.into());
+        self
+    }
+
+    /// 设置创建者
+    pub fn creator(mut self, creator: impl Into<String>) -> Self {
+        self.creator = Some(creator.into());
+        self
+    }
+
+    /// 增加一个允许的状态
+    pub fn status(mut self, status: TaskStatus) -> Self {
+        self.statuses.push(status);
+        self
+    }
+
+    /// 只返回未关闭的任务
+    pub fn open(mut self) -> Self {
+        self.open_only = true;
+        self
+    }
+
+    /// 截止时间不晚于 `timestamp`
+    pub fn due_before(mut self, timestamp: i64) -> Self {
+        self.due_before = Some(timestamp);
+        self
+    }
+
+    /// 设置返回数量限制
+    pub fn limit(mut self, n: usize) -> Self {
+        self.limit = n;
+        self
+    }
+
+    /// 任务是否满足条件（不考虑数量限制）
+    pub fn matches(&self, task: &Task) -> bool {
+        self.assignee.as_ref().is_none_or(|a| task.is_assigned_to(a))
+            && self.creator.as_ref().is_none_or(|c| task.creator == *c)
+            && (self.statuses.is_empty() || self.statuses.contains(&task.status))
+            && (!self.open_only || task.is_open())
+            && self.due_before.is_none_or(|before| task.due_at.is_some_and(|due| due <= before))
+    }
+
+    /// 按截止时间排序（无截止时间的在后，其次按创建时间）并应用数量限制
+    pub fn apply(&self, mut tasks: Vec<Task>) -> Vec<Task> {
+        tasks.sort_by_key(|t| (t.due_at.is_none(), t.due_at, t.created_at, t.id.clone()));
+        tasks.truncate(self.limit);
+        tasks
+    }
+}
+
+/// 存储后端信息，用于运维确认实际使用的数据库
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StoreBackendInfo {
+    /// 后端类型，如 "memory"、"sqlite"
+    pub backend: String,
+    /// 数据库文件路径或 URI，内存存储为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub in_memory: bool,
+}
+
+impl StoreBackendInfo {
+    /// 用于检查结果的标签，如 `sqlite:imitatort.db`
+    pub fn label(&self) -> String {
+        match &self.path {
+            Some(path) => format!("{}:{}", self.backend, path),
+            None => self.backend.clone(),
+        }
+    }
+}
+
+/// 热表与归档中的消息数
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MessageTierCounts {
+    /// 热表中的消息数
+    pub hot: usize,
+    /// 已归档的消息数
+    pub archived: usize,
+    /// 归档边界：早于该时间戳（秒）的消息都在归档中，未归档过时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archived_before: Option<i64>,
+}
+
+impl MessageTierCounts {
+    /// 过滤条件的时间范围是否延伸到归档边界之前
+    pub fn reaches_archive(archived_before: Option<i64>, filter: &MessageFilter) -> bool {
+        archived_before.is_some_and(|boundary| filter.since.is_none_or(|since| since < boundary))
+    }
 }
 
 /// 存储接口
@@ -76,6 +355,25 @@ pub trait Store: Send + Sync {
     /// 如果存储中没有组织架构，返回空Organization
     async fn load_organization(&self) -> Result<Organization>;
 
+    /// 按ID加载单个部门
+    async fn load_department(&self, department_id: &str) -> Result<Option<Department>> {
+        // 默认实现加载整个组织架构，子类可以重写为定向查询
+        Ok(self.load_organization().await?.find_department(department_id).cloned())
+    }
+
+    /// 加载部门的直属成员
+    async fn load_department_members(&self, department_id: &str) -> Result<Vec<Agent>> {
+        // 默认实现加载整个组织架构，子类可以重写为定向查询
+        let org = self.load_organization().await?;
+        Ok(org.get_department_members(department_id).into_iter().cloned().collect())
+    }
+
+    /// 按ID加载单个Agent
+    async fn load_agent(&self, agent_id: &str) -> Result<Option<Agent>> {
+        // 默认实现加载整个组织架构，子类可以重写为定向查询
+        Ok(self.load_organization().await?.find_agent(agent_id).cloned())
+    }
+
     /// 保存群聊
     async fn save_group(&self, group: &Group) -> Result<()>;
 
@@ -99,8 +397,42 @@ pub trait Store: Send + Sync {
     /// 根据过滤器查询消息
     async fn load_messages(&self, filter: MessageFilter) -> Result<Vec<Message>>;
 
+    /// 按ID加载消息，热表中没有时查找归档
+    async fn load_message(&self, _message_id: &str) -> Result<Option<Message>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 把早于 `timestamp`（秒）的消息移入归档，返回移动的条数；查询仍透明地包含归档消息
+    async fn archive_messages_before(&self, _timestamp: i64) -> Result<usize> {
+        // 默认实现：不支持归档的存储不移动任何消息
+        Ok(0)
+    }
+
+    /// 按ID删除消息（热表和归档中的都删除），返回消息是否存在
+    async fn delete_message(&self, _message_id: &str) -> Result<bool> {
+        // 默认实现：不支持删除的存储不删除任何消息
+        Ok(false)
+    }
+
+    /// 永久删除早于 `timestamp`（秒）的消息（包括归档中的），返回删除的条数
+    async fn purge_messages_before(&self, _timestamp: i64) -> Result<usize> {
+        // 默认实现：不支持删除的存储不删除任何消息
+        Ok(0)
+    }
+
+    /// 热表与归档中的消息数
+    async fn message_tier_counts(&self) -> Result<MessageTierCounts> {
+        // 默认实现，子类可以重写
+        Ok(MessageTierCounts::default())
+    }
+
     /// 加载与指定Agent相关的消息
+    ///
+    /// 包括该Agent发出的所有消息和发给它的私聊，最新的在前；`limit` 为 0 时使用默认数量
     async fn load_messages_by_agent(&self, agent_id: &str, limit: usize) -> Result<Vec<Message>> {
+        let limit = MessageFilter::new().limit(limit).effective_limit();
+
         // 查询从指定Agent发送的消息
         let from_filter = MessageFilter::new()
             .limit(limit)
@@ -138,6 +470,28 @@ pub trait Store: Send + Sync {
         self.load_messages(filter).await
     }
 
+    /// 群聊已分配的最大序号，没有带序号的消息时为 0
+    ///
+    /// 默认实现只查看最新的 [`DEFAULT_MESSAGE_LIMIT`] 条消息，子类可以重写
+    async fn max_group_seq(&self, group_id: &str) -> Result<u64> {
+        let messages = self.load_messages_by_group(group_id, 0).await?;
+        Ok(messages.iter().filter_map(Message::group_seq).max().unwrap_or(0))
+    }
+
+    /// 按序号范围（含两端）加载群聊消息，按序号从小到大排列，用于补齐接收端发现的缺口
+    ///
+    /// 默认实现只查看最新的 [`DEFAULT_MESSAGE_LIMIT`] 条消息，子类可以重写
+    async fn load_group_messages_by_seq(&self, group_id: &str, from_seq: u64, to_seq: u64) -> Result<Vec<Message>> {
+        let mut messages: Vec<Message> = self
+            .load_messages_by_group(group_id, 0)
+            .await?
+            .into_iter()
+            .filter(|m| m.group_seq().is_some_and(|seq| (from_seq..=to_seq).contains(&seq)))
+            .collect();
+        messages.sort_by_key(|m| m.group_seq());
+        Ok(messages)
+    }
+
     /// 保存用户
     async fn save_user(&self, _user: &crate::domain::user::User) -> Result<()> {
         // 默认实现，子类可以重写
@@ -185,7 +539,384 @@ pub trait Store: Send + Sync {
         // 默认实现，子类可以重写
         Ok(vec![])
     }
+
+    /// 保存某日的工具使用汇总（按工具覆盖）
+    async fn save_tool_stats(&self, _date: &str, _stats: &[ToolUsage]) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载某日的工具使用汇总
+    async fn load_tool_stats(&self, _date: &str) -> Result<Vec<ToolUsage>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 保存Agent偏好文档（覆盖）
+    async fn save_agent_preferences(&self, _agent_id: &str, _preferences: &serde_json::Value) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载Agent偏好文档
+    async fn load_agent_preferences(&self, _agent_id: &str) -> Result<Option<serde_json::Value>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 删除Agent偏好文档
+    async fn delete_agent_preferences(&self, _agent_id: &str) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 追加一条角色修订，修订号已存在时返回错误
+    async fn save_role_revision(&self, _revision: &RoleRevision) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载Agent的全部角色修订（按修订号升序）
+    async fn load_role_revisions(&self, _agent_id: &str) -> Result<Vec<RoleRevision>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 记录一次消息升级
+    async fn save_escalation(&self, _escalation: &Escalation) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 查询某条消息的升级记录
+    async fn load_escalation(&self, _message_id: &str) -> Result<Option<Escalation>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 保存登录失败计数
+    async fn save_login_failures(&self, _failures: &LoginFailures) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载登录失败计数
+    async fn load_login_failures(&self, _key: &str) -> Result<Option<LoginFailures>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 清除登录失败计数
+    async fn delete_login_failures(&self, _key: &str) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 保存幂等键及其响应，同一 (scope, key) 覆盖旧记录
+    async fn save_idempotency_record(&self, _record: &IdempotencyRecord) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载幂等键记录（可能已过期，由调用方判断）
+    async fn load_idempotency_record(&self, _scope: &str, _key: &str) -> Result<Option<IdempotencyRecord>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 删除在 `now`（秒）之前过期的幂等键，返回删除数量
+    async fn delete_expired_idempotency_records(&self, _now: i64) -> Result<usize> {
+        // 默认实现，子类可以重写
+        Ok(0)
+    }
+
+    /// 保存分享令牌（同ID覆盖）
+    async fn save_share_token(&self, _token: &ShareToken) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 按ID（令牌哈希）加载分享令牌
+    async fn load_share_token(&self, _id: &str) -> Result<Option<ShareToken>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 加载群聊的分享令牌，最新创建的在前
+    async fn load_share_tokens(&self, _group_id: &str) -> Result<Vec<ShareToken>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 保存消息译文（同一消息同一语言覆盖）
+    async fn save_translation(&self, _translation: &MessageTranslation) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载消息译为指定语言的译文
+    async fn load_translation(&self, _message_id: &str, _language: &str) -> Result<Option<MessageTranslation>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 追加一条组织架构变更记录
+    async fn save_org_change(&self, _entry: &OrgChangeEntry) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载 `since`（秒，不含）之后的组织架构变更，最新的在前
+    async fn load_org_changes(&self, _since: i64, _limit: usize) -> Result<Vec<OrgChangeEntry>> {
+        // 默认实现，子类可以重写
+        Ok(Vec::new())
+    }
+
+    /// 保存生成的活动摘要
+    async fn save_digest(&self, _digest: &Digest) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载活动摘要，按周期结束时间最新的在前
+    async fn load_digests(&self, _limit: usize) -> Result<Vec<Digest>> {
+        // 默认实现，子类可以重写
+        Ok(Vec::new())
+    }
+
+    /// 保存应用自定义状态（键值对，值为 JSON），用于上层应用的断点恢复
+    async fn save_app_state(&self, _key: &str, _value: &serde_json::Value) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载应用自定义状态
+    async fn load_app_state(&self, _key: &str) -> Result<Option<serde_json::Value>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 删除应用自定义状态
+    async fn delete_app_state(&self, _key: &str) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 添加消息回应，已存在时不重复添加，返回是否新增
+    async fn add_reaction(&self, _reaction: &MessageReaction) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 移除消息回应，返回是否存在
+    async fn remove_reaction(&self, _message_id: &str, _reactor_id: &str, _emoji: &str) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 加载多条消息的回应（按时间升序）
+    async fn load_reactions(&self, _message_ids: &[String]) -> Result<Vec<MessageReaction>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 置顶消息，会话内已置顶时不重复添加，返回是否新增
+    async fn add_pin(&self, _pin: &MessagePin) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 取消置顶，返回是否存在
+    async fn remove_pin(&self, _session_id: &str, _message_id: &str) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 加载会话的置顶消息（按置顶时间升序）
+    async fn load_pins(&self, _session_id: &str) -> Result<Vec<MessagePin>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 添加个人书签，已存在时不重复添加，返回是否新增
+    async fn add_bookmark(&self, _bookmark: &MessageBookmark) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 移除个人书签，返回是否存在
+    async fn remove_bookmark(&self, _owner_id: &str, _message_id: &str) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 加载某人的书签（最新的在前）
+    async fn load_bookmarks(&self, _owner_id: &str) -> Result<Vec<MessageBookmark>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 保存任务（同ID覆盖）
+    async fn save_task(&self, _task: &Task) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 按ID加载任务
+    async fn load_task(&self, _task_id: &str) -> Result<Option<Task>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 根据过滤器查询任务，按截止时间排序
+    async fn load_tasks(&self, _filter: TaskFilter) -> Result<Vec<Task>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 删除任务，返回是否存在
+    async fn delete_task(&self, _task_id: &str) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 获取或续期租约（原子操作）：空闲、已过期或已由 `holder` 持有的租约归 `holder`，
+    /// 否则返回当前持有者的租约，规则见 [`Lease::claim`]
+    async fn acquire_lease(&self, _name: &str, _holder: &str, _now: i64, _ttl_secs: i64) -> Result<Lease> {
+        // 默认实现：不支持租约的存储不能用于主备部署
+        anyhow::bail!("This store does not support leases")
+    }
+
+    /// 加载租约
+    async fn load_lease(&self, _name: &str) -> Result<Option<Lease>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 释放租约（持有者和令牌都匹配时），返回是否释放
+    async fn release_lease(&self, _name: &str, _holder: &str, _token: u64) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 保存被内容审核拦截的输出（同ID覆盖，用于记录复核结果）
+    async fn save_quarantined_output(&self, _output: &QuarantinedOutput) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 按ID加载被拦截的输出
+    async fn load_quarantined_output(&self, _id: &str) -> Result<Option<QuarantinedOutput>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 加载被拦截的输出，最新的在前；`pending_only` 时只返回未复核的
+    async fn load_quarantined_outputs(&self, _pending_only: bool, _limit: usize) -> Result<Vec<QuarantinedOutput>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 追加一条草稿，然后从最早的开始删除同一运行的草稿，直到内容总大小不超过 `max_bytes`
+    ///
+    /// 新追加的一条不会被删除；返回删除的条数
+    async fn append_scratchpad_entry(&self, _entry: &ScratchpadEntry, _max_bytes: usize) -> Result<usize> {
+        // 默认实现，子类可以重写
+        Ok(0)
+    }
+
+    /// 加载某次运行的草稿，最早的在前
+    async fn load_scratchpad(&self, _run_id: &str) -> Result<Vec<ScratchpadEntry>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 保存会话（同ID覆盖）
+    async fn save_chat_session(&self, _session: &ChatSession) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 按ID加载会话
+    async fn load_chat_session(&self, _session_id: &str) -> Result<Option<ChatSession>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 加载会话，按创建时间最早的在前；`include_closed` 为 false 时只返回未关闭的会话
+    async fn load_chat_sessions(&self, _include_closed: bool) -> Result<Vec<ChatSession>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 保存群聊活动统计（同群覆盖）
+    async fn save_group_stats(&self, _stats: &GroupStats) -> Result<()> {
+        // 默认实现，子类可以重写
+        Ok(())
+    }
+
+    /// 加载群聊活动统计
+    async fn load_group_stats(&self, _group_id: &str) -> Result<Option<GroupStats>> {
+        // 默认实现，子类可以重写
+        Ok(None)
+    }
+
+    /// 扫描后端特有的问题（非法枚举值、无法解析的行），见 [`crate::core::integrity`]
+    async fn scan_integrity(&self) -> Result<Vec<IntegrityFinding>> {
+        // 默认实现：类型化存储没有这类问题
+        Ok(vec![])
+    }
+
+    /// 将无法解析的行移入隔离表，返回是否移动
+    async fn quarantine_row(&self, _table: &str, _row_id: &str, _reason: &str) -> Result<bool> {
+        // 默认实现，子类可以重写
+        Ok(false)
+    }
+
+    /// 加载隔离表中的行
+    async fn load_quarantined_rows(&self) -> Result<Vec<QuarantinedRow>> {
+        // 默认实现，子类可以重写
+        Ok(vec![])
+    }
+
+    /// 就绪探测，存储不可用时返回错误
+    async fn health_check(&self) -> Result<()> {
+        // 默认实现只做一次读取，子类可以重写为读写探测
+        self.load_groups().await.map(|_| ())
+    }
+
+    /// 降级运行时的说明（如正在缓冲写入），正常时为 None
+    fn degraded(&self) -> Option<String> {
+        None
+    }
+
+    /// 保存组织架构时从明文 API Key 取出的值，每个只返回一次，由调用方展示给运维人员
+    fn take_extracted_api_keys(&self) -> Vec<ExtractedSecret> {
+        // 默认实现不改写 API Key，子类可以重写
+        Vec::new()
+    }
+
+    /// 后端类型与实际使用的数据库
+    fn backend_info(&self) -> StoreBackendInfo {
+        // 默认实现，子类可以重写
+        StoreBackendInfo {
+            backend: "custom".to_string(),
+            path: None,
+            in_memory: false,
+        }
+    }
+
+    /// 实际使用的数据库文件路径或 URI
+    fn path(&self) -> Option<String> {
+        self.backend_info().path
+    }
 }
 
+mod buffered;
+#[cfg(feature = "chaos")]
+mod chaos;
 mod memory;
+pub use buffered::{BufferConfig, BufferedStore, BufferedWrite};
+#[cfg(feature = "chaos")]
+pub use chaos::ChaosStore;
 pub use memory::MemoryStore;
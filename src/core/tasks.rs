@@ -0,0 +1,334 @@
+//! 任务管理
+//!
+//! 任务是比对话更持久的工作项：Agent 和用户都可以创建、指派和跟踪。
+//! 指派给他人时向负责人发送系统通知；周期检查在截止时间临近（或已过）时
+//! 提醒负责人一次。任务评论以关联到任务的私聊消息保存。
+//! 接入事件总线时，指派和逾期还会发出 `TaskAssigned` / `TaskOverdue` 事件。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::escalation::NO_ESCALATION_KEY;
+use crate::core::events::{CompanyEvent, EventBus};
+use crate::core::i18n::MessageCatalog;
+use crate::core::messaging::MessageBus;
+use crate::core::store::{Store, TaskFilter};
+use crate::domain::{Message, Task, TaskStatus};
+
+/// 任务通知的发送者
+pub const TASK_SENDER: &str = "system";
+
+/// 消息元数据中关联任务ID的键
+pub const TASK_ID_KEY: &str = "task_id";
+
+/// 截止时间前多久提醒负责人（秒）
+pub const DEFAULT_DUE_SOON_SECS: i64 = 24 * 60 * 60;
+
+/// 每轮检查最多扫描的任务数
+const SCAN_LIMIT: usize = 1000;
+
+/// 任务操作错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TaskError {
+    #[error("Task {task_id} not found")]
+    NotFound { task_id: String },
+    #[error("Task title must not be empty")]
+    EmptyTitle,
+    #[error("Task {task_id} cannot move from {from} to {to}")]
+    InvalidTransition { task_id: String, from: TaskStatus, to: TaskStatus },
+}
+
+/// 任务字段的部分更新，未设置的字段保持不变
+#[derive(Debug, Clone, Default)]
+pub struct TaskUpdate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub assignee: Option<String>,
+    pub status: Option<TaskStatus>,
+    pub due_at: Option<i64>,
+}
+
+/// 任务管理器
+pub struct TaskManager {
+    store: Arc<dyn Store>,
+    message_bus: Option<Arc<MessageBus>>,
+    events: Option<Arc<EventBus>>,
+    clock: Arc<dyn Clock>,
+    catalog: MessageCatalog,
+    due_soon_secs: i64,
+}
+
+impl TaskManager {
+    /// 创建管理器；未接入消息总线时通知只写入存储
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self {
+            store,
+            message_bus: None,
+            events: None,
+            clock: Arc::new(SystemClock),
+            catalog: MessageCatalog::default(),
+            due_soon_secs: DEFAULT_DUE_SOON_SECS,
+        }
+    }
+
+    /// 通过消息总线投递通知和评论，并使用总线的时钟、消息目录和事件总线
+    pub fn with_message_bus(mut self, message_bus: Arc<MessageBus>) -> Self {
+        self.clock = message_bus.clock();
+        self.catalog = message_bus.catalog();
+        self.events = message_bus.events().or(self.events);
+        self.message_bus = Some(message_bus);
+        self
+    }
+
+    /// 指派和逾期时发出事件
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 设置截止时间前多久提醒负责人（秒）
+    pub fn with_due_soon_secs(mut self, secs: i64) -> Self {
+        self.due_soon_secs = secs;
+        self
+    }
+
+    /// 创建任务，指派给他人时通知负责人
+    pub async fn create(&self, mut task: Task) -> Result<Task> {
+        task.title = task.title.trim().to_string();
+        if task.title.is_empty() {
+            return Err(TaskError::EmptyTitle.into());
+        }
+        let now = self.clock.now();
+        task.created_at = now;
+        task.updated_at = now;
+
+        self.store.save_task(&task).await?;
+        info!(target: "audit", task_id = %task.id, actor = %task.creator, "Task created");
+        if let Some(assignee) = task.assignee.clone().filter(|a| *a != task.creator) {
+            self.notify_assigned(&task, &assignee, &task.creator).await;
+        }
+        Ok(task)
+    }
+
+    /// 按ID加载任务
+    pub async fn get(&self, task_id: &str) -> Result<Task> {
+        self.store
+            .load_task(task_id)
+            .await?
+            .ok_or_else(|| TaskError::NotFound { task_id: task_id.to_string() }.into())
+    }
+
+    /// 查询任务
+    pub async fn list(&self, filter: TaskFilter) -> Result<Vec<Task>> {
+        self.store.load_tasks(filter).await
+    }
+
+    /// 负责人未关闭的任务，按截止时间排序
+    pub async fn open_tasks(&self, assignee: &str) -> Result<Vec<Task>> {
+        self.store.load_tasks(TaskFilter::new().assignee(assignee).open()).await
+    }
+
+    /// 更新任务；负责人变化时通知新负责人，截止时间变化后重新提醒
+    pub async fn update(&self, task_id: &str, actor: &str, update: TaskUpdate) -> Result<Task> {
+        let mut task = self.get(task_id).await?;
+
+        if let Some(status) = update.status.filter(|s| *s != task.status) {
+            if !task.status.can_transition_to(status) {
+                return Err(TaskError::InvalidTransition {
+                    task_id: task.id.clone(),
+                    from: task.status,
+                    to: status,
+                }
+                .into());
+            }
+            task.status = status;
+        }
+        if let Some(title) = update.title {
+            let title = title.trim().to_string();
+            if title.is_empty() {
+                return Err(TaskError::EmptyTitle.into());
+            }
+            task.title = title;
+        }
+        if let Some(description) = update.description {
+            task.description = description;
+        }
+        if update.due_at.is_some() && update.due_at != task.due_at {
+            task.due_at = update.due_at;
+            task.due_notified_at = None;
+        }
+        let reassigned = update.assignee.filter(|a| !task.is_assigned_to(a));
+        if let Some(assignee) = &reassigned {
+            task.assignee = Some(assignee.clone());
+            task.due_notified_at = None;
+        }
+        task.updated_at = self.clock.now();
+
+        self.store.save_task(&task).await?;
+        info!(target: "audit", task_id = %task.id, actor = %actor, status = %task.status, "Task updated");
+        if let Some(assignee) = reassigned.filter(|a| a != actor) {
+            self.notify_assigned(&task, &assignee, actor).await;
+        }
+        Ok(task)
+    }
+
+    /// 更新任务状态
+    pub async fn update_status(&self, task_id: &str, actor: &str, status: TaskStatus) -> Result<Task> {
+        let update = TaskUpdate {
+            status: Some(status),
+            ..Default::default()
+        };
+        self.update(task_id, actor, update).await
+    }
+
+    /// 删除任务，返回是否存在
+    pub async fn delete(&self, task_id: &str, actor: &str) -> Result<bool> {
+        let deleted = self.store.delete_task(task_id).await?;
+        if deleted {
+            info!(target: "audit", task_id = %task_id, actor = %actor, "Task deleted");
+        }
+        Ok(deleted)
+    }
+
+    /// 评论任务：发给任务的另一方（负责人评论时发给创建者），消息关联到任务
+    pub async fn comment(&self, task_id: &str, author: &str, content: &str) -> Result<Message> {
+        let mut task = self.get(task_id).await?;
+        let recipient = match task.assignee.as_deref() {
+            Some(assignee) if assignee != author => assignee.to_string(),
+            _ => task.creator.clone(),
+        };
+
+        let mut message = Message::private(author, &recipient, content)
+            .with_metadata("kind", "task_comment")
+            .with_metadata(TASK_ID_KEY, &task.id)
+            .with_metadata(NO_ESCALATION_KEY, "true");
+        message.timestamp = self.clock.now();
+
+        match &self.message_bus {
+            // 自己给自己的备注只保存，不投递
+            Some(bus) if recipient != author => bus.send(message.clone()).await?,
+            _ => self.store.save_message(&message).await?,
+        }
+
+        task.link_message(message.id.clone());
+        task.updated_at = message.timestamp;
+        self.store.save_task(&task).await?;
+        Ok(message)
+    }
+
+    /// 检查一轮截止时间，提醒负责人并返回本轮提醒的任务
+    pub async fn check_due(&self) -> Result<Vec<Task>> {
+        let now = self.clock.now();
+        let due = self
+            .store
+            .load_tasks(TaskFilter::new().open().due_before(now + self.due_soon_secs).limit(SCAN_LIMIT))
+            .await?;
+
+        let mut notified = Vec::new();
+        for mut task in due {
+            let (Some(assignee), Some(due_at)) = (task.assignee.clone(), task.due_at) else {
+                continue;
+            };
+            if task.due_notified_at.is_some() {
+                continue;
+            }
+
+            let overdue = due_at <= now;
+            let key = if overdue { "task.overdue" } else { "task.due_soon" };
+            let content = self.catalog.format(
+                key,
+                &[("title", &task.title), ("task_id", &task.id), ("due", &format_due(due_at))],
+            );
+            // 先记录再发送，避免发送失败时反复提醒
+            task.due_notified_at = Some(now);
+            self.store.save_task(&task).await?;
+            self.notify(&task, &assignee, content).await;
+            if overdue {
+                self.emit(CompanyEvent::TaskOverdue { task: Arc::new(task.clone()) });
+            }
+            notified.push(task);
+        }
+
+        Ok(notified)
+    }
+
+    /// 启动周期检查任务
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.check_due().await {
+                    warn!("Task due date check failed: {}", e);
+                }
+            }
+        })
+    }
+
+    async fn notify_assigned(&self, task: &Task, assignee: &str, actor: &str) {
+        let due = task.due_at.map(format_due).unwrap_or_else(|| "-".to_string());
+        let content = self.catalog.format(
+            "task.assigned",
+            &[("actor", actor), ("title", &task.title), ("task_id", &task.id), ("due", &due)],
+        );
+        self.notify(task, assignee, content).await;
+        self.emit(CompanyEvent::TaskAssigned {
+            task: Arc::new(task.clone()),
+            assignee: assignee.into(),
+            actor: actor.into(),
+        });
+    }
+
+    fn emit(&self, event: CompanyEvent) {
+        if let Some(events) = &self.events {
+            events.emit(event);
+        }
+    }
+
+    /// 发送系统通知，失败只记录日志
+    async fn notify(&self, task: &Task, recipient: &str, content: String) {
+        let mut notice = Message::private(TASK_SENDER, recipient, content)
+            .with_metadata("kind", "task")
+            .with_metadata(TASK_ID_KEY, &task.id)
+            .with_metadata(NO_ESCALATION_KEY, "true");
+        notice.timestamp = self.clock.now();
+
+        let result = match &self.message_bus {
+            Some(bus) => bus.send(notice).await,
+            None => self.store.save_message(&notice).await,
+        };
+        if let Err(e) = result {
+            warn!("Failed to notify {} about task {}: {}", recipient, task.id, e);
+        }
+    }
+}
+
+/// 截止时间的显示格式
+fn format_due(due_at: i64) -> String {
+    chrono::DateTime::from_timestamp(due_at, 0)
+        .map(|due| due.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| due_at.to_string())
+}
+
+/// 渲染负责人的未关闭任务，用于 Agent 提示词
+pub fn render_open_tasks(tasks: &[Task]) -> Option<String> {
+    if tasks.is_empty() {
+        return None;
+    }
+    let mut section = String::from("\nYour open tasks:\n");
+    for task in tasks {
+        section.push_str(&format!("- {}\n", task.summary()));
+    }
+    Some(section)
+}
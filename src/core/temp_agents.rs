@@ -0,0 +1,431 @@
+//! 会话内的临时 Agent
+//!
+//! 用户或 Agent 可以为某个会话临时请来一位专家（"给这个讨论找个 SQL 调优专家"）：临时 Agent
+//! 按现有 Agent 的角色（模板）或直接给出的角色创建，像普通 Agent 一样注册到消息总线，会话是群聊时
+//! 加入该群；但不写入组织架构，默认也不出现在 Agent 列表中。一段时间没有活动或到达指定的存活时间后
+//! 自动过期：停止运行、从总线注销、退出群聊并通知创建者，会话记录保留在存储中。
+//!
+//! 临时 Agent 的记录保存在存储的应用状态中，重启后恢复仍在有效期内的 Agent。ID 以
+//! [`TEMP_AGENT_ID_PREFIX`] 开头，持久化数据和界面据此区分临时 Agent。
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::messaging::MessageBus;
+use crate::core::store::{MessageFilter, Store};
+use crate::domain::user::is_user_principal;
+use crate::domain::{Agent, Message, Role};
+
+/// 临时 Agent 过期通知的发送者
+pub const TEMP_AGENT_SENDER: &str = "system";
+
+/// 临时 Agent 的 ID 前缀
+pub const TEMP_AGENT_ID_PREFIX: &str = "temp_";
+
+/// 应用状态中保存临时 Agent 记录的键
+const TEMP_AGENTS_KEY: &str = "temp_agents";
+
+/// ID 是否属于临时 Agent
+pub fn is_temporary_agent_id(agent_id: &str) -> bool {
+    agent_id.starts_with(TEMP_AGENT_ID_PREFIX)
+}
+
+/// 临时 Agent 配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct TempAgentConfig {
+    /// 是否允许创建临时 Agent（同时决定是否提供 `agent.spawn_temporary` 工具）
+    pub enabled: bool,
+    /// 全公司同时存在的临时 Agent 上限
+    pub max_active: usize,
+    /// 每个用户或 Agent 同时拥有的临时 Agent 上限
+    pub max_per_spawner: usize,
+    /// 多久没有收发消息后过期（秒）
+    pub idle_timeout_secs: u64,
+    /// 创建时可指定的最长存活时间（秒）
+    pub max_ttl_secs: u64,
+}
+
+impl Default for TempAgentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_active: 10,
+            max_per_spawner: 2,
+            idle_timeout_secs: 1800,
+            max_ttl_secs: 86_400,
+        }
+    }
+}
+
+/// 临时 Agent 记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemporaryAgent {
+    pub agent: Agent,
+    /// 所属会话：群聊 ID，或私聊的 Agent ID
+    pub session_id: String,
+    /// 创建者（`user:{id}` 或 Agent ID），过期时收到通知
+    pub spawned_by: String,
+    /// 复制角色的模板 Agent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    pub created_at: i64,
+    /// 最近一次收发消息的时间
+    pub last_active_at: i64,
+    /// 创建时指定的到期时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// 过期时间，过期前为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expired_at: Option<i64>,
+}
+
+impl TemporaryAgent {
+    /// 是否仍在运行
+    pub fn is_active(&self) -> bool {
+        self.expired_at.is_none()
+    }
+
+    /// `now` 时是否应过期
+    fn is_due(&self, now: i64, idle_timeout_secs: u64) -> bool {
+        self.expires_at.is_some_and(|at| now >= at) || now - self.last_active_at >= idle_timeout_secs as i64
+    }
+}
+
+/// 创建临时 Agent 的请求：`template` 和 `role` 至少给出一个，同时给出时使用 `role`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpawnRequest {
+    /// 显示名称，默认使用角色职位
+    pub name: Option<String>,
+    /// 复制角色、技能和模型的 Agent ID
+    pub template: Option<String>,
+    /// 直接给出的角色
+    pub role: Option<Role>,
+    /// 存活时间（秒），不填则只在闲置后过期
+    pub ttl_secs: Option<u64>,
+}
+
+/// 临时 Agent 无法创建或过期
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TempAgentError {
+    #[error("Temporary agents are disabled")]
+    Disabled,
+    #[error("Temporary agents cannot be spawned before the company is running")]
+    NotRunning,
+    #[error("The company already has {count} active temporary agents (limit {limit})")]
+    LimitReached { count: usize, limit: usize },
+    #[error("{spawner} already has {count} active temporary agents (limit {limit})")]
+    SpawnerLimitReached { spawner: String, count: usize, limit: usize },
+    #[error("Temporary agents cannot spawn other temporary agents")]
+    NestedSpawn,
+    #[error("Session {session_id} not found")]
+    SessionNotFound { session_id: String },
+    #[error("{spawner} is not a participant of session {session_id}")]
+    NotParticipant { session_id: String, spawner: String },
+    #[error("Template agent {template} not found")]
+    TemplateNotFound { template: String },
+    #[error("Either a template agent or a role is required")]
+    RoleRequired,
+    #[error("ttl_secs must be between 1 and {max}")]
+    InvalidTtl { max: u64 },
+    #[error("No LLM is configured for temporary agents")]
+    NoLlm,
+    #[error("Temporary agent {agent_id} not found or already expired")]
+    NotFound { agent_id: String },
+}
+
+/// 运行临时 Agent：由应用层的 Agent 管理器实现
+#[async_trait]
+pub trait TempAgentRunner: Send + Sync {
+    /// 创建 Agent、注册到消息总线并启动其自主循环
+    async fn start(&self, agent: &Agent) -> Result<()>;
+
+    /// 停止 Agent 的自主循环
+    async fn stop(&self, agent_id: &str);
+}
+
+impl std::fmt::Debug for dyn TempAgentRunner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("TempAgentRunner")
+    }
+}
+
+/// 临时 Agent 管理
+pub struct TemporaryAgents {
+    store: Arc<dyn Store>,
+    bus: Arc<MessageBus>,
+    config: TempAgentConfig,
+    records: Mutex<HashMap<String, TemporaryAgent>>,
+    runner: OnceLock<Arc<dyn TempAgentRunner>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl TemporaryAgents {
+    pub fn new(store: Arc<dyn Store>, bus: Arc<MessageBus>, config: TempAgentConfig) -> Self {
+        Self {
+            store,
+            bus,
+            config,
+            records: Mutex::new(HashMap::new()),
+            runner: OnceLock::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 配置
+    pub fn config(&self) -> &TempAgentConfig {
+        &self.config
+    }
+
+    /// 接入运行器后才能创建临时 Agent；同时从存储恢复记录，重启前已到期的随即过期，其余重新启动
+    pub async fn attach_runner(&self, runner: Arc<dyn TempAgentRunner>) -> Result<()> {
+        if self.runner.set(runner.clone()).is_err() {
+            return Ok(());
+        }
+        let stored: Vec<TemporaryAgent> = match self.store.load_app_state(TEMP_AGENTS_KEY).await? {
+            Some(value) => serde_json::from_value(value)?,
+            None => Vec::new(),
+        };
+        let mut records = self.records.lock().await;
+        for record in stored {
+            if record.is_active() {
+                if let Err(e) = runner.start(&record.agent).await {
+                    warn!("Failed to restore temporary agent {}: {}", record.agent.id, e);
+                }
+            }
+            records.insert(record.agent.id.clone(), record);
+        }
+        drop(records);
+        self.expire_idle().await;
+        Ok(())
+    }
+
+    /// 为会话创建临时 Agent
+    ///
+    /// 创建者必须是会话的参与者：群聊成员，或私聊中的用户（Agent 只能在自己的私聊中创建）。
+    /// 未指定模板时使用创建者（或组织中第一个 Agent）的模型。
+    pub async fn spawn(&self, spawner: &str, session_id: &str, request: SpawnRequest) -> Result<TemporaryAgent> {
+        if !self.config.enabled {
+            return Err(TempAgentError::Disabled.into());
+        }
+        if is_temporary_agent_id(spawner) {
+            return Err(TempAgentError::NestedSpawn.into());
+        }
+        let runner = self.runner.get().cloned().ok_or(TempAgentError::NotRunning)?;
+        if request.ttl_secs.is_some_and(|ttl| ttl == 0 || ttl > self.config.max_ttl_secs) {
+            return Err(TempAgentError::InvalidTtl { max: self.config.max_ttl_secs }.into());
+        }
+
+        let org = self.store.load_organization().await?;
+        let group = self.bus.get_group(session_id).await;
+        let participant = match &group {
+            Some(group) => group.has_member(spawner),
+            None if org.find_agent(session_id).is_some() => is_user_principal(spawner) || spawner == session_id,
+            None => {
+                return Err(TempAgentError::SessionNotFound { session_id: session_id.to_string() }.into());
+            }
+        };
+        if !participant {
+            return Err(TempAgentError::NotParticipant {
+                session_id: session_id.to_string(),
+                spawner: spawner.to_string(),
+            }
+            .into());
+        }
+
+        let template = match &request.template {
+            Some(template) => Some(org.find_agent(template).ok_or_else(|| TempAgentError::TemplateNotFound {
+                template: template.clone(),
+            })?),
+            None => None,
+        };
+        let role = request
+            .role
+            .or_else(|| template.map(|t| t.role.clone()))
+            .ok_or(TempAgentError::RoleRequired)?;
+        let llm = template
+            .or_else(|| org.find_agent(spawner))
+            .or_else(|| org.agents.first())
+            .map(|a| a.llm_config.clone())
+            .ok_or(TempAgentError::NoLlm)?;
+
+        // 持锁直到记录写入，并发创建时上限仍然有效
+        let mut records = self.records.lock().await;
+        let active: Vec<&TemporaryAgent> = records.values().filter(|r| r.is_active()).collect();
+        if active.len() >= self.config.max_active {
+            return Err(TempAgentError::LimitReached { count: active.len(), limit: self.config.max_active }.into());
+        }
+        let owned = active.iter().filter(|r| r.spawned_by == spawner).count();
+        if owned >= self.config.max_per_spawner {
+            return Err(TempAgentError::SpawnerLimitReached {
+                spawner: spawner.to_string(),
+                count: owned,
+                limit: self.config.max_per_spawner,
+            }
+            .into());
+        }
+
+        let now = self.clock.now();
+        let id = format!("{}{}", TEMP_AGENT_ID_PREFIX, &uuid::Uuid::new_v4().simple().to_string()[..12]);
+        let name = request
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| role.title.clone());
+        let mut agent = Agent::new(&id, name, role, llm).with_created_at(now);
+        if let Some(template) = template {
+            agent.skills = template.skills.clone();
+        }
+
+        runner.start(&agent).await?;
+        if group.is_some() {
+            if let Err(e) = self.bus.join_group(session_id, &id).await {
+                runner.stop(&id).await;
+                self.bus.unregister(&id);
+                return Err(e);
+            }
+        }
+
+        let record = TemporaryAgent {
+            agent,
+            session_id: session_id.to_string(),
+            spawned_by: spawner.to_string(),
+            template: request.template,
+            created_at: now,
+            last_active_at: now,
+            expires_at: request.ttl_secs.map(|ttl| now + ttl as i64),
+            expired_at: None,
+        };
+        records.insert(id.clone(), record.clone());
+        self.save(&records).await?;
+        info!(target: "audit", "{} spawned temporary agent {} ({}) in session {}", spawner, id, record.agent.role.title, session_id);
+        Ok(record)
+    }
+
+    /// 仍在运行的临时 Agent，`session_id` 不为空时只列出该会话的
+    pub async fn list_active(&self, session_id: Option<&str>) -> Vec<TemporaryAgent> {
+        let records = self.records.lock().await;
+        let mut active: Vec<TemporaryAgent> = records
+            .values()
+            .filter(|r| r.is_active() && session_id.is_none_or(|s| r.session_id == s))
+            .cloned()
+            .collect();
+        active.sort_by_key(|r| r.created_at);
+        active
+    }
+
+    /// 查看临时 Agent 记录（含已过期的）
+    pub async fn get(&self, agent_id: &str) -> Option<TemporaryAgent> {
+        self.records.lock().await.get(agent_id).cloned()
+    }
+
+    /// 立即让临时 Agent 过期
+    pub async fn expire(&self, agent_id: &str) -> Result<TemporaryAgent> {
+        let mut records = self.records.lock().await;
+        let record = records
+            .get_mut(agent_id)
+            .filter(|r| r.is_active())
+            .ok_or_else(|| TempAgentError::NotFound { agent_id: agent_id.to_string() })?;
+        self.shut_down(record).await;
+        let record = record.clone();
+        self.save(&records).await?;
+        Ok(record)
+    }
+
+    /// 让闲置超时或到达存活时间的临时 Agent 过期，返回过期的 ID
+    pub async fn expire_idle(&self) -> Vec<String> {
+        let now = self.clock.now();
+        let mut records = self.records.lock().await;
+        let mut expired = Vec::new();
+        for record in records.values_mut().filter(|r| r.is_active()) {
+            record.last_active_at = record.last_active_at.max(self.last_message_at(&record.agent.id).await);
+            if record.is_due(now, self.config.idle_timeout_secs) {
+                self.shut_down(record).await;
+                expired.push(record.agent.id.clone());
+            }
+        }
+        if !expired.is_empty() {
+            if let Err(e) = self.save(&records).await {
+                warn!("Failed to save expired temporary agents: {}", e);
+            }
+        }
+        expired
+    }
+
+    /// 在后台定期检查过期
+    pub fn spawn_sweeper(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.expire_idle().await;
+            }
+        })
+    }
+
+    /// 最近一条由该 Agent 发出或发给它的消息的时间
+    async fn last_message_at(&self, agent_id: &str) -> i64 {
+        let mut latest = 0;
+        for filter in [MessageFilter::new().from(agent_id), MessageFilter::new().to(agent_id)] {
+            match self.store.load_messages(filter.limit(1)).await {
+                Ok(messages) => latest = messages.iter().map(|m| m.timestamp).fold(latest, i64::max),
+                Err(e) => warn!("Failed to load recent messages of {}: {}", agent_id, e),
+            }
+        }
+        latest
+    }
+
+    /// 停止运行、注销并退出群聊，通知创建者；会话中的消息保留在存储中
+    async fn shut_down(&self, record: &mut TemporaryAgent) {
+        let agent_id = record.agent.id.clone();
+        if let Some(runner) = self.runner.get() {
+            runner.stop(&agent_id).await;
+        }
+        self.bus.unregister(&agent_id);
+        if self.bus.get_group(&record.session_id).await.is_some() {
+            if let Err(e) = self.bus.leave_group(&record.session_id, &agent_id).await {
+                warn!("Failed to remove temporary agent {} from {}: {}", agent_id, record.session_id, e);
+            }
+        }
+        record.expired_at = Some(self.clock.now());
+        info!("Temporary agent {} expired (session {})", agent_id, record.session_id);
+
+        let notice = self.bus.catalog().format(
+            "temp_agent.expired_notice",
+            &[("name", &record.agent.name), ("session_id", &record.session_id)],
+        );
+        if let Err(e) = self.bus.send(Message::private(TEMP_AGENT_SENDER, &record.spawned_by, notice)).await {
+            warn!("Failed to notify {} of expired temporary agent {}: {}", record.spawned_by, agent_id, e);
+        }
+    }
+
+    async fn save(&self, records: &HashMap<String, TemporaryAgent>) -> Result<()> {
+        let mut all: Vec<&TemporaryAgent> = records.values().collect();
+        all.sort_by_key(|r| r.created_at);
+        self.store.save_app_state(TEMP_AGENTS_KEY, &serde_json::to_value(all)?).await
+    }
+}
+
+impl std::fmt::Debug for TemporaryAgents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TemporaryAgents")
+            .field("config", &self.config)
+            .field("running", &self.runner.get().is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,323 @@
+//! 消息模板
+//!
+//! 周期性输出（状态汇报、站会总结等）使用固定模板而不是依赖 LLM 保持格式。
+//! 模板中的占位符写作 `{{name}}`，`\{{` 表示字面量 `{{`。
+//!
+//! 渲染只扫描一遍模板：变量值原样插入，其中的 `{{...}}` 不会被再次展开。
+//!
+//! 所有用户提供的模板（角色和公司模板、主动问候模板、审核拦截提示）都经过 [`TemplateSandbox`] 渲染：
+//! - 只能引用调用处列出的变量，引用其他变量时报出变量名（[`TemplateError::ForbiddenVariable`]）
+//! - 没有表达式、过滤器或文件包含，占位符只能是变量名
+//! - 输出超过上限（默认 [`DEFAULT_MAX_OUTPUT_BYTES`]）时停止渲染并报错，不会先拼出超长的字符串
+//! - 调用处按输出用途选择转义方式（[`Escape`]），只转义变量值，模板正文原样输出
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::domain::Agent;
+
+/// 渲染输出的默认上限（字节）
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// 模板渲染错误
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    #[error("Missing template variables: {}", .0.join(", "))]
+    MissingVariables(Vec<String>),
+    #[error("Unterminated placeholder at byte {0}")]
+    Unterminated(usize),
+    #[error("Invalid placeholder {0:?}: only variable names are allowed")]
+    InvalidPlaceholder(String),
+    #[error("Template variable {0} is not available here")]
+    ForbiddenVariable(String),
+    #[error("Rendered template exceeds {0} bytes")]
+    OutputTooLarge(usize),
+}
+
+/// 模板片段
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// 解析模板
+fn parse(template: &str) -> Result<Vec<Segment<'_>>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut rest = template;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find("{{") {
+        // 转义的 `\{{`
+        if start > 0 && rest.as_bytes()[start - 1] == b'\\' {
+            segments.push(Segment::Text(&rest[..start - 1]));
+            segments.push(Segment::Text("{{"));
+            rest = &rest[start + 2..];
+            offset += start + 2;
+            continue;
+        }
+
+        let end = rest[start + 2..]
+            .find("}}")
+            .ok_or(TemplateError::Unterminated(offset + start))?;
+        let name = rest[start + 2..start + 2 + end].trim();
+        if !is_variable_name(name) {
+            return Err(TemplateError::InvalidPlaceholder(name.to_string()));
+        }
+        segments.push(Segment::Text(&rest[..start]));
+        segments.push(Segment::Placeholder(name));
+        rest = &rest[start + 2 + end + 2..];
+        offset += start + 2 + end + 2;
+    }
+    segments.push(Segment::Text(rest));
+
+    Ok(segments)
+}
+
+/// 模板中的占位符（去重，按出现顺序）
+pub fn placeholders(template: &str) -> Result<Vec<String>, TemplateError> {
+    let mut names: Vec<String> = Vec::new();
+    for segment in parse(template)? {
+        if let Segment::Placeholder(name) = segment {
+            if !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// 变量名：字母、数字、`_`、`-` 和 `.`
+fn is_variable_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// 使用 JSON 对象中的变量渲染模板，缺少变量时列出全部缺失项
+///
+/// 可以引用调用方提供的任何变量，不转义，输出上限为 [`DEFAULT_MAX_OUTPUT_BYTES`]。
+pub fn render(template: &str, variables: &Value) -> Result<String, TemplateError> {
+    TemplateSandbox::new().render(template, variables)
+}
+
+/// 变量值的转义方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Escape {
+    /// 原样插入（聊天消息、提示词）
+    #[default]
+    None,
+    /// 转义 HTML 特殊字符（邮件、网页）
+    Html,
+    /// 转义为 JSON 字符串内容，模板负责写引号（webhook 载荷）
+    Json,
+}
+
+impl Escape {
+    /// 按名称（`none`、`html`、`json`）解析
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Escape::None),
+            "html" => Some(Escape::Html),
+            "json" => Some(Escape::Json),
+            _ => None,
+        }
+    }
+
+    fn apply(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Escape::None => Cow::Borrowed(value),
+            Escape::Html => {
+                if !value.contains(['&', '<', '>', '"', '\'']) {
+                    return Cow::Borrowed(value);
+                }
+                let mut escaped = String::with_capacity(value.len() + 16);
+                for c in value.chars() {
+                    match c {
+                        '&' => escaped.push_str("&amp;"),
+                        '<' => escaped.push_str("&lt;"),
+                        '>' => escaped.push_str("&gt;"),
+                        '"' => escaped.push_str("&quot;"),
+                        '\'' => escaped.push_str("&#39;"),
+                        c => escaped.push(c),
+                    }
+                }
+                Cow::Owned(escaped)
+            }
+            Escape::Json => {
+                let quoted = Value::String(value.to_string()).to_string();
+                Cow::Owned(quoted[1..quoted.len() - 1].to_string())
+            }
+        }
+    }
+}
+
+/// 受限的模板渲染
+///
+/// 每个调用处创建自己的沙箱，列出模板可以引用的变量；未列出时可以引用调用方提供的任何变量
+/// （变量本身就来自调用者的场合，如 `template.render` 工具）。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateSandbox {
+    allowed: Option<Vec<String>>,
+    escape: Escape,
+    max_output_bytes: usize,
+}
+
+impl Default for TemplateSandbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemplateSandbox {
+    pub fn new() -> Self {
+        Self {
+            allowed: None,
+            escape: Escape::None,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        }
+    }
+
+    /// 只允许引用这些变量
+    pub fn with_variables(mut self, names: &[&str]) -> Self {
+        self.allowed = Some(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    /// 设置变量值的转义方式
+    pub fn with_escape(mut self, escape: Escape) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    /// 设置输出上限（字节）
+    pub fn with_max_output_bytes(mut self, max_output_bytes: usize) -> Self {
+        self.max_output_bytes = max_output_bytes;
+        self
+    }
+
+    /// 允许引用的变量，None 表示不限
+    pub fn variables(&self) -> Option<&[String]> {
+        self.allowed.as_deref()
+    }
+
+    fn allows(&self, name: &str) -> bool {
+        self.allowed.as_ref().is_none_or(|allowed| allowed.iter().any(|n| n == name))
+    }
+
+    /// 检查模板能否在这个沙箱中渲染（不需要变量），返回其中的占位符
+    ///
+    /// 保存模板时调用，不合法的模板在配置阶段就被拒绝，而不是等到触发时才失败。
+    pub fn check(&self, template: &str) -> Result<Vec<String>, TemplateError> {
+        let names = placeholders(template)?;
+        if let Some(name) = names.iter().find(|name| !self.allows(name)) {
+            return Err(TemplateError::ForbiddenVariable(name.clone()));
+        }
+        Ok(names)
+    }
+
+    /// 渲染模板
+    ///
+    /// 先检查再输出，错误与变量值无关：模板语法错误，其次是按出现顺序第一个不允许的变量，
+    /// 再次是全部缺失的变量，最后是超出输出上限。
+    pub fn render(&self, template: &str, variables: &Value) -> Result<String, TemplateError> {
+        let segments = parse(template)?;
+        if let Some(name) = segments.iter().find_map(|segment| match segment {
+            Segment::Placeholder(name) if !self.allows(name) => Some(*name),
+            _ => None,
+        }) {
+            return Err(TemplateError::ForbiddenVariable(name.to_string()));
+        }
+
+        let empty = serde_json::Map::new();
+        let variables = variables.as_object().unwrap_or(&empty);
+        let mut missing: Vec<String> = Vec::new();
+        for segment in &segments {
+            if let Segment::Placeholder(name) = segment {
+                if matches!(variables.get(*name), Some(Value::Null) | None) && !missing.iter().any(|n| n == name) {
+                    missing.push(name.to_string());
+                }
+            }
+        }
+        if !missing.is_empty() {
+            return Err(TemplateError::MissingVariables(missing));
+        }
+
+        let mut output = String::with_capacity(template.len().min(self.max_output_bytes));
+        for segment in segments {
+            let piece = match segment {
+                Segment::Text(text) => Cow::Borrowed(text),
+                Segment::Placeholder(name) => match variables.get(name) {
+                    Some(Value::String(s)) => self.escape.apply(s),
+                    Some(other) => Cow::Owned(self.escape.apply(&other.to_string()).into_owned()),
+                    None => Cow::Borrowed(""),
+                },
+            };
+            if output.len() + piece.len() > self.max_output_bytes {
+                return Err(TemplateError::OutputTooLarge(self.max_output_bytes));
+            }
+            output.push_str(&piece);
+        }
+        Ok(output)
+    }
+}
+
+/// 查找模板：先查 Agent 角色自己的模板，再查公司级模板
+pub fn resolve<'a>(
+    agent: Option<&'a Agent>,
+    company_templates: &'a HashMap<String, String>,
+    name: &str,
+) -> Option<&'a str> {
+    agent
+        .and_then(|agent| agent.role.templates.get(name))
+        .or_else(|| company_templates.get(name))
+        .map(String::as_str)
+}
+
+/// 模板列表项
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    /// `company` 或 `role`
+    pub scope: String,
+    /// 角色模板所属的角色名称
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    pub text: String,
+    pub placeholders: Vec<String>,
+}
+
+/// 列出公司级模板和各角色模板（角色按名称去重）
+pub fn list(company_templates: &HashMap<String, String>, agents: &[Agent]) -> Vec<TemplateInfo> {
+    let info = |name: &str, text: &str, role: Option<&str>| TemplateInfo {
+        name: name.to_string(),
+        scope: if role.is_some() { "role" } else { "company" }.to_string(),
+        role: role.map(str::to_string),
+        text: text.to_string(),
+        placeholders: placeholders(text).unwrap_or_default(),
+    };
+
+    let mut templates: Vec<TemplateInfo> = company_templates
+        .iter()
+        .map(|(name, text)| info(name, text, None))
+        .collect();
+
+    let mut seen_roles: Vec<&str> = Vec::new();
+    for agent in agents {
+        let title = agent.role.title.as_str();
+        if seen_roles.contains(&title) {
+            continue;
+        }
+        seen_roles.push(title);
+        templates.extend(
+            agent.role.templates
+                .iter()
+                .map(|(name, text)| info(name, text, Some(title))),
+        );
+    }
+
+    templates.sort_by(|a, b| (&a.role, &a.name).cmp(&(&b.role, &b.name)));
+    templates
+}
@@ -0,0 +1,269 @@
+//! Token 计数
+//!
+//! 上下文预算、工具结果截断和用量估算都按 token 计算。按模型族选择分词器：
+//! 启用 `tiktoken` 特性时使用与 OpenAI 兼容的 BPE（`cl100k_base`、`o200k_base` 等），
+//! 否则退回按字符估算——中日韩字符按每字一个 token 计，其余按每 4 个字符一个 token 计。
+//! 同一条消息的计数按消息 ID 缓存，内容变化（如编辑）后重新计数。
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tracing::warn;
+
+use crate::domain::{LLMConfig, Message, MessageId};
+
+/// 估算分词器的名称
+pub const HEURISTIC_TOKENIZER: &str = "heuristic";
+
+/// 每条消息除内容外的固定开销（发送者、时间等）
+pub const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// 缓存的消息数上限，超出时清空重建
+const MAX_CACHED_MESSAGES: usize = 10_000;
+
+/// 分词器
+pub trait Tokenizer: Send + Sync + std::fmt::Debug {
+    /// 分词器名称，如 `cl100k_base`
+    fn name(&self) -> &str;
+
+    /// 文本的 token 数
+    fn count(&self, text: &str) -> usize;
+
+    /// 不超过 `max_tokens` 的最长前缀
+    fn truncate<'a>(&self, text: &'a str, max_tokens: usize) -> &'a str {
+        if self.count(text) <= max_tokens {
+            return text;
+        }
+        let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).skip(1).collect();
+        // 在字符边界上二分查找
+        let (mut low, mut high) = (0, boundaries.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if self.count(&text[..boundaries[mid - 1]]) <= max_tokens {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        match low {
+            0 => "",
+            n => &text[..boundaries[n - 1]],
+        }
+    }
+}
+
+/// 按字符估算：中日韩字符每字一个 token，其余每 4 个字符一个 token
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn name(&self) -> &str {
+        HEURISTIC_TOKENIZER
+    }
+
+    fn count(&self, text: &str) -> usize {
+        let wide = text.chars().filter(|c| is_wide(*c)).count();
+        let narrow = text.chars().count() - wide;
+        wide + narrow.div_ceil(4)
+    }
+}
+
+/// 中日韩文字及全角符号
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3000..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF
+    )
+}
+
+/// 与 tiktoken 兼容的 BPE 分词器
+#[cfg(feature = "tiktoken")]
+pub struct BpeTokenizer {
+    name: String,
+    bpe: &'static tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl BpeTokenizer {
+    /// 按编码名创建：`o200k_base`、`cl100k_base`、`p50k_base`、`p50k_edit`、`r50k_base`
+    pub fn new(encoding: &str) -> Option<Self> {
+        let bpe = match encoding {
+            "o200k_base" => tiktoken_rs::o200k_base_singleton(),
+            "cl100k_base" => tiktoken_rs::cl100k_base_singleton(),
+            "p50k_base" => tiktoken_rs::p50k_base_singleton(),
+            "p50k_edit" => tiktoken_rs::p50k_edit_singleton(),
+            "r50k_base" | "gpt2" => tiktoken_rs::r50k_base_singleton(),
+            _ => return None,
+        };
+        Some(Self {
+            name: encoding.to_string(),
+            bpe,
+        })
+    }
+
+    /// 模型名对应的编码，如 `gpt-4o` -> `o200k_base`
+    pub fn for_model(model: &str) -> Option<Self> {
+        use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer as Encoding};
+
+        let encoding = match get_tokenizer(model)? {
+            Encoding::O200kBase => "o200k_base",
+            Encoding::Cl100kBase => "cl100k_base",
+            Encoding::P50kBase => "p50k_base",
+            Encoding::P50kEdit => "p50k_edit",
+            Encoding::R50kBase | Encoding::Gpt2 => "r50k_base",
+        };
+        Self::new(encoding)
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl std::fmt::Debug for BpeTokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BpeTokenizer").field("name", &self.name).finish()
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl Tokenizer for BpeTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> usize {
+        self.bpe.encode_with_special_tokens(text).len()
+    }
+}
+
+/// 按 LLM 配置选择分词器：显式配置的 `tokenizer` 优先，其次按模型名推断，都不可用时退回估算
+pub fn tokenizer_for(config: &LLMConfig) -> Arc<dyn Tokenizer> {
+    match config.tokenizer.as_deref() {
+        Some(name) => named_tokenizer(name).unwrap_or_else(|| {
+            warn!("Tokenizer {} is not available, falling back to the heuristic", name);
+            Arc::new(HeuristicTokenizer)
+        }),
+        None => model_tokenizer(&config.model).unwrap_or_else(|| Arc::new(HeuristicTokenizer)),
+    }
+}
+
+fn named_tokenizer(name: &str) -> Option<Arc<dyn Tokenizer>> {
+    if name == HEURISTIC_TOKENIZER {
+        return Some(Arc::new(HeuristicTokenizer));
+    }
+    #[cfg(feature = "tiktoken")]
+    if let Some(bpe) = BpeTokenizer::new(name) {
+        return Some(Arc::new(bpe));
+    }
+    None
+}
+
+#[cfg(feature = "tiktoken")]
+fn model_tokenizer(model: &str) -> Option<Arc<dyn Tokenizer>> {
+    BpeTokenizer::for_model(model).map(|bpe| Arc::new(bpe) as Arc<dyn Tokenizer>)
+}
+
+#[cfg(not(feature = "tiktoken"))]
+fn model_tokenizer(_model: &str) -> Option<Arc<dyn Tokenizer>> {
+    None
+}
+
+/// 带缓存的 token 计数器
+///
+/// 消息计数按消息 ID 缓存，同时记下内容摘要：同一 ID 的内容变化后重新计数，
+/// 避免每轮都重新分词整段历史。
+#[derive(Debug)]
+pub struct TokenCounter {
+    tokenizer: Arc<dyn Tokenizer>,
+    /// 消息 ID -> (内容摘要, token 数)
+    cache: DashMap<MessageId, (u64, usize)>,
+}
+
+impl TokenCounter {
+    pub fn new(tokenizer: Arc<dyn Tokenizer>) -> Self {
+        Self {
+            tokenizer,
+            cache: DashMap::new(),
+        }
+    }
+
+    /// 按 LLM 配置选择分词器
+    pub fn for_config(config: &LLMConfig) -> Self {
+        Self::new(tokenizer_for(config))
+    }
+
+    pub fn tokenizer(&self) -> Arc<dyn Tokenizer> {
+        self.tokenizer.clone()
+    }
+
+    /// 文本的 token 数（不缓存）
+    pub fn count_text(&self, text: &str) -> usize {
+        self.tokenizer.count(text)
+    }
+
+    /// 消息的 token 数（含固定开销），按消息 ID 缓存
+    pub fn count_message(&self, message: &Message) -> usize {
+        let digest = content_digest(&message.content);
+        if let Some(entry) = self.cache.get(&message.id) {
+            if entry.0 == digest {
+                return entry.1;
+            }
+        }
+        let count = self.tokenizer.count(&message.content) + MESSAGE_OVERHEAD_TOKENS;
+        if self.cache.len() >= MAX_CACHED_MESSAGES {
+            self.cache.clear();
+        }
+        self.cache.insert(message.id.clone(), (digest, count));
+        count
+    }
+
+    /// 多条消息的 token 总数
+    pub fn count_messages(&self, messages: &[Message]) -> usize {
+        messages.iter().map(|m| self.count_message(m)).sum()
+    }
+
+    /// 丢弃消息的缓存计数
+    pub fn invalidate(&self, message_id: &str) {
+        self.cache.remove(message_id);
+    }
+
+    /// 已缓存计数的消息数
+    pub fn cached(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// 从最新的消息往前保留，总数不超过 `budget`；返回保留的消息（按原顺序）
+    pub fn fit_newest<'a>(&self, messages: &'a [Message], budget: usize) -> &'a [Message] {
+        let mut used = 0;
+        let mut start = messages.len();
+        for (i, message) in messages.iter().enumerate().rev() {
+            used += self.count_message(message);
+            if used > budget {
+                break;
+            }
+            start = i;
+        }
+        &messages[start..]
+    }
+
+    /// 截断到不超过 `max_tokens`，截断时加省略号
+    pub fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        truncate_with(self.tokenizer.as_ref(), text, max_tokens)
+    }
+}
+
+/// 用给定分词器截断到不超过 `max_tokens`，截断时加省略号
+pub fn truncate_with(tokenizer: &dyn Tokenizer, text: &str, max_tokens: usize) -> String {
+    let kept = tokenizer.truncate(text, max_tokens);
+    if kept.len() == text.len() {
+        text.to_string()
+    } else {
+        format!("{}…", kept)
+    }
+}
+
+fn content_digest(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
@@ -3,12 +3,18 @@
 //! 提供工具的多级分类管理和查询能力
 
 use anyhow::{Context, Result};
+use chrono::NaiveDate;
 use dashmap::DashMap;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use tokio::sync::RwLock;
-use tracing::debug;
+use tracing::{debug, info};
 
-use crate::domain::tool::{CategoryPath, Tool};
+use crate::domain::tool::{CategoryPath, OpenAIFunctionError, OpenAIFunctionImport, Tool, UNNAMED_FUNCTION};
+use crate::infrastructure::tool::{ToolExecutor, ToolExecutorRegistry};
 
 /// 工具注册表 - 支持多级分类查询
 pub struct ToolRegistry {
@@ -16,6 +22,113 @@ pub struct ToolRegistry {
     tools: DashMap<String, Tool>,
     /// 分类树根节点（层级遍历）
     category_root: RwLock<CategoryNode>,
+    /// 每次注册/注销递增，用于让缓存失效
+    generation: AtomicU64,
+    /// 旧工具ID -> 别名（重命名后的兼容路由）
+    aliases: DashMap<String, ToolAlias>,
+    /// 停用日期之后是否拒绝别名调用
+    hard_fail_after_sunset: AtomicBool,
+}
+
+/// 工具别名：旧ID的调用路由到规范ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolAlias {
+    /// 旧ID
+    pub alias: String,
+    /// 规范ID
+    pub canonical: String,
+    /// 停用日期（UTC），之后是否拒绝由注册表的开关决定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset: Option<NaiveDate>,
+}
+
+impl ToolAlias {
+    /// 指定日期是否已过停用日期
+    pub fn is_past_sunset(&self, today: NaiveDate) -> bool {
+        self.sunset.is_some_and(|sunset| today > sunset)
+    }
+}
+
+/// 工具ID的路由结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolRoute {
+    /// 不是别名，按原ID调用
+    Direct,
+    /// 已弃用的别名，转到规范ID
+    Alias(ToolAlias),
+    /// 已过停用日期且开启了拒绝，调用失败
+    Retired(ToolAlias),
+}
+
+/// 工具别名配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolAliasConfig {
+    /// 旧ID
+    pub from: String,
+    /// 新ID
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sunset: Option<NaiveDate>,
+}
+
+/// 工具弃用配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolDeprecationConfig {
+    /// 重命名工具的别名
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<ToolAliasConfig>,
+    /// 停用日期之后拒绝别名调用（默认只记录警告）
+    #[serde(default)]
+    pub hard_fail_after_sunset: bool,
+}
+
+/// OpenAI 函数定义的来源：JSON 文件或已解析的值
+#[derive(Debug, Clone)]
+pub enum OpenAIFunctionSource {
+    Path(PathBuf),
+    Value(Value),
+}
+
+impl From<&Path> for OpenAIFunctionSource {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl From<PathBuf> for OpenAIFunctionSource {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl From<Value> for OpenAIFunctionSource {
+    fn from(value: Value) -> Self {
+        Self::Value(value)
+    }
+}
+
+impl OpenAIFunctionSource {
+    /// 取出函数定义列表：支持数组、`{"tools": [...]}`（Assistants）、`{"functions": [...]}` 和单个函数
+    fn definitions(self) -> Result<Vec<Value>> {
+        let value = match self {
+            Self::Path(path) => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read OpenAI functions from {}", path.display()))?;
+                serde_json::from_str(&text)
+                    .with_context(|| format!("Failed to parse OpenAI functions from {}", path.display()))?
+            }
+            Self::Value(value) => value,
+        };
+        Ok(match value {
+            Value::Array(items) => items,
+            Value::Object(mut object) => match object.remove("tools").or_else(|| object.remove("functions")) {
+                Some(Value::Array(items)) => items,
+                Some(_) => return Err(anyhow::anyhow!("OpenAI `tools` / `functions` must be an array")),
+                None => vec![Value::Object(object)],
+            },
+            _ => return Err(anyhow::anyhow!("OpenAI functions must be an array or an object")),
+        })
+    }
 }
 
 impl ToolRegistry {
@@ -24,6 +137,9 @@ impl ToolRegistry {
         Self {
             tools: DashMap::new(),
             category_root: RwLock::new(CategoryNode::new("root")),
+            generation: AtomicU64::new(0),
+            aliases: DashMap::new(),
+            hard_fail_after_sunset: AtomicBool::new(false),
         }
     }
 
@@ -36,6 +152,9 @@ impl ToolRegistry {
         if self.tools.contains_key(&tool_id) {
             return Err(anyhow::anyhow!("Tool already registered: {}", tool_id));
         }
+        if self.aliases.contains_key(&tool_id) {
+            return Err(anyhow::anyhow!("Tool id is registered as an alias: {}", tool_id));
+        }
 
         // 插入工具
         self.tools.insert(tool_id.clone(), tool);
@@ -43,6 +162,7 @@ impl ToolRegistry {
         // 更新分类树
         let mut root = self.category_root.write().await;
         root.add_tool(&category, &tool_id);
+        self.generation.fetch_add(1, Ordering::Relaxed);
 
         debug!("Registered tool: {} in category: {}", tool_id, category.to_path_string());
         Ok(())
@@ -175,11 +295,155 @@ impl ToolRegistry {
 
         let mut root = self.category_root.write().await;
         root.remove_tool(&tool.category, tool_id);
+        self.generation.fetch_add(1, Ordering::Relaxed);
 
         debug!("Unregistered tool: {}", tool_id);
         Ok(())
     }
 
+    /// 注册别名：调用 `old_id` 时转到 `new_id` 的执行器并附带弃用提示
+    ///
+    /// `new_id` 本身是别名时指向它的规范ID；已指向 `old_id` 的别名改为指向 `new_id`
+    pub fn register_alias(&self, old_id: &str, new_id: &str) -> Result<()> {
+        let canonical = self.canonical_id(new_id);
+        if canonical == old_id {
+            return Err(anyhow::anyhow!("Tool alias {} would point to itself", old_id));
+        }
+        if self.tools.contains_key(old_id) {
+            return Err(anyhow::anyhow!("Tool already registered: {}", old_id));
+        }
+
+        for mut alias in self.aliases.iter_mut() {
+            if alias.canonical == old_id {
+                alias.canonical = canonical.clone();
+            }
+        }
+        let sunset = self.aliases.get(old_id).and_then(|a| a.sunset);
+        self.aliases.insert(
+            old_id.to_string(),
+            ToolAlias {
+                alias: old_id.to_string(),
+                canonical: canonical.clone(),
+                sunset,
+            },
+        );
+        self.generation.fetch_add(1, Ordering::Relaxed);
+
+        debug!("Registered tool alias: {} -> {}", old_id, canonical);
+        Ok(())
+    }
+
+    /// 设置别名的停用日期
+    pub fn set_alias_sunset(&self, old_id: &str, sunset: Option<NaiveDate>) -> Result<()> {
+        let mut alias = self.aliases.get_mut(old_id).context("Tool alias not found")?;
+        alias.sunset = sunset;
+        Ok(())
+    }
+
+    /// 停用日期之后拒绝别名调用
+    pub fn set_hard_fail_after_sunset(&self, enabled: bool) {
+        self.hard_fail_after_sunset.store(enabled, Ordering::Relaxed);
+    }
+
+    /// 按配置注册别名
+    pub fn apply_deprecation_config(&self, config: &ToolDeprecationConfig) -> Result<()> {
+        self.set_hard_fail_after_sunset(config.hard_fail_after_sunset);
+        for alias in &config.aliases {
+            self.register_alias(&alias.from, &alias.to)?;
+            self.set_alias_sunset(&alias.from, alias.sunset)?;
+        }
+        Ok(())
+    }
+
+    /// 获取别名
+    pub fn alias(&self, id: &str) -> Option<ToolAlias> {
+        self.aliases.get(id).map(|a| a.clone())
+    }
+
+    /// 所有别名（按旧ID排序）
+    pub fn aliases(&self) -> Vec<ToolAlias> {
+        let mut aliases: Vec<ToolAlias> = self.aliases.iter().map(|a| a.clone()).collect();
+        aliases.sort_by(|a, b| a.alias.cmp(&b.alias));
+        aliases
+    }
+
+    /// 规范ID：别名返回指向的ID，其他原样返回
+    pub fn canonical_id(&self, id: &str) -> String {
+        self.aliases
+            .get(id)
+            .map(|a| a.canonical.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// 按今天（UTC）的日期决定调用路由
+    pub fn route(&self, id: &str) -> ToolRoute {
+        self.route_at(id, chrono::Utc::now().date_naive())
+    }
+
+    /// 按指定日期决定调用路由
+    pub fn route_at(&self, id: &str, today: NaiveDate) -> ToolRoute {
+        match self.alias(id) {
+            None => ToolRoute::Direct,
+            Some(alias) if alias.is_past_sunset(today) && self.hard_fail_after_sunset.load(Ordering::Relaxed) => {
+                ToolRoute::Retired(alias)
+            }
+            Some(alias) => ToolRoute::Alias(alias),
+        }
+    }
+
+    /// 批量导入 OpenAI 函数定义，每个工具用 `executor_factory` 创建的执行器注册
+    ///
+    /// 先校验全部定义，有任何错误（含与已注册工具重名）时一个都不注册；
+    /// Assistants 的内置工具（如 `code_interpreter`）会被跳过
+    pub async fn import_openai_functions<F>(
+        &self,
+        source: impl Into<OpenAIFunctionSource>,
+        options: &OpenAIFunctionImport,
+        executors: &mut ToolExecutorRegistry,
+        mut executor_factory: F,
+    ) -> Result<Vec<Tool>>
+    where
+        F: FnMut(&Tool) -> Box<dyn ToolExecutor>,
+    {
+        let mut tools = Vec::new();
+        let mut seen = HashSet::new();
+        for (index, definition) in source.into().definitions()?.iter().enumerate() {
+            if let Some(kind) = definition.get("type").and_then(Value::as_str).filter(|t| *t != "function") {
+                debug!("Skipping non-function OpenAI tool #{} of type {}", index, kind);
+                continue;
+            }
+            let tool = Tool::from_openai_function_with(definition, options).map_err(|mut e| {
+                if e.function == UNNAMED_FUNCTION {
+                    e.function = format!("#{}", index);
+                }
+                e
+            })?;
+            if !seen.insert(tool.id.clone()) {
+                return Err(OpenAIFunctionError { function: tool.name, reason: "defined more than once".to_string() }.into());
+            }
+            if self.contains(&tool.id) || self.alias(&tool.id).is_some() {
+                return Err(OpenAIFunctionError {
+                    reason: format!("tool {} is already registered", tool.id),
+                    function: tool.name,
+                }
+                .into());
+            }
+            tools.push(tool);
+        }
+
+        for tool in &tools {
+            self.register(tool.clone()).await?;
+            executors.register(executor_factory(tool));
+        }
+        info!("Imported {} OpenAI functions as tools", tools.len());
+        Ok(tools)
+    }
+
+    /// 注册表版本号，工具增删后变化
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
     /// 获取工具数量
     pub fn len(&self) -> usize {
         self.tools.len()
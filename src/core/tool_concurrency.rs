@@ -0,0 +1,356 @@
+//! 工具并发控制
+//!
+//! 部署工具、写同一工作区的文件工具、限流的外部 API 等不能随意并发。工具可以声明
+//! `max_concurrency`（同一工具同时执行的上限）和 `mutex_group`（同组工具互斥执行），
+//! 不归自己管的工具可以在配置里覆盖这两项。`ToolExecutorRegistry` 执行前先在这里取得许可，
+//! 工具繁忙时按策略等待或直接拒绝；排队时长计入指标，并写进 `ToolResult` 的元数据。
+//!
+//! 信号量和互斥锁在工具第一次执行时创建并缓存，没有限制的工具只多一次 DashMap 查询。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+
+use crate::domain::tool::Tool;
+
+/// 工具繁忙时的处理策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BusyPolicy {
+    /// 排队等待
+    #[default]
+    Wait,
+    /// 立即拒绝
+    Reject,
+}
+
+/// 单个工具的并发限制
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolConcurrencyLimit {
+    /// 同一工具同时执行的上限
+    pub max_concurrency: Option<u32>,
+    /// 互斥组，同组工具一次只执行一个
+    pub mutex_group: Option<String>,
+}
+
+impl ToolConcurrencyLimit {
+    /// 工具自身声明的限制
+    pub fn of(tool: &Tool) -> Self {
+        Self {
+            max_concurrency: tool.max_concurrency,
+            mutex_group: tool.mutex_group.clone(),
+        }
+    }
+
+    /// 限制同一工具的并发数
+    pub fn max_concurrency(n: u32) -> Self {
+        Self {
+            max_concurrency: Some(n),
+            mutex_group: None,
+        }
+    }
+
+    /// 加入互斥组
+    pub fn mutex_group(group: impl Into<String>) -> Self {
+        Self {
+            max_concurrency: None,
+            mutex_group: Some(group.into()),
+        }
+    }
+
+    pub fn is_unlimited(&self) -> bool {
+        self.max_concurrency.is_none() && self.mutex_group.is_none()
+    }
+}
+
+/// 工具并发控制配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolConcurrencyConfig {
+    /// 工具繁忙时等待还是拒绝
+    pub policy: BusyPolicy,
+    /// 最长等待时间（毫秒），超时后按繁忙处理；0 表示一直等待
+    pub max_wait_ms: u64,
+    /// 按工具ID覆盖工具自身声明的限制
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub tools: HashMap<String, ToolConcurrencyLimit>,
+}
+
+impl ToolConcurrencyConfig {
+    /// 设置繁忙策略
+    pub fn with_policy(mut self, policy: BusyPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// 设置最长等待时间
+    pub fn with_max_wait_ms(mut self, max_wait_ms: u64) -> Self {
+        self.max_wait_ms = max_wait_ms;
+        self
+    }
+
+    /// 覆盖某个工具的限制
+    pub fn with_tool(mut self, tool_id: impl Into<String>, limit: ToolConcurrencyLimit) -> Self {
+        self.tools.insert(tool_id.into(), limit);
+        self
+    }
+}
+
+/// 工具繁忙，未取得执行许可
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolBusy {
+    pub tool_id: String,
+    pub mutex_group: Option<String>,
+    /// 放弃前等待的时长
+    pub waited: Duration,
+}
+
+/// 执行许可，drop 时释放
+pub struct ToolPermit {
+    _group: Option<OwnedMutexGuard<()>>,
+    _slot: Option<OwnedSemaphorePermit>,
+    queued: bool,
+    waited: Duration,
+}
+
+impl ToolPermit {
+    fn free() -> Self {
+        Self {
+            _group: None,
+            _slot: None,
+            queued: false,
+            waited: Duration::ZERO,
+        }
+    }
+
+    /// 是否排过队
+    pub fn queued(&self) -> bool {
+        self.queued
+    }
+
+    /// 排队时长
+    pub fn waited(&self) -> Duration {
+        self.waited
+    }
+}
+
+/// 单个工具的排队指标
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ToolQueueMetrics {
+    pub tool_id: String,
+    /// 取得许可的次数
+    pub acquired: u64,
+    /// 需要排队的次数
+    pub queued: u64,
+    /// 因繁忙被拒绝的次数
+    pub rejected: u64,
+    pub total_wait_ms: u64,
+    pub max_wait_ms: u64,
+}
+
+struct Gate {
+    mutex_group: Option<String>,
+    group: Option<Arc<Mutex<()>>>,
+    slots: Option<Arc<Semaphore>>,
+}
+
+impl Gate {
+    fn is_open(&self) -> bool {
+        self.group.is_none() && self.slots.is_none()
+    }
+
+    fn try_enter(&self) -> Option<(Option<OwnedMutexGuard<()>>, Option<OwnedSemaphorePermit>)> {
+        let group = match &self.group {
+            Some(group) => Some(group.clone().try_lock_owned().ok()?),
+            None => None,
+        };
+        let slot = match &self.slots {
+            Some(slots) => Some(slots.clone().try_acquire_owned().ok()?),
+            None => None,
+        };
+        Some((group, slot))
+    }
+
+    /// 先取互斥组再取信号量，顺序固定避免死锁
+    async fn enter(&self) -> (Option<OwnedMutexGuard<()>>, Option<OwnedSemaphorePermit>) {
+        let group = match &self.group {
+            Some(group) => Some(group.clone().lock_owned().await),
+            None => None,
+        };
+        let slot = match &self.slots {
+            Some(slots) => slots.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        (group, slot)
+    }
+}
+
+#[derive(Default)]
+struct Counters {
+    acquired: AtomicU64,
+    queued: AtomicU64,
+    rejected: AtomicU64,
+    total_wait_ms: AtomicU64,
+    max_wait_ms: AtomicU64,
+}
+
+impl Counters {
+    fn record_wait(&self, waited: Duration) {
+        let ms = waited.as_millis() as u64;
+        self.total_wait_ms.fetch_add(ms, Ordering::Relaxed);
+        self.max_wait_ms.fetch_max(ms, Ordering::Relaxed);
+    }
+}
+
+/// 工具并发控制器
+pub struct ToolConcurrency {
+    config: ToolConcurrencyConfig,
+    gates: DashMap<String, Arc<Gate>>,
+    groups: DashMap<String, Arc<Mutex<()>>>,
+    counters: DashMap<String, Arc<Counters>>,
+}
+
+impl ToolConcurrency {
+    pub fn new(config: ToolConcurrencyConfig) -> Self {
+        Self {
+            config,
+            gates: DashMap::new(),
+            groups: DashMap::new(),
+            counters: DashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &ToolConcurrencyConfig {
+        &self.config
+    }
+
+    /// 工具生效的限制：配置覆盖优先于工具自身声明
+    pub fn limit_for(&self, tool_id: &str, tool: Option<&Tool>) -> ToolConcurrencyLimit {
+        match self.config.tools.get(tool_id) {
+            Some(limit) => limit.clone(),
+            None => tool.map(ToolConcurrencyLimit::of).unwrap_or_default(),
+        }
+    }
+
+    fn gate(&self, tool_id: &str, tool: impl FnOnce() -> Option<Tool>) -> Arc<Gate> {
+        if let Some(gate) = self.gates.get(tool_id) {
+            return gate.clone();
+        }
+
+        let limit = match self.config.tools.get(tool_id) {
+            Some(limit) => limit.clone(),
+            None => tool().as_ref().map(ToolConcurrencyLimit::of).unwrap_or_default(),
+        };
+        let gate = Gate {
+            group: limit.mutex_group.as_ref().map(|group| {
+                self.groups
+                    .entry(group.clone())
+                    .or_insert_with(|| Arc::new(Mutex::new(())))
+                    .clone()
+            }),
+            slots: limit
+                .max_concurrency
+                .map(|n| Arc::new(Semaphore::new(n.max(1) as usize))),
+            mutex_group: limit.mutex_group,
+        };
+        self.gates.entry(tool_id.to_string()).or_insert_with(|| Arc::new(gate)).clone()
+    }
+
+    fn counters(&self, tool_id: &str) -> Arc<Counters> {
+        if let Some(counters) = self.counters.get(tool_id) {
+            return counters.clone();
+        }
+        self.counters.entry(tool_id.to_string()).or_default().clone()
+    }
+
+    /// 取得执行许可；`tool` 只在第一次执行时调用，用于读取工具自身声明的限制
+    pub async fn acquire(&self, tool_id: &str, tool: impl FnOnce() -> Option<Tool>) -> Result<ToolPermit, ToolBusy> {
+        let gate = self.gate(tool_id, tool);
+        if gate.is_open() {
+            return Ok(ToolPermit::free());
+        }
+
+        let counters = self.counters(tool_id);
+        let busy = |waited| ToolBusy {
+            tool_id: tool_id.to_string(),
+            mutex_group: gate.mutex_group.clone(),
+            waited,
+        };
+
+        if let Some((group, slot)) = gate.try_enter() {
+            counters.acquired.fetch_add(1, Ordering::Relaxed);
+            return Ok(ToolPermit {
+                _group: group,
+                _slot: slot,
+                queued: false,
+                waited: Duration::ZERO,
+            });
+        }
+
+        if self.config.policy == BusyPolicy::Reject {
+            counters.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(busy(Duration::ZERO));
+        }
+
+        counters.queued.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let entered = if self.config.max_wait_ms > 0 {
+            tokio::time::timeout(Duration::from_millis(self.config.max_wait_ms), gate.enter())
+                .await
+                .ok()
+        } else {
+            Some(gate.enter().await)
+        };
+        let waited = started.elapsed();
+        counters.record_wait(waited);
+
+        match entered {
+            Some((group, slot)) => {
+                counters.acquired.fetch_add(1, Ordering::Relaxed);
+                Ok(ToolPermit {
+                    _group: group,
+                    _slot: slot,
+                    queued: true,
+                    waited,
+                })
+            }
+            None => {
+                counters.rejected.fetch_add(1, Ordering::Relaxed);
+                Err(busy(waited))
+            }
+        }
+    }
+
+    /// 有并发限制的工具的排队指标，按工具ID排序
+    pub fn metrics(&self) -> Vec<ToolQueueMetrics> {
+        let mut metrics: Vec<ToolQueueMetrics> = self
+            .counters
+            .iter()
+            .map(|entry| {
+                let c = entry.value();
+                ToolQueueMetrics {
+                    tool_id: entry.key().clone(),
+                    acquired: c.acquired.load(Ordering::Relaxed),
+                    queued: c.queued.load(Ordering::Relaxed),
+                    rejected: c.rejected.load(Ordering::Relaxed),
+                    total_wait_ms: c.total_wait_ms.load(Ordering::Relaxed),
+                    max_wait_ms: c.max_wait_ms.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        metrics.sort_by(|a, b| a.tool_id.cmp(&b.tool_id));
+        metrics
+    }
+}
+
+impl Default for ToolConcurrency {
+    fn default() -> Self {
+        Self::new(ToolConcurrencyConfig::default())
+    }
+}
@@ -198,12 +198,100 @@ fn merge_category_tree(target: &mut CategoryNodeInfo, source: CategoryNodeInfo)
 /// 框架内置工具提供者
 ///
 /// 提供框架级别的工具定义（不执行，只提供元数据）
-pub struct FrameworkToolProvider;
+pub struct FrameworkToolProvider {
+    email: bool,
+    handoff: bool,
+    code_run: bool,
+    temp_agents: bool,
+    scratchpad: bool,
+    goals: bool,
+    chat_sessions: bool,
+    group_stats: bool,
+}
 
 impl FrameworkToolProvider {
     /// 创建框架工具提供者
     pub fn new() -> Self {
-        Self
+        Self { email: false, handoff: false, code_run: false, temp_agents: false, scratchpad: false, goals: false, chat_sessions: false, group_stats: false }
+    }
+
+    /// 同时提供 `notify.email`（配置了 SMTP 时）
+    pub fn with_email(mut self) -> Self {
+        self.email = true;
+        self
+    }
+
+    /// 同时提供 `handoff`（公司配置启用转交时）
+    pub fn with_handoff(mut self) -> Self {
+        self.handoff = true;
+        self
+    }
+
+    /// 同时提供 `code.run`（公司配置启用代码沙箱时）
+    pub fn with_code_run(mut self) -> Self {
+        self.code_run = true;
+        self
+    }
+
+    /// 同时提供 `agent.spawn_temporary`（公司配置启用临时 Agent 时）
+    pub fn with_temp_agents(mut self) -> Self {
+        self.temp_agents = true;
+        self
+    }
+
+    /// 同时提供 `scratchpad.write`（公司配置启用草稿时）
+    pub fn with_scratchpad(mut self) -> Self {
+        self.scratchpad = true;
+        self
+    }
+
+    /// 同时提供 `self.set_goal` / `self.clear_goal`（公司配置启用目标时）
+    pub fn with_goals(mut self) -> Self {
+        self.goals = true;
+        self
+    }
+
+    /// 同时提供 `session.close`（公司配置启用会话生命周期时）
+    pub fn with_chat_sessions(mut self) -> Self {
+        self.chat_sessions = true;
+        self
+    }
+
+    /// 同时提供 `group.stats`（公司配置启用群聊活动统计时）
+    pub fn with_group_stats(mut self) -> Self {
+        self.group_stats = true;
+        self
+    }
+
+    /// 当前提供的工具：默认工具加上已启用的可选工具
+    fn tools(&self) -> Vec<Tool> {
+        let mut tools = Self::get_framework_tools();
+        if self.email {
+            tools.push(Self::create_notify_email());
+        }
+        if self.handoff {
+            tools.push(Self::create_handoff());
+        }
+        if self.code_run {
+            tools.push(Self::create_code_run());
+        }
+        if self.temp_agents {
+            tools.push(Self::create_agent_spawn_temporary());
+        }
+        if self.scratchpad {
+            tools.push(Self::create_scratchpad_write());
+        }
+        if self.goals {
+            tools.push(Self::create_self_set_goal());
+            tools.push(Self::create_self_clear_goal());
+        }
+        if self.chat_sessions {
+            tools.push(Self::create_session_close());
+        }
+        if self.group_stats {
+            tools.push(Self::create_group_stats());
+        }
+        tools
     }
 
     /// 获取所有框架工具定义
@@ -213,10 +301,19 @@ impl FrameworkToolProvider {
             Self::create_tool_search(),
             Self::create_tool_list_categories(),
             Self::create_tool_get_category_tools(),
+            Self::create_tool_stats(),
             // 消息发送类
             Self::create_message_send_direct(),
             Self::create_message_send_group(),
             Self::create_message_reply(),
+            Self::create_message_send_templated(),
+            Self::create_message_send_immediate(),
+            Self::create_message_react(),
+            Self::create_message_pin(),
+            // 记忆类
+            Self::create_memory_list_pins(),
+            // 模板类
+            Self::create_template_render(),
             // 时间类
             Self::create_time_now(),
             // 组织架构类
@@ -224,8 +321,25 @@ impl FrameworkToolProvider {
             Self::create_org_get_department(),
             Self::create_org_get_leader(),
             Self::create_org_find_agents(),
+            Self::create_org_find_users(),
             Self::create_org_get_sub_departments(),
             Self::create_org_get_subordinates(),
+            Self::create_org_get_recent_changes(),
+            // 自我配置类
+            Self::create_self_get_preferences(),
+            Self::create_self_update_preferences(),
+            // 会话记录类
+            Self::create_transcript_export(),
+            // 群聊类
+            Self::create_group_create_ephemeral(),
+            Self::create_group_invite(),
+            Self::create_group_kick(),
+            Self::create_group_mute(),
+            // 任务类
+            Self::create_task_create(),
+            Self::create_task_list_mine(),
+            Self::create_task_update_status(),
+            Self::create_task_comment(),
         ]
     }
 
@@ -252,6 +366,12 @@ impl FrameworkToolProvider {
                         .description("限制在指定分类下搜索")
                         .optional(),
                 )
+                .property(
+                    "include_unavailable",
+                    JsonSchema::boolean()
+                        .description("同时搜索当前无权使用的工具（用于发现），结果中标注 available")
+                        .optional(),
+                )
                 .build(),
         )
         .with_returns(ReturnType::new(
@@ -312,6 +432,30 @@ impl FrameworkToolProvider {
         ))
     }
 
+    fn create_tool_stats() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "tool.stats",
+            "工具使用统计",
+            "获取调用次数最多的工具及其成功率、平均耗时，以及调用者自己最近的失败记录",
+            CategoryPath::from_str("tool/query"),
+            JsonSchema::object()
+                .property(
+                    "top_n",
+                    JsonSchema::integer()
+                        .description("返回的工具数量，默认10")
+                        .optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new(
+            "工具统计信息",
+            json!({"type": "object"}),
+        ))
+    }
+
     fn create_message_send_direct() -> Tool {
         use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
         use serde_json::json;
@@ -319,10 +463,10 @@ impl FrameworkToolProvider {
         Tool::new(
             "message.send_direct",
             "发送私聊消息",
-            "向指定 Agent 发送私聊消息",
+            "向指定 Agent 或用户发送私聊消息",
             CategoryPath::from_str("message/send"),
             JsonSchema::object()
-                .property("to_agent_id", JsonSchema::string().description("接收者 Agent ID"))
+                .property("to_agent_id", JsonSchema::string().description("接收者 Agent ID，或用户参与者ID（user:{id}）"))
                 .property("content", JsonSchema::string().description("消息内容"))
                 .property(
                     "reply_to_message_id",
@@ -330,6 +474,8 @@ impl FrameworkToolProvider {
                         .description("回复的消息ID")
                         .optional(),
                 )
+                .property("priority", message_priority_schema())
+                .property("allow_duplicate", allow_duplicate_schema())
                 .build(),
         )
         .with_returns(ReturnType::new("发送结果", json!({"type": "boolean"})))
@@ -359,6 +505,8 @@ impl FrameworkToolProvider {
                         .description("回复的消息ID")
                         .optional(),
                 )
+                .property("priority", message_priority_schema())
+                .property("allow_duplicate", allow_duplicate_schema())
                 .build(),
         )
         .with_returns(ReturnType::new("发送结果", json!({"type": "boolean"})))
@@ -382,11 +530,141 @@ impl FrameworkToolProvider {
                         .description("需要 @ 的 Agent ID 列表")
                         .optional(),
                 )
+                .property("priority", message_priority_schema())
+                .property("allow_duplicate", allow_duplicate_schema())
                 .build(),
         )
         .with_returns(ReturnType::new("发送结果", json!({"type": "boolean"})))
     }
 
+    fn create_message_send_immediate() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "message.send_immediate",
+            "立即发送消息",
+            "在轮次中途立即发送消息（如追问澄清问题），不等待轮次结束；其他 message.send_* 工具在轮次结束时统一发送",
+            CategoryPath::from_str("message/send"),
+            JsonSchema::object()
+                .property("to", JsonSchema::string().description("接收者 Agent ID、用户参与者ID（user:{id}）或群组（group:{id}）"))
+                .property("content", JsonSchema::string().description("消息内容"))
+                .property(
+                    "reply_to_message_id",
+                    JsonSchema::string()
+                        .description("回复的消息ID")
+                        .optional(),
+                )
+                .property("priority", message_priority_schema())
+                .property("allow_duplicate", allow_duplicate_schema())
+                .build(),
+        )
+        .with_returns(ReturnType::new("发送结果", json!({"type": "object"})))
+    }
+
+    fn create_message_send_templated() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "message.send_templated",
+            "按模板发送消息",
+            "渲染指定模板后发送私聊或群聊消息，to_agent_id 与 group_id 二选一",
+            CategoryPath::from_str("message/send"),
+            JsonSchema::object()
+                .property("template", JsonSchema::string().description("模板名称"))
+                .raw_property(
+                    "variables",
+                    json!({"type": "object", "description": "占位符变量"}),
+                    false,
+                )
+                .property("to_agent_id", JsonSchema::string().description("接收者 Agent ID").optional())
+                .property("group_id", JsonSchema::string().description("群组 ID").optional())
+                .property("priority", message_priority_schema())
+                .property("allow_duplicate", allow_duplicate_schema())
+                .build(),
+        )
+        .with_returns(ReturnType::new("发送结果及渲染后的内容", json!({"type": "object"})))
+    }
+
+    fn create_message_react() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "message.react",
+            "回应消息",
+            "用 emoji（如 👍 ✅ ❓）回应消息，作为轻量确认，不产生新消息；remove 为 true 时撤销回应",
+            CategoryPath::from_str("message/react"),
+            JsonSchema::object()
+                .property("message_id", JsonSchema::string().description("要回应的消息ID"))
+                .property("emoji", JsonSchema::string().description("回应的 emoji"))
+                .property("remove", JsonSchema::boolean().description("是否撤销回应").optional())
+                .build(),
+        )
+        .with_returns(ReturnType::new("回应是否发生变化", json!({"type": "object"})))
+    }
+
+    fn create_message_pin() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "message.pin",
+            "置顶消息",
+            "在消息所在的群聊或私聊中置顶（如决策、规格、交接信息），所有参与者可见；remove 为 true 时取消置顶，他人置顶的只有群管理员能取消",
+            CategoryPath::from_str("message/pin"),
+            JsonSchema::object()
+                .property("message_id", JsonSchema::string().description("要置顶的消息ID"))
+                .property("remove", JsonSchema::boolean().description("是否取消置顶").optional())
+                .build(),
+        )
+        .with_returns(ReturnType::new("置顶状态", json!({"type": "object"})))
+    }
+
+    fn create_memory_list_pins() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "memory.list_pins",
+            "查看置顶消息",
+            "列出群聊或与某人私聊中的置顶消息，按置顶时间排序",
+            CategoryPath::from_str("memory/pins"),
+            JsonSchema::object()
+                .property("session", JsonSchema::string().description("群ID，或私聊对方的ID"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("置顶消息列表", json!({"type": "object"})))
+    }
+
+    fn create_template_render() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "template.render",
+            "渲染模板",
+            "用变量渲染角色或公司配置的消息模板，缺少变量时返回缺失列表",
+            CategoryPath::from_str("template/render"),
+            JsonSchema::object()
+                .property("template", JsonSchema::string().description("模板名称"))
+                .raw_property(
+                    "variables",
+                    json!({"type": "object", "description": "占位符变量"}),
+                    false,
+                )
+                .property(
+                    "escape",
+                    JsonSchema::enum_values(vec!["none", "html", "json"])
+                        .description("变量值的转义方式，默认 none；结果用于网页或邮件时用 html，放进 JSON 字符串时用 json")
+                        .optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new("渲染结果", json!({"type": "string"})))
+    }
+
     fn create_time_now() -> Tool {
         use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
         use serde_json::json;
@@ -480,6 +758,30 @@ impl FrameworkToolProvider {
         ))
     }
 
+    fn create_org_find_users() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "org.find_users",
+            "查找用户",
+            "按用户名、姓名或部门查找人类用户，返回可用于私聊的参与者ID（user:{id}）",
+            CategoryPath::from_str("org/query"),
+            JsonSchema::object()
+                .property(
+                    "query",
+                    JsonSchema::string()
+                        .description("匹配用户名、姓名或部门，为空时返回全部用户")
+                        .optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new(
+            "匹配的用户列表",
+            json!({"type": "array", "items": {"type": "object"}}),
+        ))
+    }
+
     fn create_org_get_sub_departments() -> Tool {
         use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
         use serde_json::json;
@@ -517,6 +819,446 @@ impl FrameworkToolProvider {
             json!({"type": "array", "items": {"type": "object"}}),
         ))
     }
+
+    fn create_org_get_recent_changes() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "org.get_recent_changes",
+            "查看组织架构变更",
+            "List recent organization changes (agents and departments added, removed or modified), newest first",
+            CategoryPath::from_str("org/query"),
+            JsonSchema::object()
+                .property(
+                    "since",
+                    JsonSchema::integer()
+                        .description("只返回该时间戳（秒）之后的变更，默认最近 7 天")
+                        .optional(),
+                )
+                .property(
+                    "limit",
+                    JsonSchema::integer()
+                        .description("最多返回条数，默认 10，最大 50")
+                        .optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new(
+            "变更记录列表",
+            json!({"type": "array", "items": {"type": "object"}}),
+        ))
+    }
+
+    fn create_self_get_preferences() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "self.get_preferences",
+            "获取自身偏好",
+            "获取自己保存的偏好设置（如回答风格），这些偏好会在每轮思考时注入提示词",
+            CategoryPath::from_str("self/preferences"),
+            JsonSchema::object().build(),
+        )
+        .with_returns(ReturnType::new("偏好文档", json!({"type": "object"})))
+    }
+
+    fn create_self_update_preferences() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "self.update_preferences",
+            "更新自身偏好",
+            "更新自己的偏好设置，只能修改自己的偏好。顶层键覆盖，值为 null 的键被删除，下一轮生效",
+            CategoryPath::from_str("self/preferences"),
+            JsonSchema::object()
+                .raw_property(
+                    "preferences",
+                    json!({"type": "object", "description": "要写入的偏好键值"}),
+                    true,
+                )
+                .property(
+                    "replace",
+                    JsonSchema::boolean()
+                        .description("为 true 时整体替换而不是合并")
+                        .optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new("更新后的偏好文档", json!({"type": "object"})))
+    }
+
+    fn create_transcript_export() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "transcript.export",
+            "导出会话记录",
+            "把自己所在的群聊或自己的私聊导出为 Markdown/HTML/JSON 文本，可在上报问题时附上",
+            CategoryPath::from_str("transcript/export"),
+            JsonSchema::object()
+                .property(
+                    "session_id",
+                    JsonSchema::string()
+                        .description("群组 ID；不填时导出自己的私聊")
+                        .optional(),
+                )
+                .property(
+                    "format",
+                    JsonSchema::enum_values(vec!["markdown", "html", "json"])
+                        .description("导出格式，默认 markdown")
+                        .optional(),
+                )
+                .property("since", JsonSchema::integer().description("起始时间戳（秒）").optional())
+                .property("until", JsonSchema::integer().description("截止时间戳（秒）").optional())
+                .property(
+                    "max_messages",
+                    JsonSchema::integer().description("最多导出的消息数，默认 200").optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new("导出的会话文本及消息数", json!({"type": "object"})))
+    }
+
+    fn create_group_create_ephemeral() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "group.create_ephemeral",
+            "创建临时群聊",
+            "为一项短期任务拉一个临时群聊，到期后自动关闭并保存会话快照，不出现在常规群聊列表中",
+            CategoryPath::from_str("group/ephemeral"),
+            JsonSchema::object()
+                .property("name", JsonSchema::string().description("群聊名称"))
+                .property(
+                    "members",
+                    JsonSchema::string_array().description("成员 Agent ID，自己会自动加入"),
+                )
+                .property(
+                    "ttl_secs",
+                    JsonSchema::integer().description("存活时间（秒），默认 3600，最长 86400").optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new("群聊 ID、成员及到期时间", json!({"type": "object"})))
+    }
+
+    fn create_group_invite() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "group.invite",
+            "邀请群成员",
+            "把成员拉进已有的群聊，只有群管理员可以使用；新成员会私下收到置顶消息、之前讨论的总结和最近的消息，\
+             需要对方从零开始时设置 cold_start",
+            CategoryPath::from_str("group/moderation"),
+            JsonSchema::object()
+                .property("group_id", JsonSchema::string().description("群组 ID"))
+                .property("member_id", JsonSchema::string().description("要邀请的成员 ID"))
+                .property(
+                    "cold_start",
+                    JsonSchema::boolean().description("不发送前情提要，默认 false").optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new("群聊 ID 及全部成员", json!({"type": "object"})))
+    }
+
+    fn create_group_kick() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "group.kick",
+            "移出群成员",
+            "把扰乱讨论的成员移出群聊，只有群管理员可以使用；群创建者不能被移出",
+            CategoryPath::from_str("group/moderation"),
+            JsonSchema::object()
+                .property("group_id", JsonSchema::string().description("群组 ID"))
+                .property("member_id", JsonSchema::string().description("要移出的成员 ID"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("群聊 ID 及剩余成员", json!({"type": "object"})))
+    }
+
+    fn create_group_mute() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "group.mute",
+            "禁言群成员",
+            "在一段时间内禁止成员在群聊中发言（如刷屏时），到期自动解除，只有群管理员可以使用",
+            CategoryPath::from_str("group/moderation"),
+            JsonSchema::object()
+                .property("group_id", JsonSchema::string().description("群组 ID"))
+                .property("member_id", JsonSchema::string().description("要禁言的成员 ID"))
+                .property(
+                    "duration_secs",
+                    JsonSchema::integer().description("禁言时长（秒），最长 604800"),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new("群聊 ID、成员及禁言到期时间", json!({"type": "object"})))
+    }
+
+    fn create_task_create() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "task.create",
+            "创建任务",
+            "创建一个需要跟踪的任务，可以指派给 Agent 或用户并设置截止时间；负责人会收到通知，临近截止时会被提醒",
+            CategoryPath::from_str("task/manage"),
+            JsonSchema::object()
+                .property("title", JsonSchema::string().description("任务标题"))
+                .property("description", JsonSchema::string().description("任务说明").optional())
+                .property(
+                    "assignee",
+                    JsonSchema::string()
+                        .description("负责人 Agent ID，或用户参与者ID（user:{id}）；不填则不指派")
+                        .optional(),
+                )
+                .property("due_at", JsonSchema::integer().description("截止时间（Unix 秒）").optional())
+                .build(),
+        )
+        .with_returns(ReturnType::new("创建的任务", json!({"type": "object"})))
+    }
+
+    fn create_task_list_mine() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "task.list_mine",
+            "查看我的任务",
+            "列出指派给自己的任务，按截止时间排序；默认只列出未关闭的任务",
+            CategoryPath::from_str("task/query"),
+            JsonSchema::object()
+                .property("status", task_status_schema().description("只列出该状态的任务").optional())
+                .build(),
+        )
+        .with_returns(ReturnType::new("任务列表", json!({"type": "object"})))
+    }
+
+    fn create_task_update_status() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "task.update_status",
+            "更新任务状态",
+            "更新自己创建或负责的任务状态；已完成或已取消的任务只能重新打开",
+            CategoryPath::from_str("task/manage"),
+            JsonSchema::object()
+                .property("task_id", JsonSchema::string().description("任务 ID"))
+                .property("status", task_status_schema().description("新状态"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("更新后的任务", json!({"type": "object"})))
+    }
+
+    fn create_task_comment() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "task.comment",
+            "评论任务",
+            "在任务下留言，评论会私聊发给任务的另一方（负责人或创建者）并关联到任务",
+            CategoryPath::from_str("task/manage"),
+            JsonSchema::object()
+                .property("task_id", JsonSchema::string().description("任务 ID"))
+                .property("content", JsonSchema::string().description("评论内容"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("评论消息 ID 及接收者", json!({"type": "object"})))
+    }
+
+    /// 可选工具，只有配置了 SMTP 时才提供
+    pub fn create_notify_email() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "notify.email",
+            "发送邮件通知",
+            "给不在线的人发邮件，如每日总结、需要人工处理的事项；收件域名受白名单限制，每天有发送上限",
+            CategoryPath::from_str("notify/email"),
+            JsonSchema::object()
+                .property("to", JsonSchema::string().description("收件人邮箱"))
+                .property("subject", JsonSchema::string().description("邮件主题"))
+                .property("body", JsonSchema::string().description("邮件正文（纯文本）"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("发送结果及当天已发送数", json!({"type": "object"})))
+        .with_feature("email")
+    }
+
+    /// 可选工具，公司配置启用转交时提供
+    pub fn create_handoff() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "handoff",
+            "转交消息",
+            "收到的私聊超出自己的技能范围时，把它转交给具备所需技能或属于所需部门的 Agent；原发送者会收到转交通知",
+            CategoryPath::from_str("org/handoff"),
+            JsonSchema::object()
+                .property("reason", JsonSchema::string().description("为什么需要转交，会告知目标和原发送者"))
+                .property("skill", JsonSchema::string().description("处理这条消息需要的技能").optional())
+                .property("department", JsonSchema::string().description("建议转交的部门 ID 或名称").optional())
+                .property(
+                    "message_id",
+                    JsonSchema::string().description("要转交的私聊消息 ID，不填则转交最近收到的私聊").optional(),
+                )
+                .build(),
+        )
+        .with_returns(ReturnType::new("转交目标及转交链", json!({"type": "object"})))
+        .with_feature("handoff")
+    }
+
+    /// 可选工具，公司配置启用代码沙箱时提供
+    pub fn create_code_run() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "code.run",
+            "运行代码",
+            "在沙箱中运行一小段程序处理数据（解析 CSV、计算统计、转换 JSON 等）；input 以 JSON 从标准输入读入，\
+             程序打印到标准输出的内容作为结果。没有网络和文件系统，执行步数、时间和内存都有上限",
+            CategoryPath::from_str("code/run"),
+            JsonSchema::object()
+                .property("code", JsonSchema::string().description("程序源码"))
+                .property("language", JsonSchema::string().description("语言，默认 javascript").optional())
+                .raw_property("input", json!({"description": "输入数据（任意 JSON），从标准输入读取"}), false)
+                .build(),
+        )
+        .with_returns(ReturnType::new("标准输出、解析出的 JSON 结果及消耗的燃料", json!({"type": "object"})))
+        .with_feature("code_sandbox")
+    }
+
+    /// 可选工具，公司配置启用临时 Agent 时提供
+    pub fn create_agent_spawn_temporary() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "agent.spawn_temporary",
+            "创建临时 Agent",
+            "为当前会话临时请来一位专家（如 SQL 调优专家）：按现有 Agent 的角色或给出的职位和指令创建，\
+             会话是群聊时加入该群；不进入组织架构，闲置或到达存活时间后自动过期，过期时会通知你。\
+             每人同时拥有的临时 Agent 数和全公司的总数都有上限",
+            CategoryPath::from_str("org/temporary"),
+            JsonSchema::object()
+                .property("session_id", JsonSchema::string().description("会话：群聊 ID 或私聊的 Agent ID，默认为当前会话").optional())
+                .property("template", JsonSchema::string().description("复制角色的 Agent ID").optional())
+                .property("title", JsonSchema::string().description("职位，不使用模板时必填").optional())
+                .property("instructions", JsonSchema::string().description("给临时 Agent 的系统指令，不使用模板时必填").optional())
+                .property("name", JsonSchema::string().description("显示名称，默认使用职位").optional())
+                .property("ttl_secs", JsonSchema::integer().description("存活时间（秒），不填则闲置后过期").optional())
+                .build(),
+        )
+        .with_returns(ReturnType::new("临时 Agent 的 ID、所在会话和到期时间", json!({"type": "object"})))
+        .with_feature("temp_agents")
+    }
+
+    pub fn create_scratchpad_write() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "scratchpad.write",
+            "记草稿",
+            "记下本轮的中间思考（工具计划、放弃的草稿等）。草稿只供管理员调试查看，\
+             不会发送给任何人，也不会出现在你或其他 Agent 之后的上下文中",
+            CategoryPath::from_str("self/scratchpad"),
+            JsonSchema::object()
+                .property("content", JsonSchema::string().description("草稿内容"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("草稿 ID 和所属运行", json!({"type": "object"})))
+        .with_feature("scratchpad")
+    }
+
+    pub fn create_self_set_goal() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "self.set_goal",
+            "设置当前目标",
+            "替换自己的当前目标。当前目标是你正在完成的请求，每轮都会出现在提示词中，\
+             不会随对话历史被截掉；任务发生变化时更新它",
+            CategoryPath::from_str("self/goal"),
+            JsonSchema::object()
+                .property("goal", JsonSchema::string().description("新的目标，写清要交付什么"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("设置后的目标", json!({"type": "object"})))
+    }
+
+    pub fn create_self_clear_goal() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "self.clear_goal",
+            "清除当前目标",
+            "当前目标已完成或被取消时清除它，之后的提示词不再带上",
+            CategoryPath::from_str("self/goal"),
+            JsonSchema::object().build(),
+        )
+        .with_returns(ReturnType::new("被清除的目标，没有目标时为 null", json!({"type": "object"})))
+    }
+
+    pub fn create_session_close() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "session.close",
+            "结束当前会话",
+            "对话的请求已处理完（或确定无法处理）时结束自己的当前会话，系统会总结会话并记录解决状态。\
+             用户之后再发消息会重新打开会话或开启新会话",
+            CategoryPath::from_str("session"),
+            JsonSchema::object()
+                .property(
+                    "resolution",
+                    JsonSchema::enum_values(vec!["resolved", "unresolved"])
+                        .description("请求是否已解决，默认 resolved")
+                        .optional(),
+                )
+                .property("note", JsonSchema::string().description("结案说明").optional())
+                .build(),
+        )
+        .with_returns(ReturnType::new("关闭后的会话，包含总结", json!({"type": "object"})))
+    }
+
+    pub fn create_group_stats() -> Tool {
+        use crate::domain::tool::{CategoryPath, JsonSchema, ReturnType};
+        use serde_json::json;
+
+        Tool::new(
+            "group.stats",
+            "查看群聊活动统计",
+            "查看自己所在群聊中各成员最近的发言量、占比、最后发言时间和被 @ 后的平均回复时间，\
+             以及最近没有发言的成员，可以据此邀请沉默的成员参与讨论",
+            CategoryPath::from_str("group"),
+            JsonSchema::object()
+                .property("group_id", JsonSchema::string().description("群组 ID"))
+                .build(),
+        )
+        .with_returns(ReturnType::new("各成员的活动统计及沉默成员", json!({"type": "object"})))
+    }
 }
 
 impl Default for FrameworkToolProvider {
@@ -527,11 +1269,11 @@ impl Default for FrameworkToolProvider {
 
 impl ToolProvider for FrameworkToolProvider {
     fn list_tools(&self) -> Vec<Tool> {
-        Self::get_framework_tools()
+        self.tools()
     }
 
     fn search_tools(&self, query: &str, match_type: MatchType) -> Vec<Tool> {
-        let all_tools = Self::get_framework_tools();
+        let all_tools = self.tools();
         let query_lower = query.to_lowercase();
 
         all_tools
@@ -553,7 +1295,7 @@ impl ToolProvider for FrameworkToolProvider {
     }
 
     fn list_tools_by_category(&self, category: &str) -> Vec<Tool> {
-        let all_tools = Self::get_framework_tools();
+        let all_tools = self.tools();
         let category_path = crate::domain::tool::CategoryPath::from_str(category);
 
         all_tools
@@ -567,7 +1309,7 @@ impl ToolProvider for FrameworkToolProvider {
     fn get_category_tree(&self) -> CategoryNodeInfo {
         let mut root = CategoryNodeInfo::new("root", "");
 
-        for tool in Self::get_framework_tools() {
+        for tool in self.tools() {
             add_tool_to_tree(&mut root, &tool);
         }
 
@@ -575,6 +1317,23 @@ impl ToolProvider for FrameworkToolProvider {
     }
 }
 
+/// 发送类工具共用的优先级参数
+fn message_priority_schema() -> crate::domain::tool::TypeBuilder {
+    crate::domain::tool::JsonSchema::enum_values(vec!["low", "normal", "high", "urgent"])
+        .description("消息优先级，默认 normal；high/urgent 会被对方优先处理，只用于确实紧急的事项")
+        .optional()
+}
+
+fn allow_duplicate_schema() -> crate::domain::tool::TypeBuilder {
+    crate::domain::tool::JsonSchema::boolean()
+        .description("短时间内向同一目标发送完全相同的内容时默认不发送；确需重复（如提醒）时设为 true")
+        .optional()
+}
+
+fn task_status_schema() -> crate::domain::tool::TypeBuilder {
+    crate::domain::tool::JsonSchema::enum_values(vec!["open", "in_progress", "blocked", "done", "cancelled"])
+}
+
 /// 将工具添加到分类树
 fn add_tool_to_tree(root: &mut CategoryNodeInfo, tool: &Tool) {
     let segments = tool.category.segments();
@@ -0,0 +1,263 @@
+//! 工具调用统计
+//!
+//! 在 ToolExecutorRegistry 的执行路径上记录每个工具的调用次数、成败与耗时，
+//! 计数使用原子变量，避免在热路径上加锁。
+//!
+//! 计数器按天（UTC）汇总：启动时从存储恢复当日汇总，`spawn` 定期写回，
+//! 日期变化后的第一次写回把前一天的汇总存到前一天的日期下并清零计数器。
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::store::Store;
+use crate::domain::tool::ToolUsage;
+
+/// 每个调用者保留的最近失败记录数
+const RECENT_FAILURES_PER_CALLER: usize = 20;
+
+/// 单个工具的计数器
+#[derive(Default)]
+struct ToolCounters {
+    calls: AtomicU64,
+    successes: AtomicU64,
+    errors: AtomicU64,
+    total_latency_us: AtomicU64,
+}
+
+impl ToolCounters {
+    fn to_usage(&self, tool_id: &str) -> ToolUsage {
+        ToolUsage {
+            tool_id: tool_id.to_string(),
+            calls: self.calls.load(Ordering::Relaxed),
+            successes: self.successes.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            total_latency_ms: self.total_latency_us.load(Ordering::Relaxed) / 1000,
+        }
+    }
+}
+
+/// 工具调用失败记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolFailure {
+    /// 工具ID
+    pub tool_id: String,
+    /// 错误信息
+    pub error: String,
+    /// 失败时间戳
+    pub timestamp: i64,
+}
+
+/// 工具统计收集器
+pub struct ToolStats {
+    tools: DashMap<String, ToolCounters>,
+    recent_failures: DashMap<String, Mutex<VecDeque<ToolFailure>>>,
+    /// 通过已弃用别名发起的调用次数，按别名统计
+    alias_calls: DashMap<String, AtomicU64>,
+    /// 当前计数器所属的日期
+    day: Mutex<String>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for ToolStats {
+    fn default() -> Self {
+        Self {
+            tools: DashMap::new(),
+            recent_failures: DashMap::new(),
+            alias_calls: DashMap::new(),
+            day: Mutex::new(day_of(&SystemClock)),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl ToolStats {
+    /// 创建空的统计收集器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 替换时钟（测试用），计数器归属的日期随之改为时钟的当天
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.day = Mutex::new(day_of(clock.as_ref()));
+        self.clock = clock;
+        self
+    }
+
+    /// 当前计数器所属的日期
+    pub fn day(&self) -> String {
+        self.day.lock().map(|day| day.clone()).unwrap_or_default()
+    }
+
+    /// 记录一次工具调用，`error` 为 None 表示成功
+    pub fn record(&self, tool_id: &str, caller_id: &str, latency: Duration, error: Option<&str>) {
+        {
+            let counters = self.tools.entry(tool_id.to_string()).or_default();
+            counters.calls.fetch_add(1, Ordering::Relaxed);
+            counters
+                .total_latency_us
+                .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+            if error.is_some() {
+                counters.errors.fetch_add(1, Ordering::Relaxed);
+            } else {
+                counters.successes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        if let Some(error) = error {
+            let entry = self.recent_failures.entry(caller_id.to_string()).or_default();
+            if let Ok(mut failures) = entry.lock() {
+                failures.push_back(ToolFailure {
+                    tool_id: tool_id.to_string(),
+                    error: error.to_string(),
+                    timestamp: chrono::Utc::now().timestamp(),
+                });
+                while failures.len() > RECENT_FAILURES_PER_CALLER {
+                    failures.pop_front();
+                }
+            };
+        }
+    }
+
+    /// 记录一次通过已弃用别名发起的调用
+    pub fn record_alias_call(&self, alias: &str) {
+        self.alias_calls
+            .entry(alias.to_string())
+            .or_default()
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 通过别名发起的调用次数
+    pub fn alias_calls(&self, alias: &str) -> u64 {
+        self.alias_calls
+            .get(alias)
+            .map(|c| c.load(Ordering::Relaxed))
+            .unwrap_or(0)
+    }
+
+    /// 获取单个工具的统计
+    pub fn usage(&self, tool_id: &str) -> Option<ToolUsage> {
+        self.tools.get(tool_id).map(|c| c.to_usage(tool_id))
+    }
+
+    /// 获取所有工具的统计
+    pub fn snapshot(&self) -> Vec<ToolUsage> {
+        let mut usages: Vec<ToolUsage> = self
+            .tools
+            .iter()
+            .map(|entry| entry.value().to_usage(entry.key()))
+            .collect();
+        usages.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool_id.cmp(&b.tool_id)));
+        usages
+    }
+
+    /// 按调用次数取前 N 个工具
+    pub fn top_n(&self, n: usize) -> Vec<ToolUsage> {
+        let mut usages = self.snapshot();
+        usages.truncate(n);
+        usages
+    }
+
+    /// 获取某调用者最近的失败记录（最新的在前）
+    pub fn recent_failures(&self, caller_id: &str, limit: usize) -> Vec<ToolFailure> {
+        self.recent_failures
+            .get(caller_id)
+            .and_then(|failures| {
+                failures
+                    .lock()
+                    .ok()
+                    .map(|f| f.iter().rev().take(limit).cloned().collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// 用已有的汇总数据初始化计数器（用于重启后恢复）
+    pub fn restore(&self, usages: &[ToolUsage]) {
+        for usage in usages {
+            let counters = self.tools.entry(usage.tool_id.clone()).or_default();
+            counters.calls.fetch_add(usage.calls, Ordering::Relaxed);
+            counters.successes.fetch_add(usage.successes, Ordering::Relaxed);
+            counters.errors.fetch_add(usage.errors, Ordering::Relaxed);
+            counters
+                .total_latency_us
+                .fetch_add(usage.total_latency_ms * 1000, Ordering::Relaxed);
+        }
+    }
+
+    /// 将当前统计写入存储的当日汇总
+    ///
+    /// 日期已经变化时先把计数器清零，清零前的汇总写到前一天的日期下，
+    /// 上次写回之后、跨过零点之前的调用因此仍计入前一天。
+    pub async fn persist_daily(&self, store: &dyn Store) -> Result<()> {
+        let today = day_of(self.clock.as_ref());
+        let previous_day = match self.day.lock() {
+            Ok(mut day) if *day != today => Some(std::mem::replace(&mut *day, today.clone())),
+            _ => None,
+        };
+        if let Some(previous_day) = previous_day {
+            let previous = self.take();
+            if let Err(e) = store.save_tool_stats(&previous_day, &previous).await {
+                // 写入失败时放回计数器，下次写回时重试
+                self.restore(&previous);
+                if let Ok(mut day) = self.day.lock() {
+                    *day = previous_day;
+                }
+                return Err(e);
+            }
+        }
+        store.save_tool_stats(&today, &self.snapshot()).await
+    }
+
+    /// 从存储恢复当日汇总
+    pub async fn load_daily(&self, store: &dyn Store) -> Result<()> {
+        let today = day_of(self.clock.as_ref());
+        let usages = store.load_tool_stats(&today).await?;
+        if let Ok(mut day) = self.day.lock() {
+            *day = today;
+        }
+        self.restore(&usages);
+        Ok(())
+    }
+
+    /// 按间隔定期写回当日汇总
+    pub fn spawn(self: Arc<Self>, store: Arc<dyn Store>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // 第一次 tick 立即返回，此时刚恢复过当日汇总，无需写回
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.persist_daily(store.as_ref()).await {
+                    warn!("Failed to persist daily tool stats: {}", e);
+                }
+            }
+        })
+    }
+
+    /// 取出并清空所有计数器；逐个分片在写锁下移除，不会丢失并发记录的调用
+    fn take(&self) -> Vec<ToolUsage> {
+        let mut usages = Vec::new();
+        self.tools.retain(|tool_id, counters| {
+            usages.push(counters.to_usage(tool_id));
+            false
+        });
+        usages.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool_id.cmp(&b.tool_id)));
+        usages
+    }
+}
+
+/// 时钟当天的日期（UTC），作为每日汇总的键
+fn day_of(clock: &dyn Clock) -> String {
+    chrono::DateTime::from_timestamp_millis(clock.now_millis())
+        .unwrap_or_default()
+        .format("%Y-%m-%d")
+        .to_string()
+}
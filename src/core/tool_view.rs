@@ -0,0 +1,189 @@
+//! Agent 工具视图
+//!
+//! 按 Agent 持有的技能过滤工具目录，只把它真正能用的工具交给 LLM：私有工具只对
+//! 绑定技能的持有者可见，配置中停用的工具和所属功能已关闭的工具不出现，需要审批和已弃用的工具
+//! 保留但加标记。结果按 Agent 缓存，技能、绑定或注册表变化后自动重新计算，功能开关切换时
+//! 收到 `FeatureToggled` 事件后失效。
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+
+use crate::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use crate::core::features::FeatureFlags;
+use crate::core::skill::SkillManager;
+use crate::core::tool_provider::CompositeToolProvider;
+use crate::domain::tool::{MatchType, Tool, ToolProvider};
+
+/// 需要审批的工具在描述前加的标记
+pub const APPROVAL_MARKER: &str = "[Requires approval]";
+
+/// 已弃用的工具在描述前加的标记，后接弃用说明
+pub const DEPRECATED_MARKER: &str = "[Deprecated]";
+
+/// 缓存的视图及计算时的版本号
+struct CachedView {
+    stamp: (u64, u64),
+    tools: Arc<Vec<Tool>>,
+}
+
+/// 单个 Agent 可见的工具列表
+pub struct AgentToolView {
+    provider: Arc<CompositeToolProvider>,
+    skills: Arc<SkillManager>,
+    disabled: HashSet<String>,
+    features: Option<Arc<FeatureFlags>>,
+    /// Agent ID -> 持有的技能
+    agent_skills: DashMap<String, Vec<String>>,
+    cache: DashMap<String, CachedView>,
+}
+
+impl AgentToolView {
+    pub fn new(provider: Arc<CompositeToolProvider>, skills: Arc<SkillManager>) -> Self {
+        Self {
+            provider,
+            skills,
+            disabled: HashSet::new(),
+            features: None,
+            agent_skills: DashMap::new(),
+            cache: DashMap::new(),
+        }
+    }
+
+    /// 停用的工具对所有 Agent 都不可见
+    pub fn with_disabled_tools(mut self, tool_ids: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.disabled = tool_ids.into_iter().map(Into::into).collect();
+        self.cache.clear();
+        self
+    }
+
+    /// 按功能开关隐藏所属功能已关闭的工具
+    pub fn with_features(mut self, features: Arc<FeatureFlags>) -> Self {
+        self.features = Some(features);
+        self.cache.clear();
+        self
+    }
+
+    /// 设置 Agent 持有的技能
+    pub fn set_agent_skills(&self, agent_id: &str, skills: Vec<String>) {
+        self.agent_skills.insert(agent_id.to_string(), skills);
+        self.invalidate(agent_id);
+    }
+
+    /// Agent 持有的技能
+    pub fn agent_skills(&self, agent_id: &str) -> Vec<String> {
+        self.agent_skills
+            .get(agent_id)
+            .map(|s| s.value().clone())
+            .unwrap_or_default()
+    }
+
+    /// 丢弃 Agent 的缓存视图
+    pub fn invalidate(&self, agent_id: &str) {
+        self.cache.remove(agent_id);
+    }
+
+    /// 丢弃所有缓存视图
+    pub fn invalidate_all(&self) {
+        self.cache.clear();
+    }
+
+    /// 收到 `FeatureToggled` 事件时丢弃所有缓存视图（需在 Tokio 运行时中调用）
+    pub fn spawn(self: Arc<Self>, events: &EventBus) {
+        events.register_listener(Box::new(ToolViewListener(self)));
+    }
+
+    /// Agent 本轮可用的工具（发给 LLM 的列表）
+    pub fn tools_for(&self, agent_id: &str) -> Arc<Vec<Tool>> {
+        let stamp = self.stamp();
+        if let Some(cached) = self.cache.get(agent_id).filter(|c| c.stamp == stamp) {
+            return cached.tools.clone();
+        }
+
+        let skills = self.agent_skills(agent_id);
+        let tools: Vec<Tool> = self
+            .provider
+            .list_tools()
+            .into_iter()
+            .filter(|tool| self.is_available(tool, &skills))
+            .map(mark_approval)
+            .map(mark_deprecated)
+            .collect();
+        let tools = Arc::new(tools);
+        self.cache.insert(
+            agent_id.to_string(),
+            CachedView {
+                stamp,
+                tools: tools.clone(),
+            },
+        );
+        tools
+    }
+
+    /// Agent 能否使用该工具，别名按规范ID判断
+    pub fn can_use(&self, agent_id: &str, tool_id: &str) -> bool {
+        let tool_id = self.skills.tool_registry().canonical_id(tool_id);
+        self.tools_for(agent_id).iter().any(|t| t.id == tool_id)
+    }
+
+    /// 在 Agent 的视图内搜索；`include_unavailable` 为 true 时搜索整个目录
+    pub fn search(&self, agent_id: &str, query: &str, match_type: MatchType, include_unavailable: bool) -> Vec<Tool> {
+        let results = self.provider.search_tools(query, match_type);
+        if include_unavailable {
+            return results;
+        }
+        let visible = self.tools_for(agent_id);
+        results
+            .into_iter()
+            .filter_map(|tool| visible.iter().find(|t| t.id == tool.id).cloned())
+            .collect()
+    }
+
+    /// 框架工具不在注册表中，除非被停用或所属功能关闭否则总是可用；应用工具按技能权限判断
+    fn is_available(&self, tool: &Tool, skills: &[String]) -> bool {
+        if self.disabled.contains(&tool.id) {
+            return false;
+        }
+        if let (Some(feature), Some(features)) = (&tool.feature, &self.features) {
+            if !features.is_enabled(feature) {
+                return false;
+            }
+        }
+        if self.skills.tool_registry().contains(&tool.id) {
+            self.skills.can_call_tool(&tool.id, skills)
+        } else {
+            true
+        }
+    }
+
+    fn stamp(&self) -> (u64, u64) {
+        (self.skills.generation(), self.skills.tool_registry().generation())
+    }
+}
+
+struct ToolViewListener(Arc<AgentToolView>);
+
+#[async_trait]
+impl CompanyEventListener for ToolViewListener {
+    async fn on_event(&self, event: &CompanyEvent) {
+        if let CompanyEvent::FeatureToggled { .. } = event {
+            self.0.invalidate_all();
+        }
+    }
+}
+
+fn mark_approval(mut tool: Tool) -> Tool {
+    if tool.requires_approval {
+        tool.description = format!("{} {}", APPROVAL_MARKER, tool.description);
+    }
+    tool
+}
+
+fn mark_deprecated(mut tool: Tool) -> Tool {
+    if let Some(message) = &tool.deprecated {
+        tool.description = format!("{} {} {}", DEPRECATED_MARKER, message, tool.description);
+    }
+    tool
+}
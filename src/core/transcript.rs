@@ -0,0 +1,543 @@
+//! 会话记录导出
+//!
+//! 把一个会话（群聊，或某个 Agent 的私聊）渲染成 Markdown、HTML 或 JSON，用于分享和归档。
+//! 消息按时间升序分页读取、逐页渲染，导出大会话时不会把整个记录放进内存。
+//!
+//! JSON 格式 `imitatort.transcript/v1` 是稳定的：
+//!
+//! ```text
+//! {
+//!   "schema": "imitatort.transcript/v1",
+//!   "session": {"kind": "group" | "direct", "id": "...", "title": "..."},
+//!   "messages": [
+//!     {<Message 的全部字段>, "sender_name": "...", "reactions": [{"emoji": "👍", "count": 1, "reactors": ["..."]}]}
+//!   ],
+//!   "count": 1
+//! }
+//! ```
+//!
+//! `messages` 中每一项本身就是一条 `Message`（多出的 `sender_name`、`reactions` 在反序列化时被忽略），
+//! 可以直接用于批量导入。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+
+use crate::core::store::{MessageFilter, Store};
+use crate::domain::{Agent, Message, MessageId, ReactionCount};
+
+/// JSON 导出格式标识
+pub const TRANSCRIPT_SCHEMA: &str = "imitatort.transcript/v1";
+
+/// 每页读取的消息数
+pub const EXPORT_PAGE_SIZE: usize = 200;
+
+/// 回复缩进的最大层数
+const MAX_THREAD_DEPTH: usize = 4;
+
+/// 导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl TranscriptFormat {
+    /// 解析 `markdown`（或 `md`）、`html`、`json`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// 要导出的会话
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptSession {
+    /// 某个参与者收发的全部私聊
+    Direct(String),
+    /// 群聊
+    Group(String),
+}
+
+impl TranscriptSession {
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Direct(id) | Self::Group(id) => id,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Direct(_) => "direct",
+            Self::Group(_) => "group",
+        }
+    }
+}
+
+/// JSON 导出的会话信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptSessionInfo {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+}
+
+/// JSON 导出中的一条消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    #[serde(flatten)]
+    pub message: Message,
+    pub sender_name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<ReactionCount>,
+}
+
+/// 完整的 JSON 导出文档
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub schema: String,
+    pub session: TranscriptSessionInfo,
+    pub messages: Vec<TranscriptEntry>,
+    pub count: usize,
+}
+
+/// 逐条渲染消息；记住已渲染消息的回复层级以便缩进
+pub struct TranscriptRenderer {
+    format: TranscriptFormat,
+    session: TranscriptSession,
+    title: String,
+    names: HashMap<String, String>,
+    /// 消息ID -> (回复层级, 发送者)
+    threads: HashMap<MessageId, (usize, String)>,
+    count: usize,
+}
+
+impl TranscriptRenderer {
+    pub fn new(format: TranscriptFormat, session: TranscriptSession, title: impl Into<String>) -> Self {
+        Self {
+            format,
+            session,
+            title: title.into(),
+            names: HashMap::new(),
+            threads: HashMap::new(),
+            count: 0,
+        }
+    }
+
+    /// 设置参与者显示名，未知的参与者显示其ID
+    pub fn with_names(mut self, names: HashMap<String, String>) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// 已渲染的消息数
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    fn name_of(&self, id: &str) -> String {
+        self.names.get(id).cloned().unwrap_or_else(|| id.to_string())
+    }
+
+    fn session_label(&self) -> String {
+        match self.session {
+            TranscriptSession::Direct(_) => "Direct conversation".to_string(),
+            TranscriptSession::Group(_) => "Group conversation".to_string(),
+        }
+    }
+
+    /// 文档开头
+    pub fn header(&self) -> String {
+        match self.format {
+            TranscriptFormat::Markdown => format!(
+                "# {}\n\n_{} `{}`_\n\n",
+                self.title,
+                self.session_label(),
+                self.session.id()
+            ),
+            TranscriptFormat::Html => format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n<p class=\"session\">{label} <code>{id}</code></p>\n",
+                title = escape_html(&self.title),
+                label = self.session_label(),
+                id = escape_html(self.session.id()),
+            ),
+            TranscriptFormat::Json => {
+                let session = TranscriptSessionInfo {
+                    kind: self.session.kind().to_string(),
+                    id: self.session.id().to_string(),
+                    title: self.title.clone(),
+                };
+                format!(
+                    "{{\"schema\":{},\"session\":{},\"messages\":[",
+                    serde_json::Value::from(TRANSCRIPT_SCHEMA),
+                    serde_json::to_string(&session).unwrap_or_else(|_| "null".to_string())
+                )
+            }
+        }
+    }
+
+    /// 渲染一条消息；消息需按时间升序传入
+    pub fn render(&mut self, message: &Message, reactions: &[ReactionCount]) -> String {
+        let parent = message.reply_to.as_ref().map(|id| self.threads.get(id).cloned());
+        let depth = match &parent {
+            Some(Some((depth, _))) => (depth + 1).min(MAX_THREAD_DEPTH),
+            Some(None) => 1,
+            None => 0,
+        };
+        let reply_to = parent.map(|p| p.map(|(_, sender)| self.name_of(&sender)));
+        self.threads.insert(message.id.clone(), (depth, message.from.clone()));
+
+        let rendered = match self.format {
+            TranscriptFormat::Markdown => self.render_markdown(message, reactions, depth, reply_to),
+            TranscriptFormat::Html => self.render_html(message, reactions, depth, reply_to),
+            TranscriptFormat::Json => {
+                let entry = TranscriptEntry {
+                    message: message.clone(),
+                    sender_name: self.name_of(&message.from),
+                    reactions: reactions.to_vec(),
+                };
+                let separator = if self.count > 0 { "," } else { "" };
+                format!("{}{}", separator, serde_json::to_string(&entry).unwrap_or_else(|_| "null".to_string()))
+            }
+        };
+        self.count += 1;
+        rendered
+    }
+
+    /// 文档结尾
+    pub fn footer(&self) -> String {
+        match self.format {
+            TranscriptFormat::Markdown => format!("---\n\n{} messages\n", self.count),
+            TranscriptFormat::Html => format!("<footer>{} messages</footer>\n</body>\n</html>\n", self.count),
+            TranscriptFormat::Json => format!("],\"count\":{}}}\n", self.count),
+        }
+    }
+
+    fn render_markdown(
+        &self,
+        message: &Message,
+        reactions: &[ReactionCount],
+        depth: usize,
+        reply_to: Option<Option<String>>,
+    ) -> String {
+        let mut heading = format!("**{}** · {}", self.name_of(&message.from), format_time(message.timestamp));
+        match reply_to {
+            Some(Some(name)) => heading.push_str(&format!(" · ↳ reply to {}", name)),
+            Some(None) => heading.push_str(" · ↳ reply"),
+            None => {}
+        }
+
+        let mut lines = vec![heading, String::new()];
+        lines.extend(message.content.lines().map(str::to_string));
+
+        let attachments = message.attachments();
+        if !attachments.is_empty() {
+            lines.push(String::new());
+            lines.extend(attachments.iter().map(|url| format!("- 📎 [{}]({})", attachment_name(url), url)));
+        }
+
+        let citations = message.citations();
+        if !citations.is_empty() {
+            lines.push(String::new());
+            lines.extend(citations.iter().map(|c| {
+                format!("[{}]: `{}` ({}) {}", c.ref_id, c.tool_id, c.params_digest, c.excerpt.replace('\n', " "))
+            }));
+        }
+
+        let mut notes = Vec::new();
+        if !message.mentions.is_empty() {
+            let mentions: Vec<String> = message.mentions.iter().map(|id| format!("@{}", self.name_of(id))).collect();
+            notes.push(format!("Mentions: {}", mentions.join(", ")));
+        }
+        if !reactions.is_empty() {
+            notes.push(format!("Reactions: {}", summarize_reactions(reactions)));
+        }
+        if !notes.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("_{}_", notes.join(" · ")));
+        }
+
+        let quote = "> ".repeat(depth);
+        let mut rendered: String = lines
+            .iter()
+            .map(|line| format!("{}{}", quote, line).trim_end().to_string() + "\n")
+            .collect();
+        rendered.push('\n');
+        rendered
+    }
+
+    fn render_html(
+        &self,
+        message: &Message,
+        reactions: &[ReactionCount],
+        depth: usize,
+        reply_to: Option<Option<String>>,
+    ) -> String {
+        let mut html = format!(
+            "<article class=\"message\" id=\"m-{}\" style=\"margin-left: {}em\">\n<header><strong>{}</strong> · <time datetime=\"{}\">{}</time>",
+            escape_html(&message.id),
+            depth * 2,
+            escape_html(&self.name_of(&message.from)),
+            message.timestamp,
+            format_time(message.timestamp),
+        );
+        if let (Some(name), Some(reply_id)) = (reply_to, &message.reply_to) {
+            let label = match name {
+                Some(name) => format!("reply to {}", escape_html(&name)),
+                None => "reply".to_string(),
+            };
+            html.push_str(&format!(" · <a href=\"#m-{}\">↳ {}</a>", escape_html(reply_id), label));
+        }
+        html.push_str("</header>\n");
+
+        let content: Vec<String> = message.content.lines().map(escape_html).collect();
+        html.push_str(&format!("<p>{}</p>\n", content.join("<br>\n")));
+
+        let attachments = message.attachments();
+        if !attachments.is_empty() {
+            html.push_str("<ul class=\"attachments\">\n");
+            for url in attachments {
+                html.push_str(&format!(
+                    "<li><a href=\"{}\">{}</a></li>\n",
+                    escape_html(url),
+                    escape_html(attachment_name(url))
+                ));
+            }
+            html.push_str("</ul>\n");
+        }
+        let citations = message.citations();
+        if !citations.is_empty() {
+            html.push_str("<ol class=\"citations\">\n");
+            for citation in citations {
+                html.push_str(&format!(
+                    "<li id=\"m-{}-{}\"><details><summary>[{}] <code>{}</code> ({})</summary>{}</details></li>\n",
+                    escape_html(&message.id),
+                    escape_html(&citation.ref_id),
+                    escape_html(&citation.ref_id),
+                    escape_html(&citation.tool_id),
+                    escape_html(&citation.params_digest),
+                    escape_html(&citation.excerpt),
+                ));
+            }
+            html.push_str("</ol>\n");
+        }
+        if !message.mentions.is_empty() {
+            let mentions: Vec<String> = message
+                .mentions
+                .iter()
+                .map(|id| format!("@{}", escape_html(&self.name_of(id))))
+                .collect();
+            html.push_str(&format!("<p class=\"mentions\">Mentions: {}</p>\n", mentions.join(", ")));
+        }
+        if !reactions.is_empty() {
+            html.push_str(&format!(
+                "<p class=\"reactions\">Reactions: {}</p>\n",
+                escape_html(&summarize_reactions(reactions))
+            ));
+        }
+        html.push_str("</article>\n");
+        html
+    }
+}
+
+/// 按时间升序分页读取会话消息
+pub struct TranscriptPager {
+    store: Arc<dyn Store>,
+    session: TranscriptSession,
+    until: Option<i64>,
+    cursor: Option<i64>,
+    /// 时间戳等于 cursor 且已返回的消息，避免同一秒内的消息重复或遗漏
+    seen_at_cursor: HashSet<MessageId>,
+    page_size: usize,
+}
+
+impl TranscriptPager {
+    pub fn new(store: Arc<dyn Store>, session: TranscriptSession, since: Option<i64>, until: Option<i64>) -> Self {
+        Self {
+            store,
+            session,
+            until,
+            cursor: since,
+            seen_at_cursor: HashSet::new(),
+            page_size: EXPORT_PAGE_SIZE,
+        }
+    }
+
+    /// 设置每页消息数
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    fn filters(&self, limit: usize) -> Vec<MessageFilter> {
+        let mut base = MessageFilter::new().oldest_first().limit(limit);
+        base.since = self.cursor;
+        base.until = self.until;
+
+        match &self.session {
+            TranscriptSession::Direct(id) => vec![
+                base.clone().from(id.clone()).target_type("direct"),
+                base.to(id.clone()).target_type("direct"),
+            ],
+            TranscriptSession::Group(id) => vec![base.to(id.clone()).target_type("group")],
+        }
+    }
+
+    /// 下一页消息及其回应；返回空时表示已读完
+    pub async fn next_page(&mut self) -> Result<Vec<(Message, Vec<ReactionCount>)>> {
+        // 多取已返回的条数，保证去重后仍能前进
+        let limit = self.page_size + self.seen_at_cursor.len();
+        let mut messages = Vec::new();
+        for filter in self.filters(limit) {
+            messages.extend(self.store.load_messages(filter).await?);
+        }
+        messages.retain(|m| !self.seen_at_cursor.contains(&m.id));
+        messages.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+        messages.dedup_by(|a, b| a.id == b.id);
+        messages.truncate(self.page_size);
+
+        if let Some(last) = messages.last().map(|m| m.timestamp) {
+            if self.cursor != Some(last) {
+                self.cursor = Some(last);
+                self.seen_at_cursor.clear();
+            }
+            self.seen_at_cursor
+                .extend(messages.iter().filter(|m| m.timestamp == last).map(|m| m.id.clone()));
+        }
+
+        let ids: Vec<MessageId> = messages.iter().map(|m| m.id.clone()).collect();
+        let mut reactions = ReactionCount::aggregate(&self.store.load_reactions(&ids).await?);
+        Ok(messages
+            .into_iter()
+            .map(|m| {
+                let counts = reactions.remove(&m.id).unwrap_or_default();
+                (m, counts)
+            })
+            .collect())
+    }
+}
+
+/// 参与者显示名：Agent 名称，以及用户（按 `user:{id}` 主体）的姓名
+pub async fn participant_names(store: &dyn Store, agents: &[Agent]) -> Result<HashMap<String, String>> {
+    let mut names: HashMap<String, String> = agents.iter().map(|a| (a.id.clone(), a.name.clone())).collect();
+    for user in store.load_users().await? {
+        names.insert(user.principal_id(), user.name.clone());
+    }
+    Ok(names)
+}
+
+enum ExportStage {
+    Header,
+    Body,
+    Done,
+}
+
+/// 以文本块流的形式导出，每块是文档开头、一页消息或结尾
+pub fn export_stream(renderer: TranscriptRenderer, pager: TranscriptPager) -> impl Stream<Item = Result<String>> {
+    futures_util::stream::unfold(
+        (renderer, pager, ExportStage::Header),
+        |(mut renderer, mut pager, stage)| async move {
+            match stage {
+                ExportStage::Header => {
+                    let header = renderer.header();
+                    Some((Ok(header), (renderer, pager, ExportStage::Body)))
+                }
+                ExportStage::Body => match pager.next_page().await {
+                    Ok(page) if page.is_empty() => {
+                        let footer = renderer.footer();
+                        Some((Ok(footer), (renderer, pager, ExportStage::Done)))
+                    }
+                    Ok(page) => {
+                        let chunk: String = page.iter().map(|(m, r)| renderer.render(m, r)).collect();
+                        Some((Ok(chunk), (renderer, pager, ExportStage::Body)))
+                    }
+                    Err(e) => Some((Err(e), (renderer, pager, ExportStage::Done))),
+                },
+                ExportStage::Done => None,
+            }
+        },
+    )
+}
+
+/// 导出为字符串，最多 `max_messages` 条；返回文本、消息数以及是否被截断
+pub async fn export_to_string(
+    mut renderer: TranscriptRenderer,
+    pager: TranscriptPager,
+    max_messages: usize,
+) -> Result<(String, usize, bool)> {
+    let mut pager = pager.with_page_size(max_messages.min(EXPORT_PAGE_SIZE));
+    let mut output = renderer.header();
+    let mut truncated = false;
+
+    'pages: loop {
+        let page = pager.next_page().await?;
+        if page.is_empty() {
+            break;
+        }
+        for (message, reactions) in &page {
+            if renderer.count() >= max_messages {
+                truncated = true;
+                break 'pages;
+            }
+            output.push_str(&renderer.render(message, reactions));
+        }
+    }
+
+    output.push_str(&renderer.footer());
+    Ok((output, renderer.count(), truncated))
+}
+
+fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn summarize_reactions(reactions: &[ReactionCount]) -> String {
+    reactions
+        .iter()
+        .map(|r| format!("{} {}", r.emoji, r.count))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 链接最后一段作为附件名
+fn attachment_name(url: &str) -> &str {
+    url.trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .filter(|name| !name.is_empty())
+        .unwrap_or(url)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,175 @@
+//! 消息翻译
+//!
+//! 公司里同时有中文和英文的参与者时，每个读者按自己的语言阅读消息：Agent 的阅读语言
+//! 取角色的回复语言（未设置时用公司默认），用户的阅读语言取个人资料中的
+//! [`User::preferred_language`](crate::domain::user::User::preferred_language)。
+//! 消息的语言与读者不同时，上下文和 Web 推送会附上译文，原文始终保留并标明为来源。
+//!
+//! 译文按（消息ID，目标语言）缓存在存储中，每条消息每种语言最多翻译一次。
+//! 翻译失败时退回原文，不影响消息投递。存储中的消息本身从不修改。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::response_language::{detect_language, is_known_language, language_name, same_language};
+use crate::core::store::Store;
+use crate::domain::{LLMConfig, Message, MessageId, MessageTranslation};
+use crate::infrastructure::llm::OpenAIClient;
+
+/// 正在翻译的（消息ID，语言）及其锁
+type InFlight = HashMap<(MessageId, String), Arc<tokio::sync::Mutex<()>>>;
+
+/// 翻译配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationConfig {
+    /// 是否为读者附上译文
+    pub enabled: bool,
+    /// 翻译使用的模型，未设置时使用组织中第一个 Agent 的模型
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub llm: Option<LLMConfig>,
+}
+
+impl TranslationConfig {
+    /// 启用翻译
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            llm: None,
+        }
+    }
+
+    /// 设置翻译使用的模型
+    pub fn with_llm(mut self, llm: LLMConfig) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+}
+
+/// 翻译器
+#[async_trait]
+pub trait Translator: Send + Sync {
+    /// 把 `source` 语言（ISO 639-3）的文本译为 `target` 语言（配置中的语言代码）
+    async fn translate(&self, text: &str, source: &str, target: &str) -> Result<String>;
+}
+
+/// 使用 LLM 翻译
+pub struct LlmTranslator {
+    client: OpenAIClient,
+}
+
+impl LlmTranslator {
+    pub fn new(config: &LLMConfig) -> Self {
+        Self {
+            client: OpenAIClient::for_config(config),
+        }
+    }
+}
+
+#[async_trait]
+impl Translator for LlmTranslator {
+    async fn translate(&self, text: &str, source: &str, target: &str) -> Result<String> {
+        let prompt = format!(
+            "Translate the following message from {} into {}. Keep names, code and formatting unchanged. \
+             Reply with the translation only.\n\n{}",
+            language_name(source).unwrap_or(source),
+            language_name(target).unwrap_or(target),
+            text
+        );
+        self.client.complete(&prompt).await
+    }
+}
+
+/// 翻译服务：判断是否需要翻译，并通过存储缓存译文
+pub struct TranslationService {
+    translator: Arc<dyn Translator>,
+    store: Arc<dyn Store>,
+    clock: Arc<dyn Clock>,
+    /// 正在翻译的（消息ID，语言），同一份译文并发请求时只翻译一次
+    in_flight: Mutex<InFlight>,
+}
+
+impl TranslationService {
+    pub fn new(translator: Arc<dyn Translator>, store: Arc<dyn Store>) -> Self {
+        Self {
+            translator,
+            store,
+            clock: Arc::new(SystemClock),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 消息语言与 `language` 不同时返回译文；语言相同、无法判断或翻译失败时返回 None，读者看原文
+    pub async fn translate_for(&self, message: &Message, language: &str) -> Option<MessageTranslation> {
+        if !is_known_language(language) {
+            return None;
+        }
+        let source = detect_language(&message.content)?;
+        if same_language(language, source) {
+            return None;
+        }
+
+        let key = (message.id.clone(), language.to_string());
+        let lock = self.in_flight.lock().unwrap().entry(key.clone()).or_default().clone();
+        let translation = {
+            let _guard = lock.lock().await;
+            self.cached_or_translate(message, source, language).await
+        };
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight.get(&key).is_some_and(|l| Arc::ptr_eq(l, &lock) && Arc::strong_count(l) <= 2) {
+            in_flight.remove(&key);
+        }
+        translation
+    }
+
+    /// 为一批消息取译文，按消息ID返回需要翻译的那些
+    pub async fn translate_all(&self, messages: &[Message], language: &str) -> HashMap<MessageId, MessageTranslation> {
+        let mut translations = HashMap::new();
+        for message in messages {
+            if let Some(translation) = self.translate_for(message, language).await {
+                translations.insert(message.id.clone(), translation);
+            }
+        }
+        translations
+    }
+
+    async fn cached_or_translate(&self, message: &Message, source: &str, language: &str) -> Option<MessageTranslation> {
+        match self.store.load_translation(&message.id, language).await {
+            Ok(Some(translation)) => return Some(translation),
+            Ok(None) => {}
+            Err(e) => warn!("Failed to load translation of message {}: {}", message.id, e),
+        }
+
+        let text = match self.translator.translate(&message.content, source, language).await {
+            Ok(text) if !text.trim().is_empty() => text.trim().to_string(),
+            Ok(_) => return None,
+            Err(e) => {
+                warn!("Failed to translate message {} into {}: {}", message.id, language, e);
+                return None;
+            }
+        };
+        let translation = MessageTranslation {
+            message_id: message.id.clone(),
+            language: language.to_string(),
+            source_language: source.to_string(),
+            text,
+            created_at: self.clock.now(),
+        };
+        if let Err(e) = self.store.save_translation(&translation).await {
+            warn!("Failed to cache translation of message {}: {}", message.id, e);
+        }
+        Some(translation)
+    }
+}
@@ -0,0 +1,305 @@
+//! 群聊轮流发言
+//!
+//! 群里有多个 Agent 时，用户的一个问题会让所有 Agent 同时回答。群聊开启轮流发言
+//! （[`Group::turn_taking`]）后，协调器为每个来自用户的问题选出一个主回答者：
+//! 优先被 @ 的 Agent，其次是职责关键词匹配到角色的 Agent，否则按轮转顺序。
+//! 其他 Agent 被告知同伴正在回答，只有被 @ 或主回答者在静默窗口内没有回答时
+//! 才依次接手。回答消息的元数据记录了这次分配，便于查看为什么是它在回答。
+//!
+//! 协调器在公司运行时中、消息总线之上工作，总线本身不含发言策略。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::messaging::MessageBus;
+use crate::domain::{Group, Message, MessageId, MessageTarget, Organization};
+
+/// 回答消息元数据中被回答问题ID的键
+pub const TURN_FOR_KEY: &str = "turn_for";
+
+/// 回答消息元数据中回答者身份的键，取值见 [`TurnRole`]
+pub const TURN_ROLE_KEY: &str = "turn_role";
+
+/// 回答消息元数据中选择依据的键，取值见 [`TurnReason`]
+pub const TURN_REASON_KEY: &str = "turn_reason";
+
+/// 问题分配记录的最短保留时间（秒），过期后清理
+const CLAIM_RETENTION_SECS: i64 = 600;
+
+/// 主回答者的选择依据
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnReason {
+    /// 问题中 @ 了它
+    Mention,
+    /// 问题包含其角色的职责关键词
+    Keyword,
+    /// 轮转
+    RoundRobin,
+}
+
+impl TurnReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TurnReason::Mention => "mention",
+            TurnReason::Keyword => "keyword",
+            TurnReason::RoundRobin => "round_robin",
+        }
+    }
+}
+
+/// 回答者身份
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnRole {
+    /// 最初选出的主回答者
+    Primary,
+    /// 前一个回答者静默后接手
+    Escalation,
+    /// 被 @ 的其他 Agent
+    Mentioned,
+}
+
+impl TurnRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TurnRole::Primary => "primary",
+            TurnRole::Escalation => "escalation",
+            TurnRole::Mentioned => "mentioned",
+        }
+    }
+}
+
+/// 协调器对一条群聊消息的裁决
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TurnDecision {
+    /// 群聊未开启轮流发言，照常处理
+    Open,
+    /// 轮到自己回答
+    Respond { role: TurnRole },
+    /// 同伴正在回答，暂缓；静默窗口过后再询问
+    Defer { responder: String },
+    /// 不需要自己回答
+    Skip,
+}
+
+/// 暂缓的消息及正在回答它的同伴，写入提示词
+#[derive(Debug, Clone)]
+pub struct PeerTurn {
+    pub message: Message,
+    pub responder: String,
+}
+
+/// 按裁决分好的消息
+#[derive(Debug, Default)]
+pub struct TurnSelection {
+    /// 本轮处理
+    pub respond: Vec<Message>,
+    /// 暂缓，下一轮重新裁决
+    pub deferred: Vec<PeerTurn>,
+}
+
+/// 一个问题的分配记录
+#[derive(Debug)]
+struct Claim {
+    group_id: String,
+    /// 候选回答者，按接手顺序
+    candidates: Vec<String>,
+    /// 当前回答者在候选中的位置
+    current: usize,
+    /// 被 @ 的 Agent，总是可以回答
+    mentioned: Vec<String>,
+    reason: TurnReason,
+    /// 当前回答者的静默窗口开始时间
+    window_started: i64,
+    silence_window: i64,
+    answered: bool,
+}
+
+impl Claim {
+    fn responder(&self) -> Option<&str> {
+        self.candidates.get(self.current).map(String::as_str)
+    }
+
+    fn role_of(&self, agent_id: &str) -> Option<TurnRole> {
+        if self.responder() == Some(agent_id) {
+            Some(if self.current == 0 { TurnRole::Primary } else { TurnRole::Escalation })
+        } else if self.mentioned.iter().any(|m| m == agent_id) {
+            Some(TurnRole::Mentioned)
+        } else {
+            None
+        }
+    }
+
+    /// 当前回答者静默超过窗口时交给下一个候选人
+    fn escalate(&mut self, now: i64) {
+        if !self.answered && now >= self.window_started + self.silence_window && self.current + 1 < self.candidates.len() {
+            self.current += 1;
+            self.window_started = now;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CoordinatorState {
+    claims: HashMap<MessageId, Claim>,
+    /// 群聊ID -> 下一个轮转位置
+    cursors: HashMap<String, usize>,
+}
+
+/// 群聊轮流发言协调器，所有 Agent 共用一个
+pub struct TurnCoordinator {
+    message_bus: Arc<MessageBus>,
+    organization: Arc<RwLock<Organization>>,
+    clock: Arc<dyn Clock>,
+    state: Mutex<CoordinatorState>,
+}
+
+impl TurnCoordinator {
+    /// 创建协调器
+    pub fn new(message_bus: Arc<MessageBus>, organization: Arc<RwLock<Organization>>) -> Self {
+        Self {
+            message_bus,
+            organization,
+            clock: Arc::new(SystemClock),
+            state: Mutex::new(CoordinatorState::default()),
+        }
+    }
+
+    /// 设置时钟
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 裁决 `agent_id` 是否处理这条消息；同一问题对所有 Agent 的裁决一致
+    pub async fn arbitrate(&self, agent_id: &str, message: &Message) -> TurnDecision {
+        let MessageTarget::Group(group_id) = &message.to else {
+            return TurnDecision::Open;
+        };
+        let Some(group) = self.message_bus.get_group(group_id).await else {
+            return TurnDecision::Open;
+        };
+        let Some(settings) = &group.turn_taking else {
+            return TurnDecision::Open;
+        };
+
+        let org = self.organization.read().await;
+        let mentioned = message.mentions.iter().any(|m| m == agent_id);
+        // 其他 Agent 的发言只有被 @ 的 Agent 接话，避免所有人跟着回应
+        if org.find_agent(&message.from).is_some() {
+            return if mentioned { TurnDecision::Respond { role: TurnRole::Mentioned } } else { TurnDecision::Skip };
+        }
+
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        if !state.claims.contains_key(&message.id) {
+            let claim = self.open_claim(&mut state, &org, &group, settings, message, now);
+            state.claims.retain(|_, c| c.window_started + CLAIM_RETENTION_SECS.max(c.silence_window * 2) > now);
+            state.claims.insert(message.id.clone(), claim);
+        }
+        let claim = state.claims.get_mut(&message.id).expect("claim just inserted");
+        claim.escalate(now);
+
+        if let Some(role) = claim.role_of(agent_id) {
+            return TurnDecision::Respond { role };
+        }
+        let position = claim.candidates.iter().position(|c| c == agent_id);
+        match (position, claim.responder()) {
+            // 还没轮到、问题也没人回答时等待
+            (Some(position), Some(responder)) if position > claim.current && !claim.answered => {
+                TurnDecision::Defer { responder: responder.to_string() }
+            }
+            _ => TurnDecision::Skip,
+        }
+    }
+
+    /// 逐条裁决，丢弃不需要自己回答的消息
+    pub async fn select(&self, agent_id: &str, messages: Vec<Message>) -> TurnSelection {
+        let mut selection = TurnSelection::default();
+        for message in messages {
+            match self.arbitrate(agent_id, &message).await {
+                TurnDecision::Open | TurnDecision::Respond { .. } => selection.respond.push(message),
+                TurnDecision::Defer { responder } => selection.deferred.push(PeerTurn { message, responder }),
+                TurnDecision::Skip => {}
+            }
+        }
+        selection
+    }
+
+    /// 为 Agent 发到群聊的回答记录分配信息，并标记问题已有人回答
+    pub fn annotate(&self, agent_id: &str, message: &mut Message) {
+        let MessageTarget::Group(group_id) = &message.to else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        let latest = state
+            .claims
+            .iter_mut()
+            .filter(|(_, c)| &c.group_id == group_id)
+            .filter_map(|(id, c)| c.role_of(agent_id).map(|role| (id, c, role)))
+            .max_by_key(|(_, c, _)| c.window_started);
+        if let Some((question_id, claim, role)) = latest {
+            claim.answered = true;
+            message.metadata.insert(TURN_FOR_KEY.to_string(), question_id.clone());
+            message.metadata.insert(TURN_ROLE_KEY.to_string(), role.as_str().to_string());
+            message.metadata.insert(TURN_REASON_KEY.to_string(), claim.reason.as_str().to_string());
+        }
+    }
+
+    /// 选出主回答者并排好接手顺序
+    fn open_claim(
+        &self,
+        state: &mut CoordinatorState,
+        org: &Organization,
+        group: &Group,
+        settings: &crate::domain::TurnTakingSettings,
+        message: &Message,
+        now: i64,
+    ) -> Claim {
+        let members: Vec<String> = group
+            .members
+            .iter()
+            .filter(|m| **m != message.from && org.find_agent(m).is_some())
+            .cloned()
+            .collect();
+        let mentioned: Vec<String> = message.mentions.iter().filter(|m| members.contains(m)).cloned().collect();
+        let content = message.content.to_lowercase();
+        let has_keyword = |agent_id: &str| {
+            org.find_agent(agent_id).is_some_and(|agent| {
+                settings.responsibilities.iter().any(|(title, keywords)| {
+                    title.eq_ignore_ascii_case(&agent.role.title)
+                        && keywords.iter().any(|k| !k.is_empty() && content.contains(&k.to_lowercase()))
+                })
+            })
+        };
+
+        let (start, reason) = if let Some(first) = mentioned.first() {
+            (members.iter().position(|m| m == first).unwrap_or(0), TurnReason::Mention)
+        } else if let Some(index) = members.iter().position(|m| has_keyword(m)) {
+            (index, TurnReason::Keyword)
+        } else {
+            let cursor = state.cursors.entry(group.id.clone()).or_insert(0);
+            let index = if members.is_empty() { 0 } else { *cursor % members.len() };
+            *cursor = index + 1;
+            (index, TurnReason::RoundRobin)
+        };
+
+        let mut candidates = members;
+        candidates.rotate_left(start);
+        Claim {
+            group_id: group.id.clone(),
+            candidates,
+            current: 0,
+            mentioned,
+            reason,
+            window_started: now,
+            silence_window: settings.silence_window_secs as i64,
+            answered: false,
+        }
+    }
+}
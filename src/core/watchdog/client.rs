@@ -6,7 +6,7 @@ use std::sync::Arc;
 use anyhow::Result;
 use tracing::info;
 
-use super::{WatchdogFramework, WatchdogRule, TriggerCondition, ToolExecutionEvent};
+use super::{WatchdogFramework, WatchdogRule, TriggerCondition, ToolExecutionEvent, ToolPatternKind};
 
 /// Watchdog客户端
 pub struct WatchdogClient {
@@ -42,6 +42,21 @@ impl WatchdogClient {
         Ok(())
     }
 
+    /// 按匹配方式注册监控规则，如监控 `system` 分类下的所有工具
+    pub async fn register_pattern_rule(
+        &self,
+        rule_id: impl Into<String>,
+        kind: ToolPatternKind,
+        pattern: impl Into<String>,
+        condition: TriggerCondition,
+    ) -> Result<()> {
+        let rule = WatchdogRule::new(rule_id, pattern, condition, self.agent_id.clone()).with_pattern_kind(kind);
+
+        self.framework.register_rule(rule)?;
+        info!("Agent {} registered {:?} watchdog rule", self.agent_id, kind);
+        Ok(())
+    }
+
     /// 注销监控规则
     pub async fn unregister_rule(&self, rule_id: &str) -> bool {
         self.framework.remove_rule(rule_id).is_some()
@@ -2,6 +2,7 @@
 //!
 //! 提供统一的工具执行监控和事件触发能力
 
+use std::collections::HashSet;
 use std::sync::Arc;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
@@ -9,12 +10,20 @@ use tokio::sync::RwLock;
 use anyhow::Result;
 use tracing::{debug, error, info};
 
-use crate::domain::tool::ToolCallContext;
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::events::{CompanyEvent, EventBus};
+use crate::core::i18n::MessageCatalog;
+use crate::core::tool::ToolRegistry;
+use crate::domain::tool::{Tool, ToolCallContext, ToolProgress};
 
 pub mod client;
 pub mod condition;
+pub mod pattern;
 pub mod rule;
 
+pub use pattern::ToolPatternKind;
+use pattern::RuleIndex;
+
 /// 轮询配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PollingConfig {
@@ -79,6 +88,42 @@ pub enum ToolExecutionEvent {
         error: String,
         context: ToolCallContext,
     },
+    /// 流式工具报告进度
+    Progress {
+        tool_id: String,
+        progress: ToolProgress,
+        context: ToolCallContext,
+    },
+    /// 流式工具超过停滞阈值没有报告进度
+    Stalled {
+        tool_id: String,
+        idle_ms: u64,
+        context: ToolCallContext,
+    },
+}
+
+impl ToolExecutionEvent {
+    /// 事件所属的工具ID
+    pub fn tool_id(&self) -> &str {
+        match self {
+            ToolExecutionEvent::PreExecute { tool_id, .. }
+            | ToolExecutionEvent::PostExecute { tool_id, .. }
+            | ToolExecutionEvent::Error { tool_id, .. }
+            | ToolExecutionEvent::Progress { tool_id, .. }
+            | ToolExecutionEvent::Stalled { tool_id, .. } => tool_id,
+        }
+    }
+
+    /// 工具调用上下文
+    pub fn context(&self) -> &ToolCallContext {
+        match self {
+            ToolExecutionEvent::PreExecute { context, .. }
+            | ToolExecutionEvent::PostExecute { context, .. }
+            | ToolExecutionEvent::Error { context, .. }
+            | ToolExecutionEvent::Progress { context, .. }
+            | ToolExecutionEvent::Stalled { context, .. } => context,
+        }
+    }
 }
 
 /// 可轮询的监控规则
@@ -93,12 +138,15 @@ pub struct PollableWatchdogRule {
 }
 
 /// 监控规则
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WatchdogRule {
     /// 规则ID
     pub id: String,
-    /// 监控的工具ID
+    /// 监控的工具：按 `pattern_kind` 解释为工具ID、通配符、分类路径或工具标签
     pub tool_id: String,
+    /// 工具匹配方式
+    #[serde(default)]
+    pub pattern_kind: ToolPatternKind,
     /// 触发条件
     pub condition: TriggerCondition,
     /// 关联的Agent ID（触发时通知该Agent）
@@ -106,7 +154,23 @@ pub struct WatchdogRule {
     /// 规则是否启用
     pub enabled: bool,
     /// 用户定义的标签
+    #[serde(default)]
     pub tags: Vec<String>,
+    /// 工具执行出错时也触发（条件按错误文本评估）
+    #[serde(default)]
+    pub trigger_on_error: bool,
+    /// 流式工具报告进度时也触发（条件按进度 JSON 评估）
+    #[serde(default)]
+    pub trigger_on_progress: bool,
+    /// 流式工具进度停滞时也触发（条件按 `{"stalled": true, "idle_ms": n}` 评估）
+    #[serde(default)]
+    pub trigger_on_stall: bool,
+    /// 触发后的冷却时间（毫秒），冷却期内匹配的事件不再触发
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_ms: Option<u64>,
+    /// 最多触发次数，达到后不再触发，直到 [`WatchdogFramework::reset_rule_state`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_triggers: Option<u32>,
 }
 
 impl WatchdogRule {
@@ -120,33 +184,109 @@ impl WatchdogRule {
         Self {
             id: id.into(),
             tool_id: tool_id.into(),
+            pattern_kind: ToolPatternKind::Exact,
             condition,
             target_agent_id: target_agent_id.into(),
             enabled: true,
             tags: vec![],
+            trigger_on_error: false,
+            trigger_on_progress: false,
+            trigger_on_stall: false,
+            cooldown_ms: None,
+            max_triggers: None,
         }
     }
 
+    /// 设置工具匹配方式
+    pub fn with_pattern_kind(mut self, kind: ToolPatternKind) -> Self {
+        self.pattern_kind = kind;
+        self
+    }
+
+    /// 工具执行出错时也触发
+    pub fn with_trigger_on_error(mut self) -> Self {
+        self.trigger_on_error = true;
+        self
+    }
+
+    /// 流式工具报告进度时也触发
+    pub fn with_trigger_on_progress(mut self) -> Self {
+        self.trigger_on_progress = true;
+        self
+    }
+
+    /// 流式工具进度停滞时也触发
+    pub fn with_trigger_on_stall(mut self) -> Self {
+        self.trigger_on_stall = true;
+        self
+    }
+
+    /// 设置触发后的冷却时间（毫秒）
+    pub fn with_cooldown_ms(mut self, cooldown_ms: u64) -> Self {
+        self.cooldown_ms = Some(cooldown_ms);
+        self
+    }
+
+    /// 设置最多触发次数
+    pub fn with_max_triggers(mut self, max_triggers: u32) -> Self {
+        self.max_triggers = Some(max_triggers);
+        self
+    }
+
     /// 添加标签
     pub fn with_tags(mut self, tags: Vec<String>) -> Self {
         self.tags = tags;
         self
     }
 
+    /// 规则是否匹配该工具；分类和标签规则需要工具定义
+    pub fn matches_tool(&self, tool_id: &str, tool: Option<&Tool>) -> bool {
+        self.pattern_kind.matches(&self.tool_id, tool_id, tool)
+    }
+
     /// 检查规则是否应该触发
     pub fn should_trigger(&self, event: &ToolExecutionEvent) -> bool {
-        if !self.enabled {
+        self.should_trigger_for(event, None)
+    }
+
+    /// 检查规则是否应该触发，`tool` 为事件所属工具的定义
+    pub fn should_trigger_for(&self, event: &ToolExecutionEvent, tool: Option<&Tool>) -> bool {
+        if !self.enabled || !self.matches_tool(event.tool_id(), tool) {
             return false;
         }
 
         match event {
-            ToolExecutionEvent::PostExecute { tool_id, result, .. } if tool_id == &self.tool_id => {
-                self.evaluate_condition(result)
+            ToolExecutionEvent::PostExecute { result, .. } => self.evaluate_condition(result),
+            ToolExecutionEvent::Error { error, .. } if self.trigger_on_error => {
+                self.evaluate_condition(&serde_json::Value::String(error.clone()))
+            }
+            ToolExecutionEvent::Progress { progress, .. } if self.trigger_on_progress => {
+                self.evaluate_condition(&serde_json::to_value(progress).unwrap_or_default())
+            }
+            ToolExecutionEvent::Stalled { idle_ms, .. } if self.trigger_on_stall => {
+                self.evaluate_condition(&serde_json::json!({ "stalled": true, "idle_ms": idle_ms }))
             }
             _ => false,
         }
     }
 
+    /// 生成发送给目标Agent的触发通知
+    pub fn notification_text(&self, event: &ToolExecutionEvent, catalog: &MessageCatalog) -> String {
+        let result = match event {
+            ToolExecutionEvent::PreExecute { params, .. } => params.to_string(),
+            ToolExecutionEvent::PostExecute { result, .. } => result.to_string(),
+            ToolExecutionEvent::Error { error, .. } => error.clone(),
+            ToolExecutionEvent::Progress { progress, .. } => serde_json::to_string(progress).unwrap_or_default(),
+            ToolExecutionEvent::Stalled { idle_ms, .. } => serde_json::json!({ "stalled": true, "idle_ms": idle_ms }).to_string(),
+        };
+
+        catalog.format("watchdog.triggered", &[
+            ("rule_id", &self.id),
+            ("tool_id", event.tool_id()),
+            ("result", &result),
+        ])
+    }
+
     /// 评估条件
     fn evaluate_condition(&self, result: &serde_json::Value) -> bool {
         // 使用condition模块中的评估器
@@ -206,14 +346,44 @@ impl EventDispatcher {
     }
 }
 
+/// 规则的运行状态：上次触发时间（毫秒时间戳）和触发次数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RuleTriggerState {
+    pub last_triggered: Option<i64>,
+    pub trigger_count: u32,
+}
+
+impl RuleTriggerState {
+    /// 按规则的冷却时间和触发次数上限，`now_millis` 时能否再次触发
+    pub fn allows(&self, rule: &WatchdogRule, now_millis: i64) -> bool {
+        if rule.max_triggers.is_some_and(|max| self.trigger_count >= max) {
+            return false;
+        }
+        match (rule.cooldown_ms, self.last_triggered) {
+            (Some(cooldown_ms), Some(last)) => now_millis.saturating_sub(last) >= i64::try_from(cooldown_ms).unwrap_or(i64::MAX),
+            _ => true,
+        }
+    }
+}
+
 /// Watchdog框架核心
 pub struct WatchdogFramework {
     /// 监控规则存储
     rules: DashMap<String, WatchdogRule>,
+    /// 按匹配方式分桶的规则索引
+    index: RuleIndex,
+    /// 工具定义，用于分类和标签规则（可选）
+    tools: Option<Arc<ToolRegistry>>,
     /// 事件分发器
     event_dispatcher: Arc<EventDispatcher>,
     /// 全局启用状态
     enabled: Arc<RwLock<bool>>,
+    /// 生命周期事件（可选）
+    events: Option<Arc<EventBus>>,
+    /// 各规则的运行状态，检查和记录在同一个分片锁内完成
+    rule_states: DashMap<String, RuleTriggerState>,
+    /// 判断冷却时间使用的时钟
+    clock: Arc<dyn Clock>,
 }
 
 impl WatchdogFramework {
@@ -221,22 +391,88 @@ impl WatchdogFramework {
     pub fn new() -> Self {
         Self {
             rules: DashMap::new(),
+            index: RuleIndex::new(),
+            tools: None,
             event_dispatcher: Arc::new(EventDispatcher::new()),
             enabled: Arc::new(RwLock::new(true)),
+            events: None,
+            rule_states: DashMap::new(),
+            clock: Arc::new(SystemClock),
         }
     }
 
-    /// 注册监控规则
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 规则触发时发出 `WatchdogTriggered` 事件
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 从工具注册表查找工具定义，分类和标签规则才能匹配
+    pub fn with_tool_registry(mut self, tools: Arc<ToolRegistry>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// 注册监控规则，同ID的规则被替换
     pub fn register_rule(&self, rule: WatchdogRule) -> Result<()> {
-        // 直接在框架的存储中注册规则
-        self.rules.insert(rule.id.clone(), rule);
+        self.index.insert(&rule.id, rule.pattern_kind, &rule.tool_id);
+        if let Some(old) = self.rules.insert(rule.id.clone(), rule.clone()) {
+            if (old.pattern_kind, &old.tool_id) != (rule.pattern_kind, &rule.tool_id) {
+                self.index.remove(&old.id, old.pattern_kind, &old.tool_id);
+            }
+        }
         Ok(())
     }
 
-    /// 移除监控规则
+    /// 移除监控规则及其运行状态
     pub fn remove_rule(&self, rule_id: &str) -> Option<WatchdogRule> {
-        // 这里需要改进，目前使用简单实现
-        self.rules.remove(rule_id).map(|(_, rule)| rule)
+        let (_, rule) = self.rules.remove(rule_id)?;
+        self.index.remove(&rule.id, rule.pattern_kind, &rule.tool_id);
+        self.rule_states.remove(rule_id);
+        Some(rule)
+    }
+
+    /// 规则的运行状态，从未触发时为空
+    pub fn rule_state(&self, rule_id: &str) -> Option<RuleTriggerState> {
+        self.rule_states.get(rule_id).map(|state| *state)
+    }
+
+    /// 清除规则的冷却和触发次数，返回规则此前是否触发过
+    pub fn reset_rule_state(&self, rule_id: &str) -> bool {
+        let reset = self.rule_states.remove(rule_id).is_some();
+        if reset {
+            info!("Reset trigger state of rule {}", rule_id);
+        }
+        reset
+    }
+
+    /// 规则仍在冷却或已达到触发次数上限时返回 false，否则记录这次触发
+    fn try_trigger(&self, rule: &WatchdogRule) -> bool {
+        let now = self.clock.now_millis();
+        let mut state = self.rule_states.entry(rule.id.clone()).or_default();
+        if !state.allows(rule, now) {
+            debug!("Rule {} is throttled after {} triggers", rule.id, state.trigger_count);
+            return false;
+        }
+        state.last_triggered = Some(now);
+        state.trigger_count = state.trigger_count.saturating_add(1);
+        true
+    }
+
+    /// 索引中可能匹配该工具的规则ID
+    pub fn candidate_rules(&self, tool_id: &str) -> Vec<String> {
+        let tool = self.find_tool(tool_id);
+        self.index.candidates(tool_id, tool.as_ref())
+    }
+
+    fn find_tool(&self, tool_id: &str) -> Option<Tool> {
+        self.tools.as_ref().and_then(|tools| tools.get(tool_id))
     }
 
     /// 获取监控规则
@@ -285,19 +521,38 @@ impl WatchdogFramework {
         // 分发事件到所有处理器
         self.event_dispatcher.dispatch(event).await;
 
-        // 检查事件是否匹配任何规则
-        let matched_rule_ids: Vec<String> = self.rules
+        // 只检查索引中可能匹配的规则
+        let tool = self.find_tool(event.tool_id());
+        let mut matched: Vec<WatchdogRule> = self
+            .index
+            .candidates(event.tool_id(), tool.as_ref())
             .iter()
-            .filter(|rule| rule.should_trigger(event))
-            .map(|rule| rule.id.clone())
+            .filter_map(|rule_id| self.rules.get(rule_id).map(|r| r.clone()))
+            .filter(|rule| rule.should_trigger_for(event, tool.as_ref()))
             .collect();
 
+        // 多条规则匹配时按匹配方式的优先级排序，同一Agent只由优先级最高的规则通知一次；
+        // 这条规则冷却中或已用完触发次数时该Agent这次不通知，不会改由优先级更低的规则通知
+        matched.sort_by(|a, b| a.pattern_kind.cmp(&b.pattern_kind).then_with(|| a.id.cmp(&b.id)));
+        let mut notified = HashSet::new();
+        matched.retain(|rule| notified.insert(rule.target_agent_id.clone()));
+        matched.retain(|rule| self.try_trigger(rule));
+
         // 收集被触发的Agent ID
         let mut triggered_agents = Vec::new();
-        for rule_id in matched_rule_ids {
-            if let Some(rule) = self.rules.get(&rule_id) {
-                triggered_agents.push(rule.target_agent_id.clone());
-                info!("Rule {} triggered for agent {}", rule.id, rule.target_agent_id);
+        for rule in matched {
+            triggered_agents.push(rule.target_agent_id.clone());
+            info!(
+                trace_id = %event.context().trace_id,
+                "Rule {} triggered for agent {}", rule.id, rule.target_agent_id
+            );
+            if let Some(events) = &self.events {
+                events.emit(CompanyEvent::WatchdogTriggered {
+                    rule_id: rule.id.as_str().into(),
+                    tool_id: event.tool_id().into(),
+                    target_agent_id: rule.target_agent_id.as_str().into(),
+                    trace_id: event.context().trace_id.as_str().into(),
+                });
             }
         }
 
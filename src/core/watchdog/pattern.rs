@@ -0,0 +1,156 @@
+//! Watchdog工具匹配
+//!
+//! 规则可以按工具ID、通配符、分类前缀或工具标签选择工具；
+//! 索引按匹配方式分桶，处理事件时只检查可能匹配的规则
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::tool::{CategoryPath, Tool};
+
+/// 工具匹配方式（按优先级从高到低排列）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolPatternKind {
+    /// 工具ID完全相同
+    #[default]
+    Exact,
+    /// 通配符匹配工具ID：`*` 匹配任意多个字符，`?` 匹配一个字符，如 `system.*`
+    Glob,
+    /// 工具分类路径以该分类开头，如 `system` 匹配 `system/health`
+    Category,
+    /// 工具带有该标签
+    Tag,
+}
+
+impl ToolPatternKind {
+    /// 判断模式是否匹配工具；分类和标签需要工具定义，缺失时不匹配
+    pub fn matches(&self, pattern: &str, tool_id: &str, tool: Option<&Tool>) -> bool {
+        match self {
+            ToolPatternKind::Exact => pattern == tool_id,
+            ToolPatternKind::Glob => glob_match(pattern, tool_id),
+            ToolPatternKind::Category => tool.is_some_and(|t| CategoryPath::from_str(pattern).contains(&t.category)),
+            ToolPatternKind::Tag => tool.is_some_and(|t| t.has_tag(pattern)),
+        }
+    }
+}
+
+/// 通配符匹配，`*` 匹配任意多个字符，`?` 匹配一个字符
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // 最近一个 `*` 的位置及其当时对应的文本位置，失配时回溯
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// 通配符模式的分桶键：首段（第一个 `.` 之前）不含通配符时取首段，否则归入空键
+fn glob_bucket(pattern: &str) -> &str {
+    let head = pattern.split('.').next().unwrap_or_default();
+    if head.contains(['*', '?']) {
+        ""
+    } else {
+        head
+    }
+}
+
+/// 规则索引：匹配方式 + 键 -> 规则ID
+#[derive(Debug, Default)]
+pub struct RuleIndex {
+    /// 工具ID -> 规则
+    exact: DashMap<String, Vec<String>>,
+    /// 通配符首段 -> 规则
+    glob: DashMap<String, Vec<String>>,
+    /// 分类路径 -> 规则
+    category: DashMap<String, Vec<String>>,
+    /// 工具标签 -> 规则
+    tag: DashMap<String, Vec<String>>,
+}
+
+impl RuleIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入索引
+    pub fn insert(&self, rule_id: &str, kind: ToolPatternKind, pattern: &str) {
+        let (bucket, key) = self.bucket(kind, pattern);
+        let mut ids = bucket.entry(key).or_default();
+        if !ids.iter().any(|id| id == rule_id) {
+            ids.push(rule_id.to_string());
+        }
+    }
+
+    /// 移出索引，空桶一并删除
+    pub fn remove(&self, rule_id: &str, kind: ToolPatternKind, pattern: &str) {
+        let (bucket, key) = self.bucket(kind, pattern);
+        bucket.remove_if_mut(&key, |_, ids| {
+            ids.retain(|id| id != rule_id);
+            ids.is_empty()
+        });
+    }
+
+    /// 可能匹配该工具的规则ID（不重复）
+    pub fn candidates(&self, tool_id: &str, tool: Option<&Tool>) -> Vec<String> {
+        let mut keys: Vec<(&DashMap<String, Vec<String>>, String)> = vec![
+            (&self.exact, tool_id.to_string()),
+            (&self.glob, glob_bucket(tool_id).to_string()),
+            (&self.glob, String::new()),
+        ];
+        if let Some(tool) = tool {
+            let segments = tool.category.segments();
+            keys.extend((0..=segments.len()).map(|n| (&self.category, segments[..n].join("/"))));
+            keys.extend(tool.tags.iter().map(|tag| (&self.tag, tag.clone())));
+        }
+
+        let mut rule_ids: Vec<String> = Vec::new();
+        for (bucket, key) in keys {
+            if let Some(ids) = bucket.get(&key) {
+                for id in ids.iter() {
+                    if !rule_ids.contains(id) {
+                        rule_ids.push(id.clone());
+                    }
+                }
+            }
+        }
+        rule_ids
+    }
+
+    /// 索引中的规则数
+    pub fn len(&self) -> usize {
+        [&self.exact, &self.glob, &self.category, &self.tag]
+            .iter()
+            .map(|bucket| bucket.iter().map(|ids| ids.len()).sum::<usize>())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn bucket(&self, kind: ToolPatternKind, pattern: &str) -> (&DashMap<String, Vec<String>>, String) {
+        match kind {
+            ToolPatternKind::Exact => (&self.exact, pattern.to_string()),
+            ToolPatternKind::Glob => (&self.glob, glob_bucket(pattern).to_string()),
+            ToolPatternKind::Category => (&self.category, CategoryPath::from_str(pattern).to_path_string()),
+            ToolPatternKind::Tag => (&self.tag, pattern.to_string()),
+        }
+    }
+}
@@ -83,11 +83,11 @@ impl RuleManager {
             .collect()
     }
 
-    /// 根据工具ID查找规则
+    /// 根据工具ID查找规则（含匹配该ID的通配符规则）
     pub fn find_rules_by_tool(&self, tool_id: &str) -> Vec<WatchdogRule> {
         self.rules
             .iter()
-            .filter(|r| r.tool_id == tool_id)
+            .filter(|r| r.matches_tool(tool_id, None))
             .map(|r| r.clone())
             .collect()
     }
@@ -2,8 +2,12 @@
 //!
 //! Basic definition of virtual company employees
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
+use crate::domain::availability::Availability;
+
 /// Agent Unique Identifier
 pub type AgentId = String;
 
@@ -53,6 +57,15 @@ pub struct Agent {
     pub llm_config: LLMConfig,
     /// Agent mode, defaults to passive mode
     pub mode: AgentMode,
+    /// Skills held by the agent; private tools are only offered to holders of a bound skill
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skills: Vec<String>,
+    /// Working hours; without a schedule the agent is always available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability: Option<Availability>,
+    /// When the agent joined the company (seconds); used for work anniversaries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<i64>,
 }
 
 impl Agent {
@@ -70,6 +83,9 @@ impl Agent {
             department_id: None,
             llm_config,
             mode: AgentMode::Passive, // Default to passive mode
+            skills: Vec::new(),
+            availability: None,
+            created_at: None,
         }
     }
 
@@ -88,6 +104,9 @@ impl Agent {
             department_id: None,
             llm_config,
             mode,
+            skills: Vec::new(),
+            availability: None,
+            created_at: None,
         }
     }
 
@@ -103,6 +122,29 @@ impl Agent {
         self
     }
 
+    /// Set skills
+    pub fn with_skills(mut self, skills: Vec<String>) -> Self {
+        self.skills = skills;
+        self
+    }
+
+    /// Set working hours
+    pub fn with_availability(mut self, availability: Availability) -> Self {
+        self.availability = Some(availability);
+        self
+    }
+
+    /// Set when the agent joined (seconds)
+    pub fn with_created_at(mut self, created_at: i64) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Whether the agent works at the given unix timestamp (seconds)
+    pub fn is_available_at(&self, timestamp: i64) -> bool {
+        self.availability.as_ref().is_none_or(|a| a.is_available_at(timestamp))
+    }
+
     /// Generate system prompt
     pub fn system_prompt(&self) -> String {
         self.role.system_prompt.clone()
@@ -116,6 +158,15 @@ pub struct Role {
     pub responsibilities: Vec<String>,
     pub expertise: Vec<String>,
     pub system_prompt: String,
+    /// Outbound message templates (name -> text with `{{placeholders}}`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub templates: HashMap<String, String>,
+    /// Language code the agent must respond in, overrides the company default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_language: Option<String>,
+    /// Tone of the agent's messages, e.g. `formal`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tone: Option<String>,
 }
 
 impl Role {
@@ -126,6 +177,9 @@ impl Role {
             responsibilities: vec![],
             expertise: vec![],
             system_prompt: system_prompt.into(),
+            templates: HashMap::new(),
+            response_language: None,
+            tone: None,
         }
     }
 
@@ -140,6 +194,39 @@ impl Role {
         self.expertise = items;
         self
     }
+
+    /// Add a message template
+    pub fn with_template(mut self, name: impl Into<String>, text: impl Into<String>) -> Self {
+        self.templates.insert(name.into(), text.into());
+        self
+    }
+
+    /// Set the response language (e.g. `en`, `zh`)
+    pub fn with_response_language(mut self, language: impl Into<String>) -> Self {
+        self.response_language = Some(language.into());
+        self
+    }
+
+    /// Set the tone
+    pub fn with_tone(mut self, tone: impl Into<String>) -> Self {
+        self.tone = Some(tone.into());
+        self
+    }
+}
+
+/// Stored revision of an agent's role; the highest revision is in effect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoleRevision {
+    pub agent_id: AgentId,
+    /// Starts at 1, increases by one per edit
+    pub revision: u32,
+    pub role: Role,
+    /// Username of the editor
+    pub editor: String,
+    /// Change note
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub created_at: i64,
 }
 
 /// LLM Configuration
@@ -148,6 +235,9 @@ pub struct LLMConfig {
     pub model: String,
     pub api_key: String,
     pub base_url: String,
+    /// Tokenizer used for token counting, e.g. `cl100k_base`; inferred from the model when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokenizer: Option<String>,
 }
 
 impl LLMConfig {
@@ -157,6 +247,7 @@ impl LLMConfig {
             model: "gpt-4o-mini".to_string(),
             api_key: api_key.into(),
             base_url: "https://api.openai.com/v1".to_string(),
+            tokenizer: None,
         }
     }
 
@@ -171,4 +262,10 @@ impl LLMConfig {
         self.base_url = url.into();
         self
     }
+
+    /// Set the tokenizer used for token counting (`cl100k_base`, `o200k_base`, `heuristic`, ...)
+    pub fn with_tokenizer(mut self, tokenizer: impl Into<String>) -> Self {
+        self.tokenizer = Some(tokenizer.into());
+        self
+    }
 }
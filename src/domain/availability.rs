@@ -0,0 +1,203 @@
+//! Agent Availability
+//!
+//! Working hours of an agent: weekly windows in the agent's timezone plus dated
+//! exceptions (vacations, holidays, special hours). All time math is done in local
+//! time of the configured IANA timezone, so schedules follow DST transitions.
+
+use chrono::{DateTime, Datelike, Days, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// How far ahead `next_available_at` searches
+const LOOKAHEAD_DAYS: u64 = 400;
+
+/// Step used to skip over a DST gap when a window starts inside it
+const GAP_STEP_MINUTES: i64 = 15;
+
+/// Time-of-day range, start inclusive and end exclusive
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { start, end }
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        self.start <= time && time < self.end
+    }
+}
+
+/// Recurring window on the given weekdays
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WeeklyWindow {
+    pub days: Vec<Weekday>,
+    #[serde(flatten)]
+    pub hours: TimeWindow,
+}
+
+/// Dated override of the weekly schedule; no windows means unavailable all day
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DateException {
+    pub date: NaiveDate,
+    /// Last day covered (inclusive), for multi-day exceptions such as a vacation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<TimeWindow>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl DateException {
+    /// Unavailable for the whole period
+    pub fn off(date: NaiveDate, until: Option<NaiveDate>) -> Self {
+        Self { date, until, windows: Vec::new(), note: None }
+    }
+
+    fn covers(&self, date: NaiveDate) -> bool {
+        self.date <= date && date <= self.until.unwrap_or(self.date)
+    }
+}
+
+/// Working-hours schedule of an agent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Availability {
+    /// IANA timezone name, e.g. `Europe/Berlin`
+    pub timezone: String,
+    #[serde(default)]
+    pub weekly: Vec<WeeklyWindow>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exceptions: Vec<DateException>,
+    /// Urgent messages are handled even outside working hours
+    #[serde(default)]
+    pub urgent_override: bool,
+}
+
+impl Availability {
+    /// Schedule without windows (never available until windows are added)
+    pub fn new(timezone: impl Into<String>) -> Self {
+        Self {
+            timezone: timezone.into(),
+            weekly: Vec::new(),
+            exceptions: Vec::new(),
+            urgent_override: false,
+        }
+    }
+
+    /// Add a recurring window
+    pub fn with_window(mut self, days: &[Weekday], start: NaiveTime, end: NaiveTime) -> Self {
+        self.weekly.push(WeeklyWindow { days: days.to_vec(), hours: TimeWindow::new(start, end) });
+        self
+    }
+
+    /// Add a dated exception
+    pub fn with_exception(mut self, exception: DateException) -> Self {
+        self.exceptions.push(exception);
+        self
+    }
+
+    /// Allow urgent messages outside working hours
+    pub fn with_urgent_override(mut self, urgent_override: bool) -> Self {
+        self.urgent_override = urgent_override;
+        self
+    }
+
+    /// Timezone must be a known IANA name and every window non-empty
+    pub fn validate(&self) -> Result<(), String> {
+        self.tz()
+            .ok_or_else(|| format!("unknown timezone '{}'", self.timezone))?;
+        let windows = self
+            .weekly
+            .iter()
+            .map(|w| &w.hours)
+            .chain(self.exceptions.iter().flat_map(|e| &e.windows));
+        for window in windows {
+            if window.start >= window.end {
+                return Err(format!("window {}-{} is empty", window.start, window.end));
+            }
+        }
+        if let Some(exception) = self.exceptions.iter().find(|e| e.until.is_some_and(|until| until < e.date)) {
+            return Err(format!("exception starting {} ends before it starts", exception.date));
+        }
+        Ok(())
+    }
+
+    /// Whether the agent works at the given unix timestamp (seconds);
+    /// a schedule with an unknown timezone never restricts the agent
+    pub fn is_available_at(&self, timestamp: i64) -> bool {
+        let Some(local) = self.local(timestamp) else {
+            return true;
+        };
+        self.windows_on(local.date()).iter().any(|w| w.contains(local.time()))
+    }
+
+    /// Start of the next working window at or after `timestamp`, `timestamp` itself when available now
+    pub fn next_available_at(&self, timestamp: i64) -> Option<i64> {
+        if self.is_available_at(timestamp) {
+            return Some(timestamp);
+        }
+        let tz = self.tz()?;
+        let today = self.local(timestamp)?.date();
+        for offset in 0..=LOOKAHEAD_DAYS {
+            let date = today.checked_add_days(Days::new(offset))?;
+            let mut windows = self.windows_on(date);
+            windows.sort_by_key(|w| w.start);
+            for window in windows {
+                let Some(start) = resolve_local(&tz, date.and_time(window.start)) else {
+                    continue;
+                };
+                if start > timestamp {
+                    return Some(start);
+                }
+            }
+        }
+        None
+    }
+
+    /// Local rendering of a timestamp, e.g. `Mon 09:00 (Europe/Berlin)`
+    pub fn format_local(&self, timestamp: i64) -> String {
+        match self.tz().and_then(|tz| DateTime::from_timestamp(timestamp, 0).map(|t| t.with_timezone(&tz))) {
+            Some(local) => format!("{} ({})", local.format("%a %H:%M"), self.timezone),
+            None => timestamp.to_string(),
+        }
+    }
+
+    fn tz(&self) -> Option<Tz> {
+        self.timezone.parse().ok()
+    }
+
+    fn local(&self, timestamp: i64) -> Option<NaiveDateTime> {
+        let tz = self.tz()?;
+        DateTime::from_timestamp(timestamp, 0).map(|t| t.with_timezone(&tz).naive_local())
+    }
+
+    /// Windows in effect on a local date: an exception replaces the weekly schedule
+    fn windows_on(&self, date: NaiveDate) -> Vec<TimeWindow> {
+        if let Some(exception) = self.exceptions.iter().find(|e| e.covers(date)) {
+            return exception.windows.clone();
+        }
+        self.weekly
+            .iter()
+            .filter(|w| w.days.contains(&date.weekday()))
+            .map(|w| w.hours)
+            .collect()
+    }
+}
+
+/// Unix timestamp of a local time; ambiguous times (DST fall-back) use the earlier
+/// instant, times inside a DST gap move to the first valid time after it
+fn resolve_local(tz: &Tz, local: NaiveDateTime) -> Option<i64> {
+    let mut candidate = local;
+    for _ in 0..=(120 / GAP_STEP_MINUTES) {
+        match tz.from_local_datetime(&candidate) {
+            LocalResult::Single(t) => return Some(t.timestamp()),
+            LocalResult::Ambiguous(earliest, _) => return Some(earliest.timestamp()),
+            LocalResult::None => candidate += chrono::Duration::minutes(GAP_STEP_MINUTES),
+        }
+    }
+    None
+}
@@ -46,15 +46,12 @@ impl Capability {
 
     /// Get required parameter field list
     pub fn required_inputs(&self) -> Vec<String> {
-        self.input_schema
-            .get("required")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default()
+        crate::domain::schema::required_fields(&self.input_schema)
+    }
+
+    /// MCP tool definition
+    pub fn to_mcp_tool(&self) -> Value {
+        crate::domain::schema::mcp_tool(&self.id, &self.description, &self.input_schema)
     }
 
     /// 获取输入参数属性定义
@@ -140,197 +137,15 @@ impl Default for CapabilityPath {
     }
 }
 
-/// JSON Schema 输入参数构建器
-///
-/// 用于方便地构建 MCP 兼容的 JSON Schema 参数定义
-pub struct InputSchema;
-
-impl InputSchema {
-    /// 创建 object 类型的参数根
-    ///
-    /// # Example
-    /// ```
-    /// let params = InputSchema::object()
-    ///     .property("name", InputSchema::string().description("用户名"))
-    ///     .property("age", InputSchema::integer().description("年龄").optional())
-    ///     .build();
-    /// ```
-    pub fn object() -> ObjectSchemaBuilder {
-        ObjectSchemaBuilder::new()
-    }
-
-    /// 创建 string 类型
-    pub fn string() -> TypeBuilder {
-        TypeBuilder::new("string")
-    }
-
-    /// 创建 integer 类型
-    pub fn integer() -> TypeBuilder {
-        TypeBuilder::new("integer")
-    }
-
-    /// 创建 number 类型
-    pub fn number() -> TypeBuilder {
-        TypeBuilder::new("number")
-    }
-
-    /// 创建 boolean 类型
-    pub fn boolean() -> TypeBuilder {
-        TypeBuilder::new("boolean")
-    }
-
-    /// 创建 array 类型
-    pub fn array(item_schema: Value) -> TypeBuilder {
-        let mut builder = TypeBuilder::new("array");
-        builder.schema["items"] = item_schema;
-        builder
-    }
-
-    /// 创建 string 数组类型（常用）
-    pub fn string_array() -> TypeBuilder {
-        Self::array(serde_json::json!({"type": "string"}))
-    }
-
-    /// 创建 enum 类型
-    pub fn enum_values(values: Vec<&str>) -> TypeBuilder {
-        let mut builder = TypeBuilder::new("string");
-        builder.schema["enum"] = serde_json::json!(values);
-        builder
-    }
-}
-
-/// JSON Schema 输出参数构建器
-pub struct OutputSchema;
-
-impl OutputSchema {
-    /// 创建输出 schema
-    pub fn object() -> ObjectSchemaBuilder {
-        ObjectSchemaBuilder::new()
-    }
-
-    /// 创建 string 类型
-    pub fn string() -> TypeBuilder {
-        TypeBuilder::new("string")
-    }
-
-    /// 创建 integer 类型
-    pub fn integer() -> TypeBuilder {
-        TypeBuilder::new("integer")
-    }
-
-    /// 创建 number 类型
-    pub fn number() -> TypeBuilder {
-        TypeBuilder::new("number")
-    }
-
-    /// 创建 boolean 类型
-    pub fn boolean() -> TypeBuilder {
-        TypeBuilder::new("boolean")
-    }
-
-    /// 创建 array 类型
-    pub fn array(item_schema: Value) -> TypeBuilder {
-        let mut builder = TypeBuilder::new("array");
-        builder.schema["items"] = item_schema;
-        builder
-    }
-}
-
-use serde_json::json;
-
-/// Object 类型构建器
-pub struct ObjectSchemaBuilder {
-    schema: Value,
-}
-
-impl ObjectSchemaBuilder {
-    fn new() -> Self {
-        Self {
-            schema: json!({
-                "type": "object",
-                "properties": {},
-                "required": []
-            }),
-        }
-    }
-
-    /// 添加属性
-    pub fn property(mut self, name: &str, builder: TypeBuilder) -> Self {
-        // 先检查是否必填，再移动 builder
-        let is_required = builder.required;
-
-        let properties = self.schema["properties"].as_object_mut()
-            .expect("Expected 'properties' to be an object in schema");
-        properties.insert(name.to_string(), builder.build());
-
-        // 如果属性是必填的，添加到 required 数组
-        if is_required {
-            let required = self.schema["required"].as_array_mut()
-                .expect("Expected 'required' to be an array in schema");
-            required.push(json!(name));
-        }
-
-        self
-    }
-
-    /// 直接添加原始 JSON Schema 属性
-    pub fn raw_property(mut self, name: &str, schema: Value, is_required: bool) -> Self {
-        let properties = self.schema["properties"].as_object_mut()
-            .expect("Expected 'properties' to be an object in schema");
-        properties.insert(name.to_string(), schema);
-
-        if is_required {
-            let required = self.schema["required"].as_array_mut()
-                .expect("Expected 'required' to be an array in schema");
-            required.push(json!(name));
-        }
-
-        self
-    }
-
-    /// 构建最终的 JSON Schema
-    pub fn build(self) -> Value {
-        self.schema
-    }
-}
-
-/// 类型构建器
-pub struct TypeBuilder {
-    schema: Value,
-    required: bool,
-}
-
-impl TypeBuilder {
-    fn new(type_name: &str) -> Self {
-        Self {
-            schema: json!({"type": type_name}),
-            required: true,
-        }
-    }
-
-    /// 设置描述
-    pub fn description(mut self, desc: &str) -> Self {
-        self.schema["description"] = json!(desc);
-        self
-    }
+/// 旧的能力输入 Schema 构建器，与工具共用 [`JsonSchema`]
+#[deprecated(note = "use `JsonSchema`, which builds both tool and capability schemas")]
+pub type InputSchema = JsonSchema;
 
-    /// 设置为可选（用于构建时判断）
-    pub fn optional(mut self) -> Self {
-        self.required = false;
-        self
-    }
+/// 旧的能力输出 Schema 构建器，与工具共用 [`JsonSchema`]
+#[deprecated(note = "use `JsonSchema`, which builds both tool and capability schemas")]
+pub type OutputSchema = JsonSchema;
 
-    /// 添加 enum 约束
-    pub fn enum_values(mut self, values: Vec<&str>) -> Self {
-        self.schema["enum"] = json!(values);
-        self
-    }
-
-    /// 构建最终的 JSON Schema
-    pub fn build(self) -> Value {
-        self.schema
-    }
-}
+pub use crate::domain::schema::{JsonSchema, ObjectSchemaBuilder, TypeBuilder};
 
 /// 匹配类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
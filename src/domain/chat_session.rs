@@ -0,0 +1,118 @@
+//! Chat Session Domain Model
+//!
+//! A chat session is a conversation between users and one agent that formally ends: it is
+//! closed explicitly or after a period of inactivity, and closing it stores a summary and a
+//! resolution status as the session's artifact. An agent has one current session at a time;
+//! its first session uses the agent ID as the session ID so it lines up with `/chat/{id}/*`.
+
+use serde::{Deserialize, Serialize};
+
+/// Session status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatSessionStatus {
+    #[default]
+    Open,
+    Closed,
+}
+
+/// How a closed session ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionResolution {
+    /// The request was handled
+    Resolved,
+    /// Closed without handling the request
+    Unresolved,
+    /// Closed automatically after a period of inactivity
+    Inactive,
+}
+
+impl SessionResolution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionResolution::Resolved => "resolved",
+            SessionResolution::Unresolved => "unresolved",
+            SessionResolution::Inactive => "inactive",
+        }
+    }
+}
+
+/// Artifact generated when a session is closed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionArtifact {
+    pub resolution: SessionResolution,
+    /// Summary of the conversation; absent when it could not be generated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Why the summary is missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary_error: Option<String>,
+    /// Note given by whoever closed the session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Messages in the session
+    pub message_count: usize,
+    pub generated_at: i64,
+}
+
+/// Conversation with one agent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatSession {
+    pub id: String,
+    pub agent_id: String,
+    #[serde(default)]
+    pub status: ChatSessionStatus,
+    pub created_at: i64,
+    /// Last message in the session (seconds); inactivity is measured from here
+    pub last_activity_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<i64>,
+    /// User or agent that closed the session; `system` for inactivity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub closed_by: Option<String>,
+    /// Times the session was reopened after being closed
+    #[serde(default)]
+    pub reopen_count: u32,
+    /// Artifact of the most recent close
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<SessionArtifact>,
+}
+
+impl ChatSession {
+    /// Open a new session
+    pub fn open(id: impl Into<String>, agent_id: impl Into<String>, now: i64) -> Self {
+        Self {
+            id: id.into(),
+            agent_id: agent_id.into(),
+            status: ChatSessionStatus::Open,
+            created_at: now,
+            last_activity_at: now,
+            closed_at: None,
+            closed_by: None,
+            reopen_count: 0,
+            artifact: None,
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.status == ChatSessionStatus::Closed
+    }
+
+    /// Mark closed with the generated artifact
+    pub fn close(&mut self, closed_by: impl Into<String>, artifact: SessionArtifact) {
+        self.status = ChatSessionStatus::Closed;
+        self.closed_at = Some(artifact.generated_at);
+        self.closed_by = Some(closed_by.into());
+        self.artifact = Some(artifact);
+    }
+
+    /// Reopen a closed session; the previous artifact is kept until the next close
+    pub fn reopen(&mut self, now: i64) {
+        self.status = ChatSessionStatus::Open;
+        self.closed_at = None;
+        self.closed_by = None;
+        self.reopen_count += 1;
+        self.last_activity_at = now;
+    }
+}
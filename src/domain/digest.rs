@@ -0,0 +1,155 @@
+//! Activity Digest Domain Model
+//!
+//! A digest summarizes company activity over a period for stakeholders who don't
+//! watch the system. Sections that could not be generated are listed in `omitted`.
+
+use serde::{Deserialize, Serialize};
+
+/// Digest cadence
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestCadence {
+    #[default]
+    Daily,
+    Weekly,
+}
+
+impl DigestCadence {
+    /// Period length (seconds)
+    pub fn period_secs(&self) -> i64 {
+        match self {
+            DigestCadence::Daily => 24 * 3600,
+            DigestCadence::Weekly => 7 * 24 * 3600,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DigestCadence::Daily => "daily",
+            DigestCadence::Weekly => "weekly",
+        }
+    }
+}
+
+/// Generated digest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Digest {
+    pub id: String,
+    pub cadence: DigestCadence,
+    /// Period start (seconds, inclusive)
+    pub period_start: i64,
+    /// Period end (seconds, exclusive)
+    pub period_end: i64,
+    pub generated_at: i64,
+    #[serde(default)]
+    pub sections: Vec<DigestSection>,
+    /// Sections that failed to generate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub omitted: Vec<OmittedSection>,
+}
+
+impl Digest {
+    /// Create empty digest for a period
+    pub fn new(cadence: DigestCadence, period_start: i64, period_end: i64, generated_at: i64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            cadence,
+            period_start,
+            period_end,
+            generated_at,
+            sections: Vec::new(),
+            omitted: Vec::new(),
+        }
+    }
+}
+
+/// Digest section
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DigestSection {
+    /// Message volume
+    Messages {
+        total: usize,
+        direct: usize,
+        group: usize,
+        /// Most active senders, most messages first
+        top_senders: Vec<SenderCount>,
+    },
+    /// Notable decisions per group, summarized by the LLM
+    Decisions { groups: Vec<GroupDecisions> },
+    /// Tasks completed in the period
+    Tasks { completed: Vec<CompletedTask> },
+    /// Watchdog rules triggered in the period
+    Incidents { incidents: Vec<IncidentCount> },
+    /// Department LLM budget consumption on the generation day (UTC)
+    Budgets { departments: Vec<BudgetUsage> },
+    /// Who spoke how much in each group during the period
+    GroupActivity { groups: Vec<GroupActivity> },
+}
+
+impl DigestSection {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DigestSection::Messages { .. } => "messages",
+            DigestSection::Decisions { .. } => "decisions",
+            DigestSection::Tasks { .. } => "tasks",
+            DigestSection::Incidents { .. } => "incidents",
+            DigestSection::Budgets { .. } => "budgets",
+            DigestSection::GroupActivity { .. } => "group_activity",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SenderCount {
+    pub sender: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupDecisions {
+    pub group_id: String,
+    pub group_name: String,
+    pub summary: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupActivity {
+    pub group_id: String,
+    pub group_name: String,
+    /// Members who posted in the period, most messages first
+    pub senders: Vec<SenderCount>,
+    /// Members who did not post in the period
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quiet: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletedTask {
+    pub id: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IncidentCount {
+    pub rule_id: String,
+    pub tool_id: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BudgetUsage {
+    pub department_id: String,
+    pub department_name: String,
+    pub used: u64,
+    pub budget: u64,
+}
+
+/// Section left out of a digest and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OmittedSection {
+    pub section: String,
+    pub reason: String,
+}
@@ -0,0 +1,120 @@
+//! Group Activity Statistics Domain Model
+//!
+//! Per-group read model kept up to date as messages are persisted: how much each member
+//! posted (in hourly buckets so rolling windows can be answered without scanning messages),
+//! when they were last active and how quickly they reply after being mentioned.
+//! Applying the same messages in the same order always yields the same state, so the
+//! incremental state can be compared with (and repaired by) a recomputation.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain::Message;
+
+/// Activity statistics of one group
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupStats {
+    pub group_id: String,
+    /// Member ID -> activity
+    #[serde(default)]
+    pub members: BTreeMap<String, MemberActivity>,
+    /// Member ID -> time of the earliest mention they have not replied to yet
+    #[serde(default)]
+    pub pending_mentions: BTreeMap<String, i64>,
+    /// Group sequence number of the last applied message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seq: Option<u64>,
+    /// Timestamp of the latest applied message
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+/// Activity of one member in a group
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemberActivity {
+    /// Messages sent since the stats were started
+    pub total_messages: u64,
+    /// Bucket start (seconds) -> messages sent in the bucket; buckets older than the retention are dropped
+    #[serde(default)]
+    pub buckets: BTreeMap<i64, u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_active_at: Option<i64>,
+    /// Replies to mentions
+    #[serde(default)]
+    pub mention_replies: u64,
+    /// Sum of the delays between being mentioned and replying (seconds)
+    #[serde(default)]
+    pub response_secs_total: u64,
+}
+
+impl MemberActivity {
+    /// Messages sent at or after `since`
+    pub fn messages_since(&self, since: i64) -> u64 {
+        self.buckets.range(since..).map(|(_, count)| u64::from(*count)).sum()
+    }
+
+    /// Messages sent in `[start, end)`
+    pub fn messages_between(&self, start: i64, end: i64) -> u64 {
+        if start >= end {
+            return 0;
+        }
+        self.buckets.range(start..end).map(|(_, count)| u64::from(*count)).sum()
+    }
+
+    /// Average delay between being mentioned and replying (seconds)
+    pub fn average_response_secs(&self) -> Option<u64> {
+        (self.mention_replies > 0).then(|| self.response_secs_total / self.mention_replies)
+    }
+}
+
+impl GroupStats {
+    pub fn new(group_id: impl Into<String>) -> Self {
+        Self {
+            group_id: group_id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether the message was already applied (by group sequence number)
+    pub fn has_applied(&self, message: &Message) -> bool {
+        matches!((self.last_seq, message.group_seq()), (Some(last), Some(seq)) if seq <= last)
+    }
+
+    /// Apply one group message
+    ///
+    /// The sender's first message after being mentioned counts as the reply to the earliest
+    /// pending mention. Buckets are bucketed by `bucket_secs` and the ones older than
+    /// `retention_secs` before the message are dropped.
+    pub fn apply(&mut self, message: &Message, bucket_secs: i64, retention_secs: i64) {
+        let at = message.timestamp;
+        let bucket_secs = bucket_secs.max(1);
+        let member = self.members.entry(message.from.clone()).or_default();
+        member.total_messages += 1;
+        *member.buckets.entry(at.div_euclid(bucket_secs) * bucket_secs).or_default() += 1;
+        member.last_active_at = Some(member.last_active_at.map_or(at, |last| last.max(at)));
+        if let Some(mentioned_at) = self.pending_mentions.remove(&message.from) {
+            member.mention_replies += 1;
+            member.response_secs_total += (at - mentioned_at).max(0) as u64;
+        }
+
+        for mentioned in &message.mentions {
+            if *mentioned != message.from {
+                self.pending_mentions.entry(mentioned.clone()).or_insert(at);
+            }
+        }
+
+        if let Some(seq) = message.group_seq() {
+            self.last_seq = Some(self.last_seq.map_or(seq, |last| last.max(seq)));
+        }
+        self.updated_at = self.updated_at.max(at);
+        self.prune(self.updated_at - retention_secs);
+    }
+
+    /// Drop buckets that started before `before`
+    pub fn prune(&mut self, before: i64) {
+        for member in self.members.values_mut() {
+            member.buckets = member.buckets.split_off(&before);
+        }
+    }
+}
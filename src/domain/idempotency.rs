@@ -0,0 +1,32 @@
+//! Idempotency Key Records
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Stored outcome of a request made with an `Idempotency-Key` header
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IdempotencyRecord {
+    /// Principal the key belongs to, e.g. `user:42`
+    pub scope: String,
+    /// Client-supplied key
+    pub key: String,
+    /// Endpoint the key was first used on, e.g. `POST /messages`
+    pub endpoint: String,
+    /// Hash of the endpoint and request body
+    pub request_hash: String,
+    /// HTTP status of the stored response
+    pub status: u16,
+    /// Stored response body
+    pub response: Value,
+    /// Creation timestamp (seconds)
+    pub created_at: i64,
+    /// Expiration timestamp (seconds)
+    pub expires_at: i64,
+}
+
+impl IdempotencyRecord {
+    /// Whether the record has expired at `now` (seconds)
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+}
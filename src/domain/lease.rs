@@ -0,0 +1,51 @@
+//! Leadership Leases
+
+use serde::{Deserialize, Serialize};
+
+/// Time-limited, exclusive claim shared through the store, e.g. the right to drive a company's agents
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Lease {
+    /// Lease name, one per shared resource
+    pub name: String,
+    /// Node currently holding the lease
+    pub holder: String,
+    /// Fencing token, incremented every time the lease changes hands
+    pub token: u64,
+    /// When the current holder acquired the lease (seconds)
+    pub acquired_at: i64,
+    /// Expiration timestamp (seconds); the holder must renew before then
+    pub expires_at: i64,
+}
+
+impl Lease {
+    /// Whether the lease has expired at `now` (seconds)
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+
+    /// Whether `holder` holds the lease at `now`
+    pub fn is_held_by(&self, holder: &str, now: i64) -> bool {
+        self.holder == holder && !self.is_expired(now)
+    }
+
+    /// Outcome of `holder` acquiring or renewing the lease at `now`
+    ///
+    /// A live lease held by someone else is returned unchanged. Renewing keeps the token;
+    /// taking over a free or expired lease issues the next token.
+    pub fn claim(current: Option<Lease>, name: &str, holder: &str, now: i64, ttl_secs: i64) -> Lease {
+        match current {
+            Some(lease) if !lease.is_expired(now) && lease.holder != holder => lease,
+            Some(lease) if !lease.is_expired(now) => Lease {
+                expires_at: now + ttl_secs,
+                ..lease
+            },
+            current => Lease {
+                name: name.to_string(),
+                holder: holder.to_string(),
+                token: current.map_or(1, |lease| lease.token + 1),
+                acquired_at: now,
+                expires_at: now + ttl_secs,
+            },
+        }
+    }
+}
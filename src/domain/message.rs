@@ -2,11 +2,43 @@
 //!
 //! Simplified message system definition
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 /// Message ID
 pub type MessageId = String;
 
+/// Metadata key holding the trace id
+pub const TRACE_ID_KEY: &str = "trace_id";
+
+/// Metadata key holding the parent span id
+pub const PARENT_SPAN_ID_KEY: &str = "parent_span_id";
+
+/// Metadata key holding the agent-to-agent reply chain depth
+pub const CHAIN_DEPTH_KEY: &str = "chain_depth";
+
+/// Metadata key holding attachment links, one per line
+pub const ATTACHMENTS_KEY: &str = "attachments";
+
+/// Metadata key holding the JSON-encoded citations of an agent answer
+pub const CITATIONS_KEY: &str = "citations";
+
+/// Metadata key holding the per-group sequence number assigned by the message bus
+pub const GROUP_SEQ_KEY: &str = "group_seq";
+
+/// Tool result cited by an agent answer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Citation {
+    /// Reference id as it appears in the content, e.g. `T1`
+    pub ref_id: String,
+    pub tool_id: String,
+    /// Short digest of the tool call parameters
+    pub params_digest: String,
+    /// Beginning of the tool result
+    pub excerpt: String,
+}
+
 /// Message Entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
@@ -19,6 +51,12 @@ pub struct Message {
     pub reply_to: Option<String>,
     /// List of @ users
     pub mentions: Vec<String>,
+    /// Extra key-value annotations (e.g. opt-out flags, correlation ids)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: HashMap<String, String>,
+    /// Importance, higher priority messages are handled first by the recipient
+    #[serde(default)]
+    pub priority: MessagePriority,
 }
 
 impl Message {
@@ -32,6 +70,8 @@ impl Message {
             timestamp: chrono::Utc::now().timestamp(),
             reply_to: None,
             mentions: Vec::new(),
+            metadata: HashMap::new(),
+            priority: MessagePriority::Normal,
         }
     }
 
@@ -45,6 +85,8 @@ impl Message {
             timestamp: chrono::Utc::now().timestamp(),
             reply_to: None,
             mentions: Vec::new(),
+            metadata: HashMap::new(),
+            priority: MessagePriority::Normal,
         }
     }
 
@@ -74,6 +116,95 @@ impl Message {
         self
     }
 
+    /// Set priority
+    pub fn with_priority(mut self, priority: MessagePriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Set metadata entry
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    /// Get metadata entry
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    /// Attach trace id and parent span id (stored in metadata)
+    pub fn with_trace(mut self, trace_id: &str, parent_span_id: Option<&str>) -> Self {
+        self.metadata.insert(TRACE_ID_KEY.to_string(), trace_id.to_string());
+        if let Some(span_id) = parent_span_id {
+            self.metadata.insert(PARENT_SPAN_ID_KEY.to_string(), span_id.to_string());
+        }
+        self
+    }
+
+    /// Trace id of the request or agent turn that produced this message
+    pub fn trace_id(&self) -> Option<&str> {
+        self.metadata(TRACE_ID_KEY)
+    }
+
+    /// Set agent-to-agent reply chain depth (stored in metadata)
+    pub fn with_chain_depth(mut self, depth: u32) -> Self {
+        self.metadata.insert(CHAIN_DEPTH_KEY.to_string(), depth.to_string());
+        self
+    }
+
+    /// Agent-to-agent reply chain depth, 0 when not set
+    pub fn chain_depth(&self) -> u32 {
+        self.metadata(CHAIN_DEPTH_KEY).and_then(|d| d.parse().ok()).unwrap_or(0)
+    }
+
+    /// Add an attachment link (stored in metadata)
+    pub fn with_attachment(mut self, url: impl Into<String>) -> Self {
+        let url = url.into();
+        let links = match self.metadata.remove(ATTACHMENTS_KEY) {
+            Some(existing) if !existing.is_empty() => format!("{}\n{}", existing, url),
+            _ => url,
+        };
+        self.metadata.insert(ATTACHMENTS_KEY.to_string(), links);
+        self
+    }
+
+    /// Attachment links in the order they were added
+    pub fn attachments(&self) -> Vec<&str> {
+        self.metadata(ATTACHMENTS_KEY)
+            .map(|links| links.lines().filter(|l| !l.trim().is_empty()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Attach citations (stored in metadata as JSON); an empty list leaves the message unchanged
+    pub fn with_citations(mut self, citations: &[Citation]) -> Self {
+        if citations.is_empty() {
+            return self;
+        }
+        if let Ok(encoded) = serde_json::to_string(citations) {
+            self.metadata.insert(CITATIONS_KEY.to_string(), encoded);
+        }
+        self
+    }
+
+    /// Citations attached to the message, empty when there are none or they cannot be decoded
+    pub fn citations(&self) -> Vec<Citation> {
+        self.metadata(CITATIONS_KEY)
+            .and_then(|encoded| serde_json::from_str(encoded).ok())
+            .unwrap_or_default()
+    }
+
+    /// Set the per-group sequence number (stored in metadata)
+    pub fn with_group_seq(mut self, seq: u64) -> Self {
+        self.metadata.insert(GROUP_SEQ_KEY.to_string(), seq.to_string());
+        self
+    }
+
+    /// Per-group sequence number, None for direct messages and messages sent before sequencing
+    pub fn group_seq(&self) -> Option<u64> {
+        self.metadata(GROUP_SEQ_KEY).and_then(|seq| seq.parse().ok())
+    }
+
     /// Get target Agent (if private message)
     pub fn target_agent(&self) -> Option<&str> {
         match &self.to {
@@ -89,6 +220,72 @@ impl Message {
             _ => None,
         }
     }
+
+    /// Conversation the message belongs to: the group ID, or [`direct_session_id`] of both participants
+    pub fn session_id(&self) -> String {
+        match &self.to {
+            MessageTarget::Group(group_id) => group_id.clone(),
+            MessageTarget::Direct(to) => direct_session_id(&self.from, to),
+        }
+    }
+
+    /// Whether `principal` takes part in a direct message (group membership is checked elsewhere)
+    pub fn is_direct_participant(&self, principal: &str) -> bool {
+        matches!(&self.to, MessageTarget::Direct(to) if to == principal || self.from == principal)
+    }
+}
+
+/// Session ID of a direct conversation, the same whichever side is passed first
+pub fn direct_session_id(a: &str, b: &str) -> String {
+    if a <= b {
+        format!("{}|{}", a, b)
+    } else {
+        format!("{}|{}", b, a)
+    }
+}
+
+/// Message importance, ordered from lowest to highest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessagePriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Urgent,
+}
+
+impl MessagePriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+            Self::Urgent => "urgent",
+        }
+    }
+
+    /// Parse from name, case insensitive
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Self::Low),
+            "normal" => Some(Self::Normal),
+            "high" => Some(Self::High),
+            "urgent" => Some(Self::Urgent),
+            _ => None,
+        }
+    }
+
+    /// High or Urgent: wakes an idle agent instead of waiting for its next poll
+    pub fn is_elevated(&self) -> bool {
+        *self >= Self::High
+    }
+}
+
+impl std::fmt::Display for MessagePriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Message Target
@@ -101,6 +298,176 @@ pub enum MessageTarget {
     Group(String),
 }
 
+/// Messages queued during one agent turn, sent when the turn ends
+///
+/// Cloning shares the same queue, so the runtime and the tool calls of a turn
+/// see the same outbox.
+#[derive(Debug, Clone, Default)]
+pub struct TurnOutbox {
+    messages: std::sync::Arc<std::sync::Mutex<Vec<Message>>>,
+}
+
+impl TurnOutbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a message, returning the number of queued messages
+    pub fn enqueue(&self, message: Message) -> usize {
+        let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        messages.push(message);
+        messages.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Take all queued messages in enqueue order
+    pub fn take(&self) -> Vec<Message> {
+        std::mem::take(&mut *self.messages.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Maximum length of a reaction emoji in bytes
+pub const MAX_REACTION_LEN: usize = 32;
+
+/// Emoji reaction on a message, unique per message + reactor + emoji
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MessageReaction {
+    pub message_id: MessageId,
+    /// User or Agent who reacted
+    pub reactor_id: String,
+    pub emoji: String,
+    pub timestamp: i64,
+}
+
+impl MessageReaction {
+    /// Create reaction at the current time
+    pub fn new(message_id: impl Into<String>, reactor_id: impl Into<String>, emoji: impl Into<String>) -> Self {
+        Self {
+            message_id: message_id.into(),
+            reactor_id: reactor_id.into(),
+            emoji: emoji.into(),
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Whether the emoji is acceptable (non-empty, short, no whitespace)
+    pub fn is_valid_emoji(emoji: &str) -> bool {
+        !emoji.is_empty()
+            && emoji.len() <= MAX_REACTION_LEN
+            && !emoji.chars().any(|c| c.is_whitespace() || c.is_control())
+    }
+}
+
+/// Message pinned in a session, visible to everyone in it; unique per session + message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MessagePin {
+    pub message_id: MessageId,
+    /// Group ID or [`direct_session_id`]
+    pub session_id: String,
+    /// User or Agent who pinned the message
+    pub pinned_by: String,
+    pub timestamp: i64,
+}
+
+impl MessagePin {
+    /// Create pin at the current time
+    pub fn new(message_id: impl Into<String>, session_id: impl Into<String>, pinned_by: impl Into<String>) -> Self {
+        Self {
+            message_id: message_id.into(),
+            session_id: session_id.into(),
+            pinned_by: pinned_by.into(),
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Personal bookmark, visible only to its owner; unique per owner + message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MessageBookmark {
+    pub message_id: MessageId,
+    /// User principal or Agent ID
+    pub owner_id: String,
+    pub timestamp: i64,
+}
+
+impl MessageBookmark {
+    /// Create bookmark at the current time
+    pub fn new(message_id: impl Into<String>, owner_id: impl Into<String>) -> Self {
+        Self {
+            message_id: message_id.into(),
+            owner_id: owner_id.into(),
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Aggregated count of one emoji on a message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReactionCount {
+    pub emoji: String,
+    pub count: usize,
+    pub reactors: Vec<String>,
+}
+
+impl ReactionCount {
+    /// Group reactions by message, emojis in order of first reaction
+    pub fn aggregate(reactions: &[MessageReaction]) -> HashMap<MessageId, Vec<ReactionCount>> {
+        let mut sorted: Vec<&MessageReaction> = reactions.iter().collect();
+        sorted.sort_by_key(|r| r.timestamp);
+
+        let mut result: HashMap<MessageId, Vec<ReactionCount>> = HashMap::new();
+        for reaction in sorted {
+            let counts = result.entry(reaction.message_id.clone()).or_default();
+            match counts.iter_mut().find(|c| c.emoji == reaction.emoji) {
+                Some(count) => {
+                    if !count.reactors.contains(&reaction.reactor_id) {
+                        count.reactors.push(reaction.reactor_id.clone());
+                        count.count += 1;
+                    }
+                }
+                None => counts.push(ReactionCount {
+                    emoji: reaction.emoji.clone(),
+                    count: 1,
+                    reactors: vec![reaction.reactor_id.clone()],
+                }),
+            }
+        }
+        result
+    }
+}
+
+/// Translation of a message into a reader's language; the stored message keeps the original
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MessageTranslation {
+    pub message_id: MessageId,
+    /// Target language code, e.g. `en`
+    pub language: String,
+    /// Detected language of the original (ISO 639-3)
+    pub source_language: String,
+    pub text: String,
+    pub created_at: i64,
+}
+
+/// Escalation record of an unanswered direct message
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Escalation {
+    /// Original message ID
+    pub message_id: MessageId,
+    /// Agent that did not reply
+    pub agent_id: String,
+    /// Leader the message was escalated to
+    pub escalated_to: String,
+    /// Escalation timestamp
+    pub escalated_at: i64,
+}
+
 /// Group Definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Group {
@@ -109,6 +476,120 @@ pub struct Group {
     pub creator_id: String,
     pub members: Vec<String>,
     pub created_at: i64,
+    /// Ephemeral groups are closed automatically once they expire
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ephemeral: bool,
+    /// Expiry timestamp (seconds) of an ephemeral group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// Members allowed to moderate the group; always includes the creator
+    #[serde(default)]
+    pub admins: Vec<String>,
+    /// Muted member -> timestamp (seconds) the mute expires
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub muted: HashMap<String, i64>,
+    /// Turn-taking settings; when set, one agent at a time answers a question in the group
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_taking: Option<TurnTakingSettings>,
+    /// Members removed by an admin; membership sync reports them instead of re-adding them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kicked: Vec<String>,
+    /// Membership sync settings; when set, members follow the source's current members
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sync: Option<GroupSync>,
+}
+
+/// Where a synced group takes its members from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncSource {
+    /// Agents of a department
+    Department(String),
+}
+
+/// Membership sync settings of a group
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GroupSync {
+    pub source: SyncSource,
+    /// Also take agents of sub-departments, recursively
+    #[serde(default)]
+    pub include_sub_departments: bool,
+    /// Also remove members added by hand; otherwise only members the sync added are removed
+    #[serde(default)]
+    pub strict: bool,
+    /// Members added by the sync or already present when it found them in the source
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub synced_members: Vec<String>,
+}
+
+impl GroupSync {
+    /// Sync with the agents of a department
+    pub fn department(department_id: impl Into<String>) -> Self {
+        Self {
+            source: SyncSource::Department(department_id.into()),
+            include_sub_departments: false,
+            strict: false,
+            synced_members: Vec::new(),
+        }
+    }
+
+    /// Include agents of sub-departments
+    pub fn with_sub_departments(mut self) -> Self {
+        self.include_sub_departments = true;
+        self
+    }
+
+    /// Remove every member not in the source, including those added by hand
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+}
+
+/// Membership changes made by one sync of a group
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct GroupSyncReport {
+    pub group_id: String,
+    pub joined: Vec<String>,
+    pub left: Vec<String>,
+    /// Source members not re-added because an admin removed them from the group
+    pub kicked: Vec<String>,
+}
+
+impl GroupSyncReport {
+    /// Whether members joined or left
+    pub fn changed(&self) -> bool {
+        !self.joined.is_empty() || !self.left.is_empty()
+    }
+}
+
+/// Turn-taking settings of a group
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct TurnTakingSettings {
+    /// Seconds the chosen agent has to answer before the next one may
+    pub silence_window_secs: u64,
+    /// Role title -> keywords; a question containing a keyword goes to agents with that role first
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub responsibilities: HashMap<String, Vec<String>>,
+}
+
+impl Default for TurnTakingSettings {
+    fn default() -> Self {
+        Self {
+            silence_window_secs: 60,
+            responsibilities: HashMap::new(),
+        }
+    }
+}
+
+impl TurnTakingSettings {
+    /// Route questions containing one of `keywords` to agents with the given role title
+    pub fn with_responsibility(mut self, role_title: impl Into<String>, keywords: &[&str]) -> Self {
+        self.responsibilities
+            .insert(role_title.into(), keywords.iter().map(|k| k.to_string()).collect());
+        self
+    }
 }
 
 impl Group {
@@ -119,30 +600,144 @@ impl Group {
         creator_id: impl Into<String>,
         members: Vec<String>,
     ) -> Self {
+        let creator_id = creator_id.into();
         Self {
             id: id.into(),
             name: name.into(),
-            creator_id: creator_id.into(),
+            admins: vec![creator_id.clone()],
+            creator_id,
             members,
             created_at: chrono::Utc::now().timestamp(),
+            ephemeral: false,
+            expires_at: None,
+            muted: HashMap::new(),
+            turn_taking: None,
+            kicked: Vec::new(),
+            sync: None,
         }
     }
 
-    /// Add member
+    /// Mark as ephemeral, expiring at the given timestamp (seconds)
+    pub fn with_expiry(mut self, expires_at: i64) -> Self {
+        self.ephemeral = true;
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Whether an ephemeral group has expired at `now`
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.ephemeral && self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    /// Keep members in sync with the given source
+    pub fn with_sync(mut self, sync: GroupSync) -> Self {
+        self.sync = Some(sync);
+        self
+    }
+
+    /// Add member, lifting an earlier kick
     pub fn add_member(&mut self, agent_id: impl Into<String>) {
         let id = agent_id.into();
+        self.kicked.retain(|k| *k != id);
         if !self.members.contains(&id) {
             self.members.push(id);
         }
     }
 
-    /// Remove member
+    /// Remove member, dropping any admin role and mute
     pub fn remove_member(&mut self, agent_id: &str) {
         self.members.retain(|m| m != agent_id);
+        self.admins.retain(|a| a != agent_id);
+        self.muted.remove(agent_id);
+    }
+
+    /// Remove a member on an admin's behalf and remember the kick
+    pub fn kick(&mut self, agent_id: &str) {
+        self.remove_member(agent_id);
+        if !self.kicked.iter().any(|k| k == agent_id) {
+            self.kicked.push(agent_id.to_string());
+        }
+    }
+
+    /// Match members to `source_members`, the current members of the sync source
+    ///
+    /// Kicked agents are reported rather than re-added. Outside strict mode only members
+    /// the sync added are removed; the creator is never removed. Mutes of removed members
+    /// are kept so that rejoining does not lift them. Does nothing without sync settings.
+    pub fn apply_sync(&mut self, source_members: &[String]) -> GroupSyncReport {
+        let mut report = GroupSyncReport {
+            group_id: self.id.clone(),
+            ..Default::default()
+        };
+        let Some(mut sync) = self.sync.take() else {
+            return report;
+        };
+
+        for id in source_members {
+            if self.kicked.contains(id) {
+                report.kicked.push(id.clone());
+                continue;
+            }
+            if !self.members.contains(id) {
+                self.members.push(id.clone());
+                report.joined.push(id.clone());
+            }
+            if !sync.synced_members.contains(id) {
+                sync.synced_members.push(id.clone());
+            }
+        }
+
+        let stale: Vec<String> = self
+            .members
+            .iter()
+            .filter(|m| !source_members.contains(m) && **m != self.creator_id)
+            .filter(|m| sync.strict || sync.synced_members.contains(m))
+            .cloned()
+            .collect();
+        for id in stale {
+            self.members.retain(|m| *m != id);
+            self.admins.retain(|a| *a != id);
+            report.left.push(id);
+        }
+        sync.synced_members.retain(|m| self.members.contains(m));
+
+        self.sync = Some(sync);
+        report
     }
 
     /// Check if is member
     pub fn has_member(&self, agent_id: &str) -> bool {
         self.members.contains(&agent_id.to_string())
     }
+
+    /// Check if is admin (the creator always is)
+    pub fn is_admin(&self, agent_id: &str) -> bool {
+        self.creator_id == agent_id || self.admins.iter().any(|a| a == agent_id)
+    }
+
+    /// Grant the admin role
+    pub fn add_admin(&mut self, agent_id: impl Into<String>) {
+        let id = agent_id.into();
+        if !self.admins.contains(&id) {
+            self.admins.push(id);
+        }
+    }
+
+    /// Make sure the creator is listed as admin (groups stored before admins existed)
+    pub fn ensure_creator_admin(&mut self) {
+        if !self.creator_id.is_empty() && !self.admins.contains(&self.creator_id) {
+            self.admins.insert(0, self.creator_id.clone());
+        }
+    }
+
+    /// Mute a member until the given timestamp (seconds), dropping mutes already expired at `now`
+    pub fn mute(&mut self, agent_id: impl Into<String>, until: i64, now: i64) {
+        self.muted.retain(|_, at| *at > now);
+        self.muted.insert(agent_id.into(), until);
+    }
+
+    /// When the member's mute expires, None if not muted at `now`
+    pub fn muted_until(&self, agent_id: &str, now: i64) -> Option<i64> {
+        self.muted.get(agent_id).copied().filter(|until| *until > now)
+    }
 }
@@ -3,22 +3,40 @@
 //! Core business entity definitions
 
 pub mod agent;
+pub mod availability;
+pub mod chat_session;
+pub mod digest;
+pub mod group_stats;
 pub mod message;
+pub mod moderation;
 pub mod org;
+pub mod org_change;
+pub mod scratchpad;
 pub mod skill;
+pub mod task;
 pub mod tool;
 pub mod capability;
+pub mod schema;
 pub mod user;
 pub mod invitation_code;
+pub mod idempotency;
+pub mod lease;
+pub mod share_token;
 
 pub use agent::*;
+pub use availability::{Availability, DateException, TimeWindow, WeeklyWindow};
+pub use chat_session::{ChatSession, ChatSessionStatus, SessionArtifact, SessionResolution};
+pub use digest::{Digest, DigestCadence, DigestSection, OmittedSection};
+pub use group_stats::{GroupStats, MemberActivity};
 pub use message::*;
 pub use org::*;
+pub use org_change::{ChangeKind, EntityChange, FieldChange, OrgChangeEntry, OrgDiff};
 pub use skill::*;
+pub use task::{Task, TaskStatus};
 
 // Export TriggerCondition from agent module since it's used in AgentMode
 pub use agent::TriggerCondition;
 
 // Selective exports to avoid conflicts
-pub use tool::{Tool, CategoryPath, ReturnType, ToolProvider, MatchType, CategoryNodeInfo, ToolCallContext, JsonSchema, ObjectSchemaBuilder, TypeBuilder};
+pub use tool::{Tool, CategoryPath, ReturnType, ToolProvider, MatchType, CategoryNodeInfo, ToolCallContext, ToolProgress, ToolProgressEvent, ToolUsage, new_trace_id, new_span_id, JsonSchema, ObjectSchemaBuilder, TypeBuilder};
 pub use capability::{Capability, CapabilityPath, CapabilityCallContext, CapabilityProvider, CapabilityAccessType, SkillCapabilityBinding, BindingType};
@@ -0,0 +1,66 @@
+//! Moderation Quarantine Domain Model
+//!
+//! Agent outputs blocked by content moderation are replaced with a notice before delivery.
+//! The original is kept here so an admin can review the decision.
+
+use serde::{Deserialize, Serialize};
+
+use super::Message;
+
+/// Outcome of an admin review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineVerdict {
+    /// The block was correct
+    Upheld,
+    /// The output should not have been blocked
+    Overturned,
+}
+
+/// Admin review of a quarantined output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarantineReview {
+    pub reviewer: String,
+    pub verdict: QuarantineVerdict,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Review timestamp (seconds)
+    pub reviewed_at: i64,
+}
+
+/// Blocked agent output retained for review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedOutput {
+    pub id: String,
+    /// Agent that produced the output
+    pub agent_id: String,
+    /// The original message as the agent wrote it; the delivered notice reuses its ID
+    pub message: Message,
+    /// Why the output was blocked
+    pub reason: String,
+    /// Moderator that made the decision (`keyword` or `judge`)
+    pub moderator: String,
+    /// Quarantine timestamp (seconds)
+    pub quarantined_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<QuarantineReview>,
+}
+
+impl QuarantinedOutput {
+    pub fn new(message: Message, reason: impl Into<String>, moderator: impl Into<String>, quarantined_at: i64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id: message.from.clone(),
+            message,
+            reason: reason.into(),
+            moderator: moderator.into(),
+            quarantined_at,
+            review: None,
+        }
+    }
+
+    /// Whether the output is still waiting for review
+    pub fn is_pending(&self) -> bool {
+        self.review.is_none()
+    }
+}
@@ -31,16 +31,85 @@ impl Organization {
         }
     }
 
-    /// Add department
+    /// Add department without checking its id; prefer [`Self::try_add_department`]
     pub fn add_department(&mut self, dept: Department) {
         self.departments.push(dept);
     }
 
-    /// Add Agent
+    /// Add Agent without checking its id; prefer [`Self::try_add_agent`]
     pub fn add_agent(&mut self, agent: Agent) {
         self.agents.push(agent);
     }
 
+    /// Add department, rejecting an id that is already taken
+    pub fn try_add_department(&mut self, dept: Department) -> Result<(), DuplicateId> {
+        if self.find_department(&dept.id).is_some() {
+            return Err(DuplicateId::Department(dept.id));
+        }
+        self.departments.push(dept);
+        Ok(())
+    }
+
+    /// Add Agent, rejecting an id that is already taken
+    pub fn try_add_agent(&mut self, agent: Agent) -> Result<(), DuplicateId> {
+        if self.find_agent(&agent.id).is_some() {
+            return Err(DuplicateId::Agent(agent.id));
+        }
+        self.agents.push(agent);
+        Ok(())
+    }
+
+    /// Add or replace a department in place, returning the replaced one
+    pub fn upsert_department(&mut self, dept: Department) -> Option<Department> {
+        match self.departments.iter_mut().find(|d| d.id == dept.id) {
+            Some(existing) => Some(std::mem::replace(existing, dept)),
+            None => {
+                self.departments.push(dept);
+                None
+            }
+        }
+    }
+
+    /// Add or replace an Agent in place, returning the replaced one
+    pub fn upsert_agent(&mut self, agent: Agent) -> Option<Agent> {
+        match self.agents.iter_mut().find(|a| a.id == agent.id) {
+            Some(existing) => Some(std::mem::replace(existing, agent)),
+            None => {
+                self.agents.push(agent);
+                None
+            }
+        }
+    }
+
+    /// Remove an Agent and clear it as leader of the departments it led, returning the removed Agent
+    pub fn remove_agent(&mut self, id: &str) -> Option<Agent> {
+        let index = self.agents.iter().position(|a| a.id == id)?;
+        for dept in &mut self.departments {
+            if dept.leader_id.as_deref() == Some(id) {
+                dept.leader_id = None;
+            }
+        }
+        Some(self.agents.remove(index))
+    }
+
+    /// Departments led by the Agent
+    pub fn departments_led_by(&self, id: &str) -> Vec<&Department> {
+        self.departments.iter().filter(|d| d.leader_id.as_deref() == Some(id)).collect()
+    }
+
+    /// Check that no department or Agent id appears twice
+    pub fn validate_ids(&self) -> Result<(), DuplicateId> {
+        let mut seen = std::collections::HashSet::new();
+        if let Some(dept) = self.departments.iter().find(|d| !seen.insert(d.id.as_str())) {
+            return Err(DuplicateId::Department(dept.id.clone()));
+        }
+        seen.clear();
+        if let Some(agent) = self.agents.iter().find(|a| !seen.insert(a.id.as_str())) {
+            return Err(DuplicateId::Agent(agent.id.clone()));
+        }
+        Ok(())
+    }
+
     /// Find Agent
     pub fn find_agent(&self, id: &str) -> Option<&Agent> {
         self.agents.iter().find(|a| a.id == id)
@@ -59,6 +128,60 @@ impl Organization {
             .collect()
     }
 
+    /// Check that `agent_id` can join `dept_id` without exceeding its headcount limit
+    ///
+    /// Agents already in the department don't count as new members.
+    pub fn check_headcount(&self, dept_id: &str, agent_id: &str) -> Result<(), DepartmentFull> {
+        let Some(max_agents) = self.find_department(dept_id).and_then(|d| d.max_agents) else {
+            return Ok(());
+        };
+        let members = self.get_department_members(dept_id);
+        if members.iter().any(|a| a.id == agent_id) || members.len() < max_agents {
+            return Ok(());
+        }
+        Err(DepartmentFull {
+            department_id: dept_id.to_string(),
+            current: members.len(),
+            max_agents,
+        })
+    }
+
+    /// Check every department against its headcount limit
+    pub fn validate_headcount(&self) -> Result<(), DepartmentFull> {
+        for dept in &self.departments {
+            let Some(max_agents) = dept.max_agents else {
+                continue;
+            };
+            let current = self.get_department_members(&dept.id).len();
+            if current > max_agents {
+                return Err(DepartmentFull {
+                    department_id: dept.id.clone(),
+                    current,
+                    max_agents,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Add Agent, rejecting it if its department is full
+    pub fn add_agent_checked(&mut self, agent: Agent) -> Result<(), DepartmentFull> {
+        if let Some(dept_id) = &agent.department_id {
+            self.check_headcount(dept_id, &agent.id)?;
+        }
+        self.agents.push(agent);
+        Ok(())
+    }
+
+    /// Move an Agent into a department, rejecting the move if the department is full
+    pub fn move_agent(&mut self, agent_id: &str, dept_id: &str) -> Result<(), DepartmentFull> {
+        self.check_headcount(dept_id, agent_id)?;
+        if let Some(agent) = self.agents.iter_mut().find(|a| a.id == agent_id) {
+            agent.department_id = Some(dept_id.to_string());
+        }
+        Ok(())
+    }
+
     /// Get department leader
     pub fn get_department_leader(&self, dept_id: &str) -> Option<&Agent> {
         let dept = self.find_department(dept_id)?;
@@ -129,6 +252,12 @@ pub struct Department {
     pub name: String,
     pub parent_id: Option<String>,
     pub leader_id: Option<String>,
+    /// Maximum number of agents in this department (direct members only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_agents: Option<usize>,
+    /// Daily LLM token budget shared by the department's agents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llm_budget: Option<u64>,
 }
 
 impl Department {
@@ -139,6 +268,8 @@ impl Department {
             name: name.into(),
             parent_id: None,
             leader_id: None,
+            max_agents: None,
+            llm_budget: None,
         }
     }
 
@@ -153,6 +284,8 @@ impl Department {
             name: name.into(),
             parent_id: Some(parent_id.into()),
             leader_id: None,
+            max_agents: None,
+            llm_budget: None,
         }
     }
 
@@ -161,6 +294,36 @@ impl Department {
         self.leader_id = Some(leader_id.into());
         self
     }
+
+    /// Set headcount limit
+    pub fn with_max_agents(mut self, max_agents: usize) -> Self {
+        self.max_agents = Some(max_agents);
+        self
+    }
+
+    /// Set daily LLM token budget
+    pub fn with_llm_budget(mut self, tokens_per_day: u64) -> Self {
+        self.llm_budget = Some(tokens_per_day);
+        self
+    }
+}
+
+/// A department has reached its headcount limit
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Department {department_id} is full ({current}/{max_agents} agents)")]
+pub struct DepartmentFull {
+    pub department_id: String,
+    pub current: usize,
+    pub max_agents: usize,
+}
+
+/// A department or Agent id is already taken
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DuplicateId {
+    #[error("Department {0} already exists")]
+    Department(String),
+    #[error("Agent {0} already exists")]
+    Agent(String),
 }
 
 /// Organization Configuration
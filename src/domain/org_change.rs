@@ -0,0 +1,173 @@
+//! Organization Change Feed Models
+//!
+//! Every organization save is compared with the previous state; the structured
+//! difference is kept as an [`OrgChangeEntry`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::{Agent, Department, Organization};
+
+/// Placeholder for values that must not appear in the change feed
+const REDACTED: &str = "***";
+
+/// Fields whose values are never recorded
+const REDACTED_FIELDS: &[&str] = &["llm_config.api_key"];
+
+/// Kind of change to a single entity
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// Change to a single field, nested fields use dotted paths (e.g. `role.title`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Change to an agent or department
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EntityChange {
+    pub id: String,
+    pub kind: ChangeKind,
+    /// Field-level detail, only filled for modifications
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldChange>,
+}
+
+/// Structured difference between two organizations
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OrgDiff {
+    #[serde(default)]
+    pub agents: Vec<EntityChange>,
+    #[serde(default)]
+    pub departments: Vec<EntityChange>,
+}
+
+impl OrgDiff {
+    /// Compare two organizations; entities are matched by id, so reordering is not a change
+    pub fn between(before: &Organization, after: &Organization) -> Self {
+        Self {
+            agents: diff_entities(&before.agents, &after.agents, |a: &Agent| a.id.clone()),
+            departments: diff_entities(&before.departments, &after.departments, |d: &Department| d.id.clone()),
+        }
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.agents.is_empty() && self.departments.is_empty()
+    }
+}
+
+/// One entry of the organization change feed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OrgChangeEntry {
+    pub id: String,
+    /// Who made the change, e.g. a username or `system`
+    pub actor: String,
+    /// Change timestamp (seconds)
+    pub timestamp: i64,
+    #[serde(flatten)]
+    pub diff: OrgDiff,
+}
+
+impl OrgChangeEntry {
+    pub fn new(actor: impl Into<String>, diff: OrgDiff) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            actor: actor.into(),
+            timestamp: chrono::Utc::now().timestamp(),
+            diff,
+        }
+    }
+
+    /// Set the change timestamp
+    pub fn with_timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+}
+
+/// Entity flattened to dotted field paths, with a hash of the whole entity
+struct Fingerprint {
+    hash: [u8; 32],
+    fields: BTreeMap<String, Value>,
+}
+
+impl Fingerprint {
+    fn of<T: Serialize>(entity: &T) -> Self {
+        let value = serde_json::to_value(entity).unwrap_or(Value::Null);
+        let mut fields = BTreeMap::new();
+        flatten("", &value, &mut fields);
+        for field in REDACTED_FIELDS {
+            if let Some(value) = fields.get_mut(*field) {
+                *value = Value::String(REDACTED.to_string());
+            }
+        }
+        // BTreeMap 按键有序，序列化结果与字段顺序无关
+        let hash = Sha256::digest(serde_json::to_string(&fields).unwrap_or_default().as_bytes()).into();
+        Self { hash, fields }
+    }
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+                flatten(&path, value, out);
+            }
+        }
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn diff_entities<T: Serialize>(before: &[T], after: &[T], id_of: impl Fn(&T) -> String) -> Vec<EntityChange> {
+    let mut old: HashMap<String, Fingerprint> = before.iter().map(|e| (id_of(e), Fingerprint::of(e))).collect();
+    let mut changes = Vec::new();
+
+    for entity in after {
+        let id = id_of(entity);
+        let new = Fingerprint::of(entity);
+        match old.remove(&id) {
+            None => changes.push(EntityChange { id, kind: ChangeKind::Added, fields: Vec::new() }),
+            Some(prev) if prev.hash == new.hash => {}
+            Some(prev) => changes.push(EntityChange {
+                id,
+                kind: ChangeKind::Modified,
+                fields: diff_fields(prev.fields, new.fields),
+            }),
+        }
+    }
+
+    let mut removed: Vec<String> = old.into_keys().collect();
+    removed.sort();
+    changes.extend(removed.into_iter().map(|id| EntityChange { id, kind: ChangeKind::Removed, fields: Vec::new() }));
+    changes
+}
+
+fn diff_fields(mut before: BTreeMap<String, Value>, after: BTreeMap<String, Value>) -> Vec<FieldChange> {
+    let mut fields = Vec::new();
+    for (field, value) in after {
+        let prev = before.remove(&field).unwrap_or(Value::Null);
+        if prev != value {
+            fields.push(FieldChange { field, before: prev, after: value });
+        }
+    }
+    for (field, prev) in before {
+        fields.push(FieldChange { field, before: prev, after: Value::Null });
+    }
+    fields.sort_by(|a, b| a.field.cmp(&b.field));
+    fields
+}
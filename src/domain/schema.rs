@@ -0,0 +1,202 @@
+//! JSON Schema 构建与转换
+//!
+//! 工具（Tool）和能力（Capability）共用同一套 Schema 表示：构建器产出 `serde_json::Value`，
+//! 再经由这里的函数转换为 OpenAI function calling 和 MCP 的格式。
+
+use serde_json::{json, Value};
+
+/// JSON Schema 构建器
+///
+/// 工具参数、返回值以及能力的输入输出都用它构建
+pub struct JsonSchema;
+
+impl JsonSchema {
+    /// 创建 object 类型的参数根
+    ///
+    /// # Example
+    /// ```
+    /// use imitatort::domain::schema::JsonSchema;
+    ///
+    /// let params = JsonSchema::object()
+    ///     .property("name", JsonSchema::string().description("用户名"))
+    ///     .property("age", JsonSchema::integer().description("年龄").optional())
+    ///     .build();
+    /// ```
+    pub fn object() -> ObjectSchemaBuilder {
+        ObjectSchemaBuilder::new()
+    }
+
+    /// 创建 string 类型
+    pub fn string() -> TypeBuilder {
+        TypeBuilder::new("string")
+    }
+
+    /// 创建 integer 类型
+    pub fn integer() -> TypeBuilder {
+        TypeBuilder::new("integer")
+    }
+
+    /// 创建 number 类型
+    pub fn number() -> TypeBuilder {
+        TypeBuilder::new("number")
+    }
+
+    /// 创建 boolean 类型
+    pub fn boolean() -> TypeBuilder {
+        TypeBuilder::new("boolean")
+    }
+
+    /// 创建 array 类型
+    pub fn array(item_schema: Value) -> TypeBuilder {
+        let mut builder = TypeBuilder::new("array");
+        builder.schema["items"] = item_schema;
+        builder
+    }
+
+    /// 创建 string 数组类型（常用）
+    pub fn string_array() -> TypeBuilder {
+        Self::array(json!({"type": "string"}))
+    }
+
+    /// 创建 enum 类型
+    pub fn enum_values(values: Vec<&str>) -> TypeBuilder {
+        let mut builder = TypeBuilder::new("string");
+        builder.schema["enum"] = json!(values);
+        builder
+    }
+}
+
+
+/// Object 类型构建器
+pub struct ObjectSchemaBuilder {
+    schema: Value,
+}
+
+impl ObjectSchemaBuilder {
+    fn new() -> Self {
+        Self {
+            schema: json!({
+                "type": "object",
+                "properties": {},
+                "required": []
+            }),
+        }
+    }
+
+    /// 添加属性
+    pub fn property(mut self, name: &str, builder: TypeBuilder) -> Self {
+        // 先检查是否必填，再移动 builder
+        let is_required = builder.required;
+
+        let properties = self.schema["properties"].as_object_mut()
+            .expect("Expected 'properties' to be an object in schema");
+        properties.insert(name.to_string(), builder.build());
+
+        // 如果属性是必填的，添加到 required 数组
+        if is_required {
+            let required = self.schema["required"].as_array_mut()
+                .expect("Expected 'required' to be an array in schema");
+            required.push(json!(name));
+        }
+
+        self
+    }
+
+    /// 直接添加原始 JSON Schema 属性
+    pub fn raw_property(mut self, name: &str, schema: Value, is_required: bool) -> Self {
+        let properties = self.schema["properties"].as_object_mut()
+            .expect("Expected 'properties' to be an object in schema");
+        properties.insert(name.to_string(), schema);
+
+        if is_required {
+            let required = self.schema["required"].as_array_mut()
+                .expect("Expected 'required' to be an array in schema");
+            required.push(json!(name));
+        }
+
+        self
+    }
+
+    /// 构建最终的 JSON Schema
+    pub fn build(self) -> Value {
+        self.schema
+    }
+}
+
+/// 类型构建器
+pub struct TypeBuilder {
+    schema: Value,
+    required: bool,
+}
+
+impl TypeBuilder {
+    fn new(type_name: &str) -> Self {
+        Self {
+            schema: json!({"type": type_name}),
+            required: true,
+        }
+    }
+
+    /// 设置描述
+    pub fn description(mut self, desc: &str) -> Self {
+        self.schema["description"] = json!(desc);
+        self
+    }
+
+    /// 设置为可选（用于构建时判断）
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    /// 添加 enum 约束
+    pub fn enum_values(mut self, values: Vec<&str>) -> Self {
+        self.schema["enum"] = json!(values);
+        self
+    }
+
+    /// 构建最终的 JSON Schema
+    pub fn build(self) -> Value {
+        self.schema
+    }
+}
+
+/// Schema 中的必填字段
+pub fn required_fields(schema: &Value) -> Vec<String> {
+    schema
+        .get("required")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 缺省（null）的 Schema 视为没有参数的 object
+fn normalized(schema: &Value) -> Value {
+    if schema.is_null() {
+        JsonSchema::object().build()
+    } else {
+        schema.clone()
+    }
+}
+
+/// 转换为 OpenAI function calling 的函数定义
+pub fn openai_function(name: &str, description: &str, parameters: &Value) -> Value {
+    json!({
+        "name": name,
+        "description": description,
+        "parameters": normalized(parameters),
+    })
+}
+
+/// 转换为 MCP 工具定义（`inputSchema`）
+pub fn mcp_tool(name: &str, description: &str, input_schema: &Value) -> Value {
+    json!({
+        "name": name,
+        "description": description,
+        "inputSchema": normalized(input_schema),
+    })
+}
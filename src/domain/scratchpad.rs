@@ -0,0 +1,54 @@
+//! Agent Scratchpad Domain Model
+//!
+//! Intermediate reasoning an agent records during a run, such as tool plans and rejected drafts.
+//! Entries are shown to admins debugging a run; they are never delivered as messages, so they
+//! stay out of conversation history, other agents' context, exports and transcripts.
+
+use serde::{Deserialize, Serialize};
+
+/// How an entry was recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScratchpadSource {
+    /// Written by the agent with the `scratchpad.write` tool
+    Tool,
+    /// Captured from an LLM turn that ended in a tool call
+    ToolTurn,
+}
+
+/// One scratchpad entry of a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScratchpadEntry {
+    pub id: String,
+    /// Run (agent turn) the entry belongs to; the turn's trace ID
+    pub run_id: String,
+    pub agent_id: String,
+    pub source: ScratchpadSource,
+    pub content: String,
+    /// Creation timestamp (seconds)
+    pub created_at: i64,
+}
+
+impl ScratchpadEntry {
+    pub fn new(
+        run_id: impl Into<String>,
+        agent_id: impl Into<String>,
+        source: ScratchpadSource,
+        content: impl Into<String>,
+        created_at: i64,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            run_id: run_id.into(),
+            agent_id: agent_id.into(),
+            source,
+            content: content.into(),
+            created_at,
+        }
+    }
+
+    /// Size counted against the per-run cap (content bytes)
+    pub fn size(&self) -> usize {
+        self.content.len()
+    }
+}
@@ -0,0 +1,40 @@
+//! Share Tokens
+//!
+//! Read-only links to a single group's transcript for people without an account.
+//! Only a hash of the token is stored; the token itself is shown once at creation.
+
+use serde::{Deserialize, Serialize};
+
+/// Stored share token for a group transcript
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShareToken {
+    /// SHA-256 hex digest of the token, also used to revoke it
+    pub id: String,
+    /// Group the token can read
+    pub group_id: String,
+    /// Principal that created the token, e.g. `user:42`
+    pub created_by: String,
+    /// Creation timestamp (seconds)
+    pub created_at: i64,
+    /// Expiration timestamp (seconds)
+    pub expires_at: i64,
+    /// Only the newest N messages are visible; all messages when None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_window: Option<usize>,
+    /// Revocation timestamp (seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<i64>,
+    /// Number of times the transcript was read with the token
+    #[serde(default)]
+    pub access_count: u64,
+    /// Last read timestamp (seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_accessed_at: Option<i64>,
+}
+
+impl ShareToken {
+    /// Whether the token can still be used at `now` (seconds)
+    pub fn is_active(&self, now: i64) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+}
@@ -0,0 +1,177 @@
+//! Task Domain Entity
+//!
+//! Durable work items that agents and users create, assign and track.
+//! Assignees are agent ids or user principals (`user:{id}`).
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::MessageId;
+
+/// Task lifecycle status
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    #[default]
+    Open,
+    InProgress,
+    Blocked,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 5] = [
+        TaskStatus::Open,
+        TaskStatus::InProgress,
+        TaskStatus::Blocked,
+        TaskStatus::Done,
+        TaskStatus::Cancelled,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Open => "open",
+            TaskStatus::InProgress => "in_progress",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Done => "done",
+            TaskStatus::Cancelled => "cancelled",
+        }
+    }
+
+    /// Done and cancelled tasks are closed
+    pub fn is_closed(&self) -> bool {
+        matches!(self, TaskStatus::Done | TaskStatus::Cancelled)
+    }
+
+    /// Allowed transitions: open work can move freely, closed tasks can only be reopened
+    pub fn can_transition_to(&self, next: TaskStatus) -> bool {
+        if *self == next {
+            return false;
+        }
+        if self.is_closed() {
+            return next == TaskStatus::Open;
+        }
+        true
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TaskStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| format!("Unknown task status: {}", s))
+    }
+}
+
+/// Task Entity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Task {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Agent id or user principal that created the task
+    pub creator: String,
+    /// Agent id or user principal the task is assigned to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub status: TaskStatus,
+    /// Due timestamp (seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<i64>,
+    /// Messages linked to the task, including its comments
+    #[serde(default)]
+    pub linked_message_ids: Vec<MessageId>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// When the assignee was reminded of the approaching due date (seconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_notified_at: Option<i64>,
+}
+
+impl Task {
+    /// Create new open task
+    pub fn new(title: impl Into<String>, creator: impl Into<String>, now: i64) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            title: title.into(),
+            description: String::new(),
+            creator: creator.into(),
+            assignee: None,
+            status: TaskStatus::Open,
+            due_at: None,
+            linked_message_ids: Vec::new(),
+            created_at: now,
+            updated_at: now,
+            due_notified_at: None,
+        }
+    }
+
+    /// Set description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Set assignee
+    pub fn with_assignee(mut self, assignee: impl Into<String>) -> Self {
+        self.assignee = Some(assignee.into());
+        self
+    }
+
+    /// Set due timestamp (seconds)
+    pub fn with_due_at(mut self, due_at: i64) -> Self {
+        self.due_at = Some(due_at);
+        self
+    }
+
+    /// Whether the task is still being worked on
+    pub fn is_open(&self) -> bool {
+        !self.status.is_closed()
+    }
+
+    /// Whether the task is assigned to this agent id or user principal
+    pub fn is_assigned_to(&self, principal: &str) -> bool {
+        self.assignee.as_deref() == Some(principal)
+    }
+
+    /// Whether the participant created or is assigned the task
+    pub fn involves(&self, principal: &str) -> bool {
+        self.creator == principal || self.is_assigned_to(principal)
+    }
+
+    /// Link a message to the task
+    pub fn link_message(&mut self, message_id: impl Into<MessageId>) {
+        let message_id = message_id.into();
+        if !self.linked_message_ids.contains(&message_id) {
+            self.linked_message_ids.push(message_id);
+        }
+    }
+
+    /// One-line summary for prompts and notifications
+    pub fn summary(&self) -> String {
+        match self.due_at.and_then(|due| chrono::DateTime::from_timestamp(due, 0)) {
+            Some(due) => format!(
+                "[{}] {} ({}, due {})",
+                self.id,
+                self.title,
+                self.status,
+                due.format("%Y-%m-%d %H:%M UTC")
+            ),
+            None => format!("[{}] {} ({})", self.id, self.title, self.status),
+        }
+    }
+}
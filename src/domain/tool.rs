@@ -3,8 +3,13 @@
 //! Core business definition for tool system, supporting multi-level classification
 //! Parameters use JSON Schema format, directly compatible with OpenAI Tool Calling
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
+use crate::domain::message::TurnOutbox;
+
+pub use crate::domain::schema::{JsonSchema, ObjectSchemaBuilder, TypeBuilder};
 
 /// Tool Entity - Single source of truth
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +21,24 @@ pub struct Tool {
     /// Parameter JSON Schema definition
     pub parameters: Value,
     pub returns: ReturnType,
+    /// Maximum concurrent executions of this tool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrency: Option<u32>,
+    /// Tools in the same mutex group never run concurrently
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutex_group: Option<String>,
+    /// Sensitive tool: still offered to agents, but marked as needing human approval
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub requires_approval: bool,
+    /// Deprecation message shown in listings to steer agents to a replacement
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecated: Option<String>,
+    /// Free-form tags, e.g. for watchdog rules that select tools by tag
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Owning feature flag; the tool is hidden from agents while that feature is off
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature: Option<String>,
 }
 
 impl Tool {
@@ -34,6 +57,12 @@ impl Tool {
             category,
             parameters,
             returns: ReturnType::default(),
+            max_concurrency: None,
+            mutex_group: None,
+            requires_approval: false,
+            deprecated: None,
+            tags: Vec::new(),
+            feature: None,
         }
     }
 
@@ -43,17 +72,118 @@ impl Tool {
         self
     }
 
+    /// Limit concurrent executions of this tool
+    pub fn with_max_concurrency(mut self, max_concurrency: u32) -> Self {
+        self.max_concurrency = Some(max_concurrency);
+        self
+    }
+
+    /// Put this tool in a mutex group
+    pub fn with_mutex_group(mut self, group: impl Into<String>) -> Self {
+        self.mutex_group = Some(group.into());
+        self
+    }
+
+    /// Mark this tool as requiring human approval
+    pub fn with_requires_approval(mut self) -> Self {
+        self.requires_approval = true;
+        self
+    }
+
+    /// Mark this tool as deprecated
+    pub fn with_deprecated(mut self, message: impl Into<String>) -> Self {
+        self.deprecated = Some(message.into());
+        self
+    }
+
+    /// Set tags
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Declare the feature this tool belongs to
+    pub fn with_feature(mut self, feature: impl Into<String>) -> Self {
+        self.feature = Some(feature.into());
+        self
+    }
+
+    /// Whether this tool carries the given tag
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
     /// Get required parameter field list
     pub fn required_params(&self) -> Vec<String> {
-        self.parameters
-            .get("required")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default()
+        crate::domain::schema::required_fields(&self.parameters)
+    }
+
+    /// OpenAI function definition, named after the tool id (see [`openai_function_name`])
+    pub fn to_openai_function(&self) -> Value {
+        crate::domain::schema::openai_function(&openai_function_name(&self.id), &self.description, &self.parameters)
+    }
+
+    /// Build a tool from an OpenAI function definition with the default import options
+    pub fn from_openai_function(value: &Value) -> Result<Self, OpenAIFunctionError> {
+        Self::from_openai_function_with(value, &OpenAIFunctionImport::default())
+    }
+
+    /// Build a tool from an OpenAI function definition
+    ///
+    /// Accepts a bare function (`{"name", "description", "parameters"}`) or the
+    /// Assistants / Chat Completions wrapper (`{"type": "function", "function": {...}}`).
+    /// The parameters schema is kept as-is.
+    pub fn from_openai_function_with(value: &Value, options: &OpenAIFunctionImport) -> Result<Self, OpenAIFunctionError> {
+        let function = match value.get("function") {
+            Some(function) if value.get("type").and_then(Value::as_str) == Some("function") => function,
+            _ => value,
+        };
+        let Some(object) = function.as_object() else {
+            return Err(OpenAIFunctionError::new(UNNAMED_FUNCTION, "definition must be a JSON object"));
+        };
+        let name = match object.get("name") {
+            Some(Value::String(name)) if !name.is_empty() => name.as_str(),
+            Some(Value::String(_)) | None => {
+                return Err(OpenAIFunctionError::new(UNNAMED_FUNCTION, "missing function name"));
+            }
+            Some(_) => return Err(OpenAIFunctionError::new(UNNAMED_FUNCTION, "function name must be a string")),
+        };
+        if name.len() > 64 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(OpenAIFunctionError::new(name, "name must be 1-64 characters of a-z, A-Z, 0-9, _ or -"));
+        }
+
+        let id = tool_id_from_openai_name(name);
+        let segments: Vec<&str> = id.split('.').collect();
+        if segments.iter().any(|s| s.is_empty()) {
+            return Err(OpenAIFunctionError::new(name, "namespace separator `__` must join non-empty segments"));
+        }
+
+        let description = match object.get("description") {
+            Some(Value::String(description)) => description.clone(),
+            None | Some(Value::Null) => String::new(),
+            Some(_) => return Err(OpenAIFunctionError::new(name, "description must be a string")),
+        };
+
+        let parameters = match object.get("parameters") {
+            None | Some(Value::Null) => json!({"type": "object", "properties": {}}),
+            Some(parameters) => {
+                validate_parameters(parameters).map_err(|reason| OpenAIFunctionError::new(name, reason))?;
+                parameters.clone()
+            }
+        };
+
+        let category = match options.categories.get(name) {
+            Some(path) => CategoryPath::from_str(path),
+            None if segments.len() > 1 => CategoryPath::new(segments[..segments.len() - 1].iter().map(|s| s.to_string()).collect()),
+            None => CategoryPath::from_str(options.default_category.as_deref().unwrap_or(DEFAULT_IMPORT_CATEGORY)),
+        };
+
+        Ok(Self::new(id, name, description, category, parameters))
+    }
+
+    /// MCP tool definition
+    pub fn to_mcp_tool(&self) -> Value {
+        crate::domain::schema::mcp_tool(&self.id, &self.description, &self.parameters)
     }
 
     /// 获取参数属性定义
@@ -62,6 +192,99 @@ impl Tool {
     }
 }
 
+/// Function name used for a tool id: OpenAI only accepts `[a-zA-Z0-9_-]`,
+/// so ids like `tool.search` swap dots for `__`
+pub fn openai_function_name(tool_id: &str) -> String {
+    tool_id.replace('.', "__")
+}
+
+/// Tool id for an OpenAI function name, the inverse of [`openai_function_name`]
+pub fn tool_id_from_openai_name(name: &str) -> String {
+    name.replace("__", ".")
+}
+
+/// Category for imported functions with neither an override nor a namespaced name
+pub const DEFAULT_IMPORT_CATEGORY: &str = "imported";
+
+pub(crate) const UNNAMED_FUNCTION: &str = "<unnamed>";
+
+/// Options for importing OpenAI function definitions
+///
+/// Functions named `crm__contacts__create` become tool `crm.contacts.create` in
+/// category `crm/contacts`; `categories` overrides that per function name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenAIFunctionImport {
+    /// Function name -> category path, e.g. `"get_weather": "data/weather"`
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub categories: HashMap<String, String>,
+    /// Category for un-namespaced functions without an override
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_category: Option<String>,
+}
+
+impl OpenAIFunctionImport {
+    /// Assign a category to one function
+    pub fn with_category(mut self, function: impl Into<String>, path: impl Into<String>) -> Self {
+        self.categories.insert(function.into(), path.into());
+        self
+    }
+
+    /// Category for un-namespaced functions
+    pub fn with_default_category(mut self, path: impl Into<String>) -> Self {
+        self.default_category = Some(path.into());
+        self
+    }
+}
+
+/// An OpenAI function definition that could not be imported
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid OpenAI function {function}: {reason}")]
+pub struct OpenAIFunctionError {
+    /// Function name, or its position in a bulk import when it has none
+    pub function: String,
+    pub reason: String,
+}
+
+impl OpenAIFunctionError {
+    fn new(function: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            function: function.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Parameters must be an object schema whose required fields are declared
+fn validate_parameters(parameters: &Value) -> Result<(), String> {
+    let Some(schema) = parameters.as_object() else {
+        return Err("parameters must be a JSON Schema object".to_string());
+    };
+    match schema.get("type") {
+        Some(Value::String(t)) if t == "object" => {}
+        _ => return Err("parameters schema must have type \"object\"".to_string()),
+    }
+    let properties = match schema.get("properties") {
+        None => None,
+        Some(Value::Object(properties)) => Some(properties),
+        Some(_) => return Err("parameters.properties must be an object".to_string()),
+    };
+    match schema.get("required") {
+        None => {}
+        Some(Value::Array(required)) => {
+            for field in required {
+                let Some(field) = field.as_str() else {
+                    return Err("parameters.required must list property names".to_string());
+                };
+                if !properties.is_some_and(|p| p.contains_key(field)) {
+                    return Err(format!("required parameter `{}` is not defined in properties", field));
+                }
+            }
+        }
+        Some(_) => return Err("parameters.required must be an array".to_string()),
+    }
+    Ok(())
+}
+
 /// 分类路径 - 支持多级如 ["file", "read"]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct CategoryPath(Vec<String>);
@@ -134,161 +357,6 @@ impl Default for CategoryPath {
     }
 }
 
-/// JSON Schema 参数构建器
-///
-/// 用于方便地构建 OpenAI 兼容的 JSON Schema 参数定义
-pub struct JsonSchema;
-
-impl JsonSchema {
-    /// 创建 object 类型的参数根
-    ///
-    /// # Example
-    /// ```
-    /// let params = JsonSchema::object()
-    ///     .property("name", JsonSchema::string().description("用户名"))
-    ///     .property("age", JsonSchema::integer().description("年龄").optional())
-    ///     .build();
-    /// ```
-    pub fn object() -> ObjectSchemaBuilder {
-        ObjectSchemaBuilder::new()
-    }
-
-    /// 创建 string 类型
-    pub fn string() -> TypeBuilder {
-        TypeBuilder::new("string")
-    }
-
-    /// 创建 integer 类型
-    pub fn integer() -> TypeBuilder {
-        TypeBuilder::new("integer")
-    }
-
-    /// 创建 number 类型
-    pub fn number() -> TypeBuilder {
-        TypeBuilder::new("number")
-    }
-
-    /// 创建 boolean 类型
-    pub fn boolean() -> TypeBuilder {
-        TypeBuilder::new("boolean")
-    }
-
-    /// 创建 array 类型
-    pub fn array(item_schema: Value) -> TypeBuilder {
-        let mut builder = TypeBuilder::new("array");
-        builder.schema["items"] = item_schema;
-        builder
-    }
-
-    /// 创建 string 数组类型（常用）
-    pub fn string_array() -> TypeBuilder {
-        Self::array(json!({"type": "string"}))
-    }
-
-    /// 创建 enum 类型
-    pub fn enum_values(values: Vec<&str>) -> TypeBuilder {
-        let mut builder = TypeBuilder::new("string");
-        builder.schema["enum"] = json!(values);
-        builder
-    }
-}
-
-use serde_json::json;
-
-/// Object 类型构建器
-pub struct ObjectSchemaBuilder {
-    schema: Value,
-}
-
-impl ObjectSchemaBuilder {
-    fn new() -> Self {
-        Self {
-            schema: json!({
-                "type": "object",
-                "properties": {},
-                "required": []
-            }),
-        }
-    }
-
-    /// 添加属性
-    pub fn property(mut self, name: &str, builder: TypeBuilder) -> Self {
-        // 先检查是否必填，再移动 builder
-        let is_required = builder.required;
-
-        let properties = self.schema["properties"].as_object_mut()
-            .expect("Expected 'properties' to be an object in schema");
-        properties.insert(name.to_string(), builder.build());
-
-        // 如果属性是必填的，添加到 required 数组
-        if is_required {
-            let required = self.schema["required"].as_array_mut()
-                .expect("Expected 'required' to be an array in schema");
-            required.push(json!(name));
-        }
-
-        self
-    }
-
-    /// 直接添加原始 JSON Schema 属性
-    pub fn raw_property(mut self, name: &str, schema: Value, is_required: bool) -> Self {
-        let properties = self.schema["properties"].as_object_mut()
-            .expect("Expected 'properties' to be an object in schema");
-        properties.insert(name.to_string(), schema);
-
-        if is_required {
-            let required = self.schema["required"].as_array_mut()
-                .expect("Expected 'required' to be an array in schema");
-            required.push(json!(name));
-        }
-
-        self
-    }
-
-    /// 构建最终的 JSON Schema
-    pub fn build(self) -> Value {
-        self.schema
-    }
-}
-
-/// 类型构建器
-pub struct TypeBuilder {
-    schema: Value,
-    required: bool,
-}
-
-impl TypeBuilder {
-    fn new(type_name: &str) -> Self {
-        Self {
-            schema: json!({"type": type_name}),
-            required: true,
-        }
-    }
-
-    /// 设置描述
-    pub fn description(mut self, desc: &str) -> Self {
-        self.schema["description"] = json!(desc);
-        self
-    }
-
-    /// 设置为可选（用于构建时判断）
-    pub fn optional(mut self) -> Self {
-        self.required = false;
-        self
-    }
-
-    /// 添加 enum 约束
-    pub fn enum_values(mut self, values: Vec<&str>) -> Self {
-        self.schema["enum"] = json!(values);
-        self
-    }
-
-    /// 构建最终的 JSON Schema
-    pub fn build(self) -> Value {
-        self.schema
-    }
-}
-
 /// 返回值类型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReturnType {
@@ -368,6 +436,16 @@ impl CategoryNodeInfo {
     }
 }
 
+/// 生成新的追踪ID
+pub fn new_trace_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+/// 生成新的 span ID（16 位十六进制）
+pub fn new_span_id() -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
 /// Tool 调用上下文
 ///
 /// 包含单次工具调用时的上下文信息
@@ -379,6 +457,12 @@ pub struct ToolCallContext {
     pub timestamp: i64,
     /// 会话ID（如果有）
     pub session_id: Option<String>,
+    /// 追踪ID，贯穿 Web 请求 → Agent 轮次 → 工具调用 → 产生的消息
+    pub trace_id: String,
+    /// 上级 span ID（如发起调用的 Agent 轮次）
+    pub parent_span_id: Option<String>,
+    /// 本轮的发件箱；设置后 `message.send_*` 工具只入队，轮次结束时统一发送
+    pub outbox: Option<TurnOutbox>,
 }
 
 impl ToolCallContext {
@@ -388,12 +472,123 @@ impl ToolCallContext {
             caller_id: caller_id.into(),
             timestamp: chrono::Utc::now().timestamp(),
             session_id: None,
+            trace_id: new_trace_id(),
+            parent_span_id: None,
+            outbox: None,
         }
     }
 
+    /// 沿用上游的追踪ID
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = trace_id.into();
+        self
+    }
+
+    /// 设置上级 span ID
+    pub fn with_parent_span_id(mut self, parent_span_id: impl Into<String>) -> Self {
+        self.parent_span_id = Some(parent_span_id.into());
+        self
+    }
+
+    /// 使用本轮的发件箱
+    pub fn with_outbox(mut self, outbox: TurnOutbox) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
+
     /// 设置会话ID
     pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
         self.session_id = Some(session_id.into());
         self
     }
 }
+
+/// 工具执行进度
+///
+/// 流式工具在最终结果之前发出的中间事件，三项都可省略
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolProgress {
+    /// 完成百分比（0-100）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f32>,
+    /// 进度说明
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// 已产出的部分结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_data: Option<Value>,
+}
+
+impl ToolProgress {
+    /// 只带说明的进度
+    pub fn message(message: impl Into<String>) -> Self {
+        Self { message: Some(message.into()), ..Self::default() }
+    }
+
+    /// 设置完成百分比，超出 0-100 时截断
+    pub fn with_percent(mut self, percent: f32) -> Self {
+        self.percent = Some(percent.clamp(0.0, 100.0));
+        self
+    }
+
+    /// 设置部分结果
+    pub fn with_partial_data(mut self, partial_data: Value) -> Self {
+        self.partial_data = Some(partial_data);
+        self
+    }
+}
+
+/// 一次工具调用的进度事件，转发给订阅的 UI 客户端
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolProgressEvent {
+    /// 工具ID
+    pub tool_id: String,
+    /// 调用者 Agent ID
+    pub caller_id: String,
+    /// 调用的追踪ID
+    pub trace_id: String,
+    /// 本次调用内的序号，从 1 开始
+    pub sequence: u64,
+    /// 距调用开始的毫秒数
+    pub elapsed_ms: u64,
+    /// 进度内容
+    #[serde(flatten)]
+    pub progress: ToolProgress,
+}
+
+/// 工具使用统计
+///
+/// 单个工具在某一时间段内的调用汇总
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUsage {
+    /// 工具ID
+    pub tool_id: String,
+    /// 调用次数
+    pub calls: u64,
+    /// 成功次数
+    pub successes: u64,
+    /// 失败次数
+    pub errors: u64,
+    /// 累计耗时（毫秒）
+    pub total_latency_ms: u64,
+}
+
+impl ToolUsage {
+    /// 平均耗时（毫秒）
+    pub fn avg_latency_ms(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.total_latency_ms as f64 / self.calls as f64
+        }
+    }
+
+    /// 成功率
+    pub fn success_rate(&self) -> f64 {
+        if self.calls == 0 {
+            0.0
+        } else {
+            self.successes as f64 / self.calls as f64
+        }
+    }
+}
@@ -2,6 +2,19 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Prefix of message-bus principal ids that belong to human users
+pub const USER_PRINCIPAL_PREFIX: &str = "user:";
+
+/// Message-bus principal id for a user (`user:{id}`)
+pub fn user_principal(user_id: &str) -> String {
+    format!("{}{}", USER_PRINCIPAL_PREFIX, user_id)
+}
+
+/// Whether a message participant id is a user principal rather than an Agent
+pub fn is_user_principal(id: &str) -> bool {
+    id.starts_with(USER_PRINCIPAL_PREFIX)
+}
+
 /// User Position Enum
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum Position {
@@ -31,6 +44,9 @@ pub struct User {
     pub position: Position,   // Position
     pub department: String,   // Department
     pub created_at: i64,
+    /// Language code messages are translated into for this user, e.g. `en`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preferred_language: Option<String>,
 }
 
 impl User {
@@ -45,6 +61,7 @@ impl User {
             position: Position::Chairman,
             department: "Corporate Office".to_string(),
             created_at: chrono::Utc::now().timestamp(),
+            preferred_language: None,
         }
     }
 
@@ -59,6 +76,7 @@ impl User {
             position: Position::Management,
             department: "General Management Department".to_string(),
             created_at: chrono::Utc::now().timestamp(),
+            preferred_language: None,
         }
     }
 
@@ -73,6 +91,30 @@ impl User {
             position: Position::Employee,
             department,
             created_at: chrono::Utc::now().timestamp(),
+            preferred_language: None,
         }
     }
-}
\ No newline at end of file
+
+    /// Set the preferred reading language
+    pub fn with_preferred_language(mut self, language: impl Into<String>) -> Self {
+        self.preferred_language = Some(language.into());
+        self
+    }
+
+    /// Principal id used to address this user on the message bus
+    pub fn principal_id(&self) -> String {
+        user_principal(&self.id)
+    }
+}
+/// Consecutive failed login attempts for a throttle key (username or source IP)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LoginFailures {
+    /// Throttle key, e.g. `user:alice` or `ip:10.0.0.1`
+    pub key: String,
+    /// Consecutive failures
+    pub failures: u32,
+    /// Timestamp of the last failure (ms)
+    pub last_failure_ms: i64,
+    /// Locked until this timestamp (ms)
+    pub locked_until_ms: Option<i64>,
+}
@@ -1,11 +1,29 @@
 //! 认证和授权模块
 //!
-//! 提供JWT令牌和密码哈希服务
+//! 提供JWT令牌、密码哈希、密码策略、登录限流和权限模型
+
+mod password_policy;
+mod permissions;
+mod throttle;
+
+pub use password_policy::{PasswordPolicy, PasswordViolation};
+pub use permissions::{Grant, Permission, PermissionConfig, PermissionSet};
+pub use throttle::{ip_key, user_key, LoginThrottle, LoginThrottleConfig};
+
+use std::sync::OnceLock;
 
 use anyhow::Result;
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 
+/// 群聊分享令牌前缀，见 `web::share`
+pub const SHARE_TOKEN_PREFIX: &str = "shr_";
+
+/// 是否为群聊分享令牌；分享令牌是独立的凭证类型，不能当作用户令牌使用
+pub fn is_share_token(token: &str) -> bool {
+    token.starts_with(SHARE_TOKEN_PREFIX)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub id: String,
@@ -17,6 +35,9 @@ pub struct Claims {
     pub position: String,
     pub department: String,
     pub exp: usize,
+    /// 签发时的权限摘要（[`PermissionSet::digest`]），旧令牌没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions_hash: Option<String>,
 }
 
 #[derive(Clone)]
@@ -26,7 +47,7 @@ pub struct JwtService {
     algorithm: Algorithm,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInfo {
     pub id: String,
     pub username: String,
@@ -38,6 +59,14 @@ pub struct UserInfo {
     pub department: String,
 }
 
+impl std::fmt::Debug for JwtService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtService")
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
 impl JwtService {
     pub fn new(secret: &str) -> Self {
         let secret = secret.as_bytes();
@@ -49,6 +78,15 @@ impl JwtService {
     }
 
     pub fn generate_token(&self, user_info: &UserInfo) -> Result<String> {
+        self.sign(user_info, None)
+    }
+
+    /// 签发记录了权限摘要的令牌，权限变化后可以发现令牌已过时
+    pub fn generate_token_with_permissions(&self, user_info: &UserInfo, permissions_hash: &str) -> Result<String> {
+        self.sign(user_info, Some(permissions_hash.to_string()))
+    }
+
+    fn sign(&self, user_info: &UserInfo, permissions_hash: Option<String>) -> Result<String> {
         let expiration = (std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)?
             .as_secs()
@@ -64,6 +102,7 @@ impl JwtService {
             position: user_info.position.clone(),
             department: user_info.department.clone(),
             exp: expiration,
+            permissions_hash,
         };
 
         let token = encode(&Header::default(), &claims, &self.encoding_key)?;
@@ -71,13 +110,25 @@ impl JwtService {
     }
 
     pub fn validate_token(&self, token: &str) -> Result<UserInfo> {
+        self.decode_claims(token).map(UserInfo::from)
+    }
+
+    /// 校验令牌并返回全部声明
+    pub fn decode_claims(&self, token: &str) -> Result<Claims> {
+        if is_share_token(token) {
+            anyhow::bail!("Share tokens cannot be used as user credentials");
+        }
         let mut validation = Validation::new(self.algorithm);
         validation.validate_exp = true;
 
         let token_data = decode::<Claims>(token, &self.decoding_key, &validation)?;
-        let claims = token_data.claims;
+        Ok(token_data.claims)
+    }
+}
 
-        Ok(UserInfo {
+impl From<Claims> for UserInfo {
+    fn from(claims: Claims) -> Self {
+        Self {
             id: claims.id,
             username: claims.username,
             name: claims.name,
@@ -86,7 +137,7 @@ impl JwtService {
             employee_id: claims.employee_id,
             position: claims.position,
             department: claims.department,
-        })
+        }
     }
 }
 
@@ -102,4 +153,13 @@ impl PasswordService {
         let valid = bcrypt::verify(password, hash)?;
         Ok(valid)
     }
-}
\ No newline at end of file
+
+    /// 对不存在的用户执行一次等价的哈希校验，使响应时间不暴露用户名是否存在
+    pub fn verify_dummy(password: &str) {
+        static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+        let hash = DUMMY_HASH.get_or_init(|| {
+            bcrypt::hash(uuid::Uuid::new_v4().to_string(), bcrypt::DEFAULT_COST).unwrap_or_default()
+        });
+        let _ = bcrypt::verify(password, hash);
+    }
+}
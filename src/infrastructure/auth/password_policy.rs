@@ -0,0 +1,104 @@
+//! 密码策略
+//!
+//! 注册和修改密码时校验长度、字符类别以及常见弱密码。
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::i18n::MessageCatalog;
+
+/// 内置的常见弱密码
+const COMMON_PASSWORDS: &[&str] = &[
+    "password", "password1", "password123", "123456", "12345678", "123456789", "1234567890",
+    "qwerty", "qwerty123", "abc123", "111111", "000000", "iloveyou", "admin", "admin123",
+    "letmein", "welcome", "monkey", "dragon", "football", "passw0rd",
+];
+
+/// 密码策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PasswordPolicy {
+    /// 最小长度（字符数）
+    pub min_length: usize,
+    /// 需要大写字母
+    pub require_uppercase: bool,
+    /// 需要小写字母
+    pub require_lowercase: bool,
+    /// 需要数字
+    pub require_digit: bool,
+    /// 需要特殊字符
+    pub require_symbol: bool,
+    /// 额外禁止的密码（不区分大小写），内置常见弱密码总是禁止
+    pub deny_list: Vec<String>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: true,
+            require_symbol: false,
+            deny_list: Vec::new(),
+        }
+    }
+}
+
+/// 违反的规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordViolation {
+    TooShort { min_length: usize },
+    MissingUppercase,
+    MissingLowercase,
+    MissingDigit,
+    MissingSymbol,
+    TooCommon,
+}
+
+impl PasswordViolation {
+    /// 使用消息目录渲染错误信息
+    pub fn message(&self, catalog: &MessageCatalog) -> String {
+        match self {
+            PasswordViolation::TooShort { min_length } => {
+                catalog.format("password.too_short", &[("min", &min_length.to_string())])
+            }
+            PasswordViolation::MissingUppercase => catalog.get("password.missing_uppercase"),
+            PasswordViolation::MissingLowercase => catalog.get("password.missing_lowercase"),
+            PasswordViolation::MissingDigit => catalog.get("password.missing_digit"),
+            PasswordViolation::MissingSymbol => catalog.get("password.missing_symbol"),
+            PasswordViolation::TooCommon => catalog.get("password.too_common"),
+        }
+    }
+}
+
+impl PasswordPolicy {
+    /// 校验密码，返回所有违反的规则
+    pub fn check(&self, password: &str) -> Vec<PasswordViolation> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(PasswordViolation::TooShort { min_length: self.min_length });
+        }
+        if self.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            violations.push(PasswordViolation::MissingUppercase);
+        }
+        if self.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            violations.push(PasswordViolation::MissingLowercase);
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push(PasswordViolation::MissingDigit);
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push(PasswordViolation::MissingSymbol);
+        }
+
+        let lower = password.to_lowercase();
+        let denied = COMMON_PASSWORDS.contains(&lower.as_str())
+            || self.deny_list.iter().any(|p| p.to_lowercase() == lower);
+        if denied {
+            violations.push(PasswordViolation::TooCommon);
+        }
+
+        violations
+    }
+}
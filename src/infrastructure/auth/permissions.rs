@@ -0,0 +1,272 @@
+//! 权限模型
+//!
+//! 管理接口不再只区分“董事长/管理层”和其他人，而是检查具体的 [`Permission`]。
+//! 用户的权限由三部分合并而成：
+//!
+//! - 职位的授权（`positions`，未配置的职位使用内置默认：董事长拥有全部权限，
+//!   管理层拥有除故障注入和提示词日志外的全部权限，普通员工没有管理权限）
+//! - 部门负责人的授权（`leaders`），只作用于其负责的部门及下级部门
+//! - 单个用户的额外授权（`users`，按用户名）
+//!
+//! 授权可以限定到部门：`{ permission: manage_agents, department: eng }` 只允许管理
+//! `eng` 及其下级部门的 Agent。令牌中记录签发时的权限摘要，权限变化后旧令牌需要重新登录。
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::domain::Organization;
+
+use super::UserInfo;
+
+/// 权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// 修改 Agent 的角色、工作时间和偏好
+    ManageAgents,
+    /// 查看 Agent 的上下文和角色历史
+    InspectAgents,
+    /// 管理部门
+    ManageDepartments,
+    /// 查看用户列表、解除登录锁定
+    ManageUsers,
+    /// 管理邀请码
+    ManageInviteCodes,
+    /// 查看组织变更记录和活动摘要
+    ViewAudit,
+    /// 以管理员身份调用工具
+    ExecuteTools,
+    /// 管理 Watchdog 规则
+    ManageWatchdog,
+    /// 编辑和删除与自己无关的任务
+    ManageTasks,
+    /// 管理不是自己担任管理员的群聊（分享链接等）
+    ManageGroups,
+    /// 删除他人上传的附件
+    ManageAttachments,
+    /// 存储完整性检查与修复、消息分层统计
+    ManageStorage,
+    /// 调整故障注入规则
+    ManageChaos,
+    /// 调整提示词日志的脱敏级别
+    ManagePromptLog,
+    /// 运行中切换功能开关
+    ManageFeatures,
+}
+
+impl Permission {
+    /// 全部权限
+    pub const ALL: &'static [Permission] = &[
+        Permission::ManageAgents,
+        Permission::InspectAgents,
+        Permission::ManageDepartments,
+        Permission::ManageUsers,
+        Permission::ManageInviteCodes,
+        Permission::ViewAudit,
+        Permission::ExecuteTools,
+        Permission::ManageWatchdog,
+        Permission::ManageTasks,
+        Permission::ManageGroups,
+        Permission::ManageAttachments,
+        Permission::ManageStorage,
+        Permission::ManageChaos,
+        Permission::ManagePromptLog,
+        Permission::ManageFeatures,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ManageAgents => "manage_agents",
+            Permission::InspectAgents => "inspect_agents",
+            Permission::ManageDepartments => "manage_departments",
+            Permission::ManageUsers => "manage_users",
+            Permission::ManageInviteCodes => "manage_invite_codes",
+            Permission::ViewAudit => "view_audit",
+            Permission::ExecuteTools => "execute_tools",
+            Permission::ManageWatchdog => "manage_watchdog",
+            Permission::ManageTasks => "manage_tasks",
+            Permission::ManageGroups => "manage_groups",
+            Permission::ManageAttachments => "manage_attachments",
+            Permission::ManageStorage => "manage_storage",
+            Permission::ManageChaos => "manage_chaos",
+            Permission::ManagePromptLog => "manage_prompt_log",
+            Permission::ManageFeatures => "manage_features",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 一项授权，`department` 为空时不限部门
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Grant {
+    pub permission: Permission,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub department: Option<String>,
+}
+
+impl Grant {
+    /// 不限部门的授权
+    pub fn global(permission: Permission) -> Self {
+        Self {
+            permission,
+            department: None,
+        }
+    }
+
+    /// 限定到部门的授权
+    pub fn scoped(permission: Permission, department: impl Into<String>) -> Self {
+        Self {
+            permission,
+            department: Some(department.into()),
+        }
+    }
+}
+
+/// 权限配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PermissionConfig {
+    /// 职位名（`Chairman`、`Management`、`Employee`）到授权，覆盖该职位的默认授权
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub positions: HashMap<String, Vec<Grant>>,
+    /// 部门负责人在其负责的部门内拥有的权限
+    pub leaders: Vec<Permission>,
+    /// 用户名到额外授权
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub users: HashMap<String, Vec<Grant>>,
+}
+
+impl Default for PermissionConfig {
+    fn default() -> Self {
+        Self {
+            positions: HashMap::new(),
+            leaders: vec![Permission::ManageAgents, Permission::InspectAgents],
+            users: HashMap::new(),
+        }
+    }
+}
+
+impl PermissionConfig {
+    /// 设置职位的授权
+    pub fn with_position(mut self, position: impl Into<String>, grants: Vec<Grant>) -> Self {
+        self.positions.insert(position.into(), grants);
+        self
+    }
+
+    /// 设置部门负责人的权限
+    pub fn with_leaders(mut self, permissions: Vec<Permission>) -> Self {
+        self.leaders = permissions;
+        self
+    }
+
+    /// 为用户追加授权
+    pub fn with_user(mut self, username: impl Into<String>, grants: Vec<Grant>) -> Self {
+        self.users.entry(username.into()).or_default().extend(grants);
+        self
+    }
+
+    /// 职位的授权，未配置时使用默认
+    pub fn position_grants(&self, position: &str) -> Vec<Grant> {
+        if let Some(grants) = self.positions.get(position) {
+            return grants.clone();
+        }
+        let permissions: Vec<Permission> = match position {
+            "Chairman" => Permission::ALL.to_vec(),
+            "Management" => Permission::ALL
+                .iter()
+                .copied()
+                .filter(|p| !matches!(p, Permission::ManageChaos | Permission::ManagePromptLog))
+                .collect(),
+            _ => Vec::new(),
+        };
+        permissions.into_iter().map(Grant::global).collect()
+    }
+
+    /// 用户当前的权限；部门负责人和部门层级取自 `org`
+    pub fn resolve(&self, user: &UserInfo, org: &Organization) -> PermissionSet {
+        let mut grants: BTreeSet<Grant> = self.position_grants(&user.position).into_iter().collect();
+        for department in org.departments.iter().filter(|d| d.leader_id.as_deref() == Some(user.id.as_str())) {
+            grants.extend(self.leaders.iter().map(|p| Grant::scoped(*p, department.id.clone())));
+        }
+        if let Some(extra) = self.users.get(&user.username) {
+            grants.extend(extra.iter().cloned());
+        }
+        // 有不限部门的授权时，同一权限的部门授权是多余的
+        let global: BTreeSet<Permission> = grants.iter().filter(|g| g.department.is_none()).map(|g| g.permission).collect();
+        grants.retain(|g| g.department.is_none() || !global.contains(&g.permission));
+
+        PermissionSet {
+            grants,
+            parents: org.departments.iter().map(|d| (d.id.clone(), d.parent_id.clone())).collect(),
+        }
+    }
+}
+
+/// 解析后的用户权限
+#[derive(Debug, Clone, Default)]
+pub struct PermissionSet {
+    grants: BTreeSet<Grant>,
+    /// 部门到上级部门，用于判断部门授权是否覆盖下级部门
+    parents: HashMap<String, Option<String>>,
+}
+
+impl PermissionSet {
+    /// 所有授权，按权限排序
+    pub fn grants(&self) -> impl Iterator<Item = &Grant> {
+        self.grants.iter()
+    }
+
+    /// 是否在某处拥有该权限（不限部门或至少一个部门）
+    pub fn has(&self, permission: Permission) -> bool {
+        self.grants.iter().any(|g| g.permission == permission)
+    }
+
+    /// 是否拥有不限部门的该权限
+    pub fn has_global(&self, permission: Permission) -> bool {
+        self.grants.contains(&Grant::global(permission))
+    }
+
+    /// 能否对 `department` 中的对象行使该权限；对象不属于任何部门时只有不限部门的授权有效
+    pub fn allows(&self, permission: Permission, department: Option<&str>) -> bool {
+        if self.has_global(permission) {
+            return true;
+        }
+        let mut current = department.map(str::to_string);
+        // 沿上级部门查找，层级有环时最多走部门数那么多步
+        for _ in 0..=self.parents.len() {
+            let Some(department) = current else {
+                return false;
+            };
+            if self.grants.contains(&Grant::scoped(permission, department.clone())) {
+                return true;
+            }
+            current = self.parents.get(&department).cloned().flatten();
+        }
+        false
+    }
+
+    /// 拥有的权限（不论是否限定部门）
+    pub fn permissions(&self) -> BTreeSet<Permission> {
+        self.grants.iter().map(|g| g.permission).collect()
+    }
+
+    /// 授权的摘要，写入令牌以便发现权限变化
+    pub fn digest(&self) -> String {
+        let mut hasher = Sha256::new();
+        for grant in &self.grants {
+            hasher.update(grant.permission.as_str());
+            hasher.update([0]);
+            hasher.update(grant.department.as_deref().unwrap_or_default());
+            hasher.update([0]);
+        }
+        hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect()
+    }
+}
@@ -0,0 +1,203 @@
+//! 登录限流
+//!
+//! 按用户名和来源 IP 分别统计连续失败次数：超过阈值后按指数退避拒绝登录，
+//! 达到上限后临时锁定。计数缓存在内存中，并写入 Store 以便重启后恢复。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::store::Store;
+use crate::domain::user::LoginFailures;
+
+/// 限流配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LoginThrottleConfig {
+    /// 连续失败多少次后开始退避
+    pub delay_after: u32,
+    /// 连续失败多少次后锁定
+    pub lockout_after: u32,
+    /// 首次退避时长（毫秒），之后每次翻倍
+    pub base_delay_ms: u64,
+    /// 最长退避时长（毫秒）
+    pub max_delay_ms: u64,
+    /// 锁定时长（秒）
+    pub lockout_secs: u64,
+}
+
+impl Default for LoginThrottleConfig {
+    fn default() -> Self {
+        Self {
+            delay_after: 3,
+            lockout_after: 10,
+            base_delay_ms: 1000,
+            max_delay_ms: 60_000,
+            lockout_secs: 15 * 60,
+        }
+    }
+}
+
+/// 用户名的限流键
+pub fn user_key(username: &str) -> String {
+    format!("user:{}", username.to_lowercase())
+}
+
+/// 来源 IP 的限流键
+pub fn ip_key(ip: &str) -> String {
+    format!("ip:{}", ip)
+}
+
+/// 登录限流器
+pub struct LoginThrottle {
+    config: LoginThrottleConfig,
+    entries: DashMap<String, LoginFailures>,
+    store: Option<Arc<dyn Store>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LoginThrottle {
+    /// 创建仅在内存中计数的限流器
+    pub fn new(config: LoginThrottleConfig) -> Self {
+        Self {
+            config,
+            entries: DashMap::new(),
+            store: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// 使用存储持久化计数
+    pub fn with_store(mut self, store: Arc<dyn Store>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 检查是否允许登录，被限制时返回需要等待的时长
+    pub async fn check(&self, keys: &[String]) -> Option<Duration> {
+        let now = self.clock.now_millis();
+        let mut wait_ms = 0i64;
+
+        for key in keys {
+            if let Some(entry) = self.load(key).await {
+                wait_ms = wait_ms.max(self.blocked_until(&entry) - now);
+            }
+        }
+
+        (wait_ms > 0).then(|| Duration::from_millis(wait_ms as u64))
+    }
+
+    /// 记录一次失败，返回本次被锁定的键
+    pub async fn record_failure(&self, keys: &[String]) -> Vec<String> {
+        let now = self.clock.now_millis();
+        let mut locked = Vec::new();
+
+        for key in keys {
+            let mut entry = self.load(key).await.unwrap_or_else(|| LoginFailures {
+                key: key.clone(),
+                failures: 0,
+                last_failure_ms: now,
+                locked_until_ms: None,
+            });
+
+            // 锁定到期后重新计数
+            if entry.locked_until_ms.is_some_and(|until| until <= now) {
+                entry.failures = 0;
+                entry.locked_until_ms = None;
+            }
+
+            entry.failures += 1;
+            entry.last_failure_ms = now;
+            if entry.failures >= self.config.lockout_after && entry.locked_until_ms.is_none() {
+                entry.locked_until_ms = Some(now + self.config.lockout_secs as i64 * 1000);
+                locked.push(key.clone());
+                warn!(target: "audit", "Login locked for {} after {} consecutive failures", key, entry.failures);
+            }
+
+            self.save(entry).await;
+        }
+
+        locked
+    }
+
+    /// 登录成功后清除计数
+    pub async fn record_success(&self, key: &str) {
+        self.clear(key).await;
+    }
+
+    /// 管理员解锁
+    pub async fn unlock(&self, key: &str) {
+        self.clear(key).await;
+        info!(target: "audit", "Login unlocked for {}", key);
+    }
+
+    /// 当前计数（用于查询和测试）
+    pub async fn failures(&self, key: &str) -> Option<LoginFailures> {
+        self.load(key).await
+    }
+
+    /// 计数对应的解禁时间（毫秒时间戳）
+    fn blocked_until(&self, entry: &LoginFailures) -> i64 {
+        if let Some(until) = entry.locked_until_ms {
+            return until;
+        }
+        if entry.failures < self.config.delay_after {
+            return 0;
+        }
+
+        let exponent = (entry.failures - self.config.delay_after).min(32);
+        let delay = self
+            .config
+            .base_delay_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.config.max_delay_ms);
+        entry.last_failure_ms + delay as i64
+    }
+
+    async fn load(&self, key: &str) -> Option<LoginFailures> {
+        if let Some(entry) = self.entries.get(key) {
+            return Some(entry.clone());
+        }
+
+        let store = self.store.as_ref()?;
+        match store.load_login_failures(key).await {
+            Ok(Some(entry)) => {
+                self.entries.insert(key.to_string(), entry.clone());
+                Some(entry)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                warn!("Failed to load login failures for {}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    async fn save(&self, entry: LoginFailures) {
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_login_failures(&entry).await {
+                warn!("Failed to persist login failures for {}: {}", entry.key, e);
+            }
+        }
+        self.entries.insert(entry.key.clone(), entry);
+    }
+
+    async fn clear(&self, key: &str) {
+        self.entries.remove(key);
+        if let Some(store) = &self.store {
+            if let Err(e) = store.delete_login_failures(key).await {
+                warn!("Failed to clear login failures for {}: {}", key, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! 本地文件系统上的大对象存储
+//!
+//! 对象 `a/b.txt` 保存为 `<root>/a/b.txt`，内容类型和自定义元数据保存在
+//! `<root>/.blobmeta/a/b.txt.json`。写入先落到临时文件再改名，读者不会看到写了一半的对象。
+
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+
+use super::{validate_key, BlobBody, BlobError, BlobInfo, BlobMetadata, BlobObject, BlobStore, ByteRange};
+
+/// 元数据和临时文件所在的目录，不能用作对象键的第一段
+const META_DIR: &str = ".blobmeta";
+
+/// 本地目录中的大对象存储
+pub struct LocalBlobStore {
+    root: PathBuf,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// 存储根目录
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn check_key(&self, key: &str) -> Result<(), BlobError> {
+        validate_key(key)?;
+        if key.split('/').next() == Some(META_DIR) {
+            return Err(BlobError::InvalidKey { key: key.to_string() });
+        }
+        Ok(())
+    }
+
+    fn data_path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    fn meta_path(&self, key: &str) -> PathBuf {
+        self.root.join(META_DIR).join(format!("{}.json", key))
+    }
+
+    async fn read_info(&self, key: &str) -> Result<Option<BlobInfo>> {
+        let file_meta = match tokio::fs::metadata(self.data_path(key)).await {
+            Ok(meta) if meta.is_file() => meta,
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let metadata = match tokio::fs::read(self.meta_path(key)).await {
+            Ok(json) => serde_json::from_slice(&json).unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BlobMetadata::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(BlobInfo {
+            key: key.to_string(),
+            size: file_meta.len(),
+            last_modified: modified_secs(&file_meta),
+            metadata,
+        }))
+    }
+
+    /// 删除对象后清理空的上级目录，直到根目录
+    async fn prune_dirs(&self, path: &Path, stop: &Path) {
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            if current == stop || !current.starts_with(stop) || tokio::fs::remove_dir(current).await.is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+}
+
+fn modified_secs(meta: &std::fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d: Duration| d.as_secs() as i64)
+}
+
+async fn write_atomic(path: &Path, tmp_dir: &Path, mut body: BlobBody) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    tokio::fs::create_dir_all(tmp_dir).await?;
+    let tmp_path = tmp_dir.join(uuid::Uuid::new_v4().to_string());
+    let written: Result<()> = async {
+        let mut file = tokio::fs::File::create(&tmp_path).await?;
+        while let Some(chunk) = body.next().await {
+            file.write_all(&chunk?).await?;
+        }
+        file.flush().await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&tmp_path, path).await?;
+        Ok(())
+    }
+    .await;
+    if written.is_err() {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+    }
+    written
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    fn backend(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, body: BlobBody, metadata: BlobMetadata) -> Result<BlobInfo> {
+        self.check_key(key)?;
+        let tmp_dir = self.root.join(META_DIR).join("tmp");
+        write_atomic(&self.data_path(key), &tmp_dir, body).await?;
+
+        let meta_path = self.meta_path(key);
+        if let Some(parent) = meta_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&meta_path, serde_json::to_vec(&metadata)?).await?;
+
+        self.read_info(key)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("blob {} disappeared after write", key))
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<BlobInfo>> {
+        self.check_key(key)?;
+        self.read_info(key).await
+    }
+
+    async fn get(&self, key: &str, range: Option<ByteRange>) -> Result<Option<BlobObject>> {
+        self.check_key(key)?;
+        let Some(info) = self.read_info(key).await? else {
+            return Ok(None);
+        };
+        let mut file = tokio::fs::File::open(self.data_path(key)).await?;
+        let Some(range) = range else {
+            return Ok(Some(BlobObject {
+                info,
+                range: None,
+                body: ReaderStream::new(file).boxed(),
+            }));
+        };
+
+        let (start, end) = range.resolve(info.size).ok_or_else(|| BlobError::RangeNotSatisfiable {
+            key: key.to_string(),
+            size: info.size,
+        })?;
+        file.seek(SeekFrom::Start(start)).await?;
+        Ok(Some(BlobObject {
+            info,
+            range: Some((start, end)),
+            body: ReaderStream::new(file.take(end - start + 1)).boxed(),
+        }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.check_key(key)?;
+        for path in [self.data_path(key), self.meta_path(key)] {
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            }
+            let stop = if path.starts_with(self.root.join(META_DIR)) {
+                self.root.join(META_DIR)
+            } else {
+                self.root.clone()
+            };
+            self.prune_dirs(&path, &stop).await;
+        }
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<BlobInfo>> {
+        let mut keys = Vec::new();
+        let mut pending = vec![(self.root.clone(), String::new())];
+        while let Some((dir, dir_key)) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let Ok(name) = entry.file_name().into_string() else {
+                    continue;
+                };
+                if dir_key.is_empty() && name == META_DIR {
+                    continue;
+                }
+                let key = if dir_key.is_empty() { name } else { format!("{}/{}", dir_key, name) };
+                // 只进入可能包含匹配键的目录
+                let file_type = entry.file_type().await?;
+                if file_type.is_dir() {
+                    let dir_prefix = format!("{}/", key);
+                    if dir_prefix.starts_with(prefix) || prefix.starts_with(&dir_prefix) {
+                        pending.push((entry.path(), key));
+                    }
+                } else if file_type.is_file() && key.starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+        }
+
+        keys.sort();
+        let mut infos = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(info) = self.read_info(&key).await? {
+                infos.push(info);
+            }
+        }
+        Ok(infos)
+    }
+}
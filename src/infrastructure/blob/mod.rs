@@ -0,0 +1,291 @@
+//! 大对象存储
+//!
+//! 附件、工作区文件、运行轨迹和超大的工具结果不放在 SQLite 中，而是写入 [`BlobStore`]。
+//! 内置本地文件系统（[`LocalBlobStore`]）和 S3 兼容（`S3BlobStore`，需启用 `s3` 特性）两种后端，
+//! 由 `AppConfig::blob_store` 选择；多节点部署或文件系统不持久的容器应使用 S3。
+//!
+//! 对象内容以字节流读写，读取时可以只取一个字节范围。Web 层下载时，能生成预签名 URL 的后端
+//! 把客户端重定向到对象存储，否则由服务端代理输出。
+
+mod local;
+#[cfg(feature = "s3")]
+mod s3;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub use local::LocalBlobStore;
+#[cfg(feature = "s3")]
+pub use s3::S3BlobStore;
+
+/// 对象键的最大长度（字节），与 S3 相同
+pub const MAX_KEY_LEN: usize = 1024;
+
+/// 对象内容的字节流
+pub type BlobBody = BoxStream<'static, std::io::Result<Bytes>>;
+
+/// 一次性给出全部内容的字节流
+pub fn body_from_bytes(bytes: impl Into<Bytes>) -> BlobBody {
+    stream::once(std::future::ready(Ok(bytes.into()))).boxed()
+}
+
+/// 读完字节流
+pub async fn read_body(body: BlobBody) -> std::io::Result<Vec<u8>> {
+    body.try_fold(Vec::new(), |mut buf, chunk| {
+        buf.extend_from_slice(&chunk);
+        std::future::ready(Ok(buf))
+    })
+    .await
+}
+
+/// 写入对象时附带的元数据
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// 自定义元数据（S3 中为 `x-amz-meta-*`），键使用小写
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub custom: BTreeMap<String, String>,
+}
+
+impl BlobMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置内容类型
+    pub fn with_content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// 添加一项自定义元数据
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.custom.insert(key.into().to_ascii_lowercase(), value.into());
+        self
+    }
+}
+
+/// 已存储对象的信息
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobInfo {
+    pub key: String,
+    /// 对象总大小（字节）
+    pub size: u64,
+    /// 最后修改时间（秒）
+    pub last_modified: i64,
+    /// 列出对象时 S3 不返回内容类型和自定义元数据，此时为空
+    #[serde(flatten)]
+    pub metadata: BlobMetadata,
+}
+
+/// 字节范围，两端都包含
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    /// 为 None 时读到对象末尾
+    pub end: Option<u64>,
+}
+
+impl ByteRange {
+    pub fn new(start: u64, end: Option<u64>) -> Self {
+        Self { start, end }
+    }
+
+    /// 解析 HTTP `Range` 头（`bytes=0-99`、`bytes=100-`），不支持的形式返回 None，按整个对象处理
+    pub fn from_header(value: &str) -> Option<Self> {
+        let (start, end) = value.trim().strip_prefix("bytes=")?.split_once('-')?;
+        let start = start.trim().parse().ok()?;
+        let end = match end.trim() {
+            "" => None,
+            end => Some(end.parse().ok()?),
+        };
+        match end {
+            Some(end) if end < start => None,
+            _ => Some(Self { start, end }),
+        }
+    }
+
+    /// 按对象大小截断后的实际范围，起点超出对象时返回 None
+    pub fn resolve(&self, size: u64) -> Option<(u64, u64)> {
+        if self.start >= size {
+            return None;
+        }
+        let end = self.end.map_or(size - 1, |end| end.min(size - 1));
+        Some((self.start, end))
+    }
+
+    /// HTTP `Range` 头的值
+    pub fn header_value(&self) -> String {
+        match self.end {
+            Some(end) => format!("bytes={}-{}", self.start, end),
+            None => format!("bytes={}-", self.start),
+        }
+    }
+}
+
+/// 读取到的对象
+pub struct BlobObject {
+    pub info: BlobInfo,
+    /// 请求了范围时为实际返回的范围（两端包含）
+    pub range: Option<(u64, u64)>,
+    pub body: BlobBody,
+}
+
+/// 大对象存储错误
+#[derive(Debug, Error)]
+pub enum BlobError {
+    #[error("invalid blob key: {key}")]
+    InvalidKey { key: String },
+    #[error("range not satisfiable for {key} ({size} bytes)")]
+    RangeNotSatisfiable { key: String, size: u64 },
+    #[error("blob backend unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// 检查对象键：相对路径形式，不含 `.`、`..`、空段、反斜杠和控制字符
+pub fn validate_key(key: &str) -> Result<(), BlobError> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LEN
+        && !key.contains('\\')
+        && !key.chars().any(char::is_control)
+        && key.split('/').all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    if valid {
+        Ok(())
+    } else {
+        Err(BlobError::InvalidKey { key: key.to_string() })
+    }
+}
+
+/// 大对象存储
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// 后端名称，如 `local`、`s3`
+    fn backend(&self) -> &'static str;
+
+    /// 写入对象，已存在时覆盖
+    async fn put(&self, key: &str, body: BlobBody, metadata: BlobMetadata) -> Result<BlobInfo>;
+
+    /// 对象信息，不存在时返回 None
+    async fn head(&self, key: &str) -> Result<Option<BlobInfo>>;
+
+    /// 读取对象或其中一个字节范围，不存在时返回 None；范围起点超出对象时返回
+    /// [`BlobError::RangeNotSatisfiable`]
+    async fn get(&self, key: &str, range: Option<ByteRange>) -> Result<Option<BlobObject>>;
+
+    /// 删除对象，不存在时也返回成功
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// 列出键以 `prefix` 开头的对象，按键排序
+    async fn list(&self, prefix: &str) -> Result<Vec<BlobInfo>>;
+
+    /// 客户端可直接下载的限时 URL；不支持时返回 None，由 Web 层代理下载
+    async fn download_url(&self, _key: &str, _expires_in: Duration) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+impl std::fmt::Debug for dyn BlobStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlobStore").field("backend", &self.backend()).finish()
+    }
+}
+
+/// 大对象存储后端配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum BlobStoreConfig {
+    /// 本地目录，未设置时使用数据目录
+    Local {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        root: Option<PathBuf>,
+    },
+    /// S3 兼容的对象存储
+    S3(S3Config),
+}
+
+impl Default for BlobStoreConfig {
+    fn default() -> Self {
+        Self::Local { root: None }
+    }
+}
+
+impl BlobStoreConfig {
+    /// 打开配置的后端，本地后端未设置目录时使用 `default_root`
+    pub fn open(&self, default_root: PathBuf) -> Result<Arc<dyn BlobStore>> {
+        match self {
+            Self::Local { root } => Ok(Arc::new(LocalBlobStore::new(root.clone().unwrap_or(default_root)))),
+            #[cfg(feature = "s3")]
+            Self::S3(config) => Ok(Arc::new(S3BlobStore::new(config.clone())?)),
+            #[cfg(not(feature = "s3"))]
+            Self::S3(_) => Err(BlobError::Unavailable("built without the `s3` feature".to_string()).into()),
+        }
+    }
+}
+
+/// S3 兼容对象存储配置（路径风格寻址，适用于 AWS S3、MinIO、R2 等）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct S3Config {
+    /// 服务地址，如 `https://s3.eu-west-1.amazonaws.com`、`http://minio:9000`
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_region")]
+    pub region: String,
+    /// 访问密钥只从环境变量读取，不会序列化
+    #[serde(default, skip_serializing)]
+    pub access_key_id: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub secret_access_key: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub session_token: Option<String>,
+    /// 下载时把客户端重定向到预签名 URL；对象存储对客户端不可达时关闭，改由服务端代理
+    #[serde(default = "default_presign_downloads")]
+    pub presign_downloads: bool,
+    /// 分段上传的分段大小（字节），超过一段的内容使用分段上传
+    #[serde(default = "default_part_size")]
+    pub part_size: usize,
+}
+
+impl S3Config {
+    pub fn new(endpoint: impl Into<String>, bucket: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            bucket: bucket.into(),
+            region: default_region(),
+            access_key_id: None,
+            secret_access_key: None,
+            session_token: None,
+            presign_downloads: default_presign_downloads(),
+            part_size: default_part_size(),
+        }
+    }
+
+    /// 设置访问密钥
+    pub fn with_credentials(mut self, access_key_id: impl Into<String>, secret_access_key: impl Into<String>) -> Self {
+        self.access_key_id = Some(access_key_id.into());
+        self.secret_access_key = Some(secret_access_key.into());
+        self
+    }
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_presign_downloads() -> bool {
+    true
+}
+
+/// S3 要求除最后一段外每段至少 5 MiB
+fn default_part_size() -> usize {
+    8 * 1024 * 1024
+}
@@ -0,0 +1,517 @@
+//! S3 兼容对象存储上的大对象存储
+//!
+//! 使用路径风格寻址（`<endpoint>/<bucket>/<key>`）和 AWS Signature V4 签名，适用于 AWS S3、
+//! MinIO、Cloudflare R2 等。内容不超过一个分段时用一次 `PutObject` 上传，否则边读边做分段上传，
+//! 内存中最多缓存一个分段。请求体不参与签名（`UNSIGNED-PAYLOAD`），应通过 HTTPS 访问。
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, LAST_MODIFIED, RANGE};
+use reqwest::{Method, Response, StatusCode};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use super::{validate_key, BlobBody, BlobError, BlobInfo, BlobMetadata, BlobObject, BlobStore, ByteRange, S3Config};
+
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const META_HEADER_PREFIX: &str = "x-amz-meta-";
+/// 预签名 URL 的最长有效期（S3 的上限为 7 天）
+const MAX_PRESIGN_SECS: u64 = 7 * 86_400;
+
+/// S3 兼容对象存储
+pub struct S3BlobStore {
+    client: reqwest::Client,
+    config: S3Config,
+    /// `scheme://host[:port]`
+    origin: String,
+    /// `host[:port]`，参与签名
+    host: String,
+    /// 端点自带的路径前缀（通常为空）
+    base_path: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl S3BlobStore {
+    pub fn new(config: S3Config) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint).with_context(|| format!("invalid S3 endpoint {}", config.endpoint))?;
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => anyhow::bail!("S3 endpoint {} has no host", config.endpoint),
+        };
+        let (Some(access_key_id), Some(secret_access_key)) =
+            (config.access_key_id.clone(), config.secret_access_key.clone())
+        else {
+            anyhow::bail!("S3 credentials missing (S3_ACCESS_KEY_ID / S3_SECRET_ACCESS_KEY)");
+        };
+        if config.bucket.is_empty() {
+            anyhow::bail!("S3 bucket not configured");
+        }
+        Ok(Self {
+            client: reqwest::Client::new(),
+            origin: format!("{}://{}", endpoint.scheme(), host),
+            host,
+            base_path: endpoint.path().trim_end_matches('/').to_string(),
+            config,
+            access_key_id,
+            secret_access_key,
+        })
+    }
+
+    fn canonical_uri(&self, key: Option<&str>) -> String {
+        let mut uri = format!("{}/{}", self.base_path, uri_encode(&self.config.bucket, true));
+        if let Some(key) = key {
+            uri.push('/');
+            uri.push_str(&uri_encode(key, false));
+        }
+        uri
+    }
+
+    fn scope(&self, date: &str) -> String {
+        format!("{}/{}/s3/aws4_request", date, self.config.region)
+    }
+
+    fn signature(&self, date: &str, amz_date: &str, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            amz_date,
+            self.scope(date),
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = hmac(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes());
+        for part in [self.config.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        hex(&hmac(&key, string_to_sign.as_bytes()))
+    }
+
+    /// 签名后的请求
+    fn request(&self, method: Method, key: Option<&str>, query: &[(&str, &str)]) -> reqwest::RequestBuilder {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let uri = self.canonical_uri(key);
+        let query = canonical_query(query);
+
+        let mut headers = vec![
+            ("host", self.host.clone()),
+            ("x-amz-content-sha256", UNSIGNED_PAYLOAD.to_string()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.config.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let canonical_headers: String = headers.iter().map(|(name, value)| format!("{}:{}\n", name, value)).collect();
+        let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+        let canonical_request = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method, uri, query, canonical_headers, signed_headers, UNSIGNED_PAYLOAD
+        );
+        let authorization = format!(
+            "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+            ALGORITHM,
+            self.access_key_id,
+            self.scope(&date),
+            signed_headers,
+            self.signature(&date, &amz_date, &canonical_request)
+        );
+
+        let url = if query.is_empty() {
+            format!("{}{}", self.origin, uri)
+        } else {
+            format!("{}{}?{}", self.origin, uri, query)
+        };
+        let mut builder = self.client.request(method, url).header("authorization", authorization);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            builder = builder.header(name, value);
+        }
+        builder
+    }
+
+    fn with_metadata(builder: reqwest::RequestBuilder, metadata: &BlobMetadata) -> reqwest::RequestBuilder {
+        let mut builder = builder;
+        if let Some(content_type) = &metadata.content_type {
+            builder = builder.header(CONTENT_TYPE, content_type);
+        }
+        for (key, value) in &metadata.custom {
+            builder = builder.header(format!("{}{}", META_HEADER_PREFIX, key), value);
+        }
+        builder
+    }
+
+    async fn put_single(&self, key: &str, data: Vec<u8>, metadata: &BlobMetadata) -> Result<()> {
+        let builder = self
+            .request(Method::PUT, Some(key), &[])
+            .header(CONTENT_LENGTH, data.len())
+            .body(data);
+        check(Self::with_metadata(builder, metadata).send().await?).await?;
+        Ok(())
+    }
+
+    async fn put_multipart(&self, key: &str, first: Vec<u8>, body: &mut BlobBody, metadata: &BlobMetadata) -> Result<u64> {
+        let created = self.request(Method::POST, Some(key), &[("uploads", "")]);
+        let response = check(Self::with_metadata(created, metadata).send().await?).await?;
+        let xml = response.text().await?;
+        let upload_id = xml_values(&xml, "UploadId")
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("S3 CreateMultipartUpload returned no UploadId"))?;
+
+        let uploaded = self.upload_parts(key, &upload_id, first, body).await;
+        let (parts, size) = match uploaded {
+            Ok(uploaded) => uploaded,
+            Err(e) => {
+                // 放弃分段上传，避免残留的分段继续计费
+                let _ = self
+                    .request(Method::DELETE, Some(key), &[("uploadId", &upload_id)])
+                    .send()
+                    .await;
+                return Err(e);
+            }
+        };
+
+        let mut complete = String::from("<CompleteMultipartUpload>");
+        for (number, etag) in &parts {
+            complete.push_str(&format!(
+                "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                number,
+                xml_escape(etag)
+            ));
+        }
+        complete.push_str("</CompleteMultipartUpload>");
+        let response = check(
+            self.request(Method::POST, Some(key), &[("uploadId", &upload_id)])
+                .header(CONTENT_TYPE, "application/xml")
+                .body(complete)
+                .send()
+                .await?,
+        )
+        .await?;
+        // CompleteMultipartUpload 可能在 200 响应体中返回错误
+        let xml = response.text().await?;
+        if xml.contains("<Error>") {
+            anyhow::bail!("S3 CompleteMultipartUpload failed: {}", error_message(&xml));
+        }
+        Ok(size)
+    }
+
+    async fn upload_parts(
+        &self,
+        key: &str,
+        upload_id: &str,
+        first: Vec<u8>,
+        body: &mut BlobBody,
+    ) -> Result<(Vec<(usize, String)>, u64)> {
+        let mut parts = Vec::new();
+        let mut size = 0u64;
+        let mut pending = Some(first);
+        while let Some(data) = pending.take() {
+            let number = parts.len() + 1;
+            size += data.len() as u64;
+            let number_text = number.to_string();
+            let response = check(
+                self.request(Method::PUT, Some(key), &[("partNumber", &number_text), ("uploadId", upload_id)])
+                    .header(CONTENT_LENGTH, data.len())
+                    .body(data)
+                    .send()
+                    .await?,
+            )
+            .await?;
+            let etag = header_str(response.headers(), ETAG.as_str())
+                .ok_or_else(|| anyhow::anyhow!("S3 UploadPart returned no ETag"))?;
+            parts.push((number, etag));
+
+            let next = fill_part(body, self.config.part_size).await?;
+            if !next.is_empty() {
+                pending = Some(next);
+            }
+        }
+        Ok((parts, size))
+    }
+
+    fn presigned_url(&self, key: &str, expires_in: Duration) -> String {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let credential = format!("{}/{}", self.access_key_id, self.scope(&date));
+        let expires = expires_in.as_secs().clamp(1, MAX_PRESIGN_SECS).to_string();
+        let mut params = vec![
+            ("X-Amz-Algorithm", ALGORITHM),
+            ("X-Amz-Credential", credential.as_str()),
+            ("X-Amz-Date", amz_date.as_str()),
+            ("X-Amz-Expires", expires.as_str()),
+            ("X-Amz-SignedHeaders", "host"),
+        ];
+        if let Some(token) = &self.config.session_token {
+            params.push(("X-Amz-Security-Token", token));
+        }
+        let uri = self.canonical_uri(Some(key));
+        let query = canonical_query(&params);
+        let canonical_request = format!("GET\n{}\n{}\nhost:{}\n\nhost\n{}", uri, query, self.host, UNSIGNED_PAYLOAD);
+        let signature = self.signature(&date, &amz_date, &canonical_request);
+        format!("{}{}?{}&X-Amz-Signature={}", self.origin, uri, query, signature)
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    fn backend(&self) -> &'static str {
+        "s3"
+    }
+
+    async fn put(&self, key: &str, mut body: BlobBody, metadata: BlobMetadata) -> Result<BlobInfo> {
+        validate_key(key)?;
+        let first = fill_part(&mut body, self.config.part_size).await?;
+        let size = if first.len() < self.config.part_size {
+            let size = first.len() as u64;
+            self.put_single(key, first, &metadata).await?;
+            size
+        } else {
+            self.put_multipart(key, first, &mut body, &metadata).await?
+        };
+        Ok(BlobInfo {
+            key: key.to_string(),
+            size,
+            last_modified: Utc::now().timestamp(),
+            metadata,
+        })
+    }
+
+    async fn head(&self, key: &str) -> Result<Option<BlobInfo>> {
+        validate_key(key)?;
+        let response = self.request(Method::HEAD, Some(key), &[]).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check(response).await?;
+        let size = header_str(response.headers(), CONTENT_LENGTH.as_str())
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(0);
+        Ok(Some(info_from_headers(key, size, response.headers())))
+    }
+
+    async fn get(&self, key: &str, range: Option<ByteRange>) -> Result<Option<BlobObject>> {
+        validate_key(key)?;
+        let mut request = self.request(Method::GET, Some(key), &[]);
+        if let Some(range) = &range {
+            request = request.header(RANGE, range.header_value());
+        }
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(None),
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                let size = header_str(response.headers(), CONTENT_RANGE.as_str())
+                    .and_then(|value| value.rsplit('/').next().and_then(|total| total.parse().ok()))
+                    .unwrap_or(0);
+                return Err(BlobError::RangeNotSatisfiable { key: key.to_string(), size }.into());
+            }
+            _ => {}
+        }
+        let response = check(response).await?;
+
+        let length: u64 = header_str(response.headers(), CONTENT_LENGTH.as_str())
+            .and_then(|len| len.parse().ok())
+            .unwrap_or(0);
+        let (range, size) = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let content_range = header_str(response.headers(), CONTENT_RANGE.as_str())
+                    .and_then(|value| parse_content_range(&value))
+                    .ok_or_else(|| anyhow::anyhow!("S3 returned partial content without Content-Range"))?;
+                (Some((content_range.0, content_range.1)), content_range.2)
+            }
+            _ => (None, length),
+        };
+        let info = info_from_headers(key, size, response.headers());
+        let body = response.bytes_stream().map_err(std::io::Error::other).boxed();
+        Ok(Some(BlobObject { info, range, body }))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        validate_key(key)?;
+        let response = self.request(Method::DELETE, Some(key), &[]).send().await?;
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response).await?;
+        }
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<BlobInfo>> {
+        let mut infos = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token));
+            }
+            let response = check(self.request(Method::GET, None, &query).send().await?).await?;
+            let xml = response.text().await?;
+            for contents in xml.split("<Contents>").skip(1) {
+                let contents = contents.split("</Contents>").next().unwrap_or_default();
+                let Some(key) = xml_values(contents, "Key").into_iter().next() else {
+                    continue;
+                };
+                infos.push(BlobInfo {
+                    key,
+                    size: xml_values(contents, "Size").first().and_then(|s| s.parse().ok()).unwrap_or(0),
+                    last_modified: xml_values(contents, "LastModified")
+                        .first()
+                        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                        .map_or(0, |t| t.timestamp()),
+                    metadata: BlobMetadata::default(),
+                });
+            }
+            let truncated = xml_values(&xml, "IsTruncated").first().is_some_and(|t| t == "true");
+            token = xml_values(&xml, "NextContinuationToken").into_iter().next();
+            if !truncated || token.is_none() {
+                break;
+            }
+        }
+        infos.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(infos)
+    }
+
+    async fn download_url(&self, key: &str, expires_in: Duration) -> Result<Option<String>> {
+        validate_key(key)?;
+        if !self.config.presign_downloads {
+            return Ok(None);
+        }
+        Ok(Some(self.presigned_url(key, expires_in)))
+    }
+}
+
+/// 从流中读满一个分段，流结束时返回剩余内容（可能为空）
+async fn fill_part(body: &mut BlobBody, part_size: usize) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    while buf.len() < part_size {
+        match body.next().await {
+            Some(chunk) => buf.extend_from_slice(&chunk?),
+            None => break,
+        }
+    }
+    Ok(buf)
+}
+
+/// 非成功响应转为错误，带上 S3 返回的错误码和信息
+async fn check(response: Response) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    anyhow::bail!("S3 request failed ({}): {}", status, error_message(&body))
+}
+
+fn error_message(xml: &str) -> String {
+    let code = xml_values(xml, "Code").into_iter().next();
+    let message = xml_values(xml, "Message").into_iter().next();
+    match (code, message) {
+        (Some(code), Some(message)) => format!("{}: {}", code, message),
+        (Some(code), None) => code,
+        _ => xml.chars().take(200).collect(),
+    }
+}
+
+fn header_str(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+fn info_from_headers(key: &str, size: u64, headers: &HeaderMap) -> BlobInfo {
+    let custom: BTreeMap<String, String> = headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let key = name.as_str().strip_prefix(META_HEADER_PREFIX)?;
+            Some((key.to_string(), value.to_str().ok()?.to_string()))
+        })
+        .collect();
+    BlobInfo {
+        key: key.to_string(),
+        size,
+        last_modified: header_str(headers, LAST_MODIFIED.as_str())
+            .and_then(|time| DateTime::parse_from_rfc2822(&time).ok())
+            .map_or(0, |time| time.timestamp()),
+        metadata: BlobMetadata {
+            content_type: header_str(headers, CONTENT_TYPE.as_str()),
+            custom,
+        },
+    }
+}
+
+/// `bytes 0-99/1000` -> (0, 99, 1000)
+fn parse_content_range(value: &str) -> Option<(u64, u64, u64)> {
+    let (range, total) = value.trim().strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some((start.parse().ok()?, end.parse().ok()?, total.parse().ok()?))
+}
+
+/// 按键排序、键和值都经过编码的查询字符串
+fn canonical_query(params: &[(&str, &str)]) -> String {
+    let mut encoded: Vec<(String, String)> = params
+        .iter()
+        .map(|(key, value)| (uri_encode(key, true), uri_encode(value, true)))
+        .collect();
+    encoded.sort();
+    encoded
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// SigV4 的 URI 编码：保留非保留字符，路径中的 `/` 按 `encode_slash` 决定
+fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// 取出 XML 中所有 `<tag>...</tag>` 的文本
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    xml.split(open.as_str())
+        .skip(1)
+        .filter_map(|rest| rest.split_once(close.as_str()).map(|(value, _)| xml_unescape(value)))
+        .collect()
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
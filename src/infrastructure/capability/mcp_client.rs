@@ -7,9 +7,13 @@ use futures_util::{SinkExt, StreamExt};
 use serde_json::Value;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
+use crate::core::retry::{Retrier, RetryConfig};
+
 pub struct McpHttpClient {
     base_url: String,
     client: reqwest::Client,
+    /// 连接失败和超时的重试
+    retrier: Retrier,
 }
 
 impl McpHttpClient {
@@ -17,9 +21,16 @@ impl McpHttpClient {
         Self {
             base_url,
             client: reqwest::Client::new(),
+            retrier: Retrier::new("mcp.http", RetryConfig::default().mcp).with_retryable(is_retryable_mcp_error),
         }
     }
 
+    /// 设置重试执行器（子系统策略、预算和指标），只重试连接失败和超时
+    pub fn with_retrier(mut self, retrier: Retrier) -> Self {
+        self.retrier = retrier.with_retryable(is_retryable_mcp_error);
+        self
+    }
+
     /// 通过 HTTP 调用 MCP 功能
     pub async fn call_capability(&self, method: &str, params: Value) -> Result<Value> {
         let url = format!("{}/mcp/call", self.base_url);
@@ -29,23 +40,13 @@ impl McpHttpClient {
             "params": params
         });
 
-        let response = self.client
-            .post(&url)
-            .json(&payload)
-            .send()
-            .await?;
-
-        let result: Value = response.json().await?;
-        Ok(result)
+        self.post(&url, &payload).await
     }
 
     /// 列出服务器上的功能
     pub async fn list_capabilities(&self) -> Result<Value> {
         let url = format!("{}/mcp/capabilities/list", self.base_url);
-
-        let response = self.client.get(&url).send().await?;
-        let result: Value = response.json().await?;
-        Ok(result)
+        self.get(&url).await
     }
 
     /// 发现特定功能
@@ -57,21 +58,41 @@ impl McpHttpClient {
             None => serde_json::json!({}),
         };
 
-        let response = self.client.post(&url).json(&payload).send().await?;
-        let result: Value = response.json().await?;
-        Ok(result)
+        self.post(&url, &payload).await
     }
 
     /// Ping 服务器
     pub async fn ping(&self) -> Result<Value> {
         let url = format!("{}/mcp/ping", self.base_url);
+        self.get(&url).await
+    }
+
+    async fn post(&self, url: &str, payload: &Value) -> Result<Value> {
+        self.retrier
+            .run(|| async {
+                let response = self.client.post(url).json(payload).send().await?;
+                Ok(response.json().await?)
+            })
+            .await
+    }
 
-        let response = self.client.get(&url).send().await?;
-        let result: Value = response.json().await?;
-        Ok(result)
+    async fn get(&self, url: &str) -> Result<Value> {
+        self.retrier
+            .run(|| async {
+                let response = self.client.get(url).send().await?;
+                Ok(response.json().await?)
+            })
+            .await
     }
 }
 
+/// 请求没发出去（连接失败）或超时时重试，服务器返回的错误不重试
+fn is_retryable_mcp_error(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<reqwest::Error>()
+        .is_some_and(|e| e.is_connect() || e.is_timeout())
+}
+
 pub struct McpWebSocketClient {
     ws_url: String,
 }
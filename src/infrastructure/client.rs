@@ -0,0 +1,401 @@
+//! ImitatorT 服务端的 Rust 客户端
+//!
+//! 供机器人、命令行工具和测试程序使用：登录取得 JWT，调用 `/api/v1` 接口，
+//! 并通过 WebSocket 收发 [`protocol`](crate::infrastructure::web::protocol) 中定义的帧。
+//! 连接断开后按退避间隔自动重连，重连时带上令牌重新订阅用户信箱。
+//!
+//! ```rust,no_run
+//! use futures_util::StreamExt;
+//! use imitatort::infrastructure::client::ImitatorClient;
+//! use imitatort::infrastructure::web::protocol::ServerEvent;
+//!
+//! # async fn run() -> anyhow::Result<()> {
+//! let client = ImitatorClient::new("http://127.0.0.1:8080");
+//! client.login("alice", "secret").await?;
+//!
+//! let reply = client.chat("ceo", "How is the quarter going?").await?;
+//! println!("{}", reply.content);
+//!
+//! let mut events = client.subscribe().await?;
+//! while let Some(event) = events.next().await {
+//!     if let ServerEvent::Message { data } = event {
+//!         println!("[{}] {}", data.from, data.content);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::sync::RwLock;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use futures_util::stream::BoxStream;
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, warn};
+
+use crate::domain::MessagePriority;
+use crate::infrastructure::web::envelope::ApiEnvelope;
+use crate::infrastructure::web::protocol::{ClientMessage, MessageFrame, ServerEvent, ServerFrame};
+use crate::infrastructure::web::{AgentResponse, AuthRequest, SendMessageRequest};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// 断线重连的退避策略，间隔从 `initial_backoff` 开始逐次翻倍，不超过 `max_backoff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+/// 登录返回的用户信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionUser {
+    pub id: String,
+    /// 消息中代表该用户的参与者 ID（`user:{id}`）
+    pub principal_id: String,
+    pub username: String,
+    pub name: String,
+}
+
+/// 登录结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginSession {
+    pub token: String,
+    pub user: SessionUser,
+}
+
+/// `POST /messages` 的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentMessage {
+    pub id: String,
+    /// `sent`，或目标 Agent 不在工作时间时为 `queued`
+    pub status: String,
+    pub timestamp: i64,
+    /// 排队时目标 Agent 下一次工作时间的开始
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_available_at: Option<i64>,
+}
+
+/// 后台维护的 WebSocket 连接
+struct Connection {
+    outgoing: mpsc::UnboundedSender<ClientMessage>,
+    events: broadcast::Sender<ServerEvent>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// ImitatorT 服务端客户端
+pub struct ImitatorClient {
+    base_url: String,
+    http: reqwest::Client,
+    session: RwLock<Option<LoginSession>>,
+    reconnect: ReconnectPolicy,
+    chat_timeout: Duration,
+    connection: Mutex<Option<Connection>>,
+}
+
+impl ImitatorClient {
+    /// `base_url` 为服务地址（含部署前缀），如 `http://127.0.0.1:8080`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            session: RwLock::new(None),
+            reconnect: ReconnectPolicy::default(),
+            chat_timeout: Duration::from_secs(60),
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// 设置重连退避策略
+    pub fn with_reconnect_policy(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// 设置 [`chat`](Self::chat) 等待回复的超时
+    pub fn with_chat_timeout(mut self, timeout: Duration) -> Self {
+        self.chat_timeout = timeout;
+        self
+    }
+
+    /// 当前登录会话
+    pub fn session(&self) -> Option<LoginSession> {
+        self.session.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn token(&self) -> Option<String> {
+        self.session().map(|s| s.token)
+    }
+
+    fn principal(&self) -> Option<String> {
+        self.session().map(|s| s.user.principal_id)
+    }
+
+    /// 登录并保存令牌；已有连接会断开，下次使用时以新身份重连
+    pub async fn login(&self, username: &str, password: &str) -> Result<LoginSession> {
+        let request = AuthRequest {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        let response = self.http.post(self.api_url("/auth/login")).json(&request).send().await?;
+        let session: LoginSession = read_envelope(response).await?;
+
+        *self.session.write().unwrap_or_else(|e| e.into_inner()) = Some(session.clone());
+        self.connection.lock().await.take();
+        Ok(session)
+    }
+
+    /// Agent 列表
+    pub async fn list_agents(&self) -> Result<Vec<AgentResponse>> {
+        read_envelope(self.authorized(self.http.get(self.api_url("/agents"))).send().await?).await
+    }
+
+    /// 通过 WebSocket 发送消息；登录后以当前用户身份投递给 Agent
+    pub async fn send_message(&self, to: &str, content: &str) -> Result<()> {
+        self.send_message_with_priority(to, content, MessagePriority::default()).await
+    }
+
+    /// 按指定优先级发送消息
+    pub async fn send_message_with_priority(&self, to: &str, content: &str, priority: MessagePriority) -> Result<()> {
+        let from = self
+            .principal()
+            .ok_or_else(|| anyhow!("Login before sending messages"))?;
+        self.send(ClientMessage::SendMessage {
+            from,
+            to: to.to_string(),
+            content: content.to_string(),
+            priority,
+        })
+        .await
+    }
+
+    /// 以指定发送者身份调用 `POST /messages`，只广播给观察者，不进入 Agent 信箱
+    pub async fn post_message(&self, from: &str, to: &str, content: &str) -> Result<SentMessage> {
+        let request = SendMessageRequest {
+            from: from.to_string(),
+            to: Some(to.to_string()),
+            content: content.to_string(),
+            priority: MessagePriority::default(),
+            reject_if_unavailable: false,
+        };
+        let response = self
+            .authorized(self.http.post(self.api_url("/messages")))
+            .json(&request)
+            .send()
+            .await?;
+        read_envelope(response).await
+    }
+
+    /// 发送任意客户端帧；断线期间排队，重连后发出
+    pub async fn send(&self, frame: ClientMessage) -> Result<()> {
+        let (outgoing, _) = self.connection().await?;
+        outgoing
+            .send(frame)
+            .map_err(|_| anyhow!("WebSocket connection closed"))
+    }
+
+    /// 订阅服务端事件，断线重连对订阅者透明
+    pub async fn subscribe(&self) -> Result<BoxStream<'static, ServerEvent>> {
+        let (_, events) = self.connection().await?;
+        let stream = futures_util::stream::unfold(events.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => return Some((event, rx)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Client event stream lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(stream.boxed())
+    }
+
+    /// 给 Agent 发消息并等待它回复当前用户
+    pub async fn chat(&self, agent_id: &str, content: &str) -> Result<MessageFrame> {
+        let principal = self
+            .principal()
+            .ok_or_else(|| anyhow!("Login before chatting with an agent"))?;
+        let mut events = self.subscribe().await?;
+        self.send_message(agent_id, content).await?;
+
+        let reply = async {
+            while let Some(event) = events.next().await {
+                if let ServerEvent::Message { data } = event {
+                    if data.from == agent_id && data.to == principal {
+                        return Some(data);
+                    }
+                }
+            }
+            None
+        };
+        match tokio::time::timeout(self.chat_timeout, reply).await {
+            Ok(Some(reply)) => Ok(reply),
+            Ok(None) => Err(anyhow!("WebSocket connection closed")),
+            Err(_) => Err(anyhow!("No reply from {} within {:?}", agent_id, self.chat_timeout)),
+        }
+    }
+
+    /// 断开 WebSocket 连接
+    pub async fn disconnect(&self) {
+        self.connection.lock().await.take();
+    }
+
+    /// 取得（必要时建立）连接；首次连接失败直接返回错误
+    async fn connection(&self) -> Result<(mpsc::UnboundedSender<ClientMessage>, broadcast::Sender<ServerEvent>)> {
+        let mut connection = self.connection.lock().await;
+        if let Some(existing) = connection.as_ref().filter(|c| !c.task.is_finished()) {
+            return Ok((existing.outgoing.clone(), existing.events.clone()));
+        }
+
+        let url = self.ws_url()?;
+        let (socket, _) = connect_async(url.as_str()).await?;
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(256);
+        let task = tokio::spawn(run_connection(url, socket, outgoing_rx, events.clone(), self.reconnect));
+
+        let handles = (outgoing.clone(), events.clone());
+        *connection = Some(Connection { outgoing, events, task });
+        Ok(handles)
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/api/v1{}", self.base_url, path)
+    }
+
+    fn ws_url(&self) -> Result<String> {
+        let mut url = url::Url::parse(&self.api_url("/ws"))?;
+        let scheme = match url.scheme() {
+            "https" => "wss",
+            _ => "ws",
+        };
+        url.set_scheme(scheme)
+            .map_err(|_| anyhow!("Unsupported server URL: {}", self.base_url))?;
+        if let Some(token) = self.token() {
+            url.query_pairs_mut().append_pair("token", &token);
+        }
+        Ok(url.into())
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match self.token() {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+}
+
+/// 解析 `/api/v1` 信封，失败时返回其中的错误码和信息
+async fn read_envelope<T: DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+    let status = response.status();
+    let envelope: ApiEnvelope = response
+        .json()
+        .await
+        .map_err(|e| anyhow!("Unexpected response ({}): {}", status, e))?;
+    if !envelope.success {
+        let error = envelope
+            .error
+            .map(|e| format!("{}: {}", e.code, e.message))
+            .unwrap_or_else(|| status.to_string());
+        return Err(anyhow!(error));
+    }
+    Ok(serde_json::from_value(envelope.data.unwrap_or_default())?)
+}
+
+/// 收发帧直到连接断开，然后按退避策略重连；客户端丢弃连接时退出
+async fn run_connection(
+    url: String,
+    socket: Socket,
+    mut outgoing: mpsc::UnboundedReceiver<ClientMessage>,
+    events: broadcast::Sender<ServerEvent>,
+    policy: ReconnectPolicy,
+) {
+    let mut socket = Some(socket);
+    // 断线时未能发出的帧，重连后补发
+    let mut unsent: Option<ClientMessage> = None;
+    let mut backoff = policy.initial_backoff;
+
+    loop {
+        let current = match socket.take() {
+            Some(socket) => socket,
+            None => {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+                match connect_async(url.as_str()).await {
+                    Ok((socket, _)) => {
+                        debug!("WebSocket reconnected to {}", url);
+                        backoff = policy.initial_backoff;
+                        socket
+                    }
+                    Err(e) => {
+                        warn!("WebSocket reconnect failed: {}", e);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let (mut sink, mut stream) = current.split();
+        if let Some(frame) = unsent.take() {
+            if let Err(e) = sink.send(WsMessage::Text(serde_json::to_string(&frame).unwrap_or_default())).await {
+                warn!("WebSocket send failed: {}", e);
+                unsent = Some(frame);
+                continue;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                frame = stream.next() => match frame {
+                    Some(Ok(WsMessage::Text(text))) => match serde_json::from_str::<ServerFrame>(&text) {
+                        Ok(frame) => {
+                            let _ = events.send(frame.event);
+                        }
+                        Err(e) => warn!("Ignoring unknown WebSocket frame: {}", e),
+                    },
+                    Some(Ok(WsMessage::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        warn!("WebSocket error: {}", e);
+                        break;
+                    }
+                },
+                frame = outgoing.recv() => match frame {
+                    Some(frame) => {
+                        let text = serde_json::to_string(&frame).unwrap_or_default();
+                        if let Err(e) = sink.send(WsMessage::Text(text)).await {
+                            warn!("WebSocket send failed: {}", e);
+                            unsent = Some(frame);
+                            break;
+                        }
+                    }
+                    None => return,
+                },
+            }
+        }
+        warn!("WebSocket disconnected, reconnecting");
+    }
+}
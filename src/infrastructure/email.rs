@@ -0,0 +1,272 @@
+//! 邮件通知
+//!
+//! 让 Agent 能通知不在 Web 界面前的人：每日总结、升级提醒等。`EmailSender` 负责实际发送
+//! （`SmtpEmailSender` 基于 lettre），`EmailNotifier` 在其上实现收件域名白名单、每个 Agent
+//! 的每日上限、失败重试和审计日志。只有在 AppConfig 中配置了 SMTP 时才会创建 notifier，
+//! `notify.email` 工具也只有此时才会出现在 Agent 的工具列表里。
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::config::EmailPolicy;
+use crate::core::retry::Retrier;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// SMTP 连接加密方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpTls {
+    /// 明文（仅用于本地中继）
+    None,
+    /// 先明文连接再升级（通常是 587 端口）
+    #[default]
+    StartTls,
+    /// 直接 TLS 连接（通常是 465 端口）
+    Tls,
+}
+
+impl FromStr for SmtpTls {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "off" => Ok(Self::None),
+            "starttls" => Ok(Self::StartTls),
+            "tls" | "ssl" => Ok(Self::Tls),
+            other => Err(format!("unknown SMTP TLS mode: {}", other)),
+        }
+    }
+}
+
+/// SMTP 服务器配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub tls: SmtpTls,
+    /// 发件人，如 `ImitatorT <bot@example.com>`
+    pub from: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    /// 密码只从环境变量读取，不会序列化
+    #[serde(default, skip_serializing)]
+    pub password: Option<String>,
+}
+
+/// 一封待发送的邮件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl EmailMessage {
+    pub fn new(to: impl Into<String>, subject: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            to: to.into(),
+            subject: subject.into(),
+            body: body.into(),
+        }
+    }
+}
+
+/// 邮件发送器
+#[async_trait]
+pub trait EmailSender: Send + Sync {
+    /// 发送一封邮件，失败时由调用方决定是否重试
+    async fn send(&self, message: &EmailMessage) -> Result<()>;
+}
+
+/// 基于 SMTP 的发送器
+pub struct SmtpEmailSender {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpEmailSender {
+    pub fn new(config: &SmtpConfig) -> Result<Self> {
+        let builder = match config.tls {
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+        };
+        let mut builder = builder.port(config.port);
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config.from.parse()?,
+        })
+    }
+}
+
+#[async_trait]
+impl EmailSender for SmtpEmailSender {
+    async fn send(&self, message: &EmailMessage) -> Result<()> {
+        let email = lettre::Message::builder()
+            .from(self.from.clone())
+            .to(message.to.parse()?)
+            .subject(message.subject.clone())
+            .body(message.body.clone())?;
+        self.transport.send(email).await?;
+        Ok(())
+    }
+}
+
+/// 邮件通知失败原因
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EmailError {
+    #[error("Invalid email address: {0}")]
+    InvalidAddress(String),
+    #[error("Agent {agent_id} is not allowed to email {address}")]
+    DomainNotAllowed { agent_id: String, address: String },
+    #[error("Agent {agent_id} reached the daily email cap of {cap}")]
+    DailyCapReached { agent_id: String, cap: u32 },
+    #[error("Failed to send email after {attempts} attempts: {error}")]
+    Failed { attempts: u32, error: String },
+}
+
+/// 带策略的邮件通知器
+pub struct EmailNotifier {
+    sender: Arc<dyn EmailSender>,
+    policy: EmailPolicy,
+    clock: Arc<dyn Clock>,
+    retrier: Retrier,
+    /// Agent ID -> (UTC 日序号, 当天已发送数)
+    sent: Mutex<HashMap<String, (i64, u32)>>,
+}
+
+impl EmailNotifier {
+    pub fn new(sender: Arc<dyn EmailSender>, policy: EmailPolicy) -> Self {
+        Self {
+            sender,
+            retrier: Retrier::new("email.deliver", policy.retry_policy()),
+            policy,
+            clock: Arc::new(SystemClock),
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 替换时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 使用共享的重试执行器（预算和指标），次数和间隔仍取自 `EmailPolicy`
+    pub fn with_retrier(mut self, retrier: Retrier) -> Self {
+        self.retrier = retrier.with_policy(self.policy.retry_policy());
+        self
+    }
+
+    pub fn policy(&self) -> &EmailPolicy {
+        &self.policy
+    }
+
+    /// Agent 当天已发送的邮件数
+    pub fn sent_today(&self, agent_id: &str) -> u32 {
+        let today = self.today();
+        let sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        match sent.get(agent_id) {
+            Some((day, count)) if *day == today => *count,
+            _ => 0,
+        }
+    }
+
+    /// 以 Agent 身份发送：检查收件域名和每日上限，返回尝试次数
+    pub async fn send_as(&self, agent_id: &str, message: &EmailMessage) -> Result<u32, EmailError> {
+        if !is_valid_address(&message.to) {
+            return Err(EmailError::InvalidAddress(message.to.clone()));
+        }
+        if !self.policy.allows(agent_id, &message.to) {
+            warn!(target: "audit", "Email from agent {} to {} rejected: domain not allowed", agent_id, message.to);
+            return Err(EmailError::DomainNotAllowed {
+                agent_id: agent_id.to_string(),
+                address: message.to.clone(),
+            });
+        }
+        if !self.reserve(agent_id) {
+            warn!(target: "audit", "Email from agent {} to {} rejected: daily cap of {} reached", agent_id, message.to, self.policy.daily_cap);
+            return Err(EmailError::DailyCapReached {
+                agent_id: agent_id.to_string(),
+                cap: self.policy.daily_cap,
+            });
+        }
+
+        let result = self.deliver(agent_id, message).await;
+        if result.is_err() {
+            // 没发出去的不占用当天额度
+            self.release(agent_id);
+        }
+        result
+    }
+
+    /// 直接发送（系统通知，不检查白名单和上限），失败时按退避重试，返回尝试次数
+    pub async fn deliver(&self, requested_by: &str, message: &EmailMessage) -> Result<u32, EmailError> {
+        if !is_valid_address(&message.to) {
+            return Err(EmailError::InvalidAddress(message.to.clone()));
+        }
+
+        let (result, attempts) = self.retrier.run_counted(|| self.sender.send(message)).await;
+        match result {
+            Ok(()) => {
+                info!(target: "audit", "Email sent for {} to {} ({:?}, attempt {})", requested_by, message.to, message.subject, attempts);
+                Ok(attempts)
+            }
+            Err(e) => {
+                warn!(target: "audit", "Email for {} to {} failed after {} attempts: {}", requested_by, message.to, attempts, e);
+                Err(EmailError::Failed {
+                    attempts,
+                    error: e.to_string(),
+                })
+            }
+        }
+    }
+
+    fn today(&self) -> i64 {
+        self.clock.now().div_euclid(SECS_PER_DAY)
+    }
+
+    /// 占用一个当天额度，已满时返回 false
+    fn reserve(&self, agent_id: &str) -> bool {
+        let today = self.today();
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = sent.entry(agent_id.to_string()).or_insert((today, 0));
+        if entry.0 != today {
+            *entry = (today, 0);
+        }
+        if entry.1 >= self.policy.daily_cap {
+            return false;
+        }
+        entry.1 += 1;
+        true
+    }
+
+    fn release(&self, agent_id: &str) {
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = sent.get_mut(agent_id) {
+            entry.1 = entry.1.saturating_sub(1);
+        }
+    }
+}
+
+/// 只做基本格式检查，真正的校验交给 SMTP 服务器
+fn is_valid_address(address: &str) -> bool {
+    address.parse::<lettre::Address>().is_ok()
+}
@@ -2,9 +2,9 @@
 //!
 //! 使用 async-openai 提供与 OpenAI API 的交互能力
 //! 支持 Tool Calling (Function Calling)
+//! 请求直接经 reqwest 发送，以便按状态码、响应头和错误体把失败分类为 [`LlmError`]
 
 use anyhow::{Context, Result};
-use async_openai::config::OpenAIConfig;
 use async_openai::types::chat::{
     ChatCompletionMessageToolCall, ChatCompletionMessageToolCalls,
     ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
@@ -12,15 +12,196 @@ use async_openai::types::chat::{
     ChatCompletionRequestUserMessageArgs, ChatCompletionTool, ChatCompletionTools,
     ChatCompletionToolChoiceOption, CreateChatCompletionRequestArgs, ToolChoiceOptions,
 };
-use async_openai::types::chat::{FunctionCall, FunctionObject};
-use async_openai::Client;
+use async_openai::types::chat::{CreateChatCompletionRequest, CreateChatCompletionResponse, FunctionCall, FunctionObject};
+use reqwest::header::HeaderMap;
 use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "chaos")]
+use crate::core::chaos::{FaultInjector, Subsystem};
+use crate::core::retry::{Retrier, RetryPolicy};
+use crate::core::secrets::{default_resolver, SecretError, SecretRef, SecretResolver};
+use crate::core::tokenizer::{HeuristicTokenizer, Tokenizer, MESSAGE_OVERHEAD_TOKENS};
+use crate::domain::schema::openai_function;
+use crate::domain::LLMConfig;
+use crate::infrastructure::logger::{PromptExchange, PromptLog, PromptMessage, PromptToolCall};
+
+/// 未接入重试注册表时的调用点名称
+const RETRY_SITE: &str = "llm.chat";
+
+/// 错误详情的最大长度，超出部分截断
+const MAX_ERROR_DETAIL_CHARS: usize = 500;
+
+/// LLM 调用失败的分类
+///
+/// 由 HTTP 状态码和 OpenAI 兼容的错误体（`error.type`、`error.code`、`error.message`）解析而来，
+/// 调用方据此决定是否重试、缩短上下文后重发、立即熔断，以及如何向用户说明。
+/// 无法识别的错误响应归入 [`LlmError::Unknown`]。
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum LlmError {
+    /// API key 无效或被吊销（401/403），重试不会好转
+    #[error("LLM authentication failed: {detail}")]
+    AuthFailed { detail: String },
+    /// 额度用完或账单问题
+    #[error("LLM quota exceeded: {detail}")]
+    QuotaExceeded { detail: String },
+    /// 提示词超出模型的上下文长度；服务商在消息中给出时带上上限和请求的 token 数
+    #[error("Prompt exceeds the model's context length (limit {}, requested {})", format_tokens(*limit), format_tokens(*requested))]
+    ContextLengthExceeded { limit: Option<u32>, requested: Option<u32> },
+    /// 输入或输出被服务商的内容过滤拦截
+    #[error("Blocked by the LLM provider's content filter: {detail}")]
+    ContentFiltered { detail: String },
+    /// 触发速率限制，`retry_after` 取自 `Retry-After` 响应头
+    #[error("LLM rate limited{}", retry_after.map(|d| format!(", retry after {:?}", d)).unwrap_or_default())]
+    RateLimited { retry_after: Option<Duration> },
+    /// 服务端错误（5xx）
+    #[error("LLM server error ({status}): {detail}")]
+    ServerError { status: u16, detail: String },
+    /// 连接失败、超时或读取响应失败
+    #[error("Failed to reach the LLM API: {detail}")]
+    Network { detail: String },
+    /// 请求本身有误（参数、模型名等）
+    #[error("Invalid LLM request: {detail}")]
+    InvalidRequest { detail: String },
+    /// 无法识别的错误响应
+    #[error("Unexpected LLM API response ({status}): {detail}")]
+    Unknown { status: u16, detail: String },
+}
+
+fn format_tokens(tokens: Option<u32>) -> String {
+    tokens.map_or_else(|| "unknown".to_string(), |t| t.to_string())
+}
+
+impl LlmError {
+    /// 按 HTTP 状态码、`Retry-After` 和响应体分类失败的请求
+    pub fn from_response(status: u16, retry_after: Option<Duration>, body: &str) -> Self {
+        let parsed = serde_json::from_str::<Value>(body).ok();
+        let error = parsed.as_ref().map(|v| v.get("error").unwrap_or(v));
+        let field = |key: &str| -> Option<String> {
+            match error?.get(key)? {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            }
+        };
+        let message = field("message").or_else(|| error.and_then(Value::as_str).map(str::to_string));
+        let kind = field("type").unwrap_or_default().to_ascii_lowercase();
+        let code = field("code").unwrap_or_default().to_ascii_lowercase();
+        let has = |needle: &str| kind.contains(needle) || code.contains(needle);
+        let detail = truncate_detail(message.as_deref().unwrap_or(body));
+        let lower = message.as_deref().unwrap_or_default().to_ascii_lowercase();
+
+        if has("context_length") || lower.contains("maximum context length") || lower.contains("context window") {
+            return Self::ContextLengthExceeded {
+                limit: number_after(&lower, &["maximum context length is", "context length is", "limit of"]),
+                requested: number_after(&lower, &["resulted in", "you requested", "requested"]),
+            };
+        }
+        if has("insufficient_quota") || has("quota") || has("billing") || status == 402 {
+            return Self::QuotaExceeded { detail };
+        }
+        if has("content_filter") || has("content_policy") || has("safety") {
+            return Self::ContentFiltered { detail };
+        }
+        if status == 401 || status == 403 || has("invalid_api_key") || has("authentication") {
+            return Self::AuthFailed { detail };
+        }
+        match status {
+            429 => Self::RateLimited { retry_after },
+            500..=599 => Self::ServerError { status, detail },
+            400..=499 if message.is_some() => Self::InvalidRequest { detail },
+            _ => Self::Unknown { status, detail },
+        }
+    }
+
+    /// 错误链中的 LLM 错误
+    pub fn of(error: &anyhow::Error) -> Option<&LlmError> {
+        error.chain().find_map(|cause| cause.downcast_ref::<LlmError>())
+    }
+
+    /// 是否值得按退避重试：速率限制、服务端错误和连接失败
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::RateLimited { .. } | Self::ServerError { .. } | Self::Network { .. })
+    }
+
+    /// 服务端要求的等待时间
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// 写入提示词日志的错误类别
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::AuthFailed { .. } => "auth_failed",
+            Self::QuotaExceeded { .. } => "quota_exceeded",
+            Self::ContextLengthExceeded { .. } => "context_length_exceeded",
+            Self::ContentFiltered { .. } => "content_filtered",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::ServerError { .. } => "server_error",
+            Self::Network { .. } => "network",
+            Self::InvalidRequest { .. } => "invalid_request",
+            Self::Unknown { .. } => "unknown",
+        }
+    }
+}
+
+fn truncate_detail(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(MAX_ERROR_DETAIL_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// `text` 中第一个出现的标记之后的第一个整数
+fn number_after(text: &str, markers: &[&str]) -> Option<u32> {
+    markers.iter().find_map(|marker| {
+        let rest = &text[text.find(marker)? + marker.len()..];
+        let digits: String = rest
+            .trim_start()
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == ',')
+            .filter(char::is_ascii_digit)
+            .collect();
+        digits.parse().ok()
+    })
+}
+
+/// `Retry-After`（秒）或 `retry-after-ms`（毫秒）响应头
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<f64>().ok().filter(|v| *v >= 0.0);
+    header("retry-after-ms")
+        .map(|ms| Duration::from_secs_f64(ms / 1000.0))
+        .or_else(|| header("retry-after").map(Duration::from_secs_f64))
+}
 
 /// OpenAI 客户端
 #[derive(Clone)]
 pub struct OpenAIClient {
-    client: Client<OpenAIConfig>,
+    http: reqwest::Client,
+    api_key: String,
+    /// 不带末尾 `/` 的 API 地址
+    base_url: String,
     model: String,
+    /// 累计消耗的 token（克隆的客户端共享）
+    tokens_used: Arc<AtomicU64>,
+    /// 其中因响应不带 usage 而按分词器估算的部分
+    tokens_estimated: Arc<AtomicU64>,
+    /// 估算用量的分词器
+    tokenizer: Arc<dyn Tokenizer>,
+    /// 连接失败等临时错误的重试
+    retrier: Retrier,
+    /// 提示词日志（未启用时为空）
+    prompt_log: Option<Arc<PromptLog>>,
+    /// API Key 引用解析失败时的错误，每次请求都返回它
+    secret_error: Option<Arc<SecretError>>,
+    #[cfg(feature = "chaos")]
+    fault_injector: Option<Arc<FaultInjector>>,
 }
 
 impl OpenAIClient {
@@ -28,17 +209,120 @@ impl OpenAIClient {
     pub fn new_with_base_url(api_key: String, model: String, base_url: String) -> Self {
         let base_url = base_url.trim_end_matches('/').to_string();
 
-        let config = OpenAIConfig::new()
-            .with_api_key(api_key)
-            .with_api_base(base_url);
+        Self {
+            http: reqwest::Client::new(),
+            api_key,
+            base_url,
+            model,
+            tokens_used: Arc::new(AtomicU64::new(0)),
+            tokens_estimated: Arc::new(AtomicU64::new(0)),
+            tokenizer: Arc::new(HeuristicTokenizer),
+            retrier: Retrier::new(RETRY_SITE, RetryPolicy::default())
+                .with_retryable(is_transient)
+                .with_retry_after(server_retry_after),
+            prompt_log: None,
+            secret_error: None,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
+        }
+    }
+
+    /// 按 LLM 配置创建客户端，API Key 引用（`env:`、`keyring:`）用默认解析器解析
+    pub fn from_config(config: &LLMConfig) -> Result<Self, SecretError> {
+        Self::from_config_with(config, default_resolver().as_ref())
+    }
+
+    /// 按 LLM 配置创建客户端，API Key 引用用指定的解析器解析
+    pub fn from_config_with(config: &LLMConfig, resolver: &dyn SecretResolver) -> Result<Self, SecretError> {
+        let api_key = resolver.resolve(&SecretRef::parse(&config.api_key))?;
+        Ok(Self::new_with_base_url(api_key, config.model.clone(), config.base_url.clone()))
+    }
+
+    /// 按 LLM 配置创建客户端；API Key 引用解析失败时记录错误，之后的每次请求都返回该错误
+    pub fn for_config(config: &LLMConfig) -> Self {
+        Self::from_config(config).unwrap_or_else(|e| {
+            tracing::error!("{}", e);
+            let mut client = Self::new_with_base_url(String::new(), config.model.clone(), config.base_url.clone());
+            client.secret_error = Some(Arc::new(e));
+            client
+        })
+    }
+
+    /// 设置临时错误的重试次数和初始退避
+    pub fn with_retry(mut self, max_retries: u32, retry_backoff: Duration) -> Self {
+        let policy = self.retrier.policy().clone();
+        let max_delay = Duration::from_millis(policy.max_delay_ms).max(retry_backoff);
+        let policy = policy.with_max_attempts(max_retries + 1).with_delays(retry_backoff, max_delay);
+        self.retrier = self.retrier.with_policy(policy);
+        self
+    }
+
+    /// 使用共享的重试执行器（子系统策略、预算和指标），只重试临时错误
+    pub fn with_retrier(mut self, retrier: Retrier) -> Self {
+        self.retrier = retrier.with_retryable(is_transient).with_retry_after(server_retry_after);
+        self
+    }
+
+    /// 每次请求前询问故障注入器（子系统 `llm`，操作 `chat`）
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injector(mut self, injector: Arc<FaultInjector>) -> Self {
+        self.fault_injector = Some(injector);
+        self
+    }
+
+    /// 把每次请求及其响应写入提示词日志
+    pub fn with_prompt_log(mut self, prompt_log: Arc<PromptLog>) -> Self {
+        self.prompt_log = Some(prompt_log);
+        self
+    }
+
+    /// 设置估算用量的分词器（服务商不返回 usage 时使用）
+    pub fn with_tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// 累计消耗的 token，按响应中的 usage 统计；响应不带 usage 时按分词器估算
+    pub fn tokens_used(&self) -> u64 {
+        self.tokens_used.load(Ordering::Relaxed)
+    }
 
-        let client = Client::with_config(config);
+    /// 累计用量中估算的部分
+    pub fn tokens_estimated(&self) -> u64 {
+        self.tokens_estimated.load(Ordering::Relaxed)
+    }
 
-        Self { client, model }
+    fn record_usage(
+        &self,
+        usage: Option<&async_openai::types::chat::CompletionUsage>,
+        prompt_tokens: usize,
+        completion: &str,
+    ) {
+        match usage {
+            Some(usage) => {
+                self.tokens_used.fetch_add(usage.total_tokens as u64, Ordering::Relaxed);
+            }
+            None => {
+                let estimated = (prompt_tokens + self.tokenizer.count(completion)) as u64;
+                self.tokens_used.fetch_add(estimated, Ordering::Relaxed);
+                self.tokens_estimated.fetch_add(estimated, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// 请求消息的估算 token 数
+    fn prompt_tokens(&self, messages: &[Message]) -> usize {
+        messages
+            .iter()
+            .map(|m| self.tokenizer.count(&m.content) + MESSAGE_OVERHEAD_TOKENS)
+            .sum()
     }
 
     /// 调用聊天 API
     pub async fn chat(&self, messages: Vec<Message>) -> Result<String> {
+        let prompt_tokens = self.prompt_tokens(&messages);
+        let exchange = self.start_exchange(&messages, Vec::new());
+        let started = Instant::now();
         let messages: Vec<ChatCompletionRequestMessage> = messages
             .into_iter()
             .map(|msg| match msg.role.as_str() {
@@ -60,12 +344,9 @@ impl OpenAIClient {
             .build()
             .context("构建请求失败")?;
 
-        let response = self
-            .client
-            .chat()
-            .create(request)
-            .await
-            .context("调用 LLM API 失败")?;
+        let response = self.create_with_retry(request).await;
+        let response = self.log_failure(exchange.as_ref(), started, response)?;
+        let usage = response.usage.clone();
 
         let content = response
             .choices
@@ -73,6 +354,8 @@ impl OpenAIClient {
             .next()
             .and_then(|c| c.message.content)
             .unwrap_or_default();
+        self.record_usage(usage.as_ref(), prompt_tokens, &content);
+        self.log_success(exchange, started, &content, Vec::new(), usage.as_ref());
 
         Ok(content)
     }
@@ -93,6 +376,10 @@ impl OpenAIClient {
     /// # Returns
     /// * `ToolResponse` - 包含 assistant 的回复或 tool 调用请求
     pub async fn chat_with_tools(&self, messages: Vec<Message>, tools: Vec<Tool>) -> Result<ToolResponse> {
+        let prompt_tokens = self.prompt_tokens(&messages);
+        let tool_ids: Vec<String> = tools.iter().map(|t| t.id.clone()).collect();
+        let exchange = self.start_exchange(&messages, tool_ids.clone());
+        let started = Instant::now();
         let request_messages = self.build_request_messages(messages)?;
         let chat_tools = self.build_chat_tools(tools)?;
 
@@ -104,12 +391,9 @@ impl OpenAIClient {
             .build()
             .context("构建请求失败")?;
 
-        let response = self
-            .client
-            .chat()
-            .create(request)
-            .await
-            .context("调用 LLM API 失败")?;
+        let response = self.create_with_retry(request).await;
+        let response = self.log_failure(exchange.as_ref(), started, response)?;
+        let usage = response.usage.clone();
 
         let choice = response
             .choices
@@ -118,36 +402,129 @@ impl OpenAIClient {
             .context("API 返回空响应")?;
 
         let message = choice.message;
+        let completion = match &message.tool_calls {
+            Some(calls) => serde_json::to_string(calls).unwrap_or_default(),
+            None => message.content.clone().unwrap_or_default(),
+        };
+        self.record_usage(usage.as_ref(), prompt_tokens, &completion);
 
         // 检查是否有 tool_calls
-        if let Some(tool_calls) = message.tool_calls {
-            let calls: Vec<ToolCall> = tool_calls
-                .into_iter()
-                .filter_map(|tc| match tc {
-                    ChatCompletionMessageToolCalls::Function(func_call) => {
-                        let args = serde_json::from_str(&func_call.function.arguments).unwrap_or(Value::Null);
-                        Some(ToolCall {
-                            id: func_call.id,
-                            name: func_call.function.name,
-                            arguments: args,
-                        })
-                    }
-                    _ => None,
-                })
-                .collect();
-
-            if !calls.is_empty() {
-                return Ok(ToolResponse::ToolCalls {
-                    content: message.content.unwrap_or_default(),
-                    tool_calls: calls,
-                });
-            }
+        let calls: Vec<ToolCall> = message
+            .tool_calls
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|tc| match tc {
+                ChatCompletionMessageToolCalls::Function(func_call) => {
+                    let args = serde_json::from_str(&func_call.function.arguments).unwrap_or(Value::Null);
+                    // 函数名还原为工具 ID
+                    let name = tool_ids
+                        .iter()
+                        .find(|id| function_name(id) == func_call.function.name)
+                        .cloned()
+                        .unwrap_or(func_call.function.name);
+                    Some(ToolCall {
+                        id: func_call.id,
+                        name,
+                        arguments: args,
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+        let content = message.content.unwrap_or_default();
+        let logged_calls = match exchange {
+            Some(_) => calls
+                .iter()
+                .map(|c| PromptToolCall { name: c.name.clone(), arguments: c.arguments.clone() })
+                .collect(),
+            None => Vec::new(),
+        };
+        self.log_success(exchange, started, &content, logged_calls, usage.as_ref());
+
+        if !calls.is_empty() {
+            return Ok(ToolResponse::ToolCalls {
+                content,
+                tool_calls: calls,
+            });
         }
 
         // 返回普通文本回复
-        Ok(ToolResponse::Message(
-            message.content.unwrap_or_default(),
-        ))
+        Ok(ToolResponse::Message(content))
+    }
+
+    /// 启用提示词日志时记下请求内容
+    fn start_exchange(&self, messages: &[Message], tools: Vec<String>) -> Option<PromptExchange> {
+        self.prompt_log.as_ref()?;
+        let messages = messages
+            .iter()
+            .map(|m| PromptMessage { role: m.role.clone(), content: m.content.clone() })
+            .collect();
+        Some(PromptExchange::new(&self.model, messages, tools))
+    }
+
+    fn log_failure<T>(&self, exchange: Option<&PromptExchange>, started: Instant, result: Result<T>) -> Result<T> {
+        if let (Some(log), Some(exchange), Err(e)) = (&self.prompt_log, exchange, &result) {
+            let mut exchange = exchange.clone().with_error(format!("{:#}", e));
+            if let Some(error) = LlmError::of(e) {
+                exchange = exchange.with_error_kind(error.kind());
+            }
+            log.record(exchange.finished(started));
+        }
+        result
+    }
+
+    fn log_success(
+        &self,
+        exchange: Option<PromptExchange>,
+        started: Instant,
+        content: &str,
+        tool_calls: Vec<PromptToolCall>,
+        usage: Option<&async_openai::types::chat::CompletionUsage>,
+    ) {
+        let (Some(log), Some(exchange)) = (&self.prompt_log, exchange) else {
+            return;
+        };
+        let mut exchange = exchange.with_response(content, tool_calls);
+        if let Some(usage) = usage {
+            exchange = exchange.with_usage(usage.prompt_tokens, usage.completion_tokens, usage.total_tokens);
+        }
+        log.record(exchange.finished(started));
+    }
+
+    /// 发送请求，速率限制、服务端错误和连接失败按退避重试
+    async fn create_with_retry(&self, request: CreateChatCompletionRequest) -> Result<CreateChatCompletionResponse> {
+        if let Some(error) = &self.secret_error {
+            return Err(SecretError::clone(error).into());
+        }
+        self.retrier.run(|| self.create(&request)).await
+    }
+
+    async fn create(&self, request: &CreateChatCompletionRequest) -> Result<CreateChatCompletionResponse> {
+        #[cfg(feature = "chaos")]
+        if let Some(injector) = &self.fault_injector {
+            injector.inject(Subsystem::Llm, "chat").await.context("调用 LLM API 失败")?;
+        }
+        self.post_chat(request).await.context("调用 LLM API 失败")
+    }
+
+    /// 发送一次聊天请求，失败时按状态码和错误体分类
+    async fn post_chat(&self, request: &CreateChatCompletionRequest) -> Result<CreateChatCompletionResponse, LlmError> {
+        let network = |e: reqwest::Error| LlmError::Network { detail: e.to_string() };
+        let mut builder = self.http.post(format!("{}/chat/completions", self.base_url)).json(request);
+        if !self.api_key.is_empty() {
+            builder = builder.bearer_auth(&self.api_key);
+        }
+        let response = builder.send().await.map_err(network)?;
+        let status = response.status().as_u16();
+        let retry_after = retry_after(response.headers());
+        let body = response.text().await.map_err(network)?;
+        if !(200..300).contains(&status) {
+            return Err(LlmError::from_response(status, retry_after, &body));
+        }
+        serde_json::from_str(&body).map_err(|e| LlmError::Unknown {
+            status,
+            detail: format!("{}: {}", e, truncate_detail(&body)),
+        })
     }
 
     /// 构建请求消息
@@ -207,22 +584,35 @@ impl OpenAIClient {
 
     /// 构建 ChatCompletionTools 列表
     fn build_chat_tools(&self, tools: Vec<Tool>) -> Result<Vec<ChatCompletionTools>> {
-        Ok(tools
+        tools
             .into_iter()
             .map(|tool| {
-                ChatCompletionTools::Function(ChatCompletionTool {
-                    function: FunctionObject {
-                        name: tool.id,
-                        description: Some(tool.description),
-                        parameters: Some(tool.parameters),
-                        strict: None,
-                    },
-                })
+                let function = openai_function(&function_name(&tool.id), &tool.description, &tool.parameters);
+                Ok(ChatCompletionTools::Function(ChatCompletionTool {
+                    function: serde_json::from_value::<FunctionObject>(function).context("构建工具定义失败")?,
+                }))
             })
-            .collect())
+            .collect()
     }
 }
 
+/// 是否值得重试：速率限制、服务端错误、连接失败或注入的故障；鉴权、额度、上下文长度等错误不重试
+fn is_transient(error: &anyhow::Error) -> bool {
+    #[cfg(feature = "chaos")]
+    if crate::core::chaos::is_injected(error) {
+        return true;
+    }
+    LlmError::of(error).is_some_and(LlmError::is_transient)
+}
+
+/// 速率限制响应要求的等待时间，代替退避间隔
+fn server_retry_after(error: &anyhow::Error) -> Option<Duration> {
+    LlmError::of(error).and_then(LlmError::retry_after)
+}
+
+/// 工具 ID 对应的函数名：OpenAI 只接受 `[a-zA-Z0-9_-]`，`tool.search` 这样的 ID 把点换成 `__`
+pub use crate::domain::tool::openai_function_name as function_name;
+
 /// Tool 调用响应
 #[derive(Clone, Debug)]
 pub enum ToolResponse {
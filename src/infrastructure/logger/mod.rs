@@ -1,6 +1,13 @@
 //! 日志模块
 //!
-//! 提供基础日志功能
+//! 提供基础日志功能，以及可选的 LLM 提示词日志通道（见 [`prompt`]）
+
+pub mod prompt;
+
+pub use prompt::{
+    current_trace_id, with_trace_id, PromptExchange, PromptLog, PromptLogConfig, PromptLogLevel, PromptMessage,
+    PromptToolCall, RotatingFileWriter, PROMPT_LOG_FILE,
+};
 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
@@ -0,0 +1,531 @@
+//! LLM 提示词日志
+//!
+//! 排查提示词问题需要看到实际发送的内容，但把完整提示词写进主日志既嘈杂又可能泄露用户数据。
+//! 这里提供一个可选的独立通道：每次 LLM 调用（请求及其响应或错误）写成一行 JSON，
+//! 写入专用目录下按大小和时间轮转的文件，并按 [`PromptLogLevel`] 脱敏。
+//!
+//! 写入经有界队列交给后台线程，LLM 调用路径上从不等待磁盘；队列满时丢弃该条并计数。
+//! 轮转只发生在整行之间，切换文件前先刷新缓冲，已入队的行不会丢失。
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// 当前写入的文件名，轮转后改名为 `prompts.<毫秒时间戳>.jsonl`
+pub const PROMPT_LOG_FILE: &str = "prompts.jsonl";
+
+const ROTATED_PREFIX: &str = "prompts.";
+const ROTATED_SUFFIX: &str = ".jsonl";
+
+tokio::task_local! {
+    static TRACE_ID: String;
+}
+
+/// 在 `trace_id` 下运行 `future`，其中的 LLM 调用记录带上该 trace ID
+pub async fn with_trace_id<F: Future>(trace_id: impl Into<String>, future: F) -> F::Output {
+    TRACE_ID.scope(trace_id.into(), future).await
+}
+
+/// 当前任务的 trace ID（不在 [`with_trace_id`] 中时为空）
+pub fn current_trace_id() -> Option<String> {
+    TRACE_ID.try_with(|id| id.clone()).ok()
+}
+
+/// 脱敏级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptLogLevel {
+    /// 完整记录提示词和响应
+    Full,
+    /// 系统提示词完整记录；其余消息、LLM 回复和工具调用参数只保留哈希与长度，相同内容哈希相同，便于关联
+    #[default]
+    RedactUserContent,
+    /// 只记录角色、长度、工具、用量和耗时
+    MetadataOnly,
+}
+
+impl PromptLogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::RedactUserContent => "redact_user_content",
+            Self::MetadataOnly => "metadata_only",
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Full,
+            1 => Self::RedactUserContent,
+            _ => Self::MetadataOnly,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Self::Full => 0,
+            Self::RedactUserContent => 1,
+            Self::MetadataOnly => 2,
+        }
+    }
+}
+
+impl fmt::Display for PromptLogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for PromptLogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "full" => Ok(Self::Full),
+            "redact" | "redact_user_content" => Ok(Self::RedactUserContent),
+            "metadata" | "metadata_only" => Ok(Self::MetadataOnly),
+            other => Err(format!("unknown prompt log level: {}", other)),
+        }
+    }
+}
+
+/// 提示词日志配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptLogConfig {
+    /// 日志目录
+    pub directory: PathBuf,
+    /// 启动时的脱敏级别，运行中可通过管理接口调整
+    #[serde(default)]
+    pub level: PromptLogLevel,
+    /// 当前文件超过该大小后轮转
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+    /// 当前文件写入超过该时长后轮转（秒），0 表示只按大小轮转
+    #[serde(default = "default_max_age_secs")]
+    pub max_age_secs: u64,
+    /// 保留的已轮转文件数，更早的文件被删除
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+    /// 等待写入的记录上限，超出时丢弃
+    #[serde(default = "default_queue_capacity")]
+    pub queue_capacity: usize,
+}
+
+fn default_max_file_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_max_age_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_max_files() -> usize {
+    10
+}
+
+fn default_queue_capacity() -> usize {
+    1024
+}
+
+impl PromptLogConfig {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            level: PromptLogLevel::default(),
+            max_file_bytes: default_max_file_bytes(),
+            max_age_secs: default_max_age_secs(),
+            max_files: default_max_files(),
+            queue_capacity: default_queue_capacity(),
+        }
+    }
+
+    pub fn with_level(mut self, level: PromptLogLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    pub fn with_rotation(mut self, max_file_bytes: u64, max_age: Duration) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self.max_age_secs = max_age.as_secs();
+        self
+    }
+
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+}
+
+/// 请求中的一条消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptMessage {
+    pub role: String,
+    pub content: String,
+}
+
+/// 响应中的一次工具调用
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptToolCall {
+    pub name: String,
+    pub arguments: Value,
+}
+
+/// 一次 LLM 调用
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptExchange {
+    /// Unix 时间戳（毫秒）
+    pub timestamp: i64,
+    pub trace_id: Option<String>,
+    pub model: String,
+    pub messages: Vec<PromptMessage>,
+    /// 提供给模型的工具 ID
+    pub tools: Vec<String>,
+    pub response: Option<String>,
+    pub tool_calls: Vec<PromptToolCall>,
+    /// 服务商返回的用量（prompt、completion、total）
+    pub usage: Option<(u32, u32, u32)>,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+    /// LLM 错误类别（见 [`crate::infrastructure::llm::LlmError::kind`]）
+    pub error_kind: Option<String>,
+}
+
+impl PromptExchange {
+    /// 开始记录一次调用，trace ID 取自当前任务
+    pub fn new(model: impl Into<String>, messages: Vec<PromptMessage>, tools: Vec<String>) -> Self {
+        Self {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as i64)
+                .unwrap_or_default(),
+            trace_id: current_trace_id(),
+            model: model.into(),
+            messages,
+            tools,
+            response: None,
+            tool_calls: Vec::new(),
+            usage: None,
+            duration_ms: 0,
+            error: None,
+            error_kind: None,
+        }
+    }
+
+    pub fn with_response(mut self, content: impl Into<String>, tool_calls: Vec<PromptToolCall>) -> Self {
+        self.response = Some(content.into());
+        self.tool_calls = tool_calls;
+        self
+    }
+
+    pub fn with_usage(mut self, prompt_tokens: u32, completion_tokens: u32, total_tokens: u32) -> Self {
+        self.usage = Some((prompt_tokens, completion_tokens, total_tokens));
+        self
+    }
+
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    pub fn with_error_kind(mut self, kind: impl Into<String>) -> Self {
+        self.error_kind = Some(kind.into());
+        self
+    }
+
+    /// 记录从 `started` 到现在的耗时
+    pub fn finished(mut self, started: Instant) -> Self {
+        self.duration_ms = started.elapsed().as_millis() as u64;
+        self
+    }
+
+    /// 按脱敏级别渲染为一行日志
+    pub fn render(&self, level: PromptLogLevel) -> Value {
+        let messages: Vec<Value> = self
+            .messages
+            .iter()
+            .map(|m| {
+                let chars = m.content.chars().count();
+                match level {
+                    PromptLogLevel::Full => json!({ "role": m.role, "content": m.content }),
+                    PromptLogLevel::RedactUserContent if m.role == "system" => json!({ "role": m.role, "content": m.content }),
+                    PromptLogLevel::RedactUserContent => {
+                        json!({ "role": m.role, "content_hash": content_hash(&m.content), "chars": chars })
+                    }
+                    PromptLogLevel::MetadataOnly => json!({ "role": m.role, "chars": chars }),
+                }
+            })
+            .collect();
+        let tool_calls: Vec<Value> = self
+            .tool_calls
+            .iter()
+            .map(|c| match level {
+                PromptLogLevel::Full => json!({ "name": c.name, "arguments": c.arguments }),
+                PromptLogLevel::RedactUserContent => {
+                    let arguments = c.arguments.to_string();
+                    json!({ "name": c.name, "arguments_hash": content_hash(&arguments), "chars": arguments.chars().count() })
+                }
+                PromptLogLevel::MetadataOnly => json!({ "name": c.name }),
+            })
+            .collect();
+        let response = self.response.as_ref().map(|content| match level {
+            PromptLogLevel::Full => json!({ "content": content, "tool_calls": tool_calls }),
+            PromptLogLevel::RedactUserContent => json!({
+                "content_hash": content_hash(content),
+                "chars": content.chars().count(),
+                "tool_calls": tool_calls,
+            }),
+            PromptLogLevel::MetadataOnly => json!({ "chars": content.chars().count(), "tool_calls": tool_calls }),
+        });
+
+        let mut line = json!({
+            "timestamp": self.timestamp,
+            "trace_id": self.trace_id,
+            "level": level,
+            "model": self.model,
+            "duration_ms": self.duration_ms,
+            "tools": self.tools,
+            "messages": messages,
+            "response": response,
+        });
+        if let Some((prompt, completion, total)) = self.usage {
+            line["usage"] = json!({ "prompt_tokens": prompt, "completion_tokens": completion, "total_tokens": total });
+        }
+        if let Some(error) = &self.error {
+            line["error"] = json!(error);
+        }
+        if let Some(kind) = &self.error_kind {
+            line["error_kind"] = json!(kind);
+        }
+        line
+    }
+}
+
+/// 内容哈希：`sha256:` 加十六进制摘要
+fn content_hash(content: &str) -> String {
+    let digest = Sha256::digest(content.as_bytes());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", hex)
+}
+
+enum Command {
+    Write(PromptLogLevel, Box<PromptExchange>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// 提示词日志通道
+///
+/// 由后台线程写入；克隆 `Arc` 在各个 LLM 客户端间共享，最后一个引用释放时写完队列中剩余的记录。
+pub struct PromptLog {
+    tx: SyncSender<Command>,
+    level: AtomicU8,
+    dropped: AtomicU64,
+    directory: Option<PathBuf>,
+}
+
+impl PromptLog {
+    /// 按配置打开日志目录并启动写入线程
+    pub fn open(config: &PromptLogConfig) -> io::Result<Self> {
+        let writer = RotatingFileWriter::open(config)?;
+        let mut log = Self::with_writer(writer, config.level, config.queue_capacity);
+        log.directory = Some(config.directory.clone());
+        Ok(log)
+    }
+
+    /// 写入任意目标（测试中可用慢速写入器模拟磁盘）
+    pub fn with_writer(writer: impl Write + Send + 'static, level: PromptLogLevel, queue_capacity: usize) -> Self {
+        let (tx, rx) = mpsc::sync_channel(queue_capacity.max(1));
+        std::thread::Builder::new()
+            .name("prompt-log".to_string())
+            .spawn(move || write_loop(writer, rx))
+            .expect("failed to spawn prompt log writer");
+        Self {
+            tx,
+            level: AtomicU8::new(level.as_u8()),
+            dropped: AtomicU64::new(0),
+            directory: None,
+        }
+    }
+
+    /// 当前脱敏级别
+    pub fn level(&self) -> PromptLogLevel {
+        PromptLogLevel::from_u8(self.level.load(Ordering::Relaxed))
+    }
+
+    /// 调整脱敏级别，对之后记录的调用生效
+    pub fn set_level(&self, level: PromptLogLevel) {
+        self.level.store(level.as_u8(), Ordering::Relaxed);
+    }
+
+    /// 日志目录（自定义写入目标时为空）
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /// 因队列已满丢弃的记录数
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 记录一次调用，从不阻塞；队列已满时丢弃并返回 false
+    pub fn record(&self, exchange: PromptExchange) -> bool {
+        match self.tx.try_send(Command::Write(self.level(), Box::new(exchange))) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                false
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        }
+    }
+
+    /// 等待此前入队的记录全部写入（会阻塞当前线程）
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = mpsc::channel();
+        if self.tx.send(Command::Flush(done_tx)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+}
+
+impl fmt::Debug for PromptLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PromptLog")
+            .field("level", &self.level())
+            .field("directory", &self.directory)
+            .field("dropped", &self.dropped())
+            .finish()
+    }
+}
+
+fn write_loop(mut writer: impl Write, rx: Receiver<Command>) {
+    for command in rx {
+        match command {
+            Command::Write(level, exchange) => {
+                let mut line = exchange.render(level).to_string();
+                line.push('\n');
+                if let Err(e) = writer.write_all(line.as_bytes()) {
+                    warn!("Failed to write prompt log: {}", e);
+                }
+            }
+            Command::Flush(done) => {
+                if let Err(e) = writer.flush() {
+                    warn!("Failed to flush prompt log: {}", e);
+                }
+                let _ = done.send(());
+            }
+        }
+    }
+    let _ = writer.flush();
+}
+
+/// 按大小和时间轮转的文件
+///
+/// 每次 `write` 视为一整行：写入前判断是否需要轮转，因此一行不会被拆到两个文件中。
+pub struct RotatingFileWriter {
+    directory: PathBuf,
+    max_file_bytes: u64,
+    max_age: Option<Duration>,
+    max_files: usize,
+    file: BufWriter<File>,
+    size: u64,
+    opened_at: Instant,
+}
+
+impl RotatingFileWriter {
+    pub fn open(config: &PromptLogConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+        let (file, size) = open_active(&config.directory)?;
+        Ok(Self {
+            directory: config.directory.clone(),
+            max_file_bytes: config.max_file_bytes,
+            max_age: (config.max_age_secs > 0).then(|| Duration::from_secs(config.max_age_secs)),
+            max_files: config.max_files,
+            file,
+            size,
+            opened_at: Instant::now(),
+        })
+    }
+
+    /// 已轮转的文件，按时间从旧到新
+    pub fn rotated_files(directory: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| {
+                path.file_name().and_then(|n| n.to_str()).is_some_and(|name| {
+                    name != PROMPT_LOG_FILE && name.starts_with(ROTATED_PREFIX) && name.ends_with(ROTATED_SUFFIX)
+                })
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    fn should_rotate(&self, incoming: u64) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+        self.size + incoming > self.max_file_bytes || self.max_age.is_some_and(|age| self.opened_at.elapsed() >= age)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let mut target = self.directory.join(format!("{}{:013}{}", ROTATED_PREFIX, millis, ROTATED_SUFFIX));
+        let mut suffix = 1;
+        while target.exists() {
+            target = self
+                .directory
+                .join(format!("{}{:013}-{:04}{}", ROTATED_PREFIX, millis, suffix, ROTATED_SUFFIX));
+            suffix += 1;
+        }
+        fs::rename(self.directory.join(PROMPT_LOG_FILE), &target)?;
+        let (file, size) = open_active(&self.directory)?;
+        self.file = file;
+        self.size = size;
+        self.opened_at = Instant::now();
+
+        let rotated = Self::rotated_files(&self.directory)?;
+        for old in rotated.iter().take(rotated.len().saturating_sub(self.max_files)) {
+            fs::remove_file(old)?;
+        }
+        Ok(())
+    }
+}
+
+fn open_active(directory: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(directory.join(PROMPT_LOG_FILE))?;
+    let size = file.metadata()?.len();
+    Ok((BufWriter::new(file), size))
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_rotate(buf.len() as u64) {
+            self.rotate()?;
+        }
+        self.file.write_all(buf)?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,220 @@
+//! 代码沙箱
+//!
+//! `code.run` 工具在 WASM 沙箱中运行 Agent 提交的小段程序（解析 CSV、计算统计、转换 JSON 等）。
+//! 每种语言对应一个 WASI 运行时模块（如编译为 WASM 的 QuickJS）：程序源码通过命令行参数、
+//! 输入 JSON 通过标准输入交给运行时，标准输出作为结果返回。沙箱不提供网络和文件系统，
+//! 超过燃料（执行的指令数）、时间或内存上限时终止程序。
+//!
+//! 需要启用 `sandbox` 特性。引擎和编译好的运行时模块在所有调用间共享，实例内存来自池化分配器，
+//! 同时运行的程序数不超过池的大小。
+
+#[cfg(feature = "sandbox")]
+mod wasm;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 程序源码在运行时参数中的占位符
+pub const CODE_PLACEHOLDER: &str = "{code}";
+
+/// 一种语言的 WASI 运行时
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SandboxRuntime {
+    /// 运行时模块路径（`.wasm`，或 `.wat` 文本格式）
+    pub module: PathBuf,
+    /// 传给运行时的命令行参数，其中的 [`CODE_PLACEHOLDER`] 替换为程序源码
+    #[serde(default = "default_runtime_args")]
+    pub args: Vec<String>,
+}
+
+fn default_runtime_args() -> Vec<String> {
+    vec!["run".to_string(), CODE_PLACEHOLDER.to_string()]
+}
+
+impl SandboxRuntime {
+    /// 使用默认参数（`run {code}`）的运行时
+    pub fn new(module: impl Into<PathBuf>) -> Self {
+        Self {
+            module: module.into(),
+            args: default_runtime_args(),
+        }
+    }
+
+    /// 设置命令行参数
+    pub fn with_args(mut self, args: &[&str]) -> Self {
+        self.args = args.iter().map(|a| a.to_string()).collect();
+        self
+    }
+
+    /// 替换占位符后的命令行参数
+    pub fn render_args(&self, code: &str) -> Vec<String> {
+        self.args.iter().map(|a| a.replace(CODE_PLACEHOLDER, code)).collect()
+    }
+}
+
+/// 代码沙箱配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CodeSandboxConfig {
+    /// 是否向 Agent 提供 `code.run` 工具
+    pub enabled: bool,
+    /// 语言 -> 运行时，如 `javascript`
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub runtimes: HashMap<String, SandboxRuntime>,
+    /// 每次运行可消耗的燃料（约等于执行的 WASM 指令数）
+    pub fuel: u64,
+    /// 每次运行的时间上限（毫秒）
+    pub timeout_ms: u64,
+    /// 每个实例的内存上限（MiB）
+    pub memory_limit_mb: usize,
+    /// 标准输出和标准错误各自最多保留的字节数
+    pub max_output_bytes: usize,
+    /// 同时运行的程序数，也是实例池的大小
+    pub max_concurrent: usize,
+}
+
+impl Default for CodeSandboxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            runtimes: HashMap::new(),
+            fuel: 2_000_000_000,
+            timeout_ms: 5000,
+            memory_limit_mb: 64,
+            max_output_bytes: 64 * 1024,
+            max_concurrent: 4,
+        }
+    }
+}
+
+impl CodeSandboxConfig {
+    /// 启用沙箱
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    /// 添加一种语言的运行时
+    pub fn with_runtime(mut self, language: impl Into<String>, runtime: SandboxRuntime) -> Self {
+        self.runtimes.insert(language.into(), runtime);
+        self
+    }
+
+    /// 设置燃料、时间和内存上限
+    pub fn with_limits(mut self, fuel: u64, timeout_ms: u64, memory_limit_mb: usize) -> Self {
+        self.fuel = fuel;
+        self.timeout_ms = timeout_ms;
+        self.memory_limit_mb = memory_limit_mb;
+        self
+    }
+}
+
+/// 沙箱错误；各种上限分开，便于 Agent 判断是改程序还是缩小输入
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SandboxError {
+    #[error("Code sandbox is not available in this build (enable the `sandbox` feature)")]
+    Unavailable,
+    #[error("No sandbox runtime configured for language {language}")]
+    UnsupportedLanguage { language: String },
+    #[error("Failed to load sandbox runtime for {language}: {message}")]
+    RuntimeLoad { language: String, message: String },
+    #[error("Program used up its fuel ({fuel})")]
+    FuelExhausted { fuel: u64 },
+    #[error("Program ran longer than {timeout_ms} ms")]
+    Timeout { timeout_ms: u64 },
+    #[error("Program exceeded the memory limit of {limit_mb} MiB")]
+    MemoryLimit { limit_mb: usize },
+    #[error("Program output exceeded {max_bytes} bytes")]
+    OutputLimit { max_bytes: usize },
+    #[error("Program exited with code {code}: {stderr}")]
+    Exited { code: i32, stderr: String },
+    #[error("Program failed: {message}")]
+    Trapped { message: String },
+}
+
+impl SandboxError {
+    /// 触发的上限种类，不是上限错误时为 None
+    pub fn limit(&self) -> Option<&'static str> {
+        match self {
+            SandboxError::FuelExhausted { .. } => Some("fuel"),
+            SandboxError::Timeout { .. } => Some("time"),
+            SandboxError::MemoryLimit { .. } => Some("memory"),
+            SandboxError::OutputLimit { .. } => Some("output"),
+            _ => None,
+        }
+    }
+}
+
+/// 一次运行的输出
+#[derive(Debug, Clone, Serialize)]
+pub struct SandboxOutput {
+    pub stdout: String,
+    pub stderr: String,
+    /// 消耗的燃料
+    pub fuel_used: u64,
+}
+
+impl SandboxOutput {
+    /// 标准输出是 JSON 时解析出的返回值
+    pub fn value(&self) -> Option<Value> {
+        serde_json::from_str(self.stdout.trim()).ok()
+    }
+}
+
+/// 共享的代码沙箱
+pub struct CodeSandbox {
+    config: CodeSandboxConfig,
+    #[cfg(feature = "sandbox")]
+    pool: wasm::WasmPool,
+}
+
+impl CodeSandbox {
+    /// 按配置创建沙箱；未启用 `sandbox` 特性时返回 [`SandboxError::Unavailable`]
+    pub fn new(config: CodeSandboxConfig) -> Result<Self> {
+        #[cfg(feature = "sandbox")]
+        {
+            let pool = wasm::WasmPool::new(&config)?;
+            Ok(Self { config, pool })
+        }
+        #[cfg(not(feature = "sandbox"))]
+        {
+            let _ = config;
+            Err(SandboxError::Unavailable.into())
+        }
+    }
+
+    /// 沙箱配置
+    pub fn config(&self) -> &CodeSandboxConfig {
+        &self.config
+    }
+
+    /// 已配置运行时的语言
+    pub fn languages(&self) -> Vec<String> {
+        let mut languages: Vec<String> = self.config.runtimes.keys().cloned().collect();
+        languages.sort();
+        languages
+    }
+
+    /// 运行一段程序，`input` 以 JSON 写入标准输入
+    pub async fn run(&self, language: &str, code: &str, input: &Value) -> Result<SandboxOutput> {
+        let Some(runtime) = self.config.runtimes.get(language) else {
+            return Err(SandboxError::UnsupportedLanguage { language: language.to_string() }.into());
+        };
+        #[cfg(feature = "sandbox")]
+        {
+            let input = serde_json::to_vec(input)?;
+            self.pool.run(&self.config, language, runtime, code, input).await
+        }
+        #[cfg(not(feature = "sandbox"))]
+        {
+            let _ = (runtime, code, input);
+            Err(SandboxError::Unavailable.into())
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! wasmtime 实现：共享引擎、按语言缓存的预链接运行时和池化实例
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::Semaphore;
+use wasmtime::{
+    Config, Engine, InstanceAllocationStrategy, InstancePre, Linker, Module, PoolingAllocationConfig, ResourceLimiter,
+    Store, Trap,
+};
+use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::p2::WasiCtxBuilder;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::I32Exit;
+
+use super::{CodeSandboxConfig, SandboxError, SandboxOutput, SandboxRuntime};
+
+/// 时间上限的计时粒度
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// 表的元素上限
+const MAX_TABLE_ELEMENTS: usize = 100_000;
+
+/// 每个实例的状态
+struct SandboxState {
+    wasi: WasiP1Ctx,
+    memory: MemoryCap,
+}
+
+/// 内存上限，记录是否触发以便和其他失败区分
+struct MemoryCap {
+    max_bytes: usize,
+    exceeded: bool,
+}
+
+impl ResourceLimiter for MemoryCap {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        if desired > self.max_bytes {
+            self.exceeded = true;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> Result<bool> {
+        Ok(desired <= MAX_TABLE_ELEMENTS)
+    }
+}
+
+/// 共享引擎与运行时缓存
+pub(super) struct WasmPool {
+    engine: Engine,
+    linker: Arc<Linker<SandboxState>>,
+    /// 语言 -> 预链接的运行时，首次使用时编译
+    runtimes: Mutex<HashMap<String, InstancePre<SandboxState>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl WasmPool {
+    pub(super) fn new(config: &CodeSandboxConfig) -> Result<Self> {
+        let slots = config.max_concurrent.max(1) as u32;
+        let mut pooling = PoolingAllocationConfig::default();
+        pooling
+            .total_core_instances(slots)
+            .total_memories(slots)
+            .total_tables(slots)
+            .total_component_instances(slots)
+            .max_memory_size(config.memory_limit_mb << 20)
+            .table_elements(MAX_TABLE_ELEMENTS);
+        let mut engine_config = Config::new();
+        engine_config
+            .consume_fuel(true)
+            .epoch_interruption(true)
+            .allocation_strategy(InstanceAllocationStrategy::Pooling(pooling));
+        let engine = Engine::new(&engine_config)?;
+
+        let mut linker = Linker::new(&engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut SandboxState| &mut state.wasi)?;
+
+        // 引擎释放后计时线程随之退出
+        let weak = engine.weak();
+        std::thread::Builder::new()
+            .name("sandbox-epoch".to_string())
+            .spawn(move || {
+                while let Some(engine) = weak.upgrade() {
+                    engine.increment_epoch();
+                    drop(engine);
+                    std::thread::sleep(EPOCH_TICK);
+                }
+            })?;
+
+        Ok(Self {
+            engine,
+            linker: Arc::new(linker),
+            runtimes: Mutex::new(HashMap::new()),
+            permits: Arc::new(Semaphore::new(slots as usize)),
+        })
+    }
+
+    /// 取得语言的预链接运行时，首次使用时编译模块
+    fn runtime(&self, language: &str, runtime: &SandboxRuntime) -> Result<InstancePre<SandboxState>> {
+        let mut runtimes = self.runtimes.lock().unwrap();
+        if let Some(pre) = runtimes.get(language) {
+            return Ok(pre.clone());
+        }
+        let load_error = |e: anyhow::Error| SandboxError::RuntimeLoad {
+            language: language.to_string(),
+            message: e.to_string(),
+        };
+        let module = Module::from_file(&self.engine, &runtime.module).map_err(load_error)?;
+        let pre = self.linker.instantiate_pre(&module).map_err(load_error)?;
+        runtimes.insert(language.to_string(), pre.clone());
+        Ok(pre)
+    }
+
+    pub(super) async fn run(
+        &self,
+        config: &CodeSandboxConfig,
+        language: &str,
+        runtime: &SandboxRuntime,
+        code: &str,
+        input: Vec<u8>,
+    ) -> Result<SandboxOutput> {
+        let pre = self.runtime(language, runtime)?;
+        let _permit = self.permits.clone().acquire_owned().await?;
+        let args = runtime.render_args(code);
+        let limits = Limits {
+            fuel: config.fuel,
+            timeout_ms: config.timeout_ms,
+            memory_limit_mb: config.memory_limit_mb,
+            max_output_bytes: config.max_output_bytes,
+        };
+        let engine = self.engine.clone();
+        tokio::task::spawn_blocking(move || execute(&engine, &pre, &args, input, &limits)).await?
+    }
+}
+
+struct Limits {
+    fuel: u64,
+    timeout_ms: u64,
+    memory_limit_mb: usize,
+    max_output_bytes: usize,
+}
+
+/// 在当前线程实例化并运行 `_start`
+fn execute(
+    engine: &Engine,
+    pre: &InstancePre<SandboxState>,
+    args: &[String],
+    input: Vec<u8>,
+    limits: &Limits,
+) -> Result<SandboxOutput> {
+    // 不预打开目录、不开放网络、不继承环境变量
+    let stdout = MemoryOutputPipe::new(limits.max_output_bytes);
+    let stderr = MemoryOutputPipe::new(limits.max_output_bytes);
+    let wasi = WasiCtxBuilder::new()
+        .args(args)
+        .stdin(MemoryInputPipe::new(input))
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .build_p1();
+    let memory = MemoryCap {
+        max_bytes: limits.memory_limit_mb << 20,
+        exceeded: false,
+    };
+    let mut store = Store::new(engine, SandboxState { wasi, memory });
+    store.limiter(|state| &mut state.memory);
+    store.set_fuel(limits.fuel)?;
+    store.set_epoch_deadline(limits.timeout_ms.div_ceil(EPOCH_TICK.as_millis() as u64).max(1));
+
+    let result = pre.instantiate(&mut store).and_then(|instance| {
+        let start = instance.get_typed_func::<(), ()>(&mut store, "_start")?;
+        start.call(&mut store, ())
+    });
+    let fuel_used = limits.fuel.saturating_sub(store.get_fuel().unwrap_or(0));
+    let stdout = stdout.contents();
+    let stderr = String::from_utf8_lossy(&stderr.contents()).into_owned();
+
+    if let Err(e) = result {
+        let exit = e.downcast_ref::<I32Exit>().map(|exit| exit.0);
+        let error = if store.data().memory.exceeded {
+            SandboxError::MemoryLimit { limit_mb: limits.memory_limit_mb }
+        } else if let Some(code) = exit.filter(|code| *code != 0) {
+            if stdout.len() >= limits.max_output_bytes {
+                SandboxError::OutputLimit { max_bytes: limits.max_output_bytes }
+            } else {
+                SandboxError::Exited { code, stderr }
+            }
+        } else if exit.is_some() {
+            return Ok(output(&stdout, stderr, fuel_used));
+        } else {
+            match e.downcast_ref::<Trap>() {
+                Some(Trap::OutOfFuel) => SandboxError::FuelExhausted { fuel: limits.fuel },
+                Some(Trap::Interrupt) => SandboxError::Timeout { timeout_ms: limits.timeout_ms },
+                _ => SandboxError::Trapped { message: format!("{:#}", e) },
+            }
+        };
+        return Err(error.into());
+    }
+    if stdout.len() >= limits.max_output_bytes {
+        return Err(SandboxError::OutputLimit { max_bytes: limits.max_output_bytes }.into());
+    }
+    Ok(output(&stdout, stderr, fuel_used))
+}
+
+fn output(stdout: &[u8], stderr: String, fuel_used: u64) -> SandboxOutput {
+    SandboxOutput {
+        stdout: String::from_utf8_lossy(stdout).into_owned(),
+        stderr,
+        fuel_used,
+    }
+}
@@ -2,46 +2,73 @@
 //!
 //! Uses SQLite as backend, suitable for scenarios requiring persistence
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 use anyhow::Result;
 use async_trait::async_trait;
-use rusqlite::Connection;
-
-use crate::core::store::{MessageFilter, Store};
-use crate::domain::{Agent, AgentMode, Department, Group, LLMConfig, Message, MessageTarget, Organization, Role};
-use crate::domain::user::User;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::core::secrets::{env_reference_for, ExtractedSecret, SecretRef};
+use crate::core::integrity::{IntegrityFinding, IssueKind, QuarantinedRow, Repair, Severity};
+use crate::core::store::{MessageFilter, MessageTierCounts, Store, StoreBackendInfo, TaskFilter};
+use crate::domain::{Agent, AgentMode, Department, Escalation, Group, LLMConfig, Message, MessageBookmark, MessagePin, MessagePriority, MessageReaction, MessageTarget, MessageTranslation, Organization, Role, RoleRevision, Task, TaskStatus};
+use crate::domain::user::{LoginFailures, User};
+use crate::domain::idempotency::IdempotencyRecord;
+use crate::domain::lease::Lease;
+use crate::domain::moderation::QuarantinedOutput;
+use crate::domain::chat_session::ChatSession;
+use crate::domain::group_stats::GroupStats;
+use crate::domain::scratchpad::ScratchpadEntry;
+use crate::domain::share_token::ShareToken;
+use crate::domain::digest::Digest;
+use crate::domain::org_change::{OrgChangeEntry, OrgDiff};
 use crate::domain::invitation_code::InvitationCode;
+use crate::domain::tool::ToolUsage;
 
 /// SQLite Storage
 pub struct SqliteStore {
     conn: Arc<Mutex<Connection>>,
+    /// File path or URI the connection was opened with
+    location: String,
+    /// 保存时从明文 API Key 取出、还未交给调用方的值
+    extracted_api_keys: Arc<Mutex<Vec<ExtractedSecret>>>,
 }
 
 impl SqliteStore {
     /// Create new SQLite storage
     ///
-    /// If the database file doesn't exist, it will be created automatically
+    /// If the database file doesn't exist, it will be created automatically.
+    /// SQLite URI filenames are accepted too, e.g. `file:name?mode=memory&cache=shared`
+    /// opens a named in-memory database shared by every connection in the process.
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
-        let store = Self {
-            conn: Arc::new(Mutex::new(conn)),
-        };
-        store.init_schema()?;
-        Ok(store)
+        let location = db_path.as_ref().to_string_lossy().into_owned();
+        let flags = OpenFlags::default() | OpenFlags::SQLITE_OPEN_URI;
+        let conn = Connection::open_with_flags(db_path, flags)?;
+        Self::with_connection(conn, location)
     }
 
     /// Create in-memory database (for testing)
     pub fn new_in_memory() -> Result<Self> {
-        let conn = Connection::open_in_memory()?;
+        Self::with_connection(Connection::open_in_memory()?, ":memory:".to_string())
+    }
+
+    fn with_connection(conn: Connection, location: String) -> Result<Self> {
         let store = Self {
             conn: Arc::new(Mutex::new(conn)),
+            location,
+            extracted_api_keys: Arc::new(Mutex::new(Vec::new())),
         };
         store.init_schema()?;
         Ok(store)
     }
 
+    /// Whether the database lives only in memory
+    pub fn is_in_memory(&self) -> bool {
+        self.location == ":memory:" || (self.location.starts_with("file:") && self.location.contains("mode=memory"))
+    }
+
     /// Initialize database table structure
     fn init_schema(&self) -> Result<()> {
         let conn = self.conn.lock()
@@ -90,7 +117,34 @@ impl SqliteStore {
                 content TEXT NOT NULL,
                 timestamp INTEGER NOT NULL,
                 reply_to TEXT,
-                mentions TEXT
+                mentions TEXT,
+                metadata TEXT,
+                priority TEXT NOT NULL DEFAULT 'normal',
+                group_seq INTEGER
+            );
+
+            -- 消息归档表，结构与消息表相同
+            CREATE TABLE IF NOT EXISTS messages_archive (
+                id TEXT PRIMARY KEY,
+                from_agent TEXT NOT NULL,
+                target_type TEXT NOT NULL,
+                target_id TEXT,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                reply_to TEXT,
+                mentions TEXT,
+                metadata TEXT,
+                priority TEXT NOT NULL DEFAULT 'normal',
+                group_seq INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_messages_archive_from_time ON messages_archive(from_agent, timestamp);
+            CREATE INDEX IF NOT EXISTS idx_messages_archive_target_time ON messages_archive(target_type, target_id, timestamp);
+            CREATE INDEX IF NOT EXISTS idx_messages_archive_timestamp ON messages_archive(timestamp);
+
+            -- 归档边界：早于该时间戳的消息都在归档表中
+            CREATE TABLE IF NOT EXISTS message_archive_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                archived_before INTEGER NOT NULL
             );
 
             -- 用户表
@@ -118,10 +172,226 @@ impl SqliteStore {
                 created_at INTEGER NOT NULL
             );
 
+            -- 工具使用每日汇总表
+            CREATE TABLE IF NOT EXISTS tool_stats_daily (
+                date TEXT NOT NULL,
+                tool_id TEXT NOT NULL,
+                calls INTEGER NOT NULL DEFAULT 0,
+                successes INTEGER NOT NULL DEFAULT 0,
+                errors INTEGER NOT NULL DEFAULT 0,
+                total_latency_ms INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (date, tool_id)
+            );
+
+            -- Agent 偏好表
+            CREATE TABLE IF NOT EXISTS agent_preferences (
+                agent_id TEXT PRIMARY KEY,
+                preferences TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            -- Agent 角色修订表
+            CREATE TABLE IF NOT EXISTS role_revisions (
+                agent_id TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                editor TEXT NOT NULL,
+                note TEXT,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (agent_id, revision)
+            );
+
+            -- 未回复消息升级记录表
+            CREATE TABLE IF NOT EXISTS escalations (
+                message_id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                escalated_to TEXT NOT NULL,
+                escalated_at INTEGER NOT NULL
+            );
+
+            -- 登录失败计数表
+            CREATE TABLE IF NOT EXISTS login_failures (
+                key TEXT PRIMARY KEY,
+                failures INTEGER NOT NULL,
+                last_failure_ms INTEGER NOT NULL,
+                locked_until_ms INTEGER
+            );
+
+            -- 幂等键表
+            CREATE TABLE IF NOT EXISTS idempotency_keys (
+                scope TEXT NOT NULL,
+                key TEXT NOT NULL,
+                endpoint TEXT NOT NULL,
+                request_hash TEXT NOT NULL,
+                status INTEGER NOT NULL,
+                response TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                PRIMARY KEY (scope, key)
+            );
+
+            -- 群聊分享令牌表（只保存令牌哈希）
+            CREATE TABLE IF NOT EXISTS share_tokens (
+                id TEXT PRIMARY KEY,
+                group_id TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                message_window INTEGER,
+                revoked_at INTEGER,
+                access_count INTEGER NOT NULL DEFAULT 0,
+                last_accessed_at INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_share_tokens_group ON share_tokens(group_id);
+
+            -- 用户阅读语言表（个人资料中的首选语言）
+            CREATE TABLE IF NOT EXISTS user_languages (
+                user_id TEXT PRIMARY KEY,
+                language TEXT NOT NULL
+            );
+
+            -- 消息译文缓存表（每条消息每种语言最多一份）
+            CREATE TABLE IF NOT EXISTS message_translations (
+                message_id TEXT NOT NULL,
+                language TEXT NOT NULL,
+                source_language TEXT NOT NULL,
+                text TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (message_id, language)
+            );
+
+            -- 组织架构变更表
+            CREATE TABLE IF NOT EXISTS org_changes (
+                id TEXT PRIMARY KEY,
+                actor TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                diff TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_org_changes_timestamp ON org_changes(timestamp);
+
+            -- 活动摘要表
+            CREATE TABLE IF NOT EXISTS digests (
+                id TEXT PRIMARY KEY,
+                period_end INTEGER NOT NULL,
+                generated_at INTEGER NOT NULL,
+                content TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_digests_period_end ON digests(period_end);
+
+            -- 应用自定义状态表
+            CREATE TABLE IF NOT EXISTS app_state (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+
+            -- 消息回应表
+            CREATE TABLE IF NOT EXISTS message_reactions (
+                message_id TEXT NOT NULL,
+                reactor_id TEXT NOT NULL,
+                emoji TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                PRIMARY KEY (message_id, reactor_id, emoji)
+            );
+
+            -- 会话置顶消息表
+            CREATE TABLE IF NOT EXISTS message_pins (
+                session_id TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                pinned_by TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                PRIMARY KEY (session_id, message_id)
+            );
+
+            -- 个人书签表
+            CREATE TABLE IF NOT EXISTS message_bookmarks (
+                owner_id TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                PRIMARY KEY (owner_id, message_id)
+            );
+
+            -- 完整性检查隔离的行
+            CREATE TABLE IF NOT EXISTS quarantined_rows (
+                table_name TEXT NOT NULL,
+                row_id TEXT NOT NULL,
+                data TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL
+            );
+
+            -- 任务表
+            CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                creator TEXT NOT NULL,
+                assignee TEXT,
+                status TEXT NOT NULL DEFAULT 'open',
+                due_at INTEGER,
+                linked_message_ids TEXT NOT NULL DEFAULT '[]',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                due_notified_at INTEGER
+            );
+            CREATE INDEX IF NOT EXISTS idx_tasks_assignee ON tasks(assignee, status);
+            CREATE INDEX IF NOT EXISTS idx_tasks_due ON tasks(due_at);
+
+            -- 主备租约表
+            CREATE TABLE IF NOT EXISTS leases (
+                name TEXT PRIMARY KEY,
+                holder TEXT NOT NULL,
+                token INTEGER NOT NULL,
+                acquired_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL
+            );
+
+            -- 内容审核拦截的输出
+            CREATE TABLE IF NOT EXISTS moderation_quarantine (
+                id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL,
+                reviewed INTEGER NOT NULL DEFAULT 0,
+                content TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_moderation_quarantine_time ON moderation_quarantine(quarantined_at);
+
+            -- Agent 运行草稿，seq 保持写入顺序
+            CREATE TABLE IF NOT EXISTS scratchpad_entries (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                id TEXT NOT NULL UNIQUE,
+                run_id TEXT NOT NULL,
+                size INTEGER NOT NULL,
+                content TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_scratchpad_entries_run ON scratchpad_entries(run_id, seq);
+
+            -- 会话表
+            CREATE TABLE IF NOT EXISTS chat_sessions (
+                id TEXT PRIMARY KEY,
+                agent_id TEXT NOT NULL,
+                closed INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                content TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_chat_sessions_created ON chat_sessions(created_at);
+
+            -- 群聊活动统计，每个群聊一行，由消息落库时增量更新
+            CREATE TABLE IF NOT EXISTS group_stats (
+                group_id TEXT PRIMARY KEY,
+                updated_at INTEGER NOT NULL,
+                content TEXT NOT NULL
+            );
+
             -- Create indexes
-            CREATE INDEX IF NOT EXISTS idx_messages_from ON messages(from_agent);
-            CREATE INDEX IF NOT EXISTS idx_messages_target ON messages(target_type, target_id);
+            -- 按发送者或目标过滤的查询同时按时间排序和限定范围，复合索引可以直接按序读取
+            CREATE INDEX IF NOT EXISTS idx_messages_from_time ON messages(from_agent, timestamp);
+            CREATE INDEX IF NOT EXISTS idx_messages_target_time ON messages(target_type, target_id, timestamp);
             CREATE INDEX IF NOT EXISTS idx_messages_timestamp ON messages(timestamp);
+            -- 旧数据库的单列索引已被复合索引的前缀覆盖
+            DROP INDEX IF EXISTS idx_messages_from;
+            DROP INDEX IF EXISTS idx_messages_target;
+            DROP INDEX IF EXISTS idx_messages_archive_target;
 
             -- Create department index
             CREATE INDEX IF NOT EXISTS idx_departments_parent ON departments(parent_id);
@@ -134,9 +404,93 @@ impl SqliteStore {
             "
         )?;
 
+        // 旧数据库补充后加的列
+        Self::ensure_column(&conn, "messages", "metadata", "TEXT")?;
+        Self::ensure_column(&conn, "messages", "priority", "TEXT NOT NULL DEFAULT 'normal'")?;
+        Self::ensure_column(&conn, "messages", "group_seq", "INTEGER")?;
+        Self::ensure_column(&conn, "messages_archive", "group_seq", "INTEGER")?;
+        Self::ensure_column(&conn, "agents", "role_templates", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "skills", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "availability", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "role_response_language", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "role_tone", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "llm_tokenizer", "TEXT")?;
+        Self::ensure_column(&conn, "agents", "created_at", "INTEGER")?;
+        Self::ensure_column(&conn, "departments", "max_agents", "INTEGER")?;
+        Self::ensure_column(&conn, "departments", "llm_budget", "INTEGER")?;
+        Self::ensure_column(&conn, "groups", "ephemeral", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::ensure_column(&conn, "groups", "expires_at", "INTEGER")?;
+        Self::ensure_column(&conn, "groups", "admins", "TEXT NOT NULL DEFAULT '[]'")?;
+        Self::ensure_column(&conn, "groups", "muted", "TEXT NOT NULL DEFAULT '{}'")?;
+        Self::ensure_column(&conn, "groups", "turn_taking", "TEXT")?;
+        Self::ensure_column(&conn, "groups", "kicked", "TEXT NOT NULL DEFAULT '[]'")?;
+        Self::ensure_column(&conn, "groups", "sync", "TEXT")?;
+        // 依赖补充的列，不能放在上面的建表语句中
+        conn.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_messages_group_seq ON messages(target_id, group_seq);
+             CREATE INDEX IF NOT EXISTS idx_messages_archive_group_seq ON messages_archive(target_id, group_seq);",
+        )?;
+
         Ok(())
     }
 
+    /// 列不存在时通过 ALTER TABLE 添加
+    fn ensure_column(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let exists = stmt
+            .query_map([], |row| row.get::<_, String>(1))?
+            .filter_map(|name| name.ok())
+            .any(|name| name == column);
+
+        if !exists {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        }
+        Ok(())
+    }
+
+    /// 把 agents 表中的明文 API Key 换成 `env:` 引用（见 [`env_reference_for`]），返回取出的明文，
+    /// 由调用方打印一次供运维人员设置对应的环境变量；已经是引用的行不变
+    pub async fn migrate_plaintext_api_keys(&self) -> Result<Vec<ExtractedSecret>> {
+        self.execute(|conn| {
+            let tx = conn.transaction()?;
+            let rows: Vec<(String, String)> = {
+                let mut stmt = tx.prepare("SELECT id, llm_api_key FROM agents ORDER BY id")?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+            let mut extracted = Vec::new();
+            for (agent_id, value) in rows {
+                if SecretRef::parse(&value).is_reference() {
+                    continue;
+                }
+                let reference = env_reference_for(&agent_id);
+                tx.execute("UPDATE agents SET llm_api_key = ?1 WHERE id = ?2", [&reference, &agent_id])?;
+                extracted.push(ExtractedSecret { agent_id, reference, value });
+            }
+            tx.commit()?;
+            Ok(extracted)
+        }).await
+    }
+
+    /// 消息查询的查询计划（`EXPLAIN QUERY PLAN` 每一步的说明），用于在测试和基准中确认走了预期的索引
+    #[cfg(feature = "query-plan")]
+    pub async fn explain_messages(&self, filter: MessageFilter) -> Result<Vec<String>> {
+        self.execute(move |conn| {
+            let (sql, params) = messages_query(conn, &filter)?;
+            query_plan(conn, &sql, rusqlite::params_from_iter(params))
+        }).await
+    }
+
+    /// [`Store::load_messages_by_agent`] 的查询计划
+    #[cfg(feature = "query-plan")]
+    pub async fn explain_messages_by_agent(&self, agent_id: &str, limit: usize) -> Result<Vec<String>> {
+        let agent_id = agent_id.to_string();
+        self.execute(move |conn| {
+            let sql = messages_by_agent_sql(archived_before(conn)?.is_some());
+            query_plan(conn, &sql, rusqlite::params![agent_id, limit as i64])
+        }).await
+    }
+
     /// 在阻塞线程池中执行数据库操作
     async fn execute<F, T>(&self, f: F) -> Result<T>
     where
@@ -158,24 +512,29 @@ impl SqliteStore {
 impl Store for SqliteStore {
     async fn save_organization(&self, org: &Organization) -> Result<()> {
         let org = org.clone();
-        self.execute(move |conn| {
+        let extracted = self.execute(move |conn| {
+            let tx = conn.transaction()?;
+            let mut extracted = Vec::new();
             // Clear old data
-            conn.execute("DELETE FROM agents", [])?;
-            conn.execute("DELETE FROM departments", [])?;
+            tx.execute("DELETE FROM agents", [])?;
+            tx.execute("DELETE FROM departments", [])?;
 
             // Insert departments
             for dept in &org.departments {
                 let parent_id = dept.parent_id.as_deref();
                 let leader_id = dept.leader_id.as_deref();
 
-                conn.execute(
-                    "INSERT INTO departments (id, name, parent_id, leader_id)
-                     VALUES (?1, ?2, ?3, ?4)",
+                // 重复 ID 以最后一条为准，不因主键冲突导致整体保存失败
+                tx.execute(
+                    "INSERT OR REPLACE INTO departments (id, name, parent_id, leader_id, max_agents, llm_budget)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
                     rusqlite::params![
                         &dept.id,
                         &dept.name,
                         parent_id,
                         leader_id,
+                        dept.max_agents.map(|n| n as i64),
+                        dept.llm_budget.map(|n| n as i64),
                     ],
                 )?;
             }
@@ -185,13 +544,22 @@ impl Store for SqliteStore {
                 let dept_id = agent.department_id.as_deref();
                 let resp_json = serde_json::to_string(&agent.role.responsibilities).unwrap_or_default();
                 let exp_json = serde_json::to_string(&agent.role.expertise).unwrap_or_default();
+                let templates_json = (!agent.role.templates.is_empty())
+                    .then(|| serde_json::to_string(&agent.role.templates).unwrap_or_default());
+                let skills_json = (!agent.skills.is_empty())
+                    .then(|| serde_json::to_string(&agent.skills).unwrap_or_default());
+                let availability_json = agent
+                    .availability
+                    .as_ref()
+                    .map(|a| serde_json::to_string(a).unwrap_or_default());
 
-                conn.execute(
-                    "INSERT INTO agents (
+                tx.execute(
+                    "INSERT OR REPLACE INTO agents (
                         id, name, department_id,
                         role_title, role_responsibilities, role_expertise, role_system_prompt,
-                        llm_model, llm_api_key, llm_base_url
-                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                        llm_model, llm_api_key, llm_base_url, role_templates, skills, availability,
+                        role_response_language, role_tone, llm_tokenizer, created_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
                     rusqlite::params![
                         &agent.id,
                         &agent.name,
@@ -201,14 +569,24 @@ impl Store for SqliteStore {
                         exp_json,
                         &agent.role.system_prompt,
                         &agent.llm_config.model,
-                        &agent.llm_config.api_key,
+                        persisted_api_key(agent, &mut extracted),
                         &agent.llm_config.base_url,
+                        templates_json,
+                        skills_json,
+                        availability_json,
+                        &agent.role.response_language,
+                        &agent.role.tone,
+                        &agent.llm_config.tokenizer,
+                        agent.created_at,
                     ],
                 )?;
             }
 
-            Ok(())
-        }).await
+            tx.commit()?;
+            Ok(extracted)
+        }).await?;
+        self.extracted_api_keys.lock().unwrap_or_else(|e| e.into_inner()).extend(extracted);
+        Ok(())
     }
 
     async fn load_organization(&self) -> Result<Organization> {
@@ -216,60 +594,68 @@ impl Store for SqliteStore {
             let mut org = Organization::new();
 
             // Load departments
-            let mut stmt = conn.prepare(
-                "SELECT id, name, parent_id, leader_id FROM departments"
-            )?;
-
-            let dept_iter = stmt.query_map([], |row| {
-                Ok(Department {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    parent_id: row.get(2)?,
-                    leader_id: row.get(3)?,
-                })
-            })?;
-
-            for dept in dept_iter {
-                org.add_department(dept?);
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM departments", DEPARTMENT_COLUMNS))?;
+            for dept in stmt.query_map([], department_from_row)? {
+                if let Some(previous) = org.upsert_department(dept?) {
+                    tracing::warn!("Duplicate department id {} in store, keeping the last row", previous.id);
+                }
             }
 
             // Load Agent
-            let mut stmt = conn.prepare(
-                "SELECT
-                    id, name, department_id,
-                    role_title, role_responsibilities, role_expertise, role_system_prompt,
-                    llm_model, llm_api_key, llm_base_url
-                 FROM agents"
-            )?;
-
-            let agent_iter = stmt.query_map([], |row| {
-                let responsibilities: String = row.get(4)?;
-                let expertise: String = row.get(5)?;
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM agents", AGENT_COLUMNS))?;
+            for agent in stmt.query_map([], agent_from_row)? {
+                if let Some(previous) = org.upsert_agent(agent?) {
+                    tracing::warn!("Duplicate agent id {} in store, keeping the last row", previous.id);
+                }
+            }
 
-                Ok(Agent {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    department_id: row.get(2)?,
-                    role: Role {
-                        title: row.get(3)?,
-                        responsibilities: serde_json::from_str(&responsibilities).unwrap_or_default(),
-                        expertise: serde_json::from_str(&expertise).unwrap_or_default(),
-                        system_prompt: row.get(6)?,
-                    },
-                    llm_config: LLMConfig {
-                        model: row.get(7)?,
-                        api_key: row.get(8)?,
-                        base_url: row.get(9)?,
-                    },
-                    mode: AgentMode::Passive, // 默认为被动模式
-                })
-            })?;
+            Ok(org)
+        }).await
+    }
 
-            for agent in agent_iter {
-                org.add_agent(agent?);
+    async fn load_department(&self, department_id: &str) -> Result<Option<Department>> {
+        let department_id = department_id.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                &format!("SELECT {} FROM departments WHERE id = ?1", DEPARTMENT_COLUMNS),
+                [&department_id],
+                department_from_row,
+            );
+            match result {
+                Ok(dept) => Ok(Some(dept)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e.into()),
             }
+        }).await
+    }
 
-            Ok(org)
+    async fn load_department_members(&self, department_id: &str) -> Result<Vec<Agent>> {
+        let department_id = department_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM agents WHERE department_id = ?1",
+                AGENT_COLUMNS
+            ))?;
+            let agents = stmt
+                .query_map([&department_id], agent_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(agents)
+        }).await
+    }
+
+    async fn load_agent(&self, agent_id: &str) -> Result<Option<Agent>> {
+        let agent_id = agent_id.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                &format!("SELECT {} FROM agents WHERE id = ?1", AGENT_COLUMNS),
+                [&agent_id],
+                agent_from_row,
+            );
+            match result {
+                Ok(agent) => Ok(Some(agent)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
         }).await
     }
 
@@ -277,16 +663,28 @@ impl Store for SqliteStore {
         let group = group.clone();
         self.execute(move |conn| {
             let members_json = serde_json::to_string(&group.members).unwrap_or_default();
+            let admins_json = serde_json::to_string(&group.admins).unwrap_or_default();
+            let muted_json = serde_json::to_string(&group.muted).unwrap_or_default();
+            let turn_taking_json = group.turn_taking.as_ref().and_then(|s| serde_json::to_string(s).ok());
+            let kicked_json = serde_json::to_string(&group.kicked).unwrap_or_default();
+            let sync_json = group.sync.as_ref().and_then(|s| serde_json::to_string(s).ok());
 
             conn.execute(
-                "INSERT OR REPLACE INTO groups (id, name, creator_id, members, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                "INSERT OR REPLACE INTO groups (id, name, creator_id, members, created_at, ephemeral, expires_at, admins, muted, turn_taking, kicked, sync)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
                 rusqlite::params![
                     &group.id,
                     &group.name,
                     &group.creator_id,
                     members_json,
                     &group.created_at,
+                    group.ephemeral,
+                    group.expires_at,
+                    admins_json,
+                    muted_json,
+                    turn_taking_json,
+                    kicked_json,
+                    sync_json,
                 ],
             )?;
             Ok(())
@@ -296,20 +694,35 @@ impl Store for SqliteStore {
     async fn load_groups(&self) -> Result<Vec<Group>> {
         self.execute(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, name, creator_id, members, created_at FROM groups"
+                "SELECT id, name, creator_id, members, created_at, ephemeral, expires_at, admins, muted, turn_taking, kicked, sync FROM groups"
             )?;
 
             let group_iter = stmt.query_map([], |row| {
                 let members: String = row.get(3)?;
                 let created_at: i64 = row.get(4)?;
+                let admins: String = row.get(7)?;
+                let muted: String = row.get(8)?;
+                let turn_taking: Option<String> = row.get(9)?;
+                let kicked: String = row.get(10)?;
+                let sync: Option<String> = row.get(11)?;
 
-                Ok(Group {
+                let mut group = Group {
                     id: row.get(0)?,
                     name: row.get(1)?,
                     creator_id: row.get(2)?,
                     members: serde_json::from_str(&members).unwrap_or_default(),
                     created_at,
-                })
+                    ephemeral: row.get(5)?,
+                    expires_at: row.get(6)?,
+                    admins: serde_json::from_str(&admins).unwrap_or_default(),
+                    muted: serde_json::from_str(&muted).unwrap_or_default(),
+                    turn_taking: turn_taking.and_then(|s| serde_json::from_str(&s).ok()),
+                    kicked: serde_json::from_str(&kicked).unwrap_or_default(),
+                    sync: sync.and_then(|s| serde_json::from_str(&s).ok()),
+                };
+                // 旧版本的群聊没有管理员列
+                group.ensure_creator_admin();
+                Ok(group)
             })?;
 
             let mut groups = Vec::new();
@@ -338,8 +751,8 @@ impl Store for SqliteStore {
             };
 
             conn.execute(
-                "INSERT INTO messages (id, from_agent, target_type, target_id, content, timestamp, reply_to, mentions)
-                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                "INSERT INTO messages (id, from_agent, target_type, target_id, content, timestamp, reply_to, mentions, metadata, priority, group_seq)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                 rusqlite::params![
                     &message.id,
                     &message.from,
@@ -353,6 +766,9 @@ impl Store for SqliteStore {
                     } else {
                         Some(message.mentions.join(","))
                     },
+                    encode_metadata(&message.metadata),
+                    message.priority.as_str(),
+                    message.group_seq().map(|seq| seq as i64),
                 ],
             )?;
             Ok(())
@@ -371,8 +787,8 @@ impl Store for SqliteStore {
                 };
 
                 tx.execute(
-                    "INSERT INTO messages (id, from_agent, target_type, target_id, content, timestamp, reply_to, mentions)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    "INSERT INTO messages (id, from_agent, target_type, target_id, content, timestamp, reply_to, mentions, metadata, priority, group_seq)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                     rusqlite::params![
                         &message.id,
                         &message.from,
@@ -386,6 +802,9 @@ impl Store for SqliteStore {
                         } else {
                             Some(message.mentions.join(","))
                         },
+                        encode_metadata(&message.metadata),
+                        message.priority.as_str(),
+                        message.group_seq().map(|seq| seq as i64),
                     ],
                 )?;
             }
@@ -397,97 +816,150 @@ impl Store for SqliteStore {
 
     async fn load_messages(&self, filter: MessageFilter) -> Result<Vec<Message>> {
         self.execute(move |conn| {
-            let mut conditions = Vec::new();
-            let mut from_val: Option<String> = None;
-            let mut target_type_val: Option<String> = None;
-            let mut to_val: Option<String> = None;
-            let mut since_val: Option<i64> = None;
-
-            if let Some(from) = filter.from {
-                conditions.push("from_agent = ?".to_string());
-                from_val = Some(from);
-            }
+            let (sql, params) = messages_query(conn, &filter)?;
+            let mut stmt = conn.prepare(&sql)?;
+            let messages = stmt
+                .query_map(rusqlite::params_from_iter(params), message_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(messages)
+        }).await
+    }
 
-            if let Some(target_type) = filter.target_type {
-                conditions.push("target_type = ?".to_string());
-                target_type_val = Some(target_type);
-            }
+    async fn load_messages_by_agent(&self, agent_id: &str, limit: usize) -> Result<Vec<Message>> {
+        let agent_id = agent_id.to_string();
+        let limit = MessageFilter::new().limit(limit).effective_limit();
+        self.execute(move |conn| {
+            let sql = messages_by_agent_sql(archived_before(conn)?.is_some());
+            let mut stmt = conn.prepare(&sql)?;
+            let messages = stmt
+                .query_map(rusqlite::params![agent_id, limit as i64], message_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(messages)
+        }).await
+    }
 
-            if let Some(to) = filter.to {
-                conditions.push("target_id = ?".to_string());
-                to_val = Some(to);
+    async fn load_message(&self, message_id: &str) -> Result<Option<Message>> {
+        let message_id = message_id.to_string();
+        self.execute(move |conn| {
+            for table in ["messages", "messages_archive"] {
+                let result = conn.query_row(
+                    &format!("SELECT {} FROM {} WHERE id = ?1", MESSAGE_COLUMNS, table),
+                    [&message_id],
+                    message_from_row,
+                );
+                match result {
+                    Ok(message) => return Ok(Some(message)),
+                    Err(rusqlite::Error::QueryReturnedNoRows) => {}
+                    Err(e) => return Err(anyhow::anyhow!(e)),
+                }
             }
+            Ok(None)
+        }).await
+    }
 
-            if let Some(since) = filter.since {
-                conditions.push("timestamp >= ?".to_string());
-                since_val = Some(since);
+    async fn max_group_seq(&self, group_id: &str) -> Result<u64> {
+        let group_id = group_id.to_string();
+        self.execute(move |conn| {
+            let mut max = 0i64;
+            for table in ["messages", "messages_archive"] {
+                let seq: Option<i64> = conn.query_row(
+                    &format!("SELECT MAX(group_seq) FROM {} WHERE target_type = 'group' AND target_id = ?1", table),
+                    [&group_id],
+                    |row| row.get(0),
+                )?;
+                max = max.max(seq.unwrap_or(0));
             }
+            Ok(max as u64)
+        }).await
+    }
 
-            let where_clause = if conditions.is_empty() {
-                "".to_string()
-            } else {
-                format!("WHERE {}", conditions.join(" AND "))
-            };
-
+    async fn load_group_messages_by_seq(&self, group_id: &str, from_seq: u64, to_seq: u64) -> Result<Vec<Message>> {
+        let group_id = group_id.to_string();
+        self.execute(move |conn| {
             let sql = format!(
-                "SELECT id, from_agent, target_type, target_id, content, timestamp, reply_to, mentions
-                 FROM messages
-                 {}
-                 ORDER BY timestamp DESC
-                 LIMIT {}",
-                where_clause,
-                filter.limit
+                "SELECT {cols} FROM (SELECT {cols} FROM messages UNION ALL SELECT {cols} FROM messages_archive)
+                 WHERE target_type = 'group' AND target_id = ?1 AND group_seq BETWEEN ?2 AND ?3
+                 ORDER BY group_seq",
+                cols = MESSAGE_COLUMNS
             );
-
             let mut stmt = conn.prepare(&sql)?;
+            let messages = stmt
+                .query_map(rusqlite::params![group_id, from_seq as i64, to_seq as i64], message_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(messages)
+        }).await
+    }
 
-            // Bind parameters in order
-            let mut param_idx = 1;
-            if let Some(ref val) = from_val {
-                stmt.raw_bind_parameter(param_idx, val.as_str())?;
-                param_idx += 1;
-            }
-            if let Some(ref val) = target_type_val {
-                stmt.raw_bind_parameter(param_idx, val.as_str())?;
-                param_idx += 1;
-            }
-            if let Some(ref val) = to_val {
-                stmt.raw_bind_parameter(param_idx, val.as_str())?;
-                param_idx += 1;
+    async fn archive_messages_before(&self, timestamp: i64) -> Result<usize> {
+        self.execute(move |conn| {
+            let tx = conn.transaction()?;
+            tx.execute(
+                &format!(
+                    "INSERT OR REPLACE INTO messages_archive ({cols}) SELECT {cols} FROM messages WHERE timestamp < ?1",
+                    cols = MESSAGE_COLUMNS
+                ),
+                [timestamp],
+            )?;
+            let moved = tx.execute("DELETE FROM messages WHERE timestamp < ?1", [timestamp])?;
+            tx.execute(
+                "INSERT INTO message_archive_state (id, archived_before) VALUES (1, ?1)
+                 ON CONFLICT(id) DO UPDATE SET archived_before = MAX(archived_before, excluded.archived_before)",
+                [timestamp],
+            )?;
+            tx.commit()?;
+            Ok(moved)
+        }).await
+    }
+
+    async fn delete_message(&self, message_id: &str) -> Result<bool> {
+        let message_id = message_id.to_string();
+        self.execute(move |conn| {
+            let tx = conn.transaction()?;
+            for table in MESSAGE_DEPENDENT_TABLES {
+                tx.execute(&format!("DELETE FROM {} WHERE message_id = ?1", table), [&message_id])?;
             }
-            if let Some(val) = since_val {
-                stmt.raw_bind_parameter(param_idx, val)?;
+            let mut deleted = 0;
+            for table in ["messages", "messages_archive"] {
+                deleted += tx.execute(&format!("DELETE FROM {} WHERE id = ?1", table), [&message_id])?;
             }
+            tx.commit()?;
+            Ok(deleted > 0)
+        }).await
+    }
 
-            let msg_iter = stmt.raw_query().mapped(|row| {
-                let target_type: String = row.get(2)?;
-                let target_id: Option<String> = row.get(3)?;
-
-                let target = match target_type.as_str() {
-                    "direct" => MessageTarget::Direct(target_id.unwrap_or_default()),
-                    "group" => MessageTarget::Group(target_id.unwrap_or_default()),
-                    _ => MessageTarget::Direct(String::new()), // 未知类型默认为空direct
-                };
-
-                Ok(Message {
-                    id: row.get(0)?,
-                    from: row.get(1)?,
-                    to: target,
-                    content: row.get(4)?,
-                    timestamp: row.get(5)?,
-                    reply_to: row.get::<_, Option<String>>(6)?,
-                    mentions: row.get::<_, Option<String>>(7)?
-                        .map(|s| s.split(',').map(|s| s.to_string()).collect())
-                        .unwrap_or_default(),
-                })
-            });
-
-            let mut messages = Vec::new();
-            for msg in msg_iter {
-                messages.push(msg?);
+    async fn purge_messages_before(&self, timestamp: i64) -> Result<usize> {
+        self.execute(move |conn| {
+            let tx = conn.transaction()?;
+            for table in MESSAGE_DEPENDENT_TABLES {
+                tx.execute(
+                    &format!(
+                        "DELETE FROM {} WHERE message_id IN (
+                            SELECT id FROM messages WHERE timestamp < ?1
+                            UNION SELECT id FROM messages_archive WHERE timestamp < ?1
+                        )",
+                        table
+                    ),
+                    [timestamp],
+                )?;
+            }
+            let mut purged = 0;
+            for table in ["messages", "messages_archive"] {
+                purged += tx.execute(&format!("DELETE FROM {} WHERE timestamp < ?1", table), [timestamp])?;
             }
+            tx.commit()?;
+            Ok(purged)
+        }).await
+    }
 
-            Ok(messages)
+    async fn message_tier_counts(&self) -> Result<MessageTierCounts> {
+        self.execute(move |conn| {
+            let hot: i64 = conn.query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0))?;
+            let archived: i64 = conn.query_row("SELECT COUNT(*) FROM messages_archive", [], |row| row.get(0))?;
+            Ok(MessageTierCounts {
+                hot: hot as usize,
+                archived: archived as usize,
+                archived_before: archived_before(conn)?,
+            })
         }).await
     }
 
@@ -515,6 +987,13 @@ impl Store for SqliteStore {
                     &user.created_at,
                 ],
             )?;
+            match &user.preferred_language {
+                Some(language) => conn.execute(
+                    "INSERT OR REPLACE INTO user_languages (user_id, language) VALUES (?1, ?2)",
+                    [&user.id, language],
+                )?,
+                None => conn.execute("DELETE FROM user_languages WHERE user_id = ?1", [&user.id])?,
+            };
             Ok(())
         }).await
     }
@@ -523,7 +1002,8 @@ impl Store for SqliteStore {
         let username = username.to_string();
         self.execute(move |conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, username, name, email, password_hash, employee_id, position, department, created_at FROM users WHERE username = ?1"
+                "SELECT id, username, name, email, password_hash, employee_id, position, department, created_at, l.language
+                 FROM users LEFT JOIN user_languages l ON l.user_id = users.id WHERE username = ?1"
             )?;
 
             let user_result = stmt.query_row([username], |row| {
@@ -544,6 +1024,7 @@ impl Store for SqliteStore {
                     position,
                     department: row.get(7)?,
                     created_at: row.get(8)?,
+                    preferred_language: row.get(9)?,
                 })
             });
 
@@ -558,7 +1039,8 @@ impl Store for SqliteStore {
     async fn load_users(&self) -> Result<Vec<User>> {
         self.execute(|conn| {
             let mut stmt = conn.prepare(
-                "SELECT id, username, name, email, password_hash, employee_id, position, department, created_at FROM users"
+                "SELECT id, username, name, email, password_hash, employee_id, position, department, created_at, l.language
+                 FROM users LEFT JOIN user_languages l ON l.user_id = users.id"
             )?;
 
             let user_iter = stmt.query_map([], |row| {
@@ -579,6 +1061,7 @@ impl Store for SqliteStore {
                     position,
                     department: row.get(7)?,
                     created_at: row.get(8)?,
+                    preferred_language: row.get(9)?,
                 })
             })?;
 
@@ -707,4 +1190,1438 @@ impl Store for SqliteStore {
             Ok(codes)
         }).await
     }
+    async fn save_tool_stats(&self, date: &str, stats: &[ToolUsage]) -> Result<()> {
+        let date = date.to_string();
+        let stats = stats.to_vec();
+        self.execute(move |conn| {
+            let tx = conn.transaction()?;
+
+            for usage in &stats {
+                tx.execute(
+                    "INSERT OR REPLACE INTO tool_stats_daily (date, tool_id, calls, successes, errors, total_latency_ms)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    rusqlite::params![
+                        &date,
+                        &usage.tool_id,
+                        usage.calls as i64,
+                        usage.successes as i64,
+                        usage.errors as i64,
+                        usage.total_latency_ms as i64,
+                    ],
+                )?;
+            }
+
+            tx.commit()?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_tool_stats(&self, date: &str) -> Result<Vec<ToolUsage>> {
+        let date = date.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT tool_id, calls, successes, errors, total_latency_ms FROM tool_stats_daily WHERE date = ?1"
+            )?;
+
+            let usage_iter = stmt.query_map([date], |row| {
+                Ok(ToolUsage {
+                    tool_id: row.get(0)?,
+                    calls: row.get::<_, i64>(1)? as u64,
+                    successes: row.get::<_, i64>(2)? as u64,
+                    errors: row.get::<_, i64>(3)? as u64,
+                    total_latency_ms: row.get::<_, i64>(4)? as u64,
+                })
+            })?;
+
+            let mut usages = Vec::new();
+            for usage in usage_iter {
+                usages.push(usage?);
+            }
+
+            Ok(usages)
+        }).await
+    }
+
+    async fn save_agent_preferences(&self, agent_id: &str, preferences: &serde_json::Value) -> Result<()> {
+        let agent_id = agent_id.to_string();
+        let preferences_json = serde_json::to_string(preferences)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO agent_preferences (agent_id, preferences, updated_at)
+                 VALUES (?1, ?2, ?3)",
+                rusqlite::params![&agent_id, preferences_json, chrono::Utc::now().timestamp()],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_agent_preferences(&self, agent_id: &str) -> Result<Option<serde_json::Value>> {
+        let agent_id = agent_id.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                "SELECT preferences FROM agent_preferences WHERE agent_id = ?1",
+                [agent_id],
+                |row| row.get::<_, String>(0),
+            );
+
+            match result {
+                Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            }
+        }).await
+    }
+
+    async fn delete_agent_preferences(&self, agent_id: &str) -> Result<()> {
+        let agent_id = agent_id.to_string();
+        self.execute(move |conn| {
+            conn.execute("DELETE FROM agent_preferences WHERE agent_id = ?1", [agent_id])?;
+            Ok(())
+        }).await
+    }
+
+    async fn save_role_revision(&self, revision: &RoleRevision) -> Result<()> {
+        let revision = revision.clone();
+        let role_json = serde_json::to_string(&revision.role)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT INTO role_revisions (agent_id, revision, role, editor, note, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    &revision.agent_id,
+                    revision.revision,
+                    role_json,
+                    &revision.editor,
+                    &revision.note,
+                    revision.created_at,
+                ],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_role_revisions(&self, agent_id: &str) -> Result<Vec<RoleRevision>> {
+        let agent_id = agent_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT agent_id, revision, role, editor, note, created_at FROM role_revisions
+                 WHERE agent_id = ?1 ORDER BY revision",
+            )?;
+            let rows = stmt
+                .query_map([agent_id], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, u32>(1)?,
+                        row.get::<_, String>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, i64>(5)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            rows.into_iter()
+                .map(|(agent_id, revision, role, editor, note, created_at)| {
+                    Ok(RoleRevision {
+                        agent_id,
+                        revision,
+                        role: serde_json::from_str(&role)?,
+                        editor,
+                        note,
+                        created_at,
+                    })
+                })
+                .collect()
+        }).await
+    }
+
+    async fn save_escalation(&self, escalation: &Escalation) -> Result<()> {
+        let escalation = escalation.clone();
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO escalations (message_id, agent_id, escalated_to, escalated_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    &escalation.message_id,
+                    &escalation.agent_id,
+                    &escalation.escalated_to,
+                    escalation.escalated_at,
+                ],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_escalation(&self, message_id: &str) -> Result<Option<Escalation>> {
+        let message_id = message_id.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                "SELECT message_id, agent_id, escalated_to, escalated_at FROM escalations WHERE message_id = ?1",
+                [message_id],
+                |row| {
+                    Ok(Escalation {
+                        message_id: row.get(0)?,
+                        agent_id: row.get(1)?,
+                        escalated_to: row.get(2)?,
+                        escalated_at: row.get(3)?,
+                    })
+                },
+            );
+
+            match result {
+                Ok(escalation) => Ok(Some(escalation)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            }
+        }).await
+    }
+
+    async fn save_login_failures(&self, failures: &LoginFailures) -> Result<()> {
+        let failures = failures.clone();
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO login_failures (key, failures, last_failure_ms, locked_until_ms)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    &failures.key,
+                    failures.failures,
+                    failures.last_failure_ms,
+                    failures.locked_until_ms,
+                ],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_login_failures(&self, key: &str) -> Result<Option<LoginFailures>> {
+        let key = key.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                "SELECT key, failures, last_failure_ms, locked_until_ms FROM login_failures WHERE key = ?1",
+                [key],
+                |row| {
+                    Ok(LoginFailures {
+                        key: row.get(0)?,
+                        failures: row.get(1)?,
+                        last_failure_ms: row.get(2)?,
+                        locked_until_ms: row.get(3)?,
+                    })
+                },
+            );
+
+            match result {
+                Ok(failures) => Ok(Some(failures)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            }
+        }).await
+    }
+
+    async fn delete_login_failures(&self, key: &str) -> Result<()> {
+        let key = key.to_string();
+        self.execute(move |conn| {
+            conn.execute("DELETE FROM login_failures WHERE key = ?1", [key])?;
+            Ok(())
+        }).await
+    }
+
+    async fn save_idempotency_record(&self, record: &IdempotencyRecord) -> Result<()> {
+        let record = record.clone();
+        let response_json = serde_json::to_string(&record.response)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO idempotency_keys
+                 (scope, key, endpoint, request_hash, status, response, created_at, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                rusqlite::params![
+                    &record.scope,
+                    &record.key,
+                    &record.endpoint,
+                    &record.request_hash,
+                    record.status,
+                    response_json,
+                    record.created_at,
+                    record.expires_at,
+                ],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_idempotency_record(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+        let (scope, key) = (scope.to_string(), key.to_string());
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                "SELECT scope, key, endpoint, request_hash, status, response, created_at, expires_at
+                 FROM idempotency_keys WHERE scope = ?1 AND key = ?2",
+                [scope, key],
+                |row| {
+                    let response: String = row.get(5)?;
+                    Ok(IdempotencyRecord {
+                        scope: row.get(0)?,
+                        key: row.get(1)?,
+                        endpoint: row.get(2)?,
+                        request_hash: row.get(3)?,
+                        status: row.get(4)?,
+                        response: serde_json::from_str(&response).unwrap_or_default(),
+                        created_at: row.get(6)?,
+                        expires_at: row.get(7)?,
+                    })
+                },
+            );
+
+            match result {
+                Ok(record) => Ok(Some(record)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            }
+        }).await
+    }
+
+    async fn delete_expired_idempotency_records(&self, now: i64) -> Result<usize> {
+        self.execute(move |conn| {
+            let deleted = conn.execute("DELETE FROM idempotency_keys WHERE expires_at <= ?1", [now])?;
+            Ok(deleted)
+        }).await
+    }
+
+    async fn save_share_token(&self, token: &ShareToken) -> Result<()> {
+        let token = token.clone();
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO share_tokens
+                 (id, group_id, created_by, created_at, expires_at, message_window, revoked_at, access_count, last_accessed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                rusqlite::params![
+                    &token.id,
+                    &token.group_id,
+                    &token.created_by,
+                    token.created_at,
+                    token.expires_at,
+                    token.message_window.map(|w| w as i64),
+                    token.revoked_at,
+                    token.access_count as i64,
+                    token.last_accessed_at,
+                ],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_share_token(&self, id: &str) -> Result<Option<ShareToken>> {
+        let id = id.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                &format!("SELECT {} FROM share_tokens WHERE id = ?1", SHARE_TOKEN_COLUMNS),
+                [&id],
+                share_token_from_row,
+            );
+            match result {
+                Ok(token) => Ok(Some(token)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            }
+        }).await
+    }
+
+    async fn load_share_tokens(&self, group_id: &str) -> Result<Vec<ShareToken>> {
+        let group_id = group_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM share_tokens WHERE group_id = ?1 ORDER BY created_at DESC, id",
+                SHARE_TOKEN_COLUMNS
+            ))?;
+            let tokens = stmt
+                .query_map([&group_id], share_token_from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(tokens)
+        }).await
+    }
+
+    async fn save_translation(&self, translation: &MessageTranslation) -> Result<()> {
+        let translation = translation.clone();
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO message_translations (message_id, language, source_language, text, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![
+                    &translation.message_id,
+                    &translation.language,
+                    &translation.source_language,
+                    &translation.text,
+                    translation.created_at,
+                ],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_translation(&self, message_id: &str, language: &str) -> Result<Option<MessageTranslation>> {
+        let message_id = message_id.to_string();
+        let language = language.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                "SELECT message_id, language, source_language, text, created_at
+                 FROM message_translations WHERE message_id = ?1 AND language = ?2",
+                [&message_id, &language],
+                |row| {
+                    Ok(MessageTranslation {
+                        message_id: row.get(0)?,
+                        language: row.get(1)?,
+                        source_language: row.get(2)?,
+                        text: row.get(3)?,
+                        created_at: row.get(4)?,
+                    })
+                },
+            );
+            match result {
+                Ok(translation) => Ok(Some(translation)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            }
+        }).await
+    }
+
+    async fn save_org_change(&self, entry: &OrgChangeEntry) -> Result<()> {
+        let entry = entry.clone();
+        let diff_json = serde_json::to_string(&entry.diff)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT INTO org_changes (id, actor, timestamp, diff) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![&entry.id, &entry.actor, entry.timestamp, diff_json],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_org_changes(&self, since: i64, limit: usize) -> Result<Vec<OrgChangeEntry>> {
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, actor, timestamp, diff FROM org_changes
+                 WHERE timestamp > ?1 ORDER BY timestamp DESC, rowid DESC LIMIT ?2",
+            )?;
+            let entries = stmt
+                .query_map(rusqlite::params![since, limit as i64], |row| {
+                    let diff: String = row.get(3)?;
+                    Ok(OrgChangeEntry {
+                        id: row.get(0)?,
+                        actor: row.get(1)?,
+                        timestamp: row.get(2)?,
+                        diff: serde_json::from_str::<OrgDiff>(&diff).unwrap_or_default(),
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(entries)
+        }).await
+    }
+
+    async fn save_digest(&self, digest: &Digest) -> Result<()> {
+        let digest = digest.clone();
+        let content = serde_json::to_string(&digest)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO digests (id, period_end, generated_at, content) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![&digest.id, digest.period_end, digest.generated_at, content],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_digests(&self, limit: usize) -> Result<Vec<Digest>> {
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, content FROM digests ORDER BY period_end DESC, generated_at DESC LIMIT ?1",
+            )?;
+            let rows = stmt
+                .query_map(rusqlite::params![limit as i64], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let digests = rows
+                .into_iter()
+                .filter_map(|(id, content)| match serde_json::from_str::<Digest>(&content) {
+                    Ok(digest) => Some(digest),
+                    Err(e) => {
+                        tracing::warn!("Skipping unreadable digest {}: {}", id, e);
+                        None
+                    }
+                })
+                .collect();
+            Ok(digests)
+        }).await
+    }
+
+    async fn save_app_state(&self, key: &str, value: &serde_json::Value) -> Result<()> {
+        let key = key.to_string();
+        let value_json = serde_json::to_string(value)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO app_state (key, value, updated_at) VALUES (?1, ?2, ?3)",
+                rusqlite::params![&key, value_json, chrono::Utc::now().timestamp()],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_app_state(&self, key: &str) -> Result<Option<serde_json::Value>> {
+        let key = key.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                "SELECT value FROM app_state WHERE key = ?1",
+                [key],
+                |row| row.get::<_, String>(0),
+            );
+
+            match result {
+                Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(anyhow::anyhow!(e)),
+            }
+        }).await
+    }
+
+    async fn delete_app_state(&self, key: &str) -> Result<()> {
+        let key = key.to_string();
+        self.execute(move |conn| {
+            conn.execute("DELETE FROM app_state WHERE key = ?1", [key])?;
+            Ok(())
+        }).await
+    }
+
+    async fn add_reaction(&self, reaction: &MessageReaction) -> Result<bool> {
+        let reaction = reaction.clone();
+        self.execute(move |conn| {
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO message_reactions (message_id, reactor_id, emoji, timestamp)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![
+                    &reaction.message_id,
+                    &reaction.reactor_id,
+                    &reaction.emoji,
+                    reaction.timestamp,
+                ],
+            )?;
+            Ok(inserted > 0)
+        }).await
+    }
+
+    async fn remove_reaction(&self, message_id: &str, reactor_id: &str, emoji: &str) -> Result<bool> {
+        let (message_id, reactor_id, emoji) = (message_id.to_string(), reactor_id.to_string(), emoji.to_string());
+        self.execute(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM message_reactions WHERE message_id = ?1 AND reactor_id = ?2 AND emoji = ?3",
+                [message_id, reactor_id, emoji],
+            )?;
+            Ok(deleted > 0)
+        }).await
+    }
+
+    async fn load_reactions(&self, message_ids: &[String]) -> Result<Vec<MessageReaction>> {
+        if message_ids.is_empty() {
+            return Ok(vec![]);
+        }
+        let message_ids = message_ids.to_vec();
+        self.execute(move |conn| {
+            let placeholders = vec!["?"; message_ids.len()].join(", ");
+            let mut stmt = conn.prepare(&format!(
+                "SELECT message_id, reactor_id, emoji, timestamp FROM message_reactions
+                 WHERE message_id IN ({})
+                 ORDER BY timestamp ASC",
+                placeholders
+            ))?;
+            let reactions = stmt
+                .query_map(rusqlite::params_from_iter(message_ids.iter()), |row| {
+                    Ok(MessageReaction {
+                        message_id: row.get(0)?,
+                        reactor_id: row.get(1)?,
+                        emoji: row.get(2)?,
+                        timestamp: row.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(reactions)
+        }).await
+    }
+
+    async fn add_pin(&self, pin: &MessagePin) -> Result<bool> {
+        let pin = pin.clone();
+        self.execute(move |conn| {
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO message_pins (session_id, message_id, pinned_by, timestamp)
+                 VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![&pin.session_id, &pin.message_id, &pin.pinned_by, pin.timestamp],
+            )?;
+            Ok(inserted > 0)
+        }).await
+    }
+
+    async fn remove_pin(&self, session_id: &str, message_id: &str) -> Result<bool> {
+        let (session_id, message_id) = (session_id.to_string(), message_id.to_string());
+        self.execute(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM message_pins WHERE session_id = ?1 AND message_id = ?2",
+                [session_id, message_id],
+            )?;
+            Ok(deleted > 0)
+        }).await
+    }
+
+    async fn load_pins(&self, session_id: &str) -> Result<Vec<MessagePin>> {
+        let session_id = session_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT session_id, message_id, pinned_by, timestamp FROM message_pins
+                 WHERE session_id = ?1
+                 ORDER BY timestamp ASC",
+            )?;
+            let pins = stmt
+                .query_map([session_id], |row| {
+                    Ok(MessagePin {
+                        session_id: row.get(0)?,
+                        message_id: row.get(1)?,
+                        pinned_by: row.get(2)?,
+                        timestamp: row.get(3)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(pins)
+        }).await
+    }
+
+    async fn add_bookmark(&self, bookmark: &MessageBookmark) -> Result<bool> {
+        let bookmark = bookmark.clone();
+        self.execute(move |conn| {
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO message_bookmarks (owner_id, message_id, timestamp) VALUES (?1, ?2, ?3)",
+                rusqlite::params![&bookmark.owner_id, &bookmark.message_id, bookmark.timestamp],
+            )?;
+            Ok(inserted > 0)
+        }).await
+    }
+
+    async fn remove_bookmark(&self, owner_id: &str, message_id: &str) -> Result<bool> {
+        let (owner_id, message_id) = (owner_id.to_string(), message_id.to_string());
+        self.execute(move |conn| {
+            let deleted = conn.execute(
+                "DELETE FROM message_bookmarks WHERE owner_id = ?1 AND message_id = ?2",
+                [owner_id, message_id],
+            )?;
+            Ok(deleted > 0)
+        }).await
+    }
+
+    async fn load_bookmarks(&self, owner_id: &str) -> Result<Vec<MessageBookmark>> {
+        let owner_id = owner_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT owner_id, message_id, timestamp FROM message_bookmarks
+                 WHERE owner_id = ?1
+                 ORDER BY timestamp DESC",
+            )?;
+            let bookmarks = stmt
+                .query_map([owner_id], |row| {
+                    Ok(MessageBookmark {
+                        owner_id: row.get(0)?,
+                        message_id: row.get(1)?,
+                        timestamp: row.get(2)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(bookmarks)
+        }).await
+    }
+
+    async fn save_task(&self, task: &Task) -> Result<()> {
+        let task = task.clone();
+        let linked_json = serde_json::to_string(&task.linked_message_ids)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO tasks
+                 (id, title, description, creator, assignee, status, due_at, linked_message_ids, created_at, updated_at, due_notified_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                rusqlite::params![
+                    &task.id,
+                    &task.title,
+                    &task.description,
+                    &task.creator,
+                    task.assignee.as_deref(),
+                    task.status.as_str(),
+                    task.due_at,
+                    linked_json,
+                    task.created_at,
+                    task.updated_at,
+                    task.due_notified_at,
+                ],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_task(&self, task_id: &str) -> Result<Option<Task>> {
+        let task_id = task_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS))?;
+            let mut rows = stmt.query([&task_id])?;
+            match rows.next()? {
+                Some(row) => Ok(task_from_row(row)?),
+                None => Ok(None),
+            }
+        }).await
+    }
+
+    async fn load_tasks(&self, filter: TaskFilter) -> Result<Vec<Task>> {
+        self.execute(move |conn| {
+            let mut conditions = Vec::new();
+            let mut params: Vec<rusqlite::types::Value> = Vec::new();
+
+            if let Some(assignee) = filter.assignee {
+                conditions.push("assignee = ?".to_string());
+                params.push(assignee.into());
+            }
+            if let Some(creator) = filter.creator {
+                conditions.push("creator = ?".to_string());
+                params.push(creator.into());
+            }
+            if !filter.statuses.is_empty() {
+                conditions.push(format!("status IN ({})", vec!["?"; filter.statuses.len()].join(", ")));
+                params.extend(filter.statuses.iter().map(|s| s.as_str().to_string().into()));
+            }
+            if filter.open_only {
+                conditions.push("status NOT IN ('done', 'cancelled')".to_string());
+            }
+            if let Some(before) = filter.due_before {
+                conditions.push("due_at IS NOT NULL AND due_at <= ?".to_string());
+                params.push(before.into());
+            }
+
+            let where_clause = if conditions.is_empty() {
+                "".to_string()
+            } else {
+                format!("WHERE {}", conditions.join(" AND "))
+            };
+            let sql = format!(
+                "SELECT {} FROM tasks {} ORDER BY due_at IS NULL, due_at, created_at, id LIMIT {}",
+                TASK_COLUMNS, where_clause, filter.limit
+            );
+
+            let mut stmt = conn.prepare(&sql)?;
+            let mut rows = stmt.query(rusqlite::params_from_iter(params))?;
+            let mut tasks = Vec::new();
+            while let Some(row) = rows.next()? {
+                if let Some(task) = task_from_row(row)? {
+                    tasks.push(task);
+                }
+            }
+            Ok(tasks)
+        }).await
+    }
+
+    async fn delete_task(&self, task_id: &str) -> Result<bool> {
+        let task_id = task_id.to_string();
+        self.execute(move |conn| {
+            let deleted = conn.execute("DELETE FROM tasks WHERE id = ?1", [&task_id])?;
+            Ok(deleted > 0)
+        }).await
+    }
+
+    async fn acquire_lease(&self, name: &str, holder: &str, now: i64, ttl_secs: i64) -> Result<Lease> {
+        let (name, holder) = (name.to_string(), holder.to_string());
+        self.execute(move |conn| {
+            // 读取与写入在同一事务中，多个节点共享数据库时不会同时取得租约
+            let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+            let current = load_lease_row(&tx, &name)?;
+            let lease = Lease::claim(current, &name, &holder, now, ttl_secs);
+            tx.execute(
+                "INSERT OR REPLACE INTO leases (name, holder, token, acquired_at, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![&lease.name, &lease.holder, lease.token as i64, lease.acquired_at, lease.expires_at],
+            )?;
+            tx.commit()?;
+            Ok(lease)
+        }).await
+    }
+
+    async fn load_lease(&self, name: &str) -> Result<Option<Lease>> {
+        let name = name.to_string();
+        self.execute(move |conn| load_lease_row(conn, &name)).await
+    }
+
+    async fn release_lease(&self, name: &str, holder: &str, token: u64) -> Result<bool> {
+        let (name, holder) = (name.to_string(), holder.to_string());
+        self.execute(move |conn| {
+            // 标记为已过期而不是删除，下一任持有者的令牌继续递增
+            let released = conn.execute(
+                "UPDATE leases SET expires_at = acquired_at WHERE name = ?1 AND holder = ?2 AND token = ?3",
+                rusqlite::params![&name, &holder, token as i64],
+            )?;
+            Ok(released > 0)
+        }).await
+    }
+
+    async fn save_quarantined_output(&self, output: &QuarantinedOutput) -> Result<()> {
+        let output = output.clone();
+        let content = serde_json::to_string(&output)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO moderation_quarantine (id, agent_id, quarantined_at, reviewed, content)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![&output.id, &output.agent_id, output.quarantined_at, !output.is_pending(), content],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_quarantined_output(&self, id: &str) -> Result<Option<QuarantinedOutput>> {
+        let id = id.to_string();
+        self.execute(move |conn| {
+            let result = conn.query_row(
+                "SELECT content FROM moderation_quarantine WHERE id = ?1",
+                [&id],
+                |row| row.get::<_, String>(0),
+            );
+            match result {
+                Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e.into()),
+            }
+        }).await
+    }
+
+    async fn load_quarantined_outputs(&self, pending_only: bool, limit: usize) -> Result<Vec<QuarantinedOutput>> {
+        self.execute(move |conn| {
+            let sql = format!(
+                "SELECT id, content FROM moderation_quarantine {} ORDER BY quarantined_at DESC, id DESC LIMIT ?1",
+                if pending_only { "WHERE reviewed = 0" } else { "" }
+            );
+            let mut stmt = conn.prepare(&sql)?;
+            let rows = stmt
+                .query_map(rusqlite::params![limit as i64], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let outputs = rows
+                .into_iter()
+                .filter_map(|(id, content)| match serde_json::from_str::<QuarantinedOutput>(&content) {
+                    Ok(output) => Some(output),
+                    Err(e) => {
+                        tracing::warn!("Skipping unreadable quarantined output {}: {}", id, e);
+                        None
+                    }
+                })
+                .collect();
+            Ok(outputs)
+        }).await
+    }
+
+    async fn append_scratchpad_entry(&self, entry: &ScratchpadEntry, max_bytes: usize) -> Result<usize> {
+        let entry = entry.clone();
+        self.execute(move |conn| {
+            let content = serde_json::to_string(&entry)?;
+            let tx = conn.transaction()?;
+            tx.execute(
+                "INSERT INTO scratchpad_entries (id, run_id, size, content) VALUES (?1, ?2, ?3, ?4)",
+                rusqlite::params![&entry.id, &entry.run_id, entry.size() as i64, content],
+            )?;
+
+            let sizes = {
+                let mut stmt = tx.prepare("SELECT seq, size FROM scratchpad_entries WHERE run_id = ?1 ORDER BY seq")?;
+                let rows = stmt
+                    .query_map([&entry.run_id], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)? as usize)))?
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                rows
+            };
+            let mut total: usize = sizes.iter().map(|(_, size)| size).sum();
+            let mut evicted = 0;
+            for (seq, size) in &sizes[..sizes.len().saturating_sub(1)] {
+                if total <= max_bytes {
+                    break;
+                }
+                tx.execute("DELETE FROM scratchpad_entries WHERE seq = ?1", [seq])?;
+                total -= size;
+                evicted += 1;
+            }
+            tx.commit()?;
+            Ok(evicted)
+        }).await
+    }
+
+    async fn load_scratchpad(&self, run_id: &str) -> Result<Vec<ScratchpadEntry>> {
+        let run_id = run_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare("SELECT id, content FROM scratchpad_entries WHERE run_id = ?1 ORDER BY seq")?;
+            let rows = stmt
+                .query_map([&run_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let entries = rows
+                .into_iter()
+                .filter_map(|(id, content)| match serde_json::from_str::<ScratchpadEntry>(&content) {
+                    Ok(entry) => Some(entry),
+                    Err(e) => {
+                        tracing::warn!("Skipping unreadable scratchpad entry {}: {}", id, e);
+                        None
+                    }
+                })
+                .collect();
+            Ok(entries)
+        }).await
+    }
+
+    async fn save_chat_session(&self, session: &ChatSession) -> Result<()> {
+        let session = session.clone();
+        let content = serde_json::to_string(&session)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO chat_sessions (id, agent_id, closed, created_at, content) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![&session.id, &session.agent_id, session.is_closed(), session.created_at, content],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_chat_session(&self, session_id: &str) -> Result<Option<ChatSession>> {
+        let session_id = session_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare("SELECT content FROM chat_sessions WHERE id = ?1")?;
+            let mut rows = stmt.query([&session_id])?;
+            match rows.next()? {
+                Some(row) => Ok(Some(serde_json::from_str(&row.get::<_, String>(0)?)?)),
+                None => Ok(None),
+            }
+        }).await
+    }
+
+    async fn load_chat_sessions(&self, include_closed: bool) -> Result<Vec<ChatSession>> {
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, content FROM chat_sessions WHERE ?1 OR closed = 0 ORDER BY created_at, id",
+            )?;
+            let rows = stmt
+                .query_map([include_closed], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let sessions = rows
+                .into_iter()
+                .filter_map(|(id, content)| match serde_json::from_str::<ChatSession>(&content) {
+                    Ok(session) => Some(session),
+                    Err(e) => {
+                        tracing::warn!("Skipping unreadable chat session {}: {}", id, e);
+                        None
+                    }
+                })
+                .collect();
+            Ok(sessions)
+        }).await
+    }
+
+    async fn save_group_stats(&self, stats: &GroupStats) -> Result<()> {
+        let group_id = stats.group_id.clone();
+        let updated_at = stats.updated_at;
+        let content = serde_json::to_string(stats)?;
+        self.execute(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO group_stats (group_id, updated_at, content) VALUES (?1, ?2, ?3)",
+                rusqlite::params![&group_id, updated_at, content],
+            )?;
+            Ok(())
+        }).await
+    }
+
+    async fn load_group_stats(&self, group_id: &str) -> Result<Option<GroupStats>> {
+        let group_id = group_id.to_string();
+        self.execute(move |conn| {
+            let mut stmt = conn.prepare("SELECT content FROM group_stats WHERE group_id = ?1")?;
+            let mut rows = stmt.query([&group_id])?;
+            match rows.next()? {
+                Some(row) => Ok(Some(serde_json::from_str(&row.get::<_, String>(0)?)?)),
+                None => Ok(None),
+            }
+        }).await
+    }
+
+    async fn scan_integrity(&self) -> Result<Vec<IntegrityFinding>> {
+        self.execute(|conn| {
+            let mut findings = Vec::new();
+
+            let mut stmt = conn.prepare("SELECT id, target_type, priority FROM messages")?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (id, target_type, priority) in rows {
+                if target_type != "direct" && target_type != "group" {
+                    findings.push(
+                        IntegrityFinding::new(
+                            Severity::Warning,
+                            IssueKind::InvalidEnumValue,
+                            "messages",
+                            &id,
+                            format!("unknown target type {:?}", target_type),
+                        )
+                        .with_value("target_type", target_type)
+                        .with_repair(Repair::Quarantine),
+                    );
+                }
+                // 无法识别的优先级按 normal 读取
+                if let Some(priority) = priority.filter(|p| MessagePriority::parse(p).is_none()) {
+                    findings.push(
+                        IntegrityFinding::new(
+                            Severity::Info,
+                            IssueKind::InvalidEnumValue,
+                            "messages",
+                            &id,
+                            format!("unknown priority {:?}, read as normal", priority),
+                        )
+                        .with_value("priority", priority),
+                    );
+                }
+            }
+
+            let mut stmt = conn.prepare("SELECT username, position FROM users")?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (username, position) in rows {
+                if !["Chairman", "Management", "Employee"].contains(&position.as_str()) {
+                    findings.push(
+                        IntegrityFinding::new(
+                            Severity::Warning,
+                            IssueKind::InvalidEnumValue,
+                            "users",
+                            &username,
+                            format!("unknown position {:?}, read as Employee", position),
+                        )
+                        .with_value("position", position),
+                    );
+                }
+            }
+
+            let mut stmt = conn.prepare("SELECT id, members FROM groups")?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (id, members) in rows {
+                if let Err(e) = serde_json::from_str::<Vec<String>>(&members) {
+                    findings.push(
+                        IntegrityFinding::new(
+                            Severity::Critical,
+                            IssueKind::UnparseableRow,
+                            "groups",
+                            &id,
+                            format!("members are not a JSON list of IDs: {}", e),
+                        )
+                        .with_repair(Repair::Quarantine),
+                    );
+                }
+            }
+
+            let mut stmt = conn.prepare("SELECT id, status FROM tasks")?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (id, status) in rows {
+                if status.parse::<TaskStatus>().is_err() {
+                    findings.push(
+                        IntegrityFinding::new(
+                            Severity::Warning,
+                            IssueKind::InvalidEnumValue,
+                            "tasks",
+                            &id,
+                            format!("unknown task status {:?}", status),
+                        )
+                        .with_value("status", status)
+                        .with_repair(Repair::Quarantine),
+                    );
+                }
+            }
+
+            Ok(findings)
+        }).await
+    }
+
+    async fn quarantine_row(&self, table: &str, row_id: &str, reason: &str) -> Result<bool> {
+        let key_column = match table {
+            "messages" | "groups" | "agents" | "departments" | "tasks" => "id",
+            "users" => "username",
+            _ => return Err(anyhow::anyhow!("Table {} cannot be quarantined", table)),
+        };
+        let (table, row_id, reason) = (table.to_string(), row_id.to_string(), reason.to_string());
+        self.execute(move |conn| {
+            let tx = conn.transaction()?;
+            let data = {
+                let mut stmt = tx.prepare(&format!("SELECT * FROM {} WHERE {} = ?1", table, key_column))?;
+                let columns: Vec<String> = stmt.column_names().into_iter().map(str::to_string).collect();
+                let mut rows = stmt.query([&row_id])?;
+                let Some(row) = rows.next()? else {
+                    return Ok(false);
+                };
+                let mut data = serde_json::Map::new();
+                for (i, column) in columns.into_iter().enumerate() {
+                    let value = match row.get_ref(i)? {
+                        rusqlite::types::ValueRef::Null => serde_json::Value::Null,
+                        rusqlite::types::ValueRef::Integer(n) => n.into(),
+                        rusqlite::types::ValueRef::Real(f) => f.into(),
+                        rusqlite::types::ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned().into(),
+                        rusqlite::types::ValueRef::Blob(b) => String::from_utf8_lossy(b).into_owned().into(),
+                    };
+                    data.insert(column, value);
+                }
+                serde_json::Value::Object(data)
+            };
+            tx.execute(
+                "INSERT INTO quarantined_rows (table_name, row_id, data, reason, quarantined_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![&table, &row_id, data.to_string(), &reason, chrono::Utc::now().timestamp()],
+            )?;
+            tx.execute(&format!("DELETE FROM {} WHERE {} = ?1", table, key_column), [&row_id])?;
+            tx.commit()?;
+            Ok(true)
+        }).await
+    }
+
+    async fn load_quarantined_rows(&self) -> Result<Vec<QuarantinedRow>> {
+        self.execute(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT table_name, row_id, data, reason, quarantined_at FROM quarantined_rows ORDER BY quarantined_at ASC, rowid ASC"
+            )?;
+            let rows = stmt
+                .query_map([], |row| {
+                    let data: String = row.get(2)?;
+                    Ok(QuarantinedRow {
+                        table: row.get(0)?,
+                        row_id: row.get(1)?,
+                        data: serde_json::from_str(&data).unwrap_or(serde_json::Value::String(data)),
+                        reason: row.get(3)?,
+                        quarantined_at: row.get(4)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        }).await
+    }
+
+    async fn health_check(&self) -> Result<()> {
+        self.execute(|conn| {
+            // 真正写一次磁盘，只读或损坏的数据库会在这里失败
+            conn.execute(
+                "INSERT OR REPLACE INTO app_state (key, value, updated_at) VALUES ('__health_probe', '{}', ?1)",
+                [chrono::Utc::now().timestamp()],
+            )?;
+            conn.query_row("SELECT value FROM app_state WHERE key = '__health_probe'", [], |row| {
+                row.get::<_, String>(0)
+            })?;
+            Ok(())
+        }).await
+    }
+
+    fn take_extracted_api_keys(&self) -> Vec<ExtractedSecret> {
+        std::mem::take(&mut *self.extracted_api_keys.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn backend_info(&self) -> StoreBackendInfo {
+        StoreBackendInfo {
+            backend: "sqlite".to_string(),
+            path: Some(self.location.clone()),
+            in_memory: self.is_in_memory(),
+        }
+    }
+}
+
+/// 以 `message_id` 引用消息的表，删除消息时一并删除
+const MESSAGE_DEPENDENT_TABLES: [&str; 4] = ["message_reactions", "message_pins", "message_bookmarks", "message_translations"];
+
+/// 消息元数据以 JSON 文本存储，空时存 NULL
+const DEPARTMENT_COLUMNS: &str = "id, name, parent_id, leader_id, max_agents, llm_budget";
+
+const AGENT_COLUMNS: &str = "id, name, department_id,
+    role_title, role_responsibilities, role_expertise, role_system_prompt,
+    llm_model, llm_api_key, llm_base_url, role_templates, skills, availability,
+    role_response_language, role_tone, llm_tokenizer, created_at";
+
+/// agents 表只保存 API Key 引用：明文换成该 Agent 的 `env:` 引用，取出的明文记入 `extracted`，
+/// 经 [`Store::take_extracted_api_keys`] 交给调用方展示给运维人员
+fn persisted_api_key(agent: &Agent, extracted: &mut Vec<ExtractedSecret>) -> String {
+    // 空值没有可泄露的内容，原样保存
+    if agent.llm_config.api_key.is_empty() || SecretRef::parse(&agent.llm_config.api_key).is_reference() {
+        return agent.llm_config.api_key.clone();
+    }
+    let reference = env_reference_for(&agent.id);
+    tracing::warn!(
+        "Agent {} has a plaintext API key; stored as {} and handed to the operator to relocate",
+        agent.id,
+        reference
+    );
+    extracted.push(ExtractedSecret {
+        agent_id: agent.id.clone(),
+        reference: reference.clone(),
+        value: agent.llm_config.api_key.clone(),
+    });
+    reference
+}
+
+fn department_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Department> {
+    Ok(Department {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        parent_id: row.get(2)?,
+        leader_id: row.get(3)?,
+        max_agents: row.get::<_, Option<i64>>(4)?.map(|n| n as usize),
+        llm_budget: row.get::<_, Option<i64>>(5)?.map(|n| n as u64),
+    })
+}
+
+fn agent_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Agent> {
+    let responsibilities: String = row.get(4)?;
+    let expertise: String = row.get(5)?;
+    let templates: Option<String> = row.get(10)?;
+    let skills: Option<String> = row.get(11)?;
+    let availability: Option<String> = row.get(12)?;
+
+    Ok(Agent {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        department_id: row.get(2)?,
+        role: Role {
+            title: row.get(3)?,
+            responsibilities: serde_json::from_str(&responsibilities).unwrap_or_default(),
+            expertise: serde_json::from_str(&expertise).unwrap_or_default(),
+            system_prompt: row.get(6)?,
+            templates: templates
+                .and_then(|s| serde_json::from_str(&s).ok())
+                .unwrap_or_default(),
+            response_language: row.get(13)?,
+            tone: row.get(14)?,
+        },
+        llm_config: LLMConfig {
+            model: row.get(7)?,
+            api_key: row.get(8)?,
+            base_url: row.get(9)?,
+            tokenizer: row.get(15)?,
+        },
+        mode: AgentMode::Passive, // 默认为被动模式
+        skills: skills
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        availability: availability.and_then(|s| serde_json::from_str(&s).ok()),
+        created_at: row.get(16)?,
+    })
+}
+
+const TASK_COLUMNS: &str = "id, title, description, creator, assignee, status, due_at,
+    linked_message_ids, created_at, updated_at, due_notified_at";
+
+/// 无法识别状态的任务跳过（完整性检查会报告并隔离）
+///
+/// `group_seq` 与元数据中的序号相同，读取时以元数据为准，这一列用于按序号查询
+const MESSAGE_COLUMNS: &str =
+    "id, from_agent, target_type, target_id, content, timestamp, reply_to, mentions, metadata, priority, group_seq";
+
+fn message_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Message> {
+    let target_type: String = row.get(2)?;
+    let target_id: Option<String> = row.get(3)?;
+
+    let target = match target_type.as_str() {
+        "direct" => MessageTarget::Direct(target_id.unwrap_or_default()),
+        "group" => MessageTarget::Group(target_id.unwrap_or_default()),
+        _ => MessageTarget::Direct(String::new()), // 未知类型默认为空direct
+    };
+
+    Ok(Message {
+        id: row.get(0)?,
+        from: row.get(1)?,
+        to: target,
+        content: row.get(4)?,
+        timestamp: row.get(5)?,
+        reply_to: row.get::<_, Option<String>>(6)?,
+        mentions: row.get::<_, Option<String>>(7)?
+            .map(|s| s.split(',').map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        metadata: decode_metadata(row.get::<_, Option<String>>(8)?),
+        priority: row
+            .get::<_, Option<String>>(9)?
+            .and_then(|p| MessagePriority::parse(&p))
+            .unwrap_or_default(),
+    })
+}
+
+/// 按过滤条件构造消息查询的 SQL 和参数
+fn messages_query(conn: &Connection, filter: &MessageFilter) -> Result<(String, Vec<rusqlite::types::Value>)> {
+    // 时间范围延伸到归档边界之前时同时查询归档表
+    let source = if MessageTierCounts::reaches_archive(archived_before(conn)?, filter) {
+        format!("(SELECT {MESSAGE_COLUMNS} FROM messages UNION ALL SELECT {MESSAGE_COLUMNS} FROM messages_archive)")
+    } else {
+        "messages".to_string()
+    };
+
+    let mut conditions = Vec::new();
+    let mut params: Vec<rusqlite::types::Value> = Vec::new();
+
+    if let Some(from) = &filter.from {
+        conditions.push("from_agent = ?".to_string());
+        params.push(from.clone().into());
+    }
+    if let Some(target_type) = &filter.target_type {
+        conditions.push("target_type = ?".to_string());
+        params.push(target_type.clone().into());
+    }
+    if let Some(to) = &filter.to {
+        conditions.push("target_id = ?".to_string());
+        params.push(to.clone().into());
+    }
+    if let Some(since) = filter.since {
+        conditions.push("timestamp >= ?".to_string());
+        params.push(since.into());
+    }
+    if let Some(until) = filter.until {
+        conditions.push("timestamp <= ?".to_string());
+        params.push(until.into());
+    }
+    if let Some(participant) = &filter.participant {
+        conditions.push("(from_agent = ? OR target_id = ?)".to_string());
+        params.push(participant.clone().into());
+        params.push(participant.clone().into());
+    }
+    if let Some((a, b)) = &filter.between {
+        conditions.push("(target_type = 'direct' AND ((from_agent = ? AND target_id = ?) OR (from_agent = ? AND target_id = ?)))".to_string());
+        params.push(a.clone().into());
+        params.push(b.clone().into());
+        params.push(b.clone().into());
+        params.push(a.clone().into());
+    }
+    if let Some(cursor) = &filter.before {
+        conditions.push("(timestamp < ? OR (timestamp = ? AND id < ?))".to_string());
+        params.push(cursor.timestamp.into());
+        params.push(cursor.timestamp.into());
+        params.push(cursor.id.clone().into());
+    }
+
+    let where_clause = if conditions.is_empty() {
+        "".to_string()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+
+    let direction = if filter.oldest_first { "ASC" } else { "DESC" };
+    let order = if filter.stable_order {
+        format!("timestamp {direction}, id {direction}")
+    } else {
+        format!("timestamp {direction}")
+    };
+    // 数量限制和偏移作为参数绑定，未设置时取默认值；超出 i64 的值截断，负数会被 SQLite 当作没有限制或偏移
+    params.push(i64::try_from(filter.effective_limit()).unwrap_or(i64::MAX).into());
+    params.push(i64::try_from(filter.offset.unwrap_or(0)).unwrap_or(i64::MAX).into());
+    let sql = format!("SELECT {MESSAGE_COLUMNS} FROM {source} {where_clause} ORDER BY {order} LIMIT ? OFFSET ?");
+    Ok((sql, params))
+}
+
+/// 执行 `EXPLAIN QUERY PLAN`，返回每一步的说明
+#[cfg(feature = "query-plan")]
+fn query_plan(conn: &Connection, sql: &str, params: impl rusqlite::Params) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {sql}"))?;
+    let steps = stmt
+        .query_map(params, |row| row.get::<_, String>(3))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(steps)
+}
+
+/// 某个 Agent 发出的消息和发给它的私聊
+///
+/// 每个分支各自走复合索引按时间倒序取前 `?2` 条，UNION 合并时去掉发给自己的重复消息，
+/// 有归档数据时再加上归档表的两个分支
+fn messages_by_agent_sql(include_archive: bool) -> String {
+    let tables: &[&str] = if include_archive { &["messages", "messages_archive"] } else { &["messages"] };
+    let branches: Vec<String> = tables
+        .iter()
+        .flat_map(|table| {
+            [
+                format!("SELECT * FROM (SELECT {MESSAGE_COLUMNS} FROM {table} WHERE from_agent = ?1 ORDER BY timestamp DESC, id DESC LIMIT ?2)"),
+                format!(
+                    "SELECT * FROM (SELECT {MESSAGE_COLUMNS} FROM {table} WHERE target_type = 'direct' AND target_id = ?1 ORDER BY timestamp DESC, id DESC LIMIT ?2)"
+                ),
+            ]
+        })
+        .collect();
+    format!("{} ORDER BY timestamp DESC, id DESC LIMIT ?2", branches.join(" UNION "))
+}
+
+/// 归档边界，未归档过时为 None
+fn archived_before(conn: &Connection) -> Result<Option<i64>> {
+    match conn.query_row("SELECT archived_before FROM message_archive_state WHERE id = 1", [], |row| row.get(0)) {
+        Ok(boundary) => Ok(Some(boundary)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(e)),
+    }
+}
+
+/// 按名称读取租约
+fn load_lease_row(conn: &Connection, name: &str) -> Result<Option<Lease>> {
+    let result = conn.query_row(
+        "SELECT name, holder, token, acquired_at, expires_at FROM leases WHERE name = ?1",
+        [name],
+        |row| {
+            Ok(Lease {
+                name: row.get(0)?,
+                holder: row.get(1)?,
+                token: row.get::<_, i64>(2)? as u64,
+                acquired_at: row.get(3)?,
+                expires_at: row.get(4)?,
+            })
+        },
+    );
+    match result {
+        Ok(lease) => Ok(Some(lease)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(anyhow::anyhow!(e)),
+    }
+}
+
+fn task_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Option<Task>> {
+    let id: String = row.get(0)?;
+    let status: String = row.get(5)?;
+    let Ok(status) = status.parse::<TaskStatus>() else {
+        tracing::warn!("Skipping task {} with unknown status {:?}", id, status);
+        return Ok(None);
+    };
+    let linked: String = row.get(7)?;
+
+    Ok(Some(Task {
+        id,
+        title: row.get(1)?,
+        description: row.get(2)?,
+        creator: row.get(3)?,
+        assignee: row.get(4)?,
+        status,
+        due_at: row.get(6)?,
+        linked_message_ids: serde_json::from_str(&linked).unwrap_or_default(),
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        due_notified_at: row.get(10)?,
+    }))
+}
+
+const SHARE_TOKEN_COLUMNS: &str = "id, group_id, created_by, created_at, expires_at, message_window,
+    revoked_at, access_count, last_accessed_at";
+
+fn share_token_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<ShareToken> {
+    let message_window: Option<i64> = row.get(5)?;
+    let access_count: i64 = row.get(7)?;
+    Ok(ShareToken {
+        id: row.get(0)?,
+        group_id: row.get(1)?,
+        created_by: row.get(2)?,
+        created_at: row.get(3)?,
+        expires_at: row.get(4)?,
+        message_window: message_window.map(|w| w.max(0) as usize),
+        revoked_at: row.get(6)?,
+        access_count: access_count.max(0) as u64,
+        last_accessed_at: row.get(8)?,
+    })
+}
+
+fn encode_metadata(metadata: &HashMap<String, String>) -> Option<String> {
+    if metadata.is_empty() {
+        None
+    } else {
+        serde_json::to_string(metadata).ok()
+    }
+}
+
+fn decode_metadata(raw: Option<String>) -> HashMap<String, String> {
+    raw.and_then(|s| serde_json::from_str(&s).ok()).unwrap_or_default()
 }
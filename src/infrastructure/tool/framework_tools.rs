@@ -4,16 +4,66 @@
 
 use anyhow::Result;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
-use crate::core::messaging::MessageBus;
-use crate::core::store::{MessageFilter, Store};
+use crate::core::handoff::{Handoff, HandoffConfig, HandoffError, HandoffOutcome, HandoffRequest};
+use crate::core::i18n::MessageCatalog;
+use crate::core::join_context::InviteOptions;
+use crate::core::message_limits::{enforce_message_limits, MessageLimits};
+use crate::core::messaging::{GroupModerationError, MessageBus, PinError};
+use crate::core::preferences::{merge_preferences, validate_preferences};
+use crate::core::chat_sessions::{ChatSessionError, ChatSessions};
+use crate::core::group_stats::GroupStatsTracker;
+use crate::core::goals::{GoalBoard, GoalSource};
+use crate::core::scratchpad::Scratchpad;
+use crate::core::send_dedup::{SendDedup, ALLOW_DUPLICATE_KEY};
+use crate::core::store::{Store, TaskFilter};
+use crate::core::tasks::{TaskError, TaskManager};
+use crate::core::temp_agents::{SpawnRequest, TempAgentError, TemporaryAgents};
+use crate::core::template::{self, Escape, TemplateError, TemplateSandbox};
 use crate::core::tool::ToolRegistry;
+use crate::core::tool_stats::ToolStats;
 use crate::core::tool_provider::{CompositeToolProvider, FrameworkToolProvider};
-use crate::domain::{Message, MessageTarget, Organization};
+use crate::core::tool_view::AgentToolView;
+use crate::core::transcript::{
+    export_to_string, participant_names, TranscriptFormat, TranscriptPager, TranscriptRenderer, TranscriptSession,
+};
+use crate::domain::{Message, MessagePriority, SessionResolution, MessageReaction, MessageTarget, Organization, Role, Task, TaskStatus};
+use crate::domain::scratchpad::ScratchpadSource;
 use crate::domain::tool::{MatchType, ToolCallContext, ToolProvider};
-use crate::infrastructure::tool::ToolResult;
+use crate::infrastructure::blob::BlobStore;
+use crate::infrastructure::email::{EmailError, EmailMessage, EmailNotifier};
+use crate::infrastructure::sandbox::{CodeSandbox, SandboxError};
+use crate::infrastructure::tool::{
+    annotate_alias, dry_run_result, resolve_alias, take_dry_run_flag, DryRunError, DryRunReport, ToolResult,
+};
+
+/// `transcript.export` 默认最多导出的消息数
+const DEFAULT_TRANSCRIPT_MESSAGES: usize = 200;
+
+/// `group.create_ephemeral` 默认存活时间（秒）
+const DEFAULT_EPHEMERAL_GROUP_TTL_SECS: u64 = 3600;
+
+/// `group.create_ephemeral` 最长存活时间（秒）
+const MAX_EPHEMERAL_GROUP_TTL_SECS: u64 = 86_400;
+
+/// `group.mute` 最长禁言时长（秒）
+const MAX_GROUP_MUTE_SECS: u64 = 7 * 86_400;
+
+/// `org.get_recent_changes` 未指定 since 时回看的时长（秒）
+const RECENT_CHANGES_WINDOW_SECS: i64 = 7 * 86_400;
+
+/// `org.get_recent_changes` 默认返回条数
+const RECENT_CHANGES_DEFAULT_LIMIT: usize = 10;
+
+/// `org.get_recent_changes` 最多返回条数
+const RECENT_CHANGES_MAX_LIMIT: usize = 50;
+
+/// `code.run` 未指定语言时使用的语言
+const DEFAULT_CODE_LANGUAGE: &str = "javascript";
 
 /// 工具执行环境
 ///
@@ -30,6 +80,35 @@ pub struct ToolEnvironment {
     pub tool_provider: Arc<CompositeToolProvider>,
     /// 消息存储
     pub message_store: Arc<dyn Store>,
+    /// 工具返回文本使用的消息目录
+    pub catalog: MessageCatalog,
+    /// 工具调用统计
+    pub tool_stats: Arc<ToolStats>,
+    /// 公司级消息模板（共享，可在运行时替换）
+    pub templates: Arc<RwLock<HashMap<String, String>>>,
+    /// 邮件通知，未配置 SMTP 时为 None
+    pub email: Option<Arc<EmailNotifier>>,
+    /// Agent 转交配置，未启用时为 None
+    pub handoff: Option<HandoffConfig>,
+    /// 代码沙箱，未启用时为 None
+    pub code_sandbox: Option<Arc<CodeSandbox>>,
+    /// 临时 Agent 管理，未启用时为 None
+    pub temp_agents: Option<Arc<TemporaryAgents>>,
+    /// Agent 运行草稿，未启用时为 None
+    pub scratchpad: Option<Arc<Scratchpad>>,
+    pub goals: Option<Arc<GoalBoard>>,
+    /// 会话生命周期管理，未启用时为 None
+    pub chat_sessions: Option<Arc<ChatSessions>>,
+    /// 群聊活动统计，未启用时为 None
+    pub group_stats: Option<Arc<GroupStatsTracker>>,
+    /// 按 Agent 过滤的工具视图；设置后 `tool.search` 默认只搜索调用者可用的工具
+    pub tool_view: Option<Arc<AgentToolView>>,
+    /// `message.send_*` 发送消息时的大小限制
+    pub message_limits: MessageLimits,
+    /// 超长消息转为附件时使用的大对象存储，未配置时超长消息被拒绝
+    pub blob_store: Option<Arc<dyn BlobStore>>,
+    /// 重复发送缓存；立即发送的消息在这里检查，排队的消息在轮次结束时检查
+    pub send_dedup: Option<Arc<SendDedup>>,
 }
 
 impl ToolEnvironment {
@@ -51,7 +130,153 @@ impl ToolEnvironment {
             tool_registry,
             tool_provider: Arc::new(tool_provider),
             message_store,
+            catalog: MessageCatalog::default(),
+            tool_stats: Arc::new(ToolStats::new()),
+            templates: Arc::new(RwLock::new(HashMap::new())),
+            email: None,
+            handoff: None,
+            code_sandbox: None,
+            temp_agents: None,
+            scratchpad: None,
+            goals: None,
+            chat_sessions: None,
+            group_stats: None,
+            tool_view: None,
+            message_limits: MessageLimits::default(),
+            blob_store: None,
+            send_dedup: None,
+        }
+    }
+
+    /// 使用共享的工具统计收集器
+    pub fn with_tool_stats(mut self, tool_stats: Arc<ToolStats>) -> Self {
+        self.tool_stats = tool_stats;
+        self
+    }
+
+    /// 设置消息目录
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// 使用共享的公司级模板
+    pub fn with_templates(mut self, templates: Arc<RwLock<HashMap<String, String>>>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// 启用邮件通知，同时向 Agent 提供 `notify.email` 工具
+    pub fn with_email(mut self, email: Arc<EmailNotifier>) -> Self {
+        self.email = Some(email);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 启用 Agent 转交，同时向 Agent 提供 `handoff` 工具
+    pub fn with_handoff(mut self, handoff: HandoffConfig) -> Self {
+        self.handoff = Some(handoff);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 启用代码沙箱，同时向 Agent 提供 `code.run` 工具
+    pub fn with_code_sandbox(mut self, sandbox: Arc<CodeSandbox>) -> Self {
+        self.code_sandbox = Some(sandbox);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 启用临时 Agent，同时向 Agent 提供 `agent.spawn_temporary` 工具
+    pub fn with_temp_agents(mut self, temp_agents: Arc<TemporaryAgents>) -> Self {
+        self.temp_agents = Some(temp_agents);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 启用运行草稿，同时向 Agent 提供 `scratchpad.write` 工具
+    pub fn with_scratchpad(mut self, scratchpad: Arc<Scratchpad>) -> Self {
+        self.scratchpad = Some(scratchpad);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 记录当前目标，同时向 Agent 提供 `self.set_goal` / `self.clear_goal` 工具
+    pub fn with_goals(mut self, goals: Arc<GoalBoard>) -> Self {
+        self.goals = Some(goals);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 启用会话生命周期，同时向 Agent 提供 `session.close` 工具
+    pub fn with_chat_sessions(mut self, chat_sessions: Arc<ChatSessions>) -> Self {
+        self.chat_sessions = Some(chat_sessions);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 启用群聊活动统计，同时向 Agent 提供 `group.stats` 工具
+    pub fn with_group_stats(mut self, group_stats: Arc<GroupStatsTracker>) -> Self {
+        self.group_stats = Some(group_stats);
+        self.rebuild_tool_provider();
+        self
+    }
+
+    /// 丢弃窗口期内重复发送的相同消息（`allow_duplicate: true` 时跳过）
+    pub fn with_send_dedup(mut self, send_dedup: Arc<SendDedup>) -> Self {
+        self.send_dedup = Some(send_dedup);
+        self
+    }
+
+    /// 按已启用的可选工具重建工具提供者
+    fn rebuild_tool_provider(&mut self) {
+        let mut framework = FrameworkToolProvider::new();
+        if self.email.is_some() {
+            framework = framework.with_email();
+        }
+        if self.handoff.is_some() {
+            framework = framework.with_handoff();
+        }
+        if self.code_sandbox.is_some() {
+            framework = framework.with_code_run();
+        }
+        if self.temp_agents.is_some() {
+            framework = framework.with_temp_agents();
+        }
+        if self.scratchpad.is_some() {
+            framework = framework.with_scratchpad();
+        }
+        if self.goals.is_some() {
+            framework = framework.with_goals();
         }
+        if self.chat_sessions.is_some() {
+            framework = framework.with_chat_sessions();
+        }
+        if self.group_stats.is_some() {
+            framework = framework.with_group_stats();
+        }
+        let tool_provider = CompositeToolProvider::new()
+            .add_provider(Box::new(framework))
+            .with_registry(self.tool_registry.clone());
+        self.tool_provider = Arc::new(tool_provider);
+    }
+
+    /// 设置 Agent 工具视图
+    pub fn with_tool_view(mut self, tool_view: Arc<AgentToolView>) -> Self {
+        self.tool_view = Some(tool_view);
+        self
+    }
+
+    /// 设置发送消息的大小限制
+    pub fn with_message_limits(mut self, message_limits: MessageLimits) -> Self {
+        self.message_limits = message_limits;
+        self
+    }
+
+    /// 超长消息按限制配置转为附件时使用的大对象存储
+    pub fn with_blob_store(mut self, blob_store: Arc<dyn BlobStore>) -> Self {
+        self.blob_store = Some(blob_store);
+        self
     }
 }
 
@@ -66,6 +291,16 @@ impl FrameworkToolExecutor {
         Self { env }
     }
 
+    /// 按当前语言渲染文本
+    fn text(&self, key: &str, args: &[(&str, &str)]) -> String {
+        self.env.catalog.format(key, args)
+    }
+
+    /// 缺少必填参数时的错误
+    fn missing_param(&self, param: &str) -> anyhow::Error {
+        anyhow::anyhow!(self.text("tool.param_required", &[("param", param)]))
+    }
+
     /// 获取支持的框架工具ID列表
     pub fn supported_tool_ids() -> Vec<&'static str> {
         vec![
@@ -73,10 +308,19 @@ impl FrameworkToolExecutor {
             "tool.search",
             "tool.list_categories",
             "tool.get_category_tools",
+            "tool.stats",
             // 消息发送类
             "message.send_direct",
             "message.send_group",
             "message.reply",
+            "message.send_templated",
+            "message.send_immediate",
+            "message.react",
+            "message.pin",
+            // 记忆类
+            "memory.list_pins",
+            // 模板类
+            "template.render",
             // 时间类
             "time.now",
             // 组织架构类
@@ -84,13 +328,91 @@ impl FrameworkToolExecutor {
             "org.get_department",
             "org.get_leader",
             "org.find_agents",
+            "org.find_users",
             "org.get_sub_departments",
             "org.get_subordinates",
+            "org.get_recent_changes",
+            // 自我配置类
+            "self.get_preferences",
+            "self.update_preferences",
+            // 会话记录类
+            "transcript.export",
+            // 群聊类
+            "group.create_ephemeral",
+            "group.invite",
+            "group.kick",
+            "group.mute",
+            // 任务类
+            "task.create",
+            "task.list_mine",
+            "task.update_status",
+            "task.comment",
+            // 通知类
+            "notify.email",
+            // 转交类
+            "handoff",
+            // 代码类
+            "code.run",
+            // 临时 Agent 类
+            "agent.spawn_temporary",
+            // 草稿类
+            "scratchpad.write",
+            // 目标类
+            "self.set_goal",
+            "self.clear_goal",
+            "session.close",
+            "group.stats",
         ]
     }
 
-    /// 执行工具调用
+    /// 执行工具调用，已弃用的别名转到规范ID并在结果中附带提示
+    ///
+    /// 参数带 `dry_run: true` 时只预演，见 [`ToolExecutor::dry_run`](crate::infrastructure::tool::ToolExecutor::dry_run)
     pub async fn execute(
+        &self,
+        tool_id: &str,
+        mut params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let dry_run = take_dry_run_flag(&mut params);
+        let env = &self.env;
+        let (tool_id, alias) = match resolve_alias(&env.tool_registry, &env.catalog, &env.tool_stats, tool_id) {
+            Ok(resolved) => resolved,
+            Err(retired) => return Ok(retired),
+        };
+        let result = if dry_run {
+            dry_run_result(self.preview(&tool_id, params, context).await, &env.catalog)?
+        } else {
+            self.dispatch(&tool_id, params, context).await?
+        };
+        Ok(annotate_alias(result, alias.as_ref(), &env.catalog))
+    }
+
+    /// 预演：消息类工具报告收件人和渲染后的内容，`self.update_preferences` 报告修改前后的偏好，
+    /// 其余工具不支持
+    async fn preview(
+        &self,
+        tool_id: &str,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<DryRunReport> {
+        match tool_id {
+            "message.send_direct"
+            | "message.send_group"
+            | "message.reply"
+            | "message.send_templated"
+            | "message.send_immediate" => self.preview_message(tool_id, &params, context).await,
+            "self.update_preferences" => self.preview_update_preferences(&params, context).await,
+            _ => Err(DryRunError::Unsupported { tool_id: tool_id.to_string() }.into()),
+        }
+    }
+
+    /// 失败的工具结果转为错误
+    fn error_of(&self, result: ToolResult) -> anyhow::Error {
+        anyhow::anyhow!(result.error.unwrap_or_else(|| self.text("tool.unknown_error", &[])))
+    }
+
+    async fn dispatch(
         &self,
         tool_id: &str,
         params: Value,
@@ -98,13 +420,21 @@ impl FrameworkToolExecutor {
     ) -> Result<ToolResult> {
         match tool_id {
             // Tool 查询类
-            "tool.search" => self.execute_tool_search(params).await,
+            "tool.search" => self.execute_tool_search(params, context).await,
             "tool.list_categories" => self.execute_tool_list_categories(params).await,
             "tool.get_category_tools" => self.execute_tool_get_category_tools(params).await,
+            "tool.stats" => self.execute_tool_stats(params, context).await,
             // 消息发送类
-            "message.send_direct" => self.execute_message_send_direct(params, context).await,
-            "message.send_group" => self.execute_message_send_group(params, context).await,
+            "message.send_direct" | "message.send_group" => self.execute_message_send(tool_id, params, context).await,
             "message.reply" => self.execute_message_reply(params, context).await,
+            "message.send_templated" => self.execute_message_send_templated(params, context).await,
+            "message.send_immediate" => self.execute_message_send_immediate(params, context).await,
+            "message.react" => self.execute_message_react(params, context).await,
+            "message.pin" => self.execute_message_pin(params, context).await,
+            // 记忆类
+            "memory.list_pins" => self.execute_memory_list_pins(params, context).await,
+            // 模板类
+            "template.render" => self.execute_template_render(params, context).await,
             // 时间类
             "time.now" => self.execute_time_now().await,
             // 组织架构类
@@ -112,9 +442,41 @@ impl FrameworkToolExecutor {
             "org.get_department" => self.execute_org_get_department(params).await,
             "org.get_leader" => self.execute_org_get_leader(params).await,
             "org.find_agents" => self.execute_org_find_agents(params).await,
+            "org.find_users" => self.execute_org_find_users(params).await,
             "org.get_sub_departments" => self.execute_org_get_sub_departments(params).await,
             "org.get_subordinates" => self.execute_org_get_subordinates(params).await,
-            _ => Ok(ToolResult::error(format!("Unknown tool: {}", tool_id))),
+            "org.get_recent_changes" => self.execute_org_get_recent_changes(params).await,
+            // 自我配置类
+            "self.get_preferences" => self.execute_self_get_preferences(params, context).await,
+            "self.update_preferences" => self.execute_self_update_preferences(params, context).await,
+            // 会话记录类
+            "transcript.export" => self.execute_transcript_export(params, context).await,
+            // 群聊类
+            "group.create_ephemeral" => self.execute_group_create_ephemeral(params, context).await,
+            "group.invite" => self.execute_group_invite(params, context).await,
+            "group.kick" => self.execute_group_kick(params, context).await,
+            "group.mute" => self.execute_group_mute(params, context).await,
+            // 任务类
+            "task.create" => self.execute_task_create(params, context).await,
+            "task.list_mine" => self.execute_task_list_mine(params, context).await,
+            "task.update_status" => self.execute_task_update_status(params, context).await,
+            "task.comment" => self.execute_task_comment(params, context).await,
+            // 通知类
+            "notify.email" => self.execute_notify_email(params, context).await,
+            // 转交类
+            "handoff" => self.execute_handoff(params, context).await,
+            // 代码类
+            "code.run" => self.execute_code_run(params, context).await,
+            // 临时 Agent 类
+            "agent.spawn_temporary" => self.execute_agent_spawn_temporary(params, context).await,
+            // 草稿类
+            "scratchpad.write" => self.execute_scratchpad_write(params, context).await,
+            // 目标类
+            "self.set_goal" => self.execute_self_set_goal(params, context).await,
+            "self.clear_goal" => self.execute_self_clear_goal(context).await,
+            "session.close" => self.execute_session_close(params, context).await,
+            "group.stats" => self.execute_group_stats(params, context).await,
+            _ => Ok(ToolResult::error(self.text("tool.unknown", &[("tool_id", tool_id)]))),
         }
     }
 
@@ -122,10 +484,11 @@ impl FrameworkToolExecutor {
 
     async fn execute_tool_search(&self,
         params: Value,
+        context: &ToolCallContext,
     ) -> Result<ToolResult> {
         let query = params["query"].as_str().unwrap_or("");
         if query.is_empty() {
-            return Ok(ToolResult::error("Query parameter is required"));
+            return Ok(ToolResult::error(self.text("tool.param_required", &[("param", "query")])));
         }
 
         let match_type = match params["match_type"].as_str() {
@@ -135,7 +498,33 @@ impl FrameworkToolExecutor {
 
         let category_filter = params["category_filter"].as_str();
 
-        let mut results = self.env.tool_provider.search_tools(query, match_type);
+        let include_unavailable = params["include_unavailable"].as_bool().unwrap_or(false);
+        let mut matched_aliases = HashMap::new();
+        let mut results = match &self.env.tool_view {
+            Some(view) => view.search(&context.caller_id, query, match_type, include_unavailable),
+            None => self.env.tool_provider.search_tools(query, match_type),
+        };
+
+        // 查询命中已弃用的别名时返回规范工具
+        for alias in self.env.tool_registry.aliases() {
+            let matched = match match_type {
+                MatchType::Exact => alias.alias == query,
+                MatchType::Fuzzy => alias.alias.to_lowercase().contains(&query.to_lowercase()),
+            };
+            if !matched || results.iter().any(|t| t.id == alias.canonical) {
+                continue;
+            }
+            let canonical = match &self.env.tool_view {
+                Some(view) if !include_unavailable => {
+                    view.tools_for(&context.caller_id).iter().find(|t| t.id == alias.canonical).cloned()
+                }
+                _ => self.env.tool_provider.list_tools().into_iter().find(|t| t.id == alias.canonical),
+            };
+            if let Some(tool) = canonical {
+                matched_aliases.insert(tool.id.clone(), alias.alias);
+                results.push(tool);
+            }
+        }
 
         // 应用分类过滤
         if let Some(category) = category_filter {
@@ -145,12 +534,22 @@ impl FrameworkToolExecutor {
         }
 
         let tools_json: Vec<Value> = results.iter().map(|tool| {
-            json!({
+            let mut entry = json!({
                 "id": tool.id,
                 "name": tool.name,
                 "description": tool.description,
                 "category": tool.category.to_path_string(),
-            })
+            });
+            if let (true, Some(view)) = (include_unavailable, &self.env.tool_view) {
+                entry["available"] = json!(view.can_use(&context.caller_id, &tool.id));
+            }
+            if let Some(message) = &tool.deprecated {
+                entry["deprecated"] = json!(message);
+            }
+            if let Some(alias) = matched_aliases.get(&tool.id) {
+                entry["matched_alias"] = json!(alias);
+            }
+            entry
         }).collect();
 
         Ok(ToolResult::success(json!({
@@ -195,7 +594,7 @@ impl FrameworkToolExecutor {
     ) -> Result<ToolResult> {
         let category = params["category"].as_str().unwrap_or("");
         if category.is_empty() {
-            return Ok(ToolResult::error("Category parameter is required"));
+            return Ok(ToolResult::error(self.text("tool.param_required", &[("param", "category")])));
         }
 
         let _recursive = params["recursive"].as_bool().unwrap_or(true);
@@ -219,138 +618,479 @@ impl FrameworkToolExecutor {
         })))
     }
 
+    async fn execute_tool_stats(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let top_n = params["top_n"].as_u64().unwrap_or(10) as usize;
+
+        let top_tools: Vec<Value> = self.env.tool_stats.top_n(top_n).iter().map(|usage| {
+            json!({
+                "id": usage.tool_id,
+                "calls": usage.calls,
+                "successes": usage.successes,
+                "errors": usage.errors,
+                "success_rate": usage.success_rate(),
+                "avg_latency_ms": usage.avg_latency_ms(),
+            })
+        }).collect();
+
+        let recent_failures = self.env.tool_stats.recent_failures(&context.caller_id, 10);
+
+        Ok(ToolResult::success(json!({
+            "top_tools": top_tools,
+            "my_recent_failures": recent_failures,
+        })))
+    }
+
     // ==================== 消息发送类 ====================
 
-    async fn execute_message_send_direct(
+    async fn execute_message_send(
         &self,
+        tool_id: &str,
         params: Value,
         context: &ToolCallContext,
     ) -> Result<ToolResult> {
-        let to_agent_id = params["to_agent_id"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("to_agent_id is required"))?;
-        let content = params["content"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("content is required"))?;
-        let reply_to = params["reply_to_message_id"].as_str();
+        let message = match self.outgoing_message(tool_id, &params, context).await? {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let message = match self.limit(message).await {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let message = match self.check_duplicate(message, &params, context.outbox.is_some()) {
+            Ok(message) => message,
+            Err(result) => return Ok(result),
+        };
 
-        let mut message = Message::private(&context.caller_id,
-            to_agent_id,
-            content
-        );
+        let sent = self.deliver(message, context).await?;
 
-        if let Some(reply_id) = reply_to {
-            message = message.with_reply_to(reply_id);
-        }
+        Ok(ToolResult::success(json!({ "sent": sent, "queued": !sent })))
+    }
 
-        self.env.message_bus.send(message).await?;
+    async fn execute_message_reply(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let message = match self.outgoing_message("message.reply", &params, context).await? {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let message = match self.limit(message).await {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let message = match self.check_duplicate(message, &params, context.outbox.is_some()) {
+            Ok(message) => message,
+            Err(result) => return Ok(result),
+        };
+        let message_id = message.id.clone();
+        let reply_to = message.reply_to.clone();
+        let target = format!("{:?}", message.to);
 
-        Ok(ToolResult::success(json!({ "sent": true })))
+        // 发送消息
+        let sent = self.deliver(message, context).await?;
+        Ok(ToolResult::success(json!({
+            "sent": sent,
+            "queued": !sent,
+            "message_id": message_id,
+            "reply_to": reply_to,
+            "target": target,
+        })))
     }
 
-    async fn execute_message_send_group(
+    async fn execute_message_send_templated(
         &self,
         params: Value,
         context: &ToolCallContext,
     ) -> Result<ToolResult> {
-        let group_id = params["group_id"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("group_id is required"))?;
-        let content = params["content"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("content is required"))?;
+        let message = match self.outgoing_message("message.send_templated", &params, context).await? {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let content = message.content.clone();
+        let message = match self.limit(message).await {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let message = match self.check_duplicate(message, &params, context.outbox.is_some()) {
+            Ok(message) => message,
+            Err(result) => return Ok(result),
+        };
 
-        let mut message = Message::group(
-            &context.caller_id,
-            group_id,
-            content
-        );
+        let sent = self.deliver(message, context).await?;
+
+        Ok(ToolResult::success(json!({ "sent": sent, "queued": !sent, "content": content })))
+    }
+
+    async fn execute_message_send_immediate(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let message = match self.outgoing_message("message.send_immediate", &params, context).await? {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let message = match self.limit(message).await {
+            Ok(message) => message,
+            Err(error) => return Ok(error),
+        };
+        let message = match self.check_duplicate(message, &params, false) {
+            Ok(message) => message,
+            Err(result) => return Ok(result),
+        };
+
+        let message_id = message.id.clone();
+        self.env.message_bus.send(message).await?;
+
+        Ok(ToolResult::success(json!({ "sent": true, "message_id": message_id })))
+    }
+
+    /// 按 `message.send_*` / `message.reply` 的参数构造将要发送的消息，参数问题以工具错误返回
+    ///
+    /// 只读取原消息等状态，不发送也不做大小限制，执行和预演共用
+    async fn outgoing_message(
+        &self,
+        tool_id: &str,
+        params: &Value,
+        context: &ToolCallContext,
+    ) -> Result<std::result::Result<Message, ToolResult>> {
+        let content = |param: &str| params[param].as_str().ok_or_else(|| self.missing_param(param));
+        let mut message = match tool_id {
+            "message.send_direct" => {
+                let to_agent_id = content("to_agent_id")?;
+                Message::private(&context.caller_id, to_agent_id, content("content")?)
+            }
+            "message.send_group" => {
+                let group_id = content("group_id")?;
+                Message::group(&context.caller_id, group_id, content("content")?)
+            }
+            "message.reply" => {
+                let message_id = content("message_id")?;
+                let text = content("content")?;
+
+                // 从消息存储中查找原消息（包括已归档的消息）
+                let Some(original) = self.env.message_store.load_message(message_id).await? else {
+                    return Ok(Err(ToolResult::error(
+                        self.text("tool.original_message_not_found", &[("message_id", message_id)]),
+                    )));
+                };
+                let reply_content = self.text("tool.reply_prefix", &[("message_id", message_id), ("content", text)]);
+                let message = match &original.to {
+                    // 私聊回复给对方：自己发出的原消息回给收件人，否则回给原发送者
+                    MessageTarget::Direct(recipient_id) if *recipient_id == context.caller_id => {
+                        Message::private(&context.caller_id, &original.from, reply_content)
+                    }
+                    MessageTarget::Direct(recipient_id) => {
+                        Message::private(&context.caller_id, recipient_id, reply_content)
+                    }
+                    // 群聊回复到同一群组
+                    MessageTarget::Group(group_id) => Message::group(&context.caller_id, group_id, reply_content),
+                };
+                message.with_reply_to(message_id)
+            }
+            "message.send_templated" => {
+                let rendered = match self.render_template(params, context).await? {
+                    Ok(rendered) => rendered,
+                    Err(error) => return Ok(Err(error)),
+                };
+                match (params["to_agent_id"].as_str(), params["group_id"].as_str()) {
+                    (Some(to_agent_id), _) => Message::private(&context.caller_id, to_agent_id, rendered),
+                    (None, Some(group_id)) => Message::group(&context.caller_id, group_id, rendered),
+                    (None, None) => return Err(self.missing_param("to_agent_id")),
+                }
+            }
+            "message.send_immediate" => {
+                let to = content("to")?;
+                let text = content("content")?;
+                match to.strip_prefix("group:") {
+                    Some(group_id) => Message::group(&context.caller_id, group_id, text),
+                    None => Message::private(&context.caller_id, to, text),
+                }
+            }
+            other => return Err(anyhow::anyhow!("{} does not send messages", other)),
+        };
+        let priority = match self.priority_param(params) {
+            Ok(priority) => priority,
+            Err(error) => return Ok(Err(error)),
+        };
+        message = message
+            .with_priority(priority)
+            .with_trace(&context.trace_id, context.parent_span_id.as_deref());
 
         // 处理 @ 列表
-        if let Some(mentions) = params["mention_agent_ids"].as_array() {
-            for mention in mentions {
-                if let Some(id) = mention.as_str() {
+        if matches!(tool_id, "message.send_group" | "message.reply") {
+            if let Some(mentions) = params["mention_agent_ids"].as_array() {
+                for id in mentions.iter().filter_map(Value::as_str) {
                     message = message.with_mention(id);
                 }
             }
         }
 
         // 处理回复
-        if let Some(reply_id) = params["reply_to_message_id"].as_str() {
-            message = message.with_reply_to(reply_id);
+        if matches!(tool_id, "message.send_direct" | "message.send_group" | "message.send_immediate") {
+            if let Some(reply_id) = params["reply_to_message_id"].as_str() {
+                message = message.with_reply_to(reply_id);
+            }
         }
 
-        self.env.message_bus.send(message).await?;
+        Ok(Ok(message))
+    }
+
+    /// 解析收件人但不发送，不经过大小限制（超长消息转附件会写入存储）
+    async fn preview_message(
+        &self,
+        tool_id: &str,
+        params: &Value,
+        context: &ToolCallContext,
+    ) -> Result<DryRunReport> {
+        let message = match self.outgoing_message(tool_id, params, context).await? {
+            Ok(message) => message,
+            Err(error) => return Err(self.error_of(error)),
+        };
+        let (target, recipients) = match &message.to {
+            MessageTarget::Direct(agent_id) => (agent_id.clone(), vec![agent_id.clone()]),
+            MessageTarget::Group(group_id) => {
+                let members = self.env.message_bus
+                    .get_group(group_id)
+                    .await
+                    .map(|group| group.members.into_iter().filter(|m| *m != context.caller_id).collect())
+                    .unwrap_or_default();
+                (format!("group:{}", group_id), members)
+            }
+        };
+        let delivery = if tool_id != "message.send_immediate" && context.outbox.is_some() {
+            "end_of_turn"
+        } else {
+            "immediate"
+        };
 
-        Ok(ToolResult::success(json!({ "sent": true })))
+        let count = recipients.len().to_string();
+        let summary = self.text(
+            "tool.dry_run_message",
+            &[("target", &target), ("count", &count), ("priority", message.priority.as_str())],
+        );
+        Ok(DryRunReport::new(tool_id, summary, json!({
+            "target": target,
+            "recipients": recipients,
+            "content": message.content,
+            "priority": message.priority.as_str(),
+            "reply_to": message.reply_to,
+            "mentions": message.mentions,
+            "delivery": delivery,
+        })))
     }
 
-    async fn execute_message_reply(
+    /// 可选的 `priority` 参数，缺省为 normal
+    fn priority_param(&self, params: &Value) -> std::result::Result<MessagePriority, ToolResult> {
+        match params["priority"].as_str() {
+            None => Ok(MessagePriority::Normal),
+            Some(name) => MessagePriority::parse(name)
+                .ok_or_else(|| ToolResult::error(self.text("tool.priority_invalid", &[("priority", name)]))),
+        }
+    }
+
+    /// 按大小限制校验待发送的消息，超限时返回告诉 Agent 如何缩短的错误
+    async fn limit(&self, message: Message) -> std::result::Result<Message, ToolResult> {
+        enforce_message_limits(&self.env.message_limits, message, self.env.blob_store.as_deref(), &self.env.catalog)
+            .await
+            .map_err(|error| ToolResult::error(error.describe(&self.env.catalog)))
+    }
+
+    /// 重复发送检查：排队的消息在轮次结束时检查，这里只标记 `allow_duplicate`；
+    /// 立即发送的消息在这里检查，窗口期内发过相同消息时返回未发送的结果
+    fn check_duplicate(&self, message: Message, params: &Value, queued: bool) -> std::result::Result<Message, ToolResult> {
+        let allow_duplicate = params["allow_duplicate"].as_bool().unwrap_or(false);
+        if queued {
+            return Ok(match allow_duplicate {
+                true => message.with_metadata(ALLOW_DUPLICATE_KEY, "true"),
+                false => message,
+            });
+        }
+        match &self.env.send_dedup {
+            Some(dedup) if !allow_duplicate && dedup.suppress(&message) => {
+                tracing::warn!("Message from {} to {:?} suppressed as a duplicate of a recent send", message.from, message.to);
+                let window_secs = dedup.config().window_secs.to_string();
+                Err(ToolResult::success(json!({
+                    "sent": false,
+                    "duplicate": true,
+                    "note": self.text("tool.duplicate_suppressed", &[("window_secs", &window_secs)]),
+                })))
+            }
+            _ => Ok(message),
+        }
+    }
+
+    /// 本轮有发件箱时入队，轮次结束统一发送；否则立即发送。返回是否已发送
+    async fn deliver(&self, message: Message, context: &ToolCallContext) -> Result<bool> {
+        match &context.outbox {
+            Some(outbox) => {
+                outbox.enqueue(message);
+                Ok(false)
+            }
+            None => {
+                self.env.message_bus.send(message).await?;
+                Ok(true)
+            }
+        }
+    }
+
+    async fn execute_message_react(
         &self,
         params: Value,
         context: &ToolCallContext,
     ) -> Result<ToolResult> {
         let message_id = params["message_id"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("message_id is required"))?;
-        let content = params["content"]
+            .ok_or_else(|| self.missing_param("message_id"))?;
+        let emoji = params["emoji"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("content is required"))?;
-
-        // 从消息存储中查找原消息
-        let original_messages = self.env.message_store.load_messages(
-            MessageFilter::new().limit(1).to(message_id)
-        ).await?;
-
-        let reply_message = if let Some(orig_msg) = original_messages.first() {
-            // 如果找到了原始消息，则根据原始消息的目标创建回复
-            let reply_content = format!("[回复消息 {}] {}", message_id, content);
-            let mut message = match &orig_msg.to {
-                MessageTarget::Direct(sender_id) => {
-                    // 如果原始消息是私聊，回复给对方
-                    if *sender_id == context.caller_id {
-                        // 如果原始消息发送者就是当前调用者，回复给原消息的发送者
-                        Message::private(&context.caller_id, &orig_msg.from, reply_content)
-                    } else {
-                        // 否则回复给原始消息发送者
-                        Message::private(&context.caller_id, sender_id, reply_content)
-                    }
-                },
-                MessageTarget::Group(group_id) => {
-                    // 如果原始消息是群聊，回复到同一群组
-                    Message::group(&context.caller_id, group_id, reply_content)
-                }
-            };
+            .ok_or_else(|| self.missing_param("emoji"))?;
+        if !MessageReaction::is_valid_emoji(emoji) {
+            return Ok(ToolResult::error(self.text("tool.invalid_reaction", &[("emoji", emoji)])));
+        }
 
-            // 设置回复关系
-            message = message.with_reply_to(message_id);
+        let reaction = MessageReaction::new(message_id, &context.caller_id, emoji);
+        let changed = if params["remove"].as_bool().unwrap_or(false) {
+            self.env.message_bus.remove_reaction(reaction).await?
+        } else {
+            self.env.message_bus.add_reaction(reaction).await?
+        };
 
-            // 处理 @ 列表
-            if let Some(mentions) = params["mention_agent_ids"].as_array() {
-                for mention in mentions {
-                    if let Some(id) = mention.as_str() {
-                        message = message.with_mention(id);
-                    }
-                }
+        Ok(ToolResult::success(json!({ "changed": changed })))
+    }
+
+    async fn execute_message_pin(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let message_id = params["message_id"]
+            .as_str()
+            .ok_or_else(|| self.missing_param("message_id"))?;
+
+        let result = if params["remove"].as_bool().unwrap_or(false) {
+            self.env
+                .message_bus
+                .unpin_message(message_id, &context.caller_id)
+                .await
+                .map(|changed| json!({ "message_id": message_id, "pinned": false, "changed": changed }))
+        } else {
+            self.env
+                .message_bus
+                .pin_message(message_id, &context.caller_id)
+                .await
+                .map(|pin| json!({ "message_id": message_id, "pinned": true, "session_id": pin.session_id, "pinned_by": pin.pinned_by }))
+        };
+        match result {
+            Ok(data) => Ok(ToolResult::success(data)),
+            Err(e) => Ok(self.pin_error(e)),
+        }
+    }
+
+    // ==================== 记忆类 ====================
+
+    async fn execute_memory_list_pins(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let session = params["session"].as_str().ok_or_else(|| self.missing_param("session"))?;
+        match self.env.message_bus.pinned_messages(session, &context.caller_id).await {
+            Ok(pinned) => {
+                let pins: Vec<Value> = pinned
+                    .iter()
+                    .map(|p| {
+                        json!({
+                            "message_id": p.pin.message_id,
+                            "from": p.message.from,
+                            "content": p.message.content,
+                            "sent_at": p.message.timestamp,
+                            "pinned_by": p.pin.pinned_by,
+                            "pinned_at": p.pin.timestamp,
+                        })
+                    })
+                    .collect();
+                Ok(ToolResult::success(json!({ "count": pins.len(), "pins": pins })))
+            }
+            Err(e) => Ok(self.pin_error(e)),
+        }
+    }
+
+    /// 置顶失败时返回给 Agent 的说明
+    fn pin_error(&self, error: anyhow::Error) -> ToolResult {
+        match error.downcast_ref::<PinError>() {
+            Some(PinError::MessageNotFound { message_id }) => {
+                ToolResult::error(self.text("tool.pin_message_not_found", &[("message_id", message_id)]))
             }
+            Some(PinError::NotParticipant { session_id, .. }) => {
+                ToolResult::error(self.text("tool.pin_not_participant", &[("session_id", session_id)]))
+            }
+            Some(PinError::NotAdmin { pinned_by, .. }) => {
+                ToolResult::error(self.text("tool.pin_not_admin", &[("pinned_by", pinned_by)]))
+            }
+            None => ToolResult::error(self.text("tool.pin_failed", &[("error", &error.to_string())])),
+        }
+    }
 
-            let message_id_clone = message.id.clone();
-            let target_clone = format!("{:?}", message.to);
+    // ==================== 模板类 ====================
 
-            // 发送消息
-            self.env.message_bus.send(message).await?;
-            Ok(ToolResult::success(json!({
-                "sent": true,
-                "message_id": message_id_clone,
-                "reply_to": message_id,
-                "target": target_clone,
-            })))
-        } else {
-            // 如果没有找到原始消息，返回错误
-            Ok(ToolResult::error(format!("Original message not found: {}", message_id)))
+    async fn execute_template_render(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        Ok(match self.render_template(&params, context).await? {
+            Ok(content) => ToolResult::success(json!(content)),
+            Err(error) => error,
+        })
+    }
+
+    /// 查找调用者可用的模板并渲染，模板问题以工具错误返回
+    async fn render_template(
+        &self,
+        params: &Value,
+        context: &ToolCallContext,
+    ) -> Result<std::result::Result<String, ToolResult>> {
+        let name = params["template"]
+            .as_str()
+            .ok_or_else(|| self.missing_param("template"))?;
+
+        let escape = match params["escape"].as_str() {
+            None => Escape::None,
+            Some(escape) => match Escape::parse(escape) {
+                Some(escape) => escape,
+                None => {
+                    return Ok(Err(ToolResult::error(
+                        self.text("tool.template_escape_invalid", &[("escape", escape)]),
+                    )))
+                }
+            },
+        };
+
+        let text = {
+            let org = self.env.organization.read().await;
+            let templates = self.env.templates.read().await;
+            template::resolve(org.find_agent(&context.caller_id), &templates, name).map(str::to_string)
+        };
+        let Some(text) = text else {
+            return Ok(Err(ToolResult::error(self.text("tool.template_not_found", &[("name", name)]))));
         };
 
-        reply_message
+        // 变量由调用者自己提供，不限制变量名
+        let sandbox = TemplateSandbox::new().with_escape(escape);
+        Ok(sandbox.render(&text, &params["variables"]).map_err(|e| match e {
+            TemplateError::MissingVariables(names) => ToolResult::error(
+                self.text("tool.template_missing_variables", &[("names", &names.join(", "))]),
+            ),
+            e => ToolResult::error(
+                self.text("tool.template_invalid", &[("name", name), ("error", &e.to_string())]),
+            ),
+        }))
     }
 
     // ==================== 时间类 ====================
@@ -410,12 +1150,12 @@ impl FrameworkToolExecutor {
     ) -> Result<ToolResult> {
         let dept_id = params["department_id"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("department_id is required"))?;
+            .ok_or_else(|| self.missing_param("department_id"))?;
 
         let org = self.env.organization.read().await;
 
         let dept = org.find_department(dept_id)
-            .ok_or_else(|| anyhow::anyhow!("Department not found: {}", dept_id))?;
+            .ok_or_else(|| anyhow::anyhow!(self.text("tool.department_not_found", &[("department_id", dept_id)])))?;
 
         let members: Vec<&crate::domain::Agent> = org.get_department_members(dept_id);
 
@@ -441,12 +1181,12 @@ impl FrameworkToolExecutor {
     ) -> Result<ToolResult> {
         let dept_id = params["department_id"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("department_id is required"))?;
+            .ok_or_else(|| self.missing_param("department_id"))?;
 
         let org = self.env.organization.read().await;
 
         let leader = org.get_department_leader(dept_id)
-            .ok_or_else(|| anyhow::anyhow!("No leader found for department: {}", dept_id))?;
+            .ok_or_else(|| anyhow::anyhow!(self.text("tool.leader_not_found", &[("department_id", dept_id)])))?;
 
         Ok(ToolResult::success(json!({
             "id": leader.id,
@@ -462,10 +1202,10 @@ impl FrameworkToolExecutor {
     ) -> Result<ToolResult> {
         let query_type = params["query_type"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("query_type is required"))?;
+            .ok_or_else(|| self.missing_param("query_type"))?;
         let query_value = params["query_value"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("query_value is required"))?;
+            .ok_or_else(|| self.missing_param("query_value"))?;
         let fuzzy = params["fuzzy_match"].as_bool().unwrap_or(false);
 
         let org = self.env.organization.read().await;
@@ -533,13 +1273,41 @@ impl FrameworkToolExecutor {
         })))
     }
 
+    async fn execute_org_find_users(
+        &self,
+        params: Value,
+    ) -> Result<ToolResult> {
+        let query_lower = params["query"].as_str().unwrap_or("").to_lowercase();
+        let users = self.env.message_store.load_users().await?;
+
+        let users_json: Vec<Value> = users.iter().filter(|user| {
+            query_lower.is_empty()
+                || user.username.to_lowercase().contains(&query_lower)
+                || user.name.to_lowercase().contains(&query_lower)
+                || user.department.to_lowercase().contains(&query_lower)
+        }).map(|user| {
+            json!({
+                "principal_id": user.principal_id(),
+                "username": user.username,
+                "name": user.name,
+                "position": format!("{:?}", user.position),
+                "department": user.department,
+            })
+        }).collect();
+
+        Ok(ToolResult::success(json!({
+            "count": users_json.len(),
+            "users": users_json,
+        })))
+    }
+
     async fn execute_org_get_sub_departments(
         &self,
         params: Value,
     ) -> Result<ToolResult> {
         let dept_id = params["department_id"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("department_id is required"))?;
+            .ok_or_else(|| self.missing_param("department_id"))?;
 
         let org = self.env.organization.read().await;
 
@@ -566,13 +1334,13 @@ impl FrameworkToolExecutor {
     ) -> Result<ToolResult> {
         let agent_id = params["agent_id"]
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("agent_id is required"))?;
+            .ok_or_else(|| self.missing_param("agent_id"))?;
 
         let org = self.env.organization.read().await;
 
         // Find the department where this Agent belongs, check if it's a leader
         let agent = org.find_agent(agent_id)
-            .ok_or_else(|| anyhow::anyhow!("Agent not found: {}", agent_id))?;
+            .ok_or_else(|| anyhow::anyhow!(self.text("tool.agent_not_found", &[("agent_id", agent_id)])))?;
 
         let mut subordinates = Vec::new();
 
@@ -607,9 +1375,763 @@ impl FrameworkToolExecutor {
     }
 }
 
-/// 使用 domain::tool::CategoryNodeInfo
-fn find_category_node(tree: &crate::domain::tool::CategoryNodeInfo, path: &str) -> Option<crate::domain::tool::CategoryNodeInfo> {
-    if tree.path == path {
+impl FrameworkToolExecutor {
+    // ==================== 自我配置类 ====================
+
+    /// 偏好只能由Agent自己读写，显式指定他人ID时拒绝
+    fn check_self_target(&self, params: &Value, context: &ToolCallContext) -> Option<ToolResult> {
+        match params["agent_id"].as_str() {
+            Some(agent_id) if agent_id != context.caller_id => Some(ToolResult::error(
+                self.text("tool.preferences_forbidden", &[("agent_id", agent_id)]),
+            )),
+            _ => None,
+        }
+    }
+
+    async fn execute_org_get_recent_changes(
+        &self,
+        params: Value,
+    ) -> Result<ToolResult> {
+        let since = params["since"]
+            .as_i64()
+            .unwrap_or_else(|| chrono::Utc::now().timestamp() - RECENT_CHANGES_WINDOW_SECS);
+        let limit = params["limit"]
+            .as_u64()
+            .map_or(RECENT_CHANGES_DEFAULT_LIMIT, |limit| limit as usize)
+            .clamp(1, RECENT_CHANGES_MAX_LIMIT);
+
+        let changes = self.env.message_store.load_org_changes(since, limit).await?;
+
+        Ok(ToolResult::success(json!({
+            "count": changes.len(),
+            "changes": changes,
+        })))
+    }
+
+    async fn execute_self_get_preferences(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        if let Some(denied) = self.check_self_target(&params, context) {
+            return Ok(denied);
+        }
+
+        let preferences = self.env.message_store
+            .load_agent_preferences(&context.caller_id)
+            .await?
+            .unwrap_or_else(|| json!({}));
+
+        Ok(ToolResult::success(json!({
+            "agent_id": context.caller_id,
+            "preferences": preferences,
+        })))
+    }
+
+    async fn execute_self_update_preferences(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let merged = match self.updated_preferences(&params, context).await? {
+            Ok((_, merged)) => merged,
+            Err(error) => return Ok(error),
+        };
+
+        self.env.message_store
+            .save_agent_preferences(&context.caller_id, &merged)
+            .await?;
+        tracing::info!(target: "audit", "Agent {} updated its preferences: {}", context.caller_id, merged);
+
+        Ok(ToolResult::success(json!({
+            "agent_id": context.caller_id,
+            "preferences": merged,
+        })))
+    }
+
+    async fn preview_update_preferences(&self, params: &Value, context: &ToolCallContext) -> Result<DryRunReport> {
+        let (current, merged) = match self.updated_preferences(params, context).await? {
+            Ok(preferences) => preferences,
+            Err(error) => return Err(self.error_of(error)),
+        };
+
+        let summary = self.text("tool.dry_run_preferences", &[("agent_id", &context.caller_id)]);
+        Ok(DryRunReport::new("self.update_preferences", summary, json!({
+            "agent_id": context.caller_id,
+            "before": current.unwrap_or_else(|| json!({})),
+            "after": merged,
+        })))
+    }
+
+    /// 校验并合并偏好更新，返回当前保存的偏好和更新后的偏好；参数问题以工具错误返回
+    async fn updated_preferences(
+        &self,
+        params: &Value,
+        context: &ToolCallContext,
+    ) -> Result<std::result::Result<(Option<Value>, Value), ToolResult>> {
+        if let Some(denied) = self.check_self_target(params, context) {
+            return Ok(Err(denied));
+        }
+
+        let update = &params["preferences"];
+        if !update.is_object() {
+            return Ok(Err(ToolResult::error(self.text("tool.param_required", &[("param", "preferences")]))));
+        }
+
+        let current = self.env.message_store.load_agent_preferences(&context.caller_id).await?;
+        let base = if params["replace"].as_bool().unwrap_or(false) {
+            None
+        } else {
+            current.as_ref()
+        };
+        let merged = merge_preferences(base, update);
+
+        if let Err(e) = validate_preferences(&merged) {
+            return Ok(Err(ToolResult::error(
+                self.text("tool.preferences_invalid", &[("error", &e.to_string())]),
+            )));
+        }
+        Ok(Ok((current, merged)))
+    }
+
+    // ==================== 会话记录类 ====================
+
+    /// 只能导出自己的私聊或自己所在的群聊
+    async fn execute_transcript_export(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let format_name = params["format"].as_str().unwrap_or("markdown");
+        let Some(format) = TranscriptFormat::parse(format_name) else {
+            return Ok(ToolResult::error(
+                self.text("tool.transcript_format_invalid", &[("format", format_name)]),
+            ));
+        };
+
+        let org = self.env.organization.read().await.clone();
+        let (session, title) = match params["session_id"].as_str() {
+            None => (TranscriptSession::Direct(context.caller_id.clone()), context.caller_id.clone()),
+            Some(id) if id == context.caller_id => (TranscriptSession::Direct(id.to_string()), id.to_string()),
+            Some(id) => {
+                let groups = self.env.message_store.load_groups().await?;
+                match groups.into_iter().find(|g| g.id == id && g.has_member(&context.caller_id)) {
+                    Some(group) => (TranscriptSession::Group(group.id), group.name),
+                    None => {
+                        return Ok(ToolResult::error(
+                            self.text("tool.transcript_forbidden", &[("session_id", id)]),
+                        ));
+                    }
+                }
+            }
+        };
+        let title = match &session {
+            TranscriptSession::Direct(_) => org
+                .find_agent(&context.caller_id)
+                .map(|a| a.name.clone())
+                .unwrap_or(title),
+            TranscriptSession::Group(_) => title,
+        };
+
+        let names = participant_names(self.env.message_store.as_ref(), &org.agents).await?;
+        let max_messages = params["max_messages"].as_u64().map_or(DEFAULT_TRANSCRIPT_MESSAGES, |n| n as usize).max(1);
+        let renderer = TranscriptRenderer::new(format, session.clone(), title).with_names(names);
+        let pager = TranscriptPager::new(
+            self.env.message_store.clone(),
+            session.clone(),
+            params["since"].as_i64(),
+            params["until"].as_i64(),
+        );
+        let (transcript, count, truncated) = export_to_string(renderer, pager, max_messages).await?;
+
+        Ok(ToolResult::success(json!({
+            "session_id": session.id(),
+            "format": format.extension(),
+            "count": count,
+            "truncated": truncated,
+            "transcript": transcript,
+        })))
+    }
+
+    // ==================== 群聊类 ====================
+
+    async fn execute_group_create_ephemeral(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let name = params["name"].as_str().ok_or_else(|| self.missing_param("name"))?;
+        let mut members: Vec<String> = params["members"]
+            .as_array()
+            .ok_or_else(|| self.missing_param("members"))?
+            .iter()
+            .filter_map(|m| m.as_str().map(str::to_string))
+            .collect();
+        if !members.contains(&context.caller_id) {
+            members.push(context.caller_id.clone());
+        }
+
+        let ttl_secs = params["ttl_secs"].as_u64().unwrap_or(DEFAULT_EPHEMERAL_GROUP_TTL_SECS);
+        if ttl_secs == 0 || ttl_secs > MAX_EPHEMERAL_GROUP_TTL_SECS {
+            return Ok(ToolResult::error(self.text(
+                "tool.group_ttl_invalid",
+                &[("max", &MAX_EPHEMERAL_GROUP_TTL_SECS.to_string())],
+            )));
+        }
+
+        let bus = &self.env.message_bus;
+        let count = bus.active_ephemeral_groups(&context.caller_id).await;
+        if count >= bus.ephemeral_group_limit() {
+            return Ok(ToolResult::error(self.text(
+                "tool.group_limit_reached",
+                &[("count", &count.to_string()), ("limit", &bus.ephemeral_group_limit().to_string())],
+            )));
+        }
+
+        let group_id = format!("ephemeral_{}", uuid::Uuid::new_v4().simple());
+        let group = match bus
+            .create_ephemeral_group(&group_id, name, &context.caller_id, members, Duration::from_secs(ttl_secs))
+            .await
+        {
+            Ok(group) => group,
+            Err(e) => {
+                return Ok(ToolResult::error(
+                    self.text("tool.group_create_failed", &[("error", &e.to_string())]),
+                ));
+            }
+        };
+
+        Ok(ToolResult::success(json!({
+            "group_id": group.id,
+            "name": group.name,
+            "members": group.members,
+            "expires_at": group.expires_at,
+        })))
+    }
+
+    async fn execute_group_invite(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let group_id = params["group_id"].as_str().ok_or_else(|| self.missing_param("group_id"))?;
+        let member_id = params["member_id"].as_str().ok_or_else(|| self.missing_param("member_id"))?;
+        let options = InviteOptions {
+            cold_start: params["cold_start"].as_bool().unwrap_or(false),
+        };
+
+        match self.env.message_bus.invite_member(group_id, &context.caller_id, member_id, options).await {
+            Ok(group) => Ok(ToolResult::success(json!({
+                "group_id": group.id,
+                "invited": member_id,
+                "members": group.members,
+            }))),
+            Err(e) => Ok(self.moderation_error(e)),
+        }
+    }
+
+    async fn execute_group_kick(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let group_id = params["group_id"].as_str().ok_or_else(|| self.missing_param("group_id"))?;
+        let member_id = params["member_id"].as_str().ok_or_else(|| self.missing_param("member_id"))?;
+
+        match self.env.message_bus.remove_member(group_id, &context.caller_id, member_id).await {
+            Ok(group) => Ok(ToolResult::success(json!({
+                "group_id": group.id,
+                "removed": member_id,
+                "members": group.members,
+            }))),
+            Err(e) => Ok(self.moderation_error(e)),
+        }
+    }
+
+    async fn execute_group_mute(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let group_id = params["group_id"].as_str().ok_or_else(|| self.missing_param("group_id"))?;
+        let member_id = params["member_id"].as_str().ok_or_else(|| self.missing_param("member_id"))?;
+        let duration_secs = params["duration_secs"].as_u64().ok_or_else(|| self.missing_param("duration_secs"))?;
+        if duration_secs == 0 || duration_secs > MAX_GROUP_MUTE_SECS {
+            return Ok(ToolResult::error(
+                self.text("tool.group_mute_invalid", &[("max", &MAX_GROUP_MUTE_SECS.to_string())]),
+            ));
+        }
+
+        let until = self.env.message_bus.clock().now() + duration_secs as i64;
+        match self.env.message_bus.mute_member(group_id, &context.caller_id, member_id, until).await {
+            Ok(group) => Ok(ToolResult::success(json!({
+                "group_id": group.id,
+                "member_id": member_id,
+                "muted_until": until,
+            }))),
+            Err(e) => Ok(self.moderation_error(e)),
+        }
+    }
+
+    /// 群聊管理失败时返回给 Agent 的说明
+    fn moderation_error(&self, error: anyhow::Error) -> ToolResult {
+        match error.downcast_ref::<GroupModerationError>() {
+            Some(GroupModerationError::NotAdmin { group_id, .. }) => {
+                ToolResult::error(self.text("tool.group_not_admin", &[("group_id", group_id)]))
+            }
+            Some(GroupModerationError::AlreadyMember { group_id, member }) => {
+                ToolResult::error(self.text("tool.group_already_member", &[("group_id", group_id), ("member", member)]))
+            }
+            _ => ToolResult::error(self.text("tool.group_moderation_failed", &[("error", &error.to_string())])),
+        }
+    }
+
+    // ==================== 任务类 ====================
+
+    /// 任务管理器，通知经消息总线投递
+    fn tasks(&self) -> TaskManager {
+        TaskManager::new(self.env.message_store.clone()).with_message_bus(self.env.message_bus.clone())
+    }
+
+    async fn execute_task_create(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let title = params["title"].as_str().ok_or_else(|| self.missing_param("title"))?;
+        let now = self.env.message_bus.clock().now();
+        let mut task = Task::new(title, &context.caller_id, now)
+            .with_description(params["description"].as_str().unwrap_or_default());
+        if let Some(assignee) = params["assignee"].as_str() {
+            task = task.with_assignee(assignee);
+        }
+        if let Some(due_at) = params["due_at"].as_i64() {
+            task = task.with_due_at(due_at);
+        }
+
+        match self.tasks().create(task).await {
+            Ok(task) => Ok(ToolResult::success(json!(task))),
+            Err(e) => Ok(self.task_error(e)),
+        }
+    }
+
+    async fn execute_task_list_mine(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let mut filter = TaskFilter::new().assignee(&context.caller_id);
+        match params["status"].as_str() {
+            Some(status) => match status.parse::<TaskStatus>() {
+                Ok(status) => filter = filter.status(status),
+                Err(_) => {
+                    return Ok(ToolResult::error(self.text("tool.task_invalid_status", &[("status", status)])));
+                }
+            },
+            None => filter = filter.open(),
+        }
+
+        let tasks = self.tasks().list(filter).await?;
+        Ok(ToolResult::success(json!({
+            "tasks": tasks,
+            "count": tasks.len(),
+        })))
+    }
+
+    async fn execute_task_update_status(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let task_id = params["task_id"].as_str().ok_or_else(|| self.missing_param("task_id"))?;
+        let status = params["status"].as_str().ok_or_else(|| self.missing_param("status"))?;
+        let Ok(status) = status.parse::<TaskStatus>() else {
+            return Ok(ToolResult::error(self.text("tool.task_invalid_status", &[("status", status)])));
+        };
+        if let Some(denied) = self.check_task_participant(task_id, &context.caller_id).await {
+            return Ok(denied);
+        }
+
+        match self.tasks().update_status(task_id, &context.caller_id, status).await {
+            Ok(task) => Ok(ToolResult::success(json!(task))),
+            Err(e) => Ok(self.task_error(e)),
+        }
+    }
+
+    async fn execute_task_comment(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let task_id = params["task_id"].as_str().ok_or_else(|| self.missing_param("task_id"))?;
+        let content = params["content"].as_str().ok_or_else(|| self.missing_param("content"))?;
+        if let Some(denied) = self.check_task_participant(task_id, &context.caller_id).await {
+            return Ok(denied);
+        }
+
+        match self.tasks().comment(task_id, &context.caller_id, content).await {
+            Ok(message) => Ok(ToolResult::success(json!({
+                "task_id": task_id,
+                "message_id": message.id,
+                "to": message.to,
+            }))),
+            Err(e) => Ok(self.task_error(e)),
+        }
+    }
+
+    /// 只有任务的创建者和负责人可以修改任务，不允许时返回错误结果
+    async fn check_task_participant(&self, task_id: &str, caller_id: &str) -> Option<ToolResult> {
+        match self.tasks().get(task_id).await {
+            Ok(task) if task.involves(caller_id) => None,
+            Ok(_) => Some(ToolResult::error(self.text("tool.task_not_participant", &[("task_id", task_id)]))),
+            Err(e) => Some(self.task_error(e)),
+        }
+    }
+
+    /// 任务操作失败时返回给 Agent 的说明
+    fn task_error(&self, error: anyhow::Error) -> ToolResult {
+        match error.downcast_ref::<TaskError>() {
+            Some(TaskError::NotFound { task_id }) => {
+                ToolResult::error(self.text("tool.task_not_found", &[("task_id", task_id)]))
+            }
+            Some(TaskError::InvalidTransition { task_id, from, to }) => ToolResult::error(self.text(
+                "tool.task_invalid_transition",
+                &[("task_id", task_id), ("from", from.as_str()), ("to", to.as_str())],
+            )),
+            Some(TaskError::EmptyTitle) => ToolResult::error(self.text("tool.param_required", &[("param", "title")])),
+            _ => ToolResult::error(error.to_string()),
+        }
+    }
+
+    // ==================== 通知类 ====================
+
+    async fn execute_notify_email(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let Some(email) = &self.env.email else {
+            return Ok(ToolResult::error(self.text("tool.email_disabled", &[])));
+        };
+        let to = params["to"].as_str().ok_or_else(|| self.missing_param("to"))?;
+        let subject = params["subject"].as_str().ok_or_else(|| self.missing_param("subject"))?;
+        let body = params["body"].as_str().ok_or_else(|| self.missing_param("body"))?;
+
+        let message = EmailMessage::new(to.trim(), subject, body);
+        let error = match email.send_as(&context.caller_id, &message).await {
+            Ok(attempts) => {
+                return Ok(ToolResult::success(json!({
+                    "to": message.to,
+                    "attempts": attempts,
+                    "sent_today": email.sent_today(&context.caller_id),
+                    "daily_cap": email.policy().daily_cap,
+                })));
+            }
+            Err(EmailError::InvalidAddress(address)) => {
+                self.text("tool.email_invalid_address", &[("address", &address)])
+            }
+            Err(EmailError::DomainNotAllowed { address, .. }) => {
+                self.text("tool.email_domain_not_allowed", &[("address", &address)])
+            }
+            Err(EmailError::DailyCapReached { cap, .. }) => {
+                self.text("tool.email_cap_reached", &[("cap", &cap.to_string())])
+            }
+            Err(EmailError::Failed { attempts, error }) => self.text(
+                "tool.email_failed",
+                &[("attempts", &attempts.to_string()), ("error", &error)],
+            ),
+        };
+        Ok(ToolResult::error(error))
+    }
+
+    // ==================== 转交类 ====================
+
+    async fn execute_handoff(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let Some(config) = &self.env.handoff else {
+            return Ok(ToolResult::error(self.text("tool.handoff_disabled", &[])));
+        };
+        let request = HandoffRequest {
+            message_id: params["message_id"].as_str().map(str::to_string),
+            reason: params["reason"].as_str().ok_or_else(|| self.missing_param("reason"))?.to_string(),
+            skill: params["skill"].as_str().map(str::to_string),
+            department: params["department"].as_str().map(str::to_string),
+        };
+
+        let handoff = Handoff::new(config.clone(), self.env.organization.clone(), self.env.message_store.clone())
+            .with_clock(self.env.message_bus.clock())
+            .with_catalog(self.env.catalog);
+        let outcome = match handoff.hand_off(&context.caller_id, request).await {
+            Ok(outcome) => outcome,
+            Err(e) => return Ok(self.handoff_error(e)),
+        };
+
+        match outcome {
+            HandoffOutcome::Forwarded { target, chain, forward, notice } => {
+                tracing::info!(target: "audit", agent_id = %context.caller_id, to = %target, hops = chain.len(), "Message handed off");
+                let forward_id = forward.id.clone();
+                let forward = (*forward).with_trace(&context.trace_id, context.parent_span_id.as_deref());
+                let notice = notice.with_trace(&context.trace_id, context.parent_span_id.as_deref());
+                self.deliver(forward, context).await?;
+                self.deliver(notice, context).await?;
+                Ok(ToolResult::success(json!({
+                    "handed_to": target,
+                    "message_id": forward_id,
+                    "hops": chain.len(),
+                    "max_hops": config.max_hops,
+                    "chain": chain,
+                })))
+            }
+            HandoffOutcome::AskedUser { candidates, notice } => {
+                let notice = notice.with_trace(&context.trace_id, context.parent_span_id.as_deref());
+                self.deliver(notice, context).await?;
+                Ok(ToolResult::success(json!({
+                    "asked_user": true,
+                    "candidates": candidates,
+                })))
+            }
+        }
+    }
+
+    /// 转交失败时返回给 Agent 的说明
+    fn handoff_error(&self, error: anyhow::Error) -> ToolResult {
+        match error.downcast_ref::<HandoffError>() {
+            Some(HandoffError::MessageNotFound { message_id }) => {
+                ToolResult::error(self.text("tool.handoff_message_not_found", &[("message_id", message_id)]))
+            }
+            Some(HandoffError::NothingToHandOff) => ToolResult::error(self.text("tool.handoff_nothing", &[])),
+            Some(HandoffError::EmptyReason) => ToolResult::error(self.text("tool.param_required", &[("param", "reason")])),
+            Some(HandoffError::NoCriteria) => ToolResult::error(self.text("tool.param_required", &[("param", "skill")])),
+            Some(HandoffError::HopLimit { hops, max_hops }) => ToolResult::error(self.text(
+                "tool.handoff_hop_limit",
+                &[("hops", &hops.to_string()), ("max_hops", &max_hops.to_string())],
+            )),
+            Some(HandoffError::NoCandidate) => ToolResult::error(self.text("tool.handoff_no_candidate", &[])),
+            None => ToolResult::error(error.to_string()),
+        }
+    }
+
+    // ==================== 代码类 ====================
+
+    async fn execute_code_run(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let Some(sandbox) = &self.env.code_sandbox else {
+            return Ok(ToolResult::error(self.text("tool.code_run_disabled", &[])));
+        };
+        let code = params["code"].as_str().ok_or_else(|| self.missing_param("code"))?;
+        let language = params["language"].as_str().unwrap_or(DEFAULT_CODE_LANGUAGE);
+        let input = params.get("input").cloned().unwrap_or(Value::Null);
+
+        match sandbox.run(language, code, &input).await {
+            Ok(output) => {
+                tracing::info!(agent_id = %context.caller_id, language, fuel_used = output.fuel_used, "Sandboxed code finished");
+                Ok(ToolResult::success(json!({
+                    "language": language,
+                    "result": output.value(),
+                    "stdout": output.stdout,
+                    "stderr": output.stderr,
+                    "fuel_used": output.fuel_used,
+                })))
+            }
+            Err(e) => Ok(self.code_run_error(sandbox, e)),
+        }
+    }
+
+    /// 运行失败时返回给 Agent 的说明，触发上限时在附加信息中标明是哪一种
+    fn code_run_error(&self, sandbox: &CodeSandbox, error: anyhow::Error) -> ToolResult {
+        let Some(sandbox_error) = error.downcast_ref::<SandboxError>() else {
+            return ToolResult::error(self.text("tool.code_run_failed", &[("error", &error.to_string())]));
+        };
+        let config = sandbox.config();
+        let text = match sandbox_error {
+            SandboxError::UnsupportedLanguage { language } => self.text(
+                "tool.code_run_unsupported_language",
+                &[("language", language), ("languages", &sandbox.languages().join(", "))],
+            ),
+            SandboxError::FuelExhausted { fuel } => self.text("tool.code_run_fuel", &[("fuel", &fuel.to_string())]),
+            SandboxError::Timeout { timeout_ms } => {
+                self.text("tool.code_run_timeout", &[("timeout_ms", &timeout_ms.to_string())])
+            }
+            SandboxError::MemoryLimit { limit_mb } => {
+                self.text("tool.code_run_memory", &[("limit_mb", &limit_mb.to_string())])
+            }
+            SandboxError::OutputLimit { max_bytes } => {
+                self.text("tool.code_run_output", &[("max_bytes", &max_bytes.to_string())])
+            }
+            SandboxError::Unavailable => self.text("tool.code_run_disabled", &[]),
+            e => self.text("tool.code_run_failed", &[("error", &e.to_string())]),
+        };
+        let result = ToolResult::error(text).with_metadata("memory_limit_mb", config.memory_limit_mb);
+        match sandbox_error.limit() {
+            Some(limit) => result.with_metadata("limit_exceeded", limit),
+            None => result,
+        }
+    }
+
+    // ==================== 临时 Agent 类 ====================
+
+    async fn execute_agent_spawn_temporary(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let Some(temp_agents) = &self.env.temp_agents else {
+            return Ok(ToolResult::error(self.text("tool.temp_agent_disabled", &[])));
+        };
+        let session_id = params["session_id"]
+            .as_str()
+            .or(context.session_id.as_deref())
+            .ok_or_else(|| self.missing_param("session_id"))?;
+        let template = params["template"].as_str().map(str::to_string);
+        let role = match (params["title"].as_str(), params["instructions"].as_str()) {
+            (Some(title), Some(instructions)) => Some(Role::simple(title, instructions)),
+            (Some(_), None) if template.is_none() => return Err(self.missing_param("instructions")),
+            (None, Some(_)) if template.is_none() => return Err(self.missing_param("title")),
+            _ if template.is_none() => return Err(self.missing_param("template")),
+            _ => None,
+        };
+        let request = SpawnRequest {
+            name: params["name"].as_str().map(str::to_string),
+            template,
+            role,
+            ttl_secs: params["ttl_secs"].as_u64(),
+        };
+
+        match temp_agents.spawn(&context.caller_id, session_id, request).await {
+            Ok(record) => Ok(ToolResult::success(json!({
+                "agent_id": record.agent.id,
+                "name": record.agent.name,
+                "session_id": record.session_id,
+                "expires_at": record.expires_at,
+                "idle_timeout_secs": temp_agents.config().idle_timeout_secs,
+            }))),
+            Err(e) => Ok(self.temp_agent_error(e)),
+        }
+    }
+
+    /// 结果只确认写入，不回显内容，草稿不会经工具结果回到上下文
+    async fn execute_scratchpad_write(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let Some(scratchpad) = &self.env.scratchpad else {
+            return Ok(ToolResult::error(self.text("tool.scratchpad_disabled", &[])));
+        };
+        let content = params["content"]
+            .as_str()
+            .filter(|content| !content.trim().is_empty())
+            .ok_or_else(|| self.missing_param("content"))?;
+
+        let entry = scratchpad
+            .write(&context.trace_id, &context.caller_id, ScratchpadSource::Tool, content)
+            .await?;
+        Ok(ToolResult::success(json!({
+            "entry_id": entry.id,
+            "run_id": entry.run_id,
+        })))
+    }
+
+    /// 任务变化时替换自己的当前目标
+    async fn execute_self_set_goal(
+        &self,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let Some(goals) = &self.env.goals else {
+            return Ok(ToolResult::error(self.text("tool.goal_disabled", &[])));
+        };
+        let text = params["goal"]
+            .as_str()
+            .map(str::trim)
+            .filter(|goal| !goal.is_empty())
+            .ok_or_else(|| self.missing_param("goal"))?;
+
+        let goal = goals.set(&context.caller_id, text, GoalSource::Agent, None);
+        Ok(ToolResult::success(json!({ "goal": goal })))
+    }
+
+    /// 任务完成时清除自己的当前目标
+    async fn execute_self_clear_goal(&self, context: &ToolCallContext) -> Result<ToolResult> {
+        let Some(goals) = &self.env.goals else {
+            return Ok(ToolResult::error(self.text("tool.goal_disabled", &[])));
+        };
+        let cleared = goals.clear(&context.caller_id);
+        Ok(ToolResult::success(json!({ "cleared": cleared })))
+    }
+
+    /// 结束自己的当前会话并生成总结
+    async fn execute_session_close(&self, params: Value, context: &ToolCallContext) -> Result<ToolResult> {
+        let Some(chat_sessions) = &self.env.chat_sessions else {
+            return Ok(ToolResult::error(self.text("tool.session_disabled", &[])));
+        };
+        let resolution = match params["resolution"].as_str().unwrap_or("resolved") {
+            "resolved" => SessionResolution::Resolved,
+            "unresolved" => SessionResolution::Unresolved,
+            other => {
+                return Ok(ToolResult::error(self.text("tool.session_invalid_resolution", &[("resolution", other)])));
+            }
+        };
+        let note = params["note"].as_str().map(str::to_string);
+
+        match chat_sessions.close(&context.caller_id, resolution, &context.caller_id, note).await {
+            Ok(session) => Ok(ToolResult::success(json!(session))),
+            Err(e) => match e.downcast_ref::<ChatSessionError>() {
+                Some(ChatSessionError::NotFound { .. } | ChatSessionError::AlreadyClosed { .. }) => {
+                    Ok(ToolResult::error(self.text("tool.session_not_open", &[])))
+                }
+                _ => Err(e),
+            },
+        }
+    }
+
+    /// 查看自己所在群聊的活动统计
+    async fn execute_group_stats(&self, params: Value, context: &ToolCallContext) -> Result<ToolResult> {
+        let Some(group_stats) = &self.env.group_stats else {
+            return Ok(ToolResult::error(self.text("tool.group_stats_disabled", &[])));
+        };
+        let group_id = params["group_id"].as_str().ok_or_else(|| self.missing_param("group_id"))?;
+        // 只能查看自己所在的群聊，不存在的群聊同样按不是成员处理
+        let is_member = self
+            .env
+            .message_bus
+            .get_group(group_id)
+            .await
+            .is_some_and(|group| group.has_member(&context.caller_id));
+        if !is_member {
+            return Ok(ToolResult::error(self.text("tool.group_not_member", &[("group_id", group_id)])));
+        }
+        let report = group_stats.report(group_id).await?;
+        Ok(ToolResult::success(json!(report)))
+    }
+
+    /// 创建临时 Agent 失败时返回给 Agent 的说明
+    fn temp_agent_error(&self, error: anyhow::Error) -> ToolResult {
+        match error.downcast_ref::<TempAgentError>() {
+            Some(TempAgentError::Disabled) => ToolResult::error(self.text("tool.temp_agent_disabled", &[])),
+            Some(TempAgentError::NestedSpawn) => ToolResult::error(self.text("tool.temp_agent_nested", &[])),
+            Some(TempAgentError::LimitReached { count, limit }) => ToolResult::error(self.text(
+                "tool.temp_agent_limit",
+                &[("count", &count.to_string()), ("limit", &limit.to_string())],
+            )),
+            Some(TempAgentError::SpawnerLimitReached { count, limit, .. }) => ToolResult::error(self.text(
+                "tool.temp_agent_spawner_limit",
+                &[("count", &count.to_string()), ("limit", &limit.to_string())],
+            )),
+            _ => ToolResult::error(self.text("tool.temp_agent_failed", &[("error", &error.to_string())])),
+        }
+    }
+}
+
+/// 使用 domain::tool::CategoryNodeInfo
+fn find_category_node(tree: &crate::domain::tool::CategoryNodeInfo, path: &str) -> Option<crate::domain::tool::CategoryNodeInfo> {
+    if tree.path == path {
         return Some(tree.clone());
     }
 
@@ -635,12 +2157,14 @@ impl ToolExecutorTrait for FrameworkToolExecutor {
         if result.success {
             Ok(result.data)
         } else {
-            Err(anyhow::anyhow!(
-                result.error.unwrap_or_else(|| "Unknown error".to_string())
-            ))
+            Err(self.error_of(result))
         }
     }
 
+    async fn dry_run(&self, tool_id: &str, params: Value, context: &ToolCallContext) -> Result<DryRunReport> {
+        self.preview(tool_id, params, context).await
+    }
+
     fn can_execute(&self, tool_id: &str) -> bool {
         Self::supported_tool_ids().contains(&tool_id)
     }
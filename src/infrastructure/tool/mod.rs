@@ -4,16 +4,31 @@
 
 use anyhow::Result;
 use async_trait::async_trait;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
-
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{info_span, warn, Instrument};
+
+#[cfg(feature = "chaos")]
+use crate::core::chaos::{FaultInjector, Subsystem};
+use crate::core::events::{CompanyEvent, EventBus};
+use crate::core::i18n::MessageCatalog;
 use crate::core::skill::SkillManager;
-use crate::core::tool::ToolRegistry;
-use crate::domain::tool::ToolCallContext;
+use crate::core::tool::{ToolAlias, ToolRegistry, ToolRoute};
+use crate::core::tool_concurrency::ToolConcurrency;
+use crate::core::tool_stats::ToolStats;
+use crate::core::watchdog::{ToolExecutionEvent, WatchdogFramework};
+use crate::domain::tool::{ToolCallContext, ToolProgress, ToolProgressEvent};
 
 pub mod framework_tools;
+pub mod streaming;
 
 pub use framework_tools::{FrameworkToolExecutor, ToolEnvironment};
+pub use streaming::{tool_stream, ProgressReporter, StreamingToolExecutor, ToolStream, ToolStreamItem};
 
 
 /// 工具执行器接口 - 由具体实现者提供
@@ -42,6 +57,66 @@ pub trait ToolExecutor: Send + Sync {
     fn supported_tools(&self) -> Vec<String> {
         Vec::new()
     }
+
+    /// 预演工具调用：报告将会发生什么，但不产生任何副作用
+    ///
+    /// 默认不支持预演，返回 [`DryRunError::Unsupported`]；不能预演的执行器不得退而真正执行
+    async fn dry_run(&self, tool_id: &str, _params: Value, _context: &ToolCallContext) -> Result<DryRunReport> {
+        Err(DryRunError::Unsupported { tool_id: tool_id.to_string() }.into())
+    }
+}
+
+/// 工具参数中请求预演的约定字段
+pub const DRY_RUN_PARAM: &str = "dry_run";
+
+/// 预演报告
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DryRunReport {
+    pub tool_id: String,
+    /// 一句话说明将会发生什么
+    pub summary: String,
+    /// 将产生的效果，如解析后的收件人和渲染后的内容
+    pub effects: Value,
+}
+
+impl DryRunReport {
+    pub fn new(tool_id: impl Into<String>, summary: impl Into<String>, effects: Value) -> Self {
+        Self {
+            tool_id: tool_id.into(),
+            summary: summary.into(),
+            effects,
+        }
+    }
+}
+
+/// 预演失败
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DryRunError {
+    #[error("Tool {tool_id} does not support dry runs")]
+    Unsupported { tool_id: String },
+}
+
+/// 取出参数中的 `dry_run` 字段，返回是否请求预演；真正执行时工具看不到该字段
+pub fn take_dry_run_flag(params: &mut Value) -> bool {
+    params
+        .as_object_mut()
+        .and_then(|object| object.remove(DRY_RUN_PARAM))
+        .and_then(|flag| flag.as_bool())
+        .unwrap_or(false)
+}
+
+/// 把预演结果转为工具结果：报告作为数据并标记 `dry_run`，不支持预演时返回明确的失败结果
+pub(crate) fn dry_run_result(outcome: Result<DryRunReport>, catalog: &MessageCatalog) -> Result<ToolResult> {
+    match outcome {
+        Ok(report) => Ok(ToolResult::success(serde_json::to_value(report)?).with_metadata("dry_run", true)),
+        Err(e) => match e.downcast_ref::<DryRunError>() {
+            Some(DryRunError::Unsupported { tool_id }) => Ok(ToolResult::error(
+                catalog.format("tool.dry_run_unsupported", &[("tool_id", tool_id)]),
+            )
+            .with_metadata("dry_run", true)),
+            None => Err(e),
+        },
+    }
 }
 
 /// 工具调用结果
@@ -53,6 +128,8 @@ pub struct ToolResult {
     pub data: Value,
     /// 错误信息（如果失败）
     pub error: Option<String>,
+    /// 执行附加信息（如排队时长）
+    pub metadata: HashMap<String, Value>,
 }
 
 impl ToolResult {
@@ -62,6 +139,7 @@ impl ToolResult {
             success: true,
             data,
             error: None,
+            metadata: HashMap::new(),
         }
     }
 
@@ -71,16 +149,105 @@ impl ToolResult {
             success: false,
             data: Value::Null,
             error: Some(msg.into()),
+            metadata: HashMap::new(),
         }
     }
+
+    /// 添加附加信息
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// 解析工具别名：返回规范ID和命中的别名，停用后的别名直接返回失败结果
+///
+/// 命中别名时记录警告和别名调用统计
+pub(crate) fn resolve_alias(
+    registry: &ToolRegistry,
+    catalog: &MessageCatalog,
+    stats: &ToolStats,
+    tool_id: &str,
+) -> std::result::Result<(String, Option<ToolAlias>), ToolResult> {
+    match registry.route(tool_id) {
+        ToolRoute::Direct => Ok((tool_id.to_string(), None)),
+        ToolRoute::Alias(alias) => {
+            warn!("Deprecated tool alias {} called, routing to {}", alias.alias, alias.canonical);
+            stats.record_alias_call(&alias.alias);
+            Ok((alias.canonical.clone(), Some(alias)))
+        }
+        ToolRoute::Retired(alias) => {
+            warn!("Retired tool alias {} called, use {}", alias.alias, alias.canonical);
+            stats.record_alias_call(&alias.alias);
+            let sunset = alias.sunset.map(|d| d.to_string()).unwrap_or_default();
+            Err(ToolResult::error(catalog.format(
+                "tool.alias_retired",
+                &[("alias", &alias.alias), ("tool_id", &alias.canonical), ("sunset", &sunset)],
+            ))
+            .with_metadata("canonical_id", alias.canonical.as_str()))
+        }
+    }
+}
+
+/// 经别名调用的结果附带规范ID和弃用提示
+pub(crate) fn annotate_alias(result: ToolResult, alias: Option<&ToolAlias>, catalog: &MessageCatalog) -> ToolResult {
+    let Some(alias) = alias else {
+        return result;
+    };
+    result
+        .with_metadata("canonical_id", alias.canonical.as_str())
+        .with_metadata(
+            "deprecation",
+            catalog.format("tool.deprecated_alias", &[("alias", &alias.alias), ("tool_id", &alias.canonical)]),
+        )
+}
+
+/// 进度摘要中最终结果保留的字符数
+const SUMMARY_RESULT_CHARS: usize = 200;
+
+/// 路由到的执行器
+#[derive(Clone, Copy)]
+enum ExecutorRef<'a> {
+    Plain(&'a dyn ToolExecutor),
+    Streaming(&'a dyn StreamingToolExecutor),
+}
+
+/// 驱动事件流的结果
+struct StreamOutcome {
+    result: Result<Value>,
+    updates: u64,
+    /// 超过活动超时时为最后一次活动后的空闲时长
+    timed_out: Option<Duration>,
 }
 
 /// 工具执行器注册表
 ///
-/// 管理多个执行器，根据工具ID路由到对应的执行器
+/// 管理多个执行器，根据工具ID路由到对应的执行器。普通执行器和流式执行器走同一条执行路径，
+/// 普通执行器被视为只有最终结果的流
 pub struct ToolExecutorRegistry {
     executors: Vec<Box<dyn ToolExecutor>>,
+    streaming: Vec<Arc<dyn StreamingToolExecutor>>,
     skill_manager: Arc<SkillManager>,
+    catalog: MessageCatalog,
+    stats: Arc<ToolStats>,
+    events: Option<Arc<EventBus>>,
+    concurrency: Arc<ToolConcurrency>,
+    activity_timeout: Option<Duration>,
+    stall_threshold: Option<Duration>,
+    watchdog: Option<Arc<WatchdogFramework>>,
+    progress: Option<broadcast::Sender<ToolProgressEvent>>,
+    summarize_progress: bool,
+    #[cfg(feature = "chaos")]
+    fault_injector: Option<Arc<FaultInjector>>,
+}
+
+impl std::fmt::Debug for ToolExecutorRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolExecutorRegistry")
+            .field("executors", &self.executors.len())
+            .field("streaming", &self.streaming.len())
+            .finish_non_exhaustive()
+    }
 }
 
 impl ToolExecutorRegistry {
@@ -88,10 +255,100 @@ impl ToolExecutorRegistry {
     pub fn new(skill_manager: Arc<SkillManager>) -> Self {
         Self {
             executors: Vec::new(),
+            streaming: Vec::new(),
             skill_manager,
+            catalog: MessageCatalog::default(),
+            stats: Arc::new(ToolStats::new()),
+            events: None,
+            concurrency: Arc::new(ToolConcurrency::default()),
+            activity_timeout: None,
+            stall_threshold: None,
+            watchdog: None,
+            progress: None,
+            summarize_progress: false,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
         }
     }
 
+    /// 执行前询问故障注入器（子系统 `tools`，操作为工具 ID），注入的错误计入调用统计
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injector(mut self, injector: Arc<FaultInjector>) -> Self {
+        self.fault_injector = Some(injector);
+        self
+    }
+
+    /// 使用共享的工具统计收集器
+    pub fn with_stats(mut self, stats: Arc<ToolStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// 每次执行后发出 `ToolExecuted` 事件
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// 获取工具统计收集器
+    pub fn stats(&self) -> Arc<ToolStats> {
+        self.stats.clone()
+    }
+
+    /// 使用共享的工具并发控制器
+    pub fn with_concurrency(mut self, concurrency: Arc<ToolConcurrency>) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// 获取工具并发控制器（含排队指标）
+    pub fn concurrency(&self) -> Arc<ToolConcurrency> {
+        self.concurrency.clone()
+    }
+
+    /// 设置错误信息使用的消息目录
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// 活动超时：超过该时长既没有进度也没有结果时停止执行，每次进度都重新计时；
+    /// 普通执行器没有进度，相当于整体超时
+    pub fn with_activity_timeout(mut self, timeout: Duration) -> Self {
+        self.activity_timeout = Some(timeout);
+        self
+    }
+
+    /// 停滞阈值：超过该时长没有进度时向 Watchdog 发出一次 `Stalled` 事件，不中断执行
+    pub fn with_stall_threshold(mut self, threshold: Duration) -> Self {
+        self.stall_threshold = Some(threshold);
+        self
+    }
+
+    /// 把进度和停滞事件交给 Watchdog 评估规则
+    pub fn with_watchdog(mut self, watchdog: Arc<WatchdogFramework>) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// 使用共享的进度广播（如 `VirtualCompany::tool_progress_sender`），Web 层转发给订阅的客户端
+    pub fn with_progress_sender(mut self, sender: broadcast::Sender<ToolProgressEvent>) -> Self {
+        self.progress = Some(sender);
+        self
+    }
+
+    /// 订阅进度事件；未设置广播时创建一个
+    pub fn subscribe_progress(&mut self) -> broadcast::Receiver<ToolProgressEvent> {
+        self.progress.get_or_insert_with(|| broadcast::channel(256).0).subscribe()
+    }
+
+    /// 有进度的调用在结果的 `progress_summary` 中附上一句摘要（运行时长、进度次数和最终结果），
+    /// 供 Agent 代替完整结果放入上下文
+    pub fn with_progress_summary(mut self) -> Self {
+        self.summarize_progress = true;
+        self
+    }
+
     /// 创建注册表（使用默认技能管理器）
     pub fn with_default_skill_manager(tool_registry: Arc<ToolRegistry>) -> Self {
         let skill_manager = Arc::new(SkillManager::new_with_tool_registry(tool_registry));
@@ -103,6 +360,11 @@ impl ToolExecutorRegistry {
         self.executors.push(executor);
     }
 
+    /// 注册流式执行器，普通执行器都不支持的工具才路由到这里
+    pub fn register_streaming(&mut self, executor: Arc<dyn StreamingToolExecutor>) {
+        self.streaming.push(executor);
+    }
+
     /// 查找可以执行指定工具的执行器
     pub fn find_executor(&self, tool_id: &str) -> Option<&dyn ToolExecutor> {
         self.executors
@@ -119,18 +381,73 @@ impl ToolExecutorRegistry {
             .map(|e| e.as_ref())
     }
 
+    fn find_streaming(&self, tool_id: &str) -> Option<&dyn StreamingToolExecutor> {
+        self.streaming.iter().find(|e| e.can_execute(tool_id)).map(|e| e.as_ref())
+    }
+
+    /// 先找普通执行器，再找流式执行器
+    fn route(&self, tool_id: &str, skills: Option<&[String]>) -> Option<ExecutorRef<'_>> {
+        let plain = match skills {
+            Some(skills) => self.find_executor_with_skills(tool_id, skills),
+            None => self.find_executor(tool_id),
+        };
+        plain
+            .map(ExecutorRef::Plain)
+            .or_else(|| self.find_streaming(tool_id).map(ExecutorRef::Streaming))
+    }
+
     /// 执行工具调用（自动路由到合适的执行器）
-    pub async fn execute(&self, tool_id: &str, params: Value, context: &ToolCallContext) -> Result<ToolResult> {
-        match self.find_executor(tool_id) {
-            Some(executor) => {
-                let result = executor.execute(tool_id, params, context).await?;
-                Ok(ToolResult::success(result))
-            }
-            None => Ok(ToolResult::error(format!(
-                "No executor found for tool: {}",
-                tool_id
-            ))),
-        }
+    ///
+    /// 参数带 `dry_run: true` 时改为预演，见 [`Self::execute_dry_run`]
+    pub async fn execute(&self, tool_id: &str, mut params: Value, context: &ToolCallContext) -> Result<ToolResult> {
+        let dry_run = take_dry_run_flag(&mut params);
+        let registry = self.skill_manager.tool_registry();
+        let (tool_id, alias) = match resolve_alias(&registry, &self.catalog, &self.stats, tool_id) {
+            Ok(resolved) => resolved,
+            Err(retired) => return Ok(retired),
+        };
+        let result = match self.route(&tool_id, None) {
+            Some(executor) if dry_run => self.dry_run_routed(executor, &tool_id, params, context).await?,
+            Some(executor) => self.execute_tracked(executor, &tool_id, params, context).await?,
+            None => ToolResult::error(
+                self.catalog.format("tool.no_executor", &[("tool_id", &tool_id)]),
+            ),
+        };
+        Ok(annotate_alias(result, alias.as_ref(), &self.catalog))
+    }
+
+    /// 预演工具调用：由执行器报告将会发生什么，不执行、不计入调用统计
+    ///
+    /// 执行器不支持预演时返回失败结果，不会退而真正执行
+    pub async fn execute_dry_run(&self, tool_id: &str, mut params: Value, context: &ToolCallContext) -> Result<ToolResult> {
+        take_dry_run_flag(&mut params);
+        let registry = self.skill_manager.tool_registry();
+        let (tool_id, alias) = match resolve_alias(&registry, &self.catalog, &self.stats, tool_id) {
+            Ok(resolved) => resolved,
+            Err(retired) => return Ok(retired),
+        };
+        let result = match self.route(&tool_id, None) {
+            Some(executor) => self.dry_run_routed(executor, &tool_id, params, context).await?,
+            None => ToolResult::error(
+                self.catalog.format("tool.no_executor", &[("tool_id", &tool_id)]),
+            ),
+        };
+        Ok(annotate_alias(result, alias.as_ref(), &self.catalog))
+    }
+
+    /// 流式执行器没有预演接口，一律视为不支持
+    async fn dry_run_routed(
+        &self,
+        executor: ExecutorRef<'_>,
+        tool_id: &str,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let outcome = match executor {
+            ExecutorRef::Plain(executor) => executor.dry_run(tool_id, params, context).await,
+            ExecutorRef::Streaming(_) => Err(DryRunError::Unsupported { tool_id: tool_id.to_string() }.into()),
+        };
+        dry_run_result(outcome, &self.catalog)
     }
 
     /// 执行工具调用（带技能验证）
@@ -141,36 +458,253 @@ impl ToolExecutorRegistry {
         context: &ToolCallContext,
         caller_skills: &[String],
     ) -> Result<ToolResult> {
+        let mut params = params;
+        let dry_run = take_dry_run_flag(&mut params);
+        // 别名按规范ID检查技能和执行
+        let registry = self.skill_manager.tool_registry();
+        let (tool_id, alias) = match resolve_alias(&registry, &self.catalog, &self.stats, tool_id) {
+            Ok(resolved) => resolved,
+            Err(retired) => return Ok(retired),
+        };
+
         // 首先检查技能权限
-        if !self.skill_manager.can_call_tool(tool_id, caller_skills) {
-            return Ok(ToolResult::error(format!(
-                "Insufficient skills to execute tool: {}",
-                tool_id
-            )));
+        if !self.skill_manager.can_call_tool(&tool_id, caller_skills) {
+            return Ok(ToolResult::error(
+                self.catalog.format("tool.insufficient_skills", &[("tool_id", &tool_id)]),
+            ));
         }
 
         // 查找可以执行的执行器
-        match self.find_executor_with_skills(tool_id, caller_skills) {
-            Some(executor) => {
-                let result = executor.execute(tool_id, params, context).await?;
-                Ok(ToolResult::success(result))
+        let result = match self.route(&tool_id, Some(caller_skills)) {
+            Some(executor) if dry_run => self.dry_run_routed(executor, &tool_id, params, context).await?,
+            Some(executor) => self.execute_tracked(executor, &tool_id, params, context).await?,
+            None => ToolResult::error(
+                self.catalog.format("tool.no_executor", &[("tool_id", &tool_id)]),
+            ),
+        };
+        Ok(annotate_alias(result, alias.as_ref(), &self.catalog))
+    }
+
+    /// 执行并记录调用统计
+    async fn execute_tracked(
+        &self,
+        executor: ExecutorRef<'_>,
+        tool_id: &str,
+        params: Value,
+        context: &ToolCallContext,
+    ) -> Result<ToolResult> {
+        let span = info_span!(
+            "tool_call",
+            tool_id,
+            caller_id = %context.caller_id,
+            trace_id = %context.trace_id,
+            parent_span_id = context.parent_span_id.as_deref(),
+        );
+        let tool_registry = self.skill_manager.tool_registry();
+        let permit = match self.concurrency.acquire(tool_id, || tool_registry.get(tool_id)).await {
+            Ok(permit) => permit,
+            Err(busy) => {
+                let message = match &busy.mutex_group {
+                    Some(group) => self.catalog.format("tool.busy_group", &[("tool_id", tool_id), ("group", group)]),
+                    None => self.catalog.format("tool.busy", &[("tool_id", tool_id)]),
+                };
+                return Ok(ToolResult::error(message)
+                    .with_metadata("busy", true)
+                    .with_metadata("queued_ms", busy.waited.as_millis() as u64));
+            }
+        };
+
+        let started = Instant::now();
+        let outcome = match self.inject_fault(tool_id).await {
+            Ok(()) => {
+                let stream: BoxStream<'_, ToolStreamItem> = match executor {
+                    ExecutorRef::Plain(executor) => stream::once(async move {
+                        ToolStreamItem::Finished(executor.execute(tool_id, params, context).await)
+                    })
+                    .boxed(),
+                    ExecutorRef::Streaming(executor) => executor.execute_stream(tool_id, params, context),
+                };
+                self.drive(tool_id, stream, context, started).instrument(span).await
             }
-            None => Ok(ToolResult::error(format!(
-                "No executor found for tool: {}",
-                tool_id
-            ))),
+            Err(e) => StreamOutcome { result: Err(e), updates: 0, timed_out: None },
+        };
+        let (queued, waited) = (permit.queued(), permit.waited());
+        drop(permit);
+        let timeout_error = outcome.timed_out.map(|idle| {
+            let seconds = format!("{:.1}", idle.as_secs_f64());
+            self.catalog.format("tool.stalled", &[("tool_id", tool_id), ("seconds", &seconds)])
+        });
+        let result = outcome.result;
+        let error = timeout_error.clone().or_else(|| result.as_ref().err().map(|e| e.to_string()));
+        let elapsed = started.elapsed();
+        self.stats.record(tool_id, &context.caller_id, elapsed, error.as_deref());
+
+        if let Some(events) = &self.events {
+            events.emit(CompanyEvent::ToolExecuted {
+                tool_id: tool_id.into(),
+                caller_id: context.caller_id.as_str().into(),
+                trace_id: context.trace_id.as_str().into(),
+                duration_ms: elapsed.as_millis() as u64,
+                error: error.as_deref().map(Into::into),
+            });
+        }
+
+        if let Some(message) = timeout_error {
+            warn!("Tool {} timed out after {} progress updates", tool_id, outcome.updates);
+            return Ok(ToolResult::error(message)
+                .with_metadata("timed_out", true)
+                .with_metadata("progress_updates", outcome.updates));
         }
+        let data = result?;
+        let summary = (self.summarize_progress && outcome.updates > 0)
+            .then(|| self.progress_summary(tool_id, elapsed, outcome.updates, &data));
+        let mut tool_result = ToolResult::success(data);
+        if outcome.updates > 0 {
+            tool_result = tool_result
+                .with_metadata("progress_updates", outcome.updates)
+                .with_metadata("duration_ms", elapsed.as_millis() as u64);
+        }
+        if let Some(summary) = summary {
+            tool_result = tool_result.with_metadata("progress_summary", summary);
+        }
+        if queued {
+            let seconds = format!("{:.1}", waited.as_secs_f64());
+            tool_result = tool_result
+                .with_metadata("queued_ms", waited.as_millis() as u64)
+                .with_metadata("notice", self.catalog.format("tool.queued", &[("tool_id", tool_id), ("seconds", &seconds)]));
+        }
+        Ok(tool_result)
+    }
+
+    /// 读取事件流直到最终结果，转发进度并按活动超时和停滞阈值计时
+    async fn drive(
+        &self,
+        tool_id: &str,
+        mut stream: BoxStream<'_, ToolStreamItem>,
+        context: &ToolCallContext,
+        started: Instant,
+    ) -> StreamOutcome {
+        let mut updates = 0;
+        let mut last_activity = tokio::time::Instant::now();
+        let mut stalled = false;
+        loop {
+            let stall_at = self.stall_threshold.map(|threshold| last_activity + threshold);
+            let timeout_at = self.activity_timeout.map(|timeout| last_activity + timeout);
+            tokio::select! {
+                item = stream.next() => match item {
+                    Some(ToolStreamItem::Progress(progress)) => {
+                        updates += 1;
+                        last_activity = tokio::time::Instant::now();
+                        stalled = false;
+                        self.report_progress(tool_id, context, updates, started, progress).await;
+                    }
+                    Some(ToolStreamItem::Finished(result)) => {
+                        return StreamOutcome { result, updates, timed_out: None };
+                    }
+                    None => {
+                        let result = Err(anyhow::anyhow!("Tool {} ended without a result", tool_id));
+                        return StreamOutcome { result, updates, timed_out: None };
+                    }
+                },
+                _ = sleep_until(stall_at), if !stalled => {
+                    stalled = true;
+                    let idle_ms = last_activity.elapsed().as_millis() as u64;
+                    warn!("Tool {} has made no progress for {}ms", tool_id, idle_ms);
+                    self.watch(ToolExecutionEvent::Stalled {
+                        tool_id: tool_id.to_string(),
+                        idle_ms,
+                        context: context.clone(),
+                    })
+                    .await;
+                }
+                _ = sleep_until(timeout_at) => {
+                    let result = Err(anyhow::anyhow!("Tool {} timed out", tool_id));
+                    return StreamOutcome { result, updates, timed_out: Some(last_activity.elapsed()) };
+                }
+            }
+        }
+    }
+
+    /// 广播进度事件并交给 Watchdog
+    async fn report_progress(
+        &self,
+        tool_id: &str,
+        context: &ToolCallContext,
+        sequence: u64,
+        started: Instant,
+        progress: ToolProgress,
+    ) {
+        if let Some(sender) = &self.progress {
+            let _ = sender.send(ToolProgressEvent {
+                tool_id: tool_id.to_string(),
+                caller_id: context.caller_id.clone(),
+                trace_id: context.trace_id.clone(),
+                sequence,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+                progress: progress.clone(),
+            });
+        }
+        self.watch(ToolExecutionEvent::Progress {
+            tool_id: tool_id.to_string(),
+            progress,
+            context: context.clone(),
+        })
+        .await;
+    }
+
+    async fn watch(&self, event: ToolExecutionEvent) {
+        if let Some(watchdog) = &self.watchdog {
+            if let Err(e) = watchdog.process_event(&event).await {
+                warn!("Watchdog failed to process {} event: {}", event.tool_id(), e);
+            }
+        }
+    }
+
+    /// 一句话的执行摘要，最终结果截断到 [`SUMMARY_RESULT_CHARS`] 个字符
+    fn progress_summary(&self, tool_id: &str, elapsed: Duration, updates: u64, data: &Value) -> String {
+        let text = match data {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        };
+        let mut result: String = text.chars().take(SUMMARY_RESULT_CHARS).collect();
+        if result.len() < text.len() {
+            result.push('…');
+        }
+        self.catalog.format(
+            "tool.progress_summary",
+            &[
+                ("tool_id", tool_id),
+                ("duration", &format_duration(elapsed)),
+                ("updates", &updates.to_string()),
+                ("result", &result),
+            ],
+        )
+    }
+
+    #[cfg(feature = "chaos")]
+    async fn inject_fault(&self, tool_id: &str) -> Result<()> {
+        if let Some(injector) = &self.fault_injector {
+            injector.inject(Subsystem::Tools, tool_id).await?;
+        }
+        Ok(())
     }
 
-    /// 检查是否有执行器支持该工具
+    #[cfg(not(feature = "chaos"))]
+    async fn inject_fault(&self, _tool_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// 检查是否有执行器支持该工具（别名按规范ID判断）
     pub fn can_execute(&self, tool_id: &str) -> bool {
-        self.find_executor(tool_id).is_some()
+        let tool_id = self.skill_manager.tool_registry().canonical_id(tool_id);
+        self.route(&tool_id, None).is_some()
     }
 
     /// 检查是否有执行器支持该工具（带技能验证）
     pub fn can_execute_with_skills(&self, tool_id: &str, skills: &[String]) -> bool {
-        self.find_executor_with_skills(tool_id, skills).is_some() &&
-        self.skill_manager.can_call_tool(tool_id, skills)
+        let tool_id = self.skill_manager.tool_registry().canonical_id(tool_id);
+        self.route(&tool_id, Some(skills)).is_some() &&
+        self.skill_manager.can_call_tool(&tool_id, skills)
     }
 
     /// 获取所有支持的工具ID
@@ -178,10 +712,29 @@ impl ToolExecutorRegistry {
         self.executors
             .iter()
             .flat_map(|e| e.supported_tools())
+            .chain(self.streaming.iter().flat_map(|e| e.supported_tools()))
             .collect()
     }
 }
 
+/// 在截止时间休眠，没有截止时间时永不返回
+async fn sleep_until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 运行时长，如 `45s`、`3m`、`3m 5s`
+fn format_duration(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) => format!("{}s", seconds),
+        (minutes, 0) => format!("{}m", minutes),
+        (minutes, seconds) => format!("{}m {}s", minutes, seconds),
+    }
+}
+
 // 注意：由于ToolExecutorRegistry现在需要SkillManager参数，不能提供默认实现
 // 用户需要显式创建实例
 
@@ -0,0 +1,84 @@
+//! 流式工具执行器
+//!
+//! 长时间运行的工具实现 [`StreamingToolExecutor`]，执行时返回一串 [`ToolStreamItem::Progress`]，
+//! 以一个 [`ToolStreamItem::Finished`] 结束。`ToolExecutorRegistry` 把普通执行器包装成只有
+//! 最终结果的流，两类执行器走同一条执行路径（统计、超时、进度转发）。
+
+use std::future::Future;
+
+use anyhow::Result;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use serde_json::Value;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::domain::tool::{ToolCallContext, ToolProgress};
+
+/// 流式执行产生的事件
+#[derive(Debug)]
+pub enum ToolStreamItem {
+    /// 中间进度
+    Progress(ToolProgress),
+    /// 最终结果，之后的事件被忽略
+    Finished(Result<Value>),
+}
+
+/// 流式执行的事件流
+pub type ToolStream = BoxStream<'static, ToolStreamItem>;
+
+/// 流式工具执行器接口
+pub trait StreamingToolExecutor: Send + Sync {
+    /// 开始执行，返回以 [`ToolStreamItem::Finished`] 结束的事件流
+    fn execute_stream(&self, tool_id: &str, params: Value, context: &ToolCallContext) -> ToolStream;
+
+    /// 检查是否支持某工具
+    fn can_execute(&self, tool_id: &str) -> bool;
+
+    /// 获取执行器支持的所有工具ID
+    fn supported_tools(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// 向事件流报告进度
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    tx: mpsc::Sender<ToolStreamItem>,
+}
+
+impl ProgressReporter {
+    /// 报告一次进度；调用方已放弃（如超时）时静默丢弃
+    pub async fn report(&self, progress: ToolProgress) {
+        let _ = self.tx.send(ToolStreamItem::Progress(progress)).await;
+    }
+}
+
+/// 流被丢弃时中止后台任务
+struct AbortOnDrop(JoinHandle<()>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// 用异步任务构造事件流：任务通过 [`ProgressReporter`] 报告进度，返回值作为最终结果
+///
+/// 任务在后台运行，流被丢弃（如活动超时）时任务随之中止。
+pub fn tool_stream<F, Fut>(task: F) -> ToolStream
+where
+    F: FnOnce(ProgressReporter) -> Fut,
+    Fut: Future<Output = Result<Value>> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(16);
+    let fut = task(ProgressReporter { tx: tx.clone() });
+    let handle = tokio::spawn(async move {
+        let result = fut.await;
+        let _ = tx.send(ToolStreamItem::Finished(result)).await;
+    });
+    stream::unfold((rx, AbortOnDrop(handle)), |(mut rx, guard)| async move {
+        let item = rx.recv().await?;
+        Some((item, (rx, guard)))
+    })
+    .boxed()
+}
@@ -0,0 +1,119 @@
+//! Agent 批量操作接口
+//!
+//! `POST /admin/agents/batch` 一次提交多个 Agent 操作（需要 `manage_agents`），逐项检查部门范围的权限，
+//! 返回逐项结果；有变化时整体保存一次组织架构并记入组织变更。原子模式下任一项失败时不保存。
+//! 保存后与 `POST /agents` 一样经 Agent 运行器启动新建的 Agent，有变化的 Agent 重启后使用新配置。
+//! 见 [`crate::core::agent_batch`]。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::{error, info, warn};
+
+use crate::core::agent_batch::{AgentBatch, MAX_BATCH_OPERATIONS};
+use crate::core::org_changes::save_organization_tracked;
+use crate::domain::{Agent, Organization};
+
+use super::permissions::{perm, RequirePermission};
+use super::{announce_org_change, AppState, ErrorResponse};
+
+/// 应用一批 Agent 操作（需要 `manage_agents`）
+pub(super) async fn apply_agent_batch(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Json(batch): Json<AgentBatch>,
+) -> Response {
+    if batch.operations.len() > MAX_BATCH_OPERATIONS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format(
+                    "web.agent_batch_too_large",
+                    &[
+                        ("count", &batch.operations.len().to_string()),
+                        ("max", &MAX_BATCH_OPERATIONS.to_string()),
+                    ],
+                ),
+            }),
+        )
+            .into_response();
+    }
+
+    let batch_failed = |e: anyhow::Error| {
+        error!("Failed to apply agent batch: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.agent_batch_failed"),
+            }),
+        )
+            .into_response()
+    };
+    // 读-改-写期间持有组织架构锁，避免并发请求覆盖彼此的修改
+    let _org_guard = state.org_lock.lock().await;
+    let mut org = match state.store.load_organization().await {
+        Ok(org) => org,
+        Err(e) => return batch_failed(e),
+    };
+    let before = org.clone();
+    let report = batch.apply_authorized(&mut org, state.clock.now(), |department| auth.allows(department));
+    if report.committed {
+        match save_organization_tracked(state.store.as_ref(), &org, &auth.user.username).await {
+            Ok(entry) => announce_org_change(&state, entry),
+            Err(e) => return batch_failed(e),
+        }
+        sync_running_agents(&state, &before, &org).await;
+    }
+    info!(
+        target: "audit",
+        "User {} applied agent batch: {} applied, {} failed, atomic {}",
+        auth.user.username, report.applied, report.failed, report.atomic
+    );
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": report,
+    }))
+    .into_response()
+}
+
+/// 把保存后的变化同步到运行中的 Agent：新建的启动并加入 Agent 列表，有变化的（LLM 配置、部门、技能）
+/// 停止后以新配置重新启动；没有运行器时在下次启动公司时生效
+async fn sync_running_agents(state: &AppState, before: &Organization, after: &Organization) {
+    for agent in &after.agents {
+        let previous = before.find_agent(&agent.id);
+        if previous.is_some_and(|previous| !agent_changed(previous, agent)) {
+            continue;
+        }
+        match &state.agent_runner {
+            Some(runner) => {
+                if previous.is_some() {
+                    runner.stop(&agent.id).await;
+                }
+                if let Err(e) = runner.start(agent).await {
+                    warn!("Applied agent batch to {} but failed to start it: {}", agent.id, e);
+                }
+            }
+            None => warn!("Applied agent batch to {} without an agent runner, it takes effect with the next company start", agent.id),
+        }
+        state.update_agents(|agents| match agents.iter_mut().find(|a| a.id == agent.id) {
+            Some(running) => *running = agent.clone(),
+            None => agents.push(agent.clone()),
+        });
+    }
+}
+
+/// 批量操作会修改的字段是否有变化
+fn agent_changed(previous: &Agent, agent: &Agent) -> bool {
+    previous.llm_config.model != agent.llm_config.model
+        || previous.llm_config.api_key != agent.llm_config.api_key
+        || previous.llm_config.base_url != agent.llm_config.base_url
+        || previous.llm_config.tokenizer != agent.llm_config.tokenizer
+        || previous.department_id != agent.department_id
+        || previous.skills != agent.skills
+}
@@ -0,0 +1,265 @@
+//! 附件上传和下载
+//!
+//! 附件内容按 [`core::attachments`](crate::core::attachments) 的约定写入 [`BlobStore`]，键为 `attachments/{id}`：
+//!
+//! - `POST /attachments?name=report.pdf` 以请求体流式上传，内容类型取自 `Content-Type`
+//! - `GET /attachments/{id}` 下载：后端能生成预签名 URL 时重定向（307）到对象存储，
+//!   否则由服务端代理输出，支持 `Range` 请求（206）
+//! - `DELETE /attachments/{id}` 只有上传者和拥有 `manage_attachments` 权限的用户可以删除
+//!
+//! 所有接口都需要登录。发送消息时超长的正文也可以自动存为附件
+//! （[`enforce_message_limits`](crate::core::message_limits::enforce_message_limits)）。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::{StreamExt, TryStreamExt};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::core::attachments::{attachment_key, percent_decode, percent_encode, save_attachment, META_FILENAME, META_UPLOADED_BY};
+use crate::domain::user::user_principal;
+use crate::infrastructure::auth::{Permission, UserInfo};
+use crate::infrastructure::blob::{BlobError, BlobStore, ByteRange};
+
+use super::{permissions, AppState, ErrorResponse};
+
+/// 预签名下载地址的有效期
+pub const DOWNLOAD_URL_TTL: Duration = Duration::from_secs(300);
+
+/// 未指定内容类型时使用的类型
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// 上传参数
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// 原始文件名，下载时用于 `Content-Disposition`
+    pub name: Option<String>,
+}
+
+/// 上传附件
+pub async fn upload_attachment(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<UploadQuery>,
+    body: Body,
+) -> Response {
+    let (user_info, blobs) = match authorize(&state, &headers) {
+        Ok(found) => found,
+        Err(status) => return rejection(&state, status),
+    };
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let name = query.name.filter(|name| !name.trim().is_empty()).unwrap_or_else(|| id.clone());
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or(DEFAULT_CONTENT_TYPE)
+        .to_string();
+    let uploader = user_principal(&user_info.id);
+
+    let stream = body.into_data_stream().map_err(std::io::Error::other).boxed();
+    let blob = match save_attachment(blobs.as_ref(), &id, &name, &content_type, &uploader, stream).await {
+        Ok(blob) => blob,
+        Err(e) => return storage_error(&state, &id, e),
+    };
+
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "id": id,
+                "name": name,
+                "content_type": content_type,
+                "size": blob.size,
+                "uploaded_by": uploader,
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 下载附件：能预签名时重定向，否则代理输出
+pub async fn download_attachment(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let blobs = match authorize(&state, &headers) {
+        Ok((_, blobs)) => blobs,
+        Err(status) => return rejection(&state, status),
+    };
+    if !is_attachment_id(&id) {
+        return not_found(&state, &id);
+    }
+    let key = attachment_key(&id);
+
+    match blobs.head(&key).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found(&state, &id),
+        Err(e) => return storage_error(&state, &id, e),
+    }
+    match blobs.download_url(&key, DOWNLOAD_URL_TTL).await {
+        Ok(Some(url)) => {
+            return match HeaderValue::from_str(&url) {
+                Ok(location) => (StatusCode::TEMPORARY_REDIRECT, [(header::LOCATION, location)]).into_response(),
+                Err(e) => storage_error(&state, &id, e.into()),
+            };
+        }
+        Ok(None) => {}
+        Err(e) => return storage_error(&state, &id, e),
+    }
+
+    let range = headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(ByteRange::from_header);
+    let object = match blobs.get(&key, range).await {
+        Ok(Some(object)) => object,
+        Ok(None) => return not_found(&state, &id),
+        Err(e) => {
+            if let Some(BlobError::RangeNotSatisfiable { size, .. }) = e.downcast_ref::<BlobError>() {
+                let content_range = format!("bytes */{}", size);
+                return (
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    [(header::CONTENT_RANGE, content_range)],
+                    Json(ErrorResponse {
+                        error: state.catalog.format("web.attachment_range_invalid", &[("attachment_id", &id)]),
+                    }),
+                )
+                    .into_response();
+            }
+            return storage_error(&state, &id, e);
+        }
+    };
+
+    let info = object.info;
+    let name = info
+        .metadata
+        .custom
+        .get(META_FILENAME)
+        .map(|name| percent_decode(name))
+        .unwrap_or_else(|| id.clone());
+    let content_type = info.metadata.content_type.as_deref().unwrap_or(DEFAULT_CONTENT_TYPE);
+
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CONTENT_DISPOSITION, content_disposition(&name))
+        .header(header::ACCEPT_RANGES, "bytes");
+    response = match object.range {
+        Some((start, end)) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, info.size))
+            .header(header::CONTENT_LENGTH, end - start + 1),
+        None => response.status(StatusCode::OK).header(header::CONTENT_LENGTH, info.size),
+    };
+    match response.body(Body::from_stream(object.body)) {
+        Ok(response) => response,
+        Err(e) => storage_error(&state, &id, e.into()),
+    }
+}
+
+/// 删除附件（上传者或拥有 `manage_attachments` 的用户）
+pub async fn delete_attachment(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> Response {
+    let (user_info, blobs) = match authorize(&state, &headers) {
+        Ok(found) => found,
+        Err(status) => return rejection(&state, status),
+    };
+    if !is_attachment_id(&id) {
+        return not_found(&state, &id);
+    }
+    let key = attachment_key(&id);
+
+    let blob = match blobs.head(&key).await {
+        Ok(Some(blob)) => blob,
+        Ok(None) => return not_found(&state, &id),
+        Err(e) => return storage_error(&state, &id, e),
+    };
+    let actor = user_principal(&user_info.id);
+    let uploader = blob.metadata.custom.get(META_UPLOADED_BY);
+    if uploader != Some(&actor)
+        && !permissions::resolve_permissions(&state, &user_info).await.has_global(Permission::ManageAttachments)
+    {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.insufficient_permissions"),
+            }),
+        )
+            .into_response();
+    }
+
+    if let Err(e) = blobs.delete(&key).await {
+        return storage_error(&state, &id, e);
+    }
+    info!(target: "audit", attachment_id = %id, actor = %actor, "Attachment deleted");
+    Json(serde_json::json!({ "success": true })).into_response()
+}
+
+/// 校验登录并取得大对象存储，失败时返回对应的状态码，由 [`rejection`] 生成响应
+fn authorize(state: &AppState, headers: &HeaderMap) -> Result<(UserInfo, Arc<dyn BlobStore>), StatusCode> {
+    let user_info = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let blobs = state.blobs.clone().ok_or(StatusCode::SERVICE_UNAVAILABLE)?;
+    Ok((user_info, blobs))
+}
+
+fn rejection(state: &AppState, status: StatusCode) -> Response {
+    let key = if status == StatusCode::UNAUTHORIZED {
+        "web.unauthorized"
+    } else {
+        "web.attachments_unavailable"
+    };
+    (status, Json(ErrorResponse { error: state.catalog.get(key) })).into_response()
+}
+
+/// 附件 ID 由服务端生成，只接受 UUID，避免路径参数拼出其他键
+fn is_attachment_id(id: &str) -> bool {
+    uuid::Uuid::parse_str(id).is_ok()
+}
+
+fn not_found(state: &AppState, id: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.attachment_not_found", &[("attachment_id", id)]),
+        }),
+    )
+        .into_response()
+}
+
+fn storage_error(state: &AppState, id: &str, error: anyhow::Error) -> Response {
+    error!("Attachment operation for {} failed: {}", id, error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: state.catalog.get("web.attachment_failed"),
+        }),
+    )
+        .into_response()
+}
+
+/// `Content-Disposition`：ASCII 文件名作为兼容值，完整文件名放在 `filename*`（RFC 6266）
+fn content_disposition(name: &str) -> String {
+    let fallback: String = name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, percent_encode(name))
+}
@@ -0,0 +1,112 @@
+//! 故障注入管理接口（`chaos` 特性）
+//!
+//! 只有挂载了故障注入器（[`AppState::with_fault_injector`]）且注入器已显式启用时可用，
+//! 并且需要 `manage_chaos` 权限（默认只有董事长拥有）。接口只能调整规则，不能开关注入器本身。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::core::chaos::{FaultInjector, FaultRule};
+
+use super::permissions::{perm, RequirePermission};
+use super::{AppState, ErrorResponse};
+
+/// 规则调整请求
+#[derive(Debug, Deserialize)]
+pub struct ChaosRulesRequest {
+    pub rules: Vec<FaultRule>,
+    /// 为 true 时替换全部规则，否则追加
+    #[serde(default)]
+    pub replace: bool,
+}
+
+/// 校验注入器状态
+fn chaos_injector(state: &AppState) -> Result<Arc<FaultInjector>, (StatusCode, Json<ErrorResponse>)> {
+    match &state.fault_injector {
+        None => Err(error(StatusCode::NOT_FOUND, state.catalog.get("web.chaos_not_configured"))),
+        Some(injector) if !injector.is_enabled() => {
+            Err(error(StatusCode::FORBIDDEN, state.catalog.get("web.chaos_disabled")))
+        }
+        Some(injector) => Ok(injector.clone()),
+    }
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse { error: message }))
+}
+
+fn rules_response(injector: &FaultInjector) -> Response {
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "rules": injector.rules(),
+            "injected": injector.history().len(),
+        }
+    }))
+    .into_response()
+}
+
+/// 查看当前规则
+pub(super) async fn get_chaos_rules(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ManageChaos>,
+) -> impl IntoResponse {
+    match chaos_injector(&state) {
+        Ok(injector) => rules_response(&injector),
+        Err(response) => response.into_response(),
+    }
+}
+
+/// 追加或替换规则
+pub(super) async fn set_chaos_rules(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageChaos>,
+    Json(req): Json<ChaosRulesRequest>,
+) -> impl IntoResponse {
+    let username = auth.user.username;
+    let injector = match chaos_injector(&state) {
+        Ok(injector) => injector,
+        Err(response) => return response.into_response(),
+    };
+
+    if let Some(invalid) = req.rules.iter().find_map(|rule| rule.validate().err()) {
+        return error(
+            StatusCode::BAD_REQUEST,
+            state.catalog.format("web.chaos_rule_invalid", &[("error", &invalid)]),
+        )
+        .into_response();
+    }
+
+    info!(target: "audit", "User {} {} {} chaos rules", username, if req.replace { "set" } else { "added" }, req.rules.len());
+    if req.replace {
+        injector.set_rules(req.rules);
+    } else {
+        for rule in req.rules {
+            injector.add_rule(rule);
+        }
+    }
+    rules_response(&injector)
+}
+
+/// 清空全部规则
+pub(super) async fn clear_chaos_rules(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageChaos>,
+) -> impl IntoResponse {
+    let username = auth.user.username;
+    let injector = match chaos_injector(&state) {
+        Ok(injector) => injector,
+        Err(response) => return response.into_response(),
+    };
+    info!(target: "audit", "User {} cleared chaos rules", username);
+    injector.set_rules(Vec::new());
+    rules_response(&injector)
+}
@@ -0,0 +1,120 @@
+//! 会话生命周期接口
+//!
+//! `POST /chat/{id}/close` 关闭会话并返回带总结的会话，`POST /chat/{id}/reopen` 重新打开已关闭的会话，
+//! 都需要登录；`id` 为会话 ID 或 Agent ID（指它的当前会话）。未启用会话生命周期时返回 503。
+//! 见 [`crate::core::chat_sessions`]。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tracing::error;
+
+use crate::core::chat_sessions::{ChatSessionError, ChatSessions};
+use crate::domain::SessionResolution;
+
+use super::{bookmark_owner, AppState, ErrorResponse};
+
+/// 关闭会话的请求，请求体可省略
+#[derive(Debug, Default, Deserialize)]
+pub struct CloseSessionRequest {
+    /// 默认 resolved
+    pub resolution: Option<SessionResolution>,
+    pub note: Option<String>,
+}
+
+/// 关闭会话并生成总结（需要登录）
+pub(super) async fn close_chat_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    request: Option<Json<CloseSessionRequest>>,
+) -> Response {
+    let (actor, chat_sessions) = match session_actor(&state, &headers) {
+        Ok(found) => found,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let Json(request) = request.unwrap_or_default();
+    let resolution = request.resolution.unwrap_or(SessionResolution::Resolved);
+    match chat_sessions.close(&session_id, resolution, &actor, request.note).await {
+        Ok(session) => Json(serde_json::json!({
+            "success": true,
+            "data": session,
+        }))
+        .into_response(),
+        Err(e) => chat_session_error(&state, &session_id, e),
+    }
+}
+
+/// 重新打开已关闭的会话（需要登录）
+pub(super) async fn reopen_chat_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Response {
+    let chat_sessions = match session_actor(&state, &headers) {
+        Ok((_, chat_sessions)) => chat_sessions,
+        Err(rejection) => return rejection.into_response(),
+    };
+    match chat_sessions.reopen(&session_id).await {
+        Ok(session) => Json(serde_json::json!({
+            "success": true,
+            "data": session,
+        }))
+        .into_response(),
+        Err(e) => chat_session_error(&state, &session_id, e),
+    }
+}
+
+/// 当前用户和会话管理器；未登录返回 401，未启用返回 503
+fn session_actor(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<(String, Arc<ChatSessions>), (StatusCode, Json<ErrorResponse>)> {
+    let Some(actor) = bookmark_owner(state, headers) else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            }),
+        ));
+    };
+    let Some(chat_sessions) = state.chat_sessions.clone() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.chat_session_unavailable"),
+            }),
+        ));
+    };
+    Ok((actor, chat_sessions))
+}
+
+fn chat_session_error(state: &AppState, session_id: &str, e: anyhow::Error) -> Response {
+    let status = match e.downcast_ref::<ChatSessionError>() {
+        Some(ChatSessionError::NotFound { .. }) => StatusCode::NOT_FOUND,
+        Some(ChatSessionError::AlreadyClosed { .. } | ChatSessionError::NotClosed { .. }) => StatusCode::CONFLICT,
+        None => {
+            error!("Failed to update chat session {}: {}", session_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.chat_session_failed"),
+                }),
+            )
+                .into_response();
+        }
+    };
+    (
+        status,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.chat_session_invalid", &[("error", &e.to_string())]),
+        }),
+    )
+        .into_response()
+}
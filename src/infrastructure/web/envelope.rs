@@ -0,0 +1,194 @@
+//! API 版本化响应信封
+//!
+//! `/api/v1/...` 下的所有响应统一为：
+//!
+//! ```json
+//! { "api_version": "v1", "success": true, "data": ..., "error": null }
+//! ```
+//!
+//! 失败时 `data` 为 null，`error` 为 `{ "code": ..., "message": ... }`。错误码：
+//!
+//! | HTTP 状态 | code |
+//! |-----------|------|
+//! | 400 | `BAD_REQUEST` |
+//! | 401 | `UNAUTHORIZED` |
+//! | 403 | `FORBIDDEN` |
+//! | 404 | `NOT_FOUND` |
+//! | 409 | `CONFLICT` |
+//! | 415 / 422 | `INVALID_PAYLOAD` |
+//! | 5xx | `INTERNAL_ERROR` |
+//! | 其他 | `ERROR` |
+//!
+//! 处理器仍返回原有的 JSON，由 [`envelope_middleware`] 统一转换，保证各路由的结构不会漂移。
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::error;
+
+/// 当前 REST API 版本
+pub const API_VERSION: &str = "v1";
+
+/// WebSocket 帧协议版本（每个帧的 `v` 字段）
+pub const WS_PROTOCOL_VERSION: u32 = 1;
+
+/// 统一响应信封
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiEnvelope {
+    pub api_version: String,
+    pub success: bool,
+    pub data: Option<Value>,
+    pub error: Option<ApiError>,
+}
+
+/// 信封中的错误信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiError {
+    /// 稳定的错误码，供前端判断
+    pub code: String,
+    /// 面向用户的错误信息（按消息目录语言）
+    pub message: String,
+}
+
+impl ApiEnvelope {
+    /// 成功响应
+    pub fn ok(data: Value) -> Self {
+        Self {
+            api_version: API_VERSION.to_string(),
+            success: true,
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    /// 失败响应
+    pub fn err(code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            api_version: API_VERSION.to_string(),
+            success: false,
+            data: None,
+            error: Some(ApiError {
+                code: code.into(),
+                message: message.into(),
+            }),
+        }
+    }
+}
+
+/// HTTP 状态码对应的错误码
+pub fn error_code(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "BAD_REQUEST",
+        StatusCode::UNAUTHORIZED => "UNAUTHORIZED",
+        StatusCode::FORBIDDEN => "FORBIDDEN",
+        StatusCode::NOT_FOUND => "NOT_FOUND",
+        StatusCode::CONFLICT => "CONFLICT",
+        StatusCode::UNSUPPORTED_MEDIA_TYPE | StatusCode::UNPROCESSABLE_ENTITY => "INVALID_PAYLOAD",
+        s if s.is_server_error() => "INTERNAL_ERROR",
+        _ => "ERROR",
+    }
+}
+
+/// 将处理器原有的响应体转换为信封
+///
+/// 旧接口有三种形状：裸数据、`{success, data}`、`{error}`，这里统一识别
+pub fn wrap_body(status: StatusCode, body: &[u8]) -> ApiEnvelope {
+    let value: Option<Value> = serde_json::from_slice(body).ok();
+
+    if status.is_success() {
+        let data = match value {
+            Some(Value::Object(mut object)) if object.get("success").is_some_and(Value::is_boolean) => {
+                object.remove("success");
+                match object.remove("data") {
+                    Some(data) => data,
+                    None => Value::Object(object),
+                }
+            }
+            Some(value) => value,
+            None => Value::Null,
+        };
+        return ApiEnvelope::ok(data);
+    }
+
+    let message = match &value {
+        Some(Value::Object(object)) => object
+            .get("error")
+            .or_else(|| object.get("message"))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        _ => None,
+    }
+    .unwrap_or_else(|| {
+        // axum 的提取器拒绝是纯文本
+        let text = String::from_utf8_lossy(body).trim().to_string();
+        if text.is_empty() {
+            status.canonical_reason().unwrap_or("Error").to_string()
+        } else {
+            text
+        }
+    });
+
+    ApiEnvelope::err(error_code(status), message)
+}
+
+/// 为版本化路由套上响应信封
+pub async fn envelope_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+
+    // WebSocket 升级等非普通响应原样返回
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+        return response;
+    }
+    // 文件下载是流式响应，不能缓冲后再包装；重定向到预签名下载地址时同样原样返回
+    if response.headers().contains_key(header::CONTENT_DISPOSITION) || response.status().is_redirection() {
+        return response;
+    }
+    // SSE 事件流同样不能缓冲
+    let is_event_stream = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if is_event_stream {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!("Failed to read response body: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiEnvelope::err("INTERNAL_ERROR", "Failed to read response body")),
+            )
+                .into_response();
+        }
+    };
+
+    let envelope = wrap_body(parts.status, &bytes);
+    let body = serde_json::to_vec(&envelope).unwrap_or_default();
+
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+/// 为旧的未版本化路由标记弃用
+pub async fn deprecation_middleware(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    headers.insert(header::LINK, HeaderValue::from_static("</api/v1>; rel=\"successor-version\""));
+    response
+}
@@ -0,0 +1,71 @@
+//! 功能开关接口
+//!
+//! `GET /features` 列出各功能是否可用及其来源，前端据此隐藏不可用的界面；
+//! `PUT /admin/features/{name}` 在运行中关闭或重新打开可切换的功能（需要 `manage_features`），
+//! 切换后公司发出 `FeatureToggled` 事件，工具视图随之失效。见 [`crate::core::features`]。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tracing::info;
+
+use crate::core::features::FeatureError;
+
+use super::permissions::{perm, RequirePermission};
+use super::{AppState, ErrorResponse};
+
+/// 切换功能请求
+#[derive(Debug, Deserialize)]
+pub struct ToggleFeatureRequest {
+    pub enabled: bool,
+}
+
+/// 所有功能及开启的功能名称
+pub(super) async fn get_features(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "features": state.features.list(),
+            "enabled": state.features.enabled(),
+        }
+    }))
+}
+
+/// 运行中切换功能（需要 `manage_features`）
+pub(super) async fn toggle_feature(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageFeatures>,
+    Path(name): Path<String>,
+    Json(req): Json<ToggleFeatureRequest>,
+) -> Response {
+    match state.features.toggle(&name, req.enabled) {
+        Ok(feature) => {
+            info!(target: "audit", "User {} set feature {} to {}", auth.user.username, name, req.enabled);
+            Json(serde_json::json!({
+                "success": true,
+                "data": feature,
+            }))
+            .into_response()
+        }
+        Err(FeatureError::NotFound(_)) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.feature_not_found", &[("feature", &name)]),
+            }),
+        )
+            .into_response(),
+        Err(FeatureError::NotToggleable(_)) => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.feature_not_toggleable", &[("feature", &name)]),
+            }),
+        )
+            .into_response(),
+    }
+}
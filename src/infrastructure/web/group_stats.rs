@@ -0,0 +1,139 @@
+//! 群聊活动统计接口
+//!
+//! `GET /groups/{id}/stats` 返回各成员在滚动窗口内的发言量、占比、最后发言时间、被 @ 后的平均回复时间
+//! 和沉默的成员，群成员、群管理员或拥有 `manage_groups` 的用户可以查看；
+//! `POST /admin/groups/{id}/stats/recompute` 从原始消息重新计算统计，用于修复增量状态的偏差（需要 `manage_groups`）。
+//! 未启用群聊活动统计时返回 503。见 [`crate::core::group_stats`]。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::{error, info};
+
+use crate::core::group_stats::GroupStatsTracker;
+use crate::domain::user::user_principal;
+use crate::domain::Group;
+use crate::infrastructure::auth::Permission;
+
+use super::permissions::{self, perm, RequirePermission};
+use super::{AppState, ErrorResponse};
+
+/// 群聊的活动统计（群成员、群管理员或拥有 `manage_groups` 的用户）
+pub(super) async fn get_group_stats(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+) -> Response {
+    let tracker = match group_stats(&state) {
+        Ok(tracker) => tracker,
+        Err(error) => return error.into_response(),
+    };
+    let user_info = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    let Some(user_info) = user_info else {
+        return error_response(StatusCode::UNAUTHORIZED, state.catalog.get("web.unauthorized"));
+    };
+    let Some(group) = find_group(&state, &group_id).await else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            state.catalog.format("web.group_not_found", &[("group_id", &group_id)]),
+        );
+    };
+
+    let viewer = user_principal(&user_info.id);
+    if !group.has_member(&viewer)
+        && !group.is_admin(&viewer)
+        && !permissions::resolve_permissions(&state, &user_info).await.has_global(Permission::ManageGroups)
+    {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            state.catalog.format("web.group_stats_forbidden", &[("group_id", &group_id)]),
+        );
+    }
+
+    match tracker.report(&group_id).await {
+        Ok(report) => Json(serde_json::json!({
+            "success": true,
+            "data": report,
+        }))
+        .into_response(),
+        Err(e) => {
+            error!("Failed to load stats of group {}: {}", group_id, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.group_stats_failed"))
+        }
+    }
+}
+
+/// 从原始消息重新计算群聊的活动统计（需要 `manage_groups`）
+pub(super) async fn recompute_group_stats(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageGroups>,
+    Path(group_id): Path<String>,
+) -> Response {
+    let tracker = match group_stats(&state) {
+        Ok(tracker) => tracker,
+        Err(error) => return error.into_response(),
+    };
+    if find_group(&state, &group_id).await.is_none() {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            state.catalog.format("web.group_not_found", &[("group_id", &group_id)]),
+        );
+    }
+
+    if let Err(e) = tracker.recompute(&group_id).await {
+        error!("Failed to recompute stats of group {}: {}", group_id, e);
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.group_stats_failed"));
+    }
+    info!(target: "audit", username = %auth.user.username, group_id = %group_id, "Group stats recomputed from messages");
+    match tracker.report(&group_id).await {
+        Ok(report) => Json(serde_json::json!({
+            "success": true,
+            "data": report,
+        }))
+        .into_response(),
+        Err(e) => {
+            error!("Failed to load stats of group {}: {}", group_id, e);
+            error_response(StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.group_stats_failed"))
+        }
+    }
+}
+
+fn group_stats(state: &AppState) -> Result<Arc<GroupStatsTracker>, (StatusCode, Json<ErrorResponse>)> {
+    state.group_stats.clone().ok_or_else(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.group_stats_unavailable"),
+            }),
+        )
+    })
+}
+
+/// 先查消息总线中的群聊，未连接消息总线时查存储
+pub(super) async fn find_group(state: &AppState, group_id: &str) -> Option<Group> {
+    if let Some(bus) = &state.message_bus {
+        if let Some(group) = bus.get_group(group_id).await {
+            return Some(group);
+        }
+    }
+    match state.store.load_groups().await {
+        Ok(groups) => groups.into_iter().find(|g| g.id == group_id),
+        Err(e) => {
+            error!("Failed to load groups for stats of {}: {}", group_id, e);
+            None
+        }
+    }
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error })).into_response()
+}
@@ -0,0 +1,321 @@
+//! 群聊接口
+//!
+//! `GET /groups` 列出当前用户所在的群聊（拥有 `manage_groups` 的用户看到全部），
+//! `POST /groups` 以当前用户（`user:{id}`）为创建者和管理员创建群聊（需要 `manage_groups`，
+//! Agent 成员须在授权的部门内），同时注册到消息总线并写入存储；
+//! `POST /groups/{id}/members` 邀请成员（仅群管理员），移出成员见 `DELETE /groups/{id}/members/{member_id}`；
+//! `GET /groups/{id}/messages` 分页返回群聊历史，参数、上限和分页响应头与会话消息相同。
+//!
+//! 路径中的群聊 ID 可以带 WebSocket 消息使用的 `group:` 前缀。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::core::join_context::InviteOptions;
+use crate::core::messaging::MessageBus;
+use crate::core::store::{MessageCursor, MessageFilter};
+use crate::domain::user::{user_principal, USER_PRINCIPAL_PREFIX};
+use crate::infrastructure::auth::{Permission, UserInfo};
+
+use super::group_stats::find_group;
+use super::permissions::{self, perm, RequirePermission};
+use super::{
+    agent_department, insert_page_headers, moderation_actor, moderation_response, AppState, ErrorResponse,
+    SessionMessagesQuery, SESSION_MESSAGES_DEFAULT_LIMIT, SESSION_MESSAGES_MAX_LIMIT,
+};
+
+/// WebSocket 消息中群聊目标的前缀
+const GROUP_ADDRESS_PREFIX: &str = "group:";
+
+/// 创建群聊请求
+#[derive(Debug, Deserialize)]
+pub struct CreateGroupRequest {
+    /// 群聊 ID，为空时自动生成
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    /// 初始成员（Agent ID 或 `user:{id}`），创建者自动加入
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+/// 邀请成员请求
+#[derive(Debug, Deserialize)]
+pub struct InviteGroupMemberRequest {
+    pub member_id: String,
+    #[serde(flatten)]
+    pub options: InviteOptions,
+}
+
+/// 当前用户所在的群聊，拥有 `manage_groups` 的用户返回全部（不含临时群聊）
+pub(super) async fn list_groups(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let (user_info, bus) = match group_user(&state, &headers) {
+        Ok(user) => user,
+        Err(error) => return error.into_response(),
+    };
+    let groups = if permissions::resolve_permissions(&state, &user_info).await.has_global(Permission::ManageGroups) {
+        bus.list_groups(false).await
+    } else {
+        let mut groups = bus.list_agent_groups(&user_principal(&user_info.id)).await;
+        groups.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        groups
+    };
+    Json(serde_json::json!({
+        "success": true,
+        "data": groups,
+    }))
+    .into_response()
+}
+
+/// 创建群聊（需要 `manage_groups`，Agent 成员须在授权的部门内）：当前用户为创建者和管理员，
+/// 注册到消息总线并写入存储
+pub(super) async fn create_group(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageGroups>,
+    Json(req): Json<CreateGroupRequest>,
+) -> Response {
+    let Some(bus) = state.message_bus.clone() else {
+        return error_response(StatusCode::SERVICE_UNAVAILABLE, state.catalog.get("web.message_bus_unavailable"));
+    };
+    let creator = user_principal(&auth.user.id);
+
+    let name = req.name.trim();
+    if name.is_empty() {
+        return invalid_group(&state, "name must not be empty");
+    }
+    let group_id = match req.id.as_deref().map(group_id_param) {
+        Some(id) if id.is_empty() || id.chars().any(char::is_whitespace) => {
+            return invalid_group(&state, "id must be non-empty and contain no whitespace");
+        }
+        Some(id) => id,
+        None => format!("group_{}", &uuid::Uuid::new_v4().simple().to_string()[..8]),
+    };
+    // 只在存储中的群聊（如未加载到总线的）同样算作已存在
+    if find_group(&state, &group_id).await.is_some() {
+        return error_response(
+            StatusCode::CONFLICT,
+            state.catalog.format("web.group_exists", &[("group_id", &group_id)]),
+        );
+    }
+
+    let mut members = vec![creator.clone()];
+    for member in req.members.iter().map(|m| m.trim()) {
+        if members.iter().any(|m| m == member) {
+            continue;
+        }
+        if let Err(error) = check_member(&state, &bus, member).await {
+            return error.into_response();
+        }
+        if bus.is_registered(member) && !auth.allows(agent_department(&state, member).await.as_deref()) {
+            return permissions::rejection(&state, StatusCode::FORBIDDEN);
+        }
+        members.push(member.to_string());
+    }
+
+    bus.register_user(&creator);
+    if let Err(e) = bus.create_group(&group_id, name, &creator, members).await {
+        return invalid_group(&state, &e.to_string());
+    }
+    let Some(group) = bus.get_group(&group_id).await else {
+        error!("Group {} disappeared right after creation", group_id);
+        return error_response(StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.group_create_failed"));
+    };
+
+    info!(target: "audit", "{} created group {} with {} members", creator, group_id, group.members.len());
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "success": true,
+            "data": group,
+        })),
+    )
+        .into_response()
+}
+
+/// 邀请成员加入群聊（仅群管理员），成员变更写入存储
+pub(super) async fn invite_group_member(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+    Json(req): Json<InviteGroupMemberRequest>,
+) -> Response {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    let group_id = group_id_param(&group_id);
+    let member = req.member_id.trim();
+    if let Err(error) = check_member(&state, &bus, member).await {
+        return error.into_response();
+    }
+    let result = bus.invite_member(&group_id, &actor, member, req.options).await;
+    moderation_response(&state, &group_id, result)
+}
+
+/// 群聊历史（群成员、群管理员或拥有 `manage_groups` 的用户），每页按时间升序，
+/// `next_cursor` 指向更早的一页，`has_more` 表示是否还有更早的消息
+pub(super) async fn get_group_messages(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+    Query(query): Query<SessionMessagesQuery>,
+) -> Response {
+    let Some(user_info) = current_user(&state, &headers) else {
+        return error_response(StatusCode::UNAUTHORIZED, state.catalog.get("web.unauthorized"));
+    };
+    if let Err(error) = query.check_offset(&state) {
+        return error.into_response();
+    }
+    let group_id = group_id_param(&group_id);
+    let Some(group) = find_group(&state, &group_id).await else {
+        return error_response(
+            StatusCode::NOT_FOUND,
+            state.catalog.format("web.group_not_found", &[("group_id", &group_id)]),
+        );
+    };
+    let viewer = user_principal(&user_info.id);
+    if !group.has_member(&viewer)
+        && !group.is_admin(&viewer)
+        && !permissions::resolve_permissions(&state, &user_info).await.has_global(Permission::ManageGroups)
+    {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            state.catalog.format("web.group_messages_forbidden", &[("group_id", &group_id)]),
+        );
+    }
+
+    let limit = query.limit.unwrap_or(SESSION_MESSAGES_DEFAULT_LIMIT).clamp(1, SESSION_MESSAGES_MAX_LIMIT);
+    // 多取一条判断是否还有更早的消息
+    let mut filter = MessageFilter::new()
+        .to(group_id.clone())
+        .target_type("group")
+        .stable_order()
+        .limit(limit + 1);
+    if let Some(offset) = query.offset {
+        filter = filter.offset(offset);
+    }
+    if let Some(before) = &query.before {
+        match state.store.load_message(before).await {
+            Ok(Some(message)) if message.target_group() == Some(group_id.as_str()) => {
+                filter = filter.before(MessageCursor::at(&message));
+            }
+            Ok(_) => {
+                return error_response(
+                    StatusCode::NOT_FOUND,
+                    state.catalog.format("web.message_not_found", &[("message_id", before)]),
+                );
+            }
+            Err(e) => {
+                error!("Failed to load cursor message {}: {}", before, e);
+                return error_response(StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.messages_load_failed"));
+            }
+        }
+    }
+
+    let mut messages = match state.store.load_messages(filter).await {
+        Ok(messages) => messages,
+        Err(e) => {
+            error!("Failed to load messages of group {}: {}", group_id, e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.messages_load_failed"));
+        }
+    };
+    let has_more = messages.len() > limit;
+    messages.truncate(limit);
+    let next_cursor = if has_more { messages.last().map(|m| m.id.clone()) } else { None };
+    messages.reverse();
+
+    let mut response = Json(serde_json::json!({
+        "success": true,
+        "data": messages,
+        "next_cursor": next_cursor,
+        "has_more": has_more,
+    }))
+    .into_response();
+    insert_page_headers(&mut response, next_cursor.as_deref(), has_more);
+    response
+}
+
+/// 去掉 WebSocket 消息使用的 `group:` 前缀
+fn group_id_param(id: &str) -> String {
+    let id = id.trim();
+    id.strip_prefix(GROUP_ADDRESS_PREFIX).unwrap_or(id).to_string()
+}
+
+/// 成员须是已注册到消息总线的 Agent 或存储中已有的用户（`user:{id}`），否则返回 400
+async fn check_member(state: &AppState, bus: &MessageBus, member: &str) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if bus.is_registered(member) {
+        return Ok(());
+    }
+    let unknown = || {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.group_member_unknown", &[("member_id", member)]),
+            }),
+        )
+    };
+    let Some(user_id) = member.strip_prefix(USER_PRINCIPAL_PREFIX).filter(|id| !id.is_empty()) else {
+        return Err(unknown());
+    };
+    match state.store.load_users().await {
+        Ok(users) if users.iter().any(|user| user.id == user_id) => Ok(()),
+        Ok(_) => Err(unknown()),
+        Err(e) => {
+            error!("Failed to load users to check group member {}: {}", member, e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.users_load_failed"),
+                }),
+            ))
+        }
+    }
+}
+
+fn current_user(state: &AppState, headers: &HeaderMap) -> Option<UserInfo> {
+    headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok())
+}
+
+/// 当前用户和消息总线
+fn group_user(state: &AppState, headers: &HeaderMap) -> Result<(UserInfo, Arc<MessageBus>), (StatusCode, Json<ErrorResponse>)> {
+    let Some(user_info) = current_user(state, headers) else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            }),
+        ));
+    };
+    let Some(bus) = state.message_bus.clone() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.message_bus_unavailable"),
+            }),
+        ));
+    };
+    Ok((user_info, bus))
+}
+
+fn invalid_group(state: &AppState, error: &str) -> Response {
+    error_response(
+        StatusCode::BAD_REQUEST,
+        state.catalog.format("web.group_create_invalid", &[("error", error)]),
+    )
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error })).into_response()
+}
@@ -0,0 +1,185 @@
+//! 存活与就绪探针
+//!
+//! `/health/live` 只表示进程在运行；`/health/ready` 主动检查各子系统：
+//!
+//! | 检查 | 内容 |
+//! |------|------|
+//! | `store` | 存储读写探测（[`Store::health_check`]），target 为实际使用的后端与路径 |
+//! | `message_bus` | 广播通道积压未超过阈值 |
+//! | `llm` | 每个配置的 LLM base_url 可连通（`GET {base_url}/models`，结果缓存） |
+//!
+//! 哪些检查是关键的可以配置：关键检查失败返回 503，非关键检查只在结果中报告。
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::core::store::Store;
+use crate::domain::{Agent, Message};
+
+/// 就绪检查配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// 关键检查名称，失败时就绪探针返回 503
+    pub critical: Vec<String>,
+    /// LLM 连通性检查超时（毫秒）
+    pub llm_timeout_ms: u64,
+    /// LLM 检查结果缓存时长（秒）
+    pub llm_cache_secs: u64,
+    /// 广播通道积压达到多少条视为饱和
+    pub broadcast_saturation: usize,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            critical: vec!["store".to_string(), "message_bus".to_string()],
+            llm_timeout_ms: 2000,
+            llm_cache_secs: 30,
+            broadcast_saturation: 1000,
+        }
+    }
+}
+
+/// 单项检查结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: String,
+    /// 检查对象，如 LLM 的 base_url
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub ok: bool,
+    pub critical: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// 可用但处于降级状态（如存储故障期间使用缓存和写缓冲）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub degraded: Option<String>,
+}
+
+impl CheckResult {
+    fn new(name: &str, target: Option<String>, started: Instant, outcome: Result<(), String>) -> Self {
+        Self {
+            name: name.to_string(),
+            target,
+            ok: outcome.is_ok(),
+            critical: false,
+            latency_ms: started.elapsed().as_millis() as u64,
+            error: outcome.err(),
+            degraded: None,
+        }
+    }
+
+    /// 用于错误信息的名称
+    pub fn label(&self) -> String {
+        match &self.target {
+            Some(target) => format!("{}:{}", self.name, target),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// 就绪检查器，缓存 LLM 连通性结果
+pub struct HealthChecker {
+    config: HealthConfig,
+    client: reqwest::Client,
+    llm_cache: DashMap<String, (Instant, CheckResult)>,
+}
+
+impl HealthChecker {
+    pub fn new(config: HealthConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.llm_timeout_ms))
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            client,
+            llm_cache: DashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &HealthConfig {
+        &self.config
+    }
+
+    /// 执行全部检查
+    pub async fn check(
+        &self,
+        store: &dyn Store,
+        message_tx: &broadcast::Sender<Message>,
+        agents: &[Agent],
+    ) -> Vec<CheckResult> {
+        let mut results = vec![
+            self.check_store(store).await,
+            self.check_message_bus(message_tx),
+        ];
+
+        let mut seen = HashSet::new();
+        for agent in agents {
+            let base_url = agent.llm_config.base_url.trim_end_matches('/').to_string();
+            if seen.insert(base_url.clone()) {
+                results.push(self.check_llm(&base_url).await);
+            }
+        }
+
+        for result in &mut results {
+            result.critical = self.config.critical.contains(&result.name);
+        }
+        results
+    }
+
+    async fn check_store(&self, store: &dyn Store) -> CheckResult {
+        let started = Instant::now();
+        let outcome = store.health_check().await.map_err(|e| e.to_string());
+        let mut result = CheckResult::new("store", Some(store.backend_info().label()), started, outcome);
+        result.degraded = store.degraded();
+        result
+    }
+
+    fn check_message_bus(&self, message_tx: &broadcast::Sender<Message>) -> CheckResult {
+        let started = Instant::now();
+        let queued = message_tx.len();
+        let outcome = if queued >= self.config.broadcast_saturation {
+            Err(format!("{} messages queued", queued))
+        } else {
+            Ok(())
+        };
+        CheckResult::new("message_bus", None, started, outcome)
+    }
+
+    async fn check_llm(&self, base_url: &str) -> CheckResult {
+        let ttl = Duration::from_secs(self.config.llm_cache_secs);
+        if let Some(cached) = self.llm_cache.get(base_url) {
+            if cached.0.elapsed() < ttl {
+                return cached.1.clone();
+            }
+        }
+
+        // 任何 HTTP 响应（包括 401）都说明端点可达
+        let started = Instant::now();
+        let outcome = self
+            .client
+            .get(format!("{}/models", base_url))
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string());
+        let result = CheckResult::new("llm", Some(base_url.to_string()), started, outcome);
+
+        self.llm_cache.insert(base_url.to_string(), (Instant::now(), result.clone()));
+        result
+    }
+}
+
+impl Default for HealthChecker {
+    fn default() -> Self {
+        Self::new(HealthConfig::default())
+    }
+}
@@ -0,0 +1,272 @@
+//! 幂等键（`Idempotency-Key` 请求头）
+//!
+//! 创建类接口携带 `Idempotency-Key` 时，首次请求的响应按（调用方, 键）保存：
+//!
+//! - 同一个键、同样的请求体再次提交，直接返回保存的响应，不重复执行
+//! - 同一个键换了请求体或接口，返回 409
+//! - 记录在 TTL 后过期，过期后键可以复用；后台任务定期清理过期记录
+//!
+//! 键按调用方隔离，归属 `user:{id}`。匿名调用方之间无法区分，携带幂等键的请求必须带有效的
+//! Bearer token，否则返回 401，避免一个匿名客户端重放另一个的响应。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use dashmap::DashSet;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::store::Store;
+use crate::domain::idempotency::IdempotencyRecord;
+use crate::domain::user::user_principal;
+
+use super::{AppState, ErrorResponse};
+
+/// 幂等键请求头
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// 记录默认保留时长
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// 查询幂等键的结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdempotencyLookup {
+    /// 没有有效记录，需要执行请求
+    New,
+    /// 请求与记录一致，返回保存的响应
+    Replay { status: u16, response: Value },
+    /// 键已用于不同的请求
+    Conflict,
+}
+
+/// 幂等键服务
+pub struct IdempotencyKeys {
+    store: Arc<dyn Store>,
+    ttl: Duration,
+    clock: Arc<dyn Clock>,
+    /// 正在执行的（作用域, 键），防止并发重复请求同时执行
+    in_flight: DashSet<(String, String)>,
+}
+
+impl IdempotencyKeys {
+    /// 创建幂等键服务，使用默认 TTL
+    pub fn new(store: Arc<dyn Store>) -> Self {
+        Self {
+            store,
+            ttl: DEFAULT_IDEMPOTENCY_TTL,
+            clock: Arc::new(SystemClock),
+            in_flight: DashSet::new(),
+        }
+    }
+
+    /// 设置记录保留时长
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// 使用指定时钟（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 记录保留时长
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// 请求指纹：接口加请求体的 SHA-256
+    pub fn request_hash(endpoint: &str, request: &Value) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(endpoint.as_bytes());
+        hasher.update(b"\n");
+        hasher.update(request.to_string().as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// 查询键对应的记录，过期记录视为不存在
+    pub async fn lookup(&self, scope: &str, key: &str, endpoint: &str, request_hash: &str) -> anyhow::Result<IdempotencyLookup> {
+        let record = match self.store.load_idempotency_record(scope, key).await? {
+            Some(record) if !record.is_expired(self.clock.now()) => record,
+            _ => return Ok(IdempotencyLookup::New),
+        };
+
+        if record.endpoint != endpoint || record.request_hash != request_hash {
+            return Ok(IdempotencyLookup::Conflict);
+        }
+        Ok(IdempotencyLookup::Replay {
+            status: record.status,
+            response: record.response,
+        })
+    }
+
+    /// 保存请求结果
+    pub async fn remember(
+        &self,
+        scope: &str,
+        key: &str,
+        endpoint: &str,
+        request_hash: &str,
+        status: u16,
+        response: Value,
+    ) -> anyhow::Result<()> {
+        let now = self.clock.now();
+        let record = IdempotencyRecord {
+            scope: scope.to_string(),
+            key: key.to_string(),
+            endpoint: endpoint.to_string(),
+            request_hash: request_hash.to_string(),
+            status,
+            response,
+            created_at: now,
+            expires_at: now + self.ttl.as_secs() as i64,
+        };
+        self.store.save_idempotency_record(&record).await
+    }
+
+    /// 删除过期记录，返回删除数量
+    pub async fn cleanup(&self) -> anyhow::Result<usize> {
+        self.store.delete_expired_idempotency_records(self.clock.now()).await
+    }
+
+    /// 启动后台清理任务
+    pub fn spawn_cleanup(self: Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.cleanup().await {
+                    Ok(0) => {}
+                    Ok(removed) => debug!("Removed {} expired idempotency keys", removed),
+                    Err(e) => warn!("Failed to clean up idempotency keys: {}", e),
+                }
+            }
+        })
+    }
+
+    /// 标记（作用域, 键）正在执行；已有同键请求在执行时返回 None。
+    /// 返回的守卫在析构时解除标记，处理函数 panic 或请求被取消时也不会残留
+    pub fn begin(&self, scope: &str, key: &str) -> Option<InFlightKey<'_>> {
+        let entry = (scope.to_string(), key.to_string());
+        if !self.in_flight.insert(entry.clone()) {
+            return None;
+        }
+        Some(InFlightKey { keys: self, entry })
+    }
+}
+
+/// 正在执行的幂等键，析构时解除标记
+pub struct InFlightKey<'a> {
+    keys: &'a IdempotencyKeys,
+    entry: (String, String),
+}
+
+impl Drop for InFlightKey<'_> {
+    fn drop(&mut self) {
+        self.keys.in_flight.remove(&self.entry);
+    }
+}
+
+/// 请求所属的作用域：有效 Bearer token 对应的用户，没有时为 None
+fn request_scope(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok())
+        .map(|user_info| user_principal(&user_info.id))
+}
+
+/// 按幂等键执行处理函数
+///
+/// 没有 `Idempotency-Key` 头时直接执行；有时要求登录，查询记录决定重放、冲突或执行，
+/// 执行结果（5xx 除外）保存下来供重放。
+pub(crate) async fn idempotent<F, Fut>(
+    state: &AppState,
+    headers: &HeaderMap,
+    endpoint: &str,
+    request: &Value,
+    handler: F,
+) -> Response
+where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = (StatusCode, Value)>,
+{
+    let key = match headers.get(IDEMPOTENCY_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        Some(key) if !key.trim().is_empty() => key.trim().to_string(),
+        _ => {
+            let (status, body) = handler().await;
+            return (status, Json(body)).into_response();
+        }
+    };
+
+    let Some(scope) = request_scope(state, headers) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.idempotency_requires_auth"),
+            }),
+        )
+            .into_response();
+    };
+    let request_hash = IdempotencyKeys::request_hash(endpoint, request);
+    let keys = &state.idempotency;
+
+    if let Some(response) = cached_response(state, &scope, &key, endpoint, &request_hash).await {
+        return response;
+    }
+    let Some(_in_flight) = keys.begin(&scope, &key) else {
+        return conflict(state, "web.idempotency_in_progress", &key);
+    };
+    // 查询和标记之间，另一个同键请求可能刚执行完并保存了结果
+    if let Some(response) = cached_response(state, &scope, &key, endpoint, &request_hash).await {
+        return response;
+    }
+
+    let (status, body) = handler().await;
+    if !status.is_server_error() {
+        if let Err(e) = keys
+            .remember(&scope, &key, endpoint, &request_hash, status.as_u16(), body.clone())
+            .await
+        {
+            warn!("Failed to save idempotency key {}: {}", key, e);
+        }
+    }
+
+    (status, Json(body)).into_response()
+}
+
+/// 已保存的响应（重放）或冲突；没有记录时为 None，需要执行请求
+async fn cached_response(state: &AppState, scope: &str, key: &str, endpoint: &str, request_hash: &str) -> Option<Response> {
+    match state.idempotency.lookup(scope, key, endpoint, request_hash).await {
+        Ok(IdempotencyLookup::Replay { status, response }) => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+            Some((status, Json(response)).into_response())
+        }
+        Ok(IdempotencyLookup::Conflict) => Some(conflict(state, "web.idempotency_conflict", key)),
+        Ok(IdempotencyLookup::New) => None,
+        // 存储不可用时退化为普通请求
+        Err(e) => {
+            warn!("Failed to look up idempotency key {}: {}", key, e);
+            None
+        }
+    }
+}
+
+fn conflict(state: &AppState, message_key: &str, key: &str) -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(ErrorResponse {
+            error: state.catalog.format(message_key, &[("key", key)]),
+        }),
+    )
+        .into_response()
+}
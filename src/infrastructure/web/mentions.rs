@@ -0,0 +1,87 @@
+//! @提及自动补全接口
+//!
+//! `GET /mentions/suggest?q=&session_id=&limit=` 返回当前用户可见的候选人（需要登录）。
+//! `session_id` 为群ID时只有群成员才能得到群成员优先的排序，否则当作没有传；
+//! 其他值视为私聊对方的ID。见 [`crate::core::mentions`]。
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tracing::error;
+
+use crate::core::mentions::MentionQuery;
+
+use super::{task_actor, AppState, ErrorResponse};
+
+/// 默认返回的候选人数
+const MENTION_DEFAULT_LIMIT: usize = 10;
+/// 最多返回的候选人数
+const MENTION_MAX_LIMIT: usize = 50;
+
+/// 补全参数
+#[derive(Debug, Default, Deserialize)]
+pub struct MentionSuggestQuery {
+    #[serde(default)]
+    pub q: String,
+    pub session_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 按输入前缀返回排好序的候选人
+pub(super) async fn suggest_mentions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<MentionSuggestQuery>,
+) -> Response {
+    let viewer = match task_actor(&state, &headers) {
+        Ok((_, principal)) => principal,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let session_members = match &query.session_id {
+        Some(session_id) => session_members(&state, session_id, &viewer).await,
+        None => HashSet::new(),
+    };
+    let mention_query = MentionQuery {
+        viewer,
+        prefix: query.q,
+        session_members,
+        limit: query.limit.unwrap_or(MENTION_DEFAULT_LIMIT).clamp(1, MENTION_MAX_LIMIT),
+    };
+    match state.mentions.suggest(state.store.as_ref(), &mention_query).await {
+        Ok(candidates) => Json(serde_json::json!({
+            "success": true,
+            "data": candidates,
+        }))
+        .into_response(),
+        Err(e) => {
+            error!("Failed to build mention index: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.mentions_load_failed"),
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 会话中对当前用户可见的成员：群聊只对群成员公开，私聊是对方本人
+async fn session_members(state: &AppState, session_id: &str, viewer: &str) -> HashSet<String> {
+    let group = match &state.message_bus {
+        Some(bus) => bus.get_group(session_id).await,
+        None => None,
+    };
+    match group {
+        Some(group) if group.members.iter().any(|member| member == viewer) => group.members.into_iter().collect(),
+        Some(_) => HashSet::new(),
+        None => HashSet::from([session_id.to_string()]),
+    }
+}
@@ -1,26 +1,106 @@
 //! Web 服务器模块
 //!
 //! 提供 HTTP API 和 WebSocket 支持
-
+//!
+//! 接口挂载在 `/api/v1` 下并返回统一信封（见 [`envelope`]），
+//! 旧的 `/api/...` 路由在弃用期内作为别名保留，可通过配置关闭。
+
+pub mod agent_batch;
+pub mod attachments;
+pub mod chat_sessions;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod envelope;
+pub mod features;
+pub mod group_stats;
+pub mod groups;
+pub mod health;
+pub mod idempotency;
+pub mod mentions;
+pub mod moderation;
+pub mod nudge;
+pub mod permissions;
+pub mod protocol;
+pub mod scratchpad;
+pub mod server;
+pub mod share;
+pub mod standby;
+pub mod tool_preview;
+pub mod trace;
+
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use axum::{
-    extract::{Path, Query, State, WebSocketUpgrade},
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Path, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
-    routing::{delete, get, post},
-    Json, Router,
+    middleware,
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
 };
 use chrono::Utc;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast;
-use tower_http::cors::CorsLayer;
-use tracing::{error, info};
-
-use crate::domain::{Agent, AgentMode, Message, MessageTarget, Organization, Role, LLMConfig};
-use crate::domain::user::User;
+use tokio::sync::{broadcast, RwLock};
+use tracing::{error, info, warn};
+
+use crate::core::agent_batch::LlmConfigPatch;
+use crate::core::agent_draft::{AgentDraft, AgentDraftError, AgentInterviewer, DraftSession};
+#[cfg(feature = "chaos")]
+use crate::core::chaos::FaultInjector;
+use crate::core::clock::{Clock, SystemClock};
+use crate::core::i18n::MessageCatalog;
+use crate::core::integrity::StoreIntegrityChecker;
+use crate::core::tool_stats::ToolStats;
+use crate::core::group_sync::{GroupSyncError, GroupSyncReconciler};
+use crate::core::message_limits::{enforce_message_limits, MessageLimits};
+use crate::core::messaging::{GroupModerationError, MessageBus, PinError, ReactionEvent};
+use crate::core::events::CompanyEvent;
+use crate::core::org_changes::save_organization_tracked;
+use crate::core::mentions::MentionIndex;
+use crate::core::org_tree::OrgTreeProjection;
+use crate::core::features::FeatureFlags;
+use crate::core::nudge::NudgeSlots;
+use crate::core::leadership::LeaderElection;
+use crate::core::store::{MessageCursor, MessageFilter, TaskFilter};
+use crate::core::tasks::{TaskError, TaskManager, TaskUpdate};
+use crate::core::chat_sessions::ChatSessions;
+use crate::core::group_stats::GroupStatsTracker;
+use crate::core::retry::Retries;
+use crate::core::goals::GoalBoard;
+use crate::core::scratchpad::Scratchpad;
+use crate::core::secrets::{render_secret, secret_for_redaction, SecretRef};
+use crate::core::temp_agents::{SpawnRequest, TempAgentError, TempAgentRunner, TemporaryAgent, TemporaryAgents};
+use crate::core::runtime_info::RuntimeInfo;
+use crate::core::circuit_breaker::CircuitBreakers;
+use crate::core::scheduler::{TurnScheduler, TurnState};
+use crate::core::translation::TranslationService;
+use crate::core::transcript::{export_stream, participant_names, TranscriptFormat, TranscriptPager, TranscriptRenderer, TranscriptSession};
+use crate::core::role_history::{append_role_revision, role_in_effect, rollback_role};
+use crate::core::response_language::is_known_language;
+use crate::core::redaction;
+use crate::domain::{new_trace_id, Agent, AgentMode, Availability, ChatSession, DepartmentFull, Group, Message, MessageBookmark, MessagePriority, MessageReaction, MessageTarget, ReactionCount, Organization, Role, LLMConfig, OrgChangeEntry, Task, TaskStatus, ToolProgressEvent, TurnTakingSettings};
+use crate::domain::scratchpad::ScratchpadEntry;
+use crate::domain::user::{is_user_principal, user_principal, User};
 use crate::domain::invitation_code::InvitationCode;
-use crate::infrastructure::auth::{JwtService, PasswordService, UserInfo};
+use crate::infrastructure::blob::BlobStore;
+use crate::infrastructure::logger::{PromptLog, PromptLogLevel};
+use crate::infrastructure::tool::ToolExecutorRegistry;
+use crate::infrastructure::auth::{
+    ip_key, user_key, JwtService, LoginThrottle, LoginThrottleConfig, PasswordPolicy, PasswordService, Permission,
+    PermissionConfig, PermissionSet, UserInfo,
+};
+
+use permissions::{perm, PermissionMarker, RequirePermission};
+use envelope::{deprecation_middleware, envelope_middleware};
+use protocol::{EncodedFrame, FrameCodec, MessageFrame, ServerEvent, ServerFrame, DEFAULT_MIN_COMPRESS_BYTES};
+use trace::trace_middleware;
+pub use health::{HealthChecker, HealthConfig};
+pub use idempotency::{IdempotencyKeys, InFlightKey, IDEMPOTENCY_KEY_HEADER};
+pub use protocol::ClientMessage;
+pub use server::{RouteGroups, TlsConfig, TlsListener, WebServerConfig};
+pub use trace::{TraceId, TRACE_ID_HEADER};
 
 // ==================== 错误响应 ====================
 
@@ -33,44 +113,461 @@ struct ErrorResponse {
 
 #[derive(Clone)]
 pub struct AppState {
-    pub agents: Vec<Agent>,
+    /// 运行中的 Agent，通过 `agents()` 读取、`update_agents()` 修改
+    agents: Arc<std::sync::RwLock<Vec<Agent>>>,
+    /// 串行化接口对组织架构的读-改-写，并发请求不会互相覆盖
+    org_lock: Arc<tokio::sync::Mutex<()>>,
     pub message_tx: broadcast::Sender<Message>,
     pub store: Arc<dyn crate::core::store::Store>,
     pub jwt_service: JwtService,
+    pub catalog: MessageCatalog,
+    pub tool_stats: Arc<ToolStats>,
+    /// 是否保留未版本化的旧路由
+    pub legacy_api_routes: bool,
+    /// 注册和修改密码时使用的密码策略
+    pub password_policy: PasswordPolicy,
+    /// 登录限流器
+    pub login_throttle: Arc<LoginThrottle>,
+    /// 公司级消息模板
+    pub templates: Arc<RwLock<HashMap<String, String>>>,
+    /// 就绪检查器
+    pub health: Arc<HealthChecker>,
+    /// 消息回应变化广播
+    pub reactions: broadcast::Sender<ReactionEvent>,
+    /// 工具进度广播，WebSocket 客户端订阅后转发
+    pub tool_progress: broadcast::Sender<ToolProgressEvent>,
+    /// Agent 轮次调度器（与 VirtualCompany 共享）
+    pub scheduler: Option<Arc<TurnScheduler>>,
+    /// Agent 熔断器（与 VirtualCompany 共享）
+    pub circuit_breakers: Option<Arc<CircuitBreakers>>,
+    /// 消息总线（与 VirtualCompany 共享），登录用户作为 `user:{id}` 参与者注册到其上
+    pub message_bus: Option<Arc<MessageBus>>,
+    /// 创建类接口的幂等键
+    pub idempotency: Arc<IdempotencyKeys>,
+    /// 判断 Agent 工作时间使用的时钟
+    pub clock: Arc<dyn Clock>,
+    /// 消息翻译服务（与 VirtualCompany 共享），推送给用户的消息按其首选语言附上译文
+    pub translator: Option<Arc<TranslationService>>,
+    /// 公司名称、数据目录和配置来源，`/api/info` 在此基础上报告当前的 Agent 数和存储
+    pub runtime_info: Option<RuntimeInfo>,
+    /// 附件使用的大对象存储，未设置时附件接口返回 503
+    pub blobs: Option<Arc<dyn BlobStore>>,
+    /// 职位、部门负责人和用户的授权
+    pub permissions: Arc<PermissionConfig>,
+    /// 提示词日志（与 VirtualCompany 共享），挂载后管理接口 `/admin/prompt-log` 可调整脱敏级别
+    pub prompt_log: Option<Arc<PromptLog>>,
+    /// 发送消息（REST 和 WebSocket）时的大小限制
+    pub message_limits: MessageLimits,
+    /// HR 面试官，挂载后可以通过 `/agents/draft` 以对话方式创建 Agent
+    pub agent_interviewer: Option<Arc<AgentInterviewer>>,
+    /// `POST /agents` 未给出的 LLM 配置字段取自这里，为空时取组织中第一个 Agent 的配置
+    pub default_llm: Option<LLMConfig>,
+    /// 启动和停止 Agent 的自主循环（注册到消息总线），挂载后 `POST /agents` 创建的 Agent 立即开始工作，
+    /// 否则在下次启动公司时开始工作
+    pub agent_runner: Option<Arc<dyn TempAgentRunner>>,
+    /// 临时 Agent 管理，挂载后可以通过 `/chat/{session_id}/spawn-agent` 为会话创建临时 Agent
+    pub temp_agents: Option<Arc<TemporaryAgents>>,
+    /// Agent 运行草稿，挂载后可以通过 `/runs/{run_id}/scratchpad` 查看，WebSocket 客户端订阅后实时推送
+    pub scratchpad: Option<Arc<Scratchpad>>,
+    /// 各 Agent 的当前目标，挂载后 `GET /agents/{id}` 返回 `goal`
+    pub goals: Option<Arc<GoalBoard>>,
+    /// 会话生命周期，挂载后可以通过 `/chat/{id}/close|reopen` 结束或重新打开会话，`/chat/list` 默认不列出已关闭的会话
+    pub chat_sessions: Option<Arc<ChatSessions>>,
+    /// 群聊活动统计，挂载后可以通过 `/groups/{id}/stats` 查看、`/admin/groups/{id}/stats/recompute` 重新计算
+    pub group_stats: Option<Arc<GroupStatsTracker>>,
+    /// 重试注册表，挂载后可以通过 `/retries/stats` 查看各调用点的重试统计
+    pub retries: Option<Arc<Retries>>,
+    /// `/org/tree` 返回的部门树缓存，组织架构变更时失效
+    pub org_tree: Arc<OrgTreeProjection>,
+    /// `/mentions/suggest` 的候选索引，组织架构或用户变更时失效
+    pub mentions: Arc<MentionIndex>,
+    /// 各 Agent 的插话槽，`/agents/{id}/nudge` 写入
+    pub nudges: Arc<NudgeSlots>,
+    /// 功能开关，`/features` 报告、管理接口切换
+    pub features: Arc<FeatureFlags>,
+    /// 主备部署的领导权，挂载后本节点不是领导者时拒绝写请求
+    pub leadership: Option<Arc<LeaderElection>>,
+    /// 工具执行器注册表，挂载后可以通过 `/tools/{id}/dry-run` 预演工具调用
+    pub tool_executor: Option<Arc<ToolExecutorRegistry>>,
+    /// 故障注入器，挂载后管理接口 `/admin/chaos/rules` 可用
+    #[cfg(feature = "chaos")]
+    pub fault_injector: Option<Arc<FaultInjector>>,
+}
+
+impl AppState {
+    /// 创建 Web 状态，使用默认语言
+    pub fn new(
+        agents: Vec<Agent>,
+        message_tx: broadcast::Sender<Message>,
+        store: Arc<dyn crate::core::store::Store>,
+        jwt_service: JwtService,
+    ) -> Self {
+        let login_throttle = Arc::new(
+            LoginThrottle::new(LoginThrottleConfig::default()).with_store(store.clone())
+        );
+
+        let idempotency = Arc::new(IdempotencyKeys::new(store.clone()));
+
+        Self {
+            agents: Arc::new(std::sync::RwLock::new(agents)),
+            org_lock: Arc::new(tokio::sync::Mutex::new(())),
+            message_tx,
+            store,
+            jwt_service,
+            catalog: MessageCatalog::default(),
+            tool_stats: Arc::new(ToolStats::new()),
+            legacy_api_routes: true,
+            password_policy: PasswordPolicy::default(),
+            login_throttle,
+            templates: Arc::new(RwLock::new(HashMap::new())),
+            health: Arc::new(HealthChecker::default()),
+            reactions: broadcast::channel(100).0,
+            tool_progress: broadcast::channel(256).0,
+            scheduler: None,
+            circuit_breakers: None,
+            message_bus: None,
+            idempotency,
+            clock: Arc::new(SystemClock),
+            translator: None,
+            runtime_info: None,
+            blobs: None,
+            permissions: Arc::new(PermissionConfig::default()),
+            prompt_log: None,
+            message_limits: MessageLimits::default(),
+            agent_interviewer: None,
+            default_llm: None,
+            agent_runner: None,
+            temp_agents: None,
+            scratchpad: None,
+            goals: None,
+            chat_sessions: None,
+            group_stats: None,
+            retries: None,
+            org_tree: Arc::new(OrgTreeProjection::new()),
+            mentions: Arc::new(MentionIndex::default()),
+            nudges: Arc::new(NudgeSlots::new()),
+            features: Arc::new(FeatureFlags::compiled()),
+            leadership: None,
+            tool_executor: None,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
+        }
+    }
+
+    /// 设置错误信息使用的消息目录
+    pub fn with_catalog(mut self, catalog: MessageCatalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    /// 使用共享的工具统计收集器
+    pub fn with_tool_stats(mut self, tool_stats: Arc<ToolStats>) -> Self {
+        self.tool_stats = tool_stats;
+        self
+    }
+
+    /// 设置是否保留未版本化的旧路由
+    pub fn with_legacy_api_routes(mut self, enabled: bool) -> Self {
+        self.legacy_api_routes = enabled;
+        self
+    }
+
+    /// 设置密码策略
+    pub fn with_password_policy(mut self, policy: PasswordPolicy) -> Self {
+        self.password_policy = policy;
+        self
+    }
+
+    /// 使用指定的登录限流器
+    pub fn with_login_throttle(mut self, throttle: Arc<LoginThrottle>) -> Self {
+        self.login_throttle = throttle;
+        self
+    }
+
+    /// 运行中 Agent 的快照
+    pub fn agents(&self) -> Vec<Agent> {
+        self.agents.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// 修改运行中的 Agent 列表，所有共享此状态的克隆都能看到
+    pub fn update_agents(&self, update: impl FnOnce(&mut Vec<Agent>)) {
+        update(&mut self.agents.write().unwrap_or_else(|e| e.into_inner()));
+    }
+
+    /// 使用共享的公司级消息模板
+    pub fn with_templates(mut self, templates: Arc<RwLock<HashMap<String, String>>>) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// 使用共享的回应广播（如 `VirtualCompany::reaction_sender`）
+    pub fn with_reactions(mut self, reactions: broadcast::Sender<ReactionEvent>) -> Self {
+        self.reactions = reactions;
+        self
+    }
+
+    /// 使用共享的工具进度广播（如 `VirtualCompany::tool_progress_sender`）
+    pub fn with_tool_progress(mut self, tool_progress: broadcast::Sender<ToolProgressEvent>) -> Self {
+        self.tool_progress = tool_progress;
+        self
+    }
+
+    /// 使用共享的轮次调度器（如 `VirtualCompany::scheduler`）
+    pub fn with_scheduler(mut self, scheduler: Arc<TurnScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// 使用共享的 Agent 熔断器（如 `VirtualCompany::circuit_breakers`）
+    pub fn with_circuit_breakers(mut self, breakers: Arc<CircuitBreakers>) -> Self {
+        self.circuit_breakers = Some(breakers);
+        self
+    }
+
+    /// 使用共享的消息总线（如 `VirtualCompany::message_bus`）
+    pub fn with_message_bus(mut self, message_bus: Arc<MessageBus>) -> Self {
+        self.message_bus = Some(message_bus);
+        self
+    }
+
+    /// 设置就绪检查配置
+    pub fn with_health_config(mut self, config: HealthConfig) -> Self {
+        self.health = Arc::new(HealthChecker::new(config));
+        self
+    }
+
+    /// 使用指定的幂等键服务（如自定义 TTL 或时钟）
+    pub fn with_idempotency(mut self, idempotency: Arc<IdempotencyKeys>) -> Self {
+        self.idempotency = idempotency;
+        self
+    }
+
+    /// 使用指定时钟判断 Agent 工作时间（测试用）
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// 使用共享的消息翻译服务（如 `VirtualCompany::translator`）
+    pub fn with_translator(mut self, translator: Arc<TranslationService>) -> Self {
+        self.translator = Some(translator);
+        self
+    }
+
+    /// 使用公司的运行时信息（如 `VirtualCompany::runtime_info`）
+    pub fn with_runtime_info(mut self, runtime_info: RuntimeInfo) -> Self {
+        self.runtime_info = Some(runtime_info);
+        self
+    }
+
+    /// 使用大对象存储保存附件（如 `VirtualCompany::blob_store`）
+    pub fn with_blob_store(mut self, blob_store: Arc<dyn BlobStore>) -> Self {
+        self.blobs = Some(blob_store);
+        self
+    }
+
+    /// 使用共享的提示词日志（如 `VirtualCompany::prompt_log`）
+    pub fn with_prompt_log(mut self, prompt_log: Arc<PromptLog>) -> Self {
+        self.prompt_log = Some(prompt_log);
+        self
+    }
+
+    /// 设置发送消息的大小限制（`AppConfig::message_limits`）
+    pub fn with_message_limits(mut self, message_limits: MessageLimits) -> Self {
+        self.message_limits = message_limits;
+        self
+    }
+
+    /// 使用共享的 HR 面试官（如 `VirtualCompany::agent_interviewer`）
+    pub fn with_agent_interviewer(mut self, interviewer: Arc<AgentInterviewer>) -> Self {
+        self.agent_interviewer = Some(interviewer);
+        self
+    }
+
+    /// 设置新建 Agent 的默认 LLM 配置（如 `VirtualCompany::default_llm`）
+    pub fn with_default_llm(mut self, llm: LLMConfig) -> Self {
+        self.default_llm = Some(llm);
+        self
+    }
+
+    /// 使用公司的 Agent 运行器（如 `VirtualCompany::agent_runner`）
+    pub fn with_agent_runner(mut self, runner: Arc<dyn TempAgentRunner>) -> Self {
+        self.agent_runner = Some(runner);
+        self
+    }
+
+    /// 使用共享的临时 Agent 管理（如 `VirtualCompany::temp_agents`）
+    pub fn with_temp_agents(mut self, temp_agents: Arc<TemporaryAgents>) -> Self {
+        self.temp_agents = Some(temp_agents);
+        self
+    }
+
+    /// 使用共享的运行草稿（如 `VirtualCompany::scratchpad`）
+    pub fn with_scratchpad(mut self, scratchpad: Arc<Scratchpad>) -> Self {
+        self.scratchpad = Some(scratchpad);
+        self
+    }
+
+    /// 使用共享的当前目标（如 `VirtualCompany::goals`）
+    pub fn with_goals(mut self, goals: Arc<GoalBoard>) -> Self {
+        self.goals = Some(goals);
+        self
+    }
+
+    /// 使用共享的会话生命周期管理（如 `VirtualCompany::chat_sessions`）
+    pub fn with_chat_sessions(mut self, chat_sessions: Arc<ChatSessions>) -> Self {
+        self.chat_sessions = Some(chat_sessions);
+        self
+    }
+
+    /// 使用共享的群聊活动统计（如 `VirtualCompany::group_stats`）
+    pub fn with_group_stats(mut self, group_stats: Arc<GroupStatsTracker>) -> Self {
+        self.group_stats = Some(group_stats);
+        self
+    }
+
+    /// 使用共享的重试注册表（如 `VirtualCompany::retries`）
+    pub fn with_retries(mut self, retries: Arc<Retries>) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// 挂载工具执行器注册表，审批者可以在放行前预演工具调用
+    pub fn with_tool_executor(mut self, tool_executor: Arc<ToolExecutorRegistry>) -> Self {
+        self.tool_executor = Some(tool_executor);
+        self
+    }
+
+    /// 使用运行中 Agent 的插话槽（如 `VirtualCompany::nudges`），否则插话都转为 Urgent 消息
+    pub fn with_nudges(mut self, nudges: Arc<NudgeSlots>) -> Self {
+        self.nudges = nudges;
+        self
+    }
+
+    /// 使用公司的功能开关（如 `VirtualCompany::features`），管理接口的切换随之发出公司事件
+    pub fn with_features(mut self, features: Arc<FeatureFlags>) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// 使用共享的部门树缓存（如 `VirtualCompany::org_tree`），使公司保存组织架构时缓存同步失效
+    pub fn with_org_tree(mut self, org_tree: Arc<OrgTreeProjection>) -> Self {
+        self.org_tree = org_tree;
+        self
+    }
+
+    /// 使用共享的提及候选索引（如 `VirtualCompany::mentions`），使公司保存组织架构时索引同步失效
+    pub fn with_mentions(mut self, mentions: Arc<MentionIndex>) -> Self {
+        self.mentions = mentions;
+        self
+    }
+
+    /// 使用主备部署的领导权（如 `VirtualCompany::leadership`）
+    pub fn with_leadership(mut self, leadership: Arc<LeaderElection>) -> Self {
+        self.leadership = Some(leadership);
+        self
+    }
+
+    /// 使用公司配置的权限（`CompanyConfig::permissions`）
+    pub fn with_permissions(mut self, permissions: PermissionConfig) -> Self {
+        self.permissions = Arc::new(permissions);
+        self
+    }
+
+    /// 挂载故障注入器（与 LLM 客户端、存储、消息总线共享），供管理接口调整规则
+    #[cfg(feature = "chaos")]
+    pub fn with_fault_injector(mut self, injector: Arc<FaultInjector>) -> Self {
+        self.fault_injector = Some(injector);
+        self
+    }
 }
 
 // ==================== API 响应类型 ====================
 
-#[derive(Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AgentResponse {
     pub id: String,
     pub name: String,
     pub role: String,
     pub department: String,
+    /// 临时 Agent 的会话、创建者和到期时间，常驻 Agent 为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temporary: Option<TemporaryAgentInfo>,
+}
+
+/// 临时 Agent 的附加信息，界面据此与常驻 Agent 区分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemporaryAgentInfo {
+    pub session_id: String,
+    pub spawned_by: String,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+}
+
+impl From<&TemporaryAgent> for AgentResponse {
+    fn from(record: &TemporaryAgent) -> Self {
+        Self {
+            id: record.agent.id.clone(),
+            name: record.agent.name.clone(),
+            role: record.agent.role.title.clone(),
+            department: String::new(),
+            temporary: Some(TemporaryAgentInfo {
+                session_id: record.session_id.clone(),
+                spawned_by: record.spawned_by.clone(),
+                created_at: record.created_at,
+                expires_at: record.expires_at,
+            }),
+        }
+    }
+}
+
+/// Agent 列表参数
+#[derive(Debug, Default, Deserialize)]
+pub struct AgentListQuery {
+    /// 是否包含仍在运行的临时 Agent（默认只列出组织架构中的 Agent）
+    #[serde(default)]
+    pub include_temporary: bool,
+}
+
+/// 创建 Agent 的请求
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateAgentRequest {
+    /// 为空时生成
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    /// 角色名称
+    pub role: String,
+    pub system_prompt: String,
+    #[serde(default)]
+    pub department_id: Option<String>,
+    /// 未给出的字段取默认 LLM 配置
+    #[serde(default)]
+    pub llm_config: LlmConfigPatch,
+}
+
+/// 删除 Agent 的参数
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteAgentQuery {
+    /// Agent 是部门负责人时清除负责人后删除，否则返回 409
+    #[serde(default)]
+    pub force: bool,
 }
 
 // ==================== 请求类型 ====================
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct SendMessageRequest {
     pub from: String,
     pub to: Option<String>,
     pub content: String,
-}
-
-// ==================== 客户端消息类型 ====================
-
-#[derive(Deserialize)]
-#[serde(tag = "type")]
-pub enum ClientMessage {
-    #[serde(rename = "send_message")]
-    SendMessage {
-        from: String,
-        to: String,
-        content: String,
-    },
-    #[serde(rename = "ping")]
-    Ping,
+    /// 消息优先级，默认 normal
+    #[serde(default)]
+    pub priority: MessagePriority,
+    /// 目标 Agent 不在工作时间时返回 503，而不是排队等其上班
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reject_if_unavailable: bool,
 }
 
 
@@ -114,12 +611,158 @@ async fn get_current_user(
     (
         StatusCode::UNAUTHORIZED,
         Json(ErrorResponse {
-            error: "Unauthorized".to_string(),
+            error: state.catalog.get("web.unauthorized"),
         })
     ).into_response()
 }
 
 #[derive(Deserialize)]
+pub struct InboxQuery {
+    /// 只返回该时间戳（含）之后的消息
+    pub since: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+/// 获取发给当前用户的消息（离线期间 Agent 发来的私聊在此拉取）
+async fn get_my_messages(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<InboxQuery>,
+) -> impl IntoResponse {
+    let user_info = headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    let Some(user_info) = user_info else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        ).into_response();
+    };
+
+    let principal = user_principal(&user_info.id);
+    let mut filter = MessageFilter::new()
+        .to(principal.as_str())
+        .target_type("direct")
+        .limit(query.limit.unwrap_or(100));
+    if let Some(since) = query.since {
+        filter = filter.since(since);
+    }
+
+    match state.store.load_messages(filter).await {
+        Ok(messages) => {
+            // 原文保持不变，译文按消息ID单独返回
+            let translations = match (&state.translator, preferred_language(&state, &user_info).await) {
+                (Some(translator), Some(language)) => translator.translate_all(&messages, &language).await,
+                _ => HashMap::new(),
+            };
+            Json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "principal_id": principal,
+                    "messages": messages,
+                    "translations": translations,
+                }
+            })).into_response()
+        }
+        Err(e) => {
+            error!("Failed to load messages for {}: {}", principal, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.messages_load_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct PreferredLanguageRequest {
+    /// 语言代码（如 `en`、`zh`），为空表示不翻译
+    pub language: Option<String>,
+}
+
+/// 用户个人资料中的阅读语言，未启用翻译时为 None
+async fn preferred_language(state: &AppState, user_info: &UserInfo) -> Option<String> {
+    state.translator.as_ref()?;
+    match state.store.load_user_by_username(&user_info.username).await {
+        Ok(user) => user.and_then(|u| u.preferred_language),
+        Err(e) => {
+            error!("Failed to load preferred language of {}: {}", user_info.username, e);
+            None
+        }
+    }
+}
+
+/// 设置当前用户的阅读语言，发来的其他语言消息附上该语言的译文
+async fn set_preferred_language(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<PreferredLanguageRequest>,
+) -> impl IntoResponse {
+    let user_info = headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    let Some(user_info) = user_info else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        ).into_response();
+    };
+    let language = req.language.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    if let Some(code) = language.as_deref().filter(|code| !is_known_language(code)) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.preferred_language_invalid", &[("language", code)]),
+            })
+        ).into_response();
+    }
+
+    let mut user = match state.store.load_user_by_username(&user_info.username).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.unauthorized"),
+                })
+            ).into_response();
+        }
+        Err(e) => {
+            error!("Database error while loading {}: {}", user_info.username, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.database_error"),
+                })
+            ).into_response();
+        }
+    };
+    user.preferred_language = language;
+    if let Err(e) = state.store.save_user(&user).await {
+        error!("Failed to save preferred language of {}: {}", user.username, e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.database_error"),
+            })
+        ).into_response();
+    }
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": { "preferred_language": user.preferred_language }
+    })).into_response()
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct AuthRequest {
     pub username: String,
     pub password: String,
@@ -144,18 +787,71 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
+/// 就绪探针：关键检查失败时返回 503
+async fn readiness_check(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let checks = state.health.check(state.store.as_ref(), &state.message_tx, &state.agents()).await;
+    let failed: Vec<String> = checks
+        .iter()
+        .filter(|c| c.critical && !c.ok)
+        .map(|c| c.label())
+        .collect();
+    let status = if !failed.is_empty() {
+        "fail"
+    } else if checks.iter().any(|c| c.degraded.is_some()) {
+        "degraded"
+    } else {
+        "ok"
+    };
+
+    let mut body = serde_json::json!({
+        "status": status,
+        "timestamp": Utc::now().to_rfc3339(),
+        "checks": checks,
+        "store": state.store.backend_info(),
+    });
+
+    if failed.is_empty() {
+        (StatusCode::OK, Json(body))
+    } else {
+        body["error"] = serde_json::json!(state.catalog.format("web.not_ready", &[("checks", &failed.join(", "))]));
+        (StatusCode::SERVICE_UNAVAILABLE, Json(body))
+    }
+}
+
+/// 版本、构建、存储和配置信息，不含任何敏感值
+async fn get_runtime_info(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let info = match &state.runtime_info {
+        Some(info) => RuntimeInfo {
+            agent_count: state.agents().len(),
+            store: state.store.backend_info(),
+            ..info.clone()
+        },
+        None => RuntimeInfo::new("ImitatorT Virtual Company", state.agents().len(), state.store.backend_info()),
+    };
+    Json(info.with_flags(&state.features))
+}
+
 /// 获取 Agent 列表
-async fn list_agents(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let agents: Vec<AgentResponse> = state
-        .agents
+async fn list_agents(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AgentListQuery>,
+) -> impl IntoResponse {
+    let mut agents: Vec<AgentResponse> = state
+        .agents()
         .iter()
         .map(|a| AgentResponse {
             id: a.id.clone(),
             name: a.name.clone(),
             role: a.role.title.clone(),
             department: a.department_id.clone().unwrap_or_default(),
+            temporary: None,
         })
         .collect();
+    if query.include_temporary {
+        if let Some(temp_agents) = &state.temp_agents {
+            agents.extend(temp_agents.list_active(None).await.iter().map(AgentResponse::from));
+        }
+    }
 
     Json(agents)
 }
@@ -165,18 +861,46 @@ async fn get_agent(
     State(state): State<Arc<AppState>>,
     Path(agent_id): Path<String>,
 ) -> impl IntoResponse {
-    match state.agents.iter().find(|a| a.id == agent_id) {
-        Some(agent) => Json(serde_json::json!({
-            "id": agent.id,
-            "name": agent.name,
-            "role": agent.role.title,
-            "department": agent.department_id,
-        }))
-        .into_response(),
+    // 先查运行中的 Agent，再按ID定向查询存储（如注册时创建的 Agent），不加载整个组织架构
+    let running = state.agents().into_iter().find(|a| a.id == agent_id);
+    let agent = match running {
+        Some(agent) => Some(agent),
+        None => match state.store.load_agent(&agent_id).await {
+            Ok(agent) => agent,
+            Err(e) => {
+                error!("Failed to load agent {}: {}", agent_id, e);
+                None
+            }
+        },
+    };
+
+    match agent {
+        Some(agent) => {
+            let mut body = serde_json::json!({
+                "id": agent.id,
+                "name": agent.name,
+                "role": agent.role.title,
+                "department": agent.department_id,
+                "status": agent_presence(&state, &agent),
+                // API Key 只显示引用，明文显示为 ***
+                "llm": {
+                    "model": agent.llm_config.model,
+                    "base_url": agent.llm_config.base_url,
+                    "api_key": render_secret(&agent.llm_config.api_key),
+                },
+            });
+            if let Some(breakers) = &state.circuit_breakers {
+                body["breaker"] = serde_json::json!(breakers.status(&agent.id));
+            }
+            if let Some(goals) = &state.goals {
+                body["goal"] = serde_json::json!(goals.get(&agent.id));
+            }
+            Json(body).into_response()
+        }
         None => (
             StatusCode::NOT_FOUND,
             Json(ErrorResponse {
-                error: format!("Agent not found: {}", agent_id),
+                error: state.catalog.format("web.agent_not_found", &[("agent_id", &agent_id)]),
             }),
         )
             .into_response(),
@@ -185,8 +909,8 @@ async fn get_agent(
 
 /// 获取公司信息
 async fn get_company(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let departments: Vec<String> = state
-        .agents
+    let agents = state.agents();
+    let departments: Vec<String> = agents
         .iter()
         .filter_map(|a| a.department_id.clone())
         .collect::<std::collections::HashSet<_>>()
@@ -195,132 +919,622 @@ async fn get_company(State(state): State<Arc<AppState>>) -> impl IntoResponse {
 
     Json(serde_json::json!({
         "name": "ImitatorT Virtual Company",
-        "agent_count": state.agents.len(),
+        "agent_count": agents.len(),
         "departments": departments,
     }))
 }
 
-/// 发送消息
+/// 发送消息，支持 `Idempotency-Key`
 async fn send_message(
     State(state): State<Arc<AppState>>,
+    Extension(trace_id): Extension<TraceId>,
+    headers: HeaderMap,
     Json(req): Json<SendMessageRequest>,
 ) -> impl IntoResponse {
-    let to = if let Some(to_id) = req.to {
-        MessageTarget::Direct(to_id)
-    } else {
+    let request = serde_json::to_value(&req).unwrap_or_default();
+    idempotency::idempotent(&state, &headers, "POST /messages", &request, || {
+        create_message(&state, &trace_id, req)
+    })
+    .await
+}
+
+async fn create_message(state: &AppState, trace_id: &TraceId, req: SendMessageRequest) -> (StatusCode, serde_json::Value) {
+    let Some(to_id) = req.to else {
         return (
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Missing 'to' field".to_string(),
-            }),
-        )
-            .into_response();
+            serde_json::json!({ "error": state.catalog.get("web.missing_to_field") }),
+        );
     };
 
+    // 目标 Agent 不在工作时间时消息排队，或按请求返回 503；允许紧急消息打断时 Urgent 照常送达
+    let now = state.clock.now();
+    let unavailable = agent_availability(state, &to_id).await.filter(|availability| {
+        !(availability.is_available_at(now) || (availability.urgent_override && req.priority == MessagePriority::Urgent))
+    });
+    let next_available_at = unavailable.as_ref().and_then(|a| a.next_available_at(now));
+    if let (Some(availability), true) = (&unavailable, req.reject_if_unavailable) {
+        let next = next_available_at
+            .map(|next| availability.format_local(next))
+            .unwrap_or_else(|| "-".to_string());
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            serde_json::json!({
+                "error": state.catalog.format("web.agent_unavailable", &[("agent_id", &to_id), ("next", &next)]),
+                "next_available_at": next_available_at,
+            }),
+        );
+    }
+
     let message = Message {
         id: uuid::Uuid::new_v4().to_string(),
         from: req.from,
-        to,
+        to: MessageTarget::Direct(to_id),
         content: req.content,
         timestamp: Utc::now().timestamp(),
         reply_to: None,
         mentions: Vec::new(),
+        metadata: Default::default(),
+        priority: req.priority,
+    }
+    .with_trace(&trace_id.0, None);
+    let message = match enforce_message_limits(&state.message_limits, message, state.blobs.as_deref(), &state.catalog).await {
+        Ok(message) => message,
+        Err(error) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                serde_json::json!({ "error": error.describe(&state.catalog) }),
+            );
+        }
     };
 
     // 发送消息
     let _ = state.message_tx.send(message.clone());
 
-    Json(serde_json::json!({
+    let mut body = serde_json::json!({
         "id": message.id,
-        "status": "sent",
+        "status": if unavailable.is_some() { "queued" } else { "sent" },
         "timestamp": message.timestamp,
-    }))
-    .into_response()
+    });
+    if let Some(attachment) = message.attachments().first() {
+        body["attachment"] = serde_json::json!(attachment);
+    }
+    if unavailable.is_some() {
+        body["next_available_at"] = serde_json::json!(next_available_at);
+    }
+    (StatusCode::OK, body)
 }
 
-/// 登录
-async fn login(
+/// Agent 的工作时间：优先读存储（接口修改后立即生效），其次是运行中的 Agent
+async fn agent_availability(state: &AppState, agent_id: &str) -> Option<Availability> {
+    match state.store.load_agent(agent_id).await {
+        Ok(Some(agent)) => agent.availability,
+        Ok(None) => state.agents().into_iter().find(|a| a.id == agent_id).and_then(|a| a.availability),
+        Err(e) => {
+            error!("Failed to load agent {}: {}", agent_id, e);
+            None
+        }
+    }
+}
+
+/// 回应请求（POST 为 JSON 请求体，DELETE 为查询参数）
+#[derive(Deserialize)]
+pub struct ReactionRequest {
+    pub reactor_id: String,
+    pub emoji: String,
+}
+
+/// 添加消息回应，重复添加不报错
+async fn add_reaction(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<AuthRequest>,
+    Path(message_id): Path<String>,
+    Json(req): Json<ReactionRequest>,
 ) -> impl IntoResponse {
-    info!("Login attempt: {}", req.username);
+    update_reaction(&state, MessageReaction::new(message_id, req.reactor_id, req.emoji), true).await
+}
 
-    // 从数据库查找用户
-    match state.store.load_user_by_username(&req.username).await {
-        Ok(Some(user)) => {
-            // 验证密码
-            match PasswordService::verify_password(&user.password_hash, &req.password) {
-                Ok(valid) if valid => {
-                    // 生成JWT令牌
-                    let token: String = match state.jwt_service.generate_token(&UserInfo {
-                        id: user.id.clone(),
-                        username: user.username.clone(),
-                        name: user.name.clone(),
-                        email: user.email.clone(),
-                        is_director: matches!(user.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management),
-                        employee_id: user.employee_id.clone(),
-                        position: format!("{:?}", user.position),
-                        department: user.department.clone(),
-                    }) {
-                        Ok(token) => token,
-                        Err(e) => {
-                            error!("Failed to generate token: {}", e);
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(ErrorResponse {
-                                    error: "Failed to generate token".to_string(),
-                                })
-                            ).into_response();
-                        }
-                    };
+/// 撤销消息回应
+async fn remove_reaction(
+    State(state): State<Arc<AppState>>,
+    Path(message_id): Path<String>,
+    Query(req): Query<ReactionRequest>,
+) -> impl IntoResponse {
+    update_reaction(&state, MessageReaction::new(message_id, req.reactor_id, req.emoji), false).await
+}
 
-                    Json(serde_json::json!({
-                        "success": true,
-                        "data": {
-                            "token": token,
-                            "user": {
-                                "id": user.id,
-                                "username": user.username,
-                                "name": user.name,
-                                "email": user.email,
-                                "is_director": matches!(user.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management),
-                                "employee_id": user.employee_id,
-                                "position": format!("{:?}", user.position),
-                                "department": user.department,
-                            }
-                        }
-                    })).into_response()
-                },
-                Ok(_) | Err(_) => {
-                    // 密码错误
-                    (
-                        StatusCode::UNAUTHORIZED,
-                        Json(ErrorResponse {
-                            error: "Invalid username or password".to_string(),
-                        })
-                    ).into_response()
-                }
+async fn update_reaction(state: &AppState, reaction: MessageReaction, add: bool) -> axum::response::Response {
+    if !MessageReaction::is_valid_emoji(&reaction.emoji) || reaction.reactor_id.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.invalid_reaction", &[("emoji", &reaction.emoji)]),
+            }),
+        )
+            .into_response();
+    }
+
+    let changed = if add {
+        state.store.add_reaction(&reaction).await
+    } else {
+        state.store.remove_reaction(&reaction.message_id, &reaction.reactor_id, &reaction.emoji).await
+    };
+    let changed = match changed {
+        Ok(changed) => changed,
+        Err(e) => {
+            error!("Failed to update reaction on {}: {}", reaction.message_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.reaction_failed"),
+                }),
+            )
+                .into_response();
+        }
+    };
+
+    let message_id = reaction.message_id.clone();
+    if changed {
+        let event = if add { ReactionEvent::Added(reaction) } else { ReactionEvent::Removed(reaction) };
+        let _ = state.reactions.send(event);
+    }
+
+    let reactions = state
+        .store
+        .load_reactions(std::slice::from_ref(&message_id))
+        .await
+        .map(|r| ReactionCount::aggregate(&r).remove(&message_id).unwrap_or_default())
+        .unwrap_or_default();
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "changed": changed,
+            "reactions": reactions,
+        }
+    }))
+    .into_response()
+}
+
+/// 置顶消息（会话参与者）
+async fn pin_message(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(message_id): Path<String>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    match bus.pin_message(&message_id, &actor).await {
+        Ok(pin) => Json(serde_json::json!({
+            "success": true,
+            "data": pin,
+        })).into_response(),
+        Err(e) => pin_error_response(&state, &message_id, e),
+    }
+}
+
+/// 取消置顶（置顶者本人或群管理员）
+async fn unpin_message(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(message_id): Path<String>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    match bus.unpin_message(&message_id, &actor).await {
+        Ok(removed) => Json(serde_json::json!({
+            "success": true,
+            "data": { "message_id": message_id, "removed": removed },
+        })).into_response(),
+        Err(e) => pin_error_response(&state, &message_id, e),
+    }
+}
+
+/// 会话中的置顶消息；私聊会话传对方的ID
+async fn get_session_pins(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    let (bus, viewer) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    match bus.pinned_messages(&session_id, &viewer).await {
+        Ok(pins) => Json(serde_json::json!({
+            "success": true,
+            "data": pins,
+        })).into_response(),
+        Err(e) => pin_error_response(&state, &session_id, e),
+    }
+}
+
+fn pin_error_response(state: &AppState, target: &str, error: anyhow::Error) -> axum::response::Response {
+    let (status, message) = match error.downcast_ref::<PinError>() {
+        Some(PinError::MessageNotFound { message_id }) => {
+            (StatusCode::NOT_FOUND, state.catalog.format("web.message_not_found", &[("message_id", message_id)]))
+        }
+        Some(PinError::NotParticipant { session_id, .. }) => {
+            (StatusCode::FORBIDDEN, state.catalog.format("web.pin_not_participant", &[("session_id", session_id)]))
+        }
+        Some(PinError::NotAdmin { pinned_by, .. }) => {
+            (StatusCode::FORBIDDEN, state.catalog.format("web.pin_not_admin", &[("pinned_by", pinned_by)]))
+        }
+        None => {
+            error!("Failed to update pins for {}: {}", target, error);
+            (StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.pin_failed"))
+        }
+    };
+    (status, Json(ErrorResponse { error: message })).into_response()
+}
+
+/// 收藏消息（仅自己可见）
+async fn add_bookmark(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(message_id): Path<String>,
+) -> impl IntoResponse {
+    update_bookmark(&state, &headers, message_id, true).await
+}
+
+/// 取消收藏
+async fn remove_bookmark(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(message_id): Path<String>,
+) -> impl IntoResponse {
+    update_bookmark(&state, &headers, message_id, false).await
+}
+
+async fn update_bookmark(state: &AppState, headers: &HeaderMap, message_id: String, add: bool) -> axum::response::Response {
+    let Some(owner) = bookmark_owner(state, headers) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        ).into_response();
+    };
+
+    let result = if add {
+        match state.store.load_message(&message_id).await {
+            Ok(Some(_)) => state.store.add_bookmark(&MessageBookmark::new(message_id.clone(), owner.clone())).await,
+            Ok(None) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse {
+                        error: state.catalog.format("web.message_not_found", &[("message_id", &message_id)]),
+                    })
+                ).into_response();
             }
-        },
-        Ok(None) => {
-            // 用户不存在
+            Err(e) => Err(e),
+        }
+    } else {
+        state.store.remove_bookmark(&owner, &message_id).await
+    };
+    match result {
+        Ok(changed) => Json(serde_json::json!({
+            "success": true,
+            "data": { "message_id": message_id, "bookmarked": add, "changed": changed },
+        })).into_response(),
+        Err(e) => {
+            error!("Failed to update bookmark {} for {}: {}", message_id, owner, e);
             (
-                StatusCode::UNAUTHORIZED,
+                StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Invalid username or password".to_string(),
+                    error: state.catalog.get("web.bookmark_failed"),
                 })
             ).into_response()
-        },
+        }
+    }
+}
+
+/// 我的收藏（最新收藏在前），附带消息内容
+async fn get_my_bookmarks(State(state): State<Arc<AppState>>, headers: HeaderMap) -> impl IntoResponse {
+    let Some(owner) = bookmark_owner(&state, &headers) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        ).into_response();
+    };
+
+    let bookmarks = match state.store.load_bookmarks(&owner).await {
+        Ok(bookmarks) => bookmarks,
+        Err(e) => {
+            error!("Failed to load bookmarks for {}: {}", owner, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.bookmark_failed"),
+                })
+            ).into_response();
+        }
+    };
+    let mut entries = Vec::with_capacity(bookmarks.len());
+    for bookmark in bookmarks {
+        // 已清理的消息不再返回
+        if let Ok(Some(message)) = state.store.load_message(&bookmark.message_id).await {
+            entries.push(serde_json::json!({
+                "message_id": bookmark.message_id,
+                "timestamp": bookmark.timestamp,
+                "message": message,
+            }));
+        }
+    }
+    Json(serde_json::json!({
+        "success": true,
+        "data": entries,
+    })).into_response()
+}
+
+/// 收藏归属的用户（`user:{id}`）
+fn bookmark_owner(state: &AppState, headers: &HeaderMap) -> Option<String> {
+    headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok())
+        .map(|user_info| user_principal(&user_info.id))
+}
+
+/// 请求来源 IP：优先使用连接地址，其次是代理转发头
+fn client_ip(headers: &HeaderMap, extensions: &axum::http::Extensions) -> String {
+    if let Some(ConnectInfo(addr)) = extensions.get::<ConnectInfo<std::net::SocketAddr>>() {
+        return addr.ip().to_string();
+    }
+
+    headers.get("x-forwarded-for")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(',').next())
+        .map(|ip| ip.trim().to_string())
+        .filter(|ip| !ip.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// 密码不符合策略时的响应，逐条列出违反的规则
+fn password_policy_response(state: &AppState, password: &str) -> Option<axum::response::Response> {
+    let violations: Vec<String> = state.password_policy
+        .check(password)
+        .iter()
+        .map(|v| v.message(&state.catalog))
+        .collect();
+    if violations.is_empty() {
+        return None;
+    }
+
+    Some((
+        StatusCode::BAD_REQUEST,
+        Json(serde_json::json!({
+            "error": violations.join("; "),
+            "violations": violations,
+        }))
+    ).into_response())
+}
+
+/// 登录
+async fn login(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    extensions: axum::http::Extensions,
+    Json(req): Json<AuthRequest>,
+) -> impl IntoResponse {
+    info!("Login attempt: {}", req.username);
+
+    // 用户名与来源 IP 分别限流，不区分用户是否存在
+    let throttle_keys = [user_key(&req.username), ip_key(&client_ip(&headers, &extensions))];
+    if let Some(wait) = state.login_throttle.check(&throttle_keys).await {
+        let seconds = wait.as_secs().max(1).to_string();
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(axum::http::header::RETRY_AFTER, seconds.clone())],
+            Json(ErrorResponse {
+                error: state.catalog.format("web.login_throttled", &[("seconds", &seconds)]),
+            })
+        ).into_response();
+    }
+
+    // 从数据库查找用户
+    let user = match state.store.load_user_by_username(&req.username).await {
+        Ok(user) => user,
         Err(e) => {
             error!("Database error during login: {}", e);
-            (
+            return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Database error".to_string(),
+                    error: state.catalog.get("web.database_error"),
                 })
-            ).into_response()
+            ).into_response();
+        }
+    };
+
+    // 验证密码，用户不存在时也执行一次哈希校验以保持响应时间一致
+    let user = match user {
+        Some(user) if PasswordService::verify_password(&user.password_hash, &req.password).unwrap_or(false) => user,
+        Some(_) => {
+            state.login_throttle.record_failure(&throttle_keys).await;
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.invalid_credentials"),
+                })
+            ).into_response();
+        }
+        None => {
+            PasswordService::verify_dummy(&req.password);
+            state.login_throttle.record_failure(&throttle_keys).await;
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.invalid_credentials"),
+                })
+            ).into_response();
+        }
+    };
+
+    state.login_throttle.record_success(&throttle_keys[0]).await;
+    if let Some(bus) = &state.message_bus {
+        bus.register_user(&user.principal_id());
+    }
+
+    // 生成JWT令牌
+    let user_info = UserInfo {
+        id: user.id.clone(),
+        username: user.username.clone(),
+        name: user.name.clone(),
+        email: user.email.clone(),
+        is_director: matches!(user.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management),
+        employee_id: user.employee_id.clone(),
+        position: format!("{:?}", user.position),
+        department: user.department.clone(),
+    };
+    let permissions_hash = permissions::resolve_permissions(&state, &user_info).await.digest();
+    let token: String = match state.jwt_service.generate_token_with_permissions(&user_info, &permissions_hash) {
+        Ok(token) => token,
+        Err(e) => {
+            error!("Failed to generate token: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.token_generation_failed"),
+                })
+            ).into_response();
+        }
+    };
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "token": token,
+            "user": {
+                "id": user.id,
+                "principal_id": user.principal_id(),
+                "username": user.username,
+                "name": user.name,
+                "email": user.email,
+                "is_director": matches!(user.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management),
+                "employee_id": user.employee_id,
+                "position": format!("{:?}", user.position),
+                "department": user.department,
+            }
+        }
+    })).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// 修改当前用户的密码
+async fn change_password(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<ChangePasswordRequest>,
+) -> impl IntoResponse {
+    let user_info = headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    let Some(user_info) = user_info else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        ).into_response();
+    };
+
+    let mut user = match state.store.load_user_by_username(&user_info.username).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.unauthorized"),
+                })
+            ).into_response();
+        }
+        Err(e) => {
+            error!("Database error during password change: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.database_error"),
+                })
+            ).into_response();
+        }
+    };
+
+    if !PasswordService::verify_password(&user.password_hash, &req.current_password).unwrap_or(false) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.current_password_incorrect"),
+            })
+        ).into_response();
+    }
+
+    if let Some(response) = password_policy_response(&state, &req.new_password) {
+        return response;
+    }
+
+    user.password_hash = match PasswordService::hash_password(&req.new_password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            error!("Failed to hash password: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.password_processing_failed"),
+                })
+            ).into_response();
         }
+    };
+
+    if let Err(e) = state.store.save_user(&user).await {
+        error!("Failed to save user after password change: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.password_change_failed"),
+            })
+        ).into_response();
     }
+
+    info!(target: "audit", "User {} changed password", user.username);
+    Json(serde_json::json!({
+        "success": true,
+        "message": "Password changed successfully"
+    })).into_response()
+}
+
+/// 董事长和管理层注册时加入的部门
+const GUILTY_CLIFF_DEPT_ID: &str = "guilty-cliff-line";
+
+/// 部门满员（409），带上部门和当前人数
+fn department_full_response(state: &AppState, full: &DepartmentFull) -> axum::response::Response {
+    (
+        StatusCode::CONFLICT,
+        Json(ErrorResponse {
+            error: department_full_message(state, full),
+        }),
+    )
+        .into_response()
+}
+
+fn department_full_message(state: &AppState, full: &DepartmentFull) -> String {
+    state.catalog.format(
+        "web.department_full",
+        &[
+            ("department", &full.department_id),
+            ("current", &full.current.to_string()),
+            ("max", &full.max_agents.to_string()),
+        ],
+    )
 }
 
 /// 注册
@@ -330,6 +1544,10 @@ async fn register(
 ) -> impl IntoResponse {
     info!("Register attempt: {}", req.username);
 
+    if let Some(response) = password_policy_response(&state, &req.password) {
+        return response;
+    }
+
     // 检查用户名是否已存在
     match state.store.load_user_by_username(&req.username).await {
         Ok(Some(_)) => {
@@ -337,7 +1555,7 @@ async fn register(
             return (
                 StatusCode::CONFLICT,
                 Json(ErrorResponse {
-                    error: "Username already exists".to_string(),
+                    error: state.catalog.get("web.username_exists"),
                 })
             ).into_response();
         },
@@ -349,7 +1567,7 @@ async fn register(
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Database error".to_string(),
+                    error: state.catalog.get("web.database_error"),
                 })
             ).into_response();
         }
@@ -363,7 +1581,7 @@ async fn register(
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Database error".to_string(),
+                    error: state.catalog.get("web.database_error"),
                 })
             ).into_response();
         }
@@ -375,7 +1593,7 @@ async fn register(
             return (
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: "First user registration does not require an invitation code".to_string(),
+                    error: state.catalog.get("web.first_user_no_invite"),
                 })
             ).into_response();
         }
@@ -388,7 +1606,7 @@ async fn register(
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(ErrorResponse {
-                        error: "Failed to process password".to_string(),
+                        error: state.catalog.get("web.password_processing_failed"),
                     })
                 ).into_response();
             }
@@ -407,7 +1625,7 @@ async fn register(
             return (
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: "Invitation code is required for registration".to_string(),
+                    error: state.catalog.get("web.invite_code_required"),
                 })
             ).into_response();
         }
@@ -421,7 +1639,7 @@ async fn register(
                 return (
                     StatusCode::BAD_REQUEST,
                     Json(ErrorResponse {
-                        error: "Invalid invitation code".to_string(),
+                        error: state.catalog.get("web.invite_code_invalid"),
                     })
                 ).into_response();
             },
@@ -430,7 +1648,7 @@ async fn register(
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(ErrorResponse {
-                        error: "Database error".to_string(),
+                        error: state.catalog.get("web.database_error"),
                     })
                 ).into_response();
             }
@@ -441,7 +1659,7 @@ async fn register(
             return (
                 StatusCode::BAD_REQUEST,
                 Json(ErrorResponse {
-                    error: "Invitation code has expired or reached maximum usage".to_string(),
+                    error: state.catalog.get("web.invite_code_expired"),
                 })
             ).into_response();
         }
@@ -454,7 +1672,7 @@ async fn register(
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(ErrorResponse {
-                        error: "Failed to process password".to_string(),
+                        error: state.catalog.get("web.password_processing_failed"),
                     })
                 ).into_response();
             }
@@ -469,7 +1687,7 @@ async fn register(
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Failed to update invitation code".to_string(),
+                    error: state.catalog.get("web.invite_code_update_failed"),
                 })
             ).into_response();
         }
@@ -482,7 +1700,7 @@ async fn register(
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(ErrorResponse {
-                        error: "Database error".to_string(),
+                        error: state.catalog.get("web.database_error"),
                     })
                 ).into_response();
             }
@@ -498,13 +1716,29 @@ async fn register(
         )
     };
 
+    // 董事长和管理层会加入思过崖线部门，先确认部门未满员
+    if matches!(user_to_create.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management) {
+        if let Ok(Some(max_agents)) = state.store.load_department(GUILTY_CLIFF_DEPT_ID).await.map(|d| d.and_then(|d| d.max_agents)) {
+            if let Ok(members) = state.store.load_department_members(GUILTY_CLIFF_DEPT_ID).await {
+                if !members.iter().any(|a| a.id == user_to_create.id) && members.len() >= max_agents {
+                    let full = DepartmentFull {
+                        department_id: GUILTY_CLIFF_DEPT_ID.to_string(),
+                        current: members.len(),
+                        max_agents,
+                    };
+                    return department_full_response(&state, &full);
+                }
+            }
+        }
+    }
+
     // 保存用户到数据库
     if let Err(e) = state.store.save_user(&user_to_create).await {
         error!("Failed to save user: {}", e);
         return (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
-                error: "Failed to register user".to_string(),
+                error: state.catalog.get("web.register_failed"),
             })
         ).into_response();
     }
@@ -512,10 +1746,11 @@ async fn register(
     // If it's corporate chairman or management, add user to Cliff of Contemplation Line
     if matches!(user_to_create.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management) {
         // Create or update organization structure, add user to Cliff of Contemplation Line department
+        let _org_guard = state.org_lock.lock().await;
         let mut org = state.store.load_organization().await.unwrap_or_else(|_| Organization::new());
 
         // Ensure Cliff of Contemplation Line department exists
-        let guilty_cliff_dept_id = "guilty-cliff-line";
+        let guilty_cliff_dept_id = GUILTY_CLIFF_DEPT_ID;
         let guilty_cliff_dept_name = "Cliff of Contemplation Line";
 
         // Check if department already exists
@@ -531,58 +1766,61 @@ async fn register(
                 } else {
                     None
                 },
+                max_agents: None,
+                llm_budget: None,
             };
             org.add_department(dept);
         }
 
-        // 检查是否已有对应的Agent，如果没有则创建
-        let agent_exists = org.agents.iter().any(|a| a.id == user_to_create.id);
+        let is_chairman = matches!(user_to_create.position, crate::domain::user::Position::Chairman);
+        let role = if is_chairman {
+            Role::simple("Cliff of Contemplation Line Supervisor".to_string(), "You are the supervisor of the Cliff of Contemplation Line, responsible for overseeing and managing senior company affairs.".to_string())
+                .with_responsibilities(vec!["Corporate Chairman".to_string(), "Cliff of Contemplation Line Management".to_string()])
+                .with_expertise(vec!["Corporate Governance".to_string(), "Strategic Planning".to_string()])
+        } else {
+            Role::simple("Cliff of Contemplation Line Member".to_string(), "You are a member of the Cliff of Contemplation Line, participating in senior management and decision-making processes.".to_string())
+                .with_responsibilities(vec!["Management Affairs".to_string(), "Collaborative Work".to_string()])
+                .with_expertise(vec!["Team Management".to_string(), "Cross-department Collaboration".to_string()])
+        };
 
-        if !agent_exists {
-            // If Agent doesn't exist, create a corresponding Agent and add to Cliff of Contemplation Line
-            let new_agent = Agent {
+        // 已有同 ID 的 Agent 时原地更新角色和部门，否则创建新的 Agent
+        let agent = match org.find_agent(&user_to_create.id).cloned() {
+            Some(mut existing) => {
+                existing.role = role;
+                existing.department_id = Some(guilty_cliff_dept_id.to_string());
+                existing
+            }
+            None => Agent {
                 id: user_to_create.id.clone(),
                 name: user_to_create.name.clone(),
-                role: if matches!(user_to_create.position, crate::domain::user::Position::Chairman) {
-                    Role::simple("Cliff of Contemplation Line Supervisor".to_string(), "You are the supervisor of the Cliff of Contemplation Line, responsible for overseeing and managing senior company affairs.".to_string())
-                        .with_responsibilities(vec!["Corporate Chairman".to_string(), "Cliff of Contemplation Line Management".to_string()])
-                        .with_expertise(vec!["Corporate Governance".to_string(), "Strategic Planning".to_string()])
-                } else {
-                    Role::simple("Cliff of Contemplation Line Member".to_string(), "You are a member of the Cliff of Contemplation Line, participating in senior management and decision-making processes.".to_string())
-                        .with_responsibilities(vec!["Management Affairs".to_string(), "Collaborative Work".to_string()])
-                        .with_expertise(vec!["Team Management".to_string(), "Cross-department Collaboration".to_string()])
-                },
+                role,
                 department_id: Some(guilty_cliff_dept_id.to_string()),
                 llm_config: LLMConfig::openai("fake-api-key".to_string()),
                 mode: AgentMode::Passive,
-            };
-            org.agents.push(new_agent);
-        } else {
-            // If Agent already exists, update its department information
-            if let Some(agent) = org.agents.iter_mut().find(|a| a.id == user_to_create.id) {
-                agent.department_id = Some(guilty_cliff_dept_id.to_string());
-                if matches!(user_to_create.position, crate::domain::user::Position::Chairman) {
+                skills: Vec::new(),
+                availability: None,
+                created_at: Some(state.clock.now()),
+            },
+        };
+        match org.check_headcount(guilty_cliff_dept_id, &agent.id) {
+            Ok(()) => {
+                if org.upsert_agent(agent).is_some() {
+                    info!("Updated existing agent {} for guilty cliff line", user_to_create.id);
+                }
+                if is_chairman {
                     // Corporate chairman becomes Cliff of Contemplation Line supervisor
-                    agent.role = Role::simple("Cliff of Contemplation Line Supervisor".to_string(), "You are the supervisor of the Cliff of Contemplation Line, responsible for overseeing and managing senior company affairs.".to_string())
-                        .with_responsibilities(vec!["Corporate Chairman".to_string(), "Cliff of Contemplation Line Management".to_string()])
-                        .with_expertise(vec!["Corporate Governance".to_string(), "Strategic Planning".to_string()]);
-
-                    // Also update department leader
                     if let Some(dept) = org.departments.iter_mut().find(|d| d.id == guilty_cliff_dept_id) {
                         dept.leader_id = Some(user_to_create.id.clone());
                     }
-                } else {
-                    // Management member
-                    agent.role = Role::simple("Cliff of Contemplation Line Member".to_string(), "You are a member of the Cliff of Contemplation Line, participating in senior management and decision-making processes.".to_string())
-                        .with_responsibilities(vec!["Management Affairs".to_string(), "Collaborative Work".to_string()])
-                        .with_expertise(vec!["Team Management".to_string(), "Cross-department Collaboration".to_string()]);
                 }
             }
+            Err(e) => error!("Failed to add agent for {}: {}", user_to_create.id, e),
         }
 
         // 保存更新后的组织架构
-        if let Err(e) = state.store.save_organization(&org).await {
-            error!("Failed to update organization for guilty cliff line: {}", e);
+        match save_organization_tracked(state.store.as_ref(), &org, &user_to_create.username).await {
+            Ok(entry) => announce_org_change(&state, entry),
+            Err(e) => error!("Failed to update organization for guilty cliff line: {}", e),
         }
 
         // Also update user's department information
@@ -606,9 +1844,10 @@ async fn register(
     } else {
         user_to_create.clone()
     };
+    announce_user_change(&state, &final_user.id);
 
     // 生成JWT令牌
-    let token: String = match state.jwt_service.generate_token(&UserInfo {
+    let user_info = UserInfo {
         id: final_user.id.clone(),
         username: final_user.username.clone(),
         name: final_user.name.clone(),
@@ -617,14 +1856,16 @@ async fn register(
         employee_id: final_user.employee_id.clone(),
         position: format!("{:?}", final_user.position),
         department: final_user.department.clone(),
-    }) {
+    };
+    let permissions_hash = permissions::resolve_permissions(&state, &user_info).await.digest();
+    let token: String = match state.jwt_service.generate_token_with_permissions(&user_info, &permissions_hash) {
         Ok(token) => token,
         Err(e) => {
             error!("Failed to generate token: {}", e);
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Failed to generate token".to_string(),
+                    error: state.catalog.get("web.token_generation_failed"),
                 })
             ).into_response();
         }
@@ -662,19 +1903,92 @@ async fn check_username(Query(params): Query<std::collections::HashMap<String, S
     }))
 }
 
+#[derive(Deserialize)]
+struct WebSocketQuery {
+    /// 登录令牌，带上后连接桥接到该用户的信箱
+    token: Option<String>,
+}
+
 /// WebSocket 处理
 async fn websocket_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    Query(query): Query<WebSocketQuery>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_websocket(socket, state))
+    let user_info = query
+        .token
+        .and_then(|token| state.jwt_service.validate_token(&token).ok());
+    let principal = user_info.as_ref().map(|user_info| user_principal(&user_info.id));
+    let language = match &user_info {
+        Some(user_info) => preferred_language(&state, user_info).await,
+        None => None,
+    };
+    let permissions = match &user_info {
+        Some(user_info) => Some(permissions::resolve_permissions(&state, user_info).await),
+        None => None,
+    };
+    ws.on_upgrade(move |socket| handle_websocket(socket, state, principal, language, permissions))
+}
+
+/// 接收已订阅的广播（用户信箱、工具进度），未订阅时永远挂起
+async fn recv_subscribed<T: Clone>(rx: &mut Option<broadcast::Receiver<T>>) -> Option<T> {
+    match rx {
+        Some(rx) => loop {
+            match rx.recv().await {
+                Ok(message) => return Some(message),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        },
+        None => std::future::pending().await,
+    }
+}
+
+/// 消息推送帧，消息语言与读者不同时附上译文（读者自己发的消息不翻译）
+async fn message_frame(state: &AppState, message: &Message, principal: Option<&str>, language: Option<&str>) -> ServerFrame {
+    let mut frame = MessageFrame::from(message);
+    if let (Some(translator), Some(language)) = (&state.translator, language) {
+        if principal != Some(message.from.as_str()) {
+            frame.translation = translator.translate_for(message, language).await;
+        }
+    }
+    ServerFrame::new(ServerEvent::Message { data: frame })
+}
+
+/// 按连接协商的编码发送服务端帧，所有推送都经过这里
+async fn send_frame(
+    socket: &mut axum::extract::ws::WebSocket,
+    codec: &FrameCodec,
+    frame: impl Into<ServerFrame>,
+) -> Result<(), axum::Error> {
+    let message = match codec.encode(&frame.into()) {
+        EncodedFrame::Text(text) => axum::extract::ws::Message::Text(text.into()),
+        EncodedFrame::Binary(bytes) => axum::extract::ws::Message::Binary(bytes.into()),
+    };
+    socket.send(message).await
 }
 
 async fn handle_websocket(
     mut socket: axum::extract::ws::WebSocket,
     state: Arc<AppState>,
+    principal: Option<String>,
+    language: Option<String>,
+    permissions: Option<PermissionSet>,
 ) {
     let mut rx = state.message_tx.subscribe();
+    let mut reactions_rx = state.reactions.subscribe();
+    let mut user_rx = match (&principal, &state.message_bus) {
+        (Some(principal), Some(bus)) => Some(bus.subscribe_user(principal)),
+        _ => None,
+    };
+    // 客户端发送 `subscribe_tool_progress` 后才转发工具进度
+    let mut progress_rx: Option<broadcast::Receiver<ToolProgressEvent>> = None;
+    let mut progress_agent: Option<String> = None;
+    // 有 `inspect_agents` 权限的客户端发送 `subscribe_scratchpad` 后才转发运行草稿
+    let mut scratchpad_rx: Option<broadcast::Receiver<ScratchpadEntry>> = None;
+    let mut scratchpad_agent: Option<String> = None;
+    // 客户端发送 `hello` 协商编码前使用 JSON 文本帧
+    let mut codec = FrameCodec::default();
 
     info!("WebSocket connection established");
 
@@ -682,25 +1996,56 @@ async fn handle_websocket(
         tokio::select! {
             // 接收消息
             Ok(message) = rx.recv() => {
-                let to_str = match &message.to {
-                    MessageTarget::Direct(id) => Some(id.clone()),
-                    MessageTarget::Group(id) => Some(format!("group:{}", id)),
-                };
+                let frame = message_frame(&state, &message, principal.as_deref(), language.as_deref()).await;
+                if let Err(e) = send_frame(&mut socket, &codec, frame).await {
+                    error!("WebSocket send error: {}", e);
+                    break;
+                }
+            }
 
-                let msg_json = serde_json::json!({
-                    "type": "message",
-                    "data": {
-                        "id": message.id,
-                        "from": message.from,
-                        "to": to_str,
-                        "content": message.content,
-                        "timestamp": message.timestamp,
-                    }
-                });
+            // 推送发给当前用户的私聊/群聊消息
+            Some(message) = recv_subscribed(&mut user_rx) => {
+                let frame = message_frame(&state, &message, principal.as_deref(), language.as_deref()).await;
+                if let Err(e) = send_frame(&mut socket, &codec, frame).await {
+                    error!("WebSocket send error: {}", e);
+                    break;
+                }
+            }
+
+            // 推送订阅的工具进度
+            Some(progress) = recv_subscribed(&mut progress_rx) => {
+                if progress_agent.as_ref().is_some_and(|agent_id| *agent_id != progress.caller_id) {
+                    continue;
+                }
+                if let Err(e) = send_frame(&mut socket, &codec, ServerFrame::new(ServerEvent::ToolProgress { data: progress })).await {
+                    error!("WebSocket send error: {}", e);
+                    break;
+                }
+            }
+
+            // 推送订阅的运行草稿，只推送有权查看的 Agent 的
+            Some(entry) = recv_subscribed(&mut scratchpad_rx) => {
+                if scratchpad_agent.as_ref().is_some_and(|agent_id| *agent_id != entry.agent_id) {
+                    continue;
+                }
+                let department = agent_department(&state, &entry.agent_id).await;
+                if !permissions.as_ref().is_some_and(|p| p.allows(Permission::InspectAgents, department.as_deref())) {
+                    continue;
+                }
+                if let Err(e) = send_frame(&mut socket, &codec, ServerFrame::new(ServerEvent::Scratchpad { data: entry })).await {
+                    error!("WebSocket send error: {}", e);
+                    break;
+                }
+            }
+
+            // 推送回应变化
+            Ok(event) = reactions_rx.recv() => {
+                let event = match event {
+                    ReactionEvent::Added(reaction) => ServerEvent::ReactionAdded { data: reaction },
+                    ReactionEvent::Removed(reaction) => ServerEvent::ReactionRemoved { data: reaction },
+                };
 
-                if let Err(e) = socket.send(axum::extract::ws::Message::Text(
-                    msg_json.to_string().into()
-                )).await {
+                if let Err(e) = send_frame(&mut socket, &codec, ServerFrame::new(event)).await {
                     error!("WebSocket send error: {}", e);
                     break;
                 }
@@ -723,18 +2068,15 @@ async fn handle_websocket(
                         // 解析客户端发送的消息
                         if let Ok(client_message) = serde_json::from_str::<ClientMessage>(&text) {
                             match client_message {
-                                ClientMessage::SendMessage { from, to, content } => {
+                                ClientMessage::SendMessage { from, to, content, priority } => {
                                     // 验证消息参数
                                     if from.is_empty() || to.is_empty() || content.is_empty() {
                                         // 发送错误响应
-                                        let error_msg = serde_json::json!({
-                                            "type": "error",
-                                            "message": "Invalid message format: missing required fields"
+                                        let error_msg = ServerFrame::new(ServerEvent::Error {
+                                            message: "Invalid message format: missing required fields".to_string(),
                                         });
 
-                                        if let Err(e) = socket.send(axum::extract::ws::Message::Text(
-                                            error_msg.to_string().into()
-                                        )).await {
+                                        if let Err(e) = send_frame(&mut socket, &codec, error_msg).await {
                                             error!("WebSocket send error: {}", e);
                                             break;
                                         }
@@ -757,6 +2099,39 @@ async fn handle_websocket(
                                         timestamp: Utc::now().timestamp(),
                                         reply_to: None,
                                         mentions: Vec::new(),
+                                        metadata: Default::default(),
+                                        priority,
+                                    }
+                                    .with_trace(&new_trace_id(), None);
+                                    let message = match enforce_message_limits(
+                                        &state.message_limits,
+                                        message,
+                                        state.blobs.as_deref(),
+                                        &state.catalog,
+                                    ).await {
+                                        Ok(message) => message,
+                                        Err(error) => {
+                                            let error_msg = ServerFrame::new(ServerEvent::Error {
+                                                message: error.describe(&state.catalog),
+                                            });
+                                            if let Err(e) = send_frame(&mut socket, &codec, error_msg).await {
+                                                error!("WebSocket send error: {}", e);
+                                                break;
+                                            }
+                                            continue;
+                                        }
+                                    };
+
+                                    // 已登录用户以 `user:{id}` 身份经消息总线投递给 Agent
+                                    let message = match (&principal, &state.message_bus) {
+                                        (Some(principal), Some(bus)) => {
+                                            let message = Message { from: principal.clone(), ..message };
+                                            if let Err(e) = bus.send(message.clone()).await {
+                                                error!("Failed to route message from {}: {}", principal, e);
+                                            }
+                                            message
+                                        }
+                                        _ => message,
                                     };
 
                                     // 发送消息到消息总线
@@ -764,15 +2139,55 @@ async fn handle_websocket(
                                         error!("Failed to send message: {}", e);
                                     }
                                 }
+                                ClientMessage::SubscribeToolProgress { agent_id } => {
+                                    progress_rx = Some(state.tool_progress.subscribe());
+                                    progress_agent = agent_id;
+                                }
+                                ClientMessage::SubscribeScratchpad { agent_id } => {
+                                    let error = match &state.scratchpad {
+                                        None => Some(state.catalog.get("web.scratchpad_unavailable")),
+                                        Some(_) if !permissions.as_ref().is_some_and(|p| p.has(Permission::InspectAgents)) => {
+                                            Some(state.catalog.get("web.insufficient_permissions"))
+                                        }
+                                        Some(scratchpad) => {
+                                            scratchpad_rx = Some(scratchpad.subscribe());
+                                            scratchpad_agent = agent_id;
+                                            None
+                                        }
+                                    };
+                                    if let Some(message) = error {
+                                        let error_msg = ServerFrame::new(ServerEvent::Error { message });
+                                        if let Err(e) = send_frame(&mut socket, &codec, error_msg).await {
+                                            error!("WebSocket send error: {}", e);
+                                            break;
+                                        }
+                                    }
+                                }
+                                ClientMessage::Hello { encoding, min_compress_bytes } => {
+                                    let frame = if encoding.is_supported() {
+                                        ServerEvent::Welcome {
+                                            encoding,
+                                            min_compress_bytes: min_compress_bytes.unwrap_or(DEFAULT_MIN_COMPRESS_BYTES),
+                                        }
+                                    } else {
+                                        ServerEvent::Error {
+                                            message: state.catalog.get("web.ws_encoding_unsupported"),
+                                        }
+                                    };
+                                    // 确认帧仍按旧编码发送，之后的帧才切换
+                                    if let Err(e) = send_frame(&mut socket, &codec, frame.clone()).await {
+                                        error!("WebSocket send error: {}", e);
+                                        break;
+                                    }
+                                    if let ServerEvent::Welcome { encoding, min_compress_bytes } = frame {
+                                        codec = FrameCodec::new(encoding).with_min_compress_bytes(min_compress_bytes);
+                                    }
+                                }
                                 ClientMessage::Ping => {
                                     // 回复pong消息
-                                    let pong_msg = serde_json::json!({
-                                        "type": "pong"
-                                    });
+                                    let pong_msg = ServerFrame::new(ServerEvent::Pong);
 
-                                    if let Err(e) = socket.send(axum::extract::ws::Message::Text(
-                                        pong_msg.to_string().into()
-                                    )).await {
+                                    if let Err(e) = send_frame(&mut socket, &codec, pong_msg).await {
                                         error!("WebSocket send error: {}", e);
                                         break;
                                     }
@@ -780,14 +2195,11 @@ async fn handle_websocket(
                             }
                         } else {
                             // 解析JSON失败，发送错误响应
-                            let error_msg = serde_json::json!({
-                                "type": "error",
-                                "message": "Invalid JSON format"
+                            let error_msg = ServerFrame::new(ServerEvent::Error {
+                                message: "Invalid JSON format".to_string(),
                             });
 
-                            if let Err(e) = socket.send(axum::extract::ws::Message::Text(
-                                error_msg.to_string().into()
-                            )).await {
+                            if let Err(e) = send_frame(&mut socket, &codec, error_msg).await {
                                 error!("WebSocket send error: {}", e);
                                 break;
                             }
@@ -811,232 +2223,241 @@ pub struct CreateInviteCodeRequest {
     pub expires_at: Option<String>,  // ISO 8601 format
 }
 
-/// 检查用户是否具有管理员权限
-async fn check_admin_permission(state: &AppState, token: &str) -> Option<UserInfo> {
-    match state.jwt_service.validate_token(token) {
-        Ok(user_info) => {
-            // 检查是否为董事长或管理层（这里暂时认为所有管理层及以上都是管理员）
-            if matches!(user_info.position.as_str(), "Chairman" | "Management") {
-                Some(user_info)
-            } else {
-                None
-            }
+/// 组织架构有变更时经消息总线的事件总线发出 `OrgChanged`
+fn announce_org_change(state: &AppState, entry: Option<OrgChangeEntry>) {
+    let Some(entry) = entry else {
+        return;
+    };
+    state.org_tree.invalidate();
+    state.mentions.invalidate();
+    let Some(events) = state.message_bus.as_ref().and_then(|bus| bus.events()) else {
+        return;
+    };
+    events.emit(CompanyEvent::OrgChanged { entry: Arc::new(entry) });
+}
+
+/// 用户注册或资料变更后使提及索引失效，并经消息总线的事件总线发出 `UserChanged`
+fn announce_user_change(state: &AppState, user_id: &str) {
+    state.mentions.invalidate();
+    let Some(events) = state.message_bus.as_ref().and_then(|bus| bus.events()) else {
+        return;
+    };
+    events.emit(CompanyEvent::UserChanged { user_id: Arc::from(user_id) });
+}
+
+/// Agent 当前所属的部门，用于检查部门范围的授权；找不到 Agent 时为 `None`
+async fn agent_department(state: &AppState, agent_id: &str) -> Option<String> {
+    match state.store.load_agent(agent_id).await {
+        Ok(agent) => agent.and_then(|agent| agent.department_id),
+        Err(e) => {
+            warn!("Failed to load agent {} for its department: {}", agent_id, e);
+            None
         }
-        Err(_) => None,
     }
 }
 
-/// 获取所有邀请码（仅管理员）
+/// 获取所有邀请码（需要 `manage_invite_codes`）
 async fn get_invite_codes(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    _auth: RequirePermission<perm::ManageInviteCodes>,
 ) -> impl IntoResponse {
-    let auth_header = headers.get("authorization")
-        .and_then(|value| value.to_str().ok());
-
-    if let Some(auth_str) = auth_header {
-        if auth_str.starts_with("Bearer ") {
-            let token = &auth_str[7..];
-
-            if check_admin_permission(&state, token).await.is_some() {
-                match state.store.load_invitation_codes().await {
-                    Ok(codes) => {
-                        // 转换为前端友好的格式
-                        let codes_data: Vec<serde_json::Value> = codes.into_iter().map(|code| {
-                            serde_json::json!({
-                                "id": code.id,
-                                "code": code.code,
-                                "created_by": code.created_by,
-                                "created_at": code.created_at,
-                                "expires_at": code.expiry_time,
-                                "usage_count": code.current_usage,
-                                "max_usage": code.max_usage,
-                                "is_active": code.is_valid(),
-                            })
-                        }).collect();
+    match state.store.load_invitation_codes().await {
+        Ok(codes) => {
+            // 转换为前端友好的格式
+            let codes_data: Vec<serde_json::Value> = codes.into_iter().map(|code| {
+                serde_json::json!({
+                    "id": code.id,
+                    "code": code.code,
+                    "created_by": code.created_by,
+                    "created_at": code.created_at,
+                    "expires_at": code.expiry_time,
+                    "usage_count": code.current_usage,
+                    "max_usage": code.max_usage,
+                    "is_active": code.is_valid(),
+                })
+            }).collect();
 
-                        return Json(serde_json::json!({
-                            "success": true,
-                            "data": codes_data
-                        })).into_response();
-                    }
-                    Err(e) => {
-                        error!("Failed to load invitation codes: {}", e);
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(ErrorResponse {
-                                error: "Failed to load invitation codes".to_string(),
-                            })
-                        ).into_response();
-                    }
-                }
-            }
+            Json(serde_json::json!({
+                "success": true,
+                "data": codes_data
+            })).into_response()
+        }
+        Err(e) => {
+            error!("Failed to load invitation codes: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.invite_codes_load_failed"),
+                })
+            ).into_response()
         }
     }
-
-    (
-        StatusCode::FORBIDDEN,
-        Json(ErrorResponse {
-            error: "Insufficient permissions".to_string(),
-        })
-    ).into_response()
 }
 
-/// 创建邀请码（仅管理员）
+/// 创建邀请码（需要 `manage_invite_codes`）
 async fn create_invite_code(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    RequirePermission { user: user_info, .. }: RequirePermission<perm::ManageInviteCodes>,
     Json(req): Json<CreateInviteCodeRequest>,
 ) -> impl IntoResponse {
-    let auth_header = headers.get("authorization")
-        .and_then(|value| value.to_str().ok());
-
-    if let Some(auth_str) = auth_header {
-        if auth_str.starts_with("Bearer ") {
-            let token = &auth_str[7..];
-
-            if let Some(user_info) = check_admin_permission(&state, token).await {
-                // 创建邀请码
-                let mut new_code = InvitationCode::new(
-                    user_info.id.clone(),
-                    req.max_usage,
-                );
-
-                // 如果指定了过期时间，使用指定的时间，否则使用默认的1天
-                if let Some(expires_at_str) = req.expires_at {
-                    if let Ok(expires_at) = chrono::DateTime::parse_from_rfc3339(&expires_at_str) {
-                        new_code.expiry_time = expires_at.timestamp();
-                    }
-                }
+    // 创建邀请码
+    let mut new_code = InvitationCode::new(
+        user_info.id.clone(),
+        req.max_usage,
+    );
+
+    // 如果指定了过期时间，使用指定的时间，否则使用默认的1天
+    if let Some(expires_at_str) = req.expires_at {
+        if let Ok(expires_at) = chrono::DateTime::parse_from_rfc3339(&expires_at_str) {
+            new_code.expiry_time = expires_at.timestamp();
+        }
+    }
 
-                match state.store.save_invitation_code(&new_code).await {
-                    Ok(_) => {
-                        return Json(serde_json::json!({
-                            "success": true,
-                            "data": {
-                                "id": new_code.id,
-                                "code": new_code.code,
-                                "created_by": new_code.created_by,
-                                "created_at": new_code.created_at,
-                                "expires_at": new_code.expiry_time,
-                                "max_usage": new_code.max_usage,
-                                "usage_count": new_code.current_usage,
-                                "is_active": new_code.is_valid(),
-                            }
-                        })).into_response();
-                    }
-                    Err(e) => {
-                        error!("Failed to save invitation code: {}", e);
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(ErrorResponse {
-                                error: "Failed to save invitation code".to_string(),
-                            })
-                        ).into_response();
-                    }
+    match state.store.save_invitation_code(&new_code).await {
+        Ok(_) => {
+            Json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "id": new_code.id,
+                    "code": new_code.code,
+                    "created_by": new_code.created_by,
+                    "created_at": new_code.created_at,
+                    "expires_at": new_code.expiry_time,
+                    "max_usage": new_code.max_usage,
+                    "usage_count": new_code.current_usage,
+                    "is_active": new_code.is_valid(),
                 }
-            }
+            })).into_response()
+        }
+        Err(e) => {
+            error!("Failed to save invitation code: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.invite_code_save_failed"),
+                })
+            ).into_response()
         }
     }
-
-    (
-        StatusCode::FORBIDDEN,
-        Json(ErrorResponse {
-            error: "Insufficient permissions".to_string(),
-        })
-    ).into_response()
 }
 
-/// 删除邀请码（仅管理员）
+/// 删除邀请码（需要 `manage_invite_codes`）
 async fn delete_invite_code(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    _auth: RequirePermission<perm::ManageInviteCodes>,
     Path(code_id): Path<String>,
 ) -> impl IntoResponse {
-    let auth_header = headers.get("authorization")
-        .and_then(|value| value.to_str().ok());
-
-    if let Some(auth_str) = auth_header {
-        if auth_str.starts_with("Bearer ") {
-            let token = &auth_str[7..];
-
-            if check_admin_permission(&state, token).await.is_some() {
-                // 由于我们的存储接口没有直接的删除方法，我们需要先加载所有邀请码，找到要删除的，然后不保存它
-                match state.store.load_invitation_codes().await {
-                    Ok(mut codes) => {
-                        // 查找要删除的邀请码
-                        if let Some(pos) = codes.iter().position(|c| c.id == code_id) {
-                            // 从数据库中移除（通过重新保存其他码）
-                            codes.remove(pos);
-
-                            // 重新保存剩余的邀请码
-                            for code in codes {
-                                if let Err(e) = state.store.save_invitation_code(&code).await {
-                                    error!("Failed to update invitation codes after deletion: {}", e);
-                                    return (
-                                        StatusCode::INTERNAL_SERVER_ERROR,
-                                        Json(ErrorResponse {
-                                            error: "Failed to update invitation codes".to_string(),
-                                        })
-                                    ).into_response();
-                                }
-                            }
-
-                            return Json(serde_json::json!({
-                                "success": true,
-                                "message": "Invitation code deleted successfully"
-                            })).into_response();
-                        } else {
-                            return (
-                                StatusCode::NOT_FOUND,
-                                Json(ErrorResponse {
-                                    error: "Invitation code not found".to_string(),
-                                })
-                            ).into_response();
-                        }
-                    }
-                    Err(e) => {
-                        error!("Failed to load invitation codes: {}", e);
+    // 由于我们的存储接口没有直接的删除方法，我们需要先加载所有邀请码，找到要删除的，然后不保存它
+    match state.store.load_invitation_codes().await {
+        Ok(mut codes) => {
+            // 查找要删除的邀请码
+            if let Some(pos) = codes.iter().position(|c| c.id == code_id) {
+                // 从数据库中移除（通过重新保存其他码）
+                codes.remove(pos);
+
+                // 重新保存剩余的邀请码
+                for code in codes {
+                    if let Err(e) = state.store.save_invitation_code(&code).await {
+                        error!("Failed to update invitation codes after deletion: {}", e);
                         return (
                             StatusCode::INTERNAL_SERVER_ERROR,
                             Json(ErrorResponse {
-                                error: "Failed to load invitation codes".to_string(),
+                                error: state.catalog.get("web.invite_codes_update_failed"),
                             })
                         ).into_response();
                     }
                 }
+
+                Json(serde_json::json!({
+                    "success": true,
+                    "message": "Invitation code deleted successfully"
+                })).into_response()
+            } else {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse {
+                        error: state.catalog.get("web.invite_code_not_found"),
+                    })
+                ).into_response()
             }
         }
+        Err(e) => {
+            error!("Failed to load invitation codes: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.invite_codes_load_failed"),
+                })
+            ).into_response()
+        }
     }
+}
 
-    (
-        StatusCode::FORBIDDEN,
-        Json(ErrorResponse {
-            error: "Insufficient permissions".to_string(),
-        })
-    ).into_response()
+/// Agent 在线状态：熔断时为 faulted，工作时间外为 away，等待轮次许可时为 queued，否则视为在线
+fn agent_presence(state: &AppState, agent: &Agent) -> &'static str {
+    if state.circuit_breakers.as_ref().is_some_and(|b| b.is_faulted(&agent.id)) {
+        return "faulted";
+    }
+    if !agent.is_available_at(state.clock.now()) {
+        return "away";
+    }
+    match state.scheduler.as_ref().map(|s| s.state_of(&agent.id)) {
+        Some(TurnState::Queued) => "queued",
+        _ => "online",
+    }
 }
 
-/// 获取聊天会话列表
-async fn list_chat_sessions(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+/// 会话列表参数
+#[derive(Debug, Default, Deserialize)]
+pub struct ChatListQuery {
+    /// 同时列出当前会话已关闭的 Agent
+    #[serde(default)]
+    pub include_closed: bool,
+}
+
+/// 获取聊天会话列表；启用会话生命周期时附上各 Agent 的当前会话，已关闭的默认不列出
+async fn list_chat_sessions(State(state): State<Arc<AppState>>, Query(query): Query<ChatListQuery>) -> impl IntoResponse {
+    // 按创建时间先后覆盖，留下各 Agent 最近的会话
+    let mut current: HashMap<String, ChatSession> = HashMap::new();
+    if let Some(chat_sessions) = &state.chat_sessions {
+        match chat_sessions.list(true).await {
+            Ok(sessions) => current.extend(sessions.into_iter().map(|s| (s.agent_id.clone(), s))),
+            Err(e) => {
+                error!("Failed to load stored chat sessions: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: state.catalog.get("web.chat_sessions_load_failed"),
+                    })
+                ).into_response();
+            }
+        }
+    }
+
     // 从组织架构中获取Agent信息来构建会话列表
     match state.store.load_organization().await {
         Ok(org) => {
-            let sessions: Vec<serde_json::Value> = org.agents.into_iter().map(|agent| {
-                serde_json::json!({
+            let sessions: Vec<serde_json::Value> = org.agents.into_iter().filter_map(|agent| {
+                let session = current.remove(&agent.id);
+                if !query.include_closed && session.as_ref().is_some_and(ChatSession::is_closed) {
+                    return None;
+                }
+                let now = chrono::Utc::now().timestamp();
+                Some(serde_json::json!({
                     "id": agent.id,
                     "name": agent.name,
                     "participants": [{
                         "id": agent.id,
                         "name": agent.name,
                         "isAgent": true,
-                        "status": "online"  // 假设Agent始终在线
+                        "status": agent_presence(&state, &agent)
                     }],
                     "lastMessage": null,
                     "unreadCount": 0,
-                    "createdAt": chrono::Utc::now().timestamp(),
-                    "updatedAt": chrono::Utc::now().timestamp()
-                })
+                    "createdAt": session.as_ref().map_or(now, |s| s.created_at),
+                    "updatedAt": session.as_ref().map_or(now, |s| s.last_activity_at),
+                    "session": session
+                }))
             }).collect();
 
             Json(serde_json::json!({
@@ -1049,45 +2470,148 @@ async fn list_chat_sessions(State(state): State<Arc<AppState>>) -> impl IntoResp
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Failed to load chat sessions".to_string(),
+                    error: state.catalog.get("web.chat_sessions_load_failed"),
                 })
             ).into_response()
         }
     }
 }
 
-/// 获取特定会话的消息
+/// 会话消息默认每页条数
+const SESSION_MESSAGES_DEFAULT_LIMIT: usize = 50;
+/// 会话消息每页最多条数
+const SESSION_MESSAGES_MAX_LIMIT: usize = 200;
+
+/// 会话消息 `offset` 的上限，更早的消息通过 `next_cursor` 翻页
+const SESSION_MESSAGES_MAX_OFFSET: usize = 10_000;
+
+/// 下一页游标的响应头（v1 信封只保留 `data`，游标同时放在响应头中）
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+/// 是否还有更早消息的响应头（`true`/`false`），原因同 `NEXT_CURSOR_HEADER`
+pub const HAS_MORE_HEADER: &str = "x-has-more";
+
+/// 会话消息分页参数
+#[derive(Deserialize)]
+pub struct SessionMessagesQuery {
+    /// 上一页的 `next_cursor`（消息ID），返回该消息之前的更早消息
+    pub before: Option<String>,
+    /// 跳过最新的若干条（在 `before` 之后应用），不超过 `SESSION_MESSAGES_MAX_OFFSET`
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+impl SessionMessagesQuery {
+    /// `offset` 超过上限时返回 400
+    fn check_offset(&self, state: &AppState) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+        match self.offset {
+            Some(offset) if offset > SESSION_MESSAGES_MAX_OFFSET => Err((
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: state
+                        .catalog
+                        .format("web.offset_too_large", &[("max", &SESSION_MESSAGES_MAX_OFFSET.to_string())]),
+                }),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 把分页信息写入响应头，v1 信封丢弃 `data` 以外的字段后客户端仍能翻页
+fn insert_page_headers(response: &mut axum::response::Response, next_cursor: Option<&str>, has_more: bool) {
+    if let Some(cursor) = next_cursor.and_then(|c| HeaderValue::from_str(c).ok()) {
+        response.headers_mut().insert(NEXT_CURSOR_HEADER, cursor);
+    }
+    response
+        .headers_mut()
+        .insert(HAS_MORE_HEADER, HeaderValue::from_static(if has_more { "true" } else { "false" }));
+}
+
+/// 获取特定会话的消息：当前用户与该 Agent 双方向的私聊（需要登录），每页按时间升序，
+/// `next_cursor` 指向更早的一页，`has_more` 表示是否还有更早的消息
 async fn get_session_messages(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Path(session_id): Path<String>,
+    Query(query): Query<SessionMessagesQuery>,
 ) -> impl IntoResponse {
-    // 获取该Agent相关的所有消息
-    let filter = crate::core::store::MessageFilter {
-        from: Some(session_id.clone()),  // 消息来自该Agent
-        target_type: None,
-        to: None,
-        since: None,
-        limit: 50,  // 限制返回50条消息
+    let viewer = match task_actor(&state, &headers) {
+        Ok((_, principal)) => principal,
+        Err(rejection) => return rejection.into_response(),
     };
+    if let Err(error) = query.check_offset(&state) {
+        return error.into_response();
+    }
+    let limit = query.limit.unwrap_or(SESSION_MESSAGES_DEFAULT_LIMIT).clamp(1, SESSION_MESSAGES_MAX_LIMIT);
+    // 只取当前用户与该 Agent 之间的私聊，Agent 与其他人的私聊不属于这个会话；多取一条判断是否还有更早的消息
+    let mut filter = MessageFilter::new()
+        .between(viewer, session_id.clone())
+        .stable_order()
+        .limit(limit + 1);
+    if let Some(offset) = query.offset {
+        filter = filter.offset(offset);
+    }
 
-    match state.store.load_messages(filter).await {
-        Ok(messages) => {
-            // 转换消息格式以匹配前端期望
-            let formatted_messages: Vec<serde_json::Value> = messages.into_iter().map(|msg| {
-                // 获取发送者信息
-                let sender = if msg.from == session_id {
-                    // 如果是Agent发送的
-                    serde_json::json!({
-                        "id": msg.from,
-                        "name": get_agent_name_by_id(&state.agents, &msg.from),
-                        "isAgent": true
+    if let Some(before) = &query.before {
+        // 游标取消息的原始时间戳和ID，消息变化不会让它跨页
+        match state.store.load_message(before).await {
+            Ok(Some(message)) => filter = filter.before(MessageCursor::at(&message)),
+            Ok(None) => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse {
+                        error: state.catalog.format("web.message_not_found", &[("message_id", before)]),
                     })
-                } else {
-                    // 如果是用户发送的
-                    serde_json::json!({
-                        "id": msg.from,
-                        "name": "Unknown User",
-                        "isAgent": false
+                ).into_response();
+            }
+            Err(e) => {
+                error!("Failed to load cursor message {}: {}", before, e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: state.catalog.get("web.messages_load_failed"),
+                    })
+                ).into_response();
+            }
+        }
+    }
+
+    match state.store.load_messages(filter).await {
+        Ok(mut messages) => {
+            let has_more = messages.len() > limit;
+            let next_cursor = if has_more {
+                messages.truncate(limit);
+                messages.last().map(|m| m.id.clone())
+            } else {
+                None
+            };
+            messages.reverse();
+
+            let message_ids: Vec<String> = messages.iter().map(|m| m.id.clone()).collect();
+            let mut reactions = match state.store.load_reactions(&message_ids).await {
+                Ok(reactions) => ReactionCount::aggregate(&reactions),
+                Err(e) => {
+                    error!("Failed to load reactions for session {}: {}", session_id, e);
+                    HashMap::new()
+                }
+            };
+
+            // 转换消息格式以匹配前端期望
+            let formatted_messages: Vec<serde_json::Value> = messages.into_iter().map(|msg| {
+                // 获取发送者信息
+                let sender = if msg.from == session_id {
+                    // 如果是Agent发送的
+                    serde_json::json!({
+                        "id": msg.from,
+                        "name": get_agent_name_by_id(&state, &msg.from),
+                        "isAgent": true
+                    })
+                } else {
+                    // 如果是用户发送的
+                    serde_json::json!({
+                        "id": msg.from,
+                        "name": "Unknown User",
+                        "isAgent": false
                     })
                 };
 
@@ -1097,254 +2621,2268 @@ async fn get_session_messages(
                     "content": msg.content,
                     "timestamp": msg.timestamp,
                     "replyTo": msg.reply_to,
-                    "mentions": msg.mentions
+                    "mentions": msg.mentions,
+                    "reactions": reactions.remove(&msg.id).unwrap_or_default()
                 })
             }).collect();
 
-            Json(serde_json::json!({
+            let mut response = Json(serde_json::json!({
                 "success": true,
-                "data": formatted_messages
-            })).into_response()
+                "data": formatted_messages,
+                "next_cursor": next_cursor,
+                "has_more": has_more
+            })).into_response();
+            insert_page_headers(&mut response, next_cursor.as_deref(), has_more);
+            response
         },
         Err(e) => {
             error!("Failed to load messages for session {}: {}", session_id, e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Failed to load messages".to_string(),
+                    error: state.catalog.get("web.messages_load_failed"),
                 })
             ).into_response()
         }
     }
 }
 
-/// 根据ID获取Agent名称的辅助函数
-fn get_agent_name_by_id(agents: &[Agent], agent_id: &str) -> String {
-    agents.iter()
-        .find(|agent| agent.id == agent_id)
-        .map(|agent| agent.name.clone())
-        .unwrap_or_else(|| "Unknown Agent".to_string())
+/// 导出会话参数
+#[derive(Debug, Deserialize)]
+pub struct ExportTranscriptQuery {
+    /// markdown（默认）、html 或 json
+    pub format: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
 }
 
-/// 获取组织架构树
-async fn get_org_tree(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match state.store.load_organization().await {
-        Ok(org) => {
-            // First, convert flat department list to tree structure
-            let mut departments_map: std::collections::HashMap<String, serde_json::Value> = std::collections::HashMap::new();
-
-            // Step 1: Create basic structure for all departments
-            for dept in &org.departments {
-                let agent_leaders: Vec<serde_json::Value> = org.agents.iter()
-                    .filter(|agent| agent.department_id.as_ref() == Some(&dept.id) && agent.role.title.contains("主管"))
-                    .map(|agent| {
-                        serde_json::json!({
-                            "id": agent.id,
-                            "name": agent.name,
-                            "title": agent.role.title,
-                            "status": "online"
-                        })
-                    })
-                    .collect();
-
-                let dept_agents: Vec<serde_json::Value> = org.agents.iter()
-                    .filter(|agent| agent.department_id.as_ref() == Some(&dept.id) && !agent.role.title.contains("主管"))
-                    .map(|agent| {
-                        serde_json::json!({
-                            "id": agent.id,
-                            "name": agent.name,
-                            "title": agent.role.title,
-                            "status": "online"
-                        })
-                    })
-                    .collect();
-
-                let leader = if !agent_leaders.is_empty() {
-                    agent_leaders.first().cloned()
-                } else {
-                    None
-                };
-
-                let users = [&agent_leaders[..], &dept_agents[..]].concat();
+/// 导出会话记录；会话ID为群ID或 Agent ID，按页流式输出
+async fn export_session_transcript(
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+    Query(query): Query<ExportTranscriptQuery>,
+) -> impl IntoResponse {
+    let format_name = query.format.as_deref().unwrap_or("markdown");
+    let Some(format) = TranscriptFormat::parse(format_name) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.export_format_invalid", &[("format", format_name)]),
+            })
+        ).into_response();
+    };
 
-                departments_map.insert(
-                    dept.id.clone(),
-                    serde_json::json!({
-                        "id": dept.id,
-                        "name": dept.name,
-                        "parentId": dept.parent_id,
-                        "leader": leader,
-                        "users": users,
-                        "memberCount": users.len(),
-                        "children": Vec::<serde_json::Value>::new() // 初始化为空数组
+    let group = match state.store.load_groups().await {
+        Ok(groups) => groups.into_iter().find(|g| g.id == session_id),
+        Err(e) => {
+            error!("Failed to load groups for export of {}: {}", session_id, e);
+            None
+        }
+    };
+    let (session, title) = match group {
+        Some(group) => (TranscriptSession::Group(group.id), group.name),
+        None => match state.agents().into_iter().find(|a| a.id == session_id) {
+            Some(agent) => (TranscriptSession::Direct(agent.id), agent.name),
+            None => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(ErrorResponse {
+                        error: state.catalog.format("web.session_not_found", &[("session_id", &session_id)]),
                     })
-                );
+                ).into_response();
             }
+        },
+    };
 
-            // Step 2: Build parent-child relationships (add child departments to parent department's children array)
-            let mut processed_depts = std::collections::HashMap::new();
-            for dept in &org.departments {
-                if let Some(mut dept_info) = departments_map.get(&dept.id).cloned() {
-                    // Find all child departments with this department as parent
-                    let child_depts: Vec<serde_json::Value> = org.departments.iter()
-                        .filter(|child| child.parent_id.as_ref() == Some(&dept.id))
-                        .filter_map(|child| processed_depts.get(&child.id).cloned())
-                        .collect();
-
-                    // Update department information, add child departments
-                    if !child_depts.is_empty() {
-                        if let serde_json::Value::Object(ref mut obj) = dept_info {
-                            obj.insert("children".to_string(), serde_json::Value::Array(child_depts));
-                        }
-                    }
+    let agents = state.agents();
+    let names = match participant_names(state.store.as_ref(), &agents).await {
+        Ok(names) => names,
+        Err(e) => {
+            error!("Failed to load participant names for {}: {}", session_id, e);
+            agents.iter().map(|a| (a.id.clone(), a.name.clone())).collect()
+        }
+    };
+    let renderer = TranscriptRenderer::new(format, session.clone(), title).with_names(names);
+    let pager = TranscriptPager::new(state.store.clone(), session, query.since, query.until);
 
-                    processed_depts.insert(dept.id.clone(), dept_info);
-                }
-            }
+    let body = axum::body::Body::from_stream(export_stream(renderer, pager).map(|chunk| {
+        chunk.map_err(|e| {
+            error!("Transcript export failed: {}", e);
+            std::io::Error::other(e.to_string())
+        })
+    }));
+    let disposition = format!("attachment; filename=\"transcript-{}.{}\"", session_id, format.extension());
+    (
+        [
+            (axum::http::header::CONTENT_TYPE, format.content_type().to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ).into_response()
+}
+
+/// 根据ID获取Agent名称的辅助函数
+fn get_agent_name_by_id(state: &AppState, agent_id: &str) -> String {
+    state.agents().into_iter()
+        .find(|agent| agent.id == agent_id)
+        .map(|agent| agent.name)
+        .unwrap_or_else(|| state.catalog.get("web.unknown_agent"))
+}
+
+/// 获取组织架构树
+async fn get_org_tree(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    /// 借用缓存的部门树序列化，缓存命中时不复制
+    #[derive(Serialize)]
+    struct OrgTreeResponse<'a> {
+        success: bool,
+        data: &'a serde_json::Value,
+    }
 
-            // Finally get root departments (departments without parent)
-            let root_depts: Vec<serde_json::Value> = org.departments.iter()
-                .filter(|dept| dept.parent_id.is_none())
-                .filter_map(|dept| processed_depts.get(&dept.id).cloned())
-                .collect();
+    match state.org_tree.tree(state.store.as_ref()).await {
+        Ok(tree) => Json(OrgTreeResponse { success: true, data: &tree }).into_response(),
+        Err(e) => {
+            error!("Failed to load organization tree: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.org_tree_load_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
 
-            // 同时返回扁平的agents列表用于其他用途（目前未使用）
-            let _agents: Vec<serde_json::Value> = org.agents.iter().map(|agent| {
+/// 获取所有用户（需要 `manage_users`）
+async fn get_users(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ManageUsers>,
+) -> impl IntoResponse {
+    match state.store.load_users().await {
+        Ok(users) => {
+            // 转换为前端友好的格式
+            let users_data: Vec<serde_json::Value> = users.into_iter().map(|user| {
                 serde_json::json!({
-                    "id": agent.id,
-                    "name": agent.name,
-                    "title": agent.role.title,
-                    "departmentId": agent.department_id,
-                    "status": "online",  // 假设Agent始终在线
-                    "isOnline": true
+                    "id": user.id,
+                    "username": user.username,
+                    "name": user.name,
+                    "email": user.email,
+                    "employee_id": user.employee_id,
+                    "position": format!("{:?}", user.position),
+                    "department": user.department,
+                    "created_at": user.created_at,
+                    "is_director": matches!(user.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management),
                 })
             }).collect();
 
             Json(serde_json::json!({
                 "success": true,
-                "data": root_depts  // Directly return department array, matching frontend expectation
+                "data": users_data
             })).into_response()
-        },
+        }
         Err(e) => {
-            error!("Failed to load organization tree: {}", e);
+            error!("Failed to load users: {}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(ErrorResponse {
-                    error: "Failed to load organization tree".to_string(),
+                    error: state.catalog.get("web.users_load_failed"),
                 })
             ).into_response()
         }
     }
 }
 
-/// 获取所有用户（仅管理员）
-async fn get_users(
+/// 解除用户的登录锁定（需要 `manage_users`）
+async fn unlock_user(
     State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
+    RequirePermission { user: user_info, .. }: RequirePermission<perm::ManageUsers>,
+    Path(username): Path<String>,
 ) -> impl IntoResponse {
-    let auth_header = headers.get("authorization")
-        .and_then(|value| value.to_str().ok());
+    state.login_throttle.unlock(&user_key(&username)).await;
+    info!(target: "audit", "User {} unlocked login for {}", user_info.username, username);
+    Json(serde_json::json!({
+        "success": true,
+        "message": "User unlocked successfully"
+    })).into_response()
+}
 
-    if let Some(auth_str) = auth_header {
-        if auth_str.starts_with("Bearer ") {
-            let token = &auth_str[7..];
+// ==================== 工具统计 ====================
 
-            if check_admin_permission(&state, token).await.is_some() {
-                match state.store.load_users().await {
-                    Ok(users) => {
-                        // 转换为前端友好的格式
-                        let users_data: Vec<serde_json::Value> = users.into_iter().map(|user| {
-                            serde_json::json!({
-                                "id": user.id,
-                                "username": user.username,
-                                "name": user.name,
-                                "email": user.email,
-                                "employee_id": user.employee_id,
-                                "position": format!("{:?}", user.position),
-                                "department": user.department,
-                                "created_at": user.created_at,
-                                "is_director": matches!(user.position, crate::domain::user::Position::Chairman | crate::domain::user::Position::Management),
-                            })
-                        }).collect();
+#[derive(Deserialize)]
+pub struct ToolStatsQuery {
+    /// 返回的工具数量
+    pub top_n: Option<usize>,
+    /// 查询历史某日的汇总（YYYY-MM-DD），为空时返回实时统计
+    pub date: Option<String>,
+}
 
-                        return Json(serde_json::json!({
-                            "success": true,
-                            "data": users_data
-                        })).into_response();
-                    }
-                    Err(e) => {
-                        error!("Failed to load users: {}", e);
-                        return (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(ErrorResponse {
-                                error: "Failed to load users".to_string(),
-                            })
-                        ).into_response();
-                    }
-                }
+/// 获取工具使用统计
+/// 列出公司级模板和各角色模板
+async fn list_templates(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let templates = crate::core::template::list(&*state.templates.read().await, &state.agents());
+    Json(serde_json::json!({
+        "success": true,
+        "data": templates,
+    }))
+}
+
+async fn get_tool_stats(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ToolStatsQuery>,
+) -> impl IntoResponse {
+    let mut usages = match query.date {
+        Some(ref date) => match state.store.load_tool_stats(date).await {
+            Ok(usages) => usages,
+            Err(e) => {
+                error!("Failed to load tool stats for {}: {}", date, e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse {
+                        error: state.catalog.get("web.tool_stats_load_failed"),
+                    })
+                ).into_response();
             }
-        }
+        },
+        None => state.tool_stats.snapshot(),
+    };
+
+    usages.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.tool_id.cmp(&b.tool_id)));
+    if let Some(top_n) = query.top_n {
+        usages.truncate(top_n);
     }
 
-    (
-        StatusCode::FORBIDDEN,
-        Json(ErrorResponse {
-            error: "Insufficient permissions".to_string(),
+    let tools: Vec<serde_json::Value> = usages.iter().map(|usage| {
+        serde_json::json!({
+            "id": usage.tool_id,
+            "calls": usage.calls,
+            "successes": usage.successes,
+            "errors": usage.errors,
+            "success_rate": usage.success_rate(),
+            "avg_latency_ms": usage.avg_latency_ms(),
         })
-    ).into_response()
+    }).collect();
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "date": query.date,
+            "tools": tools,
+        }
+    })).into_response()
 }
 
-// ==================== 路由 ====================
+/// 轮次调度指标：并发数、队列深度与等待时间
+async fn get_scheduler_stats(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let Some(scheduler) = &state.scheduler else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.scheduler_unavailable"),
+            })
+        ).into_response();
+    };
 
-pub fn create_router(state: Arc<AppState>) -> Router {
-    let cors = CorsLayer::permissive();
-
-    Router::new()
-        .route("/api/health", get(health_check))
-        .route("/api/company", get(get_company))
-        .route("/api/agents", get(list_agents))
-        .route("/api/agents/{id}", get(get_agent))
-        .route("/api/messages", post(send_message))
-        .route("/api/auth/login", post(login))
-        .route("/api/auth/register", post(register))
-        .route("/api/auth/check-username", get(check_username))
-        .route("/api/auth/current", get(get_current_user))
-        .route("/api/admin/invite-codes", get(get_invite_codes).post(create_invite_code))
-        .route("/api/admin/invite-codes/{id}", delete(delete_invite_code))
-        .route("/api/chat/list", get(list_chat_sessions))
-        .route("/api/chat/{session_id}/messages", get(get_session_messages))
-        .route("/api/org/tree", get(get_org_tree))
-        .route("/api/admin/users", get(get_users))
-        .route("/ws", get(websocket_handler))
-        .layer(cors)
-        .with_state(state)
+    Json(serde_json::json!({
+        "success": true,
+        "data": scheduler.metrics(),
+    })).into_response()
 }
 
-// ==================== 服务器启动 ====================
+/// 各调用点的重试统计：调用、重试次数和放弃原因
+async fn get_retry_stats(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let Some(retries) = &state.retries else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.retries_unavailable"),
+            })
+        ).into_response();
+    };
 
-pub async fn start_web_server(
-    bind_addr: &str,
-    agents: Vec<Agent>,
-    message_tx: broadcast::Sender<Message>,
-    store: Arc<dyn crate::core::store::Store>,
-) -> anyhow::Result<()> {
-    // 创建JWT服务
-    let jwt_secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_key_for_dev".to_string());
-    let jwt_service = JwtService::new(&jwt_secret);
+    Json(serde_json::json!({
+        "success": true,
+        "data": retries.metrics().snapshot(),
+    })).into_response()
+}
 
-    let state = Arc::new(AppState {
-        agents,
-        message_tx,
-        store,
-        jwt_service,
-    });
+// ==================== 上下文重现 ====================
+
+#[derive(Deserialize)]
+pub struct AgentContextQuery {
+    /// 重现该时刻（秒时间戳）的上下文，为空时使用当前时间
+    pub as_of: Option<i64>,
+}
+
+/// 重现 Agent 在某时刻的思考上下文（需要 `inspect_agents`）
+async fn get_agent_context(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::InspectAgents>,
+    Path(agent_id): Path<String>,
+    Query(query): Query<AgentContextQuery>,
+) -> impl IntoResponse {
+    let Some(agent) = state.agents().into_iter().find(|a| a.id == agent_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.agent_not_found", &[("agent_id", &agent_id)]),
+            })
+        ).into_response();
+    };
+    if !auth.allows(agent.department_id.as_deref()) {
+        return permissions::rejection(&state, StatusCode::FORBIDDEN);
+    }
+
+    let as_of = query.as_of.unwrap_or_else(|| Utc::now().timestamp());
+    let context = match crate::core::agent::load_context(state.store.as_ref(), &agent_id, Some(as_of)).await {
+        Ok(context) => context,
+        Err(e) => {
+            error!("Failed to load context for {}: {}", agent_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.context_load_failed"),
+                })
+            ).into_response();
+        }
+    };
+
+    let api_key = secret_for_redaction(&agent.llm_config.api_key);
+    let system_prompt = context
+        .role_revision
+        .as_ref()
+        .map(|r| r.role.system_prompt.clone())
+        .unwrap_or_else(|| agent.system_prompt());
+    let role_revision = context.role_revision.as_ref().map(|r| r.revision);
+    let system_prompt_version = {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(system_prompt.as_bytes());
+        digest.iter().take(8).map(|b| format!("{:02x}", b)).collect::<String>()
+    };
+
+    let runtime = match crate::core::agent::AgentRuntime::new(agent).await {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            error!("Failed to create runtime for {}: {}", agent_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.context_load_failed"),
+                })
+            ).into_response();
+        }
+    };
+    let prompt = runtime.build_thinking_prompt(&context);
+
+    let secrets = [api_key.as_str()];
+    let messages: Vec<serde_json::Value> = context.history.iter().map(|msg| {
+        serde_json::json!({
+            "id": msg.id,
+            "from": msg.from,
+            "content": redaction::redact_secrets(&msg.content, &secrets),
+            "timestamp": msg.timestamp,
+        })
+    }).collect();
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "agent_id": agent_id,
+            "as_of": as_of,
+            "system_prompt_version": system_prompt_version,
+            "role_revision": role_revision,
+            "memory_summary": null,
+            "messages": messages,
+            "prompt": redaction::redact_secrets(&prompt, &secrets),
+        }
+    })).into_response()
+}
+
+// ==================== 组织架构变更 ====================
+
+/// 变更记录默认返回条数
+const ORG_CHANGES_DEFAULT_LIMIT: usize = 50;
+/// 变更记录单次最多返回条数
+const ORG_CHANGES_MAX_LIMIT: usize = 200;
+
+/// 变更记录查询参数
+#[derive(Deserialize)]
+pub struct OrgChangesQuery {
+    /// 只返回该时间（秒，不含）之后的变更
+    pub since: Option<i64>,
+    pub limit: Option<usize>,
+}
+
+/// 查看组织架构变更记录，最新的在前（需要 `view_audit`）
+async fn get_org_changes(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ViewAudit>,
+    Query(query): Query<OrgChangesQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(ORG_CHANGES_DEFAULT_LIMIT).clamp(1, ORG_CHANGES_MAX_LIMIT);
+    match state.store.load_org_changes(query.since.unwrap_or(0), limit).await {
+        Ok(changes) => Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "changes": changes,
+            }
+        })).into_response(),
+        Err(e) => {
+            error!("Failed to load organization changes: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.org_changes_load_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+/// 活动摘要默认返回条数
+const DIGESTS_DEFAULT_LIMIT: usize = 20;
 
-    let app = create_router(state);
+/// 活动摘要单次最多返回条数
+const DIGESTS_MAX_LIMIT: usize = 100;
 
-    let listener = tokio::net::TcpListener::bind(bind_addr).await?;
-    info!("Web server started on http://{}", bind_addr);
+/// 活动摘要查询参数
+#[derive(Deserialize)]
+pub struct DigestsQuery {
+    pub limit: Option<usize>,
+}
 
-    axum::serve(listener, app).await?;
+/// 浏览已生成的活动摘要，最新的在前（需要 `view_audit`）
+async fn get_digests(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ViewAudit>,
+    Query(query): Query<DigestsQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DIGESTS_DEFAULT_LIMIT).clamp(1, DIGESTS_MAX_LIMIT);
+    match state.store.load_digests(limit).await {
+        Ok(digests) => Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "digests": digests,
+            }
+        })).into_response(),
+        Err(e) => {
+            error!("Failed to load digests: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.digests_load_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+// ==================== Agent 偏好 ====================
+
+/// 查看 Agent 保存的偏好（需要 `manage_agents`）
+async fn get_agent_preferences(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(agent_id): Path<String>,
+) -> impl IntoResponse {
+    if !auth.allows(agent_department(&state, &agent_id).await.as_deref()) {
+        return permissions::rejection(&state, StatusCode::FORBIDDEN);
+    }
+    match state.store.load_agent_preferences(&agent_id).await {
+        Ok(preferences) => Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "agent_id": agent_id,
+                "preferences": preferences.unwrap_or_else(|| serde_json::json!({})),
+            }
+        })).into_response(),
+        Err(e) => {
+            error!("Failed to load preferences for {}: {}", agent_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.preferences_load_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+/// 重置 Agent 偏好（需要 `manage_agents`）
+async fn reset_agent_preferences(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(agent_id): Path<String>,
+) -> impl IntoResponse {
+    if !auth.allows(agent_department(&state, &agent_id).await.as_deref()) {
+        return permissions::rejection(&state, StatusCode::FORBIDDEN);
+    }
+    match state.store.delete_agent_preferences(&agent_id).await {
+        Ok(()) => {
+            info!(target: "audit", "User {} reset preferences of agent {}", auth.user.username, agent_id);
+            Json(serde_json::json!({
+                "success": true,
+                "message": "Agent preferences reset successfully"
+            })).into_response()
+        }
+        Err(e) => {
+            error!("Failed to reset preferences for {}: {}", agent_id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.preferences_reset_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+// ==================== Agent 角色修订 ====================
+
+/// 修改 Agent 角色请求，未提供的字段沿用当前生效的角色
+#[derive(Debug, Deserialize)]
+pub struct UpdateRoleRequest {
+    pub title: Option<String>,
+    pub system_prompt: Option<String>,
+    pub responsibilities: Option<Vec<String>>,
+    pub expertise: Option<Vec<String>>,
+    /// 回复语言代码，空字符串表示使用公司默认
+    pub response_language: Option<String>,
+    /// 语气，空字符串表示清除
+    pub tone: Option<String>,
+    /// 修改说明
+    pub note: Option<String>,
+}
+
+/// 空字符串清除设置，未提供时保留原值
+fn role_setting(update: Option<String>, current: Option<String>) -> Option<String> {
+    match update {
+        Some(value) if value.trim().is_empty() => None,
+        Some(value) => Some(value.trim().to_string()),
+        None => current,
+    }
+}
+
+/// 回滚角色参数
+#[derive(Debug, Deserialize)]
+pub struct RollbackRoleQuery {
+    pub note: Option<String>,
+}
+
+/// 查找 Agent 并检查其部门在授权范围内，失败时返回状态码，由 [`role_rejection`] 生成响应
+fn role_target<P: PermissionMarker>(
+    state: &AppState,
+    auth: &RequirePermission<P>,
+    agent_id: &str,
+) -> Result<Agent, StatusCode> {
+    let agent = state.agents().into_iter().find(|a| a.id == agent_id).ok_or(StatusCode::NOT_FOUND)?;
+    if !auth.allows(agent.department_id.as_deref()) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(agent)
+}
+
+fn role_rejection(state: &AppState, status: StatusCode, agent_id: &str) -> axum::response::Response {
+    if status != StatusCode::NOT_FOUND {
+        return permissions::rejection(state, status);
+    }
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.agent_not_found", &[("agent_id", agent_id)]),
+        })
+    ).into_response()
+}
+
+fn role_update_failed(state: &AppState) -> axum::response::Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: state.catalog.get("web.role_update_failed"),
+        })
+    ).into_response()
+}
+
+/// 保存新的角色修订（需要 `manage_agents`），下一轮思考生效
+async fn update_agent_role(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(agent_id): Path<String>,
+    Json(req): Json<UpdateRoleRequest>,
+) -> impl IntoResponse {
+    let agent = match role_target(&state, &auth, &agent_id) {
+        Ok(agent) => agent,
+        Err(status) => return role_rejection(&state, status, &agent_id),
+    };
+    let user_info = auth.user;
+
+    let current = match role_in_effect(state.store.as_ref(), &agent_id, None).await {
+        Ok(current) => current.map(|r| r.role).unwrap_or_else(|| agent.role.clone()),
+        Err(e) => {
+            error!("Failed to load role of {}: {}", agent_id, e);
+            return role_update_failed(&state);
+        }
+    };
+    let response_language = role_setting(req.response_language, current.response_language);
+    if let Some(code) = response_language.as_deref().filter(|code| !is_known_language(code)) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.response_language_invalid", &[("language", code)]),
+            })
+        ).into_response();
+    }
+    let role = Role {
+        title: req.title.unwrap_or(current.title),
+        system_prompt: req.system_prompt.unwrap_or(current.system_prompt),
+        responsibilities: req.responsibilities.unwrap_or(current.responsibilities),
+        expertise: req.expertise.unwrap_or(current.expertise),
+        templates: current.templates,
+        response_language,
+        tone: role_setting(req.tone, current.tone),
+    };
+
+    match append_role_revision(state.store.as_ref(), &agent, role, &user_info.username, req.note).await {
+        Ok(revision) => {
+            info!(target: "audit", "User {} saved role revision {} of agent {}", user_info.username, revision.revision, agent_id);
+            Json(serde_json::json!({
+                "success": true,
+                "data": revision,
+            })).into_response()
+        }
+        Err(e) => {
+            error!("Failed to save role revision for {}: {}", agent_id, e);
+            role_update_failed(&state)
+        }
+    }
+}
+
+/// 角色修订历史，最新的在前（需要 `inspect_agents`）
+async fn get_agent_role_history(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::InspectAgents>,
+    Path(agent_id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(status) = role_target(&state, &auth, &agent_id) {
+        return role_rejection(&state, status, &agent_id);
+    }
+
+    match state.store.load_role_revisions(&agent_id).await {
+        Ok(mut revisions) => {
+            revisions.reverse();
+            Json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "agent_id": agent_id,
+                    "head": revisions.first().map(|r| r.revision),
+                    "revisions": revisions,
+                }
+            })).into_response()
+        }
+        Err(e) => {
+            error!("Failed to load role history for {}: {}", agent_id, e);
+            role_update_failed(&state)
+        }
+    }
+}
+
+/// 以旧修订的角色创建新修订（需要 `manage_agents`）
+async fn rollback_agent_role(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path((agent_id, revision)): Path<(String, u32)>,
+    Query(query): Query<RollbackRoleQuery>,
+) -> impl IntoResponse {
+    let agent = match role_target(&state, &auth, &agent_id) {
+        Ok(agent) => agent,
+        Err(status) => return role_rejection(&state, status, &agent_id),
+    };
+    let user_info = auth.user;
+
+    match rollback_role(state.store.as_ref(), &agent, revision, &user_info.username, query.note).await {
+        Ok(Some(head)) => {
+            info!(target: "audit", "User {} rolled back role of agent {} to revision {}", user_info.username, agent_id, revision);
+            Json(serde_json::json!({
+                "success": true,
+                "data": head,
+            })).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.format(
+                    "web.role_revision_not_found",
+                    &[("agent_id", &agent_id), ("revision", &revision.to_string())],
+                ),
+            })
+        ).into_response(),
+        Err(e) => {
+            error!("Failed to roll back role of {}: {}", agent_id, e);
+            role_update_failed(&state)
+        }
+    }
+}
+
+/// 设置或清除 Agent 的工作时间（需要 `manage_agents`），请求体为 `null` 时清除
+async fn update_agent_availability(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(agent_id): Path<String>,
+    Json(availability): Json<Option<Availability>>,
+) -> impl IntoResponse {
+    let user_info = &auth.user;
+
+    if let Some(Err(e)) = availability.as_ref().map(Availability::validate) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.availability_invalid", &[("error", &e)]),
+            })
+        ).into_response();
+    }
+
+    let update_failed = |e: anyhow::Error| {
+        error!("Failed to update availability of {}: {}", agent_id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.availability_update_failed"),
+            })
+        ).into_response()
+    };
+    // 读-改-写期间持有组织架构锁，避免并发请求覆盖彼此的修改
+    let _org_guard = state.org_lock.lock().await;
+    let mut org = match state.store.load_organization().await {
+        Ok(org) => org,
+        Err(e) => return update_failed(e),
+    };
+    let Some(agent) = org.agents.iter_mut().find(|a| a.id == agent_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.agent_not_found", &[("agent_id", &agent_id)]),
+            })
+        ).into_response();
+    };
+    if !auth.allows(agent.department_id.as_deref()) {
+        return permissions::rejection(&state, StatusCode::FORBIDDEN);
+    }
+    agent.availability = availability.clone();
+    match save_organization_tracked(state.store.as_ref(), &org, &user_info.username).await {
+        Ok(entry) => announce_org_change(&state, entry),
+        Err(e) => return update_failed(e),
+    }
+    info!(target: "audit", "User {} updated availability of agent {}", user_info.username, agent_id);
+
+    let now = state.clock.now();
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "agent_id": agent_id,
+            "availability": availability,
+            "available_now": availability.as_ref().is_none_or(|a| a.is_available_at(now)),
+            "next_available_at": availability.as_ref().and_then(|a| a.next_available_at(now)),
+        }
+    })).into_response()
+}
+
+/// 创建 Agent（需要 `manage_agents`，且能管理目标部门），保存到组织架构并由运行器注册到消息总线、开始工作，
+/// 支持 `Idempotency-Key`
+///
+/// ID 已存在时返回 409，部门不存在时返回 404，API Key 是明文（含继承的默认配置）时返回 400。
+async fn create_agent(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    headers: HeaderMap,
+    Json(request): Json<CreateAgentRequest>,
+) -> axum::response::Response {
+    let body = serde_json::to_value(&request).unwrap_or_default();
+    idempotency::idempotent(&state, &headers, "POST /agents", &body, || insert_agent(&state, &auth, request)).await
+}
+
+async fn insert_agent(
+    state: &AppState,
+    auth: &RequirePermission<perm::ManageAgents>,
+    request: CreateAgentRequest,
+) -> (StatusCode, serde_json::Value) {
+    let error = |status: StatusCode, error: String| (status, serde_json::json!({ "error": error }));
+    let invalid = |field: &str| {
+        error(
+            StatusCode::BAD_REQUEST,
+            state.catalog.format("web.agent_create_invalid", &[("error", field)]),
+        )
+    };
+    let id = request
+        .id
+        .clone()
+        .unwrap_or_else(|| format!("agent_{}", uuid::Uuid::new_v4().simple()));
+    if id.trim().is_empty() || is_user_principal(&id) {
+        return invalid("id");
+    }
+    if request.name.trim().is_empty() {
+        return invalid("name");
+    }
+    if request.role.trim().is_empty() {
+        return invalid("role");
+    }
+    if !auth.allows(request.department_id.as_deref()) {
+        return error(StatusCode::FORBIDDEN, state.catalog.get("web.insufficient_permissions"));
+    }
+
+    let create_failed = |e: anyhow::Error| {
+        error!("Failed to create agent {}: {}", id, e);
+        error(StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.agent_create_failed"))
+    };
+    // 读-改-写期间持有组织架构锁，避免并发请求覆盖彼此的修改
+    let _org_guard = state.org_lock.lock().await;
+    let mut org = match state.store.load_organization().await {
+        Ok(org) => org,
+        Err(e) => return create_failed(e),
+    };
+    if let Some(department_id) = &request.department_id {
+        if org.find_department(department_id).is_none() {
+            return error(
+                StatusCode::NOT_FOUND,
+                state.catalog.format("web.department_not_found", &[("department_id", department_id)]),
+            );
+        }
+    }
+    if org.find_agent(&id).is_some() {
+        return error(StatusCode::CONFLICT, state.catalog.format("web.agent_exists", &[("agent_id", &id)]));
+    }
+
+    let mut llm_config = match state.default_llm.clone().or_else(|| org.agents.first().map(|a| a.llm_config.clone())) {
+        Some(llm_config) => llm_config,
+        None if request.llm_config.api_key.is_some() && request.llm_config.model.is_some() => LLMConfig::openai(""),
+        None => return error(StatusCode::BAD_REQUEST, state.catalog.get("web.agent_llm_required")),
+    };
+    request.llm_config.apply_to(&mut llm_config);
+    // 存储只保存引用，明文在这里拒绝，不在保存时被换掉
+    if !llm_config.api_key.is_empty() && !SecretRef::parse(&llm_config.api_key).is_reference() {
+        return error(
+            StatusCode::BAD_REQUEST,
+            state.catalog.format("web.agent_api_key_literal", &[("agent_id", &id)]),
+        );
+    }
+    let mut agent = Agent::new(&id, request.name.trim(), Role::simple(request.role.trim(), &request.system_prompt), llm_config)
+        .with_created_at(state.clock.now());
+    if let Some(department_id) = &request.department_id {
+        agent = agent.with_department(department_id);
+    }
+    if let Err(full) = org.add_agent_checked(agent.clone()) {
+        return error(StatusCode::CONFLICT, department_full_message(state, &full));
+    }
+    match save_organization_tracked(state.store.as_ref(), &org, &auth.user.username).await {
+        Ok(entry) => announce_org_change(state, entry),
+        Err(e) => return create_failed(e),
+    }
+
+    // 运行器注册到消息总线并启动自主循环；没有运行器时 Agent 在下次启动公司时开始工作
+    match &state.agent_runner {
+        Some(runner) => {
+            if let Err(e) = runner.start(&agent).await {
+                warn!("Created agent {} but failed to start it: {}", id, e);
+            }
+        }
+        None => warn!("Created agent {} without an agent runner, it starts with the next company start", id),
+    }
+    state.update_agents(|agents| agents.push(agent.clone()));
+    info!(target: "audit", "User {} created agent {}", auth.user.username, id);
+
+    agent.llm_config.api_key = render_secret(&agent.llm_config.api_key);
+    (
+        StatusCode::CREATED,
+        serde_json::json!({
+            "success": true,
+            "data": agent,
+        }),
+    )
+}
+
+/// 删除 Agent（需要 `manage_agents`）：从组织架构中移除，注销消息总线并退出所在的群聊
+///
+/// Agent 是部门负责人时返回 409，`?force=true` 时清除负责人后删除。
+async fn delete_agent(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(agent_id): Path<String>,
+    Query(query): Query<DeleteAgentQuery>,
+) -> impl IntoResponse {
+    let delete_failed = |e: anyhow::Error| {
+        error!("Failed to delete agent {}: {}", agent_id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.agent_delete_failed"),
+            })
+        ).into_response()
+    };
+    // 读-改-写期间持有组织架构锁，避免并发请求覆盖彼此的修改
+    let _org_guard = state.org_lock.lock().await;
+    let mut org = match state.store.load_organization().await {
+        Ok(org) => org,
+        Err(e) => return delete_failed(e),
+    };
+    let Some(agent) = org.find_agent(&agent_id) else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.agent_not_found", &[("agent_id", &agent_id)]),
+            })
+        ).into_response();
+    };
+    if !auth.allows(agent.department_id.as_deref()) {
+        return permissions::rejection(&state, StatusCode::FORBIDDEN);
+    }
+    let led: Vec<String> = org.departments_led_by(&agent_id).iter().map(|d| d.id.clone()).collect();
+    if !led.is_empty() && !query.force {
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: state.catalog.format(
+                    "web.agent_is_leader",
+                    &[("agent_id", &agent_id), ("departments", &led.join(", "))],
+                ),
+            })
+        ).into_response();
+    }
+
+    // 先退出群聊：失败时组织架构和运行状态都没有变化，重试即可；
+    // 停止 Agent 不会失败，放在组织架构保存成功之后，保存失败时 Agent 仍在运行
+    let groups = match leave_all_groups(&state, &agent_id).await {
+        Ok(groups) => groups,
+        Err(e) => return delete_failed(e),
+    };
+    org.remove_agent(&agent_id);
+    match save_organization_tracked(state.store.as_ref(), &org, &auth.user.username).await {
+        Ok(entry) => announce_org_change(&state, entry),
+        Err(e) => return delete_failed(e),
+    }
+    match (&state.agent_runner, &state.message_bus) {
+        (Some(runner), _) => runner.stop(&agent_id).await,
+        (None, Some(bus)) => bus.unregister(&agent_id),
+        (None, None) => {}
+    }
+    state.update_agents(|agents| agents.retain(|a| a.id != agent_id));
+    info!(
+        target: "audit",
+        "User {} deleted agent {} (cleared leadership of [{}], left groups [{}])",
+        auth.user.username, agent_id, led.join(", "), groups.join(", ")
+    );
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "agent_id": agent_id,
+            "cleared_leadership": led,
+            "groups": groups,
+        }
+    })).into_response()
+}
+
+/// 把成员移出所在的全部群聊（含临时群聊和只在存储中的群聊），返回涉及的群聊 ID
+async fn leave_all_groups(state: &AppState, member: &str) -> anyhow::Result<Vec<String>> {
+    let mut left = Vec::new();
+    if let Some(bus) = &state.message_bus {
+        for group in bus.list_agent_groups_with(member, true).await {
+            bus.leave_group(&group.id, member).await?;
+            left.push(group.id);
+        }
+    }
+    for mut group in state.store.load_groups().await? {
+        if group.has_member(member) {
+            group.remove_member(member);
+            state.store.save_group(&group).await?;
+            if !left.contains(&group.id) {
+                left.push(group.id);
+            }
+        }
+    }
+    Ok(left)
+}
+
+/// 手动复位 Agent 的熔断器（需要 `manage_agents`），Agent 下一轮恢复处理排队的消息
+async fn reset_agent_breaker(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(agent_id): Path<String>,
+) -> impl IntoResponse {
+    let Some(breakers) = state.circuit_breakers.clone() else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.breakers_unavailable"),
+            })
+        ).into_response();
+    };
+    if let Err(status) = role_target(&state, &auth, &agent_id) {
+        return role_rejection(&state, status, &agent_id);
+    }
+
+    let was_faulted = breakers.reset(&agent_id);
+    info!(target: "audit", "User {} reset circuit breaker of agent {} (faulted: {})", auth.user.username, agent_id, was_faulted);
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "agent_id": agent_id,
+            "was_faulted": was_faulted,
+            "breaker": breakers.status(&agent_id),
+        }
+    })).into_response()
+}
+
+// ==================== 面试式创建 Agent ====================
+
+fn agent_draft_unavailable(state: &AppState) -> axum::response::Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(ErrorResponse {
+            error: state.catalog.get("web.agent_draft_unavailable"),
+        })
+    ).into_response()
+}
+
+fn agent_draft_not_found(state: &AppState, session_id: &str) -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.agent_draft_not_found", &[("session_id", session_id)]),
+        })
+    ).into_response()
+}
+
+/// 草稿错误对应的响应：会话不存在 404，状态或 ID 冲突 409，草稿内容无效 400，部门满员 409
+fn agent_draft_error(state: &AppState, session_id: &str, e: anyhow::Error) -> axum::response::Response {
+    if let Some(full) = e.downcast_ref::<DepartmentFull>() {
+        return department_full_response(state, full);
+    }
+    let status = match e.downcast_ref::<AgentDraftError>() {
+        Some(AgentDraftError::SessionNotFound { .. }) => return agent_draft_not_found(state, session_id),
+        Some(AgentDraftError::NotDrafted { .. } | AgentDraftError::AlreadyCommitted { .. } | AgentDraftError::AgentExists { .. }) => {
+            StatusCode::CONFLICT
+        }
+        Some(AgentDraftError::Invalid { .. } | AgentDraftError::DepartmentNotFound { .. }) => StatusCode::BAD_REQUEST,
+        None => {
+            error!("Failed to process agent draft {}: {}", session_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.agent_draft_failed"),
+                })
+            ).into_response();
+        }
+    };
+    (
+        status,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.agent_draft_invalid", &[("error", &e.to_string())]),
+        })
+    ).into_response()
+}
+
+/// 当前用户自己的面试会话；不存在或属于其他用户时返回 404
+async fn own_agent_draft(
+    state: &AppState,
+    interviewer: &AgentInterviewer,
+    user: &UserInfo,
+    session_id: &str,
+) -> Result<DraftSession, axum::response::Response> {
+    match interviewer.session(session_id).await {
+        Ok(Some(session)) if session.owner == user_principal(&user.id) => Ok(session),
+        Ok(_) => Err(agent_draft_not_found(state, session_id)),
+        Err(e) => Err(agent_draft_error(state, session_id, e)),
+    }
+}
+
+/// 开始面试（需要 `manage_agents`），HR 在私聊中向当前用户提问
+async fn start_agent_draft(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+) -> impl IntoResponse {
+    let Some(interviewer) = state.agent_interviewer.clone() else {
+        return agent_draft_unavailable(&state);
+    };
+    match interviewer.start(&user_principal(&auth.user.id)).await {
+        Ok(session) => {
+            info!(target: "audit", "User {} started agent draft interview {}", auth.user.username, session.id);
+            Json(serde_json::json!({
+                "success": true,
+                "data": session,
+            })).into_response()
+        }
+        Err(e) => agent_draft_error(&state, "-", e),
+    }
+}
+
+/// 查看面试进度和草稿（需要 `manage_agents`）
+async fn get_agent_draft(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    let Some(interviewer) = state.agent_interviewer.clone() else {
+        return agent_draft_unavailable(&state);
+    };
+    match own_agent_draft(&state, &interviewer, &auth.user, &session_id).await {
+        Ok(session) => Json(serde_json::json!({
+            "success": true,
+            "data": session,
+        })).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// 用修改后的草稿替换 LLM 起草的版本（需要 `manage_agents`）
+async fn update_agent_draft(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(session_id): Path<String>,
+    Json(draft): Json<AgentDraft>,
+) -> impl IntoResponse {
+    let Some(interviewer) = state.agent_interviewer.clone() else {
+        return agent_draft_unavailable(&state);
+    };
+    if let Err(response) = own_agent_draft(&state, &interviewer, &auth.user, &session_id).await {
+        return response;
+    }
+    match interviewer.update_draft(&session_id, draft).await {
+        Ok(session) => Json(serde_json::json!({
+            "success": true,
+            "data": session,
+        })).into_response(),
+        Err(e) => agent_draft_error(&state, &session_id, e),
+    }
+}
+
+/// 按草稿创建 Agent（需要 `manage_agents`，且能管理草稿所在的部门）
+async fn commit_agent_draft(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageAgents>,
+    Path(session_id): Path<String>,
+) -> impl IntoResponse {
+    let Some(interviewer) = state.agent_interviewer.clone() else {
+        return agent_draft_unavailable(&state);
+    };
+    let session = match own_agent_draft(&state, &interviewer, &auth.user, &session_id).await {
+        Ok(session) => session,
+        Err(response) => return response,
+    };
+    let department = session.draft.as_ref().and_then(|d| d.department_id.as_deref());
+    if !auth.allows(department) {
+        return permissions::rejection(&state, StatusCode::FORBIDDEN);
+    }
+
+    match interviewer.commit(&session_id, &auth.user.username).await {
+        Ok((agent, entry)) => {
+            announce_org_change(&state, entry);
+            info!(target: "audit", "User {} created agent {} from draft interview {}", auth.user.username, agent.id, session_id);
+            Json(serde_json::json!({
+                "success": true,
+                "data": agent,
+            })).into_response()
+        }
+        Err(e) => agent_draft_error(&state, &session_id, e),
+    }
+}
+
+// ==================== 临时 Agent ====================
+
+/// 临时 Agent 错误对应的响应：会话或模板不存在 404，不是会话参与者 403，达到上限 409，
+/// 未启用或公司未运行 503，请求无效 400
+fn temp_agent_error(state: &AppState, session_id: &str, e: anyhow::Error) -> axum::response::Response {
+    let status = match e.downcast_ref::<TempAgentError>() {
+        Some(TempAgentError::SessionNotFound { .. } | TempAgentError::TemplateNotFound { .. } | TempAgentError::NotFound { .. }) => {
+            StatusCode::NOT_FOUND
+        }
+        Some(TempAgentError::NotParticipant { .. }) => StatusCode::FORBIDDEN,
+        Some(TempAgentError::LimitReached { .. } | TempAgentError::SpawnerLimitReached { .. }) => StatusCode::CONFLICT,
+        Some(TempAgentError::Disabled | TempAgentError::NotRunning | TempAgentError::NoLlm) => StatusCode::SERVICE_UNAVAILABLE,
+        Some(TempAgentError::NestedSpawn | TempAgentError::RoleRequired | TempAgentError::InvalidTtl { .. }) => {
+            StatusCode::BAD_REQUEST
+        }
+        None => {
+            error!("Failed to spawn temporary agent in {}: {}", session_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.temp_agent_failed"),
+                })
+            ).into_response();
+        }
+    };
+    (
+        status,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.temp_agent_invalid", &[("error", &e.to_string())]),
+        })
+    ).into_response()
+}
+
+/// 为会话创建临时 Agent（需要登录，且是会话的参与者）；模板为现有 Agent 的 ID，或直接给出角色
+async fn spawn_temporary_agent(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(request): Json<SpawnRequest>,
+) -> impl IntoResponse {
+    let Some(spawner) = bookmark_owner(&state, &headers) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        ).into_response();
+    };
+    let Some(temp_agents) = state.temp_agents.clone() else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.temp_agent_unavailable"),
+            })
+        ).into_response();
+    };
+    match temp_agents.spawn(&spawner, &session_id, request).await {
+        Ok(mut record) => {
+            record.agent.llm_config.api_key = render_secret(&record.agent.llm_config.api_key);
+            Json(serde_json::json!({
+                "success": true,
+                "data": record,
+            })).into_response()
+        }
+        Err(e) => temp_agent_error(&state, &session_id, e),
+    }
+}
+
+// ==================== 提示词日志 ====================
+
+/// 调整提示词日志脱敏级别的请求
+#[derive(Debug, Deserialize)]
+pub struct PromptLogLevelRequest {
+    pub level: PromptLogLevel,
+}
+
+/// 提示词日志状态（需要 `manage_prompt_log`）
+async fn get_prompt_log(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ManagePromptLog>,
+) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "success": true,
+        "data": prompt_log_status(&state),
+    }))
+}
+
+/// 运行中调整提示词日志的脱敏级别，无需重启（需要 `manage_prompt_log`）
+async fn set_prompt_log_level(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManagePromptLog>,
+    Json(req): Json<PromptLogLevelRequest>,
+) -> impl IntoResponse {
+    let Some(prompt_log) = &state.prompt_log else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.prompt_log_disabled"),
+            })
+        ).into_response();
+    };
+
+    let previous = prompt_log.level();
+    prompt_log.set_level(req.level);
+    info!(target: "audit", "User {} changed prompt log level from {} to {}", auth.user.username, previous, req.level);
+    Json(serde_json::json!({
+        "success": true,
+        "data": prompt_log_status(&state),
+    })).into_response()
+}
+
+fn prompt_log_status(state: &AppState) -> serde_json::Value {
+    match &state.prompt_log {
+        Some(prompt_log) => serde_json::json!({
+            "enabled": true,
+            "level": prompt_log.level(),
+            "directory": prompt_log.directory(),
+            "dropped": prompt_log.dropped(),
+        }),
+        None => serde_json::json!({ "enabled": false }),
+    }
+}
+
+// ==================== 群聊管理 ====================
+
+/// 设置群管理员请求
+#[derive(Debug, Deserialize)]
+pub struct GroupAdminRequest {
+    pub member_id: String,
+}
+
+/// 禁言请求
+#[derive(Debug, Deserialize)]
+pub struct GroupMuteRequest {
+    pub member_id: String,
+    /// 禁言时长（秒），到期自动解除
+    pub duration_secs: u64,
+}
+
+/// 设置群管理员（仅群管理员）
+async fn add_group_admin(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+    Json(req): Json<GroupAdminRequest>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    let result = bus.add_admin(&group_id, &actor, &req.member_id).await;
+    moderation_response(&state, &group_id, result)
+}
+
+/// 把成员移出群聊（仅群管理员）
+async fn kick_group_member(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((group_id, member_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    let result = bus.remove_member(&group_id, &actor, &member_id).await;
+    moderation_response(&state, &group_id, result)
+}
+
+/// 禁言群成员（仅群管理员）
+async fn mute_group_member(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+    Json(req): Json<GroupMuteRequest>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    if req.duration_secs == 0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.group_moderation_invalid", &[("error", "duration_secs must be positive")]),
+            })
+        ).into_response();
+    }
+    let until = bus.clock().now() + req.duration_secs as i64;
+    let result = bus.mute_member(&group_id, &actor, &req.member_id, until).await;
+    moderation_response(&state, &group_id, result)
+}
+
+/// 开启群聊轮流发言（仅群管理员）
+async fn enable_group_turn_taking(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+    Json(settings): Json<TurnTakingSettings>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    let result = bus.set_turn_taking(&group_id, &actor, Some(settings)).await;
+    moderation_response(&state, &group_id, result)
+}
+
+/// 关闭群聊轮流发言（仅群管理员）
+async fn disable_group_turn_taking(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    let result = bus.set_turn_taking(&group_id, &actor, None).await;
+    moderation_response(&state, &group_id, result)
+}
+
+/// 群聊管理请求的操作者（`user:{id}`）及消息总线
+fn moderation_actor(state: &AppState, headers: &HeaderMap) -> Result<(Arc<MessageBus>, String), (StatusCode, Json<ErrorResponse>)> {
+    let user_info = headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    let Some(user_info) = user_info else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        ));
+    };
+    let Some(bus) = state.message_bus.clone() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.message_bus_unavailable"),
+            })
+        ));
+    };
+    Ok((bus, user_principal(&user_info.id)))
+}
+
+/// 立即按组织架构同步群成员（仅群管理员）
+async fn resync_group(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+) -> impl IntoResponse {
+    let (bus, actor) = match moderation_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+    let Some(group) = bus.get_group(&group_id).await else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse { error: state.catalog.format("web.group_not_found", &[("group_id", &group_id)]) }),
+        ).into_response();
+    };
+    if !group.is_admin(&actor) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse { error: state.catalog.format("web.group_not_admin", &[("group_id", &group_id)]) }),
+        ).into_response();
+    }
+
+    match GroupSyncReconciler::new(state.store.clone(), bus).reconcile(&group_id).await {
+        Ok(report) => {
+            info!(target: "audit", "{} resynced members of group {}", actor, group_id);
+            Json(serde_json::json!({
+                "success": true,
+                "data": report,
+            })).into_response()
+        }
+        Err(e) => match e.downcast_ref::<GroupSyncError>() {
+            Some(error) => (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: state.catalog.format("web.group_sync_invalid", &[("error", &error.to_string())]),
+                }),
+            ).into_response(),
+            None => {
+                error!("Failed to sync members of group {}: {}", group_id, e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse { error: state.catalog.get("web.group_sync_failed") }),
+                ).into_response()
+            }
+        },
+    }
+}
+
+fn moderation_response(state: &AppState, group_id: &str, result: anyhow::Result<Group>) -> axum::response::Response {
+    let error = match result {
+        Ok(group) => {
+            return Json(serde_json::json!({
+                "success": true,
+                "data": group,
+            })).into_response();
+        }
+        Err(e) => e,
+    };
+    let (status, message) = match error.downcast_ref::<GroupModerationError>() {
+        Some(GroupModerationError::GroupNotFound { .. }) => {
+            (StatusCode::NOT_FOUND, state.catalog.format("web.group_not_found", &[("group_id", group_id)]))
+        }
+        Some(GroupModerationError::NotAdmin { .. }) => {
+            (StatusCode::FORBIDDEN, state.catalog.format("web.group_not_admin", &[("group_id", group_id)]))
+        }
+        Some(e) => (
+            StatusCode::BAD_REQUEST,
+            state.catalog.format("web.group_moderation_invalid", &[("error", &e.to_string())]),
+        ),
+        None => {
+            error!("Failed to moderate group {}: {}", group_id, error);
+            (StatusCode::INTERNAL_SERVER_ERROR, state.catalog.get("web.group_moderation_failed"))
+        }
+    };
+    (status, Json(ErrorResponse { error: message })).into_response()
+}
+
+// ==================== 数据完整性 ====================
+
+/// 完整性检查参数
+#[derive(Debug, Default, Deserialize)]
+pub struct IntegrityCheckQuery {
+    /// 执行安全修复（默认只检查）
+    #[serde(default)]
+    pub repair: bool,
+}
+
+/// 检查持久化数据完整性（需要 `manage_storage`），`repair=true` 时执行安全修复
+async fn run_integrity_check(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ManageStorage>,
+    Query(query): Query<IntegrityCheckQuery>,
+) -> impl IntoResponse {
+    let user_info = auth.user;
+
+    let checker = StoreIntegrityChecker::new(state.store.clone());
+    let report = if query.repair {
+        checker.repair(&user_info.username).await
+    } else {
+        checker.check().await
+    };
+    match report {
+        Ok(report) => {
+            // 修复会改写组织架构
+            if report.repaired() > 0 {
+                state.org_tree.invalidate();
+                state.mentions.invalidate();
+            }
+            info!(
+                target: "audit",
+                "User {} ran integrity check (repair: {}): {} finding(s), {} repaired",
+                user_info.username,
+                query.repair,
+                report.findings.len(),
+                report.repaired()
+            );
+            Json(serde_json::json!({
+                "success": true,
+                "data": report,
+            })).into_response()
+        }
+        Err(e) => {
+            error!("Integrity check failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.integrity_check_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+/// 热表与归档中的消息数（需要 `manage_storage`）
+async fn get_message_tiers(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ManageStorage>,
+) -> impl IntoResponse {
+    match state.store.message_tier_counts().await {
+        Ok(counts) => Json(serde_json::json!({
+            "success": true,
+            "data": counts,
+        })).into_response(),
+        Err(e) => {
+            error!("Failed to count messages: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.message_tiers_failed"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+// ==================== 任务 ====================
+
+/// 任务列表查询参数，`status` 可用逗号分隔多个状态
+#[derive(Debug, Deserialize)]
+pub struct TaskQuery {
+    pub assignee: Option<String>,
+    pub creator: Option<String>,
+    pub status: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 创建任务请求
+#[derive(Debug, Deserialize)]
+pub struct CreateTaskRequest {
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    /// Agent ID 或用户参与者ID（`user:{id}`）
+    pub assignee: Option<String>,
+    /// 截止时间（Unix 秒）
+    pub due_at: Option<i64>,
+}
+
+/// 更新任务请求，未提供的字段保持不变
+#[derive(Debug, Deserialize)]
+pub struct UpdateTaskRequest {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub assignee: Option<String>,
+    pub status: Option<TaskStatus>,
+    pub due_at: Option<i64>,
+}
+
+/// 查询任务，可按负责人、创建者和状态过滤
+async fn list_tasks(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<TaskQuery>,
+) -> impl IntoResponse {
+    if let Err(error) = task_actor(&state, &headers) {
+        return error.into_response();
+    }
+
+    let mut filter = TaskFilter::new();
+    if let Some(assignee) = &query.assignee {
+        filter = filter.assignee(assignee);
+    }
+    if let Some(creator) = &query.creator {
+        filter = filter.creator(creator);
+    }
+    for status in query.status.iter().flat_map(|s| s.split(',')).map(str::trim).filter(|s| !s.is_empty()) {
+        match status.parse::<TaskStatus>() {
+            Ok(status) => filter = filter.status(status),
+            Err(e) => return task_invalid(&state, &e).into_response(),
+        }
+    }
+    if let Some(limit) = query.limit {
+        filter = filter.limit(limit);
+    }
+
+    match task_manager(&state).list(filter).await {
+        Ok(tasks) => Json(serde_json::json!({
+            "success": true,
+            "data": tasks,
+        })).into_response(),
+        Err(e) => task_error_response(&state, "", e),
+    }
+}
+
+/// 创建任务，创建者为当前用户
+async fn create_task(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(req): Json<CreateTaskRequest>,
+) -> impl IntoResponse {
+    let (_, actor) = match task_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(error) => return error.into_response(),
+    };
+
+    let mut task = Task::new(req.title, actor, state.clock.now()).with_description(req.description);
+    task.assignee = req.assignee;
+    task.due_at = req.due_at;
+    match task_manager(&state).create(task).await {
+        Ok(task) => (
+            StatusCode::CREATED,
+            Json(serde_json::json!({
+                "success": true,
+                "data": task,
+            })),
+        ).into_response(),
+        Err(e) => task_error_response(&state, "", e),
+    }
+}
+
+/// 查看任务
+async fn get_task(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(error) = task_actor(&state, &headers) {
+        return error.into_response();
+    }
+    match task_manager(&state).get(&task_id).await {
+        Ok(task) => Json(serde_json::json!({
+            "success": true,
+            "data": task,
+        })).into_response(),
+        Err(e) => task_error_response(&state, &task_id, e),
+    }
+}
+
+/// 更新任务（创建者、负责人或管理员）
+async fn update_task(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+    Json(req): Json<UpdateTaskRequest>,
+) -> impl IntoResponse {
+    let tasks = task_manager(&state);
+    let actor = match task_editor(&state, &headers, &tasks, &task_id).await {
+        Ok(actor) => actor,
+        Err(error) => return error,
+    };
+
+    let update = TaskUpdate {
+        title: req.title,
+        description: req.description,
+        assignee: req.assignee,
+        status: req.status,
+        due_at: req.due_at,
+    };
+    match tasks.update(&task_id, &actor, update).await {
+        Ok(task) => Json(serde_json::json!({
+            "success": true,
+            "data": task,
+        })).into_response(),
+        Err(e) => task_error_response(&state, &task_id, e),
+    }
+}
+
+/// 删除任务（创建者、负责人或管理员）
+async fn delete_task(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(task_id): Path<String>,
+) -> impl IntoResponse {
+    let tasks = task_manager(&state);
+    let actor = match task_editor(&state, &headers, &tasks, &task_id).await {
+        Ok(actor) => actor,
+        Err(error) => return error,
+    };
+    match tasks.delete(&task_id, &actor).await {
+        Ok(_) => Json(serde_json::json!({
+            "success": true,
+        })).into_response(),
+        Err(e) => task_error_response(&state, &task_id, e),
+    }
+}
+
+/// 任务管理器，有消息总线时通知经总线投递
+fn task_manager(state: &AppState) -> TaskManager {
+    let tasks = TaskManager::new(state.store.clone()).with_clock(state.clock.clone());
+    match &state.message_bus {
+        Some(bus) => tasks.with_message_bus(bus.clone()),
+        None => tasks,
+    }
+}
+
+/// 当前用户及其参与者ID（`user:{id}`）
+fn task_actor(state: &AppState, headers: &HeaderMap) -> Result<(UserInfo, String), (StatusCode, Json<ErrorResponse>)> {
+    let user_info = headers.get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    match user_info {
+        Some(user_info) => {
+            let actor = user_principal(&user_info.id);
+            Ok((user_info, actor))
+        }
+        None => Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            })
+        )),
+    }
+}
+
+/// 负责人为 Agent 时按其部门检查授权，否则需要不限部门的 `manage_tasks`
+async fn can_manage_task(state: &AppState, user_info: &UserInfo, task: &Task) -> bool {
+    let department = match task.assignee.as_deref() {
+        Some(assignee) => agent_department(state, assignee).await,
+        None => None,
+    };
+    permissions::resolve_permissions(state, user_info)
+        .await
+        .allows(Permission::ManageTasks, department.as_deref())
+}
+
+/// 可以修改任务的操作者：任务的创建者、负责人，或对负责人所在部门拥有 `manage_tasks` 的用户
+async fn task_editor(
+    state: &AppState,
+    headers: &HeaderMap,
+    tasks: &TaskManager,
+    task_id: &str,
+) -> Result<String, axum::response::Response> {
+    let (user_info, actor) = task_actor(state, headers).map_err(IntoResponse::into_response)?;
+    let task = tasks.get(task_id).await.map_err(|e| task_error_response(state, task_id, e))?;
+    if !task.involves(&actor) && !can_manage_task(state, &user_info, &task).await {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.task_forbidden", &[("task_id", task_id)]),
+            })
+        ).into_response());
+    }
+    Ok(actor)
+}
+
+fn task_invalid(state: &AppState, error: &str) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.task_invalid", &[("error", error)]),
+        })
+    )
+}
+
+fn task_error_response(state: &AppState, task_id: &str, error: anyhow::Error) -> axum::response::Response {
+    match error.downcast_ref::<TaskError>() {
+        Some(TaskError::NotFound { .. }) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.task_not_found", &[("task_id", task_id)]),
+            })
+        ).into_response(),
+        Some(e) => task_invalid(state, &e.to_string()).into_response(),
+        None => {
+            error!("Task operation on {} failed: {}", task_id, error);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.database_error"),
+                })
+            ).into_response()
+        }
+    }
+}
+
+// ==================== 路由 ====================
+
+/// 未匹配的 API 路径
+async fn api_not_found(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: state.catalog.get("web.route_not_found"),
+        })
+    )
+}
+
+/// API 路由（不含前缀），同时挂载到 `/api/v1` 与旧的 `/api`
+fn api_routes(groups: &RouteGroups) -> Router<Arc<AppState>> {
+    let mut router = Router::new()
+        .route("/health", get(health_check))
+        .route("/health/live", get(health_check))
+        .route("/health/ready", get(readiness_check))
+        .route("/info", get(get_runtime_info))
+        .route("/features", get(features::get_features))
+        .route("/leadership", get(standby::get_leadership))
+        .route("/company", get(get_company))
+        .route("/agents", get(list_agents))
+        .route("/agents/{id}", get(get_agent))
+        .route("/agents/{id}/context", get(get_agent_context))
+        .route("/messages", post(send_message))
+        .route("/messages/{id}/reactions", post(add_reaction).delete(remove_reaction))
+        .route("/org/tree", get(get_org_tree))
+        .route("/tools/stats", get(get_tool_stats))
+        .route("/scheduler/stats", get(get_scheduler_stats))
+        .route("/retries/stats", get(get_retry_stats))
+        .route("/templates", get(list_templates));
+
+    if groups.auth {
+        router = router
+            .route("/auth/login", post(login))
+            .route("/auth/register", post(register))
+            .route("/auth/check-username", get(check_username))
+            .route("/auth/current", get(get_current_user))
+            .route("/auth/change-password", post(change_password))
+            .route("/auth/permissions", get(permissions::get_my_permissions))
+            .route("/users/me/messages", get(get_my_messages))
+            .route("/users/me/language", put(set_preferred_language))
+            .route("/me/bookmarks", get(get_my_bookmarks));
+    }
+    if groups.admin {
+        router = router
+            .route("/admin/invite-codes", get(get_invite_codes).post(create_invite_code))
+            .route("/admin/invite-codes/{id}", delete(delete_invite_code))
+            .route("/admin/users", get(get_users))
+            .route("/admin/users/{username}/unlock", post(unlock_user))
+            .route("/admin/integrity-check", post(run_integrity_check))
+            .route("/admin/messages/tiers", get(get_message_tiers))
+            .route("/org/changes", get(get_org_changes))
+            .route("/digests", get(get_digests))
+            .route("/admin/agents/batch", post(agent_batch::apply_agent_batch))
+            .route("/admin/agents/{id}/preferences", get(get_agent_preferences).delete(reset_agent_preferences))
+            .route("/agents/{id}/role", put(update_agent_role))
+            .route("/agents/{id}/role/history", get(get_agent_role_history))
+            .route("/agents/{id}/role/rollback/{rev}", post(rollback_agent_role))
+            .route("/agents/{id}/availability", put(update_agent_availability))
+            .route("/agents/draft", post(start_agent_draft))
+            .route("/agents/draft/{session}", get(get_agent_draft).put(update_agent_draft))
+            .route("/agents/draft/{session}/commit", post(commit_agent_draft))
+            .route("/agents/{id}/reset-breaker", post(reset_agent_breaker))
+            .route("/agents", post(create_agent))
+            .route("/agents/{id}", delete(delete_agent))
+            .route("/admin/prompt-log", get(get_prompt_log).put(set_prompt_log_level))
+            .route("/admin/features/{name}", put(features::toggle_feature))
+            .route("/admin/moderation/quarantine", get(moderation::list_quarantine))
+            .route("/admin/moderation/quarantine/{id}", get(moderation::get_quarantined))
+            .route("/admin/moderation/quarantine/{id}/review", post(moderation::review_quarantined))
+            .route("/admin/groups/{id}/stats/recompute", post(group_stats::recompute_group_stats))
+            .route("/runs/{run_id}/scratchpad", get(scratchpad::get_run_scratchpad))
+            .route("/tools/{id}/dry-run", post(tool_preview::dry_run_tool));
+        #[cfg(feature = "chaos")]
+        {
+            router = router.route(
+                "/admin/chaos/rules",
+                get(chaos::get_chaos_rules).post(chaos::set_chaos_rules).delete(chaos::clear_chaos_rules),
+            );
+        }
+    }
+    if groups.chat {
+        router = router
+            .route("/chat/list", get(list_chat_sessions))
+            .route("/chat/{session_id}/messages", get(get_session_messages))
+            .route("/chat/{session_id}/export", get(export_session_transcript))
+            .route("/chat/{session_id}/pins", get(get_session_pins))
+            .route("/chat/{session_id}/spawn-agent", post(spawn_temporary_agent))
+            .route("/chat/{session_id}/close", post(chat_sessions::close_chat_session))
+            .route("/chat/{session_id}/reopen", post(chat_sessions::reopen_chat_session))
+            .route("/mentions/suggest", get(mentions::suggest_mentions))
+            .route("/agents/{id}/nudge", post(nudge::nudge_agent))
+            .route("/messages/{id}/pin", post(pin_message).delete(unpin_message))
+            .route("/messages/{id}/bookmark", post(add_bookmark).delete(remove_bookmark))
+            .route("/groups", get(groups::list_groups).post(groups::create_group))
+            .route("/groups/{id}/members", post(groups::invite_group_member))
+            .route("/groups/{id}/messages", get(groups::get_group_messages))
+            .route("/groups/{id}/admins", post(add_group_admin))
+            .route("/groups/{id}/members/{member_id}", delete(kick_group_member))
+            .route("/groups/{id}/mutes", post(mute_group_member))
+            .route("/groups/{id}/resync", post(resync_group))
+            .route("/groups/{id}/stats", get(group_stats::get_group_stats))
+            .route("/groups/{id}/turn-taking", put(enable_group_turn_taking).delete(disable_group_turn_taking))
+            .route("/groups/{id}/share", get(share::list_share_tokens).post(share::create_share_token))
+            .route("/groups/{id}/share/{share_id}", delete(share::revoke_share_token))
+            .route("/shared/{token}", get(share::get_shared_transcript))
+            .route("/shared/{token}/stream", get(share::stream_shared_transcript))
+            .route("/tasks", get(list_tasks).post(create_task))
+            .route("/tasks/{id}", get(get_task).patch(update_task).delete(delete_task))
+            .route("/attachments", post(attachments::upload_attachment))
+            .route("/attachments/{id}", get(attachments::download_attachment).delete(attachments::delete_attachment));
+    }
+
+    router.fallback(api_not_found)
+}
+
+/// 使用默认部署配置创建路由
+pub fn create_router(state: Arc<AppState>) -> Router {
+    create_router_with_config(state, &WebServerConfig::default())
+}
+
+/// 按部署配置创建路由（CORS、请求体上限、超时、路径前缀）
+pub fn create_router_with_config(state: Arc<AppState>, config: &WebServerConfig) -> Router {
+    config.apply(create_api_router(state, &config.routes))
+}
+
+/// 创建可嵌入的路由：只包含启用的路由组和追踪中间件，
+/// 不套 CORS、请求体上限等部署层，便于宿主应用 `.nest()` 后使用自己的中间件栈
+pub fn create_api_router(state: Arc<AppState>, groups: &RouteGroups) -> Router {
+    let mut router = Router::new()
+        .nest("/api/v1", api_routes(groups).layer(middleware::from_fn(envelope_middleware)));
+
+    if groups.ws {
+        router = router
+            .route("/api/v1/ws", get(websocket_handler))
+            .route("/ws", get(websocket_handler));
+    }
+
+    if state.legacy_api_routes {
+        router = router.nest("/api", api_routes(groups).layer(middleware::from_fn(deprecation_middleware)));
+    }
+
+    router
+        .layer(middleware::from_fn_with_state(state.clone(), standby::standby_guard))
+        .layer(middleware::from_fn_with_state(state.clone(), share::share_token_guard))
+        .layer(middleware::from_fn(trace_middleware))
+        .with_state(state)
+}
+
+/// 嵌入式路由选项
+#[derive(Debug, Clone)]
+pub struct RouterOptions {
+    /// 启用的路由组
+    pub routes: RouteGroups,
+    /// 是否保留未版本化的旧路由
+    pub legacy_api_routes: bool,
+    /// 错误信息使用的消息目录，为空时使用公司语言
+    pub catalog: Option<MessageCatalog>,
+    /// 注册和修改密码时使用的密码策略
+    pub password_policy: PasswordPolicy,
+    /// 就绪检查配置
+    pub health: HealthConfig,
+    /// JWT 服务，为空时按 `JWT_SECRET` 环境变量创建
+    pub jwt_service: Option<JwtService>,
+}
+
+impl Default for RouterOptions {
+    fn default() -> Self {
+        Self {
+            routes: RouteGroups::default(),
+            legacy_api_routes: true,
+            catalog: None,
+            password_policy: PasswordPolicy::default(),
+            health: HealthConfig::default(),
+            jwt_service: None,
+        }
+    }
+}
+
+/// 按 `JWT_SECRET` 环境变量创建 JWT 服务（未设置时使用开发用密钥）
+pub fn jwt_service_from_env() -> JwtService {
+    let jwt_secret = std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_secret_key_for_dev".to_string());
+    JwtService::new(&jwt_secret)
+}
+
+// ==================== 服务器启动 ====================
+
+/// 启动 Web 服务器，`config` 可用 `WebServerConfig::default()` 保持开发模式的行为
+pub async fn start_web_server(
+    bind_addr: &str,
+    agents: Vec<Agent>,
+    message_tx: broadcast::Sender<Message>,
+    store: Arc<dyn crate::core::store::Store>,
+    config: WebServerConfig,
+) -> anyhow::Result<()> {
+    let options = WebServerOptions {
+        server: config,
+        ..WebServerOptions::default()
+    };
+    start_web_server_with_options(bind_addr, agents, message_tx, store, options).await
+}
+
+/// 启动 Web 服务器，错误信息使用指定语言的消息目录
+pub async fn start_web_server_with_catalog(
+    bind_addr: &str,
+    agents: Vec<Agent>,
+    message_tx: broadcast::Sender<Message>,
+    store: Arc<dyn crate::core::store::Store>,
+    catalog: MessageCatalog,
+) -> anyhow::Result<()> {
+    let options = WebServerOptions {
+        catalog,
+        ..WebServerOptions::default()
+    };
+    start_web_server_with_options(bind_addr, agents, message_tx, store, options).await
+}
+
+/// Web 服务器选项
+#[derive(Debug, Clone)]
+pub struct WebServerOptions {
+    /// 错误信息使用的消息目录
+    pub catalog: MessageCatalog,
+    /// 是否保留未版本化的旧路由
+    pub legacy_api_routes: bool,
+    /// 注册和修改密码时使用的密码策略
+    pub password_policy: PasswordPolicy,
+    /// 部署配置
+    pub server: WebServerConfig,
+    /// 公司级消息模板（与 VirtualCompany 共享）
+    pub templates: Arc<RwLock<HashMap<String, String>>>,
+    /// 回应广播（与 VirtualCompany 共享），为空时 Web 层单独广播
+    pub reactions: Option<broadcast::Sender<ReactionEvent>>,
+    /// 工具进度广播（与 VirtualCompany 共享），为空时没有进度可转发
+    pub tool_progress: Option<broadcast::Sender<ToolProgressEvent>>,
+    /// 轮次调度器（与 VirtualCompany 共享）
+    pub scheduler: Option<Arc<TurnScheduler>>,
+    /// Agent 熔断器（与 VirtualCompany 共享），为空时不报告故障状态
+    pub circuit_breakers: Option<Arc<CircuitBreakers>>,
+    /// 消息总线（与 VirtualCompany 共享），为空时用户不作为消息参与者
+    pub message_bus: Option<Arc<MessageBus>>,
+    /// JWT 服务，为空时按 `JWT_SECRET` 环境变量创建
+    pub jwt_service: Option<JwtService>,
+    /// 运行时信息（如 `VirtualCompany::runtime_info`），为空时 `/api/info` 只报告构建和存储
+    pub runtime_info: Option<RuntimeInfo>,
+    /// 附件使用的大对象存储（如 `AppConfig::open_blob_store`），为空时附件接口不可用
+    pub blob_store: Option<Arc<dyn BlobStore>>,
+    /// 管理接口的权限（如 `VirtualCompany::permissions`）
+    pub permissions: PermissionConfig,
+    /// 提示词日志（如 `VirtualCompany::prompt_log`），为空时管理接口报告未启用
+    pub prompt_log: Option<Arc<PromptLog>>,
+    /// 发送消息的大小限制
+    pub message_limits: MessageLimits,
+    /// HR 面试官（如 `VirtualCompany::agent_interviewer`），为空时 `/agents/draft` 返回 503
+    pub agent_interviewer: Option<Arc<AgentInterviewer>>,
+    /// 新建 Agent 的默认 LLM 配置（如 `VirtualCompany::default_llm`），为空时取组织中第一个 Agent 的配置
+    pub default_llm: Option<LLMConfig>,
+    /// Agent 运行器（如 `VirtualCompany::agent_runner`），为空时新建的 Agent 在下次启动公司时开始工作
+    pub agent_runner: Option<Arc<dyn TempAgentRunner>>,
+    /// 临时 Agent 管理（如 `VirtualCompany::temp_agents`），为空时 `/chat/{session_id}/spawn-agent` 返回 503
+    pub temp_agents: Option<Arc<TemporaryAgents>>,
+    /// 运行草稿（如 `VirtualCompany::scratchpad`），为空时 `/runs/{run_id}/scratchpad` 返回 503
+    pub scratchpad: Option<Arc<Scratchpad>>,
+    /// 当前目标（如 `VirtualCompany::goals`），为空时 `GET /agents/{id}` 不返回 `goal`
+    pub goals: Option<Arc<GoalBoard>>,
+    /// 会话生命周期（如 `VirtualCompany::chat_sessions`），为空时 `/chat/{id}/close|reopen` 返回 503
+    pub chat_sessions: Option<Arc<ChatSessions>>,
+    /// 群聊活动统计（如 `VirtualCompany::group_stats`），为空时 `/groups/{id}/stats` 返回 503
+    pub group_stats: Option<Arc<GroupStatsTracker>>,
+    /// 重试注册表（如 `VirtualCompany::retries`），为空时 `/retries/stats` 返回 404
+    pub retries: Option<Arc<Retries>>,
+    /// 部门树缓存（如 `VirtualCompany::org_tree`），为空时使用独立的缓存
+    pub org_tree: Option<Arc<OrgTreeProjection>>,
+    /// 提及候选索引（如 `VirtualCompany::mentions`），为空时使用独立的索引
+    pub mentions: Option<Arc<MentionIndex>>,
+    /// 插话槽（如 `VirtualCompany::nudges`），为空时插话都转为 Urgent 消息
+    pub nudges: Option<Arc<NudgeSlots>>,
+    /// 功能开关（如 `VirtualCompany::features`），为空时只报告编译特性
+    pub features: Option<Arc<FeatureFlags>>,
+    /// 主备部署的领导权（如 `VirtualCompany::leadership`），为空时不限制写请求
+    pub leadership: Option<Arc<LeaderElection>>,
+    /// 工具执行器注册表，为空时 `/tools/{id}/dry-run` 返回 503
+    pub tool_executor: Option<Arc<ToolExecutorRegistry>>,
+    /// 故障注入器，挂载后管理接口可调整规则
+    #[cfg(feature = "chaos")]
+    pub fault_injector: Option<Arc<FaultInjector>>,
+}
+
+impl Default for WebServerOptions {
+    fn default() -> Self {
+        Self {
+            catalog: MessageCatalog::default(),
+            legacy_api_routes: true,
+            password_policy: PasswordPolicy::default(),
+            server: WebServerConfig::default(),
+            templates: Arc::new(RwLock::new(HashMap::new())),
+            reactions: None,
+            tool_progress: None,
+            scheduler: None,
+            circuit_breakers: None,
+            message_bus: None,
+            jwt_service: None,
+            runtime_info: None,
+            blob_store: None,
+            permissions: PermissionConfig::default(),
+            prompt_log: None,
+            message_limits: MessageLimits::default(),
+            agent_interviewer: None,
+            default_llm: None,
+            agent_runner: None,
+            temp_agents: None,
+            scratchpad: None,
+            goals: None,
+            chat_sessions: None,
+            group_stats: None,
+            retries: None,
+            org_tree: None,
+            mentions: None,
+            nudges: None,
+            features: None,
+            leadership: None,
+            tool_executor: None,
+            #[cfg(feature = "chaos")]
+            fault_injector: None,
+        }
+    }
+}
+
+/// 过期幂等键的清理间隔
+const IDEMPOTENCY_CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// 按选项启动 Web 服务器
+pub async fn start_web_server_with_options(
+    bind_addr: &str,
+    agents: Vec<Agent>,
+    message_tx: broadcast::Sender<Message>,
+    store: Arc<dyn crate::core::store::Store>,
+    options: WebServerOptions,
+) -> anyhow::Result<()> {
+    let jwt_service = options.jwt_service.unwrap_or_else(jwt_service_from_env);
+
+    let mut state = AppState::new(agents, message_tx, store, jwt_service)
+        .with_catalog(options.catalog)
+        .with_legacy_api_routes(options.legacy_api_routes)
+        .with_password_policy(options.password_policy)
+        .with_templates(options.templates)
+        .with_health_config(options.server.health.clone())
+        .with_permissions(options.permissions)
+        .with_message_limits(options.message_limits);
+    if let Some(reactions) = options.reactions {
+        state = state.with_reactions(reactions);
+    }
+    if let Some(tool_progress) = options.tool_progress {
+        state = state.with_tool_progress(tool_progress);
+    }
+    if let Some(scheduler) = options.scheduler {
+        state = state.with_scheduler(scheduler);
+    }
+    if let Some(breakers) = options.circuit_breakers {
+        state = state.with_circuit_breakers(breakers);
+    }
+    if let Some(message_bus) = options.message_bus {
+        state = state.with_message_bus(message_bus);
+    }
+    if let Some(runtime_info) = options.runtime_info {
+        state = state.with_runtime_info(runtime_info);
+    }
+    if let Some(blob_store) = options.blob_store {
+        state = state.with_blob_store(blob_store);
+    }
+    if let Some(prompt_log) = options.prompt_log {
+        state = state.with_prompt_log(prompt_log);
+    }
+    if let Some(interviewer) = options.agent_interviewer {
+        state = state.with_agent_interviewer(interviewer);
+    }
+    if let Some(llm) = options.default_llm {
+        state = state.with_default_llm(llm);
+    }
+    if let Some(runner) = options.agent_runner {
+        state = state.with_agent_runner(runner);
+    }
+    if let Some(temp_agents) = options.temp_agents {
+        state = state.with_temp_agents(temp_agents);
+    }
+    if let Some(scratchpad) = options.scratchpad {
+        state = state.with_scratchpad(scratchpad);
+    }
+    if let Some(goals) = options.goals {
+        state = state.with_goals(goals);
+    }
+    if let Some(chat_sessions) = options.chat_sessions {
+        state = state.with_chat_sessions(chat_sessions);
+    }
+    if let Some(group_stats) = options.group_stats {
+        state = state.with_group_stats(group_stats);
+    }
+    if let Some(retries) = options.retries {
+        state = state.with_retries(retries);
+    }
+    if let Some(org_tree) = options.org_tree {
+        state = state.with_org_tree(org_tree);
+    }
+    if let Some(mentions) = options.mentions {
+        state = state.with_mentions(mentions);
+    }
+    if let Some(nudges) = options.nudges {
+        state = state.with_nudges(nudges);
+    }
+    if let Some(features) = options.features {
+        state = state.with_features(features);
+    }
+    if let Some(leadership) = options.leadership {
+        state = state.with_leadership(leadership);
+    }
+    if let Some(tool_executor) = options.tool_executor {
+        state = state.with_tool_executor(tool_executor);
+    }
+    #[cfg(feature = "chaos")]
+    if let Some(injector) = options.fault_injector {
+        state = state.with_fault_injector(injector);
+    }
+    let state = Arc::new(state);
+    state.idempotency.clone().spawn_cleanup(IDEMPOTENCY_CLEANUP_INTERVAL);
+
+    let app = create_router_with_config(state, &options.server);
+    let base_path = options.server.normalized_base_path();
+
+    // 携带连接地址，供登录限流按来源 IP 计数
+    let app = app.into_make_service_with_connect_info::<std::net::SocketAddr>();
+
+    match &options.server.tls {
+        Some(tls) => {
+            use axum::serve::ListenerExt;
+
+            let listener = TlsListener::bind(bind_addr, tls).await?;
+            info!("Web server started on https://{}{}", bind_addr, base_path);
+            // tap_io 让 TLS 监听器也能提供 ConnectInfo<SocketAddr>
+            axum::serve(listener.tap_io(|_| {}), app).await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(bind_addr).await?;
+            info!("Web server started on http://{}{}", bind_addr, base_path);
+            axum::serve(listener, app).await?;
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,134 @@
+//! 内容审核隔离表的复核接口
+//!
+//! 被内容审核拦截的 Agent 输出以提示代替发送，原文保存在隔离表中（见 [`crate::core::moderation`]）。
+//! 拥有 `view_audit` 权限的用户可以查看原文并记录复核结论：
+//!
+//! - `GET /admin/moderation/quarantine?pending=true&limit=50` 列出被拦截的输出，最新的在前
+//! - `GET /admin/moderation/quarantine/{id}` 查看一条
+//! - `POST /admin/moderation/quarantine/{id}/review` 记录结论（`upheld` 或 `overturned`），可以覆盖之前的结论
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use tracing::{error, info};
+
+use crate::domain::moderation::{QuarantineReview, QuarantineVerdict};
+
+use super::permissions::{perm, RequirePermission};
+use super::{AppState, ErrorResponse};
+
+/// 默认返回的条数
+const QUARANTINE_DEFAULT_LIMIT: usize = 50;
+
+/// 最多返回的条数
+const QUARANTINE_MAX_LIMIT: usize = 500;
+
+/// 列表查询参数
+#[derive(Debug, Deserialize)]
+pub struct QuarantineQuery {
+    /// 只返回未复核的
+    #[serde(default)]
+    pub pending: bool,
+    pub limit: Option<usize>,
+}
+
+/// 复核请求
+#[derive(Debug, Deserialize)]
+pub struct ReviewRequest {
+    pub verdict: QuarantineVerdict,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+fn store_error(state: &AppState, error: anyhow::Error) -> Response {
+    error!("Moderation quarantine operation failed: {}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: state.catalog.get("web.quarantine_failed"),
+        }),
+    )
+        .into_response()
+}
+
+fn not_found(state: &AppState, id: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(ErrorResponse {
+            error: state.catalog.format("web.quarantine_not_found", &[("id", id)]),
+        }),
+    )
+        .into_response()
+}
+
+/// 列出被拦截的输出
+pub(super) async fn list_quarantine(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ViewAudit>,
+    Query(query): Query<QuarantineQuery>,
+) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(QUARANTINE_DEFAULT_LIMIT).clamp(1, QUARANTINE_MAX_LIMIT);
+    match state.store.load_quarantined_outputs(query.pending, limit).await {
+        Ok(outputs) => Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "outputs": outputs,
+            }
+        }))
+        .into_response(),
+        Err(e) => store_error(&state, e),
+    }
+}
+
+/// 查看一条被拦截的输出
+pub(super) async fn get_quarantined(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ViewAudit>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match state.store.load_quarantined_output(&id).await {
+        Ok(Some(output)) => Json(serde_json::json!({
+            "success": true,
+            "data": output,
+        }))
+        .into_response(),
+        Ok(None) => not_found(&state, &id),
+        Err(e) => store_error(&state, e),
+    }
+}
+
+/// 记录复核结论
+pub(super) async fn review_quarantined(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::ViewAudit>,
+    Path(id): Path<String>,
+    Json(req): Json<ReviewRequest>,
+) -> impl IntoResponse {
+    let mut output = match state.store.load_quarantined_output(&id).await {
+        Ok(Some(output)) => output,
+        Ok(None) => return not_found(&state, &id),
+        Err(e) => return store_error(&state, e),
+    };
+
+    output.review = Some(QuarantineReview {
+        reviewer: auth.user.username.clone(),
+        verdict: req.verdict,
+        note: req.note.filter(|note| !note.trim().is_empty()),
+        reviewed_at: state.clock.now(),
+    });
+    if let Err(e) = state.store.save_quarantined_output(&output).await {
+        return store_error(&state, e);
+    }
+    info!(target: "audit", "User {} reviewed quarantined output {} of agent {}: {:?}", auth.user.username, id, output.agent_id, req.verdict);
+    Json(serde_json::json!({
+        "success": true,
+        "data": output,
+    }))
+    .into_response()
+}
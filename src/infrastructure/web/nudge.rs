@@ -0,0 +1,107 @@
+//! 插话接口
+//!
+//! `POST /agents/{id}/nudge` 给正在连续调用工具的 Agent 追加指令，下一轮 LLM 请求前注入；
+//! `cancel: true` 结束剩余的工具调用。Agent 不在工具循环中时插话改为一条 Urgent 私聊消息，
+//! 只有 cancel 而没有内容的插话此时返回 409。见 [`crate::core::nudge`]。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::trace::TraceId;
+use super::{task_actor, AppState, ErrorResponse};
+use crate::core::nudge::Nudge;
+
+/// 插话请求
+#[derive(Debug, Deserialize)]
+pub struct NudgeRequest {
+    #[serde(default)]
+    pub content: String,
+    /// 结束剩余的工具调用
+    #[serde(default)]
+    pub cancel: bool,
+}
+
+/// 给 Agent 插话（需要登录）
+pub(super) async fn nudge_agent(
+    State(state): State<Arc<AppState>>,
+    Extension(trace_id): Extension<TraceId>,
+    headers: HeaderMap,
+    Path(agent_id): Path<String>,
+    Json(req): Json<NudgeRequest>,
+) -> Response {
+    let (user_info, actor) = match task_actor(&state, &headers) {
+        Ok(actor) => actor,
+        Err(rejection) => return rejection.into_response(),
+    };
+    let content = req.content.trim();
+    if content.is_empty() && !req.cancel {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.nudge_empty"),
+            }),
+        )
+            .into_response();
+    }
+    match state.store.load_agent(&agent_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: state.catalog.format("web.agent_not_found", &[("agent_id", &agent_id)]),
+                }),
+            )
+                .into_response();
+        }
+        // 存储不可用时照常插话，由 Agent 处理
+        Err(e) => warn!("Failed to load agent {} for nudge: {}", agent_id, e),
+    }
+
+    let nudge = Nudge::new(actor, content).with_cancel(req.cancel);
+    let nudge_id = nudge.id.clone();
+    match state.nudges.offer(&agent_id, nudge) {
+        Ok(position) => {
+            info!("User {} nudged agent {} (position {}, cancel: {})", user_info.username, agent_id, position, req.cancel);
+            Json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "id": nudge_id,
+                    "delivery": "nudge",
+                    "position": position,
+                }
+            }))
+            .into_response()
+        }
+        Err(nudge) if nudge.content.is_empty() => (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.nudge_no_tool_loop", &[("agent_id", &agent_id)]),
+            }),
+        )
+            .into_response(),
+        Err(nudge) => {
+            // 没有进行中的工具循环，按 Urgent 消息送达
+            let message = nudge.into_message(&agent_id).with_trace(&trace_id.0, None);
+            let message_id = message.id.clone();
+            let _ = state.message_tx.send(message);
+            Json(serde_json::json!({
+                "success": true,
+                "data": {
+                    "id": nudge_id,
+                    "delivery": "message",
+                    "message_id": message_id,
+                }
+            }))
+            .into_response()
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! 管理接口的权限检查
+//!
+//! 管理接口用 [`RequirePermission`] 提取器声明所需权限，例如
+//! `RequirePermission<perm::ManageUsers>`：令牌无效或用户在任何范围内都没有该权限时返回 403，
+//! 令牌签发后权限发生变化时返回 401，客户端需要重新登录。针对某个 Agent 的操作还要用
+//! [`PermissionSet::allows`] 检查 Agent 所在部门是否在授权范围内。
+//!
+//! `GET /auth/permissions` 返回当前用户的授权，前端据此调整界面。
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRequestParts, State},
+    http::{request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::warn;
+
+use crate::domain::Organization;
+use crate::infrastructure::auth::{Permission, PermissionSet, UserInfo};
+
+use super::{AppState, ErrorResponse};
+
+/// 以类型标记所需权限，供 [`RequirePermission`] 使用
+pub trait PermissionMarker: Send + Sync + 'static {
+    const PERMISSION: Permission;
+}
+
+/// 每个 [`Permission`] 对应的类型标记
+pub mod perm {
+    use super::{Permission, PermissionMarker};
+
+    macro_rules! markers {
+        ($($name:ident),* $(,)?) => {
+            $(
+                #[doc = concat!("需要 [`Permission::", stringify!($name), "`]")]
+                pub struct $name;
+
+                impl PermissionMarker for $name {
+                    const PERMISSION: Permission = Permission::$name;
+                }
+            )*
+        };
+    }
+
+    markers!(
+        ManageAgents,
+        InspectAgents,
+        ManageDepartments,
+        ManageUsers,
+        ManageInviteCodes,
+        ViewAudit,
+        ExecuteTools,
+        ManageWatchdog,
+        ManageTasks,
+        ManageGroups,
+        ManageAttachments,
+        ManageStorage,
+        ManageChaos,
+        ManagePromptLog,
+        ManageFeatures,
+    );
+}
+
+/// 已登录且至少在某个范围内拥有权限 `P` 的用户
+pub struct RequirePermission<P> {
+    pub user: UserInfo,
+    pub permissions: PermissionSet,
+    marker: PhantomData<P>,
+}
+
+impl<P: PermissionMarker> RequirePermission<P> {
+    /// 能否对 `department` 中的对象行使该权限
+    pub fn allows(&self, department: Option<&str>) -> bool {
+        self.permissions.allows(P::PERMISSION, department)
+    }
+}
+
+impl<P: PermissionMarker> FromRequestParts<Arc<AppState>> for RequirePermission<P> {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let (user, permissions) = authenticate(state, &parts.headers).await.map_err(|status| rejection(state, status))?;
+        if !permissions.has(P::PERMISSION) {
+            return Err(rejection(state, StatusCode::FORBIDDEN));
+        }
+        Ok(Self {
+            user,
+            permissions,
+            marker: PhantomData,
+        })
+    }
+}
+
+/// 校验令牌并解析当前权限；令牌无效时为 403（与旧的管理员检查一致），权限已变化时为 401
+pub(super) async fn authenticate(state: &AppState, headers: &HeaderMap) -> Result<(UserInfo, PermissionSet), StatusCode> {
+    let claims = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.decode_claims(token).ok())
+        .ok_or(StatusCode::FORBIDDEN)?;
+    let issued_hash = claims.permissions_hash.clone();
+    let user = UserInfo::from(claims);
+    let permissions = resolve_permissions(state, &user).await;
+    if issued_hash.is_some_and(|hash| hash != permissions.digest()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok((user, permissions))
+}
+
+/// 按当前组织架构（部门树缓存）和存储中的职位解析用户的权限
+///
+/// 令牌中的职位是签发时的快照，职位调整后不再可信；存储中没有该用户时（外部签发的令牌）才使用令牌中的职位。
+/// 组织架构读取失败时只按职位和用户授权计算。
+pub(super) async fn resolve_permissions(state: &AppState, user: &UserInfo) -> PermissionSet {
+    let org = match state.org_tree.organization(state.store.as_ref()).await {
+        Ok(org) => org,
+        Err(e) => {
+            warn!("Failed to load organization for permissions of {}: {}", user.username, e);
+            Arc::new(Organization::new())
+        }
+    };
+    let stored = match state.store.load_user_by_username(&user.username).await {
+        Ok(stored) => stored.filter(|stored| stored.id == user.id),
+        Err(e) => {
+            warn!("Failed to load user {} for permissions: {}", user.username, e);
+            None
+        }
+    };
+    match stored {
+        Some(stored) => {
+            let current = UserInfo {
+                position: format!("{:?}", stored.position),
+                ..user.clone()
+            };
+            state.permissions.resolve(&current, &org)
+        }
+        None => state.permissions.resolve(user, &org),
+    }
+}
+
+/// 权限检查失败的响应
+pub(super) fn rejection(state: &AppState, status: StatusCode) -> Response {
+    let key = if status == StatusCode::UNAUTHORIZED {
+        "web.permissions_changed"
+    } else {
+        "web.insufficient_permissions"
+    };
+    (status, Json(ErrorResponse { error: state.catalog.get(key) })).into_response()
+}
+
+/// 当前用户的授权
+pub async fn get_my_permissions(State(state): State<Arc<AppState>>, headers: HeaderMap) -> Response {
+    let user = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    let Some(user) = user else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            }),
+        )
+            .into_response();
+    };
+
+    let permissions = resolve_permissions(&state, &user).await;
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "position": user.position,
+            "permissions": permissions.permissions(),
+            "grants": permissions.grants().collect::<Vec<_>>(),
+            "hash": permissions.digest(),
+        }
+    }))
+    .into_response()
+}
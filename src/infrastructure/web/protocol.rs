@@ -0,0 +1,267 @@
+//! WebSocket 帧定义
+//!
+//! 服务端和 [`ImitatorClient`](crate::infrastructure::client::ImitatorClient) 共用这里的类型，
+//! 帧结构只在一处定义，两端不会漂移。服务端帧统一为：
+//!
+//! ```json
+//! { "v": 1, "type": "message", "data": { ... } }
+//! ```
+//!
+//! 默认以 JSON 文本帧发送。客户端可以先发 `hello` 帧协商 [`FrameEncoding`]：`deflate`
+//! 把超过阈值的帧压缩后以二进制帧发送（小帧仍为文本），`msgpack`（需要 `msgpack` 特性）
+//! 把所有帧编码为 MessagePack 二进制帧。服务端回复 `welcome` 后切换编码。
+
+use std::io::{Read, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use super::envelope::WS_PROTOCOL_VERSION;
+use crate::domain::scratchpad::ScratchpadEntry;
+use crate::domain::{Message, MessagePriority, MessageReaction, MessageTarget, MessageTranslation, ToolProgressEvent};
+
+/// 客户端发给服务端的帧
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    #[serde(rename = "send_message")]
+    SendMessage {
+        from: String,
+        /// Agent ID，或 `group:{id}` 表示群聊
+        to: String,
+        content: String,
+        #[serde(default)]
+        priority: MessagePriority,
+    },
+    #[serde(rename = "ping")]
+    Ping,
+    /// 订阅工具进度，`agent_id` 为空时接收所有 Agent 的
+    #[serde(rename = "subscribe_tool_progress")]
+    SubscribeToolProgress {
+        #[serde(default)]
+        agent_id: Option<String>,
+    },
+    /// 订阅 Agent 运行草稿（需要 `inspect_agents`），`agent_id` 为空时接收有权查看的所有 Agent 的
+    #[serde(rename = "subscribe_scratchpad")]
+    SubscribeScratchpad {
+        #[serde(default)]
+        agent_id: Option<String>,
+    },
+    /// 协商服务端帧的编码，`min_compress_bytes` 为空时使用 [`DEFAULT_MIN_COMPRESS_BYTES`]
+    #[serde(rename = "hello")]
+    Hello {
+        #[serde(default)]
+        encoding: FrameEncoding,
+        #[serde(default)]
+        min_compress_bytes: Option<usize>,
+    },
+}
+
+/// 小于该字节数的帧不压缩
+pub const DEFAULT_MIN_COMPRESS_BYTES: usize = 1024;
+
+/// 服务端帧的编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameEncoding {
+    /// JSON 文本帧
+    #[default]
+    Json,
+    /// 超过阈值的 JSON 经 DEFLATE 压缩后以二进制帧发送
+    Deflate,
+    /// MessagePack 二进制帧，需要 `msgpack` 特性
+    Msgpack,
+}
+
+impl FrameEncoding {
+    /// 当前编译是否支持该编码
+    pub fn is_supported(&self) -> bool {
+        match self {
+            FrameEncoding::Json | FrameEncoding::Deflate => true,
+            FrameEncoding::Msgpack => cfg!(feature = "msgpack"),
+        }
+    }
+}
+
+/// 编码后的帧
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodedFrame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl EncodedFrame {
+    pub fn len(&self) -> usize {
+        match self {
+            EncodedFrame::Text(text) => text.len(),
+            EncodedFrame::Binary(bytes) => bytes.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 按连接协商的编码把服务端帧编码为 WebSocket 帧
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCodec {
+    pub encoding: FrameEncoding,
+    pub min_compress_bytes: usize,
+}
+
+impl Default for FrameCodec {
+    fn default() -> Self {
+        Self::new(FrameEncoding::Json)
+    }
+}
+
+impl FrameCodec {
+    pub fn new(encoding: FrameEncoding) -> Self {
+        Self {
+            encoding,
+            min_compress_bytes: DEFAULT_MIN_COMPRESS_BYTES,
+        }
+    }
+
+    pub fn with_min_compress_bytes(mut self, min_compress_bytes: usize) -> Self {
+        self.min_compress_bytes = min_compress_bytes;
+        self
+    }
+
+    pub fn encode(&self, frame: &ServerFrame) -> EncodedFrame {
+        match self.encoding {
+            FrameEncoding::Json => EncodedFrame::Text(frame.to_json()),
+            FrameEncoding::Deflate => {
+                let json = frame.to_json();
+                if json.len() < self.min_compress_bytes {
+                    return EncodedFrame::Text(json);
+                }
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                match encoder.write_all(json.as_bytes()).and_then(|_| encoder.finish()) {
+                    Ok(bytes) => EncodedFrame::Binary(bytes),
+                    Err(_) => EncodedFrame::Text(json),
+                }
+            }
+            #[cfg(feature = "msgpack")]
+            FrameEncoding::Msgpack => match rmp_serde::to_vec_named(frame) {
+                Ok(bytes) => EncodedFrame::Binary(bytes),
+                Err(_) => EncodedFrame::Text(frame.to_json()),
+            },
+            #[cfg(not(feature = "msgpack"))]
+            FrameEncoding::Msgpack => EncodedFrame::Text(frame.to_json()),
+        }
+    }
+
+    /// 解码服务端帧；文本帧总是 JSON
+    pub fn decode(&self, frame: &EncodedFrame) -> Result<ServerFrame, FrameDecodeError> {
+        match frame {
+            EncodedFrame::Text(text) => Ok(serde_json::from_str(text)?),
+            EncodedFrame::Binary(bytes) => match self.encoding {
+                FrameEncoding::Deflate => {
+                    let mut json = Vec::new();
+                    DeflateDecoder::new(bytes.as_slice()).read_to_end(&mut json)?;
+                    Ok(serde_json::from_slice(&json)?)
+                }
+                #[cfg(feature = "msgpack")]
+                FrameEncoding::Msgpack => {
+                    rmp_serde::from_slice(bytes).map_err(|e| FrameDecodeError::Msgpack(e.to_string()))
+                }
+                _ => Err(FrameDecodeError::UnexpectedBinary),
+            },
+        }
+    }
+}
+
+/// 服务端帧解码失败
+#[derive(Debug, thiserror::Error)]
+pub enum FrameDecodeError {
+    #[error("invalid JSON frame: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid compressed frame: {0}")]
+    Deflate(#[from] std::io::Error),
+    #[error("invalid MessagePack frame: {0}")]
+    Msgpack(String),
+    #[error("binary frame without a negotiated binary encoding")]
+    UnexpectedBinary,
+}
+
+/// 消息推送帧的数据
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageFrame {
+    pub id: String,
+    pub from: String,
+    /// Agent ID，或 `group:{id}` 表示群聊
+    pub to: String,
+    pub content: String,
+    pub timestamp: i64,
+    /// 消息语言与读者不同时附上的译文，`content` 保持原文
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<MessageTranslation>,
+}
+
+impl From<&Message> for MessageFrame {
+    fn from(message: &Message) -> Self {
+        let to = match &message.to {
+            MessageTarget::Direct(id) => id.clone(),
+            MessageTarget::Group(id) => format!("group:{}", id),
+        };
+
+        Self {
+            id: message.id.clone(),
+            from: message.from.clone(),
+            to,
+            content: message.content.clone(),
+            timestamp: message.timestamp,
+            translation: None,
+        }
+    }
+}
+
+/// 服务端推送的事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerEvent {
+    Message { data: MessageFrame },
+    ReactionAdded { data: MessageReaction },
+    ReactionRemoved { data: MessageReaction },
+    ToolProgress { data: ToolProgressEvent },
+    Scratchpad { data: ScratchpadEntry },
+    /// 确认 `hello` 协商的编码，之后的帧按该编码发送
+    Welcome {
+        encoding: FrameEncoding,
+        min_compress_bytes: usize,
+    },
+    Pong,
+    Error { message: String },
+}
+
+/// 带协议版本的服务端帧
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerFrame {
+    pub v: u32,
+    #[serde(flatten)]
+    pub event: ServerEvent,
+}
+
+impl ServerFrame {
+    /// 当前协议版本的帧
+    pub fn new(event: ServerEvent) -> Self {
+        Self {
+            v: WS_PROTOCOL_VERSION,
+            event,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl From<ServerEvent> for ServerFrame {
+    fn from(event: ServerEvent) -> Self {
+        Self::new(event)
+    }
+}
@@ -0,0 +1,63 @@
+//! Agent 运行草稿查看接口
+//!
+//! `GET /runs/{run_id}/scratchpad` 返回一次运行（轮次的 trace ID）的草稿，最早的在前，需要
+//! `inspect_agents` 权限且 Agent 所在部门在授权范围内。实时推送见 WebSocket 的 `subscribe_scratchpad`。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::error;
+
+use super::permissions::{self, perm, RequirePermission};
+use super::{agent_department, AppState, ErrorResponse};
+
+/// 查看一次运行的草稿（需要 `inspect_agents`）
+pub(super) async fn get_run_scratchpad(
+    State(state): State<Arc<AppState>>,
+    auth: RequirePermission<perm::InspectAgents>,
+    Path(run_id): Path<String>,
+) -> Response {
+    let Some(scratchpad) = &state.scratchpad else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.scratchpad_unavailable"),
+            }),
+        )
+            .into_response();
+    };
+
+    let entries = match scratchpad.entries(&run_id).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!("Failed to load scratchpad of run {}: {}", run_id, e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: state.catalog.get("web.scratchpad_load_failed"),
+                }),
+            )
+                .into_response();
+        }
+    };
+    // 一次运行只属于一个 Agent
+    if let Some(entry) = entries.first() {
+        if !auth.allows(agent_department(&state, &entry.agent_id).await.as_deref()) {
+            return permissions::rejection(&state, StatusCode::FORBIDDEN);
+        }
+    }
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "run_id": run_id,
+            "entries": entries,
+        }
+    }))
+    .into_response()
+}
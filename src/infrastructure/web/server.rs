@@ -0,0 +1,249 @@
+//! Web 服务器部署配置
+//!
+//! 部署到正式域名时需要收紧的选项：CORS 白名单、请求体大小上限、请求超时、
+//! TLS 证书，以及在反向代理后运行时的路径前缀。
+
+use std::io;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use axum::http::{HeaderValue, StatusCode};
+use axum::extract::DefaultBodyLimit;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+use tracing::{debug, warn};
+
+use super::health::HealthConfig;
+
+/// TLS 握手超时
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// TLS 证书配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// PEM 格式的证书链
+    pub cert_path: PathBuf,
+    /// PEM 格式的私钥
+    pub key_path: PathBuf,
+}
+
+/// 可单独关闭的路由组，嵌入到已有应用时避免与宿主的路由冲突
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RouteGroups {
+    /// `/auth/*` 与 `/users/me/*`
+    pub auth: bool,
+    /// `/admin/*`
+    pub admin: bool,
+    /// `/chat/*` 与群聊管理（`/groups/{id}/admins|members|mutes`）
+    pub chat: bool,
+    /// WebSocket（`/api/v1/ws`、`/ws`）
+    pub ws: bool,
+}
+
+impl Default for RouteGroups {
+    fn default() -> Self {
+        Self {
+            auth: true,
+            admin: true,
+            chat: true,
+            ws: true,
+        }
+    }
+}
+
+impl RouteGroups {
+    /// 只保留公司 API（Agent、消息、组织架构等），由宿主应用负责认证和管理
+    pub fn api_only() -> Self {
+        Self {
+            auth: false,
+            admin: false,
+            chat: false,
+            ws: false,
+        }
+    }
+
+    /// 按名称关闭路由组（`auth`、`admin`、`chat`、`ws`），未知名称返回 false
+    pub fn disable(&mut self, group: &str) -> bool {
+        let flag = match group.trim() {
+            "auth" => &mut self.auth,
+            "admin" => &mut self.admin,
+            "chat" => &mut self.chat,
+            "ws" => &mut self.ws,
+            _ => return false,
+        };
+        *flag = false;
+        true
+    }
+}
+
+/// Web 服务器配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct WebServerConfig {
+    /// 允许跨域访问的来源，为空时允许任意来源（开发模式）
+    pub allowed_origins: Vec<String>,
+    /// 请求体大小上限（字节），超出返回 413
+    pub max_body_bytes: usize,
+    /// 请求超时（秒），0 表示不限制
+    pub request_timeout_secs: u64,
+    /// 启用 HTTPS
+    pub tls: Option<TlsConfig>,
+    /// 路径前缀，如 `/imitatort`，用于挂在反向代理的子路径下
+    pub base_path: String,
+    /// 就绪探针配置
+    pub health: HealthConfig,
+    /// 启用的路由组
+    pub routes: RouteGroups,
+}
+
+impl Default for WebServerConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            max_body_bytes: 2 * 1024 * 1024,
+            request_timeout_secs: 30,
+            tls: None,
+            base_path: String::new(),
+            health: HealthConfig::default(),
+            routes: RouteGroups::default(),
+        }
+    }
+}
+
+impl WebServerConfig {
+    /// 规范化后的路径前缀：以 `/` 开头、不以 `/` 结尾，根路径为空串
+    pub fn normalized_base_path(&self) -> String {
+        let trimmed = self.base_path.trim().trim_matches('/');
+        if trimmed.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", trimmed)
+        }
+    }
+
+    /// 按配置构建 CORS 层
+    pub fn cors_layer(&self) -> CorsLayer {
+        if self.allowed_origins.is_empty() {
+            return CorsLayer::permissive();
+        }
+
+        let origins: Vec<HeaderValue> = self
+            .allowed_origins
+            .iter()
+            .filter_map(|origin| match HeaderValue::from_str(origin.trim()) {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    warn!("Ignoring invalid CORS origin: {}", origin);
+                    None
+                }
+            })
+            .collect();
+
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+
+    /// 套上 CORS、请求体上限、超时和路径前缀
+    pub fn apply(&self, router: Router) -> Router {
+        // 由 RequestBodyLimitLayer 统一限制，关闭 axum 提取器自带的 2MB 上限
+        let mut router = router
+            .layer(DefaultBodyLimit::disable())
+            .layer(RequestBodyLimitLayer::new(self.max_body_bytes));
+
+        if self.request_timeout_secs > 0 {
+            router = router.layer(TimeoutLayer::with_status_code(
+                StatusCode::REQUEST_TIMEOUT,
+                Duration::from_secs(self.request_timeout_secs),
+            ));
+        }
+
+        let router = router.layer(self.cors_layer());
+
+        match self.normalized_base_path().as_str() {
+            "" => router,
+            base => Router::new().nest(base, router),
+        }
+    }
+}
+
+/// 从 PEM 文件加载 TLS 配置
+pub fn load_tls_acceptor(tls: &TlsConfig) -> Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(&tls.cert_path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read TLS certificate {}", tls.cert_path.display()))?;
+    let key = PrivateKeyDer::from_pem_file(&tls.key_path)
+        .with_context(|| format!("Failed to read TLS key {}", tls.key_path.display()))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .context("Unsupported TLS protocol versions")?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid TLS certificate or key")?;
+
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// 在 TCP 连接上完成 TLS 握手的监听器
+pub struct TlsListener {
+    inner: TcpListener,
+    acceptor: TlsAcceptor,
+}
+
+impl TlsListener {
+    pub fn new(inner: TcpListener, acceptor: TlsAcceptor) -> Self {
+        Self { inner, acceptor }
+    }
+
+    /// 绑定地址并加载证书
+    pub async fn bind(addr: &str, tls: &TlsConfig) -> Result<Self> {
+        let acceptor = load_tls_acceptor(tls)?;
+        let inner = TcpListener::bind(addr).await?;
+        Ok(Self::new(inner, acceptor))
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        loop {
+            let (stream, addr) = match self.inner.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    warn!("Failed to accept connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+
+            // 握手失败只影响当前连接
+            match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, self.acceptor.accept(stream)).await {
+                Ok(Ok(tls)) => return (tls, addr),
+                Ok(Err(e)) => debug!("TLS handshake with {} failed: {}", addr, e),
+                Err(_) => debug!("TLS handshake with {} timed out", addr),
+            }
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        self.inner.local_addr()
+    }
+}
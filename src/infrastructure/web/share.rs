@@ -0,0 +1,464 @@
+//! 群聊分享令牌
+//!
+//! 拥有 `manage_groups` 权限的用户或群管理员可以为一个群聊生成只读分享令牌，发给没有账号的人查看：
+//!
+//! - `GET /shared/{token}` 返回群聊消息（带发送者名称），`/shared/{token}/stream` 以 SSE 推送新消息
+//! - 令牌有过期时间，可以限制只看最新的 N 条消息；撤销后立即失效，正在进行的 SSE 流随之关闭
+//! - 存储中只保存令牌哈希；每次访问都计数并写入审计日志
+//! - 分享令牌是独立的凭证类型，作为 Bearer token 使用时所有接口都拒绝
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::broadcast;
+use tracing::{error, info};
+
+use crate::core::transcript::participant_names;
+use crate::domain::share_token::ShareToken;
+use crate::domain::user::user_principal;
+use crate::domain::{Group, Message, MessageTarget};
+use crate::infrastructure::auth::{is_share_token, Permission, SHARE_TOKEN_PREFIX};
+
+use super::{permissions, AppState, ErrorResponse};
+
+/// 分享令牌最长有效期（秒）
+pub const MAX_SHARE_TTL_SECS: u64 = 30 * 86_400;
+
+/// 分享页面最多返回的消息数
+pub const MAX_SHARED_MESSAGES: usize = 500;
+
+/// SSE 流检查令牌是否仍然有效的间隔
+pub const SHARE_STREAM_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 创建分享令牌请求
+#[derive(Debug, Deserialize)]
+pub struct CreateShareRequest {
+    /// 有效期（秒）
+    pub expires_in_secs: u64,
+    /// 只分享最新的 N 条消息
+    pub message_window: Option<usize>,
+}
+
+/// 令牌哈希，即存储中的令牌ID
+pub fn share_token_id(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+/// 生成新令牌
+fn generate_share_token() -> String {
+    let mut bytes = [0u8; 24];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    format!("{}{}", SHARE_TOKEN_PREFIX, hex(&bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 拒绝携带分享令牌作为 Bearer token 的请求，分享令牌只能用于 `/shared/{token}`
+pub async fn share_token_guard(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let bearer = request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "));
+    if bearer.is_some_and(is_share_token) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.share_token_scope"),
+            }),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// 为群聊创建分享令牌（拥有 `manage_groups` 的用户或群管理员），令牌只在响应中出现一次
+pub async fn create_share_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+    Json(req): Json<CreateShareRequest>,
+) -> impl IntoResponse {
+    let actor = match share_manager(&state, &headers, &group_id).await {
+        Ok(actor) => actor,
+        Err(error) => return error,
+    };
+    if req.expires_in_secs == 0 || req.expires_in_secs > MAX_SHARE_TTL_SECS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: state
+                    .catalog
+                    .format("web.share_expiry_invalid", &[("max", &MAX_SHARE_TTL_SECS.to_string())]),
+            }),
+        )
+            .into_response();
+    }
+
+    let token = generate_share_token();
+    let now = state.clock.now();
+    let share = ShareToken {
+        id: share_token_id(&token),
+        group_id: group_id.clone(),
+        created_by: actor.clone(),
+        created_at: now,
+        expires_at: now + req.expires_in_secs as i64,
+        message_window: req.message_window.map(|w| w.clamp(1, MAX_SHARED_MESSAGES)),
+        revoked_at: None,
+        access_count: 0,
+        last_accessed_at: None,
+    };
+    if let Err(e) = state.store.save_share_token(&share).await {
+        return database_error(&state, &group_id, e);
+    }
+    info!(target: "audit", share_id = %share.id, group_id = %group_id, actor = %actor, expires_at = share.expires_at, "Share token created");
+
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "success": true,
+            "data": {
+                "token": token,
+                "share": share,
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// 列出群聊的分享令牌（不含令牌本身）
+pub async fn list_share_tokens(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(group_id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(error) = share_manager(&state, &headers, &group_id).await {
+        return error;
+    }
+    match state.store.load_share_tokens(&group_id).await {
+        Ok(tokens) => Json(serde_json::json!({
+            "success": true,
+            "data": tokens,
+        }))
+        .into_response(),
+        Err(e) => database_error(&state, &group_id, e),
+    }
+}
+
+/// 撤销分享令牌，正在进行的 SSE 流在下次检查时关闭
+pub async fn revoke_share_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((group_id, share_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let actor = match share_manager(&state, &headers, &group_id).await {
+        Ok(actor) => actor,
+        Err(error) => return error,
+    };
+    let mut share = match state.store.load_share_token(&share_id).await {
+        Ok(Some(share)) if share.group_id == group_id => share,
+        Ok(_) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: state.catalog.format("web.share_not_found", &[("share_id", &share_id)]),
+                }),
+            )
+                .into_response();
+        }
+        Err(e) => return database_error(&state, &group_id, e),
+    };
+
+    if share.revoked_at.is_none() {
+        share.revoked_at = Some(state.clock.now());
+        if let Err(e) = state.store.save_share_token(&share).await {
+            return database_error(&state, &group_id, e);
+        }
+        info!(target: "audit", share_id = %share.id, group_id = %group_id, actor = %actor, "Share token revoked");
+    }
+    Json(serde_json::json!({
+        "success": true,
+        "data": share,
+    }))
+    .into_response()
+}
+
+/// 用分享令牌读取群聊消息
+pub async fn get_shared_transcript(State(state): State<Arc<AppState>>, Path(token): Path<String>) -> impl IntoResponse {
+    let (share, group) = match open_share(&state, &token).await {
+        Ok(opened) => opened,
+        Err(error) => return error,
+    };
+    let messages = match shared_messages(&state, &share).await {
+        Ok(messages) => messages,
+        Err(e) => return database_error(&state, &share.group_id, e),
+    };
+    let names = sender_names(&state).await;
+
+    Json(serde_json::json!({
+        "success": true,
+        "data": {
+            "group_id": group.id,
+            "group_name": group.name,
+            "expires_at": share.expires_at,
+            "messages": messages.iter().map(|m| shared_message(m, &names)).collect::<Vec<_>>(),
+        }
+    }))
+    .into_response()
+}
+
+/// 用分享令牌订阅群聊（SSE）：先推送可见的历史消息，再推送新消息；
+/// 令牌过期或被撤销时发送 `revoked` 事件并关闭
+pub async fn stream_shared_transcript(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> Response {
+    let (share, group) = match open_share(&state, &token).await {
+        Ok(opened) => opened,
+        Err(error) => return error,
+    };
+    // 先订阅再读历史，避免两者之间的消息丢失
+    let live = match state.message_bus.as_ref().and_then(|bus| bus.subscribe_group(&group.id)) {
+        Some(rx) => rx,
+        None => state.message_tx.subscribe(),
+    };
+    let backlog = match shared_messages(&state, &share).await {
+        Ok(messages) => messages,
+        Err(e) => return database_error(&state, &share.group_id, e),
+    };
+    let names = sender_names(&state).await;
+
+    let backlog_events: Vec<Result<Event, Infallible>> =
+        backlog.iter().map(|m| Ok(message_event(m, &names))).collect();
+    let seen: Vec<String> = backlog.into_iter().map(|m| m.id).collect();
+    let live_events = live_stream(state, share, live, names, seen);
+
+    Sse::new(stream::iter(backlog_events).chain(live_events))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+struct LiveState {
+    state: Arc<AppState>,
+    share: ShareToken,
+    rx: broadcast::Receiver<Message>,
+    names: HashMap<String, String>,
+    seen: Vec<String>,
+    ticker: tokio::time::Interval,
+    closed: bool,
+}
+
+fn live_stream(
+    state: Arc<AppState>,
+    share: ShareToken,
+    rx: broadcast::Receiver<Message>,
+    names: HashMap<String, String>,
+    seen: Vec<String>,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let live = LiveState {
+        state,
+        share,
+        rx,
+        names,
+        seen,
+        ticker: tokio::time::interval(SHARE_STREAM_CHECK_INTERVAL),
+        closed: false,
+    };
+    stream::unfold(live, |mut live| async move {
+        if live.closed {
+            return None;
+        }
+        loop {
+            tokio::select! {
+                received = live.rx.recv() => match received {
+                    Ok(message) => {
+                        let in_group = matches!(&message.to, MessageTarget::Group(id) if *id == live.share.group_id);
+                        if !in_group || live.seen.contains(&message.id) {
+                            continue;
+                        }
+                        if !share_still_active(&live.state, &live.share.id).await {
+                            live.closed = true;
+                            return Some((Ok(revoked_event()), live));
+                        }
+                        let event = message_event(&message, &live.names);
+                        return Some((Ok(event), live));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                _ = live.ticker.tick() => {
+                    if !share_still_active(&live.state, &live.share.id).await {
+                        live.closed = true;
+                        return Some((Ok(revoked_event()), live));
+                    }
+                }
+            }
+        }
+    })
+}
+
+fn message_event(message: &Message, names: &HashMap<String, String>) -> Event {
+    Event::default()
+        .event("message")
+        .id(message.id.clone())
+        .data(shared_message(message, names).to_string())
+}
+
+fn revoked_event() -> Event {
+    Event::default().event("revoked").data("{}")
+}
+
+/// 令牌是否仍然有效；读取失败时按无效处理
+async fn share_still_active(state: &AppState, share_id: &str) -> bool {
+    match state.store.load_share_token(share_id).await {
+        Ok(Some(share)) => share.is_active(state.clock.now()),
+        _ => false,
+    }
+}
+
+/// 校验令牌并记录一次访问
+async fn open_share(state: &AppState, token: &str) -> Result<(ShareToken, Group), Response> {
+    let invalid = || {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.share_token_invalid"),
+            }),
+        )
+            .into_response()
+    };
+    if !is_share_token(token) {
+        return Err(invalid());
+    }
+    let now = state.clock.now();
+    let mut share = match state.store.load_share_token(&share_token_id(token)).await {
+        Ok(Some(share)) if share.is_active(now) => share,
+        Ok(_) => return Err(invalid()),
+        Err(e) => return Err(database_error(state, "", e)),
+    };
+    let Some(group) = find_group(state, &share.group_id).await else {
+        return Err(invalid());
+    };
+
+    share.access_count += 1;
+    share.last_accessed_at = Some(now);
+    if let Err(e) = state.store.save_share_token(&share).await {
+        return Err(database_error(state, &share.group_id, e));
+    }
+    info!(target: "audit", share_id = %share.id, group_id = %share.group_id, access_count = share.access_count, "Shared transcript accessed");
+    Ok((share, group))
+}
+
+/// 令牌可见的消息，按时间升序
+async fn shared_messages(state: &AppState, share: &ShareToken) -> anyhow::Result<Vec<Message>> {
+    let limit = share.message_window.unwrap_or(MAX_SHARED_MESSAGES);
+    let mut messages = state.store.load_messages_by_group(&share.group_id, limit).await?;
+    messages.reverse();
+    Ok(messages)
+}
+
+fn shared_message(message: &Message, names: &HashMap<String, String>) -> serde_json::Value {
+    serde_json::json!({
+        "id": message.id,
+        "sender_id": message.from,
+        "sender_name": names.get(&message.from).cloned().unwrap_or_else(|| message.from.clone()),
+        "content": message.content,
+        "timestamp": message.timestamp,
+    })
+}
+
+async fn sender_names(state: &AppState) -> HashMap<String, String> {
+    let agents = state.agents();
+    match participant_names(state.store.as_ref(), &agents).await {
+        Ok(names) => names,
+        Err(e) => {
+            error!("Failed to load participant names for shared transcript: {}", e);
+            agents.iter().map(|a| (a.id.clone(), a.name.clone())).collect()
+        }
+    }
+}
+
+async fn find_group(state: &AppState, group_id: &str) -> Option<Group> {
+    if let Some(bus) = &state.message_bus {
+        if let Some(group) = bus.get_group(group_id).await {
+            return Some(group);
+        }
+    }
+    match state.store.load_groups().await {
+        Ok(groups) => groups.into_iter().find(|g| g.id == group_id),
+        Err(e) => {
+            error!("Failed to load groups for share of {}: {}", group_id, e);
+            None
+        }
+    }
+}
+
+/// 可以管理群聊分享的用户（拥有 `manage_groups` 的用户或群管理员），返回其参与者ID
+async fn share_manager(state: &AppState, headers: &HeaderMap, group_id: &str) -> Result<String, Response> {
+    let user_info = headers
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|auth_str| auth_str.strip_prefix("Bearer "))
+        .and_then(|token| state.jwt_service.validate_token(token).ok());
+    let Some(user_info) = user_info else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.unauthorized"),
+            }),
+        )
+            .into_response());
+    };
+    let Some(group) = find_group(state, group_id).await else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.group_not_found", &[("group_id", group_id)]),
+            }),
+        )
+            .into_response());
+    };
+
+    let actor = user_principal(&user_info.id);
+    if !group.is_admin(&actor)
+        && !permissions::resolve_permissions(state, &user_info).await.has_global(Permission::ManageGroups)
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse {
+                error: state.catalog.format("web.share_forbidden", &[("group_id", group_id)]),
+            }),
+        )
+            .into_response());
+    }
+    Ok(actor)
+}
+
+fn database_error(state: &AppState, group_id: &str, error: anyhow::Error) -> Response {
+    error!("Share token operation for group {} failed: {}", group_id, error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            error: state.catalog.get("web.database_error"),
+        }),
+    )
+        .into_response()
+}
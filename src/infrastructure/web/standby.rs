@@ -0,0 +1,67 @@
+//! 主备部署中的备用节点
+//!
+//! 挂载领导权（[`AppState::with_leadership`]）后，写请求先用令牌确认本节点仍是领导者：备用节点或
+//! 已失去租约的旧领导者返回 409，响应头 [`LEADER_HEADER`] 指明当前领导者，由前端或负载均衡器
+//! 转发。读请求和登录在两个节点上都可用。`GET /leadership` 返回本节点的领导权状态。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use tracing::warn;
+
+use crate::core::leadership::LeadershipError;
+
+use super::{AppState, ErrorResponse};
+
+/// 409 响应中指明当前领导者的响应头
+pub const LEADER_HEADER: &str = "x-leader";
+
+/// 不改变状态、备用节点也可以处理的请求
+fn is_read_only(request: &Request) -> bool {
+    matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        || request.uri().path().ends_with("/auth/login")
+}
+
+/// 备用节点拒绝写请求
+pub async fn standby_guard(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Response {
+    let Some(leadership) = &state.leadership else {
+        return next.run(request).await;
+    };
+    if is_read_only(&request) {
+        return next.run(request).await;
+    }
+    let Err(e) = leadership.check_fence().await else {
+        return next.run(request).await;
+    };
+    if e.downcast_ref::<LeadershipError>().is_none() {
+        warn!("Failed to check leadership lease: {}", e);
+    }
+
+    let leader = leadership.leader().filter(|leader| leader != leadership.node_id());
+    let message = match &leader {
+        Some(leader) => state.catalog.format("web.standby_read_only", &[("leader", leader)]),
+        None => state.catalog.get("web.standby_no_leader"),
+    };
+    let mut response = (StatusCode::CONFLICT, Json(ErrorResponse { error: message })).into_response();
+    if let Some(value) = leader.and_then(|leader| HeaderValue::from_str(&leader).ok()) {
+        response.headers_mut().insert(LEADER_HEADER, value);
+    }
+    response
+}
+
+/// 本节点的领导权状态；未启用主备时 `enabled` 为 false
+pub async fn get_leadership(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match &state.leadership {
+        Some(leadership) => Json(serde_json::json!({
+            "enabled": true,
+            "status": leadership.status(),
+        })),
+        None => Json(serde_json::json!({ "enabled": false })),
+    }
+}
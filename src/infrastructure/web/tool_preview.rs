@@ -0,0 +1,76 @@
+//! 工具预演接口
+//!
+//! `POST /tools/{id}/dry-run` 以某个 Agent 的身份预演一次工具调用，返回将会发生什么（如消息的收件人和
+//! 渲染后的内容），不产生任何副作用，供审批者在放行需审批的工具前查看（需要 `execute_tools`）。
+//! 执行器不支持预演时返回失败结果，不会退而真正执行。见 [`ToolExecutorRegistry::execute_dry_run`]。
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::error;
+
+use crate::domain::tool::ToolCallContext;
+
+use super::permissions::{perm, RequirePermission};
+use super::{AppState, ErrorResponse};
+
+/// 预演请求
+#[derive(Debug, Deserialize)]
+pub struct DryRunRequest {
+    /// 以该 Agent 的身份调用
+    pub agent_id: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// 预演一次工具调用（需要 `execute_tools`）
+pub(super) async fn dry_run_tool(
+    State(state): State<Arc<AppState>>,
+    _auth: RequirePermission<perm::ExecuteTools>,
+    Path(tool_id): Path<String>,
+    Json(req): Json<DryRunRequest>,
+) -> Response {
+    let Some(executor) = &state.tool_executor else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse {
+                error: state.catalog.get("web.tool_executor_unavailable"),
+            }),
+        )
+            .into_response();
+    };
+
+    let context = ToolCallContext::new(&req.agent_id);
+    match executor.execute_dry_run(&tool_id, req.params, &context).await {
+        Ok(result) if result.success => Json(serde_json::json!({
+            "success": true,
+            "data": result.data,
+        }))
+        .into_response(),
+        // 不支持预演、没有执行器等
+        Ok(result) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(ErrorResponse {
+                error: result.error.unwrap_or_else(|| state.catalog.get("tool.unknown_error")),
+            }),
+        )
+            .into_response(),
+        Err(e) => {
+            error!("Failed to dry-run tool {} for {}: {}", tool_id, req.agent_id, e);
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ErrorResponse {
+                    error: state.catalog.format("web.tool_dry_run_failed", &[("error", &e.to_string())]),
+                }),
+            )
+                .into_response()
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! 请求追踪ID
+//!
+//! 每个请求带一个追踪ID：沿用客户端传入的 `X-Trace-Id`，否则新生成。
+//! 追踪ID放入请求扩展（[`TraceId`]），处理器产生的消息写入元数据，
+//! 并回写到响应头，便于把 `RUST_LOG` 输出与一次请求关联起来。
+
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info_span, Instrument};
+
+use crate::domain::new_trace_id;
+
+/// 追踪ID请求/响应头
+pub const TRACE_ID_HEADER: &str = "x-trace-id";
+
+/// 客户端传入追踪ID的最大长度
+const MAX_TRACE_ID_LEN: usize = 128;
+
+/// 当前请求的追踪ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceId(pub String);
+
+/// 为请求分配追踪ID并包裹在 `http_request` span 中
+pub async fn trace_middleware(mut request: Request, next: Next) -> Response {
+    let trace_id = request
+        .headers()
+        .get(TRACE_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| {
+            !value.is_empty()
+                && value.len() <= MAX_TRACE_ID_LEN
+                && value.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        })
+        .map(str::to_string)
+        .unwrap_or_else(new_trace_id);
+
+    let span = info_span!(
+        "http_request",
+        method = %request.method(),
+        path = %request.uri().path(),
+        trace_id = %trace_id,
+    );
+    request.extensions_mut().insert(TraceId(trace_id.clone()));
+
+    let mut response = next.run(request).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&trace_id) {
+        response.headers_mut().insert(TRACE_ID_HEADER, value);
+    }
+    response
+}
@@ -51,12 +51,62 @@ pub mod domain;
 /// 核心层 - 提供运行时能力和基础服务
 pub mod core {
     pub mod agent;
+    pub mod agent_batch;
+    pub mod agent_draft;
+    pub mod archive;
+    pub mod attachments;
+    pub mod budget;
+    pub mod chat_sessions;
+    #[cfg(feature = "chaos")]
+    pub mod chaos;
+    pub mod circuit_breaker;
+    pub mod citations;
+    pub mod clock;
     pub mod config;
+    pub mod escalation;
+    pub mod events;
+    pub mod features;
+    pub mod goals;
+    pub mod group_stats;
+    pub mod group_sync;
+    pub mod handoff;
+    pub mod i18n;
+    pub mod integrity;
+    pub mod join_context;
+    pub mod leadership;
+    pub mod loop_guard;
+    pub mod mentions;
+    pub mod message_limits;
     pub mod messaging;
+    pub mod moderation;
+    pub mod nudge;
+    pub mod org_changes;
+    pub mod org_tree;
+    pub mod preferences;
+    pub mod proactive;
+    pub mod redaction;
+    pub mod response_language;
+    pub mod retry;
+    pub mod role_history;
+    pub mod runtime_info;
+    pub mod scheduler;
+    pub mod scratchpad;
+    pub mod secrets;
+    pub mod send_dedup;
     pub mod skill;
     pub mod store;
+    pub mod tasks;
+    pub mod temp_agents;
+    pub mod template;
+    pub mod tokenizer;
     pub mod tool;
+    pub mod tool_concurrency;
     pub mod tool_provider;
+    pub mod tool_stats;
+    pub mod tool_view;
+    pub mod transcript;
+    pub mod translation;
+    pub mod turn_taking;
     pub mod capability;
     pub mod capability_provider;
     pub mod watchdog;
@@ -66,12 +116,17 @@ pub mod core {
 pub mod application {
     pub mod autonomous;
     pub mod company_runtime;
+    pub mod digest;
     pub mod framework;
     pub mod organization;
 }
 
+/// 场景测试工具 - 用 YAML 描述公司场景并在进程内运行、断言
+pub mod testkit;
+
 /// 基础设施层 - 外部集成和服务
 pub mod infrastructure {
+    pub mod blob;
     pub mod llm;
     pub mod logger;
     pub mod store;
@@ -79,6 +134,9 @@ pub mod infrastructure {
     pub mod tool;
     pub mod capability;
     pub mod auth;
+    pub mod client;
+    pub mod email;
+    pub mod sandbox;
 }
 
 // ================================
@@ -86,27 +144,39 @@ pub mod infrastructure {
 // ================================
 
 /// 虚拟公司框架 - 框架的主要入口点，管理多 Agent 系统
-pub use application::framework::{CompanyBuilder, VirtualCompany};
+pub use application::framework::{build_company_router, CompanyBuilder, VirtualCompany};
 
 /// 公司配置 - 定义 Agent 组织架构和设置
 pub use core::config::CompanyConfig;
 
+/// 生命周期事件 - 供扩展订阅 Agent、消息、工具与 Watchdog 事件
+pub use core::events::{CompanyEvent, CompanyEventListener, EventBus};
+
+/// 消息目录 - 框架生成文本的多语言支持
+pub use core::i18n::{Language, MessageCatalog};
+
 /// 快速启动函数 - 自动配置并启动框架
-pub use bootstrap::{quick_start, start_with_config, FrameworkLauncher};
+pub use bootstrap::{quick_start, quick_start_with, start_with_config, FrameworkLauncher, QuickStartOptions, SeedReport, StarterOrganization};
 
 // ================================
 // Web 服务 API - 内置 Web 功能
 // ================================
 
 /// 启动内置 Web 服务器 - 提供 REST API 和 WebSocket 服务
-pub use infrastructure::web::start_web_server;
+pub use infrastructure::web::{
+    start_web_server, start_web_server_with_catalog, start_web_server_with_options, RouteGroups, RouterOptions,
+    WebServerConfig, WebServerOptions,
+};
+
+/// 服务端客户端 - 供下游 Rust 程序通过 REST 和 WebSocket 与服务交互
+pub use infrastructure::client::{ImitatorClient, ReconnectPolicy};
 
 // ================================
 // 核心实体定义 - 领域模型
 // ================================
 
 /// Agent Entity - Core representation of virtual employees
-pub use domain::{Agent, AgentId, Department, Group, LLMConfig, Message, MessageTarget, Organization, Role, AgentMode};
+pub use domain::{Agent, AgentId, Department, Group, LLMConfig, Message, MessagePriority, MessageTarget, Organization, Role, AgentMode};
 /// TriggerCondition - 用于Watchdog框架的触发条件
 pub use core::watchdog::TriggerCondition;
 
@@ -118,10 +188,12 @@ pub use domain::user::User;
 // ================================
 
 /// 工具系统相关类型
-pub use domain::tool::{Tool, CategoryPath, ReturnType, ToolProvider, MatchType, CategoryNodeInfo, ToolCallContext, JsonSchema};
+pub use domain::tool::{Tool, CategoryPath, ReturnType, ToolProvider, MatchType, CategoryNodeInfo, ToolCallContext, ToolUsage, JsonSchema};
 pub use core::tool::ToolRegistry;
+pub use core::tool_stats::ToolStats;
 pub use infrastructure::tool::{ToolExecutor, ToolResult, ToolExecutorRegistry, FrameworkToolExecutor, ToolEnvironment};
 pub use core::tool_provider::{CompositeToolProvider, FrameworkToolProvider, RegistryToolProvider};
+pub use core::tool_view::AgentToolView;
 
 /// 能力系统相关类型
 pub use domain::capability::{Capability, CapabilityPath, CapabilityProvider, CapabilityAccessType, SkillCapabilityBinding, CapabilityCallContext};
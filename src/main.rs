@@ -2,11 +2,18 @@
 //!
 //! Automatically configures multi-Agent system and Web service
 
+use std::path::Path;
 use std::sync::Arc;
 
 use anyhow::Result;
+use imitatort::core::leadership::LeadershipError;
+use imitatort::core::retry::Retries;
+use imitatort::core::runtime_info::ConfigSource;
+use imitatort::core::secrets::print_extracted_secrets;
+use imitatort::infrastructure::logger::PromptLog;
+use imitatort::infrastructure::store::SqliteStore;
 use imitatort::{
-    Agent, AppConfig, CompanyBuilder, CompanyConfig, VirtualCompany, start_web_server,
+    Agent, AppConfig, CompanyBuilder, CompanyConfig, MessageCatalog, VirtualCompany, start_web_server_with_options, WebServerOptions,
 };
 use tokio::sync::broadcast;
 use tracing::{info, warn};
@@ -14,6 +21,12 @@ use tracing::{info, warn};
 // 加载环境变量
 use dotenv::dotenv;
 
+/// Company config file picked up from the working directory
+const COMPANY_CONFIG_FILE: &str = "company_config.yaml";
+
+/// 把数据库中的明文 API Key 换成 `env:` 引用后退出的子命令
+const MIGRATE_SECRETS_COMMAND: &str = "migrate-secrets";
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // 加载 .env 文件
@@ -22,10 +35,15 @@ async fn main() -> Result<()> {
     // 初始化日志
     tracing_subscriber::fmt::init();
 
-    info!("🚀 Starting ImitatorT - Multi-Agent Company Framework...");
-
     // 加载应用程序配置
     let app_config = AppConfig::from_env();
+    let catalog = MessageCatalog::new(app_config.language);
+
+    if std::env::args().nth(1).as_deref() == Some(MIGRATE_SECRETS_COMMAND) {
+        return migrate_secrets(&app_config, &catalog).await;
+    }
+
+    info!("{}", catalog.get("startup.banner"));
     info!("Using configuration: output_mode={}, web_bind={}", app_config.output_mode, app_config.web_bind);
 
     // Automatically configure and start multi-Agent system and Web service
@@ -43,21 +61,28 @@ async fn initialize_framework(app_config: &AppConfig) -> Result<VirtualCompany>
 
     // Try to load new configuration from config file
     if let Ok(config) = load_config() {
-        info!("📋 Loaded company configuration from company_config.yaml");
+        info!("📋 Loaded company configuration from {}", COMPANY_CONFIG_FILE);
+        let source = ConfigSource::new(Some(Path::new(COMPANY_CONFIG_FILE)), &config);
         // Create new company using config file, save to SQLite
         let company = CompanyBuilder::from_config(config)?
             .build_and_save()
-            .await?;
+            .await?
+            .with_config_source(source)
+            .with_data_dir(app_config.data_dir());
         info!("✅ Multi-agent system initialized with configuration");
         return Ok(company);
     }
 
     // Try to load from existing SQLite database
     info!("🔍 No config file found, attempting to load from database...");
-    match VirtualCompany::from_sqlite(&app_config.db_path).await {
+    if app_config.data_dir.is_some() {
+        app_config.prepare_data_dir()?;
+    }
+    let db_location = app_config.database_location();
+    match VirtualCompany::from_sqlite(&db_location).await {
         Ok(company) => {
-            info!("✅ Loaded existing company from database: {}", app_config.db_path);
-            Ok(company)
+            info!("✅ Loaded existing company from database: {}", db_location);
+            Ok(company.with_data_dir(app_config.data_dir()))
         }
         Err(_) => {
             warn!("⚠️  No existing database found, initializing with default configuration");
@@ -66,15 +91,29 @@ async fn initialize_framework(app_config: &AppConfig) -> Result<VirtualCompany>
                 .build_and_save()
                 .await?;
             info!("✅ Initialized default multi-agent system");
-            Ok(company)
+            Ok(company.with_data_dir(app_config.data_dir()))
         }
     }
 }
 
+/// Replace plaintext API keys in the database with `env:` references, printing each extracted key once
+async fn migrate_secrets(app_config: &AppConfig, catalog: &MessageCatalog) -> Result<()> {
+    let db_location = app_config.database_location();
+    let store = SqliteStore::new(&db_location)?;
+    let extracted = store.migrate_plaintext_api_keys().await?;
+    if extracted.is_empty() {
+        println!("{}", catalog.format("startup.secrets_migrate_none", &[("database", &db_location)]));
+        return Ok(());
+    }
+    print_extracted_secrets(catalog, &extracted);
+    info!("🔑 Migrated {} plaintext API key(s) in {} to env references", extracted.len(), db_location);
+    Ok(())
+}
+
 /// Load configuration file
 fn load_config() -> Result<CompanyConfig> {
     // Try to load configuration from YAML file
-    if let Ok(content) = std::fs::read_to_string("company_config.yaml") {
+    if let Ok(content) = std::fs::read_to_string(COMPANY_CONFIG_FILE) {
         let config: CompanyConfig = serde_yaml::from_str(&content)?;
         return Ok(config);
     }
@@ -86,6 +125,9 @@ fn load_config() -> Result<CompanyConfig> {
 async fn start_services(company: VirtualCompany, app_config: &AppConfig) -> Result<()> {
     info!("⚡ Starting framework services...");
 
+    let runtime_info = company.runtime_info().await;
+    runtime_info.log();
+
     // Get Agent list for Web API
     let agents: Vec<Agent> = company.get_agents().await?;
     info!("👥 Loaded {} agents", agents.len());
@@ -93,6 +135,20 @@ async fn start_services(company: VirtualCompany, app_config: &AppConfig) -> Resu
     // Create message broadcast channel
     let (message_tx, _) = broadcast::channel::<imitatort::Message>(1000);
 
+    let catalog = MessageCatalog::new(app_config.language);
+
+    // Log LLM prompts and responses when configured
+    let company = match &app_config.prompt_log {
+        Some(config) => company.with_prompt_log(Arc::new(PromptLog::open(config)?)),
+        None => company,
+    };
+
+    // Share the blob store, message limits and retry policies between the web API and agent tools
+    let company = company
+        .with_blob_store(app_config.open_blob_store()?)
+        .with_message_limits(app_config.message_limits.clone())
+        .with_retries(Arc::new(Retries::new(app_config.retry.clone())));
+
     // Create shared reference to company instance
     let company_arc = Arc::new(company);
 
@@ -113,20 +169,64 @@ async fn start_services(company: VirtualCompany, app_config: &AppConfig) -> Resu
     if app_config.output_mode == "web" {
         info!("🌐 Starting web server on {}", app_config.web_bind);
 
-        start_web_server(
+        let server = start_web_server_with_options(
             &app_config.web_bind,
             agents,
             message_tx,
-            company_arc.store().clone()
-        ).await?;
-
-        info!("✅ Web server started successfully");
+            company_arc.store().clone(),
+            WebServerOptions {
+                catalog,
+                legacy_api_routes: app_config.legacy_api_routes,
+                password_policy: app_config.password_policy.clone(),
+                server: app_config.web_server.clone(),
+                templates: company_arc.templates_arc(),
+                reactions: Some(company_arc.reaction_sender()),
+                tool_progress: Some(company_arc.tool_progress_sender()),
+                scheduler: Some(company_arc.scheduler()),
+                circuit_breakers: Some(company_arc.circuit_breakers()),
+                message_bus: Some(company_arc.message_bus()),
+                jwt_service: None,
+                runtime_info: Some(runtime_info),
+                blob_store: company_arc.blob_store(),
+                permissions: company_arc.permissions(),
+                prompt_log: company_arc.prompt_log(),
+                agent_interviewer: company_arc.agent_interviewer(),
+                default_llm: company_arc.default_llm(),
+                agent_runner: Some(company_arc.agent_runner()),
+                temp_agents: Some(company_arc.temp_agents()),
+                scratchpad: company_arc.scratchpad(),
+                goals: company_arc.goals(),
+                chat_sessions: company_arc.chat_sessions(),
+                group_stats: company_arc.group_stats(),
+                retries: Some(company_arc.retries()),
+                org_tree: Some(company_arc.org_tree()),
+                mentions: Some(company_arc.mentions()),
+                nudges: Some(company_arc.nudges()),
+                features: Some(company_arc.features()),
+                leadership: company_arc.leadership(),
+                tool_executor: None,
+                message_limits: company_arc.message_limits().clone(),
+                #[cfg(feature = "chaos")]
+                fault_injector: None,
+            },
+        );
+
+        // Stop serving on interrupt so that state can be flushed before exiting
+        tokio::select! {
+            result = server => result?,
+            _ = tokio::signal::ctrl_c() => info!("🛑 Received shutdown signal"),
+        }
     } else {
-        info!("ℹ️  Running in console mode (no web interface)");
+        info!("{}", catalog.get("startup.console_mode"));
         // In console mode, we still keep Agent loops running
         // Wait until terminated by interrupt signal
         tokio::signal::ctrl_c().await.expect("Failed to listen for ctrl+c");
-        info!("🛑 Received shutdown signal");
+        info!("{}", catalog.get("startup.shutdown"));
+    }
+
+    // Flush today's tool stats before exiting
+    if let Err(e) = company_arc.persist_tool_stats().await {
+        warn!("Failed to persist tool stats: {}", e);
     }
 
     Ok(())
@@ -144,6 +244,10 @@ async fn start_agent_loops(
         Ok(_) => info!("Agent operations completed"),
         Err(e) => {
             tracing::error!("Agent operations error: {}", e);
+            // Exit after losing leadership so the supervisor restarts this node as a standby
+            if e.downcast_ref::<LeadershipError>().is_some() {
+                std::process::exit(1);
+            }
         }
     }
 }
@@ -0,0 +1,194 @@
+//! 模拟 LLM
+//!
+//! 进程内的 OpenAI 兼容服务，每个 Agent 一个路径（`/agents/{id}/chat/completions`），
+//! 按场景脚本回复；脚本用完或下一步不匹配时回复 `{"action":"wait"}`。
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use axum::extract::{Path, State};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use tokio::task::JoinHandle;
+
+use crate::domain::tool::openai_function_name;
+use crate::testkit::scenario::{text_matches, LlmReply, LlmStep};
+
+/// 没有可用步骤时的回复
+const WAIT_REPLY: &str = r#"{"action":"wait"}"#;
+
+#[derive(Default)]
+struct AgentScript {
+    steps: Vec<LlmStep>,
+    next: usize,
+    requests: usize,
+    last_prompt: Option<String>,
+}
+
+type Scripts = Arc<Mutex<HashMap<String, AgentScript>>>;
+
+/// 未使用的脚本步骤
+#[derive(Debug, Clone)]
+pub struct PendingStep {
+    pub agent_id: String,
+    /// 步骤序号（从 1 开始）
+    pub index: usize,
+    pub step: LlmStep,
+    /// 该 Agent 最近一次请求的内容，用于查看 `when` 为什么没有匹配
+    pub last_prompt: Option<String>,
+}
+
+/// 运行中的模拟 LLM，drop 时停止
+pub struct MockLlm {
+    addr: SocketAddr,
+    scripts: Scripts,
+    server: JoinHandle<()>,
+}
+
+impl MockLlm {
+    /// 在随机端口启动
+    pub async fn start(scripts: BTreeMap<String, Vec<LlmStep>>) -> Result<Self> {
+        let scripts: Scripts = Arc::new(Mutex::new(
+            scripts
+                .into_iter()
+                .map(|(agent_id, steps)| (agent_id, AgentScript { steps, ..Default::default() }))
+                .collect(),
+        ));
+        let app = Router::new()
+            .route("/agents/{agent_id}/chat/completions", post(completions))
+            .with_state(scripts.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::error!("Mock LLM server stopped: {}", e);
+            }
+        });
+        Ok(Self { addr, scripts, server })
+    }
+
+    /// Agent 的 `llm_config.base_url`
+    pub fn base_url(&self, agent_id: &str) -> String {
+        format!("http://{}/agents/{}", self.addr, agent_id)
+    }
+
+    /// Agent 发来的请求数
+    pub fn requests(&self, agent_id: &str) -> usize {
+        self.scripts.lock().unwrap().get(agent_id).map_or(0, |s| s.requests)
+    }
+
+    /// 各 Agent 还没有使用的步骤，按 Agent ID 和步骤顺序排列
+    pub fn pending(&self) -> Vec<PendingStep> {
+        let scripts = self.scripts.lock().unwrap();
+        let mut pending: Vec<PendingStep> = scripts
+            .iter()
+            .flat_map(|(agent_id, script)| {
+                script.steps.iter().enumerate().skip(script.next).map(|(i, step)| PendingStep {
+                    agent_id: agent_id.clone(),
+                    index: i + 1,
+                    step: step.clone(),
+                    last_prompt: script.last_prompt.clone(),
+                })
+            })
+            .collect();
+        pending.sort_by(|a, b| a.agent_id.cmp(&b.agent_id).then(a.index.cmp(&b.index)));
+        pending
+    }
+}
+
+impl Drop for MockLlm {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn completions(
+    State(scripts): State<Scripts>,
+    Path(agent_id): Path<String>,
+    Json(request): Json<Value>,
+) -> Json<Value> {
+    let prompt = request_text(&request);
+    let has_tools = request["tools"].as_array().is_some_and(|tools| !tools.is_empty());
+    let reply = {
+        let mut scripts = scripts.lock().unwrap();
+        let script = scripts.entry(agent_id).or_default();
+        script.requests += 1;
+        let reply = script
+            .steps
+            .get(script.next)
+            .filter(|step| step.when.as_deref().is_none_or(|when| text_matches(when, &prompt)))
+            // 工具调用只能回复给带工具列表的请求
+            .filter(|step| has_tools || !matches!(step.reply, LlmReply::Tool { .. }))
+            .map(|step| step.reply.clone());
+        if reply.is_some() {
+            script.next += 1;
+        }
+        script.last_prompt = Some(prompt);
+        reply
+    };
+    Json(completion(reply))
+}
+
+/// 请求中所有消息的文本
+fn request_text(request: &Value) -> String {
+    request["messages"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|message| match &message["content"] {
+            Value::String(text) => Some(text.clone()),
+            Value::Array(parts) => Some(parts.iter().filter_map(|p| p["text"].as_str()).collect::<Vec<_>>().join("\n")),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn completion(reply: Option<LlmReply>) -> Value {
+    let message = match reply {
+        Some(LlmReply::Tool { name, params }) => {
+            let arguments = if params.is_null() { json!({}) } else { params };
+            return json!({
+                "id": "chatcmpl-testkit",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "testkit",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_testkit",
+                            "type": "function",
+                            "function": {"name": openai_function_name(&name), "arguments": arguments.to_string()}
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            });
+        }
+        Some(LlmReply::Send { to, content }) => {
+            json!({"action": "send_message", "target": to, "content": content}).to_string()
+        }
+        Some(LlmReply::CreateGroup { name, members }) => {
+            json!({"action": "create_group", "name": name, "members": members}).to_string()
+        }
+        Some(LlmReply::Text(text)) => text,
+        None => WAIT_REPLY.to_string(),
+    };
+    json!({
+        "id": "chatcmpl-testkit",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "testkit",
+        "choices": [{
+            "index": 0,
+            "message": {"role": "assistant", "content": message},
+            "finish_reason": "stop"
+        }]
+    })
+}
@@ -0,0 +1,29 @@
+//! 场景测试工具
+//!
+//! 用 YAML 描述一个公司场景（格式见 [`scenario`]）：公司配置、每个 Agent 的模拟 LLM 脚本、
+//! 依次注入的外部事件（用户消息、Webhook 请求、时钟推进、工具结果）和期望（投递到目标的消息、
+//! 带参数的工具调用、生命周期事件、存储状态）。[`ScenarioRunner`] 在进程内用 MemoryStore
+//! 和手动时钟运行公司，给出逐项的通过/失败报告，失败项附带期望与实际的差异。
+//!
+//! 下游 crate 可以用同样的格式测试自己的组织：
+//!
+//! ```no_run
+//! use imitatort::testkit::ScenarioRunner;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let report = ScenarioRunner::from_file("tests/fixtures/scenarios/delegation.yaml")?.run().await?;
+//! report.assert_passed();
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod mock_llm;
+pub mod runner;
+pub mod scenario;
+
+pub use mock_llm::{MockLlm, PendingStep};
+pub use runner::{CheckKind, CheckOutcome, ScenarioReport, ScenarioRunner};
+pub use scenario::{
+    json_subset, parse_duration, text_matches, Expectation, LlmReply, LlmStep, MessagePattern, Scenario, ScenarioEvent,
+    ScenarioGroup, StorePredicate, WebhookRequest,
+};
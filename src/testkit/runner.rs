@@ -0,0 +1,717 @@
+//! 场景运行器
+//!
+//! 每个场景在独立的 tokio 运行时中执行：用 MemoryStore 和手动时钟创建公司，Agent 指向模拟 LLM，
+//! 依次注入事件，然后轮询期望直到全部满足（且脚本全部用完）或超时。场景结束时运行时随之关闭，
+//! Agent 循环和各类后台任务不会残留到下一个场景。
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::{broadcast, oneshot};
+use tracing::warn;
+
+use crate::application::framework::{build_company_router, VirtualCompany};
+use crate::core::agent::Decision;
+use crate::core::clock::ManualClock;
+use crate::core::events::CompanyEvent;
+use crate::core::store::{MemoryStore, MessageFilter, Store, TaskFilter};
+use crate::core::watchdog::{ToolExecutionEvent, WatchdogFramework};
+use crate::domain::tool::ToolCallContext;
+use crate::domain::user::is_user_principal;
+use crate::domain::{Message, MessageTarget};
+use crate::infrastructure::auth::{JwtService, UserInfo};
+use crate::infrastructure::web::RouterOptions;
+use crate::testkit::mock_llm::MockLlm;
+use crate::testkit::scenario::{
+    json_subset, text_matches, Expectation, MessagePattern, Scenario, ScenarioEvent, StorePredicate, WebhookRequest,
+};
+
+/// 模拟 LLM 不校验 API Key
+const MOCK_API_KEY: &str = "testkit";
+/// Webhook 请求签发令牌使用的密钥
+const WEBHOOK_JWT_SECRET: &str = "testkit-webhook-secret";
+/// 期望的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// 差异中最多列出的实际项
+const MAX_LISTED: usize = 10;
+/// 差异中最多显示的请求内容（字符数，取末尾）
+const PROMPT_TAIL_CHARS: usize = 600;
+
+/// 场景运行器
+pub struct ScenarioRunner {
+    scenario: Scenario,
+}
+
+impl ScenarioRunner {
+    pub fn new(scenario: Scenario) -> Self {
+        Self { scenario }
+    }
+
+    /// 读取场景文件
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Scenario::from_file(path)?))
+    }
+
+    /// 运行场景；公司无法启动时返回错误，期望不满足记在报告中
+    pub async fn run(self) -> Result<ScenarioReport> {
+        let (tx, rx) = oneshot::channel();
+        let name = self.scenario.name.clone();
+        std::thread::Builder::new()
+            .name(format!("scenario-{}", name))
+            .spawn(move || {
+                let result = tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(2)
+                    .enable_all()
+                    .build()
+                    .map_err(anyhow::Error::from)
+                    .and_then(|runtime| {
+                        let result = runtime.block_on(execute(self.scenario));
+                        runtime.shutdown_background();
+                        result
+                    });
+                let _ = tx.send(result);
+            })
+            .context("Failed to spawn scenario thread")?;
+        rx.await.with_context(|| format!("Scenario {} panicked", name))?
+    }
+}
+
+/// 单项检查的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckKind {
+    /// 注入事件
+    Event,
+    /// 场景期望
+    Expectation,
+    /// 模拟 LLM 的脚本步骤（未使用时失败）
+    Script,
+}
+
+/// 单项检查的结果
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckOutcome {
+    pub kind: CheckKind,
+    pub label: String,
+    pub passed: bool,
+    /// 失败时期望与实际的差异
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+impl CheckOutcome {
+    fn new(kind: CheckKind, label: String, result: std::result::Result<(), String>) -> Self {
+        Self {
+            kind,
+            label,
+            passed: result.is_ok(),
+            diff: result.err(),
+        }
+    }
+}
+
+/// 场景报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ScenarioReport {
+    pub scenario: String,
+    pub passed: bool,
+    pub checks: Vec<CheckOutcome>,
+    pub elapsed_ms: u64,
+}
+
+impl ScenarioReport {
+    /// 失败的检查
+    pub fn failures(&self) -> impl Iterator<Item = &CheckOutcome> {
+        self.checks.iter().filter(|c| !c.passed)
+    }
+
+    /// 有失败时 panic，信息为完整报告
+    #[track_caller]
+    pub fn assert_passed(&self) {
+        if !self.passed {
+            panic!("{}", self);
+        }
+    }
+}
+
+impl fmt::Display for ScenarioReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let failed = self.failures().count();
+        match failed {
+            0 => writeln!(f, "scenario {}: passed ({} checks, {} ms)", self.scenario, self.checks.len(), self.elapsed_ms)?,
+            _ => writeln!(
+                f,
+                "scenario {}: FAILED ({} of {} checks failed, {} ms)",
+                self.scenario,
+                failed,
+                self.checks.len(),
+                self.elapsed_ms
+            )?,
+        }
+        for check in &self.checks {
+            writeln!(f, "  {} {}", if check.passed { "ok  " } else { "FAIL" }, check.label)?;
+            if let Some(diff) = &check.diff {
+                for line in diff.lines() {
+                    writeln!(f, "       {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 运行期间观察到的消息和事件
+#[derive(Clone, Default)]
+struct Observed(Arc<Mutex<ObservedState>>);
+
+#[derive(Default)]
+struct ObservedState {
+    messages: Vec<Message>,
+    message_ids: HashSet<String>,
+    events: Vec<CompanyEvent>,
+}
+
+impl Observed {
+    /// 订阅生命周期事件和 Web 接口发出的消息
+    fn record(company: &VirtualCompany) -> Self {
+        let observed = Self::default();
+        let mut events = company.events();
+        let recorder = observed.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => recorder.push_event(event),
+                    Err(broadcast::error::RecvError::Lagged(n)) => warn!("Scenario recorder lagged, {} events lost", n),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        let mut messages = company.subscribe_messages();
+        let recorder = observed.clone();
+        tokio::spawn(async move {
+            loop {
+                match messages.recv().await {
+                    Ok(message) => recorder.push_message(message),
+                    Err(broadcast::error::RecvError::Lagged(n)) => warn!("Scenario recorder lagged, {} messages lost", n),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        observed
+    }
+
+    fn push_event(&self, event: CompanyEvent) {
+        if let CompanyEvent::MessagePersisted { message } = &event {
+            self.push_message((**message).clone());
+        }
+        self.0.lock().unwrap().events.push(event);
+    }
+
+    fn push_message(&self, message: Message) {
+        let mut state = self.0.lock().unwrap();
+        if state.message_ids.insert(message.id.clone()) {
+            state.messages.push(message);
+        }
+    }
+
+    fn messages(&self) -> Vec<Message> {
+        self.0.lock().unwrap().messages.clone()
+    }
+
+    fn events(&self) -> Vec<CompanyEvent> {
+        self.0.lock().unwrap().events.clone()
+    }
+
+    fn started(&self, agent_id: &str) -> bool {
+        self.0
+            .lock()
+            .unwrap()
+            .events
+            .iter()
+            .any(|e| matches!(e, CompanyEvent::AgentStarted { agent_id: id } if id.as_ref() == agent_id))
+    }
+}
+
+/// 运行中的场景
+struct Harness {
+    company: Arc<VirtualCompany>,
+    store: Arc<dyn Store>,
+    clock: Arc<ManualClock>,
+    observed: Observed,
+    watchdog: WatchdogFramework,
+    web_base: Option<String>,
+    http: reqwest::Client,
+    timeout: Duration,
+}
+
+async fn execute(scenario: Scenario) -> Result<ScenarioReport> {
+    let started = Instant::now();
+    let mock = MockLlm::start(scenario.llm.clone()).await?;
+    let mut config = scenario.company.clone();
+    for agent in &mut config.organization.agents {
+        agent.llm_config.base_url = mock.base_url(&agent.id);
+        agent.llm_config.api_key = MOCK_API_KEY.to_string();
+    }
+    let agent_ids: Vec<String> = config.organization.agents.iter().map(|a| a.id.clone()).collect();
+
+    let clock = Arc::new(ManualClock::new(
+        scenario.start_time.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+    ));
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let company = Arc::new(VirtualCompany::with_store_and_clock(config, store.clone(), clock.clone()));
+    let observed = Observed::record(&company);
+    company.save().await?;
+    let watchdog = WatchdogFramework::new()
+        .with_events(company.event_bus())
+        .with_tool_registry(company.tool_registry());
+    for rule in &scenario.watchdog {
+        watchdog.register_rule(rule.clone())?;
+    }
+
+    let running = tokio::spawn({
+        let company = company.clone();
+        async move { company.run().await }
+    });
+    let deadline = Instant::now() + scenario.timeout();
+    while !agent_ids.iter().all(|id| observed.started(id)) {
+        if running.is_finished() {
+            return Err(match running.await {
+                Ok(Err(e)) => e.context(format!("Scenario {} company failed to start", scenario.name)),
+                Ok(Ok(())) => anyhow::anyhow!("Scenario {} company stopped during startup", scenario.name),
+                Err(e) => e.into(),
+            });
+        }
+        if Instant::now() >= deadline {
+            anyhow::bail!("Scenario {} agents did not start within {:?}", scenario.name, scenario.timeout());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+
+    // 群聊由第一个 Agent 成员创建（创建者需要在消息总线上注册）
+    let bus = company.message_bus();
+    for group in &scenario.groups {
+        for member in group.members.iter().filter(|m| is_user_principal(m)) {
+            bus.register_user(member);
+        }
+        let creator = group
+            .creator
+            .clone()
+            .or_else(|| group.members.iter().find(|m| agent_ids.contains(m)).cloned())
+            .with_context(|| format!("Group {} has no agent member to create it", group.id))?;
+        bus.create_group(&group.id, group.name.as_deref().unwrap_or(&group.id), &creator, group.members.clone())
+            .await?;
+    }
+
+    let mut harness = Harness {
+        company,
+        store,
+        clock,
+        observed,
+        watchdog,
+        web_base: None,
+        http: reqwest::Client::new(),
+        timeout: scenario.timeout(),
+    };
+    let mut checks = Vec::new();
+    for (i, event) in scenario.events.iter().enumerate() {
+        let result = harness.inject(event).await;
+        checks.push(CheckOutcome::new(CheckKind::Event, format!("event {}: {}", i + 1, event), result));
+    }
+
+    // 期望全部满足且脚本用完后结束，否则等到超时
+    let deadline = Instant::now() + harness.timeout;
+    let results = loop {
+        let mut results = Vec::with_capacity(scenario.expect.len());
+        for expectation in &scenario.expect {
+            results.push(harness.check(expectation).await);
+        }
+        let done = results.iter().all(|r| r.is_ok()) && mock.pending().is_empty();
+        if done || Instant::now() >= deadline {
+            break results;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+    for (i, (expectation, result)) in scenario.expect.iter().zip(results).enumerate() {
+        checks.push(CheckOutcome::new(CheckKind::Expectation, format!("expect {}: {}", i + 1, expectation), result));
+    }
+    let mut waiting: BTreeMap<String, usize> = BTreeMap::new();
+    for pending in mock.pending() {
+        // 同一 Agent 只有第一个未使用的步骤在等待匹配，之后的步骤排在它后面
+        let first = *waiting.entry(pending.agent_id.clone()).or_insert(pending.index);
+        let diff = match (&pending.step.when, &pending.last_prompt) {
+            _ if first != pending.index => format!("never used: step {} was never used", first),
+            (_, None) => "never used: the agent made no LLM requests".to_string(),
+            (Some(when), Some(prompt)) => format!(
+                "never used: no request matched {:?}\nlast request: ...{}",
+                when,
+                tail(prompt, PROMPT_TAIL_CHARS)
+            ),
+            (None, Some(_)) => "never used: no later request could take it (tool calls need a request with tools)".to_string(),
+        };
+        checks.push(CheckOutcome::new(
+            CheckKind::Script,
+            format!("llm {} step {}: {}", pending.agent_id, pending.index, pending.step.reply),
+            Err(diff),
+        ));
+    }
+
+    running.abort();
+    Ok(ScenarioReport {
+        scenario: scenario.name,
+        passed: checks.iter().all(|c| c.passed),
+        checks,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+impl Harness {
+    async fn inject(&mut self, event: &ScenarioEvent) -> std::result::Result<(), String> {
+        match event {
+            ScenarioEvent::UserMessage { from, to, content } => {
+                let bus = self.company.message_bus();
+                let message = match bus.get_group(to).await {
+                    Some(_) => Message::group(from, to, content),
+                    None => Message::private(from, to, content),
+                };
+                bus.send(message).await.map_err(|e| format!("{:#}", e))
+            }
+            ScenarioEvent::Webhook(request) => self.webhook(request).await,
+            ScenarioEvent::AdvanceClock(duration) => {
+                self.clock.advance(*duration);
+                Ok(())
+            }
+            ScenarioEvent::ToolResult { tool, caller, result } => {
+                let event = ToolExecutionEvent::PostExecute {
+                    tool_id: tool.clone(),
+                    result: result.clone(),
+                    context: ToolCallContext::new(caller),
+                };
+                self.watchdog.process_event(&event).await.map(|_| ()).map_err(|e| format!("{:#}", e))
+            }
+            ScenarioEvent::WaitFor(expectation) => {
+                let deadline = Instant::now() + self.timeout;
+                loop {
+                    let result = self.check(expectation).await;
+                    if result.is_ok() || Instant::now() >= deadline {
+                        return result.map_err(|diff| format!("timed out after {:?}\n{}", self.timeout, diff));
+                    }
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                }
+            }
+        }
+    }
+
+    /// 第一次发请求时在随机端口启动公司的 Web 路由
+    async fn web_base(&mut self) -> Result<String> {
+        if let Some(base) = &self.web_base {
+            return Ok(base.clone());
+        }
+        let options = RouterOptions {
+            jwt_service: Some(JwtService::new(WEBHOOK_JWT_SECRET)),
+            ..Default::default()
+        };
+        let router = build_company_router(&self.company, options);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let base = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                warn!("Scenario web server stopped: {}", e);
+            }
+        });
+        self.web_base = Some(base.clone());
+        Ok(base)
+    }
+
+    async fn webhook(&mut self, request: &WebhookRequest) -> std::result::Result<(), String> {
+        let base = self.web_base().await.map_err(|e| format!("{:#}", e))?;
+        let method = reqwest::Method::from_bytes(request.method.to_uppercase().as_bytes())
+            .map_err(|_| format!("invalid method {}", request.method))?;
+        let mut builder = self.http.request(method, format!("{}{}", base, request.path));
+        if !request.body.is_null() {
+            builder = builder.json(&request.body);
+        }
+        if let Some(user) = &request.as_user {
+            let id = user.strip_prefix("user:").unwrap_or(user);
+            let token = JwtService::new(WEBHOOK_JWT_SECRET)
+                .generate_token(&UserInfo {
+                    id: id.to_string(),
+                    username: id.to_string(),
+                    name: id.to_string(),
+                    email: None,
+                    is_director: request.director,
+                    employee_id: "00000".to_string(),
+                    position: "Employee".to_string(),
+                    department: String::new(),
+                })
+                .map_err(|e| format!("{:#}", e))?;
+            builder = builder.bearer_auth(token);
+        }
+        let response = builder.send().await.map_err(|e| format!("{:#}", e))?;
+        let status = response.status().as_u16();
+        let body = response.text().await.unwrap_or_default();
+        match request.expect_status {
+            Some(expected) if expected != status => {
+                Err(format!("expected: status {}\nactual:   status {}: {}", expected, status, body))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn check(&self, expectation: &Expectation) -> std::result::Result<(), String> {
+        match expectation {
+            Expectation::Message(pattern) => check_messages(pattern, &self.observed.messages()),
+            Expectation::ToolCalled { agent, tool, params } => {
+                check_tool_called(agent.as_deref(), tool, params, &self.observed.events())
+            }
+            Expectation::Event { kind, agent } => check_event(kind, agent.as_deref(), &self.observed.events()),
+            Expectation::Store(predicate) => self.check_store(predicate).await,
+        }
+    }
+
+    async fn check_store(&self, predicate: &StorePredicate) -> std::result::Result<(), String> {
+        let failed = |e: anyhow::Error| format!("store error: {:#}", e);
+        match predicate {
+            StorePredicate::Messages(pattern) => {
+                let mut filter = MessageFilter::new().limit(10_000);
+                if let Some(from) = &pattern.from {
+                    filter = filter.from(from);
+                }
+                if let Some(to) = &pattern.to {
+                    filter = filter.to(to);
+                }
+                let messages = self.store.load_messages(filter).await.map_err(failed)?;
+                check_messages(pattern, &messages)
+            }
+            StorePredicate::Task { title, assignee, creator, status, count } => {
+                let tasks = self.store.load_tasks(TaskFilter::new().limit(10_000)).await.map_err(failed)?;
+                let matching = tasks
+                    .iter()
+                    .filter(|t| title.as_deref().is_none_or(|p| text_matches(p, &t.title)))
+                    .filter(|t| assignee.is_none() || t.assignee == *assignee)
+                    .filter(|t| creator.as_ref().is_none_or(|c| &t.creator == c))
+                    .filter(|t| status.as_ref().is_none_or(|s| &t.status.to_string() == s))
+                    .count();
+                if count_satisfied(*count, matching) {
+                    return Ok(());
+                }
+                let listed: Vec<String> = tasks
+                    .iter()
+                    .map(|t| {
+                        format!(
+                            "{:?} by {} for {} ({})",
+                            t.title,
+                            t.creator,
+                            t.assignee.as_deref().unwrap_or("-"),
+                            t.status
+                        )
+                    })
+                    .collect();
+                Err(format!(
+                    "expected: {}\nactual:   {} matching; tasks in store:{}",
+                    expect_count(*count, "matching task"),
+                    matching,
+                    bullet_list(&listed)
+                ))
+            }
+            StorePredicate::Group { id, members } => {
+                // 运行时创建的群聊不一定落库，再查消息总线
+                let stored = self.store.load_groups().await.map_err(failed)?;
+                let group = match stored.iter().find(|g| &g.id == id) {
+                    Some(group) => Some(group.clone()),
+                    None => self.company.message_bus().get_group(id).await,
+                };
+                let Some(group) = group else {
+                    let ids: Vec<String> = stored.iter().map(|g| g.id.clone()).collect();
+                    return Err(format!("expected: group {}\nactual:   no such group; groups in store:{}", id, bullet_list(&ids)));
+                };
+                let missing: Vec<&String> = members.iter().filter(|m| !group.members.contains(m)).collect();
+                if missing.is_empty() {
+                    return Ok(());
+                }
+                Err(format!(
+                    "expected: members {}\nactual:   members {} (missing {})",
+                    members.join(", "),
+                    group.members.join(", "),
+                    missing.iter().map(|m| m.as_str()).collect::<Vec<_>>().join(", ")
+                ))
+            }
+        }
+    }
+}
+
+/// 消息的目标 ID（Agent、用户 principal 或群聊）
+fn target_id(target: &MessageTarget) -> &str {
+    let (MessageTarget::Direct(id) | MessageTarget::Group(id)) = target;
+    id
+}
+
+fn check_messages(pattern: &MessagePattern, messages: &[Message]) -> std::result::Result<(), String> {
+    let routed: Vec<&Message> = messages
+        .iter()
+        .filter(|m| pattern.from.as_ref().is_none_or(|from| &m.from == from))
+        .filter(|m| pattern.to.as_deref().is_none_or(|to| target_id(&m.to) == to))
+        .collect();
+    let matching = routed
+        .iter()
+        .filter(|m| pattern.content.as_deref().is_none_or(|content| text_matches(content, &m.content)))
+        .count();
+    if count_satisfied(pattern.count, matching) {
+        return Ok(());
+    }
+
+    // 列出同一路由上的消息；路由上没有消息时列出全部
+    let (scope, listed) = if routed.is_empty() {
+        ("no messages on that route; all messages", messages.iter().collect::<Vec<_>>())
+    } else {
+        ("messages on that route", routed)
+    };
+    let listed: Vec<String> = listed
+        .iter()
+        .map(|m| format!("{} -> {}: {:?}", m.from, target_id(&m.to), m.content))
+        .collect();
+    Err(format!("expected: {}\nactual:   {} matching; {}:{}", pattern, matching, scope, bullet_list(&listed)))
+}
+
+fn check_tool_called(
+    agent: Option<&str>,
+    tool: &str,
+    params: &Value,
+    events: &[CompanyEvent],
+) -> std::result::Result<(), String> {
+    let calls: Vec<(&str, &str, &Value, Option<&str>)> = events
+        .iter()
+        .filter_map(|event| match event {
+            CompanyEvent::AgentTurnCompleted {
+                agent_id,
+                decision: Some(decision),
+                error,
+                ..
+            } => match decision.as_ref() {
+                Decision::CallTool { tool_id, arguments } => {
+                    Some((agent_id.as_ref(), tool_id.as_str(), arguments, error.as_deref()))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|(caller, ..)| agent.is_none_or(|agent| *caller == agent))
+        .collect();
+    let same_tool: Vec<_> = calls.iter().filter(|(_, id, ..)| *id == tool).collect();
+    let mismatches: Vec<String> = same_tool
+        .iter()
+        .filter_map(|(caller, _, arguments, _)| match params.is_null() {
+            true => None,
+            false => json_subset(params, arguments).err().map(|diff| format!("{} called with {}: {}", caller, arguments, diff)),
+        })
+        .collect();
+    if same_tool.len() > mismatches.len() {
+        return Ok(());
+    }
+
+    let listed: Vec<String> = if same_tool.is_empty() {
+        calls
+            .iter()
+            .map(|(caller, id, arguments, error)| match error {
+                Some(error) => format!("{} called {} with {} (failed: {})", caller, id, arguments, error),
+                None => format!("{} called {} with {}", caller, id, arguments),
+            })
+            .collect()
+    } else {
+        mismatches
+    };
+    let scope = if same_tool.is_empty() { "no calls of that tool; tool calls" } else { "calls with other params" };
+    Err(format!(
+        "expected: {} called{}{}\nactual:   {}:{}",
+        tool,
+        agent.map(|a| format!(" by {}", a)).unwrap_or_default(),
+        if params.is_null() { String::new() } else { format!(" with {}", params) },
+        scope,
+        bullet_list(&listed)
+    ))
+}
+
+/// 事件关联的 Agent
+fn event_agent(event: &CompanyEvent) -> Option<&str> {
+    match event {
+        CompanyEvent::AgentStarted { agent_id }
+        | CompanyEvent::AgentTurnCompleted { agent_id, .. }
+        | CompanyEvent::OutboxFlushed { agent_id, .. }
+        | CompanyEvent::LoopGuardTripped { agent_id, .. }
+        | CompanyEvent::DuplicateSendSuppressed { agent_id, .. }
+        | CompanyEvent::AgentPresenceChanged { agent_id, .. }
+        | CompanyEvent::AgentBreakerChanged { agent_id, .. }
+        | CompanyEvent::ResponseLanguageCorrected { agent_id, .. }
+        | CompanyEvent::NudgeInjected { agent_id, .. } => Some(agent_id),
+        CompanyEvent::ToolExecuted { caller_id, .. } => Some(caller_id),
+        CompanyEvent::WatchdogTriggered { target_agent_id, .. } => Some(target_agent_id),
+        CompanyEvent::TaskAssigned { assignee, .. } => Some(assignee),
+        CompanyEvent::MessagePersisted { message } => Some(&message.from),
+        CompanyEvent::OrgChanged { .. }
+        | CompanyEvent::UserChanged { .. }
+        | CompanyEvent::FeatureToggled { .. }
+        | CompanyEvent::TaskOverdue { .. } => None,
+    }
+}
+
+fn check_event(kind: &str, agent: Option<&str>, events: &[CompanyEvent]) -> std::result::Result<(), String> {
+    let scoped: Vec<&CompanyEvent> = events
+        .iter()
+        .filter(|e| agent.is_none_or(|agent| event_agent(e) == Some(agent)))
+        .collect();
+    if scoped.iter().any(|e| e.kind() == kind) {
+        return Ok(());
+    }
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for event in scoped {
+        *counts.entry(event.kind()).or_default() += 1;
+    }
+    let listed: Vec<String> = counts.iter().map(|(kind, n)| format!("{} x{}", kind, n)).collect();
+    Err(format!(
+        "expected: event {}{}\nactual:   none; observed events:{}",
+        kind,
+        agent.map(|a| format!(" for {}", a)).unwrap_or_default(),
+        bullet_list(&listed)
+    ))
+}
+
+/// `count` 为空时至少一个，否则恰好这么多
+fn count_satisfied(count: Option<usize>, matching: usize) -> bool {
+    match count {
+        Some(count) => matching == count,
+        None => matching > 0,
+    }
+}
+
+fn expect_count(count: Option<usize>, what: &str) -> String {
+    match count {
+        Some(count) => format!("exactly {} {}(s)", count, what),
+        None => format!("at least one {}", what),
+    }
+}
+
+/// 每项一行，最多列出 [`MAX_LISTED`] 项
+fn bullet_list(items: &[String]) -> String {
+    if items.is_empty() {
+        return " (none)".to_string();
+    }
+    let mut list: String = items.iter().take(MAX_LISTED).map(|item| format!("\n  - {}", item)).collect();
+    if items.len() > MAX_LISTED {
+        list.push_str(&format!("\n  ... and {} more", items.len() - MAX_LISTED));
+    }
+    list
+}
+
+/// 文本末尾的 `max` 个字符
+fn tail(text: &str, max: usize) -> &str {
+    let count = text.chars().count();
+    match text.char_indices().nth(count.saturating_sub(max)) {
+        Some((start, _)) => &text[start..],
+        None => text,
+    }
+}
@@ -0,0 +1,469 @@
+//! 场景文件格式
+//!
+//! ```yaml
+//! name: delegation
+//! company:                  # CompanyConfig；Agent 可以省略 llm_config、mode 和角色的列表字段
+//!   name: Acme
+//!   organization:
+//!     agents:
+//!       - id: pm
+//!         name: Pat
+//!         role: { title: PM, system_prompt: "You plan work" }
+//! groups:                   # 运行前创建的群聊
+//!   - { id: group-launch, members: [pm, dev] }
+//! llm:                      # 每个 Agent 的模拟 LLM 脚本，按顺序逐步使用
+//!   pm:
+//!     - when: "login page"
+//!       send: { to: dev, content: "Please build the login page" }
+//! events:                   # 依次注入的外部事件
+//!   - user_message: { from: "user:alice", to: pm, content: "We need a login page" }
+//!   - advance_clock: 5m
+//! expect:                   # 全部满足或超时后结束
+//!   - message: { from: pm, to: dev, content: "login page" }
+//! ```
+//!
+//! 文本模式含 `*` 或 `?` 时按通配符匹配整段文本，否则按子串匹配。
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::core::config::CompanyConfig;
+use crate::core::watchdog::pattern::glob_match;
+use crate::core::watchdog::WatchdogRule;
+
+/// 默认超时（毫秒）：期望和 `wait_for` 事件最多等待这么久
+const DEFAULT_TIMEOUT_MS: u64 = 15_000;
+
+/// 一个场景
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// 公司配置，运行时每个 Agent 都指向模拟 LLM
+    pub company: CompanyConfig,
+    /// 运行前创建的群聊
+    #[serde(default)]
+    pub groups: Vec<ScenarioGroup>,
+    /// 注册到场景 Watchdog 的规则，`tool_result` 事件按这些规则检查
+    #[serde(default)]
+    pub watchdog: Vec<WatchdogRule>,
+    /// Agent ID -> 模拟 LLM 脚本
+    #[serde(default)]
+    pub llm: BTreeMap<String, Vec<LlmStep>>,
+    #[serde(default)]
+    pub events: Vec<ScenarioEvent>,
+    #[serde(default)]
+    pub expect: Vec<Expectation>,
+    /// 手动时钟的起始时间（秒级时间戳），默认为当前时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<i64>,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+impl Scenario {
+    /// 解析 YAML，补齐 Agent 省略的字段
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let mut value: YamlValue = serde_yaml::from_str(yaml).context("Invalid scenario YAML")?;
+        if let Some(organization) = value.get_mut("company").and_then(|c| c.get_mut("organization")) {
+            fill_organization_defaults(organization);
+        }
+        // serde_yaml 0.9 只接受 `!Tag` 形式的枚举，经 JSON 转换以支持 `variant: {...}` 写法
+        let value = serde_json::to_value(value).context("Invalid scenario")?;
+        let scenario: Scenario = serde_json::from_value(value).context("Invalid scenario")?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// 读取场景文件
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let yaml = std::fs::read_to_string(path).with_context(|| format!("Failed to read scenario {}", path.display()))?;
+        Self::from_yaml(&yaml).with_context(|| format!("Failed to load scenario {}", path.display()))
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms)
+    }
+
+    /// 脚本、群聊和 Watchdog 规则引用的 Agent 必须存在
+    fn validate(&self) -> Result<()> {
+        let agents = &self.company.organization.agents;
+        let known = |id: &str| agents.iter().any(|a| a.id == id);
+        if let Some(unknown) = self.llm.keys().find(|id| !known(id)) {
+            anyhow::bail!("Scenario {} scripts unknown agent {}", self.name, unknown);
+        }
+        if let Some(rule) = self.watchdog.iter().find(|r| !known(&r.target_agent_id)) {
+            anyhow::bail!("Scenario {} watchdog rule {} targets unknown agent {}", self.name, rule.id, rule.target_agent_id);
+        }
+        if let Some(group) = self.groups.iter().find(|g| g.members.is_empty()) {
+            anyhow::bail!("Scenario {} group {} has no members", self.name, group.id);
+        }
+        Ok(())
+    }
+}
+
+/// 场景文件只需写出与测试有关的字段：`departments`、Agent 的 `llm_config`、`mode`、
+/// `department_id` 以及角色的 `responsibilities`、`expertise`、`system_prompt` 可以省略
+fn fill_organization_defaults(organization: &mut YamlValue) {
+    let Some(organization) = organization.as_mapping_mut() else {
+        return;
+    };
+    insert_default(organization, "departments", YamlValue::Sequence(Vec::new()));
+    for department in organization
+        .get_mut("departments")
+        .and_then(|d| d.as_sequence_mut())
+        .into_iter()
+        .flatten()
+        .filter_map(|d| d.as_mapping_mut())
+    {
+        insert_default(department, "parent_id", YamlValue::Null);
+        insert_default(department, "leader_id", YamlValue::Null);
+    }
+    let Some(agents) = organization.get_mut("agents").and_then(|a| a.as_sequence_mut()) else {
+        return;
+    };
+    for agent in agents.iter_mut().filter_map(|a| a.as_mapping_mut()) {
+        let llm_config = serde_yaml::to_value(crate::domain::LLMConfig::openai("testkit")).unwrap_or_default();
+        insert_default(agent, "llm_config", llm_config);
+        insert_default(agent, "mode", YamlValue::String("Passive".to_string()));
+        insert_default(agent, "department_id", YamlValue::Null);
+        if let Some(role) = agent.get_mut("role").and_then(|r| r.as_mapping_mut()) {
+            insert_default(role, "responsibilities", YamlValue::Sequence(Vec::new()));
+            insert_default(role, "expertise", YamlValue::Sequence(Vec::new()));
+            insert_default(role, "system_prompt", YamlValue::String(String::new()));
+        }
+    }
+}
+
+fn insert_default(mapping: &mut Mapping, key: &str, value: YamlValue) {
+    if !mapping.contains_key(key) {
+        mapping.insert(YamlValue::String(key.to_string()), value);
+    }
+}
+
+/// 运行前创建的群聊
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioGroup {
+    pub id: String,
+    /// 默认与 ID 相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 默认为第一个成员
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator: Option<String>,
+    pub members: Vec<String>,
+}
+
+/// 脚本中的一步：请求内容匹配 `when` 时回复 `reply`，每步只使用一次
+///
+/// 同一 Agent 的脚本严格按顺序使用，下一步不匹配时回复 `{"action":"wait"}`；
+/// 省略 `when` 的步骤匹配任意请求。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LlmStep {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+    #[serde(flatten)]
+    pub reply: LlmReply,
+}
+
+/// 模拟 LLM 的回复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LlmReply {
+    /// `send_message` 决策，以 `group-` / `group_` 开头的目标为群聊
+    Send { to: String, content: String },
+    /// `create_group` 决策
+    CreateGroup { name: String, members: Vec<String> },
+    /// 工具调用（OpenAI tool_calls），只回复给带工具列表的请求
+    Tool {
+        name: String,
+        #[serde(default)]
+        params: Value,
+    },
+    /// 原样返回的文本
+    Text(String),
+}
+
+/// 注入的外部事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioEvent {
+    /// 经消息总线发出的消息；目标是已存在的群聊时发到群聊
+    UserMessage { from: String, to: String, content: String },
+    /// 发给公司 Web 路由的请求
+    Webhook(WebhookRequest),
+    /// 推进手动时钟，如 `90s`、`5m`、`2h`、`1d`
+    AdvanceClock(#[serde(deserialize_with = "deserialize_duration", serialize_with = "serialize_duration")] Duration),
+    /// 工具执行结果，交给场景 Watchdog 按规则检查
+    ToolResult {
+        tool: String,
+        caller: String,
+        #[serde(default)]
+        result: Value,
+    },
+    /// 等到期望满足后再继续，超时记为失败
+    WaitFor(Expectation),
+}
+
+/// Webhook 请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookRequest {
+    #[serde(default = "default_method")]
+    pub method: String,
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub body: Value,
+    /// 以该用户的身份签发 Bearer token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub as_user: Option<String>,
+    /// 令牌中的用户是否为总监（访问管理接口）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub director: bool,
+    /// 期望的状态码，不一致时记为失败
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect_status: Option<u16>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+/// 期望
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expectation {
+    /// 经消息总线或 Web 接口发出的消息
+    Message(MessagePattern),
+    /// Agent 调用了工具，`params` 中列出的字段需一致（字符串按文本模式匹配）
+    ToolCalled {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        agent: Option<String>,
+        tool: String,
+        #[serde(default, skip_serializing_if = "Value::is_null")]
+        params: Value,
+    },
+    /// 生命周期事件（[`CompanyEvent::kind`](crate::core::events::CompanyEvent::kind)）
+    Event {
+        kind: String,
+        /// 事件关联的 Agent（`agent_id` 或 Watchdog 的 `target_agent_id`）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        agent: Option<String>,
+    },
+    /// 存储状态
+    Store(StorePredicate),
+}
+
+/// 消息匹配条件；`count` 为空时至少一条，否则恰好这么多条
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessagePattern {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    /// Agent ID、用户 principal 或群聊 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+}
+
+/// 存储状态断言
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorePredicate {
+    /// 已保存的消息
+    Messages(MessagePattern),
+    /// 已保存的任务；`count` 为空时至少一个
+    Task {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        assignee: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        creator: Option<String>,
+        /// 如 `open`、`done`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        status: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        count: Option<usize>,
+    },
+    /// 群聊存在且包含这些成员
+    Group {
+        id: String,
+        #[serde(default)]
+        members: Vec<String>,
+    },
+}
+
+/// 文本模式：含 `*` 或 `?` 时按通配符匹配整段文本，否则按子串匹配
+pub fn text_matches(pattern: &str, text: &str) -> bool {
+    if pattern.contains(['*', '?']) {
+        glob_match(pattern, text)
+    } else {
+        text.contains(pattern)
+    }
+}
+
+/// `expected` 中列出的字段是否都与 `actual` 一致；不一致时返回第一处差异
+///
+/// 对象只比较列出的键，数组逐项比较，字符串按 [`text_matches`] 匹配。
+pub fn json_subset(expected: &Value, actual: &Value) -> std::result::Result<(), String> {
+    subset_at("$", expected, actual)
+}
+
+fn subset_at(path: &str, expected: &Value, actual: &Value) -> std::result::Result<(), String> {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => expected.iter().try_for_each(|(key, value)| {
+            let path = format!("{}.{}", path, key);
+            match actual.get(key) {
+                Some(actual) => subset_at(&path, value, actual),
+                None => Err(format!("{}: expected {}, missing", path, value)),
+            }
+        }),
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => expected
+            .iter()
+            .zip(actual)
+            .enumerate()
+            .try_for_each(|(i, (e, a))| subset_at(&format!("{}[{}]", path, i), e, a)),
+        (Value::String(pattern), Value::String(text)) if text_matches(pattern, text) => Ok(()),
+        (expected, actual) if expected == actual => Ok(()),
+        (expected, actual) => Err(format!("{}: expected {}, got {}", path, expected, actual)),
+    }
+}
+
+/// 解析 `250ms`、`90s`、`5m`、`2h`、`1d`，纯数字按秒
+pub fn parse_duration(text: &str) -> Result<Duration> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().with_context(|| format!("Invalid duration {:?}", text))?;
+    let millis = match unit.trim() {
+        "ms" => number,
+        "" | "s" => number * 1000,
+        "m" => number * 60_000,
+        "h" => number * 3_600_000,
+        "d" => number * 86_400_000,
+        other => anyhow::bail!("Invalid duration unit {:?} in {:?}", other, text),
+    };
+    Ok(Duration::from_millis(millis))
+}
+
+fn deserialize_duration<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Duration, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Seconds(u64),
+        Text(String),
+    }
+    match Raw::deserialize(deserializer)? {
+        Raw::Seconds(secs) => Ok(Duration::from_secs(secs)),
+        Raw::Text(text) => parse_duration(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+fn serialize_duration<S: serde::Serializer>(duration: &Duration, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{}ms", duration.as_millis()))
+}
+
+impl fmt::Display for MessagePattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.count {
+            Some(count) => write!(f, "exactly {} message(s)", count)?,
+            None => f.write_str("a message")?,
+        }
+        if let Some(from) = &self.from {
+            write!(f, " from {}", from)?;
+        }
+        if let Some(to) = &self.to {
+            write!(f, " to {}", to)?;
+        }
+        if let Some(content) = &self.content {
+            write!(f, " matching {:?}", content)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expectation::Message(pattern) => write!(f, "{} delivered", pattern),
+            Expectation::ToolCalled { agent, tool, params } => {
+                write!(f, "tool {} called", tool)?;
+                if let Some(agent) = agent {
+                    write!(f, " by {}", agent)?;
+                }
+                if !params.is_null() {
+                    write!(f, " with {}", params)?;
+                }
+                Ok(())
+            }
+            Expectation::Event { kind, agent } => match agent {
+                Some(agent) => write!(f, "event {} for {}", kind, agent),
+                None => write!(f, "event {}", kind),
+            },
+            Expectation::Store(StorePredicate::Messages(pattern)) => write!(f, "store has {}", pattern),
+            Expectation::Store(StorePredicate::Task { title, assignee, creator, status, count }) => {
+                match count {
+                    Some(count) => write!(f, "store has exactly {} task(s)", count)?,
+                    None => f.write_str("store has a task")?,
+                }
+                if let Some(title) = title {
+                    write!(f, " titled {:?}", title)?;
+                }
+                if let Some(assignee) = assignee {
+                    write!(f, " assigned to {}", assignee)?;
+                }
+                if let Some(creator) = creator {
+                    write!(f, " created by {}", creator)?;
+                }
+                if let Some(status) = status {
+                    write!(f, " with status {}", status)?;
+                }
+                Ok(())
+            }
+            Expectation::Store(StorePredicate::Group { id, members }) if members.is_empty() => {
+                write!(f, "group {} exists", id)
+            }
+            Expectation::Store(StorePredicate::Group { id, members }) => {
+                write!(f, "group {} has members {}", id, members.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ScenarioEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScenarioEvent::UserMessage { from, to, .. } => write!(f, "message {} -> {}", from, to),
+            ScenarioEvent::Webhook(request) => write!(f, "webhook {} {}", request.method, request.path),
+            ScenarioEvent::AdvanceClock(duration) => write!(f, "advance clock {:?}", duration),
+            ScenarioEvent::ToolResult { tool, caller, .. } => write!(f, "tool result {} from {}", tool, caller),
+            ScenarioEvent::WaitFor(expectation) => write!(f, "wait for {}", expectation),
+        }
+    }
+}
+
+impl fmt::Display for LlmReply {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LlmReply::Send { to, content } => write!(f, "send {:?} to {}", content, to),
+            LlmReply::CreateGroup { name, .. } => write!(f, "create group {:?}", name),
+            LlmReply::Tool { name, params } => write!(f, "call {} with {}", name, params),
+            LlmReply::Text(text) => write!(f, "reply {:?}", text),
+        }
+    }
+}
@@ -44,6 +44,8 @@ async fn test_agent_responds_to_message() {
         timestamp: chrono::Utc::now().timestamp(),
         reply_to: None,
         mentions: vec![],
+        metadata: Default::default(),
+        priority: Default::default(),
     };
 
     // 验证agent可以处理消息
@@ -136,6 +138,8 @@ async fn test_agent_message_routing() {
         timestamp: chrono::Utc::now().timestamp(),
         reply_to: None,
         mentions: vec![],
+        metadata: Default::default(),
+        priority: Default::default(),
     };
 
     let message_to_group = Message {
@@ -146,6 +150,8 @@ async fn test_agent_message_routing() {
         timestamp: chrono::Utc::now().timestamp(),
         reply_to: None,
         mentions: vec![],
+        metadata: Default::default(),
+        priority: Default::default(),
     };
 
     // 验证消息目标类型
@@ -183,6 +189,8 @@ async fn test_agent_conversation_flow() {
             timestamp: chrono::Utc::now().timestamp(),
             reply_to: None,
             mentions: vec![],
+            metadata: Default::default(),
+            priority: Default::default(),
         },
         Message {
             id: "msg-2".to_string(),
@@ -192,6 +200,8 @@ async fn test_agent_conversation_flow() {
             timestamp: chrono::Utc::now().timestamp() + 1,
             reply_to: Some("msg-1".to_string()),
             mentions: vec![],
+            metadata: Default::default(),
+            priority: Default::default(),
         },
         Message {
             id: "msg-3".to_string(),
@@ -201,6 +211,8 @@ async fn test_agent_conversation_flow() {
             timestamp: chrono::Utc::now().timestamp() + 2,
             reply_to: Some("msg-2".to_string()),
             mentions: vec![],
+            metadata: Default::default(),
+            priority: Default::default(),
         },
     ];
 
@@ -0,0 +1,52 @@
+//! 应用配置测试：数据目录与数据库位置
+
+use std::path::Path;
+
+use imitatort::AppConfig;
+
+fn config(db_path: &str, data_dir: Option<&Path>) -> AppConfig {
+    AppConfig {
+        db_path: db_path.to_string(),
+        data_dir: data_dir.map(|dir| dir.to_string_lossy().into_owned()),
+        ..AppConfig::default()
+    }
+}
+
+#[test]
+fn test_prepare_data_dir_creates_layout() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path().join("data");
+    let dirs = config("imitatort.db", Some(&root)).prepare_data_dir().unwrap();
+
+    assert_eq!(dirs.root, root);
+    for dir in [&dirs.db, &dirs.workspaces, &dirs.attachments] {
+        assert!(dir.is_dir(), "{} not created", dir.display());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&dirs.root).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
+
+#[test]
+fn test_database_location() {
+    let data_dir = Path::new("/srv/imitatort");
+
+    // 未配置数据目录时保持原有行为
+    assert_eq!(config("imitatort.db", None).database_location(), "imitatort.db");
+
+    let expected = data_dir.join("db").join("imitatort.db");
+    assert_eq!(
+        config("imitatort.db", Some(data_dir)).database_location(),
+        expected.to_string_lossy()
+    );
+
+    // URI、内存库与绝对路径原样使用
+    let uri = "file:test?mode=memory&cache=shared";
+    assert_eq!(config(uri, Some(data_dir)).database_location(), uri);
+    assert_eq!(config(":memory:", Some(data_dir)).database_location(), ":memory:");
+    let absolute = std::env::temp_dir().join("abs.db").to_string_lossy().into_owned();
+    assert_eq!(config(&absolute, Some(data_dir)).database_location(), absolute);
+}
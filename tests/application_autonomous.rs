@@ -1,6 +1,237 @@
 //! 自主Agent实现测试
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use chrono::{NaiveTime, TimeZone, Utc, Weekday};
+use serde_json::{json, Value};
+
+use imitatort::application::autonomous::{AutonomousAgent, AUTO_REPLY_KEY};
+use imitatort::core::agent::{AgentRuntime, Context, SnapshotConfig};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::scheduler::{SchedulerConfig, TurnPriority, TurnScheduler};
+use imitatort::core::store::MemoryStore;
+use imitatort::domain::Availability;
+use imitatort::{Agent, LLMConfig, Message, Role};
+
 #[test]
 fn test_autonomous_agent_creation() {
     // 这只是编译时检查，需要真实LLM才能运行
 }
+
+async fn spawn_llm() -> (Arc<AtomicUsize>, String) {
+    async fn completions(State(calls): State<Arc<AtomicUsize>>, Json(_): Json<Value>) -> Json<Value> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": json!({ "action": "wait" }).to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state(calls.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (calls, format!("http://{}", addr))
+}
+
+/// 记录每次请求的 prompt
+async fn spawn_recording_llm() -> (Arc<Mutex<Vec<String>>>, String) {
+    async fn completions(State(prompts): State<Arc<Mutex<Vec<String>>>>, Json(body): Json<Value>) -> Json<Value> {
+        prompts.lock().unwrap().push(body["messages"].to_string());
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": json!({ "action": "wait" }).to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state(prompts.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (prompts, format!("http://{}", addr))
+}
+
+fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+    Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap().timestamp()
+}
+
+#[tokio::test]
+async fn test_agent_outside_working_hours_acknowledges_and_waits() {
+    let (calls, url) = spawn_llm().await;
+    let weekdays = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+    let schedule = Availability::new("Europe/Berlin").with_window(
+        &weekdays,
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+    );
+    let dev = Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k").with_base_url(url))
+        .with_availability(schedule);
+
+    // 周五 18:00（柏林）下班后
+    let clock = Arc::new(ManualClock::new(utc(2026, 3, 27, 17, 0)));
+    let events = Arc::new(EventBus::new());
+    let mut event_rx = events.subscribe();
+    let bus = Arc::new(MessageBus::new());
+    let mut alice_rx = bus.register("alice");
+    let autonomous = AutonomousAgent::new(dev, bus.clone())
+        .await
+        .unwrap()
+        .with_clock(clock.clone())
+        .with_events(events);
+    let handle = tokio::spawn(async move {
+        let _ = autonomous.run_loop().await;
+    });
+
+    let first = Message::private("alice", "dev", "Can you review my PR?");
+    bus.send(first.clone()).await.unwrap();
+    bus.send(Message::private("alice", "dev", "It's small")).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    // 每个发送者只自动回复一次，不调用 LLM
+    let ack = alice_rx.try_recv().unwrap();
+    assert_eq!(ack.reply_to.as_deref(), Some(first.id.as_str()));
+    assert_eq!(ack.metadata(AUTO_REPLY_KEY), Some("true"));
+    assert!(ack.content.contains("Mon 09:00 (Europe/Berlin)"), "{}", ack.content);
+    assert!(alice_rx.try_recv().is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    // 周一上班后处理排队的消息
+    clock.set(utc(2026, 3, 30, 7, 30));
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    handle.abort();
+    assert!(calls.load(Ordering::SeqCst) >= 1);
+
+    let mut presence = Vec::new();
+    while let Ok(event) = event_rx.try_recv() {
+        if let CompanyEvent::AgentPresenceChanged { available, next_available_at, .. } = event {
+            presence.push((available, next_available_at));
+        }
+    }
+    assert_eq!(presence, vec![(false, Some(utc(2026, 3, 30, 7, 0))), (true, None)]);
+}
+
+#[tokio::test]
+async fn test_messages_after_snapshot_wait_for_next_turn() {
+    let (prompts, url) = spawn_recording_llm().await;
+    let dev = Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k").with_base_url(url));
+    let bus = Arc::new(MessageBus::with_store(Arc::new(MemoryStore::new())));
+    let scheduler = Arc::new(TurnScheduler::new(SchedulerConfig {
+        max_concurrent_turns: 1,
+        ..Default::default()
+    }));
+    let autonomous = AutonomousAgent::new(dev, bus.clone())
+        .await
+        .unwrap()
+        .with_scheduler(scheduler.clone());
+
+    // 占住唯一的轮次许可，让 Agent 取完快照后停在等待许可
+    let permit = scheduler.acquire("someone-else", TurnPriority::User).await;
+    bus.send(Message::private("alice", "dev", "First half of my thought")).await.unwrap();
+    let handle = tokio::spawn(async move {
+        let _ = autonomous.run_loop().await;
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    bus.send(Message::private("alice", "dev", "second half, which changes everything")).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drop(permit);
+    // 模拟 LLM 选择 wait，Agent 休眠 1 秒后进入下一轮
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    handle.abort();
+
+    let prompts = prompts.lock().unwrap();
+    assert!(prompts.len() >= 2, "{:?}", prompts);
+    // 第一轮只看到快照内的消息，后到的只提示数量（既不在未读里，也不在历史里）
+    assert!(prompts[0].contains("First half of my thought"));
+    assert!(!prompts[0].contains("second half"), "{}", prompts[0]);
+    assert!(prompts[0].contains("Arrived while you were thinking: 1 new message."));
+    // 下一轮完整可见
+    assert!(prompts[1].contains("[alice]: second half, which changes everything"));
+    assert!(!prompts[1].contains("Arrived while you were thinking"));
+}
+
+#[tokio::test]
+async fn test_group_burst_is_debounced_into_one_snapshot() {
+    async fn first_prompt(snapshot: SnapshotConfig) -> String {
+        let (prompts, url) = spawn_recording_llm().await;
+        let dev = Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k").with_base_url(url));
+        let bus = Arc::new(MessageBus::new());
+        let _alice_rx = bus.register("alice");
+        bus.create_group("g1", "Team", "alice", vec!["alice".to_string(), "dev".to_string()])
+            .await
+            .unwrap();
+        let autonomous = AutonomousAgent::new(dev, bus.clone())
+            .await
+            .unwrap()
+            .with_snapshot_config(snapshot);
+        let handle = tokio::spawn(async move {
+            let _ = autonomous.run_loop().await;
+        });
+
+        for part in ["burst one", "burst two", "burst three"] {
+            bus.send(Message::group("alice", "g1", part)).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(60)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        handle.abort();
+        let prompts = prompts.lock().unwrap();
+        prompts[0].clone()
+    }
+
+    let debounced = first_prompt(SnapshotConfig { group_debounce_ms: 200, late_arrival_content: false, ..SnapshotConfig::default() }).await;
+    for part in ["burst one", "burst two", "burst three"] {
+        assert!(debounced.contains(part), "{}", debounced);
+    }
+
+    let immediate = first_prompt(SnapshotConfig { group_debounce_ms: 0, late_arrival_content: false, ..SnapshotConfig::default() }).await;
+    assert!(immediate.contains("burst one"));
+    assert!(!immediate.contains("burst three"), "{}", immediate);
+}
+
+#[tokio::test]
+async fn test_late_arrival_content_behind_flag() {
+    let agent = Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k"));
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+    let late = vec![
+        Message::private("alice", "dev", "one more thing"),
+        Message::private("bob", "dev", "me too"),
+    ];
+
+    let counted = runtime.build_thinking_prompt(&Context::default().with_late_arrivals(late.clone(), false));
+    assert!(counted.contains("Arrived while you were thinking: 2 new messages."));
+    assert!(!counted.contains("one more thing"));
+
+    let shown = runtime.build_thinking_prompt(&Context::default().with_late_arrivals(late, true));
+    assert!(shown.contains("- [alice]: one more thing"));
+    assert!(shown.contains("- [bob]: me too"));
+}
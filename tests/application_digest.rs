@@ -0,0 +1,364 @@
+//! 活动摘要测试：摘要结构快照、某一部分失败时省略并注明、周期对齐与不重复生成、
+//! token 上限、群聊发言分布、发到群聊和 GET /digests
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::application::digest::{DigestGenerator, Summarizer, DIGEST_SENDER};
+use imitatort::core::budget::DepartmentBudgets;
+use imitatort::core::clock::ManualClock;
+use imitatort::core::config::{DigestConfig, DigestRecipients, DigestSections};
+use imitatort::core::events::CompanyEvent;
+use imitatort::core::group_stats::{GroupStatsConfig, GroupStatsTracker};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store, TaskFilter};
+use imitatort::domain::{
+    Agent, Department, DigestCadence, Group, LLMConfig, Message, Organization, Role, Task, TaskStatus,
+};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::web::{create_router, AppState};
+
+fn utc(d: u32, h: u32) -> i64 {
+    Utc.with_ymd_and_hms(2026, 3, d, h, 0, 0).unwrap().timestamp()
+}
+
+/// 2026-03-10（周二）09:00，最近的每日周期是 03-09 08:00 到 03-10 08:00
+fn now() -> i64 {
+    utc(10, 9)
+}
+
+/// 记录收到的记录，`fail` 时总是失败
+struct MockSummarizer {
+    transcripts: Mutex<Vec<String>>,
+    fail: bool,
+}
+
+impl MockSummarizer {
+    fn new(fail: bool) -> Arc<Self> {
+        Arc::new(Self { transcripts: Mutex::new(Vec::new()), fail })
+    }
+}
+
+#[async_trait]
+impl Summarizer for MockSummarizer {
+    async fn summarize(&self, group_name: &str, transcript: &str) -> Result<String> {
+        self.transcripts.lock().unwrap().push(transcript.to_string());
+        if self.fail {
+            return Err(anyhow!("model overloaded"));
+        }
+        Ok(format!("{} agreed on {} point(s).\n", group_name, transcript.lines().count()))
+    }
+}
+
+fn message_at(message: Message, timestamp: i64) -> Message {
+    Message { timestamp, ..message }
+}
+
+fn done_task(title: &str, assignee: &str, at: i64) -> Task {
+    Task {
+        status: TaskStatus::Done,
+        updated_at: at,
+        ..Task::new(title, "alice", at - 3600).with_assignee(assignee)
+    }
+}
+
+async fn seeded_store() -> Arc<dyn Store> {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering").with_llm_budget(1000));
+    org.add_department(Department::top_level("ops", "Operations"));
+    for (id, dept) in [("alice", "eng"), ("bob", "eng"), ("carol", "ops")] {
+        org.add_agent(Agent::new(id, id, Role::simple("Engineer", "You code"), LLMConfig::openai("k")).with_department(dept));
+    }
+    store.save_organization(&org).await.unwrap();
+    store
+        .save_group(&Group::new("launch", "Launch", "alice", vec!["alice".into(), "bob".into()]))
+        .await
+        .unwrap();
+
+    let messages = [
+        message_at(Message::group("alice", "launch", "Ship on Friday?"), utc(9, 10)),
+        message_at(Message::group("bob", "launch", "Agreed, Friday it is."), utc(9, 11)),
+        message_at(Message::private("alice", "carol", "Can you prepare the rollout?"), utc(9, 12)),
+        message_at(Message::private("carol", "alice", "Done."), utc(10, 7)),
+        // 周期之外
+        message_at(Message::group("alice", "launch", "Old news"), utc(8, 12)),
+        message_at(Message::private("bob", "alice", "Too late"), utc(10, 8)),
+    ];
+    store.save_messages(&messages).await.unwrap();
+
+    store.save_task(&done_task("Write release notes", "bob", utc(9, 15))).await.unwrap();
+    store.save_task(&done_task("Old task", "bob", utc(8, 15))).await.unwrap();
+    store.save_task(&Task::new("Still open", "alice", utc(9, 9))).await.unwrap();
+    store
+}
+
+fn generator(store: Arc<dyn Store>, clock: Arc<ManualClock>) -> DigestGenerator {
+    DigestGenerator::new(DigestConfig::enabled(DigestCadence::Daily), store).with_clock(clock)
+}
+
+fn watchdog(rule_id: &str) -> CompanyEvent {
+    CompanyEvent::WatchdogTriggered {
+        rule_id: rule_id.into(),
+        tool_id: "deploy".into(),
+        target_agent_id: "carol".into(),
+        trace_id: "t".into(),
+    }
+}
+
+#[tokio::test]
+async fn test_digest_structure() {
+    let store = seeded_store().await;
+    let clock = Arc::new(ManualClock::new(utc(9, 20)));
+    let budgets = Arc::new(DepartmentBudgets::with_clock(clock.clone()));
+    budgets.sync(&store.load_organization().await.unwrap());
+    let summarizer = MockSummarizer::new(false);
+    let generator = generator(store.clone(), clock.clone())
+        .with_summarizer(summarizer.clone())
+        .with_budgets(budgets.clone());
+
+    generator.record(&watchdog("deploy-failed"));
+    generator.record(&watchdog("deploy-failed"));
+    generator.record(&watchdog("slow-build"));
+    clock.set(now());
+    // 预算用量按生成当天（UTC）统计
+    budgets.record("alice", 300);
+    budgets.record("carol", 50);
+
+    let (start, end) = generator.latest_period(now());
+    assert_eq!((start, end), (utc(9, 8), utc(10, 8)));
+    let digest = generator.generate(start, end).await;
+    assert!(digest.omitted.is_empty(), "{:?}", digest.omitted);
+    assert_eq!(digest.generated_at, now());
+
+    let tasks = store.load_tasks(TaskFilter::new()).await.unwrap();
+    let release_notes = tasks.iter().find(|t| t.title == "Write release notes").unwrap();
+    let mut value = serde_json::to_value(&digest).unwrap();
+    value.as_object_mut().unwrap().remove("id");
+    assert_eq!(
+        value,
+        json!({
+            "cadence": "daily",
+            "period_start": utc(9, 8),
+            "period_end": utc(10, 8),
+            "generated_at": now(),
+            "sections": [
+                {
+                    "kind": "messages",
+                    "total": 4,
+                    "direct": 2,
+                    "group": 2,
+                    "top_senders": [
+                        { "sender": "alice", "count": 2 },
+                        { "sender": "bob", "count": 1 },
+                        { "sender": "carol", "count": 1 },
+                    ],
+                },
+                {
+                    "kind": "decisions",
+                    "groups": [
+                        { "group_id": "launch", "group_name": "Launch", "summary": "Launch agreed on 2 point(s)." },
+                    ],
+                },
+                {
+                    "kind": "tasks",
+                    "completed": [
+                        { "id": release_notes.id, "title": "Write release notes", "assignee": "bob" },
+                    ],
+                },
+                {
+                    "kind": "incidents",
+                    "incidents": [
+                        { "rule_id": "deploy-failed", "tool_id": "deploy", "count": 2 },
+                        { "rule_id": "slow-build", "tool_id": "deploy", "count": 1 },
+                    ],
+                },
+                {
+                    "kind": "budgets",
+                    "departments": [
+                        { "department_id": "eng", "department_name": "Engineering", "used": 300, "budget": 1000 },
+                    ],
+                },
+            ],
+        })
+    );
+    assert_eq!(
+        summarizer.transcripts.lock().unwrap().as_slice(),
+        ["alice: Ship on Friday?\nbob: Agreed, Friday it is."]
+    );
+
+    let (subject, body) = generator.render(&digest);
+    assert_eq!(subject, "Company digest for 2026-03-09 08:00 UTC – 2026-03-10 08:00 UTC");
+    assert!(body.contains("Messages: 4 (2 direct, 2 in groups)"), "{}", body);
+    assert!(body.contains("- Launch: Launch agreed on 2 point(s)."), "{}", body);
+    assert!(body.contains("- Engineering: 300/1000"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_failing_section_is_omitted_with_note() {
+    let store = seeded_store().await;
+    let clock = Arc::new(ManualClock::new(now()));
+    let sections = DigestSections { incidents: false, ..DigestSections::default() };
+    let generator = DigestGenerator::new(DigestConfig::enabled(DigestCadence::Daily).with_sections(sections), store)
+        .with_clock(clock)
+        .with_summarizer(MockSummarizer::new(true));
+
+    let (start, end) = generator.latest_period(now());
+    let digest = generator.generate(start, end).await;
+    let kinds: Vec<&str> = digest.sections.iter().map(|s| s.kind()).collect();
+    assert_eq!(kinds, ["messages", "tasks"]);
+    assert_eq!(digest.omitted.len(), 2);
+    assert_eq!(digest.omitted[0].section, "decisions");
+    assert!(digest.omitted[0].reason.contains("model overloaded"), "{}", digest.omitted[0].reason);
+    assert_eq!(digest.omitted[1].section, "budgets");
+
+    let (_, body) = generator.render(&digest);
+    assert!(body.contains("Not included: decisions (failed to summarize launch: model overloaded)"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_group_activity_comes_from_group_stats() {
+    let store = seeded_store().await;
+    let clock = Arc::new(ManualClock::new(now()));
+    let tracker = Arc::new(GroupStatsTracker::new(GroupStatsConfig::default(), store.clone()).with_clock(clock.clone()));
+    for message in store.load_messages(MessageFilter::new().oldest_first()).await.unwrap() {
+        tracker.record_message(&message).await.unwrap();
+    }
+    let sections = DigestSections {
+        messages: false,
+        decisions: false,
+        tasks: false,
+        incidents: false,
+        budgets: false,
+        group_activity: true,
+    };
+    let config = DigestConfig::enabled(DigestCadence::Daily).with_sections(sections);
+
+    // 未挂载群聊活动统计时省略并注明
+    let without = DigestGenerator::new(config.clone(), store.clone()).with_clock(clock.clone());
+    let (start, end) = without.latest_period(now());
+    let digest = without.generate(start, end).await;
+    assert_eq!(digest.omitted[0].reason, "group stats not attached");
+
+    let generator = DigestGenerator::new(config, store).with_clock(clock).with_group_stats(tracker);
+    let digest = generator.generate(start, end).await;
+    assert!(digest.omitted.is_empty(), "{:?}", digest.omitted);
+    let value = serde_json::to_value(&digest.sections).unwrap();
+    assert_eq!(
+        value,
+        json!([{
+            "kind": "group_activity",
+            "groups": [{
+                "group_id": "launch",
+                "group_name": "Launch",
+                "senders": [{ "sender": "alice", "count": 1 }, { "sender": "bob", "count": 1 }],
+            }],
+        }])
+    );
+    let (_, body) = generator.render(&digest);
+    assert!(body.contains("Who spoke in groups:\n- Launch: alice (1), bob (1)"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_token_budget_keeps_newest_messages() {
+    let store = seeded_store().await;
+    let clock = Arc::new(ManualClock::new(now()));
+    let summarizer = MockSummarizer::new(false);
+    // "bob: Agreed, Friday it is." 约 7 个 token，放不下第一条
+    let config = DigestConfig::enabled(DigestCadence::Daily).with_token_budget(10);
+    let generator = DigestGenerator::new(config, store).with_clock(clock).with_summarizer(summarizer.clone());
+
+    generator.generate(utc(9, 8), utc(10, 8)).await;
+    assert_eq!(summarizer.transcripts.lock().unwrap().as_slice(), ["bob: Agreed, Friday it is."]);
+}
+
+#[tokio::test]
+async fn test_run_due_persists_and_posts_to_group() {
+    let store = seeded_store().await;
+    let clock = Arc::new(ManualClock::new(now()));
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _alice = bus.register("alice");
+    let _bob = bus.register("bob");
+    bus.create_group("launch", "Launch", "alice", vec!["alice".into(), "bob".into()])
+        .await
+        .unwrap();
+    let mut group = bus.subscribe_group("launch").unwrap();
+    let config = DigestConfig::enabled(DigestCadence::Daily).with_recipients(DigestRecipients {
+        group: Some("launch".to_string()),
+        ..DigestRecipients::default()
+    });
+    let generator = DigestGenerator::new(config, store.clone())
+        .with_clock(clock.clone())
+        .with_message_bus(bus);
+
+    let digest = generator.run_due().await.unwrap().unwrap();
+    assert_eq!(store.load_digests(10).await.unwrap(), vec![digest.clone()]);
+    let posted = group.recv().await.unwrap();
+    assert_eq!(posted.from, DIGEST_SENDER);
+    assert_eq!(posted.metadata.get("kind").map(String::as_str), Some("digest"));
+    assert!(posted.content.starts_with("Company digest for 2026-03-09 08:00 UTC"), "{}", posted.content);
+
+    // 同一周期不重复生成；下一个周期到期后再生成
+    assert!(generator.run_due().await.unwrap().is_none());
+    clock.set(utc(11, 8));
+    let next = generator.run_due().await.unwrap().unwrap();
+    assert_eq!((next.period_start, next.period_end), (utc(10, 8), utc(11, 8)));
+    let stored = store.load_digests(10).await.unwrap();
+    assert_eq!(stored.iter().map(|d| d.period_end).collect::<Vec<_>>(), [utc(11, 8), utc(10, 8)]);
+}
+
+#[test]
+fn test_weekly_period_ends_on_monday() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let generator = DigestGenerator::new(DigestConfig::enabled(DigestCadence::Weekly), store);
+    // 2026-03-09 是周一
+    assert_eq!(generator.latest_period(now()), (utc(2, 8), utc(9, 8)));
+    assert_eq!(generator.latest_period(utc(9, 8)), (utc(2, 8), utc(9, 8)));
+    assert_eq!(generator.latest_period(utc(9, 7)).1, Utc.with_ymd_and_hms(2026, 3, 2, 8, 0, 0).unwrap().timestamp());
+}
+
+#[tokio::test]
+async fn test_get_digests() {
+    let store = seeded_store().await;
+    let generator = generator(store.clone(), Arc::new(ManualClock::new(now())));
+    generator.run_due().await.unwrap().unwrap();
+
+    let jwt = JwtService::new("test-secret");
+    let token = |position: &str| {
+        jwt.generate_token(&UserInfo {
+            id: "u1".to_string(),
+            username: "u1".to_string(),
+            name: "U1".to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: position.to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+    };
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(Vec::new(), message_tx, store, jwt.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/api/v1/digests", addr);
+    let response = client.get(&url).bearer_auth(token("Employee")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let body: Value = client.get(&url).bearer_auth(token("Management")).send().await.unwrap().json().await.unwrap();
+    let digests = body["data"]["digests"].as_array().unwrap();
+    assert_eq!(digests.len(), 1);
+    assert_eq!(digests[0]["period_end"], utc(10, 8));
+    assert_eq!(digests[0]["omitted"][0]["section"], "decisions");
+}
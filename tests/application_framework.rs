@@ -7,10 +7,7 @@ use imitatort::domain::{Agent, Department, LLMConfig, Organization, Role};
 #[tokio::test]
 async fn test_company_builder() {
     let org = Organization::new();
-    let config = CompanyConfig {
-        name: "Test Co".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("Test Co", org);
 
     let temp_dir = tempfile::tempdir().unwrap();
     let db_path = temp_dir.path().join("test.db");
@@ -36,10 +33,7 @@ async fn test_agent_creation() {
     );
     org.add_agent(agent);
 
-    let config = CompanyConfig {
-        name: "Test".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("Test", org);
 
     let temp_dir = tempfile::tempdir().unwrap();
     let db_path = temp_dir.path().join("test.db");
@@ -68,10 +62,7 @@ async fn test_company_with_sqlite_store() {
     );
     org.add_agent(agent);
 
-    let config = CompanyConfig {
-        name: "Test Co".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("Test Co", org);
 
     // 使用 SQLite 构建
     let company = CompanyBuilder::with_sqlite(&db_path)
@@ -115,10 +106,7 @@ async fn test_company_load_from_sqlite() {
     .with_department("tech");
     org.add_agent(agent);
 
-    let config = CompanyConfig {
-        name: "Tech Co".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("Tech Co", org);
 
     // 创建并保存
     let company = CompanyBuilder::with_sqlite(&db_path)
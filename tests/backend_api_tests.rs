@@ -41,12 +41,7 @@ fn create_test_app_state() -> Arc<AppState> {
     // 创建JWT服务
     let jwt_service = JwtService::new("test-secret-for-testing");
 
-    Arc::new(AppState {
-        agents,
-        message_tx,
-        store,
-        jwt_service,
-    })
+    Arc::new(AppState::new(agents, message_tx, store, jwt_service))
 }
 
 #[tokio::test]
@@ -0,0 +1,506 @@
+//! 大对象存储测试：本地和 S3 兼容后端跑同一套用例（流式上传、范围读取、列出、删除），
+//! 以及附件接口的代理下载和预签名重定向
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures_util::stream::{self, StreamExt};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::blob::{
+    body_from_bytes, read_body, BlobBody, BlobError, BlobMetadata, BlobStore, BlobStoreConfig, ByteRange,
+    LocalBlobStore,
+};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+
+/// 确定性的测试内容
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+/// 分块给出内容的字节流
+fn chunked(data: &[u8], chunk: usize) -> BlobBody {
+    let chunks: Vec<std::io::Result<Bytes>> = data.chunks(chunk).map(|c| Ok(Bytes::copy_from_slice(c))).collect();
+    stream::iter(chunks).boxed()
+}
+
+/// 所有后端都必须满足的行为
+async fn exercise(store: Arc<dyn BlobStore>) {
+    let data = payload(3000);
+    let metadata = BlobMetadata::new()
+        .with_content_type("application/pdf")
+        .with("Filename", "report.pdf");
+    let put = store.put("docs/report.pdf", chunked(&data, 700), metadata.clone()).await.unwrap();
+    assert_eq!(put.size, 3000);
+
+    let info = store.head("docs/report.pdf").await.unwrap().unwrap();
+    assert_eq!(info.size, 3000);
+    assert_eq!(info.metadata, metadata);
+    assert_eq!(info.metadata.custom["filename"], "report.pdf");
+
+    let full = store.get("docs/report.pdf", None).await.unwrap().unwrap();
+    assert_eq!(full.range, None);
+    assert_eq!(read_body(full.body).await.unwrap(), data);
+
+    let part = store
+        .get("docs/report.pdf", Some(ByteRange::new(100, Some(199))))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(part.range, Some((100, 199)));
+    assert_eq!(part.info.size, 3000);
+    assert_eq!(read_body(part.body).await.unwrap(), data[100..200]);
+
+    // 开放结尾和超出末尾的范围截断到对象末尾
+    let tail = store
+        .get("docs/report.pdf", ByteRange::from_header("bytes=2900-"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(tail.range, Some((2900, 2999)));
+    assert_eq!(read_body(tail.body).await.unwrap(), data[2900..]);
+    let clipped = store
+        .get("docs/report.pdf", Some(ByteRange::new(2990, Some(5000))))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(clipped.range, Some((2990, 2999)));
+
+    let beyond = store.get("docs/report.pdf", Some(ByteRange::new(3000, None))).await;
+    match beyond.err().and_then(|e| e.downcast::<BlobError>().ok()) {
+        Some(BlobError::RangeNotSatisfiable { size, .. }) => assert_eq!(size, 3000),
+        other => panic!("expected RangeNotSatisfiable, got {:?}", other),
+    }
+
+    // 覆盖写入
+    store.put("docs/notes.txt", body_from_bytes("v1"), BlobMetadata::new()).await.unwrap();
+    store.put("docs/notes.txt", body_from_bytes("v2!"), BlobMetadata::new()).await.unwrap();
+    let notes = store.get("docs/notes.txt", None).await.unwrap().unwrap();
+    assert_eq!(read_body(notes.body).await.unwrap(), b"v2!");
+
+    store.put("other/a.bin", body_from_bytes(vec![1u8; 10]), BlobMetadata::new()).await.unwrap();
+    store.put("docs/deep/x.bin", body_from_bytes(Vec::new()), BlobMetadata::new()).await.unwrap();
+    let keys: Vec<String> = store.list("docs/").await.unwrap().into_iter().map(|b| b.key).collect();
+    assert_eq!(keys, ["docs/deep/x.bin", "docs/notes.txt", "docs/report.pdf"]);
+    let all = store.list("").await.unwrap();
+    assert_eq!(all.len(), 4);
+    assert_eq!(all.iter().find(|b| b.key == "other/a.bin").unwrap().size, 10);
+
+    store.delete("docs/report.pdf").await.unwrap();
+    assert!(store.head("docs/report.pdf").await.unwrap().is_none());
+    assert!(store.get("docs/report.pdf", None).await.unwrap().is_none());
+    store.delete("docs/report.pdf").await.unwrap();
+    let keys: Vec<String> = store.list("docs/").await.unwrap().into_iter().map(|b| b.key).collect();
+    assert_eq!(keys, ["docs/deep/x.bin", "docs/notes.txt"]);
+
+    for key in ["", "../etc/passwd", "a//b", "a/./b", "a\\b"] {
+        let error = store.put(key, body_from_bytes("x"), BlobMetadata::new()).await.unwrap_err();
+        assert!(matches!(error.downcast_ref::<BlobError>(), Some(BlobError::InvalidKey { .. })), "{}", key);
+    }
+}
+
+#[tokio::test]
+async fn test_local_blob_store() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let local = LocalBlobStore::new(temp_dir.path());
+    exercise(Arc::new(local)).await;
+
+    // 元数据目录不作为对象出现，删除后不留下空目录
+    let store = LocalBlobStore::new(temp_dir.path());
+    assert!(store.put(".blobmeta/x", body_from_bytes("x"), BlobMetadata::new()).await.is_err());
+    store.delete("docs/deep/x.bin").await.unwrap();
+    assert!(!temp_dir.path().join("docs/deep").exists());
+    assert!(store.download_url("docs/notes.txt", std::time::Duration::from_secs(60)).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_blob_store_config_defaults_to_data_dir() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let store = BlobStoreConfig::default().open(temp_dir.path().to_path_buf()).unwrap();
+    assert_eq!(store.backend(), "local");
+    store.put("attachments/a", body_from_bytes("hi"), BlobMetadata::new()).await.unwrap();
+    assert!(temp_dir.path().join("attachments/a").is_file());
+
+    let config: BlobStoreConfig = serde_json::from_str(r#"{"backend": "s3", "endpoint": "http://minio:9000", "bucket": "b"}"#).unwrap();
+    assert!(matches!(&config, BlobStoreConfig::S3(s3) if s3.region == "us-east-1" && s3.presign_downloads));
+    // 没有密钥时打开失败（未启用 s3 特性时同样失败）
+    assert!(config.open(temp_dir.path().to_path_buf()).is_err());
+}
+
+// ==================== 附件接口 ====================
+
+struct Server {
+    url: String,
+    jwt_service: JwtService,
+    client: reqwest::Client,
+}
+
+impl Server {
+    async fn start(blobs: Option<Arc<dyn BlobStore>>) -> Self {
+        let jwt_service = JwtService::new("test-secret");
+        let (message_tx, _) = broadcast::channel(16);
+        let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+        let mut state = AppState::new(Vec::new(), message_tx, store, jwt_service.clone());
+        if let Some(blobs) = blobs {
+            state = state.with_blob_store(blobs);
+        }
+        let app = create_router(Arc::new(state));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        Self {
+            url: format!("http://{}/api/v1", addr),
+            jwt_service,
+            client: reqwest::Client::builder().redirect(reqwest::redirect::Policy::none()).build().unwrap(),
+        }
+    }
+
+    fn token(&self, id: &str) -> String {
+        self.jwt_service
+            .generate_token(&UserInfo {
+                id: id.to_string(),
+                username: id.to_string(),
+                name: id.to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: "Employee".to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    }
+
+    /// 以分块请求体上传，返回附件 ID
+    async fn upload(&self, name: &str, data: &[u8]) -> String {
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> = data.chunks(512).map(|c| Ok(c.to_vec())).collect();
+        let response = self
+            .client
+            .post(format!("{}/attachments", self.url))
+            .query(&[("name", name)])
+            .bearer_auth(self.token("alice"))
+            .header("content-type", "text/plain")
+            .body(reqwest::Body::wrap_stream(stream::iter(chunks)))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["data"]["size"], data.len());
+        assert_eq!(body["data"]["name"], name);
+        body["data"]["id"].as_str().unwrap().to_string()
+    }
+
+    async fn download(&self, id: &str, range: Option<&str>) -> reqwest::Response {
+        let mut request = self
+            .client
+            .get(format!("{}/attachments/{}", self.url, id))
+            .bearer_auth(self.token("bob"));
+        if let Some(range) = range {
+            request = request.header("range", range);
+        }
+        request.send().await.unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_attachments_proxied_from_local_store() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let server = Server::start(Some(Arc::new(LocalBlobStore::new(temp_dir.path())))).await;
+    let data = payload(2000);
+    let id = server.upload("季度报告.txt", &data).await;
+    assert!(temp_dir.path().join("attachments").join(&id).is_file());
+
+    let response = server.download(&id, None).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/plain");
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    let disposition = response.headers()["content-disposition"].to_str().unwrap().to_string();
+    assert!(disposition.contains("filename*=UTF-8''%E5%AD%A3%E5%BA%A6%E6%8A%A5%E5%91%8A.txt"), "{}", disposition);
+    assert_eq!(response.bytes().await.unwrap(), data);
+
+    let response = server.download(&id, Some("bytes=10-19")).await;
+    assert_eq!(response.status(), 206);
+    assert_eq!(response.headers()["content-range"], "bytes 10-19/2000");
+    assert_eq!(response.bytes().await.unwrap(), data[10..20]);
+
+    let response = server.download(&id, Some("bytes=5000-")).await;
+    assert_eq!(response.status(), 416);
+    assert_eq!(response.headers()["content-range"], "bytes */2000");
+
+    // 只有上传者和管理员可以删除
+    let url = format!("{}/attachments/{}", server.url, id);
+    let response = server.client.delete(&url).bearer_auth(server.token("bob")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    let response = server.client.delete(&url).bearer_auth(server.token("alice")).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(server.download(&id, None).await.status(), 404);
+
+    assert_eq!(server.client.get(&url).send().await.unwrap().status(), 401);
+    assert_eq!(server.download("../../etc/passwd", None).await.status(), 404);
+}
+
+#[tokio::test]
+async fn test_attachments_unavailable_without_blob_store() {
+    let server = Server::start(None).await;
+    let response = server
+        .client
+        .post(format!("{}/attachments", server.url))
+        .bearer_auth(server.token("alice"))
+        .body("x")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 503);
+}
+
+#[cfg(feature = "s3")]
+mod s3 {
+    //! 针对进程内模拟 S3 服务运行同一套用例
+
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use axum::body::Bytes;
+    use axum::extract::{Query, State};
+    use axum::http::{HeaderMap, Method, StatusCode, Uri};
+    use axum::response::{IntoResponse, Response};
+    use axum::Router;
+
+    use imitatort::infrastructure::blob::{BlobStore, S3BlobStore, S3Config};
+
+    use super::{exercise, payload, Server};
+
+    const BUCKET: &str = "imitatort";
+    /// 每页最多返回的对象数，覆盖分页
+    const PAGE_SIZE: usize = 2;
+
+    #[derive(Clone)]
+    struct Object {
+        data: Vec<u8>,
+        headers: Vec<(String, String)>,
+    }
+
+    /// 进行中的分段上传：对象的元数据头和已上传的分段
+    type Upload = (Vec<(String, String)>, BTreeMap<u32, Vec<u8>>);
+
+    #[derive(Default)]
+    struct MockS3 {
+        objects: BTreeMap<String, Object>,
+        uploads: HashMap<String, Upload>,
+        next_upload: u32,
+        /// 完成的分段上传数
+        multipart_completed: usize,
+    }
+
+    type Shared = Arc<Mutex<MockS3>>;
+
+    /// 对象的元数据头：内容类型和 `x-amz-meta-*`
+    fn object_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .filter(|(name, _)| name.as_str() == "content-type" || name.as_str().starts_with("x-amz-meta-"))
+            .map(|(name, value)| (name.to_string(), value.to_str().unwrap().to_string()))
+            .collect()
+    }
+
+    fn with_headers(status: StatusCode, headers: &[(String, String)], body: Vec<u8>) -> Response {
+        let mut response = Response::builder().status(status);
+        for (name, value) in headers {
+            response = response.header(name, value);
+        }
+        response.body(axum::body::Body::from(body)).unwrap()
+    }
+
+    async fn handle(
+        State(s3): State<Shared>,
+        method: Method,
+        uri: Uri,
+        Query(query): Query<HashMap<String, String>>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Response {
+        let signed = headers
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|auth| auth.starts_with("AWS4-HMAC-SHA256 Credential=test-key/"));
+        if !signed || headers.get("x-amz-date").is_none() {
+            return StatusCode::FORBIDDEN.into_response();
+        }
+        let path = uri.path().strip_prefix(&format!("/{}", BUCKET)).unwrap_or_default();
+        let mut s3 = s3.lock().unwrap();
+
+        if path.is_empty() || path == "/" {
+            let prefix = query.get("prefix").cloned().unwrap_or_default();
+            let after = query.get("continuation-token").cloned().unwrap_or_default();
+            let keys: Vec<&String> = s3
+                .objects
+                .keys()
+                .filter(|key| key.starts_with(&prefix) && key.as_str() > after.as_str())
+                .collect();
+            let page = &keys[..keys.len().min(PAGE_SIZE)];
+            let mut xml = String::from("<ListBucketResult>");
+            for key in page {
+                xml.push_str(&format!(
+                    "<Contents><Key>{}</Key><LastModified>2024-01-01T00:00:00.000Z</LastModified><Size>{}</Size></Contents>",
+                    key,
+                    s3.objects[*key].data.len()
+                ));
+            }
+            let truncated = keys.len() > PAGE_SIZE;
+            xml.push_str(&format!("<IsTruncated>{}</IsTruncated>", truncated));
+            if truncated {
+                xml.push_str(&format!("<NextContinuationToken>{}</NextContinuationToken>", page[PAGE_SIZE - 1]));
+            }
+            xml.push_str("</ListBucketResult>");
+            return xml.into_response();
+        }
+        let key = path.trim_start_matches('/').to_string();
+
+        match method {
+            Method::POST if query.contains_key("uploads") => {
+                s3.next_upload += 1;
+                let upload_id = format!("upload-{}", s3.next_upload);
+                s3.uploads.insert(upload_id.clone(), (object_headers(&headers), BTreeMap::new()));
+                format!("<InitiateMultipartUploadResult><UploadId>{}</UploadId></InitiateMultipartUploadResult>", upload_id)
+                    .into_response()
+            }
+            Method::PUT if query.contains_key("uploadId") => {
+                let number: u32 = query["partNumber"].parse().unwrap();
+                let Some((_, parts)) = s3.uploads.get_mut(&query["uploadId"]) else {
+                    return StatusCode::NOT_FOUND.into_response();
+                };
+                parts.insert(number, body.to_vec());
+                ([("etag", format!("\"etag-{}\"", number))], "").into_response()
+            }
+            Method::POST if query.contains_key("uploadId") => {
+                let Some((object_headers, parts)) = s3.uploads.remove(&query["uploadId"]) else {
+                    return StatusCode::NOT_FOUND.into_response();
+                };
+                let listed = String::from_utf8_lossy(&body).matches("<PartNumber>").count();
+                assert_eq!(listed, parts.len());
+                let data = parts.into_values().flatten().collect();
+                s3.objects.insert(key, Object { data, headers: object_headers });
+                s3.multipart_completed += 1;
+                "<CompleteMultipartUploadResult></CompleteMultipartUploadResult>".into_response()
+            }
+            Method::DELETE if query.contains_key("uploadId") => {
+                s3.uploads.remove(&query["uploadId"]);
+                StatusCode::NO_CONTENT.into_response()
+            }
+            Method::PUT => {
+                let object = Object { data: body.to_vec(), headers: object_headers(&headers) };
+                s3.objects.insert(key, object);
+                StatusCode::OK.into_response()
+            }
+            Method::HEAD => match s3.objects.get(&key) {
+                Some(object) => {
+                    let mut headers = object.headers.clone();
+                    headers.push(("content-length".to_string(), object.data.len().to_string()));
+                    with_headers(StatusCode::OK, &headers, Vec::new())
+                }
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+            Method::GET => {
+                let Some(object) = s3.objects.get(&key) else {
+                    return StatusCode::NOT_FOUND.into_response();
+                };
+                let size = object.data.len();
+                let range = headers
+                    .get("range")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(imitatort::infrastructure::blob::ByteRange::from_header);
+                let Some(range) = range else {
+                    return with_headers(StatusCode::OK, &object.headers, object.data.clone());
+                };
+                let Some((start, end)) = range.resolve(size as u64) else {
+                    let headers = [("content-range".to_string(), format!("bytes */{}", size))];
+                    return with_headers(StatusCode::RANGE_NOT_SATISFIABLE, &headers, Vec::new());
+                };
+                let mut headers = object.headers.clone();
+                headers.push(("content-range".to_string(), format!("bytes {}-{}/{}", start, end, size)));
+                let data = object.data[start as usize..=end as usize].to_vec();
+                with_headers(StatusCode::PARTIAL_CONTENT, &headers, data)
+            }
+            Method::DELETE => {
+                s3.objects.remove(&key);
+                StatusCode::NO_CONTENT.into_response()
+            }
+            _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+        }
+    }
+
+    async fn start_mock() -> (String, Shared) {
+        let shared = Shared::default();
+        let app = Router::new().fallback(handle).with_state(shared.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (format!("http://{}", addr), shared)
+    }
+
+    fn config(endpoint: &str) -> S3Config {
+        S3Config {
+            part_size: 1024,
+            ..S3Config::new(endpoint, BUCKET).with_credentials("test-key", "test-secret")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_s3_blob_store() {
+        let (endpoint, mock) = start_mock().await;
+        exercise(Arc::new(S3BlobStore::new(config(&endpoint)).unwrap())).await;
+        // 3000 字节按 1024 字节分段上传
+        assert_eq!(mock.lock().unwrap().multipart_completed, 1);
+        assert!(mock.lock().unwrap().uploads.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_s3_presigned_download_url() {
+        let (endpoint, _mock) = start_mock().await;
+        let store = S3BlobStore::new(config(&endpoint)).unwrap();
+        let url = store
+            .download_url("attachments/a b.txt", Duration::from_secs(300))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(url.starts_with(&format!("{}/{}/attachments/a%20b.txt?", endpoint, BUCKET)), "{}", url);
+        for param in ["X-Amz-Algorithm=AWS4-HMAC-SHA256", "X-Amz-Expires=300", "X-Amz-SignedHeaders=host", "X-Amz-Signature="] {
+            assert!(url.contains(param), "{} missing {}", url, param);
+        }
+
+        let proxied = S3BlobStore::new(S3Config { presign_downloads: false, ..config(&endpoint) }).unwrap();
+        assert!(proxied.download_url("a", Duration::from_secs(60)).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_attachments_redirect_to_presigned_s3_url() {
+        let (endpoint, _mock) = start_mock().await;
+        let blobs: Arc<dyn BlobStore> = Arc::new(S3BlobStore::new(config(&endpoint)).unwrap());
+        let server = Server::start(Some(blobs)).await;
+        let data = payload(1500);
+        let id = server.upload("data.csv", &data).await;
+
+        let response = server.download(&id, None).await;
+        assert_eq!(response.status(), 307);
+        let location = response.headers()["location"].to_str().unwrap();
+        assert!(location.starts_with(&format!("{}/{}/attachments/{}?", endpoint, BUCKET, id)), "{}", location);
+        assert!(location.contains("X-Amz-Signature="));
+
+        // 关闭预签名时由服务端代理
+        let blobs: Arc<dyn BlobStore> =
+            Arc::new(S3BlobStore::new(S3Config { presign_downloads: false, ..config(&endpoint) }).unwrap());
+        let server = Server::start(Some(blobs)).await;
+        let response = server.download(&id, Some("bytes=0-9")).await;
+        assert_eq!(response.status(), 206);
+        assert_eq!(response.headers()["content-range"], "bytes 0-9/1500");
+        assert_eq!(response.bytes().await.unwrap(), data[..10]);
+    }
+}
@@ -0,0 +1,139 @@
+//! 首次启动数据初始化测试
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use imitatort::bootstrap::{DEMO_GROUP_ID, DEMO_MESSAGE_KEY};
+use imitatort::core::store::{MessageFilter, Store};
+use imitatort::infrastructure::auth::PasswordService;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::{AppConfig, CompanyConfig, FrameworkLauncher, QuickStartOptions};
+
+fn launcher(db_path: &std::path::Path, options: QuickStartOptions) -> FrameworkLauncher {
+    let config = AppConfig {
+        db_path: db_path.to_string_lossy().into_owned(),
+        ..AppConfig::default()
+    };
+    FrameworkLauncher::with_config(config).with_options(options)
+}
+
+/// 收集日志输出
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn text(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().unwrap()).into_owned()
+    }
+}
+
+#[tokio::test]
+async fn test_seeding_runs_once() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("seed.db");
+    let options = QuickStartOptions::new()
+        .with_admin_credentials("root", "S3cure-pass")
+        .with_template("startup")
+        .with_demo_conversation(true);
+
+    let (company, first) = launcher(&db_path, options.clone()).initialize().await.unwrap();
+    assert!(first.organization);
+    assert_eq!(first.admin.as_deref(), Some("root"));
+    assert!(!first.admin_password_generated);
+    assert_eq!(first.demo_messages, 3);
+    assert_eq!(company.get_agents().await.unwrap().len(), 3);
+    drop(company);
+
+    // 第二次启动检测到已初始化，不再写入
+    let (company, second) = launcher(&db_path, options).initialize().await.unwrap();
+    assert!(second.is_empty());
+    assert_eq!(company.get_agents().await.unwrap().len(), 3);
+
+    let store = SqliteStore::new(&db_path).unwrap();
+    let users = store.load_users().await.unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].username, "root");
+
+    let messages = store.load_messages(MessageFilter::new().limit(10)).await.unwrap();
+    assert_eq!(messages.len(), 3);
+    assert!(messages.iter().all(|m| m.metadata.get(DEMO_MESSAGE_KEY).map(String::as_str) == Some("true")));
+    assert_eq!(store.load_groups().await.unwrap().iter().filter(|g| g.id == DEMO_GROUP_ID).count(), 1);
+}
+
+#[tokio::test]
+async fn test_existing_store_is_not_reseeded() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("existing.db");
+
+    // 默认选项保持原有行为：只创建默认组织
+    let (_, report) = launcher(&db_path, QuickStartOptions::default()).initialize().await.unwrap();
+    assert!(report.organization);
+    assert!(report.admin.is_none());
+    assert_eq!(report.demo_messages, 0);
+
+    // 已有组织时模板不会覆盖
+    let options = QuickStartOptions::new().with_template("startup");
+    let (company, report) = launcher(&db_path, options).initialize().await.unwrap();
+    assert!(report.is_empty());
+    assert_eq!(company.get_agents().await.unwrap().len(), CompanyConfig::test_config().organization.agents.len());
+}
+
+#[tokio::test]
+async fn test_unknown_template_fails() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let options = QuickStartOptions::new().with_template("conglomerate");
+    let err = launcher(&temp_dir.path().join("x.db"), options).initialize().await.err().unwrap();
+    assert!(err.to_string().contains("startup"));
+}
+
+#[tokio::test]
+async fn test_generated_password_is_redacted_in_logs() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("admin.db");
+    std::env::remove_var("ADMIN_PASSWORD");
+    let (_, report) = launcher(&db_path, QuickStartOptions::new().with_admin())
+        .initialize()
+        .await
+        .unwrap();
+    assert!(report.admin_password_generated);
+
+    let store = SqliteStore::new(&db_path).unwrap();
+    let users = store.load_users().await.unwrap();
+    assert_eq!(users.len(), 1);
+
+    let logs = captured.text();
+    assert!(logs.contains("[REDACTED]"));
+    // 日志中没有任何一个词能通过密码校验
+    let hash = &users[0].password_hash;
+    for word in logs.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| w.len() == 16) {
+        assert!(!PasswordService::verify_password(hash, word).unwrap());
+    }
+}
+
+#[test]
+fn test_starter_templates() {
+    for name in CompanyConfig::STARTER_TEMPLATES {
+        let config = CompanyConfig::starter(name).unwrap();
+        assert!(!config.organization.agents.is_empty());
+    }
+    assert!(CompanyConfig::starter("conglomerate").is_none());
+}
@@ -40,10 +40,7 @@ async fn test_company_creation_from_config() {
     org.add_agent(engineering_manager);
     org.add_agent(product_manager);
 
-    let config = CompanyConfig {
-        name: "Test Corporation".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("Test Corporation", org);
 
     // 创建临时数据库文件用于测试
     let temp_dir = tempfile::tempdir().unwrap();
@@ -99,10 +96,7 @@ async fn test_company_message_flow() {
     org.add_agent(manager);
     org.add_agent(developer);
 
-    let config = CompanyConfig {
-        name: "IT Team".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("IT Team", org);
 
     let temp_dir = tempfile::tempdir().unwrap();
     let db_path = temp_dir.path().join("message_test.db");
@@ -126,6 +120,8 @@ async fn test_company_message_flow() {
         timestamp: chrono::Utc::now().timestamp(),
         reply_to: None,
         mentions: vec![],
+        metadata: Default::default(),
+        priority: Default::default(),
     };
 
     // 验证消息结构
@@ -157,10 +153,7 @@ async fn test_company_persistence() {
     org.add_agent(hr_director);
     org.add_agent(hr_specialist);
 
-    let config = CompanyConfig {
-        name: "HR Department".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("HR Department", org);
 
     let temp_dir = tempfile::tempdir().unwrap();
     let db_path = temp_dir.path().join("persistence_test.db");
@@ -237,10 +230,7 @@ async fn test_organization_hierarchy() {
     org.add_agent(backend_lead);
     org.add_agent(frontend_lead);
 
-    let config = CompanyConfig {
-        name: "Hierarchical Company".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("Hierarchical Company", org);
 
     let temp_dir = tempfile::tempdir().unwrap();
     let db_path = temp_dir.path().join("hierarchy_test.db");
@@ -282,10 +272,7 @@ async fn test_organization_hierarchy() {
 async fn test_company_config_validation() {
     // 测试空配置
     let empty_org = Organization::new();
-    let empty_config = CompanyConfig {
-        name: "Empty Company".to_string(),
-        organization: empty_org,
-    };
+    let empty_config = CompanyConfig::new("Empty Company", empty_org);
 
     let temp_dir = tempfile::tempdir().unwrap();
     let db_path = temp_dir.path().join("empty_test.db");
@@ -310,10 +297,7 @@ async fn test_company_config_validation() {
     );
     org.add_agent(agent);
 
-    let single_config = CompanyConfig {
-        name: "Single Agent Company".to_string(),
-        organization: org,
-    };
+    let single_config = CompanyConfig::new("Single Agent Company", org);
 
     let db_path2 = temp_dir.path().join("single_test.db");
     let single_company = CompanyBuilder::with_sqlite(&db_path2)
@@ -0,0 +1,194 @@
+//! 故障注入测试（需 `--features chaos`）：LLM 重试、存储包装、消息丢弃和管理接口
+#![cfg(feature = "chaos")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use imitatort::core::chaos::{is_injected, FaultInjector, FaultKind, FaultRule, Subsystem};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{ChaosStore, MemoryStore, Store};
+use imitatort::domain::Message;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::llm::{Message as LlmMessage, OpenAIClient};
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+async fn spawn_llm() -> (Arc<AtomicUsize>, String) {
+    async fn completions(State(calls): State<Arc<AtomicUsize>>, Json(_): Json<Value>) -> Json<Value> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "ok" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 8, "completion_tokens": 2, "total_tokens": 10 }
+        }))
+    }
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state(calls.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (calls, format!("http://{}", addr))
+}
+
+fn enabled_injector() -> Arc<FaultInjector> {
+    Arc::new(FaultInjector::new().with_enabled(true).with_seed(7))
+}
+
+#[tokio::test]
+async fn test_disabled_injector_injects_nothing() {
+    let injector = FaultInjector::new();
+    injector.add_rule(FaultRule::new(Subsystem::Llm, FaultKind::Error));
+    assert!(injector.inject(Subsystem::Llm, "chat").await.is_ok());
+    assert!(injector.history().is_empty());
+}
+
+#[tokio::test]
+async fn test_rules_match_operation_and_window() {
+    let injector = enabled_injector();
+    injector.add_rule(FaultRule::new(Subsystem::Store, FaultKind::Error).with_operation("save_message"));
+    injector.add_rule(FaultRule::new(Subsystem::Tools, FaultKind::Error).with_window(0, 1));
+
+    assert!(injector.inject(Subsystem::Store, "load_messages").await.is_ok());
+    let fault = injector.inject(Subsystem::Store, "save_message").await.unwrap_err();
+    assert_eq!(fault.operation, "save_message");
+    // 时间窗口早已结束
+    assert!(injector.inject(Subsystem::Tools, "org.find_agents").await.is_ok());
+    assert_eq!(injector.history(), vec![fault]);
+
+    assert!(FaultRule::new(Subsystem::Llm, FaultKind::Error).with_probability(1.5).validate().is_err());
+    assert!(FaultRule::new(Subsystem::Llm, FaultKind::Error).with_window(10, 10).validate().is_err());
+}
+
+#[tokio::test]
+async fn test_llm_retries_keep_success_rate_under_error_injection() {
+    let (calls, url) = spawn_llm().await;
+    let injector = enabled_injector();
+    injector.add_rule(FaultRule::new(Subsystem::Llm, FaultKind::Error).with_probability(0.2));
+    let client = OpenAIClient::new_with_base_url("k".to_string(), "mock".to_string(), url)
+        .with_retry(2, Duration::from_millis(1))
+        .with_fault_injector(injector.clone());
+
+    let total = 50;
+    let mut succeeded = 0;
+    for _ in 0..total {
+        if client.chat(vec![LlmMessage::user("ping")]).await.is_ok() {
+            succeeded += 1;
+        }
+    }
+
+    let injected = injector.injected_count(Subsystem::Llm);
+    assert!(injected > 0, "no faults injected");
+    // 三次都失败的概率为 0.8%，重试后成功率应远高于阈值
+    assert!(succeeded * 100 >= total * 95, "{} of {} succeeded", succeeded, total);
+    // 注入的错误没有打到服务端
+    assert_eq!(calls.load(Ordering::SeqCst), succeeded);
+}
+
+#[tokio::test]
+async fn test_chaos_store_fails_matching_operations() {
+    let injector = enabled_injector();
+    let store = ChaosStore::new(Arc::new(MemoryStore::new()), injector.clone());
+    injector.add_rule(FaultRule::new(Subsystem::Store, FaultKind::Error).with_operation("save_message"));
+
+    let message = Message::private("alice", "bob", "hello");
+    let err = store.save_message(&message).await.unwrap_err();
+    assert!(is_injected(&err));
+    assert!(store.load_organization().await.is_ok());
+
+    injector.set_rules(Vec::new());
+    store.save_message(&message).await.unwrap();
+    assert_eq!(injector.injected_count(Subsystem::Store), 1);
+}
+
+#[tokio::test]
+async fn test_message_bus_drop_skips_delivery() {
+    let injector = enabled_injector();
+    let bus = MessageBus::new().with_fault_injector(injector.clone());
+    let mut inbox = bus.register("bob");
+
+    injector.add_rule(FaultRule::new(Subsystem::Messaging, FaultKind::Drop));
+    bus.send(Message::private("alice", "bob", "lost")).await.unwrap();
+    assert!(inbox.try_recv().is_err());
+
+    injector.set_rules(vec![FaultRule::new(Subsystem::Messaging, FaultKind::Error)]);
+    let err = bus.send(Message::private("alice", "bob", "failed")).await.unwrap_err();
+    assert!(is_injected(&err));
+
+    injector.set_rules(Vec::new());
+    bus.send(Message::private("alice", "bob", "delivered")).await.unwrap();
+    assert_eq!(inbox.try_recv().unwrap().content, "delivered");
+    assert_eq!(injector.injected_count(Subsystem::Messaging), 2);
+}
+
+#[tokio::test]
+async fn test_rules_endpoint_requires_chairman_and_enabled_injector() {
+    let jwt_service = JwtService::new("test-secret");
+    let token = |position: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: "u1".to_string(),
+                username: "u1".to_string(),
+                name: "U1".to_string(),
+                email: None,
+                is_director: true,
+                employee_id: "00001".to_string(),
+                position: position.to_string(),
+                department: "board".to_string(),
+            })
+            .unwrap()
+    };
+    let (chairman, management) = (token("Chairman"), token("Management"));
+
+    let injector = Arc::new(FaultInjector::new());
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, Arc::new(MemoryStore::new()), jwt_service.clone())
+        .with_fault_injector(injector.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/api/admin/chaos/rules", addr);
+    let body = json!({ "rules": [{ "subsystem": "llm", "fault": { "type": "error" }, "probability": 0.2 }] });
+
+    let response = client.post(&url).bearer_auth(&management).json(&body).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    // 注入器未启用时接口不可用
+    let response = client.post(&url).bearer_auth(&chairman).json(&body).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    assert!(injector.rules().is_empty());
+
+    injector.set_enabled(true);
+    let response = client.post(&url).bearer_auth(&chairman).json(&body).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let rules = injector.rules();
+    assert_eq!(rules.len(), 1);
+    assert_eq!(rules[0].subsystem, Subsystem::Llm);
+    assert_eq!(rules[0].probability, 0.2);
+
+    let invalid = json!({ "rules": [{ "subsystem": "llm", "fault": { "type": "error" }, "probability": 2.0 }] });
+    let response = client.post(&url).bearer_auth(&chairman).json(&invalid).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client.delete(&url).bearer_auth(&chairman).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(injector.rules().is_empty());
+}
@@ -0,0 +1,39 @@
+//! Web 接口测试共用的夹具：登录令牌、Agent 和在随机端口上启动的测试服务器
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Role};
+
+/// eng 部门用户的令牌，权限由职位决定
+pub fn token(jwt_service: &JwtService, id: &str, position: &str) -> String {
+    jwt_service
+        .generate_token(&UserInfo {
+            id: id.to_string(),
+            username: id.to_string(),
+            name: id.to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: position.to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+}
+
+/// 名称与 ID 相同的工程师 Agent
+pub fn agent(id: &str, department: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Engineer", "You code"), LLMConfig::openai("k")).with_department(department)
+}
+
+/// 在随机端口上启动完整的路由，返回监听地址
+pub async fn serve(state: AppState) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+    addr
+}
@@ -2,8 +2,55 @@
 //!
 //! 注意：parse_decision 是私有方法，集成测试无法直接测试
 
+use imitatort::core::agent::{AgentRuntime, Context};
+use imitatort::domain::{Agent, LLMConfig, Message, MessagePriority, MessageReaction, ReactionCount, Role};
+
 #[test]
 fn test_agent_runtime_placeholder() {
     // AgentRuntime 的测试需要通过公共 API 进行
     // parse_decision 等私有方法已在单元测试中验证
 }
+
+#[tokio::test]
+async fn test_reactions_are_rendered_compactly() {
+    let agent = Agent::new(
+        "agent-1",
+        "Agent One",
+        Role::simple("Writer", "You write documents"),
+        LLMConfig::openai("test-key"),
+    );
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+
+    let message = Message::private("pm", "agent-1", "Ship it?");
+    let reactions = vec![
+        MessageReaction::new(&message.id, "a", "👍"),
+        MessageReaction::new(&message.id, "b", "👍"),
+        MessageReaction::new(&message.id, "c", "👍"),
+    ];
+    let context = Context::default().with_history(vec![message.clone()]);
+    let prompt = runtime.build_thinking_prompt(&context);
+    assert!(prompt.contains("- [pm]: Ship it?\n"));
+
+    let context = context.with_reactions(ReactionCount::aggregate(&reactions));
+    let prompt = runtime.build_thinking_prompt(&context);
+    assert!(prompt.contains("- [pm]: Ship it? [3 reacted 👍]\n"));
+}
+
+#[tokio::test]
+async fn test_message_priority_is_annotated() {
+    let agent = Agent::new(
+        "agent-1",
+        "Agent One",
+        Role::simple("Writer", "You write documents"),
+        LLMConfig::openai("test-key"),
+    );
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+
+    let context = Context::default().with_messages(vec![
+        Message::private("ceo", "agent-1", "Stop the release").with_priority(MessagePriority::Urgent),
+        Message::private("pm", "agent-1", "Lunch?"),
+    ]);
+    let prompt = runtime.build_thinking_prompt(&context);
+    assert!(prompt.contains("- [ceo] (URGENT priority): Stop the release\n"));
+    assert!(prompt.contains("- [pm]: Lunch?\n"));
+}
@@ -0,0 +1,293 @@
+//! Agent 批量操作测试：非原子模式下部分成功、原子模式下全部回滚、逐项检查人数上限/部门/重复 ID、
+//! `CompanyBuilder::apply_batch`、`POST /admin/agents/batch` 的权限和 API Key 不回显，
+//! 以及批量保存后新建的 Agent 启动并出现在列表中、有变化的 Agent 以新配置重启
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc};
+
+use imitatort::core::agent_batch::{AgentBatch, AgentOperation, AgentOperationStatus, LlmConfigPatch};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::temp_agents::TempAgentRunner;
+use imitatort::domain::Department;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, CompanyBuilder, CompanyConfig, LLMConfig, Message, Organization, Role};
+
+fn organization() -> Organization {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    org.add_department(Department::top_level("ops", "Operations").with_max_agents(1));
+    org.add_agent(
+        Agent::new("dev", "Dev", Role::simple("Engineer", "You write code"), LLMConfig::openai("sk-old"))
+            .with_department("eng")
+            .with_skills(vec!["rust".to_string()]),
+    );
+    org.add_agent(
+        Agent::new("sre", "SRE", Role::simple("SRE", "You run things"), LLMConfig::openai("sk-old")).with_department("ops"),
+    );
+    org
+}
+
+fn create(id: &str, department_id: Option<&str>) -> AgentOperation {
+    AgentOperation::Create {
+        id: id.to_string(),
+        template: "dev".to_string(),
+        name: None,
+        department_id: department_id.map(str::to_string),
+        llm_config: LlmConfigPatch::default(),
+        skills: None,
+    }
+}
+
+fn rotate(agent_id: &str, api_key: &str) -> AgentOperation {
+    AgentOperation::UpdateLlmConfig {
+        agent_id: agent_id.to_string(),
+        llm_config: LlmConfigPatch {
+            api_key: Some(api_key.to_string()),
+            ..LlmConfigPatch::default()
+        },
+    }
+}
+
+fn mixed_batch() -> AgentBatch {
+    AgentBatch::new(vec![
+        create("dev2", None),
+        create("dev", None),
+        rotate("dev", "sk-new"),
+        AgentOperation::ReassignDepartment { agent_id: "dev2".to_string(), department_id: "ops".to_string() },
+        AgentOperation::ReassignDepartment { agent_id: "dev2".to_string(), department_id: "sales".to_string() },
+        AgentOperation::SetSkills { agent_id: "ghost".to_string(), skills: vec![] },
+        AgentOperation::SetSkills { agent_id: "dev2".to_string(), skills: vec!["sql".to_string()] },
+    ])
+}
+
+#[test]
+fn test_non_atomic_batch_applies_what_it_can() {
+    let mut org = organization();
+    let report = mixed_batch().apply(&mut org, 1_000);
+
+    let statuses: Vec<AgentOperationStatus> = report.results.iter().map(|r| r.status).collect();
+    use AgentOperationStatus::{Applied, Failed};
+    assert_eq!(statuses, vec![Applied, Failed, Applied, Failed, Failed, Failed, Applied]);
+    assert!(report.committed);
+    assert_eq!((report.applied, report.failed), (3, 4));
+    assert!(report.results[1].error.as_ref().unwrap().contains("already exists"));
+    assert!(report.results[3].error.as_ref().unwrap().contains("full"));
+    assert!(report.results[4].error.as_ref().unwrap().contains("sales"));
+    assert!(report.results[5].error.as_ref().unwrap().contains("ghost"));
+
+    // 新 Agent 复制模板，后面的操作能看到前面创建的 Agent
+    let dev2 = org.find_agent("dev2").unwrap();
+    assert_eq!(dev2.role.title, "Engineer");
+    assert_eq!(dev2.department_id.as_deref(), Some("eng"));
+    assert_eq!(dev2.skills, vec!["sql".to_string()]);
+    assert_eq!(dev2.created_at, Some(1_000));
+    assert_eq!(org.find_agent("dev").unwrap().llm_config.api_key, "sk-new");
+    assert_eq!(org.agents.iter().filter(|a| a.id == "dev").count(), 1);
+}
+
+#[test]
+fn test_atomic_batch_rolls_back_on_failure() {
+    let mut org = organization();
+    let report = mixed_batch().with_atomic(true).apply(&mut org, 1_000);
+
+    assert!(!report.committed);
+    assert_eq!((report.applied, report.failed), (0, 4));
+    assert_eq!(report.results[0].status, AgentOperationStatus::RolledBack);
+    assert_eq!(report.results[2].status, AgentOperationStatus::RolledBack);
+    assert!(org.find_agent("dev2").is_none());
+    assert_eq!(org.find_agent("dev").unwrap().llm_config.api_key, "sk-old");
+
+    // 全部成功时整体生效
+    let batch = AgentBatch::new(vec![create("dev2", Some("eng")), rotate("dev2", "sk-new")]).with_atomic(true);
+    let report = batch.apply(&mut org, 1_000);
+    assert!(report.committed);
+    assert_eq!(report.applied, 2);
+    assert_eq!(org.find_agent("dev2").unwrap().llm_config.api_key, "sk-new");
+}
+
+#[tokio::test]
+async fn test_company_builder_apply_batch() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let mut builder = CompanyBuilder::with_store(store.clone());
+    assert!(builder.apply_batch(&AgentBatch::new(vec![create("dev2", None)])).is_err());
+
+    let mut builder = builder.config(CompanyConfig::new("Acme", organization()));
+    let batch = AgentBatch::new(vec![create("dev2", None), create("dev3", Some("ops"))]);
+    let report = builder.apply_batch(&batch).unwrap();
+    assert_eq!((report.applied, report.failed), (1, 1));
+
+    let company = builder.build().unwrap();
+    let org = company.organization().await;
+    assert!(org.find_agent("dev2").is_some());
+    assert!(org.find_agent("dev3").is_none());
+}
+
+fn token(jwt: &JwtService, position: &str) -> String {
+    jwt.generate_token(&UserInfo {
+        id: position.to_lowercase(),
+        username: position.to_lowercase(),
+        name: position.to_string(),
+        email: None,
+        is_director: false,
+        employee_id: "00001".to_string(),
+        position: position.to_string(),
+        department: "eng".to_string(),
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_batch_endpoint_rotates_keys_without_echoing_them() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    store.save_organization(&organization()).await.unwrap();
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store.clone(), jwt.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/admin/agents/batch", addr);
+    let client = reqwest::Client::new();
+    let secret = "sk-rotated-9f8e7d6c5b4a";
+    let request = json!({
+        "operations": [
+            {"op": "update_llm_config", "agent_id": "dev", "llm_config": {"api_key": secret, "model": "gpt-4o"}},
+            {"op": "update_llm_config", "agent_id": "sre", "llm_config": {"api_key": secret}},
+            {"op": "update_llm_config", "agent_id": "ghost", "llm_config": {"api_key": secret}},
+        ]
+    });
+
+    let response = client.post(&url).bearer_auth(token(&jwt, "Employee")).json(&request).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = client.post(&url).bearer_auth(token(&jwt, "Chairman")).json(&request).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let text = response.text().await.unwrap();
+    assert!(!text.contains(secret), "{}", text);
+    let body: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(body["data"]["applied"], 2);
+    assert_eq!(body["data"]["results"][0]["status"], "applied");
+    assert_eq!(body["data"]["results"][2]["status"], "failed");
+
+    let org = store.load_organization().await.unwrap();
+    assert_eq!(org.find_agent("dev").unwrap().llm_config.api_key, secret);
+    assert_eq!(org.find_agent("dev").unwrap().llm_config.model, "gpt-4o");
+    assert_eq!(org.find_agent("sre").unwrap().llm_config.api_key, secret);
+
+    // 原子模式下失败时不保存
+    let request = json!({
+        "atomic": true,
+        "operations": [
+            {"op": "set_skills", "agent_id": "dev", "skills": ["go"]},
+            {"op": "create", "id": "sre", "template": "dev"},
+        ]
+    });
+    let body: Value = client
+        .post(&url)
+        .bearer_auth(token(&jwt, "Chairman"))
+        .json(&request)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["committed"], false);
+    assert_eq!(body["data"]["results"][0]["status"], "rolled_back");
+    let org = store.load_organization().await.unwrap();
+    assert_eq!(org.find_agent("dev").unwrap().skills, vec!["rust".to_string()]);
+}
+
+/// 记录启动（ID 和模型）与停止的运行器，启动时注册到消息总线
+struct RecordingRunner {
+    bus: Arc<MessageBus>,
+    started: Mutex<Vec<(String, String)>>,
+    stopped: Mutex<Vec<String>>,
+    inboxes: Mutex<Vec<mpsc::Receiver<Message>>>,
+}
+
+#[async_trait]
+impl TempAgentRunner for RecordingRunner {
+    async fn start(&self, agent: &Agent) -> Result<()> {
+        self.inboxes.lock().unwrap().push(self.bus.register(&agent.id));
+        self.started.lock().unwrap().push((agent.id.clone(), agent.llm_config.model.clone()));
+        Ok(())
+    }
+
+    async fn stop(&self, agent_id: &str) {
+        self.bus.unregister(agent_id);
+        self.stopped.lock().unwrap().push(agent_id.to_string());
+    }
+}
+
+#[tokio::test]
+async fn test_batch_endpoint_starts_created_and_restarts_updated_agents() {
+    let org = organization();
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    store.save_organization(&org).await.unwrap();
+    let bus = Arc::new(MessageBus::new());
+    let _inboxes: Vec<_> = org.agents.iter().map(|a| bus.register(&a.id)).collect();
+    let runner = Arc::new(RecordingRunner {
+        bus: bus.clone(),
+        started: Mutex::new(Vec::new()),
+        stopped: Mutex::new(Vec::new()),
+        inboxes: Mutex::new(Vec::new()),
+    });
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(org.agents.clone(), message_tx, store.clone(), jwt.clone())
+        .with_message_bus(bus.clone())
+        .with_agent_runner(runner.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+
+    let request = json!({
+        "operations": [
+            {"op": "create", "id": "dev2", "template": "dev", "department_id": "eng"},
+            {"op": "update_llm_config", "agent_id": "dev", "llm_config": {"model": "gpt-4o"}},
+            {"op": "set_skills", "agent_id": "ghost", "skills": []},
+        ]
+    });
+    let response = client
+        .post(format!("http://{}/api/admin/agents/batch", addr))
+        .bearer_auth(token(&jwt, "Chairman"))
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // 新建的 Agent 已启动、注册到消息总线并出现在列表中；改了配置的 Agent 以新配置重启，其余不动
+    assert!(bus.is_registered("dev2"));
+    let template_model = org.find_agent("dev").unwrap().llm_config.model.clone();
+    assert_eq!(
+        *runner.started.lock().unwrap(),
+        vec![("dev".to_string(), "gpt-4o".to_string()), ("dev2".to_string(), template_model)]
+    );
+    assert_eq!(*runner.stopped.lock().unwrap(), vec!["dev".to_string()]);
+    let agents: Vec<Value> = client
+        .get(format!("http://{}/api/agents", addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let listed: Vec<(&str, &str)> = agents
+        .iter()
+        .map(|a| (a["id"].as_str().unwrap(), a["department"].as_str().unwrap()))
+        .collect();
+    assert_eq!(listed, vec![("dev", "eng"), ("sre", "ops"), ("dev2", "eng")]);
+}
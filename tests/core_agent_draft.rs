@@ -0,0 +1,315 @@
+//! 面试式创建 Agent 测试：私聊问答后由 LLM 起草、会话持久化后可继续、审阅修改后按修改后的草稿创建 Agent
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use imitatort::core::agent_draft::{AgentInterviewer, DraftSession, DraftStatus, INTERVIEWER_ID};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::Store;
+use imitatort::domain::user::user_principal;
+use imitatort::domain::{Agent, AgentMode, Department, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+const ANSWERS: [&str; 4] = [
+    "Triage incoming support tickets and escalate outages",
+    "Friendly but concise",
+    "The ticket queue, watch it for new tickets",
+    "Support",
+];
+
+/// LLM 起草的版本
+fn scripted_draft() -> Value {
+    json!({
+        "agent_id": "triage",
+        "name": "Tina",
+        "department_id": "support",
+        "role": {
+            "title": "Support Triage Specialist",
+            "responsibilities": ["Triage tickets", "Escalate outages"],
+            "expertise": ["Customer support"],
+            "system_prompt": "You triage support tickets.",
+            "tone": "friendly"
+        },
+        "skills": ["ticketing"],
+        "watched_tools": ["tickets.queue"]
+    })
+}
+
+/// 记录提示词的模拟 LLM，回复前后带说明文字的草稿
+async fn spawn_llm() -> (Arc<Mutex<Vec<String>>>, LLMConfig) {
+    async fn completions(State(prompts): State<Arc<Mutex<Vec<String>>>>, Json(body): Json<Value>) -> Json<Value> {
+        let prompt: String = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|m| m["content"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        prompts.lock().unwrap().push(prompt);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": format!("Here is the draft:\n{}", scripted_draft()) },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(prompts.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (prompts, LLMConfig::openai("k").with_base_url(format!("http://{}", addr)))
+}
+
+async fn create_store(max_support: Option<usize>) -> Arc<SqliteStore> {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    let mut support = Department::top_level("support", "Support");
+    if let Some(max) = max_support {
+        support = support.with_max_agents(max);
+    }
+    org.add_department(support);
+    org.add_agent(
+        Agent::new("lead", "Lead", Role::simple("Lead", "You lead"), LLMConfig::openai("k")).with_department("eng"),
+    );
+    store.save_organization(&org).await.unwrap();
+    store
+}
+
+async fn wait_for_status(interviewer: &AgentInterviewer, session_id: &str, status: DraftStatus) -> DraftSession {
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let session = interviewer.session(session_id).await.unwrap().unwrap();
+            if session.status == status {
+                return session;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("interview reaches the expected status")
+}
+
+#[tokio::test]
+async fn test_interview_over_chat_produces_draft() {
+    let store = create_store(None).await;
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let (prompts, llm) = spawn_llm().await;
+    let interviewer = Arc::new(AgentInterviewer::new(store.clone(), bus.clone(), &llm));
+    interviewer.clone().spawn();
+
+    let alice = user_principal("alice");
+    let mut inbox = bus.subscribe_user(&alice);
+    let session = interviewer.start(&alice).await.unwrap();
+    assert_eq!(session.status, DraftStatus::Interviewing);
+
+    // 每个回答之后收到下一个问题
+    let mut questions = vec![inbox.recv().await.unwrap()];
+    for answer in ANSWERS {
+        bus.send(Message::private(&alice, INTERVIEWER_ID, answer)).await.unwrap();
+        questions.push(tokio::time::timeout(Duration::from_secs(5), inbox.recv()).await.unwrap().unwrap());
+    }
+    assert!(questions.iter().all(|m| m.from == INTERVIEWER_ID));
+    assert!(questions[0].content.contains("responsible for"), "{}", questions[0].content);
+    assert!(questions[3].content.contains("Which department"), "{}", questions[3].content);
+
+    let session = wait_for_status(&interviewer, &session.id, DraftStatus::Drafted).await;
+    let draft = session.draft.unwrap();
+    assert_eq!(draft.agent_id, "triage");
+    assert_eq!(draft.role.title, "Support Triage Specialist");
+    assert_eq!(draft.watched_tools, vec!["tickets.queue"]);
+    assert_eq!(session.answers.len(), 4);
+    assert_eq!(session.answers[1].answer, "Friendly but concise");
+
+    // 起草的提示词包含回答和可选部门
+    let prompt = prompts.lock().unwrap()[0].clone();
+    assert!(prompt.contains("You are the HR partner"));
+    assert!(prompt.contains("Triage incoming support tickets"));
+    assert!(prompt.contains("- support: Support"));
+
+    // 草稿就绪后通知用户，面试结束后的私聊不再当作回答
+    let ready = tokio::time::timeout(Duration::from_secs(5), inbox.recv()).await.unwrap().unwrap();
+    assert!(ready.content.contains(&session.id), "{}", ready.content);
+    assert!(interviewer.answer(&alice, "one more thing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_interview_survives_restart() {
+    let store = create_store(None).await;
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let (_prompts, llm) = spawn_llm().await;
+    let alice = user_principal("alice");
+
+    let first = AgentInterviewer::new(store.clone(), bus.clone(), &llm);
+    let session = first.start(&alice).await.unwrap();
+    first.answer(&alice, ANSWERS[0]).await.unwrap();
+    first.answer(&alice, ANSWERS[1]).await.unwrap();
+    drop(first);
+
+    // 新的面试官从存储中接着问
+    let second = AgentInterviewer::new(store.clone(), bus, &llm);
+    let resumed = second.answer(&alice, ANSWERS[2]).await.unwrap().unwrap();
+    assert_eq!(resumed.id, session.id);
+    assert_eq!(resumed.answers.len(), 3);
+    let done = second.answer(&alice, ANSWERS[3]).await.unwrap().unwrap();
+    assert_eq!(done.status, DraftStatus::Drafted);
+    assert!(second.answer(&user_principal("bob"), "hello").await.unwrap().is_none());
+}
+
+fn token(jwt_service: &JwtService, id: &str) -> String {
+    jwt_service
+        .generate_token(&UserInfo {
+            id: id.to_string(),
+            username: id.to_string(),
+            name: id.to_string(),
+            email: None,
+            is_director: true,
+            employee_id: "00001".to_string(),
+            position: "Chairman".to_string(),
+            department: "board".to_string(),
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_commit_creates_agent_from_edited_draft() {
+    let store = create_store(Some(1)).await;
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let (_prompts, llm) = spawn_llm().await;
+    let interviewer = Arc::new(AgentInterviewer::new(store.clone(), bus.clone(), &llm));
+    let jwt_service = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+
+    // 未挂载面试官时不可用
+    let state = AppState::new(Vec::new(), message_tx.clone(), store.clone(), jwt_service.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let bare = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("http://{}/api/v1/agents/draft", bare))
+        .bearer_auth(token(&jwt_service, "alice"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 503);
+
+    let state = AppState::new(Vec::new(), message_tx, store.clone(), jwt_service.clone())
+        .with_message_bus(bus)
+        .with_agent_interviewer(interviewer.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let base = format!("http://{}/api/v1/agents/draft", addr);
+    let alice = token(&jwt_service, "alice");
+
+    let response = client.post(&base).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    let body: Value = client.post(&base).bearer_auth(&alice).send().await.unwrap().json().await.unwrap();
+    let session_id = body["data"]["id"].as_str().unwrap().to_string();
+    let url = format!("{}/{}", base, session_id);
+
+    // 面试未完成时不能提交，其他用户看不到
+    let response = client.post(format!("{}/commit", url)).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+    let response = client.get(&url).bearer_auth(token(&jwt_service, "bob")).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    for answer in ANSWERS {
+        interviewer.answer(&user_principal("alice"), answer).await.unwrap();
+    }
+    let body: Value = client.get(&url).bearer_auth(&alice).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["data"]["status"], "drafted");
+
+    // 审阅后修改：改名、改标题，并移到 eng 部门
+    let mut edited = body["data"]["draft"].clone();
+    edited["name"] = json!("Triage Bot");
+    edited["department_id"] = json!("eng");
+    edited["role"]["title"] = json!("Ticket Triage Lead");
+    edited["watched_tools"] = json!([]);
+    let response = client.put(&url).bearer_auth(&alice).json(&edited).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body: Value = client
+        .post(format!("{}/commit", url))
+        .bearer_auth(&alice)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["id"], "triage", "{}", body);
+
+    let org = store.load_organization().await.unwrap();
+    let agent = org.agents.iter().find(|a| a.id == "triage").unwrap();
+    assert_eq!(agent.name, "Triage Bot");
+    assert_eq!(agent.role.title, "Ticket Triage Lead");
+    assert_eq!(agent.role.system_prompt, "You triage support tickets.");
+    assert_eq!(agent.department_id.as_deref(), Some("eng"));
+    assert_eq!(agent.skills, vec!["ticketing"]);
+    assert!(matches!(agent.mode, AgentMode::Passive));
+    assert_eq!(agent.llm_config.base_url, llm.base_url);
+    assert!(!store.load_org_changes(0, 10).await.unwrap().is_empty());
+
+    // 已提交的草稿不能再提交
+    let response = client.post(format!("{}/commit", url)).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+
+    // 部门满员、ID 重复和无效草稿走正常的创建校验
+    let session = interviewer.start(&user_principal("alice")).await.unwrap();
+    for answer in ANSWERS {
+        interviewer.answer(&user_principal("alice"), answer).await.unwrap();
+    }
+    let url = format!("{}/{}", base, session.id);
+    let mut draft = scripted_draft();
+    draft["agent_id"] = json!("triage-2");
+    client.put(&url).bearer_auth(&alice).json(&draft).send().await.unwrap();
+    store
+        .save_organization(&{
+            let mut org = store.load_organization().await.unwrap();
+            org.add_agent(
+                Agent::new("ops", "Ops", Role::simple("Ops", "You operate"), LLMConfig::openai("k"))
+                    .with_department("support"),
+            );
+            org
+        })
+        .await
+        .unwrap();
+    let response = client.post(format!("{}/commit", url)).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("is full"), "{}", body);
+
+    draft["agent_id"] = json!("triage");
+    draft["department_id"] = json!("eng");
+    client.put(&url).bearer_auth(&alice).json(&draft).send().await.unwrap();
+    let response = client.post(format!("{}/commit", url)).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+
+    draft["agent_id"] = json!("user:mallory");
+    client.put(&url).bearer_auth(&alice).json(&draft).send().await.unwrap();
+    let response = client.post(format!("{}/commit", url)).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+}
@@ -0,0 +1,213 @@
+//! 部门人数上限与 LLM 预算测试
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::budget::DepartmentBudgets;
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, CompanyBuilder, CompanyConfig, Department, LLMConfig, Message, Organization, Role};
+
+fn agent(id: &str, dept: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Engineer", "You write code"), LLMConfig::openai("test")).with_department(dept)
+}
+
+fn org_with_limits() -> Organization {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering").with_max_agents(2).with_llm_budget(150));
+    org.add_department(Department::top_level("ops", "Operations"));
+    org.add_agent(agent("a1", "eng"));
+    org.add_agent(agent("a2", "eng"));
+    org.add_agent(agent("o1", "ops"));
+    org
+}
+
+#[test]
+fn test_add_agent_rejected_when_department_full() {
+    let mut org = org_with_limits();
+    let err = org.add_agent_checked(agent("a3", "eng")).unwrap_err();
+    assert_eq!(err.department_id, "eng");
+    assert_eq!(err.current, 2);
+    assert_eq!(err.max_agents, 2);
+    assert!(org.find_agent("a3").is_none());
+
+    // 没有上限的部门不受影响
+    org.add_agent_checked(agent("o2", "ops")).unwrap();
+    assert!(org.find_agent("o2").is_some());
+}
+
+#[test]
+fn test_move_agent_respects_headcount() {
+    let mut org = org_with_limits();
+    assert!(org.move_agent("o1", "eng").is_err());
+    assert_eq!(org.find_agent("o1").unwrap().department_id.as_deref(), Some("ops"));
+
+    // 已在部门内的 Agent 不占用新名额
+    org.move_agent("a1", "eng").unwrap();
+    org.move_agent("a1", "ops").unwrap();
+    org.move_agent("o1", "eng").unwrap();
+    assert_eq!(org.find_agent("o1").unwrap().department_id.as_deref(), Some("eng"));
+    assert!(org.validate_headcount().is_ok());
+}
+
+#[test]
+fn test_company_build_rejects_overfull_department() {
+    let mut org = org_with_limits();
+    org.add_agent(agent("a3", "eng"));
+    let result = CompanyBuilder::with_store(Arc::new(MemoryStore::new()))
+        .config(CompanyConfig::new("Acme", org))
+        .build();
+    let err = result.err().expect("over-limit organization must be rejected");
+    assert!(err.to_string().contains("Department eng is full (3/2 agents)"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_registration_rejected_when_department_full() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("guilty-cliff-line", "Cliff of Contemplation Line").with_max_agents(0));
+    store.save_organization(&org).await.unwrap();
+
+    let (message_tx, _) = broadcast::channel(16);
+    let app = create_router(Arc::new(AppState::new(vec![], message_tx, store.clone(), JwtService::new("test-secret"))));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/api/auth/register", addr))
+        .json(&json!({ "username": "boss", "password": "Str0ng-enough", "name": "Boss" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Department guilty-cliff-line is full (0/0 agents)");
+
+    // 被拒绝的注册不留下用户
+    assert!(store.load_user_by_username("boss").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_limits_round_trip_through_sqlite() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    store.save_organization(&org_with_limits()).await.unwrap();
+    let org = store.load_organization().await.unwrap();
+    let eng = org.departments.iter().find(|d| d.id == "eng").unwrap();
+    assert_eq!(eng.max_agents, Some(2));
+    assert_eq!(eng.llm_budget, Some(150));
+    let ops = org.departments.iter().find(|d| d.id == "ops").unwrap();
+    assert_eq!(ops.max_agents, None);
+    assert_eq!(ops.llm_budget, None);
+
+    let yaml = serde_yaml::to_string(ops).unwrap();
+    assert!(!yaml.contains("max_agents"));
+}
+
+#[test]
+fn test_budget_resets_next_day() {
+    let clock = Arc::new(ManualClock::new(86_400 * 100));
+    let budgets = DepartmentBudgets::with_clock(clock.clone());
+    budgets.sync(&org_with_limits());
+
+    budgets.record("a1", 100);
+    assert!(budgets.check("a2").is_ok());
+    budgets.record("a2", 60);
+    let err = budgets.check("a1").unwrap_err();
+    assert_eq!((err.used, err.budget), (160, 150));
+    assert_eq!(budgets.department_usage("eng"), 160);
+
+    // 未设预算的部门不受限
+    budgets.record("o1", 10_000);
+    assert!(budgets.check("o1").is_ok());
+
+    clock.advance(Duration::from_secs(86_400));
+    assert_eq!(budgets.department_usage("eng"), 0);
+    assert!(budgets.check("a1").is_ok());
+}
+
+/// 每次调用消耗 100 token 的模拟 LLM，返回计数器和地址
+async fn spawn_metered_llm() -> (Arc<AtomicUsize>, String) {
+    async fn completions(State(calls): State<Arc<AtomicUsize>>, Json(_): Json<Value>) -> Json<Value> {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": json!({ "action": "wait" }).to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 80, "completion_tokens": 20, "total_tokens": 100 }
+        }))
+    }
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state(calls.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (calls, format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_agent_pauses_when_budget_spent() {
+    let (calls, url) = spawn_metered_llm().await;
+    let dev = Agent::new("a1", "Dev", Role::simple("Engineer", "You write code"), LLMConfig::openai("k").with_base_url(url))
+        .with_department("eng");
+
+    let budgets = Arc::new(DepartmentBudgets::new());
+    budgets.sync(&org_with_limits());
+    let bus = Arc::new(MessageBus::new());
+    let autonomous = AutonomousAgent::new(dev, bus).await.unwrap().with_budgets(budgets.clone());
+    let handle = tokio::spawn(async move {
+        let _ = autonomous.run_loop().await;
+    });
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    handle.abort();
+
+    // 150 token 的预算在第二次调用（累计 200）后用尽
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(budgets.department_usage("eng"), 200);
+    assert!(budgets.check("a2").is_err());
+}
+
+#[tokio::test]
+async fn test_paused_agent_keeps_draining_mailbox() {
+    let budgets = Arc::new(DepartmentBudgets::new());
+    budgets.sync(&org_with_limits());
+    budgets.record("a1", 200);
+    let bus = Arc::new(MessageBus::new());
+    let autonomous = AutonomousAgent::new(agent("a1", "eng"), bus.clone()).await.unwrap().with_budgets(budgets);
+    let handle = tokio::spawn(async move {
+        let _ = autonomous.run_loop().await;
+    });
+
+    // 超过信箱容量的消息不会阻塞发送方
+    let flood = async {
+        for i in 0..300 {
+            bus.send(Message::private("o1", "a1", format!("ping {}", i))).await.unwrap();
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(3), flood).await.expect("sender blocked by paused agent");
+    handle.abort();
+}
@@ -0,0 +1,228 @@
+//! 存储降级测试：读缓存、写缓冲与恢复后重放
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use imitatort::core::store::{BufferConfig, BufferedStore, MemoryStore, MessageFilter, Store};
+use imitatort::domain::{Department, Group, Message, Organization};
+
+/// 可以随时切换为不可用的存储，并记录成功写入的消息顺序
+#[derive(Clone, Default)]
+struct FlakyStore {
+    inner: Arc<MemoryStore>,
+    down: Arc<AtomicBool>,
+    written: Arc<Mutex<Vec<String>>>,
+}
+
+impl FlakyStore {
+    fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+
+    fn check(&self) -> anyhow::Result<()> {
+        if self.down.load(Ordering::SeqCst) {
+            Err(anyhow::anyhow!("unable to open database file"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[async_trait]
+impl Store for FlakyStore {
+    async fn save_organization(&self, org: &Organization) -> anyhow::Result<()> {
+        self.check()?;
+        self.inner.save_organization(org).await
+    }
+
+    async fn load_organization(&self) -> anyhow::Result<Organization> {
+        self.check()?;
+        self.inner.load_organization().await
+    }
+
+    async fn save_group(&self, group: &Group) -> anyhow::Result<()> {
+        self.check()?;
+        self.inner.save_group(group).await
+    }
+
+    async fn load_groups(&self) -> anyhow::Result<Vec<Group>> {
+        self.check()?;
+        self.inner.load_groups().await
+    }
+
+    async fn delete_group(&self, group_id: &str) -> anyhow::Result<()> {
+        self.check()?;
+        self.inner.delete_group(group_id).await
+    }
+
+    async fn save_message(&self, message: &Message) -> anyhow::Result<()> {
+        self.check()?;
+        self.written.lock().unwrap().push(message.id.clone());
+        self.inner.save_message(message).await
+    }
+
+    async fn load_messages(&self, filter: MessageFilter) -> anyhow::Result<Vec<Message>> {
+        self.check()?;
+        self.inner.load_messages(filter).await
+    }
+
+    async fn save_app_state(&self, key: &str, value: &serde_json::Value) -> anyhow::Result<()> {
+        self.check()?;
+        self.inner.save_app_state(key, value).await
+    }
+
+    async fn load_app_state(&self, key: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        self.check()?;
+        self.inner.load_app_state(key).await
+    }
+}
+
+fn message(id: &str, from: &str, to: &str, timestamp: i64) -> Message {
+    let mut message = Message::private(from, to, format!("message {}", id));
+    message.id = id.to_string();
+    message.timestamp = timestamp;
+    message
+}
+
+fn buffered(flaky: &FlakyStore, config: BufferConfig) -> BufferedStore<FlakyStore> {
+    BufferedStore::new(flaky.clone(), config).unwrap()
+}
+
+#[tokio::test]
+async fn test_writes_are_buffered_and_replayed_in_order() {
+    let flaky = FlakyStore::default();
+    let store = buffered(&flaky, BufferConfig::default());
+    store.save_message(&message("m1", "a", "b", 1)).await.unwrap();
+    let filter = MessageFilter::new().from("a");
+    store.load_messages(filter.clone()).await.unwrap();
+
+    flaky.set_down(true);
+    store.save_message(&message("m2", "a", "b", 2)).await.unwrap();
+    store.save_message(&message("m3", "b", "a", 3)).await.unwrap();
+    assert_eq!(store.buffered_writes(), 2);
+    assert!(store.is_degraded());
+
+    // 故障期间读取缓存，并合并自己刚写入的消息
+    let ids: Vec<String> = store
+        .load_messages(filter)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| m.id)
+        .collect();
+    assert_eq!(ids, vec!["m2", "m1"]);
+
+    flaky.set_down(false);
+    assert_eq!(store.flush().await, 2);
+    assert_eq!(store.buffered_writes(), 0);
+    assert!(!store.is_degraded());
+    assert_eq!(*flaky.written.lock().unwrap(), vec!["m1", "m2", "m3"]);
+    assert_eq!(flaky.inner.load_messages(MessageFilter::new()).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_reads_fall_back_to_cache() {
+    let flaky = FlakyStore::default();
+    let store = buffered(&flaky, BufferConfig::default());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("tech", "Tech"));
+    store.save_organization(&org).await.unwrap();
+    store.save_app_state("counter", &json!(1)).await.unwrap();
+
+    assert_eq!(store.load_organization().await.unwrap().departments.len(), 1);
+    assert_eq!(store.load_app_state("counter").await.unwrap(), Some(json!(1)));
+
+    flaky.set_down(true);
+    assert_eq!(store.load_organization().await.unwrap().departments.len(), 1);
+    assert_eq!(store.load_app_state("counter").await.unwrap(), Some(json!(1)));
+    // 从未读过的键没有缓存，仍然报错
+    assert!(store.load_app_state("other").await.is_err());
+
+    // 缓冲中的写入优先于缓存
+    org.add_department(Department::top_level("sales", "Sales"));
+    store.save_organization(&org).await.unwrap();
+    store.save_app_state("counter", &json!(2)).await.unwrap();
+    assert_eq!(store.load_organization().await.unwrap().departments.len(), 2);
+    assert_eq!(store.load_app_state("counter").await.unwrap(), Some(json!(2)));
+}
+
+#[tokio::test]
+async fn test_group_writes_are_visible_while_buffered() {
+    let flaky = FlakyStore::default();
+    let store = buffered(&flaky, BufferConfig::default());
+    store
+        .save_group(&Group::new("g1", "Old", "a", vec!["a".to_string()]))
+        .await
+        .unwrap();
+    store.load_groups().await.unwrap();
+
+    flaky.set_down(true);
+    store
+        .save_group(&Group::new("g2", "New", "a", vec!["a".to_string()]))
+        .await
+        .unwrap();
+    store.delete_group("g1").await.unwrap();
+
+    let groups = store.load_groups().await.unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].id, "g2");
+}
+
+#[tokio::test]
+async fn test_full_buffer_rejects_writes() {
+    let flaky = FlakyStore::default();
+    let store = buffered(
+        &flaky,
+        BufferConfig {
+            max_buffered_writes: 2,
+            ..BufferConfig::default()
+        },
+    );
+
+    flaky.set_down(true);
+    store.save_message(&message("m1", "a", "b", 1)).await.unwrap();
+    assert!(store.health_check().await.is_ok());
+    assert!(store.degraded().unwrap().contains("1 of 2"));
+
+    store.save_message(&message("m2", "a", "b", 2)).await.unwrap();
+    let err = store.save_message(&message("m3", "a", "b", 3)).await.unwrap_err();
+    assert!(err.to_string().contains("buffer is full"));
+    assert!(store.health_check().await.is_err());
+
+    // 恢复后健康检查会顺带重放
+    flaky.set_down(false);
+    store.health_check().await.unwrap();
+    assert_eq!(store.buffered_writes(), 0);
+    assert!(store.degraded().is_none());
+}
+
+#[tokio::test]
+async fn test_journal_survives_restart() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let config = BufferConfig {
+        journal_path: Some(temp_dir.path().join("journal.jsonl")),
+        ..BufferConfig::default()
+    };
+
+    let flaky = FlakyStore::default();
+    flaky.set_down(true);
+    {
+        let store = buffered(&flaky, config.clone());
+        store.save_message(&message("m1", "a", "b", 1)).await.unwrap();
+        store.save_app_state("k", &json!("v")).await.unwrap();
+    }
+
+    // 重启后载入日志，恢复时重放
+    let store = buffered(&flaky, config.clone());
+    assert_eq!(store.buffered_writes(), 2);
+    flaky.set_down(false);
+    assert_eq!(store.flush().await, 2);
+    assert_eq!(flaky.inner.load_app_state("k").await.unwrap(), Some(json!("v")));
+    assert_eq!(*flaky.written.lock().unwrap(), vec!["m1"]);
+
+    let store = buffered(&flaky, config);
+    assert_eq!(store.buffered_writes(), 0);
+}
@@ -0,0 +1,294 @@
+//! 会话生命周期测试：闲置自动关闭、关闭后再发消息时重新打开或开启新会话、用 LLM 总结生成产物、
+//! `session.close` 工具，以及 `/chat/{id}/close|reopen` 和 `/chat/list?include_closed=true`
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+use imitatort::application::digest::LlmSummarizer;
+use imitatort::core::chat_sessions::{ChatSessionConfig, ChatSessions, ClosedSessionPolicy};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::Store;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{ChatSessionStatus, SessionResolution};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Message, Organization, Role};
+
+const ALICE: &str = "user:alice";
+
+fn support(llm_config: LLMConfig) -> Agent {
+    Agent::new("support", "Support", Role::simple("Support", "You answer customers"), llm_config)
+}
+
+async fn seeded_store() -> Arc<dyn Store> {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_agent(support(LLMConfig::openai("k")));
+    store.save_organization(&org).await.unwrap();
+    store
+}
+
+/// 保存消息并记入会话，与消息总线落库后的顺序一致
+async fn send(store: &Arc<dyn Store>, sessions: &ChatSessions, message: Message) {
+    store.save_message(&message).await.unwrap();
+    sessions.record_message(&message).await.unwrap();
+}
+
+fn config(policy: ClosedSessionPolicy) -> ChatSessionConfig {
+    ChatSessionConfig {
+        enabled: true,
+        inactivity_secs: 600,
+        on_message_after_close: policy,
+        ..ChatSessionConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn test_inactive_session_closes_automatically() {
+    let store = seeded_store().await;
+    let clock = Arc::new(ManualClock::new(chrono::Utc::now().timestamp()));
+    let sessions = ChatSessions::new(config(ClosedSessionPolicy::Reopen), store.clone()).with_clock(clock.clone());
+
+    send(&store, &sessions, Message::private(ALICE, "support", "My invoice is wrong")).await;
+    send(&store, &sessions, Message::private("support", ALICE, "Let me check that")).await;
+    // 群聊和用户之间的私聊不属于会话
+    sessions.record_message(&Message::group(ALICE, "lobby", "hi")).await.unwrap();
+    assert!(sessions.record_message(&Message::private(ALICE, "user:bob", "hi")).await.unwrap().is_none());
+
+    clock.advance(Duration::from_secs(599));
+    assert!(sessions.close_inactive().await.unwrap().is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    let closed = sessions.close_inactive().await.unwrap();
+    assert_eq!(closed.len(), 1);
+    let session = &closed[0];
+    assert_eq!(session.id, "support");
+    assert_eq!(session.status, ChatSessionStatus::Closed);
+    assert_eq!(session.closed_by.as_deref(), Some("system"));
+    let artifact = session.artifact.as_ref().unwrap();
+    assert_eq!(artifact.resolution, SessionResolution::Inactive);
+    assert_eq!(artifact.message_count, 2);
+    // 没有总结器时只记录解决状态
+    assert!(artifact.summary.is_none());
+
+    assert_eq!(store.load_chat_session("support").await.unwrap().unwrap(), *session);
+    assert!(sessions.list(false).await.unwrap().is_empty());
+    assert!(sessions.close_inactive().await.unwrap().is_empty());
+
+    // 不自动关闭
+    let never = ChatSessions::new(
+        ChatSessionConfig { inactivity_secs: 0, ..config(ClosedSessionPolicy::Reopen) },
+        seeded_store().await,
+    );
+    never.record_message(&Message::private(ALICE, "support", "hello")).await.unwrap();
+    assert!(never.close_inactive().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_message_after_close_reopens_or_starts_new_session() {
+    let store = seeded_store().await;
+    let sessions = ChatSessions::new(config(ClosedSessionPolicy::Reopen), store.clone());
+    send(&store, &sessions, Message::private(ALICE, "support", "Reset my password")).await;
+    sessions.close("support", SessionResolution::Resolved, "support", None).await.unwrap();
+
+    // Agent 在关闭后的回复不重新打开会话
+    assert!(sessions.record_message(&Message::private("support", ALICE, "Anything else?")).await.unwrap().is_none());
+    assert!(sessions.current("support").await.unwrap().unwrap().is_closed());
+
+    let reopened = sessions
+        .record_message(&Message::private(ALICE, "support", "It still fails"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(reopened.id, "support");
+    assert_eq!(reopened.status, ChatSessionStatus::Open);
+    assert_eq!(reopened.reopen_count, 1);
+    // 上次关闭的产物保留到下次关闭
+    assert_eq!(reopened.artifact.unwrap().resolution, SessionResolution::Resolved);
+    assert_eq!(store.load_chat_sessions(true).await.unwrap().len(), 1);
+
+    let store = seeded_store().await;
+    let sessions = ChatSessions::new(config(ClosedSessionPolicy::NewSession), store.clone());
+    send(&store, &sessions, Message::private(ALICE, "support", "Reset my password")).await;
+    sessions.close("support", SessionResolution::Resolved, "support", None).await.unwrap();
+
+    let fresh = sessions
+        .record_message(&Message::private(ALICE, "support", "New question"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_ne!(fresh.id, "support");
+    assert_eq!(fresh.agent_id, "support");
+    assert_eq!(fresh.reopen_count, 0);
+    assert!(store.load_chat_session("support").await.unwrap().unwrap().is_closed());
+    assert_eq!(sessions.current("support").await.unwrap().unwrap().id, fresh.id);
+    // Agent ID 指向当前会话
+    assert_eq!(sessions.resolve("support").await.unwrap().unwrap().id, "support");
+    let closed = sessions.close(&fresh.id, SessionResolution::Unresolved, "user:alice", None).await.unwrap();
+    assert_eq!(closed.id, fresh.id);
+    assert!(sessions.close(&fresh.id, SessionResolution::Resolved, "user:alice", None).await.is_err());
+}
+
+/// 记录每次请求的提示词，回复固定的总结
+async fn spawn_llm() -> (String, Arc<Mutex<Vec<String>>>) {
+    async fn completions(State(prompts): State<Arc<Mutex<Vec<String>>>>, Json(body): Json<Value>) -> Json<Value> {
+        let prompt: String = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|m| m["content"].as_str())
+            .collect();
+        prompts.lock().unwrap().push(prompt);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "  Alice asked for a refund; it was issued.\n" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(prompts.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (format!("http://{}", addr), prompts)
+}
+
+#[tokio::test]
+async fn test_close_tool_generates_summary_artifact() {
+    let store = seeded_store().await;
+    let (url, prompts) = spawn_llm().await;
+    let summarizer = LlmSummarizer::new(&LLMConfig::openai("sk-test").with_base_url(url));
+    let sessions = Arc::new(
+        ChatSessions::new(config(ClosedSessionPolicy::Reopen), store.clone()).with_summarizer(Arc::new(summarizer)),
+    );
+    send(&store, &sessions, Message::private(ALICE, "support", "I was charged twice for order 42")).await;
+    send(&store, &sessions, Message::private("support", ALICE, "Refund issued for the duplicate charge")).await;
+
+    let executor = FrameworkToolExecutor::new(
+        ToolEnvironment::new(
+            Arc::new(MessageBus::new()),
+            Arc::new(RwLock::new(Organization::new())),
+            Arc::new(ToolRegistry::new()),
+            store.clone(),
+        )
+        .with_chat_sessions(sessions.clone()),
+    );
+    let context = ToolCallContext::new("support");
+    let invalid = executor.execute("session.close", json!({ "resolution": "maybe" }), &context).await.unwrap();
+    assert!(!invalid.success);
+
+    let result = executor
+        .execute("session.close", json!({ "note": "Refunded order 42" }), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["status"], "closed");
+    assert_eq!(result.data["closed_by"], "support");
+    assert_eq!(result.data["artifact"]["resolution"], "resolved");
+    assert_eq!(result.data["artifact"]["summary"], "Alice asked for a refund; it was issued.");
+    assert_eq!(result.data["artifact"]["note"], "Refunded order 42");
+    assert_eq!(result.data["artifact"]["message_count"], 2);
+
+    let prompts = prompts.lock().unwrap().clone();
+    assert_eq!(prompts.len(), 1);
+    assert!(prompts[0].contains("Support"));
+    assert!(prompts[0].contains("user:alice: I was charged twice for order 42"));
+
+    // 已关闭时不能再关闭
+    let again = executor.execute("session.close", json!({}), &context).await.unwrap();
+    assert!(!again.success);
+}
+
+fn token(jwt_service: &JwtService) -> String {
+    jwt_service
+        .generate_token(&UserInfo {
+            id: "alice".to_string(),
+            username: "alice".to_string(),
+            name: "alice".to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: "Employee".to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_close_and_reopen_endpoints() {
+    let store = seeded_store().await;
+    let sessions = Arc::new(ChatSessions::new(config(ClosedSessionPolicy::Reopen), store.clone()));
+    send(&store, &sessions, Message::private(ALICE, "support", "Where is my parcel?")).await;
+
+    let jwt_service = JwtService::new("test-secret");
+    let token = token(&jwt_service);
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![support(LLMConfig::openai("k"))], message_tx, store, jwt_service)
+        .with_chat_sessions(sessions.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+    let base = format!("http://{}/api/v1", addr);
+    let client = reqwest::Client::new();
+    let list = |include_closed: bool| {
+        let client = client.clone();
+        let url = format!("{}/chat/list?include_closed={}", base, include_closed);
+        async move { client.get(url).send().await.unwrap().json::<Value>().await.unwrap()["data"].clone() }
+    };
+
+    let listed = list(false).await;
+    assert_eq!(listed[0]["id"], "support");
+    assert_eq!(listed[0]["session"]["status"], "open");
+
+    let response = client.post(format!("{}/chat/support/close", base)).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let response = client
+        .post(format!("{}/chat/support/close", base))
+        .bearer_auth(&token)
+        .json(&json!({ "resolution": "unresolved", "note": "Carrier has no tracking" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["closed_by"], "user:alice");
+    assert_eq!(body["data"]["artifact"]["resolution"], "unresolved");
+
+    let response = client.post(format!("{}/chat/support/close", base)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+
+    // 已关闭的会话默认不列出
+    assert_eq!(list(false).await.as_array().unwrap().len(), 0);
+    let listed = list(true).await;
+    assert_eq!(listed[0]["session"]["status"], "closed");
+
+    let response = client.post(format!("{}/chat/ghost/reopen", base)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+    let response = client.post(format!("{}/chat/support/reopen", base)).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["status"], "open");
+    assert_eq!(body["data"]["reopen_count"], 1);
+    assert_eq!(list(false).await.as_array().unwrap().len(), 1);
+}
@@ -0,0 +1,327 @@
+//! Agent 熔断测试：LLM 连续失败、鉴权失败和工具失败率熔断、试探退避翻倍、按 Agent 覆盖阈值、
+//! 熔断期间消息排队和管理层告警、试探成功后恢复并处理排队消息、手动复位接口
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::application::autonomous::{AutonomousAgent, BREAKER_ALERT_SENDER};
+use imitatort::core::circuit_breaker::{
+    BreakerGate, BreakerState, BreakerTransition, BreakerTrip, CircuitBreakerConfig, CircuitBreakerPolicy,
+    CircuitBreakers,
+};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::domain::user::User;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Message, Role};
+
+const START: i64 = 1_700_000_000;
+
+fn policy() -> CircuitBreakerPolicy {
+    CircuitBreakerPolicy::default()
+        .with_llm_failure_threshold(3)
+        .with_tool_error_rate(0.5, 4, 60)
+        .with_backoff(60, 200)
+}
+
+fn breakers(config: CircuitBreakerConfig) -> (Arc<ManualClock>, CircuitBreakers) {
+    let clock = Arc::new(ManualClock::new(START));
+    (clock.clone(), CircuitBreakers::new(config).with_clock(clock))
+}
+
+#[test]
+fn test_llm_failures_trip_and_probe_backoff_grows() {
+    let (clock, breakers) = breakers(CircuitBreakerConfig::new(policy()));
+
+    assert_eq!(breakers.record_llm("dev", Some("401 invalid api key")), None);
+    assert_eq!(breakers.record_llm("dev", Some("401 invalid api key")), None);
+    // 成功一次后重新计数
+    assert_eq!(breakers.record_llm("dev", None), None);
+    assert_eq!(breakers.record_llm("dev", Some("401 invalid api key")), None);
+    assert_eq!(breakers.record_llm("dev", Some("401 invalid api key")), None);
+    assert_eq!(breakers.record_llm("dev", Some("401 invalid api key")), Some(BreakerTransition::Tripped));
+
+    let status = breakers.status("dev");
+    assert_eq!(status.state, BreakerState::Open);
+    assert_eq!(status.trip, Some(BreakerTrip::LlmFailures { consecutive: 3 }));
+    assert_eq!(status.last_error.as_deref(), Some("401 invalid api key"));
+    assert_eq!((status.opened_at, status.retry_at), (Some(START), Some(START + 60)));
+    assert!(breakers.is_faulted("dev"));
+    assert!(!breakers.is_faulted("ops"));
+    assert_eq!(breakers.gate("dev"), BreakerGate::Open { retry_at: START + 60 });
+
+    // 试探失败后退避翻倍：60 → 120 → 200（上限）
+    let mut now = START;
+    for backoff in [120, 200, 200] {
+        now = breakers.status("dev").retry_at.unwrap();
+        clock.set(now);
+        assert_eq!(breakers.gate("dev"), BreakerGate::Probe);
+        assert_eq!(breakers.status("dev").state, BreakerState::HalfOpen);
+        assert_eq!(breakers.record_llm("dev", Some("still broken")), Some(BreakerTransition::ProbeFailed));
+        assert_eq!(breakers.status("dev").retry_at, Some(now + backoff));
+    }
+    assert_eq!(breakers.status("dev").failed_probes, 3);
+
+    clock.set(now + 200);
+    assert_eq!(breakers.gate("dev"), BreakerGate::Probe);
+    assert_eq!(breakers.record_llm("dev", None), Some(BreakerTransition::Recovered));
+    let status = breakers.status("dev");
+    assert_eq!(status.state, BreakerState::Closed);
+    assert_eq!((status.failed_probes, status.retry_at), (0, None));
+    assert_eq!(breakers.gate("dev"), BreakerGate::Closed);
+}
+
+#[test]
+fn test_tool_error_rate_over_sliding_window() {
+    let (clock, breakers) = breakers(CircuitBreakerConfig::new(policy()));
+
+    // 窗口外的失败不计入
+    breakers.record_tool("dev", Some("timeout"));
+    breakers.record_tool("dev", Some("timeout"));
+    clock.advance(Duration::from_secs(61));
+    assert_eq!(breakers.record_tool("dev", None), None);
+    assert_eq!(breakers.record_tool("dev", Some("timeout")), None);
+    assert_eq!(breakers.record_tool("dev", None), None);
+    assert!(!breakers.is_faulted("dev"));
+
+    assert_eq!(breakers.record_tool("dev", Some("timeout")), Some(BreakerTransition::Tripped));
+    assert_eq!(
+        breakers.status("dev").trip,
+        Some(BreakerTrip::ToolErrorRate { errors: 2, calls: 4, window_secs: 60 })
+    );
+    // 熔断期间不再计入工具调用
+    assert_eq!(breakers.record_tool("dev", Some("timeout")), None);
+}
+
+#[test]
+fn test_per_agent_policy_and_reset() {
+    let config = CircuitBreakerConfig::new(policy())
+        .with_agent("flaky", CircuitBreakerPolicy::default().with_llm_failure_threshold(1))
+        .with_agent("lenient", CircuitBreakerPolicy::default().with_llm_failure_threshold(0));
+    let (_, breakers) = breakers(config);
+
+    assert_eq!(breakers.record_llm("flaky", Some("boom")), Some(BreakerTransition::Tripped));
+    assert_eq!(breakers.status("flaky").retry_at, Some(START + 30));
+    for _ in 0..20 {
+        assert_eq!(breakers.record_llm("lenient", Some("boom")), None);
+    }
+    assert!(!breakers.is_faulted("lenient"));
+
+    assert!(breakers.reset("flaky"));
+    assert_eq!(breakers.status("flaky").state, BreakerState::Closed);
+    assert_eq!(breakers.gate("flaky"), BreakerGate::Closed);
+    assert!(!breakers.reset("flaky"));
+}
+
+#[test]
+fn test_llm_auth_failure_trips_immediately() {
+    let (_clock, strict) = breakers(CircuitBreakerConfig::new(policy()));
+
+    assert_eq!(strict.record_llm_auth_failure("dev", "invalid api key"), Some(BreakerTransition::Tripped));
+    let status = strict.status("dev");
+    assert_eq!(status.trip, Some(BreakerTrip::LlmAuthFailed));
+    assert_eq!(status.last_error.as_deref(), Some("invalid api key"));
+    assert_eq!(status.retry_at, Some(START + 60));
+
+    // 阈值为 0 时关闭 LLM 检查，鉴权失败也不熔断
+    let disabled = CircuitBreakerConfig::new(policy().with_llm_failure_threshold(0));
+    let (_clock, lenient) = breakers(disabled);
+    assert_eq!(lenient.record_llm_auth_failure("dev", "invalid api key"), None);
+    assert!(!lenient.is_faulted("dev"));
+}
+
+#[test]
+fn test_config_from_yaml() {
+    let yaml = "llm_failure_threshold: 2\nmax_backoff_secs: 600\nagents:\n  dev:\n    llm_failure_threshold: 10\n";
+    let config: CircuitBreakerConfig = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(config.defaults.llm_failure_threshold, 2);
+    assert_eq!(config.defaults.max_backoff_secs, 600);
+    assert_eq!(config.defaults.initial_backoff_secs, 30);
+    assert_eq!(config.policy_for("dev").llm_failure_threshold, 10);
+    assert_eq!(config.policy_for("ops").llm_failure_threshold, 2);
+}
+
+#[derive(Default)]
+struct MockLlm {
+    failing: AtomicBool,
+    calls: AtomicUsize,
+    requests: Mutex<Vec<String>>,
+}
+
+/// 失败时返回 401（如 API key 被吊销），否则让 Agent 等待
+async fn spawn_mock_llm() -> (Arc<MockLlm>, String) {
+    async fn completions(State(llm): State<Arc<MockLlm>>, Json(request): Json<Value>) -> axum::response::Response {
+        llm.calls.fetch_add(1, Ordering::SeqCst);
+        llm.requests.lock().unwrap().push(request.to_string());
+        if llm.failing.load(Ordering::SeqCst) {
+            let error = json!({ "error": { "message": "Incorrect API key provided", "type": "invalid_request_error", "code": "invalid_api_key" } });
+            return (StatusCode::UNAUTHORIZED, Json(error)).into_response();
+        }
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": json!({ "action": "wait" }).to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 8, "completion_tokens": 2, "total_tokens": 10 }
+        }))
+        .into_response()
+    }
+
+    let llm = Arc::new(MockLlm::default());
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state(llm.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (llm, format!("http://{}", addr))
+}
+
+async fn wait_until(mut condition: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("condition not reached in time");
+}
+
+#[tokio::test]
+async fn test_agent_faults_queues_messages_and_recovers() {
+    let (llm, url) = spawn_mock_llm().await;
+    llm.failing.store(true, Ordering::SeqCst);
+
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let boss = User::new_management("boss".into(), "Boss".into(), "x".into(), 2, None);
+    let employee = User::new_employee("emp".into(), "Emp".into(), "x".into(), 3, "eng".into(), None);
+    store.save_user(&boss).await.unwrap();
+    store.save_user(&employee).await.unwrap();
+
+    let (clock, breakers) = breakers(CircuitBreakerConfig::new(policy()));
+    let breakers = Arc::new(breakers);
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let dev = Agent::new("dev", "Dev", Role::simple("Engineer", "You write code"), LLMConfig::openai("revoked").with_base_url(url));
+    let agent = AutonomousAgent::new(dev, bus.clone()).await.unwrap().with_circuit_breakers(breakers.clone());
+    let handle = tokio::spawn(async move {
+        let _ = agent.run_loop().await;
+    });
+
+    // 鉴权失败不重试，也不等连续失败阈值，立即熔断
+    wait_until(|| breakers.is_faulted("dev")).await;
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(breakers.status("dev").trip, Some(BreakerTrip::LlmAuthFailed));
+
+    // 熔断期间不调用 LLM，新消息排队
+    bus.send(Message::private(employee.principal_id(), "dev", "Are you there?")).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert_eq!(llm.calls.load(Ordering::SeqCst), 1);
+
+    // 告警只发给管理层
+    let alerts = store
+        .load_messages(MessageFilter::new().from(BREAKER_ALERT_SENDER))
+        .await
+        .unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].target_agent(), Some(boss.principal_id().as_str()));
+    assert!(alerts[0].content.contains("the LLM API rejected its API key"), "{}", alerts[0].content);
+    assert!(alerts[0].content.contains("Incorrect API key"), "{}", alerts[0].content);
+
+    // 退避结束后试探成功，恢复并处理排队的消息
+    llm.failing.store(false, Ordering::SeqCst);
+    clock.advance(Duration::from_secs(60));
+    wait_until(|| breakers.status("dev").state == BreakerState::Closed).await;
+    // 试探轮之后排队的消息触发新一轮
+    wait_until(|| llm.calls.load(Ordering::SeqCst) >= 3).await;
+    handle.abort();
+
+    let requests = llm.requests.lock().unwrap();
+    assert!(requests[2].contains("Are you there?"));
+}
+
+#[tokio::test]
+async fn test_reset_breaker_endpoint() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let agents = vec![Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k"))];
+    let breakers = Arc::new(CircuitBreakers::new(CircuitBreakerConfig::new(policy())));
+    for _ in 0..3 {
+        breakers.record_llm("dev", Some("boom"));
+    }
+
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(agents, message_tx, store, jwt.clone()).with_circuit_breakers(breakers.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let token = |position: &str| {
+        jwt.generate_token(&UserInfo {
+            id: "u1".to_string(),
+            username: "u1".to_string(),
+            name: "U1".to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: position.to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+    };
+    let client = reqwest::Client::new();
+
+    let agent: Value = client
+        .get(format!("http://{}/api/v1/agents/dev", addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(agent["data"]["status"], "faulted");
+    assert_eq!(agent["data"]["breaker"]["state"], "open");
+    assert_eq!(agent["data"]["breaker"]["trip"], json!({ "kind": "llm_failures", "consecutive": 3 }));
+
+    let url = format!("http://{}/api/v1/agents/dev/reset-breaker", addr);
+    let response = client.post(&url).bearer_auth(token("Employee")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    assert!(breakers.is_faulted("dev"));
+
+    let body: Value = client
+        .post(&url)
+        .bearer_auth(token("Management"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["was_faulted"], true);
+    assert_eq!(body["data"]["breaker"]["state"], "closed");
+    assert!(!breakers.is_faulted("dev"));
+
+    let response = client
+        .post(format!("http://{}/api/v1/agents/ghost/reset-breaker", addr))
+        .bearer_auth(token("Management"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
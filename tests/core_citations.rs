@@ -0,0 +1,136 @@
+//! 工具结果引用测试：引用解析、编造引用的删除、消息元数据和导出
+
+use imitatort::core::agent::{AgentRuntime, Context};
+use imitatort::core::citations::{citation_markers, params_digest, CitationTracker, EXCERPT_CHARS};
+use imitatort::core::transcript::{TranscriptFormat, TranscriptRenderer, TranscriptSession};
+use imitatort::domain::{Citation, Message, CITATIONS_KEY};
+use imitatort::{Agent, LLMConfig, Role};
+use serde_json::{json, Value};
+
+fn tracker_with_results() -> CitationTracker {
+    let mut tracker = CitationTracker::new();
+    assert_eq!(
+        tracker.record("web.fetch", &json!({"url": "https://example.com/q3"}), &json!("Q3 revenue was $12M")),
+        "T1"
+    );
+    assert_eq!(
+        tracker.record("org.find_agents", &json!({"department": "eng"}), &json!({"count": 2})),
+        "T2"
+    );
+    tracker
+}
+
+#[test]
+fn test_valid_citations_are_kept_in_order() {
+    let tracker = tracker_with_results();
+    let (content, citations) = tracker.resolve("Engineering has two people [T2]; revenue was $12M [T1]. Again [T2].");
+
+    assert_eq!(content, "Engineering has two people [T2]; revenue was $12M [T1]. Again [T2].");
+    let ids: Vec<&str> = citations.iter().map(|c| c.ref_id.as_str()).collect();
+    assert_eq!(ids, vec!["T2", "T1"]);
+    assert_eq!(citations[1].tool_id, "web.fetch");
+    assert_eq!(citations[1].excerpt, "Q3 revenue was $12M");
+    assert_eq!(citations[0].excerpt, r#"{"count":2}"#);
+}
+
+#[test]
+fn test_hallucinated_citation_is_stripped() {
+    let tracker = tracker_with_results();
+    let (content, citations) = tracker.resolve("Revenue was $12M [T1], margin was 40% [T9].");
+
+    assert_eq!(content, "Revenue was $12M [T1], margin was 40%.");
+    assert_eq!(citations.len(), 1);
+    assert_eq!(citations[0].ref_id, "T1");
+}
+
+#[test]
+fn test_missing_citations_leave_content_unchanged() {
+    let tracker = tracker_with_results();
+    let (content, citations) = tracker.resolve("Revenue was $12M. See [Tx] and [T] for details.");
+    assert_eq!(content, "Revenue was $12M. See [Tx] and [T] for details.");
+    assert!(citations.is_empty());
+
+    // 没有工具结果时任何标记都是编造的
+    let (content, citations) = CitationTracker::new().resolve("Done [T1]");
+    assert_eq!(content, "Done");
+    assert!(citations.is_empty());
+}
+
+#[test]
+fn test_markers_and_digest() {
+    assert_eq!(citation_markers("a [T3] b [T10][T3] [t1] [T-1]"), vec!["T3", "T10"]);
+    // 参数摘要与键顺序无关
+    assert_eq!(params_digest(&json!({"a": 1, "b": 2})), params_digest(&json!({"b": 2, "a": 1})));
+    assert_eq!(params_digest(&json!({})).len(), 12);
+}
+
+#[test]
+fn test_long_results_are_excerpted_and_numbering_restarts() {
+    let mut tracker = CitationTracker::new();
+    tracker.record("web.fetch", &json!({}), &json!("x".repeat(1000)));
+    let (_, citations) = tracker.resolve("[T1]");
+    assert_eq!(citations[0].excerpt.chars().count(), EXCERPT_CHARS + 1);
+    assert!(citations[0].excerpt.ends_with('…'));
+
+    tracker.clear();
+    assert!(tracker.is_empty());
+    assert_eq!(tracker.record("web.fetch", &json!({}), &json!("again")), "T1");
+}
+
+#[test]
+fn test_citation_metadata_shape() {
+    let tracker = tracker_with_results();
+    let (content, citations) = tracker.resolve("Revenue was $12M [T1].");
+    let message = Message::private("analyst", "user-alice", content).with_citations(&citations);
+
+    let encoded: Value = serde_json::from_str(message.metadata(CITATIONS_KEY).unwrap()).unwrap();
+    assert_eq!(
+        encoded,
+        json!([{
+            "ref_id": "T1",
+            "tool_id": "web.fetch",
+            "params_digest": params_digest(&json!({"url": "https://example.com/q3"})),
+            "excerpt": "Q3 revenue was $12M"
+        }])
+    );
+    assert_eq!(message.citations(), citations);
+
+    // 没有引用时不写元数据
+    let plain = Message::private("analyst", "user-alice", "hi").with_citations(&[]);
+    assert!(plain.metadata(CITATIONS_KEY).is_none());
+    assert!(plain.citations().is_empty());
+}
+
+#[tokio::test]
+async fn test_prompt_lists_tool_results() {
+    let agent = Agent::new("analyst", "Analyst", Role::simple("Analyst", "You analyze"), LLMConfig::openai("k"));
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+    let tracker = tracker_with_results();
+
+    let prompt = runtime.build_thinking_prompt(&Context::default().with_tool_results(tracker.prompt_section().unwrap()));
+    assert!(prompt.contains("- [T1] web.fetch: Q3 revenue was $12M"), "{}", prompt);
+    assert!(prompt.contains("cite it inline"));
+
+    assert!(CitationTracker::new().prompt_section().is_none());
+    assert!(!runtime.build_thinking_prompt(&Context::default()).contains("Tool results"));
+}
+
+#[test]
+fn test_exports_include_citations() {
+    let citation = Citation {
+        ref_id: "T1".to_string(),
+        tool_id: "web.fetch".to_string(),
+        params_digest: "abc123".to_string(),
+        excerpt: "Q3 revenue was $12M".to_string(),
+    };
+    let message = Message::private("analyst", "user-alice", "Revenue was $12M [T1].").with_citations(&[citation]);
+    let session = TranscriptSession::Direct("analyst".to_string());
+
+    let mut markdown = TranscriptRenderer::new(TranscriptFormat::Markdown, session.clone(), "Q3");
+    assert!(markdown.render(&message, &[]).contains("[T1]: `web.fetch` (abc123) Q3 revenue was $12M"));
+
+    let mut html = TranscriptRenderer::new(TranscriptFormat::Html, session, "Q3");
+    let rendered = html.render(&message, &[]);
+    assert!(rendered.contains("<ol class=\"citations\">"), "{}", rendered);
+    assert!(rendered.contains("<summary>[T1] <code>web.fetch</code> (abc123)</summary>Q3 revenue was $12M"));
+}
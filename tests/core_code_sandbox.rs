@@ -0,0 +1,138 @@
+//! 代码沙箱测试（需 `--features sandbox`）：用 WAT 编写的小运行时验证数据转换、燃料/时间/内存上限和 `code.run` 工具
+#![cfg(feature = "sandbox")]
+
+use std::sync::Arc;
+
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::MemoryStore;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::Organization;
+use imitatort::infrastructure::sandbox::{CodeSandbox, CodeSandboxConfig, SandboxError, SandboxRuntime};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+
+/// 把标准输入中的小写字母转成大写后写到标准输出
+const UPPERCASE: &str = r#"
+(module
+  (import "wasi_snapshot_preview1" "fd_read" (func $fd_read (param i32 i32 i32 i32) (result i32)))
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (func (export "_start")
+    (local $n i32) (local $i i32) (local $c i32)
+    (i32.store (i32.const 0) (i32.const 64))
+    (i32.store (i32.const 4) (i32.const 4096))
+    (drop (call $fd_read (i32.const 0) (i32.const 0) (i32.const 1) (i32.const 8)))
+    (local.set $n (i32.load (i32.const 8)))
+    (block $done
+      (loop $next
+        (br_if $done (i32.ge_u (local.get $i) (local.get $n)))
+        (local.set $c (i32.load8_u (i32.add (i32.const 64) (local.get $i))))
+        (if (i32.and (i32.ge_u (local.get $c) (i32.const 97)) (i32.le_u (local.get $c) (i32.const 122)))
+          (then (i32.store8 (i32.add (i32.const 64) (local.get $i)) (i32.sub (local.get $c) (i32.const 32)))))
+        (local.set $i (i32.add (local.get $i) (i32.const 1)))
+        (br $next)))
+    (i32.store (i32.const 4) (local.get $n))
+    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+"#;
+
+/// 死循环
+const SPIN: &str = r#"(module (func (export "_start") (loop $l (br $l))))"#;
+
+/// 申请 2000 页（约 125 MiB）内存，失败时陷入
+const GREEDY: &str = r#"
+(module
+  (memory 1)
+  (func (export "_start")
+    (if (i32.eq (memory.grow (i32.const 2000)) (i32.const -1)) (then unreachable))))
+"#;
+
+fn write_module(dir: &tempfile::TempDir, name: &str, wat: &str) -> SandboxRuntime {
+    let path = dir.path().join(format!("{}.wat", name));
+    std::fs::write(&path, wat).unwrap();
+    SandboxRuntime::new(path)
+}
+
+fn config(dir: &tempfile::TempDir) -> CodeSandboxConfig {
+    CodeSandboxConfig::enabled()
+        .with_runtime("upper", write_module(dir, "upper", UPPERCASE))
+        .with_runtime("spin", write_module(dir, "spin", SPIN))
+        .with_runtime("greedy", write_module(dir, "greedy", GREEDY))
+}
+
+fn executor(sandbox: Option<Arc<CodeSandbox>>) -> FrameworkToolExecutor {
+    let store = Arc::new(MemoryStore::new());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let env = ToolEnvironment::new(bus, Arc::new(RwLock::new(Organization::new())), Arc::new(ToolRegistry::new()), store);
+    let env = match sandbox {
+        Some(sandbox) => env.with_code_sandbox(sandbox),
+        None => env,
+    };
+    FrameworkToolExecutor::new(env)
+}
+
+#[tokio::test]
+async fn test_transforms_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let sandbox = CodeSandbox::new(config(&dir)).unwrap();
+
+    let output = sandbox.run("upper", "ignored", &json!({"name": "ada", "role": "engineer"})).await.unwrap();
+    assert_eq!(output.value(), Some(json!({"NAME": "ADA", "ROLE": "ENGINEER"})));
+    assert!(output.fuel_used > 0);
+
+    // 编译好的运行时在调用间复用
+    let output = sandbox.run("upper", "ignored", &json!(["x"])).await.unwrap();
+    assert_eq!(output.stdout, r#"["X"]"#);
+
+    let err = sandbox.run("python", "print(1)", &json!(null)).await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<SandboxError>(), Some(SandboxError::UnsupportedLanguage { .. })));
+}
+
+#[tokio::test]
+async fn test_limits_stop_the_program() {
+    let dir = tempfile::tempdir().unwrap();
+    let sandbox = CodeSandbox::new(config(&dir).with_limits(1_000_000, 60_000, 64)).unwrap();
+    let err = sandbox.run("spin", "", &json!(null)).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<SandboxError>(), Some(&SandboxError::FuelExhausted { fuel: 1_000_000 }));
+
+    let err = sandbox.run("greedy", "", &json!(null)).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<SandboxError>(), Some(&SandboxError::MemoryLimit { limit_mb: 64 }));
+
+    // 燃料足够时由时间上限终止
+    let sandbox = CodeSandbox::new(config(&dir).with_limits(u64::MAX, 100, 64)).unwrap();
+    let err = sandbox.run("spin", "", &json!(null)).await.unwrap_err();
+    assert_eq!(err.downcast_ref::<SandboxError>(), Some(&SandboxError::Timeout { timeout_ms: 100 }));
+}
+
+#[tokio::test]
+async fn test_code_run_tool() {
+    let dir = tempfile::tempdir().unwrap();
+    let sandbox = Arc::new(CodeSandbox::new(config(&dir).with_limits(1_000_000, 5000, 64)).unwrap());
+    let enabled = executor(Some(sandbox));
+    let context = ToolCallContext::new("dev");
+
+    let result = enabled
+        .execute("code.run", json!({"language": "upper", "code": "x", "input": {"city": "paris"}}), &context)
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["result"], json!({"CITY": "PARIS"}));
+
+    // 触发上限时说明是哪一种，便于 Agent 调整
+    let result = enabled.execute("code.run", json!({"language": "spin", "code": "x"}), &context).await.unwrap();
+    assert!(!result.success);
+    assert_eq!(result.metadata["limit_exceeded"], json!("fuel"));
+    let result = enabled.execute("code.run", json!({"language": "greedy", "code": "x"}), &context).await.unwrap();
+    assert_eq!(result.metadata["limit_exceeded"], json!("memory"));
+
+    let result = enabled.execute("code.run", json!({"code": "1 + 1"}), &context).await.unwrap();
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("javascript"));
+
+    // 未启用沙箱时工具不可用
+    let result = executor(None).execute("code.run", json!({"code": "x"}), &context).await.unwrap();
+    assert!(!result.success);
+    assert!(!result.metadata.contains_key("limit_exceeded"));
+}
@@ -0,0 +1,147 @@
+//! 按时间点重现 Agent 上下文测试
+
+use std::sync::Arc;
+
+use imitatort::core::agent::{load_context, AgentRuntime};
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::domain::{Agent, LLMConfig, Message, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+fn message_at(from: &str, to: &str, content: &str, timestamp: i64) -> Message {
+    let mut message = Message::private(from, to, content);
+    message.timestamp = timestamp;
+    message
+}
+
+async fn seed(store: &dyn Store) {
+    store.save_message(&message_at("user", "dev", "first question", 100)).await.unwrap();
+    store.save_message(&message_at("dev", "user", "first answer", 200)).await.unwrap();
+    store.save_message(&message_at("user", "dev", "second question", 300)).await.unwrap();
+    store.save_message(&message_at("user", "other", "not for dev", 150)).await.unwrap();
+}
+
+fn contents(context: &imitatort::core::agent::Context) -> Vec<&str> {
+    context.history.iter().map(|m| m.content.as_str()).collect()
+}
+
+async fn assert_reconstructions(store: &dyn Store) {
+    seed(store).await;
+
+    let before = load_context(store, "dev", Some(50)).await.unwrap();
+    assert!(before.history.is_empty());
+
+    let at_first = load_context(store, "dev", Some(100)).await.unwrap();
+    assert_eq!(contents(&at_first), vec!["first question"]);
+
+    let at_answer = load_context(store, "dev", Some(250)).await.unwrap();
+    assert_eq!(contents(&at_answer), vec!["first question", "first answer"]);
+
+    let now = load_context(store, "dev", None).await.unwrap();
+    assert_eq!(contents(&now), vec!["first question", "first answer", "second question"]);
+
+    // 相同时间点的重现结果一致
+    let again = load_context(store, "dev", Some(250)).await.unwrap();
+    assert_eq!(contents(&again), contents(&at_answer));
+}
+
+#[tokio::test]
+async fn test_context_as_of_memory_store() {
+    assert_reconstructions(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn test_context_as_of_sqlite_store() {
+    assert_reconstructions(&SqliteStore::new_in_memory().unwrap()).await;
+}
+
+#[tokio::test]
+async fn test_message_filter_until() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    seed(&store).await;
+
+    let messages = store
+        .load_messages(MessageFilter::new().since(150).until(200))
+        .await
+        .unwrap();
+    assert_eq!(messages.len(), 2);
+    assert!(messages.iter().all(|m| (150..=200).contains(&m.timestamp)));
+}
+
+#[tokio::test]
+async fn test_prompt_includes_history_window() {
+    let store = MemoryStore::new();
+    seed(&store).await;
+
+    let agent = Agent::new("dev", "Dev", Role::simple("Developer", "You write code"), LLMConfig::openai("test-key"));
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+    let prompt = runtime.build_thinking_prompt(&load_context(&store, "dev", Some(250)).await.unwrap());
+
+    assert!(prompt.contains("Recent conversation:"));
+    assert!(prompt.contains("first answer"));
+    assert!(!prompt.contains("second question"));
+}
+
+#[tokio::test]
+async fn test_context_endpoint_redacts_secrets() {
+    let store = Arc::new(MemoryStore::new());
+    store.save_message(&message_at("user", "dev", "use key sk-abcdefghijklmnop please", 100)).await.unwrap();
+
+    let agent = Agent::new(
+        "dev",
+        "Dev",
+        Role::simple("Developer", "You write code"),
+        LLMConfig::openai("secret-api-key-123"),
+    );
+    let (message_tx, _) = broadcast::channel::<Message>(16);
+    let jwt_service = JwtService::new("test-secret");
+    let token = jwt_service
+        .generate_token(&UserInfo {
+            id: "admin".to_string(),
+            username: "admin".to_string(),
+            name: "Admin".to_string(),
+            email: None,
+            is_director: true,
+            employee_id: "00001".to_string(),
+            position: "Chairman".to_string(),
+            department: "board".to_string(),
+        })
+        .unwrap();
+    let app = create_router(Arc::new(AppState::new(vec![agent], message_tx, store, jwt_service)));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(format!("http://{}/api/v1/agents/dev/context?as_of=100", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let body: Value = client
+        .get(format!("http://{}/api/v1/agents/dev/context?as_of=100", addr))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let data = &body["data"];
+    assert_eq!(data["as_of"], 100);
+    assert_eq!(data["messages"].as_array().unwrap().len(), 1);
+    let prompt = data["prompt"].as_str().unwrap();
+    assert!(prompt.contains("You write code"));
+    assert!(!prompt.contains("sk-abcdefghijklmnop"));
+    assert!(prompt.contains("sk-[REDACTED]"));
+    assert!(data["system_prompt_version"].is_string());
+}
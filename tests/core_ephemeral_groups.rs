@@ -0,0 +1,155 @@
+//! 临时群聊测试：过期关闭、会话快照、默认列表过滤和创建上限
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::{MessageBus, GROUP_EXPIRY_SENDER};
+use imitatort::core::store::Store;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Message, Organization};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use serde_json::json;
+use tokio::sync::RwLock;
+
+const START: i64 = 1_700_000_000;
+
+fn bus_with(store: Arc<SqliteStore>, clock: Arc<ManualClock>) -> MessageBus {
+    MessageBus::with_store(store).with_clock(clock)
+}
+
+fn members(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_expired_group_is_closed_with_snapshot() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let clock = Arc::new(ManualClock::new(START));
+    let bus = bus_with(store.clone(), clock.clone());
+    let _lead_rx = bus.register("lead");
+    let mut dev_rx = bus.register("dev");
+
+    bus.create_group("team", "Team", "lead", members(&["lead", "dev"])).await.unwrap();
+    let group = bus
+        .create_ephemeral_group("notes", "Release notes", "lead", members(&["lead", "dev"]), Duration::from_secs(600))
+        .await
+        .unwrap();
+    assert_eq!(group.expires_at, Some(START + 600));
+
+    // 默认列表不含临时群聊
+    let listed: Vec<String> = bus.list_agent_groups("dev").await.into_iter().map(|g| g.id).collect();
+    assert_eq!(listed, vec!["team"]);
+    assert_eq!(bus.list_agent_groups_with("dev", true).await.len(), 2);
+
+    bus.send(Message::group("lead", "notes", "Draft is in the doc")).await.unwrap();
+    assert_eq!(dev_rx.recv().await.unwrap().content, "Draft is in the doc");
+
+    // 未到期不关闭
+    clock.advance(Duration::from_secs(599));
+    assert!(bus.close_expired_groups().await.is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(bus.close_expired_groups().await, vec!["notes"]);
+
+    let notice = dev_rx.recv().await.unwrap();
+    assert_eq!(notice.from, GROUP_EXPIRY_SENDER);
+    assert!(notice.content.contains("Release notes"), "{}", notice.content);
+
+    assert!(bus.get_group("notes").await.is_none());
+    assert!(store.load_groups().await.unwrap().iter().all(|g| g.id != "notes"));
+    assert!(bus.get_group("team").await.is_some());
+
+    let snapshot = bus.group_snapshot("notes").await.unwrap().expect("snapshot saved");
+    assert_eq!(snapshot.closed_at, START + 600);
+    assert_eq!(snapshot.group.members, members(&["lead", "dev"]));
+    let contents: Vec<&str> = snapshot.messages.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents[0], "Draft is in the doc");
+    assert_eq!(snapshot.messages.len(), 2);
+    assert_eq!(snapshot.messages[1].from, GROUP_EXPIRY_SENDER);
+}
+
+#[tokio::test]
+async fn test_expired_group_cannot_be_reactivated() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let clock = Arc::new(ManualClock::new(START));
+    let bus = bus_with(store.clone(), clock.clone());
+    let _rx = bus.register("lead");
+
+    bus.create_ephemeral_group("tmp", "Tmp", "lead", members(&["lead"]), Duration::from_secs(60))
+        .await
+        .unwrap();
+    clock.advance(Duration::from_secs(60));
+    bus.close_expired_groups().await;
+
+    let err = bus.create_group("tmp", "Tmp", "lead", members(&["lead"])).await.unwrap_err();
+    assert!(err.to_string().contains("cannot be re-activated"), "{}", err);
+
+    // 重启后依据存储中的快照仍然拒绝
+    let restarted = bus_with(store, clock);
+    let _rx = restarted.register("lead");
+    let err = restarted
+        .create_ephemeral_group("tmp", "Tmp", "lead", members(&["lead"]), Duration::from_secs(60))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("cannot be re-activated"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_sweeper_closes_groups_left_over_from_restart() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let clock = Arc::new(ManualClock::new(START));
+    let bus = bus_with(store.clone(), clock.clone());
+    let _rx = bus.register("lead");
+    bus.create_ephemeral_group("old", "Old", "lead", members(&["lead"]), Duration::from_secs(60))
+        .await
+        .unwrap();
+    drop(bus);
+
+    clock.advance(Duration::from_secs(120));
+    let restarted = bus_with(store.clone(), clock);
+    assert_eq!(restarted.close_expired_groups().await, vec!["old"]);
+    assert!(store.load_groups().await.unwrap().is_empty());
+    assert!(restarted.group_snapshot("old").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_create_ephemeral_tool_caps_per_agent() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let clock = Arc::new(ManualClock::new(START));
+    let bus = Arc::new(bus_with(store.clone(), clock).with_ephemeral_group_limit(1));
+    let _rx = bus.register("lead");
+    let env = ToolEnvironment::new(
+        bus.clone(),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store,
+    );
+    let executor = FrameworkToolExecutor::new(env);
+    let context = ToolCallContext::new("lead");
+
+    let result = executor
+        .execute("group.create_ephemeral", json!({ "name": "Notes", "members": ["dev"], "ttl_secs": 0 }), &context)
+        .await
+        .unwrap();
+    assert!(!result.success);
+
+    let result = executor
+        .execute("group.create_ephemeral", json!({ "name": "Notes", "members": ["dev", "qa"], "ttl_secs": 900 }), &context)
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["expires_at"], START + 900);
+    let group = bus.get_group(result.data["group_id"].as_str().unwrap()).await.unwrap();
+    assert!(group.ephemeral);
+    assert_eq!(group.members, members(&["dev", "qa", "lead"]));
+
+    let result = executor
+        .execute("group.create_ephemeral", json!({ "name": "More", "members": ["dev"] }), &context)
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.as_deref(), Some("You already have 1 active temporary groups (limit 1)"));
+}
@@ -0,0 +1,136 @@
+//! 未回复消息升级测试
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::config::{CompanyConfig, EscalationPolicy};
+use imitatort::core::escalation::{EscalationChecker, NO_ESCALATION_KEY};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::domain::{Agent, Department, LLMConfig, Message, Organization, Role};
+use tokio::sync::RwLock;
+
+fn agent(id: &str, dept: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Employee", "You work here"), LLMConfig::openai("test-key"))
+        .with_department(dept)
+}
+
+/// tech（负责人 cto）下有 backend（负责人 lead），dev 是 backend 中不回复消息的员工
+fn create_org() -> Organization {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("tech", "Tech").with_leader("cto"));
+    org.add_department(Department::child("backend", "Backend", "tech").with_leader("lead"));
+    org.add_agent(agent("cto", "tech"));
+    org.add_agent(agent("lead", "backend"));
+    org.add_agent(agent("dev", "backend"));
+    org
+}
+
+struct Fixture {
+    checker: EscalationChecker,
+    bus: Arc<MessageBus>,
+    store: Arc<dyn Store>,
+    clock: Arc<ManualClock>,
+}
+
+fn create_fixture() -> Fixture {
+    let org = create_org();
+    let config = CompanyConfig::new("Test", org.clone())
+        .with_escalation("tech", EscalationPolicy::after_secs(300));
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let clock = Arc::new(ManualClock::new(chrono::Utc::now().timestamp()));
+
+    let checker = EscalationChecker::new(config, Arc::new(RwLock::new(org)), bus.clone(), store.clone())
+        .with_clock(clock.clone());
+
+    Fixture { checker, bus, store, clock }
+}
+
+#[tokio::test]
+async fn test_silent_agent_is_escalated_once() {
+    let fixture = create_fixture();
+    let _dev_rx = fixture.bus.register("dev");
+    let mut lead_rx = fixture.bus.register("lead");
+
+    let question = Message::private("user", "dev", "Is the build green?");
+    fixture.bus.send(question.clone()).await.unwrap();
+
+    // 未到时间不升级
+    fixture.clock.advance(Duration::from_secs(60));
+    assert!(fixture.checker.check().await.unwrap().is_empty());
+
+    fixture.clock.advance(Duration::from_secs(300));
+    let escalations = fixture.checker.check().await.unwrap();
+    assert_eq!(escalations.len(), 1);
+    assert_eq!(escalations[0].escalated_to, "lead");
+
+    let notice = lead_rx.try_recv().unwrap();
+    assert_eq!(notice.from, "system");
+    assert!(notice.content.contains("Is the build green?"));
+    assert_eq!(notice.metadata("escalated_message_id"), Some(question.id.as_str()));
+
+    // 已记录，不重复升级，且升级通知本身不会再被升级
+    fixture.clock.advance(Duration::from_secs(600));
+    assert!(fixture.checker.check().await.unwrap().is_empty());
+    assert!(fixture.store.load_escalation(&question.id).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_unresponsive_leader_escalates_to_parent_department() {
+    let fixture = create_fixture();
+    let _lead_rx = fixture.bus.register("lead");
+    let _cto_rx = fixture.bus.register("cto");
+
+    fixture.bus.send(Message::private("user", "lead", "Status?")).await.unwrap();
+    fixture.clock.advance(Duration::from_secs(301));
+
+    let escalations = fixture.checker.check().await.unwrap();
+    assert_eq!(escalations.len(), 1);
+    assert_eq!(escalations[0].agent_id, "lead");
+    assert_eq!(escalations[0].escalated_to, "cto");
+}
+
+#[tokio::test]
+async fn test_replied_and_opted_out_messages_are_not_escalated() {
+    let fixture = create_fixture();
+    let _dev_rx = fixture.bus.register("dev");
+    let _user_rx = fixture.bus.register("user");
+
+    let answered = Message::private("user", "dev", "Ping");
+    fixture.bus.send(answered.clone()).await.unwrap();
+    fixture
+        .bus
+        .send(Message::private("dev", "user", "Pong").with_reply_to(&answered.id))
+        .await
+        .unwrap();
+
+    let opted_out = Message::private("user", "dev", "FYI, no need to answer")
+        .with_metadata(NO_ESCALATION_KEY, "true");
+    fixture.bus.send(opted_out).await.unwrap();
+
+    fixture.clock.advance(Duration::from_secs(3600));
+    assert!(fixture.checker.check().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_departments_without_policy_are_ignored() {
+    let org = create_org();
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let clock = Arc::new(ManualClock::new(chrono::Utc::now().timestamp()));
+    let checker = EscalationChecker::new(
+        CompanyConfig::new("Test", org.clone()),
+        Arc::new(RwLock::new(org)),
+        bus.clone(),
+        store,
+    )
+    .with_clock(clock.clone());
+
+    let _dev_rx = bus.register("dev");
+    bus.send(Message::private("user", "dev", "Hello?")).await.unwrap();
+    clock.advance(Duration::from_secs(3600));
+
+    assert!(checker.check().await.unwrap().is_empty());
+}
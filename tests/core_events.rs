@@ -0,0 +1,257 @@
+//! 生命周期事件测试
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use imitatort::core::events::{CompanyEvent, CompanyEventListener, EventBus};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::watchdog::{TriggerCondition, WatchdogFramework, WatchdogRule};
+use imitatort::core::watchdog::ToolExecutionEvent;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Agent, LLMConfig, Organization, Role};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment, ToolExecutorRegistry};
+use imitatort::{CompanyConfig, VirtualCompany};
+
+/// 固定返回一条 send_message 决策的 LLM
+async fn spawn_mock_llm() -> String {
+    async fn completions(Json(_request): Json<Value>) -> Json<Value> {
+        let decision = json!({"action": "send_message", "target": "bob", "content": "hi"});
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": decision.to_string()},
+                "finish_reason": "stop"
+            }]
+        }))
+    }
+
+    let app = Router::new().route("/chat/completions", post(completions));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<CompanyEvent>>>);
+
+impl Recorder {
+    fn events(&self) -> Vec<CompanyEvent> {
+        self.0.lock().unwrap().clone()
+    }
+
+    async fn wait_for(&self, predicate: impl Fn(&[CompanyEvent]) -> bool) {
+        for _ in 0..100 {
+            if predicate(&self.events()) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        panic!("timed out waiting for events: {:?}", self.events());
+    }
+}
+
+#[async_trait]
+impl CompanyEventListener for Recorder {
+    async fn on_event(&self, event: &CompanyEvent) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+/// 第一次收到事件时 panic 的监听器
+struct Panicky(Recorder);
+
+#[async_trait]
+impl CompanyEventListener for Panicky {
+    async fn on_event(&self, event: &CompanyEvent) {
+        let first = self.0.events().is_empty();
+        self.0.on_event(event).await;
+        if first {
+            panic!("listener failure");
+        }
+    }
+}
+
+fn kinds(events: &[CompanyEvent]) -> Vec<&'static str> {
+    events.iter().map(CompanyEvent::kind).collect()
+}
+
+#[tokio::test]
+async fn test_scripted_turn_emits_event_sequence() {
+    let base_url = spawn_mock_llm().await;
+    let mut org = Organization::new();
+    org.add_agent(Agent::new(
+        "alice",
+        "Alice",
+        Role::simple("Engineer", "You are an engineer"),
+        LLMConfig {
+            model: "mock".to_string(),
+            api_key: "test".to_string(),
+            base_url,
+            tokenizer: None,
+        },
+    ));
+    let company = Arc::new(VirtualCompany::with_store(
+        CompanyConfig::new("Events", org),
+        Arc::new(MemoryStore::new()),
+    ));
+
+    let recorder = Recorder::default();
+    company.register_event_listener(Box::new(recorder.clone()));
+    let mut stream = company.events();
+
+    let runner = company.clone();
+    let handle = tokio::spawn(async move { runner.run().await });
+
+    // Agent：先启动，再完成一轮决策
+    recorder
+        .wait_for(|events| events.iter().any(|e| e.kind() == "agent_turn_completed"))
+        .await;
+    let agent_events = recorder.events();
+    // 决策里的消息在轮次结束时经消息总线发出；bob 不在组织里，整批不发送
+    assert_eq!(kinds(&agent_events)[..3], ["agent_started", "outbox_flushed", "agent_turn_completed"]);
+    match &agent_events[1] {
+        CompanyEvent::OutboxFlushed { turn_failed, report, .. } => {
+            assert!(!turn_failed);
+            assert!(report.sent.is_empty());
+            assert!(report.failed[0].1.contains("bob"));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+    match &agent_events[2] {
+        CompanyEvent::AgentTurnCompleted { agent_id, decision, error, .. } => {
+            assert_eq!(&**agent_id, "alice");
+            assert!(decision.is_some(), "error: {:?}", error);
+            assert!(error.is_none());
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    // 工具执行：发送消息先落库，再报告工具完成
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(company.tool_registry())
+        .with_events(company.event_bus());
+    registry.register(Box::new(company.get_framework_tool_executor()));
+    let result = registry
+        .execute(
+            "message.send_direct",
+            json!({"to_agent_id": "alice", "content": "status?"}),
+            &ToolCallContext::new("alice"),
+        )
+        .await
+        .unwrap();
+    assert!(result.success);
+
+    recorder
+        .wait_for(|events| events.iter().any(|e| e.kind() == "tool_executed"))
+        .await;
+    let other: Vec<&'static str> = kinds(&recorder.events())
+        .into_iter()
+        .filter(|k| !k.starts_with("agent_") && *k != "outbox_flushed")
+        .collect();
+    assert_eq!(other, ["message_persisted", "tool_executed"]);
+
+    // broadcast 订阅者同样收到事件
+    assert_eq!(stream.recv().await.unwrap().kind(), "agent_started");
+
+    handle.abort();
+}
+
+#[tokio::test]
+async fn test_watchdog_and_panicking_listener() {
+    let events = Arc::new(EventBus::new());
+    let panicky = Recorder::default();
+    let recorder = Recorder::default();
+    events.register_listener(Box::new(Panicky(panicky.clone())));
+    events.register_listener(Box::new(recorder.clone()));
+
+    let watchdog = WatchdogFramework::new().with_events(events.clone());
+    watchdog
+        .register_rule(WatchdogRule::new(
+            "high_load",
+            "server.load",
+            TriggerCondition::NumericRange { min: 80.0, max: 100.0 },
+            "ops",
+        ))
+        .unwrap();
+
+    for load in [90.0, 10.0, 95.0] {
+        watchdog
+            .process_event(&ToolExecutionEvent::PostExecute {
+                tool_id: "server.load".to_string(),
+                result: json!(load),
+                context: ToolCallContext::new("monitor").with_trace_id("load-check"),
+            })
+            .await
+            .unwrap();
+    }
+
+    recorder.wait_for(|events| events.len() == 2).await;
+    match &recorder.events()[0] {
+        CompanyEvent::WatchdogTriggered { rule_id, target_agent_id, trace_id, .. } => {
+            assert_eq!(&**rule_id, "high_load");
+            assert_eq!(&**target_agent_id, "ops");
+            assert_eq!(&**trace_id, "load-check");
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    // panic 之后监听器继续处理后续事件
+    panicky.wait_for(|events| events.len() == 2).await;
+}
+
+#[tokio::test]
+async fn test_trace_id_links_tool_call_and_message() {
+    let store = Arc::new(MemoryStore::new());
+    let events = Arc::new(EventBus::new());
+    let recorder = Recorder::default();
+    events.register_listener(Box::new(recorder.clone()));
+
+    let bus = Arc::new(MessageBus::with_store(store.clone()).with_events(events.clone()));
+    let _inbox = bus.register("bob");
+    let tools = Arc::new(ToolRegistry::new());
+    let env = ToolEnvironment::new(bus, Arc::new(RwLock::new(Organization::new())), tools.clone(), store.clone());
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(tools).with_events(events);
+    registry.register(Box::new(FrameworkToolExecutor::new(env)));
+    let context = ToolCallContext::new("alice")
+        .with_trace_id("trace-42")
+        .with_parent_span_id("turn-1");
+    registry
+        .execute(
+            "message.send_direct",
+            json!({"to_agent_id": "bob", "content": "ping"}),
+            &context,
+        )
+        .await
+        .unwrap();
+
+    recorder
+        .wait_for(|events| events.iter().any(|e| e.kind() == "tool_executed"))
+        .await;
+    let events = recorder.events();
+    let tool_event = events.iter().find(|e| e.kind() == "tool_executed").unwrap();
+    let persisted = events.iter().find(|e| e.kind() == "message_persisted").unwrap();
+    assert_eq!(tool_event.trace_id(), Some("trace-42"));
+    assert_eq!(persisted.trace_id(), Some("trace-42"));
+
+    // 落库的消息带着同一个追踪ID和上级 span
+    let stored = store.load_messages(MessageFilter::new().from("alice")).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].trace_id(), Some("trace-42"));
+    assert_eq!(stored[0].metadata("parent_span_id"), Some("turn-1"));
+
+    // 未指定时每个上下文生成独立的追踪ID
+    assert_ne!(ToolCallContext::new("a").trace_id, ToolCallContext::new("a").trace_id);
+}
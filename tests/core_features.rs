@@ -0,0 +1,105 @@
+//! 功能开关测试：配置和编译特性的登记、关闭的功能隐藏其工具、运行中切换后工具视图随事件失效
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::config::CompanyConfig;
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::features::{Feature, FeatureError, FeatureFlags, FeatureSource};
+use imitatort::core::skill::SkillManager;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::tool_provider::{CompositeToolProvider, FrameworkToolProvider};
+use imitatort::core::tool_view::AgentToolView;
+use imitatort::domain::tool::{CategoryPath, JsonSchema, Tool};
+use imitatort::Organization;
+
+fn app_tool(id: &str, feature: &str) -> Tool {
+    Tool::new(id, id, "Report tool", CategoryPath::from_str("reports"), JsonSchema::object().build()).with_feature(feature)
+}
+
+fn visible(view: &AgentToolView, tool_id: &str) -> bool {
+    view.tools_for("dev").iter().any(|t| t.id == tool_id)
+}
+
+async fn view_with(flags: Arc<FeatureFlags>) -> AgentToolView {
+    let registry = Arc::new(ToolRegistry::new());
+    registry.register(app_tool("reports.export", "reports")).await.unwrap();
+    registry.register(app_tool("reports.custom", "unregistered")).await.unwrap();
+    let provider = CompositeToolProvider::new()
+        .add_provider(Box::new(FrameworkToolProvider::new().with_handoff()))
+        .with_registry(registry.clone());
+    AgentToolView::new(Arc::new(provider), Arc::new(SkillManager::new_with_tool_registry(registry))).with_features(flags)
+}
+
+#[test]
+fn test_flags_from_config() {
+    let mut config = CompanyConfig::new("Acme", Organization::new());
+    config.handoff.enabled = true;
+    let flags = FeatureFlags::from_config(&config);
+
+    let handoff = flags.get("handoff").unwrap();
+    assert!(handoff.enabled && handoff.toggleable);
+    assert_eq!(handoff.source, FeatureSource::Config);
+    // 配置中关闭的功能需要重启才能启用
+    let scratchpad = flags.get("scratchpad").unwrap();
+    assert!(!scratchpad.enabled && !scratchpad.toggleable);
+    assert_eq!(flags.toggle("scratchpad", true), Err(FeatureError::NotToggleable("scratchpad".to_string())));
+    assert_eq!(flags.toggle("ghost", true), Err(FeatureError::NotFound("ghost".to_string())));
+
+    let s3 = flags.get("s3").unwrap();
+    assert_eq!(s3.source, FeatureSource::Compiled);
+    assert_eq!(s3.enabled, cfg!(feature = "s3"));
+    assert!(flags.enabled().contains(&"handoff".to_string()));
+    assert!(flags.is_enabled("unregistered"));
+}
+
+#[tokio::test]
+async fn test_tools_of_disabled_features_are_hidden() {
+    let flags = Arc::new(FeatureFlags::new());
+    flags.register(Feature::new("reports", false, FeatureSource::Runtime));
+    flags.register(Feature::new("handoff", false, FeatureSource::Config));
+    let view = view_with(flags).await;
+
+    assert!(!visible(&view, "reports.export"));
+    assert!(!visible(&view, "handoff"));
+    assert!(!view.can_use("dev", "handoff"));
+    // 未登记的功能视为开启，没有声明功能的工具不受影响
+    assert!(visible(&view, "reports.custom"));
+    assert!(visible(&view, "message.send_direct"));
+}
+
+#[tokio::test]
+async fn test_toggle_invalidates_tool_view_through_event() {
+    let events = Arc::new(EventBus::new());
+    let mut events_rx = events.subscribe();
+    let flags = Arc::new(FeatureFlags::new().with_events(events.clone()));
+    flags.register(Feature::new("reports", true, FeatureSource::Runtime).with_toggleable(true));
+    let view = Arc::new(view_with(flags.clone()).await);
+    view.clone().spawn(&events);
+    assert!(visible(&view, "reports.export"));
+
+    let feature = flags.toggle("reports", false).unwrap();
+    assert!(!feature.enabled);
+    match events_rx.recv().await.unwrap() {
+        CompanyEvent::FeatureToggled { feature } => assert_eq!(feature.name, "reports"),
+        other => panic!("unexpected event {}", other.kind()),
+    }
+    let mut hidden = false;
+    for _ in 0..50 {
+        if !visible(&view, "reports.export") {
+            hidden = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(hidden);
+
+    // 状态不变时不发事件
+    flags.toggle("reports", false).unwrap();
+    flags.toggle("reports", true).unwrap();
+    match events_rx.recv().await.unwrap() {
+        CompanyEvent::FeatureToggled { feature } => assert!(feature.enabled),
+        other => panic!("unexpected event {}", other.kind()),
+    }
+    assert!(events_rx.try_recv().is_err());
+}
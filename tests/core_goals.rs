@@ -0,0 +1,285 @@
+//! 当前目标测试：历史被大幅截断时目标仍出现在每轮提示词中直到被清除、目标段落按 token 上限截断、
+//! 闲置过期、`self.set_goal` / `self.clear_goal` 工具、追踪事件与 `GET /agents/{id}` 中的目标
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::agent::{AgentRuntime, Context};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::goals::{GoalBoard, GoalConfig, GoalSource};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::Store;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Message, Organization, Role};
+
+const ALICE: &str = "user:alice";
+const REQUEST: &str = "Prepare the Q3 board deck with revenue by region";
+
+fn dev(llm_config: LLMConfig) -> Agent {
+    Agent::new("dev", "Dev", Role::simple("Engineer", "You write code"), llm_config)
+}
+
+fn goal_executor(goals: Arc<GoalBoard>) -> FrameworkToolExecutor {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    FrameworkToolExecutor::new(
+        ToolEnvironment::new(
+            Arc::new(MessageBus::new()),
+            Arc::new(RwLock::new(Organization::new())),
+            Arc::new(ToolRegistry::new()),
+            store,
+        )
+        .with_goals(goals),
+    )
+}
+
+#[tokio::test]
+async fn test_goal_survives_truncated_history_until_cleared() {
+    let runtime = AgentRuntime::new(dev(LLMConfig::openai("k")))
+        .await
+        .unwrap()
+        .with_history_token_budget(40);
+    let goals = Arc::new(GoalBoard::new(GoalConfig::default()));
+    let executor = goal_executor(goals.clone());
+    let context = ToolCallContext::new("dev");
+
+    let mut conversation = vec![Message::private(ALICE, "dev", REQUEST)];
+    goals.set("dev", REQUEST, GoalSource::User, Some(conversation[0].id.clone()));
+    for i in 1..30 {
+        let message = if i % 2 == 1 {
+            Message::private("dev", ALICE, format!("Working on part {} of the deck, pulling the numbers now", i))
+        } else {
+            Message::private(ALICE, "dev", format!("Follow-up {}: also double check the totals", i))
+        };
+        conversation.push(message);
+
+        let goal = goals.get("dev").unwrap();
+        let prompt = runtime.build_thinking_prompt(
+            &Context::default().with_history(conversation.clone()).with_goal(goal.text),
+        );
+        let section = prompt.split("Active goal").nth(1).unwrap_or_else(|| panic!("message {}: {}", i, prompt));
+        assert!(section.contains(REQUEST), "message {}: {}", i, prompt);
+        // 请求本身早已被截出历史，只在目标段落中出现
+        if i >= 5 {
+            assert_eq!(prompt.matches(REQUEST).count(), 1, "message {}: {}", i, prompt);
+        }
+    }
+
+    let result = executor.execute("self.clear_goal", json!({}), &context).await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["cleared"]["text"], REQUEST);
+    assert!(goals.get("dev").is_none());
+    let prompt = runtime.build_thinking_prompt(&Context::default().with_history(conversation));
+    assert!(!prompt.contains("Active goal"));
+    assert!(!prompt.contains(REQUEST));
+}
+
+#[tokio::test]
+async fn test_goal_section_truncated_to_reserve() {
+    let runtime = AgentRuntime::new(dev(LLMConfig::openai("k"))).await.unwrap();
+    let long_goal = format!("{} {}", REQUEST, "and every appendix ".repeat(200));
+    let truncated = runtime.token_counter().truncate(&long_goal, 50);
+    assert!(truncated.ends_with('…'));
+
+    let prompt = runtime.build_thinking_prompt(&Context::default().with_goal(truncated.clone()));
+    assert!(prompt.contains(&truncated));
+    assert!(!prompt.contains(&long_goal));
+}
+
+#[test]
+fn test_idle_goal_expires() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let config = GoalConfig { idle_expiry_secs: 600, ..GoalConfig::default() };
+    let goals = GoalBoard::new(config).with_clock(clock.clone());
+
+    goals.set("dev", REQUEST, GoalSource::User, None);
+    clock.advance(Duration::from_secs(500));
+    assert!(goals.take_expired("dev").is_none());
+    // 有活动时从最近一次活动重新计时
+    goals.touch("dev");
+    clock.advance(Duration::from_secs(500));
+    assert!(goals.take_expired("dev").is_none());
+    clock.advance(Duration::from_secs(100));
+    let expired = goals.take_expired("dev").unwrap();
+    assert_eq!(expired.text, REQUEST);
+    assert!(goals.get("dev").is_none());
+
+    let notice = expired.expiry_notice("dev");
+    assert_eq!(notice.from, "system");
+    assert!(notice.content.contains(REQUEST));
+    assert!(notice.content.contains("self.set_goal"));
+
+    // 0 表示不过期
+    let goals = GoalBoard::new(GoalConfig { idle_expiry_secs: 0, ..GoalConfig::default() }).with_clock(clock.clone());
+    goals.set("dev", REQUEST, GoalSource::User, None);
+    clock.advance(Duration::from_secs(86_400));
+    assert!(goals.take_expired("dev").is_none());
+}
+
+#[tokio::test]
+async fn test_set_goal_tool() {
+    let goals = Arc::new(GoalBoard::new(GoalConfig::default()));
+    let executor = goal_executor(goals.clone());
+    let context = ToolCallContext::new("dev");
+    goals.set("dev", REQUEST, GoalSource::User, None);
+
+    let result = executor
+        .execute("self.set_goal", json!({"goal": "Only the EMEA slide is left"}), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["goal"]["source"], "agent");
+    let goal = goals.get("dev").unwrap();
+    assert_eq!(goal.text, "Only the EMEA slide is left");
+    assert_eq!(goal.source, GoalSource::Agent);
+
+    assert!(executor.execute("self.set_goal", json!({"goal": "  "}), &context).await.is_err());
+
+    // 未启用目标时工具返回失败结果
+    let disabled = FrameworkToolExecutor::new(ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        Arc::new(SqliteStore::new_in_memory().unwrap()),
+    ));
+    let result = disabled.execute("self.clear_goal", json!({}), &context).await.unwrap();
+    assert!(!result.success);
+}
+
+/// 记录每次请求的提示词，只回复等待
+async fn spawn_llm() -> (String, Arc<Mutex<Vec<String>>>) {
+    async fn completions(State(prompts): State<Arc<Mutex<Vec<String>>>>, Json(body): Json<Value>) -> Json<Value> {
+        let prompt: String = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|m| m["content"].as_str())
+            .collect();
+        prompts.lock().unwrap().push(prompt);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": json!({"action": "wait"}).to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(prompts.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (format!("http://{}", addr), prompts)
+}
+
+/// 等到请求数达到 `count`
+async fn wait_for_prompts(prompts: &Mutex<Vec<String>>, count: usize) {
+    for _ in 0..100 {
+        if prompts.lock().unwrap().len() >= count {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("only {} prompts", prompts.lock().unwrap().len());
+}
+
+#[tokio::test]
+async fn test_agent_keeps_user_request_as_goal() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _alice_rx = bus.register_user(ALICE);
+    let events = Arc::new(EventBus::new());
+    let mut events_rx = events.subscribe();
+    let goals = Arc::new(GoalBoard::new(GoalConfig::default()));
+
+    let (url, prompts) = spawn_llm().await;
+    let agent = AutonomousAgent::new(dev(LLMConfig::openai("sk-test").with_base_url(url)), bus.clone())
+        .await
+        .unwrap()
+        .with_events(events)
+        .with_goals(goals.clone());
+    let handle = tokio::spawn(async move {
+        let _ = agent.run_loop().await;
+    });
+
+    // 最初的请求成为目标，之后 29 条追问不替换它，请求被挤出 20 条的历史窗口后仍在目标段落中
+    bus.send(Message::private(ALICE, "dev", REQUEST)).await.unwrap();
+    for i in 1..30 {
+        bus.send(Message::private(ALICE, "dev", format!("Follow-up {}", i))).await.unwrap();
+    }
+    let sent = prompts.lock().unwrap().len();
+    wait_for_prompts(&prompts, sent + 2).await;
+    let goal = goals.get("dev").unwrap();
+    assert_eq!(goal.text, REQUEST);
+    assert_eq!(goal.source, GoalSource::User);
+
+    goals.clear("dev");
+    let cleared_at = prompts.lock().unwrap().len();
+    wait_for_prompts(&prompts, cleared_at + 3).await;
+    handle.abort();
+
+    let prompts = prompts.lock().unwrap().clone();
+    let first = prompts.iter().position(|p| p.contains(REQUEST)).unwrap();
+    for (i, prompt) in prompts[first..cleared_at].iter().enumerate() {
+        let section = prompt.split("Active goal").nth(1).unwrap_or_else(|| panic!("prompt {}: {}", i, prompt));
+        assert!(section.contains(REQUEST), "prompt {}: {}", i, prompt);
+    }
+    let last = prompts.last().unwrap();
+    assert!(last.contains("Follow-up 29"));
+    assert!(!last.contains("Active goal"));
+    assert!(!last.contains(REQUEST));
+
+    // 目标记入每轮的追踪
+    let mut recorded = Vec::new();
+    while let Ok(event) = events_rx.try_recv() {
+        if let CompanyEvent::AgentTurnCompleted { goal, .. } = event {
+            recorded.push(goal);
+        }
+    }
+    let set = recorded.iter().position(|goal| goal.as_deref() == Some(REQUEST)).unwrap();
+    assert!(recorded[set..].contains(&None));
+}
+
+#[tokio::test]
+async fn test_agent_endpoint_reports_goal() {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let goals = Arc::new(GoalBoard::new(GoalConfig::default()));
+    goals.set("dev", REQUEST, GoalSource::User, Some("m1".to_string()));
+
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![dev(LLMConfig::openai("k"))], message_tx, store, JwtService::new("test-secret"))
+        .with_goals(goals.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/agents/dev", addr);
+
+    let body: Value = reqwest::get(&url).await.unwrap().json().await.unwrap();
+    assert_eq!(body["data"]["goal"]["text"], REQUEST);
+    assert_eq!(body["data"]["goal"]["source"], "user");
+    assert_eq!(body["data"]["goal"]["message_id"], "m1");
+
+    goals.clear("dev");
+    let body: Value = reqwest::get(&url).await.unwrap().json().await.unwrap();
+    assert!(body["data"]["goal"].is_null());
+}
@@ -0,0 +1,229 @@
+//! 群聊管理测试：管理员权限、移出成员取消实时订阅、禁言到期、持久化和管理接口
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::{GroupModerationError, MessageBus, MessageReceiver, GROUP_EXPIRY_SENDER};
+use imitatort::core::store::{MessageFilter, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::user::user_principal;
+use imitatort::domain::{Agent, LLMConfig, Message, MessageTarget, Organization, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+const START: i64 = 1_700_000_000;
+
+fn members(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn moderation_error(error: anyhow::Error) -> GroupModerationError {
+    error.downcast::<GroupModerationError>().expect("typed moderation error")
+}
+
+fn executor(bus: Arc<MessageBus>, store: Arc<SqliteStore>) -> FrameworkToolExecutor {
+    FrameworkToolExecutor::new(ToolEnvironment::new(
+        bus,
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store,
+    ))
+}
+
+#[tokio::test]
+async fn test_only_admins_can_moderate() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let (_lead, _dev, _ops) = (bus.register("lead"), bus.register("dev"), bus.register("ops"));
+    bus.create_group("team", "Team", "lead", members(&["lead", "dev", "ops"])).await.unwrap();
+    assert_eq!(bus.get_group("team").await.unwrap().admins, vec!["lead"]);
+
+    let err = moderation_error(bus.remove_member("team", "dev", "ops").await.unwrap_err());
+    assert_eq!(err, GroupModerationError::NotAdmin { group_id: "team".into(), actor: "dev".into() });
+    assert!(bus.add_admin("team", "dev", "dev").await.is_err());
+
+    let tools = executor(bus.clone(), store.clone());
+    let result = tools
+        .execute("group.kick", json!({ "group_id": "team", "member_id": "ops" }), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.unwrap(), "Only admins of group team can do this");
+    assert!(bus.get_group("team").await.unwrap().has_member("ops"));
+
+    // 新管理员可以管理，但不能针对创建者
+    bus.add_admin("team", "lead", "dev").await.unwrap();
+    let result = tools
+        .execute("group.kick", json!({ "group_id": "team", "member_id": "ops" }), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let err = moderation_error(bus.mute_member("team", "dev", "lead", START).await.unwrap_err());
+    assert!(matches!(err, GroupModerationError::Creator { .. }));
+    let err = moderation_error(bus.remove_member("team", "lead", "ghost").await.unwrap_err());
+    assert!(matches!(err, GroupModerationError::NotMember { .. }));
+
+    // 管理员和成员变化写入存储
+    let stored = store.load_groups().await.unwrap();
+    assert_eq!(stored[0].admins, vec!["lead", "dev"]);
+    assert_eq!(stored[0].members, vec!["lead", "dev"]);
+}
+
+#[tokio::test]
+async fn test_kick_removes_live_subscription() {
+    let bus = MessageBus::with_store(Arc::new(SqliteStore::new_in_memory().unwrap()));
+    let _lead = bus.register("lead");
+    let mut ops = MessageReceiver::new("ops".into(), bus.register("ops"));
+    bus.create_group("team", "Team", "lead", members(&["lead", "ops"])).await.unwrap();
+    let mut observer = bus.subscribe_group("team").unwrap();
+    ops.join_group("team", &bus).unwrap();
+
+    bus.send(Message::group("lead", "team", "standup in 5")).await.unwrap();
+    assert_eq!(ops.try_recv().unwrap().content, "standup in 5");
+    assert!(ops.try_recv().is_none());
+
+    bus.remove_member("team", "lead", "ops").await.unwrap();
+    bus.send(Message::group("lead", "team", "ops is gone")).await.unwrap();
+
+    let notice = ops.try_recv().unwrap();
+    assert_eq!(notice.from, GROUP_EXPIRY_SENDER);
+    assert_eq!(notice.to, MessageTarget::Direct("ops".into()));
+    assert!(notice.content.contains("lead removed you from Team"), "{}", notice.content);
+    assert!(ops.try_recv().is_none());
+    assert!(ops.joined_groups().is_empty());
+
+    // 其他订阅者不受影响
+    assert_eq!(observer.try_recv().unwrap().content, "standup in 5");
+    assert_eq!(observer.try_recv().unwrap().content, "ops is gone");
+}
+
+#[tokio::test]
+async fn test_mute_expires_automatically() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let clock = Arc::new(ManualClock::new(START));
+    let bus = Arc::new(MessageBus::with_store(store.clone()).with_clock(clock.clone()));
+    let (_lead, _ops) = (bus.register("lead"), bus.register("ops"));
+    bus.create_group("team", "Team", "lead", members(&["lead", "ops"])).await.unwrap();
+
+    let result = executor(bus.clone(), store.clone())
+        .execute(
+            "group.mute",
+            json!({ "group_id": "team", "member_id": "ops", "duration_secs": 60 }),
+            &ToolCallContext::new("lead"),
+        )
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["muted_until"], START + 60);
+
+    let err = bus.send(Message::group("ops", "team", "spam")).await.unwrap_err();
+    assert!(err.to_string().contains("ops is muted in group team"), "{}", err);
+    assert_eq!(
+        moderation_error(err),
+        GroupModerationError::Muted { group_id: "team".into(), member: "ops".into(), until: START + 60 }
+    );
+    // 被拒绝的消息不落库；私聊不受禁言影响
+    assert!(store.load_messages(MessageFilter::new()).await.unwrap().is_empty());
+    bus.send(Message::private("ops", "lead", "sorry")).await.unwrap();
+
+    // 禁言在重启后仍然有效
+    assert_eq!(store.load_groups().await.unwrap()[0].muted_until("ops", START), Some(START + 60));
+
+    clock.advance(Duration::from_secs(60));
+    bus.send(Message::group("ops", "team", "back")).await.unwrap();
+    assert_eq!(store.load_messages(MessageFilter::new().target_type("group")).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_moderation_endpoints() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let (_lead, _ops) = (bus.register("lead"), bus.register("ops"));
+    let (alice, bob) = (user_principal("alice"), user_principal("bob"));
+    bus.create_group("team", "Team", "lead", vec!["lead".into(), "ops".into(), alice.clone(), bob.clone()])
+        .await
+        .unwrap();
+    bus.add_admin("team", "lead", &alice).await.unwrap();
+
+    let jwt_service = JwtService::new("test-secret");
+    let token = |id: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: id.to_string(),
+                username: id.to_string(),
+                name: id.to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: "Employee".to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    };
+    let (message_tx, _) = broadcast::channel(16);
+    let agent = Agent::new("lead", "Lead", Role::simple("Lead", "You lead"), LLMConfig::openai("k"));
+    let state = AppState::new(vec![agent], message_tx, store.clone(), jwt_service.clone()).with_message_bus(bus.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/groups", addr);
+    let client = reqwest::Client::new();
+
+    let response = client.delete(format!("{}/team/members/ops", url)).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client
+        .delete(format!("{}/team/members/ops", url))
+        .bearer_auth(token("bob"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = client
+        .delete(format!("{}/missing/members/ops", url))
+        .bearer_auth(token("alice"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let body: Value = client
+        .post(format!("{}/team/mutes", url))
+        .bearer_auth(token("alice"))
+        .json(&json!({ "member_id": bob, "duration_secs": 300 }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(body["data"]["muted"][&bob].as_i64().is_some(), "{}", body);
+
+    let body: Value = client
+        .delete(format!("{}/team/members/ops", url))
+        .bearer_auth(token("alice"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["members"], json!(["lead", alice, bob]));
+
+    let response = client
+        .post(format!("{}/team/admins", url))
+        .bearer_auth(token("alice"))
+        .json(&json!({ "member_id": "ops" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
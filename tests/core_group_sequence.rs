@@ -0,0 +1,130 @@
+//! 群聊序号测试：并发发送得到连续且不重复的序号、重启后从存储中的最大序号继续、
+//! 接收端发现缺口后从存储补齐，以及上下文按序号排列群聊历史
+
+use std::sync::Arc;
+
+use tokio::sync::mpsc;
+
+use imitatort::core::agent::load_context;
+use imitatort::core::messaging::{GroupSequenceTracker, MessageBus, MessageReceiver, SequenceCheck};
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::domain::Message;
+use imitatort::infrastructure::store::SqliteStore;
+
+async fn group_bus(store: Arc<dyn Store>) -> Arc<MessageBus> {
+    let bus = Arc::new(MessageBus::with_store(store));
+    bus.register("alice");
+    bus.create_group("team", "Team", "alice", vec!["alice".into(), "bob".into()])
+        .await
+        .unwrap();
+    bus
+}
+
+fn sequences(messages: &[Message]) -> Vec<u64> {
+    messages.iter().map(|m| m.group_seq().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_concurrent_sends_get_contiguous_sequences() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let bus = group_bus(store.clone()).await;
+    let mut bob = MessageReceiver::new("bob".to_string(), bus.register("bob")).with_store(store.clone());
+
+    let sends: Vec<_> = (0..20)
+        .map(|i| {
+            let bus = bus.clone();
+            tokio::spawn(async move { bus.send(Message::group("alice", "team", format!("update {}", i))).await })
+        })
+        .collect();
+    for send in sends {
+        send.await.unwrap().unwrap();
+    }
+
+    let mut stored = store
+        .load_messages(MessageFilter::new().to("team").target_type("group"))
+        .await
+        .unwrap();
+    stored.sort_by_key(|m| m.group_seq());
+    assert_eq!(sequences(&stored), (1..=20).collect::<Vec<_>>());
+
+    // 成员信箱按序号顺序收到
+    let mut received = Vec::new();
+    while let Some(message) = bob.try_recv() {
+        received.push(message);
+    }
+    assert_eq!(sequences(&received), (1..=20).collect::<Vec<_>>());
+
+    // 同一秒内发出的消息在上下文中按序号排列，而不是按消息ID
+    let context = load_context(store.as_ref(), "alice", None).await.unwrap();
+    assert_eq!(sequences(&context.history), (1..=20).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_sequence_continues_after_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("company.db");
+
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new(&path).unwrap());
+    let bus = group_bus(store.clone()).await;
+    for i in 0..3 {
+        bus.send(Message::group("alice", "team", format!("before restart {}", i))).await.unwrap();
+    }
+    drop(bus);
+    drop(store);
+
+    let store = Arc::new(SqliteStore::new(&path).unwrap());
+    assert_eq!(store.max_group_seq("team").await.unwrap(), 3);
+    let bus = group_bus(store.clone()).await;
+    bus.send(Message::group("alice", "team", "after restart")).await.unwrap();
+    let latest = store.load_messages_by_group("team", 1).await.unwrap();
+    assert_eq!(latest[0].group_seq(), Some(4));
+
+    // 归档后的消息仍计入最大序号
+    store.archive_messages_before(i64::MAX).await.unwrap();
+    drop(bus);
+    let bus = group_bus(store.clone()).await;
+    bus.send(Message::group("alice", "team", "after archive")).await.unwrap();
+    let filled = store.load_group_messages_by_seq("team", 4, 5).await.unwrap();
+    assert_eq!(sequences(&filled), vec![4, 5]);
+    assert_eq!(filled[1].content, "after archive");
+}
+
+#[tokio::test]
+async fn test_receiver_fills_gap_from_store() {
+    let store = Arc::new(MemoryStore::new());
+    let messages: Vec<Message> = (41..=44)
+        .map(|seq| Message::group("alice", "team", format!("message {}", seq)).with_group_seq(seq))
+        .collect();
+    for message in &messages {
+        store.save_message(message).await.unwrap();
+    }
+
+    let (tx, rx) = mpsc::channel(16);
+    let mut bob = MessageReceiver::new("bob".to_string(), rx).with_store(store);
+    // 42 在投递中丢失，43 先于 44 到达，41 的重复投递被去掉
+    for index in [0, 2, 0, 3] {
+        tx.send(messages[index].clone()).await.unwrap();
+    }
+
+    assert_eq!(bob.recv().await.unwrap().group_seq(), Some(41));
+    // 缺口补齐前不返回后到的消息
+    assert!(bob.try_recv().is_none());
+    assert!(bob.has_gaps());
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        received.push(bob.recv().await.unwrap());
+    }
+    assert_eq!(sequences(&received), vec![42, 43, 44]);
+    assert_eq!(received[0].content, "message 42");
+    assert!(!bob.has_gaps());
+
+    // 迟到的 42 已经处理过，不再返回
+    tx.send(messages[1].clone()).await.unwrap();
+    assert!(bob.try_recv().is_none());
+
+    let mut tracker = GroupSequenceTracker::new();
+    assert_eq!(tracker.observe("team", 7), SequenceCheck::InOrder);
+    assert_eq!(tracker.observe("team", 10), SequenceCheck::Gap { from: 8, to: 9 });
+    assert_eq!(tracker.observe("team", 9), SequenceCheck::Stale);
+    assert_eq!(tracker.last_seen("team"), Some(10));
+}
@@ -0,0 +1,221 @@
+//! 群聊活动统计测试：消息落库时的增量更新与从原始消息重新计算一致、重新计算修复偏差、
+//! 假时钟下滚动窗口过期与被 @ 后的回复延迟，以及 `group.stats` 工具和 `/groups/{id}/stats`
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::events::EventBus;
+use imitatort::core::group_stats::{GroupStatsConfig, GroupStatsTracker};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MessageFilter, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Group, GroupStats, Message, Organization};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+
+const HOUR: i64 = 3600;
+const DAY: i64 = 24 * HOUR;
+/// 对齐到整点的起始时间
+const T0: i64 = 1_700_006_400;
+
+fn members(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn at(message: Message, timestamp: i64) -> Message {
+    Message { timestamp, ..message }
+}
+
+async fn wait_for_seq(tracker: &GroupStatsTracker, group_id: &str, seq: u64) -> GroupStats {
+    for _ in 0..200 {
+        if let Some(stats) = tracker.stats(group_id).await.unwrap() {
+            if stats.last_seq == Some(seq) {
+                return stats;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("stats of {} never reached seq {}", group_id, seq);
+}
+
+#[tokio::test]
+async fn test_incremental_stats_match_recomputation() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let events = Arc::new(EventBus::new());
+    let bus = Arc::new(MessageBus::with_store(store.clone()).with_events(events.clone()));
+    let tracker = Arc::new(GroupStatsTracker::new(GroupStatsConfig::default(), store.clone()));
+    tracker.clone().spawn(&events);
+    let _receivers: Vec<_> = ["lead", "dev", "qa", "analyst"].iter().map(|id| bus.register(id)).collect();
+    bus.create_group("team", "Team", "lead", members(&["lead", "dev", "qa", "analyst"])).await.unwrap();
+    bus.create_group("other", "Other", "lead", members(&["lead", "dev"])).await.unwrap();
+
+    let history = [
+        Message::group("lead", "team", "Kickoff: what do the numbers say?").with_mention("analyst"),
+        Message::group("dev", "team", "Build is green"),
+        Message::group("analyst", "team", "Revenue is up 4%"),
+        Message::group("dev", "team", "@qa can you verify the fix?").with_mention("qa"),
+        Message::group("lead", "team", "Good"),
+        Message::group("dev", "team", "Deploying now"),
+        Message::group("qa", "team", "Verified"),
+        Message::group("lead", "team", "@dev @analyst sync tomorrow").with_mentions(vec!["dev", "analyst"]),
+    ];
+    for message in history {
+        bus.send(message).await.unwrap();
+    }
+    bus.send(Message::group("dev", "other", "Not part of team")).await.unwrap();
+    bus.send(Message::private("lead", "dev", "Direct messages are not counted")).await.unwrap();
+
+    let incremental = wait_for_seq(&tracker, "team", 8).await;
+    let count = |id: &str| incremental.members[id].total_messages;
+    assert_eq!((count("lead"), count("dev"), count("qa"), count("analyst")), (3, 3, 1, 1));
+    assert_eq!(incremental.members["analyst"].mention_replies, 1);
+    assert_eq!(incremental.members["qa"].mention_replies, 1);
+    // dev 和 analyst 还没有回复最后一次 @
+    assert_eq!(incremental.pending_mentions.keys().collect::<Vec<_>>(), vec!["analyst", "dev"]);
+
+    let recomputed = tracker.recompute("team").await.unwrap();
+    assert_eq!(recomputed, incremental);
+
+    // 增量状态出现偏差时重新计算修复
+    store.save_group_stats(&GroupStats::new("team")).await.unwrap();
+    assert_eq!(tracker.recompute("team").await.unwrap(), incremental);
+
+    // 同一条消息再次落库不会重复计入
+    let persisted = store
+        .load_messages(MessageFilter::new().to("team".to_string()).target_type("group").oldest_first())
+        .await
+        .unwrap();
+    assert!(tracker.record_message(&persisted[0]).await.unwrap().is_none());
+    assert_eq!(tracker.stats("team").await.unwrap().unwrap(), incremental);
+}
+
+#[tokio::test]
+async fn test_rolling_windows_expire_with_fake_clock() {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    store
+        .save_group(&Group::new("team", "Team", "lead", members(&["lead", "dev", "qa", "analyst"])))
+        .await
+        .unwrap();
+    let clock = Arc::new(ManualClock::new(T0));
+    let tracker = GroupStatsTracker::new(GroupStatsConfig::default(), store.clone()).with_clock(clock.clone());
+
+    let history = [
+        at(Message::group("lead", "team", "@analyst numbers?").with_mention("analyst"), T0),
+        at(Message::group("analyst", "team", "Up 4%"), T0 + 600),
+        at(Message::group("dev", "team", "Shipped"), T0 + 1200),
+        at(Message::group("lead", "team", "@analyst and now?").with_mention("analyst"), T0 + 2 * DAY),
+        at(Message::group("analyst", "team", "Still up"), T0 + 2 * DAY + 1800),
+    ];
+    for message in &history {
+        tracker.record_message(message).await.unwrap();
+    }
+
+    clock.set(T0 + 2 * DAY + HOUR);
+    let report = tracker.report("team").await.unwrap();
+    let windows = |id: &str| {
+        let member = report.members.iter().find(|m| m.member_id == id).unwrap();
+        member.windows.iter().map(|w| w.messages).collect::<Vec<_>>()
+    };
+    assert_eq!(windows("lead"), vec![1, 2]);
+    assert_eq!(windows("analyst"), vec![1, 2]);
+    assert_eq!(windows("dev"), vec![0, 1]);
+    assert_eq!(report.members[0].windows[0].share, 0.5);
+    assert_eq!(report.quiet, vec!["qa"]);
+    let analyst = report.members.iter().find(|m| m.member_id == "analyst").unwrap();
+    assert_eq!(analyst.mention_replies, 2);
+    assert_eq!(analyst.average_response_secs, Some(1200));
+    assert_eq!(analyst.last_active_at, Some(T0 + 2 * DAY + 1800));
+
+    // 一天后最短的窗口过期，一周后所有窗口都过期，累计数和最后发言时间保留
+    clock.advance(Duration::from_secs(DAY as u64));
+    let report = tracker.report("team").await.unwrap();
+    assert!(report.members.iter().all(|m| m.windows[0].messages == 0 && m.windows[0].share == 0.0));
+    assert_eq!(report.quiet, vec!["qa"]);
+    clock.advance(Duration::from_secs(7 * DAY as u64));
+    let report = tracker.report("team").await.unwrap();
+    assert_eq!(report.quiet, vec!["analyst", "dev", "lead", "qa"]);
+    let lead = report.members.iter().find(|m| m.member_id == "lead").unwrap();
+    assert_eq!((lead.total_messages, lead.last_active_at), (2, Some(T0 + 2 * DAY)));
+
+    // 新消息落库时丢弃保留时长之外的分桶
+    tracker.record_message(&at(Message::group("qa", "team", "Hello?"), T0 + 10 * DAY)).await.unwrap();
+    let stats = tracker.stats("team").await.unwrap().unwrap();
+    assert!(stats.members["analyst"].buckets.is_empty());
+    assert_eq!(stats.members["qa"].buckets.len(), 1);
+}
+
+fn token(jwt_service: &JwtService, id: &str) -> String {
+    jwt_service
+        .generate_token(&UserInfo {
+            id: id.to_string(),
+            username: id.to_string(),
+            name: id.to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: "Employee".to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_stats_tool_and_endpoint_are_limited_to_members() {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _receivers: Vec<_> = ["lead", "dev", "outsider"].iter().map(|id| bus.register(id)).collect();
+    bus.create_group("team", "Team", "lead", members(&["lead", "dev", "user:alice"])).await.unwrap();
+    store.save_group(&bus.get_group("team").await.unwrap()).await.unwrap();
+    let tracker = Arc::new(GroupStatsTracker::new(GroupStatsConfig::default(), store.clone()));
+    tracker.record_message(&Message::group("lead", "team", "Hi").with_group_seq(1)).await.unwrap();
+
+    let tools = FrameworkToolExecutor::new(
+        ToolEnvironment::new(
+            bus.clone(),
+            Arc::new(RwLock::new(Organization::new())),
+            Arc::new(ToolRegistry::new()),
+            store.clone(),
+        )
+        .with_group_stats(tracker.clone()),
+    );
+    let denied = tools
+        .execute("group.stats", json!({ "group_id": "team" }), &ToolCallContext::new("outsider"))
+        .await
+        .unwrap();
+    assert_eq!(denied.error.unwrap(), "You are not a member of group team");
+    let result = tools
+        .execute("group.stats", json!({ "group_id": "team" }), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["members"][0]["member_id"], "lead");
+    assert_eq!(result.data["quiet"], json!(["dev", "user:alice"]));
+
+    let jwt_service = JwtService::new("test-secret");
+    let (alice, bob) = (token(&jwt_service, "alice"), token(&jwt_service, "bob"));
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store, jwt_service).with_group_stats(tracker);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/groups/team/stats", addr);
+    let client = reqwest::Client::new();
+
+    assert_eq!(client.get(&url).send().await.unwrap().status(), 401);
+    assert_eq!(client.get(&url).bearer_auth(&bob).send().await.unwrap().status(), 403);
+    let response = client.get(&url).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["members"][0]["total_messages"], 1);
+    let recompute = format!("http://{}/api/v1/admin/groups/team/stats/recompute", addr);
+    assert_eq!(client.post(&recompute).bearer_auth(&alice).send().await.unwrap().status(), 403);
+}
@@ -0,0 +1,240 @@
+//! 群成员与部门同步测试：部门调动触发成员互换、保留手动成员、严格模式、被移出成员的冲突报告和重新同步接口
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::group_sync::{GroupSyncError, GroupSyncReconciler};
+use imitatort::core::messaging::{MessageBus, GROUP_EXPIRY_SENDER};
+use imitatort::core::org_changes::save_organization_tracked;
+use imitatort::core::store::{MessageFilter, Store};
+use imitatort::domain::user::user_principal;
+use imitatort::domain::{Agent, Department, GroupSync, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::Value;
+use tokio::sync::{broadcast, mpsc};
+
+fn agent(id: &str, dept: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Employee", "You work here"), LLMConfig::openai("test-key")).with_department(dept)
+}
+
+fn members(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+/// eng 下有 backend；lead、dev 在 eng，api 在 backend，ops 在 support
+fn create_org() -> Organization {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering").with_leader("lead"));
+    org.add_department(Department::child("backend", "Backend", "eng"));
+    org.add_department(Department::top_level("support", "Support"));
+    org.add_agent(agent("lead", "eng"));
+    org.add_agent(agent("dev", "eng"));
+    org.add_agent(agent("api", "backend"));
+    org.add_agent(agent("ops", "support"));
+    org
+}
+
+struct Fixture {
+    store: Arc<SqliteStore>,
+    bus: Arc<MessageBus>,
+    reconciler: Arc<GroupSyncReconciler>,
+    _receivers: Vec<mpsc::Receiver<Message>>,
+}
+
+/// `eng-room` 由 lead 创建并跟随 eng 部门，`extra` 是手动加入的成员
+async fn setup(sync: GroupSync, extra: &[&str]) -> Fixture {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    store.save_organization(&create_org()).await.unwrap();
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let receivers = ["lead", "dev", "api", "ops", "guest"].iter().map(|id| bus.register(id)).collect();
+    let mut initial = vec!["lead"];
+    initial.extend_from_slice(extra);
+    bus.create_group("eng-room", "Eng Room", "lead", members(&initial)).await.unwrap();
+    bus.set_group_sync("eng-room", "lead", Some(sync)).await.unwrap();
+    let reconciler = Arc::new(GroupSyncReconciler::new(store.clone(), bus.clone()));
+    Fixture { store, bus, reconciler, _receivers: receivers }
+}
+
+async fn group_members(bus: &MessageBus) -> Vec<String> {
+    bus.get_group("eng-room").await.unwrap().members
+}
+
+async fn notices(store: &SqliteStore) -> Vec<String> {
+    let filter = MessageFilter::new().to("eng-room").target_type("group").oldest_first();
+    store
+        .load_messages(filter)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|m| m.from == GROUP_EXPIRY_SENDER)
+        .map(|m| m.content)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_department_move_swaps_membership() {
+    let fixture = setup(GroupSync::department("eng"), &[]).await;
+    let report = fixture.reconciler.reconcile("eng-room").await.unwrap();
+    assert_eq!(report.joined, members(&["dev"]));
+    assert!(report.left.is_empty());
+    assert_eq!(group_members(&fixture.bus).await, members(&["lead", "dev"]));
+
+    // 组织架构变更事件触发同步：dev 调到 support，ops 调到 eng
+    let events = EventBus::new();
+    fixture.reconciler.clone().spawn(&events);
+    let mut org = create_org();
+    org.move_agent("dev", "support").unwrap();
+    org.move_agent("ops", "eng").unwrap();
+    let entry = save_organization_tracked(fixture.store.as_ref(), &org, "admin").await.unwrap().unwrap();
+    events.emit(CompanyEvent::OrgChanged { entry: Arc::new(entry) });
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while group_members(&fixture.bus).await != members(&["lead", "ops"]) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("membership follows the department");
+
+    let posted = notices(&fixture.store).await;
+    assert_eq!(posted.len(), 3, "{:?}", posted);
+    assert!(posted[1].contains("ops joined Eng Room with department Engineering"), "{}", posted[1]);
+    assert!(posted[2].contains("dev left Eng Room"), "{}", posted[2]);
+
+    // 同步设置与成员一起持久化
+    let stored = fixture.store.load_groups().await.unwrap().into_iter().find(|g| g.id == "eng-room").unwrap();
+    assert_eq!(stored.members, members(&["lead", "ops"]));
+    assert_eq!(stored.sync.unwrap().synced_members, members(&["lead", "ops"]));
+
+    // 没有变化时不再发通知
+    let report = fixture.reconciler.reconcile("eng-room").await.unwrap();
+    assert!(!report.changed());
+    assert_eq!(notices(&fixture.store).await.len(), 3);
+}
+
+#[tokio::test]
+async fn test_extra_members_are_preserved_unless_strict() {
+    let fixture = setup(GroupSync::department("eng").with_sub_departments(), &["guest"]).await;
+    let report = fixture.reconciler.reconcile("eng-room").await.unwrap();
+    assert_eq!(report.joined, members(&["dev", "api"]));
+    assert_eq!(group_members(&fixture.bus).await, members(&["lead", "guest", "dev", "api"]));
+
+    // 整个部门调走：同步加入的成员离开，手动加入的 guest 和创建者留下
+    let mut org = create_org();
+    for id in ["lead", "dev", "api"] {
+        org.move_agent(id, "support").unwrap();
+    }
+    fixture.store.save_organization(&org).await.unwrap();
+    let report = fixture.reconciler.reconcile("eng-room").await.unwrap();
+    assert_eq!(report.left, members(&["dev", "api"]));
+    assert_eq!(group_members(&fixture.bus).await, members(&["lead", "guest"]));
+
+    // 严格模式下手动成员也被移出，创建者始终保留
+    let fixture = setup(GroupSync::department("backend").strict(), &["guest", "dev"]).await;
+    let report = fixture.reconciler.reconcile("eng-room").await.unwrap();
+    assert_eq!(report.joined, members(&["api"]));
+    assert_eq!(report.left, members(&["guest", "dev"]));
+    assert_eq!(group_members(&fixture.bus).await, members(&["lead", "api"]));
+}
+
+#[tokio::test]
+async fn test_kicked_member_is_reported_not_readded() {
+    let fixture = setup(GroupSync::department("eng"), &[]).await;
+    fixture.reconciler.reconcile("eng-room").await.unwrap();
+    fixture.bus.remove_member("eng-room", "lead", "dev").await.unwrap();
+
+    let report = fixture.reconciler.reconcile("eng-room").await.unwrap();
+    assert!(!report.changed());
+    assert_eq!(report.kicked, members(&["dev"]));
+    assert_eq!(group_members(&fixture.bus).await, members(&["lead"]));
+    let posted = notices(&fixture.store).await;
+    assert!(posted.last().unwrap().contains("dev are in department Engineering but were removed"), "{:?}", posted);
+
+    // 管理员手动加回后恢复同步
+    let mut group = fixture.bus.get_group("eng-room").await.unwrap();
+    group.add_member("dev");
+    assert!(group.kicked.is_empty());
+    let report = group.apply_sync(&members(&["lead", "dev"]));
+    assert!(report.kicked.is_empty());
+
+    // 禁言中的成员调离部门后禁言记录保留
+    let now = chrono::Utc::now().timestamp();
+    group.mute("dev", now + 600, now);
+    group.apply_sync(&members(&["lead"]));
+    assert!(!group.has_member("dev"));
+    group.apply_sync(&members(&["lead", "dev"]));
+    assert_eq!(group.muted_until("dev", now), Some(now + 600));
+
+    let err = GroupSyncReconciler::new(fixture.store.clone(), fixture.bus.clone())
+        .reconcile("missing")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Group not found"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_resync_endpoint() {
+    let fixture = setup(GroupSync::department("eng"), &[]).await;
+    let alice = user_principal("alice");
+    fixture.bus.register_user(&alice);
+    for group in ["plain", "synced"] {
+        fixture.bus.create_group(group, group, "lead", members(&["lead", &alice])).await.unwrap();
+        fixture.bus.add_admin(group, "lead", &alice).await.unwrap();
+    }
+    fixture.bus.set_group_sync("synced", &alice, Some(GroupSync::department("eng"))).await.unwrap();
+
+    let jwt_service = JwtService::new("test-secret");
+    let token = |id: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: id.to_string(),
+                username: id.to_string(),
+                name: id.to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: "Employee".to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    };
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![agent("lead", "eng")], message_tx, fixture.store.clone(), jwt_service.clone())
+        .with_message_bus(fixture.bus.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/groups", addr);
+    let client = reqwest::Client::new();
+
+    let response = client.post(format!("{}/eng-room/resync", url)).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client.post(format!("{}/eng-room/resync", url)).bearer_auth(token("alice")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    let response = client.post(format!("{}/missing/resync", url)).bearer_auth(token("alice")).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+    let response = client.post(format!("{}/plain/resync", url)).bearer_auth(token("alice")).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+
+    let err = fixture.reconciler.reconcile("plain").await.unwrap_err();
+    assert!(matches!(err.downcast_ref::<GroupSyncError>(), Some(GroupSyncError::NotSynced { .. })));
+
+    // alice 不在部门中，但作为手动成员保留
+    let body: Value = client
+        .post(format!("{}/synced/resync", url))
+        .bearer_auth(token("alice"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["joined"], serde_json::json!(["dev"]), "{}", body);
+    assert_eq!(fixture.bus.get_group("synced").await.unwrap().members, members(&["lead", &alice, "dev"]));
+}
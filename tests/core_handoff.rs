@@ -0,0 +1,219 @@
+//! Agent 转交测试：按技能两跳转交、转交链元数据、转交次数上限和选择策略
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use imitatort::core::agent::{AgentRuntime, Context, Decision};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::handoff::{
+    handoff_chain, HandoffConfig, HandoffPolicy, HANDOFF_CHAIN_KEY, HANDOFF_ORIGIN_FROM_KEY, HANDOFF_ORIGIN_ID_KEY,
+};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::tool_provider::FrameworkToolProvider;
+use imitatort::domain::tool::{ToolCallContext, ToolProvider};
+use imitatort::domain::{Agent, Department, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment, ToolResult};
+
+const CUSTOMER: &str = "user:alice";
+
+fn agent(id: &str, department: &str, skills: &[&str]) -> Agent {
+    Agent::new(id, id, Role::simple(id, "You help customers"), LLMConfig::openai("k"))
+        .with_department(department)
+        .with_skills(skills.iter().map(|s| s.to_string()).collect())
+}
+
+struct Company {
+    store: Arc<MemoryStore>,
+    bus: Arc<MessageBus>,
+    clock: Arc<ManualClock>,
+    executor: FrameworkToolExecutor,
+    _receivers: Vec<tokio::sync::mpsc::Receiver<Message>>,
+}
+
+/// 前台 sales、财务部 billing（负责人 cfo）、法务部 legal
+fn company(config: Option<HandoffConfig>) -> Company {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("front", "Front Desk"));
+    org.add_department(Department::top_level("finance", "Finance").with_leader("cfo"));
+    org.add_department(Department::top_level("legal", "Legal"));
+    org.add_agent(agent("sales", "front", &[]));
+    org.add_agent(agent("billing", "finance", &["invoicing"]));
+    org.add_agent(agent("cfo", "finance", &["budgeting"]));
+    org.add_agent(agent("legal", "legal", &["contracts"]));
+
+    let store = Arc::new(MemoryStore::new());
+    let clock = Arc::new(ManualClock::new(1_700_000_000));
+    let bus = Arc::new(MessageBus::with_store(store.clone()).with_clock(clock.clone()));
+    let receivers = ["sales", "billing", "cfo", "legal"].into_iter().map(|id| bus.register(id)).collect();
+    let env = ToolEnvironment::new(bus.clone(), Arc::new(RwLock::new(org)), Arc::new(ToolRegistry::new()), store.clone());
+    let env = match config {
+        Some(config) => env.with_handoff(config),
+        None => env,
+    };
+    Company { store, bus, clock, executor: FrameworkToolExecutor::new(env), _receivers: receivers }
+}
+
+impl Company {
+    /// 最近一条发给 `to` 的私聊
+    async fn latest_to(&self, to: &str) -> Message {
+        self.store.load_messages(MessageFilter::new().to(to).limit(1)).await.unwrap().remove(0)
+    }
+}
+
+/// 按顺序返回预设的 `handoff` 工具调用参数的 LLM
+async fn spawn_scripted_llm(script: Vec<Value>) -> String {
+    async fn completions(State(script): State<Arc<Mutex<VecDeque<Value>>>>) -> Json<Value> {
+        let arguments = script.lock().unwrap().pop_front().expect("unexpected LLM call");
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "handoff", "arguments": arguments.to_string()}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }))
+    }
+
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state(Arc::new(Mutex::new(VecDeque::from(script))));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// 让 Agent 处理收到的消息，执行 LLM 选择的工具调用
+async fn run_turn(company: &Company, base_url: &str, agent_id: &str, message: Message) -> ToolResult {
+    let agent = Agent::new(agent_id, agent_id, Role::simple(agent_id, "You help"), LLMConfig::openai("k").with_base_url(base_url));
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+    let tools = vec![FrameworkToolProvider::create_handoff()];
+    let decision = runtime
+        .think_with_tools(Context::default().with_messages(vec![message]), &tools)
+        .await
+        .unwrap();
+    let Decision::CallTool { tool_id, arguments } = decision else {
+        panic!("unexpected decision: {:?}", decision);
+    };
+    company.executor.execute(&tool_id, arguments, &ToolCallContext::new(agent_id)).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_two_hop_handoff_records_chain_and_stops_at_cap() {
+    let company = company(Some(HandoffConfig { max_hops: 2, ..Default::default() }));
+    let base_url = spawn_scripted_llm(vec![
+        json!({"reason": "billing question", "skill": "invoicing"}),
+        json!({"reason": "it is a contract dispute", "skill": "contracts"}),
+        json!({"reason": "needs sales again", "department": "front"}),
+    ])
+    .await;
+
+    let original = Message::private(CUSTOMER, "sales", "Why was I charged twice under my contract?");
+    company.bus.send(original.clone()).await.unwrap();
+
+    // 第一跳：sales -> billing
+    let result = run_turn(&company, &base_url, "sales", original.clone()).await;
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["handed_to"], "billing");
+    let first = company.latest_to("billing").await;
+    assert_eq!(first.from, "sales");
+    assert!(first.content.contains("billing question"));
+    assert!(first.content.contains(&original.content));
+    assert_eq!(first.metadata(HANDOFF_ORIGIN_ID_KEY), Some(original.id.as_str()));
+    assert_eq!(first.metadata(HANDOFF_ORIGIN_FROM_KEY), Some(CUSTOMER));
+    let notice = company.latest_to(CUSTOMER).await;
+    assert!(notice.content.contains("billing"));
+
+    // 第二跳：billing -> legal，转交链累加，通知仍发给原发送者
+    company.clock.advance(Duration::from_secs(60));
+    let result = run_turn(&company, &base_url, "billing", first).await;
+    assert_eq!(result.data["handed_to"], "legal", "{:?}", result.error);
+    assert_eq!(result.data["hops"], 2);
+    let second = company.latest_to("legal").await;
+    let chain = handoff_chain(&second);
+    let hops: Vec<(&str, &str)> = chain.iter().map(|h| (h.from.as_str(), h.to.as_str())).collect();
+    assert_eq!(hops, vec![("sales", "billing"), ("billing", "legal")]);
+    assert_eq!(chain[1].reason, "it is a contract dispute");
+    assert_eq!(second.metadata(HANDOFF_ORIGIN_FROM_KEY), Some(CUSTOMER));
+    assert!(second.content.contains(&original.content));
+    let notice = company.latest_to(CUSTOMER).await;
+    assert!(notice.content.contains("legal"));
+    assert!(second.metadata(HANDOFF_CHAIN_KEY).is_some());
+
+    // 第三跳超过上限，不再转发
+    let result = run_turn(&company, &base_url, "legal", second.clone()).await;
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("limit 2"));
+    let to_sales = company.store.load_messages(MessageFilter::new().to("sales")).await.unwrap();
+    assert_eq!(to_sales.len(), 1);
+    assert_eq!(company.latest_to("legal").await.id, second.id);
+}
+
+#[tokio::test]
+async fn test_selection_policies() {
+    let context = ToolCallContext::new("sales");
+
+    // 部门负责人策略转给财务部负责人，而不是具备技能的成员
+    let leader = company(Some(HandoffConfig { policy: HandoffPolicy::DepartmentLeader, ..Default::default() }));
+    leader.bus.send(Message::private(CUSTOMER, "sales", "Refund please")).await.unwrap();
+    let result = leader
+        .executor
+        .execute("handoff", json!({"reason": "refunds", "skill": "invoicing"}), &context)
+        .await
+        .unwrap();
+    assert_eq!(result.data["handed_to"], "cfo");
+
+    // 询问用户策略不转发，把候选人列给原发送者
+    let ask = company(Some(HandoffConfig { policy: HandoffPolicy::AskUser, ..Default::default() }));
+    ask.bus.send(Message::private(CUSTOMER, "sales", "Refund please")).await.unwrap();
+    let result = ask
+        .executor
+        .execute("handoff", json!({"reason": "refunds", "department": "Finance"}), &context)
+        .await
+        .unwrap();
+    assert_eq!(result.data["candidates"], json!(["billing", "cfo"]));
+    assert!(ask.store.load_messages(MessageFilter::new().to("billing")).await.unwrap().is_empty());
+    assert!(ask.latest_to(CUSTOMER).await.content.contains("billing (billing), cfo (cfo)"));
+
+    // 没有匹配的 Agent、缺少条件时返回错误
+    let result = ask.executor.execute("handoff", json!({"reason": "x", "skill": "juggling"}), &context).await.unwrap();
+    assert!(!result.success);
+    let result = ask.executor.execute("handoff", json!({"reason": "x"}), &context).await.unwrap();
+    assert!(!result.success);
+}
+
+#[tokio::test]
+async fn test_handoff_tool_is_optional() {
+    assert!(!FrameworkToolProvider::new().list_tools().iter().any(|t| t.id == "handoff"));
+    assert!(FrameworkToolProvider::new().with_handoff().list_tools().iter().any(|t| t.id == "handoff"));
+
+    let disabled = company(None);
+    disabled.bus.send(Message::private(CUSTOMER, "sales", "Hello")).await.unwrap();
+    let result = disabled
+        .executor
+        .execute("handoff", json!({"reason": "x", "skill": "invoicing"}), &ToolCallContext::new("sales"))
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert!(disabled.store.load_messages(MessageFilter::new().to("billing")).await.unwrap().is_empty());
+}
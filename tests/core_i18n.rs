@@ -0,0 +1,89 @@
+//! 消息目录（i18n）测试
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use imitatort::application::framework::VirtualCompany;
+use imitatort::core::config::CompanyConfig;
+use imitatort::core::i18n::{Language, MessageCatalog};
+use imitatort::core::store::MemoryStore;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::Organization;
+
+#[test]
+fn test_catalogs_have_same_keys() {
+    let en: BTreeSet<_> = MessageCatalog::keys(Language::En).into_iter().collect();
+    let zh: BTreeSet<_> = MessageCatalog::keys(Language::Zh).into_iter().collect();
+
+    let missing_in_zh: Vec<_> = en.difference(&zh).collect();
+    let missing_in_en: Vec<_> = zh.difference(&en).collect();
+    assert!(missing_in_zh.is_empty(), "zh catalog is missing keys: {:?}", missing_in_zh);
+    assert!(missing_in_en.is_empty(), "en catalog is missing keys: {:?}", missing_in_en);
+
+    // 键不应重复
+    assert_eq!(en.len(), MessageCatalog::keys(Language::En).len());
+    assert_eq!(zh.len(), MessageCatalog::keys(Language::Zh).len());
+}
+
+#[test]
+fn test_format_and_fallback() {
+    let en = MessageCatalog::new(Language::En);
+    let zh = MessageCatalog::new(Language::Zh);
+
+    assert_eq!(en.format("tool.unknown", &[("tool_id", "x.y")]), "Unknown tool: x.y");
+    assert_eq!(zh.format("tool.unknown", &[("tool_id", "x.y")]), "未知工具: x.y");
+
+    // 未知键返回键本身
+    assert_eq!(zh.get("no.such.key"), "no.such.key");
+}
+
+#[test]
+fn test_language_parsing() {
+    assert_eq!("zh-CN".parse::<Language>().unwrap(), Language::Zh);
+    assert_eq!("en_US".parse::<Language>().unwrap(), Language::En);
+    assert!("fr".parse::<Language>().is_err());
+}
+
+#[tokio::test]
+async fn test_company_language_switches_tool_error() {
+    let context = ToolCallContext::new("agent-1");
+
+    let en_company = VirtualCompany::with_store(
+        CompanyConfig::new("Test", Organization::new()),
+        Arc::new(MemoryStore::new()),
+    );
+    let result = en_company
+        .get_framework_tool_executor()
+        .execute("no.such_tool", serde_json::json!({}), &context)
+        .await
+        .unwrap();
+    assert_eq!(result.error.as_deref(), Some("Unknown tool: no.such_tool"));
+
+    let zh_company = VirtualCompany::with_store(
+        CompanyConfig::new("Test", Organization::new()).with_language(Language::Zh),
+        Arc::new(MemoryStore::new()),
+    );
+    let result = zh_company
+        .get_framework_tool_executor()
+        .execute("no.such_tool", serde_json::json!({}), &context)
+        .await
+        .unwrap();
+    assert_eq!(result.error.as_deref(), Some("未知工具: no.such_tool"));
+}
+
+#[test]
+fn test_company_config_language_from_yaml() {
+    let yaml = r#"
+name: Test
+organization:
+  departments: []
+  agents: []
+language: zh
+agent_languages:
+  ceo: en
+"#;
+    let config: CompanyConfig = serde_yaml::from_str(yaml).unwrap();
+    assert_eq!(config.language, Language::Zh);
+    assert_eq!(config.language_for("ceo"), Language::En);
+    assert_eq!(config.language_for("cto"), Language::Zh);
+}
@@ -0,0 +1,238 @@
+//! 存储完整性检查测试：逐类植入损坏数据，验证检测、修复、启动策略和管理接口
+
+use std::sync::Arc;
+
+use imitatort::core::config::CompanyConfig;
+use imitatort::core::integrity::{IntegrityConfig, IntegrityFinding, IntegrityMode, IssueKind, Repair, Severity, StoreIntegrityChecker};
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::domain::user::{user_principal, User};
+use imitatort::domain::{Agent, Department, Group, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::VirtualCompany;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+fn agent(id: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Engineer", "You code"), LLMConfig::openai("k"))
+}
+
+/// dev 所属部门被删除，rnd 的负责人和上级部门都已不存在，群聊里有已删除的 Agent
+async fn seed_dangling(store: &dyn Store) {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering").with_leader("ceo"));
+    org.add_department(Department::child("rnd", "Research", "lab").with_leader("ghost"));
+    org.add_agent(agent("ceo").with_department("eng"));
+    org.add_agent(agent("dev").with_department("sales"));
+    store.save_organization(&org).await.unwrap();
+    store
+        .save_group(&Group::new("g1", "Team", "ceo", vec!["ceo".into(), "dev".into(), "intern".into()]))
+        .await
+        .unwrap();
+}
+
+fn find<'a>(findings: &'a [IntegrityFinding], table: &str, row_id: &str, column: &str) -> &'a IntegrityFinding {
+    findings
+        .iter()
+        .find(|f| f.table == table && f.row_id == row_id && f.column.as_deref() == Some(column))
+        .unwrap_or_else(|| panic!("no finding for {}.{} of {}: {:?}", table, column, row_id, findings))
+}
+
+#[tokio::test]
+async fn test_dangling_references_and_orphaned_members() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    seed_dangling(store.as_ref()).await;
+    let checker = StoreIntegrityChecker::new(store.clone());
+
+    let report = checker.check().await.unwrap();
+    assert_eq!(report.findings.len(), 4, "{:?}", report.findings);
+    let dev = find(&report.findings, "agents", "dev", "department_id");
+    assert_eq!((dev.severity, dev.kind), (Severity::Critical, IssueKind::DanglingReference));
+    assert_eq!(find(&report.findings, "departments", "rnd", "leader_id").value.as_deref(), Some("ghost"));
+    assert_eq!(find(&report.findings, "departments", "rnd", "parent_id").value.as_deref(), Some("lab"));
+    let intern = find(&report.findings, "groups", "g1", "members");
+    assert_eq!((intern.kind, intern.repair), (IssueKind::OrphanedGroupMember, Some(Repair::DropMember)));
+    assert!(report.has_critical());
+    // 只检查时不修改数据
+    assert_eq!(store.load_groups().await.unwrap()[0].members.len(), 3);
+
+    let report = checker.repair("admin").await.unwrap();
+    assert_eq!(report.repaired(), 4);
+    assert!(!report.has_critical());
+    let org = store.load_organization().await.unwrap();
+    assert_eq!(org.find_agent("dev").unwrap().department_id, None);
+    let rnd = org.find_department("rnd").unwrap();
+    assert_eq!((rnd.leader_id.as_deref(), rnd.parent_id.as_deref()), (None, None));
+    assert_eq!(org.find_department("eng").unwrap().leader_id.as_deref(), Some("ceo"));
+    assert_eq!(store.load_groups().await.unwrap()[0].members, vec!["ceo", "dev"]);
+
+    // 组织架构的修复记入变更记录
+    let changes = store.load_org_changes(0, 10).await.unwrap();
+    assert_eq!(changes.last().unwrap().actor, "admin");
+    assert!(checker.check().await.unwrap().is_clean());
+}
+
+#[tokio::test]
+async fn test_user_members_checked_against_users() {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_agent(agent("dev"));
+    store.save_organization(&org).await.unwrap();
+    let alice = User::new_chairman("alice".into(), "Alice".into(), "hash".into(), None);
+    store.save_user(&alice).await.unwrap();
+    store
+        .save_group(&Group::new(
+            "g1",
+            "Team",
+            "dev",
+            vec!["dev".into(), user_principal(&alice.id), user_principal("deleted-user")],
+        ))
+        .await
+        .unwrap();
+
+    let report = StoreIntegrityChecker::new(store).check().await.unwrap();
+    assert_eq!(report.findings.len(), 1, "{:?}", report.findings);
+    assert_eq!(report.findings[0].value, Some(user_principal("deleted-user")));
+}
+
+#[tokio::test]
+async fn test_invalid_rows_detected_and_quarantined() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("company.db");
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new(&path).unwrap());
+    store.save_message(&Message::private("alice", "dev", "hello")).await.unwrap();
+    store.save_message(&Message::private("alice", "dev", "odd priority")).await.unwrap();
+    store.save_group(&Group::new("g1", "Team", "dev", vec![])).await.unwrap();
+    let bob = User::new_employee("bob".into(), "Bob".into(), "hash".into(), 7, "eng".into(), None);
+    let carol = User::new_employee("carol".into(), "Carol".into(), "hash".into(), 7, "eng".into(), None);
+
+    // 旧版本的用户表没有唯一约束，另有手工改过的行
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "DROP TABLE users;
+             CREATE TABLE users (
+                id TEXT PRIMARY KEY, username TEXT NOT NULL, name TEXT NOT NULL, email TEXT,
+                password_hash TEXT NOT NULL, employee_id TEXT NOT NULL,
+                position TEXT NOT NULL DEFAULT 'Employee', department TEXT NOT NULL DEFAULT '',
+                created_at INTEGER NOT NULL
+             );
+             UPDATE messages SET target_type = 'channel' WHERE content = 'hello';
+             UPDATE messages SET priority = 'asap' WHERE content = 'odd priority';
+             UPDATE groups SET members = 'dev,ops' WHERE id = 'g1';",
+        )
+        .unwrap();
+    }
+    store.save_user(&bob).await.unwrap();
+    store.save_user(&carol).await.unwrap();
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute("UPDATE users SET position = 'Intern' WHERE username = 'carol'", []).unwrap();
+    }
+
+    let checker = StoreIntegrityChecker::new(store.clone());
+    let report = checker.check().await.unwrap();
+    let kinds = |kind: IssueKind| report.findings.iter().filter(|f| f.kind == kind).count();
+    assert_eq!(kinds(IssueKind::DuplicateEmployeeId), 2, "{:?}", report.findings);
+    assert_eq!(kinds(IssueKind::UnparseableRow), 1);
+    assert_eq!(kinds(IssueKind::InvalidEnumValue), 3);
+    let target_type = report.findings.iter().find(|f| f.column.as_deref() == Some("target_type")).unwrap();
+    assert_eq!((target_type.value.as_deref(), target_type.repair), (Some("channel"), Some(Repair::Quarantine)));
+    assert_eq!(find(&report.findings, "users", "carol", "position").severity, Severity::Warning);
+
+    let report = checker.repair("admin").await.unwrap();
+    let quarantined = store.load_quarantined_rows().await.unwrap();
+    assert_eq!(quarantined.len(), 2, "{:?}", quarantined);
+    assert_eq!(quarantined[0].table, "messages");
+    assert_eq!(quarantined[0].data["content"], "hello");
+    assert_eq!(quarantined[1].table, "groups");
+    assert_eq!(quarantined[1].data["members"], "dev,ops");
+    assert!(store.load_groups().await.unwrap().is_empty());
+    let messages = store.load_messages(MessageFilter::new()).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].content, "odd priority");
+
+    // 重复工号需要人工处理
+    assert!(report.has_critical());
+    let remaining = checker.check().await.unwrap();
+    assert!(remaining.findings.iter().all(|f| f.repair.is_none()), "{:?}", remaining.findings);
+}
+
+#[tokio::test]
+async fn test_startup_warns_or_refuses() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    seed_dangling(store.as_ref()).await;
+    let checker = StoreIntegrityChecker::new(store.clone());
+    let strict = IntegrityConfig { mode: IntegrityMode::Strict, repair: false };
+
+    assert!(checker.run_startup(IntegrityConfig::default(), "system").await.unwrap().is_some());
+    assert!(checker.run_startup(IntegrityConfig { mode: IntegrityMode::Off, repair: false }, "system").await.unwrap().is_none());
+    assert!(checker.run_startup(strict, "system").await.is_err());
+
+    let mut org = Organization::new();
+    org.add_agent(agent("ceo"));
+    let company = VirtualCompany::with_store(CompanyConfig::new("Acme", org).with_integrity(strict), store.clone());
+    let err = company.run().await.unwrap_err();
+    assert!(err.to_string().contains("critical"), "{}", err);
+
+    // 启动时修复后严重问题消失，严格模式也可以启动
+    let report = checker
+        .run_startup(IntegrityConfig { mode: IntegrityMode::Strict, repair: true }, "system")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.repaired(), 4);
+}
+
+#[tokio::test]
+async fn test_integrity_check_endpoint() {
+    let store = Arc::new(MemoryStore::new());
+    seed_dangling(store.as_ref()).await;
+    let jwt_service = JwtService::new("test-secret");
+    let token = |position: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: "u1".to_string(),
+                username: "u1".to_string(),
+                name: "U1".to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: position.to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    };
+    let (admin, employee) = (token("Management"), token("Employee"));
+    let (message_tx, _) = broadcast::channel(16);
+    let app = create_router(Arc::new(AppState::new(vec![agent("ceo")], message_tx, store.clone(), jwt_service.clone())));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let url = format!("http://{}/api/admin/integrity-check", addr);
+    let client = reqwest::Client::new();
+
+    let response = client.post(&url).bearer_auth(&employee).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let body: Value = client.post(&url).bearer_auth(&admin).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["data"]["findings"].as_array().unwrap().len(), 4);
+    assert_eq!(body["data"]["repair_mode"], false);
+    assert_eq!(store.load_groups().await.unwrap()[0].members.len(), 3);
+
+    let body: Value = client
+        .post(format!("{}?repair=true", url))
+        .bearer_auth(&admin)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(body["data"]["findings"].as_array().unwrap().iter().all(|f| f["repaired"] == true));
+    assert_eq!(store.load_groups().await.unwrap()[0].members.len(), 2);
+    assert_eq!(store.load_org_changes(0, 10).await.unwrap().last().unwrap().actor, "u1");
+}
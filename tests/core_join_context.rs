@@ -0,0 +1,183 @@
+//! 加入群聊的前情提要测试：提要组成与总结缓存、只投递给新成员、进入新成员下一轮的提示词、
+//! 冷启动跳过提要以及 group.invite 工具
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use imitatort::core::agent::{AgentRuntime, Context};
+use imitatort::core::join_context::{
+    GroupSummarizer, InviteOptions, JoinContext, JoinContextConfig, JOIN_CONTEXT_KEY, JOIN_CONTEXT_SENDER,
+};
+use imitatort::core::messaging::{GroupModerationError, MessageBus, MessageReceiver};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Agent, LLMConfig, Message, MessageTarget, Organization, Role};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use serde_json::json;
+use tokio::sync::RwLock;
+
+/// 记下收到的对话记录、按调用次数给总结编号的总结器
+#[derive(Default)]
+struct RecordingSummarizer {
+    calls: AtomicUsize,
+    transcripts: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl GroupSummarizer for RecordingSummarizer {
+    async fn summarize_group(&self, group_name: &str, transcript: &str) -> Result<String> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        self.transcripts.lock().unwrap().push(transcript.to_string());
+        Ok(format!("{} agreed on Postgres (summary {})", group_name, call))
+    }
+}
+
+fn members(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn bus_with_join_context(recent_messages: usize) -> (Arc<MessageBus>, Arc<JoinContext>, Arc<RecordingSummarizer>) {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let summarizer = Arc::new(RecordingSummarizer::default());
+    let config = JoinContextConfig {
+        recent_messages,
+        ..Default::default()
+    };
+    let join_context = Arc::new(JoinContext::new(config, store.clone()).with_summarizer(summarizer.clone()));
+    let bus = Arc::new(MessageBus::with_store(store).with_join_context(join_context.clone()));
+    (bus, join_context, summarizer)
+}
+
+fn drain(receiver: &mut MessageReceiver) -> Vec<Message> {
+    std::iter::from_fn(|| receiver.try_recv()).collect()
+}
+
+async fn prompt_for(id: &str, unread: Vec<Message>) -> String {
+    let agent = Agent::new(id, id, Role::simple("Engineer", "You build things"), LLMConfig::openai("k"));
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+    runtime.build_thinking_prompt(&Context::default().with_messages(unread))
+}
+
+#[tokio::test]
+async fn test_package_has_pins_summary_and_recent_messages_of_that_group_only() {
+    let (bus, join_context, summarizer) = bus_with_join_context(3);
+    let (_lead, _dev, _qa) = (bus.register("lead"), bus.register("dev"), bus.register("qa"));
+    bus.create_group("team", "Team", "lead", members(&["lead", "dev"])).await.unwrap();
+    bus.create_group("secret", "Secret", "lead", members(&["lead"])).await.unwrap();
+
+    let decision = Message::group("lead", "team", "Decision: we use Postgres");
+    let decision_id = decision.id.clone();
+    bus.send(decision).await.unwrap();
+    for i in 1..=5 {
+        bus.send(Message::group("dev", "team", format!("update {}", i))).await.unwrap();
+    }
+    bus.pin_message(&decision_id, "dev").await.unwrap();
+    let secret = Message::group("lead", "secret", "launch codes");
+    let secret_id = secret.id.clone();
+    bus.send(secret).await.unwrap();
+    bus.pin_message(&secret_id, "lead").await.unwrap();
+
+    let group = bus.get_group("team").await.unwrap();
+    let package = join_context.build(&group).await.unwrap();
+    assert_eq!(package.group_id, "team");
+    assert_eq!(package.pinned.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec![decision_id.as_str()]);
+    assert_eq!(package.earlier, 3);
+    assert_eq!(package.summary.as_deref(), Some("Team agreed on Postgres (summary 1)"));
+    assert_eq!(
+        package.recent.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(),
+        vec!["update 3", "update 4", "update 5"]
+    );
+
+    // 只总结最近消息之前的部分，其他群聊的内容不会出现
+    let transcript = summarizer.transcripts.lock().unwrap()[0].clone();
+    assert_eq!(transcript, "lead: Decision: we use Postgres\ndev: update 1\ndev: update 2");
+    let text = package.render(&bus.catalog());
+    assert!(text.contains("You were added to Team"), "{}", text);
+    assert!(text.contains("Summary of the earlier discussion (3 messages)"), "{}", text);
+    assert!(text.contains("- dev: update 5"), "{}", text);
+    assert!(!text.contains("launch codes"), "{}", text);
+
+    // 较早的消息没有变化时使用缓存的总结
+    join_context.build(&group).await.unwrap();
+    assert_eq!(summarizer.calls.load(Ordering::SeqCst), 1);
+    assert_eq!(join_context.cached_summary("team").as_deref(), Some("Team agreed on Postgres (summary 1)"));
+    bus.send(Message::group("dev", "team", "update 6")).await.unwrap();
+    assert_eq!(join_context.build(&group).await.unwrap().summary.as_deref(), Some("Team agreed on Postgres (summary 2)"));
+}
+
+#[tokio::test]
+async fn test_package_only_reaches_the_new_member_and_their_next_prompt() {
+    let (bus, _join_context, _summarizer) = bus_with_join_context(20);
+    let mut lead = MessageReceiver::new("lead".into(), bus.register("lead"));
+    let mut dev = MessageReceiver::new("dev".into(), bus.register("dev"));
+    let mut qa = MessageReceiver::new("qa".into(), bus.register("qa"));
+    bus.create_group("team", "Team", "lead", members(&["lead", "dev"])).await.unwrap();
+    bus.send(Message::group("lead", "team", "The login bug is fixed by restarting the cache")).await.unwrap();
+    drain(&mut lead);
+    drain(&mut dev);
+
+    let group = bus.invite_member("team", "lead", "qa", InviteOptions::default()).await.unwrap();
+    assert!(group.has_member("qa"));
+
+    let received = drain(&mut qa);
+    assert_eq!(received.len(), 1);
+    let package = &received[0];
+    assert_eq!(package.from, JOIN_CONTEXT_SENDER);
+    assert_eq!(package.to, MessageTarget::Direct("qa".into()));
+    assert_eq!(package.metadata(JOIN_CONTEXT_KEY), Some("team"));
+    assert!(package.content.contains("restarting the cache"), "{}", package.content);
+    let (lead_unread, dev_unread) = (drain(&mut lead), drain(&mut dev));
+    assert!(lead_unread.is_empty() && dev_unread.is_empty());
+
+    let qa_prompt = prompt_for("qa", received.clone()).await;
+    assert!(qa_prompt.contains("You were added to Team"), "{}", qa_prompt);
+    for (id, unread) in [("lead", lead_unread), ("dev", dev_unread)] {
+        assert!(!prompt_for(id, unread).await.contains("You were added to Team"));
+    }
+
+    // 提要不能被转发到群聊
+    let mut forwarded = package.clone();
+    forwarded.to = MessageTarget::Group("team".into());
+    assert!(bus.send(forwarded).await.is_err());
+    assert!(drain(&mut dev).is_empty());
+}
+
+#[tokio::test]
+async fn test_cold_start_and_invite_tool() {
+    let (bus, _join_context, _summarizer) = bus_with_join_context(20);
+    let _lead = bus.register("lead");
+    let mut qa = MessageReceiver::new("qa".into(), bus.register("qa"));
+    let mut ops = MessageReceiver::new("ops".into(), bus.register("ops"));
+    bus.create_group("team", "Team", "lead", members(&["lead"])).await.unwrap();
+    bus.send(Message::group("lead", "team", "kickoff")).await.unwrap();
+
+    bus.invite_member("team", "lead", "qa", InviteOptions::cold_start()).await.unwrap();
+    assert!(drain(&mut qa).is_empty());
+    let err = bus.invite_member("team", "lead", "qa", InviteOptions::default()).await.unwrap_err();
+    assert!(matches!(err.downcast::<GroupModerationError>().unwrap(), GroupModerationError::AlreadyMember { .. }));
+
+    let tools = FrameworkToolExecutor::new(ToolEnvironment::new(
+        bus.clone(),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        bus.store().unwrap(),
+    ));
+    let denied = tools
+        .execute("group.invite", json!({ "group_id": "team", "member_id": "ops" }), &ToolCallContext::new("qa"))
+        .await
+        .unwrap();
+    assert_eq!(denied.error.unwrap(), "Only admins of group team can do this");
+
+    let result = tools
+        .execute("group.invite", json!({ "group_id": "team", "member_id": "ops" }), &ToolCallContext::new("lead"))
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["members"], json!(["lead", "qa", "ops"]));
+    let received = drain(&mut ops);
+    assert_eq!(received.len(), 1);
+    assert!(received[0].content.contains("- lead: kickoff"), "{}", received[0].content);
+}
@@ -0,0 +1,202 @@
+//! 主备领导权测试：租约到期后备用节点接管且两个节点不会同时领导、令牌拦截时钟落后的旧领导者、
+//! SQLite 租约、备用节点拒绝写请求并指明领导者、组织架构保存前检查令牌
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::leadership::{LeaderElection, LeadershipConfig, LeadershipError};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::standby::LEADER_HEADER;
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{CompanyConfig, Organization, VirtualCompany};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+const START: i64 = 1_700_000_000;
+
+fn config(node_id: &str) -> LeadershipConfig {
+    LeadershipConfig {
+        enabled: true,
+        node_id: Some(node_id.to_string()),
+        lease_ttl_secs: 15,
+        renew_interval_secs: 5,
+    }
+}
+
+fn node(store: &Arc<dyn Store>, clock: &Arc<ManualClock>, node_id: &str) -> LeaderElection {
+    LeaderElection::new(store.clone(), &config(node_id)).with_clock(clock.clone())
+}
+
+fn fence_error(result: anyhow::Result<()>) -> LeadershipError {
+    result.unwrap_err().downcast::<LeadershipError>().unwrap()
+}
+
+#[tokio::test]
+async fn test_standby_takes_over_after_expiry_without_overlap() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let clock = Arc::new(ManualClock::new(START));
+    let a = node(&store, &clock, "a");
+    let b = node(&store, &clock, "b");
+    let mut a_leading = a.subscribe();
+
+    assert!(a.tick().await.unwrap().is_leader);
+    let status = b.tick().await.unwrap();
+    assert!(!status.is_leader);
+    assert_eq!(status.leader.as_deref(), Some("a"));
+    assert_eq!(a.token(), Some(1));
+    assert!(*a_leading.borrow_and_update());
+    a.check_fence().await.unwrap();
+    assert!(matches!(fence_error(b.check_fence().await), LeadershipError::NotLeader { leader: Some(ref l), .. } if l == "a"));
+
+    // a 每 5 秒续期到第 20 秒后停止（崩溃），b 每秒尝试一次；每一秒最多只有一个领导者
+    let mut takeover = None;
+    for second in 1..=60 {
+        clock.advance(Duration::from_secs(1));
+        if second <= 20 && second % 5 == 0 {
+            a.tick().await.unwrap();
+        }
+        b.tick().await.unwrap();
+        assert!(!(a.is_leader() && b.is_leader()), "both nodes lead at +{}s", second);
+        assert!(a.is_leader() || second > 20);
+        if b.is_leader() && takeover.is_none() {
+            takeover = Some(second);
+        }
+    }
+    // 最后一次续期在第 20 秒，租约在第 35 秒到期
+    assert_eq!(takeover, Some(35));
+    assert_eq!(b.token(), Some(2));
+    b.check_fence().await.unwrap();
+
+    // a 恢复后成为备用节点
+    let status = a.tick().await.unwrap();
+    assert!(!status.is_leader);
+    assert_eq!(status.leader.as_deref(), Some("b"));
+    assert!(!*a_leading.borrow_and_update());
+    assert!(matches!(fence_error(a.check_fence().await), LeadershipError::NotLeader { .. }));
+
+    // 主动释放后另一个节点立即接管，令牌继续递增
+    assert!(b.release().await.unwrap());
+    assert!(a.tick().await.unwrap().is_leader);
+    assert_eq!(a.token(), Some(3));
+}
+
+#[tokio::test]
+async fn test_fencing_token_rejects_stale_leader() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    // a 的时钟落后 30 秒：租约在存储中已经到期，a 仍然认为自己在领导
+    let slow = Arc::new(ManualClock::new(START));
+    let clock = Arc::new(ManualClock::new(START));
+    let a = node(&store, &slow, "a");
+    let b = node(&store, &clock, "b");
+
+    a.tick().await.unwrap();
+    clock.advance(Duration::from_secs(30));
+    assert!(b.tick().await.unwrap().is_leader);
+    assert!(a.is_leader());
+
+    let error = fence_error(a.check_fence().await);
+    assert_eq!(error, LeadershipError::Fenced { node_id: "a".to_string(), token: 1 });
+    b.check_fence().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_sqlite_lease_hands_over_with_next_token() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    let lease = store.acquire_lease("company", "a", START, 15).await.unwrap();
+    assert_eq!((lease.holder.as_str(), lease.token, lease.expires_at), ("a", 1, START + 15));
+
+    // 续期保留令牌；有效期内其他节点拿到的是 a 的租约
+    let lease = store.acquire_lease("company", "a", START + 5, 15).await.unwrap();
+    assert_eq!((lease.token, lease.acquired_at, lease.expires_at), (1, START, START + 20));
+    let lease = store.acquire_lease("company", "b", START + 19, 15).await.unwrap();
+    assert_eq!(lease.holder, "a");
+
+    let lease = store.acquire_lease("company", "b", START + 20, 15).await.unwrap();
+    assert_eq!((lease.holder.as_str(), lease.token), ("b", 2));
+    assert!(!store.release_lease("company", "a", 1).await.unwrap());
+    assert!(store.release_lease("company", "b", 2).await.unwrap());
+    let lease = store.acquire_lease("company", "a", START + 21, 15).await.unwrap();
+    assert_eq!((lease.holder.as_str(), lease.token), ("a", 3));
+    assert_eq!(store.load_lease("company").await.unwrap(), Some(lease));
+}
+
+async fn serve(store: Arc<dyn Store>, leadership: Arc<LeaderElection>) -> std::net::SocketAddr {
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store, JwtService::new("secret")).with_leadership(leadership);
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr
+}
+
+#[tokio::test]
+async fn test_standby_rejects_writes_and_points_at_leader() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    store.save_organization(&Organization::new()).await.unwrap();
+    let clock = Arc::new(ManualClock::new(START));
+    let leader = Arc::new(node(&store, &clock, "a"));
+    let standby = Arc::new(node(&store, &clock, "b"));
+    leader.tick().await.unwrap();
+    standby.tick().await.unwrap();
+
+    let client = reqwest::Client::new();
+    let standby_addr = serve(store.clone(), standby).await;
+    let message = json!({ "from": "user", "to": "dev", "content": "hi" });
+
+    let response = client
+        .post(format!("http://{}/api/v1/messages", standby_addr))
+        .json(&message)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+    assert_eq!(response.headers()[LEADER_HEADER], "a");
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("a"), "{}", body);
+
+    // 读请求照常处理
+    let response = client.get(format!("http://{}/api/v1/org/tree", standby_addr)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = client
+        .get(format!("http://{}/api/leadership", standby_addr))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["enabled"], true);
+    assert_eq!(body["status"]["is_leader"], false);
+    assert_eq!(body["status"]["leader"], "a");
+
+    // 领导者的写请求通过检查
+    let leader_addr = serve(store.clone(), leader).await;
+    let response = client
+        .post(format!("http://{}/api/v1/messages", leader_addr))
+        .json(&message)
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(response.status(), 409);
+}
+
+#[tokio::test]
+async fn test_company_save_requires_current_lease() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let config = CompanyConfig::new("Acme", Organization::new()).with_leadership(config("a"));
+    let company = VirtualCompany::with_store(config, store.clone());
+    let leadership = company.leadership().unwrap();
+
+    let error = company.save().await.unwrap_err();
+    assert!(matches!(error.downcast_ref::<LeadershipError>(), Some(LeadershipError::NotLeader { .. })));
+
+    leadership.tick().await.unwrap();
+    company.save().await.unwrap();
+    assert!(store.load_lease("company").await.unwrap().is_some_and(|lease| lease.holder == "a"));
+}
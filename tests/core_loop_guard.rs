@@ -0,0 +1,265 @@
+//! Agent 回声循环抑制测试
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::events::{CompanyEvent, CompanyEventListener};
+use imitatort::core::loop_guard::{LoopGuard, LoopGuardConfig, LoopTrip, LoopVerdict, LOOP_NOTICE_SENDER};
+use imitatort::core::send_dedup::SendDedupConfig;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::domain::{Agent, LLMConfig, Message, Organization, Role};
+use imitatort::{CompanyConfig, VirtualCompany};
+
+fn config() -> LoopGuardConfig {
+    LoopGuardConfig {
+        max_chain_depth: 0,
+        max_pair_exchanges: 0,
+        pair_window_secs: 60,
+        duplicate_window: 0,
+        duplicate_similarity: 0.9,
+        cooldown_secs: 30,
+    }
+}
+
+fn guard(config: LoopGuardConfig) -> (LoopGuard, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock::new(1_000_000));
+    (LoopGuard::new(config).with_clock(clock.clone()), clock)
+}
+
+fn peers(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn trip(verdict: LoopVerdict) -> (LoopTrip, bool) {
+    match verdict {
+        LoopVerdict::Block { trip, notify } => (trip, notify),
+        LoopVerdict::Allow => panic!("expected block"),
+    }
+}
+
+#[test]
+fn test_chain_depth_trips_then_cools_down() {
+    let (guard, clock) = guard(LoopGuardConfig {
+        max_chain_depth: 2,
+        ..config()
+    });
+    let bob = peers(&["bob"]);
+
+    let ok = Message::private("alice", "bob", "hi").with_chain_depth(2);
+    assert_eq!(guard.check(&ok, &bob), LoopVerdict::Allow);
+
+    let deep = Message::private("alice", "bob", "again").with_chain_depth(3).with_trace("t-1", None);
+    let (first, notify) = trip(guard.check(&deep, &bob));
+    assert_eq!(first, LoopTrip::ChainDepth { depth: 3, max: 2 });
+    assert!(notify);
+    assert!(guard.is_cooling_down("alice"));
+
+    // 提示沿用被拦截消息的深度，不会重置回复链
+    let notice = guard.notice(&deep, &first);
+    assert_eq!(notice.from, LOOP_NOTICE_SENDER);
+    assert_eq!(notice.target_agent(), Some("alice"));
+    assert_eq!(notice.chain_depth(), 3);
+    assert_eq!(notice.trace_id(), Some("t-1"));
+    assert!(notice.content.contains("depth 3"));
+
+    // 冷却期内任何消息都被拦截，但不再重复提示
+    let (second, notify) = trip(guard.check(&ok, &bob));
+    assert!(matches!(second, LoopTrip::Cooldown { remaining_secs: 30 }));
+    assert!(!notify);
+
+    clock.advance(Duration::from_secs(31));
+    assert_eq!(guard.check(&ok, &bob), LoopVerdict::Allow);
+
+    let metrics = guard.metrics();
+    assert_eq!(metrics.chain_depth, 1);
+    assert_eq!(metrics.cooldown, 1);
+}
+
+#[test]
+fn test_pair_rate_counts_both_directions_and_resets_on_human_input() {
+    let (guard, clock) = guard(LoopGuardConfig {
+        max_pair_exchanges: 3,
+        ..config()
+    });
+
+    assert_eq!(guard.check(&Message::private("alice", "bob", "1"), &peers(&["bob"])), LoopVerdict::Allow);
+    assert_eq!(guard.check(&Message::private("bob", "alice", "2"), &peers(&["alice"])), LoopVerdict::Allow);
+    assert_eq!(guard.check(&Message::group("alice", "g", "3"), &peers(&["bob"])), LoopVerdict::Allow);
+    // 与其他 Agent 的往来不受影响
+    assert_eq!(guard.check(&Message::private("alice", "carol", "x"), &peers(&["carol"])), LoopVerdict::Allow);
+
+    let (tripped, _) = trip(guard.check(&Message::private("bob", "alice", "4"), &peers(&["alice"])));
+    assert_eq!(
+        tripped,
+        LoopTrip::PairRate {
+            peer: "alice".to_string(),
+            exchanges: 3,
+            window_secs: 60
+        }
+    );
+
+    // 人类介入清除冷却和计数
+    guard.note_human_input("bob");
+    assert!(!guard.is_cooling_down("bob"));
+    assert_eq!(guard.check(&Message::private("bob", "alice", "5"), &peers(&["alice"])), LoopVerdict::Allow);
+
+    // 窗口外的往来不再计数
+    clock.advance(Duration::from_secs(61));
+    for i in 0..3 {
+        let message = Message::private("alice", "bob", i.to_string());
+        assert_eq!(guard.check(&message, &peers(&["bob"])), LoopVerdict::Allow);
+    }
+}
+
+#[test]
+fn test_near_duplicate_outputs_trip() {
+    let duplicates = LoopGuardConfig {
+        duplicate_window: 2,
+        ..config()
+    };
+    let bob = peers(&["bob"]);
+
+    // 与最近 K 条中任意一条不同都不算循环
+    let (varied, _) = guard(duplicates.clone());
+    for content in ["Sounds good", "Deploy is done", "Sounds good"] {
+        assert_eq!(varied.check(&Message::private("alice", "bob", content), &bob), LoopVerdict::Allow);
+    }
+
+    let (repeated, _) = guard(duplicates);
+    for content in ["Sounds good, thanks!", "sounds good thanks"] {
+        assert_eq!(repeated.check(&Message::private("alice", "bob", content), &bob), LoopVerdict::Allow);
+    }
+    let (tripped, notify) = trip(repeated.check(&Message::private("alice", "bob", "SOUNDS GOOD... thanks"), &bob));
+    assert_eq!(tripped, LoopTrip::Duplicate { similarity: 1.0 });
+    assert!(notify);
+}
+
+/// 有未读消息时回复 `peer`，否则等待
+async fn spawn_mock_llm(peer: &'static str) -> String {
+    let app = Router::new().route(
+        "/chat/completions",
+        post(move |Json(request): Json<Value>| async move {
+            let decision = if request.to_string().contains("Unread messages:") {
+                json!({"action": "send_message", "target": peer, "content": format!("your turn, {}", peer)})
+            } else {
+                json!({"action": "wait"})
+            };
+            Json(json!({
+                "id": "chatcmpl-mock",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "mock",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": decision.to_string()},
+                    "finish_reason": "stop"
+                }]
+            }))
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+fn agent(id: &str, base_url: String) -> Agent {
+    Agent::new(
+        id,
+        id,
+        Role::simple("Engineer", "You are an engineer"),
+        LLMConfig {
+            model: "mock".to_string(),
+            api_key: "test".to_string(),
+            base_url,
+            tokenizer: None,
+        },
+    )
+}
+
+#[derive(Clone, Default)]
+struct Trips(Arc<Mutex<Vec<(String, LoopTrip)>>>);
+
+#[async_trait]
+impl CompanyEventListener for Trips {
+    async fn on_event(&self, event: &CompanyEvent) {
+        if let CompanyEvent::LoopGuardTripped { agent_id, trip, .. } = event {
+            self.0.lock().unwrap().push((agent_id.to_string(), (**trip).clone()));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_two_agent_ping_pong_stops_at_max_depth() {
+    let mut org = Organization::new();
+    org.add_agent(agent("alice", spawn_mock_llm("bob").await));
+    org.add_agent(agent("bob", spawn_mock_llm("alice").await));
+    let store = Arc::new(MemoryStore::new());
+    // 模拟 LLM 每轮回复相同内容，关闭重复发送抑制才能观察深度上限
+    let config = CompanyConfig::new("Loops", org)
+        .with_loop_guard(LoopGuardConfig {
+            max_chain_depth: 3,
+            ..config()
+        })
+        .with_send_dedup(SendDedupConfig {
+            window_secs: 0,
+            ..Default::default()
+        });
+    let company = Arc::new(VirtualCompany::with_store(config, store.clone()));
+    let trips = Trips::default();
+    company.register_event_listener(Box::new(trips.clone()));
+
+    let runner = company.clone();
+    let handle = tokio::spawn(async move { runner.run().await });
+    for _ in 0..100 {
+        if company.message_bus().is_registered("bob") && company.message_bus().is_registered("alice") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    // 组织外的人启动对话
+    company
+        .message_bus()
+        .send(Message::private("director", "alice", "please sync with bob"))
+        .await
+        .unwrap();
+
+    let mut waited = 0;
+    while trips.0.lock().unwrap().len() < 2 {
+        assert!(waited < 300, "loop was never stopped: {:?}", trips.0.lock().unwrap());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        waited += 1;
+    }
+    // 冷却期内的拦截不会继续产生提示或消息
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    handle.abort();
+
+    let messages = store.load_messages(MessageFilter::new().limit(100)).await.unwrap();
+    let mut exchanged: Vec<(String, u32)> = messages
+        .iter()
+        .filter(|m| m.from == "alice" || m.from == "bob")
+        .map(|m| (m.from.clone(), m.chain_depth()))
+        .collect();
+    exchanged.sort_by_key(|(_, depth)| *depth);
+    assert_eq!(
+        exchanged,
+        [("alice".into(), 0), ("bob".into(), 1), ("alice".into(), 2), ("bob".into(), 3)]
+    );
+
+    let notices: Vec<&Message> = messages.iter().filter(|m| m.from == LOOP_NOTICE_SENDER).collect();
+    assert_eq!(notices.len(), 1);
+    assert_eq!(notices[0].target_agent(), Some("alice"));
+
+    let trips = trips.0.lock().unwrap().clone();
+    assert_eq!(trips[0], ("alice".to_string(), LoopTrip::ChainDepth { depth: 4, max: 3 }));
+    assert!(trips[1..].iter().all(|(_, t)| matches!(t, LoopTrip::Cooldown { .. })));
+    assert_eq!(company.loop_guard().metrics().chain_depth, 1);
+}
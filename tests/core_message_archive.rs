@@ -0,0 +1,152 @@
+//! 消息归档测试：归档前后同一过滤条件的查询结果一致、按ID查找跨越归档边界、归档任务和管理接口
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::archive::{MessageArchiveConfig, MessageArchiver};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::store::{MemoryStore, MessageFilter, MessageTierCounts, Store};
+use imitatort::domain::{Agent, LLMConfig, Message, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+const DAY: i64 = 86_400;
+const NOW: i64 = 1_700_000_000;
+
+/// 30 天内每 6 小时一条，私聊与群聊交替，部分消息回复上一条
+fn seed() -> Vec<Message> {
+    let mut messages: Vec<Message> = Vec::new();
+    for i in 0..120i64 {
+        let from = ["ceo", "cto", "dev"][i as usize % 3];
+        let mut message = if i % 2 == 0 {
+            Message::private(from, "dev", format!("dm {}", i))
+        } else {
+            Message::group(from, "eng", format!("group {}", i))
+        };
+        message.timestamp = NOW - 30 * DAY + i * 6 * 3600;
+        if i % 5 == 0 {
+            message.reply_to = messages.last().map(|m| m.id.clone());
+        }
+        messages.push(message);
+    }
+    messages
+}
+
+fn filters() -> Vec<MessageFilter> {
+    vec![
+        MessageFilter::new().limit(1000),
+        MessageFilter::new().limit(10),
+        MessageFilter::new().limit(25).oldest_first(),
+        MessageFilter::new().to("dev").target_type("direct").limit(1000),
+        MessageFilter::new().to("eng").target_type("group").limit(7),
+        MessageFilter::new().from("cto").limit(1000),
+        MessageFilter::new().since(NOW - 20 * DAY).until(NOW - 5 * DAY).limit(1000),
+        MessageFilter::new().since(NOW - 3 * DAY).limit(1000),
+        MessageFilter::new().until(NOW - 15 * DAY).limit(1000).oldest_first(),
+    ]
+}
+
+fn ids(messages: &[Message]) -> Vec<String> {
+    messages.iter().map(|m| m.id.clone()).collect()
+}
+
+async fn assert_archive_is_transparent(store: &dyn Store) {
+    let seeded = seed();
+    store.save_messages(&seeded).await.unwrap();
+    let mut before = Vec::new();
+    for filter in filters() {
+        before.push(ids(&store.load_messages(filter).await.unwrap()));
+    }
+
+    let moved = store.archive_messages_before(NOW - 10 * DAY).await.unwrap();
+    assert_eq!(moved, 80);
+    let counts = store.message_tier_counts().await.unwrap();
+    assert_eq!(counts, MessageTierCounts { hot: 40, archived: 80, archived_before: Some(NOW - 10 * DAY) });
+
+    for (filter, expected) in filters().into_iter().zip(before) {
+        assert_eq!(ids(&store.load_messages(filter.clone()).await.unwrap()), expected, "{:?}", filter);
+    }
+
+    // 热表中的回复能找到已归档的原消息
+    let boundary_reply = seeded.iter().find(|m| m.timestamp >= NOW - 10 * DAY && m.reply_to.is_some()).unwrap();
+    let parent = store.load_message(boundary_reply.reply_to.as_deref().unwrap()).await.unwrap().unwrap();
+    assert!(parent.timestamp < boundary_reply.timestamp);
+    assert_eq!(store.load_message(&seeded[0].id).await.unwrap().unwrap().content, "dm 0");
+    assert!(store.load_message("missing").await.unwrap().is_none());
+
+    // 再次归档不会移动已归档的消息，边界只前移
+    assert_eq!(store.archive_messages_before(NOW - 20 * DAY).await.unwrap(), 0);
+    assert_eq!(store.message_tier_counts().await.unwrap().archived_before, Some(NOW - 10 * DAY));
+}
+
+#[tokio::test]
+async fn test_sqlite_archive_keeps_query_results() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    assert_archive_is_transparent(&store).await;
+}
+
+#[tokio::test]
+async fn test_memory_archive_keeps_query_results() {
+    let store = MemoryStore::new();
+    assert_archive_is_transparent(&store).await;
+}
+
+#[tokio::test]
+async fn test_archiver_moves_messages_past_retention() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    store.save_messages(&seed()).await.unwrap();
+    let clock = Arc::new(ManualClock::new(NOW));
+
+    let disabled = MessageArchiver::new(store.clone(), MessageArchiveConfig::default()).with_clock(clock.clone());
+    assert_eq!(disabled.run_once().await.unwrap(), 0);
+
+    let archiver = MessageArchiver::new(store.clone(), MessageArchiveConfig::keep_days(7)).with_clock(clock.clone());
+    assert_eq!(archiver.run_once().await.unwrap(), 92);
+    clock.advance(Duration::from_secs(DAY as u64));
+    assert_eq!(archiver.run_once().await.unwrap(), 4);
+    assert_eq!(store.message_tier_counts().await.unwrap().hot, 24);
+}
+
+#[tokio::test]
+async fn test_admin_endpoint_reports_tiers() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    store.save_messages(&seed()).await.unwrap();
+    store.archive_messages_before(NOW - 10 * DAY).await.unwrap();
+
+    let jwt_service = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let agents = vec![Agent::new("ceo", "CEO", Role::simple("CEO", "You lead"), LLMConfig::openai("k"))];
+    let app = create_router(Arc::new(AppState::new(agents, message_tx, store, jwt_service.clone())));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let token = |position: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: "1".to_string(),
+                username: "boss".to_string(),
+                name: "Boss".to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: position.to_string(),
+                department: "hq".to_string(),
+            })
+            .unwrap()
+    };
+    let url = format!("http://{}/api/v1/admin/messages/tiers", addr);
+    let client = reqwest::Client::new();
+    let response = client.get(&url).bearer_auth(token("Employee")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let body: Value = client.get(&url).bearer_auth(token("Management")).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["data"]["hot"], 40);
+    assert_eq!(body["data"]["archived"], 80);
+    assert_eq!(body["data"]["archived_before"], NOW - 10 * DAY);
+}
@@ -0,0 +1,229 @@
+//! 消息大小限制测试：校验函数、REST / WebSocket / 框架工具一致地拒绝或转为附件、配置切换
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use imitatort::core::attachments::attachment_key;
+use imitatort::core::message_limits::{MessageLimitError, MessageLimits, OversizeAction};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::MemoryStore;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Agent, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::blob::{read_body, BlobStore, LocalBlobStore};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::AppConfig;
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+/// 测试用的小上限：正文 100 字节、3 个 @、200 字节元数据
+fn limits() -> MessageLimits {
+    MessageLimits::default()
+        .with_max_content_bytes(100)
+        .with_max_mentions(3)
+        .with_max_metadata_bytes(200)
+}
+
+fn oversized() -> String {
+    "x".repeat(250)
+}
+
+/// 附件 ID 取自消息的附件链接
+async fn attachment_text(blobs: &LocalBlobStore, message: &Message) -> String {
+    let link = message.attachments()[0].to_string();
+    let id = link.rsplit('/').next().unwrap();
+    let object = blobs.get(&attachment_key(id), None).await.unwrap().unwrap();
+    String::from_utf8(read_body(object.body).await.unwrap()).unwrap()
+}
+
+#[test]
+fn test_check_and_preview() {
+    let limits = limits();
+    assert!(limits.check(&Message::private("a", "b", "x".repeat(100))).is_ok());
+    assert_eq!(
+        limits.check(&Message::private("a", "b", "x".repeat(101))),
+        Err(MessageLimitError::ContentTooLarge { size: 101, limit: 100 })
+    );
+
+    let mut crowded = Message::group("a", "team", "hi");
+    for id in ["b", "c", "d", "e"] {
+        crowded = crowded.with_mention(id);
+    }
+    assert_eq!(limits.check(&crowded), Err(MessageLimitError::TooManyMentions { count: 4, limit: 3 }));
+    let heavy = Message::private("a", "b", "hi").with_metadata("blob", "y".repeat(300));
+    assert!(matches!(limits.check(&heavy), Err(MessageLimitError::MetadataTooLarge { limit: 200, .. })));
+
+    // 预览按字符截断，不超过正文上限的一半
+    let limits = limits.attach_oversized(10);
+    assert_eq!(limits.preview(&"字".repeat(30)), "字".repeat(10));
+    assert_eq!(limits.preview(&"ab".repeat(40)), "ab".repeat(5));
+    assert_eq!(MessageLimits::default().with_max_content_bytes(12).preview(&"字".repeat(30)), "字".repeat(2));
+}
+
+#[test]
+fn test_config_toggle() {
+    let config: AppConfig = serde_json::from_value(json!({
+        "db_path": "test.db",
+        "web_bind": "127.0.0.1:0",
+        "output_mode": "web",
+        "message_channel_capacity": 10,
+        "default_api_base_url": "http://localhost",
+        "default_model": "m",
+        "log_level": "info",
+        "run_agent_loops": false,
+        "message_limits": { "max_content_bytes": 2048, "oversize": "attach" }
+    }))
+    .unwrap();
+    assert_eq!(config.message_limits.max_content_bytes, 2048);
+    assert_eq!(config.message_limits.oversize, OversizeAction::Attach);
+    assert_eq!(config.message_limits.max_mentions, MessageLimits::default().max_mentions);
+    assert_eq!("reject".parse::<OversizeAction>(), Ok(OversizeAction::Reject));
+    assert!("drop".parse::<OversizeAction>().is_err());
+}
+
+struct Server {
+    addr: std::net::SocketAddr,
+    message_tx: broadcast::Sender<Message>,
+    blobs: Arc<LocalBlobStore>,
+    _dir: tempfile::TempDir,
+}
+
+async fn serve(limits: MessageLimits) -> Server {
+    let dir = tempfile::tempdir().unwrap();
+    let blobs = Arc::new(LocalBlobStore::new(dir.path()));
+    let (message_tx, _) = broadcast::channel(16);
+    let agent = Agent::new("dev", "Dev", Role::simple("Dev", "You build"), LLMConfig::openai("k"));
+    let state = AppState::new(vec![agent], message_tx.clone(), Arc::new(MemoryStore::new()), JwtService::new("secret"))
+        .with_blob_store(blobs.clone())
+        .with_message_limits(limits);
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    Server { addr, message_tx, blobs, _dir: dir }
+}
+
+#[tokio::test]
+async fn test_rest_send_rejects_or_converts() {
+    let server = serve(limits()).await;
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/api/v1/messages", server.addr);
+    let mut sent = server.message_tx.subscribe();
+
+    let response = client
+        .post(&url)
+        .json(&json!({ "from": "user", "to": "dev", "content": oversized() }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 413);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("250 bytes, over the 100-byte limit"), "{}", body);
+    assert!(sent.try_recv().is_err());
+
+    let server = serve(limits().attach_oversized(20)).await;
+    let mut sent = server.message_tx.subscribe();
+    let url = format!("http://{}/api/v1/messages", server.addr);
+    let body: Value = client
+        .post(&url)
+        .json(&json!({ "from": "user", "to": "dev", "content": oversized() }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["status"], "sent", "{}", body);
+    let message = sent.recv().await.unwrap();
+    assert!(message.content.starts_with(&"x".repeat(20)));
+    assert!(message.content.contains("full 250-byte message is in attachment"), "{}", message.content);
+    assert!(message.content.len() <= 100 + 100, "{}", message.content.len());
+    assert_eq!(body["data"]["attachment"], message.attachments()[0]);
+    assert_eq!(attachment_text(&server.blobs, &message).await, oversized());
+}
+
+#[tokio::test]
+async fn test_websocket_send_rejects_or_converts() {
+    let server = serve(limits()).await;
+    let mut sent = server.message_tx.subscribe();
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/v1/ws", server.addr)).await.unwrap();
+    let frame = json!({ "type": "send_message", "from": "user", "to": "dev", "content": oversized() });
+    socket
+        .send(tokio_tungstenite::tungstenite::Message::Text(frame.to_string()))
+        .await
+        .unwrap();
+    let reply = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+    let reply: Value = serde_json::from_str(reply.to_text().unwrap()).unwrap();
+    assert_eq!(reply["type"], "error");
+    assert!(reply["message"].as_str().unwrap().contains("Shorten it"), "{}", reply);
+    assert!(sent.try_recv().is_err());
+
+    let server = serve(limits().attach_oversized(20)).await;
+    let mut sent = server.message_tx.subscribe();
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/v1/ws", server.addr)).await.unwrap();
+    socket
+        .send(tokio_tungstenite::tungstenite::Message::Text(frame.to_string()))
+        .await
+        .unwrap();
+    let message = tokio::time::timeout(Duration::from_secs(5), sent.recv()).await.unwrap().unwrap();
+    assert!(message.content.contains("in attachment"), "{}", message.content);
+    assert_eq!(attachment_text(&server.blobs, &message).await, oversized());
+}
+
+#[tokio::test]
+async fn test_framework_tools_reject_or_convert() {
+    let bus = Arc::new(MessageBus::new());
+    let mut inbox = bus.register("dev");
+    let _lead = bus.register("lead");
+    bus.create_group("team", "Team", "lead", vec!["lead".into(), "dev".into()]).await.unwrap();
+    let env = ToolEnvironment::new(
+        bus.clone(),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        Arc::new(MemoryStore::new()),
+    );
+    let context = ToolCallContext::new("lead");
+
+    let executor = FrameworkToolExecutor::new(env.clone().with_message_limits(limits()));
+    for (tool, params) in [
+        ("message.send_direct", json!({ "to_agent_id": "dev", "content": oversized() })),
+        ("message.send_group", json!({ "group_id": "team", "content": oversized() })),
+        ("message.send_immediate", json!({ "to": "dev", "content": oversized() })),
+    ] {
+        let result = executor.execute(tool, params, &context).await.unwrap();
+        assert!(!result.success, "{}", tool);
+        let error = result.error.unwrap();
+        assert!(error.contains("250 bytes") && error.contains("Shorten it"), "{}: {}", tool, error);
+    }
+    assert!(inbox.try_recv().is_err());
+
+    // 转为附件，@ 超限仍然拒绝
+    let dir = tempfile::tempdir().unwrap();
+    let blobs = Arc::new(LocalBlobStore::new(dir.path()));
+    let executor = FrameworkToolExecutor::new(
+        env.with_message_limits(limits().attach_oversized(20)).with_blob_store(blobs.clone()),
+    );
+    let result = executor
+        .execute("message.send_direct", json!({ "to_agent_id": "dev", "content": oversized() }), &context)
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let message = inbox.try_recv().unwrap();
+    assert!(message.content.contains("in attachment"), "{}", message.content);
+    assert_eq!(attachment_text(&blobs, &message).await, oversized());
+
+    let result = executor
+        .execute(
+            "message.send_group",
+            json!({ "group_id": "team", "content": "hi", "mention_agent_ids": ["a", "b", "c", "d"] }),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("Mention fewer agents"));
+}
@@ -0,0 +1,328 @@
+//! 消息置顶与收藏测试：会话范围、取消置顶权限、收藏私有、上下文中的置顶消息、框架工具和 Web 接口
+
+use std::sync::Arc;
+
+use imitatort::core::agent::{load_pinned_messages, AgentRuntime, Context};
+use imitatort::core::messaging::{MessageBus, PinError};
+use imitatort::core::store::{MessageFilter, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::user::user_principal;
+use imitatort::domain::{direct_session_id, Agent, LLMConfig, Message, MessageBookmark, Organization, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+fn members(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn pin_error(error: anyhow::Error) -> PinError {
+    error.downcast::<PinError>().expect("typed pin error")
+}
+
+async fn setup() -> (Arc<SqliteStore>, Arc<MessageBus>, Vec<tokio::sync::mpsc::Receiver<Message>>) {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let receivers = ["lead", "dev", "ops", "pm"].iter().map(|id| bus.register(id)).collect();
+    bus.create_group("alpha", "Alpha", "lead", members(&["lead", "dev", "ops"])).await.unwrap();
+    bus.create_group("beta", "Beta", "pm", members(&["pm", "dev"])).await.unwrap();
+    for id in ["alpha", "beta"] {
+        store.save_group(&bus.get_group(id).await.unwrap()).await.unwrap();
+    }
+    (store, bus, receivers)
+}
+
+async fn send(bus: &MessageBus, message: Message) -> String {
+    let id = message.id.clone();
+    bus.send(message).await.unwrap();
+    id
+}
+
+#[tokio::test]
+async fn test_pins_are_scoped_to_their_session() {
+    let (_store, bus, _receivers) = setup().await;
+    let decision = send(&bus, Message::group("lead", "alpha", "Decision: ship on Friday")).await;
+    let other = send(&bus, Message::group("pm", "beta", "Spec v2 is final")).await;
+
+    let pin = bus.pin_message(&decision, "dev").await.unwrap();
+    assert_eq!(pin.session_id, "alpha");
+    assert_eq!(pin.pinned_by, "dev");
+    // 重复置顶保留原记录
+    assert_eq!(bus.pin_message(&decision, "lead").await.unwrap().pinned_by, "dev");
+    bus.pin_message(&other, "pm").await.unwrap();
+
+    let alpha = bus.pinned_messages("alpha", "ops").await.unwrap();
+    assert_eq!(alpha.len(), 1);
+    assert_eq!(alpha[0].message.content, "Decision: ship on Friday");
+    let beta = bus.pinned_messages("beta", "dev").await.unwrap();
+    assert_eq!(beta.len(), 1);
+    assert_eq!(beta[0].pin.message_id, other);
+
+    // 非成员既不能查看也不能置顶
+    let err = pin_error(bus.pinned_messages("beta", "ops").await.unwrap_err());
+    assert_eq!(err, PinError::NotParticipant { session_id: "beta".into(), actor: "ops".into() });
+    assert!(matches!(pin_error(bus.pin_message(&other, "ops").await.unwrap_err()), PinError::NotParticipant { .. }));
+    assert!(matches!(
+        pin_error(bus.pin_message("missing", "dev").await.unwrap_err()),
+        PinError::MessageNotFound { .. }
+    ));
+
+    // 私聊置顶双方可见，按对方ID查询
+    let handoff = send(&bus, Message::private("lead", "dev", "Credentials are in the vault")).await;
+    let pin = bus.pin_message(&handoff, "dev").await.unwrap();
+    assert_eq!(pin.session_id, direct_session_id("lead", "dev"));
+    assert_eq!(bus.pinned_messages("dev", "lead").await.unwrap()[0].pin.message_id, handoff);
+    assert_eq!(bus.pinned_messages("lead", "dev").await.unwrap().len(), 1);
+    assert!(bus.pinned_messages("lead", "ops").await.unwrap().is_empty());
+    assert!(bus.pin_message(&handoff, "ops").await.is_err());
+}
+
+#[tokio::test]
+async fn test_only_admins_unpin_others_pins() {
+    let (store, bus, _receivers) = setup().await;
+    let spec = send(&bus, Message::group("ops", "alpha", "Runbook: restart order is db, api, web")).await;
+    bus.pin_message(&spec, "ops").await.unwrap();
+
+    let err = pin_error(bus.unpin_message(&spec, "dev").await.unwrap_err());
+    assert_eq!(
+        err,
+        PinError::NotAdmin { session_id: "alpha".into(), actor: "dev".into(), pinned_by: "ops".into() }
+    );
+    assert_eq!(store.load_pins("alpha").await.unwrap().len(), 1);
+
+    // 群管理员可以取消他人的置顶，本人也可以取消自己的
+    assert!(bus.unpin_message(&spec, "lead").await.unwrap());
+    assert!(!bus.unpin_message(&spec, "lead").await.unwrap());
+    bus.pin_message(&spec, "dev").await.unwrap();
+    assert!(bus.unpin_message(&spec, "dev").await.unwrap());
+
+    // 私聊中只有置顶者本人能取消
+    let note = send(&bus, Message::private("lead", "dev", "Budget approved")).await;
+    bus.pin_message(&note, "lead").await.unwrap();
+    assert!(matches!(pin_error(bus.unpin_message(&note, "dev").await.unwrap_err()), PinError::NotAdmin { .. }));
+}
+
+#[tokio::test]
+async fn test_bookmarks_are_private() {
+    let (store, bus, _receivers) = setup().await;
+    let first = send(&bus, Message::group("lead", "alpha", "Retro notes")).await;
+    let second = send(&bus, Message::group("lead", "alpha", "Launch checklist")).await;
+
+    assert!(store.add_bookmark(&MessageBookmark::new(first.clone(), "dev")).await.unwrap());
+    assert!(!store.add_bookmark(&MessageBookmark::new(first.clone(), "dev")).await.unwrap());
+    let mut later = MessageBookmark::new(second.clone(), "dev");
+    later.timestamp += 10;
+    store.add_bookmark(&later).await.unwrap();
+
+    let bookmarks = store.load_bookmarks("dev").await.unwrap();
+    assert_eq!(bookmarks.iter().map(|b| b.message_id.as_str()).collect::<Vec<_>>(), vec![second.as_str(), first.as_str()]);
+    assert!(store.load_bookmarks("ops").await.unwrap().is_empty());
+    // 收藏不是置顶
+    assert!(store.load_pins("alpha").await.unwrap().is_empty());
+
+    assert!(store.remove_bookmark("dev", &first).await.unwrap());
+    assert!(!store.remove_bookmark("ops", &second).await.unwrap());
+    assert_eq!(store.load_bookmarks("dev").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_context_includes_pinned_messages() {
+    let (store, bus, _receivers) = setup().await;
+    let alpha = send(&bus, Message::group("lead", "alpha", "Decision: use Postgres")).await;
+    let beta = send(&bus, Message::group("pm", "beta", "Spec: export must support CSV")).await;
+    let direct = send(&bus, Message::private("lead", "dev", "You own the migration")).await;
+    send(&bus, Message::private("lead", "ops", "Ops-only secret")).await;
+    bus.pin_message(&alpha, "lead").await.unwrap();
+    bus.pin_message(&beta, "pm").await.unwrap();
+    bus.pin_message(&direct, "lead").await.unwrap();
+
+    let history = store.load_messages(MessageFilter::new()).await.unwrap();
+    let pinned = load_pinned_messages(store.as_ref(), "dev", &history).await.unwrap();
+    let ids: Vec<&str> = pinned.iter().map(|m| m.id.as_str()).collect();
+    assert_eq!(ids.len(), 3);
+    assert!(ids.contains(&alpha.as_str()) && ids.contains(&beta.as_str()) && ids.contains(&direct.as_str()));
+
+    // ops 不在 beta，也看不到 dev 的私聊置顶
+    let pinned_for_ops = load_pinned_messages(store.as_ref(), "ops", &history).await.unwrap();
+    assert_eq!(pinned_for_ops.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), vec![alpha.as_str()]);
+
+    let agent = Agent::new("dev", "Dev", Role::simple("Developer", "You write code"), LLMConfig::openai("test-key"));
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+    let prompt = runtime.build_thinking_prompt(&Context::default());
+    assert!(!prompt.contains("Pinned messages:"));
+    let prompt = runtime.build_thinking_prompt(&Context::default().with_pinned(pinned));
+    let section = &prompt[prompt.find("Pinned messages:").expect("pinned section")..];
+    assert!(section.contains("Decision: use Postgres"));
+    assert!(section.contains("You own the migration"));
+    assert!(!prompt.contains("Ops-only secret"));
+}
+
+#[tokio::test]
+async fn test_pin_tools() {
+    let (store, bus, _receivers) = setup().await;
+    let spec = send(&bus, Message::group("lead", "alpha", "API freeze on the 3rd")).await;
+    let tools = FrameworkToolExecutor::new(ToolEnvironment::new(
+        bus.clone(),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store.clone(),
+    ));
+
+    let result = tools
+        .execute("message.pin", json!({ "message_id": spec }), &ToolCallContext::new("ops"))
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["session_id"], "alpha");
+
+    let result = tools
+        .execute("memory.list_pins", json!({ "session": "alpha" }), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert_eq!(result.data["count"], 1);
+    assert_eq!(result.data["pins"][0]["content"], "API freeze on the 3rd");
+    assert_eq!(result.data["pins"][0]["pinned_by"], "ops");
+
+    let result = tools
+        .execute("memory.list_pins", json!({ "session": "beta" }), &ToolCallContext::new("ops"))
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(result.error.unwrap(), "You are not a participant of beta");
+
+    let result = tools
+        .execute("message.pin", json!({ "message_id": spec, "remove": true }), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert_eq!(result.error.unwrap(), "Only ops or a group admin can unpin this message");
+    let result = tools
+        .execute("message.pin", json!({ "message_id": spec, "remove": true }), &ToolCallContext::new("lead"))
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["changed"], true);
+}
+
+#[tokio::test]
+async fn test_pin_and_bookmark_endpoints() {
+    let (store, bus, _receivers) = setup().await;
+    let (alice, bob) = (user_principal("alice"), user_principal("bob"));
+    bus.create_group("team", "Team", "lead", vec!["lead".into(), alice.clone()]).await.unwrap();
+    let notice = send(&bus, Message::group("lead", "team", "Offsite moved to May")).await;
+    let secret = send(&bus, Message::group("lead", "beta", "Beta roadmap")).await;
+
+    let jwt_service = JwtService::new("test-secret");
+    let token = |id: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: id.to_string(),
+                username: id.to_string(),
+                name: id.to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: "Employee".to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    };
+    let (message_tx, _) = broadcast::channel(16);
+    let agent = Agent::new("lead", "Lead", Role::simple("Lead", "You lead"), LLMConfig::openai("k"));
+    let state = AppState::new(vec![agent], message_tx, store.clone(), jwt_service.clone()).with_message_bus(bus.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let url = format!("http://{}/api", addr);
+    let client = reqwest::Client::new();
+
+    let response = client.post(format!("{}/messages/{}/pin", url, notice)).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client
+        .post(format!("{}/messages/{}/pin", url, notice))
+        .bearer_auth(token("bob"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = client
+        .post(format!("{}/messages/missing/pin", url))
+        .bearer_auth(token("alice"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let body: Value = client
+        .post(format!("{}/messages/{}/pin", url, notice))
+        .bearer_auth(token("alice"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["pinned_by"], alice.as_str(), "{}", body);
+
+    let body: Value = client
+        .get(format!("{}/chat/team/pins", url))
+        .bearer_auth(token("alice"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"][0]["message"]["content"], "Offsite moved to May", "{}", body);
+    let response = client.get(format!("{}/chat/team/pins", url)).bearer_auth(token("bob")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    // 收藏不要求是会话成员，但只有自己能看到
+    for id in [&notice, &secret] {
+        let response = client
+            .post(format!("{}/messages/{}/bookmark", url, id))
+            .bearer_auth(token("bob"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+    }
+    let body: Value = client
+        .get(format!("{}/me/bookmarks", url))
+        .bearer_auth(token("bob"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"].as_array().unwrap().len(), 2, "{}", body);
+    let body: Value = client
+        .get(format!("{}/me/bookmarks", url))
+        .bearer_auth(token("alice"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(body["data"].as_array().unwrap().is_empty(), "{}", body);
+
+    let body: Value = client
+        .delete(format!("{}/messages/{}/bookmark", url, secret))
+        .bearer_auth(token("bob"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["changed"], true);
+    assert_eq!(store.load_bookmarks(&bob).await.unwrap().len(), 1);
+}
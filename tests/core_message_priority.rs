@@ -0,0 +1,137 @@
+//! 消息优先级测试
+
+use std::sync::Arc;
+
+use imitatort::core::messaging::{MessageBus, MessageReceiver, PriorityInbox, UrgentRateLimit};
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::domain::{Message, MessagePriority};
+use imitatort::infrastructure::store::SqliteStore;
+
+fn message(content: &str, priority: MessagePriority) -> Message {
+    Message::private("ceo", "dev", content).with_priority(priority)
+}
+
+#[test]
+fn test_priority_parse_and_default() {
+    assert_eq!(Message::private("a", "b", "hi").priority, MessagePriority::Normal);
+    assert_eq!(MessagePriority::parse("URGENT"), Some(MessagePriority::Urgent));
+    assert_eq!(MessagePriority::parse("sometime"), None);
+    assert!(MessagePriority::High.is_elevated());
+    assert!(!MessagePriority::Normal.is_elevated());
+    assert!(MessagePriority::Low < MessagePriority::Normal);
+}
+
+#[test]
+fn test_inbox_orders_by_priority_then_arrival() {
+    let mut inbox = PriorityInbox::new();
+    inbox.push(message("routine 1", MessagePriority::Normal));
+    inbox.push(message("fyi", MessagePriority::Low));
+    inbox.push(message("important", MessagePriority::High));
+    inbox.push(message("routine 2", MessagePriority::Normal));
+    inbox.push(message("stop the release", MessagePriority::Urgent));
+    assert!(inbox.has_elevated());
+    assert_eq!(inbox.len(), 5);
+
+    let order: Vec<String> = inbox.drain().into_iter().map(|m| m.content).collect();
+    assert_eq!(order, ["stop the release", "important", "routine 1", "routine 2", "fyi"]);
+    assert!(inbox.is_empty());
+    assert!(!inbox.has_elevated());
+}
+
+#[tokio::test]
+async fn test_inbox_fill_from_receiver() {
+    let bus = MessageBus::new();
+    let mut rx = MessageReceiver::new("dev".to_string(), bus.register("dev"));
+    for i in 0..30 {
+        bus.send(message(&format!("routine {}", i), MessagePriority::Normal)).await.unwrap();
+    }
+    bus.send(message("chairman says hi", MessagePriority::Urgent)).await.unwrap();
+
+    let mut inbox = PriorityInbox::new();
+    assert_eq!(inbox.fill(&mut rx), 31);
+    let messages = inbox.drain();
+    assert_eq!(messages[0].content, "chairman says hi");
+    assert_eq!(messages[1].content, "routine 0");
+}
+
+#[tokio::test]
+async fn test_urgent_messages_rate_limited_per_sender() {
+    let bus = MessageBus::new().with_urgent_rate_limit(UrgentRateLimit {
+        max_messages: 2,
+        window_secs: 60,
+    });
+    let mut rx = MessageReceiver::new("dev".to_string(), bus.register("dev"));
+    for _ in 0..3 {
+        bus.send(message("urgent", MessagePriority::Urgent)).await.unwrap();
+    }
+    bus.send(Message::private("cto", "dev", "urgent too").with_priority(MessagePriority::Urgent))
+        .await
+        .unwrap();
+
+    let priorities: Vec<MessagePriority> = std::iter::from_fn(|| rx.try_recv()).map(|m| m.priority).collect();
+    assert_eq!(
+        priorities,
+        [
+            MessagePriority::Urgent,
+            MessagePriority::Urgent,
+            MessagePriority::High,
+            MessagePriority::Urgent,
+        ]
+    );
+}
+
+async fn assert_round_trip(store: &dyn Store) {
+    store.save_message(&message("urgent", MessagePriority::Urgent)).await.unwrap();
+    store.save_message(&message("low", MessagePriority::Low)).await.unwrap();
+    store.save_message(&Message::private("ceo", "dev", "plain")).await.unwrap();
+
+    let loaded = store.load_messages(MessageFilter::new().limit(10)).await.unwrap();
+    let priority_of = |content: &str| loaded.iter().find(|m| m.content == content).unwrap().priority;
+    assert_eq!(priority_of("urgent"), MessagePriority::Urgent);
+    assert_eq!(priority_of("low"), MessagePriority::Low);
+    assert_eq!(priority_of("plain"), MessagePriority::Normal);
+}
+
+#[tokio::test]
+async fn test_priority_round_trip_sqlite() {
+    assert_round_trip(&SqliteStore::new_in_memory().unwrap()).await;
+}
+
+#[tokio::test]
+async fn test_priority_round_trip_memory() {
+    assert_round_trip(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn test_sqlite_migrates_messages_without_priority() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("old.db");
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE messages (
+                id TEXT PRIMARY KEY,
+                from_agent TEXT NOT NULL,
+                target_type TEXT NOT NULL,
+                target_id TEXT,
+                content TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                reply_to TEXT,
+                mentions TEXT,
+                metadata TEXT
+            );
+            INSERT INTO messages (id, from_agent, target_type, target_id, content, timestamp)
+            VALUES ('old-1', 'ceo', 'direct', 'dev', 'from before priorities', 1700000000);",
+        )
+        .unwrap();
+    }
+
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new(&db_path).unwrap());
+    let loaded = store.load_messages(MessageFilter::new().limit(10)).await.unwrap();
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].priority, MessagePriority::Normal);
+
+    store.save_message(&message("new", MessagePriority::High)).await.unwrap();
+    let loaded = store.load_messages(MessageFilter::new().limit(10)).await.unwrap();
+    assert!(loaded.iter().any(|m| m.content == "new" && m.priority == MessagePriority::High));
+}
@@ -1,6 +1,6 @@
 //! 消息通信层测试
 
-use imitatort::core::messaging::MessageBus;
+use imitatort::core::messaging::{MessageBus, MessageReceiver};
 use imitatort::domain::Message;
 
 #[test]
@@ -42,6 +42,121 @@ async fn test_group_messaging() {
         .await
         .unwrap();
 
-    // 群聊消息发送在集成测试中需要订阅者保持存活
-    // 这里仅验证群组创建成功
+    // 没有任何订阅者时发送也不报错
+    bus.send(Message::group("agent-1", "g1", "hi")).await.unwrap();
+}
+
+async fn setup_group(bus: &MessageBus) {
+    bus.create_group(
+        "g1",
+        "测试群",
+        "agent-1",
+        vec!["agent-1".to_string(), "agent-2".to_string(), "agent-3".to_string()],
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_group_message_delivered_to_unsubscribed_member() {
+    let bus = MessageBus::new();
+    let _rx1 = bus.register("agent-1");
+    let mut rx2 = MessageReceiver::new("agent-2".to_string(), bus.register("agent-2"));
+    setup_group(&bus).await;
+
+    // agent-2 没有订阅群聊，只通过私聊信箱收到
+    let msg = Message::group("agent-1", "g1", "offline hello");
+    bus.send(msg.clone()).await.unwrap();
+
+    let received = rx2.try_recv().unwrap();
+    assert_eq!(received.id, msg.id);
+    assert_eq!(received.content, "offline hello");
+    assert!(rx2.try_recv().is_none());
+}
+
+#[tokio::test]
+async fn test_group_message_delivered_once_to_subscribed_member() {
+    let bus = MessageBus::new();
+    let _rx1 = bus.register("agent-1");
+    let mut rx3 = MessageReceiver::new("agent-3".to_string(), bus.register("agent-3"));
+    setup_group(&bus).await;
+    rx3.join_group("g1", &bus).unwrap();
+
+    let first = Message::group("agent-1", "g1", "one");
+    let second = Message::group("agent-1", "g1", "two");
+    bus.send(first.clone()).await.unwrap();
+    bus.send(second.clone()).await.unwrap();
+
+    // 广播和信箱各到达一次，去重后每条只返回一次
+    let mut ids = Vec::new();
+    while let Some(msg) = rx3.try_recv() {
+        ids.push(msg.id);
+    }
+    ids.sort();
+    let mut expected = vec![first.id, second.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+}
+
+#[tokio::test]
+async fn test_full_mailbox_does_not_block_other_members() {
+    let bus = MessageBus::new();
+    let _rx1 = bus.register("agent-1");
+    // agent-2 从不读取信箱
+    let _rx2 = bus.register("agent-2");
+    let mut rx3 = MessageReceiver::new("agent-3".to_string(), bus.register("agent-3"));
+    setup_group(&bus).await;
+
+    let mut received = 0;
+    let send_all = async {
+        for i in 0..150 {
+            bus.send(Message::group("agent-1", "g1", format!("msg {}", i))).await.unwrap();
+            while rx3.try_recv().is_some() {
+                received += 1;
+            }
+        }
+    };
+    tokio::time::timeout(std::time::Duration::from_secs(5), send_all)
+        .await
+        .expect("group send blocked by a full mailbox");
+
+    assert_eq!(received, 150);
+    assert_eq!(bus.dropped_group_deliveries(), 50);
+}
+
+#[tokio::test]
+async fn test_group_message_skips_sender() {
+    let bus = MessageBus::new();
+    let mut rx1 = MessageReceiver::new("agent-1".to_string(), bus.register("agent-1"));
+    let mut rx2 = MessageReceiver::new("agent-2".to_string(), bus.register("agent-2"));
+    setup_group(&bus).await;
+    rx1.join_group("g1", &bus).unwrap();
+
+    bus.send(Message::group("agent-1", "g1", "hello")).await.unwrap();
+
+    assert!(rx1.try_recv().is_none());
+    assert!(rx2.recv().await.is_some());
+}
+
+#[tokio::test]
+async fn test_direct_message_to_user_principal() {
+    let bus = MessageBus::new();
+    let principal = imitatort::domain::user::user_principal("u-1");
+
+    // 没有存储且用户未注册时无法投递
+    assert!(bus.send(Message::private("agent-1", principal.as_str(), "Hi")).await.is_err());
+
+    let mut session_a = bus.subscribe_user(&principal);
+    let mut session_b = bus.subscribe_user(&principal);
+    bus.send(Message::private("agent-1", principal.as_str(), "Hi")).await.unwrap();
+    assert_eq!(session_a.recv().await.unwrap().content, "Hi");
+    assert_eq!(session_b.recv().await.unwrap().content, "Hi");
+
+    // 用户作为群成员同样收到群消息
+    let _agent_rx = bus.register("agent-1");
+    bus.create_group("g1", "Team", "agent-1", vec!["agent-1".to_string(), principal.clone()])
+        .await
+        .unwrap();
+    bus.send(Message::group("agent-1", "g1", "Standup")).await.unwrap();
+    assert_eq!(session_a.recv().await.unwrap().content, "Standup");
 }
@@ -0,0 +1,356 @@
+//! 输出内容审核测试：脚本化评审者的放行、改写、拦截和评审失败时退回关键词规则、拦截的原文进入隔离表并可复核、
+//! Agent 之间的内部消息和关闭审核的 Agent 跳过审核、多次拦截触发熔断并告警
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::application::autonomous::{AutonomousAgent, BREAKER_ALERT_SENDER};
+use imitatort::core::circuit_breaker::{
+    BreakerState, BreakerTransition, BreakerTrip, CircuitBreakerConfig, CircuitBreakerPolicy, CircuitBreakers,
+};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::moderation::{
+    is_internal_traffic, KeywordModerator, ModerationConfig, ModerationDecision, ModerationService, Moderator,
+    MODERATION_KEY, REDACTION,
+};
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::domain::user::User;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Message, Role};
+
+const START: i64 = 1_700_000_000;
+const ALICE: &str = "user:alice";
+
+/// 按顺序返回预设结论的评审者，`None` 表示评审失败
+struct ScriptedJudge {
+    script: Mutex<VecDeque<Option<ModerationDecision>>>,
+    calls: AtomicUsize,
+}
+
+impl ScriptedJudge {
+    fn new(script: Vec<Option<ModerationDecision>>) -> Arc<Self> {
+        Arc::new(Self {
+            script: Mutex::new(script.into()),
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl Moderator for ScriptedJudge {
+    fn name(&self) -> &str {
+        "judge"
+    }
+
+    async fn moderate(&self, _content: &str, _policy: &str) -> anyhow::Result<ModerationDecision> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        match self.script.lock().unwrap().pop_front() {
+            Some(Some(decision)) => Ok(decision),
+            Some(None) => anyhow::bail!("judge unavailable"),
+            None => Ok(ModerationDecision::Allow),
+        }
+    }
+}
+
+fn block(reason: &str) -> ModerationDecision {
+    ModerationDecision::Block { reason: reason.to_string() }
+}
+
+fn config() -> ModerationConfig {
+    ModerationConfig::enabled()
+        .with_policy("No leaked credentials or abuse.")
+        .with_keywords(vec!["exploit".to_string()], vec!["hunter2".to_string()])
+}
+
+#[test]
+fn test_keyword_rules_and_judge_reply_parsing() {
+    let rules = KeywordModerator::new(vec!["Exploit".to_string()], vec!["hunter2".to_string(), "密码".to_string()]);
+    assert_eq!(rules.check("All good here"), ModerationDecision::Allow);
+    assert!(matches!(rules.check("Here is an EXPLOIT for you"), ModerationDecision::Block { .. }));
+    assert_eq!(
+        rules.check("Password: Hunter2, 密码是 hunter2"),
+        ModerationDecision::Rewrite {
+            content: format!("Password: {r}, {r}是 {r}", r = REDACTION),
+            reason: "redacted \"hunter2\", \"密码\"".to_string(),
+        }
+    );
+
+    let parse = ModerationDecision::parse_judgement;
+    assert_eq!(parse("```json\n{\"decision\": \"allow\"}\n```").unwrap(), ModerationDecision::Allow);
+    assert_eq!(parse("{\"decision\": \"BLOCK\", \"reason\": \"abuse\"}").unwrap(), block("abuse"));
+    assert_eq!(
+        parse("{\"decision\": \"rewrite\", \"reason\": \"secret\", \"content\": \"fixed\"}").unwrap(),
+        ModerationDecision::Rewrite { content: "fixed".to_string(), reason: "secret".to_string() }
+    );
+    assert!(parse("{\"decision\": \"rewrite\", \"reason\": \"secret\"}").is_err());
+    assert!(parse("{\"decision\": \"maybe\"}").is_err());
+    assert!(parse("looks fine to me").is_err());
+}
+
+#[tokio::test]
+async fn test_scripted_judge_decisions_and_quarantine() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let judge = ScriptedJudge::new(vec![
+        Some(ModerationDecision::Allow),
+        Some(ModerationDecision::Rewrite { content: "The password is [redacted].".to_string(), reason: "credential".to_string() }),
+        Some(block("abusive language")),
+        None,
+    ]);
+    let service = ModerationService::new(config().with_notice("{agent} cannot share that reply."), store.clone())
+        .with_judge(judge.clone())
+        .with_clock(Arc::new(ManualClock::new(START)));
+
+    let allowed = service.review("Dev", Message::private("dev", ALICE, "Deploy finished."), false).await;
+    assert_eq!(allowed.decision, Some(ModerationDecision::Allow));
+    assert_eq!(allowed.message.content, "Deploy finished.");
+    assert!(!allowed.message.metadata.contains_key(MODERATION_KEY));
+
+    let rewritten = service.review("Dev", Message::private("dev", ALICE, "The password is hunter3."), false).await;
+    assert_eq!(rewritten.message.content, "The password is [redacted].");
+    assert_eq!(rewritten.message.metadata[MODERATION_KEY], "rewritten");
+    assert!(rewritten.quarantined.is_none());
+
+    let original = Message::private("dev", ALICE, "You are an idiot.");
+    let blocked = service.review("Dev", original.clone(), false).await;
+    assert!(blocked.is_blocked());
+    assert_eq!(blocked.message.id, original.id);
+    assert_eq!(blocked.message.to, original.to);
+    assert_eq!(blocked.message.content, "Dev cannot share that reply.");
+    assert_eq!(blocked.message.metadata[MODERATION_KEY], "blocked");
+
+    // 评审失败时退回关键词规则
+    let fallback = service.review("Dev", Message::private("dev", ALICE, "Try this exploit."), false).await;
+    assert!(fallback.is_blocked());
+    assert_eq!(judge.calls(), 4);
+
+    // 原文保存在隔离表中，最新的在前
+    let quarantined = store.load_quarantined_outputs(true, 10).await.unwrap();
+    assert_eq!(quarantined.len(), 2);
+    let abusive = quarantined.iter().find(|q| q.message.id == original.id).unwrap();
+    assert_eq!(abusive.message.content, "You are an idiot.");
+    assert_eq!((abusive.agent_id.as_str(), abusive.reason.as_str(), abusive.moderator.as_str()), ("dev", "abusive language", "judge"));
+    assert_eq!(abusive.quarantined_at, START);
+    let by_keyword = quarantined.iter().find(|q| q.moderator == "keyword").unwrap();
+    assert_eq!(by_keyword.message.content, "Try this exploit.");
+    assert_eq!(store.load_quarantined_output(&abusive.id).await.unwrap().unwrap().message.id, original.id);
+
+    // 未配置提示模板时使用内置提示
+    let judge = ScriptedJudge::new(vec![Some(block("abuse"))]);
+    let service = ModerationService::new(config(), store.clone()).with_judge(judge);
+    let blocked = service.review("Dev", Message::private("dev", ALICE, "..."), false).await;
+    assert!(blocked.message.content.contains("Dev") && blocked.message.content.contains("withheld"), "{}", blocked.message.content);
+}
+
+#[tokio::test]
+async fn test_internal_traffic_and_disabled_agents_skip_moderation() {
+    let store = Arc::new(MemoryStore::new());
+    let bus = MessageBus::with_store(store.clone());
+    let _dev_rx = bus.register("dev");
+    let _ops_rx = bus.register("ops");
+    bus.create_group("agents", "Agents", "dev", vec!["dev".to_string(), "ops".to_string()]).await.unwrap();
+    bus.create_group("support", "Support", "dev", vec!["dev".to_string(), ALICE.to_string()]).await.unwrap();
+
+    let to_ops = Message::private("dev", "ops", "Ship the exploit fix");
+    let to_alice = Message::private("dev", ALICE, "Ship the exploit fix");
+    assert!(is_internal_traffic(&to_ops, &bus).await);
+    assert!(!is_internal_traffic(&to_alice, &bus).await);
+    assert!(is_internal_traffic(&Message::group("dev", "agents", "hi"), &bus).await);
+    assert!(!is_internal_traffic(&Message::group("dev", "support", "hi"), &bus).await);
+    // 不是 Agent 的收件人（外部联系人）视为面向用户
+    assert!(!is_internal_traffic(&Message::private("dev", "stranger", "hi"), &bus).await);
+
+    let judge = ScriptedJudge::new(vec![Some(block("abuse")), Some(block("abuse"))]);
+    let service = ModerationService::new(config().with_agent("ops", false), store.clone()).with_judge(judge.clone());
+    let internal = service.review("Dev", to_ops.clone(), true).await;
+    assert_eq!(internal.decision, None);
+    assert_eq!(internal.message.content, to_ops.content);
+    let disabled = service.review("Ops", Message::private("ops", ALICE, "hi"), false).await;
+    assert_eq!(disabled.decision, None);
+    assert_eq!(judge.calls(), 0);
+    assert!(service.review("Dev", to_alice, false).await.is_blocked());
+
+    // 显式开启后也审核内部消息
+    let service = ModerationService::new(config().with_internal(true), store.clone()).with_judge(judge.clone());
+    assert!(service.review("Dev", to_ops, true).await.is_blocked());
+    assert_eq!(judge.calls(), 2);
+
+    // 只为单个 Agent 开启
+    let config = ModerationConfig::default().with_agent("dev", true);
+    assert!(config.is_active());
+    assert!(config.applies_to("dev") && !config.applies_to("ops"));
+}
+
+#[test]
+fn test_repeated_blocks_trip_breaker() {
+    let clock = Arc::new(ManualClock::new(START));
+    let policy = CircuitBreakerPolicy::default().with_moderation_blocks(2, 60);
+    let breakers = CircuitBreakers::new(CircuitBreakerConfig::new(policy)).with_clock(clock.clone());
+
+    assert_eq!(breakers.record_moderation_block("dev", "abuse"), None);
+    // 窗口外的拦截不计入
+    clock.advance(Duration::from_secs(61));
+    assert_eq!(breakers.record_moderation_block("dev", "abuse"), None);
+    clock.advance(Duration::from_secs(10));
+    assert_eq!(breakers.record_moderation_block("dev", "leak"), Some(BreakerTransition::Tripped));
+
+    let status = breakers.status("dev");
+    assert_eq!(status.state, BreakerState::Open);
+    assert_eq!(status.trip, Some(BreakerTrip::ModerationBlocks { blocks: 2, window_secs: 60 }));
+    assert_eq!(status.last_error.as_deref(), Some("leak"));
+    assert_eq!(breakers.record_moderation_block("dev", "leak"), None);
+}
+
+/// 收到用户消息时回复一句会被拦截的话
+async fn spawn_llm() -> String {
+    async fn completions(Json(body): Json<Value>) -> Json<Value> {
+        let prompt = body.to_string();
+        let decision = if prompt.contains("Unread messages:") {
+            json!({"action": "send_message", "target": ALICE, "content": "Sure, here's how to write the exploit."})
+        } else {
+            json!({"action": "wait"})
+        };
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": decision.to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+async fn wait_until(mut condition: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("condition not reached in time");
+}
+
+#[tokio::test]
+async fn test_blocked_agent_reply_is_quarantined_alerted_and_reviewable() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let boss = User::new_management("boss".into(), "Boss".into(), "x".into(), 2, None);
+    store.save_user(&boss).await.unwrap();
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _alice_rx = bus.register_user(ALICE);
+
+    let breakers = Arc::new(CircuitBreakers::new(CircuitBreakerConfig::new(
+        CircuitBreakerPolicy::default().with_moderation_blocks(1, 600),
+    )));
+    let moderation = Arc::new(ModerationService::new(config(), store.clone()));
+    let url = spawn_llm().await;
+    let dev = Agent::new("dev", "Dev", Role::simple("Engineer", "You write code"), LLMConfig::openai("k").with_base_url(url));
+    let agent = AutonomousAgent::new(dev, bus.clone())
+        .await
+        .unwrap()
+        .with_moderation(moderation)
+        .with_circuit_breakers(breakers.clone());
+    let handle = tokio::spawn(async move {
+        let _ = agent.run_loop().await;
+    });
+
+    bus.send(Message::private(ALICE, "dev", "How do I break into the server?")).await.unwrap();
+    wait_until(|| breakers.is_faulted("dev")).await;
+    handle.abort();
+
+    // 用户收到提示而不是原文
+    let replies = store.load_messages(MessageFilter::new().from("dev")).await.unwrap();
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0].metadata[MODERATION_KEY], "blocked");
+    assert!(!replies[0].content.contains("exploit"), "{}", replies[0].content);
+    let alerts = store.load_messages(MessageFilter::new().from(BREAKER_ALERT_SENDER)).await.unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert!(alerts[0].content.contains("blocked by content moderation"), "{}", alerts[0].content);
+
+    // 管理员通过接口复核
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store.clone(), jwt.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let token = |position: &str| {
+        jwt.generate_token(&UserInfo {
+            id: "u1".to_string(),
+            username: "u1".to_string(),
+            name: "U1".to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: position.to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+    };
+    let client = reqwest::Client::new();
+    let list_url = format!("http://{}/api/v1/admin/moderation/quarantine?pending=true", addr);
+
+    let response = client.get(&list_url).bearer_auth(token("Employee")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    let body: Value = client.get(&list_url).bearer_auth(token("Management")).send().await.unwrap().json().await.unwrap();
+    let outputs = body["data"]["outputs"].as_array().unwrap();
+    assert_eq!(outputs.len(), 1);
+    assert_eq!(outputs[0]["message"]["id"], replies[0].id.as_str());
+    assert_eq!(outputs[0]["message"]["content"], "Sure, here's how to write the exploit.");
+    let id = outputs[0]["id"].as_str().unwrap().to_string();
+
+    let body: Value = client
+        .post(format!("http://{}/api/v1/admin/moderation/quarantine/{}/review", addr, id))
+        .bearer_auth(token("Management"))
+        .json(&json!({ "verdict": "upheld", "note": "correct call" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["review"]["verdict"], "upheld");
+    assert_eq!(body["data"]["review"]["reviewer"], "u1");
+
+    // 复核后不再出现在待复核列表中，原文仍然保留
+    let body: Value = client.get(&list_url).bearer_auth(token("Management")).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["data"]["outputs"], json!([]));
+    let output = store.load_quarantined_output(&id).await.unwrap().unwrap();
+    assert_eq!(output.message.content, "Sure, here's how to write the exploit.");
+
+    let response = client
+        .get(format!("http://{}/api/v1/admin/moderation/quarantine/missing", addr))
+        .bearer_auth(token("Management"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,248 @@
+//! 插话测试：工具循环中的插话在下一轮 LLM 请求前作为 user 消息注入并记入追踪、多条插话按顺序排队、
+//! cancel 插话结束剩余的工具调用、不在工具循环中的插话退回给调用方转为 Urgent 消息
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::nudge::{Nudge, NudgeSlots, NUDGE_KEY};
+use imitatort::core::skill::SkillManager;
+use imitatort::core::store::{MessageFilter, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::tool_view::AgentToolView;
+use imitatort::domain::MessagePriority;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::{Agent, LLMConfig, Message, Organization, Role};
+
+const ALICE: &str = "user:alice";
+
+#[test]
+fn test_slots_only_accept_nudges_during_tool_loop() {
+    let slots = NudgeSlots::new();
+    let rejected = slots.offer("dev", Nudge::new(ALICE, "first")).unwrap_err();
+    assert_eq!(rejected.content, "first");
+
+    slots.set_tool_loop("dev", true);
+    assert!(slots.in_tool_loop("dev"));
+    assert_eq!(slots.offer("dev", Nudge::new(ALICE, "first")), Ok(1));
+    assert_eq!(slots.offer("dev", Nudge::new(ALICE, "second")), Ok(2));
+    let taken: Vec<String> = slots.take("dev").into_iter().map(|n| n.content).collect();
+    assert_eq!(taken, vec!["first", "second"]);
+    assert!(slots.take("dev").is_empty());
+
+    // 工具循环结束时退回还没注入的插话
+    slots.offer("dev", Nudge::new(ALICE, "late")).unwrap();
+    let leftover = slots.set_tool_loop("dev", false);
+    assert_eq!(leftover.len(), 1);
+    assert!(!slots.in_tool_loop("dev"));
+
+    let message = leftover.into_iter().next().unwrap().into_message("dev");
+    assert_eq!(message.priority, MessagePriority::Urgent);
+    assert_eq!(message.from, ALICE);
+    assert!(message.metadata(NUDGE_KEY).is_some());
+}
+
+#[test]
+fn test_cancel_prompt() {
+    assert_eq!(Nudge::new(ALICE, "Use the EU data").prompt(), "[Nudge from user:alice] Use the EU data");
+    let cancel = Nudge::new(ALICE, "").with_cancel(true).prompt();
+    assert!(cancel.starts_with("[Nudge from user:alice] Stop calling tools"), "{}", cancel);
+}
+
+struct MockLlm {
+    requests: Mutex<Vec<Value>>,
+    /// 回复 Alice 的是第几次请求
+    replied_at: Mutex<Option<usize>>,
+    slots: Arc<NudgeSlots>,
+    /// 第 2 次带工具的请求时放入的插话
+    nudges: Vec<Nudge>,
+}
+
+/// 前 4 次带工具的请求都调用 `time.now`，之后（或不带工具时）回复 Alice，回复后只等待；第 2 次请求时插话
+async fn spawn_llm(slots: Arc<NudgeSlots>, nudges: Vec<Nudge>) -> (String, Arc<MockLlm>) {
+    async fn completions(State(mock): State<Arc<MockLlm>>, Json(body): Json<Value>) -> Json<Value> {
+        let (count, tool_rounds) = {
+            let mut requests = mock.requests.lock().unwrap();
+            requests.push(body.clone());
+            (requests.len(), requests.iter().filter(|r| r.get("tools").is_some()).count())
+        };
+        let mut replied_at = mock.replied_at.lock().unwrap();
+        let has_tools = body.get("tools").is_some();
+        if has_tools && tool_rounds == 2 {
+            for nudge in &mock.nudges {
+                mock.slots.offer("dev", nudge.clone()).unwrap();
+            }
+        }
+        let message = if replied_at.is_some() {
+            json!({ "role": "assistant", "content": json!({"action": "wait"}).to_string() })
+        } else if has_tools && tool_rounds <= 4 {
+            json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{
+                    "id": format!("call_{}", tool_rounds),
+                    "type": "function",
+                    "function": {"name": "time__now", "arguments": "{}"}
+                }]
+            })
+        } else {
+            *replied_at = Some(count);
+            let content = if has_tools { "Finished" } else { "Finished early" };
+            let decision = json!({"action": "send_message", "target": ALICE, "content": content});
+            json!({ "role": "assistant", "content": decision.to_string() })
+        };
+        drop(replied_at);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{ "index": 0, "message": message, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let mock = Arc::new(MockLlm {
+        requests: Mutex::new(Vec::new()),
+        replied_at: Mutex::new(None),
+        slots,
+        nudges,
+    });
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(mock.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (format!("http://{}", addr), mock)
+}
+
+struct Run {
+    requests: Vec<Value>,
+    replied_at: Option<usize>,
+    events: Vec<CompanyEvent>,
+    replies: Vec<Message>,
+}
+
+/// 启动带 `time.now` 工具的 Agent，Alice 发出请求后等到 Agent 回复
+async fn run(nudges: Vec<Nudge>) -> Run {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _alice_rx = bus.register_user(ALICE);
+    let slots = Arc::new(NudgeSlots::new());
+    let events = Arc::new(EventBus::new());
+    let mut events_rx = events.subscribe();
+
+    let registry = Arc::new(ToolRegistry::new());
+    let env = ToolEnvironment::new(bus.clone(), Arc::new(RwLock::new(Organization::new())), registry.clone(), store.clone());
+    let view = Arc::new(AgentToolView::new(
+        env.tool_provider.clone(),
+        Arc::new(SkillManager::new_with_tool_registry(registry)),
+    ));
+    let executor = Arc::new(FrameworkToolExecutor::new(env));
+
+    let (url, mock) = spawn_llm(slots.clone(), nudges).await;
+    let dev = Agent::new("dev", "Dev", Role::simple("Engineer", "You write code"), LLMConfig::openai("sk-test").with_base_url(url));
+    let agent = AutonomousAgent::new(dev, bus.clone())
+        .await
+        .unwrap()
+        .with_tools(view, executor)
+        .with_events(events)
+        .with_nudges(slots);
+    let handle = tokio::spawn(async move {
+        let _ = agent.run_loop().await;
+    });
+
+    bus.send(Message::private(ALICE, "dev", "Please run the report")).await.unwrap();
+    let mut replies = Vec::new();
+    for _ in 0..100 {
+        replies = store.load_messages(MessageFilter::new().from("dev")).await.unwrap();
+        if !replies.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    handle.abort();
+
+    let mut collected = Vec::new();
+    while let Ok(event) = events_rx.try_recv() {
+        collected.push(event);
+    }
+    let requests = mock.requests.lock().unwrap().clone();
+    let replied_at = *mock.replied_at.lock().unwrap();
+    Run { requests, replied_at, events: collected, replies }
+}
+
+/// 请求中的 user 消息内容
+fn user_messages(request: &Value) -> Vec<String> {
+    request["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|m| m["role"] == "user")
+        .map(|m| m["content"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_nudges_are_injected_before_next_round() {
+    let run = run(vec![Nudge::new(ALICE, "Use the EU data"), Nudge::new(ALICE, "And keep it short")]).await;
+
+    // 4 轮工具调用后回复；插话在第 2 轮之后，只出现在第 3 轮请求中，位于提示词之后并保持顺序
+    assert_eq!(run.replies.len(), 1);
+    assert_eq!(run.replies[0].content, "Finished");
+    assert_eq!(run.replied_at, Some(5));
+    for (round, request) in run.requests.iter().take(5).enumerate() {
+        assert!(request.get("tools").is_some());
+        let messages = user_messages(request);
+        if round == 2 {
+            assert_eq!(messages.len(), 3, "{:?}", messages);
+            assert_eq!(messages[1], "[Nudge from user:alice] Use the EU data");
+            assert_eq!(messages[2], "[Nudge from user:alice] And keep it short");
+        } else {
+            assert_eq!(messages.len(), 1, "round {}: {:?}", round + 1, messages);
+        }
+    }
+
+    // 注入记入第 3 轮的追踪
+    let turns: Vec<&str> = run
+        .events
+        .iter()
+        .filter_map(|e| match e {
+            CompanyEvent::AgentTurnCompleted { trace_id, .. } => Some(trace_id.as_ref()),
+            _ => None,
+        })
+        .collect();
+    let injected: Vec<(&str, &str)> = run
+        .events
+        .iter()
+        .filter_map(|e| match e {
+            CompanyEvent::NudgeInjected { trace_id, nudge, .. } => Some((trace_id.as_ref(), nudge.content.as_str())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(injected, vec![(turns[2], "Use the EU data"), (turns[2], "And keep it short")]);
+}
+
+#[tokio::test]
+async fn test_cancel_nudge_finishes_without_more_tool_calls() {
+    let run = run(vec![Nudge::new(ALICE, "").with_cancel(true)]).await;
+
+    // 第 3 轮不再提供工具，Agent 用已有结果收尾
+    assert_eq!(run.replied_at, Some(3));
+    assert!(run.requests[..2].iter().all(|r| r.get("tools").is_some()));
+    let finishing = &run.requests[2];
+    assert!(finishing.get("tools").is_none());
+    let messages = user_messages(finishing);
+    assert_eq!(messages.len(), 2);
+    assert!(messages[1].contains("Stop calling tools"), "{}", messages[1]);
+    assert_eq!(run.replies.len(), 1);
+    assert_eq!(run.replies[0].content, "Finished early");
+}
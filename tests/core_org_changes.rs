@@ -0,0 +1,223 @@
+//! 组织架构变更记录测试：差异计算、保存时记录、查询接口和 Agent 工具
+
+use std::sync::Arc;
+
+use imitatort::core::org_changes::{save_organization_tracked, SYSTEM_ACTOR};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::messaging::MessageBus;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{ChangeKind, EntityChange, FieldChange, OrgChangeEntry, OrgDiff};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, Department, LLMConfig, Organization, Role};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+fn agent(id: &str, dept: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Engineer", "You write code"), LLMConfig::openai("sk-secret")).with_department(dept)
+}
+
+fn base_org() -> Organization {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    org.add_department(Department::top_level("ops", "Operations"));
+    org.add_agent(agent("alice", "eng"));
+    org.add_agent(agent("bob", "eng"));
+    org
+}
+
+fn ids(changes: &[EntityChange], kind: ChangeKind) -> Vec<&str> {
+    changes.iter().filter(|c| c.kind == kind).map(|c| c.id.as_str()).collect()
+}
+
+#[test]
+fn test_reordering_is_not_a_change() {
+    let before = base_org();
+    let mut after = base_org();
+    after.agents.reverse();
+    after.departments.reverse();
+    assert!(OrgDiff::between(&before, &after).is_empty());
+}
+
+#[test]
+fn test_agents_added_removed_and_modified() {
+    let before = base_org();
+    let mut after = base_org();
+    after.agents.retain(|a| a.id != "bob");
+    after.add_agent(agent("carol", "ops"));
+    let alice = after.agents.iter_mut().find(|a| a.id == "alice").unwrap();
+    alice.department_id = Some("ops".to_string());
+    alice.role.title = "Lead".to_string();
+
+    let diff = OrgDiff::between(&before, &after);
+    assert!(diff.departments.is_empty());
+    assert_eq!(ids(&diff.agents, ChangeKind::Added), vec!["carol"]);
+    assert_eq!(ids(&diff.agents, ChangeKind::Removed), vec!["bob"]);
+    assert_eq!(ids(&diff.agents, ChangeKind::Modified), vec!["alice"]);
+
+    let modified = diff.agents.iter().find(|c| c.kind == ChangeKind::Modified).unwrap();
+    assert_eq!(
+        modified.fields,
+        vec![
+            FieldChange { field: "department_id".to_string(), before: json!("eng"), after: json!("ops") },
+            FieldChange { field: "role.title".to_string(), before: json!("Engineer"), after: json!("Lead") },
+        ]
+    );
+}
+
+#[test]
+fn test_departments_added_removed_and_modified() {
+    let before = base_org();
+    let mut after = base_org();
+    after.departments.retain(|d| d.id != "ops");
+    after.add_department(Department::child("platform", "Platform", "eng"));
+    after.departments.iter_mut().find(|d| d.id == "eng").unwrap().leader_id = Some("alice".to_string());
+
+    let diff = OrgDiff::between(&before, &after);
+    assert!(diff.agents.is_empty());
+    assert_eq!(ids(&diff.departments, ChangeKind::Added), vec!["platform"]);
+    assert_eq!(ids(&diff.departments, ChangeKind::Removed), vec!["ops"]);
+    let modified = diff.departments.iter().find(|c| c.kind == ChangeKind::Modified).unwrap();
+    assert_eq!(
+        modified.fields,
+        vec![FieldChange { field: "leader_id".to_string(), before: Value::Null, after: json!("alice") }]
+    );
+}
+
+#[test]
+fn test_api_keys_never_recorded() {
+    let before = base_org();
+    let mut after = base_org();
+    after.agents[0].llm_config = LLMConfig::openai("sk-rotated");
+    assert!(OrgDiff::between(&before, &after).is_empty());
+
+    after.agents[0].llm_config.model = "custom-model".to_string();
+    let diff = OrgDiff::between(&before, &after);
+    let text = serde_json::to_string(&diff).unwrap();
+    assert!(!text.contains("sk-"), "{}", text);
+    assert_eq!(diff.agents[0].fields.len(), 1);
+    assert_eq!(diff.agents[0].fields[0].field, "llm_config.model");
+}
+
+async fn assert_bulk_save_records_changes(store: &dyn Store) {
+    // 首次保存：全部记为新增
+    let first = save_organization_tracked(store, &base_org(), SYSTEM_ACTOR).await.unwrap().unwrap();
+    assert_eq!(first.actor, SYSTEM_ACTOR);
+    assert_eq!(ids(&first.diff.agents, ChangeKind::Added), vec!["alice", "bob"]);
+    assert_eq!(ids(&first.diff.departments, ChangeKind::Added), vec!["eng", "ops"]);
+
+    // 内容未变（仅顺序不同）时不记录
+    let mut reordered = base_org();
+    reordered.agents.reverse();
+    assert!(save_organization_tracked(store, &reordered, "admin").await.unwrap().is_none());
+
+    let mut grown = base_org();
+    grown.add_agent(agent("carol", "ops"));
+    let second = save_organization_tracked(store, &grown, "admin").await.unwrap().unwrap();
+    assert_eq!(second.diff.agents, vec![EntityChange { id: "carol".to_string(), kind: ChangeKind::Added, fields: vec![] }]);
+    assert!(store.load_organization().await.unwrap().find_agent("carol").is_some());
+
+    let changes = store.load_org_changes(0, 10).await.unwrap();
+    assert_eq!(changes.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec![second.id.as_str(), first.id.as_str()]);
+    assert_eq!(changes[0], second);
+    assert_eq!(store.load_org_changes(0, 1).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_bulk_save_records_changes_memory_store() {
+    assert_bulk_save_records_changes(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn test_bulk_save_records_changes_sqlite_store() {
+    assert_bulk_save_records_changes(&SqliteStore::new_in_memory().unwrap()).await;
+}
+
+#[tokio::test]
+async fn test_changes_since_filter() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    for (timestamp, id) in [(100, "a"), (200, "b"), (300, "c")] {
+        let diff = OrgDiff {
+            agents: vec![EntityChange { id: id.to_string(), kind: ChangeKind::Added, fields: vec![] }],
+            departments: vec![],
+        };
+        store.save_org_change(&OrgChangeEntry::new("admin", diff).with_timestamp(timestamp)).await.unwrap();
+    }
+    let changes = store.load_org_changes(100, 10).await.unwrap();
+    let agents: Vec<&str> = changes.iter().map(|c| c.diff.agents[0].id.as_str()).collect();
+    assert_eq!(agents, vec!["c", "b"]);
+}
+
+#[tokio::test]
+async fn test_recent_changes_tool() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    save_organization_tracked(store.as_ref(), &base_org(), SYSTEM_ACTOR).await.unwrap();
+    let env = ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(base_org())),
+        Arc::new(ToolRegistry::new()),
+        store,
+    );
+    let executor = FrameworkToolExecutor::new(env);
+
+    let result = executor
+        .execute("org.get_recent_changes", json!({}), &ToolCallContext::new("alice"))
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["count"], 1);
+    assert_eq!(result.data["changes"][0]["agents"][0], json!({ "id": "alice", "kind": "added" }));
+
+    // since 在最后一次变更之后时没有结果
+    let result = executor
+        .execute("org.get_recent_changes", json!({ "since": i64::MAX / 2 }), &ToolCallContext::new("alice"))
+        .await
+        .unwrap();
+    assert_eq!(result.data["count"], 0);
+}
+
+#[tokio::test]
+async fn test_changes_endpoint_requires_admin() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    save_organization_tracked(store.as_ref(), &base_org(), SYSTEM_ACTOR).await.unwrap();
+    let jwt_service = JwtService::new("test-secret");
+    let token = |position: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: "u1".to_string(),
+                username: "u1".to_string(),
+                name: "U1".to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: position.to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    };
+    let (admin, employee) = (token("Management"), token("Employee"));
+
+    let (message_tx, _) = broadcast::channel(16);
+    let app = create_router(Arc::new(AppState::new(vec![], message_tx, store, jwt_service.clone())));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+    let url = format!("http://{}/api/v1/org/changes?since=0&limit=5", addr);
+
+    let response = client.get(&url).bearer_auth(&employee).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = client.get(&url).bearer_auth(&admin).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    let changes = body["data"]["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0]["actor"], SYSTEM_ACTOR);
+    assert_eq!(changes[0]["departments"].as_array().unwrap().len(), 2);
+}
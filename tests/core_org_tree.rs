@@ -0,0 +1,177 @@
+//! 部门树测试：三级部门不论声明顺序都完整嵌套、负责人排在成员最前、缓存命中时不读取存储、
+//! OrgChanged 事件、组织架构接口和思过崖线调整使缓存失效
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::application::organization::guilty_line::GuiltyLineManager;
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::org_changes::save_organization_tracked;
+use imitatort::core::org_tree::{build_org_tree, OrgTreeProjection};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::domain::user::Position;
+use imitatort::domain::{Agent, Department, LLMConfig, Organization, Role};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+fn agent(id: &str, title: &str, dept: &str) -> Agent {
+    Agent::new(id, id, Role::simple(title, "You work here"), LLMConfig::openai("test-key")).with_department(dept)
+}
+
+/// eng → backend → api 三级部门；`children_first` 时下级部门先于上级声明
+fn create_org(children_first: bool) -> Organization {
+    let mut departments = vec![
+        Department::top_level("eng", "Engineering"),
+        Department::child("backend", "Backend", "eng"),
+        Department::child("api", "API", "backend"),
+    ];
+    if children_first {
+        departments.reverse();
+    }
+    let mut org = Organization::new();
+    for dept in departments {
+        org.add_department(dept);
+    }
+    org.add_department(Department::top_level("support", "Support"));
+    org.add_agent(agent("dev", "Engineer", "api"));
+    org.add_agent(agent("lead", "技术主管", "api"));
+    org.add_agent(agent("cto", "CTO", "eng"));
+    org
+}
+
+fn names(nodes: &Value) -> Vec<&str> {
+    nodes.as_array().unwrap().iter().map(|n| n["name"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn test_three_level_hierarchy_is_fully_nested() {
+    for children_first in [false, true] {
+        let tree = build_org_tree(&create_org(children_first));
+        assert_eq!(names(&tree), vec!["Engineering", "Support"], "children_first={}", children_first);
+
+        let backend = &tree[0]["children"][0];
+        assert_eq!(backend["id"], "backend");
+        assert_eq!(backend["parentId"], "eng");
+        let api = &backend["children"][0];
+        assert_eq!(api["id"], "api", "grandchild missing (children_first={}): {}", children_first, tree);
+        assert_eq!(api["children"], Value::Array(vec![]));
+
+        assert_eq!(api["memberCount"], 2);
+        assert_eq!(api["leader"]["id"], "lead");
+        assert_eq!(names(&api["users"]), vec!["lead", "dev"]);
+        assert_eq!(api["users"][1]["status"], "online");
+        assert_eq!(tree[0]["leader"], Value::Null);
+        assert_eq!(tree[0]["memberCount"], 1);
+    }
+
+    // 循环引用的部门不是根部门，也不会导致无限递归
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("root", "Root"));
+    org.add_department(Department::child("a", "A", "b"));
+    org.add_department(Department::child("b", "B", "a"));
+    assert_eq!(names(&build_org_tree(&org)), vec!["Root"]);
+}
+
+#[tokio::test]
+async fn test_projection_serves_cache_until_invalidated() {
+    let store = MemoryStore::new();
+    store.save_organization(&create_org(false)).await.unwrap();
+    let projection = Arc::new(OrgTreeProjection::new());
+
+    let first = projection.tree(&store).await.unwrap();
+    assert_eq!(names(&first), vec!["Engineering", "Support"]);
+
+    // 绕过事件直接改写存储：缓存命中时不读取存储，结果保持不变
+    let mut org = create_org(false);
+    org.add_department(Department::top_level("sales", "Sales"));
+    store.save_organization(&org).await.unwrap();
+    for _ in 0..1000 {
+        let tree = projection.tree(&store).await.unwrap();
+        assert!(Arc::ptr_eq(&tree, &first));
+    }
+    assert_eq!(projection.builds(), 1);
+
+    projection.invalidate();
+    assert_eq!(names(&projection.tree(&store).await.unwrap()), vec!["Engineering", "Support", "Sales"]);
+    assert_eq!(projection.builds(), 2);
+    // 组织架构与部门树共用同一份缓存
+    assert_eq!(projection.organization(&store).await.unwrap().departments.len(), 5);
+    assert_eq!(projection.builds(), 2);
+
+    // OrgChanged 事件使缓存失效
+    let events = EventBus::new();
+    let mut received = events.subscribe();
+    projection.clone().spawn(&events);
+    org.departments.retain(|d| d.id != "support");
+    let entry = save_organization_tracked(&store, &org, "admin").await.unwrap().unwrap();
+    events.emit(CompanyEvent::OrgChanged { entry: Arc::new(entry) });
+    received.recv().await.unwrap();
+    let mut tree = projection.tree(&store).await.unwrap();
+    for _ in 0..50 {
+        if names(&tree) == vec!["Engineering", "Sales"] {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        tree = projection.tree(&store).await.unwrap();
+    }
+    assert_eq!(names(&tree), vec!["Engineering", "Sales"]);
+    assert_eq!(projection.builds(), 3);
+}
+
+#[tokio::test]
+async fn test_guilty_line_updates_invalidate_projection() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let mut org = create_org(false);
+    org.add_department(Department::top_level("guilty_line", "Cliff of Contemplation Line"));
+    store.save_organization(&org).await.unwrap();
+    let projection = Arc::new(OrgTreeProjection::new());
+    let manager = GuiltyLineManager::new(store.clone()).with_org_tree(projection.clone());
+
+    let before = projection.tree(store.as_ref()).await.unwrap();
+    assert_eq!(before[2]["memberCount"], 0);
+
+    manager.add_user_to_guilty_line("dev", &Position::Management).await.unwrap();
+    let after = projection.tree(store.as_ref()).await.unwrap();
+    assert_eq!(names(&after), vec!["Engineering", "Support", "Cliff of Contemplation Line"]);
+    assert_eq!(after[2]["users"][0]["id"], "dev");
+    assert_eq!(projection.builds(), 2);
+    // 变更同时写入变更记录
+    assert_eq!(store.load_org_changes(0, 10).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_org_tree_endpoint_uses_shared_projection() {
+    let store = Arc::new(MemoryStore::new());
+    store.save_organization(&create_org(true)).await.unwrap();
+    let projection = Arc::new(OrgTreeProjection::new());
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store.clone(), JwtService::new("secret"))
+        .with_org_tree(projection.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = reqwest::Client::new();
+    let fetch = || async {
+        let response = client.get(format!("http://{}/api/org/tree", addr)).send().await.unwrap();
+        assert!(response.status().is_success());
+        response.json::<Value>().await.unwrap()
+    };
+    let body = fetch().await;
+    assert_eq!(body["success"], true);
+    assert_eq!(body["data"][0]["children"][0]["children"][0]["id"], "api");
+
+    store.save_organization(&Organization::new()).await.unwrap();
+    for _ in 0..10 {
+        assert_eq!(fetch().await, body);
+    }
+    assert_eq!(projection.builds(), 1);
+
+    projection.invalidate();
+    assert_eq!(fetch().await["data"], Value::Array(vec![]));
+}
@@ -0,0 +1,189 @@
+//! 主动问候测试：组织变更只送达持有策略的同事、重复事件去重、限流、持久化策略和入职周年
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::org_changes::{save_organization_tracked, SYSTEM_ACTOR};
+use imitatort::core::proactive::{
+    ProactiveConfig, ProactiveDispatcher, ProactivePolicy, ProactiveTrigger, PROACTIVE_SENDER, PROACTIVE_TRIGGER_KEY,
+};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::tasks::TaskManager;
+use imitatort::domain::{Agent, Department, LLMConfig, Message, MessageTarget, Organization, Role, Task};
+use tokio::sync::mpsc;
+
+const START: i64 = 1_700_000_000;
+const DAY: i64 = 24 * 3600;
+
+fn agent(id: &str, dept: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Engineer", "You write code"), LLMConfig::openai("sk-test")).with_department(dept)
+}
+
+fn base_org() -> Organization {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    org.add_department(Department::top_level("ops", "Operations"));
+    org.add_agent(agent("alice", "eng"));
+    org.add_agent(agent("bob", "eng"));
+    org.add_agent(agent("carol", "ops"));
+    org
+}
+
+fn welcome_policy() -> ProactivePolicy {
+    ProactivePolicy::new().with_template(
+        ProactiveTrigger::TeammateJoined,
+        "{{teammate_name}} just joined {{department}} as {{teammate_role}}. Say hello, {{agent_name}}.",
+    )
+}
+
+fn drain(rx: &mut mpsc::Receiver<Message>) -> Vec<Message> {
+    let mut messages = Vec::new();
+    while let Ok(message) = rx.try_recv() {
+        messages.push(message);
+    }
+    messages
+}
+
+#[tokio::test]
+async fn test_org_change_reaches_only_policy_holding_teammates() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let events = Arc::new(EventBus::new());
+    let bus = Arc::new(MessageBus::with_store(store.clone()).with_events(events.clone()));
+    let mut alice = bus.register("alice");
+    let mut bob = bus.register("bob");
+    let mut carol = bus.register("carol");
+    let mut dave = bus.register("dave");
+
+    // carol 也有策略，但她不在新同事的部门
+    let config = ProactiveConfig::default()
+        .with_policy("alice", welcome_policy())
+        .with_policy("carol", welcome_policy());
+    let dispatcher = Arc::new(ProactiveDispatcher::new(config, store.clone()).with_message_bus(bus.clone()));
+    dispatcher.clone().spawn(&events);
+
+    save_organization_tracked(store.as_ref(), &base_org(), SYSTEM_ACTOR).await.unwrap();
+    let mut org = base_org();
+    org.add_agent(agent("dave", "eng"));
+    let entry = save_organization_tracked(store.as_ref(), &org, "admin").await.unwrap().unwrap();
+    let event = CompanyEvent::OrgChanged { entry: Arc::new(entry) };
+    events.emit(event.clone());
+
+    let greeting = tokio::time::timeout(Duration::from_secs(2), alice.recv()).await.unwrap().unwrap();
+    assert_eq!(greeting.from, PROACTIVE_SENDER);
+    assert_eq!(greeting.content, "dave just joined Engineering as Engineer. Say hello, alice.");
+    assert_eq!(greeting.metadata.get("kind").map(String::as_str), Some("proactive"));
+    assert_eq!(greeting.metadata.get(PROACTIVE_TRIGGER_KEY).map(String::as_str), Some("teammate_joined"));
+
+    // 同一事件再次出现不会再问候
+    events.emit(event);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(drain(&mut alice).is_empty());
+    assert!(drain(&mut bob).is_empty());
+    assert!(drain(&mut carol).is_empty());
+    assert!(drain(&mut dave).is_empty());
+}
+
+#[tokio::test]
+async fn test_rate_cap_and_persisted_policy() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    store.save_organization(&base_org()).await.unwrap();
+    let clock = Arc::new(ManualClock::new(START));
+    let config = ProactiveConfig::default().with_rate_cap(2, DAY).with_policy(
+        "bob",
+        ProactivePolicy::new().with_template(ProactiveTrigger::TaskAssigned, "New task: {{task_title}}"),
+    );
+    let dispatcher = ProactiveDispatcher::new(config, store.clone()).with_clock(clock.clone());
+
+    let assigned = |title: &str| CompanyEvent::TaskAssigned {
+        task: Arc::new(Task::new(title, "alice", START).with_assignee("bob")),
+        assignee: "bob".into(),
+        actor: "alice".into(),
+    };
+    assert_eq!(dispatcher.handle(&assigned("one")).await.len(), 1);
+    assert_eq!(dispatcher.handle(&assigned("two")).await.len(), 1);
+    assert!(dispatcher.handle(&assigned("three")).await.is_empty(), "third check-in exceeds the cap");
+
+    clock.advance(Duration::from_secs(DAY as u64));
+    let sent = dispatcher.handle(&assigned("four")).await;
+    assert_eq!(sent[0].content, "New task: four");
+
+    // 持久化的策略优先于配置；停用后不再产生消息
+    let persisted = ProactivePolicy::new().with_template(ProactiveTrigger::TaskAssigned, "Please look at {{task_id}}");
+    dispatcher.save_policy("bob", &persisted).await.unwrap();
+    let sent = dispatcher.handle(&assigned("five")).await;
+    assert!(sent[0].content.starts_with("Please look at "));
+    dispatcher.save_policy("bob", &ProactivePolicy { enabled: false, ..persisted }).await.unwrap();
+    assert!(dispatcher.handle(&assigned("six")).await.is_empty());
+    dispatcher.delete_policy("bob").await.unwrap();
+    assert_eq!(dispatcher.policy("bob").await.unwrap().unwrap().template(ProactiveTrigger::TaskAssigned), Some("New task: {{task_title}}"));
+
+    // 负责人不是 Agent 时没有接收者
+    let for_user = CompanyEvent::TaskAssigned {
+        task: Arc::new(Task::new("review", "alice", START).with_assignee("user:1")),
+        assignee: "user:1".into(),
+        actor: "alice".into(),
+    };
+    assert!(dispatcher.handle(&for_user).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_work_anniversary_is_announced_once_per_year() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let mut org = base_org();
+    org.agents.retain(|a| a.id != "bob");
+    org.add_agent(agent("bob", "eng").with_created_at(START - 2 * 365 * DAY));
+    store.save_organization(&org).await.unwrap();
+
+    let clock = Arc::new(ManualClock::new(START));
+    let config = ProactiveConfig::default().with_policy(
+        "alice",
+        ProactivePolicy::new().with_template(
+            ProactiveTrigger::WorkAnniversary,
+            "{{teammate_name}} has been here {{years}} years today.",
+        ),
+    );
+    let dispatcher = ProactiveDispatcher::new(config, store.clone()).with_clock(clock.clone());
+
+    let sent = dispatcher.check_anniversaries().await.unwrap();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, MessageTarget::Direct("alice".to_string()));
+    assert_eq!(sent[0].content, "bob has been here 2 years today.");
+    assert!(dispatcher.check_anniversaries().await.unwrap().is_empty());
+
+    // 重启后（新的分发器）同一年也不重复
+    let restarted = ProactiveDispatcher::new(ProactiveConfig::default().with_policy("alice", ProactivePolicy::new()), store.clone())
+        .with_clock(clock.clone());
+    assert!(restarted.check_anniversaries().await.unwrap().is_empty());
+
+    clock.advance(Duration::from_secs(DAY as u64));
+    assert!(dispatcher.check_anniversaries().await.unwrap().is_empty(), "not the anniversary date");
+}
+
+#[tokio::test]
+async fn test_task_manager_emits_assignment_and_overdue_events() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let events = Arc::new(EventBus::new());
+    let mut rx = events.subscribe();
+    let clock = Arc::new(ManualClock::new(START));
+    let tasks = TaskManager::new(store).with_clock(clock.clone()).with_events(events);
+
+    let task = tasks
+        .create(Task::new("Ship it", "alice", 0).with_assignee("bob").with_due_at(START + 3600))
+        .await
+        .unwrap();
+    match rx.recv().await.unwrap() {
+        CompanyEvent::TaskAssigned { task: assigned, assignee, actor } => {
+            assert_eq!(assigned.id, task.id);
+            assert_eq!(&*assignee, "bob");
+            assert_eq!(&*actor, "alice");
+        }
+        other => panic!("unexpected event {}", other.kind()),
+    }
+
+    clock.advance(Duration::from_secs(2 * 3600));
+    tasks.check_due().await.unwrap();
+    assert_eq!(rx.recv().await.unwrap().kind(), "task_overdue");
+}
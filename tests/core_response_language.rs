@@ -0,0 +1,228 @@
+//! 回复语言与语气测试：提示词说明、语言不符时重试一次、代码块和短消息豁免、角色接口
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::agent::{AgentRuntime, Context, Decision};
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::response_language::{check_language, LanguageCheck, LanguageCorrection, ResponseStyle};
+use imitatort::core::store::MemoryStore;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Message, Role};
+
+const CHINESE: &str = "好的，我已经看过你的代码了，整体结构很清晰，但是错误处理还需要再完善一下，明天我们再详细讨论吧。";
+const ENGLISH: &str = "Sure, I have read through your code. The overall structure is clear, but the error handling still needs some work.";
+
+/// 首次回复固定内容；提示词带纠正说明时改用英文
+async fn spawn_llm(first: &'static str) -> (Arc<Mutex<Vec<String>>>, String) {
+    async fn completions(
+        State((prompts, first)): State<(Arc<Mutex<Vec<String>>>, &'static str)>,
+        Json(body): Json<Value>,
+    ) -> Json<Value> {
+        let prompt = body["messages"].to_string();
+        let content = if prompt.contains("Your previous answer was not written in") { ENGLISH } else { first };
+        prompts.lock().unwrap().push(prompt);
+        let decision = json!({ "action": "send_message", "target": "alice", "content": content });
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": decision.to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state((prompts.clone(), first));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (prompts, format!("http://{}", addr))
+}
+
+fn english_agent(url: &str) -> Agent {
+    Agent::new(
+        "dev",
+        "Dev",
+        Role::simple("Engineer", "You code").with_response_language("en"),
+        LLMConfig::openai("k").with_base_url(url),
+    )
+}
+
+fn content(decision: &Decision) -> &str {
+    match decision {
+        Decision::SendMessage { content, .. } => content,
+        other => panic!("unexpected decision {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_style_instruction_in_prompt() {
+    let agent = Agent::new(
+        "dev",
+        "Dev",
+        Role::simple("Engineer", "You code").with_response_language("zh"),
+        LLMConfig::openai("k"),
+    );
+    let runtime = AgentRuntime::new(agent)
+        .await
+        .unwrap()
+        .with_response_style(ResponseStyle::new(Some("en".into()), Some("formal".into())));
+
+    // 角色语言覆盖公司默认，语气沿用公司默认
+    let prompt = runtime.build_thinking_prompt(&Context::default());
+    assert!(prompt.contains("Always write your messages in Chinese"), "{}", prompt);
+    assert!(prompt.contains("Use a formal tone."));
+
+    let plain = AgentRuntime::new(Agent::new("ops", "Ops", Role::simple("Operator", "You operate"), LLMConfig::openai("k")))
+        .await
+        .unwrap();
+    assert!(!plain.build_thinking_prompt(&Context::default()).contains("Response style"));
+}
+
+#[tokio::test]
+async fn test_wrong_language_is_retried_once() {
+    let (prompts, url) = spawn_llm(CHINESE).await;
+    let runtime = AgentRuntime::new(english_agent(&url)).await.unwrap();
+
+    let decision = runtime.think(Context::default()).await.unwrap();
+    assert_eq!(content(&decision), ENGLISH);
+    assert_eq!(prompts.lock().unwrap().len(), 2);
+    assert_eq!(
+        runtime.take_language_correction(),
+        Some(LanguageCorrection { expected: "en".into(), detected: "cmn".into(), corrected: true })
+    );
+    assert!(runtime.take_language_correction().is_none());
+}
+
+#[tokio::test]
+async fn test_code_blocks_and_short_messages_are_not_retried() {
+    const CODE: &str = "改成这样：\n```rust\nfn retry_once(client: &Client) -> Result<Response> {\n    client.send().or_else(|_| client.send())\n}\n```";
+    for first in [CODE, "好的，收到！", ENGLISH] {
+        let (prompts, url) = spawn_llm(first).await;
+        let runtime = AgentRuntime::new(english_agent(&url)).await.unwrap();
+        let decision = runtime.think(Context::default()).await.unwrap();
+        assert_eq!(content(&decision), first);
+        assert_eq!(prompts.lock().unwrap().len(), 1, "{}", first);
+        assert!(runtime.take_language_correction().is_none());
+    }
+
+    assert_eq!(check_language("en", CODE), LanguageCheck::Skipped);
+    assert_eq!(check_language("en", "好的，收到！"), LanguageCheck::Skipped);
+    assert_eq!(check_language("en", &format!("`{}` {}", "x".repeat(10), CHINESE)), LanguageCheck::Mismatch { detected: "cmn".into() });
+    assert_eq!(check_language("zh", CHINESE), LanguageCheck::Matches);
+}
+
+#[tokio::test]
+async fn test_correction_recorded_in_turn_events() {
+    let (_prompts, url) = spawn_llm(CHINESE).await;
+    let events = Arc::new(EventBus::new());
+    let mut event_rx = events.subscribe();
+    let bus = Arc::new(MessageBus::with_store(Arc::new(MemoryStore::new())));
+    let mut alice_rx = bus.register("alice");
+    let autonomous = AutonomousAgent::new(english_agent(&url), bus.clone())
+        .await
+        .unwrap()
+        .with_events(events);
+
+    bus.send(Message::private("alice", "dev", "你好，能帮我看看这段代码吗？")).await.unwrap();
+    let handle = tokio::spawn(async move {
+        let _ = autonomous.run_loop().await;
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    handle.abort();
+
+    // 只发出纠正后的回复，纠正记录在同一轮的追踪ID下
+    let reply = alice_rx.try_recv().unwrap();
+    while let Ok(message) = alice_rx.try_recv() {
+        assert_eq!(message.content, ENGLISH);
+    }
+    assert_eq!(reply.content, ENGLISH);
+    let mut corrections = Vec::new();
+    while let Ok(event) = event_rx.try_recv() {
+        if let CompanyEvent::ResponseLanguageCorrected { correction, .. } = &event {
+            corrections.push((event.trace_id().map(str::to_string), correction.detected.clone()));
+        }
+    }
+    assert_eq!(corrections[0], (reply.trace_id().map(str::to_string), "cmn".to_string()));
+}
+
+#[tokio::test]
+async fn test_role_api_sets_response_language() {
+    let store = Arc::new(MemoryStore::new());
+    let (message_tx, _) = broadcast::channel::<Message>(16);
+    let jwt_service = JwtService::new("test-secret");
+    let token = jwt_service
+        .generate_token(&UserInfo {
+            id: "admin".to_string(),
+            username: "admin".to_string(),
+            name: "Admin".to_string(),
+            email: None,
+            is_director: true,
+            employee_id: "00001".to_string(),
+            position: "Chairman".to_string(),
+            department: "board".to_string(),
+        })
+        .unwrap();
+    let agent = Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k"));
+    let app = create_router(Arc::new(AppState::new(vec![agent], message_tx, store, jwt_service)));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/agents/dev/role", addr);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .put(&url)
+        .bearer_auth(&token)
+        .json(&json!({ "response_language": "klingon" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let body: Value = client
+        .put(&url)
+        .bearer_auth(&token)
+        .json(&json!({ "response_language": "zh", "tone": "friendly" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["role"]["response_language"], "zh");
+    assert_eq!(body["data"]["role"]["tone"], "friendly");
+
+    // 未提供的字段保留，空字符串清除
+    let body: Value = client
+        .put(&url)
+        .bearer_auth(&token)
+        .json(&json!({ "tone": "" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["role"]["response_language"], "zh");
+    assert!(body["data"]["role"].get("tone").is_none());
+}
@@ -0,0 +1,231 @@
+//! 统一重试测试：带 jitter 的等待序列、不可重试错误立即返回、整体截止时间、
+//! 并发调用方共享的重试预算耗尽，以及按调用点统计的指标
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use futures_util::future::join_all;
+
+use imitatort::core::clock::{Clock, ManualClock};
+use imitatort::core::retry::{
+    retry, Retrier, Retries, RetryBudget, RetryBudgetConfig, RetryConfig, RetryPolicy, RetrySubsystem, Sleeper,
+};
+
+/// 记录每次等待并推进手动时钟
+struct RecordingSleeper {
+    clock: Arc<ManualClock>,
+    delays: Mutex<Vec<Duration>>,
+}
+
+impl RecordingSleeper {
+    fn new(clock: Arc<ManualClock>) -> Arc<Self> {
+        Arc::new(Self {
+            clock,
+            delays: Mutex::new(Vec::new()),
+        })
+    }
+
+    fn delays(&self) -> Vec<Duration> {
+        self.delays.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Sleeper for RecordingSleeper {
+    async fn sleep(&self, duration: Duration) {
+        self.delays.lock().unwrap().push(duration);
+        self.clock.advance(duration);
+    }
+}
+
+fn policy(max_attempts: u32, jitter: bool) -> RetryPolicy {
+    RetryPolicy::default()
+        .with_max_attempts(max_attempts)
+        .with_delays(Duration::from_millis(100), Duration::from_millis(1000))
+        .with_factor(2.0)
+        .with_jitter(jitter)
+}
+
+#[tokio::test]
+async fn test_delay_sequence_with_jitter_bounds() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let sleeper = RecordingSleeper::new(clock.clone());
+    let calls = AtomicU32::new(0);
+    let retrier = Retrier::new("test.jitter", policy(6, true)).with_clock(clock.clone(), sleeper.clone());
+
+    let result: anyhow::Result<()> = retrier
+        .run(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow!("upstream unavailable"))
+        })
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 6);
+    let delays = sleeper.delays();
+    assert_eq!(delays.len(), 5);
+    let caps = [100, 200, 400, 800, 1000];
+    for (delay, cap) in delays.iter().zip(caps) {
+        assert!(*delay <= Duration::from_millis(cap), "{:?} exceeds cap {}ms", delay, cap);
+    }
+    let waited: u128 = delays.iter().map(|d| d.as_millis()).sum();
+    assert_eq!(clock.now_millis(), 1_000_000 + waited as i64);
+
+    // 关闭 jitter 时等待固定为上限，按因子增长并封顶
+    let sleeper = RecordingSleeper::new(clock.clone());
+    let retrier = Retrier::new("test.fixed", policy(6, false)).with_clock(clock.clone(), sleeper.clone());
+    let _: anyhow::Result<()> = retrier.run(|| async { Err(anyhow!("upstream unavailable")) }).await;
+    let millis: Vec<u128> = sleeper.delays().iter().map(|d| d.as_millis()).collect();
+    assert_eq!(millis, vec![100, 200, 400, 800, 1000]);
+
+    let policy = policy(6, true);
+    assert_eq!(policy.delay(3, 0.5), Duration::from_millis(200));
+    assert_eq!(policy.delay(3, 0.0), Duration::ZERO);
+}
+
+#[tokio::test]
+async fn test_non_retryable_error_short_circuits() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let sleeper = RecordingSleeper::new(clock.clone());
+    let retries = Retries::default();
+    let calls = AtomicU32::new(0);
+    let retrier = retries
+        .retrier(RetrySubsystem::Webhook, "test.webhook")
+        .with_retryable(|e| !e.to_string().contains("400"))
+        .with_clock(clock.clone(), sleeper.clone());
+
+    let result: anyhow::Result<()> = retrier
+        .run(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow!("HTTP 400 Bad Request"))
+        })
+        .await;
+
+    assert_eq!(result.unwrap_err().to_string(), "HTTP 400 Bad Request");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert!(sleeper.delays().is_empty());
+    let stats = retries.metrics().site("test.webhook").unwrap();
+    assert_eq!((stats.calls, stats.attempts, stats.retries), (1, 1, 0));
+    assert_eq!((stats.failures, stats.non_retryable), (1, 1));
+
+    // 临时错误重试后成功
+    let (result, attempts) = retrier
+        .run_counted(|| async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                1 => Err(anyhow!("connection reset")),
+                _ => Ok("delivered"),
+            }
+        })
+        .await;
+    assert_eq!(result.unwrap(), "delivered");
+    assert_eq!(attempts, 2);
+    let stats = retries.metrics().site("test.webhook").unwrap();
+    assert_eq!((stats.calls, stats.successes, stats.retries), (2, 1, 1));
+}
+
+#[tokio::test]
+async fn test_deadline_stops_retrying() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let sleeper = RecordingSleeper::new(clock.clone());
+    let metrics = Arc::new(imitatort::core::retry::RetryMetrics::new());
+    let calls = AtomicU32::new(0);
+    let retrier = Retrier::new("test.deadline", policy(10, false).with_deadline(Duration::from_millis(500)))
+        .with_metrics(metrics.clone())
+        .with_clock(clock.clone(), sleeper.clone());
+
+    let result: anyhow::Result<()> = retrier
+        .run(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(anyhow!("timeout"))
+        })
+        .await;
+
+    // 100ms + 200ms 后下一次 400ms 的等待会越过 500ms 的截止时间
+    assert!(result.is_err());
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    let millis: Vec<u128> = sleeper.delays().iter().map(|d| d.as_millis()).collect();
+    assert_eq!(millis, vec![100, 200]);
+    let stats = metrics.site("test.deadline").unwrap();
+    assert_eq!((stats.attempts, stats.deadline_exceeded, stats.exhausted), (3, 1, 0));
+}
+
+#[tokio::test]
+async fn test_shared_budget_exhausts_across_concurrent_callers() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let config = RetryConfig {
+        llm: policy(10, false).with_delays(Duration::from_millis(1), Duration::from_millis(1)),
+        budget: RetryBudgetConfig {
+            capacity: 3,
+            refill_per_sec: 0.0,
+        },
+        ..RetryConfig::default()
+    };
+    let retries = Retries::new(config).with_clock(clock.clone());
+    let calls = Arc::new(AtomicU32::new(0));
+
+    let runs = (0..5).map(|_| {
+        let retrier = retries
+            .retrier(RetrySubsystem::Llm, "test.llm")
+            .with_clock(clock.clone(), clock.clone());
+        let calls = calls.clone();
+        async move {
+            retrier
+                .run(|| async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err::<(), _>(anyhow!("connection refused"))
+                })
+                .await
+        }
+    });
+    let results = join_all(runs).await;
+
+    // 5 次首次尝试加上预算允许的 3 次重试
+    assert!(results.iter().all(|result| result.is_err()));
+    assert_eq!(calls.load(Ordering::SeqCst), 8);
+    let stats = retries.metrics().site("test.llm").unwrap();
+    assert_eq!((stats.calls, stats.attempts, stats.retries), (5, 8, 3));
+    assert_eq!((stats.failures, stats.budget_denied), (5, 5));
+    assert!(retries.budget(RetrySubsystem::Llm).available() < 1.0);
+    // 其他子系统的预算不受影响
+    assert_eq!(retries.budget(RetrySubsystem::Webhook).available(), 3.0);
+
+    // 令牌按时间补充，不超过容量
+    let budget = RetryBudget::new(RetryBudgetConfig {
+        capacity: 2,
+        refill_per_sec: 1.0,
+    })
+    .with_clock(clock.clone());
+    assert!(budget.try_acquire() && budget.try_acquire());
+    assert!(!budget.try_acquire());
+    clock.advance(Duration::from_millis(1500));
+    assert!(budget.try_acquire());
+    assert!(!budget.try_acquire());
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(budget.available(), 2.0);
+}
+
+#[tokio::test]
+async fn test_retry_combinator_and_config() {
+    let calls = AtomicU32::new(0);
+    let policy = RetryPolicy::default().with_delays(Duration::from_millis(1), Duration::from_millis(2));
+    let value = retry(&policy, || async {
+        match calls.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => Err(anyhow!("flaky")),
+            n => Ok(n),
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(value, 2);
+
+    let config: RetryConfig = serde_json::from_str(r#"{"webhook": {"max_attempts": 1}, "budget": {"capacity": 0}}"#).unwrap();
+    assert_eq!(config.webhook.max_attempts, 1);
+    assert_eq!(config.webhook.base_delay_ms, RetryPolicy::default().base_delay_ms);
+    assert_eq!(config.llm, RetryConfig::default().llm);
+    // 容量为 0 时不限制重试
+    let budget = Retries::new(config).budget(RetrySubsystem::Mcp);
+    assert!((0..100).all(|_| budget.try_acquire()));
+}
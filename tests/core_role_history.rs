@@ -0,0 +1,215 @@
+//! Agent 角色修订历史测试
+
+use std::sync::Arc;
+
+use imitatort::core::agent::{load_context, AgentRuntime};
+use imitatort::core::role_history::{append_role_revision, role_in_effect, rollback_role, CONFIG_EDITOR};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::domain::{Agent, LLMConfig, Message, Role, RoleRevision};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+fn agent() -> Agent {
+    Agent::new("dev", "Dev", Role::simple("Developer", "You write code"), LLMConfig::openai("test-key"))
+}
+
+fn prompts(revisions: &[RoleRevision]) -> Vec<&str> {
+    revisions.iter().map(|r| r.role.system_prompt.as_str()).collect()
+}
+
+async fn assert_history(store: &dyn Store) {
+    let agent = agent();
+    assert!(store.load_role_revisions("dev").await.unwrap().is_empty());
+
+    let second = append_role_revision(store, &agent, Role::simple("Developer", "You write tests"), "alice", Some("tests first".into()))
+        .await
+        .unwrap();
+    assert_eq!(second.revision, 2);
+    append_role_revision(store, &agent, Role::simple("Developer", "You review code"), "bob", None)
+        .await
+        .unwrap();
+
+    let revisions = store.load_role_revisions("dev").await.unwrap();
+    assert_eq!(revisions.iter().map(|r| r.revision).collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(prompts(&revisions), vec!["You write code", "You write tests", "You review code"]);
+    assert_eq!(revisions[0].editor, CONFIG_EDITOR);
+    assert_eq!(revisions[1].note.as_deref(), Some("tests first"));
+
+    // 修订号重复时拒绝写入
+    assert!(store.save_role_revision(&revisions[2]).await.is_err());
+
+    // 回滚生成新的最新修订，旧修订保持不变
+    let head = rollback_role(store, &agent, 1, "alice", Some("too strict".into())).await.unwrap().unwrap();
+    assert_eq!(head.revision, 4);
+    assert_eq!(head.role.system_prompt, "You write code");
+    assert_eq!(head.note.as_deref(), Some("Rollback to revision 1: too strict"));
+    assert_eq!(store.load_role_revisions("dev").await.unwrap().len(), 4);
+    assert_eq!(role_in_effect(store, "dev", None).await.unwrap().unwrap().revision, 4);
+
+    assert!(rollback_role(store, &agent, 9, "alice", None).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_role_history_memory_store() {
+    assert_history(&MemoryStore::new()).await;
+}
+
+#[tokio::test]
+async fn test_role_history_sqlite_store() {
+    assert_history(&SqliteStore::new_in_memory().unwrap()).await;
+}
+
+#[tokio::test]
+async fn test_role_in_effect_as_of() {
+    let store = MemoryStore::new();
+    let mut revision = RoleRevision {
+        agent_id: "dev".to_string(),
+        revision: 1,
+        role: Role::simple("Developer", "v1"),
+        editor: CONFIG_EDITOR.to_string(),
+        note: None,
+        created_at: 100,
+    };
+    store.save_role_revision(&revision).await.unwrap();
+    revision.revision = 2;
+    revision.role = Role::simple("Developer", "v2");
+    revision.created_at = 200;
+    store.save_role_revision(&revision).await.unwrap();
+
+    assert!(role_in_effect(&store, "dev", Some(50)).await.unwrap().is_none());
+    assert_eq!(role_in_effect(&store, "dev", Some(150)).await.unwrap().unwrap().revision, 1);
+    assert_eq!(role_in_effect(&store, "dev", None).await.unwrap().unwrap().revision, 2);
+}
+
+#[tokio::test]
+async fn test_runtime_picks_up_new_revision_without_restart() {
+    let store = MemoryStore::new();
+    let runtime = AgentRuntime::new(agent()).await.unwrap();
+
+    let before = runtime.build_thinking_prompt(&load_context(&store, "dev", None).await.unwrap());
+    assert!(before.starts_with("You write code"));
+
+    append_role_revision(&store, &agent(), Role::simple("Developer", "You only write Rust"), "alice", None)
+        .await
+        .unwrap();
+
+    let context = load_context(&store, "dev", None).await.unwrap();
+    assert_eq!(context.role_revision.as_ref().map(|r| r.revision), Some(2));
+    let after = runtime.build_thinking_prompt(&context);
+    assert!(after.starts_with("You only write Rust"));
+    assert!(!after.contains("You write code"));
+}
+
+#[tokio::test]
+async fn test_role_api_history_and_rollback() {
+    let store = Arc::new(MemoryStore::new());
+    let (message_tx, _) = broadcast::channel::<Message>(16);
+    let jwt_service = JwtService::new("test-secret");
+    let token = jwt_service
+        .generate_token(&UserInfo {
+            id: "admin".to_string(),
+            username: "admin".to_string(),
+            name: "Admin".to_string(),
+            email: None,
+            is_director: true,
+            employee_id: "00001".to_string(),
+            position: "Chairman".to_string(),
+            department: "board".to_string(),
+        })
+        .unwrap();
+    let app = create_router(Arc::new(AppState::new(vec![agent()], message_tx, store.clone(), jwt_service)));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let base = format!("http://{}/api/v1/agents", addr);
+    let client = reqwest::Client::new();
+
+    let response = client
+        .put(format!("{}/dev/role", base))
+        .json(&json!({"system_prompt": "nope"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = client
+        .put(format!("{}/ghost/role", base))
+        .bearer_auth(&token)
+        .json(&json!({"system_prompt": "nope"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let body: Value = client
+        .put(format!("{}/dev/role", base))
+        .bearer_auth(&token)
+        .json(&json!({"system_prompt": "You write docs", "note": "docs sprint"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["revision"], 2);
+    assert_eq!(body["data"]["editor"], "admin");
+    // 未提供的字段沿用当前角色
+    assert_eq!(body["data"]["role"]["title"], "Developer");
+
+    let body: Value = client
+        .post(format!("{}/dev/role/rollback/1?note=back", base))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["revision"], 3);
+    assert_eq!(body["data"]["role"]["system_prompt"], "You write code");
+
+    let response = client
+        .post(format!("{}/dev/role/rollback/7", base))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let body: Value = client
+        .get(format!("{}/dev/role/history", base))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let data = &body["data"];
+    assert_eq!(data["head"], 3);
+    let revisions: Vec<u64> = data["revisions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["revision"].as_u64().unwrap())
+        .collect();
+    assert_eq!(revisions, vec![3, 2, 1]);
+    assert_eq!(data["revisions"][0]["note"], "Rollback to revision 1: back");
+
+    let body: Value = client
+        .get(format!("{}/dev/context", base))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["role_revision"], 3);
+}
@@ -0,0 +1,164 @@
+//! 轮次调度器测试
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use imitatort::core::scheduler::{SchedulerConfig, TurnPriority, TurnScheduler, TurnState};
+
+fn scheduler(max_concurrent_turns: usize, starvation_ms: u64) -> Arc<TurnScheduler> {
+    Arc::new(TurnScheduler::new(SchedulerConfig {
+        max_concurrent_turns,
+        starvation_ms,
+    }))
+}
+
+/// 等待调度器队列达到指定深度
+async fn wait_queued(scheduler: &TurnScheduler, depth: usize) {
+    for _ in 0..200 {
+        if scheduler.metrics().queued == depth {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    panic!("queue never reached depth {}: {:?}", depth, scheduler.metrics());
+}
+
+#[tokio::test]
+async fn test_concurrency_is_bounded_and_fair() {
+    let scheduler = scheduler(4, 60_000);
+    let running = Arc::new(AtomicUsize::new(0));
+    let max_running = Arc::new(AtomicUsize::new(0));
+    let order = Arc::new(Mutex::new(Vec::new()));
+
+    // 先占满并发，保证后续 50 个轮次按入队顺序排队
+    let mut blockers = vec![];
+    for i in 0..4 {
+        blockers.push(scheduler.acquire(&format!("blocker-{}", i), TurnPriority::Background).await);
+    }
+
+    let mut handles = vec![];
+    for i in 0..50 {
+        let turns = scheduler.clone();
+        let running = running.clone();
+        let max_running = max_running.clone();
+        let order = order.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = turns.acquire(&format!("agent-{}", i), TurnPriority::Background).await;
+            order.lock().unwrap().push(i);
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now, Ordering::SeqCst);
+            // 模拟 LLM 调用
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        }));
+        wait_queued(&scheduler, i + 1).await;
+    }
+
+    assert_eq!(scheduler.state_of("agent-0"), TurnState::Queued);
+    assert_eq!(scheduler.state_of("blocker-0"), TurnState::Running);
+    drop(blockers);
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    assert_eq!(max_running.load(Ordering::SeqCst), 4);
+    assert_eq!(*order.lock().unwrap(), (0..50).collect::<Vec<_>>());
+
+    let metrics = scheduler.metrics();
+    assert_eq!(metrics.running, 0);
+    assert_eq!(metrics.queued, 0);
+    assert_eq!(metrics.total_turns, 54);
+    assert!(metrics.max_wait_ms > 0);
+    assert_eq!(scheduler.state_of("agent-0"), TurnState::Idle);
+}
+
+#[tokio::test]
+async fn test_user_turns_jump_background_queue() {
+    let scheduler = scheduler(1, 60_000);
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let blocker = scheduler.acquire("blocker", TurnPriority::Background).await;
+
+    let mut handles = vec![];
+    for (i, (id, priority)) in [
+        ("bg-1", TurnPriority::Background),
+        ("bg-2", TurnPriority::Background),
+        ("user", TurnPriority::User),
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let turns = scheduler.clone();
+        let order = order.clone();
+        handles.push(tokio::spawn(async move {
+            let _permit = turns.acquire(id, priority).await;
+            order.lock().unwrap().push(id);
+        }));
+        wait_queued(&scheduler, i + 1).await;
+    }
+
+    let metrics = scheduler.metrics();
+    assert_eq!(metrics.queued_user, 1);
+    assert_eq!(metrics.queued_background, 2);
+
+    drop(blocker);
+    for handle in handles {
+        handle.await.unwrap();
+    }
+    assert_eq!(*order.lock().unwrap(), vec!["user", "bg-1", "bg-2"]);
+}
+
+#[tokio::test]
+async fn test_background_turns_do_not_starve() {
+    let scheduler = scheduler(1, 20);
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let blocker = scheduler.acquire("blocker", TurnPriority::Background).await;
+
+    let mut handles = vec![];
+    let spawn = |id: &'static str, priority| {
+        let scheduler = scheduler.clone();
+        let order = order.clone();
+        tokio::spawn(async move {
+            let _permit = scheduler.acquire(id, priority).await;
+            order.lock().unwrap().push(id);
+        })
+    };
+    handles.push(spawn("bg", TurnPriority::Background));
+    wait_queued(&scheduler, 1).await;
+    // 后台轮次等待超过饥饿阈值后，先于后到的用户轮次执行
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    handles.push(spawn("user", TurnPriority::User));
+    wait_queued(&scheduler, 2).await;
+
+    drop(blocker);
+    for handle in handles {
+        handle.await.unwrap();
+    }
+    assert_eq!(*order.lock().unwrap(), vec!["bg", "user"]);
+}
+
+#[tokio::test]
+async fn test_cancelled_waiter_releases_slot() {
+    let scheduler = scheduler(1, 60_000);
+    let blocker = scheduler.acquire("blocker", TurnPriority::Background).await;
+
+    let cancelled = {
+        let scheduler = scheduler.clone();
+        tokio::spawn(async move {
+            let _permit = scheduler.acquire("cancelled", TurnPriority::User).await;
+        })
+    };
+    wait_queued(&scheduler, 1).await;
+    cancelled.abort();
+    let _ = cancelled.await;
+
+    drop(blocker);
+    assert_eq!(scheduler.metrics().running, 0);
+    let permit = tokio::time::timeout(
+        Duration::from_secs(1),
+        scheduler.acquire("next", TurnPriority::Background),
+    )
+    .await
+    .expect("slot leaked by cancelled waiter");
+    assert_eq!(permit.agent_id(), "next");
+}
@@ -0,0 +1,233 @@
+//! Agent 运行草稿测试：超出上限时从最早的开始删除、写入前脱敏、草稿不进入之后轮次的上下文和消息记录、
+//! 以工具调用结束的 LLM 回复按配置自动记录
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::scratchpad::{Scratchpad, ScratchpadConfig};
+use imitatort::core::skill::SkillManager;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::tool_view::AgentToolView;
+use imitatort::domain::scratchpad::{ScratchpadEntry, ScratchpadSource};
+use imitatort::domain::tool::{ToolCallContext, ToolProvider};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::{Agent, LLMConfig, Message, Organization, Role};
+
+const ALICE: &str = "user:alice";
+const API_KEY: &str = "sk-scratchpad-secret";
+
+fn config(max_bytes_per_run: usize) -> ScratchpadConfig {
+    ScratchpadConfig {
+        enabled: true,
+        max_bytes_per_run,
+        capture_tool_turns: false,
+    }
+}
+
+fn contents(entries: &[ScratchpadEntry]) -> Vec<&str> {
+    entries.iter().map(|e| e.content.as_str()).collect()
+}
+
+#[test]
+fn test_disabled_by_default() {
+    assert!(!ScratchpadConfig::default().enabled);
+    assert!(!ScratchpadConfig::default().capture_tool_turns);
+}
+
+#[tokio::test]
+async fn test_size_cap_drops_oldest_entries_first() {
+    let stores: Vec<Arc<dyn Store>> = vec![Arc::new(MemoryStore::new()), Arc::new(SqliteStore::new_in_memory().unwrap())];
+    for store in stores {
+        let scratchpad = Scratchpad::new(config(20), store.clone());
+        for content in ["first---", "second--", "third---"] {
+            scratchpad.write("run-1", "dev", ScratchpadSource::Tool, content).await.unwrap();
+        }
+        scratchpad.write("run-2", "dev", ScratchpadSource::Tool, "other run").await.unwrap();
+
+        // 8 + 8 + 8 字节超出 20 字节，删除最早的一条；其他运行不受影响
+        assert_eq!(contents(&scratchpad.entries("run-1").await.unwrap()), vec!["second--", "third---"]);
+        assert_eq!(contents(&scratchpad.entries("run-2").await.unwrap()), vec!["other run"]);
+
+        // 单条超过上限时只保留开头，并挤掉之前的全部
+        let long = scratchpad.write("run-1", "dev", ScratchpadSource::Tool, &"x".repeat(50)).await.unwrap();
+        assert_eq!(long.content.len(), 20);
+        assert_eq!(scratchpad.entries("run-1").await.unwrap(), vec![long]);
+    }
+}
+
+#[tokio::test]
+async fn test_entries_are_redacted_and_broadcast() {
+    let scratchpad = Scratchpad::new(config(1024), Arc::new(MemoryStore::new())).with_secrets(vec![API_KEY.to_string()]);
+    let mut rx = scratchpad.subscribe();
+
+    let entry = scratchpad
+        .write("run-1", "dev", ScratchpadSource::Tool, &format!("call the API with {}", API_KEY))
+        .await
+        .unwrap();
+    assert!(!entry.content.contains(API_KEY), "{}", entry.content);
+    assert_eq!(rx.try_recv().unwrap(), entry);
+    assert_eq!(scratchpad.entries("run-1").await.unwrap(), vec![entry]);
+}
+
+#[tokio::test]
+async fn test_tool_requires_enabled_scratchpad() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let env = ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store.clone(),
+    );
+    assert!(!env.tool_provider.list_tools().iter().any(|t| t.id == "scratchpad.write"));
+    let disabled = FrameworkToolExecutor::new(env.clone());
+    let context = ToolCallContext::new("dev").with_trace_id("run-1");
+    let result = disabled.execute("scratchpad.write", json!({"content": "plan"}), &context).await.unwrap();
+    assert!(!result.success);
+
+    let env = env.with_scratchpad(Arc::new(Scratchpad::new(config(1024), store.clone())));
+    assert!(env.tool_provider.list_tools().iter().any(|t| t.id == "scratchpad.write"));
+    let result = FrameworkToolExecutor::new(env)
+        .execute("scratchpad.write", json!({"content": "plan"}), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["run_id"], "run-1");
+    assert!(result.data.get("content").is_none());
+    assert_eq!(contents(&store.load_scratchpad("run-1").await.unwrap()), vec!["plan"]);
+}
+
+/// 先用 `scratchpad.write` 记下计划（同时附带一段思考），收到“now answer”后回复用户；记录所有请求体
+async fn spawn_llm() -> (String, Arc<Mutex<Vec<String>>>) {
+    async fn completions(State(requests): State<Arc<Mutex<Vec<String>>>>, Json(body): Json<Value>) -> Json<Value> {
+        let prompt = body.to_string();
+        requests.lock().unwrap().push(prompt.clone());
+        let message = if prompt.contains("now answer") {
+            let decision = json!({"action": "send_message", "target": ALICE, "content": "Done."});
+            json!({ "role": "assistant", "content": decision.to_string() })
+        } else if prompt.contains("plan it") {
+            let arguments = json!({"content": format!("SECRET-PLAN using {}", API_KEY)});
+            json!({
+                "role": "assistant",
+                "content": "REJECTED-DRAFT: maybe ask the CTO first",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "scratchpad__write", "arguments": arguments.to_string()}
+                }]
+            })
+        } else {
+            json!({ "role": "assistant", "content": json!({"action": "wait"}).to_string() })
+        };
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{ "index": 0, "message": message, "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(requests.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (format!("http://{}", addr), requests)
+}
+
+async fn wait_until(mut condition: impl FnMut() -> bool) {
+    for _ in 0..100 {
+        if condition() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("condition not reached in time");
+}
+
+#[tokio::test]
+async fn test_entries_stay_out_of_later_turns_and_messages() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _alice_rx = bus.register_user(ALICE);
+    let scratchpad = Arc::new(
+        Scratchpad::new(ScratchpadConfig { capture_tool_turns: true, ..config(4096) }, store.clone())
+            .with_secrets(vec![API_KEY.to_string()]),
+    );
+    let mut entries_rx = scratchpad.subscribe();
+
+    let registry = Arc::new(ToolRegistry::new());
+    let env = ToolEnvironment::new(
+        bus.clone(),
+        Arc::new(RwLock::new(Organization::new())),
+        registry.clone(),
+        store.clone(),
+    )
+    .with_scratchpad(scratchpad.clone());
+    let view = Arc::new(AgentToolView::new(
+        env.tool_provider.clone(),
+        Arc::new(SkillManager::new_with_tool_registry(registry)),
+    ));
+    let executor = Arc::new(FrameworkToolExecutor::new(env));
+
+    let (url, requests) = spawn_llm().await;
+    let dev = Agent::new("dev", "Dev", Role::simple("Engineer", "You write code"), LLMConfig::openai(API_KEY).with_base_url(url));
+    let agent = AutonomousAgent::new(dev, bus.clone())
+        .await
+        .unwrap()
+        .with_tools(view, executor)
+        .with_scratchpad(scratchpad.clone());
+    let handle = tokio::spawn(async move {
+        let _ = agent.run_loop().await;
+    });
+
+    bus.send(Message::private(ALICE, "dev", "Please plan it")).await.unwrap();
+    let captured = tokio::time::timeout(Duration::from_secs(5), entries_rx.recv()).await.unwrap().unwrap();
+    let written = tokio::time::timeout(Duration::from_secs(5), entries_rx.recv()).await.unwrap().unwrap();
+    let first_turn = requests.lock().unwrap().len();
+
+    bus.send(Message::private(ALICE, "dev", "Thanks, now answer")).await.unwrap();
+    wait_until(|| requests.lock().unwrap().iter().any(|r| r.contains("now answer"))).await;
+    for _ in 0..100 {
+        if !store.load_messages(MessageFilter::new().from("dev")).await.unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    handle.abort();
+
+    // 两条草稿属于同一次运行：先自动记录的思考，再是工具写入的计划（已脱敏）
+    assert_eq!(captured.source, ScratchpadSource::ToolTurn);
+    assert_eq!(captured.content, "REJECTED-DRAFT: maybe ask the CTO first");
+    assert_eq!(written.source, ScratchpadSource::Tool);
+    assert_eq!(written.run_id, captured.run_id);
+    assert!(written.content.starts_with("SECRET-PLAN using "));
+    assert!(!written.content.contains(API_KEY));
+    assert_eq!(scratchpad.entries(&captured.run_id).await.unwrap(), vec![captured, written]);
+
+    // 之后的轮次看不到草稿
+    let requests = requests.lock().unwrap();
+    for request in &requests[first_turn..] {
+        assert!(!request.contains("SECRET-PLAN"), "{}", request);
+        assert!(!request.contains("REJECTED-DRAFT"), "{}", request);
+    }
+
+    // 草稿不是消息，不会出现在会话记录、导出和群聊记录中
+    let messages = store.load_messages(MessageFilter::new().limit(1000)).await.unwrap();
+    assert!(messages.iter().any(|m| m.from == "dev" && m.content == "Done."));
+    for message in messages {
+        assert!(!message.content.contains("SECRET-PLAN"), "{}", message.content);
+        assert!(!message.content.contains("REJECTED-DRAFT"), "{}", message.content);
+    }
+}
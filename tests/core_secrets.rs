@@ -0,0 +1,180 @@
+//! API Key 引用测试：`env:` 和 `keyring:` 两种引用的解析与错误信息、SQLite 只保存引用、
+//! 保存时取出的明文只交给调用方一次、明文迁移成 `env:` 引用，以及接口响应和变更记录中不出现解析出的值
+
+use std::sync::Arc;
+
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use imitatort::core::agent::AgentRuntime;
+use imitatort::core::org_changes::save_organization_tracked;
+use imitatort::core::secrets::{
+    env_reference_for, render_secret, EnvSecretResolver, SecretError, SecretRef, SecretResolver,
+};
+use imitatort::core::store::Store;
+use imitatort::domain::{Agent, LLMConfig, Organization, Role};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::llm::{Message, OpenAIClient};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+
+fn agent(id: &str, api_key: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Engineer", "You build things"), LLMConfig::openai(api_key))
+}
+
+/// 只认识 `keyring:` 引用的测试钥匙串
+struct FakeKeyring;
+
+impl SecretResolver for FakeKeyring {
+    fn resolve(&self, secret: &SecretRef) -> Result<String, SecretError> {
+        match secret {
+            SecretRef::Keyring(name) if name == "company-openai" => Ok("sk-from-keyring".to_string()),
+            SecretRef::Keyring(_) => Err(SecretError::Backend {
+                reference: secret.to_string(),
+                message: "no such entry".to_string(),
+            }),
+            other => EnvSecretResolver.resolve(other),
+        }
+    }
+}
+
+#[test]
+fn test_reference_forms_resolve_or_name_the_reference() {
+    std::env::set_var("IMITATORT_TEST_SECRET_ENV", "sk-from-env");
+    assert_eq!(SecretRef::parse("env:IMITATORT_TEST_SECRET_ENV"), SecretRef::Env("IMITATORT_TEST_SECRET_ENV".into()));
+    assert_eq!(SecretRef::parse("keyring:company-openai"), SecretRef::Keyring("company-openai".into()));
+    assert!(!SecretRef::parse("sk-plain").is_reference());
+
+    let resolver = EnvSecretResolver;
+    assert_eq!(resolver.resolve(&SecretRef::parse("env:IMITATORT_TEST_SECRET_ENV")).unwrap(), "sk-from-env");
+    assert_eq!(resolver.resolve(&SecretRef::parse("sk-plain")).unwrap(), "sk-plain");
+    let missing = resolver.resolve(&SecretRef::parse("env:IMITATORT_TEST_SECRET_MISSING")).unwrap_err();
+    assert!(missing.to_string().contains("env:IMITATORT_TEST_SECRET_MISSING"), "{}", missing);
+    let keyring = resolver.resolve(&SecretRef::parse("keyring:company-openai")).unwrap_err();
+    assert!(matches!(keyring, SecretError::KeyringUnavailable { .. }));
+    assert!(keyring.to_string().contains("keyring:company-openai"));
+
+    assert!(OpenAIClient::from_config_with(&LLMConfig::openai("keyring:company-openai"), &FakeKeyring).is_ok());
+    let error = OpenAIClient::from_config_with(&LLMConfig::openai("keyring:other"), &FakeKeyring).err().unwrap();
+    assert_eq!(error.to_string(), "Secret keyring:other could not be resolved: no such entry");
+
+    // 显示时引用原样，明文隐藏
+    assert_eq!(render_secret("env:IMITATORT_TEST_SECRET_ENV"), "env:IMITATORT_TEST_SECRET_ENV");
+    assert_eq!(render_secret("sk-plain"), "***");
+    assert_eq!(env_reference_for("ops-bot.2"), "env:IMITATORT_API_KEY_OPS_BOT_2");
+}
+
+#[tokio::test]
+async fn test_unresolvable_reference_fails_at_startup_and_turn() {
+    let error = AgentRuntime::new(agent("dev", "env:IMITATORT_TEST_SECRET_UNSET")).await.err().unwrap();
+    assert!(error.to_string().contains("dev"), "{}", error);
+    assert!(error.to_string().contains("env:IMITATORT_TEST_SECRET_UNSET"), "{}", error);
+
+    // 可选组件在每次请求时报告同一个错误，不会带着空 Key 访问服务商
+    let client = OpenAIClient::for_config(&LLMConfig::openai("env:IMITATORT_TEST_SECRET_UNSET").with_base_url("http://127.0.0.1:9"));
+    let error = client.chat(vec![Message::user("hi")]).await.unwrap_err();
+    assert!(error.to_string().contains("env:IMITATORT_TEST_SECRET_UNSET"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_store_keeps_references_and_migrates_plaintext() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("company.db");
+    let store = SqliteStore::new(&path).unwrap();
+    let mut org = Organization::new();
+    org.add_agent(agent("dev", "env:OPENAI_KEY"));
+    org.add_agent(agent("ops", "keyring:company-openai"));
+    org.add_agent(agent("qa", "sk-plaintext-qa"));
+    store.save_organization(&org).await.unwrap();
+
+    let loaded = store.load_organization().await.unwrap();
+    let key = |org: &Organization, id: &str| org.find_agent(id).unwrap().llm_config.api_key.clone();
+    assert_eq!(key(&loaded, "dev"), "env:OPENAI_KEY");
+    assert_eq!(key(&loaded, "ops"), "keyring:company-openai");
+    assert_eq!(key(&loaded, "qa"), "env:IMITATORT_API_KEY_QA");
+    let raw = std::fs::read(&path).unwrap();
+    assert!(!String::from_utf8_lossy(&raw).contains("sk-plaintext"));
+
+    // 旧版本写入的明文行
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    conn.execute("UPDATE agents SET llm_api_key = 'sk-legacy-ops' WHERE id = 'ops'", []).unwrap();
+    conn.execute("UPDATE agents SET llm_api_key = 'sk-legacy-qa' WHERE id = 'qa'", []).unwrap();
+    drop(conn);
+
+    let extracted = store.migrate_plaintext_api_keys().await.unwrap();
+    let summary: Vec<(&str, &str, &str)> = extracted
+        .iter()
+        .map(|s| (s.agent_id.as_str(), s.reference.as_str(), s.value.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("ops", "env:IMITATORT_API_KEY_OPS", "sk-legacy-ops"),
+            ("qa", "env:IMITATORT_API_KEY_QA", "sk-legacy-qa"),
+        ]
+    );
+    assert!(!format!("{:?}", extracted).contains("sk-legacy"));
+    let migrated = store.load_organization().await.unwrap();
+    assert_eq!(key(&migrated, "dev"), "env:OPENAI_KEY");
+    assert_eq!(key(&migrated, "ops"), "env:IMITATORT_API_KEY_OPS");
+    // 再次迁移没有可迁移的行
+    assert!(store.migrate_plaintext_api_keys().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_saved_plaintext_key_is_reported_once() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    let mut org = Organization::new();
+    org.add_agent(agent("dev", "env:OPENAI_KEY"));
+    org.add_agent(agent("qa", "sk-plaintext-qa"));
+    store.save_organization(&org).await.unwrap();
+
+    // 库中只有引用，取出的明文交给调用方展示一次
+    let reloaded = store.load_organization().await.unwrap();
+    assert_eq!(reloaded.find_agent("qa").unwrap().llm_config.api_key, env_reference_for("qa"));
+    let extracted = store.take_extracted_api_keys();
+    let summary: Vec<(&str, &str, &str)> = extracted
+        .iter()
+        .map(|s| (s.agent_id.as_str(), s.reference.as_str(), s.value.as_str()))
+        .collect();
+    assert_eq!(summary, vec![("qa", "env:IMITATORT_API_KEY_QA", "sk-plaintext-qa")]);
+    assert!(store.take_extracted_api_keys().is_empty());
+
+    // 重新保存加载出的组织架构不会再取出任何值
+    store.save_organization(&reloaded).await.unwrap();
+    assert!(store.take_extracted_api_keys().is_empty());
+}
+
+#[tokio::test]
+async fn test_agent_endpoint_and_change_feed_never_show_resolved_key() {
+    std::env::set_var("IMITATORT_TEST_SECRET_WEB", "sk-resolved-4f3e2d");
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_agent(agent("dev", "env:IMITATORT_TEST_SECRET_WEB"));
+    org.add_agent(agent("ops", "sk-literal-9a8b"));
+    save_organization_tracked(store.as_ref(), &org, "admin").await.unwrap();
+
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(org.agents.clone(), message_tx, store.clone(), JwtService::new("secret"));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+
+    for (id, rendered) in [("dev", "env:IMITATORT_TEST_SECRET_WEB"), ("ops", "***")] {
+        let text = reqwest::get(format!("http://{}/api/agents/{}", addr, id)).await.unwrap().text().await.unwrap();
+        assert!(!text.contains("sk-"), "{}", text);
+        let body: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(body["llm"]["api_key"], rendered);
+    }
+
+    // 轮换 Key 和改用引用都不会在变更记录中留下值
+    org.agents[1].llm_config.api_key = "env:IMITATORT_TEST_SECRET_WEB".to_string();
+    org.agents[1].llm_config.model = "gpt-4o".to_string();
+    save_organization_tracked(store.as_ref(), &org, "admin").await.unwrap();
+    let changes = store.load_org_changes(0, 10).await.unwrap();
+    let feed = serde_json::to_string(&changes).unwrap();
+    assert!(!feed.contains("sk-"), "{}", feed);
+    assert!(!feed.contains("IMITATORT_TEST_SECRET_WEB"), "{}", feed);
+}
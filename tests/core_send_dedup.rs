@@ -0,0 +1,120 @@
+//! 重复发送抑制测试：窗口期内相同消息被拦截、窗口过后正常发送、
+//! 发送工具的 allow_duplicate 参数跳过检查，以及缓存容量上限
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::send_dedup::{SendDedup, SendDedupConfig};
+use imitatort::core::store::MemoryStore;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Message, Organization};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment, ToolExecutorRegistry};
+
+fn dedup(window_secs: u64, max_entries: usize, clock: Arc<ManualClock>) -> SendDedup {
+    SendDedup::new(SendDedupConfig {
+        window_secs,
+        max_entries,
+    })
+    .with_clock(clock)
+}
+
+#[test]
+fn test_duplicate_within_window_suppressed_until_expiry() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let dedup = dedup(30, 100, clock.clone());
+
+    assert!(!dedup.suppress(&Message::private("alice", "bob", "Report is ready")));
+    assert!(dedup.suppress(&Message::private("alice", "bob", "Report is ready")));
+    // 只差空白也算重复
+    assert!(dedup.suppress(&Message::private("alice", "bob", "  Report   is\nready ")));
+    assert_eq!(dedup.suppressed(), 2);
+
+    // 换发送者、换目标或换内容都不算重复
+    assert!(!dedup.suppress(&Message::private("carol", "bob", "Report is ready")));
+    assert!(!dedup.suppress(&Message::private("alice", "carol", "Report is ready")));
+    assert!(!dedup.suppress(&Message::group("alice", "bob", "Report is ready")));
+    assert!(!dedup.suppress(&Message::private("alice", "bob", "Report is ready!")));
+
+    clock.advance(Duration::from_secs(29));
+    assert!(dedup.suppress(&Message::private("alice", "bob", "Report is ready")));
+    clock.advance(Duration::from_secs(1));
+    assert!(!dedup.suppress(&Message::private("alice", "bob", "Report is ready")));
+    assert_eq!(dedup.suppressed(), 3);
+}
+
+#[test]
+fn test_cache_bounded_and_disabled_window() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let dedup = dedup(30, 3, clock.clone());
+    for i in 0..5 {
+        assert!(!dedup.suppress(&Message::private("alice", "bob", format!("update {}", i))));
+    }
+    assert_eq!(dedup.len(), 3);
+    // 最早的条目被淘汰，再发不会被拦截
+    assert!(!dedup.suppress(&Message::private("alice", "bob", "update 0")));
+    assert!(dedup.suppress(&Message::private("alice", "bob", "update 4")));
+
+    let disabled = self::dedup(0, 100, clock);
+    assert!(!disabled.suppress(&Message::private("alice", "bob", "hi")));
+    assert!(!disabled.suppress(&Message::private("alice", "bob", "hi")));
+    assert!(disabled.is_empty());
+}
+
+#[tokio::test]
+async fn test_send_tool_suppresses_duplicate_unless_allowed() {
+    let clock = Arc::new(ManualClock::new(1_000));
+    let store = Arc::new(MemoryStore::new());
+    let message_bus = Arc::new(MessageBus::with_store(store.clone()));
+    message_bus.register("alice");
+    let mut bob = message_bus.register("bob");
+    let dedup = Arc::new(dedup(30, 100, clock.clone()));
+
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(Arc::new(ToolRegistry::new()));
+    registry.register(Box::new(FrameworkToolExecutor::new(
+        ToolEnvironment::new(
+            message_bus.clone(),
+            Arc::new(RwLock::new(Organization::new())),
+            Arc::new(ToolRegistry::new()),
+            store,
+        )
+        .with_send_dedup(dedup.clone()),
+    )));
+    let context = ToolCallContext::new("alice");
+    let params = json!({"to": "bob", "content": "Standup in 5"});
+
+    let result = registry.execute("message.send_immediate", params.clone(), &context).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(bob.try_recv().unwrap().content, "Standup in 5");
+
+    let result = registry.execute("message.send_immediate", params.clone(), &context).await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["sent"], false);
+    assert_eq!(result.data["duplicate"], true);
+    assert!(bob.try_recv().is_err());
+    assert_eq!(dedup.suppressed(), 1);
+
+    // 显式允许重复时照常发送
+    let result = registry
+        .execute(
+            "message.send_direct",
+            json!({"to_agent_id": "bob", "content": "Standup in 5", "allow_duplicate": true}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(bob.try_recv().unwrap().content, "Standup in 5");
+    assert_eq!(dedup.suppressed(), 1);
+
+    // 窗口过后不再拦截
+    clock.advance(Duration::from_secs(31));
+    let result = registry.execute("message.send_immediate", params, &context).await.unwrap();
+    assert!(result.success);
+    assert_eq!(bob.try_recv().unwrap().content, "Standup in 5");
+}
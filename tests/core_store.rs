@@ -1,7 +1,10 @@
 //! 存储接口定义测试
 
 use imitatort::core::store::{MemoryStore, MessageFilter, Store};
-use imitatort::domain::{Agent, Department, LLMConfig, Message, Organization, Role};
+use imitatort::domain::{
+    Agent, Department, LLMConfig, Message, MessageBookmark, MessagePin, MessageReaction, MessageTranslation, Organization,
+    Role,
+};
 
 fn create_test_organization() -> Organization {
     let mut org = Organization::new();
@@ -49,6 +52,38 @@ async fn test_memory_store_basic() {
     assert_eq!(loaded.departments.len(), 1);
 }
 
+#[tokio::test]
+async fn test_memory_store_partial_org_queries() {
+    let store = MemoryStore::new();
+    assert!(store.load_agent("ceo").await.unwrap().is_none());
+
+    store.save_organization(&create_test_organization()).await.unwrap();
+
+    assert_eq!(store.load_agent("ceo").await.unwrap().unwrap().name, "CEO");
+    assert_eq!(store.load_department("tech").await.unwrap().unwrap().name, "技术部");
+    assert_eq!(store.load_department_members("tech").await.unwrap().len(), 1);
+    assert!(store.load_department_members("missing").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_memory_store_reactions() {
+    let store = MemoryStore::new();
+    let reaction = MessageReaction::new("m1", "alice", "👍");
+
+    assert!(store.add_reaction(&reaction).await.unwrap());
+    // 重复回应是幂等的
+    assert!(!store.add_reaction(&reaction).await.unwrap());
+    assert!(store.add_reaction(&MessageReaction::new("m1", "bob", "👍")).await.unwrap());
+    assert!(store.add_reaction(&MessageReaction::new("m2", "bob", "✅")).await.unwrap());
+
+    let loaded = store.load_reactions(&["m1".to_string()]).await.unwrap();
+    assert_eq!(loaded.len(), 2);
+
+    assert!(store.remove_reaction("m1", "alice", "👍").await.unwrap());
+    assert!(!store.remove_reaction("m1", "alice", "👍").await.unwrap());
+    assert_eq!(store.load_reactions(&["m1".to_string(), "m2".to_string()]).await.unwrap().len(), 2);
+}
+
 #[tokio::test]
 async fn test_memory_store_messages() {
     let store = MemoryStore::new();
@@ -78,6 +113,53 @@ async fn test_memory_store_messages() {
     assert_eq!(messages[0].content, "Hello!");
 }
 
+#[tokio::test]
+async fn test_memory_store_delete_and_purge_messages() {
+    let store = MemoryStore::new();
+    let messages: Vec<Message> = (0..10)
+        .map(|i| Message { timestamp: 1_000 + i, ..Message::private("a1", "a2", format!("msg{}", i)) })
+        .collect();
+    store.save_messages(&messages).await.unwrap();
+    // 回应、置顶、书签和译文随消息一起删除
+    for message in [&messages[1], &messages[7], &messages[8]] {
+        store.add_reaction(&MessageReaction::new(&message.id, "alice", "👍")).await.unwrap();
+        store.add_pin(&MessagePin::new(&message.id, "s1", "alice")).await.unwrap();
+        store.add_bookmark(&MessageBookmark::new(&message.id, "alice")).await.unwrap();
+        let translation = MessageTranslation {
+            message_id: message.id.clone(),
+            language: "en".to_string(),
+            source_language: "cmn".to_string(),
+            text: message.content.clone(),
+            created_at: message.timestamp,
+        };
+        store.save_translation(&translation).await.unwrap();
+    }
+    // 归档中的消息同样会被删除
+    assert_eq!(store.archive_messages_before(1_002).await.unwrap(), 2);
+
+    assert_eq!(store.purge_messages_before(1_004).await.unwrap(), 4);
+    assert_eq!(store.purge_messages_before(1_004).await.unwrap(), 0);
+    assert!(store.delete_message(&messages[7].id).await.unwrap());
+    assert!(!store.delete_message(&messages[7].id).await.unwrap());
+    assert!(!store.delete_message(&messages[0].id).await.unwrap());
+
+    let loaded = store.load_messages(MessageFilter::new().oldest_first()).await.unwrap();
+    let contents: Vec<&str> = loaded.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, vec!["msg4", "msg5", "msg6", "msg8", "msg9"]);
+    assert!(store.load_message(&messages[7].id).await.unwrap().is_none());
+    let ids: Vec<String> = messages.iter().map(|m| m.id.clone()).collect();
+    let kept = vec![messages[8].id.clone()];
+    let reacted: Vec<String> = store.load_reactions(&ids).await.unwrap().into_iter().map(|r| r.message_id).collect();
+    assert_eq!(reacted, kept);
+    let pinned: Vec<String> = store.load_pins("s1").await.unwrap().into_iter().map(|p| p.message_id).collect();
+    assert_eq!(pinned, kept);
+    let bookmarked: Vec<String> = store.load_bookmarks("alice").await.unwrap().into_iter().map(|b| b.message_id).collect();
+    assert_eq!(bookmarked, kept);
+    for (message, translated) in [(&messages[1], false), (&messages[7], false), (&messages[8], true)] {
+        assert_eq!(store.load_translation(&message.id, "en").await.unwrap().is_some(), translated);
+    }
+}
+
 #[tokio::test]
 async fn test_memory_store_groups() {
     let store = MemoryStore::new();
@@ -99,3 +181,16 @@ async fn test_memory_store_groups() {
     let groups = store.load_groups().await.unwrap();
     assert_eq!(groups.len(), 0);
 }
+
+#[tokio::test]
+async fn test_memory_store_app_state() {
+    let store = MemoryStore::new();
+    assert!(store.load_app_state("k").await.unwrap().is_none());
+
+    store.save_app_state("k", &serde_json::json!({"day": 1})).await.unwrap();
+    store.save_app_state("k", &serde_json::json!({"day": 2})).await.unwrap();
+    assert_eq!(store.load_app_state("k").await.unwrap(), Some(serde_json::json!({"day": 2})));
+
+    store.delete_app_state("k").await.unwrap();
+    assert!(store.load_app_state("k").await.unwrap().is_none());
+}
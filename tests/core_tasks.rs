@@ -0,0 +1,330 @@
+//! 任务测试：状态流转、指派与截止提醒（手动时钟）、按负责人过滤、任务工具、上下文和任务接口
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::agent::{load_context, AgentRuntime};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::{MessageBus, MessageReceiver};
+use imitatort::core::store::{MemoryStore, MessageFilter, Store, TaskFilter};
+use imitatort::core::tasks::{TaskError, TaskManager, TaskUpdate, TASK_ID_KEY, TASK_SENDER};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::user::user_principal;
+use imitatort::domain::{Agent, LLMConfig, Organization, Role, Task, TaskStatus};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+const START: i64 = 1_700_000_000;
+const HOUR: i64 = 3600;
+
+fn task_error(error: anyhow::Error) -> TaskError {
+    error.downcast::<TaskError>().expect("typed task error")
+}
+
+#[tokio::test]
+async fn test_lifecycle_transitions() {
+    let tasks = TaskManager::new(Arc::new(MemoryStore::new())).with_clock(Arc::new(ManualClock::new(START)));
+    let task = tasks.create(Task::new("  Write release notes ", "lead", 0).with_assignee("dev")).await.unwrap();
+    assert_eq!(task.title, "Write release notes");
+    assert_eq!(task.status, TaskStatus::Open);
+    assert_eq!(task.created_at, START);
+
+    for status in [TaskStatus::InProgress, TaskStatus::Blocked, TaskStatus::InProgress, TaskStatus::Done] {
+        assert_eq!(tasks.update_status(&task.id, "dev", status).await.unwrap().status, status);
+    }
+
+    // 已关闭的任务只能重新打开
+    let err = task_error(tasks.update_status(&task.id, "dev", TaskStatus::InProgress).await.unwrap_err());
+    assert_eq!(
+        err,
+        TaskError::InvalidTransition { task_id: task.id.clone(), from: TaskStatus::Done, to: TaskStatus::InProgress }
+    );
+    assert_eq!(tasks.get(&task.id).await.unwrap().status, TaskStatus::Done);
+    tasks.update_status(&task.id, "lead", TaskStatus::Open).await.unwrap();
+    tasks.update_status(&task.id, "lead", TaskStatus::Cancelled).await.unwrap();
+    assert!(!TaskStatus::Cancelled.can_transition_to(TaskStatus::Done));
+    assert!(!TaskStatus::Open.can_transition_to(TaskStatus::Open));
+
+    assert_eq!(task_error(tasks.create(Task::new(" ", "lead", 0)).await.unwrap_err()), TaskError::EmptyTitle);
+    assert!(matches!(task_error(tasks.get("missing").await.unwrap_err()), TaskError::NotFound { .. }));
+    assert_eq!("in_progress".parse::<TaskStatus>().unwrap(), TaskStatus::InProgress);
+    assert!("finished".parse::<TaskStatus>().is_err());
+}
+
+#[tokio::test]
+async fn test_assignment_and_due_date_notifications() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let clock = Arc::new(ManualClock::new(START));
+    let bus = Arc::new(MessageBus::with_store(store.clone()).with_clock(clock.clone()));
+    let _lead = bus.register("lead");
+    let mut dev = MessageReceiver::new("dev".into(), bus.register("dev"));
+    let mut ops = MessageReceiver::new("ops".into(), bus.register("ops"));
+    let tasks = TaskManager::new(store.clone()).with_message_bus(bus.clone()).with_due_soon_secs(HOUR);
+
+    let task = tasks
+        .create(Task::new("Rotate certificates", "lead", 0).with_assignee("dev").with_due_at(START + 2 * HOUR))
+        .await
+        .unwrap();
+    let notice = dev.try_recv().unwrap();
+    assert_eq!(notice.from, TASK_SENDER);
+    assert_eq!(notice.metadata.get(TASK_ID_KEY), Some(&task.id));
+    assert!(notice.content.contains("lead assigned you task"), "{}", notice.content);
+    assert!(notice.content.contains("Rotate certificates"));
+
+    // 自己给自己的任务不通知
+    tasks.create(Task::new("Personal note", "dev", 0).with_assignee("dev")).await.unwrap();
+    assert!(dev.try_recv().is_none());
+
+    assert!(tasks.check_due().await.unwrap().is_empty());
+    clock.advance(Duration::from_secs(90 * 60));
+    let notified = tasks.check_due().await.unwrap();
+    assert_eq!(notified.len(), 1);
+    assert_eq!(notified[0].due_notified_at, Some(START + 90 * 60));
+    let reminder = dev.try_recv().unwrap();
+    assert!(reminder.content.contains("is due at"), "{}", reminder.content);
+
+    // 每个任务只提醒一次，逾期后也不再重复
+    assert!(tasks.check_due().await.unwrap().is_empty());
+    clock.advance(Duration::from_secs(2 * HOUR as u64));
+    assert!(tasks.check_due().await.unwrap().is_empty());
+    assert!(dev.try_recv().is_none());
+
+    // 重新指派后新负责人收到指派通知和逾期提醒
+    let update = TaskUpdate { assignee: Some("ops".into()), ..Default::default() };
+    tasks.update(&task.id, "lead", update).await.unwrap();
+    assert!(ops.try_recv().unwrap().content.contains("lead assigned you task"));
+    tasks.check_due().await.unwrap();
+    assert!(ops.try_recv().unwrap().content.contains("is overdue"));
+
+    // 已完成的任务不提醒
+    let done = tasks
+        .create(Task::new("Ship it", "lead", 0).with_assignee("ops").with_due_at(START + 5 * HOUR))
+        .await
+        .unwrap();
+    ops.try_recv().unwrap();
+    tasks.update_status(&done.id, "ops", TaskStatus::Done).await.unwrap();
+    assert!(tasks.check_due().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_filter_by_assignee_and_status() {
+    let stores: Vec<Arc<dyn Store>> = vec![Arc::new(MemoryStore::new()), Arc::new(SqliteStore::new_in_memory().unwrap())];
+    for store in stores {
+        let tasks = TaskManager::new(store.clone()).with_clock(Arc::new(ManualClock::new(START)));
+        let late = tasks.create(Task::new("late", "lead", 0).with_assignee("dev").with_due_at(START + 900)).await.unwrap();
+        let soon = tasks.create(Task::new("soon", "lead", 0).with_assignee("dev").with_due_at(START + 100)).await.unwrap();
+        let undated = tasks.create(Task::new("undated", "lead", 0).with_assignee("dev")).await.unwrap();
+        let other = tasks.create(Task::new("other", "lead", 0).with_assignee("ops")).await.unwrap();
+        tasks.update_status(&late.id, "dev", TaskStatus::Done).await.unwrap();
+
+        let titles = |found: Vec<Task>| found.into_iter().map(|t| t.title).collect::<Vec<_>>();
+        assert_eq!(titles(tasks.open_tasks("dev").await.unwrap()), vec!["soon", "undated"]);
+        assert_eq!(
+            titles(tasks.list(TaskFilter::new().assignee("dev")).await.unwrap()),
+            vec!["soon", "late", "undated"]
+        );
+        assert_eq!(
+            titles(tasks.list(TaskFilter::new().assignee("dev").status(TaskStatus::Done)).await.unwrap()),
+            vec!["late"]
+        );
+        assert_eq!(titles(tasks.list(TaskFilter::new().assignee("ops")).await.unwrap()), vec!["other"]);
+        assert_eq!(tasks.list(TaskFilter::new().creator("lead").limit(2)).await.unwrap().len(), 2);
+        assert_eq!(titles(tasks.list(TaskFilter::new().due_before(START + 500)).await.unwrap()), vec!["soon"]);
+
+        assert!(tasks.delete(&undated.id, "lead").await.unwrap());
+        assert!(!tasks.delete(&undated.id, "lead").await.unwrap());
+        assert_eq!(tasks.get(&soon.id).await.unwrap(), store.load_task(&soon.id).await.unwrap().unwrap());
+        assert_eq!(other.assignee.as_deref(), Some("ops"));
+    }
+}
+
+#[tokio::test]
+async fn test_task_tools() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _lead = bus.register("lead");
+    let mut dev = MessageReceiver::new("dev".into(), bus.register("dev"));
+    let _ops = bus.register("ops");
+    let tools = FrameworkToolExecutor::new(ToolEnvironment::new(
+        bus.clone(),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store.clone(),
+    ));
+    let call = |tool_id: &'static str, params: Value, caller: &'static str| {
+        let tools = &tools;
+        async move { tools.execute(tool_id, params, &ToolCallContext::new(caller)).await.unwrap() }
+    };
+
+    let result = call("task.create", json!({ "title": "Fix login bug", "assignee": "dev", "due_at": START }), "lead").await;
+    assert!(result.success, "{:?}", result.error);
+    let task_id = result.data["id"].as_str().unwrap().to_string();
+    assert!(dev.try_recv().unwrap().content.contains("Fix login bug"));
+
+    let result = call("task.list_mine", json!({}), "dev").await;
+    assert_eq!(result.data["count"], 1);
+    assert_eq!(result.data["tasks"][0]["id"], task_id);
+    assert_eq!(call("task.list_mine", json!({}), "ops").await.data["count"], 0);
+    let result = call("task.list_mine", json!({ "status": "finished" }), "dev").await;
+    assert!(!result.success);
+
+    // 只有创建者和负责人可以修改
+    let result = call("task.update_status", json!({ "task_id": task_id, "status": "done" }), "ops").await;
+    assert_eq!(result.error.unwrap(), format!("You are not the creator or assignee of task {}", task_id));
+    let result = call("task.update_status", json!({ "task_id": task_id, "status": "in_progress" }), "dev").await;
+    assert_eq!(result.data["status"], "in_progress");
+    let result = call("task.update_status", json!({ "task_id": "missing", "status": "done" }), "dev").await;
+    assert_eq!(result.error.unwrap(), "Task not found: missing");
+
+    // 负责人的评论发给创建者并关联到任务
+    let result = call("task.comment", json!({ "task_id": task_id, "content": "Found the cause" }), "dev").await;
+    assert!(result.success, "{:?}", result.error);
+    let message_id = result.data["message_id"].as_str().unwrap().to_string();
+    let stored = store.load_messages(MessageFilter::new().to("lead")).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].content, "Found the cause");
+    assert_eq!(stored[0].metadata.get(TASK_ID_KEY), Some(&task_id));
+    assert_eq!(store.load_task(&task_id).await.unwrap().unwrap().linked_message_ids, vec![message_id]);
+
+    let result = call("task.update_status", json!({ "task_id": task_id, "status": "done" }), "dev").await;
+    assert!(result.success);
+    let result = call("task.update_status", json!({ "task_id": task_id, "status": "blocked" }), "lead").await;
+    assert_eq!(result.error.unwrap(), format!("Task {} cannot move from done to blocked", task_id));
+}
+
+#[tokio::test]
+async fn test_context_includes_open_tasks() {
+    let store = Arc::new(MemoryStore::new());
+    let tasks = TaskManager::new(store.clone());
+    tasks.create(Task::new("Review PR 42", "lead", 0).with_assignee("dev")).await.unwrap();
+    let closed = tasks.create(Task::new("Old chore", "lead", 0).with_assignee("dev")).await.unwrap();
+    tasks.update_status(&closed.id, "dev", TaskStatus::Cancelled).await.unwrap();
+
+    let context = load_context(store.as_ref(), "dev", None).await.unwrap();
+    assert_eq!(context.open_tasks.len(), 1);
+    // 回放历史上下文时不带任务
+    assert!(load_context(store.as_ref(), "dev", Some(START)).await.unwrap().open_tasks.is_empty());
+
+    let agent = Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k"));
+    let prompt = AgentRuntime::new(agent).await.unwrap().build_thinking_prompt(&context);
+    // 指派通知在历史中，关闭的任务不在任务列表中
+    let section = prompt.split("Your open tasks:").nth(1).expect("open tasks section");
+    assert!(section.contains("Review PR 42 (open)"), "{}", prompt);
+    assert!(!section.contains("Old chore"));
+}
+
+#[tokio::test]
+async fn test_task_endpoints() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _dev = bus.register("dev");
+    let jwt_service = JwtService::new("test-secret");
+    let token = |id: &str, position: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: id.to_string(),
+                username: id.to_string(),
+                name: id.to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: position.to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    };
+    let (message_tx, _) = broadcast::channel(16);
+    let agent = Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k"));
+    let state = AppState::new(vec![agent], message_tx, store.clone(), jwt_service.clone()).with_message_bus(bus.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/tasks", addr);
+    let client = reqwest::Client::new();
+
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let response = client
+        .post(&url)
+        .bearer_auth(token("alice", "Employee"))
+        .json(&json!({ "title": "Prepare demo", "assignee": "dev", "due_at": START }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    let task_id = body["data"]["id"].as_str().unwrap().to_string();
+    assert_eq!(body["data"]["creator"], user_principal("alice"));
+    assert_eq!(store.load_messages(MessageFilter::new().to("dev")).await.unwrap().len(), 1);
+
+    client
+        .post(&url)
+        .bearer_auth(token("alice", "Employee"))
+        .json(&json!({ "title": "Book venue", "assignee": user_principal("alice") }))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = client
+        .get(format!("{}?assignee=dev&status=open,in_progress", url))
+        .bearer_auth(token("bob", "Employee"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["id"], task_id);
+    let response = client
+        .get(format!("{}?status=finished", url))
+        .bearer_auth(token("bob", "Employee"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    // 不相关的用户不能修改，管理员可以
+    let task_url = format!("{}/{}", url, task_id);
+    let response = client
+        .patch(&task_url)
+        .bearer_auth(token("bob", "Employee"))
+        .json(&json!({ "status": "done" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let body: Value = client
+        .patch(&task_url)
+        .bearer_auth(token("carol", "Management"))
+        .json(&json!({ "status": "done", "title": "Prepare the demo" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["status"], "done");
+    assert_eq!(body["data"]["title"], "Prepare the demo");
+    let response = client
+        .patch(&task_url)
+        .bearer_auth(token("alice", "Employee"))
+        .json(&json!({ "status": "blocked" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client.delete(&task_url).bearer_auth(token("alice", "Employee")).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let response = client.get(&task_url).bearer_auth(token("alice", "Employee")).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,377 @@
+//! 临时 Agent 测试：通过工具创建及上限、参与会话、过期后注销并保留会话记录、
+//! REST 创建和 Agent 列表过滤
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::core::temp_agents::{
+    SpawnRequest, TempAgentConfig, TempAgentError, TempAgentRunner, TemporaryAgents, TEMP_AGENT_ID_PREFIX,
+    TEMP_AGENT_SENDER,
+};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::{ToolCallContext, ToolProvider};
+use imitatort::domain::{Agent, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc, RwLock};
+
+const GROUP: &str = "war-room";
+
+/// 只注册到消息总线、不运行 LLM 的运行器，保留接收端供测试读取
+#[derive(Default)]
+struct FakeRunner {
+    bus: Mutex<Option<Arc<MessageBus>>>,
+    inboxes: Mutex<HashMap<String, mpsc::Receiver<Message>>>,
+    stopped: Mutex<Vec<String>>,
+}
+
+impl FakeRunner {
+    fn new(bus: Arc<MessageBus>) -> Arc<Self> {
+        let runner = Self::default();
+        *runner.bus.lock().unwrap() = Some(bus);
+        Arc::new(runner)
+    }
+
+    fn inbox(&self, agent_id: &str) -> mpsc::Receiver<Message> {
+        self.inboxes.lock().unwrap().remove(agent_id).expect("agent started")
+    }
+}
+
+#[async_trait]
+impl TempAgentRunner for FakeRunner {
+    async fn start(&self, agent: &Agent) -> anyhow::Result<()> {
+        let bus = self.bus.lock().unwrap().clone().unwrap();
+        let rx = bus.register(&agent.id);
+        self.inboxes.lock().unwrap().insert(agent.id.clone(), rx);
+        Ok(())
+    }
+
+    async fn stop(&self, agent_id: &str) {
+        self.stopped.lock().unwrap().push(agent_id.to_string());
+    }
+}
+
+struct Fixture {
+    store: Arc<MemoryStore>,
+    bus: Arc<MessageBus>,
+    clock: Arc<ManualClock>,
+    temp_agents: Arc<TemporaryAgents>,
+    runner: Arc<FakeRunner>,
+    _inboxes: Vec<mpsc::Receiver<Message>>,
+}
+
+fn org() -> Organization {
+    let mut org = Organization::new();
+    org.add_agent(Agent::new("lead", "Lead", Role::simple("Tech Lead", "You lead"), LLMConfig::openai("k")));
+    org.add_agent(
+        Agent::new("dba", "DBA", Role::simple("Database Admin", "You tune SQL"), LLMConfig::openai("k"))
+            .with_skills(vec!["sql".to_string()]),
+    );
+    org.add_agent(Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k")));
+    org
+}
+
+async fn fixture(config: TempAgentConfig) -> Fixture {
+    let store = Arc::new(MemoryStore::new());
+    store.save_organization(&org()).await.unwrap();
+    // 消息时间戳取自系统时间，时钟从当前时间开始
+    let clock = Arc::new(ManualClock::new(chrono::Utc::now().timestamp()));
+    let bus = Arc::new(MessageBus::with_store(store.clone()).with_clock(clock.clone()));
+    let _inboxes = ["lead", "dba", "dev"].iter().map(|id| bus.register(id)).collect();
+    let members = ["lead", "dev", "user:alice"].iter().map(|m| m.to_string()).collect();
+    bus.create_group(GROUP, "War room", "lead", members).await.unwrap();
+
+    let temp_agents =
+        Arc::new(TemporaryAgents::new(store.clone(), bus.clone(), config).with_clock(clock.clone()));
+    let runner = FakeRunner::new(bus.clone());
+    Fixture { store, bus, clock, temp_agents, runner, _inboxes }
+}
+
+fn environment(fixture: &Fixture) -> ToolEnvironment {
+    ToolEnvironment::new(
+        fixture.bus.clone(),
+        Arc::new(RwLock::new(org())),
+        Arc::new(ToolRegistry::new()),
+        fixture.store.clone(),
+    )
+}
+
+fn offers_spawn_tool(env: &ToolEnvironment) -> bool {
+    env.tool_provider.list_tools().iter().any(|t| t.id == "agent.spawn_temporary")
+}
+
+fn sql_expert() -> Value {
+    json!({ "session_id": GROUP, "title": "SQL tuning expert", "instructions": "Tune the slow queries" })
+}
+
+#[tokio::test]
+async fn test_spawn_via_tool_enforces_caps() {
+    let config = TempAgentConfig { max_active: 3, max_per_spawner: 2, ..TempAgentConfig::default() };
+    let fixture = fixture(config).await;
+    // 工具只在启用临时 Agent 时提供
+    let env = environment(&fixture).with_temp_agents(fixture.temp_agents.clone());
+    assert!(offers_spawn_tool(&env));
+    assert!(!offers_spawn_tool(&environment(&fixture)));
+    let executor = FrameworkToolExecutor::new(env);
+    let lead = ToolCallContext::new("lead");
+
+    // 公司运行前不能创建
+    let result = executor.execute("agent.spawn_temporary", sql_expert(), &lead).await.unwrap();
+    assert!(!result.success);
+    fixture.temp_agents.attach_runner(fixture.runner.clone()).await.unwrap();
+
+    let result = executor.execute("agent.spawn_temporary", sql_expert(), &lead).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let agent_id = result.data["agent_id"].as_str().unwrap().to_string();
+    assert!(agent_id.starts_with(TEMP_AGENT_ID_PREFIX));
+    assert_eq!(result.data["name"], "SQL tuning expert");
+    assert!(fixture.bus.is_registered(&agent_id));
+    assert!(fixture.bus.get_group(GROUP).await.unwrap().has_member(&agent_id));
+
+    // 按模板复制角色和技能
+    let params = json!({ "session_id": GROUP, "template": "dba", "name": "Query doctor", "ttl_secs": 600 });
+    let result = executor.execute("agent.spawn_temporary", params, &lead).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    let record = fixture.temp_agents.get(result.data["agent_id"].as_str().unwrap()).await.unwrap();
+    assert_eq!(record.agent.role.title, "Database Admin");
+    assert_eq!(record.agent.skills, vec!["sql"]);
+    assert_eq!(record.expires_at, Some(record.created_at + 600));
+
+    // 每人上限
+    let result = executor.execute("agent.spawn_temporary", sql_expert(), &lead).await.unwrap();
+    assert_eq!(
+        result.error.as_deref(),
+        Some("You already have 2 temporary agents (limit 2); try again after one expires")
+    );
+
+    // 全公司上限
+    let dev = ToolCallContext::new("dev");
+    assert!(executor.execute("agent.spawn_temporary", sql_expert(), &dev).await.unwrap().success);
+    let result = executor.execute("agent.spawn_temporary", sql_expert(), &dev).await.unwrap();
+    assert_eq!(
+        result.error.as_deref(),
+        Some("The company already has 3 temporary agents (limit 3); try again after one expires")
+    );
+    assert_eq!(fixture.temp_agents.list_active(Some(GROUP)).await.len(), 3);
+
+    // 临时 Agent 不能再创建临时 Agent，非成员不能为群聊创建
+    let nested = ToolCallContext::new(agent_id.as_str());
+    let result = executor.execute("agent.spawn_temporary", sql_expert(), &nested).await.unwrap();
+    assert_eq!(result.error.as_deref(), Some("Temporary agents cannot spawn other temporary agents"));
+    let error = fixture
+        .temp_agents
+        .spawn("dba", GROUP, SpawnRequest { template: Some("dba".into()), ..SpawnRequest::default() })
+        .await
+        .unwrap_err();
+    assert!(matches!(error.downcast_ref::<TempAgentError>(), Some(TempAgentError::NotParticipant { .. })));
+
+    // 临时 Agent 不进入组织架构
+    let org = fixture.store.load_organization().await.unwrap();
+    assert_eq!(org.agents.len(), 3);
+}
+
+#[tokio::test]
+async fn test_temporary_agent_participates_in_session() {
+    let fixture = fixture(TempAgentConfig::default()).await;
+    fixture.temp_agents.attach_runner(fixture.runner.clone()).await.unwrap();
+    let record = fixture.temp_agents.spawn("user:alice", GROUP, SpawnRequest {
+        role: Some(Role::simple("SQL tuning expert", "Tune the slow queries")),
+        ..SpawnRequest::default()
+    })
+    .await
+    .unwrap();
+    let expert = record.agent.id.clone();
+    assert_eq!(record.spawned_by, "user:alice");
+    // 未指定模板时使用组织中第一个 Agent 的模型
+    assert_eq!(record.agent.llm_config.api_key, "k");
+
+    let mut inbox = fixture.runner.inbox(&expert);
+    fixture.bus.send(Message::group("lead", GROUP, "Why is the report query slow?")).await.unwrap();
+    assert_eq!(inbox.recv().await.unwrap().content, "Why is the report query slow?");
+
+    // 临时 Agent 的回复和普通成员一样落库
+    fixture.bus.send(Message::group(&expert, GROUP, "Add an index on created_at")).await.unwrap();
+    let history = fixture.bus.get_group_message_history(GROUP, 10).await.unwrap();
+    assert!(history.iter().any(|m| m.from == expert));
+
+    // 私聊会话：用户可以在与 Agent 的私聊中创建，Agent 只能在自己的私聊中创建
+    let direct = fixture
+        .temp_agents
+        .spawn("lead", "lead", SpawnRequest { template: Some("dba".into()), ..SpawnRequest::default() })
+        .await
+        .unwrap();
+    assert!(fixture.bus.is_registered(&direct.agent.id));
+    let error = fixture
+        .temp_agents
+        .spawn("lead", "dev", SpawnRequest { template: Some("dba".into()), ..SpawnRequest::default() })
+        .await
+        .unwrap_err();
+    assert!(matches!(error.downcast_ref::<TempAgentError>(), Some(TempAgentError::NotParticipant { .. })));
+    let error = fixture
+        .temp_agents
+        .spawn("user:alice", "nowhere", SpawnRequest { template: Some("dba".into()), ..SpawnRequest::default() })
+        .await
+        .unwrap_err();
+    assert!(matches!(error.downcast_ref::<TempAgentError>(), Some(TempAgentError::SessionNotFound { .. })));
+}
+
+#[tokio::test]
+async fn test_expiry_unregisters_and_keeps_transcript() {
+    let config = TempAgentConfig { idle_timeout_secs: 1800, ..TempAgentConfig::default() };
+    let fixture = fixture(config).await;
+    fixture.temp_agents.attach_runner(fixture.runner.clone()).await.unwrap();
+    let mut alice = fixture.bus.subscribe_user("user:alice");
+
+    let timed = fixture
+        .temp_agents
+        .spawn("user:alice", GROUP, SpawnRequest {
+            template: Some("dba".into()),
+            ttl_secs: Some(600),
+            ..SpawnRequest::default()
+        })
+        .await
+        .unwrap();
+    let idle = fixture
+        .temp_agents
+        .spawn("lead", GROUP, SpawnRequest { template: Some("dba".into()), ..SpawnRequest::default() })
+        .await
+        .unwrap();
+    let (timed_id, idle_id) = (timed.agent.id.clone(), idle.agent.id.clone());
+    fixture.bus.send(Message::group(&timed_id, GROUP, "Index added, query is 40x faster")).await.unwrap();
+
+    // 未到期不过期
+    fixture.clock.advance(Duration::from_secs(599));
+    assert!(fixture.temp_agents.expire_idle().await.is_empty());
+
+    fixture.clock.advance(Duration::from_secs(1));
+    assert_eq!(fixture.temp_agents.expire_idle().await, vec![timed_id.clone()]);
+    assert!(!fixture.bus.is_registered(&timed_id));
+    assert!(!fixture.bus.get_group(GROUP).await.unwrap().has_member(&timed_id));
+    assert_eq!(*fixture.runner.stopped.lock().unwrap(), vec![timed_id.clone()]);
+    assert!(fixture.temp_agents.get(&timed_id).await.unwrap().expired_at.is_some());
+
+    // 创建者收到通知
+    let notice = loop {
+        let message = alice.recv().await.unwrap();
+        if message.from == TEMP_AGENT_SENDER {
+            break message;
+        }
+    };
+    assert!(notice.content.contains("Database Admin") && notice.content.contains(GROUP), "{}", notice.content);
+
+    // 会话记录保留
+    let transcript = fixture.store.load_messages(MessageFilter::new().from(&timed_id)).await.unwrap();
+    assert_eq!(transcript[0].content, "Index added, query is 40x faster");
+
+    // 闲置超时后过期，已过期的不能再次过期
+    fixture.clock.advance(Duration::from_secs(1200));
+    assert_eq!(fixture.temp_agents.expire_idle().await, vec![idle_id.clone()]);
+    assert!(!fixture.bus.is_registered(&idle_id));
+    assert!(fixture.temp_agents.list_active(None).await.is_empty());
+    let error = fixture.temp_agents.expire(&idle_id).await.unwrap_err();
+    assert!(matches!(error.downcast_ref::<TempAgentError>(), Some(TempAgentError::NotFound { .. })));
+
+    // 记录持久化，重启后不再恢复已过期的 Agent
+    let restarted = TemporaryAgents::new(fixture.store.clone(), fixture.bus.clone(), TempAgentConfig::default());
+    let runner = FakeRunner::new(fixture.bus.clone());
+    restarted.attach_runner(runner.clone()).await.unwrap();
+    assert!(restarted.get(&timed_id).await.unwrap().expired_at.is_some());
+    assert!(runner.inboxes.lock().unwrap().is_empty());
+}
+
+fn user(id: &str) -> UserInfo {
+    UserInfo {
+        id: id.to_string(),
+        username: id.to_string(),
+        name: id.to_uppercase(),
+        email: None,
+        is_director: false,
+        employee_id: "00001".to_string(),
+        position: "Employee".to_string(),
+        department: "eng".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_spawn_endpoint_and_agent_listing() {
+    let fixture = fixture(TempAgentConfig::default()).await;
+    fixture.temp_agents.attach_runner(fixture.runner.clone()).await.unwrap();
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(org().agents, message_tx, fixture.store.clone(), jwt.clone())
+        .with_message_bus(fixture.bus.clone())
+        .with_temp_agents(fixture.temp_agents.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let base = format!("http://{}/api/v1", addr);
+    let client = reqwest::Client::new();
+    let body = json!({ "template": "dba", "ttl_secs": 600 });
+
+    let response = client.post(format!("{}/chat/{}/spawn-agent", base, GROUP)).json(&body).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    // 不是群成员
+    let bob = jwt.generate_token(&user("bob")).unwrap();
+    let response = client
+        .post(format!("{}/chat/{}/spawn-agent", base, GROUP))
+        .bearer_auth(&bob)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let alice = jwt.generate_token(&user("alice")).unwrap();
+    let response = client
+        .post(format!("{}/chat/{}/spawn-agent", base, GROUP))
+        .bearer_auth(&alice)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let spawned: Value = response.json().await.unwrap();
+    let agent_id = spawned["data"]["agent"]["id"].as_str().unwrap().to_string();
+    assert_eq!(spawned["data"]["spawned_by"], "user:alice");
+
+    let response = client
+        .post(format!("{}/chat/nowhere/spawn-agent", base))
+        .bearer_auth(&alice)
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // 默认列表不含临时 Agent
+    let agents: Value = client.get(format!("{}/agents", base)).send().await.unwrap().json().await.unwrap();
+    let ids = |agents: &Value| -> Vec<String> {
+        agents["data"].as_array().unwrap().iter().map(|a| a["id"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(ids(&agents), vec!["lead", "dba", "dev"]);
+    assert!(agents["data"][0].get("temporary").is_none());
+
+    let agents: Value = client
+        .get(format!("{}/agents?include_temporary=true", base))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ids(&agents).len(), 4);
+    assert_eq!(agents["data"][3]["id"], agent_id);
+    assert_eq!(agents["data"][3]["temporary"]["session_id"], GROUP);
+    assert_eq!(agents["data"][3]["role"], "Database Admin");
+}
@@ -0,0 +1,274 @@
+//! 消息模板测试：渲染与缺失变量、角色模板优先、模板工具，以及沙箱的变量白名单、转义、输出上限、
+//! 随机生成的畸形模板（嵌套花括号、超长展开、递归引用）不会 panic 且输出有界，和各调用处的白名单
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::moderation::{ModerationConfig, ModerationService};
+use imitatort::core::proactive::{ProactiveConfig, ProactiveDispatcher, ProactivePolicy, ProactiveTrigger};
+use imitatort::core::store::MemoryStore;
+use imitatort::core::template::{self, Escape, TemplateError, TemplateSandbox};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Agent, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+
+#[test]
+fn test_render_fills_placeholders() {
+    let rendered = template::render(
+        "Status for {{ project }}: {{done}}/{{total}} done",
+        &json!({"project": "Apollo", "done": 3, "total": 5}),
+    )
+    .unwrap();
+    assert_eq!(rendered, "Status for Apollo: 3/5 done");
+}
+
+#[test]
+fn test_render_lists_all_missing_variables() {
+    let err = template::render("{{a}} {{b}} {{a}} {{c}}", &json!({"b": "x", "c": null})).unwrap_err();
+    assert_eq!(err, TemplateError::MissingVariables(vec!["a".to_string(), "c".to_string()]));
+
+    assert_eq!(
+        template::render("{{a", &json!({})).unwrap_err(),
+        TemplateError::Unterminated(0)
+    );
+}
+
+#[test]
+fn test_user_values_are_not_expanded() {
+    // 变量值中的占位符语法原样输出，不会再次展开
+    let rendered = template::render(
+        "Note: {{note}}",
+        &json!({"note": "{{secret}} \\{{x}}", "secret": "leaked"}),
+    )
+    .unwrap();
+    assert_eq!(rendered, "Note: {{secret}} \\{{x}}");
+
+    // 模板中的 \{{ 是字面量
+    let rendered = template::render("\\{{literal}} {{v}}", &json!({"v": "ok"})).unwrap();
+    assert_eq!(rendered, "{{literal}} ok");
+    assert_eq!(template::placeholders("\\{{literal}} {{v}}").unwrap(), vec!["v".to_string()]);
+}
+
+#[test]
+fn test_role_templates_take_precedence() {
+    let agent = Agent::new(
+        "pm",
+        "PM",
+        Role::simple("PM", "prompt").with_template("standup", "PM standup: {{summary}}"),
+        LLMConfig::openai("key"),
+    );
+    let company: HashMap<String, String> = [
+        ("standup".to_string(), "Standup: {{summary}}".to_string()),
+        ("report".to_string(), "Report: {{body}}".to_string()),
+    ]
+    .into();
+
+    assert_eq!(template::resolve(Some(&agent), &company, "standup"), Some("PM standup: {{summary}}"));
+    assert_eq!(template::resolve(Some(&agent), &company, "report"), Some("Report: {{body}}"));
+    assert_eq!(template::resolve(None, &company, "standup"), Some("Standup: {{summary}}"));
+    assert_eq!(template::resolve(Some(&agent), &company, "missing"), None);
+
+    let listed = template::list(&company, &[agent]);
+    assert_eq!(listed.len(), 3);
+    assert!(listed.iter().any(|t| t.scope == "role" && t.role.as_deref() == Some("PM")));
+}
+
+#[tokio::test]
+async fn test_template_tools() {
+    let mut org = Organization::new();
+    org.add_agent(Agent::new(
+        "pm",
+        "PM",
+        Role::simple("PM", "prompt").with_template("standup", "Standup: {{summary}}"),
+        LLMConfig::openai("key"),
+    ));
+    let bus = Arc::new(MessageBus::new());
+    let mut inbox = bus.register("dev");
+    let env = ToolEnvironment::new(
+        bus.clone(),
+        Arc::new(RwLock::new(org)),
+        Arc::new(ToolRegistry::new()),
+        Arc::new(MemoryStore::new()),
+    );
+    let executor = FrameworkToolExecutor::new(env);
+    let context = ToolCallContext::new("pm");
+
+    let result = executor
+        .execute("template.render", json!({"template": "standup", "variables": {}}), &context)
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("summary"));
+
+    let result = executor
+        .execute(
+            "message.send_templated",
+            json!({"template": "standup", "variables": {"summary": "all green"}, "to_agent_id": "dev"}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(inbox.recv().await.unwrap().content, "Standup: all green");
+
+    // 其他 Agent 看不到 PM 的角色模板
+    let result = executor
+        .execute("template.render", json!({"template": "standup"}), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert!(!result.success);
+}
+
+#[test]
+fn test_sandbox_whitelist_escaping_and_limit() {
+    let sandbox = TemplateSandbox::new().with_variables(&["name"]);
+    let variables = json!({"name": "<b>Ann</b> \"A&B\"", "api_key": "sk-secret"});
+    assert_eq!(
+        sandbox.render("Hi {{name}}, key {{api_key}}", &variables).unwrap_err(),
+        TemplateError::ForbiddenVariable("api_key".to_string())
+    );
+    assert_eq!(sandbox.check("{{name}} {{env.HOME}}").unwrap_err(), TemplateError::ForbiddenVariable("env.HOME".to_string()));
+    // 白名单内缺失的变量照常列出
+    assert_eq!(
+        sandbox.render("Hi {{name}}", &json!({"api_key": "sk-secret"})).unwrap_err(),
+        TemplateError::MissingVariables(vec!["name".to_string()])
+    );
+
+    assert_eq!(sandbox.render("<p>{{name}}</p>", &variables).unwrap(), "<p><b>Ann</b> \"A&B\"</p>");
+    assert_eq!(
+        sandbox.clone().with_escape(Escape::Html).render("<p>{{name}}</p>", &variables).unwrap(),
+        "<p>&lt;b&gt;Ann&lt;/b&gt; &quot;A&amp;B&quot;</p>"
+    );
+    let payload = sandbox
+        .clone()
+        .with_escape(Escape::Json)
+        .render("{\"text\": \"{{name}}\"}", &json!({"name": "line\n\"quoted\" \\ end"}))
+        .unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&payload).unwrap()["text"], "line\n\"quoted\" \\ end");
+
+    // 超长展开在达到上限时停止
+    let sandbox = TemplateSandbox::new().with_max_output_bytes(1024);
+    let huge = "{{v}}".repeat(10_000);
+    assert_eq!(sandbox.render(&huge, &json!({"v": "x".repeat(1000)})).unwrap_err(), TemplateError::OutputTooLarge(1024));
+    assert_eq!(sandbox.render("{{v}}", &json!({"v": "x".repeat(1024)})).unwrap().len(), 1024);
+}
+
+#[test]
+fn test_malformed_templates_fail_deterministically() {
+    let variables = json!({"a": "{{a}}", "b": "{{b}}{{b}}"});
+    // 嵌套的花括号不是变量名
+    assert_eq!(
+        template::render("{{ {{a}} }}", &variables).unwrap_err(),
+        TemplateError::InvalidPlaceholder("{{a".to_string())
+    );
+    assert_eq!(
+        template::render("{{a | upper}}", &variables).unwrap_err(),
+        TemplateError::InvalidPlaceholder("a | upper".to_string())
+    );
+    assert_eq!(template::render("{{}}", &variables).unwrap_err(), TemplateError::InvalidPlaceholder(String::new()));
+    // 引用自身的值不会展开
+    assert_eq!(template::render("{{a}}{{b}}", &variables).unwrap(), "{{a}}{{b}}{{b}}");
+    // 同一模板总是得到同一个错误
+    for _ in 0..3 {
+        assert_eq!(
+            TemplateSandbox::new().with_variables(&["a"]).render("{{zz}} {{b}} {{missing}}", &variables).unwrap_err(),
+            TemplateError::ForbiddenVariable("zz".to_string())
+        );
+    }
+}
+
+#[test]
+fn test_random_templates_never_panic_and_stay_bounded() {
+    const LIMIT: usize = 4096;
+    let fragments = [
+        "{{", "}}", "{", "}", "\\", "\\{{", "{{a}}", "{{ b }}", "{{c}}", "{{{{a}}}}", "{{a}", " ", "text", "日本語", "{{名字}}",
+        "{{a.b}}", "{{-}}", "\n",
+    ];
+    let values = json!({
+        "a": "{{b}}".repeat(500),
+        "b": "x".repeat(3000),
+        "c": {"nested": ["{{a}}"]},
+        "名字": "值",
+        "a.b": null,
+        "-": 42,
+    });
+    let mut rng = StdRng::seed_from_u64(1460);
+    for _ in 0..5_000 {
+        let template: String = (0..rng.gen_range(0..40)).map(|_| fragments[rng.gen_range(0..fragments.len())]).collect();
+        let escape = [Escape::None, Escape::Html, Escape::Json][rng.gen_range(0..3)];
+        let sandbox = TemplateSandbox::new().with_escape(escape).with_max_output_bytes(LIMIT);
+        let restricted = sandbox.clone().with_variables(&["a", "c"]);
+        for sandbox in [&sandbox, &restricted] {
+            let first = sandbox.render(&template, &values);
+            if let Ok(output) = &first {
+                assert!(output.len() <= LIMIT, "{} bytes from {:?}", output.len(), template);
+            }
+            assert_eq!(first, sandbox.render(&template, &values), "{:?}", template);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_call_sites_only_expose_their_variables() {
+    // 主动问候：只能引用所属触发类型的变量，保存策略时拒绝
+    let dispatcher = ProactiveDispatcher::new(ProactiveConfig::default(), Arc::new(MemoryStore::new()));
+    let leaking = ProactivePolicy::new().with_template(ProactiveTrigger::TaskAssigned, "{{task_title}} for {{years}}");
+    let err = dispatcher.save_policy("bob", &leaking).await.unwrap_err();
+    assert_eq!(err.downcast::<TemplateError>().unwrap(), TemplateError::ForbiddenVariable("years".to_string()));
+    assert!(dispatcher.policy("bob").await.unwrap().is_none());
+    let anniversary = ProactivePolicy::new().with_template(ProactiveTrigger::WorkAnniversary, "{{teammate_name}}: {{years}}");
+    dispatcher.save_policy("bob", &anniversary).await.unwrap();
+
+    // 审核拦截提示：只能引用 agent，其他变量退回内置提示；旧写法 {agent} 仍然可用
+    let blocked = Message::private("dev", "user:alice", "here is the exploit");
+    let notice = |notice: &str| {
+        ModerationService::new(
+            ModerationConfig::enabled()
+                .with_keywords(vec!["exploit".to_string()], vec![])
+                .with_notice(notice),
+            Arc::new(MemoryStore::new()),
+        )
+    };
+    let outcome = notice("{{agent}} withheld ({{content}})").review("Dev", blocked.clone(), false).await;
+    assert!(outcome.is_blocked());
+    assert_eq!(
+        outcome.message.content,
+        "This reply from Dev was withheld by content moderation. An administrator can review it."
+    );
+    let outcome = notice("{{ agent }} withheld").review("Dev", blocked.clone(), false).await;
+    assert_eq!(outcome.message.content, "Dev withheld");
+
+    // 模板工具：变量由调用者提供，可以选择转义方式
+    let templates: HashMap<String, String> = [("card".to_string(), "<h1>{{title}}</h1>".to_string())].into();
+    let executor = FrameworkToolExecutor::new(
+        ToolEnvironment::new(
+            Arc::new(MessageBus::new()),
+            Arc::new(RwLock::new(Organization::new())),
+            Arc::new(ToolRegistry::new()),
+            Arc::new(MemoryStore::new()),
+        )
+        .with_templates(Arc::new(RwLock::new(templates))),
+    );
+    let context = ToolCallContext::new("pm");
+    let result = executor
+        .execute(
+            "template.render",
+            json!({"template": "card", "variables": {"title": "<script>"}, "escape": "html"}),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.data, json!("<h1>&lt;script&gt;</h1>"));
+    let result = executor
+        .execute("template.render", json!({"template": "card", "variables": {}, "escape": "xml"}), &context)
+        .await
+        .unwrap();
+    assert_eq!(result.error.unwrap(), "Invalid escape: xml (expected none, html or json)");
+}
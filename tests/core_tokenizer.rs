@@ -0,0 +1,160 @@
+//! Token 计数测试：估算与 BPE 的已知计数、按消息 ID 缓存及编辑后失效、历史裁剪和用量估算
+
+use std::sync::Arc;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use imitatort::core::agent::{AgentRuntime, Context};
+use imitatort::core::tokenizer::{tokenizer_for, HeuristicTokenizer, TokenCounter, Tokenizer, MESSAGE_OVERHEAD_TOKENS};
+use imitatort::{Agent, LLMConfig, Message, Role};
+
+const ENGLISH: &str = "The quick brown fox jumps over the lazy dog.";
+const CHINESE: &str = "我们明天上午十点开会讨论发布计划。";
+
+fn agent(config: LLMConfig) -> Agent {
+    Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), config)
+}
+
+#[test]
+fn test_heuristic_counts_cjk_per_character() {
+    let tokenizer = HeuristicTokenizer;
+    assert_eq!(tokenizer.count("Hello, world!"), 4);
+    assert_eq!(tokenizer.count(ENGLISH), 11);
+    // 按 4 个字符一个 token 估算只有 5 个
+    assert_eq!(tokenizer.count(CHINESE), 17);
+    assert_eq!(tokenizer.count("你好，world"), 5);
+
+    assert_eq!(tokenizer.truncate(CHINESE, 4), "我们明天");
+    assert_eq!(tokenizer.truncate(ENGLISH, 100), ENGLISH);
+    assert_eq!(tokenizer.truncate(ENGLISH, 0), "");
+
+    // 未启用或未知的分词器退回估算
+    assert_eq!(tokenizer_for(&LLMConfig::openai("k").with_model("mock")).name(), "heuristic");
+    assert_eq!(tokenizer_for(&LLMConfig::openai("k").with_tokenizer("unknown")).name(), "heuristic");
+}
+
+#[cfg(feature = "tiktoken")]
+#[test]
+fn test_bpe_counts_match_tiktoken() {
+    use imitatort::core::tokenizer::BpeTokenizer;
+
+    let cl100k = BpeTokenizer::new("cl100k_base").unwrap();
+    assert_eq!(cl100k.count("Hello, world!"), 4);
+    assert_eq!(cl100k.count(ENGLISH), 10);
+    assert_eq!(cl100k.count("你好，世界！"), 7);
+    assert_eq!(cl100k.count(CHINESE), 17);
+
+    let o200k = BpeTokenizer::new("o200k_base").unwrap();
+    assert_eq!(o200k.count(ENGLISH), 10);
+    assert_eq!(o200k.count(CHINESE), 12);
+
+    // 显式配置优先，未配置时按模型推断
+    assert_eq!(tokenizer_for(&LLMConfig::openai("k")).name(), "o200k_base");
+    assert_eq!(tokenizer_for(&LLMConfig::openai("k").with_model("gpt-4")).name(), "cl100k_base");
+    assert_eq!(tokenizer_for(&LLMConfig::openai("k").with_tokenizer("cl100k_base")).name(), "cl100k_base");
+
+    let kept = cl100k.truncate(CHINESE, 5);
+    assert!(cl100k.count(kept) <= 5 && CHINESE.starts_with(kept), "{}", kept);
+}
+
+#[test]
+fn test_message_counts_cached_until_edited() {
+    let counter = TokenCounter::new(Arc::new(HeuristicTokenizer));
+    let mut message = Message::private("alice", "dev", CHINESE);
+
+    assert_eq!(counter.count_message(&message), 17 + MESSAGE_OVERHEAD_TOKENS);
+    assert_eq!(counter.count_message(&message), 17 + MESSAGE_OVERHEAD_TOKENS);
+    assert_eq!(counter.cached(), 1);
+
+    // 同一 ID 编辑后重新计数
+    message.content = ENGLISH.to_string();
+    assert_eq!(counter.count_message(&message), 11 + MESSAGE_OVERHEAD_TOKENS);
+    assert_eq!(counter.cached(), 1);
+
+    counter.invalidate(&message.id);
+    assert_eq!(counter.cached(), 0);
+}
+
+#[test]
+fn test_history_trimmed_to_token_budget() {
+    let counter = TokenCounter::new(Arc::new(HeuristicTokenizer));
+    let history = vec![
+        Message::private("alice", "dev", "old ".repeat(100)),
+        Message::private("alice", "dev", CHINESE),
+        Message::private("dev", "alice", ENGLISH),
+    ];
+    let per_message = |i: usize| counter.count_message(&history[i]);
+    let budget = per_message(1) + per_message(2);
+
+    let kept = counter.fit_newest(&history, budget);
+    assert_eq!(kept.len(), 2);
+    assert_eq!(kept[0].content, CHINESE);
+    assert!(counter.fit_newest(&history, budget - 1).len() == 1);
+    assert_eq!(counter.count_messages(&history), per_message(0) + budget);
+}
+
+#[tokio::test]
+async fn test_prompt_history_respects_budget() {
+    let runtime = AgentRuntime::new(agent(LLMConfig::openai("k")))
+        .await
+        .unwrap()
+        .with_history_token_budget(25);
+    let history = vec![
+        Message::private("alice", "dev", "an older message that no longer fits"),
+        Message::private("alice", "dev", CHINESE),
+    ];
+
+    let prompt = runtime.build_thinking_prompt(&Context::default().with_history(history));
+    assert!(prompt.contains(CHINESE), "{}", prompt);
+    assert!(!prompt.contains("no longer fits"));
+    assert_eq!(runtime.token_counter().cached(), 2);
+}
+
+/// 返回固定决策的模型服务，`usage` 为 None 时响应不带用量
+async fn spawn_llm(usage: Option<Value>) -> String {
+    async fn completions(State(usage): State<Option<Value>>) -> Json<Value> {
+        let decision = json!({ "action": "send_message", "target": "alice", "content": "收到，我马上处理。" });
+        let mut response = json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": decision.to_string() },
+                "finish_reason": "stop"
+            }]
+        });
+        if let Some(usage) = usage {
+            response["usage"] = usage;
+        }
+        Json(response)
+    }
+
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(usage);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_usage_estimated_when_provider_omits_it() {
+    let usage = json!({ "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 });
+    let runtime = AgentRuntime::new(agent(LLMConfig::openai("k").with_base_url(spawn_llm(Some(usage)).await)))
+        .await
+        .unwrap();
+    runtime.think(Context::default()).await.unwrap();
+    assert_eq!(runtime.tokens_used(), 15);
+
+    let runtime = AgentRuntime::new(agent(LLMConfig::openai("k").with_base_url(spawn_llm(None).await)))
+        .await
+        .unwrap();
+    let context = Context::default().with_messages(vec![Message::private("alice", "dev", CHINESE)]);
+    let prompt_tokens = HeuristicTokenizer.count(&runtime.build_thinking_prompt(&context));
+    runtime.think(context).await.unwrap();
+    assert!(runtime.tokens_used() > prompt_tokens as u64, "{}", runtime.tokens_used());
+}
@@ -0,0 +1,227 @@
+//! 工具别名与弃用测试：别名路由、按规范ID检查技能、停用日期后拒绝调用、搜索和列表展示
+
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::skill::SkillManager;
+use imitatort::core::store::MemoryStore;
+use imitatort::core::tool::{ToolDeprecationConfig, ToolRegistry, ToolRoute};
+use imitatort::core::tool_provider::{CompositeToolProvider, FrameworkToolProvider};
+use imitatort::core::tool_view::{AgentToolView, DEPRECATED_MARKER};
+use imitatort::domain::skill::{BindingType, Skill, SkillToolBinding};
+use imitatort::domain::tool::{CategoryPath, JsonSchema, Tool, ToolCallContext};
+use imitatort::domain::Organization;
+use imitatort::infrastructure::tool::{FnToolExecutor, FrameworkToolExecutor, ToolEnvironment, ToolExecutorRegistry};
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+fn app_tool(id: &str, description: &str) -> Tool {
+    Tool::new(id, id, description, CategoryPath::from_str("finance"), JsonSchema::object().build())
+}
+
+/// `finance.invoice` 已改名为 `finance.create_invoice`，新工具是绑定 finance 技能的私有工具
+async fn setup() -> (Arc<ToolRegistry>, Arc<SkillManager>, ToolExecutorRegistry) {
+    let registry = Arc::new(ToolRegistry::new());
+    registry
+        .register(app_tool("finance.create_invoice", "Create an invoice"))
+        .await
+        .unwrap();
+    registry.register_alias("finance.invoice", "finance.create_invoice").unwrap();
+
+    let skills = Arc::new(SkillManager::new_with_tool_registry(registry.clone()));
+    skills
+        .register_skill(Skill::new("finance", "Finance", "Bookkeeping", "finance", "1.0", "test"))
+        .unwrap();
+    skills
+        .bind_skill_tool(SkillToolBinding::new("finance", "finance.create_invoice", BindingType::Required))
+        .unwrap();
+
+    let mut executors = ToolExecutorRegistry::new(skills.clone());
+    executors.register(Box::new(FnToolExecutor::new("finance.create_invoice", |params| async move {
+        Ok(json!({ "invoice": params["amount"] }))
+    })));
+    (registry, skills, executors)
+}
+
+#[tokio::test]
+async fn test_alias_routes_to_new_executor() {
+    let (_registry, _skills, executors) = setup().await;
+    let context = ToolCallContext::new("accountant");
+    let finance = vec!["finance".to_string()];
+
+    let result = executors
+        .execute_with_skills("finance.invoice", json!({ "amount": 42 }), &context, &finance)
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data, json!({ "invoice": 42 }));
+    assert_eq!(result.metadata["canonical_id"], "finance.create_invoice");
+    assert!(result.metadata["deprecation"]
+        .as_str()
+        .unwrap()
+        .contains("finance.invoice is deprecated"));
+
+    // 统计记在规范ID下，另有别名调用计数
+    let stats = executors.stats();
+    assert_eq!(stats.usage("finance.create_invoice").unwrap().calls, 1);
+    assert!(stats.usage("finance.invoice").is_none());
+    assert_eq!(stats.alias_calls("finance.invoice"), 1);
+
+    // 直接调用新ID不带弃用提示
+    let result = executors
+        .execute_with_skills("finance.create_invoice", json!({ "amount": 1 }), &context, &finance)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert!(!result.metadata.contains_key("deprecation"));
+}
+
+#[tokio::test]
+async fn test_skill_check_uses_canonical_id() {
+    let (registry, skills, executors) = setup().await;
+    let context = ToolCallContext::new("dev");
+
+    assert!(skills.can_call_tool("finance.invoice", &["finance".to_string()]));
+    assert!(!skills.can_call_tool("finance.invoice", &[]));
+    assert!(executors.can_execute_with_skills("finance.invoice", &["finance".to_string()]));
+
+    let result = executors
+        .execute_with_skills("finance.invoice", json!({ "amount": 42 }), &context, &[])
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("finance.create_invoice"));
+
+    let provider = CompositeToolProvider::new()
+        .add_provider(Box::new(FrameworkToolProvider::new()))
+        .with_registry(registry);
+    let view = AgentToolView::new(Arc::new(provider), skills);
+    view.set_agent_skills("accountant", vec!["finance".to_string()]);
+    assert!(view.can_use("accountant", "finance.invoice"));
+    assert!(!view.can_use("dev", "finance.invoice"));
+}
+
+#[tokio::test]
+async fn test_alias_fails_after_sunset_when_enforced() {
+    let (registry, _skills, executors) = setup().await;
+    let context = ToolCallContext::new("accountant");
+    let finance = vec!["finance".to_string()];
+    registry.set_alias_sunset("finance.invoice", Some(date(2020, 1, 1))).unwrap();
+
+    // 默认过了停用日期仍然转发，只记录警告
+    let result = executors
+        .execute_with_skills("finance.invoice", json!({ "amount": 42 }), &context, &finance)
+        .await
+        .unwrap();
+    assert!(result.success);
+
+    registry.set_hard_fail_after_sunset(true);
+    assert!(matches!(registry.route_at("finance.invoice", date(2020, 1, 1)), ToolRoute::Alias(_)));
+    assert!(matches!(registry.route_at("finance.invoice", date(2020, 1, 2)), ToolRoute::Retired(_)));
+
+    let result = executors
+        .execute_with_skills("finance.invoice", json!({ "amount": 42 }), &context, &finance)
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert_eq!(
+        result.error.unwrap(),
+        "Tool finance.invoice was retired on 2020-01-01; use finance.create_invoice instead"
+    );
+    assert_eq!(result.metadata["canonical_id"], "finance.create_invoice");
+    assert_eq!(executors.stats().usage("finance.create_invoice").unwrap().calls, 1);
+}
+
+#[tokio::test]
+async fn test_alias_registration_rules() {
+    let registry = ToolRegistry::new();
+    registry.register(app_tool("finance.create_invoice", "Create an invoice")).await.unwrap();
+
+    assert!(registry.register_alias("finance.create_invoice", "finance.other").is_err());
+    assert!(registry.register_alias("finance.invoice", "finance.invoice").is_err());
+
+    // 别名链被压平：旧别名指向最终的规范ID
+    registry.register_alias("invoice.create", "finance.invoice").unwrap();
+    registry.register_alias("finance.invoice", "finance.create_invoice").unwrap();
+    assert_eq!(registry.canonical_id("invoice.create"), "finance.create_invoice");
+    assert_eq!(registry.canonical_id("finance.invoice"), "finance.create_invoice");
+    assert_eq!(registry.canonical_id("time.now"), "time.now");
+
+    // 已是别名的ID不能再注册为工具
+    assert!(registry.register(app_tool("finance.invoice", "Old")).await.is_err());
+
+    let config: ToolDeprecationConfig = serde_json::from_value(json!({
+        "aliases": [{ "from": "clock.now", "to": "time.now", "sunset": "2030-06-30" }],
+        "hard_fail_after_sunset": true
+    }))
+    .unwrap();
+    registry.apply_deprecation_config(&config).unwrap();
+    let alias = registry.alias("clock.now").unwrap();
+    assert_eq!(alias.canonical, "time.now");
+    assert_eq!(alias.sunset, Some(date(2030, 6, 30)));
+    assert!(matches!(registry.route_at("clock.now", date(2030, 7, 1)), ToolRoute::Retired(_)));
+}
+
+#[tokio::test]
+async fn test_framework_tools_search_and_listing() {
+    let registry = Arc::new(ToolRegistry::new());
+    registry
+        .register(app_tool("finance.create_invoice", "Create an invoice"))
+        .await
+        .unwrap();
+    registry
+        .register(app_tool("finance.bill", "Old billing tool").with_deprecated("Use finance.create_invoice."))
+        .await
+        .unwrap();
+    registry.register_alias("finance.invoice", "finance.create_invoice").unwrap();
+    registry.register_alias("clock.now", "time.now").unwrap();
+
+    let env = ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(Organization::new())),
+        registry.clone(),
+        Arc::new(MemoryStore::new()),
+    );
+    let executor = FrameworkToolExecutor::new(env.clone());
+    let context = ToolCallContext::new("dev");
+
+    // 框架工具也可以通过别名调用
+    let result = executor.execute("clock.now", json!({}), &context).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.metadata["canonical_id"], "time.now");
+    assert!(result.metadata.contains_key("deprecation"));
+    assert_eq!(env.tool_stats.alias_calls("clock.now"), 1);
+
+    // 搜索旧ID返回规范ID
+    let result = executor
+        .execute("tool.search", json!({ "query": "finance.invoice", "match_type": "exact" }), &context)
+        .await
+        .unwrap();
+    let tools = result.data["tools"].as_array().unwrap();
+    assert_eq!(tools.len(), 1, "{:?}", tools);
+    assert_eq!(tools[0]["id"], "finance.create_invoice");
+    assert_eq!(tools[0]["matched_alias"], "finance.invoice");
+
+    // 已弃用的工具在搜索结果和列表中都带有说明
+    let result = executor
+        .execute("tool.search", json!({ "query": "billing" }), &context)
+        .await
+        .unwrap();
+    let bill: &Value = &result.data["tools"][0];
+    assert_eq!(bill["id"], "finance.bill");
+    assert_eq!(bill["deprecated"], "Use finance.create_invoice.");
+
+    let view = AgentToolView::new(env.tool_provider.clone(), Arc::new(SkillManager::new_with_tool_registry(registry)));
+    let tools = view.tools_for("dev");
+    let bill = tools.iter().find(|t| t.id == "finance.bill").unwrap();
+    assert_eq!(
+        bill.description,
+        format!("{} Use finance.create_invoice. Old billing tool", DEPRECATED_MARKER)
+    );
+}
@@ -0,0 +1,208 @@
+//! 工具并发限制测试
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::tool_concurrency::{BusyPolicy, ToolConcurrency, ToolConcurrencyConfig, ToolConcurrencyLimit};
+use imitatort::domain::tool::{CategoryPath, JsonSchema, Tool, ToolCallContext};
+use imitatort::infrastructure::tool::{FnToolExecutor, ToolExecutorRegistry, ToolResult};
+use serde_json::json;
+
+/// 记录同时执行的调用数及其峰值
+#[derive(Clone, Default)]
+struct Tracker {
+    active: Arc<AtomicUsize>,
+    peak: Arc<AtomicUsize>,
+}
+
+impl Tracker {
+    fn executor(&self, tool_id: &str, hold: Duration) -> FnToolExecutor {
+        let tracker = self.clone();
+        FnToolExecutor::new(tool_id, move |_| {
+            let tracker = tracker.clone();
+            async move {
+                let now = tracker.active.fetch_add(1, Ordering::SeqCst) + 1;
+                tracker.peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(hold).await;
+                tracker.active.fetch_sub(1, Ordering::SeqCst);
+                Ok(json!({"ok": true}))
+            }
+        })
+    }
+
+    fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+}
+
+fn tool(id: &str) -> Tool {
+    Tool::new(id, id, "test tool", CategoryPath::from_str("test"), JsonSchema::object().build())
+}
+
+async fn registry(tools: Vec<Tool>, config: ToolConcurrencyConfig) -> ToolExecutorRegistry {
+    let tool_registry = Arc::new(ToolRegistry::new());
+    for tool in tools {
+        tool_registry.register(tool).await.unwrap();
+    }
+    ToolExecutorRegistry::with_default_skill_manager(tool_registry)
+        .with_concurrency(Arc::new(ToolConcurrency::new(config)))
+}
+
+async fn run_all(registry: Arc<ToolExecutorRegistry>, calls: &[&str]) -> Vec<ToolResult> {
+    let handles: Vec<_> = calls
+        .iter()
+        .map(|tool_id| {
+            let registry = registry.clone();
+            let tool_id = tool_id.to_string();
+            tokio::spawn(async move {
+                registry.execute(&tool_id, json!({}), &ToolCallContext::new("agent")).await.unwrap()
+            })
+        })
+        .collect();
+    let mut results = Vec::new();
+    for handle in handles {
+        results.push(handle.await.unwrap());
+    }
+    results
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_mutex_group_serializes_tools() {
+    let tracker = Tracker::default();
+    let mut registry = registry(
+        vec![
+            tool("deploy.apply").with_mutex_group("deploy"),
+            tool("deploy.rollback").with_mutex_group("deploy"),
+        ],
+        ToolConcurrencyConfig::default(),
+    )
+    .await;
+    registry.register(Box::new(tracker.executor("deploy.apply", Duration::from_millis(40))));
+    registry.register(Box::new(tracker.executor("deploy.rollback", Duration::from_millis(40))));
+    let registry = Arc::new(registry);
+
+    let results = run_all(registry.clone(), &["deploy.apply", "deploy.rollback", "deploy.apply", "deploy.rollback"]).await;
+
+    assert!(results.iter().all(|r| r.success));
+    assert_eq!(tracker.peak(), 1);
+
+    // 排队的调用在结果里带上排队时长和提示
+    let queued: Vec<&ToolResult> = results.iter().filter(|r| r.metadata.contains_key("queued_ms")).collect();
+    assert_eq!(queued.len(), 3);
+    assert!(queued.iter().any(|r| r.metadata["queued_ms"].as_u64().unwrap() >= 30));
+    assert!(queued[0].metadata["notice"].as_str().unwrap().contains("was busy, queued for"));
+
+    let metrics = registry.concurrency().metrics();
+    let queued_total: u64 = metrics.iter().map(|m| m.queued).sum();
+    assert_eq!(queued_total, 3);
+    assert_eq!(metrics.iter().map(|m| m.acquired).sum::<u64>(), 4);
+    assert!(metrics.iter().any(|m| m.max_wait_ms >= 30));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_reject_policy_fails_fast() {
+    let tracker = Tracker::default();
+    let mut registry = registry(
+        vec![
+            tool("deploy.apply").with_mutex_group("deploy"),
+            tool("deploy.rollback").with_mutex_group("deploy"),
+        ],
+        ToolConcurrencyConfig::default().with_policy(BusyPolicy::Reject),
+    )
+    .await;
+    registry.register(Box::new(tracker.executor("deploy.apply", Duration::from_millis(200))));
+    registry.register(Box::new(tracker.executor("deploy.rollback", Duration::from_millis(10))));
+    let registry = Arc::new(registry);
+
+    let running = {
+        let registry = registry.clone();
+        tokio::spawn(async move {
+            registry.execute("deploy.apply", json!({}), &ToolCallContext::new("a")).await.unwrap()
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let rejected = registry.execute("deploy.rollback", json!({}), &ToolCallContext::new("b")).await.unwrap();
+    assert!(!rejected.success);
+    assert_eq!(rejected.metadata["busy"], true);
+    assert!(rejected.error.unwrap().contains("group deploy"));
+
+    assert!(running.await.unwrap().success);
+    // 释放后可以立即执行
+    let after = registry.execute("deploy.rollback", json!({}), &ToolCallContext::new("b")).await.unwrap();
+    assert!(after.success);
+    assert!(after.metadata.is_empty());
+
+    let metrics = registry.concurrency().metrics();
+    let rollback = metrics.iter().find(|m| m.tool_id == "deploy.rollback").unwrap();
+    assert_eq!(rollback.rejected, 1);
+    assert_eq!(rollback.acquired, 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_wait_timeout_rejects() {
+    let tracker = Tracker::default();
+    let mut registry = registry(
+        vec![tool("api.call").with_max_concurrency(1)],
+        ToolConcurrencyConfig::default().with_max_wait_ms(30),
+    )
+    .await;
+    registry.register(Box::new(tracker.executor("api.call", Duration::from_millis(200))));
+    let registry = Arc::new(registry);
+
+    let results = run_all(registry, &["api.call", "api.call"]).await;
+    let rejected: Vec<&ToolResult> = results.iter().filter(|r| !r.success).collect();
+    assert_eq!(rejected.len(), 1);
+    assert!(rejected[0].metadata["queued_ms"].as_u64().unwrap() >= 30);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_max_concurrency_and_config_override() {
+    // 没有声明限制的工具可以在配置里设置
+    let tracker = Tracker::default();
+    let mut limited = registry(
+        vec![tool("files.write")],
+        ToolConcurrencyConfig::default().with_tool("files.write", ToolConcurrencyLimit::max_concurrency(2)),
+    )
+    .await;
+    limited.register(Box::new(tracker.executor("files.write", Duration::from_millis(40))));
+    let results = run_all(Arc::new(limited), &["files.write"; 6]).await;
+    assert!(results.iter().all(|r| r.success));
+    assert_eq!(tracker.peak(), 2);
+
+    // 没有任何限制时完全并发
+    let free = Tracker::default();
+    let mut unlimited = registry(vec![tool("files.read")], ToolConcurrencyConfig::default()).await;
+    unlimited.register(Box::new(free.executor("files.read", Duration::from_millis(40))));
+    let results = run_all(Arc::new(unlimited), &["files.read"; 4]).await;
+    assert!(results.iter().all(|r| r.success && r.metadata.is_empty()));
+    assert_eq!(free.peak(), 4);
+}
+
+#[test]
+fn test_config_overrides_tool_declaration() {
+    let concurrency = ToolConcurrency::new(
+        ToolConcurrencyConfig::default().with_tool("deploy.apply", ToolConcurrencyLimit::mutex_group("release")),
+    );
+    let declared = tool("deploy.apply").with_mutex_group("deploy").with_max_concurrency(3);
+
+    assert_eq!(
+        concurrency.limit_for("deploy.apply", Some(&declared)),
+        ToolConcurrencyLimit::mutex_group("release")
+    );
+    assert_eq!(
+        concurrency.limit_for("other", Some(&tool("other").with_max_concurrency(3))),
+        ToolConcurrencyLimit::max_concurrency(3)
+    );
+    assert!(concurrency.limit_for("unknown", None).is_unlimited());
+
+    let config: ToolConcurrencyConfig = serde_json::from_value(json!({
+        "policy": "reject",
+        "tools": {"deploy.apply": {"mutex_group": "deploy"}}
+    }))
+    .unwrap();
+    assert_eq!(config.policy, BusyPolicy::Reject);
+    assert_eq!(config.tools["deploy.apply"], ToolConcurrencyLimit::mutex_group("deploy"));
+}
@@ -0,0 +1,204 @@
+//! OpenAI 函数定义导入测试：名称与分类映射、参数透传、往返转换、校验错误、批量导入与执行器注册
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::{CategoryPath, JsonSchema, OpenAIFunctionError, OpenAIFunctionImport, Tool, ToolCallContext};
+use imitatort::infrastructure::tool::{FnToolExecutor, ToolExecutorRegistry};
+
+fn fixture_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/openai_functions.json")
+}
+
+/// 夹具中的函数定义（去掉 Assistants 内置工具）
+fn fixture_functions() -> Vec<Value> {
+    let fixture: Value = serde_json::from_str(&std::fs::read_to_string(fixture_path()).unwrap()).unwrap();
+    fixture["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|t| t["type"] == "function")
+        .map(|t| t["function"].clone())
+        .collect()
+}
+
+fn error(value: Value) -> OpenAIFunctionError {
+    Tool::from_openai_function(&value).unwrap_err()
+}
+
+#[test]
+fn test_round_trip_fixture_functions() {
+    let functions = fixture_functions();
+    assert_eq!(functions.len(), 12);
+
+    for function in &functions {
+        let tool = Tool::from_openai_function(function).unwrap();
+        assert_eq!(&tool.to_openai_function(), function, "{}", function["name"]);
+
+        // 包装格式与裸函数等价
+        let wrapped = Tool::from_openai_function(&json!({ "type": "function", "function": function })).unwrap();
+        assert_eq!(wrapped.id, tool.id);
+        assert_eq!(wrapped.parameters, tool.parameters);
+    }
+}
+
+#[test]
+fn test_name_and_category_mapping() {
+    let functions = fixture_functions();
+    let find = |name: &str| functions.iter().find(|f| f["name"] == name).unwrap();
+
+    let tool = Tool::from_openai_function(find("crm__deals__update_stage")).unwrap();
+    assert_eq!(tool.id, "crm.deals.update_stage");
+    assert_eq!(tool.name, "crm__deals__update_stage");
+    assert_eq!(tool.category, CategoryPath::from_str("crm/deals"));
+    assert_eq!(tool.required_params(), vec!["deal_id", "stage"]);
+    assert_eq!(tool.parameters["properties"]["stage"]["enum"][3], "won");
+
+    // 嵌套 object 参数原样保留
+    let tool = Tool::from_openai_function(find("crm__create_contact")).unwrap();
+    assert_eq!(tool.category, CategoryPath::from_str("crm"));
+    assert_eq!(
+        tool.parameters["properties"]["company"]["properties"]["address"]["required"],
+        json!(["city", "country"])
+    );
+
+    let weather = find("get_current_weather");
+    let tool = Tool::from_openai_function(weather).unwrap();
+    assert_eq!(tool.id, "get_current_weather");
+    assert_eq!(tool.category, CategoryPath::from_str("imported"));
+
+    let options = OpenAIFunctionImport::default()
+        .with_default_category("external")
+        .with_category("get_current_weather", "data/weather")
+        .with_category("crm__create_contact", "sales");
+    let tool = Tool::from_openai_function_with(weather, &options).unwrap();
+    assert_eq!(tool.category, CategoryPath::from_str("data/weather"));
+    let tool = Tool::from_openai_function_with(find("crm__create_contact"), &options).unwrap();
+    assert_eq!(tool.category, CategoryPath::from_str("sales"));
+    let tool = Tool::from_openai_function_with(find("send_email"), &options).unwrap();
+    assert_eq!(tool.category, CategoryPath::from_str("external"));
+}
+
+#[test]
+fn test_missing_parameters_and_description() {
+    let tool = Tool::from_openai_function(&json!({ "name": "ping" })).unwrap();
+    assert_eq!(tool.description, "");
+    assert_eq!(tool.parameters, json!({ "type": "object", "properties": {} }));
+
+    // 框架自己构建的工具导出后也能导回
+    let native = Tool::new(
+        "file.read",
+        "Read",
+        "Read a file",
+        CategoryPath::from_str("file"),
+        JsonSchema::object().property("path", JsonSchema::string()).build(),
+    );
+    let imported = Tool::from_openai_function(&native.to_openai_function()).unwrap();
+    assert_eq!(imported.id, "file.read");
+    assert_eq!(imported.category, native.category);
+    assert_eq!(imported.parameters, native.parameters);
+}
+
+#[test]
+fn test_validation_errors_name_the_function() {
+    let err = error(json!({ "name": "bad name", "parameters": { "type": "object" } }));
+    assert_eq!(err.function, "bad name");
+
+    let err = error(json!({ "name": "create_ticket", "parameters": { "type": "string" } }));
+    assert_eq!(err.function, "create_ticket");
+    assert!(err.to_string().starts_with("Invalid OpenAI function create_ticket:"), "{}", err);
+
+    let err = error(json!({
+        "name": "create_ticket",
+        "parameters": { "type": "object", "properties": { "title": { "type": "string" } }, "required": ["title", "priority"] }
+    }));
+    assert!(err.reason.contains("`priority`"), "{}", err.reason);
+
+    let err = error(json!({ "name": "crm____create" }));
+    assert_eq!(err.function, "crm____create");
+    let err = error(json!({ "name": "x".repeat(65) }));
+    assert_eq!(err.function.len(), 65);
+    let err = error(json!({ "name": "lookup", "description": 42 }));
+    assert_eq!(err.function, "lookup");
+    let err = error(json!({ "description": "no name" }));
+    assert_eq!(err.function, "<unnamed>");
+}
+
+fn executors(registry: &Arc<ToolRegistry>) -> ToolExecutorRegistry {
+    ToolExecutorRegistry::with_default_skill_manager(registry.clone())
+}
+
+#[tokio::test]
+async fn test_import_file_registers_tools_with_executors() {
+    let registry = Arc::new(ToolRegistry::new());
+    let mut executors = executors(&registry);
+
+    let mut created = Vec::new();
+    let imported = registry
+        .import_openai_functions(fixture_path(), &OpenAIFunctionImport::default(), &mut executors, |tool| {
+            created.push(tool.id.clone());
+            let tool_id = tool.id.clone();
+            Box::new(FnToolExecutor::new(tool.id.clone(), move |params| {
+                let tool_id = tool_id.clone();
+                async move { Ok(json!({ "forwarded": tool_id, "params": params })) }
+            }))
+        })
+        .await
+        .unwrap();
+
+    // 内置的 code_interpreter / file_search 被跳过
+    assert_eq!(imported.len(), 12);
+    assert_eq!(registry.len(), 12);
+    assert_eq!(created.len(), 12);
+    assert_eq!(registry.find_by_category("crm").await.len(), 3);
+    assert_eq!(registry.find_direct_by_category("crm/deals").await.len(), 1);
+
+    let result = executors
+        .execute("billing.issue_refund", json!({ "payment_id": "pay_1" }), &ToolCallContext::new("finance"))
+        .await
+        .unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data, json!({ "forwarded": "billing.issue_refund", "params": { "payment_id": "pay_1" } }));
+}
+
+#[tokio::test]
+async fn test_import_is_all_or_nothing() {
+    let registry = Arc::new(ToolRegistry::new());
+    let mut executors = executors(&registry);
+    let factory = |tool: &Tool| -> Box<dyn imitatort::ToolExecutor> {
+        Box::new(FnToolExecutor::new(tool.id.clone(), |_| async { Ok(Value::Null) }))
+    };
+
+    let batch = json!([
+        { "name": "ok_one", "parameters": { "type": "object", "properties": {} } },
+        { "type": "function", "function": { "description": "missing name" } },
+    ]);
+    let err = registry
+        .import_openai_functions(batch, &OpenAIFunctionImport::default(), &mut executors, factory)
+        .await
+        .unwrap_err();
+    assert_eq!(err.downcast_ref::<OpenAIFunctionError>().unwrap().function, "#1");
+    assert!(registry.is_empty());
+
+    let batch = json!({ "functions": [{ "name": "crm__sync" }, { "name": "crm__sync" }] });
+    let err = registry
+        .import_openai_functions(batch, &OpenAIFunctionImport::default(), &mut executors, factory)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Invalid OpenAI function crm__sync: defined more than once");
+    assert!(registry.is_empty());
+
+    registry
+        .import_openai_functions(json!({ "name": "crm__sync" }), &OpenAIFunctionImport::default(), &mut executors, factory)
+        .await
+        .unwrap();
+    let err = registry
+        .import_openai_functions(json!([{ "name": "ping" }, { "name": "crm__sync" }]), &OpenAIFunctionImport::default(), &mut executors, factory)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("crm.sync is already registered"), "{}", err);
+    assert!(!registry.contains("ping"));
+}
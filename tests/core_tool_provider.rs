@@ -34,3 +34,11 @@ fn test_list_by_category() {
     assert!(!tools.is_empty());
     assert!(tools.iter().all(|t| t.category.to_path_string().starts_with("tool")));
 }
+
+#[test]
+fn test_code_run_is_optional() {
+    assert!(!FrameworkToolProvider::new().list_tools().iter().any(|t| t.id == "code.run"));
+    let provider = FrameworkToolProvider::new().with_code_run();
+    let tool = provider.list_tools().into_iter().find(|t| t.id == "code.run").unwrap();
+    assert_eq!(tool.category.to_path_string(), "code/run");
+}
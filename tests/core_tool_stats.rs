@@ -0,0 +1,142 @@
+//! 工具调用统计测试
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::tool_stats::ToolStats;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::Organization;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FnToolExecutor, FrameworkToolExecutor, ToolEnvironment, ToolExecutorRegistry};
+use serde_json::json;
+use tokio::sync::RwLock;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_executions_are_counted_exactly() {
+    let tool_registry = Arc::new(ToolRegistry::new());
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(tool_registry);
+    registry.register(Box::new(FnToolExecutor::new("test.ok", |_| async move {
+        Ok(json!({ "ok": true }))
+    })));
+    registry.register(Box::new(FnToolExecutor::new("test.fail", |_| async move {
+        Err(anyhow::anyhow!("boom"))
+    })));
+    let registry = Arc::new(registry);
+
+    let mut handles = Vec::new();
+    for i in 0..300 {
+        let registry = registry.clone();
+        handles.push(tokio::spawn(async move {
+            let context = ToolCallContext::new(format!("agent-{}", i % 3));
+            let tool_id = if i % 5 == 0 { "test.fail" } else { "test.ok" };
+            let _ = registry.execute(tool_id, json!({}), &context).await;
+        }));
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    let stats = registry.stats();
+    let ok = stats.usage("test.ok").unwrap();
+    assert_eq!(ok.calls, 240);
+    assert_eq!(ok.successes, 240);
+    assert_eq!(ok.errors, 0);
+
+    let fail = stats.usage("test.fail").unwrap();
+    assert_eq!(fail.calls, 60);
+    assert_eq!(fail.successes, 0);
+    assert_eq!(fail.errors, 60);
+
+    let top = stats.top_n(1);
+    assert_eq!(top[0].tool_id, "test.ok");
+
+    let failures = stats.recent_failures("agent-0", 5);
+    assert_eq!(failures.len(), 5);
+    assert!(failures.iter().all(|f| f.tool_id == "test.fail" && f.error == "boom"));
+}
+
+#[tokio::test]
+async fn test_daily_stats_survive_restart() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("stats.db");
+
+    {
+        let store = SqliteStore::new(&db_path).unwrap();
+        let stats = ToolStats::new();
+        stats.record("time.now", "a", Duration::from_millis(4), None);
+        stats.record("time.now", "a", Duration::from_millis(6), Some("timeout"));
+        stats.persist_daily(&store).await.unwrap();
+    }
+
+    let store = SqliteStore::new(&db_path).unwrap();
+    let restored = ToolStats::new();
+    restored.load_daily(&store).await.unwrap();
+
+    let usage = restored.usage("time.now").unwrap();
+    assert_eq!(usage.calls, 2);
+    assert_eq!(usage.successes, 1);
+    assert_eq!(usage.errors, 1);
+    assert_eq!(usage.total_latency_ms, 10);
+    assert_eq!(usage.avg_latency_ms(), 5.0);
+}
+
+#[tokio::test]
+async fn test_counters_reset_at_date_change() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    // 2025-03-01 23:59:00 UTC
+    let clock = Arc::new(ManualClock::new(1_740_873_540));
+    let stats = ToolStats::new().with_clock(clock.clone());
+    stats.record("time.now", "a", Duration::from_millis(2), None);
+    stats.persist_daily(&store).await.unwrap();
+    stats.record("time.now", "a", Duration::from_millis(2), None);
+
+    // 跨过零点：写回前的调用计入前一天，计数器从零开始
+    clock.advance(Duration::from_secs(120));
+    stats.persist_daily(&store).await.unwrap();
+    assert_eq!(stats.day(), "2025-03-02");
+    assert!(stats.usage("time.now").is_none());
+    assert_eq!(store.load_tool_stats("2025-03-01").await.unwrap()[0].calls, 2);
+    assert!(store.load_tool_stats("2025-03-02").await.unwrap().is_empty());
+
+    stats.record("time.now", "a", Duration::from_millis(2), None);
+    stats.persist_daily(&store).await.unwrap();
+    assert_eq!(store.load_tool_stats("2025-03-02").await.unwrap()[0].calls, 1);
+    assert_eq!(store.load_tool_stats("2025-03-01").await.unwrap()[0].calls, 2);
+
+    // 重启后只恢复当天的汇总
+    let restored = ToolStats::new().with_clock(clock);
+    restored.load_daily(&store).await.unwrap();
+    assert_eq!(restored.usage("time.now").unwrap().calls, 1);
+}
+
+#[tokio::test]
+async fn test_tool_stats_framework_tool() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let stats = Arc::new(ToolStats::new());
+    stats.record("org.get_leader", "agent-1", Duration::from_millis(1), Some("no leader"));
+    stats.record("time.now", "agent-2", Duration::from_millis(1), None);
+
+    let env = ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store,
+    )
+    .with_tool_stats(stats);
+    let executor = FrameworkToolExecutor::new(env);
+
+    let result = executor
+        .execute("tool.stats", json!({ "top_n": 5 }), &ToolCallContext::new("agent-1"))
+        .await
+        .unwrap();
+
+    assert!(result.success);
+    assert_eq!(result.data["top_tools"].as_array().unwrap().len(), 2);
+    let failures = result.data["my_recent_failures"].as_array().unwrap();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0]["tool_id"], "org.get_leader");
+}
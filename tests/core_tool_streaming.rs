@@ -0,0 +1,220 @@
+//! 流式工具测试：分阶段进度按序转发给 WebSocket 订阅者、普通执行器透明桥接、活动超时随进度重置、
+//! Watchdog 规则按进度和停滞触发、进度摘要
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::store::MemoryStore;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::watchdog::{TriggerCondition, WatchdogFramework, WatchdogRule};
+use imitatort::domain::tool::{ToolCallContext, ToolProgress, ToolProgressEvent};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::tool::{
+    tool_stream, FnToolExecutor, StreamingToolExecutor, ToolExecutorRegistry, ToolStream,
+};
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+/// 每隔 `step` 报告一次进度（共 `stages` 次），最后一次进度后再等 `tail` 返回结果
+struct StagedTool {
+    stages: u32,
+    step: Duration,
+    tail: Duration,
+}
+
+impl StreamingToolExecutor for StagedTool {
+    fn execute_stream(&self, _tool_id: &str, params: Value, _context: &ToolCallContext) -> ToolStream {
+        let (stages, step, tail) = (self.stages, self.step, self.tail);
+        tool_stream(move |progress| async move {
+            for stage in 1..=stages {
+                tokio::time::sleep(step).await;
+                let percent = stage as f32 * 100.0 / stages as f32;
+                progress
+                    .report(
+                        ToolProgress::message(format!("stage {}", stage))
+                            .with_percent(percent)
+                            .with_partial_data(json!({ "rows": stage * 10 })),
+                    )
+                    .await;
+            }
+            tokio::time::sleep(tail).await;
+            Ok(json!({ "rows": stages * 10, "input": params }))
+        })
+    }
+
+    fn can_execute(&self, tool_id: &str) -> bool {
+        tool_id == "report.build"
+    }
+
+    fn supported_tools(&self) -> Vec<String> {
+        vec!["report.build".to_string()]
+    }
+}
+
+fn registry(tool: StagedTool) -> ToolExecutorRegistry {
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(Arc::new(ToolRegistry::new()));
+    registry.register_streaming(Arc::new(tool));
+    registry
+}
+
+fn staged(stages: u32, step_ms: u64, tail_ms: u64) -> StagedTool {
+    StagedTool {
+        stages,
+        step: Duration::from_millis(step_ms),
+        tail: Duration::from_millis(tail_ms),
+    }
+}
+
+#[tokio::test]
+async fn test_progress_is_delivered_in_order() {
+    let mut registry = registry(staged(3, 20, 0)).with_progress_summary();
+    let mut progress = registry.subscribe_progress();
+    assert!(registry.can_execute("report.build"));
+    assert_eq!(registry.list_supported_tools(), vec!["report.build"]);
+
+    let context = ToolCallContext::new("dev").with_trace_id("trace-1");
+    let result = registry.execute("report.build", json!({ "q": 1 }), &context).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.data["rows"], 30);
+    assert_eq!(result.metadata["progress_updates"], 3);
+    let summary = result.metadata["progress_summary"].as_str().unwrap();
+    assert!(summary.starts_with("Tool report.build ran 0s, 3 progress updates, final result: {"), "{}", summary);
+
+    let events: Vec<ToolProgressEvent> = (0..3).map(|_| progress.try_recv().unwrap()).collect();
+    assert_eq!(events.iter().map(|e| e.sequence).collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(events[0].progress.message.as_deref(), Some("stage 1"));
+    assert_eq!(events[2].progress.percent, Some(100.0));
+    assert_eq!(events[1].progress.partial_data, Some(json!({ "rows": 20 })));
+    assert!(events.iter().all(|e| e.caller_id == "dev" && e.trace_id == "trace-1"));
+    assert!(events[0].elapsed_ms <= events[2].elapsed_ms);
+
+    // 普通执行器被透明桥接：没有进度，也没有摘要
+    let mut registry = registry.with_activity_timeout(Duration::from_millis(500));
+    registry.register(Box::new(FnToolExecutor::new("math.add", |params| async move {
+        Ok(json!(params["a"].as_i64().unwrap() + params["b"].as_i64().unwrap()))
+    })));
+    let result = registry.execute("math.add", json!({ "a": 1, "b": 2 }), &context).await.unwrap();
+    assert_eq!(result.data, json!(3));
+    assert!(!result.metadata.contains_key("progress_updates"));
+    assert!(!result.metadata.contains_key("progress_summary"));
+}
+
+#[tokio::test]
+async fn test_activity_timeout_resets_on_progress() {
+    let context = ToolCallContext::new("dev");
+
+    // 共运行约 400ms，但每 80ms 有一次进度，150ms 的活动超时不会触发
+    let registry = registry(staged(5, 80, 0)).with_activity_timeout(Duration::from_millis(150));
+    let result = registry.execute("report.build", json!({}), &context).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.metadata["progress_updates"], 5);
+
+    // 最后一次进度后停滞，超时返回失败结果并记入统计
+    let registry = registry_with_tail(Duration::from_secs(5)).with_activity_timeout(Duration::from_millis(150));
+    let started = std::time::Instant::now();
+    let result = registry.execute("report.build", json!({}), &context).await.unwrap();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(!result.success);
+    assert_eq!(result.metadata["timed_out"], true);
+    assert_eq!(result.metadata["progress_updates"], 2);
+    let error = result.error.unwrap();
+    assert!(error.starts_with("Tool report.build made no progress for 0.") && error.ends_with("s and was stopped"), "{}", error);
+    assert_eq!(registry.stats().snapshot()[0].errors, 1);
+
+    // 普通执行器没有进度，活动超时相当于整体超时
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(Arc::new(ToolRegistry::new()))
+        .with_activity_timeout(Duration::from_millis(100));
+    registry.register(Box::new(FnToolExecutor::new("slow.op", |_| async {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok(json!("done"))
+    })));
+    let result = registry.execute("slow.op", json!({}), &context).await.unwrap();
+    assert_eq!(result.metadata["timed_out"], true);
+}
+
+fn registry_with_tail(tail: Duration) -> ToolExecutorRegistry {
+    registry(StagedTool { stages: 2, step: Duration::from_millis(20), tail })
+}
+
+#[tokio::test]
+async fn test_watchdog_triggers_on_progress_and_stall() {
+    let events = Arc::new(EventBus::new());
+    let mut triggered = events.subscribe();
+    let watchdog = Arc::new(WatchdogFramework::new().with_events(events.clone()));
+    let halfway = TriggerCondition::NumericRange { min: 50.0, max: 60.0 };
+    watchdog
+        .register_rule(WatchdogRule::new("halfway", "report.build", halfway, "lead").with_trigger_on_progress())
+        .unwrap();
+    let stalled = TriggerCondition::StringContains { content: "\"stalled\":true".to_string() };
+    watchdog
+        .register_rule(WatchdogRule::new("stalled", "report.build", stalled, "ops").with_trigger_on_stall())
+        .unwrap();
+
+    // 第 2 次进度是 50%；之后停滞 300ms，超过 100ms 的停滞阈值一次
+    let registry = registry(StagedTool {
+        stages: 4,
+        step: Duration::from_millis(10),
+        tail: Duration::from_millis(300),
+    })
+    .with_stall_threshold(Duration::from_millis(100))
+    .with_watchdog(watchdog);
+    let result = registry.execute("report.build", json!({}), &ToolCallContext::new("dev")).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+
+    let mut rules = Vec::new();
+    while let Ok(event) = triggered.try_recv() {
+        if let CompanyEvent::WatchdogTriggered { rule_id, target_agent_id, .. } = event {
+            rules.push((rule_id.to_string(), target_agent_id.to_string()));
+        }
+    }
+    assert_eq!(rules, vec![("halfway".into(), "lead".into()), ("stalled".into(), "ops".into())]);
+}
+
+async fn next_frame(
+    socket: &mut tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+) -> Value {
+    let frame = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+    serde_json::from_str(frame.to_text().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_progress_frames_reach_subscribed_websocket_clients() {
+    let (progress_tx, _) = broadcast::channel(64);
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, Arc::new(MemoryStore::new()), JwtService::new("secret"))
+        .with_tool_progress(progress_tx.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/v1/ws", addr)).await.unwrap();
+    let subscribe = json!({ "type": "subscribe_tool_progress", "agent_id": "dev" });
+    socket.send(WsMessage::Text(subscribe.to_string())).await.unwrap();
+    // pong 说明订阅已处理
+    socket.send(WsMessage::Text(json!({ "type": "ping" }).to_string())).await.unwrap();
+    assert_eq!(next_frame(&mut socket).await["type"], "pong");
+
+    // 其他 Agent 的进度被过滤
+    let registry = registry(staged(3, 10, 0)).with_progress_sender(progress_tx);
+    registry.execute("report.build", json!({}), &ToolCallContext::new("other")).await.unwrap();
+    registry.execute("report.build", json!({}), &ToolCallContext::new("dev")).await.unwrap();
+
+    for sequence in 1..=3 {
+        let frame = next_frame(&mut socket).await;
+        assert_eq!(frame["type"], "tool_progress", "{}", frame);
+        assert_eq!(frame["v"], 1);
+        assert_eq!(frame["data"]["caller_id"], "dev");
+        assert_eq!(frame["data"]["tool_id"], "report.build");
+        assert_eq!(frame["data"]["sequence"], sequence);
+        assert_eq!(frame["data"]["message"], format!("stage {}", sequence));
+    }
+    socket.send(WsMessage::Text(json!({ "type": "ping" }).to_string())).await.unwrap();
+    assert_eq!(next_frame(&mut socket).await["type"], "pong");
+}
@@ -0,0 +1,203 @@
+//! Agent 工具视图测试
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use imitatort::core::agent::{AgentRuntime, Context, Decision};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::skill::SkillManager;
+use imitatort::core::store::MemoryStore;
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::tool_provider::{CompositeToolProvider, FrameworkToolProvider};
+use imitatort::core::tool_view::{AgentToolView, APPROVAL_MARKER};
+use imitatort::domain::tool::{CategoryPath, JsonSchema, MatchType, Tool, ToolCallContext, ToolProvider};
+use imitatort::domain::skill::{BindingType, Skill, SkillToolBinding};
+use imitatort::domain::{Agent, LLMConfig, Organization, Role};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+
+fn app_tool(id: &str, description: &str) -> Tool {
+    Tool::new(id, id, description, CategoryPath::from_str("finance"), JsonSchema::object().build())
+}
+
+fn framework_tool_ids() -> BTreeSet<String> {
+    FrameworkToolProvider::new().list_tools().into_iter().map(|t| t.id).collect()
+}
+
+fn ids(tools: &[Tool]) -> BTreeSet<String> {
+    tools.iter().map(|t| t.id.clone()).collect()
+}
+
+/// 注册表里有一个绑定到 finance 技能的私有工具
+async fn setup() -> (Arc<ToolRegistry>, Arc<SkillManager>, AgentToolView) {
+    let registry = Arc::new(ToolRegistry::new());
+    registry.register(app_tool("finance.invoice", "Create an invoice")).await.unwrap();
+    let skills = Arc::new(SkillManager::new_with_tool_registry(registry.clone()));
+    skills
+        .register_skill(Skill::new("finance", "Finance", "Bookkeeping", "finance", "1.0", "test"))
+        .unwrap();
+    skills
+        .bind_skill_tool(SkillToolBinding::new("finance", "finance.invoice", BindingType::Required))
+        .unwrap();
+
+    let provider = CompositeToolProvider::new()
+        .add_provider(Box::new(FrameworkToolProvider::new()))
+        .with_registry(registry.clone());
+    let view = AgentToolView::new(Arc::new(provider), skills.clone());
+    view.set_agent_skills("accountant", vec!["finance".to_string()]);
+    (registry, skills, view)
+}
+
+#[tokio::test]
+async fn test_skill_less_agent_sees_only_public_framework_tools() {
+    let (_registry, _skills, view) = setup().await;
+
+    assert_eq!(ids(&view.tools_for("dev")), framework_tool_ids());
+    assert!(!view.can_use("dev", "finance.invoice"));
+
+    let mut expected = framework_tool_ids();
+    expected.insert("finance.invoice".to_string());
+    assert_eq!(ids(&view.tools_for("accountant")), expected);
+}
+
+#[tokio::test]
+async fn test_disabled_and_approval_tools() {
+    let (registry, _skills, view) = setup().await;
+    registry
+        .register(app_tool("finance.pay", "Send a payment").with_requires_approval())
+        .await
+        .unwrap();
+    let view = view.with_disabled_tools(["message.send_group"]);
+
+    let tools = view.tools_for("dev");
+    assert!(!tools.iter().any(|t| t.id == "message.send_group"));
+    let pay = tools.iter().find(|t| t.id == "finance.pay").unwrap();
+    assert_eq!(pay.description, format!("{} Send a payment", APPROVAL_MARKER));
+}
+
+#[tokio::test]
+async fn test_view_cache_follows_registry_and_skill_changes() {
+    let (registry, skills, view) = setup().await;
+    let before = view.tools_for("dev");
+    assert!(Arc::ptr_eq(&before, &view.tools_for("dev")));
+
+    registry.register(app_tool("finance.rates", "Exchange rates")).await.unwrap();
+    assert!(view.can_use("dev", "finance.rates"));
+
+    skills
+        .bind_skill_tool(SkillToolBinding::new("finance", "finance.rates", BindingType::Optional))
+        .unwrap();
+    assert!(!view.can_use("dev", "finance.rates"));
+
+    view.set_agent_skills("dev", vec!["finance".to_string()]);
+    assert!(view.can_use("dev", "finance.rates"));
+}
+
+#[tokio::test]
+async fn test_search_within_view() {
+    let (registry, _skills, view) = setup().await;
+    assert!(view.search("dev", "invoice", MatchType::Fuzzy, false).is_empty());
+    assert_eq!(view.search("accountant", "invoice", MatchType::Fuzzy, false).len(), 1);
+    assert_eq!(view.search("dev", "invoice", MatchType::Fuzzy, true).len(), 1);
+
+    let env = ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(Organization::new())),
+        registry,
+        Arc::new(MemoryStore::new()),
+    )
+    .with_tool_view(Arc::new(view));
+    let executor = FrameworkToolExecutor::new(env);
+
+    let result = executor
+        .execute("tool.search", json!({"query": "invoice"}), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert_eq!(result.data["count"], 0);
+
+    let result = executor
+        .execute(
+            "tool.search",
+            json!({"query": "invoice", "include_unavailable": true}),
+            &ToolCallContext::new("dev"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.data["count"], 1);
+    assert_eq!(result.data["tools"][0]["available"], false);
+}
+
+/// 记录请求体，并让模型调用 `tool.search` 的 LLM
+async fn spawn_recording_llm() -> (String, Arc<Mutex<Vec<Value>>>) {
+    async fn completions(State(requests): State<Arc<Mutex<Vec<Value>>>>, Json(request): Json<Value>) -> Json<Value> {
+        requests.lock().unwrap().push(request);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "tool__search", "arguments": "{\"query\":\"invoice\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        }))
+    }
+
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state(requests.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (format!("http://{}", addr), requests)
+}
+
+#[tokio::test]
+async fn test_llm_request_contains_only_the_agent_view() {
+    let (base_url, requests) = spawn_recording_llm().await;
+    let (_registry, _skills, view) = setup().await;
+    let agent = Agent::new(
+        "dev",
+        "Dev",
+        Role::simple("Developer", "You write code"),
+        LLMConfig::openai("test-key").with_base_url(base_url),
+    );
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+
+    let decision = runtime
+        .think_with_tools(Context::default(), &view.tools_for("dev"))
+        .await
+        .unwrap();
+    match decision {
+        Decision::CallTool { tool_id, arguments } => {
+            assert_eq!(tool_id, "tool.search");
+            assert_eq!(arguments["query"], "invoice");
+        }
+        other => panic!("unexpected decision: {:?}", other),
+    }
+
+    let requests = requests.lock().unwrap();
+    let sent: BTreeSet<String> = requests[0]["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["function"]["name"].as_str().unwrap().to_string())
+        .collect();
+    let expected: BTreeSet<String> = framework_tool_ids().iter().map(|id| id.replace('.', "__")).collect();
+    assert_eq!(sent, expected);
+}
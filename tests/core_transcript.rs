@@ -0,0 +1,272 @@
+//! 会话记录导出测试
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::transcript::{
+    export_stream, participant_names, Transcript, TranscriptFormat, TranscriptPager, TranscriptRenderer,
+    TranscriptSession, TRANSCRIPT_SCHEMA,
+};
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::{Agent, Group, LLMConfig, Message, MessageReaction, Organization, Role};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use imitatort::infrastructure::web::{create_router, AppState};
+use tokio::sync::{broadcast, RwLock};
+
+const START: i64 = 1_700_000_000;
+
+fn agent(id: &str, name: &str) -> Agent {
+    Agent::new(id, name, Role::simple("Engineer", "You are an engineer"), LLMConfig::openai("test-key"))
+}
+
+fn agents() -> Vec<Agent> {
+    vec![agent("alice", "Alice"), agent("bob", "Bob")]
+}
+
+fn at(mut message: Message, id: &str, offset: i64) -> Message {
+    message.id = id.to_string();
+    message.timestamp = START + offset;
+    message
+}
+
+async fn seed(store: &dyn Store) {
+    store
+        .save_group(&Group::new("launch", "Launch Plan", "alice", vec!["alice".to_string(), "bob".to_string()]))
+        .await
+        .unwrap();
+
+    let messages = [
+        at(
+            Message::group("alice", "launch", "Ship on Friday?\nChecklist attached.")
+                .with_mention("bob")
+                .with_attachment("https://files.example.com/plan.pdf"),
+            "m1",
+            0,
+        ),
+        at(Message::group("bob", "launch", "Yes, QA signed off.").with_reply_to("m1"), "m2", 60),
+        at(Message::group("alice", "launch", "Great, booking the release.").with_reply_to("m2"), "m3", 120),
+        at(Message::group("carol", "launch", "Who is on call?"), "m4", 180),
+        at(Message::private("alice", "bob", "Not part of the group"), "d1", 90),
+    ];
+    for message in &messages {
+        store.save_message(message).await.unwrap();
+    }
+    store.add_reaction(&MessageReaction::new("m2", "alice", "👍")).await.unwrap();
+    store.add_reaction(&MessageReaction::new("m2", "carol", "👍")).await.unwrap();
+}
+
+async fn export(store: Arc<dyn Store>, format: TranscriptFormat, session: TranscriptSession, page_size: usize) -> String {
+    let names = participant_names(store.as_ref(), &agents()).await.unwrap();
+    let renderer = TranscriptRenderer::new(format, session.clone(), "Launch Plan").with_names(names);
+    let pager = TranscriptPager::new(store, session, None, None).with_page_size(page_size);
+    let chunks: Vec<String> = export_stream(renderer, pager).map(|chunk| chunk.unwrap()).collect().await;
+    chunks.concat()
+}
+
+const EXPECTED_MARKDOWN: &str = "# Launch Plan
+
+_Group conversation `launch`_
+
+**Alice** · 2023-11-14 22:13 UTC
+
+Ship on Friday?
+Checklist attached.
+
+- 📎 [plan.pdf](https://files.example.com/plan.pdf)
+
+_Mentions: @Bob_
+
+> **Bob** · 2023-11-14 22:14 UTC · ↳ reply to Alice
+>
+> Yes, QA signed off.
+>
+> _Reactions: 👍 2_
+
+> > **Alice** · 2023-11-14 22:15 UTC · ↳ reply to Bob
+> >
+> > Great, booking the release.
+
+**carol** · 2023-11-14 22:16 UTC
+
+Who is on call?
+
+---
+
+4 messages
+";
+
+#[tokio::test]
+async fn test_markdown_snapshot() {
+    let store = Arc::new(MemoryStore::new());
+    seed(store.as_ref()).await;
+
+    let markdown = export(store, TranscriptFormat::Markdown, TranscriptSession::Group("launch".into()), 200).await;
+    assert_eq!(markdown, EXPECTED_MARKDOWN);
+}
+
+#[tokio::test]
+async fn test_small_pages_render_the_same() {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    seed(store.as_ref()).await;
+    // 同一秒内的多条消息跨页也不会重复或遗漏
+    for (i, id) in ["x1", "x2", "x3"].iter().enumerate() {
+        let message = at(Message::group("bob", "other", format!("tick {}", i)), id, 500);
+        store.save_message(&message).await.unwrap();
+    }
+
+    let session = TranscriptSession::Group("launch".into());
+    assert_eq!(export(store.clone(), TranscriptFormat::Markdown, session, 1).await, EXPECTED_MARKDOWN);
+
+    let ticks = export(store, TranscriptFormat::Json, TranscriptSession::Group("other".into()), 2).await;
+    let transcript: Transcript = serde_json::from_str(&ticks).unwrap();
+    let ids: Vec<&str> = transcript.messages.iter().map(|e| e.message.id.as_str()).collect();
+    assert_eq!(ids, vec!["x1", "x2", "x3"]);
+}
+
+#[tokio::test]
+async fn test_direct_session_includes_both_directions() {
+    let store = Arc::new(MemoryStore::new());
+    seed(store.as_ref()).await;
+    store.save_message(&at(Message::private("bob", "alice", "Got it"), "d2", 95)).await.unwrap();
+
+    let json = export(store, TranscriptFormat::Json, TranscriptSession::Direct("alice".into()), 200).await;
+    let transcript: Transcript = serde_json::from_str(&json).unwrap();
+    let ids: Vec<&str> = transcript.messages.iter().map(|e| e.message.id.as_str()).collect();
+    assert_eq!(ids, vec!["d1", "d2"]);
+}
+
+#[tokio::test]
+async fn test_json_schema_round_trips_to_messages() {
+    let store = Arc::new(MemoryStore::new());
+    seed(store.as_ref()).await;
+
+    let json = export(store, TranscriptFormat::Json, TranscriptSession::Group("launch".into()), 200).await;
+    let transcript: Transcript = serde_json::from_str(&json).unwrap();
+    assert_eq!(transcript.schema, TRANSCRIPT_SCHEMA);
+    assert_eq!(transcript.session.kind, "group");
+    assert_eq!(transcript.count, 4);
+    assert_eq!(transcript.messages[1].sender_name, "Bob");
+    assert_eq!(transcript.messages[1].reactions[0].count, 2);
+
+    // 每一项都能直接作为 Message 重新导入
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let messages: Vec<Message> = serde_json::from_value(value["messages"].clone()).unwrap();
+    assert_eq!(messages[0].attachments(), vec!["https://files.example.com/plan.pdf"]);
+    assert_eq!(messages[0].mentions, vec!["bob"]);
+    assert_eq!(messages[2].reply_to.as_deref(), Some("m2"));
+
+    let reimported = MemoryStore::new();
+    reimported.save_messages(&messages).await.unwrap();
+    assert_eq!(reimported.load_messages_by_group("launch", 10).await.unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_html_escapes_content() {
+    let store = Arc::new(MemoryStore::new());
+    store.save_message(&at(Message::group("alice", "g", "<script>alert(1)</script>"), "h1", 0)).await.unwrap();
+
+    let html = export(store, TranscriptFormat::Html, TranscriptSession::Group("g".into()), 200).await;
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    assert!(!html.contains("<script>"));
+    assert!(html.trim_end().ends_with("</html>"));
+}
+
+#[tokio::test]
+async fn test_export_endpoint_streams_download() {
+    let store = Arc::new(MemoryStore::new());
+    seed(store.as_ref()).await;
+    let (message_tx, _) = broadcast::channel::<Message>(16);
+    let jwt_service = imitatort::infrastructure::auth::JwtService::new("test-secret");
+    let app = create_router(Arc::new(AppState::new(agents(), message_tx, store, jwt_service)));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("http://{}/api/v1/chat/launch/export?format=markdown", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/markdown"));
+    assert!(response.headers()["content-disposition"].to_str().unwrap().contains("transcript-launch.md"));
+    assert_eq!(response.text().await.unwrap(), EXPECTED_MARKDOWN);
+
+    let response = client
+        .get(format!("http://{}/api/chat/launch/export?format=json&since={}&until={}", addr, START + 60, START + 120))
+        .send()
+        .await
+        .unwrap();
+    let transcript: Transcript = response.json().await.unwrap();
+    assert_eq!(transcript.count, 2);
+
+    let response = client
+        .get(format!("http://{}/api/chat/launch/export?format=pdf", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .get(format!("http://{}/api/chat/nowhere/export", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn test_transcript_export_tool() {
+    let store = Arc::new(MemoryStore::new());
+    seed(store.as_ref()).await;
+    let mut org = Organization::new();
+    for agent in agents() {
+        org.add_agent(agent);
+    }
+    let env = ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(org)),
+        Arc::new(ToolRegistry::new()),
+        store,
+    );
+    let executor = FrameworkToolExecutor::new(env);
+
+    let result = executor
+        .execute(
+            "transcript.export",
+            serde_json::json!({"session_id": "launch", "max_messages": 2}),
+            &ToolCallContext::new("bob"),
+        )
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["count"], 2);
+    assert_eq!(result.data["truncated"], true);
+    let transcript = result.data["transcript"].as_str().unwrap();
+    assert!(transcript.contains("> Yes, QA signed off."));
+    assert!(!transcript.contains("booking the release"));
+
+    // 不能导出自己不在的群或他人的私聊
+    let denied = executor
+        .execute("transcript.export", serde_json::json!({"session_id": "launch"}), &ToolCallContext::new("carol"))
+        .await
+        .unwrap();
+    assert!(!denied.success);
+
+    let own = executor
+        .execute("transcript.export", serde_json::json!({"format": "json"}), &ToolCallContext::new("bob"))
+        .await
+        .unwrap();
+    let transcript: Transcript = serde_json::from_str(own.data["transcript"].as_str().unwrap()).unwrap();
+    assert_eq!(transcript.session.title, "Bob");
+    assert_eq!(transcript.count, 1);
+}
@@ -0,0 +1,188 @@
+//! 消息翻译测试：译文按（消息，语言）缓存只翻译一次、翻译失败退回原文、Agent 上下文附译文且存储中的消息不被修改
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::agent::{AgentRuntime, Context};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::core::translation::{TranslationService, Translator};
+use imitatort::domain::{Agent, LLMConfig, Message, Role};
+
+const ALICE: &str = "user:alice";
+const CHINESE: &str = "我们下周需要把新版本部署到生产环境，请帮我检查一下发布计划和回滚方案。";
+const ENGLISH: &str = "Sure, I checked the release plan and the rollback steps look good to me.";
+
+/// 记录调用次数的翻译器，`fail` 时总是失败
+struct MockTranslator {
+    calls: AtomicUsize,
+    fail: bool,
+}
+
+impl MockTranslator {
+    fn new(fail: bool) -> Arc<Self> {
+        Arc::new(Self { calls: AtomicUsize::new(0), fail })
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl Translator for MockTranslator {
+    async fn translate(&self, text: &str, source: &str, target: &str) -> anyhow::Result<String> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        if self.fail {
+            anyhow::bail!("translation backend unavailable");
+        }
+        Ok(match target {
+            "en" => "We need to deploy the new version next week; please check the release and rollback plan.".to_string(),
+            _ => format!("[{}->{}] {}", source, target, text),
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_translation_is_cached_per_language() {
+    let store = Arc::new(MemoryStore::new());
+    let translator = MockTranslator::new(false);
+    let service = Arc::new(TranslationService::new(translator.clone(), store.clone()));
+    let message = Message::private(ALICE, "dev", CHINESE);
+
+    // 并发请求同一译文也只翻译一次
+    let (first, second) = tokio::join!(service.translate_for(&message, "en"), service.translate_for(&message, "en"));
+    let first = first.unwrap();
+    assert_eq!(first, second.unwrap());
+    assert_eq!(first.source_language, "cmn");
+    assert!(first.text.starts_with("We need to deploy"));
+    assert_eq!(translator.calls(), 1);
+
+    // 缓存在存储中，新的服务实例也不再翻译
+    let reloaded = TranslationService::new(translator.clone(), store.clone());
+    assert_eq!(reloaded.translate_for(&message, "en").await, Some(first.clone()));
+    assert_eq!(store.load_translation(&message.id, "en").await.unwrap(), Some(first));
+    assert_eq!(translator.calls(), 1);
+
+    // 缓存按读者配置的语言代码区分
+    assert_eq!(reloaded.translate_for(&message, "english").await.map(|t| t.language), Some("english".to_string()));
+    assert_eq!(translator.calls(), 2);
+
+    // 读者语言与消息相同、语言无法判断时不翻译
+    assert!(service.translate_for(&message, "zh").await.is_none());
+    assert!(service.translate_for(&Message::private(ALICE, "dev", "ok"), "zh").await.is_none());
+    assert!(service.translate_for(&message, "klingon").await.is_none());
+    assert_eq!(translator.calls(), 2);
+}
+
+#[tokio::test]
+async fn test_failed_translation_falls_back_to_original() {
+    let store = Arc::new(MemoryStore::new());
+    let translator = MockTranslator::new(true);
+    let service = TranslationService::new(translator.clone(), store.clone());
+    let message = Message::private(ALICE, "dev", CHINESE);
+
+    let translations = service.translate_all(std::slice::from_ref(&message), "en").await;
+    assert!(translations.is_empty());
+    assert!(store.load_translation(&message.id, "en").await.unwrap().is_none());
+
+    // 上下文中照常显示原文
+    let runtime = AgentRuntime::new(Agent::new("dev", "Dev", Role::simple("Engineer", "You build things"), LLMConfig::openai("k")))
+        .await
+        .unwrap();
+    let context = Context::default().with_messages(vec![message]).with_translations(translations);
+    let prompt = runtime.build_thinking_prompt(&context);
+    assert!(prompt.contains(&format!("- [{}]: {}", ALICE, CHINESE)));
+    assert!(!prompt.contains("translated from"));
+
+    // 失败不缓存，下次请求重新翻译
+    assert!(service.translate_for(&Message::private(ALICE, "dev", CHINESE), "en").await.is_none());
+    assert_eq!(translator.calls(), 2);
+}
+
+/// 收到未读消息时用英文回复给用户
+async fn spawn_llm() -> (Arc<Mutex<Vec<String>>>, String) {
+    async fn completions(State(prompts): State<Arc<Mutex<Vec<String>>>>, Json(body): Json<Value>) -> Json<Value> {
+        let prompt: String = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|m| m["content"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let decision = if prompt.contains("Unread messages:") {
+            json!({"action": "send_message", "target": ALICE, "content": ENGLISH})
+        } else {
+            json!({"action": "wait"})
+        };
+        prompts.lock().unwrap().push(prompt);
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": decision.to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(prompts.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (prompts, format!("http://{}", addr))
+}
+
+#[tokio::test]
+async fn test_agent_reads_translation_and_stored_messages_are_unchanged() {
+    let store = Arc::new(MemoryStore::new());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let translator = MockTranslator::new(false);
+    let service = Arc::new(TranslationService::new(translator.clone(), store.clone()));
+    let (prompts, url) = spawn_llm().await;
+
+    let role = Role::simple("Engineer", "You build things").with_response_language("en");
+    let agent = Agent::new("dev", "Dev", role, LLMConfig::openai("k").with_base_url(url));
+    let autonomous = AutonomousAgent::new(agent, bus.clone()).await.unwrap().with_translator(service.clone());
+    let _alice_rx = bus.register_user(ALICE);
+    let handle = tokio::spawn(async move {
+        let _ = autonomous.run_loop().await;
+    });
+
+    let question = Message::private(ALICE, "dev", CHINESE);
+    bus.send(question.clone()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    handle.abort();
+
+    // Agent 读到译文，原文作为来源保留
+    let prompt = prompts.lock().unwrap().iter().find(|p| p.contains("Unread messages:")).cloned().unwrap();
+    assert!(prompt.contains("We need to deploy the new version next week"));
+    assert!(prompt.contains(&format!("(translated from Chinese; original: {})", CHINESE)));
+
+    // 存储中的消息保持原文，Agent 自己发出的消息不翻译
+    let stored = store.load_messages(MessageFilter::new().limit(10)).await.unwrap();
+    let incoming = stored.iter().find(|m| m.id == question.id).unwrap();
+    assert_eq!(incoming.content, CHINESE);
+    let reply = stored.iter().find(|m| m.from == "dev").unwrap();
+    assert_eq!(reply.content, ENGLISH);
+    assert_eq!(translator.calls(), 1);
+
+    // 中文读者看 Agent 的回复时得到译文，存储中的回复不变
+    let translation = service.translate_for(reply, "zh").await.unwrap();
+    assert_eq!(translation.text, format!("[eng->zh] {}", ENGLISH));
+    let stored = store.load_messages(MessageFilter::new().from("dev")).await.unwrap();
+    assert_eq!(stored[0].content, ENGLISH);
+}
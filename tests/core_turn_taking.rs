@@ -0,0 +1,228 @@
+//! 群聊轮流发言测试：四个 Agent 的群里一个问题只有一个 Agent 主动回答、按 @ 和职责关键词选择、静默后依次接手
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::core::turn_taking::{TurnCoordinator, TURN_FOR_KEY, TURN_REASON_KEY, TURN_ROLE_KEY};
+use imitatort::domain::{Agent, LLMConfig, Message, Organization, Role, TurnTakingSettings};
+
+const GROUP: &str = "group-team";
+const ALICE: &str = "user:alice";
+const AGENTS: [(&str, &str); 4] = [("dev", "Engineer"), ("design", "Designer"), ("ops", "Ops"), ("pm", "PM")];
+
+/// 未读消息里有用户的问题时回答到群里，否则等待；`answers` 为 false 时总是等待
+async fn spawn_llm(answers: bool) -> (Arc<Mutex<Vec<String>>>, String) {
+    type LlmState = (Arc<Mutex<Vec<String>>>, bool);
+    async fn completions(State((prompts, answers)): State<LlmState>, Json(body): Json<Value>) -> Json<Value> {
+        let prompt: String = body["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|m| m["content"].as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let asked = prompt
+            .split("Unread messages:")
+            .nth(1)
+            .is_some_and(|unread| unread.contains(&format!("[{}]", ALICE)));
+        prompts.lock().unwrap().push(prompt);
+        let decision = if answers && asked {
+            json!({"action": "send_message", "target": GROUP, "content": "Here is my answer"})
+        } else {
+            json!({"action": "wait"})
+        };
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": decision.to_string() },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15 }
+        }))
+    }
+
+    let prompts = Arc::new(Mutex::new(Vec::new()));
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state((prompts.clone(), answers));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (prompts, format!("http://{}", addr))
+}
+
+struct Company {
+    store: Arc<MemoryStore>,
+    bus: Arc<MessageBus>,
+    clock: Arc<ManualClock>,
+    /// 每个 Agent 的 LLM 收到的提示词
+    prompts: Vec<(String, Arc<Mutex<Vec<String>>>)>,
+    handles: Vec<tokio::task::JoinHandle<()>>,
+}
+
+/// 四个 Agent 的群聊开启轮流发言，`silent` 中的 Agent 从不回答
+async fn company(settings: TurnTakingSettings, silent: &[&str]) -> Company {
+    let mut org = Organization::new();
+    let store = Arc::new(MemoryStore::new());
+    let clock = Arc::new(ManualClock::new(1_700_000_000));
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let mut agents = Vec::new();
+    let mut prompts = Vec::new();
+    for (id, title) in AGENTS {
+        let (recorded, url) = spawn_llm(!silent.contains(&id)).await;
+        let agent = Agent::new(id, id, Role::simple(title, "You help the team"), LLMConfig::openai("k").with_base_url(url));
+        org.add_agent(agent.clone());
+        agents.push(agent);
+        prompts.push((id.to_string(), recorded));
+    }
+    let coordinator = Arc::new(
+        TurnCoordinator::new(bus.clone(), Arc::new(RwLock::new(org))).with_clock(clock.clone()),
+    );
+
+    let mut autonomous_agents = Vec::new();
+    for agent in agents {
+        let autonomous = AutonomousAgent::new(agent, bus.clone())
+            .await
+            .unwrap()
+            .with_turn_coordinator(coordinator.clone());
+        autonomous_agents.push(autonomous);
+    }
+
+    // 群聊由 pm 创建，pm 是管理员
+    bus.register_user(ALICE);
+    let mut members = vec![ALICE.to_string()];
+    members.extend(AGENTS.iter().map(|(id, _)| id.to_string()));
+    bus.create_group(GROUP, "Team", "pm", members).await.unwrap();
+    bus.set_turn_taking(GROUP, "pm", Some(settings)).await.unwrap();
+
+    let handles = autonomous_agents
+        .into_iter()
+        .map(|autonomous| {
+            tokio::spawn(async move {
+                let _ = autonomous.run_loop().await;
+            })
+        })
+        .collect();
+    Company { store, bus, clock, prompts, handles }
+}
+
+impl Company {
+    async fn ask(&self, content: &str) -> Message {
+        let question = Message::group(ALICE, GROUP, content);
+        self.bus.send(question.clone()).await.unwrap();
+        question
+    }
+
+    /// 群里 Agent 对该问题的回答
+    async fn answers_to(&self, question: &Message) -> Vec<Message> {
+        let messages = self.store.load_messages(MessageFilter::new().to(GROUP).limit(100)).await.unwrap();
+        messages
+            .into_iter()
+            .filter(|m| m.from != ALICE && m.metadata(TURN_FOR_KEY) == Some(question.id.as_str()))
+            .collect()
+    }
+
+    async fn agent_messages(&self) -> usize {
+        let messages = self.store.load_messages(MessageFilter::new().to(GROUP).limit(100)).await.unwrap();
+        messages.iter().filter(|m| m.from != ALICE).count()
+    }
+
+    fn prompts_of(&self, agent_id: &str) -> Vec<String> {
+        let (_, prompts) = self.prompts.iter().find(|(id, _)| id == agent_id).unwrap();
+        prompts.lock().unwrap().clone()
+    }
+
+    fn stop(&self) {
+        for handle in &self.handles {
+            handle.abort();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_one_agent_answers_each_question() {
+    let settings = TurnTakingSettings::default().with_responsibility("Ops", &["deploy", "outage"]);
+    let company = company(settings, &[]).await;
+
+    // 没有 @ 也没有关键词：按轮转选第一个 Agent，其他 Agent 被告知同伴在回答
+    let question = company.ask("What should we focus on this week?").await;
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+    let answers = company.answers_to(&question).await;
+    assert_eq!(answers.len(), 1, "{:?}", answers);
+    assert_eq!(answers[0].from, "dev");
+    assert_eq!(answers[0].metadata(TURN_ROLE_KEY), Some("primary"));
+    assert_eq!(answers[0].metadata(TURN_REASON_KEY), Some("round_robin"));
+    assert_eq!(company.agent_messages().await, 1);
+    assert!(company.prompts_of("design").iter().any(|p| p.contains("dev is answering [user:alice]")));
+
+    // 职责关键词匹配角色
+    let question = company.ask("The deploy failed again").await;
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+    let answers = company.answers_to(&question).await;
+    assert_eq!(answers.len(), 1, "{:?}", answers);
+    assert_eq!(answers[0].from, "ops");
+    assert_eq!(answers[0].metadata(TURN_REASON_KEY), Some("keyword"));
+
+    // @ 优先
+    let question = company.ask("@pm can you check the deploy plan?").await;
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+    let answers = company.answers_to(&question).await;
+    assert_eq!(answers.len(), 1, "{:?}", answers);
+    assert_eq!(answers[0].from, "pm");
+    assert_eq!(answers[0].metadata(TURN_REASON_KEY), Some("mention"));
+    assert_eq!(company.agent_messages().await, 3);
+    company.stop();
+}
+
+#[tokio::test]
+async fn test_next_agent_answers_after_silence_window() {
+    let settings = TurnTakingSettings { silence_window_secs: 30, ..Default::default() };
+    let company = company(settings, &["dev"]).await;
+
+    let question = company.ask("Who can help me with the release notes?").await;
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    // 主回答者 dev 没有回答，其他 Agent 在静默窗口内不插话
+    assert_eq!(company.agent_messages().await, 0);
+    assert!(company.prompts_of("dev").iter().any(|p| p.contains("Who can help me")));
+
+    company.clock.advance(Duration::from_secs(31));
+    tokio::time::sleep(Duration::from_millis(2000)).await;
+    let answers = company.answers_to(&question).await;
+    assert_eq!(answers.len(), 1, "{:?}", answers);
+    assert_eq!(answers[0].from, "design");
+    assert_eq!(answers[0].metadata(TURN_ROLE_KEY), Some("escalation"));
+    assert_eq!(company.agent_messages().await, 1);
+    company.stop();
+}
+
+#[tokio::test]
+async fn test_turn_taking_is_a_group_setting() {
+    let store = Arc::new(MemoryStore::new());
+    let bus = MessageBus::with_store(store.clone());
+    let _pm_rx = bus.register("pm");
+    bus.create_group(GROUP, "Team", "pm", vec!["pm".to_string(), "dev".to_string()]).await.unwrap();
+
+    // 只有管理员能修改，设置随群聊保存
+    assert!(bus.set_turn_taking(GROUP, "dev", Some(TurnTakingSettings::default())).await.is_err());
+    let settings = TurnTakingSettings::default().with_responsibility("Engineer", &["bug"]);
+    bus.set_turn_taking(GROUP, "pm", Some(settings.clone())).await.unwrap();
+    let saved = store.load_groups().await.unwrap().remove(0);
+    assert_eq!(saved.turn_taking, Some(settings));
+
+    bus.set_turn_taking(GROUP, "pm", None).await.unwrap();
+    assert!(store.load_groups().await.unwrap()[0].turn_taking.is_none());
+}
@@ -2,12 +2,15 @@
 //!
 //! 测试Watchdog框架的核心功能
 
+use imitatort::core::clock::ManualClock;
 use imitatort::core::watchdog::{
-    WatchdogFramework, WatchdogRule, TriggerCondition, ToolExecutionEvent,
+    WatchdogFramework, WatchdogRule, TriggerCondition, ToolExecutionEvent, ToolPatternKind,
     client::WatchdogClient,
 };
 use imitatort::domain::tool::ToolCallContext;
 use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[tokio::test]
 async fn test_watchdog_framework_creation() {
@@ -173,4 +176,109 @@ async fn test_watchdog_framework_disable_enable() {
     }).await.unwrap();
 
     assert_eq!(triggered, vec!["test_agent"]);
-}
\ No newline at end of file
+}
+
+fn error_event(tool_id: &str) -> ToolExecutionEvent {
+    ToolExecutionEvent::Error {
+        tool_id: tool_id.to_string(),
+        error: "ERROR: disk full".to_string(),
+        context: ToolCallContext::new("monitor".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn test_watchdog_rule_cooldown_and_max_triggers() {
+    let clock = Arc::new(ManualClock::new(1_700_000_000));
+    let framework = WatchdogFramework::new().with_clock(clock.clone());
+    let condition = || TriggerCondition::StringContains { content: "ERROR".to_string() };
+    framework
+        .register_rule(WatchdogRule::new("cooldown", "monitor", condition(), "ops").with_trigger_on_error().with_cooldown_ms(200))
+        .unwrap();
+
+    // 连续两次匹配的事件只触发一次，冷却结束后再次触发
+    assert_eq!(framework.process_event(&error_event("monitor")).await.unwrap(), vec!["ops"]);
+    assert!(framework.process_event(&error_event("monitor")).await.unwrap().is_empty());
+    assert_eq!(framework.rule_state("cooldown").unwrap().trigger_count, 1);
+    clock.advance(Duration::from_millis(199));
+    assert!(framework.process_event(&error_event("monitor")).await.unwrap().is_empty());
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(framework.process_event(&error_event("monitor")).await.unwrap(), vec!["ops"]);
+
+    // 用完触发次数后不再触发，手动重置后恢复
+    framework
+        .register_rule(WatchdogRule::new("limited", "disk", condition(), "ops").with_trigger_on_error().with_max_triggers(2))
+        .unwrap();
+    for _ in 0..2 {
+        assert_eq!(framework.process_event(&error_event("disk")).await.unwrap(), vec!["ops"]);
+    }
+    assert!(framework.process_event(&error_event("disk")).await.unwrap().is_empty());
+    assert!(framework.reset_rule_state("limited"));
+    assert!(!framework.reset_rule_state("limited"));
+    assert_eq!(framework.process_event(&error_event("disk")).await.unwrap(), vec!["ops"]);
+}
+
+#[tokio::test]
+async fn test_watchdog_cooldown_holds_across_concurrent_events() {
+    let framework = Arc::new(WatchdogFramework::new());
+    framework
+        .register_rule(
+            WatchdogRule::new("flood", "monitor", TriggerCondition::StringContains { content: "ERROR".to_string() }, "ops")
+                .with_trigger_on_error()
+                .with_cooldown_ms(60_000),
+        )
+        .unwrap();
+
+    let tasks: Vec<_> = (0..16)
+        .map(|_| {
+            let framework = framework.clone();
+            tokio::spawn(async move { framework.process_event(&error_event("monitor")).await.unwrap().len() })
+        })
+        .collect();
+    let mut triggered = 0;
+    for task in tasks {
+        triggered += task.await.unwrap();
+    }
+    assert_eq!(triggered, 1);
+    assert_eq!(framework.rule_state("flood").unwrap().trigger_count, 1);
+}
+
+#[tokio::test]
+async fn test_throttled_winner_suppresses_lower_priority_rules() {
+    let clock = Arc::new(ManualClock::new(1_700_000_000));
+    let framework = WatchdogFramework::new().with_clock(clock.clone());
+    let condition = || TriggerCondition::StringContains { content: "ERROR".to_string() };
+    framework
+        .register_rule(WatchdogRule::new("exact", "monitor", condition(), "ops").with_trigger_on_error().with_cooldown_ms(1_000))
+        .unwrap();
+    framework
+        .register_rule(
+            WatchdogRule::new("glob", "mon*", condition(), "ops")
+                .with_pattern_kind(ToolPatternKind::Glob)
+                .with_trigger_on_error(),
+        )
+        .unwrap();
+    framework
+        .register_rule(
+            WatchdogRule::new("other-agent", "mon*", condition(), "dev")
+                .with_pattern_kind(ToolPatternKind::Glob)
+                .with_trigger_on_error(),
+        )
+        .unwrap();
+
+    // 同一Agent只由优先级最高的规则通知
+    let mut triggered = framework.process_event(&error_event("monitor")).await.unwrap();
+    triggered.sort();
+    assert_eq!(triggered, vec!["dev", "ops"]);
+    assert_eq!(framework.rule_state("exact").unwrap().trigger_count, 1);
+    assert!(framework.rule_state("glob").is_none());
+
+    // 最高优先级的规则冷却中时该Agent不通知，不会改由优先级更低的规则通知
+    assert_eq!(framework.process_event(&error_event("monitor")).await.unwrap(), vec!["dev"]);
+    assert!(framework.rule_state("glob").is_none());
+
+    clock.advance(Duration::from_secs(1));
+    let mut triggered = framework.process_event(&error_event("monitor")).await.unwrap();
+    triggered.sort();
+    assert_eq!(triggered, vec!["dev", "ops"]);
+    assert_eq!(framework.rule_state("exact").unwrap().trigger_count, 2);
+}
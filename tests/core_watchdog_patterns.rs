@@ -0,0 +1,178 @@
+//! Watchdog规则模式匹配测试：通配符、分类前缀、工具标签、多规则优先级与索引维护
+
+use std::sync::Arc;
+
+use imitatort::core::tool::ToolRegistry;
+use imitatort::core::watchdog::pattern::glob_match;
+use imitatort::core::watchdog::{ToolExecutionEvent, ToolPatternKind, TriggerCondition, WatchdogFramework, WatchdogRule};
+use imitatort::domain::tool::{CategoryPath, Tool, ToolCallContext};
+use serde_json::json;
+
+fn any_result() -> TriggerCondition {
+    TriggerCondition::StringContains { content: String::new() }
+}
+
+fn rule(id: &str, kind: ToolPatternKind, pattern: &str, agent: &str) -> WatchdogRule {
+    WatchdogRule::new(id, pattern, any_result(), agent).with_pattern_kind(kind)
+}
+
+fn succeeded(tool_id: &str) -> ToolExecutionEvent {
+    ToolExecutionEvent::PostExecute {
+        tool_id: tool_id.to_string(),
+        result: json!("ok"),
+        context: ToolCallContext::new("caller"),
+    }
+}
+
+fn failed(tool_id: &str) -> ToolExecutionEvent {
+    ToolExecutionEvent::Error {
+        tool_id: tool_id.to_string(),
+        error: "disk full".to_string(),
+        context: ToolCallContext::new("caller"),
+    }
+}
+
+async fn registry() -> Arc<ToolRegistry> {
+    let registry = Arc::new(ToolRegistry::new());
+    let tool = |id: &str, category: &str| Tool::new(id, id, "test tool", CategoryPath::from_str(category), json!({}));
+    registry.register(tool("system.health", "system/health")).await.unwrap();
+    registry.register(tool("system.disk", "system/storage").with_tags(vec!["ops".into()])).await.unwrap();
+    registry.register(tool("file.read", "file/read").with_tags(vec!["ops".into(), "io".into()])).await.unwrap();
+    registry.register(tool("calc.add", "math")).await.unwrap();
+    registry
+}
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("system.*", "system.health"));
+    assert!(glob_match("*.read", "file.read"));
+    assert!(glob_match("file.?ead", "file.read"));
+    assert!(glob_match("*", ""));
+    assert!(glob_match("s*m.*h", "system.health"));
+    assert!(!glob_match("system.*", "systems.health"));
+    assert!(!glob_match("file.?", "file.read"));
+}
+
+#[tokio::test]
+async fn test_each_pattern_kind() {
+    let framework = WatchdogFramework::new().with_tool_registry(registry().await);
+    framework.register_rule(rule("exact", ToolPatternKind::Exact, "calc.add", "a_exact")).unwrap();
+    framework.register_rule(rule("glob", ToolPatternKind::Glob, "system.*", "a_glob")).unwrap();
+    framework.register_rule(rule("category", ToolPatternKind::Category, "system", "a_category")).unwrap();
+    framework.register_rule(rule("tag", ToolPatternKind::Tag, "io", "a_tag")).unwrap();
+
+    let triggered = |tool_id: &'static str| {
+        let framework = &framework;
+        async move { framework.process_event(&succeeded(tool_id)).await.unwrap() }
+    };
+    assert_eq!(triggered("calc.add").await, vec!["a_exact"]);
+    assert_eq!(triggered("system.health").await, vec!["a_glob", "a_category"]);
+    assert_eq!(triggered("file.read").await, vec!["a_tag"]);
+    assert!(triggered("unknown.tool").await.is_empty());
+
+    // 分类按路径段匹配，不按字符串前缀
+    let rule = rule("sys", ToolPatternKind::Category, "sys", "x");
+    let tool = Tool::new("system.health", "health", "", CategoryPath::from_str("system/health"), json!({}));
+    assert!(!rule.should_trigger_for(&succeeded("system.health"), Some(&tool)));
+    // 没有工具定义时分类和标签规则不匹配
+    let without_tools = WatchdogFramework::new();
+    without_tools.register_rule(WatchdogRule::new("category", "system", any_result(), "x").with_pattern_kind(ToolPatternKind::Category)).unwrap();
+    assert!(without_tools.candidate_rules("system.health").is_empty());
+    assert!(without_tools.process_event(&succeeded("system.health")).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_category_rule_on_errors() {
+    let framework = WatchdogFramework::new().with_tool_registry(registry().await);
+    framework
+        .register_rule(rule("system_errors", ToolPatternKind::Category, "system", "ops").with_trigger_on_error())
+        .unwrap();
+    framework.register_rule(rule("system_results", ToolPatternKind::Category, "system", "auditor")).unwrap();
+
+    assert_eq!(framework.process_event(&failed("system.disk")).await.unwrap(), vec!["ops"]);
+    assert_eq!(framework.process_event(&failed("system.health")).await.unwrap(), vec!["ops"]);
+    assert!(framework.process_event(&failed("file.read")).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_precedence_when_rules_overlap() {
+    let framework = WatchdogFramework::new().with_tool_registry(registry().await);
+    framework.register_rule(rule("by_tag", ToolPatternKind::Tag, "ops", "ops")).unwrap();
+    framework.register_rule(rule("by_category", ToolPatternKind::Category, "system", "ops")).unwrap();
+    framework.register_rule(rule("by_glob", ToolPatternKind::Glob, "system.d*", "ops")).unwrap();
+    framework.register_rule(rule("by_id", ToolPatternKind::Exact, "system.disk", "ops")).unwrap();
+    framework.register_rule(rule("audit", ToolPatternKind::Tag, "ops", "auditor")).unwrap();
+
+    let events = Arc::new(imitatort::core::events::EventBus::new());
+    let mut rx = events.subscribe();
+    let framework = framework.with_events(events);
+
+    // 同一Agent只由最具体的规则通知一次，不同Agent各自通知
+    let triggered = framework.process_event(&succeeded("system.disk")).await.unwrap();
+    assert_eq!(triggered, vec!["ops", "auditor"]);
+    let mut fired = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        if let imitatort::core::events::CompanyEvent::WatchdogTriggered { rule_id, tool_id, .. } = event {
+            assert_eq!(&*tool_id, "system.disk");
+            fired.push(rule_id.to_string());
+        }
+    }
+    assert_eq!(fired, vec!["by_id", "audit"]);
+
+    // 最具体的规则禁用后由下一条规则接替
+    framework.set_rule_enabled("by_id", false);
+    assert_eq!(framework.process_event(&succeeded("system.disk")).await.unwrap(), vec!["ops", "auditor"]);
+    assert_eq!(framework.process_event(&succeeded("file.read")).await.unwrap(), vec!["auditor", "ops"]);
+}
+
+#[tokio::test]
+async fn test_index_after_rule_removal() {
+    let framework = WatchdogFramework::new().with_tool_registry(registry().await);
+    framework.register_rule(rule("g1", ToolPatternKind::Glob, "system.*", "a")).unwrap();
+    framework.register_rule(rule("g2", ToolPatternKind::Glob, "system.h*", "b")).unwrap();
+    framework.register_rule(rule("any", ToolPatternKind::Glob, "*.read", "c")).unwrap();
+    framework.register_rule(rule("cat", ToolPatternKind::Category, "system/health", "d")).unwrap();
+    for i in 0..200 {
+        framework.register_rule(rule(&format!("noise{}", i), ToolPatternKind::Exact, &format!("other.{}", i), "n")).unwrap();
+    }
+
+    // 索引只给出相关的规则
+    let mut candidates = framework.candidate_rules("system.health");
+    candidates.sort();
+    assert_eq!(candidates, vec!["any", "cat", "g1", "g2"]);
+
+    framework.remove_rule("g1").unwrap();
+    framework.remove_rule("cat").unwrap();
+    let mut candidates = framework.candidate_rules("system.health");
+    candidates.sort();
+    assert_eq!(candidates, vec!["any", "g2"]);
+    assert_eq!(framework.process_event(&succeeded("system.health")).await.unwrap(), vec!["b"]);
+    assert!(framework.process_event(&succeeded("system.disk")).await.unwrap().is_empty());
+
+    // 同ID重新注册为其他模式时旧索引项被替换
+    framework.register_rule(rule("g2", ToolPatternKind::Tag, "io", "b")).unwrap();
+    assert!(framework.process_event(&succeeded("system.health")).await.unwrap().is_empty());
+    assert_eq!(framework.process_event(&succeeded("file.read")).await.unwrap(), vec!["c", "b"]);
+    assert!(!framework.candidate_rules("system.health").contains(&"g2".to_string()));
+}
+
+#[test]
+fn test_pattern_kind_round_trips() {
+    let rule = rule("r1", ToolPatternKind::Category, "system", "ops").with_trigger_on_error();
+    let value = serde_json::to_value(&rule).unwrap();
+    assert_eq!(value["pattern_kind"], "category");
+    let restored: WatchdogRule = serde_json::from_value(value).unwrap();
+    assert_eq!(restored.pattern_kind, ToolPatternKind::Category);
+    assert!(restored.trigger_on_error);
+
+    // 旧数据没有匹配方式时按工具ID精确匹配
+    let legacy: WatchdogRule = serde_json::from_value(json!({
+        "id": "old",
+        "tool_id": "calc.add",
+        "condition": { "NumericRange": { "min": 0.0, "max": 1.0 } },
+        "target_agent_id": "a",
+        "enabled": true
+    }))
+    .unwrap();
+    assert_eq!(legacy.pattern_kind, ToolPatternKind::Exact);
+}
@@ -0,0 +1,136 @@
+//! Agent working-hours tests: weekly windows, DST transitions, date exceptions and persistence
+
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use imitatort::core::store::Store;
+use imitatort::domain::{Availability, DateException, TimeWindow};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::{Agent, LLMConfig, Organization, Role};
+use serde_json::json;
+
+const WEEKDAYS: [Weekday; 5] = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+
+fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+    Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap().timestamp()
+}
+
+fn hm(h: u32, m: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(h, m, 0).unwrap()
+}
+
+fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+}
+
+/// Mon-Fri 09:00-17:00 in Berlin (CET -> CEST on 2026-03-29)
+fn office_hours() -> Availability {
+    Availability::new("Europe/Berlin").with_window(&WEEKDAYS, hm(9, 0), hm(17, 0))
+}
+
+#[test]
+fn test_weekly_windows_in_local_time() {
+    let schedule = office_hours();
+    // Friday 2026-03-27, CET (UTC+1)
+    assert!(!schedule.is_available_at(utc(2026, 3, 27, 7, 59)));
+    assert!(schedule.is_available_at(utc(2026, 3, 27, 8, 0)));
+    assert!(schedule.is_available_at(utc(2026, 3, 27, 15, 59)));
+    assert!(!schedule.is_available_at(utc(2026, 3, 27, 16, 0)));
+    // Saturday
+    assert!(!schedule.is_available_at(utc(2026, 3, 28, 10, 0)));
+    // Monday 2026-03-30, CEST (UTC+2): 09:00 local is 07:00 UTC
+    assert!(!schedule.is_available_at(utc(2026, 3, 30, 6, 30)));
+    assert!(schedule.is_available_at(utc(2026, 3, 30, 7, 0)));
+}
+
+#[test]
+fn test_next_available_across_spring_forward() {
+    let schedule = office_hours();
+    let friday_evening = utc(2026, 3, 27, 17, 0);
+    // 72 hours later would be 08:00 UTC; the clocks moved forward over the weekend
+    assert_eq!(schedule.next_available_at(friday_evening), Some(utc(2026, 3, 30, 7, 0)));
+    assert_eq!(schedule.format_local(utc(2026, 3, 30, 7, 0)), "Mon 09:00 (Europe/Berlin)");
+
+    // Available now: the timestamp itself
+    let monday_morning = utc(2026, 3, 30, 8, 0);
+    assert_eq!(schedule.next_available_at(monday_morning), Some(monday_morning));
+}
+
+#[test]
+fn test_window_starting_in_dst_gap() {
+    // 02:30 does not exist on 2026-03-29 in Berlin; the window opens when the clocks jump to 03:00
+    let schedule = Availability::new("Europe/Berlin").with_window(&[Weekday::Sun], hm(2, 30), hm(4, 0));
+    assert_eq!(schedule.next_available_at(utc(2026, 3, 29, 0, 0)), Some(utc(2026, 3, 29, 1, 0)));
+    assert!(schedule.is_available_at(utc(2026, 3, 29, 1, 30)));
+    assert!(!schedule.is_available_at(utc(2026, 3, 29, 2, 0)));
+}
+
+#[test]
+fn test_window_in_repeated_hour_uses_first_occurrence() {
+    // 02:00-02:30 happens twice on 2026-10-25 in Berlin (CEST -> CET)
+    let schedule = Availability::new("Europe/Berlin").with_window(&[Weekday::Sun], hm(2, 0), hm(2, 30));
+    assert_eq!(schedule.next_available_at(utc(2026, 10, 24, 12, 0)), Some(utc(2026, 10, 25, 0, 0)));
+    assert!(schedule.is_available_at(utc(2026, 10, 25, 0, 15)));
+    assert!(schedule.is_available_at(utc(2026, 10, 25, 1, 15)));
+}
+
+#[test]
+fn test_date_exceptions() {
+    let schedule = office_hours()
+        .with_exception(DateException::off(date(2026, 3, 30), Some(date(2026, 4, 1))))
+        .with_exception(DateException {
+            date: date(2026, 4, 3),
+            until: None,
+            windows: vec![TimeWindow::new(hm(10, 0), hm(12, 0))],
+            note: Some("Good Friday, short day".to_string()),
+        });
+
+    // Vacation Mon-Wed: back on Thursday 09:00 CEST
+    assert!(!schedule.is_available_at(utc(2026, 3, 30, 8, 0)));
+    assert_eq!(schedule.next_available_at(utc(2026, 3, 27, 17, 0)), Some(utc(2026, 4, 2, 7, 0)));
+    // Special hours replace the weekly window that day
+    assert!(!schedule.is_available_at(utc(2026, 4, 3, 7, 30)));
+    assert_eq!(schedule.next_available_at(utc(2026, 4, 3, 7, 30)), Some(utc(2026, 4, 3, 8, 0)));
+    assert!(!schedule.is_available_at(utc(2026, 4, 3, 10, 30)));
+}
+
+#[test]
+fn test_schedule_json_and_validation() {
+    let schedule: Availability = serde_json::from_value(json!({
+        "timezone": "America/New_York",
+        "weekly": [{ "days": ["mon", "Tue"], "start": "09:00", "end": "17:30" }],
+        "exceptions": [{ "date": "2026-07-03" }],
+        "urgent_override": true
+    }))
+    .unwrap();
+    assert!(schedule.validate().is_ok());
+    assert_eq!(schedule.weekly[0].days, vec![Weekday::Mon, Weekday::Tue]);
+    assert!(schedule.urgent_override);
+
+    assert!(Availability::new("Mars/Olympus").validate().is_err());
+    assert!(Availability::new("UTC").with_window(&[Weekday::Mon], hm(17, 0), hm(9, 0)).validate().is_err());
+    assert!(office_hours()
+        .with_exception(DateException::off(date(2026, 4, 2), Some(date(2026, 4, 1))))
+        .validate()
+        .is_err());
+
+    // No schedule: always available
+    let agent = Agent::new("a1", "A1", Role::simple("Engineer", "You code"), LLMConfig::openai("k"));
+    assert!(agent.is_available_at(utc(2026, 3, 28, 3, 0)));
+    assert!(!agent.with_availability(office_hours()).is_available_at(utc(2026, 3, 28, 3, 0)));
+}
+
+#[tokio::test]
+async fn test_availability_persisted_in_sqlite() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    let schedule = office_hours()
+        .with_exception(DateException::off(date(2026, 12, 24), Some(date(2026, 12, 31))))
+        .with_urgent_override(true);
+    let mut org = Organization::new();
+    org.add_agent(
+        Agent::new("a1", "A1", Role::simple("Engineer", "You code"), LLMConfig::openai("k")).with_availability(schedule.clone()),
+    );
+    org.add_agent(Agent::new("a2", "A2", Role::simple("Engineer", "You code"), LLMConfig::openai("k")));
+    store.save_organization(&org).await.unwrap();
+
+    assert_eq!(store.load_agent("a1").await.unwrap().unwrap().availability, Some(schedule));
+    assert_eq!(store.load_agent("a2").await.unwrap().unwrap().availability, None);
+}
@@ -1,5 +1,6 @@
 //! Message 领域实体测试
 
+use imitatort::domain::{MessageReaction, ReactionCount};
 use imitatort::{Group, Message};
 
 #[test]
@@ -31,3 +32,30 @@ fn test_group_management() {
     group.remove_member("agent-a");
     assert!(!group.has_member("agent-a"));
 }
+
+#[test]
+fn test_reaction_aggregate() {
+    let mut reactions = vec![
+        MessageReaction::new("m1", "alice", "👍"),
+        MessageReaction::new("m1", "bob", "👍"),
+        MessageReaction::new("m1", "bob", "✅"),
+        MessageReaction::new("m2", "carol", "❓"),
+    ];
+    for (i, r) in reactions.iter_mut().enumerate() {
+        r.timestamp = i as i64;
+    }
+
+    let counts = ReactionCount::aggregate(&reactions);
+    let m1 = &counts["m1"];
+    assert_eq!(m1.len(), 2);
+    assert_eq!(m1[0].emoji, "👍");
+    assert_eq!(m1[0].count, 2);
+    assert_eq!(m1[0].reactors, vec!["alice".to_string(), "bob".to_string()]);
+    assert_eq!(m1[1].count, 1);
+    assert_eq!(counts["m2"][0].emoji, "❓");
+
+    assert!(MessageReaction::is_valid_emoji("👍"));
+    assert!(!MessageReaction::is_valid_emoji(""));
+    assert!(!MessageReaction::is_valid_emoji("👍 👍"));
+    assert!(!MessageReaction::is_valid_emoji(&"x".repeat(64)));
+}
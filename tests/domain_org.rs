@@ -1,5 +1,6 @@
 //! Organization 领域实体测试
 
+use imitatort::domain::DuplicateId;
 use imitatort::{Agent, Department, LLMConfig, Organization, Role};
 
 #[test]
@@ -65,3 +66,45 @@ fn test_get_members() {
     assert_eq!(members.len(), 1);
     assert_eq!(members[0].id, "dev1");
 }
+
+#[test]
+fn test_duplicate_ids_rejected() {
+    let mut org = Organization::new();
+    org.try_add_department(Department::top_level("tech", "Technology Department")).unwrap();
+    org.try_add_agent(Agent::new("dev1", "Developer", Role::simple("Dev", "prompt"), LLMConfig::openai("k"))).unwrap();
+
+    let err = org.try_add_department(Department::top_level("tech", "Other")).unwrap_err();
+    assert_eq!(err, DuplicateId::Department("tech".to_string()));
+    let err = org
+        .try_add_agent(Agent::new("dev1", "Impostor", Role::simple("Dev", "prompt"), LLMConfig::openai("k")))
+        .unwrap_err();
+    assert_eq!(err, DuplicateId::Agent("dev1".to_string()));
+    assert_eq!(org.agents.len(), 1);
+    assert_eq!(org.find_agent("dev1").unwrap().name, "Developer");
+    assert!(org.validate_ids().is_ok());
+
+    // 不检查的 add_agent 仍会留下重复，由 validate_ids 发现
+    org.add_agent(Agent::new("dev1", "Impostor", Role::simple("Dev", "prompt"), LLMConfig::openai("k")));
+    assert_eq!(org.validate_ids(), Err(DuplicateId::Agent("dev1".to_string())));
+}
+
+#[test]
+fn test_upsert_replaces_in_place() {
+    let mut org = Organization::new();
+    org.add_agent(Agent::new("a", "A", Role::simple("Dev", "prompt"), LLMConfig::openai("k")));
+    org.add_agent(Agent::new("b", "B", Role::simple("Dev", "prompt"), LLMConfig::openai("k")));
+
+    let replaced = org.upsert_agent(Agent::new("a", "A v2", Role::simple("Lead", "prompt"), LLMConfig::openai("k")));
+    assert_eq!(replaced.unwrap().name, "A");
+    assert_eq!(org.agents.len(), 2);
+    // 保持原来的位置
+    assert_eq!(org.agents[0].name, "A v2");
+    assert!(org.upsert_agent(Agent::new("c", "C", Role::simple("Dev", "prompt"), LLMConfig::openai("k"))).is_none());
+    assert_eq!(org.agents.len(), 3);
+
+    assert!(org.upsert_department(Department::top_level("eng", "Engineering")).is_none());
+    let replaced = org.upsert_department(Department::top_level("eng", "Engineering v2")).unwrap();
+    assert_eq!(replaced.name, "Engineering");
+    assert_eq!(org.departments.len(), 1);
+    assert_eq!(org.find_department("eng").unwrap().name, "Engineering v2");
+}
@@ -0,0 +1,145 @@
+//! JSON Schema 构建器测试：工具和能力共用一套构建器，输出与原有格式逐字节一致
+
+#![allow(deprecated)]
+
+use imitatort::domain::capability::{Capability, CapabilityPath, InputSchema, OutputSchema};
+use imitatort::domain::tool::{CategoryPath, JsonSchema, ReturnType, Tool};
+use serde_json::{json, Value};
+
+fn text(schema: &Value) -> String {
+    serde_json::to_string(schema).unwrap()
+}
+
+#[test]
+fn test_type_builders_serialize_unchanged() {
+    let cases = [
+        (JsonSchema::string().build(), r#"{"type":"string"}"#),
+        (JsonSchema::integer().build(), r#"{"type":"integer"}"#),
+        (JsonSchema::number().build(), r#"{"type":"number"}"#),
+        (JsonSchema::boolean().build(), r#"{"type":"boolean"}"#),
+        (
+            JsonSchema::array(json!({"type": "integer"})).build(),
+            r#"{"items":{"type":"integer"},"type":"array"}"#,
+        ),
+        (JsonSchema::string_array().build(), r#"{"items":{"type":"string"},"type":"array"}"#),
+        (JsonSchema::enum_values(vec!["a", "b"]).build(), r#"{"enum":["a","b"],"type":"string"}"#),
+        (
+            JsonSchema::string().description("名称").build(),
+            r#"{"description":"名称","type":"string"}"#,
+        ),
+        (
+            JsonSchema::string().enum_values(vec!["x"]).optional().build(),
+            r#"{"enum":["x"],"type":"string"}"#,
+        ),
+        (JsonSchema::object().build(), r#"{"properties":{},"required":[],"type":"object"}"#),
+    ];
+    for (schema, expected) in cases {
+        assert_eq!(text(&schema), expected);
+    }
+}
+
+#[test]
+fn test_object_builder_serializes_unchanged() {
+    let schema = JsonSchema::object()
+        .property("name", JsonSchema::string().description("用户名"))
+        .property("age", JsonSchema::integer().description("年龄").optional())
+        .property("tags", JsonSchema::string_array().optional())
+        .raw_property("extra", json!({"type": "object"}), true)
+        .raw_property("meta", JsonSchema::object().build(), false)
+        .build();
+    assert_eq!(
+        text(&schema),
+        concat!(
+            r#"{"properties":{"age":{"description":"年龄","type":"integer"},"extra":{"type":"object"},"#,
+            r#""meta":{"properties":{},"required":[],"type":"object"},"name":{"description":"用户名","type":"string"},"#,
+            r#""tags":{"items":{"type":"string"},"type":"array"}},"required":["name","extra"],"type":"object"}"#
+        )
+    );
+}
+
+#[test]
+fn test_capability_shims_match_json_schema() {
+    let pairs = [
+        (InputSchema::string().build(), JsonSchema::string().build()),
+        (InputSchema::integer().build(), JsonSchema::integer().build()),
+        (InputSchema::number().build(), JsonSchema::number().build()),
+        (InputSchema::boolean().build(), JsonSchema::boolean().build()),
+        (InputSchema::array(json!({"type": "string"})).build(), JsonSchema::array(json!({"type": "string"})).build()),
+        (InputSchema::string_array().build(), JsonSchema::string_array().build()),
+        (InputSchema::enum_values(vec!["a"]).build(), JsonSchema::enum_values(vec!["a"]).build()),
+        (OutputSchema::string().build(), JsonSchema::string().build()),
+        (OutputSchema::integer().build(), JsonSchema::integer().build()),
+        (OutputSchema::number().build(), JsonSchema::number().build()),
+        (OutputSchema::boolean().build(), JsonSchema::boolean().build()),
+        (OutputSchema::array(json!({"type": "object"})).build(), JsonSchema::array(json!({"type": "object"})).build()),
+        (
+            InputSchema::object()
+                .property("q", InputSchema::string().description("查询").optional())
+                .raw_property("r", json!({}), true)
+                .build(),
+            JsonSchema::object()
+                .property("q", JsonSchema::string().description("查询").optional())
+                .raw_property("r", json!({}), true)
+                .build(),
+        ),
+        (OutputSchema::object().build(), JsonSchema::object().build()),
+    ];
+    for (shim, schema) in pairs {
+        assert_eq!(text(&shim), text(&schema));
+    }
+}
+
+#[test]
+fn test_required_fields_shared_by_tool_and_capability() {
+    let schema = JsonSchema::object()
+        .property("a", JsonSchema::string())
+        .property("b", JsonSchema::string().optional())
+        .property("c", JsonSchema::integer())
+        .build();
+    let tool = Tool::new("t.x", "X", "X", CategoryPath::from_str("t"), schema.clone())
+        .with_returns(ReturnType::new("r", json!({"type": "object"})));
+    let capability = Capability::new(
+        "c.x",
+        "X",
+        "X",
+        CapabilityPath::from_str("c"),
+        schema,
+        JsonSchema::object().build(),
+        "http".to_string(),
+        None,
+    );
+    assert_eq!(tool.required_params(), vec!["a", "c"]);
+    assert_eq!(capability.required_inputs(), tool.required_params());
+}
+
+#[test]
+fn test_shared_openai_and_mcp_conversion() {
+    let schema = JsonSchema::object()
+        .property("query", JsonSchema::string().description("关键词"))
+        .build();
+    let tool = Tool::new("tool.search", "Search", "Search tools", CategoryPath::from_str("tool"), schema.clone());
+    let capability = Capability::new(
+        "tool.search",
+        "Search",
+        "Search tools",
+        CapabilityPath::from_str("tool"),
+        schema.clone(),
+        JsonSchema::object().build(),
+        "http".to_string(),
+        None,
+    );
+
+    assert_eq!(
+        tool.to_openai_function(),
+        json!({ "name": "tool__search", "description": "Search tools", "parameters": schema })
+    );
+    assert_eq!(
+        tool.to_mcp_tool(),
+        json!({ "name": "tool.search", "description": "Search tools", "inputSchema": schema })
+    );
+    assert_eq!(capability.to_mcp_tool(), tool.to_mcp_tool());
+
+    // 缺省的 Schema 按无参数 object 导出
+    let bare = imitatort::domain::schema::openai_function("f", "d", &Value::Null);
+    assert_eq!(bare["parameters"], JsonSchema::object().build());
+}
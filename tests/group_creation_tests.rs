@@ -30,10 +30,7 @@ async fn test_group_creation() {
     org.add_agent(agent1);
     org.add_agent(agent2);
 
-    let config = CompanyConfig {
-        name: "Test Company".to_string(),
-        organization: org,
-    };
+    let config = CompanyConfig::new("Test Company", org);
 
     // 创建虚拟公司
     let company = VirtualCompany::with_store(
@@ -0,0 +1,175 @@
+//! Rust 客户端集成测试：进程内启动服务端和使用模拟 LLM 的 Agent，通过客户端完成一次对话
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{extract::State, routing::post, Json, Router};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::application::autonomous::AutonomousAgent;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::Store;
+use imitatort::domain::user::User;
+use imitatort::domain::{Agent, LLMConfig, Message, Role};
+use imitatort::infrastructure::auth::{JwtService, PasswordService};
+use imitatort::infrastructure::client::ImitatorClient;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::protocol::{ClientMessage, ServerEvent, ServerFrame};
+use imitatort::infrastructure::web::{create_router, AppState};
+
+const QUESTION: &str = "What's the build status?";
+
+/// 看到用户的问题后回复一次，其余轮次等待
+async fn spawn_mock_llm(principal: String) -> String {
+    async fn completions(
+        State((principal, replied)): State<(String, Arc<AtomicBool>)>,
+        Json(request): Json<Value>,
+    ) -> Json<Value> {
+        let decision = if request.to_string().contains(QUESTION) && !replied.swap(true, Ordering::SeqCst) {
+            json!({ "action": "send_message", "target": principal, "content": "All green" })
+        } else {
+            json!({ "action": "wait" })
+        };
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": decision.to_string() },
+                "finish_reason": "stop"
+            }]
+        }))
+    }
+
+    let app = Router::new()
+        .route("/chat/completions", post(completions))
+        .with_state((principal, Arc::new(AtomicBool::new(false))));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+/// 启动带一个自主 Agent 的服务端，返回服务地址
+async fn spawn_server() -> String {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let user = User::new_chairman(
+        "alice".to_string(),
+        "Alice".to_string(),
+        PasswordService::hash_password("Str0ng-enough").unwrap(),
+        None,
+    );
+    store.save_user(&user).await.unwrap();
+
+    let llm_url = spawn_mock_llm(user.principal_id()).await;
+    let agent = Agent::new(
+        "ops",
+        "Ops",
+        Role::simple("Operations", "You keep the build green"),
+        LLMConfig::openai("test-key").with_base_url(llm_url),
+    );
+
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let autonomous = AutonomousAgent::new(agent.clone(), bus.clone()).await.unwrap();
+    tokio::spawn(async move {
+        let _ = autonomous.run_loop().await;
+    });
+
+    let (message_tx, _) = broadcast::channel::<Message>(100);
+    let state = AppState::new(vec![agent], message_tx, store, JwtService::new("test-secret-for-testing"))
+        .with_message_bus(bus);
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_conversation_through_client() {
+    let base = spawn_server().await;
+    let client = ImitatorClient::new(&base).with_chat_timeout(Duration::from_secs(10));
+
+    // 未登录不能以用户身份发消息
+    assert!(client.send_message("ops", "hi").await.is_err());
+    let err = client.login("alice", "wrong-password").await.unwrap_err();
+    assert!(err.to_string().starts_with("UNAUTHORIZED"), "{}", err);
+
+    let session = client.login("alice", "Str0ng-enough").await.unwrap();
+    assert_eq!(session.user.username, "alice");
+
+    let agents = client.list_agents().await.unwrap();
+    assert_eq!(agents.len(), 1);
+    assert_eq!(agents[0].id, "ops");
+    assert_eq!(agents[0].role, "Operations");
+
+    let mut events = client.subscribe().await.unwrap();
+    let reply = client.chat("ops", QUESTION).await.unwrap();
+    assert_eq!(reply.from, "ops");
+    assert_eq!(reply.to, session.user.principal_id);
+    assert_eq!(reply.content, "All green");
+
+    // 其他订阅者也收到同一条回复
+    let event = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match events.next().await {
+                Some(ServerEvent::Message { data }) if data.from == "ops" => return data,
+                Some(_) => continue,
+                None => panic!("event stream closed"),
+            }
+        }
+    })
+    .await
+    .unwrap();
+    assert_eq!(event.id, reply.id);
+
+    client.send(ClientMessage::Ping).await.unwrap();
+    let pong = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Some(ServerEvent::Pong) = events.next().await {
+                return;
+            }
+        }
+    })
+    .await;
+    assert!(pong.is_ok());
+}
+
+#[test]
+fn test_frames_keep_wire_format() {
+    let frame: ServerFrame = serde_json::from_value(json!({
+        "v": 1,
+        "type": "message",
+        "data": { "id": "m1", "from": "ops", "to": "group:g1", "content": "hi", "timestamp": 5 }
+    }))
+    .unwrap();
+    match &frame.event {
+        ServerEvent::Message { data } => assert_eq!(data.to, "group:g1"),
+        other => panic!("unexpected event: {:?}", other),
+    }
+
+    let error = serde_json::to_value(ServerFrame::new(ServerEvent::Error {
+        message: "Invalid JSON format".to_string(),
+    }))
+    .unwrap();
+    assert_eq!(error, json!({ "v": 1, "type": "error", "message": "Invalid JSON format" }));
+
+    let send = serde_json::to_value(ClientMessage::SendMessage {
+        from: "a".to_string(),
+        to: "b".to_string(),
+        content: "hi".to_string(),
+        priority: Default::default(),
+    })
+    .unwrap();
+    assert_eq!(send["type"], "send_message");
+    assert_eq!(send["to"], "b");
+}
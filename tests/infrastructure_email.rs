@@ -0,0 +1,287 @@
+//! 邮件通知测试
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use imitatort::core::clock::ManualClock;
+use imitatort::core::config::{CompanyConfig, EmailPolicy, EscalationPolicy};
+use imitatort::core::escalation::EscalationChecker;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::{ToolCallContext, ToolProvider};
+use imitatort::domain::{Agent, Department, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::email::{
+    EmailError, EmailMessage, EmailNotifier, EmailSender, SmtpConfig, SmtpEmailSender, SmtpTls,
+};
+use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment};
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::RwLock;
+
+/// 记录发出的邮件，可预设前几次发送失败
+#[derive(Default)]
+struct StubSender {
+    sent: Mutex<Vec<EmailMessage>>,
+    failures: Mutex<VecDeque<&'static str>>,
+    attempts: Mutex<u32>,
+}
+
+impl StubSender {
+    fn failing(times: usize) -> Self {
+        let stub = Self::default();
+        stub.failures.lock().unwrap().extend(std::iter::repeat_n("connection refused", times));
+        stub
+    }
+
+    fn sent(&self) -> Vec<EmailMessage> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    fn attempts(&self) -> u32 {
+        *self.attempts.lock().unwrap()
+    }
+}
+
+#[async_trait]
+impl EmailSender for StubSender {
+    async fn send(&self, message: &EmailMessage) -> anyhow::Result<()> {
+        *self.attempts.lock().unwrap() += 1;
+        if let Some(error) = self.failures.lock().unwrap().pop_front() {
+            return Err(anyhow::anyhow!(error));
+        }
+        self.sent.lock().unwrap().push(message.clone());
+        Ok(())
+    }
+}
+
+fn policy() -> EmailPolicy {
+    EmailPolicy::allow_domains(vec!["example.com"])
+        .with_agent_domains("sales", vec!["customer.io"])
+        .with_daily_cap(2)
+        .with_retry(2, 1)
+}
+
+fn email(to: &str) -> EmailMessage {
+    EmailMessage::new(to, "Daily summary", "All good")
+}
+
+#[test]
+fn test_policy_domain_allowlist() {
+    let policy = policy();
+    assert!(policy.allows("dev", "alice@example.com"));
+    assert!(policy.allows("dev", "ops@eu.Example.COM"));
+    assert!(!policy.allows("dev", "alice@example.com.evil.net"));
+    assert!(!policy.allows("dev", "alice@notexample.com"));
+    // 单独配置的 Agent 只能用自己的白名单
+    assert!(policy.allows("sales", "buyer@customer.io"));
+    assert!(!policy.allows("sales", "alice@example.com"));
+    // 默认不允许任何域名
+    assert!(!EmailPolicy::default().allows("dev", "alice@example.com"));
+}
+
+#[tokio::test]
+async fn test_daily_cap_resets_next_day() {
+    let sender = Arc::new(StubSender::default());
+    let clock = Arc::new(ManualClock::new(1_700_000_000));
+    let notifier = EmailNotifier::new(sender.clone(), policy()).with_clock(clock.clone());
+
+    assert_eq!(notifier.send_as("dev", &email("a@example.com")).await, Ok(1));
+    assert_eq!(notifier.send_as("dev", &email("b@example.com")).await, Ok(1));
+    assert_eq!(
+        notifier.send_as("dev", &email("c@example.com")).await,
+        Err(EmailError::DailyCapReached { agent_id: "dev".into(), cap: 2 })
+    );
+    // 上限按 Agent 计算
+    assert!(notifier.send_as("ops", &email("c@example.com")).await.is_ok());
+
+    clock.advance(Duration::from_secs(24 * 60 * 60));
+    assert_eq!(notifier.sent_today("dev"), 0);
+    assert!(notifier.send_as("dev", &email("c@example.com")).await.is_ok());
+    assert_eq!(sender.sent().len(), 4);
+}
+
+#[tokio::test]
+async fn test_rejected_domains_are_not_sent() {
+    let sender = Arc::new(StubSender::default());
+    let notifier = EmailNotifier::new(sender.clone(), policy());
+
+    let result = notifier.send_as("dev", &email("someone@gmail.com")).await;
+    assert!(matches!(result, Err(EmailError::DomainNotAllowed { .. })));
+    assert!(matches!(notifier.send_as("dev", &email("not an address")).await, Err(EmailError::InvalidAddress(_))));
+    assert_eq!(sender.attempts(), 0);
+    assert_eq!(notifier.sent_today("dev"), 0);
+}
+
+#[tokio::test]
+async fn test_failures_are_retried_with_backoff() {
+    let sender = Arc::new(StubSender::failing(2));
+    let notifier = EmailNotifier::new(sender.clone(), policy());
+    assert_eq!(notifier.send_as("dev", &email("a@example.com")).await, Ok(3));
+    assert_eq!(sender.sent().len(), 1);
+
+    // 重试耗尽后报错，且不占用当天额度
+    let sender = Arc::new(StubSender::failing(5));
+    let notifier = EmailNotifier::new(sender.clone(), policy());
+    let result = notifier.send_as("dev", &email("a@example.com")).await;
+    assert_eq!(
+        result,
+        Err(EmailError::Failed { attempts: 3, error: "connection refused".into() })
+    );
+    assert_eq!(sender.attempts(), 3);
+    assert_eq!(notifier.sent_today("dev"), 0);
+}
+
+fn environment() -> ToolEnvironment {
+    ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        Arc::new(MemoryStore::new()),
+    )
+}
+
+#[tokio::test]
+async fn test_notify_email_tool_requires_configuration() {
+    let env = environment();
+    assert!(!env.tool_provider.list_tools().iter().any(|t| t.id == "notify.email"));
+
+    let params = json!({"to": "a@example.com", "subject": "Hi", "body": "Hello"});
+    let result = FrameworkToolExecutor::new(env)
+        .execute("notify.email", params.clone(), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("not configured"));
+
+    let sender = Arc::new(StubSender::default());
+    let env = environment().with_email(Arc::new(EmailNotifier::new(sender.clone(), policy())));
+    assert!(env.tool_provider.list_tools().iter().any(|t| t.id == "notify.email"));
+    let executor = FrameworkToolExecutor::new(env);
+
+    let result = executor.execute("notify.email", params.clone(), &ToolCallContext::new("dev")).await.unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["sent_today"], 1);
+    assert_eq!(result.data["daily_cap"], 2);
+    assert_eq!(sender.sent()[0].subject, "Hi");
+
+    let denied = executor
+        .execute("notify.email", json!({"to": "x@gmail.com", "subject": "Hi", "body": "Hello"}), &ToolCallContext::new("dev"))
+        .await
+        .unwrap();
+    assert!(!denied.success);
+    assert!(denied.error.unwrap().contains("not allowed"));
+
+    executor.execute("notify.email", params.clone(), &ToolCallContext::new("dev")).await.unwrap();
+    let capped = executor.execute("notify.email", params, &ToolCallContext::new("dev")).await.unwrap();
+    assert!(capped.error.unwrap().contains("Daily email limit of 2"));
+}
+
+#[tokio::test]
+async fn test_escalation_emails_responsible_human() {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("tech", "Tech").with_leader("lead"));
+    for id in ["lead", "dev"] {
+        org.add_agent(
+            Agent::new(id, id, Role::simple("Employee", "You work here"), LLMConfig::openai("test-key"))
+                .with_department("tech"),
+        );
+    }
+    let config = CompanyConfig::new("Test", org.clone()).with_escalation(
+        "tech",
+        EscalationPolicy::after_secs(60).with_notify_email("oncall@example.com"),
+    );
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _rx = (bus.register("dev"), bus.register("lead"));
+    let clock = Arc::new(ManualClock::new(chrono::Utc::now().timestamp()));
+    let sender = Arc::new(StubSender::default());
+    // 系统升级邮件不受 Agent 白名单限制
+    let notifier = Arc::new(EmailNotifier::new(sender.clone(), EmailPolicy::default()));
+
+    let checker = EscalationChecker::new(config, Arc::new(RwLock::new(org)), bus.clone(), store)
+        .with_clock(clock.clone())
+        .with_email(notifier);
+
+    bus.send(Message::private("user", "dev", "Is the build green?")).await.unwrap();
+    clock.advance(Duration::from_secs(120));
+    assert_eq!(checker.check().await.unwrap().len(), 1);
+
+    let sent = sender.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "oncall@example.com");
+    assert!(sent[0].subject.contains("dev"));
+    assert!(sent[0].body.contains("Is the build green?"));
+}
+
+/// 只实现发送一封邮件所需命令的 SMTP 服务器
+async fn mock_smtp_server() -> (u16, tokio::task::JoinHandle<String>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let handle = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let (read, mut write) = socket.into_split();
+        let mut lines = BufReader::new(read).lines();
+        let mut transcript = String::new();
+        let mut in_data = false;
+
+        write.write_all(b"220 mock ESMTP\r\n").await.unwrap();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            transcript.push_str(&line);
+            transcript.push('\n');
+            let reply: &[u8] = if in_data {
+                if line != "." {
+                    continue;
+                }
+                in_data = false;
+                b"250 queued\r\n"
+            } else if line.starts_with("EHLO") {
+                b"250 mock\r\n"
+            } else if line == "DATA" {
+                in_data = true;
+                b"354 go ahead\r\n"
+            } else if line == "QUIT" {
+                write.write_all(b"221 bye\r\n").await.unwrap();
+                break;
+            } else {
+                b"250 ok\r\n"
+            };
+            write.write_all(reply).await.unwrap();
+        }
+        transcript
+    });
+    (port, handle)
+}
+
+#[tokio::test]
+async fn test_smtp_sender_against_mock_server() {
+    let (port, server) = mock_smtp_server().await;
+    let sender = SmtpEmailSender::new(&SmtpConfig {
+        host: "127.0.0.1".to_string(),
+        port,
+        tls: SmtpTls::None,
+        from: "ImitatorT <bot@example.com>".to_string(),
+        username: None,
+        password: None,
+    })
+    .unwrap();
+
+    sender.send(&email("alice@example.com")).await.unwrap();
+    drop(sender);
+
+    let transcript = tokio::time::timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+    assert!(transcript.contains("MAIL FROM:<bot@example.com>"));
+    assert!(transcript.contains("RCPT TO:<alice@example.com>"));
+    assert!(transcript.contains("Subject: Daily summary"));
+    assert!(transcript.contains("All good"));
+}
+
+#[test]
+fn test_smtp_tls_parse() {
+    assert_eq!("STARTTLS".parse::<SmtpTls>(), Ok(SmtpTls::StartTls));
+    assert_eq!("ssl".parse::<SmtpTls>(), Ok(SmtpTls::Tls));
+    assert_eq!("none".parse::<SmtpTls>(), Ok(SmtpTls::None));
+    assert!("maybe".parse::<SmtpTls>().is_err());
+}
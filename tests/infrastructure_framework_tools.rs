@@ -1,9 +1,11 @@
 //! 框架内置工具实现测试
 
-use imitatort::core::messaging::MessageBus;
+use imitatort::core::messaging::{MessageBus, OutboxPolicy, ReactionEvent};
+use imitatort::core::store::Store;
 use imitatort::core::tool::ToolRegistry;
 use imitatort::domain::{Organization, Agent, Role, LLMConfig};
 use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::TurnOutbox;
 use imitatort::infrastructure::tool::{FrameworkToolExecutor, ToolEnvironment, ToolExecutor};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -106,6 +108,43 @@ async fn test_org_find_agents() {
     assert!(result.data.get("agents").is_some());
 }
 
+#[tokio::test]
+async fn test_org_find_users() {
+    let store = Arc::new(imitatort::infrastructure::store::SqliteStore::new_in_memory().unwrap());
+    let user = imitatort::domain::user::User::new_employee(
+        "bob".to_string(),
+        "Bob".to_string(),
+        "hash".to_string(),
+        1,
+        "Engineering".to_string(),
+        None,
+    );
+    store.save_user(&user).await.unwrap();
+
+    let env = ToolEnvironment::new(
+        Arc::new(MessageBus::new()),
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store,
+    );
+    let executor = FrameworkToolExecutor::new(env);
+    let context = ToolCallContext::new("test-agent");
+
+    let result = executor
+        .execute("org.find_users", serde_json::json!({ "query": "engineer" }), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.data["count"], 1);
+    assert_eq!(result.data["users"][0]["principal_id"], user.principal_id());
+
+    let result = executor
+        .execute("org.find_users", serde_json::json!({ "query": "nobody" }), &context)
+        .await
+        .unwrap();
+    assert_eq!(result.data["count"], 0);
+}
+
 #[tokio::test]
 async fn test_unknown_tool() {
     let env = create_test_environment();
@@ -121,3 +160,142 @@ async fn test_unknown_tool() {
     assert!(!result.success);
     assert!(result.error.is_some());
 }
+
+#[tokio::test]
+async fn test_message_react() {
+    let store = Arc::new(imitatort::core::store::MemoryStore::new());
+    let message_bus = Arc::new(MessageBus::with_store(store.clone()));
+    let mut reactions = message_bus.subscribe_reactions();
+    let env = ToolEnvironment::new(
+        message_bus,
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store.clone(),
+    );
+    let executor = FrameworkToolExecutor::new(env);
+    let context = ToolCallContext::new("agent-1");
+    let params = serde_json::json!({"message_id": "m1", "emoji": "✅"});
+
+    let result = executor.execute("message.react", params.clone(), &context).await.unwrap();
+    assert_eq!(result.data, serde_json::json!({"changed": true}));
+    match reactions.recv().await.unwrap() {
+        ReactionEvent::Added(reaction) => {
+            assert_eq!(reaction.reactor_id, "agent-1");
+            assert_eq!(reaction.emoji, "✅");
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    // 重复回应不再广播
+    let result = executor.execute("message.react", params, &context).await.unwrap();
+    assert_eq!(result.data, serde_json::json!({"changed": false}));
+    assert!(reactions.try_recv().is_err());
+
+    let result = executor
+        .execute("message.react", serde_json::json!({"message_id": "m1", "emoji": "✅", "remove": true}), &context)
+        .await
+        .unwrap();
+    assert_eq!(result.data, serde_json::json!({"changed": true}));
+    assert!(matches!(reactions.recv().await.unwrap(), ReactionEvent::Removed(_)));
+}
+
+fn outbox_executor(message_bus: Arc<MessageBus>) -> FrameworkToolExecutor {
+    FrameworkToolExecutor::new(ToolEnvironment::new(
+        message_bus,
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        Arc::new(imitatort::core::store::MemoryStore::new()),
+    ))
+}
+
+/// 本轮排了两条消息后轮次失败
+async fn enqueue_two(executor: &FrameworkToolExecutor, context: &ToolCallContext) {
+    for content in ["first", "second"] {
+        let result = executor
+            .execute("message.send_direct", serde_json::json!({"to_agent_id": "bob", "content": content}), context)
+            .await
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["queued"], true);
+    }
+}
+
+#[tokio::test]
+async fn test_outbox_discarded_on_failed_turn() {
+    let message_bus = Arc::new(MessageBus::new());
+    let mut bob = message_bus.register("bob");
+    message_bus.register("alice");
+    let executor = outbox_executor(message_bus.clone());
+    let outbox = TurnOutbox::new();
+    let context = ToolCallContext::new("alice").with_outbox(outbox.clone());
+
+    enqueue_two(&executor, &context).await;
+    assert_eq!(outbox.len(), 2);
+    assert!(bob.try_recv().is_err());
+
+    let report = message_bus.flush_outbox("alice", "trace-1", &outbox, true, OutboxPolicy::Discard).await;
+    assert!(report.sent.is_empty());
+    assert_eq!(report.discarded.len(), 2);
+    assert!(outbox.is_empty());
+    assert!(bob.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_outbox_flushed_on_failed_turn() {
+    let message_bus = Arc::new(MessageBus::new());
+    let mut bob = message_bus.register("bob");
+    message_bus.register("alice");
+    let executor = outbox_executor(message_bus.clone());
+    let outbox = TurnOutbox::new();
+    let context = ToolCallContext::new("alice").with_outbox(outbox.clone());
+
+    enqueue_two(&executor, &context).await;
+    assert!(bob.try_recv().is_err());
+
+    let report = message_bus.flush_outbox("alice", "trace-1", &outbox, true, OutboxPolicy::Flush).await;
+    assert_eq!(report.sent.len(), 2);
+    assert_eq!(bob.try_recv().unwrap().content, "first");
+    assert_eq!(bob.try_recv().unwrap().content, "second");
+}
+
+#[tokio::test]
+async fn test_outbox_is_all_or_nothing() {
+    let message_bus = Arc::new(MessageBus::new());
+    let mut bob = message_bus.register("bob");
+    message_bus.register("alice");
+    let executor = outbox_executor(message_bus.clone());
+    let outbox = TurnOutbox::new();
+    let context = ToolCallContext::new("alice").with_outbox(outbox.clone());
+
+    for to in ["bob", "nobody"] {
+        executor
+            .execute("message.send_direct", serde_json::json!({"to_agent_id": to, "content": "hi"}), &context)
+            .await
+            .unwrap();
+    }
+
+    let report = message_bus.flush_outbox("alice", "trace-1", &outbox, false, OutboxPolicy::Discard).await;
+    assert!(report.sent.is_empty());
+    assert_eq!(report.discarded.len(), 1);
+    assert_eq!(report.failed.len(), 1);
+    assert!(report.failed[0].1.contains("nobody"));
+    assert!(bob.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_send_immediate_bypasses_outbox() {
+    let message_bus = Arc::new(MessageBus::new());
+    let mut bob = message_bus.register("bob");
+    message_bus.register("alice");
+    let executor = outbox_executor(message_bus);
+    let outbox = TurnOutbox::new();
+    let context = ToolCallContext::new("alice").with_outbox(outbox.clone());
+
+    let result = executor
+        .execute("message.send_immediate", serde_json::json!({"to": "bob", "content": "which branch?"}), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert!(outbox.is_empty());
+    assert_eq!(bob.try_recv().unwrap().content, "which branch?");
+}
@@ -1,6 +1,19 @@
-//! LLM 客户端测试
+//! LLM 客户端测试：错误响应分类（鉴权、额度、上下文长度、内容过滤、速率限制、服务端、请求、
+//! 连接失败和无法识别的响应）、只重试临时错误并遵守 Retry-After、上下文超长时缩短历史重试一次
 
-use imitatort::infrastructure::llm::{Message, OpenAIClient};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::post;
+use axum::{Json, Router};
+use imitatort::core::agent::{AgentRuntime, Context, Decision};
+use imitatort::domain::{Agent, LLMConfig, Message as ChatMessage, Role};
+use imitatort::infrastructure::llm::{LlmError, Message, OpenAIClient};
+use serde_json::{json, Value};
 
 #[test]
 fn test_client_creation() {
@@ -25,3 +38,195 @@ fn test_message_creation() {
     assert_eq!(msg.role, "user");
     assert_eq!(msg.content, "Hello");
 }
+
+/// 模型服务的一次响应：状态码、响应头和响应体
+type Reply = (u16, Vec<(&'static str, &'static str)>, String);
+
+#[derive(Default)]
+struct MockLlm {
+    /// 依次返回的响应，用完后让 Agent 等待
+    replies: Mutex<VecDeque<Reply>>,
+    requests: Mutex<Vec<Value>>,
+}
+
+impl MockLlm {
+    fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn spawn_llm(replies: Vec<Reply>) -> (Arc<MockLlm>, String) {
+    async fn completions(State(llm): State<Arc<MockLlm>>, Json(request): Json<Value>) -> axum::response::Response {
+        llm.requests.lock().unwrap().push(request);
+        let Some((status, headers, body)) = llm.replies.lock().unwrap().pop_front() else {
+            return Json(json!({
+                "id": "chatcmpl-mock",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "mock",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": json!({ "action": "wait" }).to_string() },
+                    "finish_reason": "stop"
+                }]
+            }))
+            .into_response();
+        };
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            header_map.insert(HeaderName::from_static(name), HeaderValue::from_static(value));
+        }
+        (StatusCode::from_u16(status).unwrap(), header_map, body).into_response()
+    }
+
+    let llm = Arc::new(MockLlm {
+        replies: Mutex::new(replies.into()),
+        ..Default::default()
+    });
+    let app = Router::new().route("/chat/completions", post(completions)).with_state(llm.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (llm, format!("http://{}", addr))
+}
+
+fn error_body(message: &str, kind: Value, code: Value) -> String {
+    json!({ "error": { "message": message, "type": kind, "param": null, "code": code } }).to_string()
+}
+
+fn client(url: &str) -> OpenAIClient {
+    OpenAIClient::new_with_base_url("k".into(), "gpt-test".into(), url.into())
+}
+
+/// 不重试，返回单次请求的错误分类
+async fn classify(status: u16, headers: Vec<(&'static str, &'static str)>, body: String) -> LlmError {
+    let (_llm, url) = spawn_llm(vec![(status, headers, body)]).await;
+    let error = client(&url).with_retry(0, Duration::ZERO).complete("hi").await.unwrap_err();
+    LlmError::of(&error).cloned().unwrap_or_else(|| panic!("untyped error: {:#}", error))
+}
+
+#[tokio::test]
+async fn test_documented_error_shapes_are_classified() {
+    let auth = error_body("Incorrect API key provided: sk-***", json!("invalid_request_error"), json!("invalid_api_key"));
+    assert!(matches!(classify(401, vec![], auth).await, LlmError::AuthFailed { detail } if detail.starts_with("Incorrect API key")));
+
+    let quota = error_body("You exceeded your current quota", json!("insufficient_quota"), json!("insufficient_quota"));
+    assert!(matches!(classify(429, vec![], quota).await, LlmError::QuotaExceeded { .. }));
+
+    let context = error_body(
+        "This model's maximum context length is 8192 tokens. However, your messages resulted in 9,000 tokens. \
+         Please reduce the length of the messages.",
+        json!("invalid_request_error"),
+        json!("context_length_exceeded"),
+    );
+    assert_eq!(
+        classify(400, vec![], context).await,
+        LlmError::ContextLengthExceeded { limit: Some(8192), requested: Some(9000) }
+    );
+
+    let filtered = error_body(
+        "The response was filtered due to the prompt triggering the content management policy.",
+        Value::Null,
+        json!("content_filter"),
+    );
+    assert!(matches!(classify(400, vec![], filtered).await, LlmError::ContentFiltered { .. }));
+
+    let rate_limited = error_body("Rate limit reached for requests", json!("requests"), json!("rate_limit_exceeded"));
+    assert_eq!(
+        classify(429, vec![("retry-after", "2")], rate_limited).await,
+        LlmError::RateLimited { retry_after: Some(Duration::from_secs(2)) }
+    );
+    let rate_limited = error_body("Rate limit reached", json!("requests"), Value::Null);
+    assert_eq!(
+        classify(429, vec![("retry-after-ms", "250")], rate_limited).await,
+        LlmError::RateLimited { retry_after: Some(Duration::from_millis(250)) }
+    );
+
+    let invalid = error_body("The model `gpt-9` does not exist", json!("invalid_request_error"), json!("model_not_found"));
+    assert!(matches!(classify(404, vec![], invalid).await, LlmError::InvalidRequest { detail } if detail.contains("gpt-9")));
+
+    // 服务端错误不保证返回 JSON
+    assert_eq!(
+        classify(503, vec![], "upstream connect error".into()).await,
+        LlmError::ServerError { status: 503, detail: "upstream connect error".into() }
+    );
+}
+
+#[tokio::test]
+async fn test_malformed_and_unreachable_responses_map_to_catch_all() {
+    assert!(matches!(
+        classify(418, vec![], "<html>I'm a teapot</html>".into()).await,
+        LlmError::Unknown { status: 418, detail } if detail.contains("teapot")
+    ));
+    assert!(matches!(classify(400, vec![], json!({ "error": 42 }).to_string()).await, LlmError::Unknown { status: 400, .. }));
+    assert!(matches!(classify(200, vec![], "not json".into()).await, LlmError::Unknown { status: 200, .. }));
+    // 码点边界上截断过长的详情，不会 panic
+    let long = "错".repeat(2000);
+    assert!(matches!(classify(502, vec![], long).await, LlmError::ServerError { detail, .. } if detail.ends_with("...")));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let error = client(&url).with_retry(0, Duration::ZERO).complete("hi").await.unwrap_err();
+    assert!(matches!(LlmError::of(&error), Some(LlmError::Network { .. })), "{:#}", error);
+}
+
+#[tokio::test]
+async fn test_only_transient_errors_are_retried() {
+    let auth = error_body("Incorrect API key provided", json!("invalid_request_error"), json!("invalid_api_key"));
+    let (llm, url) = spawn_llm(vec![(401, vec![], auth)]).await;
+    let client = client(&url).with_retry(3, Duration::from_millis(1));
+    assert!(client.complete("hi").await.is_err());
+    assert_eq!(llm.requests().len(), 1);
+
+    // 速率限制按 Retry-After 等待后重试，服务端错误按退避重试
+    let rate_limited = error_body("Rate limit reached", json!("requests"), json!("rate_limit_exceeded"));
+    let (llm, url) = spawn_llm(vec![
+        (429, vec![("retry-after", "0.2")], rate_limited),
+        (500, vec![], "boom".into()),
+    ])
+    .await;
+    let client = OpenAIClient::new_with_base_url("k".into(), "gpt-test".into(), url).with_retry(3, Duration::from_millis(1));
+    let started = std::time::Instant::now();
+    assert_eq!(client.complete("hi").await.unwrap(), json!({ "action": "wait" }).to_string());
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(llm.requests().len(), 3);
+}
+
+#[tokio::test]
+async fn test_context_length_error_shrinks_history_and_retries_once() {
+    let context_error = || {
+        let body = error_body(
+            "This model's maximum context length is 1000 tokens. However, your messages resulted in 1400 tokens.",
+            json!("invalid_request_error"),
+            json!("context_length_exceeded"),
+        );
+        (400, vec![], body)
+    };
+    let (llm, url) = spawn_llm(vec![context_error()]).await;
+    let agent = Agent::new("dev", "Dev", Role::simple("Engineer", "You build things"), LLMConfig::openai("k").with_base_url(url));
+    let runtime = AgentRuntime::new(agent).await.unwrap();
+    let history: Vec<ChatMessage> = (0..20)
+        .map(|i| ChatMessage::private("alice", "dev", format!("message {} {}", i, "background detail ".repeat(10))))
+        .collect();
+    let context = Context::default().with_history(history);
+
+    assert!(matches!(runtime.think(context.clone()).await.unwrap(), Decision::Wait));
+    let requests = llm.requests();
+    assert_eq!(requests.len(), 2);
+    let prompt = |request: &Value| request["messages"][0]["content"].as_str().unwrap().to_string();
+    let (first, retried) = (prompt(&requests[0]), prompt(&requests[1]));
+    assert!(first.contains("message 0 "), "{}", first);
+    assert!(retried.len() < first.len());
+    // 保留最新的消息
+    assert!(!retried.contains("message 0 ") && retried.contains("message 19 "), "{}", retried);
+
+    // 缩短后仍然超长时不再重试
+    let (llm, url) = spawn_llm(vec![context_error(), context_error()]).await;
+    let agent = Agent::new("dev", "Dev", Role::simple("Engineer", "You build things"), LLMConfig::openai("k").with_base_url(url));
+    let error = AgentRuntime::new(agent).await.unwrap().think(context).await.unwrap_err();
+    assert!(matches!(LlmError::of(&error), Some(LlmError::ContextLengthExceeded { .. })));
+    assert_eq!(llm.requests().len(), 2);
+}
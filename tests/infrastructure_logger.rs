@@ -1,6 +1,319 @@
-//! 日志模块测试
+//! 日志模块测试：提示词日志的轮转、脱敏级别、慢速磁盘下不阻塞、trace ID 关联和运行中调整级别
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{routing::post, Json, Router};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::llm::{Message as LlmMessage, OpenAIClient};
+use imitatort::infrastructure::logger::{
+    with_trace_id, PromptExchange, PromptLog, PromptLogConfig, PromptLogLevel, PromptMessage, PromptToolCall,
+    RotatingFileWriter, PROMPT_LOG_FILE,
+};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
 
 #[test]
 fn test_logger_init() {
     // 日志初始化测试
 }
+
+fn exchange(user_content: &str) -> PromptExchange {
+    PromptExchange::new(
+        "gpt-test",
+        vec![
+            PromptMessage { role: "system".into(), content: "You are the release manager".into() },
+            PromptMessage { role: "user".into(), content: user_content.into() },
+            PromptMessage { role: "tool".into(), content: "{\"rows\": 3}".into() },
+        ],
+        vec!["message.send".into()],
+    )
+    .with_response(
+        "Sending the summary",
+        vec![PromptToolCall { name: "message.send".into(), arguments: json!({ "to": "pm", "content": "done" }) }],
+    )
+    .with_usage(120, 30, 150)
+}
+
+fn lines_in(path: &Path) -> Vec<Value> {
+    std::fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is a complete JSON record"))
+        .collect()
+}
+
+#[test]
+fn test_redaction_levels() {
+    let record = exchange("My card is 4111 1111 1111 1111");
+
+    let full = record.render(PromptLogLevel::Full);
+    assert_eq!(full["level"], "full");
+    assert_eq!(full["messages"][1]["content"], "My card is 4111 1111 1111 1111");
+    assert_eq!(full["response"]["tool_calls"][0]["arguments"]["to"], "pm");
+    assert_eq!(full["usage"]["total_tokens"], 150);
+    assert_eq!(full["tools"], json!(["message.send"]));
+
+    let redacted = record.render(PromptLogLevel::RedactUserContent);
+    let user = &redacted["messages"][1];
+    assert!(user.get("content").is_none());
+    assert_eq!(user["chars"], 30);
+    let hash = user["content_hash"].as_str().unwrap();
+    assert!(hash.starts_with("sha256:") && hash.len() == 7 + 64, "{}", hash);
+    assert!(redacted["messages"][2].get("content").is_none());
+    assert_eq!(redacted["messages"][0]["content"], "You are the release manager");
+    // LLM 回复和工具调用参数同样只保留哈希与长度
+    let response = &redacted["response"];
+    assert!(response.get("content").is_none());
+    assert_eq!(response["chars"], 19);
+    assert!(response["content_hash"].as_str().unwrap().starts_with("sha256:"));
+    let call = &response["tool_calls"][0];
+    assert_eq!(call["name"], "message.send");
+    assert!(call.get("arguments").is_none());
+    assert!(call["arguments_hash"].as_str().unwrap().starts_with("sha256:"));
+    assert!(!redacted.to_string().contains("Sending the summary"));
+    assert!(!redacted.to_string().contains("\"pm\""));
+    // 相同内容哈希相同，便于关联
+    let again = exchange("My card is 4111 1111 1111 1111").render(PromptLogLevel::RedactUserContent);
+    assert_eq!(again["messages"][1]["content_hash"], hash);
+    let other = exchange("Something else").render(PromptLogLevel::RedactUserContent);
+    assert_ne!(other["messages"][1]["content_hash"], hash);
+
+    let metadata = record.render(PromptLogLevel::MetadataOnly);
+    assert!(!metadata.to_string().contains("4111"));
+    assert!(!metadata.to_string().contains("release manager"));
+    assert!(!metadata.to_string().contains("sha256:"));
+    assert_eq!(metadata["messages"], json!([
+        { "role": "system", "chars": 27 },
+        { "role": "user", "chars": 30 },
+        { "role": "tool", "chars": 11 },
+    ]));
+    assert_eq!(metadata["response"], json!({ "chars": 19, "tool_calls": [{ "name": "message.send" }] }));
+    assert_eq!(metadata["usage"]["prompt_tokens"], 120);
+
+    let failed = PromptExchange::new("gpt-test", Vec::new(), Vec::new()).with_error("401 invalid api key");
+    let failed = failed.render(PromptLogLevel::MetadataOnly);
+    assert_eq!(failed["error"], "401 invalid api key");
+    assert!(failed["response"].is_null());
+}
+
+#[test]
+fn test_rotation_at_size_threshold() {
+    let dir = tempfile::tempdir().unwrap();
+    let line_len = exchange("hello").render(PromptLogLevel::Full).to_string().len() as u64 + 1;
+    let config = PromptLogConfig::new(dir.path())
+        .with_level(PromptLogLevel::Full)
+        .with_rotation(line_len * 3, Duration::ZERO)
+        .with_max_files(100);
+    let log = PromptLog::open(&config).unwrap();
+
+    for _ in 0..10 {
+        assert!(log.record(exchange("hello")));
+    }
+    log.flush();
+
+    // 每个文件最多三行，没有行被拆开或丢失
+    let rotated = RotatingFileWriter::rotated_files(dir.path()).unwrap();
+    assert_eq!(rotated.len(), 3);
+    let mut total = 0;
+    for file in rotated.iter().chain([&dir.path().join(PROMPT_LOG_FILE)]) {
+        assert!(std::fs::metadata(file).unwrap().len() <= line_len * 3);
+        total += lines_in(file).len();
+    }
+    assert_eq!(total, 10);
+    assert_eq!(lines_in(&dir.path().join(PROMPT_LOG_FILE)).len(), 1);
+
+    // 只保留最近的已轮转文件
+    drop(log);
+    let config = config.with_max_files(2);
+    let log = PromptLog::open(&config).unwrap();
+    for _ in 0..6 {
+        log.record(exchange("hello"));
+    }
+    log.flush();
+    assert_eq!(RotatingFileWriter::rotated_files(dir.path()).unwrap().len(), 2);
+}
+
+#[test]
+fn test_rotation_by_age_and_lines_written_on_drop() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = PromptLogConfig::new(dir.path()).with_level(PromptLogLevel::MetadataOnly);
+    config.max_age_secs = 1;
+    let log = PromptLog::open(&config).unwrap();
+    log.record(exchange("first"));
+    log.flush();
+    std::thread::sleep(Duration::from_millis(1100));
+    log.record(exchange("second"));
+    log.record(exchange("third"));
+    // 释放时写完队列中剩余的记录
+    drop(log);
+    std::thread::sleep(Duration::from_millis(200));
+
+    let rotated = RotatingFileWriter::rotated_files(dir.path()).unwrap();
+    assert_eq!(rotated.len(), 1);
+    assert_eq!(lines_in(&rotated[0]).len(), 1);
+    assert_eq!(lines_in(&dir.path().join(PROMPT_LOG_FILE)).len(), 2);
+}
+
+/// 每次写入都要等待的慢速磁盘
+#[derive(Clone, Default)]
+struct ThrottledWriter {
+    lines: Arc<AtomicUsize>,
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Write for ThrottledWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        std::thread::sleep(Duration::from_millis(20));
+        self.output.lock().unwrap().extend_from_slice(buf);
+        self.lines.fetch_add(1, Ordering::SeqCst);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_slow_disk_never_blocks_callers() {
+    let writer = ThrottledWriter::default();
+    let log = PromptLog::with_writer(writer.clone(), PromptLogLevel::RedactUserContent, 8);
+
+    let started = Instant::now();
+    let accepted = (0..200).filter(|_| log.record(exchange("hello"))).count();
+    // 200 次写入在慢速磁盘上需要 4 秒，记录本身立即返回
+    assert!(started.elapsed() < Duration::from_millis(200), "{:?}", started.elapsed());
+    assert!(accepted >= 8 && accepted < 200, "{}", accepted);
+    assert_eq!(log.dropped(), (200 - accepted) as u64);
+
+    log.flush();
+    assert_eq!(writer.lines.load(Ordering::SeqCst), accepted);
+    let output = String::from_utf8(writer.output.lock().unwrap().clone()).unwrap();
+    assert_eq!(output.lines().count(), accepted);
+}
+
+#[tokio::test]
+async fn test_llm_calls_are_logged_with_trace_id() {
+    async fn completions(Json(_): Json<Value>) -> Json<Value> {
+        Json(json!({
+            "id": "chatcmpl-mock",
+            "object": "chat.completion",
+            "created": 0,
+            "model": "mock",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Ship it on Friday" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 8, "completion_tokens": 4, "total_tokens": 12 }
+        }))
+    }
+    let app = Router::new().route("/chat/completions", post(completions));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let log = Arc::new(PromptLog::open(&PromptLogConfig::new(dir.path())).unwrap());
+    let client = OpenAIClient::new_with_base_url("k".into(), "mock-model".into(), format!("http://{}", addr))
+        .with_prompt_log(log.clone());
+
+    let reply = with_trace_id("trace-42", client.chat(vec![LlmMessage::user("When do we ship?")]))
+        .await
+        .unwrap();
+    assert_eq!(reply, "Ship it on Friday");
+    log.set_level(PromptLogLevel::MetadataOnly);
+    client.complete("untraced").await.unwrap();
+    log.flush();
+
+    let lines = lines_in(&dir.path().join(PROMPT_LOG_FILE));
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["trace_id"], "trace-42");
+    assert_eq!(lines[0]["model"], "mock-model");
+    assert_eq!(lines[0]["level"], "redact_user_content");
+    assert!(lines[0]["messages"][0]["content_hash"].is_string());
+    assert_eq!(lines[0]["response"]["chars"], 17);
+    assert_eq!(lines[0]["usage"]["total_tokens"], 12);
+    assert!(lines[1]["trace_id"].is_null());
+    assert_eq!(lines[1]["level"], "metadata_only");
+}
+
+#[tokio::test]
+async fn test_admin_endpoint_toggles_level() {
+    let dir = tempfile::tempdir().unwrap();
+    let log = Arc::new(PromptLog::open(&PromptLogConfig::new(dir.path())).unwrap());
+    let jwt = JwtService::new("test-secret");
+    let token = |position: &str| {
+        jwt.generate_token(&UserInfo {
+            id: position.to_lowercase(),
+            username: position.to_lowercase(),
+            name: position.to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: position.to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+    };
+    let (message_tx, _) = broadcast::channel(16);
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let state = AppState::new(Vec::new(), message_tx, store, jwt.clone()).with_prompt_log(log.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let url = format!("http://{}/api/v1/admin/prompt-log", addr);
+    let client = reqwest::Client::new();
+
+    // 管理层默认没有该权限
+    for position in ["Employee", "Management"] {
+        let response = client
+            .put(&url)
+            .bearer_auth(token(position))
+            .json(&json!({ "level": "full" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403, "{}", position);
+    }
+    assert_eq!(log.level(), PromptLogLevel::RedactUserContent);
+
+    let body: Value = client
+        .put(&url)
+        .bearer_auth(token("Chairman"))
+        .json(&json!({ "level": "metadata_only" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["level"], "metadata_only", "{}", body);
+    assert_eq!(log.level(), PromptLogLevel::MetadataOnly);
+
+    let body: Value = client.get(&url).bearer_auth(token("Chairman")).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["data"]["enabled"], true);
+    assert_eq!(body["data"]["dropped"], 0);
+
+    let response = client
+        .put(&url)
+        .bearer_auth(token("Chairman"))
+        .json(&json!({ "level": "everything" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_client_error());
+    assert_eq!(log.level(), PromptLogLevel::MetadataOnly);
+}
@@ -1,7 +1,10 @@
 //! SQLite 存储实现测试
 
 use imitatort::core::store::{MessageFilter, Store};
-use imitatort::domain::{Agent, Department, Group, LLMConfig, Message, Organization, Role};
+use imitatort::domain::{
+    Agent, Department, Group, LLMConfig, Message, MessageBookmark, MessagePin, MessageReaction, MessageTranslation,
+    Organization, ReactionCount, Role,
+};
 use imitatort::infrastructure::store::SqliteStore;
 
 fn create_test_organization() -> Organization {
@@ -41,6 +44,62 @@ async fn test_sqlite_store_organization() {
     assert_eq!(agent.role.responsibilities.len(), 2);
 }
 
+#[tokio::test]
+async fn test_sqlite_store_partial_org_queries() {
+    let store = SqliteStore::new_in_memory().unwrap();
+
+    // 生成 1000 个 Agent、10 个部门的组织
+    let mut org = Organization::new();
+    for d in 0..10 {
+        org.add_department(Department::top_level(format!("dept-{}", d), format!("部门{}", d)));
+    }
+    for i in 0..1000 {
+        org.add_agent(
+            Agent::new(
+                format!("agent-{}", i),
+                format!("Employee {}", i),
+                Role::simple("Engineer", "prompt"),
+                LLMConfig::openai("test-key"),
+            )
+            .with_department(format!("dept-{}", i % 10)),
+        );
+    }
+    store.save_organization(&org).await.unwrap();
+
+    let agent = store.load_agent("agent-42").await.unwrap().unwrap();
+    assert_eq!(agent.name, "Employee 42");
+    assert_eq!(agent.department_id.as_deref(), Some("dept-2"));
+    assert!(store.load_agent("missing").await.unwrap().is_none());
+
+    assert_eq!(store.load_department("dept-3").await.unwrap().unwrap().name, "部门3");
+    assert!(store.load_department("missing").await.unwrap().is_none());
+    let members = store.load_department_members("dept-3").await.unwrap();
+    assert_eq!(members.len(), 100);
+    assert!(members.iter().all(|a| a.department_id.as_deref() == Some("dept-3")));
+}
+
+#[tokio::test]
+async fn test_sqlite_store_reactions() {
+    let store = SqliteStore::new_in_memory().unwrap();
+
+    assert!(store.add_reaction(&MessageReaction::new("m1", "alice", "👍")).await.unwrap());
+    assert!(!store.add_reaction(&MessageReaction::new("m1", "alice", "👍")).await.unwrap());
+    assert!(store.add_reaction(&MessageReaction::new("m1", "bob", "👍")).await.unwrap());
+    assert!(store.add_reaction(&MessageReaction::new("m1", "bob", "❓")).await.unwrap());
+    assert!(store.add_reaction(&MessageReaction::new("m2", "carol", "✅")).await.unwrap());
+
+    let reactions = store.load_reactions(&["m1".to_string()]).await.unwrap();
+    let counts = ReactionCount::aggregate(&reactions);
+    let thumbs = counts["m1"].iter().find(|c| c.emoji == "👍").unwrap();
+    assert_eq!(thumbs.count, 2);
+    assert_eq!(counts["m1"].len(), 2);
+    assert!(store.load_reactions(&[]).await.unwrap().is_empty());
+
+    assert!(store.remove_reaction("m1", "bob", "❓").await.unwrap());
+    assert!(!store.remove_reaction("m1", "bob", "❓").await.unwrap());
+    assert_eq!(store.load_reactions(&["m1".to_string(), "m2".to_string()]).await.unwrap().len(), 3);
+}
+
 #[tokio::test]
 async fn test_sqlite_store_messages() {
     let store = SqliteStore::new_in_memory().unwrap();
@@ -110,6 +169,55 @@ async fn test_sqlite_store_batch_messages() {
     assert_eq!(loaded.len(), 50);
 }
 
+#[tokio::test]
+async fn test_sqlite_store_delete_and_purge_messages() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    let messages: Vec<Message> = (0..10)
+        .map(|i| Message { timestamp: 1_000 + i, ..Message::private("a1", "a2", format!("msg{}", i)) })
+        .collect();
+    store.save_messages(&messages).await.unwrap();
+    // 回应、置顶、书签和译文随消息一起删除
+    for message in [&messages[1], &messages[7], &messages[8]] {
+        store.add_reaction(&MessageReaction::new(&message.id, "alice", "👍")).await.unwrap();
+        store.add_pin(&MessagePin::new(&message.id, "s1", "alice")).await.unwrap();
+        store.add_bookmark(&MessageBookmark::new(&message.id, "alice")).await.unwrap();
+        let translation = MessageTranslation {
+            message_id: message.id.clone(),
+            language: "en".to_string(),
+            source_language: "cmn".to_string(),
+            text: message.content.clone(),
+            created_at: message.timestamp,
+        };
+        store.save_translation(&translation).await.unwrap();
+    }
+    // 归档中的消息同样会被删除
+    assert_eq!(store.archive_messages_before(1_002).await.unwrap(), 2);
+
+    assert_eq!(store.purge_messages_before(1_004).await.unwrap(), 4);
+    assert_eq!(store.purge_messages_before(1_004).await.unwrap(), 0);
+    assert!(store.delete_message(&messages[7].id).await.unwrap());
+    assert!(!store.delete_message(&messages[7].id).await.unwrap());
+    assert!(!store.delete_message(&messages[0].id).await.unwrap());
+
+    let loaded = store.load_messages(MessageFilter::new().oldest_first()).await.unwrap();
+    let contents: Vec<&str> = loaded.iter().map(|m| m.content.as_str()).collect();
+    assert_eq!(contents, vec!["msg4", "msg5", "msg6", "msg8", "msg9"]);
+    assert!(store.load_message(&messages[7].id).await.unwrap().is_none());
+    let ids: Vec<String> = messages.iter().map(|m| m.id.clone()).collect();
+    let kept = vec![messages[8].id.clone()];
+    let reacted: Vec<String> = store.load_reactions(&ids).await.unwrap().into_iter().map(|r| r.message_id).collect();
+    assert_eq!(reacted, kept);
+    let pinned: Vec<String> = store.load_pins("s1").await.unwrap().into_iter().map(|p| p.message_id).collect();
+    assert_eq!(pinned, kept);
+    let bookmarked: Vec<String> = store.load_bookmarks("alice").await.unwrap().into_iter().map(|b| b.message_id).collect();
+    assert_eq!(bookmarked, kept);
+    for (message, translated) in [(&messages[1], false), (&messages[7], false), (&messages[8], true)] {
+        assert_eq!(store.load_translation(&message.id, "en").await.unwrap().is_some(), translated);
+    }
+    let counts = store.message_tier_counts().await.unwrap();
+    assert_eq!((counts.hot, counts.archived), (5, 0));
+}
+
 #[tokio::test]
 async fn test_sqlite_store_load_messages_by_agent() {
     let store = SqliteStore::new_in_memory().unwrap();
@@ -129,3 +237,148 @@ async fn test_sqlite_store_load_messages_by_agent() {
     let a1_messages = store.load_messages_by_agent("a1", 10).await.unwrap();
     assert_eq!(a1_messages.len(), 3); // 发送2条 + 接收1条
 }
+
+#[tokio::test]
+async fn test_sqlite_store_message_metadata_and_escalation() {
+    let store = SqliteStore::new_in_memory().unwrap();
+
+    let message = Message::private("user", "dev", "Hello").with_metadata("no_escalation", "true");
+    store.save_message(&message).await.unwrap();
+    let loaded = store.load_messages(MessageFilter::new().to("dev")).await.unwrap();
+    assert_eq!(loaded[0].metadata("no_escalation"), Some("true"));
+
+    let escalation = imitatort::domain::Escalation {
+        message_id: message.id.clone(),
+        agent_id: "dev".to_string(),
+        escalated_to: "lead".to_string(),
+        escalated_at: 100,
+    };
+    store.save_escalation(&escalation).await.unwrap();
+    assert_eq!(store.load_escalation(&message.id).await.unwrap(), Some(escalation));
+    assert!(store.load_escalation("missing").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_sqlite_store_app_state_survives_reopen() {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct GameState {
+        day: u32,
+        phase: String,
+        alive: Vec<String>,
+        dead: Vec<String>,
+    }
+
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("app_state.db");
+    let state = GameState {
+        day: 2,
+        phase: "night".to_string(),
+        alive: vec!["alice".to_string(), "carol".to_string()],
+        dead: vec!["bob".to_string()],
+    };
+
+    {
+        let store = SqliteStore::new(&db_path).unwrap();
+        assert!(store.load_app_state("game").await.unwrap().is_none());
+        store.save_app_state("game", &serde_json::to_value(&state).unwrap()).await.unwrap();
+    }
+
+    // 重新打开后恢复到夜晚阶段
+    let store = SqliteStore::new(&db_path).unwrap();
+    let value = store.load_app_state("game").await.unwrap().unwrap();
+    let resumed: GameState = serde_json::from_value(value).unwrap();
+    assert_eq!(resumed, state);
+
+    store.delete_app_state("game").await.unwrap();
+    assert!(store.load_app_state("game").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_sqlite_store_role_templates() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    let mut org = Organization::new();
+    org.add_agent(Agent::new(
+        "pm",
+        "PM",
+        Role::simple("PM", "prompt").with_template("standup", "Standup: {{summary}}"),
+        LLMConfig::openai("key"),
+    ));
+    store.save_organization(&org).await.unwrap();
+
+    let loaded = store.load_organization().await.unwrap();
+    let agent = loaded.find_agent("pm").unwrap();
+    assert_eq!(agent.role.templates.get("standup").map(String::as_str), Some("Standup: {{summary}}"));
+}
+
+#[tokio::test]
+async fn test_sqlite_store_shared_memory_uri() {
+    let uri = "file:shared_uri_test?mode=memory&cache=shared";
+    let writer = SqliteStore::new(uri).unwrap();
+    let reader = SqliteStore::new(uri).unwrap();
+
+    writer.save_message(&Message::private("a1", "a2", "visible?")).await.unwrap();
+    let messages = reader.load_messages(MessageFilter::new()).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].content, "visible?");
+
+    reader.save_message(&Message::private("a2", "a1", "yes")).await.unwrap();
+    assert_eq!(writer.load_messages(MessageFilter::new()).await.unwrap().len(), 2);
+
+    let info = reader.backend_info();
+    assert_eq!(info.backend, "sqlite");
+    assert_eq!(info.path.as_deref(), Some(uri));
+    assert!(info.in_memory);
+
+    // 不同名字的共享内存库互不可见
+    let other = SqliteStore::new("file:other_uri_test?mode=memory&cache=shared").unwrap();
+    assert!(other.load_messages(MessageFilter::new()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_sqlite_store_backend_info_reports_file() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("info.db");
+    let store = SqliteStore::new(&db_path).unwrap();
+
+    let info = store.backend_info();
+    assert!(!info.in_memory);
+    assert_eq!(store.path(), Some(db_path.to_string_lossy().into_owned()));
+    assert_eq!(info.label(), format!("sqlite:{}", db_path.display()));
+    assert!(SqliteStore::new_in_memory().unwrap().backend_info().in_memory);
+}
+
+#[tokio::test]
+async fn test_sqlite_store_loads_legacy_duplicate_agent() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("legacy.db");
+    {
+        let store = SqliteStore::new(&db_path).unwrap();
+        store.save_organization(&create_test_organization()).await.unwrap();
+    }
+    // 模拟没有主键约束时留下的重复行
+    {
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "ALTER TABLE agents RENAME TO agents_old;
+             CREATE TABLE agents AS SELECT * FROM agents_old;
+             DROP TABLE agents_old;
+             INSERT INTO agents SELECT * FROM agents WHERE id = 'ceo';
+             UPDATE agents SET name = 'CEO v2' WHERE rowid = (SELECT MAX(rowid) FROM agents);",
+        )
+        .unwrap();
+    }
+
+    let store = SqliteStore::new(&db_path).unwrap();
+    let org = store.load_organization().await.unwrap();
+    assert_eq!(org.agents.len(), 1);
+    assert_eq!(org.find_agent("ceo").unwrap().name, "CEO v2");
+
+    // 带重复 ID 的组织架构保存时以最后一条为准，不再因主键冲突失败
+    let mut dup = org.clone();
+    dup.add_agent(Agent::new("ceo", "CEO v3", Role::simple("CEO", "prompt"), LLMConfig::openai("k")));
+    let fresh = SqliteStore::new_in_memory().unwrap();
+    fresh.save_organization(&dup).await.unwrap();
+    let saved = fresh.load_organization().await.unwrap();
+    assert_eq!(saved.agents.len(), 1);
+    assert_eq!(saved.agents[0].name, "CEO v3");
+}
@@ -0,0 +1,133 @@
+//! 工具预演测试：消息类工具报告收件人和内容但不经过消息总线，不支持预演的执行器明确拒绝而不是执行
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::tool::ToolCallContext;
+use imitatort::domain::Organization;
+use imitatort::infrastructure::tool::{
+    DryRunReport, FnToolExecutor, FrameworkToolExecutor, ToolEnvironment, ToolExecutor, ToolExecutorRegistry,
+};
+
+fn framework_executor(message_bus: Arc<MessageBus>, store: Arc<dyn Store>) -> FrameworkToolExecutor {
+    FrameworkToolExecutor::new(ToolEnvironment::new(
+        message_bus,
+        Arc::new(RwLock::new(Organization::new())),
+        Arc::new(ToolRegistry::new()),
+        store,
+    ))
+}
+
+#[tokio::test]
+async fn test_dry_run_group_message_never_hits_bus() {
+    let store = Arc::new(MemoryStore::new());
+    let message_bus = Arc::new(MessageBus::with_store(store.clone()));
+    message_bus.register("alice");
+    let mut bob = message_bus.register("bob");
+    message_bus
+        .create_group("g1", "Launch", "alice", vec!["alice".into(), "bob".into(), "carol".into()])
+        .await
+        .unwrap();
+    let mut group = message_bus.subscribe_group("g1").unwrap();
+
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(Arc::new(ToolRegistry::new()));
+    registry.register(Box::new(framework_executor(message_bus.clone(), store.clone())));
+    let context = ToolCallContext::new("alice");
+
+    let params = json!({
+        "group_id": "g1",
+        "content": "Ship it",
+        "priority": "high",
+        "mention_agent_ids": ["bob"],
+        "dry_run": true,
+    });
+    let result = registry.execute("message.send_group", params, &context).await.unwrap();
+    assert!(result.success, "{:?}", result.error);
+    assert_eq!(result.metadata["dry_run"], true);
+
+    let report: DryRunReport = serde_json::from_value(result.data).unwrap();
+    assert_eq!(report.tool_id, "message.send_group");
+    assert_eq!(report.effects["target"], "group:g1");
+    assert_eq!(report.effects["recipients"], json!(["bob", "carol"]));
+    assert_eq!(report.effects["content"], "Ship it");
+    assert_eq!(report.effects["priority"], "high");
+    assert_eq!(report.effects["mentions"], json!(["bob"]));
+
+    assert!(group.try_recv().is_err());
+    assert!(bob.try_recv().is_err());
+    assert!(store.load_messages_by_group("g1", 10).await.unwrap().is_empty());
+    assert_eq!(registry.stats().snapshot().len(), 0);
+
+    // 同一调用去掉 dry_run 后才真正发送
+    let result = registry
+        .execute("message.send_direct", json!({"to_agent_id": "bob", "content": "hi", "dry_run": false}), &context)
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert!(!result.metadata.contains_key("dry_run"));
+    assert_eq!(bob.try_recv().unwrap().content, "hi");
+}
+
+#[tokio::test]
+async fn test_dry_run_unsupported_executor_does_not_execute() {
+    let called = Arc::new(AtomicBool::new(false));
+    let flag = called.clone();
+    let executor = FnToolExecutor::new("deploy.production", move |params| {
+        let flag = flag.clone();
+        async move {
+            flag.store(true, Ordering::SeqCst);
+            Ok(params)
+        }
+    });
+    let mut registry = ToolExecutorRegistry::with_default_skill_manager(Arc::new(ToolRegistry::new()));
+    registry.register(Box::new(executor));
+    let context = ToolCallContext::new("alice");
+
+    let result = registry.execute_dry_run("deploy.production", json!({"env": "prod"}), &context).await.unwrap();
+    assert!(!result.success);
+    assert!(result.error.unwrap().contains("deploy.production"));
+
+    let result = registry
+        .execute("deploy.production", json!({"env": "prod", "dry_run": true}), &context)
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert!(!called.load(Ordering::SeqCst));
+
+    // 真正执行时工具看不到 dry_run 字段
+    let result = registry
+        .execute("deploy.production", json!({"env": "prod", "dry_run": false}), &context)
+        .await
+        .unwrap();
+    assert!(called.load(Ordering::SeqCst));
+    assert_eq!(result.data, json!({"env": "prod"}));
+}
+
+#[tokio::test]
+async fn test_dry_run_preferences_reports_diff_without_saving() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    store.save_agent_preferences("alice", &json!({"tone": "formal"})).await.unwrap();
+    let executor = framework_executor(Arc::new(MessageBus::new()), store.clone());
+    let context = ToolCallContext::new("alice");
+
+    let report = ToolExecutor::dry_run(
+        &executor,
+        "self.update_preferences",
+        json!({"preferences": {"tone": "casual"}}),
+        &context,
+    )
+    .await
+    .unwrap();
+    assert_eq!(report.effects["before"]["tone"], "formal");
+    assert_eq!(report.effects["after"]["tone"], "casual");
+    assert_eq!(store.load_agent_preferences("alice").await.unwrap().unwrap()["tone"], "formal");
+
+    // 读取类工具没有可预演的效果，明确拒绝
+    assert!(ToolExecutor::dry_run(&executor, "time.now", json!({}), &context).await.is_err());
+}
@@ -18,6 +18,8 @@ async fn test_message_storage() {
         timestamp: 1234567890,
         reply_to: None,
         mentions: vec![],
+        metadata: Default::default(),
+        priority: Default::default(),
     };
 
     // 保存消息
@@ -45,6 +47,8 @@ async fn test_message_storage_with_filters() {
         timestamp: 1234567890,
         reply_to: None,
         mentions: vec![],
+        metadata: Default::default(),
+        priority: Default::default(),
     };
 
     let msg2 = Message {
@@ -55,6 +59,8 @@ async fn test_message_storage_with_filters() {
         timestamp: 1234567891,
         reply_to: None,
         mentions: vec![],
+        metadata: Default::default(),
+        priority: Default::default(),
     };
 
     // 保存消息
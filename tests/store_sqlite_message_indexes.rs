@@ -0,0 +1,180 @@
+//! SQLite 消息查询测试：按 Agent 单条查询与旧的两次查询合并结果一致、默认数量限制、复合索引的使用（需 `--features query-plan`）和大数据量下的延迟
+
+use std::time::{Duration, Instant};
+
+use imitatort::core::store::{MemoryStore, MessageFilter, Store, DEFAULT_MESSAGE_LIMIT};
+use imitatort::domain::Message;
+use imitatort::infrastructure::store::SqliteStore;
+
+const NOW: i64 = 1_700_000_000;
+const AGENTS: [&str; 6] = ["ceo", "cto", "dev", "qa", "pm", "ops"];
+
+/// 每条消息时间戳不同；私聊、群聊和发给自己的私聊混合
+fn seed(count: usize) -> Vec<Message> {
+    (0..count)
+        .map(|i| {
+            let from = AGENTS[i % AGENTS.len()];
+            let mut message = match i % 7 {
+                0 => Message::private(from, from, format!("note {}", i)),
+                1 | 2 | 3 => Message::private(from, AGENTS[(i / 7) % AGENTS.len()], format!("dm {}", i)),
+                _ => Message::group(from, ["eng", "ops", "all"][i % 3], format!("group {}", i)),
+            };
+            message.timestamp = NOW - (count - i) as i64;
+            message
+        })
+        .collect()
+}
+
+/// 旧的实现：分别查询发出的消息和收到的私聊，合并去重后按时间排序
+async fn merged_by_agent(store: &dyn Store, agent_id: &str, limit: usize) -> Vec<Message> {
+    let limit = MessageFilter::new().limit(limit).effective_limit();
+    let mut all = store.load_messages(MessageFilter::new().from(agent_id).limit(limit)).await.unwrap();
+    let to = store
+        .load_messages(MessageFilter::new().to(agent_id).target_type("direct").limit(limit))
+        .await
+        .unwrap();
+    for message in to {
+        if !all.iter().any(|m| m.id == message.id) {
+            all.push(message);
+        }
+    }
+    all.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+    all.truncate(limit);
+    all
+}
+
+fn ids(messages: &[Message]) -> Vec<String> {
+    messages.iter().map(|m| m.id.clone()).collect()
+}
+
+#[tokio::test]
+async fn test_messages_by_agent_matches_merged_queries() {
+    let seeded = seed(3_000);
+    let sqlite = SqliteStore::new_in_memory().unwrap();
+    let memory = MemoryStore::new();
+    sqlite.save_messages(&seeded).await.unwrap();
+    memory.save_messages(&seeded).await.unwrap();
+
+    for archived in [false, true] {
+        if archived {
+            // 一部分消息进入归档表后结果不变
+            assert!(sqlite.archive_messages_before(NOW - 1_000).await.unwrap() > 0);
+        }
+        for agent in AGENTS.iter().chain(["eng", "nobody"].iter()) {
+            for limit in [0, 1, 7, 100, 10_000] {
+                let single = sqlite.load_messages_by_agent(agent, limit).await.unwrap();
+                let merged = merged_by_agent(&sqlite, agent, limit).await;
+                assert_eq!(ids(&single), ids(&merged), "agent {} limit {} archived {}", agent, limit, archived);
+                assert_eq!(
+                    ids(&single),
+                    ids(&memory.load_messages_by_agent(agent, limit).await.unwrap()),
+                    "agent {} limit {} archived {}",
+                    agent,
+                    limit,
+                    archived
+                );
+            }
+        }
+    }
+
+    // 发给自己的私聊只出现一次
+    let own = sqlite.load_messages_by_agent("ceo", 10_000).await.unwrap();
+    let mut unique = ids(&own);
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), own.len());
+}
+
+#[tokio::test]
+async fn test_zero_limit_uses_default() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    let memory = MemoryStore::new();
+    let seeded = seed(DEFAULT_MESSAGE_LIMIT * 10);
+    store.save_messages(&seeded).await.unwrap();
+    memory.save_messages(&seeded).await.unwrap();
+
+    let unbounded = MessageFilter { limit: 0, ..Default::default() };
+    assert_eq!(store.load_messages(unbounded.clone()).await.unwrap().len(), DEFAULT_MESSAGE_LIMIT);
+    assert_eq!(memory.load_messages(unbounded).await.unwrap().len(), DEFAULT_MESSAGE_LIMIT);
+    assert_eq!(store.load_messages_by_agent("ceo", 0).await.unwrap().len(), DEFAULT_MESSAGE_LIMIT);
+    assert_eq!(store.load_messages(MessageFilter::new().limit(5)).await.unwrap().len(), 5);
+}
+
+#[cfg(feature = "query-plan")]
+#[tokio::test]
+async fn test_message_queries_use_composite_indexes() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    store.save_messages(&seed(500)).await.unwrap();
+
+    let plan = store
+        .explain_messages(MessageFilter::new().from("ceo").target_type("direct").since(NOW - 100))
+        .await
+        .unwrap();
+    assert!(plan.iter().any(|step| step.contains("idx_messages_from_time")), "{:?}", plan);
+
+    let plan = store
+        .explain_messages(MessageFilter::new().to("eng").target_type("group").since(NOW - 100))
+        .await
+        .unwrap();
+    assert!(plan.iter().any(|step| step.contains("idx_messages_target_time")), "{:?}", plan);
+
+    // 每个分支都走索引，没有全表扫描
+    store.archive_messages_before(NOW - 100).await.unwrap();
+    let plan = store.explain_messages_by_agent("ceo", 50).await.unwrap();
+    for index in ["idx_messages_from_time", "idx_messages_target_time", "idx_messages_archive_from_time", "idx_messages_archive_target_time"] {
+        assert!(plan.iter().any(|step| step.contains(index)), "{} missing from {:?}", index, plan);
+    }
+    assert!(!plan.iter().any(|step| step.starts_with("SCAN messages")), "{:?}", plan);
+}
+
+async fn timed<F, T>(runs: u32, mut f: impl FnMut() -> F) -> Duration
+where
+    F: std::future::Future<Output = T>,
+{
+    let start = Instant::now();
+    for _ in 0..runs {
+        f().await;
+    }
+    start.elapsed() / runs
+}
+
+/// 20 万条消息下比较旧的合并查询与单条查询，以及复合索引前后的组合过滤查询
+/// （耗时较长：`cargo test --release --test store_sqlite_message_indexes -- --ignored --nocapture`）
+#[tokio::test]
+#[ignore]
+async fn test_message_query_latency_200k() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("messages.db");
+    let store = SqliteStore::new(&path).unwrap();
+    for chunk in seed(200_000).chunks(10_000) {
+        store.save_messages(chunk).await.unwrap();
+    }
+
+    let merged = timed(20, || merged_by_agent(&store, "dev", 50)).await;
+    let single = timed(20, || async { store.load_messages_by_agent("dev", 50).await.unwrap() }).await;
+    assert_eq!(
+        ids(&store.load_messages_by_agent("dev", 50).await.unwrap()),
+        ids(&merged_by_agent(&store, "dev", 50).await)
+    );
+
+    let combined = || store.load_messages(MessageFilter::new().from("dev").target_type("direct").since(NOW - 150_000).limit(50));
+    let with_composite = timed(20, || async { combined().await.unwrap() }).await;
+
+    // 换回旧的单列索引
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "DROP INDEX idx_messages_from_time;
+             DROP INDEX idx_messages_target_time;
+             CREATE INDEX idx_messages_from ON messages(from_agent);
+             CREATE INDEX idx_messages_target ON messages(target_type, target_id);",
+        )
+        .unwrap();
+    }
+    let with_single_column = timed(20, || async { combined().await.unwrap() }).await;
+
+    println!("by agent: merged {:?}, single query {:?}", merged, single);
+    println!("from + target_type + since: single-column {:?}, composite {:?}", with_single_column, with_composite);
+    assert!(single < merged, "single query {:?} merged {:?}", single, merged);
+    assert!(with_composite < with_single_column, "composite {:?} single-column {:?}", with_composite, with_single_column);
+}
@@ -0,0 +1,154 @@
+//! 场景测试工具：示例场景（委派、群聊讨论、Watchdog 触发）通过，期望匹配规则，
+//! 以及失败报告中的差异
+
+use std::time::Duration;
+
+use imitatort::domain::AgentMode;
+use imitatort::testkit::{json_subset, parse_duration, text_matches, CheckKind, Scenario, ScenarioRunner};
+use serde_json::json;
+
+async fn run_example(name: &str) {
+    let path = format!("{}/tests/fixtures/scenarios/{}.yaml", env!("CARGO_MANIFEST_DIR"), name);
+    let report = ScenarioRunner::from_file(path).unwrap().run().await.unwrap();
+    println!("{}", report);
+    report.assert_passed();
+}
+
+#[tokio::test]
+async fn test_delegation_scenario() {
+    run_example("delegation").await;
+}
+
+#[tokio::test]
+async fn test_group_discussion_scenario() {
+    run_example("group_discussion").await;
+}
+
+#[tokio::test]
+async fn test_watchdog_trigger_scenario() {
+    run_example("watchdog_trigger").await;
+}
+
+#[test]
+fn test_text_matches_glob_and_substring() {
+    assert!(text_matches("login form", "Please build the login form today"));
+    assert!(text_matches("Design*", "Design assets are ready"));
+    assert!(!text_matches("Design*", "The Design assets"));
+    assert!(text_matches("user:?lice", "user:alice"));
+    assert!(!text_matches("shipped", "in progress"));
+}
+
+#[test]
+fn test_json_subset_reports_first_difference() {
+    let actual = json!({"title": "Build the login form", "assignee": "dev", "tags": ["ui", "auth"]});
+
+    assert!(json_subset(&json!({"assignee": "dev"}), &actual).is_ok());
+    assert!(json_subset(&json!({"title": "*login form", "tags": ["ui", "auth"]}), &actual).is_ok());
+
+    let diff = json_subset(&json!({"assignee": "pm"}), &actual).unwrap_err();
+    assert_eq!(diff, r#"$.assignee: expected "pm", got "dev""#);
+    let diff = json_subset(&json!({"priority": 1}), &actual).unwrap_err();
+    assert_eq!(diff, "$.priority: expected 1, missing");
+    let diff = json_subset(&json!({"tags": ["ui", "db"]}), &actual).unwrap_err();
+    assert_eq!(diff, r#"$.tags[1]: expected "db", got "auth""#);
+}
+
+#[test]
+fn test_parse_duration_units() {
+    assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+    assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+    assert_eq!(parse_duration("2h").unwrap(), Duration::from_secs(7200));
+    assert_eq!(parse_duration("1d").unwrap(), Duration::from_secs(86_400));
+    assert!(parse_duration("5 weeks").is_err());
+    assert!(parse_duration("soon").is_err());
+}
+
+#[test]
+fn test_scenario_defaults_and_validation() {
+    let scenario = Scenario::from_yaml(
+        r#"
+name: minimal
+company:
+  name: Acme
+  organization:
+    agents:
+      - { id: solo, name: Sam, role: { title: Engineer } }
+llm:
+  solo:
+    - send: { to: "user:alice", content: hi }
+"#,
+    )
+    .unwrap();
+    let agent = &scenario.company.organization.agents[0];
+    assert!(scenario.company.organization.departments.is_empty());
+    assert_eq!(agent.llm_config.model, "gpt-4o-mini");
+    assert!(matches!(agent.mode, AgentMode::Passive));
+    assert!(agent.role.responsibilities.is_empty());
+    assert_eq!(scenario.timeout(), Duration::from_secs(15));
+
+    let err = Scenario::from_yaml(
+        r#"
+name: typo
+company:
+  name: Acme
+  organization:
+    agents:
+      - { id: solo, name: Sam, role: { title: Engineer } }
+llm:
+  sollo:
+    - send: { to: "user:alice", content: hi }
+"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("unknown agent sollo"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_failure_report_shows_expected_and_actual() {
+    let scenario = Scenario::from_yaml(
+        r#"
+name: failing
+timeout_ms: 3000
+company:
+  name: Acme
+  organization:
+    agents:
+      - { id: pm, name: Pat, role: { title: Product Manager } }
+      - { id: dev, name: Dana, role: { title: Engineer } }
+llm:
+  dev:
+    - when: "status"
+      send: { to: pm, content: "Still working on it" }
+    - when: "never sent"
+      send: { to: pm, content: "Done" }
+events:
+  - user_message: { from: "user:alice", to: dev, content: "Any status update?" }
+  - webhook:
+      path: /api/v1/tasks
+      as_user: alice
+      body: { title: "Write release notes", assignee: pm }
+      expect_status: 200
+expect:
+  - message: { from: dev, to: pm, content: "finished" }
+"#,
+    )
+    .unwrap();
+
+    let report = ScenarioRunner::new(scenario).run().await.unwrap();
+    println!("{}", report);
+    assert!(!report.passed);
+
+    let failures: Vec<_> = report.failures().collect();
+    let webhook = failures.iter().find(|c| c.kind == CheckKind::Event).unwrap();
+    assert!(webhook.diff.as_deref().unwrap().contains("expected: status 200"));
+    let message = failures.iter().find(|c| c.kind == CheckKind::Expectation).unwrap();
+    let diff = message.diff.as_deref().unwrap();
+    assert!(diff.contains("expected:") && diff.contains("actual:"), "{}", diff);
+    assert!(diff.contains("Still working on it"), "{}", diff);
+    let script = failures.iter().find(|c| c.kind == CheckKind::Script).unwrap();
+    assert!(script.label.contains("dev"), "{}", script.label);
+
+    let text = report.to_string();
+    assert!(text.contains("FAILED") && text.contains("FAIL "), "{}", text);
+}
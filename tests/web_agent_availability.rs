@@ -0,0 +1,198 @@
+//! Agent 工作时间接口测试：消息排队或 503、紧急消息打断、在线状态和 PUT /agents/{id}/availability
+
+use std::sync::Arc;
+
+use chrono::{NaiveTime, TimeZone, Utc, Weekday};
+use imitatort::core::clock::ManualClock;
+use imitatort::core::store::Store;
+use imitatort::domain::Availability;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Organization, Role};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+    Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap().timestamp()
+}
+
+fn office_hours() -> Availability {
+    let weekdays = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+    Availability::new("Europe/Berlin").with_window(
+        &weekdays,
+        NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+    )
+}
+
+struct TestServer {
+    base: String,
+    store: Arc<SqliteStore>,
+    admin: String,
+    employee: String,
+}
+
+/// 时钟停在周五 18:00（柏林），dev 下班、ops 未设置工作时间
+async fn spawn_server() -> TestServer {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_agent(
+        Agent::new("dev", "Dev", Role::simple("Engineer", "You code"), LLMConfig::openai("k"))
+            .with_availability(office_hours().with_urgent_override(true)),
+    );
+    org.add_agent(Agent::new("ops", "Ops", Role::simple("Operator", "You operate"), LLMConfig::openai("k")));
+    store.save_organization(&org).await.unwrap();
+
+    let jwt_service = JwtService::new("test-secret");
+    let token = |position: &str| {
+        jwt_service
+            .generate_token(&UserInfo {
+                id: "u1".to_string(),
+                username: "u1".to_string(),
+                name: "U1".to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: position.to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    };
+    let (admin, employee) = (token("Management"), token("Employee"));
+
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(org.agents.clone(), message_tx, store.clone(), jwt_service.clone())
+        .with_clock(Arc::new(ManualClock::new(utc(2026, 3, 27, 17, 0))));
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    TestServer { base: format!("http://{}", addr), store, admin, employee }
+}
+
+#[tokio::test]
+async fn test_messages_to_away_agent_are_queued_or_rejected() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let monday_morning = utc(2026, 3, 30, 7, 0);
+
+    // 默认排队，并告知下一次工作时间（跨越夏令时切换）
+    let response = client
+        .post(format!("{}/api/messages", server.base))
+        .json(&json!({ "from": "user-alice", "to": "dev", "content": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "queued");
+    assert_eq!(body["next_available_at"], monday_morning);
+
+    // 请求方要求立即处理时返回 503
+    let response = client
+        .post(format!("{}/api/messages", server.base))
+        .json(&json!({ "from": "user-alice", "to": "dev", "content": "hi", "reject_if_unavailable": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 503);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["next_available_at"], monday_morning);
+    assert!(body["error"].as_str().unwrap().contains("Mon 09:00 (Europe/Berlin)"), "{}", body);
+
+    let response = client
+        .post(format!("{}/api/v1/messages", server.base))
+        .json(&json!({ "from": "user-alice", "to": "dev", "content": "hi", "reject_if_unavailable": true }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 503);
+
+    // 允许打断时紧急消息照常送达；没有工作时间的 Agent 不受影响
+    for (to, priority) in [("dev", "urgent"), ("ops", "normal")] {
+        let response = client
+            .post(format!("{}/api/messages", server.base))
+            .json(&json!({ "from": "user-alice", "to": to, "content": "hi", "priority": priority, "reject_if_unavailable": true }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["status"], "sent");
+        assert!(body.get("next_available_at").is_none());
+    }
+}
+
+#[tokio::test]
+async fn test_presence_shows_away() {
+    let server = spawn_server().await;
+    let body: Value = reqwest::get(format!("{}/api/chat/list", server.base)).await.unwrap().json().await.unwrap();
+    let status = |id: &str| {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["id"] == id)
+            .map(|s| s["participants"][0]["status"].clone())
+            .unwrap()
+    };
+    assert_eq!(status("dev"), "away");
+    assert_eq!(status("ops"), "online");
+}
+
+#[tokio::test]
+async fn test_update_availability() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/v1/agents/ops/availability", server.base);
+    let schedule = json!({
+        "timezone": "Europe/Berlin",
+        "weekly": [{ "days": ["sat"], "start": "10:00", "end": "14:00" }]
+    });
+
+    let response = client.put(&url).bearer_auth(&server.employee).json(&schedule).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let invalid = json!({ "timezone": "Nowhere/City", "weekly": [] });
+    let response = client.put(&url).bearer_auth(&server.admin).json(&invalid).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .put(format!("{}/api/v1/agents/ghost/availability", server.base))
+        .bearer_auth(&server.admin)
+        .json(&schedule)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = client.put(&url).bearer_auth(&server.admin).json(&schedule).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["available_now"], false);
+    assert_eq!(body["data"]["next_available_at"], utc(2026, 3, 28, 9, 0));
+    let stored = server.store.load_agent("ops").await.unwrap().unwrap().availability.unwrap();
+    assert_eq!(stored.weekly[0].days, vec![Weekday::Sat]);
+
+    // 修改记入组织架构变更
+    let changes = server.store.load_org_changes(0, 10).await.unwrap();
+    assert_eq!(changes[0].actor, "u1");
+    assert_eq!(changes[0].diff.agents[0].id, "ops");
+
+    // 新的工作时间立即用于消息
+    let response = client
+        .post(format!("{}/api/messages", server.base))
+        .json(&json!({ "from": "user-alice", "to": "ops", "content": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "queued");
+
+    let response = client.put(&url).bearer_auth(&server.admin).json(&Value::Null).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(server.store.load_agent("ops").await.unwrap().unwrap().availability.is_none());
+}
@@ -0,0 +1,288 @@
+//! 创建 Agent 接口测试：POST /agents 保存到组织架构、经运行器注册到消息总线、LLM 配置回退到默认值，
+//! 部门不存在、ID 重复、明文 API Key 和权限不足时的错误，`Idempotency-Key` 重试不重复创建，
+//! 以及并发创建时组织架构的修改不会丢失
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::Store;
+use imitatort::core::temp_agents::TempAgentRunner;
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::AppState;
+use imitatort::{Agent, Department, LLMConfig, Message, Organization};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, mpsc};
+
+use common::{agent, serve, token};
+
+/// 只注册到消息总线、收下收件箱的运行器
+struct InboxRunner {
+    bus: Arc<MessageBus>,
+    inboxes: Mutex<Vec<(String, mpsc::Receiver<Message>)>>,
+}
+
+#[async_trait]
+impl TempAgentRunner for InboxRunner {
+    async fn start(&self, agent: &Agent) -> Result<()> {
+        let inbox = self.bus.register(&agent.id);
+        self.inboxes.lock().unwrap().push((agent.id.clone(), inbox));
+        Ok(())
+    }
+
+    async fn stop(&self, agent_id: &str) {
+        self.inboxes.lock().unwrap().retain(|(id, _)| id != agent_id);
+        self.bus.unregister(agent_id);
+    }
+}
+
+struct TestServer {
+    base: String,
+    store: Arc<SqliteStore>,
+    bus: Arc<MessageBus>,
+    runner: Arc<InboxRunner>,
+    admin: String,
+    employee: String,
+}
+
+/// 默认 LLM 配置中的 API Key 引用
+const DEFAULT_KEY: &str = "env:IMITATORT_DEFAULT_LLM_KEY";
+
+async fn spawn_server() -> TestServer {
+    spawn_server_with_default_key(DEFAULT_KEY).await
+}
+
+async fn spawn_server_with_default_key(api_key: &str) -> TestServer {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    org.add_agent(agent("lead", "eng"));
+    store.save_organization(&org).await.unwrap();
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _lead = bus.register("lead");
+    let runner = Arc::new(InboxRunner { bus: bus.clone(), inboxes: Mutex::new(Vec::new()) });
+
+    let jwt_service = JwtService::new("test-secret");
+    let (admin, employee) = (token(&jwt_service, "u1", "Management"), token(&jwt_service, "u1", "Employee"));
+
+    let (message_tx, _) = broadcast::channel(16);
+    let default_llm = LLMConfig {
+        model: "default-model".to_string(),
+        api_key: api_key.to_string(),
+        base_url: "https://llm.example.com/v1".to_string(),
+        tokenizer: None,
+    };
+    let state = AppState::new(org.agents.clone(), message_tx, store.clone(), jwt_service.clone())
+        .with_message_bus(bus.clone())
+        .with_agent_runner(runner.clone())
+        .with_default_llm(default_llm);
+    let addr = serve(state).await;
+    TestServer { base: format!("http://{}", addr), store, bus, runner, admin, employee }
+}
+
+#[tokio::test]
+async fn test_create_agent_persists_and_registers() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/agents", server.base);
+    let request = json!({
+        "name": "Dana",
+        "role": "Designer",
+        "system_prompt": "You design screens",
+        "department_id": "eng",
+        "llm_config": { "model": "custom-model" },
+    });
+
+    assert_eq!(client.post(&url).json(&request).send().await.unwrap().status(), 403);
+    assert_eq!(client.post(&url).bearer_auth(&server.employee).json(&request).send().await.unwrap().status(), 403);
+
+    let response = client.post(&url).bearer_auth(&server.admin).json(&request).send().await.unwrap();
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    let id = body["data"]["id"].as_str().unwrap().to_string();
+    assert!(id.starts_with("agent_"), "{}", id);
+    assert_eq!(body["data"]["role"]["title"], "Designer");
+    // 未给出的字段取默认配置，API Key 引用原样保留
+    assert_eq!(body["data"]["llm_config"]["model"], "custom-model");
+    assert_eq!(body["data"]["llm_config"]["base_url"], "https://llm.example.com/v1");
+    assert_eq!(body["data"]["llm_config"]["api_key"], DEFAULT_KEY);
+
+    let org = server.store.load_organization().await.unwrap();
+    let stored = org.find_agent(&id).unwrap();
+    assert_eq!(stored.department_id.as_deref(), Some("eng"));
+    assert_eq!(stored.llm_config.api_key, DEFAULT_KEY);
+    assert!(server.bus.is_registered(&id));
+    server.bus.send(Message::private("lead", &id, "Welcome aboard")).await.unwrap();
+    let received = {
+        let mut inboxes = server.runner.inboxes.lock().unwrap();
+        let (started, inbox) = &mut inboxes[0];
+        assert_eq!(*started, id);
+        inbox.try_recv().unwrap()
+    };
+    assert_eq!(received.content, "Welcome aboard");
+
+    let agents: Vec<Value> = reqwest::get(&url).await.unwrap().json().await.unwrap();
+    assert!(agents.iter().any(|a| a["id"] == id.as_str()));
+    assert_eq!(reqwest::get(format!("{}/{}", url, id)).await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn test_create_agent_rejects_unknown_department_and_duplicates() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/agents", server.base);
+    let create = |id: &str, department_id: &str| {
+        client.post(&url).bearer_auth(&server.admin).json(&json!({
+            "id": id,
+            "name": id,
+            "role": "Engineer",
+            "system_prompt": "You code",
+            "department_id": department_id,
+        }))
+    };
+
+    assert_eq!(create("dev", "sales").send().await.unwrap().status(), 404);
+    assert_eq!(create("dev", "eng").send().await.unwrap().status(), 201);
+    let response = create("dev", "eng").send().await.unwrap();
+    assert_eq!(response.status(), 409);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "Agent dev already exists");
+    assert_eq!(create("lead", "eng").send().await.unwrap().status(), 409);
+
+    let org = server.store.load_organization().await.unwrap();
+    assert_eq!(org.agents.iter().filter(|a| a.id == "dev").count(), 1);
+}
+
+#[tokio::test]
+async fn test_create_agent_rejects_plaintext_api_keys() {
+    let request = |api_key: Option<&str>| {
+        let mut request = json!({
+            "id": "dana",
+            "name": "Dana",
+            "role": "Designer",
+            "system_prompt": "You design screens",
+            "department_id": "eng",
+        });
+        if let Some(api_key) = api_key {
+            request["llm_config"] = json!({ "api_key": api_key });
+        }
+        request
+    };
+
+    // 请求中给出的明文
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/agents", server.base);
+    let response = client.post(&url).bearer_auth(&server.admin).json(&request(Some("sk-plain"))).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("dana"), "{}", body);
+    assert!(server.store.load_organization().await.unwrap().find_agent("dana").is_none());
+    let response = client.post(&url).bearer_auth(&server.admin).json(&request(Some("env:DANA_KEY"))).send().await.unwrap();
+    assert_eq!(response.status(), 201);
+    let org = server.store.load_organization().await.unwrap();
+    assert_eq!(org.find_agent("dana").unwrap().llm_config.api_key, "env:DANA_KEY");
+
+    // 从明文默认配置继承的 API Key 同样被拒绝，给出引用时可以创建
+    let server = spawn_server_with_default_key("sk-default").await;
+    let url = format!("{}/api/agents", server.base);
+    assert_eq!(client.post(&url).bearer_auth(&server.admin).json(&request(None)).send().await.unwrap().status(), 400);
+    assert!(server.store.load_organization().await.unwrap().find_agent("dana").is_none());
+    let response = client.post(&url).bearer_auth(&server.admin).json(&request(Some("env:DANA_KEY"))).send().await.unwrap();
+    assert_eq!(response.status(), 201);
+}
+
+#[tokio::test]
+async fn test_create_agent_retry_with_idempotency_key_creates_once() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/agents", server.base);
+    // 不带 ID，每次执行都会生成新的 ID
+    let request = json!({
+        "name": "Dana",
+        "role": "Designer",
+        "system_prompt": "You design screens",
+        "department_id": "eng",
+    });
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let response = client
+            .post(&url)
+            .bearer_auth(&server.admin)
+            .header("Idempotency-Key", "create-dana")
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+        let body: Value = response.json().await.unwrap();
+        ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+    assert_eq!(ids[0], ids[1]);
+    let org = server.store.load_organization().await.unwrap();
+    assert_eq!(org.agents.iter().filter(|a| a.name == "Dana").count(), 1);
+    assert_eq!(server.runner.inboxes.lock().unwrap().len(), 1);
+
+    // 同一个键换了请求体是冲突，换一个键则是新的创建
+    let other = json!({ "name": "Eve", "role": "Designer", "system_prompt": "You design", "department_id": "eng" });
+    let response = client
+        .post(&url)
+        .bearer_auth(&server.admin)
+        .header("Idempotency-Key", "create-dana")
+        .json(&other)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+    let response = client
+        .post(&url)
+        .bearer_auth(&server.admin)
+        .header("Idempotency-Key", "create-eve")
+        .json(&other)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_concurrent_creates_are_serialized() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/agents", server.base);
+
+    let mut handles = Vec::new();
+    for i in 0..8 {
+        // 前 4 个请求使用同一个 ID，其余各不相同
+        let id = if i < 4 { "twin".to_string() } else { format!("solo-{}", i) };
+        let request = client.post(&url).bearer_auth(&server.admin).json(&json!({
+            "id": id,
+            "name": id,
+            "role": "Engineer",
+            "system_prompt": "You code",
+            "department_id": "eng",
+        }));
+        handles.push(tokio::spawn(async move { request.send().await.unwrap().status().as_u16() }));
+    }
+    let mut statuses = Vec::new();
+    for handle in handles {
+        statuses.push(handle.await.unwrap());
+    }
+
+    assert_eq!(statuses[..4].iter().filter(|s| **s == 201).count(), 1, "{:?}", statuses);
+    assert_eq!(statuses[..4].iter().filter(|s| **s == 409).count(), 3, "{:?}", statuses);
+    assert!(statuses[4..].iter().all(|s| *s == 201), "{:?}", statuses);
+
+    // 每个成功的创建都留在组织架构中
+    let org = server.store.load_organization().await.unwrap();
+    assert_eq!(org.agents.iter().filter(|a| a.id == "twin").count(), 1);
+    for i in 4..8 {
+        assert!(org.find_agent(&format!("solo-{}", i)).is_some());
+    }
+    assert_eq!(org.agents.len(), 6);
+}
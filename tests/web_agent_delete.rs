@@ -0,0 +1,180 @@
+//! 删除 Agent 接口测试：DELETE /agents/{id} 级联清理组织架构、消息总线和群聊成员，
+//! 部门负责人返回 409 或 force 时清除负责人，删除后不再出现在 Agent 列表中，
+//! 以及清理群聊失败时 Agent 仍留在组织架构和消息总线上
+
+mod common;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, MessageFilter, Store};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::AppState;
+use imitatort::{Department, Group, Message, Organization};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use common::{agent, serve, token};
+
+struct TestServer {
+    base: String,
+    store: Arc<SqliteStore>,
+    bus: Arc<MessageBus>,
+    admin: String,
+    employee: String,
+}
+
+/// 群聊写入失败的存储（模拟群聊表只读），其余操作转给内存存储
+struct ReadonlyGroupsStore {
+    inner: MemoryStore,
+}
+
+#[async_trait]
+impl Store for ReadonlyGroupsStore {
+    async fn save_organization(&self, org: &Organization) -> anyhow::Result<()> {
+        self.inner.save_organization(org).await
+    }
+
+    async fn load_organization(&self) -> anyhow::Result<Organization> {
+        self.inner.load_organization().await
+    }
+
+    async fn save_group(&self, _group: &Group) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("attempt to write a readonly database"))
+    }
+
+    async fn load_groups(&self) -> anyhow::Result<Vec<Group>> {
+        self.inner.load_groups().await
+    }
+
+    async fn delete_group(&self, group_id: &str) -> anyhow::Result<()> {
+        self.inner.delete_group(group_id).await
+    }
+
+    async fn save_message(&self, message: &Message) -> anyhow::Result<()> {
+        self.inner.save_message(message).await
+    }
+
+    async fn load_messages(&self, filter: MessageFilter) -> anyhow::Result<Vec<Message>> {
+        self.inner.load_messages(filter).await
+    }
+}
+
+/// lead 领导 eng 部门，dev 和 qa 是成员；三人都在 team 群聊中
+async fn spawn_server() -> TestServer {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering").with_leader("lead"));
+    for id in ["lead", "dev", "qa"] {
+        org.add_agent(agent(id, "eng"));
+    }
+    store.save_organization(&org).await.unwrap();
+
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    for id in ["lead", "dev", "qa"] {
+        bus.register(id);
+    }
+    let members = ["lead", "dev", "qa"].iter().map(|id| id.to_string()).collect();
+    bus.create_group("team", "Team", "lead", members).await.unwrap();
+
+    let jwt_service = JwtService::new("test-secret");
+    let (admin, employee) = (token(&jwt_service, "u1", "Management"), token(&jwt_service, "u1", "Employee"));
+
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(org.agents.clone(), message_tx, store.clone(), jwt_service.clone())
+        .with_message_bus(bus.clone());
+    let addr = serve(state).await;
+    TestServer { base: format!("http://{}", addr), store, bus, admin, employee }
+}
+
+async fn listed_agents(server: &TestServer) -> Vec<String> {
+    let agents: Vec<Value> = reqwest::get(format!("{}/api/agents", server.base)).await.unwrap().json().await.unwrap();
+    agents.iter().map(|a| a["id"].as_str().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn test_delete_agent_cleans_up_org_bus_and_groups() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/agents/dev", server.base);
+
+    assert_eq!(client.delete(&url).send().await.unwrap().status(), 403);
+    assert_eq!(client.delete(&url).bearer_auth(&server.employee).send().await.unwrap().status(), 403);
+
+    let response = client.delete(&url).bearer_auth(&server.admin).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["groups"], serde_json::json!(["team"]));
+    assert_eq!(body["data"]["cleared_leadership"], serde_json::json!([]));
+
+    let org = server.store.load_organization().await.unwrap();
+    assert!(org.find_agent("dev").is_none());
+    assert!(!server.bus.is_registered("dev"));
+    assert!(!server.bus.get_group("team").await.unwrap().has_member("dev"));
+    let stored = server.store.load_groups().await.unwrap();
+    assert_eq!(stored[0].members, vec!["lead", "qa"]);
+    assert_eq!(listed_agents(&server).await, vec!["lead", "qa"]);
+    assert_eq!(reqwest::get(&url).await.unwrap().status(), 404);
+
+    // 已删除的 Agent 返回 404
+    assert_eq!(client.delete(&url).bearer_auth(&server.admin).send().await.unwrap().status(), 404);
+}
+
+#[tokio::test]
+async fn test_deleting_a_department_leader_requires_force() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/agents/lead", server.base);
+
+    let response = client.delete(&url).bearer_auth(&server.admin).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("eng"), "{}", body);
+    assert!(server.store.load_organization().await.unwrap().find_agent("lead").is_some());
+    assert!(server.bus.is_registered("lead"));
+    assert_eq!(listed_agents(&server).await, vec!["lead", "dev", "qa"]);
+
+    let response = client.delete(format!("{}?force=true", url)).bearer_auth(&server.admin).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["cleared_leadership"], serde_json::json!(["eng"]));
+
+    let org = server.store.load_organization().await.unwrap();
+    assert!(org.find_agent("lead").is_none());
+    assert_eq!(org.find_department("eng").unwrap().leader_id, None);
+    assert_eq!(listed_agents(&server).await, vec!["dev", "qa"]);
+}
+
+#[tokio::test]
+async fn test_failed_group_cleanup_keeps_agent_in_org_and_on_bus() {
+    let inner = MemoryStore::new();
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    for id in ["lead", "dev"] {
+        org.add_agent(agent(id, "eng"));
+    }
+    inner.save_organization(&org).await.unwrap();
+    inner.save_group(&Group::new("team", "Team", "lead", vec!["lead".to_string(), "dev".to_string()])).await.unwrap();
+    let store = Arc::new(ReadonlyGroupsStore { inner });
+
+    let bus = Arc::new(MessageBus::new());
+    let _lead = bus.register("lead");
+    let _dev = bus.register("dev");
+
+    let jwt_service = JwtService::new("test-secret");
+    let admin = token(&jwt_service, "u1", "Management");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(org.agents.clone(), message_tx, store.clone(), jwt_service).with_message_bus(bus.clone());
+    let addr = serve(state).await;
+
+    let url = format!("http://{}/api/agents/dev", addr);
+    let response = reqwest::Client::new().delete(&url).bearer_auth(&admin).send().await.unwrap();
+    assert_eq!(response.status(), 500);
+
+    // 组织架构、消息总线和 Agent 列表都没有变化，可以重试
+    assert!(store.load_organization().await.unwrap().find_agent("dev").is_some());
+    assert!(bus.is_registered("dev"));
+    assert_eq!(reqwest::get(&url).await.unwrap().status(), 200);
+}
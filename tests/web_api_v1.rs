@@ -0,0 +1,468 @@
+//! `/api/v1` 响应信封契约测试
+
+use std::sync::Arc;
+
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::scheduler::{SchedulerConfig, TurnPriority, TurnScheduler};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::domain::{Agent, LLMConfig, Message, Organization, Role};
+use imitatort::domain::user::User;
+use imitatort::infrastructure::auth::{JwtService, PasswordService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+
+fn create_state() -> AppState {
+    let mut org = Organization::new();
+    org.add_agent(Agent::new(
+        "test-agent-1",
+        "Test Employee 1",
+        Role::simple("Developer", "You are a developer"),
+        LLMConfig::openai("test-key"),
+    ));
+
+    let (message_tx, _) = broadcast::channel::<Message>(100);
+    AppState::new(
+        org.agents.clone(),
+        message_tx,
+        Arc::new(MemoryStore::new()),
+        JwtService::new("test-secret-for-testing"),
+    )
+}
+
+fn token(id: &str) -> String {
+    JwtService::new("test-secret-for-testing")
+        .generate_token(&UserInfo {
+            id: id.to_string(),
+            username: id.to_string(),
+            name: id.to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: "Employee".to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+}
+
+async fn spawn_server(state: AppState) -> String {
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr.to_string()
+}
+
+/// 断言响应符合信封结构并返回 (状态码, 信封)
+async fn envelope(response: reqwest::Response) -> (u16, Value) {
+    let status = response.status().as_u16();
+    let body: Value = response.json().await.unwrap();
+
+    assert_eq!(body["api_version"], "v1", "body: {}", body);
+    let object = body.as_object().unwrap();
+    for key in ["api_version", "success", "data", "error"] {
+        assert!(object.contains_key(key), "missing '{}' in {}", key, body);
+    }
+    if body["success"] == true {
+        assert!(body["error"].is_null());
+    } else {
+        assert!(body["data"].is_null());
+        assert!(body["error"]["code"].is_string());
+        assert!(body["error"]["message"].is_string());
+    }
+    (status, body)
+}
+
+#[tokio::test]
+async fn test_public_routes_are_enveloped() {
+    let state = create_state();
+    let mut stored = Organization::new();
+    stored.add_agent(Agent::new("stored-only", "Stored Only", Role::simple("QA", "prompt"), LLMConfig::openai("k")));
+    state.store.save_organization(&stored).await.unwrap();
+    let addr = spawn_server(state).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = envelope(client.get(format!("http://{}/api/v1/health", addr)).send().await.unwrap()).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["status"], "ok");
+
+    // 旧接口返回裸数组，信封中放在 data 下
+    let (_, body) = envelope(client.get(format!("http://{}/api/v1/agents", addr)).send().await.unwrap()).await;
+    assert_eq!(body["data"][0]["id"], "test-agent-1");
+
+    let (status, body) = envelope(
+        client.get(format!("http://{}/api/v1/agents/nobody", addr)).send().await.unwrap(),
+    ).await;
+    assert_eq!(status, 404);
+    assert_eq!(body["error"]["code"], "NOT_FOUND");
+    assert_eq!(body["error"]["message"], "Agent not found: nobody");
+
+    // 不在运行列表中的 Agent 按ID从存储查询
+    let (status, body) = envelope(
+        client.get(format!("http://{}/api/v1/agents/stored-only", addr)).send().await.unwrap(),
+    ).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["name"], "Stored Only");
+
+    let (status, body) = envelope(client.get(format!("http://{}/api/v1/org/tree", addr)).send().await.unwrap()).await;
+    assert_eq!(status, 200);
+    assert!(body["success"].as_bool().unwrap());
+    let (status, body) = envelope(client.get(format!("http://{}/api/v1/templates", addr)).send().await.unwrap()).await;
+    assert_eq!(status, 200);
+    assert!(body["data"].is_array());
+}
+
+#[tokio::test]
+async fn test_message_routes_are_enveloped() {
+    let addr = spawn_server(create_state()).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = envelope(
+        client
+            .post(format!("http://{}/api/v1/messages", addr))
+            .json(&json!({ "from": "user", "to": "test-agent-1", "content": "hi" }))
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["status"], "sent");
+
+    let (status, body) = envelope(
+        client
+            .post(format!("http://{}/api/v1/messages", addr))
+            .json(&json!({ "from": "user", "content": "hi" }))
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["code"], "BAD_REQUEST");
+
+    // 提取器拒绝的纯文本错误也会被包装
+    let (status, body) = envelope(
+        client
+            .post(format!("http://{}/api/v1/messages", addr))
+            .json(&json!({ "unexpected": true }))
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert_eq!(status, 422);
+    assert_eq!(body["error"]["code"], "INVALID_PAYLOAD");
+
+    let (status, body) = envelope(client.get(format!("http://{}/api/v1/chat/list", addr)).send().await.unwrap()).await;
+    assert_eq!(status, 200);
+    assert!(body["data"].is_array());
+}
+
+#[tokio::test]
+async fn test_trace_id_flows_into_messages() {
+    let state = create_state();
+    let mut messages = state.message_tx.subscribe();
+    let addr = spawn_server(state).await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("http://{}/api/v1/messages", addr))
+        .header("x-trace-id", "req-123")
+        .json(&json!({ "from": "user", "to": "test-agent-1", "content": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["x-trace-id"], "req-123");
+    assert_eq!(messages.recv().await.unwrap().trace_id(), Some("req-123"));
+
+    // 未传入或格式非法时生成新的追踪ID
+    let response = client
+        .post(format!("http://{}/api/v1/messages", addr))
+        .header("x-trace-id", "bad id!")
+        .json(&json!({ "from": "user", "to": "test-agent-1", "content": "hi" }))
+        .send()
+        .await
+        .unwrap();
+    let generated = response.headers()["x-trace-id"].to_str().unwrap().to_string();
+    assert_ne!(generated, "bad id!");
+    assert_eq!(messages.recv().await.unwrap().trace_id(), Some(generated.as_str()));
+}
+
+#[tokio::test]
+async fn test_auth_and_admin_routes_are_enveloped() {
+    let addr = spawn_server(create_state()).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = envelope(
+        client.get(format!("http://{}/api/v1/auth/current", addr)).send().await.unwrap(),
+    ).await;
+    assert_eq!(status, 401);
+    assert_eq!(body["error"]["code"], "UNAUTHORIZED");
+
+    let (status, body) = envelope(
+        client
+            .post(format!("http://{}/api/v1/auth/login", addr))
+            .json(&json!({ "username": "nobody", "password": "wrong" }))
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert!(status >= 400);
+    assert_eq!(body["success"], false);
+
+    let (status, body) = envelope(
+        client.get(format!("http://{}/api/v1/admin/users", addr)).send().await.unwrap(),
+    ).await;
+    assert_eq!(status, 403);
+    assert_eq!(body["error"]["code"], "FORBIDDEN");
+
+    let (status, body) = envelope(
+        client.get(format!("http://{}/api/v1/tools/stats", addr)).send().await.unwrap(),
+    ).await;
+    assert_eq!(status, 200);
+    assert!(body["data"]["tools"].is_array());
+
+    let (status, body) = envelope(
+        client.get(format!("http://{}/api/v1/no/such/route", addr)).send().await.unwrap(),
+    ).await;
+    assert_eq!(status, 404);
+    assert_eq!(body["error"]["code"], "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn test_legacy_routes_can_be_disabled() {
+    let client = reqwest::Client::new();
+
+    let addr = spawn_server(create_state()).await;
+    let response = client.get(format!("http://{}/api/agents", addr)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["deprecation"], "true");
+    let body: Value = response.json().await.unwrap();
+    assert!(body.is_array());
+
+    let addr = spawn_server(create_state().with_legacy_api_routes(false)).await;
+    let response = client.get(format!("http://{}/api/agents", addr)).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+    let response = client.get(format!("http://{}/api/v1/agents", addr)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_websocket_frames_carry_version() {
+    let state = create_state();
+    let message_tx = state.message_tx.clone();
+    let addr = spawn_server(state).await;
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/v1/ws", addr))
+        .await
+        .unwrap();
+
+    // 等待服务端订阅消息通道
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    message_tx
+        .send(Message::private("user", "test-agent-1", "hello"))
+        .unwrap();
+
+    let frame = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let frame: Value = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+    assert_eq!(frame["v"], 1);
+    assert_eq!(frame["type"], "message");
+    assert_eq!(frame["data"]["content"], "hello");
+}
+
+#[tokio::test]
+async fn test_message_reactions() {
+    let state = create_state();
+    let message = Message::private("test-agent-1", "user:u1", "Deployed");
+    state.store.save_message(&message).await.unwrap();
+    let addr = spawn_server(state).await;
+    let client = reqwest::Client::new();
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/v1/ws", addr))
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    let url = format!("http://{}/api/v1/messages/{}/reactions", addr, message.id);
+    let react = |reactor: &str, emoji: &str| {
+        client.post(&url).json(&json!({ "reactor_id": reactor, "emoji": emoji })).send()
+    };
+
+    let (status, body) = envelope(react("user", "👍").await.unwrap()).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["changed"], true);
+
+    // 重复回应幂等
+    let (_, body) = envelope(react("user", "👍").await.unwrap()).await;
+    assert_eq!(body["data"]["changed"], false);
+    let (_, body) = envelope(react("test-agent-1", "👍").await.unwrap()).await;
+    assert_eq!(body["data"]["reactions"][0]["count"], 2);
+
+    let (status, body) = envelope(react("user", "not an emoji").await.unwrap()).await;
+    assert_eq!(status, 400);
+    assert_eq!(body["error"]["code"], "BAD_REQUEST");
+
+    // 会话消息中带有聚合计数
+    let (_, body) = envelope(
+        client
+            .get(format!("http://{}/api/v1/chat/test-agent-1/messages", addr))
+            .bearer_auth(token("u1"))
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert_eq!(body["data"][0]["reactions"], json!([{
+        "emoji": "👍", "count": 2, "reactors": ["user", "test-agent-1"]
+    }]));
+
+    let (_, body) = envelope(
+        client
+            .delete(format!("{}?reactor_id=user&emoji=%F0%9F%91%8D", url))
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert_eq!(body["data"]["changed"], true);
+    assert_eq!(body["data"]["reactions"][0]["count"], 1);
+
+    // WebSocket 依次收到两次新增与一次移除
+    let mut types = vec![];
+    while types.len() < 3 {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let frame: Value = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+        assert_eq!(frame["data"]["message_id"], message.id.as_str());
+        types.push(frame["type"].as_str().unwrap().to_string());
+    }
+    assert_eq!(types, ["reaction_added", "reaction_added", "reaction_removed"]);
+}
+
+#[tokio::test]
+async fn test_scheduler_stats_and_queued_presence() {
+    let addr = spawn_server(create_state()).await;
+    let response = reqwest::get(format!("http://{}/api/v1/scheduler/stats", addr)).await.unwrap();
+    let (status, _) = envelope(response).await;
+    assert_eq!(status, 404);
+
+    let scheduler = Arc::new(TurnScheduler::new(SchedulerConfig {
+        max_concurrent_turns: 1,
+        ..SchedulerConfig::default()
+    }));
+    let state = create_state().with_scheduler(scheduler.clone());
+    let mut org = Organization::new();
+    org.agents = state.agents();
+    state.store.save_organization(&org).await.unwrap();
+    let addr = spawn_server(state).await;
+
+    let _running = scheduler.acquire("other-agent", TurnPriority::Background).await;
+    let queued = scheduler.clone();
+    tokio::spawn(async move {
+        let _permit = queued.acquire("test-agent-1", TurnPriority::User).await;
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+    let response = reqwest::get(format!("http://{}/api/v1/scheduler/stats", addr)).await.unwrap();
+    let (status, body) = envelope(response).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["max_concurrent_turns"], 1);
+    assert_eq!(body["data"]["running"], 1);
+    assert_eq!(body["data"]["queued_user"], 1);
+
+    let response = reqwest::get(format!("http://{}/api/v1/chat/list", addr)).await.unwrap();
+    let (_, body) = envelope(response).await;
+    assert_eq!(body["data"][0]["participants"][0]["status"], "queued");
+}
+
+#[tokio::test]
+async fn test_agent_dm_reaches_offline_user_on_login() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let mut agent_rx = bus.register("test-agent-1");
+
+    let user = User::new_chairman(
+        "alice".to_string(),
+        "Alice".to_string(),
+        PasswordService::hash_password("Str0ng-enough").unwrap(),
+        None,
+    );
+    store.save_user(&user).await.unwrap();
+
+    // 用户离线：消息落库即视为送达
+    bus.send(Message::private("test-agent-1", user.principal_id(), "Build is green")).await.unwrap();
+
+    let (message_tx, _) = broadcast::channel::<Message>(100);
+    let state = AppState::new(vec![], message_tx, store, JwtService::new("test-secret-for-testing"))
+        .with_message_bus(bus.clone());
+    let addr = spawn_server(state).await;
+    let client = reqwest::Client::new();
+
+    let (status, body) = envelope(
+        client
+            .post(format!("http://{}/api/v1/auth/login", addr))
+            .json(&json!({ "username": "alice", "password": "Str0ng-enough" }))
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["user"]["principal_id"], user.principal_id());
+    assert!(bus.is_user_registered(&user.principal_id()));
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+
+    let (status, body) = envelope(
+        client
+            .get(format!("http://{}/api/v1/users/me/messages", addr))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap(),
+    ).await;
+    assert_eq!(status, 200);
+    let messages = body["data"]["messages"].as_array().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["from"], "test-agent-1");
+    assert_eq!(messages[0]["content"], "Build is green");
+
+    let (status, _) = envelope(
+        client.get(format!("http://{}/api/v1/users/me/messages", addr)).send().await.unwrap(),
+    ).await;
+    assert_eq!(status, 401);
+
+    // 在线会话实时收到 Agent 私聊，用户发出的消息以参与者身份进入 Agent 信箱
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/v1/ws?token={}", addr, token))
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    bus.send(Message::private("test-agent-1", user.principal_id(), "Deploying now")).await.unwrap();
+    let frame = socket.next().await.unwrap().unwrap();
+    let frame: Value = serde_json::from_str(frame.to_text().unwrap()).unwrap();
+    assert_eq!(frame["type"], "message");
+    assert_eq!(frame["data"]["content"], "Deploying now");
+    assert_eq!(frame["data"]["to"], user.principal_id());
+
+    use futures_util::SinkExt;
+    socket
+        .send(tokio_tungstenite::tungstenite::Message::Text(
+            json!({ "type": "send_message", "from": "anyone", "to": "test-agent-1", "content": "Thanks" })
+                .to_string(),
+        ))
+        .await
+        .unwrap();
+    let received = tokio::time::timeout(std::time::Duration::from_secs(2), agent_rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received.from, user.principal_id());
+    assert_eq!(received.content, "Thanks");
+}
@@ -0,0 +1,109 @@
+//! 嵌入到宿主 axum 应用的路由测试
+
+use std::sync::Arc;
+
+use axum::routing::post;
+use axum::Router;
+use serde_json::Value;
+
+use imitatort::core::store::MemoryStore;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::{
+    build_company_router, Agent, CompanyConfig, LLMConfig, Organization, Role, RouteGroups, RouterOptions,
+    VirtualCompany,
+};
+
+fn company(name: &str, agent_id: &str) -> VirtualCompany {
+    let mut org = Organization::new();
+    org.add_agent(Agent::new(
+        agent_id,
+        agent_id,
+        Role::simple("Developer", "You are a developer"),
+        LLMConfig::openai("test-key"),
+    ));
+    VirtualCompany::with_store(CompanyConfig::new(name, org), Arc::new(MemoryStore::new()))
+}
+
+fn token(jwt: &JwtService) -> String {
+    jwt.generate_token(&UserInfo {
+        id: "u-1".to_string(),
+        username: "alice".to_string(),
+        name: "Alice".to_string(),
+        email: None,
+        is_director: false,
+        employee_id: "10001".to_string(),
+        position: "Employee".to_string(),
+        department: "tech".to_string(),
+    })
+    .unwrap()
+}
+
+async fn spawn_host(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+async fn get(url: String, bearer: Option<&str>) -> (u16, Value) {
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(token) = bearer {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await.unwrap();
+    let status = response.status().as_u16();
+    (status, response.json().await.unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn test_company_routers_nest_into_host_app() {
+    let company_a = company("A", "agent-a");
+    let company_b = company("B", "agent-b");
+    let jwt_b = JwtService::new("host-secret");
+
+    let api_only = RouterOptions {
+        routes: RouteGroups::api_only(),
+        legacy_api_routes: false,
+        ..RouterOptions::default()
+    };
+    let with_auth = RouterOptions {
+        jwt_service: Some(jwt_b.clone()),
+        ..RouterOptions::default()
+    };
+
+    let host = Router::new()
+        .route("/auth/login", post(|| async { "host login" }))
+        .nest("/imitator-a", build_company_router(&company_a, api_only))
+        .nest("/imitator-b", build_company_router(&company_b, with_auth));
+    let base = spawn_host(host).await;
+
+    // 每个公司只看到自己的 Agent
+    let (status, body) = get(format!("{}/imitator-a/api/v1/agents", base), None).await;
+    assert_eq!(status, 200);
+    let ids: Vec<&str> = body["data"].as_array().unwrap().iter().map(|a| a["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["agent-a"]);
+    let (_, body) = get(format!("{}/imitator-b/api/v1/agents", base), None).await;
+    assert_eq!(body["data"][0]["id"], "agent-b");
+
+    // 关闭的路由组不挂载，宿主自己的认证路由不受影响
+    let (status, _) = get(format!("{}/imitator-a/api/v1/auth/current", base), None).await;
+    assert_eq!(status, 404);
+    let (status, _) = get(format!("{}/imitator-a/api/v1/admin/users", base), None).await;
+    assert_eq!(status, 404);
+    let (status, _) = get(format!("{}/imitator-a/ws", base), None).await;
+    assert_eq!(status, 404);
+    let (status, _) = get(format!("{}/imitator-a/api/agents", base), None).await;
+    assert_eq!(status, 404);
+    let response = reqwest::Client::new().post(format!("{}/auth/login", base)).send().await.unwrap();
+    assert_eq!(response.text().await.unwrap(), "host login");
+
+    // 注入的 JWT 服务只接受宿主签发的令牌
+    let (status, body) = get(format!("{}/imitator-b/api/v1/auth/current", base), Some(&token(&jwt_b))).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["username"], "alice");
+    let other = JwtService::new("some-other-secret");
+    let (status, _) = get(format!("{}/imitator-b/api/v1/auth/current", base), Some(&token(&other))).await;
+    assert_eq!(status, 401);
+}
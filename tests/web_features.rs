@@ -0,0 +1,119 @@
+//! 功能开关接口测试：`GET /features` 的结构、`/info` 中的开关、管理接口切换功能并发出事件
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::features::{Feature, FeatureFlags, FeatureSource};
+use imitatort::core::store::MemoryStore;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::web::{create_router, AppState};
+
+struct Server {
+    base: String,
+    jwt: JwtService,
+    flags: Arc<FeatureFlags>,
+    events: broadcast::Receiver<CompanyEvent>,
+}
+
+impl Server {
+    fn token(&self, position: &str) -> String {
+        self.jwt
+            .generate_token(&UserInfo {
+                id: position.to_lowercase(),
+                username: position.to_lowercase(),
+                name: position.to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: position.to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    }
+}
+
+async fn spawn_server() -> Server {
+    let events = Arc::new(EventBus::new());
+    let events_rx = events.subscribe();
+    let flags = Arc::new(FeatureFlags::compiled().with_events(events));
+    flags.register(Feature::new("email", true, FeatureSource::Runtime).with_toggleable(true));
+    flags.register(Feature::new("digest", false, FeatureSource::Config));
+
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(Vec::new(), message_tx, Arc::new(MemoryStore::new()), jwt.clone()).with_features(flags.clone());
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    Server { base: format!("http://{}/api/v1", addr), jwt, flags, events: events_rx }
+}
+
+#[tokio::test]
+async fn test_features_endpoint_shape() {
+    let server = spawn_server().await;
+    let body: Value = reqwest::get(format!("{}/features", server.base)).await.unwrap().json().await.unwrap();
+
+    let features = body["data"]["features"].as_array().unwrap();
+    let email = features.iter().find(|f| f["name"] == "email").unwrap();
+    assert_eq!(email, &json!({"name": "email", "enabled": true, "source": "runtime", "toggleable": true}));
+    let s3 = features.iter().find(|f| f["name"] == "s3").unwrap();
+    assert_eq!(s3["source"], "compiled");
+    assert_eq!(s3["enabled"], cfg!(feature = "s3"));
+    assert!(body["data"]["enabled"].as_array().unwrap().contains(&json!("email")));
+
+    let info: Value = reqwest::get(format!("{}/info", server.base)).await.unwrap().json().await.unwrap();
+    assert_eq!(info["data"]["flags"]["email"], true);
+    assert_eq!(info["data"]["flags"]["digest"], false);
+}
+
+#[tokio::test]
+async fn test_admin_toggles_runtime_feature() {
+    let mut server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/admin/features/email", server.base);
+
+    let response = client.put(&url).bearer_auth(server.token("Employee")).json(&json!({"enabled": false})).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+    assert!(server.flags.is_enabled("email"));
+
+    let body: Value = client
+        .put(&url)
+        .bearer_auth(server.token("Chairman"))
+        .json(&json!({"enabled": false}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["name"], "email");
+    assert_eq!(body["data"]["enabled"], false);
+    assert!(!server.flags.is_enabled("email"));
+    match server.events.try_recv().unwrap() {
+        CompanyEvent::FeatureToggled { feature } => assert!(!feature.enabled),
+        other => panic!("unexpected event {}", other.kind()),
+    }
+
+    let response = client
+        .put(format!("{}/admin/features/digest", server.base))
+        .bearer_auth(server.token("Chairman"))
+        .json(&json!({"enabled": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 409);
+    let response = client
+        .put(format!("{}/admin/features/ghost", server.base))
+        .bearer_auth(server.token("Chairman"))
+        .json(&json!({"enabled": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
@@ -0,0 +1,160 @@
+//! 群聊接口测试：创建群聊写入消息总线和存储、邀请成员并持久化、经 WebSocket 发送后分页读取群聊历史（含 v1 响应头），
+//! 以及创建权限与部门范围、`group:` 前缀、重复 ID（含只在存储中的群聊）、未知成员和非成员的错误
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::SinkExt;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::Store;
+use imitatort::domain::user::User;
+use imitatort::infrastructure::auth::{Grant, JwtService, Permission, PermissionConfig};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{AppState, HAS_MORE_HEADER, NEXT_CURSOR_HEADER};
+use imitatort::{Department, Group, Organization};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use common::{agent, serve, token};
+
+#[tokio::test]
+async fn test_create_invite_send_and_read_history() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    org.add_department(Department::top_level("sales", "Sales"));
+    for (id, department) in [("dev", "eng"), ("qa", "eng"), ("ops", "sales")] {
+        org.add_agent(agent(id, department));
+    }
+    store.save_organization(&org).await.unwrap();
+    let carol = User::new_employee("carol".into(), "Carol".into(), "hash".into(), 2, "eng".into(), None);
+    store.save_user(&carol).await.unwrap();
+
+    let bus = Arc::new(MessageBus::with_store(store.clone()));
+    let _dev = bus.register("dev");
+    let mut qa = bus.register("qa");
+    let _ops = bus.register("ops");
+
+    // alice 拥有全局 manage_groups，erin 只能在 eng 部门内建群，bob 没有该权限
+    let jwt_service = JwtService::new("test-secret");
+    let alice = token(&jwt_service, "alice", "Management");
+    let bob = token(&jwt_service, "bob", "Employee");
+    let erin = token(&jwt_service, "erin", "Employee");
+    let permissions =
+        PermissionConfig::default().with_user("erin", vec![Grant::scoped(Permission::ManageGroups, "eng")]);
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store.clone(), jwt_service)
+        .with_message_bus(bus.clone())
+        .with_permissions(permissions);
+    let addr = serve(state).await;
+    let base = format!("http://{}/api/groups", addr);
+    let client = reqwest::Client::new();
+
+    // 创建：ID 可以带 `group:` 前缀，创建者自动成为成员和管理员，用户成员须已存在
+    let create = json!({ "id": "group:launch", "name": "Launch", "members": ["dev", carol.principal_id()] });
+    assert_eq!(client.post(&base).json(&create).send().await.unwrap().status(), 403);
+    assert_eq!(client.post(&base).bearer_auth(&bob).json(&create).send().await.unwrap().status(), 403);
+    let response = client.post(&base).bearer_auth(&alice).json(&create).send().await.unwrap();
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["id"], "launch");
+    assert_eq!(body["data"]["members"], json!(["user:alice", "dev", carol.principal_id()]));
+    assert_eq!(body["data"]["admins"], json!(["user:alice"]));
+    assert_eq!(store.load_groups().await.unwrap()[0].id, "launch");
+
+    // 总线上或只在存储中的同名群聊都算重复
+    assert_eq!(client.post(&base).bearer_auth(&alice).json(&create).send().await.unwrap().status(), 409);
+    store.save_group(&Group::new("archived", "Archived", "dev", vec!["dev".to_string()])).await.unwrap();
+    let archived = json!({ "id": "archived", "name": "Archived again" });
+    assert_eq!(client.post(&base).bearer_auth(&alice).json(&archived).send().await.unwrap().status(), 409);
+
+    for unknown in ["ghost", "user:ghost", "user:"] {
+        let request = json!({ "name": "Ghosts", "members": [unknown] });
+        let response = client.post(&base).bearer_auth(&alice).json(&request).send().await.unwrap();
+        assert_eq!(response.status(), 400);
+        assert_eq!(response.json::<Value>().await.unwrap()["error"], format!("Unknown member: {}", unknown));
+    }
+
+    // 部门范围的授权只能拉入本部门的 Agent
+    let request = json!({ "id": "eng-only", "name": "Eng", "members": ["qa"] });
+    assert_eq!(client.post(&base).bearer_auth(&erin).json(&request).send().await.unwrap().status(), 201);
+    let request = json!({ "name": "Cross", "members": ["qa", "ops"] });
+    assert_eq!(client.post(&base).bearer_auth(&erin).json(&request).send().await.unwrap().status(), 403);
+
+    // 邀请：只有群管理员可以邀请，成员变更写入存储
+    let members = format!("{}/launch/members", base);
+    let invite = json!({ "member_id": "qa" });
+    assert_eq!(client.post(&members).bearer_auth(&bob).json(&invite).send().await.unwrap().status(), 403);
+    let response = client.post(&members).bearer_auth(&alice).json(&invite).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let stored = store.load_groups().await.unwrap().into_iter().find(|g| g.id == "launch").unwrap();
+    assert_eq!(stored.members, vec!["user:alice".to_string(), "dev".to_string(), carol.principal_id(), "qa".to_string()]);
+    assert!(bus.get_group("launch").await.unwrap().has_member("qa"));
+    let invite = json!({ "member_id": "user:ghost" });
+    assert_eq!(client.post(&members).bearer_auth(&alice).json(&invite).send().await.unwrap().status(), 400);
+
+    // 经 WebSocket 以 `group:launch` 为目标发送，新成员收到群聊消息
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/api/v1/ws?token={}", addr, alice))
+        .await
+        .unwrap();
+    for content in ["Kickoff", "Agenda"] {
+        let frame = json!({ "type": "send_message", "from": "alice", "to": "group:launch", "content": content });
+        socket
+            .send(tokio_tungstenite::tungstenite::Message::Text(frame.to_string()))
+            .await
+            .unwrap();
+    }
+    for content in ["Kickoff", "Agenda"] {
+        let received = tokio::time::timeout(Duration::from_secs(5), qa.recv()).await.unwrap().unwrap();
+        assert_eq!(received.content, content);
+        assert_eq!(received.from, "user:alice");
+    }
+
+    // 历史（v1）：信封只保留 `data`，分页信息在响应头中；两页首尾相接覆盖全部消息
+    let history = format!("http://{}/api/v1/groups/group:launch/messages", addr);
+    let response = client.get(format!("{}?limit=1", history)).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()[HAS_MORE_HEADER], "true");
+    let cursor = response.headers()[NEXT_CURSOR_HEADER].to_str().unwrap().to_string();
+    let body: Value = response.json().await.unwrap();
+    let mut contents = vec![body["data"][0]["content"].as_str().unwrap().to_string()];
+    let response = client
+        .get(format!("{}?limit=1&before={}", history, cursor))
+        .bearer_auth(&alice)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[HAS_MORE_HEADER], "false");
+    assert!(response.headers().get(NEXT_CURSOR_HEADER).is_none());
+    let body: Value = response.json().await.unwrap();
+    contents.push(body["data"][0]["content"].as_str().unwrap().to_string());
+    contents.sort();
+    assert_eq!(contents, vec!["Agenda", "Kickoff"]);
+
+    // 旧接口的响应体仍带 `has_more` 和 `next_cursor`
+    let body: Value = client
+        .get(format!("{}/launch/messages?limit=1", base))
+        .bearer_auth(&alice)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["has_more"], true);
+    assert_eq!(body["next_cursor"], cursor.as_str());
+
+    let response = client.get(format!("{}?offset=10001", history)).bearer_auth(&alice).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+    assert_eq!(client.get(&history).bearer_auth(&bob).send().await.unwrap().status(), 403);
+
+    // 列表：有全局 manage_groups 时返回总线上的全部群聊，否则只包含自己所在的
+    let listed: Value = client.get(&base).bearer_auth(&alice).send().await.unwrap().json().await.unwrap();
+    assert_eq!(listed["data"].as_array().unwrap().len(), 2);
+    let listed: Value = client.get(&base).bearer_auth(&erin).send().await.unwrap().json().await.unwrap();
+    assert_eq!(listed["data"][0]["id"], "eng-only");
+    let listed: Value = client.get(&base).bearer_auth(&bob).send().await.unwrap().json().await.unwrap();
+    assert_eq!(listed["data"], json!([]));
+}
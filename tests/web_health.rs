@@ -0,0 +1,189 @@
+//! 存活与就绪探针测试
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use imitatort::core::store::{BufferConfig, BufferedStore, MemoryStore, MessageFilter, Store};
+use imitatort::domain::{Agent, Group, LLMConfig, Message, Organization, Role};
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState, HealthConfig};
+
+/// 所有操作都失败的存储（模拟磁盘只读或数据库损坏）
+struct FailingStore;
+
+#[async_trait]
+impl Store for FailingStore {
+    async fn save_organization(&self, _org: &Organization) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("attempt to write a readonly database"))
+    }
+
+    async fn load_organization(&self) -> anyhow::Result<Organization> {
+        Err(anyhow::anyhow!("disk I/O error"))
+    }
+
+    async fn save_group(&self, _group: &Group) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("attempt to write a readonly database"))
+    }
+
+    async fn load_groups(&self) -> anyhow::Result<Vec<Group>> {
+        Err(anyhow::anyhow!("disk I/O error"))
+    }
+
+    async fn delete_group(&self, _group_id: &str) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("attempt to write a readonly database"))
+    }
+
+    async fn save_message(&self, _message: &Message) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!("attempt to write a readonly database"))
+    }
+
+    async fn load_messages(&self, _filter: MessageFilter) -> anyhow::Result<Vec<Message>> {
+        Err(anyhow::anyhow!("disk I/O error"))
+    }
+}
+
+fn create_state(store: Arc<dyn Store>, agents: Vec<Agent>) -> AppState {
+    let (message_tx, _) = broadcast::channel::<Message>(100);
+    AppState::new(agents, message_tx, store, JwtService::new("test-secret-for-testing"))
+}
+
+/// 指向无人监听端口的 Agent
+fn unreachable_agent() -> Agent {
+    Agent::new(
+        "dev",
+        "Dev",
+        Role::simple("Developer", "You are a developer"),
+        LLMConfig {
+            model: "mock".to_string(),
+            api_key: "test".to_string(),
+            base_url: "http://127.0.0.1:1/v1".to_string(),
+            tokenizer: None,
+        },
+    )
+}
+
+async fn spawn_server(state: AppState) -> String {
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+async fn get(url: String) -> (u16, Value) {
+    let response = reqwest::get(url).await.unwrap();
+    (response.status().as_u16(), response.json().await.unwrap())
+}
+
+fn check<'a>(body: &'a Value, name: &str) -> &'a Value {
+    body["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap_or_else(|| panic!("missing check {} in {}", name, body))
+}
+
+#[tokio::test]
+async fn test_ready_when_subsystems_healthy() {
+    let base = spawn_server(create_state(Arc::new(MemoryStore::new()), vec![])).await;
+
+    let (status, body) = get(format!("{}/api/v1/health/live", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["data"]["status"], "ok");
+
+    let (status, body) = get(format!("{}/api/v1/health/ready", base)).await;
+    assert_eq!(status, 200);
+    let data = &body["data"];
+    assert_eq!(data["status"], "ok");
+    assert_eq!(check(data, "store")["ok"], true);
+    assert_eq!(check(data, "store")["critical"], true);
+    assert_eq!(check(data, "message_bus")["ok"], true);
+    assert!(check(data, "store")["latency_ms"].is_u64());
+    assert_eq!(check(data, "store")["target"], "memory");
+    assert_eq!(data["store"]["backend"], "memory");
+}
+
+#[tokio::test]
+async fn test_ready_reports_database_in_use() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let db_path = temp_dir.path().join("ready.db");
+    let store = Arc::new(SqliteStore::new(&db_path).unwrap());
+    let base = spawn_server(create_state(store, vec![])).await;
+
+    let (status, body) = get(format!("{}/api/v1/health/ready", base)).await;
+    assert_eq!(status, 200);
+    let data = &body["data"];
+    assert_eq!(data["store"]["backend"], "sqlite");
+    assert_eq!(data["store"]["path"], db_path.to_string_lossy().as_ref());
+    assert_eq!(data["store"]["in_memory"], false);
+    assert_eq!(check(data, "store")["target"], format!("sqlite:{}", db_path.display()));
+}
+
+#[tokio::test]
+async fn test_failing_store_is_not_ready() {
+    let base = spawn_server(create_state(Arc::new(FailingStore), vec![])).await;
+
+    // 存活探针不受影响
+    let (status, _) = get(format!("{}/api/v1/health/live", base)).await;
+    assert_eq!(status, 200);
+
+    let (status, body) = get(format!("{}/api/health/ready", base)).await;
+    assert_eq!(status, 503);
+    assert_eq!(body["status"], "fail");
+    assert_eq!(check(&body, "store")["ok"], false);
+    assert!(check(&body, "store")["error"].as_str().unwrap().contains("disk I/O error"));
+
+    let (status, body) = get(format!("{}/api/v1/health/ready", base)).await;
+    assert_eq!(status, 503);
+    assert!(body["error"]["message"].as_str().unwrap().contains("store"));
+}
+
+#[tokio::test]
+async fn test_unreachable_llm_respects_criticality() {
+    // 默认 LLM 检查只报告，不影响就绪
+    let state = create_state(Arc::new(MemoryStore::new()), vec![unreachable_agent()]);
+    let base = spawn_server(state).await;
+
+    let (status, body) = get(format!("{}/api/health/ready", base)).await;
+    assert_eq!(status, 200);
+    let llm = check(&body, "llm");
+    assert_eq!(llm["ok"], false);
+    assert_eq!(llm["critical"], false);
+    assert_eq!(llm["target"], "http://127.0.0.1:1/v1");
+
+    // 配置为关键检查后返回 503
+    let state = create_state(Arc::new(MemoryStore::new()), vec![unreachable_agent()]).with_health_config(
+        HealthConfig {
+            critical: vec!["store".to_string(), "llm".to_string()],
+            ..HealthConfig::default()
+        },
+    );
+    let base = spawn_server(state).await;
+
+    let (status, body) = get(format!("{}/api/health/ready", base)).await;
+    assert_eq!(status, 503);
+    assert_eq!(check(&body, "llm")["critical"], true);
+    assert!(body["error"].as_str().unwrap().contains("llm:http://127.0.0.1:1/v1"));
+}
+
+#[tokio::test]
+async fn test_buffered_store_reports_degraded() {
+    let store = BufferedStore::new(FailingStore, BufferConfig::default()).unwrap();
+    store.save_message(&Message::private("a", "b", "hello")).await.unwrap();
+    let base = spawn_server(create_state(Arc::new(store), vec![])).await;
+
+    // 缓冲未满时仍然就绪，但标记为降级
+    let (status, body) = get(format!("{}/api/v1/health/ready", base)).await;
+    assert_eq!(status, 200);
+    let data = &body["data"];
+    assert_eq!(data["status"], "degraded");
+    assert_eq!(check(data, "store")["ok"], true);
+    assert!(check(data, "store")["degraded"].as_str().unwrap().contains("1 of 1000"));
+}
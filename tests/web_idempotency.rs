@@ -0,0 +1,189 @@
+//! Idempotency-Key 测试：重放、请求体冲突、过期复用、按调用方隔离、匿名请求被拒绝和执行标记的释放
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::domain::idempotency::IdempotencyRecord;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState, IdempotencyKeys, IDEMPOTENCY_KEY_HEADER};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+const START: i64 = 1_700_000_000;
+
+struct Server {
+    base: String,
+    jwt_service: JwtService,
+    message_rx: broadcast::Receiver<imitatort::domain::Message>,
+}
+
+async fn spawn_server(store: Arc<dyn Store>, clock: Arc<ManualClock>) -> Server {
+    let (message_tx, message_rx) = broadcast::channel(16);
+    let jwt_service = JwtService::new("test-secret");
+    let idempotency = IdempotencyKeys::new(store.clone())
+        .with_ttl(Duration::from_secs(60))
+        .with_clock(clock);
+    let state = AppState::new(vec![], message_tx, store, jwt_service.clone()).with_idempotency(Arc::new(idempotency));
+    let app = create_router(Arc::new(state));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    Server {
+        base: format!("http://{}", addr),
+        jwt_service,
+        message_rx,
+    }
+}
+
+fn token(jwt_service: &JwtService, id: &str) -> String {
+    jwt_service
+        .generate_token(&UserInfo {
+            id: id.to_string(),
+            username: id.to_string(),
+            name: id.to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: "Employee".to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+}
+
+async fn post_message(server: &Server, key: &str, content: &str, bearer: Option<&str>) -> (u16, Value) {
+    let mut request = reqwest::Client::new()
+        .post(format!("{}/api/v1/messages", server.base))
+        .header(IDEMPOTENCY_KEY_HEADER, key)
+        .json(&json!({ "from": "alice", "to": "bob", "content": content }));
+    if let Some(bearer) = bearer {
+        request = request.bearer_auth(bearer);
+    }
+    let response = request.send().await.unwrap();
+    (response.status().as_u16(), response.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn test_replay_returns_same_message() {
+    let clock = Arc::new(ManualClock::new(START));
+    let mut server = spawn_server(Arc::new(SqliteStore::new_in_memory().unwrap()), clock).await;
+    let alice = token(&server.jwt_service, "alice");
+
+    let (status, first) = post_message(&server, "k-1", "hello", Some(&alice)).await;
+    assert_eq!(status, 200);
+    let (status, second) = post_message(&server, "k-1", "hello", Some(&alice)).await;
+    assert_eq!(status, 200);
+    assert_eq!(first["data"]["id"], second["data"]["id"]);
+
+    // 重放不会再次广播
+    assert_eq!(server.message_rx.try_recv().unwrap().content, "hello");
+    assert!(server.message_rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_different_body_conflicts() {
+    let clock = Arc::new(ManualClock::new(START));
+    let server = spawn_server(Arc::new(SqliteStore::new_in_memory().unwrap()), clock).await;
+    let alice = token(&server.jwt_service, "alice");
+
+    post_message(&server, "k-1", "hello", Some(&alice)).await;
+    let (status, body) = post_message(&server, "k-1", "goodbye", Some(&alice)).await;
+    assert_eq!(status, 409);
+    assert_eq!(body["error"]["code"], "CONFLICT");
+    assert_eq!(body["error"]["message"], "Idempotency key k-1 was already used for a different request");
+}
+
+#[tokio::test]
+async fn test_expired_key_can_be_reused() {
+    let clock = Arc::new(ManualClock::new(START));
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let server = spawn_server(store.clone(), clock.clone()).await;
+    let alice = token(&server.jwt_service, "alice");
+
+    let (_, first) = post_message(&server, "k-1", "hello", Some(&alice)).await;
+    clock.advance(Duration::from_secs(60));
+
+    let (status, second) = post_message(&server, "k-1", "goodbye", Some(&alice)).await;
+    assert_eq!(status, 200);
+    assert_ne!(first["data"]["id"], second["data"]["id"]);
+
+    clock.advance(Duration::from_secs(60));
+    let keys = IdempotencyKeys::new(store.clone()).with_clock(clock);
+    assert_eq!(keys.cleanup().await.unwrap(), 1);
+    assert!(store.load_idempotency_record("user:alice", "k-1").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_keys_are_scoped_per_principal() {
+    let clock = Arc::new(ManualClock::new(START));
+    let server = spawn_server(Arc::new(MemoryStore::new()), clock).await;
+    let alice = token(&server.jwt_service, "alice");
+    let bob = token(&server.jwt_service, "bob");
+
+    let (_, from_alice) = post_message(&server, "shared", "hello", Some(&alice)).await;
+    let (status, from_bob) = post_message(&server, "shared", "goodbye", Some(&bob)).await;
+    assert_eq!(status, 200);
+    assert_ne!(from_alice["data"]["id"], from_bob["data"]["id"]);
+
+    let (_, again) = post_message(&server, "shared", "hello", Some(&alice)).await;
+    assert_eq!(again["data"]["id"], from_alice["data"]["id"]);
+}
+
+#[tokio::test]
+async fn test_anonymous_requests_with_key_are_rejected() {
+    let clock = Arc::new(ManualClock::new(START));
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let mut server = spawn_server(store.clone(), clock).await;
+
+    // 匿名调用方之间无法区分，不能共享记录
+    let (status, _) = post_message(&server, "k-1", "hello", None).await;
+    assert_eq!(status, 401);
+    let (status, _) = post_message(&server, "k-1", "hello", Some("not-a-token")).await;
+    assert_eq!(status, 401);
+    assert!(server.message_rx.try_recv().is_err());
+    assert!(store.load_idempotency_record("anonymous", "k-1").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_in_flight_marker_released_on_drop() {
+    let keys = IdempotencyKeys::new(Arc::new(MemoryStore::new()));
+    let guard = keys.begin("user:alice", "k-1").unwrap();
+    assert!(keys.begin("user:alice", "k-1").is_none());
+    assert!(keys.begin("user:bob", "k-1").is_some());
+    drop(guard);
+    assert!(keys.begin("user:alice", "k-1").is_some());
+
+    // 处理函数 panic 时同样释放
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let _guard = keys.begin("user:alice", "k-2").unwrap();
+        panic!("handler failed");
+    }));
+    assert!(result.is_err());
+    assert!(keys.begin("user:alice", "k-2").is_some());
+}
+
+#[tokio::test]
+async fn test_records_round_trip_through_sqlite() {
+    let store = SqliteStore::new_in_memory().unwrap();
+    let record = IdempotencyRecord {
+        scope: "user:1".to_string(),
+        key: "k".to_string(),
+        endpoint: "POST /messages".to_string(),
+        request_hash: "abc".to_string(),
+        status: 201,
+        response: json!({ "id": "m1" }),
+        created_at: START,
+        expires_at: START + 10,
+    };
+    store.save_idempotency_record(&record).await.unwrap();
+    assert_eq!(store.load_idempotency_record("user:1", "k").await.unwrap(), Some(record));
+    assert!(store.load_idempotency_record("user:2", "k").await.unwrap().is_none());
+
+    assert_eq!(store.delete_expired_idempotency_records(START + 9).await.unwrap(), 0);
+    assert_eq!(store.delete_expired_idempotency_records(START + 10).await.unwrap(), 1);
+}
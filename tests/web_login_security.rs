@@ -0,0 +1,212 @@
+//! 密码策略与登录限流测试
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::i18n::{Language, MessageCatalog};
+use imitatort::core::store::Store;
+use imitatort::domain::user::User;
+use imitatort::infrastructure::auth::{
+    user_key, JwtService, LoginThrottle, LoginThrottleConfig, PasswordPolicy, PasswordService, PasswordViolation,
+    UserInfo,
+};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+#[test]
+fn test_password_policy_violations() {
+    let policy = PasswordPolicy {
+        require_uppercase: true,
+        require_symbol: true,
+        deny_list: vec!["Company2024!".to_string()],
+        ..PasswordPolicy::default()
+    };
+
+    assert_eq!(
+        policy.check("abc"),
+        vec![
+            PasswordViolation::TooShort { min_length: 8 },
+            PasswordViolation::MissingUppercase,
+            PasswordViolation::MissingDigit,
+            PasswordViolation::MissingSymbol,
+        ]
+    );
+    assert!(policy.check("").contains(&PasswordViolation::TooShort { min_length: 8 }));
+    assert_eq!(policy.check("company2024!"), vec![PasswordViolation::MissingUppercase, PasswordViolation::TooCommon]);
+    assert!(policy.check("Str0ng-enough").is_empty());
+
+    // 内置弱密码总是被拒绝
+    assert!(PasswordPolicy::default().check("password123").contains(&PasswordViolation::TooCommon));
+
+    let zh = MessageCatalog::new(Language::Zh);
+    assert_eq!(PasswordViolation::TooShort { min_length: 8 }.message(&zh), "密码长度至少为 8 个字符");
+}
+
+#[tokio::test]
+async fn test_throttle_delay_lockout_and_unlock() {
+    let clock = Arc::new(ManualClock::new(1_000_000));
+    let throttle = LoginThrottle::new(LoginThrottleConfig::default()).with_clock(clock.clone());
+    let keys = [user_key("Alice")];
+
+    // 前三次失败不限流
+    for _ in 0..2 {
+        throttle.record_failure(&keys).await;
+        assert!(throttle.check(&keys).await.is_none());
+    }
+
+    // 第三次失败后开始指数退避
+    throttle.record_failure(&keys).await;
+    assert_eq!(throttle.check(&keys).await, Some(Duration::from_secs(1)));
+    clock.advance(Duration::from_secs(1));
+    assert!(throttle.check(&keys).await.is_none());
+
+    throttle.record_failure(&keys).await;
+    assert_eq!(throttle.check(&keys).await, Some(Duration::from_secs(2)));
+
+    // 第十次失败后锁定
+    let mut locked = Vec::new();
+    for _ in 4..10 {
+        clock.advance(Duration::from_secs(120));
+        locked = throttle.record_failure(&keys).await;
+    }
+    assert_eq!(locked, vec![user_key("alice")]);
+    clock.advance(Duration::from_secs(120));
+    assert!(throttle.check(&keys).await.unwrap() > Duration::from_secs(60));
+
+    throttle.unlock(&user_key("alice")).await;
+    assert!(throttle.check(&keys).await.is_none());
+    assert!(throttle.failures(&user_key("alice")).await.is_none());
+}
+
+#[tokio::test]
+async fn test_throttle_counter_survives_restart() {
+    let store: Arc<dyn Store> = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let clock = Arc::new(ManualClock::new(1_000_000));
+    let keys = [user_key("bob")];
+
+    let throttle = LoginThrottle::new(LoginThrottleConfig::default())
+        .with_store(store.clone())
+        .with_clock(clock.clone());
+    for _ in 0..3 {
+        throttle.record_failure(&keys).await;
+    }
+
+    let restarted = LoginThrottle::new(LoginThrottleConfig::default())
+        .with_store(store)
+        .with_clock(clock);
+    assert_eq!(restarted.failures(&keys[0]).await.unwrap().failures, 3);
+    assert!(restarted.check(&keys).await.is_some());
+}
+
+async fn spawn_app(state: AppState) -> String {
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr.to_string()
+}
+
+#[tokio::test]
+async fn test_register_rejects_weak_password() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let (message_tx, _) = broadcast::channel(16);
+    let addr = spawn_app(AppState::new(vec![], message_tx, store, JwtService::new("test-secret"))).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/api/auth/register", addr))
+        .json(&json!({ "username": "boss", "password": "", "name": "Boss" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let body: Value = response.json().await.unwrap();
+    let violations = body["violations"].as_array().unwrap();
+    assert!(violations.contains(&json!("Password must be at least 8 characters long")));
+    assert!(violations.contains(&json!("Password must contain a digit")));
+}
+
+#[tokio::test]
+async fn test_login_lockout_and_admin_unlock() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let hash = PasswordService::hash_password("Correct-horse-1").unwrap();
+    store
+        .save_user(&User::new_chairman("boss".to_string(), "Boss".to_string(), hash, None))
+        .await
+        .unwrap();
+
+    let throttle = Arc::new(LoginThrottle::new(LoginThrottleConfig {
+        delay_after: 5,
+        lockout_after: 3,
+        ..LoginThrottleConfig::default()
+    }));
+    let jwt_service = JwtService::new("test-secret");
+    let admin_token = jwt_service
+        .generate_token(&UserInfo {
+            id: "admin".to_string(),
+            username: "admin".to_string(),
+            name: "Admin".to_string(),
+            email: None,
+            is_director: true,
+            employee_id: "00001".to_string(),
+            position: "Chairman".to_string(),
+            department: "board".to_string(),
+        })
+        .unwrap();
+    let (message_tx, _) = broadcast::channel(16);
+    let addr = spawn_app(
+        AppState::new(vec![], message_tx, store, jwt_service).with_login_throttle(throttle),
+    )
+    .await;
+    let client = reqwest::Client::new();
+    let login = |password: &'static str, ip: &'static str| {
+        client
+            .post(format!("http://{}/api/v1/auth/login", addr))
+            .header("x-forwarded-for", ip)
+            .json(&json!({ "username": "boss", "password": password }))
+            .send()
+    };
+
+    // 不存在的用户与密码错误的响应一致
+    let unknown: Value = client
+        .post(format!("http://{}/api/v1/auth/login", addr))
+        .header("x-forwarded-for", "10.0.0.9")
+        .json(&json!({ "username": "ghost", "password": "whatever1" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    let mut wrong = Value::Null;
+    for _ in 0..3 {
+        let response = login("wrong-password-1", "10.0.0.1").await.unwrap();
+        assert_eq!(response.status(), 401);
+        wrong = response.json().await.unwrap();
+    }
+    assert_eq!(unknown, wrong);
+
+    // 账户已锁定：换一个 IP 用正确密码也无法登录
+    let response = login("Correct-horse-1", "10.0.0.2").await.unwrap();
+    assert_eq!(response.status(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+
+    let response = client
+        .post(format!("http://{}/api/v1/admin/users/boss/unlock", addr))
+        .bearer_auth(&admin_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = login("Correct-horse-1", "10.0.0.2").await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["data"]["token"].is_string());
+}
@@ -0,0 +1,186 @@
+//! @提及补全测试：会话成员、同部门、其他人的排序，私有部门和非成员群聊的可见范围，
+//! 以及 Agent 改名后索引随 `OrgChanged` 事件刷新
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+use imitatort::core::events::{CompanyEvent, EventBus};
+use imitatort::core::mentions::{MentionConfig, MentionIndex, MentionKind, MentionQuery};
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::org_changes::save_organization_tracked;
+use imitatort::core::store::Store;
+use imitatort::domain::user::User;
+use imitatort::domain::{Agent, Department, LLMConfig, Organization, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+
+fn agent(id: &str, name: &str, title: &str, department: &str) -> Agent {
+    Agent::new(id, name, Role::simple(title, ""), LLMConfig::openai("test-key")).with_department(department)
+}
+
+fn user(id: &str, name: &str, seq: u32, department: &str) -> User {
+    let mut user = User::new_employee(id.to_string(), name.to_string(), "hash".to_string(), seq, department.to_string(), None);
+    user.id = id.to_string();
+    user
+}
+
+/// alice 在工程部；法务部是私有部门
+async fn seed() -> Arc<SqliteStore> {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering"));
+    org.add_department(Department::top_level("sales", "Sales"));
+    org.add_department(Department::top_level("legal", "Legal"));
+    org.add_agent(agent("dave", "Dave", "Account Manager", "sales"));
+    org.add_agent(agent("devon", "Devon", "SRE", "eng"));
+    org.add_agent(agent("dana", "Dana", "Engineer", "eng"));
+    org.add_agent(agent("derek", "Derek", "Counsel", "legal"));
+    org.add_agent(agent("mia", "Mia", "Sales Rep", "sales"));
+    store.save_organization(&org).await.unwrap();
+    store.save_user(&user("alice", "Alice", 2, "Engineering")).await.unwrap();
+    store.save_user(&user("dora", "Dora", 3, "Sales")).await.unwrap();
+    store
+}
+
+fn index() -> MentionIndex {
+    MentionIndex::new(MentionConfig {
+        private_departments: vec!["legal".to_string()],
+    })
+}
+
+fn token(id: &str) -> String {
+    JwtService::new("secret")
+        .generate_token(&UserInfo {
+            id: id.to_string(),
+            username: id.to_string(),
+            name: id.to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00002".to_string(),
+            position: "Employee".to_string(),
+            department: "Engineering".to_string(),
+        })
+        .unwrap()
+}
+
+async fn serve(store: Arc<SqliteStore>, bus: Arc<MessageBus>) -> std::net::SocketAddr {
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store, JwtService::new("secret"))
+        .with_message_bus(bus)
+        .with_mentions(Arc::new(index()));
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr
+}
+
+fn ids(body: &Value) -> Vec<&str> {
+    body["data"].as_array().unwrap().iter().map(|c| c["id"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn test_suggest_ranks_session_then_department_then_others() {
+    let store = seed().await;
+    let bus = Arc::new(MessageBus::new());
+    for id in ["user:alice", "user:dora"] {
+        bus.register(id);
+    }
+    bus.create_group("launch", "Launch", "user:alice", vec!["user:alice".into(), "dave".into(), "derek".into()])
+        .await
+        .unwrap();
+    bus.create_group("board", "Board", "user:dora", vec!["user:dora".into(), "mia".into()])
+        .await
+        .unwrap();
+    let addr = serve(store, bus).await;
+    let client = reqwest::Client::new();
+    let suggest = |query: &'static str| {
+        let client = client.clone();
+        async move {
+            let response = client
+                .get(format!("http://{}/api/mentions/suggest{}", addr, query))
+                .bearer_auth(token("alice"))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), 200);
+            response.json::<Value>().await.unwrap()
+        }
+    };
+
+    // 同部门的排在前面，私有部门的 Derek 不可见，自己不出现
+    let body = suggest("?q=d").await;
+    assert_eq!(ids(&body), vec!["dana", "devon", "dave", "user:dora"]);
+    assert_eq!(body["data"][0]["type"], "agent");
+    assert_eq!(body["data"][0]["title"], "Engineer");
+    assert_eq!(body["data"][0]["department"], "Engineering");
+    assert_eq!(body["data"][3]["type"], "user");
+    assert_eq!(body["data"][3]["department"], "Sales");
+
+    // 当前群聊的成员最先，包括私有部门的成员
+    let body = suggest("?q=D&session_id=launch").await;
+    assert_eq!(ids(&body), vec!["dave", "derek", "dana", "devon", "user:dora"]);
+
+    // 不是成员的群聊不影响排序，也不暴露其成员
+    let body = suggest("?q=d&session_id=board").await;
+    assert_eq!(ids(&body), vec!["dana", "devon", "dave", "user:dora"]);
+
+    // 私聊会话的对方优先；名称前缀和 @ 都可以
+    let body = suggest("?q=@m&session_id=mia").await;
+    assert_eq!(ids(&body), vec!["mia"]);
+    let body = suggest("?q=de&limit=1").await;
+    assert_eq!(ids(&body), vec!["devon"]);
+
+    let response = client
+        .get(format!("http://{}/api/mentions/suggest?q=d", addr))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+}
+
+#[tokio::test]
+async fn test_index_refreshes_after_agent_rename() {
+    let store = seed().await;
+    let events = EventBus::new();
+    let index = Arc::new(index());
+    index.clone().spawn(&events);
+    let query = |prefix: &str| MentionQuery {
+        viewer: "user:alice".to_string(),
+        prefix: prefix.to_string(),
+        session_members: HashSet::new(),
+        limit: 10,
+    };
+
+    let found = index.suggest(store.as_ref(), &query("dan")).await.unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].name.as_str(), found[0].kind), ("Dana", MentionKind::Agent));
+    index.suggest(store.as_ref(), &query("")).await.unwrap();
+    assert_eq!(index.builds(), 1);
+
+    let mut org = store.load_organization().await.unwrap();
+    org.agents.iter_mut().find(|a| a.id == "dana").unwrap().name = "Zara".to_string();
+    let entry = save_organization_tracked(store.as_ref(), &org, "admin").await.unwrap().unwrap();
+    // 事件到达前仍使用缓存的索引
+    assert_eq!(index.suggest(store.as_ref(), &query("zar")).await.unwrap().len(), 0);
+    events.emit(CompanyEvent::OrgChanged { entry: Arc::new(entry) });
+
+    let mut renamed = Vec::new();
+    for _ in 0..50 {
+        renamed = index.suggest(store.as_ref(), &query("zar")).await.unwrap();
+        if !renamed.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(renamed.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), vec!["dana"]);
+    assert_eq!(index.suggest(store.as_ref(), &query("dana")).await.unwrap()[0].name, "Zara");
+    assert_eq!(index.builds(), 2);
+}
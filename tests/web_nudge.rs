@@ -0,0 +1,133 @@
+//! 插话接口测试：`POST /agents/{id}/nudge` 需要登录、工具循环中放入插话槽、否则转为 Urgent 私聊消息，
+//! 没有工具循环时只有 cancel 的插话返回 409
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use imitatort::core::nudge::{NudgeSlots, NUDGE_KEY};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::domain::{MessagePriority, MessageTarget};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, LLMConfig, Message, Organization, Role};
+
+struct Server {
+    base: String,
+    token: String,
+    slots: Arc<NudgeSlots>,
+    messages: broadcast::Receiver<Message>,
+}
+
+async fn spawn_server() -> Server {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let mut org = Organization::new();
+    org.add_agent(Agent::new("dev", "Dev", Role::simple("Engineer", "prompt"), LLMConfig::openai("k")));
+    store.save_organization(&org).await.unwrap();
+
+    let jwt_service = JwtService::new("test-secret");
+    let token = jwt_service
+        .generate_token(&UserInfo {
+            id: "alice".to_string(),
+            username: "alice".to_string(),
+            name: "Alice".to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00002".to_string(),
+            position: "Employee".to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap();
+    let (message_tx, messages) = broadcast::channel(16);
+    let slots = Arc::new(NudgeSlots::new());
+    let state = AppState::new(org.agents.clone(), message_tx, store, jwt_service).with_nudges(slots.clone());
+    let app = create_router(Arc::new(state));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    Server { base: format!("http://{}/api/v1", addr), token, slots, messages }
+}
+
+#[tokio::test]
+async fn test_nudge_validation() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/agents/dev/nudge", server.base);
+
+    let response = client.post(&url).json(&json!({"content": "hi"})).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let response = client.post(&url).bearer_auth(&server.token).json(&json!({"content": "  "})).send().await.unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .post(format!("{}/agents/ghost/nudge", server.base))
+        .bearer_auth(&server.token)
+        .json(&json!({"content": "hi"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // 没有工具循环时没有可结束的工具调用
+    let response = client.post(&url).bearer_auth(&server.token).json(&json!({"cancel": true})).send().await.unwrap();
+    assert_eq!(response.status(), 409);
+}
+
+#[tokio::test]
+async fn test_nudge_is_queued_during_tool_loop() {
+    let server = spawn_server().await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/agents/dev/nudge", server.base);
+    server.slots.set_tool_loop("dev", true);
+
+    for (content, position) in [("Use the EU data", 1), ("Stop here", 2)] {
+        let body: Value = client
+            .post(&url)
+            .bearer_auth(&server.token)
+            .json(&json!({"content": content, "cancel": position == 2}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["data"]["delivery"], "nudge");
+        assert_eq!(body["data"]["position"], position);
+    }
+
+    let queued = server.slots.take("dev");
+    assert_eq!(queued.len(), 2);
+    assert_eq!(queued[0].from, "user:alice");
+    assert_eq!(queued[0].content, "Use the EU data");
+    assert!(!queued[0].cancel);
+    assert!(queued[1].cancel);
+}
+
+#[tokio::test]
+async fn test_nudge_without_tool_loop_becomes_urgent_message() {
+    let mut server = spawn_server().await;
+    let body: Value = reqwest::Client::new()
+        .post(format!("{}/agents/dev/nudge", server.base))
+        .bearer_auth(&server.token)
+        .json(&json!({"content": "Use the EU data"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["delivery"], "message");
+
+    let message = server.messages.try_recv().unwrap();
+    assert_eq!(body["data"]["message_id"], message.id.as_str());
+    assert_eq!(message.from, "user:alice");
+    assert_eq!(message.to, MessageTarget::Direct("dev".to_string()));
+    assert_eq!(message.content, "Use the EU data");
+    assert_eq!(message.priority, MessagePriority::Urgent);
+    assert_eq!(message.metadata(NUDGE_KEY), body["data"]["id"].as_str());
+}
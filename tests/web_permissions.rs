@@ -0,0 +1,254 @@
+//! 细粒度权限测试：部门负责人只能管理本部门（含下级部门）的 Agent、按用户授权、
+//! GET /auth/permissions、令牌中权限摘要过期和职位以存储为准
+
+use std::sync::Arc;
+
+use imitatort::core::store::Store;
+use imitatort::domain::user::{Position, User};
+use imitatort::infrastructure::auth::{Grant, JwtService, Permission, PermissionConfig, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState};
+use imitatort::{Agent, Department, LLMConfig, Organization, Role};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+fn user(id: &str, position: &str) -> UserInfo {
+    UserInfo {
+        id: id.to_string(),
+        username: id.to_string(),
+        name: id.to_uppercase(),
+        email: None,
+        is_director: false,
+        employee_id: "00001".to_string(),
+        position: position.to_string(),
+        department: "eng".to_string(),
+    }
+}
+
+fn agent(id: &str, dept: &str) -> Agent {
+    Agent::new(id, id, Role::simple("Engineer", "You code"), LLMConfig::openai("k")).with_department(dept)
+}
+
+/// `lead` 负责 eng（下有 web），`ops` 部门没有负责人
+fn org() -> Organization {
+    let mut org = Organization::new();
+    org.add_department(Department::top_level("eng", "Engineering").with_leader("lead"));
+    org.add_department(Department::child("web", "Web", "eng"));
+    org.add_department(Department::top_level("ops", "Operations"));
+    org.add_agent(agent("dev", "web"));
+    org.add_agent(agent("sre", "ops"));
+    org
+}
+
+struct TestServer {
+    base: String,
+    jwt: JwtService,
+    store: Arc<SqliteStore>,
+}
+
+impl TestServer {
+    fn token(&self, id: &str, position: &str) -> String {
+        self.jwt.generate_token(&user(id, position)).unwrap()
+    }
+}
+
+async fn spawn_server(permissions: PermissionConfig) -> TestServer {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    let org = org();
+    store.save_organization(&org).await.unwrap();
+
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(org.agents.clone(), message_tx, store.clone(), jwt.clone()).with_permissions(permissions);
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    TestServer { base: format!("http://{}", addr), jwt, store }
+}
+
+#[tokio::test]
+async fn test_department_leader_is_scoped_to_own_department() {
+    let server = spawn_server(PermissionConfig::default()).await;
+    let client = reqwest::Client::new();
+    let lead = server.token("lead", "Employee");
+
+    // 下级部门的 Agent 也在负责人的范围内
+    let response = client
+        .put(format!("{}/api/v1/agents/dev/availability", server.base))
+        .bearer_auth(&lead)
+        .json(&Value::Null)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let response = client
+        .put(format!("{}/api/v1/agents/dev/role", server.base))
+        .bearer_auth(&lead)
+        .json(&json!({ "title": "Senior Engineer" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // 其他部门的 Agent 不行
+    let response = client
+        .put(format!("{}/api/v1/agents/sre/availability", server.base))
+        .bearer_auth(&lead)
+        .json(&Value::Null)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = client
+        .put(format!("{}/api/v1/agents/sre/role", server.base))
+        .bearer_auth(&lead)
+        .json(&json!({ "title": "Senior Operator" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    let response = client
+        .get(format!("{}/api/v1/agents/sre/role/history", server.base))
+        .bearer_auth(&lead)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    // 负责人权限不包括全局管理接口
+    let response = client
+        .get(format!("{}/api/v1/admin/users", server.base))
+        .bearer_auth(&lead)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    // 管理层默认不限部门
+    let response = client
+        .put(format!("{}/api/v1/agents/sre/availability", server.base))
+        .bearer_auth(server.token("boss", "Management"))
+        .json(&Value::Null)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+}
+
+#[tokio::test]
+async fn test_configured_grants() {
+    let permissions = PermissionConfig::default()
+        .with_position("Management", vec![Grant::global(Permission::ViewAudit)])
+        .with_user("ops-lead", vec![Grant::scoped(Permission::ManageAgents, "ops")]);
+    let server = spawn_server(permissions).await;
+    let client = reqwest::Client::new();
+
+    let manager = server.token("boss", "Management");
+    let response = client.get(format!("{}/api/v1/org/changes", server.base)).bearer_auth(&manager).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let response = client.get(format!("{}/api/v1/admin/users", server.base)).bearer_auth(&manager).send().await.unwrap();
+    assert_eq!(response.status(), 403, "configured grants replace the position defaults");
+
+    let ops_lead = server.token("ops-lead", "Employee");
+    for (agent_id, status) in [("sre", 200), ("dev", 403)] {
+        let response = client
+            .put(format!("{}/api/v1/agents/{}/availability", server.base, agent_id))
+            .bearer_auth(&ops_lead)
+            .json(&Value::Null)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "{}", agent_id);
+    }
+}
+
+#[tokio::test]
+async fn test_get_my_permissions() {
+    let server = spawn_server(PermissionConfig::default()).await;
+    let client = reqwest::Client::new();
+
+    let response = client.get(format!("{}/api/v1/auth/permissions", server.base)).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let body: Value = client
+        .get(format!("{}/api/v1/auth/permissions", server.base))
+        .bearer_auth(server.token("lead", "Employee"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["position"], "Employee");
+    assert_eq!(body["data"]["permissions"], json!(["manage_agents", "inspect_agents"]));
+    assert_eq!(
+        body["data"]["grants"],
+        json!([
+            { "permission": "manage_agents", "department": "eng" },
+            { "permission": "inspect_agents", "department": "eng" },
+        ])
+    );
+    assert_eq!(body["data"]["hash"].as_str().unwrap().len(), 16);
+
+    let body: Value = client
+        .get(format!("{}/api/v1/auth/permissions", server.base))
+        .bearer_auth(server.token("chair", "Chairman"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["permissions"].as_array().unwrap().len(), Permission::ALL.len());
+    assert!(body["data"]["grants"].as_array().unwrap().iter().all(|g| g.get("department").is_none()));
+}
+
+#[tokio::test]
+async fn test_stale_permissions_hash_requires_login() {
+    let server = spawn_server(PermissionConfig::default()).await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/v1/agents/dev/role/history", server.base);
+
+    let current: Value = client
+        .get(format!("{}/api/v1/auth/permissions", server.base))
+        .bearer_auth(server.token("lead", "Employee"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let hash = current["data"]["hash"].as_str().unwrap();
+    let fresh = server.jwt.generate_token_with_permissions(&user("lead", "Employee"), hash).unwrap();
+    let response = client.get(&url).bearer_auth(&fresh).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let stale = server.jwt.generate_token_with_permissions(&user("lead", "Employee"), "0000000000000000").unwrap();
+    let response = client.get(&url).bearer_auth(&stale).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("log in again"), "{}", body);
+}
+
+#[tokio::test]
+async fn test_position_is_read_from_store() {
+    let server = spawn_server(PermissionConfig::default()).await;
+    let client = reqwest::Client::new();
+    let url = format!("{}/api/v1/agents/sre/availability", server.base);
+
+    // 令牌签发后被降为普通员工，令牌中的 Management 不再有效
+    let mut demoted = User::new_employee("boss".into(), "Boss".into(), "hash".into(), 2, "eng".into(), None);
+    demoted.id = "boss".to_string();
+    server.store.save_user(&demoted).await.unwrap();
+    let token = server.token("boss", "Management");
+    let response = client.put(&url).bearer_auth(&token).json(&Value::Null).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    demoted.position = Position::Management;
+    server.store.save_user(&demoted).await.unwrap();
+    let response = client.put(&url).bearer_auth(&token).json(&Value::Null).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+}
@@ -0,0 +1,82 @@
+//! 运行时信息测试：`/api/info` 的字段、配置哈希不包含 API 密钥
+
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use imitatort::core::runtime_info::{redacted_config, BuildInfo, ConfigSource};
+use imitatort::core::store::MemoryStore;
+use imitatort::{build_company_router, Agent, CompanyConfig, LLMConfig, Organization, Role, RouterOptions, VirtualCompany};
+
+const API_KEY: &str = "sk-live-9f8e7d6c5b4a";
+
+fn config(name: &str) -> CompanyConfig {
+    let mut org = Organization::new();
+    org.add_agent(Agent::new(
+        "dev",
+        "Dev",
+        Role::simple("Developer", "You are a developer"),
+        LLMConfig::openai(API_KEY),
+    ));
+    CompanyConfig::new(name, org)
+}
+
+#[test]
+fn test_config_hash_source_never_contains_secrets() {
+    let config = config("Acme");
+    let source = redacted_config(&config);
+    assert!(!source.contains(API_KEY));
+    assert!(source.contains("\"api_key\":\"***\""));
+    assert!(source.contains("Developer"));
+
+    // 只有密钥不同的配置哈希相同，其他字段变化时哈希变化
+    let mut rotated = config.clone();
+    rotated.organization.agents[0].llm_config.api_key = "sk-other".to_string();
+    let hash = ConfigSource::new(None, &config).sha256;
+    assert_eq!(hash.len(), 64);
+    assert_eq!(ConfigSource::new(None, &rotated).sha256, hash);
+    assert_ne!(ConfigSource::new(None, &self::config("Other")).sha256, hash);
+}
+
+#[tokio::test]
+async fn test_info_endpoint_reports_build_store_and_config() {
+    let config = config("Acme");
+    let source = ConfigSource::new(Some(Path::new("company_config.yaml")), &config);
+    let company = VirtualCompany::with_store(config, Arc::new(MemoryStore::new()))
+        .with_config_source(source.clone())
+        .with_data_dir("/var/lib/imitatort");
+
+    let info = company.runtime_info().await;
+    assert_eq!(info.build, BuildInfo::current());
+    assert_eq!(info.company, "Acme");
+    assert_eq!(info.agent_count, 1);
+    assert_eq!(info.config, Some(source.clone()));
+    assert_eq!(info.flags, company.features().states());
+
+    let router = build_company_router(&company, RouterOptions::default());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+
+    let response = reqwest::get(format!("http://{}/api/info", addr)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let text = response.text().await.unwrap();
+    assert!(!text.contains(API_KEY));
+
+    let body: Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["git_commit"].as_str().is_some_and(|c| !c.is_empty()));
+    assert!(body["build_timestamp"].as_str().is_some_and(|t| !t.is_empty()));
+    assert!(body["features"].is_array());
+    assert_eq!(body["company"], "Acme");
+    assert_eq!(body["agent_count"], 1);
+    assert_eq!(body["store"]["backend"], "memory");
+    assert_eq!(body["data_dir"], "/var/lib/imitatort");
+    assert!(body["config"]["path"].as_str().unwrap().ends_with("company_config.yaml"));
+    assert_eq!(body["config"]["sha256"], source.sha256.as_str());
+    assert_eq!(body["flags"]["email"], false);
+    assert_eq!(body["flags"]["temp_agents"], true);
+}
@@ -0,0 +1,136 @@
+//! 运行草稿接口测试：`GET /runs/{run_id}/scratchpad` 的权限和返回内容、WebSocket 订阅后实时推送草稿帧
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+use imitatort::core::scratchpad::{Scratchpad, ScratchpadConfig};
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::domain::scratchpad::ScratchpadSource;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::web::{create_router, AppState};
+
+struct Server {
+    addr: std::net::SocketAddr,
+    jwt_service: JwtService,
+    scratchpad: Arc<Scratchpad>,
+}
+
+async fn spawn_server(with_scratchpad: bool) -> Server {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let config = ScratchpadConfig { enabled: true, ..Default::default() };
+    let scratchpad = Arc::new(Scratchpad::new(config, store.clone()));
+    let jwt_service = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store, jwt_service.clone());
+    let state = if with_scratchpad { state.with_scratchpad(scratchpad.clone()) } else { state };
+    let app = create_router(Arc::new(state));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    Server { addr, jwt_service, scratchpad }
+}
+
+fn token(jwt_service: &JwtService, position: &str) -> String {
+    jwt_service
+        .generate_token(&UserInfo {
+            id: "u1".to_string(),
+            username: "u1".to_string(),
+            name: "U1".to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: position.to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+}
+
+async fn next_frame(
+    socket: &mut tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+) -> Value {
+    let frame = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+    serde_json::from_str(frame.to_text().unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_run_scratchpad_requires_inspect_agents() {
+    let server = spawn_server(true).await;
+    server.scratchpad.write("run-1", "dev", ScratchpadSource::Tool, "plan A").await.unwrap();
+    server.scratchpad.write("run-1", "dev", ScratchpadSource::ToolTurn, "plan B").await.unwrap();
+    let url = format!("http://{}/api/v1/runs/run-1/scratchpad", server.addr);
+    let client = reqwest::Client::new();
+
+    let response = client.get(&url).bearer_auth(token(&server.jwt_service, "Employee")).send().await.unwrap();
+    assert_eq!(response.status(), 403);
+
+    let body: Value = client
+        .get(&url)
+        .bearer_auth(token(&server.jwt_service, "Management"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["run_id"], "run-1");
+    let entries = body["data"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["content"], "plan A");
+    assert_eq!(entries[1]["source"], "tool_turn");
+
+    let unavailable = spawn_server(false).await;
+    let response = client
+        .get(format!("http://{}/api/v1/runs/run-1/scratchpad", unavailable.addr))
+        .bearer_auth(token(&unavailable.jwt_service, "Management"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 503);
+}
+
+#[tokio::test]
+async fn test_scratchpad_frames_reach_subscribed_inspectors() {
+    let server = spawn_server(true).await;
+
+    // 没有 inspect_agents 权限的订阅被拒绝
+    let url = format!("ws://{}/api/v1/ws?token={}", server.addr, token(&server.jwt_service, "Employee"));
+    let (mut employee, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    employee
+        .send(WsMessage::Text(json!({ "type": "subscribe_scratchpad" }).to_string()))
+        .await
+        .unwrap();
+    assert_eq!(next_frame(&mut employee).await["type"], "error");
+
+    let url = format!("ws://{}/api/v1/ws?token={}", server.addr, token(&server.jwt_service, "Management"));
+    let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+    socket
+        .send(WsMessage::Text(json!({ "type": "subscribe_scratchpad", "agent_id": "dev" }).to_string()))
+        .await
+        .unwrap();
+    // pong 说明订阅已处理
+    socket.send(WsMessage::Text(json!({ "type": "ping" }).to_string())).await.unwrap();
+    assert_eq!(next_frame(&mut socket).await["type"], "pong");
+
+    // 其他 Agent 的草稿被过滤
+    server.scratchpad.write("run-0", "other", ScratchpadSource::Tool, "not mine").await.unwrap();
+    let entry = server.scratchpad.write("run-1", "dev", ScratchpadSource::Tool, "plan A").await.unwrap();
+
+    let frame = next_frame(&mut socket).await;
+    assert_eq!(frame["type"], "scratchpad", "{}", frame);
+    assert_eq!(frame["v"], 1);
+    assert_eq!(frame["data"]["id"], entry.id.as_str());
+    assert_eq!(frame["data"]["run_id"], "run-1");
+    assert_eq!(frame["data"]["content"], "plan A");
+
+    // 未订阅的员工连接收不到草稿
+    employee.send(WsMessage::Text(json!({ "type": "ping" }).to_string())).await.unwrap();
+    assert_eq!(next_frame(&mut employee).await["type"], "pong");
+}
@@ -0,0 +1,110 @@
+//! Web 服务器部署配置测试：CORS 白名单、请求体上限、路径前缀
+
+use std::sync::Arc;
+
+use tokio::sync::broadcast;
+
+use imitatort::core::store::MemoryStore;
+use imitatort::domain::Message;
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::web::{create_router_with_config, AppState, WebServerConfig};
+
+async fn spawn_server(config: WebServerConfig) -> String {
+    let (message_tx, _) = broadcast::channel::<Message>(100);
+    let state = AppState::new(
+        Vec::new(),
+        message_tx,
+        Arc::new(MemoryStore::new()),
+        JwtService::new("test-secret-for-testing"),
+    );
+    let app = create_router_with_config(Arc::new(state), &config);
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+async fn preflight(base: &str, origin: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .request(reqwest::Method::OPTIONS, format!("{}/api/v1/auth/login", base))
+        .header("Origin", origin)
+        .header("Access-Control-Request-Method", "POST")
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_cors_allow_list() {
+    let base = spawn_server(WebServerConfig {
+        allowed_origins: vec!["https://app.example.com".to_string()],
+        ..WebServerConfig::default()
+    })
+    .await;
+
+    let allowed = preflight(&base, "https://app.example.com").await;
+    assert_eq!(
+        allowed.headers().get("access-control-allow-origin").unwrap(),
+        "https://app.example.com"
+    );
+
+    let denied = preflight(&base, "https://evil.example.com").await;
+    assert!(denied.headers().get("access-control-allow-origin").is_none());
+}
+
+#[tokio::test]
+async fn test_cors_permissive_by_default() {
+    let base = spawn_server(WebServerConfig::default()).await;
+
+    let response = preflight(&base, "http://localhost:5173").await;
+    assert_eq!(response.headers().get("access-control-allow-origin").unwrap(), "*");
+}
+
+#[tokio::test]
+async fn test_oversized_body_rejected() {
+    let base = spawn_server(WebServerConfig {
+        max_body_bytes: 1024,
+        ..WebServerConfig::default()
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    let oversized = serde_json::json!({ "username": "a".repeat(4096), "password": "x" });
+    let response = client
+        .post(format!("{}/api/v1/auth/login", base))
+        .json(&oversized)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 413);
+
+    // 未超限的请求正常进入处理器
+    let small = serde_json::json!({ "username": "nobody", "password": "x" });
+    let response = client
+        .post(format!("{}/api/v1/auth/login", base))
+        .json(&small)
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(response.status(), 413);
+}
+
+#[tokio::test]
+async fn test_base_path_prefix() {
+    let config = WebServerConfig {
+        base_path: "/imitatort/".to_string(),
+        ..WebServerConfig::default()
+    };
+    assert_eq!(config.normalized_base_path(), "/imitatort");
+
+    let base = spawn_server(config).await;
+
+    let response = reqwest::get(format!("{}/imitatort/api/v1/health", base)).await.unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = reqwest::get(format!("{}/api/v1/health", base)).await.unwrap();
+    assert_eq!(response.status(), 404);
+}
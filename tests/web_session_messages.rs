@@ -0,0 +1,258 @@
+//! 会话消息分页测试：双方向的私聊都返回、同一秒内的消息按ID稳定排序、翻页不重复不遗漏、
+//! 偏移分页在两次请求之间顺序稳定、`has_more`、游标不存在时返回 404、v1 响应头携带游标和 `has_more`、
+//! 超大偏移返回 400 且不会溢出，只返回当前用户与该 Agent 之间的私聊
+
+use std::sync::Arc;
+
+use imitatort::core::store::{BufferConfig, BufferedStore, MemoryStore, MessageCursor, MessageFilter, Store};
+use imitatort::domain::Message;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::{create_router, AppState, HAS_MORE_HEADER, NEXT_CURSOR_HEADER};
+use serde_json::Value;
+use tokio::sync::broadcast;
+
+const ALICE: &str = "user:alice";
+
+fn message(id: &str, from: &str, to: &str, timestamp: i64) -> Message {
+    let mut message = Message::private(from, to, format!("{} says {}", from, id));
+    message.id = id.to_string();
+    message.timestamp = timestamp;
+    message
+}
+
+/// dev 与 alice 交替发言，m3–m5 在同一秒；另有与 dev 无关的私聊和 dev 的群消息
+fn seed() -> Vec<Message> {
+    let mut messages = vec![
+        message("m1", ALICE, "dev", 100),
+        message("m2", "dev", ALICE, 101),
+        message("m5", "dev", ALICE, 102),
+        message("m3", ALICE, "dev", 102),
+        message("m4", "dev", ALICE, 102),
+        message("m6", ALICE, "dev", 103),
+        message("m7", "dev", ALICE, 104),
+        message("other", "ops", ALICE, 102),
+    ];
+    messages.push({
+        let mut group = Message::group("dev", "eng-room", "hi team");
+        group.timestamp = 102;
+        group
+    });
+    messages
+}
+
+fn ids(messages: &[Message]) -> Vec<&str> {
+    messages.iter().map(|m| m.id.as_str()).collect()
+}
+
+#[tokio::test]
+async fn test_conversation_filter_pages_in_stable_order() {
+    let stores: Vec<Arc<dyn Store>> = vec![Arc::new(MemoryStore::new()), Arc::new(SqliteStore::new_in_memory().unwrap())];
+    for store in stores {
+        store.save_messages(&seed()).await.unwrap();
+        let conversation = || MessageFilter::new().participant("dev").target_type("direct").stable_order();
+
+        let all = store.load_messages(conversation().oldest_first()).await.unwrap();
+        assert_eq!(ids(&all), vec!["m1", "m2", "m3", "m4", "m5", "m6", "m7"]);
+
+        // 从最新的一页开始向前翻，游标落在同一秒的消息中间
+        let page = store.load_messages(conversation().limit(4)).await.unwrap();
+        assert_eq!(ids(&page), vec!["m7", "m6", "m5", "m4"]);
+        let cursor = MessageCursor::at(page.last().unwrap());
+        let page = store.load_messages(conversation().before(cursor).limit(4)).await.unwrap();
+        assert_eq!(ids(&page), vec!["m3", "m2", "m1"]);
+
+        // 偏移分页的两页首尾相接，同一秒内的消息不重复也不遗漏
+        let first = store.load_messages(conversation().offset(0).limit(3)).await.unwrap();
+        let second = store.load_messages(conversation().offset(3).limit(3)).await.unwrap();
+        assert_eq!(ids(&first), vec!["m7", "m6", "m5"]);
+        assert_eq!(ids(&second), vec!["m4", "m3", "m2"]);
+        assert!(store.load_messages(conversation().offset(10)).await.unwrap().is_empty());
+        // 超出 i64 的偏移不会变成负数（SQLite 会当作 0 返回最新一页）
+        assert!(store.load_messages(conversation().offset(usize::MAX)).await.unwrap().is_empty());
+    }
+
+    // 缓冲存储按 limit + offset 向下层取数，超大偏移不会溢出
+    let buffered = BufferedStore::new(MemoryStore::new(), BufferConfig::default()).unwrap();
+    buffered.save_messages(&seed()).await.unwrap();
+    assert!(buffered.load_messages(MessageFilter::new().offset(usize::MAX)).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_between_filter_excludes_third_party_dms() {
+    let stores: Vec<Arc<dyn Store>> = vec![Arc::new(MemoryStore::new()), Arc::new(SqliteStore::new_in_memory().unwrap())];
+    for store in stores {
+        store.save_messages(&seed()).await.unwrap();
+        store.save_messages(&third_party_dms()).await.unwrap();
+
+        let conversation = store
+            .load_messages(MessageFilter::new().between(ALICE, "dev").stable_order().oldest_first())
+            .await
+            .unwrap();
+        assert_eq!(ids(&conversation), vec!["m1", "m2", "m3", "m4", "m5", "m6", "m7"]);
+        let peers = store.load_messages(MessageFilter::new().between("dev", "ops")).await.unwrap();
+        assert_eq!(ids(&peers), vec!["peer-2", "peer-1"]);
+    }
+}
+
+/// dev 与其他 Agent、其他用户之间的私聊，不属于 alice 的会话
+fn third_party_dms() -> Vec<Message> {
+    vec![
+        message("peer-1", "ops", "dev", 105),
+        message("peer-2", "dev", "ops", 106),
+        message("bob-1", "user:bob", "dev", 107),
+    ]
+}
+
+fn token(id: &str) -> String {
+    JwtService::new("secret")
+        .generate_token(&UserInfo {
+            id: id.to_string(),
+            username: id.to_string(),
+            name: id.to_string(),
+            email: None,
+            is_director: false,
+            employee_id: "00001".to_string(),
+            position: "Employee".to_string(),
+            department: "eng".to_string(),
+        })
+        .unwrap()
+}
+
+async fn serve(store: Arc<SqliteStore>) -> std::net::SocketAddr {
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store, JwtService::new("secret"));
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr
+}
+
+#[tokio::test]
+async fn test_session_messages_endpoint_paginates_both_directions() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    store.save_messages(&seed()).await.unwrap();
+    store.save_messages(&third_party_dms()).await.unwrap();
+    let addr = serve(store.clone()).await;
+    let client = reqwest::Client::new();
+    let alice = token("alice");
+    let fetch = |query: String| {
+        let client = client.clone();
+        let alice = alice.clone();
+        async move {
+            let response = client
+                .get(format!("http://{}/api/chat/dev/messages{}", addr, query))
+                .bearer_auth(alice)
+                .send()
+                .await
+                .unwrap();
+            let status = response.status().as_u16();
+            (status, response.json::<Value>().await.unwrap())
+        }
+    };
+    let page_ids = |body: &Value| -> Vec<String> {
+        body["data"].as_array().unwrap().iter().map(|m| m["id"].as_str().unwrap().to_string()).collect()
+    };
+
+    // 默认一页即全部，按时间升序，双方的消息都在；dev 与 ops、bob 的私聊不在
+    let (_, body) = fetch(String::new()).await;
+    assert_eq!(page_ids(&body), vec!["m1", "m2", "m3", "m4", "m5", "m6", "m7"]);
+    assert_eq!(body["next_cursor"], Value::Null);
+    assert_eq!(body["has_more"], false);
+    assert_eq!(body["data"][0]["sender"]["isAgent"], false);
+    assert_eq!(body["data"][1]["sender"]["isAgent"], true);
+
+    let mut pages = Vec::new();
+    let mut query = "?limit=3".to_string();
+    loop {
+        let (status, body) = fetch(query).await;
+        assert_eq!(status, 200);
+        pages.push(page_ids(&body));
+        match body["next_cursor"].as_str() {
+            Some(cursor) => query = format!("?limit=3&before={}", cursor),
+            None => break,
+        }
+    }
+    assert_eq!(pages, vec![vec!["m5", "m6", "m7"], vec!["m2", "m3", "m4"], vec!["m1"]]);
+
+    // 按偏移翻页得到同样的页
+    let (_, body) = fetch("?limit=3&offset=3".to_string()).await;
+    assert_eq!(page_ids(&body), vec!["m2", "m3", "m4"]);
+    assert_eq!(body["has_more"], true);
+    let (_, body) = fetch("?limit=3&offset=6".to_string()).await;
+    assert_eq!(page_ids(&body), vec!["m1"]);
+    assert_eq!(body["has_more"], false);
+    let (_, body) = fetch("?limit=2&before=m5&offset=1".to_string()).await;
+    assert_eq!(page_ids(&body), vec!["m2", "m3"]);
+
+    // 新消息到达不影响已有游标指向的更早页
+    store.save_message(&message("m8", ALICE, "dev", 104)).await.unwrap();
+    let (_, body) = fetch("?limit=3&before=m5".to_string()).await;
+    assert_eq!(page_ids(&body), vec!["m2", "m3", "m4"]);
+
+    let (status, _) = fetch("?before=missing".to_string()).await;
+    assert_eq!(status, 404);
+
+    let response = client
+        .get(format!("http://{}/api/v1/chat/dev/messages?limit=2", addr))
+        .bearer_auth(&alice)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()[NEXT_CURSOR_HEADER], "m7");
+    let body: Value = response.json().await.unwrap();
+    let ids: Vec<&str> = body["data"].as_array().unwrap().iter().map(|m| m["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec!["m7", "m8"]);
+
+    // 需要登录，不能按 Agent 读取他人的会话
+    let response = client.get(format!("http://{}/api/chat/dev/messages", addr)).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+    let body: Value = client
+        .get(format!("http://{}/api/chat/dev/messages", addr))
+        .bearer_auth(token("bob"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page_ids(&body), vec!["bob-1"]);
+}
+
+#[tokio::test]
+async fn test_v1_session_messages_carry_has_more_and_cap_offset() {
+    let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+    store.save_messages(&seed()).await.unwrap();
+    let addr = serve(store).await;
+    let client = reqwest::Client::new();
+    let fetch = |query: &str| {
+        client
+            .get(format!("http://{}/api/v1/chat/dev/messages{}", addr, query))
+            .bearer_auth(token("alice"))
+            .send()
+    };
+
+    // v1 信封只保留 `data`，分页信息在响应头中
+    let response = fetch("?limit=3&offset=3").await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()[HAS_MORE_HEADER], "true");
+    assert_eq!(response.headers()[NEXT_CURSOR_HEADER], "m2");
+    let body: Value = response.json().await.unwrap();
+    let ids: Vec<&str> = body["data"].as_array().unwrap().iter().map(|m| m["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec!["m2", "m3", "m4"]);
+
+    let response = fetch("?limit=3&offset=6").await.unwrap();
+    assert_eq!(response.headers()[HAS_MORE_HEADER], "false");
+    assert!(response.headers().get(NEXT_CURSOR_HEADER).is_none());
+
+    // 超过上限的偏移返回 400，而不是溢出或悄悄返回最新一页
+    let response = fetch("?offset=10001").await.unwrap();
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("10000"), "{}", body);
+    assert_eq!(fetch(&format!("?offset={}", usize::MAX)).await.unwrap().status(), 400);
+    assert_eq!(fetch("?offset=10000").await.unwrap().status(), 200);
+}
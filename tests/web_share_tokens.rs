@@ -0,0 +1,208 @@
+//! 群聊分享令牌测试：作用域限制、过期、撤销对 SSE 流立即生效、访问计数和凭证类型隔离
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use imitatort::core::clock::ManualClock;
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::Store;
+use imitatort::domain::user::user_principal;
+use imitatort::domain::{Agent, LLMConfig, Message, Role};
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::store::SqliteStore;
+use imitatort::infrastructure::web::share::share_token_id;
+use imitatort::infrastructure::web::{create_router, AppState};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+const START: i64 = 1_700_000_000;
+
+struct Server {
+    url: String,
+    store: Arc<SqliteStore>,
+    bus: Arc<MessageBus>,
+    clock: Arc<ManualClock>,
+    jwt_service: JwtService,
+    client: reqwest::Client,
+}
+
+impl Server {
+    async fn start() -> Self {
+        let store = Arc::new(SqliteStore::new_in_memory().unwrap());
+        let clock = Arc::new(ManualClock::new(START));
+        let bus = Arc::new(MessageBus::with_store(store.clone()).with_clock(clock.clone()));
+        let (_lead, _dev) = (bus.register("lead"), bus.register("dev"));
+        let members = vec!["lead".to_string(), "dev".to_string(), user_principal("alice")];
+        bus.create_group("launch", "Launch", "lead", members.clone()).await.unwrap();
+        bus.create_group("payroll", "Payroll", "lead", members).await.unwrap();
+        bus.add_admin("launch", "lead", &user_principal("alice")).await.unwrap();
+
+        let jwt_service = JwtService::new("test-secret");
+        let (message_tx, _) = broadcast::channel(16);
+        let agents = vec![
+            Agent::new("lead", "Lead Li", Role::simple("Lead", "You lead"), LLMConfig::openai("k")),
+            Agent::new("dev", "Dev Du", Role::simple("Engineer", "You code"), LLMConfig::openai("k")),
+        ];
+        let state = AppState::new(agents, message_tx, store.clone(), jwt_service.clone())
+            .with_message_bus(bus.clone())
+            .with_clock(clock.clone());
+        let app = create_router(Arc::new(state));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        Self { url: format!("http://{}/api/v1", addr), store, bus, clock, jwt_service, client: reqwest::Client::new() }
+    }
+
+    fn token(&self, id: &str) -> String {
+        self.jwt_service
+            .generate_token(&UserInfo {
+                id: id.to_string(),
+                username: id.to_string(),
+                name: id.to_string(),
+                email: None,
+                is_director: false,
+                employee_id: "00001".to_string(),
+                position: "Employee".to_string(),
+                department: "eng".to_string(),
+            })
+            .unwrap()
+    }
+
+    async fn share(&self, group_id: &str, body: Value) -> reqwest::Response {
+        self.client
+            .post(format!("{}/groups/{}/share", self.url, group_id))
+            .bearer_auth(self.token("alice"))
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+    }
+
+    async fn share_token(&self, group_id: &str, body: Value) -> String {
+        let response = self.share(group_id, body).await;
+        assert_eq!(response.status(), 201);
+        let body: Value = response.json().await.unwrap();
+        body["data"]["token"].as_str().unwrap().to_string()
+    }
+}
+
+#[tokio::test]
+async fn test_token_scoped_to_one_group() {
+    let server = Server::start().await;
+    let sent = [("lead", "launch", "ship friday"), ("dev", "launch", "tests green"), ("lead", "payroll", "salaries")];
+    for (i, (from, group, content)) in sent.into_iter().enumerate() {
+        let mut message = Message::group(from, group, content);
+        message.timestamp = START - 100 + i as i64;
+        server.bus.send(message).await.unwrap();
+    }
+
+    // 只有管理员或群管理员可以分享
+    let response = server
+        .client
+        .post(format!("{}/groups/launch/share", server.url))
+        .bearer_auth(server.token("bob"))
+        .json(&json!({ "expires_in_secs": 3600 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+    assert_eq!(server.share("payroll", json!({ "expires_in_secs": 3600 })).await.status(), 403);
+    assert_eq!(server.share("launch", json!({ "expires_in_secs": 0 })).await.status(), 400);
+
+    let token = server.share_token("launch", json!({ "expires_in_secs": 3600 })).await;
+    let body: Value = server.client.get(format!("{}/shared/{}", server.url, token)).send().await.unwrap().json().await.unwrap();
+    let messages = body["data"]["messages"].as_array().unwrap();
+    assert_eq!(body["data"]["group_name"], "Launch");
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0]["content"], "ship friday");
+    assert_eq!(messages[0]["sender_name"], "Lead Li");
+    assert_eq!(messages[1]["sender_name"], "Dev Du");
+
+    // 群 A 的令牌不能读群 B，也不能用作其他接口的凭证
+    for path in ["/chat/payroll/messages", "/chat/list", "/agents", "/groups/payroll/share"] {
+        let response = server.client.get(format!("{}{}", server.url, path)).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), 403, "{}", path);
+    }
+    assert_eq!(server.client.get(format!("{}/shared/shr_forged", server.url)).send().await.unwrap().status(), 401);
+    assert_eq!(server.client.get(format!("{}/shared/{}", server.url, server.token("alice"))).send().await.unwrap().status(), 401);
+
+    // 消息窗口只露出最新的消息
+    let window = server.share_token("launch", json!({ "expires_in_secs": 3600, "message_window": 1 })).await;
+    let body: Value = server.client.get(format!("{}/shared/{}", server.url, window)).send().await.unwrap().json().await.unwrap();
+    assert_eq!(body["data"]["messages"], json!([body["data"]["messages"][0]]));
+    assert_eq!(body["data"]["messages"][0]["content"], "tests green");
+
+    // 每次访问都计数，列表中不含令牌本身
+    let stored = server.store.load_share_token(&share_token_id(&token)).await.unwrap().unwrap();
+    assert_eq!(stored.access_count, 1);
+    let body: Value = server
+        .client
+        .get(format!("{}/groups/launch/share", server.url))
+        .bearer_auth(server.token("alice"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    assert!(!body.to_string().contains(&token));
+}
+
+#[tokio::test]
+async fn test_token_expires() {
+    let server = Server::start().await;
+    let token = server.share_token("launch", json!({ "expires_in_secs": 60 })).await;
+    let url = format!("{}/shared/{}", server.url, token);
+    assert_eq!(server.client.get(&url).send().await.unwrap().status(), 200);
+
+    server.clock.advance(Duration::from_secs(60));
+    assert_eq!(server.client.get(&url).send().await.unwrap().status(), 401);
+    assert_eq!(server.client.get(format!("{}/stream", url)).send().await.unwrap().status(), 401);
+}
+
+/// 读取 SSE 流直到出现 `needle`，超时返回 None
+async fn read_until(response: &mut reqwest::Response, buffer: &mut String, needle: &str) -> Option<()> {
+    while !buffer.contains(needle) {
+        let chunk = tokio::time::timeout(Duration::from_secs(5), response.chunk()).await.ok()?.ok()??;
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+    }
+    Some(())
+}
+
+#[tokio::test]
+async fn test_revocation_closes_live_stream() {
+    let server = Server::start().await;
+    server.bus.send(Message::group("lead", "launch", "kickoff")).await.unwrap();
+    let token = server.share_token("launch", json!({ "expires_in_secs": 3600 })).await;
+
+    let mut stream = server.client.get(format!("{}/shared/{}/stream", server.url, token)).send().await.unwrap();
+    assert_eq!(stream.status(), 200);
+    let mut buffer = String::new();
+    read_until(&mut stream, &mut buffer, "kickoff").await.expect("backlog event");
+
+    server.bus.send(Message::group("dev", "launch", "live update")).await.unwrap();
+    server.bus.send(Message::group("dev", "payroll", "not shared")).await.unwrap();
+    read_until(&mut stream, &mut buffer, "live update").await.expect("live event");
+    assert!(buffer.contains("Dev Du"));
+
+    let share_id = share_token_id(&token);
+    let response = server
+        .client
+        .delete(format!("{}/groups/launch/share/{}", server.url, share_id))
+        .bearer_auth(server.token("alice"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // 流在下一次检查时发送 revoked 并结束
+    read_until(&mut stream, &mut buffer, "event: revoked").await.expect("revoked event");
+    let end = tokio::time::timeout(Duration::from_secs(5), stream.chunk()).await.expect("stream closed");
+    assert!(end.unwrap().is_none());
+    assert!(!buffer.contains("not shared"));
+    assert_eq!(server.client.get(format!("{}/shared/{}", server.url, token)).send().await.unwrap().status(), 401);
+}
@@ -0,0 +1,89 @@
+//! 工具预演接口测试：`POST /tools/{id}/dry-run` 的权限、预演结果和不支持预演时的拒绝
+
+use std::sync::Arc;
+
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, RwLock};
+
+use imitatort::core::messaging::MessageBus;
+use imitatort::core::store::{MemoryStore, Store};
+use imitatort::core::tool::ToolRegistry;
+use imitatort::domain::Organization;
+use imitatort::infrastructure::auth::{JwtService, UserInfo};
+use imitatort::infrastructure::tool::{FnToolExecutor, FrameworkToolExecutor, ToolEnvironment, ToolExecutorRegistry};
+use imitatort::infrastructure::web::{create_router, AppState};
+
+fn token(jwt: &JwtService, position: &str) -> String {
+    jwt.generate_token(&UserInfo {
+        id: position.to_lowercase(),
+        username: position.to_lowercase(),
+        name: position.to_string(),
+        email: None,
+        is_director: false,
+        employee_id: "00001".to_string(),
+        position: position.to_string(),
+        department: "eng".to_string(),
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_dry_run_endpoint() {
+    let store: Arc<dyn Store> = Arc::new(MemoryStore::new());
+    let message_bus = Arc::new(MessageBus::new());
+    let mut bob = message_bus.register("bob");
+    let tool_registry = Arc::new(ToolRegistry::new());
+    let mut executors = ToolExecutorRegistry::with_default_skill_manager(tool_registry.clone());
+    executors.register(Box::new(FrameworkToolExecutor::new(ToolEnvironment::new(
+        message_bus,
+        Arc::new(RwLock::new(Organization::new())),
+        tool_registry,
+        store.clone(),
+    ))));
+    executors.register(Box::new(FnToolExecutor::new("deploy.production", |params| async move { Ok(params) })));
+
+    let jwt = JwtService::new("test-secret");
+    let (message_tx, _) = broadcast::channel(16);
+    let state = AppState::new(vec![], message_tx, store, jwt.clone()).with_tool_executor(Arc::new(executors));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(Arc::new(state))).await.unwrap();
+    });
+    let base = format!("http://{}/api/v1", addr);
+    let client = reqwest::Client::new();
+    let request = json!({"agent_id": "alice", "params": {"to_agent_id": "bob", "content": "hi"}});
+
+    let response = client
+        .post(format!("{}/tools/message.send_direct/dry-run", base))
+        .bearer_auth(token(&jwt, "Employee"))
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 403);
+
+    let response = client
+        .post(format!("{}/tools/message.send_direct/dry-run", base))
+        .bearer_auth(token(&jwt, "Chairman"))
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["effects"]["recipients"], json!(["bob"]));
+    assert_eq!(body["data"]["effects"]["content"], "hi");
+    assert!(bob.try_recv().is_err());
+
+    let response = client
+        .post(format!("{}/tools/deploy.production/dry-run", base))
+        .bearer_auth(token(&jwt, "Chairman"))
+        .json(&json!({"agent_id": "alice", "params": {}}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 422);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"]["message"].as_str().unwrap().contains("deploy.production"));
+}
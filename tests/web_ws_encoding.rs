@@ -0,0 +1,118 @@
+//! WebSocket 帧编码协商测试：同时建立普通连接和压缩连接，收到的大消息解码后内容一致且压缩帧更小
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use imitatort::core::store::MemoryStore;
+use imitatort::domain::Message;
+use imitatort::infrastructure::auth::JwtService;
+use imitatort::infrastructure::web::protocol::{EncodedFrame, FrameCodec, FrameEncoding, ServerEvent, ServerFrame};
+use imitatort::infrastructure::web::{create_router, AppState};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+async fn spawn_server() -> (String, broadcast::Sender<Message>) {
+    let (message_tx, _) = broadcast::channel::<Message>(100);
+    let state = AppState::new(Vec::new(), message_tx.clone(), Arc::new(MemoryStore::new()), JwtService::new("test-secret"));
+    let app = create_router(Arc::new(state));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    (format!("ws://{}/api/v1/ws", addr), message_tx)
+}
+
+async fn next_frame(socket: &mut Socket) -> EncodedFrame {
+    let frame = tokio::time::timeout(Duration::from_secs(5), socket.next()).await.unwrap().unwrap().unwrap();
+    match frame {
+        WsMessage::Text(text) => EncodedFrame::Text(text),
+        WsMessage::Binary(bytes) => EncodedFrame::Binary(bytes),
+        other => panic!("unexpected frame {:?}", other),
+    }
+}
+
+/// 发送 `hello` 并等待 `welcome`
+async fn negotiate(socket: &mut Socket, encoding: FrameEncoding) -> FrameCodec {
+    let hello = json!({ "type": "hello", "encoding": encoding, "min_compress_bytes": 256 });
+    socket.send(WsMessage::Text(hello.to_string())).await.unwrap();
+    let welcome = FrameCodec::default().decode(&next_frame(socket).await).unwrap();
+    match welcome.event {
+        ServerEvent::Welcome { encoding: agreed, min_compress_bytes } => {
+            assert_eq!(agreed, encoding);
+            assert_eq!(min_compress_bytes, 256);
+            FrameCodec::new(agreed).with_min_compress_bytes(min_compress_bytes)
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_plain_and_deflate_connections_receive_same_message() {
+    let (url, message_tx) = spawn_server().await;
+    let (mut plain, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+    let (mut compressed, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+    let codec = negotiate(&mut compressed, FrameEncoding::Deflate).await;
+
+    let content = "Quarterly report: revenue grew in every region. ".repeat(200);
+    message_tx.send(Message::private("analyst", "ceo", content.clone())).unwrap();
+
+    let plain_frame = next_frame(&mut plain).await;
+    let compressed_frame = next_frame(&mut compressed).await;
+    assert!(matches!(plain_frame, EncodedFrame::Text(_)));
+    assert!(matches!(compressed_frame, EncodedFrame::Binary(_)));
+    assert!(compressed_frame.len() * 4 < plain_frame.len());
+
+    let plain_decoded = FrameCodec::default().decode(&plain_frame).unwrap();
+    let compressed_decoded = codec.decode(&compressed_frame).unwrap();
+    assert_eq!(plain_decoded, compressed_decoded);
+    match compressed_decoded.event {
+        ServerEvent::Message { data } => assert_eq!(data.content, content),
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    // 小于阈值的帧不压缩
+    compressed.send(WsMessage::Text(json!({ "type": "ping" }).to_string())).await.unwrap();
+    let pong = next_frame(&mut compressed).await;
+    assert!(matches!(pong, EncodedFrame::Text(_)));
+    assert_eq!(codec.decode(&pong).unwrap(), ServerFrame::new(ServerEvent::Pong));
+}
+
+#[cfg(feature = "msgpack")]
+#[tokio::test]
+async fn test_msgpack_connection_decodes_binary_frames() {
+    let (url, message_tx) = spawn_server().await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+    let codec = negotiate(&mut socket, FrameEncoding::Msgpack).await;
+
+    message_tx.send(Message::private("analyst", "ceo", "hi")).unwrap();
+    let frame = next_frame(&mut socket).await;
+    assert!(matches!(frame, EncodedFrame::Binary(_)));
+    match codec.decode(&frame).unwrap().event {
+        ServerEvent::Message { data } => assert_eq!(data.content, "hi"),
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+#[cfg(not(feature = "msgpack"))]
+#[tokio::test]
+async fn test_unsupported_encoding_keeps_json() {
+    let (url, message_tx) = spawn_server().await;
+    let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+    socket
+        .send(WsMessage::Text(json!({ "type": "hello", "encoding": "msgpack" }).to_string()))
+        .await
+        .unwrap();
+    let reply = FrameCodec::default().decode(&next_frame(&mut socket).await).unwrap();
+    assert!(matches!(reply.event, ServerEvent::Error { .. }));
+
+    message_tx.send(Message::private("analyst", "ceo", "hi")).unwrap();
+    assert!(matches!(next_frame(&mut socket).await, EncodedFrame::Text(_)));
+}